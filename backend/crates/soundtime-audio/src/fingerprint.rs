@@ -0,0 +1,248 @@
+//! Comparison of Chromaprint acoustic fingerprints.
+//!
+//! `fpcalc` prints fingerprints in Chromaprint's compressed form: URL-safe
+//! base64 of a header (algorithm byte, 24-bit big-endian item count), then
+//! the set bits of each item XORed with the previous one, as 3-bit
+//! position deltas with 5-bit overflow values after them. Two encodes of the
+//! same recording decode to nearly but rarely exactly the same items, so
+//! fingerprints are compared by their bit error rate, not as strings.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+/// Similarity at or above which two fingerprints are the same recording.
+pub const MATCH_THRESHOLD: f64 = 0.85;
+/// Largest difference in duration between two tracks worth comparing.
+pub const DURATION_TOLERANCE_SECS: f32 = 2.0;
+/// Items (about 0.12 s each) two fingerprints are shifted by at most when
+/// aligning them, e.g. for encoder padding.
+const MAX_OFFSET: usize = 8;
+/// Items compared from the start of the overlap, about two minutes.
+const MAX_COMPARED_ITEMS: usize = 1000;
+/// Overlap below which two fingerprints are not compared.
+const MIN_OVERLAP: usize = 16;
+/// Largest position delta stored in the 3-bit section.
+const MAX_NORMAL_VALUE: u8 = 7;
+
+/// Decode a compressed fingerprint into its 32-bit items. Returns `None` if
+/// it is not valid base64 or its payload is truncated.
+pub fn decode_fingerprint(fingerprint: &str) -> Option<Vec<u32>> {
+    let data = URL_SAFE_NO_PAD
+        .decode(fingerprint.trim_end_matches('='))
+        .ok()?;
+    if data.len() < 4 {
+        return None;
+    }
+    let num_items = ((data[1] as usize) << 16) | ((data[2] as usize) << 8) | data[3] as usize;
+    let payload = &data[4..];
+
+    // Position deltas of every item, each list ended by a 0
+    let mut bits = Vec::new();
+    let mut found = 0;
+    let mut exceptional = 0usize;
+    for i in 0..payload.len() * 8 / 3 {
+        if found == num_items {
+            break;
+        }
+        let bit = unpack(payload, i, 3);
+        if bit == 0 {
+            found += 1;
+        } else if bit == MAX_NORMAL_VALUE {
+            exceptional += 1;
+        }
+        bits.push(bit);
+    }
+    if found != num_items {
+        return None;
+    }
+
+    let overflow = &payload[(bits.len() * 3).div_ceil(8)..];
+    if overflow.len() < (exceptional * 5).div_ceil(8) {
+        return None;
+    }
+    for (j, bit) in bits
+        .iter_mut()
+        .filter(|b| **b == MAX_NORMAL_VALUE)
+        .enumerate()
+    {
+        *bit += unpack(overflow, j, 5);
+    }
+
+    let mut items: Vec<u32> = Vec::with_capacity(num_items);
+    let mut value = 0u32;
+    let mut last_bit = 0u32;
+    for bit in bits {
+        if bit == 0 {
+            let prev = items.last().copied().unwrap_or(0);
+            items.push(value ^ prev);
+            value = 0;
+            last_bit = 0;
+            continue;
+        }
+        last_bit += bit as u32;
+        if last_bit > 32 {
+            return None;
+        }
+        value |= 1 << (last_bit - 1);
+    }
+    Some(items)
+}
+
+/// Value `index` of `width` bits in `data`, packed least significant bit first.
+fn unpack(data: &[u8], index: usize, width: usize) -> u8 {
+    let mut value = 0;
+    for k in 0..width {
+        let bit = index * width + k;
+        if (data[bit / 8] >> (bit % 8)) & 1 == 1 {
+            value |= 1 << k;
+        }
+    }
+    value
+}
+
+/// Share of matching bits (0.0–1.0) between two decoded fingerprints, at
+/// the best alignment within [`MAX_OFFSET`] items. Unrelated audio scores
+/// about 0.5; fingerprints too short to compare score 0.0.
+pub fn similarity(a: &[u32], b: &[u32]) -> f64 {
+    let mut best = 0.0f64;
+    for offset in 0..=MAX_OFFSET {
+        for (x, y) in [(a, b), (b, a)] {
+            let Some(shifted) = x.get(offset..) else {
+                continue;
+            };
+            let overlap = shifted.len().min(y.len()).min(MAX_COMPARED_ITEMS);
+            if overlap < MIN_OVERLAP {
+                continue;
+            }
+            let errors: u32 = shifted
+                .iter()
+                .zip(y)
+                .take(overlap)
+                .map(|(p, q)| (p ^ q).count_ones())
+                .sum();
+            best = best.max(1.0 - errors as f64 / (overlap as f64 * 32.0));
+        }
+    }
+    best
+}
+
+/// Whether two compressed fingerprints are the same recording. Identical
+/// strings always match; otherwise both are decoded and compared.
+pub fn fingerprints_match(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    match (decode_fingerprint(a), decode_fingerprint(b)) {
+        (Some(a), Some(b)) => similarity(&a, &b) >= MATCH_THRESHOLD,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compress `items` the way Chromaprint does (algorithm 1).
+    fn compress(items: &[u32]) -> String {
+        let mut bits = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            let mut x = if i == 0 { *item } else { item ^ items[i - 1] };
+            let (mut bit, mut last_bit) = (1u8, 0u8);
+            while x != 0 {
+                if x & 1 == 1 {
+                    bits.push(bit - last_bit);
+                    last_bit = bit;
+                }
+                x >>= 1;
+                bit += 1;
+            }
+            bits.push(0);
+        }
+        let normal: Vec<u8> = bits.iter().map(|b| (*b).min(MAX_NORMAL_VALUE)).collect();
+        let overflow: Vec<u8> = bits
+            .iter()
+            .filter(|b| **b >= MAX_NORMAL_VALUE)
+            .map(|b| b - MAX_NORMAL_VALUE)
+            .collect();
+
+        let n = items.len();
+        let mut out = vec![1, (n >> 16) as u8, (n >> 8) as u8, n as u8];
+        out.extend(pack(&normal, 3));
+        out.extend(pack(&overflow, 5));
+        URL_SAFE_NO_PAD.encode(out)
+    }
+
+    fn pack(values: &[u8], width: usize) -> Vec<u8> {
+        let mut out = vec![0u8; (values.len() * width).div_ceil(8)];
+        for (i, v) in values.iter().enumerate() {
+            for k in 0..width {
+                if (v >> k) & 1 == 1 {
+                    let bit = i * width + k;
+                    out[bit / 8] |= 1 << (bit % 8);
+                }
+            }
+        }
+        out
+    }
+
+    /// Deterministic pseudo-random items (xorshift).
+    fn items(seed: u32, n: usize) -> Vec<u32> {
+        let mut x = seed;
+        (0..n)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_roundtrip() {
+        let original = items(7, 200);
+        assert_eq!(decode_fingerprint(&compress(&original)), Some(original));
+    }
+
+    #[test]
+    fn test_decode_rejects_garbage() {
+        assert_eq!(decode_fingerprint("not base64!"), None);
+        assert_eq!(decode_fingerprint("AQ"), None);
+        // Header claims more items than the payload holds
+        let mut truncated = compress(&items(3, 50));
+        truncated.truncate(truncated.len() / 2);
+        assert_eq!(decode_fingerprint(&truncated), None);
+    }
+
+    #[test]
+    fn test_near_identical_fingerprints_match() {
+        let a = items(11, 300);
+        // A re-encode flips a few bits per item
+        let b: Vec<u32> = a.iter().map(|x| x ^ 0b1001).collect();
+        assert!(similarity(&a, &b) > 0.9);
+        assert!(fingerprints_match(&compress(&a), &compress(&b)));
+    }
+
+    #[test]
+    fn test_shifted_fingerprint_matches() {
+        let a = items(5, 300);
+        let b = a[3..].to_vec();
+        assert_eq!(similarity(&a, &b), 1.0);
+        assert_eq!(similarity(&b, &a), 1.0);
+    }
+
+    #[test]
+    fn test_unrelated_fingerprints_differ() {
+        let a = compress(&items(1, 300));
+        let b = compress(&items(2, 300));
+        assert!(!fingerprints_match(&a, &b));
+    }
+
+    #[test]
+    fn test_too_short_to_compare() {
+        assert_eq!(similarity(&items(1, 4), &items(1, 4)), 0.0);
+        // Identical strings match without decoding
+        let short = compress(&items(1, 4));
+        assert!(fingerprints_match(&short, &short));
+    }
+}
@@ -1,11 +1,20 @@
 pub mod convert;
+pub mod fingerprint;
 pub mod metadata;
 pub mod storage;
 pub mod waveform;
 
 pub use convert::{convert_aiff_to_flac, needs_aiff_conversion};
-pub use metadata::{extract_metadata_from_file, AudioMetadata};
+pub use fingerprint::fingerprints_match;
+pub use metadata::{
+    compute_fingerprint, extract_embedded_cover, extract_metadata_from_file, is_lossless_format,
+    measure_loudness, measure_loudness_async, parse_filename_metadata, AudioMetadata, MetadataTier,
+    PartialMetadata,
+};
 pub use storage::{
-    ensure_local_file, sanitize_filename, AudioStorage, S3Storage, StorageBackend, StorageError,
+    ensure_local_file, sanitize_filename, AudioStorage, S3Options, S3Storage, ShardMove,
+    ShardingStrategy, StorageBackend, StorageError,
+};
+pub use waveform::{
+    generate_waveform, generate_waveform_async, generate_waveform_with_progress, WaveformProgress,
 };
-pub use waveform::generate_waveform;
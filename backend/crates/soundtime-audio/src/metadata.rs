@@ -1,8 +1,19 @@
+use bytes::Bytes;
+use lofty::picture::{Picture, PictureType};
 use lofty::prelude::*;
 use lofty::probe::Probe;
-use lofty::tag::ItemKey;
+use lofty::tag::{ItemKey, Tag};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -35,6 +46,15 @@ pub struct AudioMetadata {
     pub format: String,
     pub file_size: u64,
     pub cover_art: Option<Vec<u8>>,
+    /// EBU R128 integrated loudness in LUFS (`None` if the audio could not
+    /// be decoded or is silent). Left unset by [`extract_metadata_from_file`];
+    /// see [`measure_loudness`]
+    pub loudness_lufs: Option<f32>,
+    /// EBU R128 loudness range in LU
+    pub dynamic_range: Option<f32>,
+    /// How the audio is encoded, e.g. "CBR 320kbps", "VBR 245kbps" or
+    /// "Lossless 16-bit/44.1kHz"
+    pub encoding_quality: Option<String>,
 }
 
 /// Supported audio formats
@@ -42,6 +62,14 @@ const SUPPORTED_EXTENSIONS: &[&str] = &[
     "mp3", "flac", "ogg", "wav", "aac", "opus", "m4a", "aif", "aiff",
 ];
 
+/// Formats stored without lossy compression
+pub const LOSSLESS_FORMATS: &[&str] = &["flac", "wav", "aiff"];
+
+/// Whether `format` (as stored in `tracks.format`) is lossless.
+pub fn is_lossless_format(format: &str) -> bool {
+    LOSSLESS_FORMATS.contains(&format.to_lowercase().as_str())
+}
+
 /// Check if a file extension is supported
 pub fn is_supported_format(extension: &str) -> bool {
     SUPPORTED_EXTENSIONS.contains(&extension.to_lowercase().as_str())
@@ -77,7 +105,137 @@ pub fn normalize_genre(raw: &str) -> String {
         .join(" ")
 }
 
-/// Extract metadata from an audio file using lofty
+/// Where the metadata of a track came from, most reliable first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MetadataTier {
+    /// ID3v2, Vorbis comments or APEv2 tags in the file
+    Tags,
+    /// Parsed from the file name, e.g. `Artist - Title.mp3`
+    Filename,
+    /// Guessed from the directories, e.g. `Artist/Album/01 Title.flac`
+    Directory,
+}
+
+impl MetadataTier {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MetadataTier::Tags => "tags",
+            MetadataTier::Filename => "filename",
+            MetadataTier::Directory => "directory",
+        }
+    }
+}
+
+/// Track details guessed from a file's path by [`parse_filename_metadata`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PartialMetadata {
+    /// `None` only if the file name has no stem
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    /// From the parent directory
+    pub album: Option<String>,
+    pub track_number: Option<u32>,
+    /// Whether `artist` came from the directories rather than the file name
+    pub artist_from_directory: bool,
+}
+
+/// `01 - Artist - Title`
+static NUMBERED_ARTIST_TITLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?P<track>\d{1,3})\s*[-.)]?\s+(?P<artist>.+?)\s+-\s+(?P<title>.+)$").unwrap()
+});
+
+/// `01 Title`, `01. Title`, `01 - Title`
+static NUMBERED_TITLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?P<track>\d{1,3})(?:\s*[-.)]\s*|\s+)(?P<title>.+)$").unwrap());
+
+/// `Artist - Title`
+static ARTIST_TITLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?P<artist>.+?)\s+-\s+(?P<title>.+)$").unwrap());
+
+/// Guess the title, artist, album and track number of a file without tags
+/// from its path: `Artist - Title.mp3`, `01 Title.mp3`, `01 - Artist -
+/// Title.mp3`, and `Artist/Album/01 Title.flac` for the directories. The
+/// title falls back to the file name without its extension.
+pub fn parse_filename_metadata(path: &Path) -> PartialMetadata {
+    let mut guess = PartialMetadata::default();
+    let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+        return guess;
+    };
+    let stem = stem.replace('_', " ");
+    let stem = stem.trim();
+    let field = |caps: &regex::Captures, name: &str| {
+        caps.name(name)
+            .map(|m| m.as_str().trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    if let Some(caps) = NUMBERED_ARTIST_TITLE.captures(stem) {
+        guess.track_number = caps["track"].parse().ok();
+        guess.artist = field(&caps, "artist");
+        guess.title = field(&caps, "title");
+    } else if let Some(caps) = NUMBERED_TITLE.captures(stem) {
+        guess.track_number = caps["track"].parse().ok();
+        guess.title = field(&caps, "title");
+    } else if let Some(caps) = ARTIST_TITLE.captures(stem) {
+        guess.artist = field(&caps, "artist");
+        guess.title = field(&caps, "title");
+    }
+    if guess.title.is_none() && !stem.is_empty() {
+        guess.title = Some(stem.to_string());
+    }
+
+    // Artist/Album/track layout
+    let mut dirs = path
+        .parent()
+        .into_iter()
+        .flat_map(|p| p.components().rev())
+        .filter_map(|c| match c {
+            std::path::Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+    guess.album = dirs.next().map(str::to_string);
+    if guess.artist.is_none() {
+        guess.artist = dirs.next().map(str::to_string);
+        guess.artist_from_directory = guess.artist.is_some();
+    }
+    guess
+}
+
+impl AudioMetadata {
+    /// Fill the fields the tags left empty from `guess` and return the
+    /// least reliable tier that supplied one ([`MetadataTier::Tags`] if
+    /// none was needed).
+    pub fn fill_missing(&mut self, guess: PartialMetadata) -> MetadataTier {
+        let mut tier = MetadataTier::Tags;
+        if self.title.is_none() && guess.title.is_some() {
+            self.title = guess.title;
+            tier = tier.max(MetadataTier::Filename);
+        }
+        if self.track_number.is_none() && guess.track_number.is_some() {
+            self.track_number = guess.track_number;
+            tier = tier.max(MetadataTier::Filename);
+        }
+        if self.artist.is_none() && guess.artist.is_some() {
+            self.artist = guess.artist;
+            tier = tier.max(if guess.artist_from_directory {
+                MetadataTier::Directory
+            } else {
+                MetadataTier::Filename
+            });
+        }
+        if self.album.is_none() && guess.album.is_some() {
+            self.album = guess.album;
+            tier = MetadataTier::Directory;
+        }
+        tier
+    }
+}
+
+/// Extract metadata from an audio file using lofty. Only tags and stream
+/// properties are read; loudness, which takes decoding the whole file, is
+/// measured separately with [`measure_loudness`].
 pub fn extract_metadata_from_file(path: &Path) -> Result<AudioMetadata, MetadataError> {
     let extension = path
         .extension()
@@ -98,6 +256,7 @@ pub fn extract_metadata_from_file(path: &Path) -> Result<AudioMetadata, Metadata
     let bitrate = properties.audio_bitrate();
     let sample_rate = properties.sample_rate();
     let channels = properties.channels();
+    let bit_depth = properties.bit_depth();
 
     // Extract tags (try primary tag first, then others)
     let tag = tagged_file
@@ -136,6 +295,12 @@ pub fn extract_metadata_from_file(path: &Path) -> Result<AudioMetadata, Metadata
     }
     .to_string();
 
+    let mp3_mode = if format == "mp3" {
+        read_mp3_bitrate_mode(path).ok()
+    } else {
+        None
+    };
+    let encoding_quality = encoding_quality(&format, bitrate, bit_depth, sample_rate, mp3_mode);
     Ok(AudioMetadata {
         title,
         artist,
@@ -152,9 +317,241 @@ pub fn extract_metadata_from_file(path: &Path) -> Result<AudioMetadata, Metadata
         format,
         file_size,
         cover_art,
+        loudness_lufs: None,
+        dynamic_range: None,
+        encoding_quality,
+    })
+}
+
+/// Pick the front cover among the pictures of `tags`, falling back to the
+/// first picture of any type.
+fn pick_cover(tags: &[Tag]) -> Option<&Picture> {
+    let pictures = || tags.iter().flat_map(|t| t.pictures());
+    pictures()
+        .find(|p| p.pic_type() == PictureType::CoverFront)
+        .or_else(|| pictures().next())
+}
+
+/// Extract the album art embedded in an audio file's tags — ID3v2 `APIC`
+/// frames, FLAC `PICTURE` blocks, MP4 `covr` atoms and Vorbis comments alike.
+/// Every tag in the file is searched and the front cover is preferred.
+/// Returns `None` if the file has no art or cannot be read.
+pub fn extract_embedded_cover(path: &Path) -> Option<Bytes> {
+    let tagged_file = Probe::open(path).ok()?.read().ok()?;
+    pick_cover(tagged_file.tags()).map(|p| Bytes::copy_from_slice(p.data()))
+}
+
+/// Whether an MP3 stream keeps the same bitrate in every frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BitrateMode {
+    Constant,
+    Variable,
+}
+
+/// Describe how the audio is encoded from its format and stream properties.
+/// `bitrate` is in kbps.
+fn encoding_quality(
+    format: &str,
+    bitrate: Option<u32>,
+    bit_depth: Option<u8>,
+    sample_rate: Option<u32>,
+    mp3_mode: Option<BitrateMode>,
+) -> Option<String> {
+    if is_lossless_format(format) {
+        return Some(match (bit_depth, sample_rate) {
+            (Some(bits), Some(rate)) => {
+                format!("Lossless {bits}-bit/{}kHz", rate as f64 / 1000.0)
+            }
+            _ => "Lossless".to_string(),
+        });
+    }
+    let kbps = bitrate.filter(|&b| b > 0)?;
+    Some(match mp3_mode {
+        Some(BitrateMode::Constant) => format!("CBR {kbps}kbps"),
+        Some(BitrateMode::Variable) => format!("VBR {kbps}kbps"),
+        None => format!("{kbps}kbps"),
     })
 }
 
+/// Read the start of an MP3 file, past any ID3v2 tag, and tell CBR from VBR.
+fn read_mp3_bitrate_mode(path: &Path) -> std::io::Result<BitrateMode> {
+    let mut file = std::fs::File::open(path)?;
+    let mut header = [0u8; 10];
+    file.read_exact(&mut header)?;
+
+    let mut offset = 0u64;
+    if &header[..3] == b"ID3" {
+        // Tag size is a 28-bit syncsafe integer, excluding the header and
+        // the optional footer
+        let size = header[6..10]
+            .iter()
+            .fold(0u64, |acc, &b| (acc << 7) | u64::from(b & 0x7f));
+        let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+        offset = 10 + size + footer;
+    }
+
+    file.seek(SeekFrom::Start(offset))?;
+    let mut head = Vec::with_capacity(4096);
+    file.take(4096).read_to_end(&mut head)?;
+    Ok(mp3_bitrate_mode(&head))
+}
+
+/// Tell CBR from VBR by the header in the first MP3 frame: encoders write
+/// `Xing` or `VBRI` for VBR and `Info` for CBR. Files without one are CBR.
+fn mp3_bitrate_mode(head: &[u8]) -> BitrateMode {
+    for window in head.windows(4) {
+        match window {
+            b"Xing" | b"VBRI" => return BitrateMode::Variable,
+            b"Info" => return BitrateMode::Constant,
+            _ => {}
+        }
+    }
+    BitrateMode::Constant
+}
+
+/// [`measure_loudness`] on the blocking thread pool, so decoding a long
+/// file does not stall the async runtime.
+pub async fn measure_loudness_async(path: PathBuf) -> (Option<f32>, Option<f32>) {
+    tokio::task::spawn_blocking(move || measure_loudness(&path))
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("loudness measurement task failed: {e}");
+            (None, None)
+        })
+}
+
+/// Measure EBU R128 integrated loudness (LUFS) and loudness range (LU) by
+/// decoding the whole file. Either is `None` if the file cannot be decoded
+/// or is too short or quiet to measure.
+pub fn measure_loudness(path: &Path) -> (Option<f32>, Option<f32>) {
+    match decode_loudness(path) {
+        Ok(loudness) => loudness,
+        Err(e) => {
+            tracing::debug!(path = %path.display(), "loudness measurement failed: {e}");
+            (None, None)
+        }
+    }
+}
+
+fn decode_loudness(path: &Path) -> Result<(Option<f32>, Option<f32>), String> {
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| e.to_string())?;
+    let mut format = probed.format;
+
+    let track = format
+        .default_track()
+        .ok_or_else(|| "no default track".to_string())?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| e.to_string())?;
+
+    // Created on the first decoded packet, once channels and rate are known
+    let mut meter: Option<ebur128::EbuR128> = None;
+
+    while let Ok(packet) = format.next_packet() {
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(d) => d,
+            Err(_) => continue,
+        };
+
+        let spec = *decoded.spec();
+        let mut sample_buf = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        sample_buf.copy_interleaved_ref(decoded);
+
+        if meter.is_none() {
+            meter = Some(
+                ebur128::EbuR128::new(
+                    spec.channels.count() as u32,
+                    spec.rate,
+                    ebur128::Mode::I | ebur128::Mode::LRA,
+                )
+                .map_err(|e| e.to_string())?,
+            );
+        }
+        if let Some(ref mut meter) = meter {
+            meter
+                .add_frames_f32(sample_buf.samples())
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    let Some(meter) = meter else {
+        return Ok((None, None));
+    };
+    let finite =
+        |v: Result<f64, ebur128::Error>| v.ok().filter(|v| v.is_finite()).map(|v| v as f32);
+    Ok((
+        finite(meter.loudness_global()),
+        finite(meter.loudness_range()),
+    ))
+}
+
+/// Compute the Chromaprint acoustic fingerprint of an audio file.
+///
+/// Shells out to `fpcalc` (from the chromaprint tools; override the binary
+/// with `FPCALC_PATH`). Returns the compressed base64 fingerprint, or `None`
+/// if `fpcalc` is unavailable or cannot decode the file — fingerprinting is
+/// best-effort and never blocks an upload.
+pub async fn compute_fingerprint(path: &Path) -> Option<String> {
+    let fpcalc = std::env::var("FPCALC_PATH").unwrap_or_else(|_| "fpcalc".to_string());
+
+    let output = match tokio::process::Command::new(&fpcalc)
+        .arg("-json")
+        .arg(path)
+        .output()
+        .await
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            tracing::debug!("fpcalc not found — skipping audio fingerprint");
+            return None;
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to run fpcalc");
+            return None;
+        }
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        tracing::warn!(path = %path.display(), %stderr, "fpcalc failed");
+        return None;
+    }
+
+    parse_fpcalc_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Extract the fingerprint from `fpcalc -json` output.
+fn parse_fpcalc_output(stdout: &str) -> Option<String> {
+    #[derive(Deserialize)]
+    struct FpcalcOutput {
+        fingerprint: String,
+    }
+
+    serde_json::from_str::<FpcalcOutput>(stdout.trim())
+        .ok()
+        .map(|o| o.fingerprint)
+        .filter(|f| !f.is_empty())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -249,10 +646,14 @@ mod tests {
             format: "mp3".into(),
             file_size: 5_000_000,
             cover_art: None,
+            loudness_lufs: Some(-9.5),
+            dynamic_range: Some(6.2),
+            encoding_quality: Some("CBR 320kbps".into()),
         };
         let json = serde_json::to_string(&meta).unwrap();
         assert!(json.contains("\"title\":\"Test Song\""));
         assert!(json.contains("\"format\":\"mp3\""));
+        assert!(json.contains("\"encoding_quality\":\"CBR 320kbps\""));
     }
 
     #[test]
@@ -279,6 +680,9 @@ mod tests {
         assert_eq!(meta.album_artist, None);
         assert_eq!(meta.format, "flac");
         assert_eq!(meta.file_size, 1_000_000);
+        // Quality fields are absent from metadata serialized before they existed
+        assert_eq!(meta.loudness_lufs, None);
+        assert_eq!(meta.encoding_quality, None);
     }
 
     #[test]
@@ -286,4 +690,317 @@ mod tests {
         // Ensure we have all 9 supported extensions
         assert_eq!(SUPPORTED_EXTENSIONS.len(), 9);
     }
+
+    // ── Chromaprint fingerprint ──
+
+    #[test]
+    fn test_parse_fpcalc_output() {
+        let out = r#"{"duration": 215.53, "fingerprint": "AQADtEmUSEkSJcmRI"}"#;
+        assert_eq!(
+            parse_fpcalc_output(out).as_deref(),
+            Some("AQADtEmUSEkSJcmRI")
+        );
+    }
+
+    #[test]
+    fn test_parse_fpcalc_output_invalid() {
+        assert!(parse_fpcalc_output("DURATION=215\nFINGERPRINT=abc").is_none());
+        assert!(parse_fpcalc_output(r#"{"duration": 1.0, "fingerprint": ""}"#).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_compute_fingerprint_missing_binary() {
+        std::env::set_var("FPCALC_PATH", "/nonexistent/fpcalc");
+        let result = compute_fingerprint(Path::new("/tmp/whatever.mp3")).await;
+        std::env::remove_var("FPCALC_PATH");
+        assert!(result.is_none());
+    }
+
+    // ── Quality metadata ──
+
+    #[test]
+    fn test_is_lossless_format() {
+        assert!(is_lossless_format("flac"));
+        assert!(is_lossless_format("WAV"));
+        assert!(is_lossless_format("aiff"));
+        assert!(!is_lossless_format("mp3"));
+        assert!(!is_lossless_format("ogg"));
+    }
+
+    #[test]
+    fn test_encoding_quality() {
+        assert_eq!(
+            encoding_quality("flac", Some(900), Some(16), Some(44100), None).as_deref(),
+            Some("Lossless 16-bit/44.1kHz")
+        );
+        assert_eq!(
+            encoding_quality("wav", None, Some(24), Some(96000), None).as_deref(),
+            Some("Lossless 24-bit/96kHz")
+        );
+        assert_eq!(
+            encoding_quality("aiff", None, None, None, None).as_deref(),
+            Some("Lossless")
+        );
+        assert_eq!(
+            encoding_quality(
+                "mp3",
+                Some(320),
+                None,
+                Some(44100),
+                Some(BitrateMode::Constant)
+            )
+            .as_deref(),
+            Some("CBR 320kbps")
+        );
+        assert_eq!(
+            encoding_quality(
+                "mp3",
+                Some(245),
+                None,
+                Some(44100),
+                Some(BitrateMode::Variable)
+            )
+            .as_deref(),
+            Some("VBR 245kbps")
+        );
+        assert_eq!(
+            encoding_quality("ogg", Some(160), None, Some(48000), None).as_deref(),
+            Some("160kbps")
+        );
+        assert_eq!(encoding_quality("aac", None, None, None, None), None);
+    }
+
+    #[test]
+    fn test_mp3_bitrate_mode() {
+        let mut frame = vec![0xff, 0xfb, 0x90, 0x64];
+        frame.extend_from_slice(&[0u8; 32]);
+
+        let mut vbr = frame.clone();
+        vbr.extend_from_slice(b"Xing");
+        assert_eq!(mp3_bitrate_mode(&vbr), BitrateMode::Variable);
+
+        let mut vbri = frame.clone();
+        vbri.extend_from_slice(b"VBRI");
+        assert_eq!(mp3_bitrate_mode(&vbri), BitrateMode::Variable);
+
+        let mut cbr = frame.clone();
+        cbr.extend_from_slice(b"Info");
+        assert_eq!(mp3_bitrate_mode(&cbr), BitrateMode::Constant);
+
+        assert_eq!(mp3_bitrate_mode(&frame), BitrateMode::Constant);
+    }
+
+    #[test]
+    fn test_read_mp3_bitrate_mode_skips_id3v2() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vbr.mp3");
+        // ID3v2 tag of 20 bytes whose body contains "Info", then a VBR frame
+        let mut bytes = b"ID3\x04\x00\x00\x00\x00\x00\x14".to_vec();
+        bytes.extend_from_slice(b"Info");
+        bytes.extend_from_slice(&[0u8; 16]);
+        bytes.extend_from_slice(&[0xff, 0xfb, 0x90, 0x64]);
+        bytes.extend_from_slice(&[0u8; 32]);
+        bytes.extend_from_slice(b"Xing");
+        std::fs::write(&path, bytes).unwrap();
+
+        assert_eq!(read_mp3_bitrate_mode(&path).unwrap(), BitrateMode::Variable);
+    }
+
+    #[test]
+    fn test_measure_loudness_sine() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sine.wav");
+        // 3 seconds of a 1 kHz sine at -20 dBFS: about -23 LUFS
+        write_sine_wav(&path, 48000, 3 * 48000, 0.1);
+
+        let (lufs, range) = measure_loudness(&path);
+        let lufs = lufs.expect("loudness measured");
+        assert!((lufs + 23.0).abs() < 0.5, "unexpected loudness {lufs}");
+        // A steady tone has no loudness range to speak of
+        assert!(range.unwrap_or(0.0) < 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_measure_loudness_async() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sine.wav");
+        write_sine_wav(&path, 48000, 3 * 48000, 0.1);
+        assert_eq!(
+            measure_loudness_async(path.clone()).await,
+            measure_loudness(&path)
+        );
+    }
+
+    #[test]
+    fn test_measure_loudness_undecodable() {
+        let tmp = tempfile::NamedTempFile::with_suffix(".mp3").unwrap();
+        assert_eq!(measure_loudness(tmp.path()), (None, None));
+    }
+
+    #[test]
+    fn test_extract_metadata_wav_quality() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tone.wav");
+        write_sine_wav(&path, 48000, 48000, 0.5);
+
+        let meta = extract_metadata_from_file(&path).unwrap();
+        assert_eq!(meta.format, "wav");
+        assert_eq!(
+            meta.encoding_quality.as_deref(),
+            Some("Lossless 16-bit/48kHz")
+        );
+        // Loudness is measured separately
+        assert_eq!(meta.loudness_lufs, None);
+    }
+
+    /// Write a mono 16-bit PCM WAV of a 1 kHz sine with peak `amplitude`.
+    fn write_sine_wav(path: &Path, sample_rate: u32, num_samples: u32, amplitude: f64) {
+        let data_size = num_samples * 2;
+        let mut bytes = Vec::with_capacity(44 + data_size as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_size.to_le_bytes());
+        for i in 0..num_samples {
+            let t = i as f64 / sample_rate as f64;
+            let sample = amplitude * (t * 1000.0 * 2.0 * std::f64::consts::PI).sin();
+            bytes.extend_from_slice(&((sample * i16::MAX as f64) as i16).to_le_bytes());
+        }
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    // ── filename fallback ──
+
+    #[test]
+    fn test_parse_filename_artist_title() {
+        let guess = parse_filename_metadata(Path::new("Daft Punk - One More Time.mp3"));
+        assert_eq!(guess.artist.as_deref(), Some("Daft Punk"));
+        assert_eq!(guess.title.as_deref(), Some("One More Time"));
+        assert_eq!(guess.track_number, None);
+        assert_eq!(guess.album, None);
+    }
+
+    #[test]
+    fn test_parse_filename_numbered() {
+        for name in [
+            "01 Intro.mp3",
+            "01. Intro.mp3",
+            "01 - Intro.mp3",
+            "01_Intro.mp3",
+        ] {
+            let guess = parse_filename_metadata(Path::new(name));
+            assert_eq!(guess.track_number, Some(1), "{name}");
+            assert_eq!(guess.title.as_deref(), Some("Intro"), "{name}");
+            assert_eq!(guess.artist, None, "{name}");
+        }
+
+        let guess = parse_filename_metadata(Path::new("07 - Air - La femme d'argent.flac"));
+        assert_eq!(guess.track_number, Some(7));
+        assert_eq!(guess.artist.as_deref(), Some("Air"));
+        assert_eq!(guess.title.as_deref(), Some("La femme d'argent"));
+
+        // Digits that are part of the name are not a track number
+        let guess = parse_filename_metadata(Path::new("2Pac - Changes.mp3"));
+        assert_eq!(guess.track_number, None);
+        assert_eq!(guess.artist.as_deref(), Some("2Pac"));
+    }
+
+    #[test]
+    fn test_parse_filename_directories() {
+        let guess =
+            parse_filename_metadata(Path::new("Air/Moon Safari/03 Kelly Watch the Stars.flac"));
+        assert_eq!(guess.artist.as_deref(), Some("Air"));
+        assert!(guess.artist_from_directory);
+        assert_eq!(guess.album.as_deref(), Some("Moon Safari"));
+        assert_eq!(guess.track_number, Some(3));
+        assert_eq!(guess.title.as_deref(), Some("Kelly Watch the Stars"));
+
+        // An artist in the file name wins over the directory
+        let guess = parse_filename_metadata(Path::new("Compilations/Air - Sexy Boy.mp3"));
+        assert_eq!(guess.artist.as_deref(), Some("Air"));
+        assert!(!guess.artist_from_directory);
+        assert_eq!(guess.album.as_deref(), Some("Compilations"));
+    }
+
+    #[test]
+    fn test_parse_filename_falls_back_to_stem() {
+        let guess = parse_filename_metadata(Path::new("untitled track.mp3"));
+        assert_eq!(guess.title.as_deref(), Some("untitled track"));
+        assert_eq!(guess.artist, None);
+        assert_eq!(parse_filename_metadata(Path::new("")).title, None);
+    }
+
+    #[test]
+    fn test_fill_missing_reports_tier() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("untagged.wav");
+        write_sine_wav(&path, 44_100, 4_410, 0.5);
+        let untagged = extract_metadata_from_file(&path).unwrap();
+        assert!(untagged.title.is_none());
+
+        let mut meta = untagged.clone();
+        let tier = meta.fill_missing(parse_filename_metadata(Path::new("Air - Sexy Boy.wav")));
+        assert_eq!(tier, MetadataTier::Filename);
+        assert_eq!(meta.title.as_deref(), Some("Sexy Boy"));
+        assert_eq!(meta.artist.as_deref(), Some("Air"));
+
+        let mut meta = untagged.clone();
+        let tier = meta.fill_missing(parse_filename_metadata(Path::new(
+            "Air/Moon Safari/01 La femme.wav",
+        )));
+        assert_eq!(tier, MetadataTier::Directory);
+        assert_eq!(meta.album.as_deref(), Some("Moon Safari"));
+
+        // Tags are never overwritten
+        let mut meta = untagged;
+        meta.title = Some("Tagged".to_string());
+        let tier = meta.fill_missing(parse_filename_metadata(Path::new("Other.wav")));
+        assert_eq!(tier, MetadataTier::Tags);
+        assert_eq!(meta.title.as_deref(), Some("Tagged"));
+    }
+
+    // ── embedded cover ──
+
+    fn picture(pic_type: PictureType, data: &[u8]) -> Picture {
+        Picture::new_unchecked(pic_type, None, None, data.to_vec())
+    }
+
+    #[test]
+    fn test_pick_cover_prefers_front_cover_across_tags() {
+        let mut id3 = Tag::new(lofty::tag::TagType::Id3v2);
+        id3.push_picture(picture(PictureType::Artist, b"artist"));
+        let mut ape = Tag::new(lofty::tag::TagType::Ape);
+        ape.push_picture(picture(PictureType::CoverFront, b"front"));
+
+        let tags = [id3, ape];
+        assert_eq!(pick_cover(&tags).map(|p| p.data()), Some(&b"front"[..]));
+    }
+
+    #[test]
+    fn test_pick_cover_falls_back_to_first_picture() {
+        let mut tag = Tag::new(lofty::tag::TagType::Id3v2);
+        tag.push_picture(picture(PictureType::Other, b"first"));
+        tag.push_picture(picture(PictureType::CoverBack, b"back"));
+
+        assert_eq!(pick_cover(&[tag]).map(|p| p.data()), Some(&b"first"[..]));
+    }
+
+    #[test]
+    fn test_pick_cover_none_without_pictures() {
+        assert!(pick_cover(&[Tag::new(lofty::tag::TagType::Id3v2)]).is_none());
+        assert!(pick_cover(&[]).is_none());
+    }
+
+    #[test]
+    fn test_extract_embedded_cover_unreadable_file() {
+        assert!(extract_embedded_cover(Path::new("/nonexistent/track.mp3")).is_none());
+    }
 }
@@ -1,6 +1,8 @@
 use async_trait::async_trait;
 use sha2::{Digest, Sha256};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use thiserror::Error;
 use tokio::fs;
 use uuid::Uuid;
@@ -50,16 +52,115 @@ pub trait StorageBackend: Send + Sync {
     }
 
     async fn list_files(&self, prefix: &str) -> Result<Vec<String>, StorageError>;
+
+    /// A time-limited URL clients can stream the file from directly, if the
+    /// backend supports one.
+    async fn presigned_url(&self, _relative_path: &str) -> Option<String> {
+        None
+    }
+
+    /// The local filesystem backend, for operations only it supports.
+    fn as_local(&self) -> Option<&AudioStorage> {
+        None
+    }
 }
 
 // ─── Local Filesystem Backend ──────────────────────────────────────
 
+/// How the local backend spreads audio files over directories
+/// (`AUDIO_STORAGE_SHARDING`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShardingStrategy {
+    /// `{user_id}/{album}/{filename}`
+    #[default]
+    None,
+    /// `{first character of the filename}/{filename}`
+    ByFirstChar,
+    /// `{hash[0..2]}/{hash[2..4]}/{filename}`, from the SHA-256 of the file
+    ByHashPrefix2,
+}
+
+impl ShardingStrategy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ShardingStrategy::None => "none",
+            ShardingStrategy::ByFirstChar => "by-first-char",
+            ShardingStrategy::ByHashPrefix2 => "by-hash-prefix-2",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" => Some(ShardingStrategy::None),
+            "by-first-char" => Some(ShardingStrategy::ByFirstChar),
+            "by-hash-prefix-2" => Some(ShardingStrategy::ByHashPrefix2),
+            _ => None,
+        }
+    }
+
+    /// Directory, relative to the storage root, a file is stored in under
+    /// this strategy; `None` for [`ShardingStrategy::None`], which keeps the
+    /// user and album layout. `sha256_hex` is only used by
+    /// [`ShardingStrategy::ByHashPrefix2`].
+    pub fn shard_dir(self, filename: &str, sha256_hex: &str) -> Option<PathBuf> {
+        match self {
+            ShardingStrategy::None => None,
+            ShardingStrategy::ByFirstChar => {
+                let first = filename
+                    .chars()
+                    .next()
+                    .filter(|c| c.is_alphanumeric())
+                    .map_or_else(|| "_".to_string(), |c| c.to_lowercase().to_string());
+                Some(PathBuf::from(first))
+            }
+            ShardingStrategy::ByHashPrefix2 => {
+                let shard = |range: Range<usize>| sha256_hex.get(range).unwrap_or("00");
+                Some(Path::new(shard(0..2)).join(shard(2..4)))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ShardingStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A file being moved by [`AudioStorage::link_to_shard`], as paths relative
+/// to the storage root.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ShardMove {
+    pub from: String,
+    pub to: String,
+}
+
+/// `dir/filename`, or `dir/{stem}_{uuid}.{ext}` if that file already exists.
+fn unique_path(dir: &Path, filename: &str) -> PathBuf {
+    let file_path = dir.join(filename);
+    if !file_path.exists() {
+        return file_path;
+    }
+    let stem = Path::new(filename)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("audio");
+    let ext = Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("bin");
+    dir.join(format!("{}_{}.{}", stem, Uuid::new_v4(), ext))
+}
+
 #[derive(Debug, Clone)]
 pub struct AudioStorage {
     base_path: PathBuf,
     /// Optional separate directory for metadata files (covers, etc.).
     /// When set, `store_cover` writes here instead of `base_path`.
     metadata_path: Option<PathBuf>,
+    /// Directory layout for new audio files
+    sharding: ShardingStrategy,
 }
 
 impl AudioStorage {
@@ -67,6 +168,7 @@ impl AudioStorage {
         Self {
             base_path: base_path.into(),
             metadata_path: None,
+            sharding: ShardingStrategy::None,
         }
     }
 
@@ -76,12 +178,30 @@ impl AudioStorage {
         let metadata = std::env::var("METADATA_STORAGE_PATH")
             .ok()
             .map(PathBuf::from);
+        let sharding = match std::env::var("AUDIO_STORAGE_SHARDING") {
+            Ok(value) => ShardingStrategy::parse(&value).unwrap_or_else(|| {
+                tracing::warn!(%value, "unknown AUDIO_STORAGE_SHARDING, using none");
+                ShardingStrategy::None
+            }),
+            Err(_) => ShardingStrategy::None,
+        };
         Self {
             base_path: base.into(),
             metadata_path: metadata,
+            sharding,
         }
     }
 
+    /// Use `sharding` for new audio files.
+    pub fn with_sharding(mut self, sharding: ShardingStrategy) -> Self {
+        self.sharding = sharding;
+        self
+    }
+
+    pub fn sharding(&self) -> ShardingStrategy {
+        self.sharding
+    }
+
     pub fn base(&self) -> &Path {
         &self.base_path
     }
@@ -97,6 +217,124 @@ impl AudioStorage {
     pub fn metadata_full_path(&self, relative_path: &str) -> PathBuf {
         self.metadata_base().join(relative_path)
     }
+
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.base_path)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Start moving the file at `relative_path` to its directory under
+    /// `strategy`: link (or copy) it to the new path, leaving the original
+    /// in place. Returns `None` if the file is already there. The caller
+    /// points its records at [`ShardMove::to`] and then calls
+    /// [`finish_shard_move`](Self::finish_shard_move), or
+    /// [`undo_shard_move`](Self::undo_shard_move) if it cannot. Both paths
+    /// hold the file in between, so a crash loses nothing, and running the
+    /// move again reuses the link already made.
+    pub async fn link_to_shard(
+        &self,
+        strategy: ShardingStrategy,
+        relative_path: &str,
+    ) -> Result<Option<ShardMove>, StorageError> {
+        if strategy == ShardingStrategy::None {
+            return Err(StorageError::Config(
+                "files can only be migrated to a sharded layout".to_string(),
+            ));
+        }
+        let source = self.full_path(relative_path);
+        let filename = match source.file_name().and_then(|f| f.to_str()) {
+            Some(f) if fs::metadata(&source).await.is_ok_and(|m| m.is_file()) => f,
+            _ => return Err(StorageError::NotFound(relative_path.to_string())),
+        };
+        let hash = match strategy {
+            ShardingStrategy::ByHashPrefix2 => self.hash_file(relative_path).await?,
+            _ => String::new(),
+        };
+        let Some(shard) = strategy.shard_dir(filename, &hash) else {
+            return Ok(None);
+        };
+        let dir = self.base_path.join(shard);
+        if source.parent() == Some(dir.as_path()) {
+            return Ok(None);
+        }
+        fs::create_dir_all(&dir).await?;
+
+        // Left behind by an earlier run that stopped before finishing
+        let existing = dir.join(filename);
+        if Self::same_file(&source, &existing).await {
+            return Ok(Some(ShardMove {
+                from: relative_path.to_string(),
+                to: self.relative(&existing),
+            }));
+        }
+
+        let target = unique_path(&dir, filename);
+        if fs::hard_link(&source, &target).await.is_err() {
+            // Filesystems without hard links get a copy
+            fs::copy(&source, &target).await?;
+        }
+        Ok(Some(ShardMove {
+            from: relative_path.to_string(),
+            to: self.relative(&target),
+        }))
+    }
+
+    /// Remove the original of a move started by
+    /// [`link_to_shard`](Self::link_to_shard), once nothing refers to it,
+    /// along with the directories it leaves empty.
+    pub async fn finish_shard_move(&self, m: &ShardMove) -> Result<(), StorageError> {
+        let source = self.full_path(&m.from);
+        match fs::remove_file(&source).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.remove_empty_dirs(source.parent()).await;
+        Ok(())
+    }
+
+    /// Drop the new path of a move started by
+    /// [`link_to_shard`](Self::link_to_shard), keeping the original. Best
+    /// effort: failures are logged.
+    pub async fn undo_shard_move(&self, m: &ShardMove) {
+        let target = self.full_path(&m.to);
+        if let Err(e) = fs::remove_file(&target).await {
+            tracing::error!(path = %m.to, error = %e, "failed to remove sharded copy");
+        }
+        self.remove_empty_dirs(target.parent()).await;
+    }
+
+    /// Internal: whether two paths are hard links to the same file.
+    #[cfg(unix)]
+    async fn same_file(a: &Path, b: &Path) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        match (fs::metadata(a).await, fs::metadata(b).await) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+
+    #[cfg(not(unix))]
+    async fn same_file(_a: &Path, _b: &Path) -> bool {
+        false
+    }
+
+    /// Internal: remove `dir` and its parents up to the storage root while
+    /// they are empty.
+    async fn remove_empty_dirs(&self, mut dir: Option<&Path>) {
+        while let Some(d) = dir {
+            if d == self.base_path || !d.starts_with(&self.base_path) {
+                break;
+            }
+            // Fails, and stops here, if the directory is not empty
+            if fs::remove_dir(d).await.is_err() {
+                break;
+            }
+            dir = d.parent();
+        }
+    }
 }
 
 #[async_trait]
@@ -112,39 +350,24 @@ impl StorageBackend for AudioStorage {
         let sanitized_album = sanitize_filename(album_dir);
         let sanitized_file = sanitize_filename(filename);
 
-        let dir = self
-            .base_path
-            .join(user_id.to_string())
-            .join(&sanitized_album);
+        let hash = match self.sharding {
+            ShardingStrategy::ByHashPrefix2 => format!("{:x}", Sha256::digest(data)),
+            _ => String::new(),
+        };
+        let dir = match self.sharding.shard_dir(&sanitized_file, &hash) {
+            Some(shard) => self.base_path.join(shard),
+            None => self
+                .base_path
+                .join(user_id.to_string())
+                .join(&sanitized_album),
+        };
 
         fs::create_dir_all(&dir).await?;
 
-        let file_path = dir.join(&sanitized_file);
-
-        let final_path = if file_path.exists() {
-            let stem = Path::new(&sanitized_file)
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("audio");
-            let ext = Path::new(&sanitized_file)
-                .extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("bin");
-            let unique = format!("{}_{}.{}", stem, Uuid::new_v4(), ext);
-            dir.join(unique)
-        } else {
-            file_path
-        };
-
+        let final_path = unique_path(&dir, &sanitized_file);
         fs::write(&final_path, data).await?;
 
-        let relative = final_path
-            .strip_prefix(&self.base_path)
-            .unwrap_or(&final_path)
-            .to_string_lossy()
-            .to_string();
-
-        Ok(relative)
+        Ok(self.relative(&final_path))
     }
 
     fn full_path(&self, relative_path: &str) -> PathBuf {
@@ -205,6 +428,10 @@ impl StorageBackend for AudioStorage {
         collect_files_recursive(&dir, &self.base_path, &mut result).await?;
         Ok(result)
     }
+
+    fn as_local(&self) -> Option<&AudioStorage> {
+        Some(self)
+    }
 }
 
 async fn collect_files_recursive(
@@ -231,12 +458,89 @@ async fn collect_files_recursive(
 
 // ─── S3 Backend ────────────────────────────────────────────────────
 
+/// Smallest part S3 accepts in a multipart upload (except the last one).
+const MIN_S3_PART_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Most parts S3 accepts in a multipart upload.
+const MAX_S3_PARTS: u64 = 10_000;
+
+/// Longest lifetime S3 allows for a pre-signed URL (7 days).
+const MAX_PRESIGNED_URL_EXPIRY_SECS: u64 = 7 * 24 * 3600;
+
+/// Upload and streaming settings for [`S3Storage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct S3Options {
+    /// Size of each multipart upload part; smaller files are sent in a
+    /// single request
+    pub part_size_bytes: u64,
+    /// Parts of one file uploaded at the same time
+    pub max_concurrent_parts: usize,
+    /// Lifetime of the pre-signed URLs tracks are streamed from
+    /// (0 = stream through the backend). Anyone holding one can read the
+    /// file until it expires.
+    pub presigned_url_expiry_secs: u64,
+}
+
+impl Default for S3Options {
+    fn default() -> Self {
+        Self {
+            part_size_bytes: 100 * 1024 * 1024,
+            max_concurrent_parts: 4,
+            presigned_url_expiry_secs: 3600,
+        }
+    }
+}
+
+impl S3Options {
+    /// Read `S3_PART_SIZE_MB`, `S3_UPLOAD_CONCURRENCY` and
+    /// `S3_PRESIGNED_URL_EXPIRY_SECS`, keeping the default for unset or
+    /// invalid values.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let part_size_bytes = std::env::var("S3_PART_SIZE_MB")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|mb| *mb > 0)
+            .map_or(default.part_size_bytes, |mb| mb * 1024 * 1024);
+        let max_concurrent_parts = std::env::var("S3_UPLOAD_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(default.max_concurrent_parts);
+        let presigned_url_expiry_secs = std::env::var("S3_PRESIGNED_URL_EXPIRY_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map_or(default.presigned_url_expiry_secs, |secs| {
+                secs.min(MAX_PRESIGNED_URL_EXPIRY_SECS)
+            });
+        Self {
+            part_size_bytes,
+            max_concurrent_parts,
+            presigned_url_expiry_secs,
+        }
+    }
+}
+
+/// Byte ranges of the parts a `len`-byte file is uploaded in. Parts are
+/// `part_size` bytes, raised to S3's 5 MiB minimum and to fit in 10,000
+/// parts; the last one holds the remainder.
+fn part_ranges(len: u64, part_size: u64) -> Vec<Range<u64>> {
+    let part_size = part_size
+        .max(MIN_S3_PART_SIZE)
+        .max(len.div_ceil(MAX_S3_PARTS));
+    (0..len)
+        .step_by(part_size as usize)
+        .map(|start| start..(start + part_size).min(len))
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct S3Storage {
     client: aws_sdk_s3::Client,
     bucket: String,
     prefix: String,
     cache_path: PathBuf,
+    options: S3Options,
 }
 
 impl S3Storage {
@@ -247,6 +551,27 @@ impl S3Storage {
         secret_key: &str,
         bucket: &str,
         prefix: &str,
+    ) -> Result<Self, StorageError> {
+        Self::from_config_with_options(
+            endpoint,
+            region,
+            access_key,
+            secret_key,
+            bucket,
+            prefix,
+            S3Options::default(),
+        )
+        .await
+    }
+
+    pub async fn from_config_with_options(
+        endpoint: Option<&str>,
+        region: &str,
+        access_key: &str,
+        secret_key: &str,
+        bucket: &str,
+        prefix: &str,
+        options: S3Options,
     ) -> Result<Self, StorageError> {
         let creds =
             aws_sdk_s3::config::Credentials::new(access_key, secret_key, None, None, "soundtime");
@@ -276,9 +601,123 @@ impl S3Storage {
             bucket: bucket.to_string(),
             prefix: prefix.to_string(),
             cache_path,
+            options,
         })
     }
 
+    /// Upload `data` to `key`, in parts of `options.part_size_bytes` when it
+    /// is larger than one part.
+    async fn put_object(&self, key: &str, data: &[u8]) -> Result<(), StorageError> {
+        if data.len() as u64 <= self.options.part_size_bytes.max(MIN_S3_PART_SIZE) {
+            self.client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .body(data.to_vec().into())
+                .send()
+                .await
+                .map_err(|e| StorageError::S3(format!("PutObject failed: {e}")))?;
+            return Ok(());
+        }
+
+        let upload = self
+            .client
+            .create_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| StorageError::S3(format!("CreateMultipartUpload failed: {e}")))?;
+        let upload_id = upload
+            .upload_id()
+            .ok_or_else(|| StorageError::S3("CreateMultipartUpload returned no upload ID".into()))?
+            .to_string();
+
+        let result = self.upload_parts(key, &upload_id, data).await;
+        if result.is_err() {
+            // Don't leave the uploaded parts billed in the bucket
+            if let Err(e) = self
+                .client
+                .abort_multipart_upload()
+                .bucket(&self.bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .send()
+                .await
+            {
+                tracing::warn!(%key, error = %e, "failed to abort multipart upload");
+            }
+        }
+        result
+    }
+
+    /// Upload the parts of a multipart upload, at most
+    /// `options.max_concurrent_parts` at a time, and complete it.
+    async fn upload_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+        data: &[u8],
+    ) -> Result<(), StorageError> {
+        use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+
+        let ranges = part_ranges(data.len() as u64, self.options.part_size_bytes);
+        tracing::debug!(%key, parts = ranges.len(), "starting multipart upload");
+
+        let mut in_flight = tokio::task::JoinSet::new();
+        let mut parts = Vec::with_capacity(ranges.len());
+        for (i, range) in ranges.into_iter().enumerate() {
+            if in_flight.len() >= self.options.max_concurrent_parts.max(1) {
+                if let Some(done) = in_flight.join_next().await {
+                    parts.push(done.map_err(|e| StorageError::S3(format!("UploadPart: {e}")))??);
+                }
+            }
+            let part_number = i as i32 + 1;
+            let request = self
+                .client
+                .upload_part()
+                .bucket(&self.bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(
+                    data[range.start as usize..range.end as usize]
+                        .to_vec()
+                        .into(),
+                );
+            in_flight.spawn(async move {
+                let resp = request.send().await.map_err(|e| {
+                    StorageError::S3(format!("UploadPart {part_number} failed: {e}"))
+                })?;
+                Ok::<_, StorageError>(
+                    CompletedPart::builder()
+                        .set_e_tag(resp.e_tag().map(str::to_string))
+                        .part_number(part_number)
+                        .build(),
+                )
+            });
+        }
+        while let Some(done) = in_flight.join_next().await {
+            parts.push(done.map_err(|e| StorageError::S3(format!("UploadPart: {e}")))??);
+        }
+        parts.sort_by_key(|p| p.part_number());
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|e| StorageError::S3(format!("CompleteMultipartUpload failed: {e}")))?;
+        Ok(())
+    }
+
     fn s3_key(&self, relative_path: &str) -> String {
         if self.prefix.is_empty() {
             relative_path.to_string()
@@ -332,14 +771,7 @@ impl StorageBackend for S3Storage {
 
         let final_key = self.s3_key(&final_relative);
 
-        self.client
-            .put_object()
-            .bucket(&self.bucket)
-            .key(&final_key)
-            .body(data.to_vec().into())
-            .send()
-            .await
-            .map_err(|e| StorageError::S3(format!("PutObject failed: {e}")))?;
+        self.put_object(&final_key, data).await?;
 
         // Cache locally for streaming
         let cache_file = self.cache_path.join(&final_relative);
@@ -500,6 +932,30 @@ impl StorageBackend for S3Storage {
 
         Ok(result)
     }
+
+    async fn presigned_url(&self, relative_path: &str) -> Option<String> {
+        if self.options.presigned_url_expiry_secs == 0 {
+            return None;
+        }
+        let config = aws_sdk_s3::presigning::PresigningConfig::expires_in(Duration::from_secs(
+            self.options.presigned_url_expiry_secs,
+        ))
+        .ok()?;
+        match self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(self.s3_key(relative_path))
+            .presigned(config)
+            .await
+        {
+            Ok(request) => Some(request.uri().to_string()),
+            Err(e) => {
+                tracing::warn!(%relative_path, error = %e, "failed to pre-sign S3 URL");
+                None
+            }
+        }
+    }
 }
 
 // ─── Helpers ───────────────────────────────────────────────────────
@@ -577,6 +1033,64 @@ mod tests {
         assert_eq!(sanitize_filename("日本語の曲.mp3"), "日本語の曲.mp3");
     }
 
+    #[test]
+    fn test_part_ranges_cover_file() {
+        let mib = 1024 * 1024;
+        let ranges = part_ranges(250 * mib, 100 * mib);
+        assert_eq!(
+            ranges,
+            vec![0..100 * mib, 100 * mib..200 * mib, 200 * mib..250 * mib]
+        );
+    }
+
+    #[test]
+    fn test_part_ranges_respect_s3_limits() {
+        // Raised to the 5 MiB minimum
+        let ranges = part_ranges(12 * 1024 * 1024, 1024);
+        assert_eq!(ranges.len(), 3);
+        assert_eq!(ranges[0].end - ranges[0].start, MIN_S3_PART_SIZE);
+
+        // Grown so a huge file fits in 10,000 parts
+        let len = 60 * 1024 * 1024 * 1024;
+        let ranges = part_ranges(len, MIN_S3_PART_SIZE);
+        assert!(ranges.len() as u64 <= MAX_S3_PARTS);
+        assert_eq!(ranges.last().unwrap().end, len);
+    }
+
+    #[test]
+    fn test_s3_options_from_env() {
+        std::env::remove_var("S3_PART_SIZE_MB");
+        std::env::remove_var("S3_UPLOAD_CONCURRENCY");
+        std::env::remove_var("S3_PRESIGNED_URL_EXPIRY_SECS");
+        assert_eq!(S3Options::from_env(), S3Options::default());
+        assert_eq!(S3Options::default().part_size_bytes, 100 * 1024 * 1024);
+        assert_eq!(S3Options::default().presigned_url_expiry_secs, 3600);
+
+        std::env::set_var("S3_PART_SIZE_MB", "16");
+        std::env::set_var("S3_UPLOAD_CONCURRENCY", "8");
+        std::env::set_var("S3_PRESIGNED_URL_EXPIRY_SECS", "0");
+        let opts = S3Options::from_env();
+        assert_eq!(opts.part_size_bytes, 16 * 1024 * 1024);
+        assert_eq!(opts.max_concurrent_parts, 8);
+        assert_eq!(opts.presigned_url_expiry_secs, 0);
+
+        // Zero or invalid sizes keep the defaults; expiry is capped at 7 days
+        std::env::set_var("S3_PART_SIZE_MB", "0");
+        std::env::set_var("S3_UPLOAD_CONCURRENCY", "many");
+        std::env::set_var("S3_PRESIGNED_URL_EXPIRY_SECS", "99999999");
+        let opts = S3Options::from_env();
+        assert_eq!(opts.part_size_bytes, 100 * 1024 * 1024);
+        assert_eq!(opts.max_concurrent_parts, 4);
+        assert_eq!(
+            opts.presigned_url_expiry_secs,
+            MAX_PRESIGNED_URL_EXPIRY_SECS
+        );
+
+        std::env::remove_var("S3_PART_SIZE_MB");
+        std::env::remove_var("S3_UPLOAD_CONCURRENCY");
+        std::env::remove_var("S3_PRESIGNED_URL_EXPIRY_SECS");
+    }
+
     #[test]
     fn test_audio_storage_new() {
         let storage = AudioStorage::new("/tmp/test-storage");
@@ -778,6 +1292,7 @@ mod tests {
         let storage = AudioStorage {
             base_path: tmp_audio.path().to_path_buf(),
             metadata_path: Some(tmp_meta.path().to_path_buf()),
+            sharding: ShardingStrategy::None,
         };
         let user_id = Uuid::new_v4();
 
@@ -791,4 +1306,153 @@ mod tests {
         assert!(!tmp_audio.path().join(&relative).exists());
         assert!(relative.contains("cover.jpg"));
     }
+
+    #[test]
+    fn test_sharding_strategy_parse() {
+        for strategy in [
+            ShardingStrategy::None,
+            ShardingStrategy::ByFirstChar,
+            ShardingStrategy::ByHashPrefix2,
+        ] {
+            assert_eq!(ShardingStrategy::parse(strategy.as_str()), Some(strategy));
+        }
+        assert_eq!(
+            ShardingStrategy::parse(" BY-HASH-PREFIX-2 "),
+            Some(ShardingStrategy::ByHashPrefix2)
+        );
+        assert_eq!(ShardingStrategy::parse("by-album"), None);
+    }
+
+    #[test]
+    fn test_shard_dir() {
+        assert_eq!(ShardingStrategy::None.shard_dir("song.mp3", "abcdef"), None);
+        assert_eq!(
+            ShardingStrategy::ByFirstChar.shard_dir("Song.mp3", ""),
+            Some(PathBuf::from("s"))
+        );
+        assert_eq!(
+            ShardingStrategy::ByFirstChar.shard_dir("_intro.mp3", ""),
+            Some(PathBuf::from("_"))
+        );
+        assert_eq!(
+            ShardingStrategy::ByHashPrefix2.shard_dir("song.mp3", "abcdef"),
+            Some(PathBuf::from("ab/cd"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_store_file_sharded_by_hash() {
+        let tmp = TempDir::new().unwrap();
+        let storage = AudioStorage::new(tmp.path()).with_sharding(ShardingStrategy::ByHashPrefix2);
+
+        let relative = storage
+            .store_file(
+                Uuid::new_v4(),
+                Some("album"),
+                "song.mp3",
+                b"fake audio data",
+            )
+            .await
+            .unwrap();
+
+        let hash = storage.hash_file(&relative).await.unwrap();
+        assert_eq!(
+            PathBuf::from(&relative),
+            Path::new(&hash[0..2]).join(&hash[2..4]).join("song.mp3")
+        );
+    }
+
+    /// Move every file in `paths` the way the sharding migration does.
+    async fn shard_all(storage: &AudioStorage, paths: &[String]) -> Vec<ShardMove> {
+        let mut moves = Vec::new();
+        for path in paths {
+            if let Some(m) = storage
+                .link_to_shard(ShardingStrategy::ByFirstChar, path)
+                .await
+                .unwrap()
+            {
+                storage.finish_shard_move(&m).await.unwrap();
+                moves.push(m);
+            }
+        }
+        moves
+    }
+
+    #[tokio::test]
+    async fn test_shard_moves_files() {
+        let tmp = TempDir::new().unwrap();
+        let storage = AudioStorage::new(tmp.path());
+        let user_id = Uuid::new_v4();
+        let first = storage
+            .store_file(user_id, Some("album"), "alpha.mp3", b"a")
+            .await
+            .unwrap();
+        let second = storage
+            .store_file(user_id, Some("album"), "beta.mp3", b"b")
+            .await
+            .unwrap();
+
+        let moves = shard_all(&storage, &[first.clone(), second]).await;
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[0].from, first);
+        assert_eq!(
+            PathBuf::from(&moves[0].to),
+            Path::new("a").join("alpha.mp3")
+        );
+        assert_eq!(storage.read_file(&moves[0].to).await.unwrap(), b"a");
+        assert!(!storage.file_exists(&first).await);
+        // The emptied album and user directories are removed
+        assert!(!tmp.path().join(user_id.to_string()).exists());
+
+        // Running it again finds everything in place
+        let paths: Vec<String> = moves.iter().map(|m| m.to.clone()).collect();
+        assert!(shard_all(&storage, &paths).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_shard_move_keeps_original_until_finished() {
+        let tmp = TempDir::new().unwrap();
+        let storage = AudioStorage::new(tmp.path());
+        let path = storage
+            .store_file(Uuid::new_v4(), None, "song.mp3", b"data")
+            .await
+            .unwrap();
+
+        let m = storage
+            .link_to_shard(ShardingStrategy::ByHashPrefix2, &path)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(storage.read_file(&m.from).await.unwrap(), b"data");
+        assert_eq!(storage.read_file(&m.to).await.unwrap(), b"data");
+
+        // A rerun after a crash at this point reuses the link
+        let again = storage
+            .link_to_shard(ShardingStrategy::ByHashPrefix2, &path)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(again, m);
+
+        // Undoing keeps only the original
+        storage.undo_shard_move(&m).await;
+        assert!(!storage.file_exists(&m.to).await);
+        assert_eq!(storage.read_file(&path).await.unwrap(), b"data");
+    }
+
+    #[tokio::test]
+    async fn test_shard_missing_file() {
+        let tmp = TempDir::new().unwrap();
+        let storage = AudioStorage::new(tmp.path());
+
+        let result = storage
+            .link_to_shard(ShardingStrategy::ByHashPrefix2, "missing/track.mp3")
+            .await;
+        assert!(matches!(result, Err(StorageError::NotFound(_))));
+
+        let result = storage
+            .link_to_shard(ShardingStrategy::None, "missing/track.mp3")
+            .await;
+        assert!(matches!(result, Err(StorageError::Config(_))));
+    }
 }
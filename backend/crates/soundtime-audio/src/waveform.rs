@@ -1,4 +1,6 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
 use symphonia::core::audio::SampleBuffer;
 use symphonia::core::codecs::DecoderOptions;
 use symphonia::core::formats::FormatOptions;
@@ -13,11 +15,63 @@ pub enum WaveformError {
     Io(#[from] std::io::Error),
     #[error("Decode error: {0}")]
     Decode(String),
+    #[error("Waveform task failed: {0}")]
+    Task(String),
+}
+
+/// Fraction (0.0..=1.0) of the audio decoded so far by a running waveform
+/// generation, readable from another thread.
+#[derive(Debug, Default)]
+pub struct WaveformProgress(AtomicU32);
+
+impl WaveformProgress {
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    fn set(&self, fraction: f32) {
+        self.0
+            .store(fraction.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Fraction of a track decoded once `decoded_frames` of `total_frames` are
+/// done. `None` if the container does not say how long the track is.
+fn decoded_fraction(decoded_frames: u64, total_frames: Option<u64>) -> Option<f32> {
+    match total_frames {
+        Some(total) if total > 0 => Some((decoded_frames as f64 / total as f64).min(1.0) as f32),
+        _ => None,
+    }
+}
+
+/// [`generate_waveform`] on the blocking thread pool, so decoding a long
+/// file does not stall the async runtime. `progress` is updated as packets
+/// are decoded.
+pub async fn generate_waveform_async(
+    path: PathBuf,
+    num_points: usize,
+    progress: Arc<WaveformProgress>,
+) -> Result<Vec<f32>, WaveformError> {
+    tokio::task::spawn_blocking(move || {
+        generate_waveform_with_progress(&path, num_points, &progress)
+    })
+    .await
+    .map_err(|e| WaveformError::Task(e.to_string()))?
 }
 
 /// Generate a waveform summary (peak amplitudes) for visualization.
 /// Returns a vector of normalized peak values (0.0..1.0) with `num_points` entries.
 pub fn generate_waveform(path: &Path, num_points: usize) -> Result<Vec<f32>, WaveformError> {
+    generate_waveform_with_progress(path, num_points, &WaveformProgress::default())
+}
+
+/// [`generate_waveform`], reporting how much of the file has been decoded
+/// to `progress`.
+pub fn generate_waveform_with_progress(
+    path: &Path,
+    num_points: usize,
+    progress: &WaveformProgress,
+) -> Result<Vec<f32>, WaveformError> {
     let file = std::fs::File::open(path)?;
     let mss = MediaSourceStream::new(Box::new(file), Default::default());
 
@@ -42,6 +96,7 @@ pub fn generate_waveform(path: &Path, num_points: usize) -> Result<Vec<f32>, Wav
         .ok_or_else(|| WaveformError::Decode("no default track".into()))?;
 
     let track_id = track.id;
+    let total_frames = track.codec_params.n_frames;
 
     let mut decoder = symphonia::default::get_codecs()
         .make(&track.codec_params, &DecoderOptions::default())
@@ -64,6 +119,9 @@ pub fn generate_waveform(path: &Path, num_points: usize) -> Result<Vec<f32>, Wav
         if packet.track_id() != track_id {
             continue;
         }
+        if let Some(fraction) = decoded_fraction(packet.ts() + packet.dur(), total_frames) {
+            progress.set(fraction);
+        }
 
         let decoded = match decoder.decode(&packet) {
             Ok(d) => d,
@@ -85,6 +143,7 @@ pub fn generate_waveform(path: &Path, num_points: usize) -> Result<Vec<f32>, Wav
         }
     }
 
+    progress.set(1.0);
     if all_peaks.is_empty() {
         return Ok(vec![0.0; num_points]);
     }
@@ -224,6 +283,43 @@ mod tests {
         // Trailing values should be padded with 0.0
     }
 
+    // 9. Decoded fraction is clamped and unknown without a frame count
+    #[test]
+    fn test_decoded_fraction() {
+        assert_eq!(decoded_fraction(0, Some(1000)), Some(0.0));
+        assert_eq!(decoded_fraction(250, Some(1000)), Some(0.25));
+        assert_eq!(decoded_fraction(1500, Some(1000)), Some(1.0));
+        assert_eq!(decoded_fraction(250, None), None);
+        assert_eq!(decoded_fraction(250, Some(0)), None);
+    }
+
+    // 10. Async generation matches the blocking one and finishes its progress
+    #[tokio::test]
+    async fn test_generate_waveform_async_reports_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("async.wav");
+        create_test_wav(&path, 44100, 1, 16, 44100);
+
+        let progress = Arc::new(WaveformProgress::default());
+        assert_eq!(progress.get(), 0.0);
+        let waveform = generate_waveform_async(path.clone(), 50, Arc::clone(&progress))
+            .await
+            .unwrap();
+        assert_eq!(waveform, generate_waveform(&path, 50).unwrap());
+        assert_eq!(progress.get(), 1.0);
+    }
+
+    // 11. Async generation surfaces decode errors
+    #[tokio::test]
+    async fn test_generate_waveform_async_not_audio() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notaudio.wav");
+        std::fs::write(&path, "this is not audio data").unwrap();
+
+        let result = generate_waveform_async(path, 50, Arc::default()).await;
+        assert!(matches!(result, Err(WaveformError::Decode(_))));
+    }
+
     // ─── Helper: create a minimal WAV file ───────────────────────────
     fn create_test_wav(
         path: &std::path::Path,
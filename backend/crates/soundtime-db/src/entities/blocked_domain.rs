@@ -12,6 +12,9 @@ pub struct Model {
     pub reason: Option<String>,
     pub blocked_by: Option<Uuid>,
     pub created_at: DateTimeWithTimeZone,
+    pub expires_at: Option<DateTimeWithTimeZone>,
+    /// Subscription that added the block, `None` for a manual block
+    pub source_subscription_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -22,6 +25,12 @@ pub enum Relation {
         to = "super::user::Column::Id"
     )]
     User,
+    #[sea_orm(
+        belongs_to = "super::blocklist_subscription::Entity",
+        from = "Column::SourceSubscriptionId",
+        to = "super::blocklist_subscription::Column::Id"
+    )]
+    BlocklistSubscription,
 }
 
 impl Related<super::user::Entity> for Entity {
@@ -30,4 +39,10 @@ impl Related<super::user::Entity> for Entity {
     }
 }
 
+impl Related<super::blocklist_subscription::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BlocklistSubscription.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
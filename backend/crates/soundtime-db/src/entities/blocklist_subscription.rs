@@ -0,0 +1,40 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A remote blocklist, in the format of the blocklist export, fetched
+/// periodically and merged into `blocked_domains`.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "blocklist_subscriptions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    #[sea_orm(column_type = "Text", unique)]
+    pub url: String,
+    pub enabled: bool,
+    /// `ETag` of the last successful fetch, sent back as `If-None-Match`
+    #[sea_orm(column_type = "Text", nullable)]
+    pub etag: Option<String>,
+    pub last_fetched: Option<DateTimeWithTimeZone>,
+    /// `ok`, `not_modified` or `error`
+    pub last_status: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_error: Option<String>,
+    /// Entries in the upstream list at the last successful fetch
+    pub entry_count: i32,
+    pub added_by: Option<Uuid>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::blocked_domain::Entity")]
+    BlockedDomain,
+}
+
+impl Related<super::blocked_domain::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BlockedDomain.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,28 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Counts from one health sweep of remote tracks.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "health_sweep_runs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub run_at: DateTimeWithTimeZone,
+    pub total_checked: i64,
+    pub healthy: i64,
+    pub recovered: i64,
+    pub failed: i64,
+    pub dereferenced: i64,
+    /// Tracks whose origin peer was offline
+    pub unavailable_source: i64,
+    /// Cached blobs whose bytes no longer matched their hash
+    pub corrupted: i64,
+    /// Long-dereferenced tracks hidden or deleted after the sweep
+    pub cleaned_up: i64,
+    pub duration_ms: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
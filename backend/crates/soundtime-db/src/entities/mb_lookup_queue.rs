@@ -0,0 +1,28 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A track whose MusicBrainz lookup failed and will be retried, or a
+/// replicated track waiting for the lookup that decides whether it is kept.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "mb_lookup_queue")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub track_id: Uuid,
+    pub title: String,
+    pub artist_name: String,
+    /// Lookups that failed so far, including the first
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// The track is hidden until a match is found, and rejected if the
+    /// lookup finds none
+    pub pending_verification: bool,
+    /// Peer a pending track was announced by
+    pub announced_by: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,21 +1,34 @@
+pub mod admin_audit_log;
 pub mod album;
 pub mod artist;
 pub mod blocked_domain;
+pub mod blocked_hash;
+pub mod blocklist_override;
+pub mod blocklist_subscription;
 pub mod favorite;
+pub mod health_sweep_run;
 pub mod instance_setting;
 pub mod library;
 pub mod library_track;
 pub mod listen_history;
+pub mod mb_lookup_queue;
 pub mod p2p_peer;
+pub mod p2p_peer_filter;
+pub mod p2p_seed_peer;
+pub mod peer_track_grant;
+pub mod pinned_track;
 pub mod playlist;
 pub mod playlist_track;
 pub mod plugin;
 pub mod plugin_config;
 pub mod plugin_events_log;
+pub mod published_hash;
+pub mod remote_play_count;
 pub mod remote_track;
 pub mod theme;
 pub mod track;
 pub mod track_embedding;
+pub mod track_recovery_attempt;
 pub mod track_report;
 pub mod user;
 pub mod user_setting;
@@ -1,7 +1,7 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "p2p_peers")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
@@ -12,6 +12,16 @@ pub struct Model {
     pub is_online: bool,
     pub last_seen_at: DateTimeWithTimeZone,
     pub created_at: DateTimeWithTimeZone,
+    pub last_catalog_sync_at: Option<DateTimeWithTimeZone>,
+    pub capabilities: Option<serde_json::Value>,
+    pub trusted_moderator: bool,
+    pub p50_rtt_ms: Option<i32>,
+    pub label: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub notes: Option<String>,
+    pub trust: String,
+    pub avg_latency_ms: Option<i32>,
+    pub success_rate: Option<f32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,25 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "p2p_peer_filters")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub peer_id: String,
+    /// Lowercase genre names (JSON array, empty = any genre)
+    #[sea_orm(column_type = "JsonBinary")]
+    pub allowed_genres: serde_json::Value,
+    /// Lowercase artist names (JSON array, empty = any artist)
+    #[sea_orm(column_type = "JsonBinary")]
+    pub allowed_artists: serde_json::Value,
+    /// Most tracks replicated from this peer (`None` = no limit)
+    pub max_tracks: Option<i64>,
+    /// Replicate catalog entries but never download the peer's blobs
+    pub metadata_only: bool,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,23 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A seed peer added by an admin. The node connects to it at startup and
+/// on every refresh cycle, alongside the seeds from `P2P_SEED_PEERS`.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "p2p_seed_peers")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    #[sea_orm(unique)]
+    pub node_id: String,
+    /// Free-form name shown in the admin peer list
+    pub label: Option<String>,
+    pub added_at: DateTimeWithTimeZone,
+    /// Admin who added the seed
+    pub added_by: Option<Uuid>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
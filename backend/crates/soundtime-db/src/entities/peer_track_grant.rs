@@ -0,0 +1,24 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A signed, time-limited grant letting a peer fetch one private track.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "peer_track_grants")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub content_hash: String,
+    /// Peer allowed to fetch the track
+    pub node_id: String,
+    /// Peer that signed the grant and serves the track
+    pub issuer_node: String,
+    #[sea_orm(column_type = "Text")]
+    pub token: String,
+    pub issued_at: DateTimeWithTimeZone,
+    pub expires_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
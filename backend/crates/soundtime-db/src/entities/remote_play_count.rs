@@ -0,0 +1,21 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Latest play count a peer gossiped for a content hash.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "remote_play_counts")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub content_hash: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub peer_id: String,
+    pub play_count: i64,
+    /// Period the count covers on the peer, in seconds
+    pub window_secs: i64,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
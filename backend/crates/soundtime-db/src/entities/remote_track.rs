@@ -20,6 +20,12 @@ pub struct Model {
     pub format: Option<String>,
     pub is_available: bool,
     pub last_checked_at: Option<DateTimeWithTimeZone>,
+    /// When a health sweep first found the track dereferenced; cleared once
+    /// it is available again
+    pub dereferenced_at: Option<DateTimeWithTimeZone>,
+    /// The origin's signature from the announcement the track was
+    /// replicated from, forwarded when reseeding it
+    pub signature: Option<String>,
     pub created_at: DateTimeWithTimeZone,
 }
 
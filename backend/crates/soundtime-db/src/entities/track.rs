@@ -25,8 +25,28 @@ pub struct Model {
     pub uploaded_by: Option<Uuid>,
     /// BLAKE3 content hash from iroh-blobs (set when P2P is enabled)
     pub content_hash: Option<String>,
+    /// Chromaprint acoustic fingerprint (from `fpcalc`), used to detect
+    /// re-encodes of the same recording
+    pub fingerprint: Option<String>,
     #[sea_orm(default_value = "0")]
     pub play_count: i64,
+    /// Only served to peers holding a grant (see `peer_track_grants`)
+    #[sea_orm(default_value = "false")]
+    pub is_private: bool,
+    /// Replicated track left out of browsing after being dereferenced for
+    /// longer than the cleanup retention, or because its hash is blocked
+    #[sea_orm(default_value = "false")]
+    pub is_hidden: bool,
+    /// Hidden by the block on its hash rather than for being dead or
+    /// unverified; shown again when the hash is unblocked
+    #[sea_orm(default_value = "false")]
+    pub hidden_by_block: bool,
+    /// EBU R128 integrated loudness in LUFS
+    pub loudness_lufs: Option<f32>,
+    /// EBU R128 loudness range in LU
+    pub dynamic_range: Option<f32>,
+    /// How the audio is encoded, e.g. "CBR 320kbps" or "Lossless 16-bit/44.1kHz"
+    pub encoding_quality: Option<String>,
     pub created_at: DateTimeWithTimeZone,
 }
 
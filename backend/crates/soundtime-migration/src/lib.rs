@@ -33,6 +33,33 @@ mod m20240101_000030_add_behavioral_signals;
 mod m20240101_000031_add_track_embeddings;
 mod m20240101_000032_add_performance_indexes;
 mod m20240101_000033_refresh_collation_version;
+mod m20240101_000034_add_track_fingerprint;
+mod m20240101_000035_add_peer_catalog_sync_at;
+mod m20240101_000036_add_peer_capabilities;
+mod m20240101_000037_create_blocked_hashes;
+mod m20240101_000038_add_peer_p50_rtt;
+mod m20240101_000039_create_p2p_peer_filters;
+mod m20240101_000040_create_remote_play_counts;
+mod m20240101_000041_create_peer_track_grants;
+mod m20240101_000042_create_health_sweep_runs;
+mod m20240101_000043_add_trigram_search;
+mod m20240101_000044_create_published_hashes;
+mod m20240101_000045_create_track_recovery_attempts;
+mod m20240101_000046_create_mb_lookup_queue;
+mod m20240101_000047_add_health_sweep_corrupted;
+mod m20240101_000048_add_dereferenced_cleanup;
+mod m20240101_000049_add_track_quality_metadata;
+mod m20240101_000050_create_pinned_tracks;
+mod m20240101_000051_create_p2p_seed_peers;
+mod m20240101_000052_add_peer_label_notes_trust;
+mod m20240101_000053_add_peer_reliability;
+mod m20240101_000054_add_blocked_domain_expiry;
+mod m20240101_000055_create_blocklist_subscriptions;
+mod m20240101_000056_create_admin_audit_log;
+mod m20240101_000057_add_track_listing_index;
+mod m20240101_000058_add_remote_track_signature;
+mod m20240101_000059_add_mb_pending_verification;
+mod m20240101_000060_add_track_hidden_by_block;
 
 pub struct Migrator;
 
@@ -73,6 +100,33 @@ impl MigratorTrait for Migrator {
             Box::new(m20240101_000031_add_track_embeddings::Migration),
             Box::new(m20240101_000032_add_performance_indexes::Migration),
             Box::new(m20240101_000033_refresh_collation_version::Migration),
+            Box::new(m20240101_000034_add_track_fingerprint::Migration),
+            Box::new(m20240101_000035_add_peer_catalog_sync_at::Migration),
+            Box::new(m20240101_000036_add_peer_capabilities::Migration),
+            Box::new(m20240101_000037_create_blocked_hashes::Migration),
+            Box::new(m20240101_000038_add_peer_p50_rtt::Migration),
+            Box::new(m20240101_000039_create_p2p_peer_filters::Migration),
+            Box::new(m20240101_000040_create_remote_play_counts::Migration),
+            Box::new(m20240101_000041_create_peer_track_grants::Migration),
+            Box::new(m20240101_000042_create_health_sweep_runs::Migration),
+            Box::new(m20240101_000043_add_trigram_search::Migration),
+            Box::new(m20240101_000044_create_published_hashes::Migration),
+            Box::new(m20240101_000045_create_track_recovery_attempts::Migration),
+            Box::new(m20240101_000046_create_mb_lookup_queue::Migration),
+            Box::new(m20240101_000047_add_health_sweep_corrupted::Migration),
+            Box::new(m20240101_000048_add_dereferenced_cleanup::Migration),
+            Box::new(m20240101_000049_add_track_quality_metadata::Migration),
+            Box::new(m20240101_000050_create_pinned_tracks::Migration),
+            Box::new(m20240101_000051_create_p2p_seed_peers::Migration),
+            Box::new(m20240101_000052_add_peer_label_notes_trust::Migration),
+            Box::new(m20240101_000053_add_peer_reliability::Migration),
+            Box::new(m20240101_000054_add_blocked_domain_expiry::Migration),
+            Box::new(m20240101_000055_create_blocklist_subscriptions::Migration),
+            Box::new(m20240101_000056_create_admin_audit_log::Migration),
+            Box::new(m20240101_000057_add_track_listing_index::Migration),
+            Box::new(m20240101_000058_add_remote_track_signature::Migration),
+            Box::new(m20240101_000059_add_mb_pending_verification::Migration),
+            Box::new(m20240101_000060_add_track_hidden_by_block::Migration),
         ]
     }
 }
@@ -0,0 +1,58 @@
+//! Migration 34 — Chromaprint acoustic fingerprint on tracks.
+//!
+//! Adds a nullable `tracks.fingerprint` column holding the compressed
+//! Chromaprint fingerprint produced by `fpcalc`, plus a partial index so
+//! duplicate lookups (`WHERE fingerprint = $1`) and the admin duplicates
+//! report do not scan tracks without a fingerprint.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tracks::Table)
+                    .add_column(ColumnDef::new(Tracks::Fingerprint).text().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE INDEX IF NOT EXISTS idx_tracks_fingerprint ON tracks (fingerprint) WHERE fingerprint IS NOT NULL",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DROP INDEX IF EXISTS idx_tracks_fingerprint")
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tracks::Table)
+                    .drop_column(Tracks::Fingerprint)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tracks {
+    Table,
+    Fingerprint,
+}
@@ -0,0 +1,49 @@
+//! Migration 35 — remember when each peer last received our full catalog.
+//!
+//! Adds a nullable `p2p_peers.last_catalog_sync_at` column. Peers with a
+//! timestamp get a `CatalogDelta` of newer tracks on Ping instead of the
+//! whole catalog.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(P2pPeers::Table)
+                    .add_column(
+                        ColumnDef::new(P2pPeers::LastCatalogSyncAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(P2pPeers::Table)
+                    .drop_column(P2pPeers::LastCatalogSyncAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum P2pPeers {
+    Table,
+    LastCatalogSyncAt,
+}
@@ -0,0 +1,44 @@
+//! Migration 36 — remember the optional features each peer supports.
+//!
+//! Adds a nullable JSONB `p2p_peers.capabilities` column holding the
+//! capability names from the peer's last Pong (e.g. `signed-announcements`).
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(P2pPeers::Table)
+                    .add_column(ColumnDef::new(P2pPeers::Capabilities).json_binary().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(P2pPeers::Table)
+                    .drop_column(P2pPeers::Capabilities)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum P2pPeers {
+    Table,
+    Capabilities,
+}
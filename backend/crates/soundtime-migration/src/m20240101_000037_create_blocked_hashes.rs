@@ -0,0 +1,48 @@
+//! Migration 37 — network-wide moderation of content hashes.
+//!
+//! Creates `blocked_hashes`, holding track blob hashes this instance refuses
+//! to serve, either blocked by its own admin or received from a peer
+//! (`source_peer`). Blocks from peers not trusted as moderators wait with
+//! status `pending` until an admin approves them. Also adds
+//! `p2p_peers.trusted_moderator`, set by the admin for peers whose blocks
+//! are applied automatically.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE TABLE IF NOT EXISTS blocked_hashes (
+                hash        VARCHAR(255) PRIMARY KEY,
+                reason      TEXT NOT NULL DEFAULT '',
+                source_peer VARCHAR(255),
+                status      VARCHAR(16) NOT NULL DEFAULT 'active',
+                created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "ALTER TABLE p2p_peers
+                ADD COLUMN IF NOT EXISTS trusted_moderator BOOLEAN NOT NULL DEFAULT false",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("ALTER TABLE p2p_peers DROP COLUMN IF EXISTS trusted_moderator")
+            .await?;
+        db.execute_unprepared("DROP TABLE IF EXISTS blocked_hashes")
+            .await?;
+        Ok(())
+    }
+}
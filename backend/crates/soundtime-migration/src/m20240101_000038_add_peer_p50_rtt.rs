@@ -0,0 +1,46 @@
+//! Migration 38 — remember each peer's median latency.
+//!
+//! Adds a nullable `p2p_peers.p50_rtt_ms` column with the median of the
+//! peer's recent ping round-trip times, used to query low-latency peers
+//! first in distributed search.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(P2pPeers::Table)
+                    .add_column(ColumnDef::new(P2pPeers::P50RttMs).integer().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(P2pPeers::Table)
+                    .drop_column(P2pPeers::P50RttMs)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum P2pPeers {
+    Table,
+    #[sea_orm(iden = "p50_rtt_ms")]
+    P50RttMs,
+}
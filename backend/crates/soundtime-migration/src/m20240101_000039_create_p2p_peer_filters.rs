@@ -0,0 +1,39 @@
+//! Migration 39 — per-peer replication filters.
+//!
+//! Creates `p2p_peer_filters`, one optional row per peer restricting which
+//! of its announced tracks are replicated: genre and artist allowlists
+//! (JSON arrays, empty = any), a cap on tracks replicated from the peer, and
+//! a metadata-only mode in which its blobs are never downloaded.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE TABLE IF NOT EXISTS p2p_peer_filters (
+                peer_id         VARCHAR(255) PRIMARY KEY,
+                allowed_genres  JSONB NOT NULL DEFAULT '[]',
+                allowed_artists JSONB NOT NULL DEFAULT '[]',
+                max_tracks      BIGINT,
+                metadata_only   BOOLEAN NOT NULL DEFAULT false,
+                updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP TABLE IF EXISTS p2p_peer_filters")
+            .await?;
+        Ok(())
+    }
+}
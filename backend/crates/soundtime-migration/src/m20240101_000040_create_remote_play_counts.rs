@@ -0,0 +1,45 @@
+//! Migration 40 — play counts gossiped by peers.
+//!
+//! Creates `remote_play_counts`, one row per (content hash, peer) holding the
+//! latest count the peer reported in `PopularityGossip`. Rows are replaced
+//! whenever the peer gossips again; `updated_at` drives the decay applied
+//! when ranking popular tracks.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE TABLE IF NOT EXISTS remote_play_counts (
+                content_hash VARCHAR(255) NOT NULL,
+                peer_id      VARCHAR(255) NOT NULL,
+                play_count   BIGINT NOT NULL DEFAULT 0,
+                window_secs  BIGINT NOT NULL DEFAULT 0,
+                updated_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (content_hash, peer_id)
+            )",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_remote_play_counts_peer
+                ON remote_play_counts (peer_id)",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP TABLE IF EXISTS remote_play_counts")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+//! Migration 41 — per-track access grants for private tracks.
+//!
+//! Adds `tracks.is_private`: the blob of a private track is only served to
+//! peers holding a grant. Creates `peer_track_grants`, one row per grant
+//! token, both those this instance issued (`issuer_node` = local node) and
+//! those peers issued to it.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "ALTER TABLE tracks ADD COLUMN IF NOT EXISTS is_private BOOLEAN NOT NULL DEFAULT FALSE",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE TABLE IF NOT EXISTS peer_track_grants (
+                id           UUID PRIMARY KEY,
+                content_hash VARCHAR(255) NOT NULL,
+                node_id      VARCHAR(255) NOT NULL,
+                issuer_node  VARCHAR(255) NOT NULL,
+                token        TEXT NOT NULL,
+                issued_at    TIMESTAMPTZ NOT NULL,
+                expires_at   TIMESTAMPTZ NOT NULL
+            )",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_peer_track_grants_lookup
+                ON peer_track_grants (issuer_node, content_hash, expires_at)",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP TABLE IF EXISTS peer_track_grants")
+            .await?;
+        db.execute_unprepared("ALTER TABLE tracks DROP COLUMN IF EXISTS is_private")
+            .await?;
+        Ok(())
+    }
+}
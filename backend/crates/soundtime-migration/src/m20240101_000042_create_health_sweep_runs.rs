@@ -0,0 +1,47 @@
+//! Migration 42 — history of track health sweeps.
+//!
+//! Creates `health_sweep_runs`, one row per sweep of remote tracks with its
+//! counts and duration, so admins can follow health over time. Rows older
+//! than 90 days are deleted at the start of each sweep.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE TABLE IF NOT EXISTS health_sweep_runs (
+                id                 UUID PRIMARY KEY,
+                run_at             TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                total_checked      BIGINT NOT NULL DEFAULT 0,
+                healthy            BIGINT NOT NULL DEFAULT 0,
+                recovered          BIGINT NOT NULL DEFAULT 0,
+                failed             BIGINT NOT NULL DEFAULT 0,
+                dereferenced       BIGINT NOT NULL DEFAULT 0,
+                unavailable_source BIGINT NOT NULL DEFAULT 0,
+                duration_ms        BIGINT NOT NULL DEFAULT 0
+            )",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_health_sweep_runs_run_at
+                ON health_sweep_runs (run_at DESC)",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP TABLE IF EXISTS health_sweep_runs")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+//! Migration 43 — trigram indexes for fuzzy P2P search.
+//!
+//! Enables `pg_trgm` and adds GIN trigram indexes on `tracks.title` and
+//! `artists.name`. Peer searches that find nothing with full-text search
+//! fall back to trigram similarity, so misspelled queries still match.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS pg_trgm")
+            .await?;
+
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_tracks_title_trgm \
+             ON tracks USING gin (title gin_trgm_ops)",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_artists_name_trgm \
+             ON artists USING gin (name gin_trgm_ops)",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP INDEX IF EXISTS idx_artists_name_trgm")
+            .await?;
+        db.execute_unprepared("DROP INDEX IF EXISTS idx_tracks_title_trgm")
+            .await?;
+        // Don't drop the extension — other things might use it
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+//! Migration 44 — persisted set of published blob hashes.
+//!
+//! Creates `published_hashes`, one row per blob this node serves to peers
+//! (track audio and images), kept in step with the blob store's
+//! `published-*` tags. At startup the node reads it page by page instead
+//! of scanning `tracks`. Seeded from the content hashes already in `tracks`.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE TABLE IF NOT EXISTS published_hashes (
+                hash         VARCHAR(64) PRIMARY KEY,
+                kind         VARCHAR(16) NOT NULL,
+                published_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "INSERT INTO published_hashes (hash, kind)
+                SELECT DISTINCT content_hash, 'track' FROM tracks
+                WHERE content_hash IS NOT NULL
+             ON CONFLICT (hash) DO NOTHING",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP TABLE IF EXISTS published_hashes")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+//! Migration 45 — audit log of track recovery attempts.
+//!
+//! Creates `track_recovery_attempts`, one row per run of the auto-repair
+//! that follows a failed P2P fetch: which peer it last tried and whether
+//! the track came back. Only the newest 10,000 rows are kept; older ones are
+//! deleted at the start of each health sweep.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE TABLE IF NOT EXISTS track_recovery_attempts (
+                id            UUID PRIMARY KEY,
+                content_hash  VARCHAR(64) NOT NULL,
+                origin_node   VARCHAR(255) NOT NULL,
+                peer_tried    VARCHAR(255),
+                success       BOOLEAN NOT NULL,
+                error_message TEXT,
+                attempted_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_track_recovery_attempts_attempted_at
+                ON track_recovery_attempts (attempted_at DESC)",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_track_recovery_attempts_hash
+                ON track_recovery_attempts (content_hash, attempted_at DESC)",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP TABLE IF EXISTS track_recovery_attempts")
+            .await?;
+        Ok(())
+    }
+}
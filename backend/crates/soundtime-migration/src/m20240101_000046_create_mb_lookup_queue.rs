@@ -0,0 +1,40 @@
+//! Migration 46 — queue of failed MusicBrainz lookups.
+//!
+//! Creates `mb_lookup_queue`, one row per track whose MusicBrainz lookup
+//! failed with a network error or a 5xx/429 response after all retries.
+//! The next `POST /api/admin/metadata/enrich-all` retries every queued
+//! lookup before enriching the rest of the library.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE TABLE IF NOT EXISTS mb_lookup_queue (
+                track_id    UUID PRIMARY KEY REFERENCES tracks(id) ON DELETE CASCADE,
+                title       TEXT NOT NULL,
+                artist_name TEXT NOT NULL,
+                attempts    INTEGER NOT NULL DEFAULT 1,
+                last_error  TEXT,
+                created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP TABLE IF EXISTS mb_lookup_queue")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+//! Migration 47 — count corrupted blobs found by health sweeps.
+//!
+//! Adds `health_sweep_runs.corrupted`, the number of locally cached blobs
+//! whose bytes no longer matched their content hash when a sweep re-hashed
+//! them. Earlier runs count as 0.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HealthSweepRuns::Table)
+                    .add_column(
+                        ColumnDef::new(HealthSweepRuns::Corrupted)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HealthSweepRuns::Table)
+                    .drop_column(HealthSweepRuns::Corrupted)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum HealthSweepRuns {
+    Table,
+    Corrupted,
+}
@@ -0,0 +1,110 @@
+//! Migration 48 — retention cleanup of dereferenced remote tracks.
+//!
+//! Adds `remote_tracks.dereferenced_at`, set when a health sweep first finds
+//! the track dereferenced and cleared once it is available again, so the
+//! cleanup pass can tell how long it has been unplayable. `tracks.is_hidden`
+//! keeps hidden replicated tracks out of browsing, and
+//! `health_sweep_runs.cleaned_up` counts the tracks each sweep hid or
+//! deleted.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RemoteTracks::Table)
+                    .add_column(
+                        ColumnDef::new(RemoteTracks::DereferencedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tracks::Table)
+                    .add_column(
+                        ColumnDef::new(Tracks::IsHidden)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HealthSweepRuns::Table)
+                    .add_column(
+                        ColumnDef::new(HealthSweepRuns::CleanedUp)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HealthSweepRuns::Table)
+                    .drop_column(HealthSweepRuns::CleanedUp)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tracks::Table)
+                    .drop_column(Tracks::IsHidden)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RemoteTracks::Table)
+                    .drop_column(RemoteTracks::DereferencedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum RemoteTracks {
+    Table,
+    DereferencedAt,
+}
+
+#[derive(DeriveIden)]
+enum Tracks {
+    Table,
+    IsHidden,
+}
+
+#[derive(DeriveIden)]
+enum HealthSweepRuns {
+    Table,
+    CleanedUp,
+}
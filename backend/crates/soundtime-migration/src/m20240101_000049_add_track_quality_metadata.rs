@@ -0,0 +1,53 @@
+//! Migration 49 — audio quality metadata on tracks.
+//!
+//! Adds `tracks.loudness_lufs` (EBU R128 integrated loudness),
+//! `tracks.dynamic_range` (EBU R128 loudness range, in LU) and
+//! `tracks.encoding_quality` (e.g. "CBR 320kbps", "Lossless 24-bit/96kHz").
+//! All are nullable: tracks uploaded before this migration, and replicated
+//! tracks from peers that don't send them, have none.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tracks::Table)
+                    .add_column(ColumnDef::new(Tracks::LoudnessLufs).float().null())
+                    .add_column(ColumnDef::new(Tracks::DynamicRange).float().null())
+                    .add_column(ColumnDef::new(Tracks::EncodingQuality).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tracks::Table)
+                    .drop_column(Tracks::EncodingQuality)
+                    .drop_column(Tracks::DynamicRange)
+                    .drop_column(Tracks::LoudnessLufs)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tracks {
+    Table,
+    LoudnessLufs,
+    DynamicRange,
+    EncodingQuality,
+}
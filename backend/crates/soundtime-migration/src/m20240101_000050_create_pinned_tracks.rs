@@ -0,0 +1,36 @@
+//! Migration 50 — tracks pinned in the P2P blob cache.
+//!
+//! Creates `pinned_tracks`, one row per track whose blob the LRU cache must
+//! never evict. The content hash is copied so the node can load its pins at
+//! startup without joining `tracks`. Deleting the track drops the pin.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE TABLE IF NOT EXISTS pinned_tracks (
+                track_id     UUID PRIMARY KEY REFERENCES tracks(id) ON DELETE CASCADE,
+                content_hash VARCHAR(255) NOT NULL,
+                pinned_by    UUID REFERENCES users(id) ON DELETE SET NULL,
+                pinned_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP TABLE IF EXISTS pinned_tracks")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+//! Migration 51 — P2P seed peers managed from the admin API.
+//!
+//! Creates `p2p_seed_peers`, the seed list admins edit at runtime. The node
+//! connects to these on startup and retries them every refresh cycle, on top
+//! of the seeds from `P2P_SEED_PEERS`.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE TABLE IF NOT EXISTS p2p_seed_peers (
+                id       UUID PRIMARY KEY,
+                node_id  VARCHAR(255) NOT NULL UNIQUE,
+                label    VARCHAR(255),
+                added_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                added_by UUID REFERENCES users(id) ON DELETE SET NULL
+            )",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP TABLE IF EXISTS p2p_seed_peers")
+            .await?;
+        Ok(())
+    }
+}
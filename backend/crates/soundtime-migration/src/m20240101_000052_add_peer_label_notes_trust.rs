@@ -0,0 +1,40 @@
+//! Migration 52 — admin annotations on known peers.
+//!
+//! Adds `p2p_peers.label` (a nickname), `p2p_peers.notes` (free text) and
+//! `p2p_peers.trust` (`trusted`, `normal` or `quarantined`). Quarantined
+//! peers are still pinged but left out of catalog sync and peer exchange;
+//! trusted peers are preferred as fetch sources.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "ALTER TABLE p2p_peers
+                ADD COLUMN IF NOT EXISTS label VARCHAR(255),
+                ADD COLUMN IF NOT EXISTS notes TEXT,
+                ADD COLUMN IF NOT EXISTS trust VARCHAR(16) NOT NULL DEFAULT 'normal'",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "ALTER TABLE p2p_peers
+                DROP COLUMN IF EXISTS trust,
+                DROP COLUMN IF EXISTS notes,
+                DROP COLUMN IF EXISTS label",
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+//! Migration 53 — peer latency and reliability history.
+//!
+//! Adds `p2p_peers.avg_latency_ms` and `p2p_peers.success_rate` (0–1), both
+//! computed over the peer's last 50 pings and fetches, so fetch source
+//! selection keeps preferring responsive peers across restarts.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "ALTER TABLE p2p_peers
+                ADD COLUMN IF NOT EXISTS avg_latency_ms INTEGER,
+                ADD COLUMN IF NOT EXISTS success_rate REAL",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "ALTER TABLE p2p_peers
+                DROP COLUMN IF EXISTS success_rate,
+                DROP COLUMN IF EXISTS avg_latency_ms",
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+//! Migration 54 — time-limited peer blocks.
+//!
+//! Adds a nullable `blocked_domains.expires_at`. Rows without it stay
+//! permanent; rows whose expiry has passed are treated as unblocked and
+//! cleaned up the next time the block is checked.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "ALTER TABLE blocked_domains ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_blocked_domains_expires_at
+                ON blocked_domains (expires_at) WHERE expires_at IS NOT NULL",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP INDEX IF EXISTS idx_blocked_domains_expires_at")
+            .await?;
+        db.execute_unprepared("ALTER TABLE blocked_domains DROP COLUMN IF EXISTS expires_at")
+            .await?;
+        Ok(())
+    }
+}
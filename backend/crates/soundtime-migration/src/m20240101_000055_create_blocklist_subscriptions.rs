@@ -0,0 +1,77 @@
+//! Migration 55 — blocklist subscriptions.
+//!
+//! Creates `blocklist_subscriptions`, remote blocklists (in the format of
+//! `GET /api/admin/blocked-domains/export`) fetched periodically and merged
+//! into `blocked_domains`. `blocked_domains.source_subscription_id` records
+//! which subscription added a block; it is `NULL` for manual blocks.
+//! `blocklist_overrides` holds domains an admin unblocked by hand, which
+//! subscriptions must not block again.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE TABLE IF NOT EXISTS blocklist_subscriptions (
+                id           UUID PRIMARY KEY,
+                url          TEXT NOT NULL UNIQUE,
+                enabled      BOOLEAN NOT NULL DEFAULT TRUE,
+                etag         TEXT,
+                last_fetched TIMESTAMPTZ,
+                last_status  VARCHAR(32),
+                last_error   TEXT,
+                entry_count  INTEGER NOT NULL DEFAULT 0,
+                added_by     UUID REFERENCES users(id) ON DELETE SET NULL,
+                created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+        )
+        .await?;
+
+        // Dropping a subscription drops the blocks it added
+        db.execute_unprepared(
+            "ALTER TABLE blocked_domains
+                ADD COLUMN IF NOT EXISTS source_subscription_id UUID
+                    REFERENCES blocklist_subscriptions(id) ON DELETE CASCADE",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_blocked_domains_source_subscription
+                ON blocked_domains (source_subscription_id)
+                WHERE source_subscription_id IS NOT NULL",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE TABLE IF NOT EXISTS blocklist_overrides (
+                domain     VARCHAR(255) PRIMARY KEY,
+                created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP TABLE IF EXISTS blocklist_overrides")
+            .await?;
+        db.execute_unprepared("DROP INDEX IF EXISTS idx_blocked_domains_source_subscription")
+            .await?;
+        db.execute_unprepared(
+            "ALTER TABLE blocked_domains DROP COLUMN IF EXISTS source_subscription_id",
+        )
+        .await?;
+        db.execute_unprepared("DROP TABLE IF EXISTS blocklist_subscriptions")
+            .await?;
+        Ok(())
+    }
+}
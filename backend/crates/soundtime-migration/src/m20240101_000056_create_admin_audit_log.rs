@@ -0,0 +1,58 @@
+//! Migration 56 — admin audit log.
+//!
+//! Creates `admin_audit_log`: one row per admin action (peer block/unblock,
+//! user ban/unban, track moderation, setting change) with who did it, to
+//! what, why, when and from which IP. Rows outlive the acting user, whose
+//! ID is then cleared.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE TABLE IF NOT EXISTS admin_audit_log (
+                id         UUID PRIMARY KEY,
+                actor_id   UUID REFERENCES users(id) ON DELETE SET NULL,
+                action     VARCHAR(32) NOT NULL,
+                target     TEXT NOT NULL,
+                reason     TEXT,
+                ip_address VARCHAR(64),
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_admin_audit_log_created_at
+                ON admin_audit_log (created_at DESC)",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_admin_audit_log_actor
+                ON admin_audit_log (actor_id, created_at DESC)",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_admin_audit_log_action
+                ON admin_audit_log (action, created_at DESC)",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP TABLE IF EXISTS admin_audit_log")
+            .await?;
+        Ok(())
+    }
+}
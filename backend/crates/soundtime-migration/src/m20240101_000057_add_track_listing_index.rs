@@ -0,0 +1,32 @@
+//! Migration 57 — index for cursor pagination of the track listing.
+//!
+//! `GET /api/tracks?after=` seeks with `(created_at, id) < (cursor)` and
+//! orders by `created_at DESC, id DESC`; this index serves both, so a page
+//! deep into the catalog costs as much as the first one.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_tracks_listing
+                ON tracks (created_at DESC, id DESC) WHERE is_hidden = false",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP INDEX IF EXISTS idx_tracks_listing")
+            .await?;
+        Ok(())
+    }
+}
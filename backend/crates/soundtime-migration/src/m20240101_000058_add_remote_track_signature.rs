@@ -0,0 +1,28 @@
+//! Migration 58 — keep the origin's signature of replicated tracks.
+//!
+//! Adds a nullable `remote_tracks.signature`: the origin's ed25519
+//! signature from the announcement a track was replicated from, so the
+//! track can be reseeded with it. NULL for unsigned announcements and
+//! non-P2P rows.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("ALTER TABLE remote_tracks ADD COLUMN IF NOT EXISTS signature TEXT")
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("ALTER TABLE remote_tracks DROP COLUMN IF EXISTS signature")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+//! Migration 59 — replicated tracks waiting for MusicBrainz verification.
+//!
+//! With the replication policy's MusicBrainz requirement on, a replicated
+//! track is stored hidden and its lookup runs in the background. Adds to
+//! `mb_lookup_queue`:
+//!
+//! - `pending_verification`: the track stays hidden until the lookup finds
+//!   a match, and is rejected if it finds none
+//! - `announced_by`: the peer the track came from, for the rejection log
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "ALTER TABLE mb_lookup_queue
+                ADD COLUMN IF NOT EXISTS pending_verification BOOLEAN NOT NULL DEFAULT FALSE,
+                ADD COLUMN IF NOT EXISTS announced_by TEXT",
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "ALTER TABLE mb_lookup_queue
+                DROP COLUMN IF EXISTS announced_by,
+                DROP COLUMN IF EXISTS pending_verification",
+        )
+        .await?;
+        Ok(())
+    }
+}
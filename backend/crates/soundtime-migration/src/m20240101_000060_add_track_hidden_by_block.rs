@@ -0,0 +1,30 @@
+//! Migration 60 — replicated tracks hidden because their hash is blocked.
+//!
+//! `tracks.is_hidden` is also set for dead and unverified replicated
+//! tracks. Adds `tracks.hidden_by_block` so unblocking a hash shows again
+//! exactly the tracks its block hid.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "ALTER TABLE tracks
+                ADD COLUMN IF NOT EXISTS hidden_by_block BOOLEAN NOT NULL DEFAULT FALSE",
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("ALTER TABLE tracks DROP COLUMN IF EXISTS hidden_by_block")
+            .await?;
+        Ok(())
+    }
+}
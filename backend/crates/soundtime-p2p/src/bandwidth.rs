@@ -0,0 +1,268 @@
+//! Upload bandwidth limiting for blobs served to peers.
+//!
+//! Outgoing `FetchTrack` responses are written in chunks, and every chunk must
+//! first take tokens from a global bucket (shared by all connections) and from
+//! the requesting peer's own bucket. When a bucket is empty the writer sleeps
+//! until enough tokens have refilled instead of buffering data.
+//!
+//! A limit of `0` means unlimited. Limits can be changed at runtime.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Size of each chunk written to the stream when serving a blob.
+pub const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Mutable state of a token bucket.
+struct BucketState {
+    /// Refill rate in bytes per second (0 = unlimited).
+    rate: u64,
+    /// Currently available tokens (bytes). May go negative when a chunk
+    /// larger than the bucket capacity is admitted.
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket holding up to one second worth of bytes.
+pub struct TokenBucket {
+    state: Mutex<BucketState>,
+}
+
+impl TokenBucket {
+    /// Create a bucket refilling at `rate` bytes per second (0 = unlimited).
+    /// The bucket starts full.
+    pub fn new(rate: u64) -> Self {
+        Self {
+            state: Mutex::new(BucketState {
+                rate,
+                tokens: rate as f64,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Current rate in bytes per second (0 = unlimited).
+    pub async fn rate(&self) -> u64 {
+        self.state.lock().await.rate
+    }
+
+    /// Change the refill rate. Available tokens are clamped to the new capacity.
+    pub async fn set_rate(&self, rate: u64) {
+        let mut state = self.state.lock().await;
+        state.rate = rate;
+        state.tokens = state.tokens.min(rate as f64);
+        state.last_refill = Instant::now();
+    }
+
+    /// Wait until `n` bytes may be sent, then consume them.
+    ///
+    /// A request larger than the bucket capacity is admitted once the bucket
+    /// is full, leaving it in debt so the average rate is still respected.
+    pub async fn acquire(&self, n: usize) {
+        let n = n as f64;
+        loop {
+            let wait = {
+                let mut state = self.state.lock().await;
+                if state.rate == 0 {
+                    return;
+                }
+
+                let now = Instant::now();
+                let capacity = state.rate as f64;
+                let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+                state.tokens = (state.tokens + elapsed * capacity).min(capacity);
+                state.last_refill = now;
+
+                if state.tokens >= n.min(capacity) {
+                    state.tokens -= n;
+                    return;
+                }
+
+                let missing = n.min(capacity) - state.tokens;
+                Duration::from_secs_f64(missing / capacity)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Global + per-peer upload limiter shared by all incoming connections.
+pub struct UploadLimiter {
+    global: TokenBucket,
+    per_peer_bps: AtomicU64,
+    peers: Mutex<HashMap<String, Arc<TokenBucket>>>,
+}
+
+impl UploadLimiter {
+    /// Create a limiter with the given global and per-peer limits
+    /// in bytes per second (0 = unlimited).
+    pub fn new(global_bps: u64, per_peer_bps: u64) -> Self {
+        Self {
+            global: TokenBucket::new(global_bps),
+            per_peer_bps: AtomicU64::new(per_peer_bps),
+            peers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Current global limit in bytes per second (0 = unlimited).
+    pub async fn global_limit(&self) -> u64 {
+        self.global.rate().await
+    }
+
+    /// Current per-peer limit in bytes per second (0 = unlimited).
+    pub fn per_peer_limit(&self) -> u64 {
+        self.per_peer_bps.load(Ordering::Relaxed)
+    }
+
+    /// Change the global limit at runtime.
+    pub async fn set_global_limit(&self, bps: u64) {
+        self.global.set_rate(bps).await;
+    }
+
+    /// Change the per-peer limit at runtime, applying it to existing peers.
+    pub async fn set_per_peer_limit(&self, bps: u64) {
+        self.per_peer_bps.store(bps, Ordering::Relaxed);
+        let buckets: Vec<Arc<TokenBucket>> = self.peers.lock().await.values().cloned().collect();
+        for bucket in buckets {
+            bucket.set_rate(bps).await;
+        }
+    }
+
+    /// Wait until `n` bytes may be sent to `peer_id`.
+    pub async fn acquire(&self, peer_id: &str, n: usize) {
+        let peer_bucket = {
+            let mut peers = self.peers.lock().await;
+            peers
+                .entry(peer_id.to_string())
+                .or_insert_with(|| Arc::new(TokenBucket::new(self.per_peer_limit())))
+                .clone()
+        };
+        peer_bucket.acquire(n).await;
+        self.global.acquire(n).await;
+    }
+
+    /// Forget the per-peer bucket of a peer (e.g. when it is removed).
+    pub async fn remove_peer(&self, peer_id: &str) {
+        self.peers.lock().await.remove(peer_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Simulate serving a blob through the limiter the way the `FetchTrack`
+    /// handler does, returning the elapsed (virtual) time.
+    async fn serve(limiter: &UploadLimiter, peer_id: &str, size: usize) -> Duration {
+        let data = vec![0u8; size];
+        let mut sink = tokio::io::sink();
+        let start = Instant::now();
+        for chunk in data.chunks(UPLOAD_CHUNK_SIZE) {
+            limiter.acquire(peer_id, chunk.len()).await;
+            tokio::io::AsyncWriteExt::write_all(&mut sink, chunk)
+                .await
+                .unwrap();
+        }
+        start.elapsed()
+    }
+
+    // ── token bucket ──
+
+    #[tokio::test(start_paused = true)]
+    async fn test_unlimited_does_not_sleep() {
+        let limiter = UploadLimiter::new(0, 0);
+        let elapsed = serve(&limiter, "peer", 10 * 1024 * 1024).await;
+        assert_eq!(elapsed, Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_global_limit_10mb_at_1mb_per_sec() {
+        let limiter = UploadLimiter::new(1024 * 1024, 0);
+        let elapsed = serve(&limiter, "peer", 10 * 1024 * 1024).await;
+        // One second of burst is available up front, the rest is paced.
+        let secs = elapsed.as_secs_f64();
+        assert!((8.5..=10.5).contains(&secs), "took {secs}s");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_per_peer_limit() {
+        let limiter = UploadLimiter::new(0, 512 * 1024);
+        let elapsed = serve(&limiter, "peer", 2 * 1024 * 1024).await;
+        let secs = elapsed.as_secs_f64();
+        assert!((2.5..=4.5).contains(&secs), "took {secs}s");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_global_bucket_shared_across_peers() {
+        let limiter = Arc::new(UploadLimiter::new(1024 * 1024, 0));
+        let start = Instant::now();
+        let a = {
+            let limiter = Arc::clone(&limiter);
+            tokio::spawn(async move { serve(&limiter, "a", 3 * 1024 * 1024).await })
+        };
+        let b = {
+            let limiter = Arc::clone(&limiter);
+            tokio::spawn(async move { serve(&limiter, "b", 3 * 1024 * 1024).await })
+        };
+        a.await.unwrap();
+        b.await.unwrap();
+        // 6 MB total through a 1 MB/s shared bucket
+        let secs = start.elapsed().as_secs_f64();
+        assert!((4.5..=6.5).contains(&secs), "took {secs}s");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_chunk_larger_than_capacity() {
+        let bucket = TokenBucket::new(1000);
+        bucket.acquire(5000).await;
+        let start = Instant::now();
+        bucket.acquire(1000).await;
+        // Bucket was left 4000 bytes in debt, then needs 1000 more
+        let secs = start.elapsed().as_secs_f64();
+        assert!((4.5..=5.5).contains(&secs), "took {secs}s");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_remove_peer_drops_its_bucket() {
+        let limiter = UploadLimiter::new(0, 1024 * 1024);
+        // The first megabyte is the bucket's burst, the second is paced
+        serve(&limiter, "peer", 1024 * 1024).await;
+        let secs = serve(&limiter, "peer", 1024 * 1024).await.as_secs_f64();
+        assert!(secs >= 0.5, "took {secs}s");
+
+        // A removed peer starts over with a full bucket
+        limiter.remove_peer("peer").await;
+        assert!(limiter.peers.lock().await.is_empty());
+        let elapsed = serve(&limiter, "peer", 1024 * 1024).await;
+        assert_eq!(elapsed, Duration::ZERO);
+    }
+
+    // ── runtime adjustment ──
+
+    #[tokio::test]
+    async fn test_set_limits_at_runtime() {
+        let limiter = UploadLimiter::new(100, 10);
+        limiter.acquire("peer", 1).await;
+
+        limiter.set_global_limit(2000).await;
+        limiter.set_per_peer_limit(500).await;
+        assert_eq!(limiter.global_limit().await, 2000);
+        assert_eq!(limiter.per_peer_limit(), 500);
+
+        let peers = limiter.peers.lock().await;
+        assert_eq!(peers.get("peer").unwrap().rate().await, 500);
+    }
+
+    #[tokio::test]
+    async fn test_remove_peer() {
+        let limiter = UploadLimiter::new(0, 10);
+        limiter.acquire("peer", 1).await;
+        limiter.remove_peer("peer").await;
+        assert!(limiter.peers.lock().await.is_empty());
+    }
+}
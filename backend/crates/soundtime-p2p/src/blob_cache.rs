@@ -6,21 +6,102 @@
 //!
 //! This module does NOT own the blob store — it wraps access patterns to
 //! provide bounded-size caching on top of the persistent `FsStore`.
+//!
+//! The limit can be changed at runtime; an admin-set limit is stored in
+//! `instance_settings` under [`SETTING_KEY`] and wins over the environment.
+//!
+//! Pinned blobs (see `pinned_tracks`) are never evicted. They still count
+//! towards the total size, so pins can push the rest of the cache out.
+//!
+//! With `P2P_CACHE_MIN_FREE_PCT` set, eviction also watches the filesystem
+//! holding the blobs: when its free space drops below that percentage, blobs
+//! are evicted until the freed bytes cover the shortfall, even if the cache
+//! is within its own limit. This keeps a growing library on the same disk
+//! from filling it up.
 
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use iroh_blobs::store::fs::FsStore;
 use iroh_blobs::{Hash, HashAndFormat};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, FromQueryResult, QueryFilter,
+    Set, Statement,
+};
+use serde::Serialize;
+use soundtime_db::entities::instance_setting;
 use tokio::sync::{Mutex, RwLock};
 use tracing::{debug, info, warn};
 
+use crate::error::P2pError;
+use crate::metrics::P2P_METRICS;
+use crate::track_health::{verify_blob, TrackFetcher};
+
 // ── Constants ────────────────────────────────────────────────────────
 
 /// Default maximum cache size: 2 GB.
 const DEFAULT_MAX_CACHE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
 
+/// Default number of recently played P2P tracks fetched at startup.
+pub const DEFAULT_WARM_UP_LIMIT: usize = 20;
+
+/// `instance_settings` key holding an admin-set cache limit in bytes.
+pub const SETTING_KEY: &str = "p2p_cache_max_bytes";
+
 // ── Types ────────────────────────────────────────────────────────────
 
+/// Size and free space of a filesystem, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskSpace {
+    pub total: u64,
+    pub available: u64,
+}
+
+impl DiskSpace {
+    /// Bytes to free to get back to `min_free_pct` percent free.
+    pub fn deficit(&self, min_free_pct: f64) -> u64 {
+        let wanted = (self.total as f64 * min_free_pct / 100.0).ceil() as u64;
+        wanted.saturating_sub(self.available)
+    }
+}
+
+/// Reports free space on the filesystem holding the blobs. Mocked in tests.
+pub trait FreeSpaceProvider: Send + Sync {
+    fn disk_space(&self) -> std::io::Result<DiskSpace>;
+}
+
+/// [`FreeSpaceProvider`] reading the filesystem containing `path`
+/// (`statvfs` on Unix).
+pub struct FsFreeSpace {
+    path: PathBuf,
+}
+
+impl FsFreeSpace {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl FreeSpaceProvider for FsFreeSpace {
+    fn disk_space(&self) -> std::io::Result<DiskSpace> {
+        Ok(DiskSpace {
+            total: fs2::total_space(&self.path)?,
+            available: fs2::available_space(&self.path)?,
+        })
+    }
+}
+
+/// Minimum free space to keep on the blob filesystem.
+struct DiskWatermark {
+    min_free_pct: f64,
+    provider: Box<dyn FreeSpaceProvider>,
+    /// Bytes evicted for the watermark that the filesystem may not show as
+    /// free yet (blobs are only deleted by garbage collection). Cleared once
+    /// the watermark is met again.
+    pending: AtomicU64,
+}
+
 /// Metadata for a single cached blob.
 #[derive(Debug, Clone)]
 struct CacheEntry {
@@ -38,10 +119,47 @@ pub struct BlobCache {
     entries: RwLock<HashMap<Hash, CacheEntry>>,
     /// Total size of all cached blobs in bytes.
     total_size: RwLock<u64>,
-    /// Maximum cache size in bytes.
-    max_size: u64,
+    /// Maximum cache size in bytes, adjustable at runtime.
+    max_size: AtomicU64,
+    /// Blobs evicted since startup.
+    evictions: AtomicU64,
     /// Set of hashes currently being fetched (prevents duplicate fetches).
     in_flight: Mutex<HashSet<Hash>>,
+    /// Hashes exempt from eviction, cached or not, with how many pins hold
+    /// each (tracks sharing a blob pin it once each).
+    pins: RwLock<HashMap<Hash, usize>>,
+    /// Recently played P2P tracks fetched at startup (0 = no warm-up).
+    warm_up_limit: usize,
+    /// When the last warm-up finished.
+    last_warm_up: std::sync::Mutex<Option<chrono::DateTime<chrono::Utc>>>,
+    /// Blobs fetched by the last pre-warm cycle.
+    prewarmed_last_cycle: std::sync::Mutex<Option<usize>>,
+    /// Free-space watermark on the blob filesystem, if enabled.
+    watermark: Option<DiskWatermark>,
+}
+
+/// Cache usage, as reported by `GET /api/admin/p2p/cache/stats`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlobCacheStats {
+    pub total_bytes: u64,
+    pub max_bytes: u64,
+    pub entries: usize,
+    /// Blobs evicted to stay under `max_bytes` since startup
+    pub evictions: u64,
+    /// Pinned hashes, including those not fetched yet
+    pub pinned: usize,
+    /// Part of `total_bytes` taken by pinned blobs
+    pub pinned_bytes: u64,
+    /// Track reads served from the local blob store since startup
+    pub hits: u64,
+    /// Track reads that had to go to the network since startup
+    pub misses: u64,
+    /// `hits / (hits + misses)`, `None` before the first read
+    pub hit_rate: Option<f64>,
+    pub last_warm_up_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Blobs of favorited or playlisted tracks fetched by the last pre-warm
+    /// cycle; `None` if pre-warming is off or has not run yet
+    pub prewarmed_last_cycle: Option<usize>,
 }
 
 impl BlobCache {
@@ -50,8 +168,31 @@ impl BlobCache {
         Self {
             entries: RwLock::new(HashMap::new()),
             total_size: RwLock::new(0),
-            max_size,
+            max_size: AtomicU64::new(max_size),
+            evictions: AtomicU64::new(0),
             in_flight: Mutex::new(HashSet::new()),
+            pins: RwLock::new(HashMap::new()),
+            warm_up_limit: DEFAULT_WARM_UP_LIMIT,
+            last_warm_up: std::sync::Mutex::new(None),
+            prewarmed_last_cycle: std::sync::Mutex::new(None),
+            watermark: None,
+        }
+    }
+
+    /// Also evict when free space reported by `provider` drops below
+    /// `min_free_pct` percent, whatever the cache's own size.
+    pub fn with_disk_watermark(
+        self,
+        min_free_pct: f64,
+        provider: impl FreeSpaceProvider + 'static,
+    ) -> Self {
+        Self {
+            watermark: Some(DiskWatermark {
+                min_free_pct,
+                provider: Box::new(provider),
+                pending: AtomicU64::new(0),
+            }),
+            ..self
         }
     }
 
@@ -59,17 +200,169 @@ impl BlobCache {
     ///
     /// Accepts values like `"2GB"`, `"512MB"`, `"1TB"`, or raw byte counts.
     /// Falls back to [`DEFAULT_MAX_CACHE_BYTES`] (2 GB) if not set or invalid.
-    pub fn from_env() -> Self {
+    /// `P2P_CACHE_MIN_FREE_PCT` enables the free-space watermark on the
+    /// filesystem holding `blobs_dir`.
+    pub fn from_env(blobs_dir: &Path) -> Self {
         let max_size = std::env::var("P2P_CACHE_MAX_SIZE")
             .ok()
             .and_then(|v| parse_size(&v))
             .unwrap_or(DEFAULT_MAX_CACHE_BYTES);
+        let warm_up_limit = std::env::var("P2P_CACHE_WARM_UP_LIMIT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_WARM_UP_LIMIT);
+
+        let min_free_pct = std::env::var("P2P_CACHE_MIN_FREE_PCT")
+            .ok()
+            .and_then(|v| parse_min_free_pct(&v));
 
         info!(
             max_size_mb = max_size / (1024 * 1024),
+            warm_up_limit,
+            min_free_pct = min_free_pct.unwrap_or(0.0),
             "P2P blob cache configured"
         );
-        Self::new(max_size)
+        let cache = Self {
+            warm_up_limit,
+            ..Self::new(max_size)
+        };
+        match min_free_pct {
+            Some(pct) => cache.with_disk_watermark(pct, FsFreeSpace::new(blobs_dir)),
+            None => cache,
+        }
+    }
+
+    /// Recently played P2P tracks to fetch at startup, from
+    /// `P2P_CACHE_WARM_UP_LIMIT` (default [`DEFAULT_WARM_UP_LIMIT`]).
+    pub fn warm_up_limit(&self) -> usize {
+        self.warm_up_limit
+    }
+
+    /// Fetch the blobs of the `limit` P2P tracks played most recently, so
+    /// their first play after a restart does not wait on the network.
+    ///
+    /// Blobs already in the store are skipped. Each blob is tried from every
+    /// peer known to hold it, online ones first, and stored only once its
+    /// hash verifies. Returns the hashes that were fetched. The caller is
+    /// left to [`evict_if_needed`](Self::evict_if_needed).
+    pub async fn warm_up_from_history(
+        &self,
+        db: &DatabaseConnection,
+        blob_store: &FsStore,
+        fetcher: &dyn TrackFetcher,
+        limit: usize,
+    ) -> Vec<Hash> {
+        if limit == 0 {
+            return Vec::new();
+        }
+        let hashes = match recently_played_p2p_hashes(db, limit).await {
+            Ok(h) => h,
+            Err(e) => {
+                warn!("failed to read listen history for cache warm-up: {e}");
+                return Vec::new();
+            }
+        };
+
+        let mut warmed = Vec::new();
+        for hash_str in hashes {
+            let Ok(hash) = hash_str.parse::<Hash>() else {
+                continue;
+            };
+            if fetcher.check_blob_exists(&hash_str).await || !self.try_start_fetch(hash).await {
+                continue;
+            }
+            let result = self.warm_up_blob(blob_store, fetcher, hash).await;
+            self.finish_fetch(hash).await;
+            match result {
+                Ok(peer) => {
+                    info!(%hash, %peer, "warmed blob cache");
+                    warmed.push(hash);
+                }
+                Err(e) => debug!(%hash, "cache warm-up fetch failed: {e}"),
+            }
+        }
+
+        *self.last_warm_up.lock().unwrap_or_else(|e| e.into_inner()) = Some(chrono::Utc::now());
+        info!(warmed = warmed.len(), "blob cache warm-up complete");
+        warmed
+    }
+
+    /// Internal: fetch and store one blob for the warm-up. Returns the peer
+    /// that served it.
+    async fn warm_up_blob(
+        &self,
+        blob_store: &FsStore,
+        fetcher: &dyn TrackFetcher,
+        hash: Hash,
+    ) -> Result<String, P2pError> {
+        let hash_str = hash.to_string();
+        let mut sources = fetcher.alternative_sources(&hash_str).await;
+        sources.sort_by_key(|s| !s.is_online);
+
+        let mut last_err = P2pError::TrackNotFound(hash_str.clone());
+        for source in sources {
+            let result = fetcher
+                .fetch_track(&source.peer_id, &hash_str)
+                .await
+                .and_then(|data| verify_blob(&hash, &data).map(|()| data));
+            let data = match result {
+                Ok(data) => data,
+                Err(e) => {
+                    last_err = e;
+                    continue;
+                }
+            };
+            let _tag = blob_store
+                .blobs()
+                .add_bytes(data.clone())
+                .temp_tag()
+                .await
+                .map_err(|e| P2pError::BlobStore(e.to_string()))?;
+            self.record_access_with_tag(hash, data.len() as u64, blob_store)
+                .await;
+            return Ok(source.peer_id);
+        }
+        Err(last_err)
+    }
+
+    /// Record how many blobs the latest pre-warm cycle fetched.
+    pub fn set_prewarmed_last_cycle(&self, count: usize) {
+        *self
+            .prewarmed_last_cycle
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(count);
+    }
+
+    /// Current usage, with hit counts since startup.
+    pub async fn stats(&self) -> BlobCacheStats {
+        let hits = P2P_METRICS.blob_cache_hits_total.get();
+        let misses = P2P_METRICS.blob_cache_misses_total.get();
+        let reads = hits + misses;
+        let pins = self.pins.read().await.clone();
+        let pinned_bytes = self
+            .entries
+            .read()
+            .await
+            .iter()
+            .filter(|(hash, _)| pins.contains_key(*hash))
+            .map(|(_, entry)| entry.size)
+            .sum();
+        BlobCacheStats {
+            total_bytes: self.total_size().await,
+            max_bytes: self.max_size(),
+            entries: self.entry_count().await,
+            evictions: self.evictions.load(Ordering::Relaxed),
+            pinned: pins.len(),
+            pinned_bytes,
+            hits,
+            misses,
+            hit_rate: (reads > 0).then(|| hits as f64 / reads as f64),
+            last_warm_up_at: *self.last_warm_up.lock().unwrap_or_else(|e| e.into_inner()),
+            prewarmed_last_cycle: *self
+                .prewarmed_last_cycle
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()),
+        }
     }
 
     /// Record an access to a blob, adding it to the cache if not already tracked.
@@ -145,36 +438,116 @@ impl BlobCache {
         in_flight.insert(hash)
     }
 
+    /// Whether a fetch for this hash is currently in progress.
+    pub async fn is_fetching(&self, hash: Hash) -> bool {
+        self.in_flight.lock().await.contains(&hash)
+    }
+
     /// Mark a fetch as completed (whether success or failure).
     pub async fn finish_fetch(&self, hash: Hash) {
         let mut in_flight = self.in_flight.lock().await;
         in_flight.remove(&hash);
     }
 
-    /// Evict least-recently-used blobs until total size is within the limit.
+    /// Exempt a blob from eviction, whether or not it is cached yet. Pins
+    /// are counted, so every call needs a matching [`unpin`](Self::unpin).
+    /// Returns `false` if it was already pinned.
+    pub async fn pin(&self, hash: Hash) -> bool {
+        let mut pins = self.pins.write().await;
+        let count = pins.entry(hash).or_insert(0);
+        *count += 1;
+        *count == 1
+    }
+
+    /// Release one pin on a blob. Once the last is released the blob is
+    /// evictable again and stays cached until the next
+    /// [`evict_if_needed`](Self::evict_if_needed) needs the space. Returns
+    /// `true` only when this released the last pin.
+    pub async fn unpin(&self, hash: Hash) -> bool {
+        let mut pins = self.pins.write().await;
+        match pins.get_mut(&hash) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                false
+            }
+            Some(_) => {
+                pins.remove(&hash);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a blob is pinned.
+    pub async fn is_pinned(&self, hash: Hash) -> bool {
+        self.pins.read().await.contains_key(&hash)
+    }
+
+    /// Bytes to free to restore the free-space watermark, less what earlier
+    /// evictions will free once collected; 0 when it is met, disabled or
+    /// the filesystem cannot be read.
+    fn watermark_deficit(&self) -> u64 {
+        let Some(watermark) = &self.watermark else {
+            return 0;
+        };
+        match watermark.provider.disk_space() {
+            Ok(space) => {
+                let deficit = space.deficit(watermark.min_free_pct);
+                if deficit == 0 {
+                    watermark.pending.store(0, Ordering::Relaxed);
+                }
+                deficit.saturating_sub(watermark.pending.load(Ordering::Relaxed))
+            }
+            Err(e) => {
+                warn!(error = %e, "failed to read free disk space for the blob cache");
+                0
+            }
+        }
+    }
+
+    /// Evict least-recently-used blobs until total size is within the limit
+    /// and, with a disk watermark, until the evicted bytes cover the free
+    /// space shortfall.
     ///
     /// Blobs are evicted by removing their tags from the `FsStore`, which makes
-    /// them eligible for garbage collection by iroh-blobs.
+    /// them eligible for garbage collection by iroh-blobs. Pinned blobs are
+    /// skipped, so the cache may stay over the limit if pins alone exceed it.
     /// If tag deletion fails for a particular blob, it is skipped and the error logged.
-    pub async fn evict_if_needed(&self, blob_store: &FsStore) {
+    /// Returns the hashes that were evicted.
+    pub async fn evict_if_needed(&self, blob_store: &FsStore) -> Vec<Hash> {
+        let max_size = self.max_size();
         let current_total = *self.total_size.read().await;
-        if current_total <= self.max_size {
-            return;
+        let deficit = self.watermark_deficit();
+        if current_total <= max_size && deficit == 0 {
+            return Vec::new();
+        }
+        if deficit > 0 {
+            warn!(
+                deficit_mb = deficit / (1024 * 1024),
+                min_free_pct = self.watermark.as_ref().map(|w| w.min_free_pct),
+                "free disk space below watermark, evicting cached blobs"
+            );
         }
 
+        let pins = self.pins.read().await.clone();
         let mut entries = self.entries.write().await;
         let mut total = self.total_size.write().await;
 
         // Sort by last_accessed ascending (oldest first)
-        let mut sorted: Vec<(Hash, CacheEntry)> =
-            entries.iter().map(|(h, e)| (*h, e.clone())).collect();
+        let mut sorted: Vec<(Hash, CacheEntry)> = entries
+            .iter()
+            .filter(|(h, _)| !pins.contains_key(*h))
+            .map(|(h, e)| (*h, e.clone()))
+            .collect();
         sorted.sort_by_key(|(_, e)| e.last_accessed);
 
-        let mut evicted_count = 0u64;
+        let mut evicted = Vec::new();
         let mut evicted_bytes = 0u64;
+        let mut watermark_evictions = 0u64;
 
         for (hash, entry) in &sorted {
-            if *total <= self.max_size {
+            let over_limit = *total > max_size;
+            if !over_limit && evicted_bytes >= deficit {
                 break;
             }
 
@@ -187,8 +560,11 @@ impl BlobCache {
                 Ok(_) => {
                     *total -= entry.size;
                     evicted_bytes += entry.size;
-                    evicted_count += 1;
+                    evicted.push(*hash);
                     entries.remove(hash);
+                    if !over_limit {
+                        watermark_evictions += 1;
+                    }
                     debug!(%hash, size = entry.size, "evicted blob from cache (tag removed, pending GC)");
                 }
                 Err(e) => {
@@ -197,14 +573,29 @@ impl BlobCache {
             }
         }
 
-        if evicted_count > 0 {
+        if deficit > 0 {
+            if let Some(watermark) = &self.watermark {
+                watermark
+                    .pending
+                    .fetch_add(evicted_bytes, Ordering::Relaxed);
+            }
+        }
+        if watermark_evictions > 0 {
+            P2P_METRICS
+                .blob_cache_watermark_evictions_total
+                .inc_by(watermark_evictions);
+        }
+        if !evicted.is_empty() {
+            self.evictions
+                .fetch_add(evicted.len() as u64, Ordering::Relaxed);
             info!(
-                evicted_count,
+                evicted_count = evicted.len(),
                 evicted_mb = evicted_bytes / (1024 * 1024),
                 remaining_mb = *total / (1024 * 1024),
                 "LRU cache eviction complete"
             );
         }
+        evicted
     }
 
     /// Remove a specific blob from the cache tracker (without deleting from store).
@@ -223,17 +614,111 @@ impl BlobCache {
 
     /// Get the configured maximum cache size in bytes.
     pub fn max_size(&self) -> u64 {
-        self.max_size
+        self.max_size.load(Ordering::Relaxed)
+    }
+
+    /// Change the maximum cache size. Nothing is evicted until the next
+    /// [`evict_if_needed`](Self::evict_if_needed).
+    pub fn set_max_bytes(&self, max_bytes: u64) {
+        let previous = self.max_size.swap(max_bytes, Ordering::Relaxed);
+        info!(
+            max_size_mb = max_bytes / (1024 * 1024),
+            previous_mb = previous / (1024 * 1024),
+            "P2P blob cache limit changed"
+        );
     }
 
     /// Get the number of blobs currently tracked.
     pub async fn entry_count(&self) -> usize {
         self.entries.read().await.len()
     }
+
+    /// Hashes the cache is holding on to: tracked entries, which only LRU
+    /// eviction may drop, fetches still in progress and pinned blobs.
+    pub async fn pinned(&self) -> HashSet<Hash> {
+        let mut pinned: HashSet<Hash> = self.entries.read().await.keys().copied().collect();
+        pinned.extend(self.in_flight.lock().await.iter().copied());
+        pinned.extend(self.pins.read().await.keys().copied());
+        pinned
+    }
+}
+
+// ── Persisted limit ──────────────────────────────────────────────────
+
+/// Cache limit saved by an admin, if any. Unparsable values are ignored.
+pub async fn load_max_bytes(db: &DatabaseConnection) -> Result<Option<u64>, P2pError> {
+    let row = instance_setting::Entity::find()
+        .filter(instance_setting::Column::Key.eq(SETTING_KEY))
+        .one(db)
+        .await?;
+    Ok(row.and_then(|r| r.value.trim().parse().ok()))
+}
+
+/// Store the cache limit so it survives restarts.
+pub async fn save_max_bytes(db: &DatabaseConnection, max_bytes: u64) -> Result<(), P2pError> {
+    let existing = instance_setting::Entity::find()
+        .filter(instance_setting::Column::Key.eq(SETTING_KEY))
+        .one(db)
+        .await?;
+    let now: sea_orm::prelude::DateTimeWithTimeZone = chrono::Utc::now().into();
+    match existing {
+        Some(row) => {
+            let mut update: instance_setting::ActiveModel = row.into();
+            update.value = Set(max_bytes.to_string());
+            update.updated_at = Set(now);
+            update.update(db).await?;
+        }
+        None => {
+            instance_setting::ActiveModel {
+                id: Set(uuid::Uuid::new_v4()),
+                key: Set(SETTING_KEY.to_string()),
+                value: Set(max_bytes.to_string()),
+                updated_at: Set(now),
+            }
+            .insert(db)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+// ── Listen history ───────────────────────────────────────────────────
+
+#[derive(FromQueryResult)]
+struct PlayedHash {
+    hash: String,
+}
+
+/// Content hashes of the `limit` replicated tracks played most recently by
+/// any local user, newest first.
+async fn recently_played_p2p_hashes(
+    db: &DatabaseConnection,
+    limit: usize,
+) -> Result<Vec<String>, P2pError> {
+    let rows = PlayedHash::find_by_statement(Statement::from_sql_and_values(
+        sea_orm::DatabaseBackend::Postgres,
+        "SELECT t.content_hash AS hash \
+         FROM listen_history l JOIN tracks t ON t.id = l.track_id \
+         WHERE t.content_hash IS NOT NULL AND t.file_path LIKE 'p2p://%' \
+         GROUP BY t.content_hash \
+         ORDER BY MAX(l.listened_at) DESC \
+         LIMIT $1",
+        [(limit as i64).into()],
+    ))
+    .all(db)
+    .await?;
+    Ok(rows.into_iter().map(|r| r.hash).collect())
 }
 
 // ── Size parsing ─────────────────────────────────────────────────────
 
+/// Parse `P2P_CACHE_MIN_FREE_PCT`. `None` (watermark off) for 0, values of
+/// 100 and above, and anything unparsable.
+fn parse_min_free_pct(s: &str) -> Option<f64> {
+    let pct: f64 = s.trim().trim_end_matches('%').trim().parse().ok()?;
+    (pct > 0.0 && pct < 100.0).then_some(pct)
+}
+
 /// Parse a human-readable size string like `"2GB"`, `"512MB"`, `"1TB"`, or `"1073741824"`.
 fn parse_size(s: &str) -> Option<u64> {
     let s = s.trim().to_uppercase();
@@ -343,8 +828,10 @@ mod tests {
 
         assert!(cache.try_start_fetch(hash).await); // first: OK
         assert!(!cache.try_start_fetch(hash).await); // second: already in flight
+        assert!(cache.is_fetching(hash).await);
 
         cache.finish_fetch(hash).await;
+        assert!(!cache.is_fetching(hash).await);
         assert!(cache.try_start_fetch(hash).await); // after finish: OK again
     }
 
@@ -401,7 +888,7 @@ mod tests {
         assert_eq!(cache.total_size().await, 200);
 
         // Eviction should remove h1 (oldest) to bring total under 150
-        cache.evict_if_needed(&store).await;
+        assert_eq!(cache.evict_if_needed(&store).await, vec![h1]);
         assert_eq!(cache.entry_count().await, 1);
         assert_eq!(cache.total_size().await, 100);
 
@@ -417,4 +904,388 @@ mod tests {
 
         store.shutdown().await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_pinned_includes_entries_and_in_flight() {
+        let cache = BlobCache::new(1024 * 1024);
+        let cached = Hash::from_bytes([1u8; 32]);
+        let fetching = Hash::from_bytes([2u8; 32]);
+
+        cache.record_access(cached, 100).await;
+        cache.try_start_fetch(fetching).await;
+        let pinned = cache.pinned().await;
+        assert!(pinned.contains(&cached));
+        assert!(pinned.contains(&fetching));
+
+        cache.finish_fetch(fetching).await;
+        assert!(!cache.pinned().await.contains(&fetching));
+    }
+
+    // ── warm-up ──────────────────────────────────────────────────────
+
+    /// Serves `data` from `holder` only; every other peer is unreachable.
+    struct HistoryFetcher {
+        holder: &'static str,
+        data: bytes::Bytes,
+        peers: Vec<crate::track_health::PeerTrackInfo>,
+    }
+
+    fn source(peer_id: &str, is_online: bool) -> crate::track_health::PeerTrackInfo {
+        crate::track_health::PeerTrackInfo {
+            peer_id: peer_id.to_string(),
+            format: "mp3".to_string(),
+            bitrate: None,
+            sample_rate: None,
+            is_online,
+            file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl TrackFetcher for HistoryFetcher {
+        async fn fetch_track(&self, peer_id: &str, hash: &str) -> Result<bytes::Bytes, P2pError> {
+            if peer_id == self.holder {
+                Ok(self.data.clone())
+            } else {
+                Err(P2pError::TrackNotFound(hash.to_string()))
+            }
+        }
+
+        async fn check_blob_exists(&self, _hash: &str) -> bool {
+            false
+        }
+
+        async fn peer_is_online(&self, _peer_id: &str) -> bool {
+            true
+        }
+
+        async fn alternative_sources(
+            &self,
+            _hash: &str,
+        ) -> Vec<crate::track_health::PeerTrackInfo> {
+            self.peers.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_blob_stores_verified_copy() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+        let cache = BlobCache::new(1024 * 1024);
+        let data = bytes::Bytes::from_static(b"recently played");
+        let hash = Hash::new(&data);
+        // The offline holder is tried after the online peer that lacks it
+        let fetcher = HistoryFetcher {
+            holder: "offline",
+            data: data.clone(),
+            peers: vec![source("offline", false), source("online", true)],
+        };
+
+        let peer = cache.warm_up_blob(&store, &fetcher, hash).await.unwrap();
+
+        assert_eq!(peer, "offline");
+        assert!(store.blobs().has(hash).await.unwrap());
+        assert_eq!(cache.entry_count().await, 1);
+        assert_eq!(cache.total_size().await, data.len() as u64);
+        store.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_blob_rejects_wrong_bytes() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+        let cache = BlobCache::new(1024 * 1024);
+        let hash = Hash::new(b"the real track");
+        let fetcher = HistoryFetcher {
+            holder: "evil",
+            data: bytes::Bytes::from_static(b"something else"),
+            peers: vec![source("evil", true)],
+        };
+
+        let result = cache.warm_up_blob(&store, &fetcher, hash).await;
+
+        assert!(matches!(result, Err(P2pError::HashMismatch { .. })));
+        assert_eq!(cache.entry_count().await, 0);
+        store.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_disabled_with_zero_limit() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+        let cache = BlobCache::new(1024 * 1024);
+        let fetcher = HistoryFetcher {
+            holder: "peer",
+            data: bytes::Bytes::new(),
+            peers: Vec::new(),
+        };
+
+        let warmed = cache
+            .warm_up_from_history(&DatabaseConnection::Disconnected, &store, &fetcher, 0)
+            .await;
+
+        assert!(warmed.is_empty());
+        assert!(cache.stats().await.last_warm_up_at.is_none());
+        store.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stats_reports_usage() {
+        let cache = BlobCache::new(1000);
+        cache.record_access(Hash::from_bytes([1u8; 32]), 300).await;
+
+        let stats = cache.stats().await;
+        assert_eq!(stats.total_bytes, 300);
+        assert_eq!(stats.max_bytes, 1000);
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.evictions, 0);
+        assert!(stats.last_warm_up_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_shrinking_limit_evicts_and_counts() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+
+        let cache = BlobCache::new(1000);
+        for i in 1..=3u8 {
+            cache
+                .record_access_with_tag(Hash::from_bytes([i; 32]), 100, &store)
+                .await;
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        // Still under the original limit
+        assert!(cache.evict_if_needed(&store).await.is_empty());
+
+        cache.set_max_bytes(150);
+        let evicted = cache.evict_if_needed(&store).await;
+        assert_eq!(
+            evicted,
+            vec![Hash::from_bytes([1u8; 32]), Hash::from_bytes([2u8; 32])]
+        );
+
+        let stats = cache.stats().await;
+        assert_eq!(stats.max_bytes, 150);
+        assert_eq!(stats.total_bytes, 100);
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.evictions, 2);
+
+        // Growing the limit again evicts nothing
+        cache.set_max_bytes(1000);
+        assert!(cache.evict_if_needed(&store).await.is_empty());
+        assert_eq!(cache.stats().await.evictions, 2);
+
+        store.shutdown().await.unwrap();
+    }
+
+    // ── pins ─────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_pinned_blobs_survive_eviction() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+
+        let cache = BlobCache::new(250);
+        let h1 = Hash::from_bytes([1u8; 32]);
+        let h2 = Hash::from_bytes([2u8; 32]);
+        let h3 = Hash::from_bytes([3u8; 32]);
+        assert!(cache.pin(h1).await);
+        assert!(!cache.pin(h1).await);
+
+        for h in [h1, h2, h3] {
+            cache.record_access_with_tag(h, 100, &store).await;
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        // h1 is the oldest but pinned, so h2 goes first
+        assert_eq!(cache.evict_if_needed(&store).await, vec![h2]);
+        assert!(store
+            .tags()
+            .get(&format!("p2p-cache-{h1}"))
+            .await
+            .unwrap()
+            .is_some());
+        // Under a limit smaller than the pin alone, everything else goes but
+        // the pinned blob stays
+        cache.set_max_bytes(50);
+        assert_eq!(cache.evict_if_needed(&store).await, vec![h3]);
+        assert!(cache.evict_if_needed(&store).await.is_empty());
+        assert_eq!(cache.total_size().await, 100);
+
+        let stats = cache.stats().await;
+        assert_eq!(stats.pinned, 1);
+        assert_eq!(stats.pinned_bytes, 100);
+
+        store.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unpinned_blobs_evictable_again() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+
+        let cache = BlobCache::new(50);
+        let h1 = Hash::from_bytes([1u8; 32]);
+        cache.pin(h1).await;
+        cache.record_access_with_tag(h1, 100, &store).await;
+        assert!(cache.evict_if_needed(&store).await.is_empty());
+
+        assert!(cache.unpin(h1).await);
+        assert!(!cache.unpin(h1).await);
+        assert!(!cache.is_pinned(h1).await);
+        assert_eq!(cache.evict_if_needed(&store).await, vec![h1]);
+        assert_eq!(cache.stats().await.pinned_bytes, 0);
+
+        store.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shared_pin_held_until_last_unpin() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+
+        let cache = BlobCache::new(50);
+        let h1 = Hash::from_bytes([1u8; 32]);
+        // Two tracks share the blob
+        assert!(cache.pin(h1).await);
+        assert!(!cache.pin(h1).await);
+        cache.record_access_with_tag(h1, 100, &store).await;
+
+        // Unpinning one track keeps the other's pin
+        assert!(!cache.unpin(h1).await);
+        assert!(cache.is_pinned(h1).await);
+        assert!(cache.evict_if_needed(&store).await.is_empty());
+        assert_eq!(cache.stats().await.pinned, 1);
+
+        assert!(cache.unpin(h1).await);
+        assert!(!cache.is_pinned(h1).await);
+        assert_eq!(cache.evict_if_needed(&store).await, vec![h1]);
+
+        store.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pinned_includes_pins_not_yet_cached() {
+        let cache = BlobCache::new(1024);
+        let h = Hash::from_bytes([7u8; 32]);
+        cache.pin(h).await;
+        assert!(cache.pinned().await.contains(&h));
+
+        let stats = cache.stats().await;
+        assert_eq!(stats.pinned, 1);
+        assert_eq!(stats.pinned_bytes, 0);
+    }
+
+    #[test]
+    fn test_warm_up_limit_from_env() {
+        assert_eq!(BlobCache::new(1).warm_up_limit(), DEFAULT_WARM_UP_LIMIT);
+        std::env::set_var("P2P_CACHE_WARM_UP_LIMIT", "5");
+        assert_eq!(BlobCache::from_env(Path::new(".")).warm_up_limit(), 5);
+        std::env::set_var("P2P_CACHE_WARM_UP_LIMIT", "many");
+        assert_eq!(
+            BlobCache::from_env(Path::new(".")).warm_up_limit(),
+            DEFAULT_WARM_UP_LIMIT
+        );
+        std::env::remove_var("P2P_CACHE_WARM_UP_LIMIT");
+    }
+
+    // ── disk watermark ───────────────────────────────────────────────
+
+    struct FixedSpace(DiskSpace);
+
+    impl FreeSpaceProvider for FixedSpace {
+        fn disk_space(&self) -> std::io::Result<DiskSpace> {
+            Ok(self.0)
+        }
+    }
+
+    struct BrokenSpace;
+
+    impl FreeSpaceProvider for BrokenSpace {
+        fn disk_space(&self) -> std::io::Result<DiskSpace> {
+            Err(std::io::Error::other("no statvfs"))
+        }
+    }
+
+    #[test]
+    fn test_disk_space_deficit() {
+        let space = DiskSpace {
+            total: 1000,
+            available: 50,
+        };
+        assert_eq!(space.deficit(10.0), 50);
+        assert_eq!(space.deficit(5.0), 0);
+        assert_eq!(space.deficit(2.5), 0);
+    }
+
+    #[test]
+    fn test_parse_min_free_pct() {
+        assert_eq!(parse_min_free_pct("10"), Some(10.0));
+        assert_eq!(parse_min_free_pct(" 7.5% "), Some(7.5));
+        assert_eq!(parse_min_free_pct("0"), None);
+        assert_eq!(parse_min_free_pct("100"), None);
+        assert_eq!(parse_min_free_pct("-5"), None);
+        assert_eq!(parse_min_free_pct("lots"), None);
+    }
+
+    #[tokio::test]
+    async fn test_watermark_evicts_under_cache_limit() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+
+        // The cache is far below its limit, but the disk is 5% free of a
+        // 10% watermark: 50 bytes must go
+        let cache = BlobCache::new(1024 * 1024).with_disk_watermark(
+            10.0,
+            FixedSpace(DiskSpace {
+                total: 1000,
+                available: 50,
+            }),
+        );
+        let h1 = Hash::from_bytes([1u8; 32]);
+        let h2 = Hash::from_bytes([2u8; 32]);
+        let h3 = Hash::from_bytes([3u8; 32]);
+        cache.pin(h1).await;
+        for h in [h1, h2, h3] {
+            cache.record_access_with_tag(h, 100, &store).await;
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        let before = P2P_METRICS.blob_cache_watermark_evictions_total.get();
+        // Oldest unpinned blob covers the deficit
+        assert_eq!(cache.evict_if_needed(&store).await, vec![h2]);
+        assert!(P2P_METRICS.blob_cache_watermark_evictions_total.get() > before);
+        assert_eq!(cache.total_size().await, 200);
+        // The disk still reads 5% free until garbage collection runs, but
+        // the evicted bytes already cover the deficit
+        assert!(cache.evict_if_needed(&store).await.is_empty());
+        assert_eq!(cache.total_size().await, 200);
+
+        store.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_watermark_met_or_unreadable_evicts_nothing() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+
+        let roomy = BlobCache::new(1024 * 1024).with_disk_watermark(
+            10.0,
+            FixedSpace(DiskSpace {
+                total: 1000,
+                available: 500,
+            }),
+        );
+        let broken = BlobCache::new(1024 * 1024).with_disk_watermark(10.0, BrokenSpace);
+        let h = Hash::from_bytes([1u8; 32]);
+        for cache in [&roomy, &broken] {
+            cache.record_access_with_tag(h, 100, &store).await;
+            assert!(cache.evict_if_needed(&store).await.is_empty());
+        }
+
+        store.shutdown().await.unwrap();
+    }
 }
@@ -0,0 +1,535 @@
+//! Garbage collection of unreferenced blobs.
+//!
+//! Blobs stay in the `FsStore` as long as a tag points at them. Tags this
+//! node creates are `published-{hash}` (see `P2pNode::publish_track`) and
+//! `p2p-cache-{hash}` (see [`BlobCache`](crate::blob_cache::BlobCache)).
+//! Once the tracks, replicated tracks and published images that used a blob
+//! are gone, its tags keep it on disk forever. [`collect_garbage`] lists the
+//! tags, keeps every hash still referenced in the database or pinned by the
+//! blob cache, and deletes the tags of the rest. Tags with any other name
+//! are never touched, and neither is a blob they point at.
+//!
+//! The blob data itself is reclaimed by the store's own garbage collector,
+//! which [`load_store`] enables: every blob without a tag is swept on its
+//! next run.
+//!
+//! `published_hashes` was seeded from track content hashes only, so covers
+//! and artist images tagged before it existed are not in it.
+//! [`backfill_published`] records them from their tags before the first
+//! collection, or they would be collected as unreferenced.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+use futures_lite::StreamExt;
+use iroh_blobs::api::blobs::BlobStatus;
+use iroh_blobs::store::fs::options::{GcConfig, Options};
+use iroh_blobs::store::fs::FsStore;
+use iroh_blobs::Hash;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect};
+use serde::Serialize;
+use soundtime_db::entities::{published_hash, remote_track, track};
+use tracing::{debug, info};
+
+use crate::error::P2pError;
+use crate::published::{PublishedHashes, PublishedKind};
+
+/// Name prefix of the tags of published blobs.
+pub const PUBLISHED_TAG_PREFIX: &str = "published-";
+
+/// Name prefixes of the tags this node creates and may delete.
+pub const OWNED_TAG_PREFIXES: [&str; 2] = [PUBLISHED_TAG_PREFIX, "p2p-cache-"];
+
+/// How often the blob store sweeps blobs that no tag protects.
+pub const STORE_GC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Open the blob store at `root` with its garbage collector sweeping
+/// untagged blobs every `gc_interval`.
+pub async fn load_store(root: &Path, gc_interval: Duration) -> Result<FsStore, P2pError> {
+    let mut options = Options::new(root);
+    options.gc = Some(GcConfig {
+        interval: gc_interval,
+        add_protected: None,
+    });
+    FsStore::load_with_opts(root.join("blobs.db"), options)
+        .await
+        .map_err(|e| P2pError::BlobStore(e.to_string()))
+}
+
+/// A tag in the blob store.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlobTag {
+    pub name: String,
+    pub hash: Hash,
+}
+
+/// What a garbage collection run removed, or would remove in a dry run.
+///
+/// `blobs_deleted` counts the blobs left without any tag; the store's
+/// garbage collector frees their space on its next sweep.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct GcReport {
+    pub dry_run: bool,
+    pub tags_scanned: usize,
+    pub tags_deleted: usize,
+    pub blobs_deleted: usize,
+    pub bytes_reclaimed: u64,
+}
+
+/// Tags and blobs a run should delete.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GcPlan {
+    pub tags: Vec<String>,
+    pub blobs: Vec<Hash>,
+}
+
+fn is_owned_tag(name: &str) -> bool {
+    OWNED_TAG_PREFIXES.iter().any(|p| name.starts_with(p))
+}
+
+/// Decide what to delete. A blob is collected only when it is neither
+/// referenced nor pinned and every tag pointing at it is one of ours.
+pub fn plan_gc(tags: &[BlobTag], referenced: &HashSet<String>, pinned: &HashSet<Hash>) -> GcPlan {
+    let mut by_hash: HashMap<Hash, Vec<&BlobTag>> = HashMap::new();
+    for tag in tags {
+        by_hash.entry(tag.hash).or_default().push(tag);
+    }
+
+    let mut plan = GcPlan::default();
+    for (hash, tags) in by_hash {
+        if pinned.contains(&hash)
+            || referenced.contains(&hash.to_string())
+            || !tags.iter().all(|t| is_owned_tag(&t.name))
+        {
+            continue;
+        }
+        plan.tags.extend(tags.iter().map(|t| t.name.clone()));
+        plan.blobs.push(hash);
+    }
+    plan.tags.sort();
+    plan.blobs.sort();
+    plan
+}
+
+/// Hash of a replicated track from its `p2p://<node>/<hash>` URI.
+fn remote_uri_hash(uri: &str) -> Option<&str> {
+    let rest = uri.strip_prefix("p2p://")?;
+    let (_, hash) = rest.rsplit_once('/')?;
+    (!hash.is_empty()).then_some(hash)
+}
+
+/// Every blob hash the database still refers to: track content, replicated
+/// tracks and published images.
+pub async fn referenced_hashes(db: &DatabaseConnection) -> Result<HashSet<String>, P2pError> {
+    let mut referenced: HashSet<String> = track::Entity::find()
+        .select_only()
+        .column(track::Column::ContentHash)
+        .filter(track::Column::ContentHash.is_not_null())
+        .into_tuple::<Option<String>>()
+        .all(db)
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let uris: Vec<String> = remote_track::Entity::find()
+        .select_only()
+        .column(remote_track::Column::RemoteUri)
+        .filter(remote_track::Column::RemoteUri.starts_with("p2p://"))
+        .into_tuple()
+        .all(db)
+        .await?;
+    referenced.extend(
+        uris.iter()
+            .filter_map(|uri| remote_uri_hash(uri))
+            .map(str::to_string),
+    );
+
+    let published: Vec<String> = published_hash::Entity::find()
+        .select_only()
+        .column(published_hash::Column::Hash)
+        .into_tuple()
+        .all(db)
+        .await?;
+    referenced.extend(published);
+
+    Ok(referenced)
+}
+
+/// All tags in `store`.
+pub async fn list_tags(store: &FsStore) -> Result<Vec<BlobTag>, P2pError> {
+    let mut stream = std::pin::pin!(store
+        .tags()
+        .list()
+        .await
+        .map_err(|e| P2pError::BlobStore(e.to_string()))?);
+    let mut tags = Vec::new();
+    while let Some(info) = stream.next().await {
+        let info = info.map_err(|e| P2pError::BlobStore(e.to_string()))?;
+        tags.push(BlobTag {
+            name: String::from_utf8_lossy(info.name.as_ref()).into_owned(),
+            hash: info.hash,
+        });
+    }
+    Ok(tags)
+}
+
+/// Record every blob behind a `published-*` tag in `published`, except
+/// hashes in `blocked`. Track audio was seeded into `published_hashes` by its
+/// migration, so the blobs found here are images. Returns how many were
+/// added.
+pub async fn backfill_published(
+    tags: &[BlobTag],
+    published: &PublishedHashes,
+    blocked: &HashSet<String>,
+) -> Result<usize, P2pError> {
+    let mut added = 0;
+    for tag in tags {
+        if !tag.name.starts_with(PUBLISHED_TAG_PREFIX) {
+            continue;
+        }
+        let hash = tag.hash.to_string();
+        if blocked.contains(&hash) || published.contains(&hash).await {
+            continue;
+        }
+        published.publish(&hash, PublishedKind::Image).await?;
+        added += 1;
+    }
+    if added > 0 {
+        info!(
+            added,
+            "recorded published blobs tagged before published_hashes existed"
+        );
+    }
+    Ok(added)
+}
+
+/// Delete the tags of every hash among `tags` that is not in `referenced`
+/// or `pinned`, leaving the blobs to the store's garbage collector. With
+/// `dry_run` nothing is deleted and the report says what would have been.
+///
+/// `tags` must be listed before `referenced` is read: a blob is recorded in
+/// the database before it is tagged, so one published in between is
+/// either missing from `tags` or present in `referenced`.
+pub async fn collect_garbage(
+    store: &FsStore,
+    tags: &[BlobTag],
+    referenced: &HashSet<String>,
+    pinned: &HashSet<Hash>,
+    dry_run: bool,
+) -> Result<GcReport, P2pError> {
+    let plan = plan_gc(tags, referenced, pinned);
+    let mut report = GcReport {
+        dry_run,
+        tags_scanned: tags.len(),
+        ..Default::default()
+    };
+
+    for hash in &plan.blobs {
+        let size = match store
+            .blobs()
+            .status(*hash)
+            .await
+            .map_err(|e| P2pError::BlobStore(e.to_string()))?
+        {
+            BlobStatus::Complete { size } => size,
+            BlobStatus::Partial { size } => size.unwrap_or(0),
+            BlobStatus::NotFound => 0,
+        };
+        report.bytes_reclaimed += size;
+        debug!(%hash, size, dry_run, "collecting unreferenced blob");
+    }
+
+    if dry_run {
+        report.tags_deleted = plan.tags.len();
+        report.blobs_deleted = plan.blobs.len();
+    } else {
+        for name in &plan.tags {
+            store
+                .tags()
+                .delete(name)
+                .await
+                .map_err(|e| P2pError::BlobStore(format!("failed to delete tag {name}: {e}")))?;
+            report.tags_deleted += 1;
+        }
+        // With no tags left the blobs are unprotected and the store's
+        // garbage collector sweeps them
+        report.blobs_deleted = plan.blobs.len();
+    }
+
+    info!(
+        dry_run,
+        tags_scanned = report.tags_scanned,
+        tags_deleted = report.tags_deleted,
+        blobs_deleted = report.blobs_deleted,
+        bytes_reclaimed = report.bytes_reclaimed,
+        "blob garbage collection finished"
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use iroh_blobs::HashAndFormat;
+    use std::sync::Arc;
+
+    fn hash(byte: u8) -> Hash {
+        Hash::from_bytes([byte; 32])
+    }
+
+    fn tag(name: &str, hash: Hash) -> BlobTag {
+        BlobTag {
+            name: name.to_string(),
+            hash,
+        }
+    }
+
+    // ── plan_gc ──
+
+    #[test]
+    fn test_referenced_blob_is_never_collected() {
+        let h = hash(1);
+        let tags = [
+            tag(&format!("published-{h}"), h),
+            tag(&format!("p2p-cache-{h}"), h),
+        ];
+        let referenced = HashSet::from([h.to_string()]);
+
+        assert_eq!(
+            plan_gc(&tags, &referenced, &HashSet::new()),
+            GcPlan::default()
+        );
+    }
+
+    #[test]
+    fn test_pinned_blob_is_never_collected() {
+        let h = hash(1);
+        let tags = [tag(&format!("p2p-cache-{h}"), h)];
+
+        let plan = plan_gc(&tags, &HashSet::new(), &HashSet::from([h]));
+        assert_eq!(plan, GcPlan::default());
+    }
+
+    #[test]
+    fn test_unreferenced_blob_loses_all_its_tags() {
+        let kept = hash(1);
+        let orphan = hash(2);
+        let tags = [
+            tag(&format!("published-{kept}"), kept),
+            tag(&format!("published-{orphan}"), orphan),
+            tag(&format!("p2p-cache-{orphan}"), orphan),
+        ];
+        let referenced = HashSet::from([kept.to_string()]);
+
+        let plan = plan_gc(&tags, &referenced, &HashSet::new());
+        assert_eq!(plan.blobs, [orphan]);
+        assert_eq!(
+            plan.tags,
+            [format!("p2p-cache-{orphan}"), format!("published-{orphan}")]
+        );
+    }
+
+    #[test]
+    fn test_foreign_tag_protects_blob() {
+        let h = hash(1);
+        let tags = [
+            tag(&format!("published-{h}"), h),
+            tag("someone-elses-tag", h),
+        ];
+
+        let plan = plan_gc(&tags, &HashSet::new(), &HashSet::new());
+        assert_eq!(plan, GcPlan::default());
+    }
+
+    #[test]
+    fn test_remote_uri_hash() {
+        assert_eq!(remote_uri_hash("p2p://node/abc"), Some("abc"));
+        assert_eq!(remote_uri_hash("p2p://node/"), None);
+        assert_eq!(remote_uri_hash("p2p://node"), None);
+        assert_eq!(remote_uri_hash("https://example.com/abc"), None);
+    }
+
+    // ── collect_garbage ──
+
+    async fn add_tagged(store: &FsStore, data: &'static [u8], name: &str) -> Hash {
+        let temp = store
+            .blobs()
+            .add_bytes(Bytes::from_static(data))
+            .temp_tag()
+            .await
+            .unwrap();
+        let h = temp.hash();
+        store
+            .tags()
+            .set(format!("{name}-{h}"), HashAndFormat::raw(h))
+            .await
+            .unwrap();
+        h
+    }
+
+    #[tokio::test]
+    async fn test_collect_garbage_keeps_referenced_blob() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+        let kept = add_tagged(&store, b"referenced track", "published").await;
+        let orphan = add_tagged(&store, b"deleted track", "published").await;
+        let referenced = HashSet::from([kept.to_string()]);
+
+        let tags = list_tags(&store).await.unwrap();
+        let report = collect_garbage(&store, &tags, &referenced, &HashSet::new(), false)
+            .await
+            .unwrap();
+        assert_eq!(report.tags_scanned, 2);
+        assert_eq!((report.tags_deleted, report.blobs_deleted), (1, 1));
+        assert_eq!(report.bytes_reclaimed, b"deleted track".len() as u64);
+
+        let kept_tag = store.tags().get(format!("published-{kept}")).await.unwrap();
+        assert!(kept_tag.is_some(), "referenced blob's tag must survive");
+        assert!(store.blobs().has(kept).await.unwrap());
+        let orphan_tag = store
+            .tags()
+            .get(format!("published-{orphan}"))
+            .await
+            .unwrap();
+        assert!(orphan_tag.is_none());
+
+        store.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_deletes_nothing() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+        let orphan = add_tagged(&store, b"deleted track", "p2p-cache").await;
+
+        let tags = list_tags(&store).await.unwrap();
+        let report = collect_garbage(&store, &tags, &HashSet::new(), &HashSet::new(), true)
+            .await
+            .unwrap();
+        assert!(report.dry_run);
+        assert_eq!((report.tags_deleted, report.blobs_deleted), (1, 1));
+        assert_eq!(report.bytes_reclaimed, b"deleted track".len() as u64);
+
+        let tag = store
+            .tags()
+            .get(format!("p2p-cache-{orphan}"))
+            .await
+            .unwrap();
+        assert!(tag.is_some(), "dry run must not delete tags");
+        assert!(store.blobs().has(orphan).await.unwrap());
+
+        store.shutdown().await.unwrap();
+    }
+
+    // ── backfill_published ──
+
+    /// In-memory stand-in for the `published_hashes` table.
+    #[derive(Default)]
+    struct MemoryPublished {
+        rows: std::sync::Mutex<std::collections::BTreeMap<String, PublishedKind>>,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::published::PublishedStore for MemoryPublished {
+        async fn page_after(&self, after: &str, limit: u64) -> Result<Vec<String>, sea_orm::DbErr> {
+            let rows = self.rows.lock().unwrap();
+            Ok(rows
+                .keys()
+                .filter(|h| h.as_str() > after)
+                .take(limit as usize)
+                .cloned()
+                .collect())
+        }
+
+        async fn save(&self, hash: &str, kind: PublishedKind) -> Result<(), sea_orm::DbErr> {
+            self.rows
+                .lock()
+                .unwrap()
+                .entry(hash.to_string())
+                .or_insert(kind);
+            Ok(())
+        }
+
+        async fn delete(&self, hash: &str) -> Result<(), sea_orm::DbErr> {
+            self.rows.lock().unwrap().remove(hash);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backfill_keeps_pre_upgrade_images_from_gc() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+        // A track seeded by the migration, and a cover and a blocked image
+        // tagged before published_hashes existed
+        let track = add_tagged(&store, b"track audio", "published").await;
+        let cover = add_tagged(&store, b"old cover", "published").await;
+        let blocked = add_tagged(&store, b"blocked image", "published").await;
+        let cached = add_tagged(&store, b"cached replica", "p2p-cache").await;
+
+        let rows = Arc::new(MemoryPublished::default());
+        rows.rows
+            .lock()
+            .unwrap()
+            .insert(track.to_string(), PublishedKind::Track);
+        let published = PublishedHashes::new(rows.clone());
+        published.load().await.unwrap();
+
+        let tags = list_tags(&store).await.unwrap();
+        let added = backfill_published(&tags, &published, &HashSet::from([blocked.to_string()]))
+            .await
+            .unwrap();
+        assert_eq!(added, 1);
+        assert_eq!(
+            rows.rows.lock().unwrap().get(&cover.to_string()),
+            Some(&PublishedKind::Image)
+        );
+        assert!(published.contains(&cover.to_string()).await);
+        assert!(!published.contains(&cached.to_string()).await);
+
+        // Running it again finds nothing new
+        let again = backfill_published(&tags, &published, &HashSet::from([blocked.to_string()]))
+            .await
+            .unwrap();
+        assert_eq!(again, 0);
+
+        // The track and the cover now count as referenced
+        let referenced: HashSet<String> = rows.rows.lock().unwrap().keys().cloned().collect();
+        let plan = plan_gc(&tags, &referenced, &HashSet::new());
+        assert!(!plan.blobs.contains(&track));
+        assert!(!plan.blobs.contains(&cover));
+        assert!(plan.blobs.contains(&cached));
+
+        store.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_store_gc_reclaims_collected_blob() {
+        let td = tempfile::tempdir().unwrap();
+        let store = load_store(&td.path().join("blobs"), Duration::from_millis(50))
+            .await
+            .unwrap();
+        let kept = add_tagged(&store, b"referenced track", "published").await;
+        let orphan = add_tagged(&store, b"deleted track", "p2p-cache").await;
+        let referenced = HashSet::from([kept.to_string()]);
+
+        let tags = list_tags(&store).await.unwrap();
+        collect_garbage(&store, &tags, &referenced, &HashSet::new(), false)
+            .await
+            .unwrap();
+
+        let swept = tokio::time::timeout(Duration::from_secs(10), async {
+            while store.blobs().has(orphan).await.unwrap() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await;
+        assert!(swept.is_ok(), "store GC should reclaim the untagged blob");
+        assert!(store.blobs().has(kept).await.unwrap());
+
+        store.shutdown().await.unwrap();
+    }
+}
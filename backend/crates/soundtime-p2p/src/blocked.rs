@@ -1,18 +1,66 @@
 //! Peer blocking — check if a peer is blocked by PeerID or readable name.
+//!
+//! Blocks are permanent unless `expires_at` is set. A lapsed block counts as
+//! no block at all and its row is removed the next time it is looked at.
 
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
 use soundtime_db::entities::blocked_domain;
 
+/// Whether a block with the given expiry is still in force at `now`.
+/// A block without an expiry never lapses; one expiring exactly at `now`
+/// has lapsed.
+pub fn is_block_active(expires_at: Option<DateTime<FixedOffset>>, now: DateTime<Utc>) -> bool {
+    match expires_at {
+        Some(expires_at) => expires_at > now,
+        None => true,
+    }
+}
+
+/// Seconds until a block lapses, clamped at zero. `None` for permanent blocks.
+pub fn remaining_secs(
+    expires_at: Option<DateTime<FixedOffset>>,
+    now: DateTime<Utc>,
+) -> Option<i64> {
+    expires_at.map(|expires_at| (expires_at.with_timezone(&Utc) - now).num_seconds().max(0))
+}
+
 /// Check if a peer is blocked, either by its iroh NodeId string or readable name.
 /// We reuse the `blocked_domains` table — the `domain` column stores either
 /// the iroh NodeId (base32 string) or a human-readable peer name.
+///
+/// An expired block is deleted on the spot and the peer is let through.
 pub async fn is_peer_blocked(db: &DatabaseConnection, peer_id: &str) -> bool {
     let result = blocked_domain::Entity::find()
         .filter(blocked_domain::Column::Domain.eq(peer_id))
         .one(db)
         .await;
 
-    matches!(result, Ok(Some(_)))
+    let Ok(Some(block)) = result else {
+        return false;
+    };
+
+    if is_block_active(block.expires_at, Utc::now()) {
+        return true;
+    }
+
+    tracing::info!(peer_id = %peer_id, "peer block expired, lifting it");
+    if let Err(e) = blocked_domain::Entity::delete_by_id(block.id)
+        .exec(db)
+        .await
+    {
+        tracing::warn!(peer_id = %peer_id, "failed to delete expired peer block: {e}");
+    }
+    false
+}
+
+/// Delete every block whose expiry has passed. Returns how many were removed.
+pub async fn purge_expired_blocks(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    let result = blocked_domain::Entity::delete_many()
+        .filter(blocked_domain::Column::ExpiresAt.lte(Utc::now()))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
 }
 
 /// Check if any of the provided identifiers (NodeId, peer name) are blocked.
@@ -64,6 +112,7 @@ mod tests {
         use sea_orm::ColumnTrait;
         let _domain_col = blocked_domain::Column::Domain;
         let _id_col = blocked_domain::Column::Id;
+        let _expires_col = blocked_domain::Column::ExpiresAt;
         // The eq filter should accept a string
         let _filter = blocked_domain::Column::Domain.eq("test");
     }
@@ -80,4 +129,49 @@ mod tests {
         assert!(identifiers.is_empty());
         // This is the expected behavior: no identifiers → not blocked
     }
+
+    // ── expiry ──
+
+    fn at(secs: i64) -> DateTime<FixedOffset> {
+        DateTime::from_timestamp(secs, 0).unwrap().fixed_offset()
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+    }
+
+    #[test]
+    fn test_permanent_block_is_always_active() {
+        assert!(is_block_active(None, now()));
+        assert_eq!(remaining_secs(None, now()), None);
+    }
+
+    #[test]
+    fn test_block_active_before_expiry() {
+        let expires = at(1_700_000_001);
+        assert!(is_block_active(Some(expires), now()));
+        assert_eq!(remaining_secs(Some(expires), now()), Some(1));
+    }
+
+    #[test]
+    fn test_block_lapses_exactly_at_expiry() {
+        let expires = at(1_700_000_000);
+        assert!(!is_block_active(Some(expires), now()));
+        assert_eq!(remaining_secs(Some(expires), now()), Some(0));
+    }
+
+    #[test]
+    fn test_block_lapsed_after_expiry_clamps_remaining() {
+        let expires = at(1_699_999_000);
+        assert!(!is_block_active(Some(expires), now()));
+        assert_eq!(remaining_secs(Some(expires), now()), Some(0));
+    }
+
+    #[test]
+    fn test_block_expiry_ignores_offset() {
+        // Same instant expressed in UTC+2 is still one second in the future.
+        let expires = at(1_700_000_001).with_timezone(&FixedOffset::east_opt(7200).unwrap());
+        assert!(is_block_active(Some(expires), now()));
+        assert_eq!(remaining_secs(Some(expires), now()), Some(1));
+    }
 }
@@ -0,0 +1,505 @@
+//! Acknowledged catalog sync pages.
+//!
+//! Peers on protocol v2 receive each page of a full catalog push as a
+//! `CatalogSyncPage` carrying a [`CatalogPageHeader`], and answer on the same
+//! stream with a [`CatalogPageAck`] counting the tracks they inserted, skipped
+//! (already known) and failed to store. A page that is not acknowledged, or
+//! whose ack reports failures, is sent once more by [`deliver_page`]. The
+//! tally of each finished push is kept in [`CatalogSyncHistory`]. v1 peers
+//! still get plain `CatalogSync` messages, which are not acknowledged.
+
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::catalog_rate::{CATALOG_RATE_LIMIT_BACKOFF, MAX_RATE_LIMITED_WAITS};
+use crate::error::P2pError;
+use crate::node::TrackAnnouncement;
+
+/// Attempts made per catalog page: the first send plus one retry.
+pub const PAGE_ATTEMPTS: u32 = 2;
+
+/// Finished pushes remembered per peer.
+pub const MAX_HISTORY_PER_PEER: usize = 10;
+
+/// Identifies one page of a catalog push.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogPageHeader {
+    /// Shared by every page of the same push
+    pub sync_id: Uuid,
+    /// Zero-based page number
+    pub page: u64,
+    pub total_pages: u64,
+}
+
+/// Receiver's result for one catalog page.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogPageAck {
+    pub sync_id: Uuid,
+    pub page: u64,
+    /// Tracks added to the receiver's catalog
+    pub inserted: u64,
+    /// Tracks the receiver already had
+    pub skipped: u64,
+    /// Tracks that could not be stored
+    pub failed: u64,
+}
+
+impl CatalogPageAck {
+    /// Whether this ack answers the page described by `header`.
+    pub fn matches(&self, header: &CatalogPageHeader) -> bool {
+        self.sync_id == header.sync_id && self.page == header.page
+    }
+
+    /// Combine with the ack of a retry of the same page. Tracks this attempt
+    /// inserted are reported as skipped by the retry, so they are moved back.
+    fn merge_retry(self, retry: CatalogPageAck) -> CatalogPageAck {
+        CatalogPageAck {
+            inserted: self.inserted + retry.inserted,
+            skipped: retry.skipped.saturating_sub(self.inserted),
+            ..retry
+        }
+    }
+}
+
+/// Something that can send a catalog page to a peer and wait for its ack.
+#[async_trait]
+pub trait CatalogPageSink: Send + Sync {
+    /// Whether `peer_id` acknowledges pages; if not, a push falls back to
+    /// unacknowledged `CatalogSync` messages.
+    async fn acknowledges(&self, _peer_id: &str) -> bool {
+        true
+    }
+
+    async fn send_page(
+        &self,
+        peer_id: &str,
+        header: &CatalogPageHeader,
+        tracks: &[TrackAnnouncement],
+    ) -> Result<CatalogPageAck, P2pError>;
+}
+
+/// Outcome of delivering one page, after any retry.
+#[derive(Clone, Debug)]
+pub struct PageDelivery {
+    /// Counts from the matching acks received, combined across attempts
+    pub ack: Option<CatalogPageAck>,
+    pub attempts: u32,
+}
+
+impl PageDelivery {
+    /// The peer acknowledged the page without failures.
+    pub fn succeeded(&self) -> bool {
+        self.ack.as_ref().is_some_and(|a| a.failed == 0)
+    }
+}
+
+/// Send a page and wait for its ack, sending it once more if the first
+/// attempt fails or the peer reports failed tracks. Tracks stored by the
+/// first attempt are skipped by the receiver on the second. A page the peer
+/// refuses for its rate limit is sent again after a backoff without using
+/// up an attempt, up to `MAX_RATE_LIMITED_WAITS` times.
+pub async fn deliver_page<S: CatalogPageSink + ?Sized>(
+    sink: &S,
+    peer_id: &str,
+    header: &CatalogPageHeader,
+    tracks: &[TrackAnnouncement],
+) -> PageDelivery {
+    let mut ack = None;
+    let mut attempts = 0;
+    let mut rate_limited = 0;
+    while attempts < PAGE_ATTEMPTS {
+        attempts += 1;
+        match sink.send_page(peer_id, header, tracks).await {
+            Err(P2pError::RateLimited(_)) if rate_limited < MAX_RATE_LIMITED_WAITS => {
+                rate_limited += 1;
+                attempts -= 1;
+                warn!(
+                    peer = %peer_id,
+                    page = header.page,
+                    backoff_secs = CATALOG_RATE_LIMIT_BACKOFF.as_secs(),
+                    "peer is rate limiting catalog pages, backing off"
+                );
+                tokio::time::sleep(CATALOG_RATE_LIMIT_BACKOFF).await;
+            }
+            Ok(a) if !a.matches(header) => {
+                warn!(
+                    peer = %peer_id,
+                    page = header.page,
+                    acked_page = a.page,
+                    "catalog page ack does not match the page sent"
+                );
+            }
+            Ok(a) => {
+                info!(
+                    peer = %peer_id,
+                    page = header.page,
+                    inserted = a.inserted,
+                    skipped = a.skipped,
+                    failed = a.failed,
+                    attempt = attempts,
+                    "catalog page acknowledged"
+                );
+                let clean = a.failed == 0;
+                ack = Some(match ack.take() {
+                    Some(earlier) => CatalogPageAck::merge_retry(earlier, a),
+                    None => a,
+                });
+                if clean {
+                    break;
+                }
+            }
+            Err(e) => {
+                warn!(peer = %peer_id, page = header.page, attempt = attempts, "catalog page not acknowledged: {e}");
+            }
+        }
+    }
+    PageDelivery { ack, attempts }
+}
+
+/// Final tally of one catalog push to a peer.
+#[derive(Clone, Debug, Serialize)]
+pub struct CatalogSyncRecord {
+    pub sync_id: Uuid,
+    pub peer_id: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Whether the peer acknowledged pages; v1 peers report no track counts
+    pub acknowledged: bool,
+    pub pages_sent: u64,
+    /// Pages still failing after the retry
+    pub pages_failed: u64,
+    /// Pages that needed a second attempt
+    pub pages_retried: u64,
+    pub inserted: u64,
+    pub skipped: u64,
+    pub failed: u64,
+}
+
+impl CatalogSyncRecord {
+    pub fn new(sync_id: Uuid, peer_id: &str, acknowledged: bool) -> Self {
+        Self {
+            sync_id,
+            peer_id: peer_id.to_string(),
+            started_at: Utc::now(),
+            finished_at: None,
+            acknowledged,
+            pages_sent: 0,
+            pages_failed: 0,
+            pages_retried: 0,
+            inserted: 0,
+            skipped: 0,
+            failed: 0,
+        }
+    }
+
+    /// Count a page sent to a v2 peer.
+    pub fn add_delivery(&mut self, delivery: &PageDelivery) {
+        self.pages_sent += 1;
+        if delivery.attempts > 1 {
+            self.pages_retried += 1;
+        }
+        if !delivery.succeeded() {
+            self.pages_failed += 1;
+        }
+        if let Some(ref ack) = delivery.ack {
+            self.inserted += ack.inserted;
+            self.skipped += ack.skipped;
+            self.failed += ack.failed;
+        }
+    }
+
+    /// Count a page sent to a v1 peer, which does not acknowledge it.
+    pub fn add_unacknowledged(&mut self, delivered: bool) {
+        self.pages_sent += 1;
+        if !delivered {
+            self.pages_failed += 1;
+        }
+    }
+}
+
+/// Recent finished catalog pushes, keyed by peer EndpointId, newest first.
+#[derive(Default)]
+pub struct CatalogSyncHistory {
+    records: DashMap<String, VecDeque<CatalogSyncRecord>>,
+}
+
+impl CatalogSyncHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store a finished push, dropping the oldest beyond
+    /// [`MAX_HISTORY_PER_PEER`].
+    pub fn record(&self, mut record: CatalogSyncRecord) {
+        record.finished_at.get_or_insert_with(Utc::now);
+        let mut entries = self.records.entry(record.peer_id.clone()).or_default();
+        entries.push_front(record);
+        entries.truncate(MAX_HISTORY_PER_PEER);
+    }
+
+    /// Finished pushes to `peer_id`, newest first.
+    pub fn for_peer(&self, peer_id: &str) -> Vec<CatalogSyncRecord> {
+        self.records
+            .get(peer_id)
+            .map(|r| r.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Peer that fails the pages listed in `fail_first` on their first
+    /// attempt, either by dropping them or by acking with failed tracks.
+    struct MockPeer {
+        fail_first: Vec<u64>,
+        drop_instead: bool,
+        attempts: Mutex<Vec<u64>>,
+        /// Pages refused for the rate limit before any is accepted
+        refusals: Mutex<u32>,
+    }
+
+    impl MockPeer {
+        fn new(fail_first: &[u64], drop_instead: bool) -> Self {
+            Self {
+                fail_first: fail_first.to_vec(),
+                drop_instead,
+                attempts: Mutex::new(Vec::new()),
+                refusals: Mutex::new(0),
+            }
+        }
+
+        fn rate_limited(refusals: u32) -> Self {
+            let peer = Self::new(&[], false);
+            *peer.refusals.lock().unwrap() = refusals;
+            peer
+        }
+
+        fn attempts_for(&self, page: u64) -> usize {
+            self.attempts
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|p| **p == page)
+                .count()
+        }
+    }
+
+    #[async_trait]
+    impl CatalogPageSink for MockPeer {
+        async fn send_page(
+            &self,
+            _peer_id: &str,
+            header: &CatalogPageHeader,
+            tracks: &[TrackAnnouncement],
+        ) -> Result<CatalogPageAck, P2pError> {
+            {
+                let mut refusals = self.refusals.lock().unwrap();
+                if *refusals > 0 {
+                    *refusals -= 1;
+                    return Err(P2pError::RateLimited("peer-a".into()));
+                }
+            }
+            let first = {
+                let mut attempts = self.attempts.lock().unwrap();
+                attempts.push(header.page);
+                attempts.iter().filter(|p| **p == header.page).count() == 1
+            };
+            let n = tracks.len() as u64;
+            let mut ack = CatalogPageAck {
+                sync_id: header.sync_id,
+                page: header.page,
+                inserted: n,
+                ..Default::default()
+            };
+            if first && self.fail_first.contains(&header.page) {
+                if self.drop_instead {
+                    return Err(P2pError::Connection("stream reset".into()));
+                }
+                ack.inserted = n - 1;
+                ack.failed = 1;
+            } else if !first {
+                // Whatever went in on the first attempt is already there
+                ack.inserted = 1;
+                ack.skipped = n - 1;
+            }
+            Ok(ack)
+        }
+    }
+
+    fn tracks(n: usize) -> Vec<TrackAnnouncement> {
+        (0..n)
+            .map(|i| TrackAnnouncement {
+                hash: format!("hash-{i}"),
+                title: format!("Track {i}"),
+                artist_name: "Artist".into(),
+                album_artist_name: None,
+                album_title: None,
+                duration_secs: 60.0,
+                format: "mp3".into(),
+                file_size: 1,
+                genre: None,
+                year: None,
+                track_number: None,
+                disc_number: None,
+                bitrate: None,
+                sample_rate: None,
+                origin_node: "node".into(),
+                cover_hash: None,
+                fingerprint: None,
+                waveform_data: None,
+                artist_image_hash: None,
+                artist_bio: None,
+                loudness_lufs: None,
+                dynamic_range: None,
+                encoding_quality: None,
+                signature: None,
+            })
+            .collect()
+    }
+
+    async fn push(peer: &MockPeer, pages: u64) -> CatalogSyncRecord {
+        let sync_id = Uuid::new_v4();
+        let mut record = CatalogSyncRecord::new(sync_id, "peer-a", true);
+        let page = tracks(5);
+        for n in 0..pages {
+            let header = CatalogPageHeader {
+                sync_id,
+                page: n,
+                total_pages: pages,
+            };
+            let delivery = deliver_page(peer, "peer-a", &header, &page).await;
+            record.add_delivery(&delivery);
+        }
+        record
+    }
+
+    // ── deliver_page ──
+
+    #[tokio::test]
+    async fn test_dropped_page_is_retried() {
+        let peer = MockPeer::new(&[1], true);
+        let record = push(&peer, 3).await;
+
+        assert_eq!(peer.attempts_for(0), 1);
+        assert_eq!(peer.attempts_for(1), 2);
+        assert_eq!(peer.attempts_for(2), 1);
+        assert_eq!(record.pages_sent, 3);
+        assert_eq!(record.pages_retried, 1);
+        assert_eq!(record.pages_failed, 0);
+        // Tracks stored before the stream dropped show up as skipped
+        assert_eq!(record.inserted, 11);
+        assert_eq!(record.skipped, 4);
+    }
+
+    #[tokio::test]
+    async fn test_page_with_failed_tracks_is_retried() {
+        let peer = MockPeer::new(&[0], false);
+        let record = push(&peer, 2).await;
+
+        assert_eq!(peer.attempts_for(0), 2);
+        assert_eq!(record.pages_retried, 1);
+        assert_eq!(record.pages_failed, 0);
+        // The retry stored the missing track; the rest were not double-counted
+        assert_eq!(record.inserted, 10);
+        assert_eq!(record.skipped, 0);
+        assert_eq!(record.failed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_page_failing_twice_is_reported() {
+        struct DeadPeer;
+
+        #[async_trait]
+        impl CatalogPageSink for DeadPeer {
+            async fn send_page(
+                &self,
+                _peer_id: &str,
+                _header: &CatalogPageHeader,
+                _tracks: &[TrackAnnouncement],
+            ) -> Result<CatalogPageAck, P2pError> {
+                Err(P2pError::Connection("peer went away".into()))
+            }
+        }
+
+        let header = CatalogPageHeader {
+            sync_id: Uuid::new_v4(),
+            page: 0,
+            total_pages: 1,
+        };
+        let delivery = deliver_page(&DeadPeer, "peer-a", &header, &tracks(1)).await;
+        assert_eq!(delivery.attempts, PAGE_ATTEMPTS);
+        assert!(!delivery.succeeded());
+        assert!(delivery.ack.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limited_page_waits_and_is_resent() {
+        let peer = MockPeer::rate_limited(3);
+        let started = tokio::time::Instant::now();
+        let record = push(&peer, 1).await;
+
+        // Refusals do not use up attempts
+        assert_eq!(peer.attempts_for(0), 1);
+        assert_eq!(record.pages_sent, 1);
+        assert_eq!(record.pages_retried, 0);
+        assert_eq!(record.inserted, 5);
+        assert!(started.elapsed() >= CATALOG_RATE_LIMIT_BACKOFF * 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_page_refused_past_backoff_budget_fails() {
+        let peer = MockPeer::rate_limited(MAX_RATE_LIMITED_WAITS + PAGE_ATTEMPTS);
+        let record = push(&peer, 1).await;
+
+        assert_eq!(peer.attempts_for(0), 0);
+        assert_eq!(record.pages_failed, 1);
+        assert_eq!(record.inserted, 0);
+    }
+
+    #[test]
+    fn test_ack_must_match_header() {
+        let header = CatalogPageHeader {
+            sync_id: Uuid::new_v4(),
+            page: 3,
+            total_pages: 4,
+        };
+        let mut ack = CatalogPageAck {
+            sync_id: header.sync_id,
+            page: 3,
+            ..Default::default()
+        };
+        assert!(ack.matches(&header));
+        ack.page = 2;
+        assert!(!ack.matches(&header));
+    }
+
+    // ── CatalogSyncHistory ──
+
+    #[test]
+    fn test_history_is_bounded_newest_first() {
+        let history = CatalogSyncHistory::new();
+        let ids: Vec<Uuid> = (0..MAX_HISTORY_PER_PEER + 2)
+            .map(|_| Uuid::new_v4())
+            .collect();
+        for id in &ids {
+            history.record(CatalogSyncRecord::new(*id, "peer-a", true));
+        }
+        history.record(CatalogSyncRecord::new(Uuid::new_v4(), "peer-b", false));
+
+        let records = history.for_peer("peer-a");
+        assert_eq!(records.len(), MAX_HISTORY_PER_PEER);
+        assert_eq!(records[0].sync_id, *ids.last().unwrap());
+        assert!(records.iter().all(|r| r.finished_at.is_some()));
+        assert_eq!(history.for_peer("peer-b").len(), 1);
+        assert!(history.for_peer("ghost").is_empty());
+    }
+}
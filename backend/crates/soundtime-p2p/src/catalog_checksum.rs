@@ -0,0 +1,278 @@
+//! Catalog checksums, to notice when a peer's catalog drifted from ours.
+//!
+//! A lost `AnnounceTrack` or `UpdateTrackMetadata` leaves two instances with
+//! different catalogs and nothing to tell them apart. Every periodic cycle
+//! the node asks one random online peer for its checksum
+//! (`P2pMessage::CatalogChecksumRequest`): a BLAKE3 hash over every distinct
+//! `content_hash` of the tracks we host (not replicated `p2p://` ones),
+//! sorted bytewise. If the checksums differ and the peer has more tracks, we
+//! ask it for its full catalog.
+//!
+//! Computing it reads every content hash, so the result is kept in a
+//! [`ChecksumCache`] until the catalog changes or [`CHECKSUM_MAX_AGE`]
+//! passes.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use sea_orm::{DatabaseConnection, FromQueryResult, Statement};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::error::P2pError;
+
+/// Content hashes read per query while computing the checksum.
+const CHECKSUM_PAGE_SIZE: i64 = 1000;
+
+/// Longest a cached checksum is reused. Catalog changes made without
+/// [`ChecksumCache::invalidate`] (such as moderation deletes) show up
+/// after this long.
+pub const CHECKSUM_MAX_AGE: Duration = Duration::from_secs(300);
+
+/// Checksum of a catalog and the number of content hashes it covers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogChecksum {
+    /// Hex-encoded BLAKE3 hash
+    pub checksum: String,
+    pub track_count: u64,
+}
+
+impl CatalogChecksum {
+    /// Whether a peer reporting `checksum` and `track_count` has tracks we
+    /// are probably missing, so its full catalog is worth requesting.
+    pub fn behind(&self, checksum: &str, track_count: u64) -> bool {
+        self.checksum != checksum && track_count > self.track_count
+    }
+}
+
+/// Incremental checksum over content hashes fed in sorted order.
+#[derive(Default)]
+pub struct ChecksumBuilder {
+    hasher: blake3::Hasher,
+    count: u64,
+}
+
+impl ChecksumBuilder {
+    pub fn update(&mut self, content_hash: &str) {
+        // Newline-terminated so adjacent hashes cannot run together
+        self.hasher.update(content_hash.as_bytes());
+        self.hasher.update(b"\n");
+        self.count += 1;
+    }
+
+    pub fn finish(self) -> CatalogChecksum {
+        CatalogChecksum {
+            checksum: self.hasher.finalize().to_hex().to_string(),
+            track_count: self.count,
+        }
+    }
+}
+
+#[derive(FromQueryResult)]
+struct HashRow {
+    content_hash: String,
+}
+
+/// Last computed checksum, dropped when the catalog changes.
+#[derive(Default)]
+pub struct ChecksumCache {
+    /// Bumped by every invalidation, so a checksum computed while the
+    /// catalog changed is not kept
+    generation: AtomicU64,
+    cached: Mutex<Option<(u64, Instant, CatalogChecksum)>>,
+}
+
+impl ChecksumCache {
+    /// The cached checksum if it is still current, otherwise a fresh one
+    /// from [`compute`]. Concurrent callers wait for a single computation.
+    pub async fn get(&self, db: &DatabaseConnection) -> Result<CatalogChecksum, P2pError> {
+        self.get_or_compute(compute(db)).await
+    }
+
+    async fn get_or_compute(
+        &self,
+        compute: impl std::future::Future<Output = Result<CatalogChecksum, P2pError>>,
+    ) -> Result<CatalogChecksum, P2pError> {
+        let mut cached = self.cached.lock().await;
+        let generation = self.generation.load(Ordering::Acquire);
+        if let Some((gen, at, checksum)) = cached.as_ref() {
+            if *gen == generation && at.elapsed() < CHECKSUM_MAX_AGE {
+                return Ok(checksum.clone());
+            }
+        }
+        let checksum = compute.await?;
+        *cached = (self.generation.load(Ordering::Acquire) == generation)
+            .then(|| (generation, Instant::now(), checksum.clone()));
+        Ok(checksum)
+    }
+
+    /// Drop the cached checksum after tracks were added, deleted or given a
+    /// content hash.
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// Checksum every content hash of the tracks we host, reading them page by
+/// page in key order so large catalogs are never loaded at once. Replicated
+/// (`p2p://`) tracks are left out, as from our catalog pushes. Hashes are
+/// compared with the `C` collation so every instance sorts them alike.
+pub async fn compute(db: &DatabaseConnection) -> Result<CatalogChecksum, P2pError> {
+    let mut builder = ChecksumBuilder::default();
+    let mut after = String::new();
+    loop {
+        let rows = HashRow::find_by_statement(Statement::from_sql_and_values(
+            sea_orm::DatabaseBackend::Postgres,
+            r#"SELECT DISTINCT content_hash COLLATE "C" AS content_hash
+               FROM tracks
+               WHERE content_hash IS NOT NULL
+                 AND file_path NOT LIKE 'p2p://%'
+                 AND content_hash COLLATE "C" > $1
+               ORDER BY 1
+               LIMIT $2"#,
+            [after.clone().into(), CHECKSUM_PAGE_SIZE.into()],
+        ))
+        .all(db)
+        .await?;
+
+        let Some(last) = rows.last() else {
+            break;
+        };
+        after = last.content_hash.clone();
+        let full_page = rows.len() as i64 == CHECKSUM_PAGE_SIZE;
+        for row in &rows {
+            builder.update(&row.content_hash);
+        }
+        if !full_page {
+            break;
+        }
+    }
+    Ok(builder.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checksum_of(hashes: &[&str]) -> CatalogChecksum {
+        let mut builder = ChecksumBuilder::default();
+        for hash in hashes {
+            builder.update(hash);
+        }
+        builder.finish()
+    }
+
+    // ── ChecksumBuilder ──
+
+    #[test]
+    fn test_checksum_is_deterministic() {
+        let a = checksum_of(&["aaa", "bbb", "ccc"]);
+        let b = checksum_of(&["aaa", "bbb", "ccc"]);
+        assert_eq!(a, b);
+        assert_eq!(a.track_count, 3);
+        assert_eq!(a.checksum.len(), 64);
+    }
+
+    #[test]
+    fn test_checksum_changes_with_catalog() {
+        let base = checksum_of(&["aaa", "bbb"]);
+        assert_ne!(base, checksum_of(&["aaa", "bbc"]));
+        assert_ne!(base, checksum_of(&["aaa"]));
+        // Hashes are separated, so concatenations do not collide
+        assert_ne!(base.checksum, checksum_of(&["aaab", "bb"]).checksum);
+    }
+
+    #[test]
+    fn test_empty_catalog() {
+        let empty = checksum_of(&[]);
+        assert_eq!(empty.track_count, 0);
+        assert_eq!(empty.checksum, blake3::hash(b"").to_hex().to_string());
+    }
+
+    // ── behind ──
+
+    #[test]
+    fn test_behind_only_when_peer_has_more_tracks() {
+        let local = checksum_of(&["aaa", "bbb"]);
+        assert!(!local.behind(&local.checksum, 2));
+        assert!(local.behind("other", 3));
+        // The peer is the one missing tracks; it will ask us
+        assert!(!local.behind("other", 1));
+        assert!(!local.behind("other", 2));
+    }
+
+    // ── ChecksumCache ──
+
+    #[tokio::test]
+    async fn test_cache_reused_until_invalidated() {
+        let cache = ChecksumCache::default();
+        let first = checksum_of(&["aaa"]);
+        let second = checksum_of(&["aaa", "bbb"]);
+
+        assert_eq!(
+            cache
+                .get_or_compute(async { Ok(first.clone()) })
+                .await
+                .unwrap(),
+            first
+        );
+        // Cached: the new catalog is not looked at
+        assert_eq!(
+            cache
+                .get_or_compute(async { Ok(second.clone()) })
+                .await
+                .unwrap(),
+            first
+        );
+
+        cache.invalidate();
+        assert_eq!(
+            cache
+                .get_or_compute(async { Ok(second.clone()) })
+                .await
+                .unwrap(),
+            second
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cache_drops_checksum_invalidated_while_computing() {
+        let cache = ChecksumCache::default();
+        let stale = checksum_of(&["aaa"]);
+        let fresh = checksum_of(&["aaa", "bbb"]);
+
+        let computed = cache
+            .get_or_compute(async {
+                cache.invalidate();
+                Ok(stale.clone())
+            })
+            .await
+            .unwrap();
+        assert_eq!(computed, stale);
+        // The catalog changed meanwhile, so the next caller computes again
+        assert_eq!(
+            cache
+                .get_or_compute(async { Ok(fresh.clone()) })
+                .await
+                .unwrap(),
+            fresh
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cache_keeps_nothing_on_error() {
+        let cache = ChecksumCache::default();
+        let result = cache
+            .get_or_compute(async { Err(P2pError::Connection("db down".into())) })
+            .await;
+        assert!(result.is_err());
+        let checksum = checksum_of(&["aaa"]);
+        assert_eq!(
+            cache
+                .get_or_compute(async { Ok(checksum.clone()) })
+                .await
+                .unwrap(),
+            checksum
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Progress of full catalog pushes to individual peers.
+//!
+//! `P2pNode::announce_all_tracks_to_peer` sends our catalog as paginated
+//! `CatalogSync` messages. [`CatalogSyncTracker`] records, per peer, whether
+//! such a push is running and how many pages have gone out, so an admin can
+//! trigger a re-sync and poll it. Finished entries are kept until the next
+//! push to the same peer replaces them.
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// State of the latest catalog push to one peer.
+#[derive(Clone, Debug, Serialize)]
+pub struct CatalogSyncProgress {
+    pub task_id: Uuid,
+    pub peer_id: String,
+    pub running: bool,
+    /// `CatalogSync` pages delivered to the peer
+    pub pages_sent: u64,
+    /// Pages that could not be read or sent
+    pub pages_failed: u64,
+    /// Total pages in this push (0 until the catalog has been counted)
+    pub total_pages: u64,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Set when the push stopped before sending anything
+    pub error: Option<String>,
+}
+
+/// Per-peer catalog push progress, keyed by peer EndpointId.
+#[derive(Default)]
+pub struct CatalogSyncTracker {
+    tasks: DashMap<String, CatalogSyncProgress>,
+}
+
+impl CatalogSyncTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new push to `peer_id`. Returns `None` if one is already
+    /// running for that peer.
+    pub fn start(&self, peer_id: &str) -> Option<Uuid> {
+        let mut entry = self
+            .tasks
+            .entry(peer_id.to_string())
+            .or_insert_with(|| Self::fresh(peer_id));
+        if entry.running {
+            return None;
+        }
+        *entry = Self::fresh(peer_id);
+        entry.running = true;
+        Some(entry.task_id)
+    }
+
+    fn fresh(peer_id: &str) -> CatalogSyncProgress {
+        CatalogSyncProgress {
+            task_id: Uuid::new_v4(),
+            peer_id: peer_id.to_string(),
+            running: false,
+            pages_sent: 0,
+            pages_failed: 0,
+            total_pages: 0,
+            started_at: Utc::now(),
+            finished_at: None,
+            error: None,
+        }
+    }
+
+    pub fn set_total_pages(&self, peer_id: &str, total_pages: u64) {
+        if let Some(mut p) = self.tasks.get_mut(peer_id) {
+            p.total_pages = total_pages;
+        }
+    }
+
+    pub fn page_sent(&self, peer_id: &str) {
+        if let Some(mut p) = self.tasks.get_mut(peer_id) {
+            p.pages_sent += 1;
+        }
+    }
+
+    pub fn page_failed(&self, peer_id: &str) {
+        if let Some(mut p) = self.tasks.get_mut(peer_id) {
+            p.pages_failed += 1;
+        }
+    }
+
+    /// Mark the push to `peer_id` as done, optionally with an error.
+    pub fn finish(&self, peer_id: &str, error: Option<String>) {
+        if let Some(mut p) = self.tasks.get_mut(peer_id) {
+            p.running = false;
+            p.finished_at = Some(Utc::now());
+            p.error = error;
+        }
+    }
+
+    /// Latest push to `peer_id`, running or finished.
+    pub fn get(&self, peer_id: &str) -> Option<CatalogSyncProgress> {
+        self.tasks.get(peer_id).map(|p| p.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_start_rejects_concurrent_push() {
+        let tracker = CatalogSyncTracker::new();
+        let task = tracker.start("peer-a").unwrap();
+        assert!(tracker.start("peer-a").is_none());
+        // Other peers are independent
+        assert!(tracker.start("peer-b").is_some());
+
+        tracker.finish("peer-a", None);
+        let next = tracker.start("peer-a").unwrap();
+        assert_ne!(task, next);
+    }
+
+    #[test]
+    fn test_progress_counts_pages() {
+        let tracker = CatalogSyncTracker::new();
+        let task = tracker.start("peer-a").unwrap();
+        tracker.set_total_pages("peer-a", 3);
+        tracker.page_sent("peer-a");
+        tracker.page_failed("peer-a");
+
+        let p = tracker.get("peer-a").unwrap();
+        assert_eq!(p.task_id, task);
+        assert!(p.running);
+        assert_eq!(p.pages_sent, 1);
+        assert_eq!(p.pages_failed, 1);
+        assert_eq!(p.total_pages, 3);
+
+        tracker.finish("peer-a", None);
+        let p = tracker.get("peer-a").unwrap();
+        assert!(!p.running);
+        assert!(p.finished_at.is_some());
+    }
+
+    #[test]
+    fn test_unknown_peer_has_no_progress() {
+        let tracker = CatalogSyncTracker::new();
+        tracker.page_sent("ghost");
+        assert!(tracker.get("ghost").is_none());
+    }
+}
@@ -0,0 +1,95 @@
+//! Per-peer rate limit on incoming catalog sync pages.
+//!
+//! A peer pushing its catalog sends pages as fast as it can, and every
+//! track of every page goes through `process_track_announcement`. A peer
+//! with 100,000 tracks would keep that path busy for as long as it takes.
+//! The receiver therefore accepts at most `P2P_CATALOG_SYNC_PAGES_PER_MINUTE`
+//! pages per peer in each one-minute window. A page over the limit is
+//! answered by closing the stream without an ack, and the peer's next page
+//! is held back for [`CATALOG_RATE_LIMIT_PAUSE`]. The sender reads the bare
+//! `fin` as [`P2pError::RateLimited`](crate::error::P2pError::RateLimited)
+//! and waits [`CATALOG_RATE_LIMIT_BACKOFF`] before sending the page again.
+
+use std::time::{Duration, Instant};
+
+/// Default pages accepted from one peer per minute.
+pub const DEFAULT_CATALOG_SYNC_PAGES_PER_MINUTE: u32 = 60;
+
+/// Length of the window pages are counted over.
+pub const CATALOG_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Delay before the next page from a peer over the limit is looked at.
+pub const CATALOG_RATE_LIMIT_PAUSE: Duration = Duration::from_secs(1);
+
+/// Delay before the sender retries a page the peer refused.
+pub const CATALOG_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Refusals a page may get before it counts as failed: enough backoffs to
+/// outlast a whole window.
+pub const MAX_RATE_LIMITED_WAITS: u32 = 7;
+
+/// Catalog pages received from one peer in the current window.
+#[derive(Debug)]
+pub struct PeerCatalogSync {
+    pub last_minute_pages: u32,
+    pub window_start: Instant,
+}
+
+impl PeerCatalogSync {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            last_minute_pages: 0,
+            window_start: now,
+        }
+    }
+
+    /// Count a page received at `now`. Returns `false`, without counting
+    /// it, if the peer already sent `limit` pages in the current window.
+    pub fn admit(&mut self, now: Instant, limit: u32) -> bool {
+        if now.saturating_duration_since(self.window_start) >= CATALOG_RATE_WINDOW {
+            self.window_start = now;
+            self.last_minute_pages = 0;
+        }
+        if self.last_minute_pages >= limit {
+            return false;
+        }
+        self.last_minute_pages += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admits_up_to_limit() {
+        let start = Instant::now();
+        let mut sync = PeerCatalogSync::new(start);
+        for i in 0..60 {
+            assert!(sync.admit(start + Duration::from_millis(i * 100), 60));
+        }
+        assert!(!sync.admit(start + Duration::from_secs(10), 60));
+        assert!(!sync.admit(start + Duration::from_secs(59), 60));
+        assert_eq!(sync.last_minute_pages, 60);
+    }
+
+    #[test]
+    fn test_window_resets_after_a_minute() {
+        let start = Instant::now();
+        let mut sync = PeerCatalogSync::new(start);
+        assert!(sync.admit(start, 2));
+        assert!(sync.admit(start, 2));
+        assert!(!sync.admit(start + Duration::from_secs(30), 2));
+
+        let later = start + CATALOG_RATE_WINDOW;
+        assert!(sync.admit(later, 2));
+        assert_eq!(sync.window_start, later);
+        assert_eq!(sync.last_minute_pages, 1);
+    }
+
+    #[test]
+    fn test_backoffs_outlast_a_window() {
+        assert!(CATALOG_RATE_LIMIT_BACKOFF * MAX_RATE_LIMITED_WAITS > CATALOG_RATE_WINDOW);
+    }
+}
@@ -0,0 +1,141 @@
+//! Per-IP limit on concurrent incoming P2P connections.
+//!
+//! The global connection semaphore stops the node from being overwhelmed, but
+//! on its own a single aggressive host could occupy every slot. The accept
+//! loop therefore also takes a slot from [`IpConnectionLimiter`] for the
+//! remote IP; the slot is released when the returned guard is dropped at the
+//! end of the connection handler.
+
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+
+/// Default maximum number of live connections from a single IP.
+pub const DEFAULT_MAX_CONNECTIONS_PER_IP: u32 = 4;
+
+/// Counts live incoming connections per remote IP.
+pub struct IpConnectionLimiter {
+    /// Maximum live connections per IP (0 = unlimited)
+    max_per_ip: u32,
+    counts: Arc<DashMap<IpAddr, AtomicU32>>,
+}
+
+impl IpConnectionLimiter {
+    pub fn new(max_per_ip: u32) -> Self {
+        Self {
+            max_per_ip,
+            counts: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Configured per-IP limit (0 = unlimited).
+    pub fn max_per_ip(&self) -> u32 {
+        self.max_per_ip
+    }
+
+    /// Take a connection slot for `ip`, or `None` if the IP is at its limit.
+    pub fn try_acquire(&self, ip: IpAddr) -> Option<IpConnectionGuard> {
+        // The entry holds the shard lock, so check-and-increment is atomic.
+        let entry = self.counts.entry(ip).or_insert_with(|| AtomicU32::new(0));
+        let live = entry.load(Ordering::Relaxed);
+        if self.max_per_ip > 0 && live >= self.max_per_ip {
+            return None;
+        }
+        entry.store(live + 1, Ordering::Relaxed);
+        drop(entry);
+
+        Some(IpConnectionGuard {
+            ip,
+            counts: Arc::clone(&self.counts),
+        })
+    }
+
+    /// Number of live connections currently held by `ip`.
+    pub fn live_connections(&self, ip: IpAddr) -> u32 {
+        self.counts
+            .get(&ip)
+            .map(|c| c.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+}
+
+/// Releases the connection slot for its IP when dropped.
+pub struct IpConnectionGuard {
+    ip: IpAddr,
+    counts: Arc<DashMap<IpAddr, AtomicU32>>,
+}
+
+impl Drop for IpConnectionGuard {
+    fn drop(&mut self) {
+        if let Some(entry) = self.counts.get(&self.ip) {
+            entry.fetch_sub(1, Ordering::Relaxed);
+        }
+        // Forget IPs with no live connections so the map does not grow unbounded
+        self.counts
+            .remove_if(&self.ip, |_, c| c.load(Ordering::Relaxed) == 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_only_four_of_ten_connections_from_same_ip() {
+        let limiter = Arc::new(IpConnectionLimiter::new(DEFAULT_MAX_CONNECTIONS_PER_IP));
+        let peer = ip("203.0.113.7");
+
+        let handles: Vec<_> = (0..10)
+            .map(|_| {
+                let limiter = Arc::clone(&limiter);
+                tokio::spawn(async move { limiter.try_acquire(peer) })
+            })
+            .collect();
+
+        let mut accepted = Vec::new();
+        for h in handles {
+            if let Some(guard) = h.await.unwrap() {
+                accepted.push(guard);
+            }
+        }
+        assert_eq!(accepted.len(), 4);
+        assert_eq!(limiter.live_connections(peer), 4);
+    }
+
+    #[test]
+    fn test_slot_released_on_drop() {
+        let limiter = IpConnectionLimiter::new(1);
+        let peer = ip("198.51.100.1");
+
+        let guard = limiter.try_acquire(peer).unwrap();
+        assert!(limiter.try_acquire(peer).is_none());
+        drop(guard);
+
+        assert_eq!(limiter.live_connections(peer), 0);
+        assert!(limiter.counts.is_empty());
+        assert!(limiter.try_acquire(peer).is_some());
+    }
+
+    #[test]
+    fn test_limit_is_per_ip() {
+        let limiter = IpConnectionLimiter::new(1);
+        let _a = limiter.try_acquire(ip("192.0.2.1")).unwrap();
+        let _b = limiter.try_acquire(ip("192.0.2.2")).unwrap();
+        let _c = limiter.try_acquire(ip("2001:db8::1")).unwrap();
+        assert!(limiter.try_acquire(ip("192.0.2.1")).is_none());
+    }
+
+    #[test]
+    fn test_zero_means_unlimited() {
+        let limiter = IpConnectionLimiter::new(0);
+        let peer = ip("192.0.2.9");
+        let guards: Vec<_> = (0..100).filter_map(|_| limiter.try_acquire(peer)).collect();
+        assert_eq!(guards.len(), 100);
+    }
+}
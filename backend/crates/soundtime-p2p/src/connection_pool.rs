@@ -3,45 +3,427 @@
 //! Instead of opening a new QUIC connection for every message,
 //! the pool caches connections by peer `EndpointId` and reuses them
 //! for subsequent stream opens. Stale connections are evicted
-//! automatically when `open_bi()` fails.
+//! automatically when `open_bi()` fails, and a periodic keepalive pass
+//! ([`ConnectionPool::keepalive`]) probes cached connections so one to a
+//! restarted peer is dropped before the next caller picks it up.
+//!
+//! One-way messages are not written directly by callers: each peer gets a
+//! pair of outbound queues (high and low priority) drained by a dedicated
+//! send task, so that control traffic (pings, peer exchange, search) is never
+//! stuck behind a multi-page catalog sync. High-priority messages are also
+//! sent while a low-priority one is still in flight (each on its own
+//! stream), so a slow or retrying catalog page does not hold them up either.
+//! Once a peer has more than
+//! [`DEFAULT_MAX_QUEUE_DEPTH`] messages waiting, new low-priority ones are
+//! refused rather than piling up for a peer that cannot keep up.
 
 use std::collections::HashMap;
-use std::time::Instant;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use iroh::endpoint::Connection;
+use iroh::endpoint::{ConnectOptions, Connection};
 use iroh::{Endpoint, EndpointAddr, EndpointId};
-use tokio::sync::Mutex;
-use tracing::debug;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
 
 use crate::error::P2pError;
+use crate::metrics::P2P_METRICS;
+use crate::node::ProtocolVersion;
+use crate::stats::ConnectionPoolStats;
 
 /// Maximum number of cached connections.
 const MAX_POOL_SIZE: usize = 128;
 
-/// Connections idle longer than this are evicted on next access.
-const MAX_IDLE_SECS: u64 = 60;
+/// Default idle TTL: connections unused for longer are evicted.
+pub const MAX_IDLE_SECS: u64 = 60;
+
+/// Maximum time a keepalive probe waits for the peer to close the stream.
+const PROBE_TIMEOUT_SECS: u64 = 5;
+
+/// Capacity of each per-peer outbound queue.
+const OUTBOUND_QUEUE_CAPACITY: usize = 256;
+
+/// Default number of messages a peer may have waiting before new
+/// low-priority ones are refused.
+pub const DEFAULT_MAX_QUEUE_DEPTH: usize = 500;
+
+/// Delivery attempts per outbound message before giving up.
+const MAX_SEND_ATTEMPTS: u32 = 3;
+
+/// Maximum time to wait for the peer to acknowledge a stream.
+const STREAM_ACK_TIMEOUT_SECS: u64 = 30;
+
+/// Scheduling class of an outbound message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessagePriority {
+    /// Latency-sensitive control traffic, always sent first.
+    High,
+    /// Bulk catalog replication traffic.
+    Low,
+}
+
+/// A serialized message waiting in a peer's outbound queue.
+struct OutboundMessage {
+    bytes: Vec<u8>,
+    /// Oldest protocol version the peer must have negotiated.
+    min_version: ProtocolVersion,
+    /// Notified with the final delivery result.
+    done: oneshot::Sender<Result<(), P2pError>>,
+}
+
+/// Sender handles for a peer's two outbound queues.
+#[derive(Clone)]
+struct PeerSender {
+    high: mpsc::Sender<OutboundMessage>,
+    low: mpsc::Sender<OutboundMessage>,
+    /// Messages handed to [`ConnectionPool::send`] that the send task has
+    /// not picked up yet, including callers still waiting for a free slot.
+    depth: Arc<AtomicUsize>,
+}
+
+impl PeerSender {
+    fn for_priority(&self, priority: MessagePriority) -> &mpsc::Sender<OutboundMessage> {
+        match priority {
+            MessagePriority::High => &self.high,
+            MessagePriority::Low => &self.low,
+        }
+    }
+}
 
 /// A cached connection entry.
 struct PoolEntry {
     conn: Connection,
+    /// Protocol version negotiated via ALPN for this connection.
+    version: ProtocolVersion,
     last_used: Instant,
 }
 
+/// What a keepalive probe found out about a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProbeResult {
+    Alive,
+    Dead,
+    /// No answer in time; the peer may just be busy with an earlier stream.
+    Inconclusive,
+}
+
 /// Thread-safe pool of reusable QUIC connections keyed by peer `EndpointId`.
+///
+/// Besides the raw connections (used for request/response exchanges such as
+/// ping or blob fetches), the pool holds the per-peer outbound queue senders
+/// used by [`ConnectionPool::send`].
 pub struct ConnectionPool {
     endpoint: Endpoint,
-    alpn: &'static [u8],
+    /// ALPNs offered when connecting, most preferred first.
+    alpns: &'static [&'static [u8]],
     entries: Mutex<HashMap<EndpointId, PoolEntry>>,
+    senders: Mutex<HashMap<EndpointId, PeerSender>>,
+    /// Direct addresses configured for peers, used when dialling them.
+    known_addrs: Mutex<HashMap<EndpointId, EndpointAddr>>,
+    /// Connections unused for longer than this are evicted.
+    idle_ttl: Duration,
+    /// Queue depth above which low-priority messages are refused.
+    max_queue_depth: usize,
+    /// Mirror of `entries.len()`, readable without the lock.
+    open: AtomicUsize,
+    evictions: AtomicU64,
+    failed_probes: AtomicU64,
 }
 
 impl ConnectionPool {
     /// Create a new connection pool wrapping the given iroh `Endpoint`.
-    pub fn new(endpoint: Endpoint, alpn: &'static [u8]) -> Self {
+    ///
+    /// `alpns` lists the protocol versions to offer, most preferred first;
+    /// the peer picks one during the handshake.
+    pub fn new(endpoint: Endpoint, alpns: &'static [&'static [u8]]) -> Self {
         Self {
             endpoint,
-            alpn,
+            alpns,
             entries: Mutex::new(HashMap::new()),
+            senders: Mutex::new(HashMap::new()),
+            known_addrs: Mutex::new(HashMap::new()),
+            idle_ttl: Duration::from_secs(MAX_IDLE_SECS),
+            max_queue_depth: DEFAULT_MAX_QUEUE_DEPTH,
+            open: AtomicUsize::new(0),
+            evictions: AtomicU64::new(0),
+            failed_probes: AtomicU64::new(0),
+        }
+    }
+
+    /// Evict connections left unused for longer than `ttl` instead of the
+    /// default [`MAX_IDLE_SECS`].
+    pub fn with_idle_ttl(mut self, ttl: Duration) -> Self {
+        self.idle_ttl = ttl;
+        self
+    }
+
+    /// Refuse low-priority messages for a peer with more than `depth`
+    /// messages waiting, instead of the default [`DEFAULT_MAX_QUEUE_DEPTH`].
+    pub fn with_max_queue_depth(mut self, depth: usize) -> Self {
+        self.max_queue_depth = depth;
+        self
+    }
+
+    /// Queue a serialized message for delivery to a peer and wait for the result.
+    ///
+    /// The message goes into the peer's high- or low-priority queue; the peer's
+    /// send task always drains pending high-priority messages before picking
+    /// up the next low-priority one, and keeps sending high-priority ones
+    /// while that low-priority one is delivered. Delivery is retried up to 3 times with
+    /// doubling delays (1s, 2s, 4s). Messages the peer's negotiated protocol
+    /// version does not understand fail with [`P2pError::UnsupportedProtocol`].
+    /// Low-priority messages fail with [`P2pError::QueueFull`] while the
+    /// peer's queue depth is at the limit.
+    pub async fn send(
+        self: &Arc<Self>,
+        node_id: EndpointId,
+        priority: MessagePriority,
+        min_version: ProtocolVersion,
+        bytes: Vec<u8>,
+    ) -> Result<(), P2pError> {
+        let (done_tx, done_rx) = oneshot::channel();
+        let mut msg = OutboundMessage {
+            bytes,
+            min_version,
+            done: done_tx,
+        };
+
+        // A closed queue means the send task exited (peer pruned); retry once
+        // with a freshly spawned task.
+        for _ in 0..2 {
+            let sender = self.peer_sender(node_id).await;
+            let depth = sender.depth.load(Ordering::Relaxed);
+            if priority == MessagePriority::Low && depth >= self.max_queue_depth {
+                warn!(
+                    peer = %node_id,
+                    depth,
+                    limit = self.max_queue_depth,
+                    "outbound queue full, dropping low-priority message"
+                );
+                return Err(P2pError::QueueFull(node_id.to_string()));
+            }
+            sender.depth.fetch_add(1, Ordering::Relaxed);
+            let result = sender.for_priority(priority).send(msg).await;
+            if result.is_err() {
+                sender.depth.fetch_sub(1, Ordering::Relaxed);
+            }
+            match result {
+                Ok(()) => {
+                    return done_rx.await.unwrap_or_else(|_| {
+                        Err(P2pError::Connection("outbound send task dropped".into()))
+                    });
+                }
+                Err(mpsc::error::SendError(returned)) => {
+                    msg = returned;
+                    self.senders.lock().await.remove(&node_id);
+                }
+            }
+        }
+
+        Err(P2pError::Connection(format!(
+            "outbound queue for peer {node_id} is closed"
+        )))
+    }
+
+    /// Get the queue senders for a peer, spawning its send task on first use.
+    async fn peer_sender(self: &Arc<Self>, node_id: EndpointId) -> PeerSender {
+        let mut senders = self.senders.lock().await;
+        if let Some(sender) = senders.get(&node_id) {
+            return sender.clone();
+        }
+
+        let (high_tx, high_rx) = mpsc::channel(OUTBOUND_QUEUE_CAPACITY);
+        let (low_tx, low_rx) = mpsc::channel(OUTBOUND_QUEUE_CAPACITY);
+        let sender = PeerSender {
+            high: high_tx,
+            low: low_tx,
+            depth: Arc::new(AtomicUsize::new(0)),
+        };
+        senders.insert(node_id, sender.clone());
+
+        let pool = Arc::clone(self);
+        let depth = Arc::clone(&sender.depth);
+        tokio::spawn(async move {
+            pool.run_send_task(node_id, high_rx, low_rx, depth).await;
+        });
+
+        sender
+    }
+
+    /// Per-peer send loop. Exits once both queues are closed and drained.
+    async fn run_send_task(
+        &self,
+        node_id: EndpointId,
+        mut high: mpsc::Receiver<OutboundMessage>,
+        mut low: mpsc::Receiver<OutboundMessage>,
+        depth: Arc<AtomicUsize>,
+    ) {
+        debug!(peer = %node_id, "outbound send task started");
+        while let Some((priority, msg)) = next_outbound(&mut high, &mut low).await {
+            depth.fetch_sub(1, Ordering::Relaxed);
+            match priority {
+                MessagePriority::High => self.deliver(node_id, msg).await,
+                MessagePriority::Low => self.deliver_low(node_id, msg, &mut high, &depth).await,
+            }
+        }
+        debug!(peer = %node_id, "outbound send task stopped");
+    }
+
+    /// Deliver a queued message and report the result to its sender.
+    async fn deliver(&self, node_id: EndpointId, msg: OutboundMessage) {
+        let result = self
+            .send_with_retry(node_id, msg.min_version, &msg.bytes)
+            .await;
+        // The caller may have given up waiting — that's fine.
+        let _ = msg.done.send(result);
+    }
+
+    /// Deliver a low-priority message, sending the high-priority messages
+    /// queued meanwhile one at a time alongside it instead of after it.
+    async fn deliver_low(
+        &self,
+        node_id: EndpointId,
+        msg: OutboundMessage,
+        high: &mut mpsc::Receiver<OutboundMessage>,
+        depth: &AtomicUsize,
+    ) {
+        let low = self.deliver(node_id, msg);
+        tokio::pin!(low);
+        loop {
+            tokio::select! {
+                biased;
+                () = &mut low => return,
+                Some(urgent) = high.recv() => {
+                    depth.fetch_sub(1, Ordering::Relaxed);
+                    let urgent = self.deliver(node_id, urgent);
+                    tokio::pin!(urgent);
+                    tokio::select! {
+                        biased;
+                        () = &mut urgent => {}
+                        () = &mut low => {
+                            urgent.await;
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Deliver message bytes with retry and exponential backoff.
+    async fn send_with_retry(
+        &self,
+        node_id: EndpointId,
+        min_version: ProtocolVersion,
+        bytes: &[u8],
+    ) -> Result<(), P2pError> {
+        let mut delay = Duration::from_secs(1);
+
+        for attempt in 0..MAX_SEND_ATTEMPTS {
+            match self.try_send_bytes(node_id, min_version, bytes).await {
+                Ok(()) => return Ok(()),
+                // Retrying cannot change the peer's protocol version
+                Err(e @ P2pError::UnsupportedProtocol(_)) => return Err(e),
+                Err(e) if attempt < MAX_SEND_ATTEMPTS - 1 => {
+                    warn!(peer = %node_id, attempt, "send failed, retrying in {:?}: {e}", delay);
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+                Err(e) => return Err(e),
+            }
         }
+        unreachable!()
+    }
+
+    /// Write a message to a peer right away, bypassing its outbound queue,
+    /// with a single attempt. For messages that cannot wait, like the
+    /// `Goodbye` sent on shutdown.
+    pub async fn send_now(
+        &self,
+        node_id: EndpointId,
+        min_version: ProtocolVersion,
+        msg_bytes: &[u8],
+    ) -> Result<(), P2pError> {
+        self.try_send_bytes(node_id, min_version, msg_bytes).await
+    }
+
+    /// Single attempt to write length-prefixed message bytes on a new stream.
+    async fn try_send_bytes(
+        &self,
+        node_id: EndpointId,
+        min_version: ProtocolVersion,
+        msg_bytes: &[u8],
+    ) -> Result<(), P2pError> {
+        let conn = self.get_connection(node_id).await.inspect_err(|_| {
+            P2P_METRICS.peer_connection_errors_total.inc();
+        })?;
+
+        let version = self
+            .negotiated_version(&node_id)
+            .await
+            .unwrap_or(ProtocolVersion::V1);
+        if version < min_version {
+            return Err(P2pError::UnsupportedProtocol(format!(
+                "peer {node_id} negotiated v{} but message requires v{}",
+                version.as_u8(),
+                min_version.as_u8()
+            )));
+        }
+
+        let result: Result<(), P2pError> = async {
+            let (mut send, _recv) = conn
+                .open_bi()
+                .await
+                .map_err(|e| P2pError::Connection(e.to_string()))?;
+
+            send.write_all(&(msg_bytes.len() as u32).to_be_bytes())
+                .await
+                .map_err(|e| P2pError::Connection(e.to_string()))?;
+            send.write_all(msg_bytes)
+                .await
+                .map_err(|e| P2pError::Connection(e.to_string()))?;
+            send.finish()
+                .map_err(|e| P2pError::Connection(e.to_string()))?;
+
+            // Wait for the peer to acknowledge receipt (with timeout)
+            match tokio::time::timeout(Duration::from_secs(STREAM_ACK_TIMEOUT_SECS), send.stopped())
+                .await
+            {
+                Ok(Ok(_)) => {}
+                Ok(Err(_)) => {}
+                Err(_) => {
+                    warn!("timed out waiting for stream ack");
+                }
+            }
+
+            Ok(())
+        }
+        .await;
+
+        match &result {
+            Ok(()) => {
+                P2P_METRICS.messages_sent_total.inc();
+                P2P_METRICS.bytes_sent_total.inc_by(msg_bytes.len() as u64);
+            }
+            Err(_) => {
+                P2P_METRICS.peer_connection_errors_total.inc();
+                self.invalidate(&node_id).await;
+            }
+        }
+
+        result
+    }
+
+    /// Remember direct addresses for a peer (e.g. from `P2P_SEED_PEERS`) so
+    /// every new connection to it dials them, without needing relay or DNS
+    /// discovery. Addresses found by discovery are still used as well.
+    pub async fn add_known_addr(&self, addr: EndpointAddr) {
+        if addr.ip_addrs().next().is_none() {
+            return;
+        }
+        self.known_addrs.lock().await.insert(addr.id, addr);
     }
 
     /// Get a reusable connection to a peer, or establish a new one.
@@ -53,7 +435,7 @@ impl ConnectionPool {
 
         // Check for an existing cached connection
         if let Some(entry) = entries.get_mut(&node_id) {
-            if entry.last_used.elapsed().as_secs() < MAX_IDLE_SECS {
+            if entry.last_used.elapsed() < self.idle_ttl {
                 entry.last_used = Instant::now();
                 let conn = entry.conn.clone();
                 drop(entries);
@@ -62,19 +444,37 @@ impl ConnectionPool {
                 // Stale — remove it
                 debug!(peer = %node_id, "evicting idle connection from pool");
                 entries.remove(&node_id);
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+                self.open.store(entries.len(), Ordering::Relaxed);
             }
         }
 
         // Drop the lock before connecting (connecting is async and slow)
         drop(entries);
 
-        // Establish new connection
-        let peer_addr = EndpointAddr::new(node_id);
+        // Establish new connection, offering every supported protocol version
+        let peer_addr = self
+            .known_addrs
+            .lock()
+            .await
+            .get(&node_id)
+            .cloned()
+            .unwrap_or_else(|| EndpointAddr::new(node_id));
+        let (preferred, fallbacks) = self
+            .alpns
+            .split_first()
+            .ok_or_else(|| P2pError::Connection("no ALPN configured".into()))?;
+        let options = ConnectOptions::new()
+            .with_additional_alpns(fallbacks.iter().map(|alpn| alpn.to_vec()).collect());
         let conn = self
             .endpoint
-            .connect(peer_addr, self.alpn)
+            .connect_with_opts(peer_addr, preferred, options)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?
             .await
             .map_err(|e| P2pError::Connection(e.to_string()))?;
+        let version = ProtocolVersion::from_alpn(conn.alpn()).unwrap_or(ProtocolVersion::V1);
+        debug!(peer = %node_id, version = version.as_u8(), "negotiated P2P protocol version");
 
         // Cache the new connection
         let mut entries = self.entries.lock().await;
@@ -88,13 +488,20 @@ impl ConnectionPool {
             node_id,
             PoolEntry {
                 conn: conn.clone(),
+                version,
                 last_used: Instant::now(),
             },
         );
+        self.open.store(entries.len(), Ordering::Relaxed);
 
         Ok(conn)
     }
 
+    /// Protocol version negotiated on the cached connection to a peer, if any.
+    pub async fn negotiated_version(&self, node_id: &EndpointId) -> Option<ProtocolVersion> {
+        self.entries.lock().await.get(node_id).map(|e| e.version)
+    }
+
     /// Remove a cached connection (e.g., after a stream error).
     ///
     /// Call this when `open_bi()` or a write fails so the next attempt
@@ -103,6 +510,8 @@ impl ConnectionPool {
         let mut entries = self.entries.lock().await;
         if entries.remove(node_id).is_some() {
             debug!(peer = %node_id, "invalidated pooled connection");
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+            self.open.store(entries.len(), Ordering::Relaxed);
         }
     }
 
@@ -116,6 +525,26 @@ impl ConnectionPool {
         if evicted > 0 {
             debug!(evicted, "cleaned up stale pool entries");
         }
+        self.open.store(entries.len(), Ordering::Relaxed);
+        drop(entries);
+
+        // Dropping the senders closes the queues; each send task finishes
+        // whatever is still queued and then exits.
+        self.senders
+            .lock()
+            .await
+            .retain(|id, _| active_peers.contains(id));
+    }
+
+    /// Messages waiting in each peer's outbound queues, keyed by peer ID.
+    /// Peers without a send task are left out.
+    pub async fn queue_depths(&self) -> HashMap<String, usize> {
+        self.senders
+            .lock()
+            .await
+            .iter()
+            .map(|(id, sender)| (id.to_string(), sender.depth.load(Ordering::Relaxed)))
+            .collect()
     }
 
     /// Number of currently cached connections.
@@ -128,6 +557,87 @@ impl ConnectionPool {
         self.entries.lock().await.is_empty()
     }
 
+    /// Current pool counters. `evictions` counts connections dropped for
+    /// being dead, idle past the TTL or the oldest at capacity.
+    pub fn stats(&self) -> ConnectionPoolStats {
+        ConnectionPoolStats {
+            open_connections: self.open.load(Ordering::Relaxed) as u64,
+            evictions: self.evictions.load(Ordering::Relaxed),
+            failed_probes: self.failed_probes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// One keepalive pass: evict connections idle past the TTL, then probe
+    /// the rest and evict the ones that turn out to be dead.
+    ///
+    /// `keepalive_msg` (a serialized `KeepAlive`) is only sent to peers that
+    /// negotiated v2; v1 connections are just checked for a close QUIC has
+    /// already seen. A probe that times out is inconclusive and keeps the
+    /// connection. Returns the number of probe messages sent.
+    pub async fn keepalive(&self, keepalive_msg: &[u8]) -> usize {
+        let candidates: Vec<(EndpointId, Connection, ProtocolVersion)> = {
+            let mut entries = self.entries.lock().await;
+            let before = entries.len();
+            entries.retain(|_, e| e.last_used.elapsed() < self.idle_ttl);
+            let idle = before - entries.len();
+            if idle > 0 {
+                debug!(evicted = idle, "evicted idle connections from pool");
+                self.evictions.fetch_add(idle as u64, Ordering::Relaxed);
+            }
+            self.open.store(entries.len(), Ordering::Relaxed);
+            entries
+                .iter()
+                .map(|(id, e)| (*id, e.conn.clone(), e.version))
+                .collect()
+        };
+
+        let keepalive_msg: Arc<[u8]> = keepalive_msg.into();
+        let mut probes = JoinSet::new();
+        let mut sent = 0;
+        for (node_id, conn, version) in candidates {
+            if version >= ProtocolVersion::V2 {
+                sent += 1;
+            }
+            let msg = Arc::clone(&keepalive_msg);
+            probes.spawn(async move {
+                let result = probe_connection(&conn, version, &msg).await;
+                (node_id, conn.stable_id(), result)
+            });
+        }
+
+        let mut dead = Vec::new();
+        while let Some(joined) = probes.join_next().await {
+            match joined {
+                Ok((node_id, stable_id, ProbeResult::Dead)) => dead.push((node_id, stable_id)),
+                Ok((node_id, _, ProbeResult::Inconclusive)) => {
+                    debug!(peer = %node_id, "keepalive probe timed out, keeping connection");
+                }
+                Ok((_, _, ProbeResult::Alive)) => {}
+                Err(e) => warn!("keepalive probe task failed: {e}"),
+            }
+        }
+
+        if !dead.is_empty() {
+            self.failed_probes
+                .fetch_add(dead.len() as u64, Ordering::Relaxed);
+            let mut entries = self.entries.lock().await;
+            for (node_id, stable_id) in dead {
+                // A caller may have replaced the entry while the probe ran
+                if entries
+                    .get(&node_id)
+                    .is_some_and(|e| e.conn.stable_id() == stable_id)
+                {
+                    entries.remove(&node_id);
+                    self.evictions.fetch_add(1, Ordering::Relaxed);
+                    debug!(peer = %node_id, "evicted dead connection from pool");
+                }
+            }
+            self.open.store(entries.len(), Ordering::Relaxed);
+        }
+
+        sent
+    }
+
     /// Evict the oldest entry to make room.
     fn evict_oldest(&self, entries: &mut HashMap<EndpointId, PoolEntry>) {
         if let Some(oldest_id) = entries
@@ -136,14 +646,86 @@ impl ConnectionPool {
             .map(|(id, _)| *id)
         {
             entries.remove(&oldest_id);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
             debug!(peer = %oldest_id, "evicted oldest connection from pool (at capacity)");
         }
     }
 }
 
+/// Check that a pooled connection still reaches the peer.
+///
+/// The peer answers a `KeepAlive` by finishing its side of the stream
+/// without a reply.
+async fn probe_connection(
+    conn: &Connection,
+    version: ProtocolVersion,
+    keepalive_msg: &[u8],
+) -> ProbeResult {
+    if let Some(reason) = conn.close_reason() {
+        debug!(peer = %conn.remote_id(), "pooled connection closed: {reason}");
+        return ProbeResult::Dead;
+    }
+    if version < ProtocolVersion::V2 {
+        return ProbeResult::Alive;
+    }
+
+    let exchange = async {
+        let (mut send, mut recv) = conn.open_bi().await.map_err(|e| e.to_string())?;
+        send.write_all(&(keepalive_msg.len() as u32).to_be_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+        send.write_all(keepalive_msg)
+            .await
+            .map_err(|e| e.to_string())?;
+        send.finish().map_err(|e| e.to_string())?;
+        recv.read_to_end(0).await.map_err(|e| e.to_string())?;
+        Ok::<(), String>(())
+    };
+
+    match tokio::time::timeout(Duration::from_secs(PROBE_TIMEOUT_SECS), exchange).await {
+        Ok(Ok(())) => ProbeResult::Alive,
+        Ok(Err(e)) => {
+            debug!(peer = %conn.remote_id(), "keepalive probe failed: {e}");
+            ProbeResult::Dead
+        }
+        Err(_) => ProbeResult::Inconclusive,
+    }
+}
+
+/// Receive the next outbound message, preferring the high-priority queue.
+///
+/// Returns `None` once both queues are closed and empty.
+async fn next_outbound(
+    high: &mut mpsc::Receiver<OutboundMessage>,
+    low: &mut mpsc::Receiver<OutboundMessage>,
+) -> Option<(MessagePriority, OutboundMessage)> {
+    tokio::select! {
+        biased;
+        Some(msg) = high.recv() => Some((MessagePriority::High, msg)),
+        Some(msg) = low.recv() => Some((MessagePriority::Low, msg)),
+        else => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    use iroh::address_lookup::MemoryLookup;
+    use iroh::{RelayMode, SecretKey};
+    use rand::SeedableRng;
+
+    use crate::node::{P2pMessage, SOUNDTIME_ALPN, SUPPORTED_ALPNS};
+
+    fn outbound(tag: u8) -> OutboundMessage {
+        let (done, _) = oneshot::channel();
+        OutboundMessage {
+            bytes: vec![tag],
+            min_version: ProtocolVersion::V1,
+            done,
+        }
+    }
 
     #[test]
     fn test_pool_constants() {
@@ -157,4 +739,411 @@ mod tests {
         let now = Instant::now();
         assert!(now.elapsed().as_secs() < 1);
     }
+
+    // ── outbound priority queues ──
+
+    #[tokio::test]
+    async fn test_next_outbound_drains_high_first() {
+        let (high_tx, mut high_rx) = mpsc::channel(8);
+        let (low_tx, mut low_rx) = mpsc::channel(8);
+
+        // Low-priority messages queued before the high-priority ones
+        low_tx.send(outbound(10)).await.unwrap();
+        low_tx.send(outbound(11)).await.unwrap();
+        high_tx.send(outbound(1)).await.unwrap();
+        high_tx.send(outbound(2)).await.unwrap();
+        drop(high_tx);
+        drop(low_tx);
+
+        let mut order = Vec::new();
+        while let Some((priority, msg)) = next_outbound(&mut high_rx, &mut low_rx).await {
+            order.push((priority, msg.bytes[0]));
+        }
+        assert_eq!(
+            order,
+            vec![
+                (MessagePriority::High, 1),
+                (MessagePriority::High, 2),
+                (MessagePriority::Low, 10),
+                (MessagePriority::Low, 11)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_next_outbound_low_only() {
+        let (high_tx, mut high_rx) = mpsc::channel::<OutboundMessage>(8);
+        let (low_tx, mut low_rx) = mpsc::channel(8);
+
+        low_tx.send(outbound(7)).await.unwrap();
+        let (priority, msg) = next_outbound(&mut high_rx, &mut low_rx).await.unwrap();
+        assert_eq!(priority, MessagePriority::Low);
+        assert_eq!(msg.bytes, vec![7]);
+
+        drop(high_tx);
+        drop(low_tx);
+        assert!(next_outbound(&mut high_rx, &mut low_rx).await.is_none());
+    }
+
+    // ── keepalive ──
+
+    /// Bind a peer endpoint on localhost that answers `ping` with `pong` and
+    /// finishes any other stream without a reply, like a `KeepAlive`.
+    async fn start_peer(key: SecretKey, port: u16) -> Endpoint {
+        start_peer_with_alpns(key, port, SUPPORTED_ALPNS).await
+    }
+
+    /// Like [`start_peer`], accepting only `alpns`.
+    async fn start_peer_with_alpns(key: SecretKey, port: u16, alpns: &[&[u8]]) -> Endpoint {
+        // A closed endpoint frees its port only once its accept task has
+        // dropped the last clone, so rebinding it may take a few tries
+        let mut attempts = 0;
+        let endpoint = loop {
+            let bound = Endpoint::empty_builder(RelayMode::Disabled)
+                .secret_key(key.clone())
+                .alpns(alpns.iter().map(|alpn| alpn.to_vec()).collect())
+                .bind_addr(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port))
+                .unwrap()
+                .bind()
+                .await;
+            match bound {
+                Ok(endpoint) => break endpoint,
+                Err(_) if attempts < 50 => {
+                    attempts += 1;
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+                Err(e) => panic!("failed to bind peer endpoint: {e}"),
+            }
+        };
+        let accept = endpoint.clone();
+        tokio::spawn(async move {
+            while let Some(incoming) = accept.accept().await {
+                let Ok(conn) = incoming.await else { continue };
+                tokio::spawn(async move {
+                    while let Ok((mut send, mut recv)) = conn.accept_bi().await {
+                        let mut len_buf = [0u8; 4];
+                        if recv.read_exact(&mut len_buf).await.is_err() {
+                            break;
+                        }
+                        let len = u32::from_be_bytes(len_buf) as usize;
+                        let Ok(msg) = recv.read_to_end(len).await else {
+                            break;
+                        };
+                        if msg == b"ping" {
+                            let _ = send.write_all(b"pong").await;
+                        }
+                        let _ = send.finish();
+                    }
+                });
+            }
+        });
+        endpoint
+    }
+
+    async fn ping(pool: &ConnectionPool, peer: EndpointId) -> Result<Vec<u8>, P2pError> {
+        let conn = pool.get_connection(peer).await?;
+        let (mut send, mut recv) = conn
+            .open_bi()
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.write_all(&4u32.to_be_bytes())
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.write_all(b"ping")
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.finish()
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        recv.read_to_end(16)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))
+    }
+
+    // ── protocol negotiation ──
+
+    /// Pool offering `offered`, connected to a new peer accepting `accepted`.
+    async fn connect_pool(
+        offered: &'static [&'static [u8]],
+        accepted: &[&[u8]],
+    ) -> (
+        ConnectionPool,
+        EndpointId,
+        Result<Connection, P2pError>,
+        Endpoint,
+    ) {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let peer_id = key.public();
+        let client = Endpoint::empty_builder(RelayMode::Disabled)
+            .bind()
+            .await
+            .unwrap();
+        let pool = ConnectionPool::new(client, offered);
+        let peer = start_peer_with_alpns(key, 0, accepted).await;
+        pool.add_known_addr(peer.addr()).await;
+        let conn = pool.get_connection(peer_id).await;
+        (pool, peer_id, conn, peer)
+    }
+
+    #[tokio::test]
+    async fn test_negotiates_v2_between_current_nodes() {
+        let (pool, peer_id, conn, _peer) = connect_pool(SUPPORTED_ALPNS, SUPPORTED_ALPNS).await;
+        conn.unwrap();
+        assert_eq!(
+            pool.negotiated_version(&peer_id).await,
+            Some(ProtocolVersion::V2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_negotiates_v1_with_v1_only_peer() {
+        let (pool, peer_id, conn, _peer) = connect_pool(SUPPORTED_ALPNS, &[SOUNDTIME_ALPN]).await;
+        conn.unwrap();
+        assert_eq!(
+            pool.negotiated_version(&peer_id).await,
+            Some(ProtocolVersion::V1)
+        );
+
+        // Messages that need v2 are refused before anything is written
+        let err = pool
+            .send_now(peer_id, ProtocolVersion::V2, b"ping")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, P2pError::UnsupportedProtocol(_)));
+        pool.send_now(peer_id, ProtocolVersion::V1, b"ping")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_v1_only_node_negotiates_v1_with_current_peer() {
+        let (pool, peer_id, conn, _peer) = connect_pool(&[SOUNDTIME_ALPN], SUPPORTED_ALPNS).await;
+        conn.unwrap();
+        assert_eq!(
+            pool.negotiated_version(&peer_id).await,
+            Some(ProtocolVersion::V1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_common_alpn_fails_to_connect() {
+        let (pool, peer_id, conn, _peer) = connect_pool(SUPPORTED_ALPNS, &[b"other/1"]).await;
+        assert!(conn.is_err());
+        assert_eq!(pool.negotiated_version(&peer_id).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_keepalive_evicts_connection_to_restarted_peer() {
+        let port = std::net::UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let peer_id = key.public();
+
+        let lookup = MemoryLookup::new();
+        let client = Endpoint::empty_builder(RelayMode::Disabled)
+            .address_lookup(lookup.clone())
+            .bind()
+            .await
+            .unwrap();
+        let pool = ConnectionPool::new(client, SUPPORTED_ALPNS);
+        let keepalive = serde_json::to_vec(&P2pMessage::KeepAlive).unwrap();
+
+        let peer = start_peer(key.clone(), port).await;
+        lookup.add_endpoint_info(peer.addr());
+        assert_eq!(ping(&pool, peer_id).await.unwrap(), b"pong");
+
+        // A live v2 connection is probed and kept
+        assert_eq!(pool.keepalive(&keepalive).await, 1);
+        assert_eq!(pool.stats().open_connections, 1);
+        assert_eq!(pool.stats().failed_probes, 0);
+
+        // Restart the peer with the same key on the same port
+        peer.close().await;
+        drop(peer);
+        let _peer = start_peer(key, port).await;
+
+        pool.keepalive(&keepalive).await;
+        assert_eq!(
+            pool.stats(),
+            ConnectionPoolStats {
+                open_connections: 0,
+                evictions: 1,
+                failed_probes: 1,
+            }
+        );
+
+        // The stale entry is gone, so the first ping opens a fresh connection
+        assert_eq!(ping(&pool, peer_id).await.unwrap(), b"pong");
+        assert_eq!(pool.stats().open_connections, 1);
+    }
+
+    #[tokio::test]
+    async fn test_known_addr_connects_without_discovery() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let peer_id = key.public();
+        let client = Endpoint::empty_builder(RelayMode::Disabled)
+            .bind()
+            .await
+            .unwrap();
+        let pool = ConnectionPool::new(client, SUPPORTED_ALPNS);
+        let peer = start_peer(key, 0).await;
+
+        // A bare EndpointId cannot be reached with no discovery service
+        assert!(ping(&pool, peer_id).await.is_err());
+
+        pool.add_known_addr(peer.addr()).await;
+        assert_eq!(ping(&pool, peer_id).await.unwrap(), b"pong");
+    }
+
+    #[tokio::test]
+    async fn test_keepalive_evicts_idle_connections() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let lookup = MemoryLookup::new();
+        let client = Endpoint::empty_builder(RelayMode::Disabled)
+            .address_lookup(lookup.clone())
+            .bind()
+            .await
+            .unwrap();
+        let pool =
+            ConnectionPool::new(client, SUPPORTED_ALPNS).with_idle_ttl(Duration::from_millis(50));
+
+        let peer = start_peer(key.clone(), 0).await;
+        lookup.add_endpoint_info(peer.addr());
+        ping(&pool, key.public()).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let keepalive = serde_json::to_vec(&P2pMessage::KeepAlive).unwrap();
+        assert_eq!(pool.keepalive(&keepalive).await, 0);
+        assert_eq!(pool.stats().open_connections, 0);
+        assert_eq!(pool.stats().evictions, 1);
+        assert_eq!(pool.stats().failed_probes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_send_refuses_low_priority_when_queue_full() {
+        let endpoint = Endpoint::empty_builder(RelayMode::Disabled)
+            .bind()
+            .await
+            .unwrap();
+        let pool = Arc::new(ConnectionPool::new(endpoint, SUPPORTED_ALPNS).with_max_queue_depth(2));
+        let peer_id = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng()).public();
+        // No send task drains these queues, so the depth only grows
+        let (high, mut high_rx) = mpsc::channel(8);
+        let (low, _low_rx) = mpsc::channel(8);
+        let depth = Arc::new(AtomicUsize::new(2));
+        pool.senders
+            .lock()
+            .await
+            .insert(peer_id, PeerSender { high, low, depth });
+
+        let result = pool
+            .send(peer_id, MessagePriority::Low, ProtocolVersion::V1, vec![1])
+            .await;
+        assert!(matches!(result, Err(P2pError::QueueFull(_))));
+        assert_eq!(pool.queue_depths().await[&peer_id.to_string()], 2);
+
+        // Control traffic still gets through
+        let sending = tokio::spawn({
+            let pool = Arc::clone(&pool);
+            async move {
+                pool.send(peer_id, MessagePriority::High, ProtocolVersion::V1, vec![2])
+                    .await
+            }
+        });
+        let msg = high_rx.recv().await.unwrap();
+        assert_eq!(msg.bytes, vec![2]);
+        assert_eq!(pool.queue_depths().await[&peer_id.to_string()], 3);
+        msg.done.send(Ok(())).unwrap();
+        sending.await.unwrap().unwrap();
+    }
+
+    /// Bind a peer endpoint that reads messages longer than one byte only
+    /// after `delay`, and handles each stream on its own. A message larger
+    /// than the stream's flow-control window cannot be written until then.
+    async fn start_slow_peer(key: SecretKey, delay: Duration) -> Endpoint {
+        let endpoint = Endpoint::empty_builder(RelayMode::Disabled)
+            .secret_key(key)
+            .alpns(SUPPORTED_ALPNS.iter().map(|alpn| alpn.to_vec()).collect())
+            .bind()
+            .await
+            .unwrap();
+        let accept = endpoint.clone();
+        tokio::spawn(async move {
+            while let Some(incoming) = accept.accept().await {
+                let Ok(conn) = incoming.await else { continue };
+                tokio::spawn(async move {
+                    while let Ok((mut send, mut recv)) = conn.accept_bi().await {
+                        tokio::spawn(async move {
+                            let mut len_buf = [0u8; 4];
+                            if recv.read_exact(&mut len_buf).await.is_err() {
+                                return;
+                            }
+                            let len = u32::from_be_bytes(len_buf) as usize;
+                            if len > 1 {
+                                tokio::time::sleep(delay).await;
+                            }
+                            let _ = recv.read_to_end(len).await;
+                            let _ = send.finish();
+                        });
+                    }
+                });
+            }
+        });
+        endpoint
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_sent_while_low_priority_in_flight() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let peer_id = key.public();
+        let peer = start_slow_peer(key, Duration::from_secs(3)).await;
+        let client = Endpoint::empty_builder(RelayMode::Disabled)
+            .bind()
+            .await
+            .unwrap();
+        let pool = Arc::new(ConnectionPool::new(client, SUPPORTED_ALPNS));
+        pool.add_known_addr(peer.addr()).await;
+
+        let bulk = tokio::spawn({
+            let pool = Arc::clone(&pool);
+            async move {
+                let page = vec![0; 16 * 1024 * 1024];
+                pool.send(peer_id, MessagePriority::Low, ProtocolVersion::V1, page)
+                    .await
+            }
+        });
+        // Let the send task pick up the catalog page first
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let started = Instant::now();
+        pool.send(peer_id, MessagePriority::High, ProtocolVersion::V1, vec![1])
+            .await
+            .unwrap();
+        assert!(
+            started.elapsed() < Duration::from_secs(2),
+            "high-priority message waited {:?} behind the low-priority one",
+            started.elapsed()
+        );
+        assert!(!bulk.is_finished());
+        bulk.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_peer_sender_for_priority() {
+        let (high, _high_rx) = mpsc::channel(1);
+        let (low, _low_rx) = mpsc::channel(1);
+        let sender = PeerSender {
+            high,
+            low,
+            depth: Arc::new(AtomicUsize::new(0)),
+        };
+        assert!(sender
+            .for_priority(MessagePriority::High)
+            .same_channel(&sender.high));
+        assert!(sender
+            .for_priority(MessagePriority::Low)
+            .same_channel(&sender.low));
+    }
 }
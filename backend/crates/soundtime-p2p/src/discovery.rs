@@ -4,15 +4,16 @@
 //! Peers announce themselves via ping/pong and track announcements.
 //! Future: integrate with iroh's built-in DNS/Pkarr discovery or DHT.
 
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
 use iroh::{EndpointAddr, EndpointId};
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use tracing::{debug, info, warn};
 
 use crate::error::P2pError;
-use crate::node::{P2pMessage, P2pNode};
+use crate::node::{P2pMessage, P2pNode, ProtocolVersion};
 
 /// Information about a known peer.
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -30,37 +31,427 @@ pub struct PeerInfo {
     pub last_seen: chrono::DateTime<chrono::Utc>,
     /// Whether the peer responded to our last ping
     pub is_online: bool,
+    /// P2P protocol version negotiated with the peer via ALPN (1, 2, …);
+    /// `None` until a connection has been established this run
+    #[serde(default)]
+    pub protocol_version: Option<u8>,
+    /// Round-trip time of our last Ping to this peer, in milliseconds;
+    /// `None` until we have pinged it this run
+    #[serde(default)]
+    pub rtt_ms: Option<u32>,
+    /// Our last [`MAX_RTT_SAMPLES`] ping round-trip times, oldest first
+    #[serde(skip)]
+    pub rtt_samples: VecDeque<u32>,
+    /// Median of `rtt_samples` (restored from the database until the peer
+    /// is pinged again); `None` if never measured
+    #[serde(default)]
+    pub p50_rtt_ms: Option<u32>,
+    /// Outcomes of our last [`MAX_PEER_INTERACTIONS`] pings and fetches
+    /// with the peer, oldest first
+    #[serde(skip)]
+    pub interactions: VecDeque<PeerInteraction>,
+    /// Mean ping RTT over `interactions` (restored from the database until
+    /// the peer is pinged again); `None` if never measured
+    #[serde(default)]
+    pub avg_latency_ms: Option<u32>,
+    /// Share of `interactions` that succeeded, from 0 to 1 (restored from
+    /// the database until we next interact); `None` if there were none
+    #[serde(default)]
+    pub success_rate: Option<f32>,
+    /// When this peer last received our complete catalog; later syncs only
+    /// send tracks created after this time. `None` until a full sync succeeds.
+    #[serde(default)]
+    pub last_catalog_sync_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Optional features the peer advertised in its last Pong
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// When the peer last announced it was shutting down (`Goodbye`);
+    /// cleared as soon as we hear from it again
+    #[serde(default)]
+    pub departed_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Pings or sends that failed in a row since we last heard from the
+    /// peer; see [`PeerRegistry::evict_dead_peers`]
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    /// Nickname set by our admin (unlike `name`, which the peer chooses)
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Free-text admin notes about the peer
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// How much our admin trusts the peer
+    #[serde(default)]
+    pub trust: PeerTrust,
+}
+
+impl PeerInfo {
+    /// Add a ping or fetch outcome to the rolling window and recompute
+    /// `avg_latency_ms` and `success_rate` from it.
+    pub fn record_interaction(&mut self, interaction: PeerInteraction) {
+        if self.interactions.len() == MAX_PEER_INTERACTIONS {
+            self.interactions.pop_front();
+        }
+        self.interactions.push_back(interaction);
+        self.avg_latency_ms = average_latency(&self.interactions).or(self.avg_latency_ms);
+        self.success_rate = success_rate(&self.interactions);
+    }
+
+    /// Whether the peer advertised the given capability.
+    pub fn has_capability(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+
+    /// Whether the peer is quarantined by our admin.
+    pub fn is_quarantined(&self) -> bool {
+        self.trust == PeerTrust::Quarantined
+    }
+}
+
+/// Trust tier an admin assigns to a peer (`p2p_peers.trust`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PeerTrust {
+    /// Preferred when picking which peer to fetch a track from
+    Trusted,
+    #[default]
+    Normal,
+    /// Still pinged, but never sent our catalog and left out of peer
+    /// exchange in both directions
+    Quarantined,
+}
+
+impl PeerTrust {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PeerTrust::Trusted => "trusted",
+            PeerTrust::Normal => "normal",
+            PeerTrust::Quarantined => "quarantined",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "trusted" => Some(PeerTrust::Trusted),
+            "normal" => Some(PeerTrust::Normal),
+            "quarantined" => Some(PeerTrust::Quarantined),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for PeerTrust {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Ping round-trip times kept per peer for its median latency.
+pub const MAX_RTT_SAMPLES: usize = 20;
+
+/// Pings and fetches kept per peer for its latency and reliability history.
+pub const MAX_PEER_INTERACTIONS: usize = 50;
+
+/// One ping or fetch with a peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeerInteraction {
+    pub succeeded: bool,
+    /// Round-trip time, for pings
+    pub latency_ms: Option<u32>,
+}
+
+impl PeerInteraction {
+    /// A ping answered after `rtt_ms`.
+    pub fn ping(rtt_ms: u32) -> Self {
+        Self {
+            succeeded: true,
+            latency_ms: Some(rtt_ms),
+        }
+    }
+
+    /// A blob fetch, successful or not.
+    pub fn fetch(succeeded: bool) -> Self {
+        Self {
+            succeeded,
+            latency_ms: None,
+        }
+    }
+}
+
+/// Mean latency of the interactions that measured one, or `None` if none did.
+pub fn average_latency(interactions: &VecDeque<PeerInteraction>) -> Option<u32> {
+    let latencies: Vec<u64> = interactions
+        .iter()
+        .filter_map(|i| i.latency_ms.map(u64::from))
+        .collect();
+    if latencies.is_empty() {
+        return None;
+    }
+    Some((latencies.iter().sum::<u64>() / latencies.len() as u64) as u32)
+}
+
+/// Share of successful interactions, or `None` if there are none.
+pub fn success_rate(interactions: &VecDeque<PeerInteraction>) -> Option<f32> {
+    if interactions.is_empty() {
+        return None;
+    }
+    let succeeded = interactions.iter().filter(|i| i.succeeded).count();
+    Some(succeeded as f32 / interactions.len() as f32)
+}
+
+/// Median of the RTT samples (the lower one of the middle two for an even
+/// count), or `None` if there are none.
+pub fn median_rtt(samples: &VecDeque<u32>) -> Option<u32> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut sorted: Vec<u32> = samples.iter().copied().collect();
+    sorted.sort_unstable();
+    Some(sorted[(sorted.len() - 1) / 2])
+}
+
+/// Order peer IDs by median RTT, fastest first; peers never measured keep
+/// their relative order after all measured ones.
+pub fn order_by_latency(peers: &mut [String], p50_rtts: &HashMap<String, u32>) {
+    peers.sort_by_key(|id| match p50_rtts.get(id) {
+        Some(rtt) => (false, *rtt),
+        None => (true, 0),
+    });
+}
+
+/// Whether `peer` has been offline since before `cutoff` and may be
+/// forgotten. Seed peers and trusted peers are always kept.
+pub fn is_prunable(
+    peer: &PeerInfo,
+    cutoff: chrono::DateTime<chrono::Utc>,
+    seeds: &HashSet<String>,
+) -> bool {
+    !peer.is_online
+        && peer.last_seen < cutoff
+        && peer.trust != PeerTrust::Trusted
+        && !seeds.contains(&peer.node_id)
+}
+
+/// Whether we ever completed a ping or fetch with `peer`. Node identities
+/// cost nothing to generate, so only a verified peer is known to be a real
+/// node worth keeping.
+pub fn is_verified(peer: &PeerInfo) -> bool {
+    peer.p50_rtt_ms.is_some() || peer.success_rate.is_some_and(|rate| rate > 0.0)
+}
+
+/// Whether `peer` may be forgotten to make room for a new peer when the
+/// registry is full: only unverified offline peers and quarantined peers,
+/// never seed peers or trusted peers.
+pub fn is_displaceable(peer: &PeerInfo, seeds: &HashSet<String>) -> bool {
+    ((!peer.is_online && !is_verified(peer)) || peer.is_quarantined())
+        && peer.trust != PeerTrust::Trusted
+        && !seeds.contains(&peer.node_id)
+}
+
+/// The peer to forget first to make room for a new one: offline peers
+/// before quarantined online ones, then the one unseen for longest. `None`
+/// if no peer is [displaceable](is_displaceable).
+pub fn eviction_candidate<'a>(
+    peers: impl IntoIterator<Item = &'a PeerInfo>,
+    seeds: &HashSet<String>,
+) -> Option<&'a PeerInfo> {
+    peers
+        .into_iter()
+        .filter(|p| is_displaceable(p, seeds))
+        .min_by_key(|p| (p.is_online, !p.is_quarantined(), p.last_seen))
+}
+
+/// How our catalog should be pushed to a peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CatalogSyncPlan {
+    /// Never synced — send every track via `CatalogSync`
+    Full,
+    /// Send only tracks created after `since` via `CatalogDelta`
+    Delta {
+        since: chrono::DateTime<chrono::Utc>,
+    },
+}
+
+/// Most recent evictions kept for `GET /api/admin/p2p/evicted-peers`.
+pub const MAX_EVICTION_LOG: usize = 100;
+
+/// A peer removed by [`PeerRegistry::evict_dead_peers`], as listed to admins.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct PeerEviction {
+    pub node_id: String,
+    pub name: Option<String>,
+    pub consecutive_failures: u32,
+    pub last_seen: chrono::DateTime<chrono::Utc>,
+    pub evicted_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Peer status updates buffered per subscriber before a slow one starts
+/// missing updates.
+pub const PEER_STATUS_CHANNEL_CAPACITY: usize = 256;
+
+/// What a peer status subscriber is told about a peer.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct PeerStatus {
+    pub node_id: String,
+    pub track_count: u64,
+    pub rtt_ms: Option<u32>,
+    pub version: Option<String>,
+}
+
+impl From<&PeerInfo> for PeerStatus {
+    fn from(info: &PeerInfo) -> Self {
+        Self {
+            node_id: info.node_id.clone(),
+            track_count: info.track_count,
+            rtt_ms: info.rtt_ms,
+            version: info.version.clone(),
+        }
+    }
+}
+
+/// A peer coming online, changing what it reports, or going offline.
+/// Serialized with a `type` field (`peer_online` or `peer_offline`).
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PeerStatusUpdate {
+    PeerOnline(PeerStatus),
+    PeerOffline(PeerStatus),
 }
 
 /// Manages the set of known peers and handles discovery.
 pub struct PeerRegistry {
     /// Known peers, keyed by EndpointId string
     peers: RwLock<HashMap<String, PeerInfo>>,
+    /// Latest evictions, newest first
+    evictions: Mutex<VecDeque<PeerEviction>>,
+    /// Peer status changes, for live dashboards
+    status_tx: broadcast::Sender<PeerStatusUpdate>,
+    /// Most peers kept before new ones must displace old ones (0 = no limit)
+    max_peers: AtomicUsize,
+    /// Seed peers: always admitted, never displaced
+    seeds: Mutex<HashSet<String>>,
+    /// Peers displaced by new ones since the last
+    /// [`take_displaced_peers`](Self::take_displaced_peers)
+    displaced: Mutex<Vec<String>>,
 }
 
 impl PeerRegistry {
     /// Create a new empty peer registry.
     pub fn new() -> Self {
+        let (status_tx, _) = broadcast::channel(PEER_STATUS_CHANNEL_CAPACITY);
         Self {
             peers: RwLock::new(HashMap::new()),
+            evictions: Mutex::new(VecDeque::new()),
+            status_tx,
+            max_peers: AtomicUsize::new(0),
+            seeds: Mutex::new(HashSet::new()),
+            displaced: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Limit the registry to `max_peers` peers (0 = no limit). Peers already
+    /// known are kept; the limit applies when new peers are added.
+    pub fn set_max_peers(&self, max_peers: usize) {
+        self.max_peers.store(max_peers, Ordering::Relaxed);
+    }
+
+    /// Mark `node_id` as a seed peer, which the peer limit never turns away
+    /// or displaces.
+    pub fn add_seed(&self, node_id: &str) {
+        let mut seeds = self.seeds.lock().unwrap_or_else(|e| e.into_inner());
+        seeds.insert(node_id.to_string());
+    }
+
+    /// Whether `node_id` was marked as a seed peer.
+    pub fn is_seed(&self, node_id: &str) -> bool {
+        let seeds = self.seeds.lock().unwrap_or_else(|e| e.into_inner());
+        seeds.contains(node_id)
+    }
+
+    /// How many new peers can still be added, counting peers they would
+    /// displace; `None` if there is no limit.
+    pub async fn admission_capacity(&self) -> Option<usize> {
+        let max = self.max_peers.load(Ordering::Relaxed);
+        if max == 0 {
+            return None;
+        }
+        let peers = self.peers.read().await;
+        let seeds = self.seeds.lock().unwrap_or_else(|e| e.into_inner());
+        let displaceable = peers
+            .values()
+            .filter(|p| is_displaceable(p, &seeds))
+            .count();
+        Some(max.saturating_sub(peers.len()) + displaceable)
+    }
+
+    /// Peers displaced to make room for new ones since the last call, so
+    /// their database rows and search index entries can be removed.
+    pub fn take_displaced_peers(&self) -> Vec<String> {
+        let mut displaced = self.displaced.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut *displaced)
+    }
+
+    /// Internal: make room for the new peer `node_id` if the registry is
+    /// full, by displacing the [`eviction_candidate`]. Returns `false` if
+    /// the peer must be turned away; seed peers are admitted even when
+    /// nothing can be displaced.
+    fn admit(&self, peers: &mut HashMap<String, PeerInfo>, node_id: &str) -> bool {
+        let max = self.max_peers.load(Ordering::Relaxed);
+        if max == 0 || peers.len() < max {
+            return true;
         }
+        let seeds = self.seeds.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(victim) = eviction_candidate(peers.values(), &seeds).map(|p| p.node_id.clone())
+        else {
+            return seeds.contains(node_id);
+        };
+        if let Some(peer) = peers.remove(&victim) {
+            info!(
+                peer = %victim,
+                last_seen = %peer.last_seen,
+                new_peer = %node_id,
+                "peer limit of {max} reached, displacing peer"
+            );
+            let mut displaced = self.displaced.lock().unwrap_or_else(|e| e.into_inner());
+            displaced.push(victim);
+        }
+        true
+    }
+
+    /// Receive a [`PeerStatusUpdate`] whenever a peer comes online, reports
+    /// a different track count or version, or goes offline.
+    pub fn subscribe_status(&self) -> broadcast::Receiver<PeerStatusUpdate> {
+        self.status_tx.subscribe()
     }
 
-    /// Register or update a peer.
-    pub async fn upsert_peer(&self, node_id: &str, name: Option<String>, track_count: u64) {
+    /// Internal: tell status subscribers, if there are any.
+    fn publish_status(&self, update: PeerStatusUpdate) {
+        let _ = self.status_tx.send(update);
+    }
+
+    /// Register or update a peer. Returns `false` if it is new and the
+    /// peer limit turned it away (see [`set_max_peers`](Self::set_max_peers)).
+    pub async fn upsert_peer(&self, node_id: &str, name: Option<String>, track_count: u64) -> bool {
         self.upsert_peer_versioned(node_id, name, track_count, None)
-            .await;
+            .await
     }
 
-    /// Register or update a peer with version information.
+    /// Register or update a peer with version information. Returns `false`
+    /// if it is new and the peer limit turned it away.
     pub async fn upsert_peer_versioned(
         &self,
         node_id: &str,
         name: Option<String>,
         track_count: u64,
         version: Option<String>,
-    ) {
+    ) -> bool {
         let mut peers = self.peers.write().await;
+        if !peers.contains_key(node_id) && !self.admit(&mut peers, node_id) {
+            debug!(%node_id, "peer limit reached, not adding peer");
+            return false;
+        }
+        let before = peers
+            .get(node_id)
+            .map(|p| (p.is_online, p.track_count, p.version.clone()));
         let info = peers
             .entry(node_id.to_string())
             .or_insert_with(|| PeerInfo {
@@ -70,9 +461,25 @@ impl PeerRegistry {
                 track_count: 0,
                 last_seen: chrono::Utc::now(),
                 is_online: true,
+                protocol_version: None,
+                rtt_ms: None,
+                rtt_samples: VecDeque::new(),
+                p50_rtt_ms: None,
+                interactions: VecDeque::new(),
+                avg_latency_ms: None,
+                success_rate: None,
+                last_catalog_sync_at: None,
+                capabilities: Vec::new(),
+                departed_at: None,
+                consecutive_failures: 0,
+                label: None,
+                notes: None,
+                trust: PeerTrust::Normal,
             });
         info.last_seen = chrono::Utc::now();
         info.is_online = true;
+        info.departed_at = None;
+        info.consecutive_failures = 0;
         info.track_count = track_count;
         if name.is_some() {
             info.name = name;
@@ -80,17 +487,179 @@ impl PeerRegistry {
         if version.is_some() {
             info.version = version;
         }
+        // Announcements upsert their sender too, so only report changes
+        if before != Some((true, info.track_count, info.version.clone())) {
+            self.publish_status(PeerStatusUpdate::PeerOnline(PeerStatus::from(&*info)));
+        }
         debug!(%node_id, "peer updated in registry");
+        true
+    }
+
+    /// Record the protocol version negotiated with a known peer.
+    pub async fn set_protocol_version(&self, node_id: &str, version: ProtocolVersion) {
+        let mut peers = self.peers.write().await;
+        if let Some(info) = peers.get_mut(node_id) {
+            info.protocol_version = Some(version.as_u8());
+        }
+    }
+
+    /// Record the capabilities a known peer advertised in its Pong.
+    pub async fn set_capabilities(&self, node_id: &str, capabilities: Vec<String>) {
+        let mut peers = self.peers.write().await;
+        if let Some(info) = peers.get_mut(node_id) {
+            info.capabilities = capabilities;
+        }
+    }
+
+    /// Record the round-trip time of a Ping/Pong exchange with `node_id`
+    /// and update its median over the last [`MAX_RTT_SAMPLES`] pings.
+    pub async fn record_rtt(&self, node_id: &str, rtt_ms: u32) {
+        let mut peers = self.peers.write().await;
+        if let Some(info) = peers.get_mut(node_id) {
+            info.rtt_ms = Some(rtt_ms);
+            if info.rtt_samples.len() == MAX_RTT_SAMPLES {
+                info.rtt_samples.pop_front();
+            }
+            info.rtt_samples.push_back(rtt_ms);
+            info.p50_rtt_ms = median_rtt(&info.rtt_samples);
+            info.record_interaction(PeerInteraction::ping(rtt_ms));
+        }
+    }
+
+    /// Record whether a blob fetch from `node_id` succeeded.
+    pub async fn record_fetch(&self, node_id: &str, succeeded: bool) {
+        let mut peers = self.peers.write().await;
+        if let Some(info) = peers.get_mut(node_id) {
+            info.record_interaction(PeerInteraction::fetch(succeeded));
+        }
+    }
+
+    /// Median RTT of every peer that has one, keyed by node ID.
+    pub async fn p50_rtts(&self) -> HashMap<String, u32> {
+        let peers = self.peers.read().await;
+        peers
+            .values()
+            .filter_map(|p| p.p50_rtt_ms.map(|rtt| (p.node_id.clone(), rtt)))
+            .collect()
+    }
+
+    /// Record that `node_id` received our catalog as of `at`.
+    pub async fn mark_catalog_synced(&self, node_id: &str, at: chrono::DateTime<chrono::Utc>) {
+        let mut peers = self.peers.write().await;
+        if let Some(info) = peers.get_mut(node_id) {
+            info.last_catalog_sync_at = Some(at);
+        }
+    }
+
+    /// Decide whether `node_id` needs our full catalog or only a delta.
+    pub async fn catalog_sync_plan(&self, node_id: &str) -> CatalogSyncPlan {
+        let peers = self.peers.read().await;
+        match peers.get(node_id).and_then(|p| p.last_catalog_sync_at) {
+            Some(since) => CatalogSyncPlan::Delta { since },
+            None => CatalogSyncPlan::Full,
+        }
     }
 
-    /// Mark a peer as offline.
+    /// Mark a peer as offline after a failed ping or send.
     pub async fn mark_offline(&self, node_id: &str) {
         let mut peers = self.peers.write().await;
         if let Some(info) = peers.get_mut(node_id) {
+            let was_online = info.is_online;
+            info.is_online = false;
+            info.consecutive_failures = info.consecutive_failures.saturating_add(1);
+            if was_online {
+                self.publish_status(PeerStatusUpdate::PeerOffline(PeerStatus::from(&*info)));
+            }
+        }
+    }
+
+    /// Remove peers that failed `threshold` or more times in a row and return
+    /// their IDs (`threshold` 0 disables eviction). An evicted peer is
+    /// re-added if it is learned again through peer exchange or seed peers.
+    pub async fn evict_dead_peers(&self, threshold: u32) -> Vec<String> {
+        if threshold == 0 {
+            return Vec::new();
+        }
+        let mut peers = self.peers.write().await;
+        let dead: Vec<PeerInfo> = peers
+            .values()
+            .filter(|p| p.consecutive_failures >= threshold)
+            .cloned()
+            .collect();
+        if dead.is_empty() {
+            return Vec::new();
+        }
+
+        let now = chrono::Utc::now();
+        let mut evictions = self.evictions.lock().unwrap_or_else(|e| e.into_inner());
+        let mut evicted = Vec::with_capacity(dead.len());
+        for peer in dead {
+            peers.remove(&peer.node_id);
+            info!(
+                "evicted dead peer {} after {} consecutive failures",
+                peer.node_id, peer.consecutive_failures
+            );
+            if evictions.len() >= MAX_EVICTION_LOG {
+                evictions.pop_back();
+            }
+            evictions.push_front(PeerEviction {
+                node_id: peer.node_id.clone(),
+                name: peer.name,
+                consecutive_failures: peer.consecutive_failures,
+                last_seen: peer.last_seen,
+                evicted_at: now,
+            });
+            evicted.push(peer.node_id);
+        }
+        evicted
+    }
+
+    /// Remove offline peers last seen before `cutoff`, unless they are in
+    /// `seeds` or trusted, and return them.
+    pub async fn prune_offline_peers(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+        seeds: &HashSet<String>,
+    ) -> Vec<PeerInfo> {
+        let mut peers = self.peers.write().await;
+        let stale: Vec<String> = peers
+            .values()
+            .filter(|p| is_prunable(p, cutoff, seeds))
+            .map(|p| p.node_id.clone())
+            .collect();
+        stale
+            .iter()
+            .filter_map(|node_id| peers.remove(node_id))
+            .collect()
+    }
+
+    /// Most recent evictions, newest first.
+    pub fn recent_evictions(&self) -> Vec<PeerEviction> {
+        let evictions = self.evictions.lock().unwrap_or_else(|e| e.into_inner());
+        evictions.iter().cloned().collect()
+    }
+
+    /// Mark a peer offline because it told us it is shutting down. Unlike a
+    /// failed ping this is certain, so fetches and searches skip the peer
+    /// until it is heard from again (see [`has_departed`](Self::has_departed)).
+    pub async fn mark_departed(&self, node_id: &str) {
+        let mut peers = self.peers.write().await;
+        if let Some(info) = peers.get_mut(node_id) {
+            let was_online = info.is_online;
             info.is_online = false;
+            info.departed_at = Some(chrono::Utc::now());
+            if was_online {
+                self.publish_status(PeerStatusUpdate::PeerOffline(PeerStatus::from(&*info)));
+            }
         }
     }
 
+    /// Whether the peer said `Goodbye` and has not been heard from since.
+    pub async fn has_departed(&self, node_id: &str) -> bool {
+        let peers = self.peers.read().await;
+        peers.get(node_id).is_some_and(|p| p.departed_at.is_some())
+    }
+
     /// Remove a peer from the registry.
     pub async fn remove_peer(&self, node_id: &str) {
         let mut peers = self.peers.write().await;
@@ -109,6 +678,21 @@ impl PeerRegistry {
         peers.values().filter(|p| p.is_online).cloned().collect()
     }
 
+    /// Online peers not heard from for longer than `max_silence` before
+    /// `now`.
+    pub async fn quiet_peers(
+        &self,
+        max_silence: chrono::Duration,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<PeerInfo> {
+        let peers = self.peers.read().await;
+        peers
+            .values()
+            .filter(|p| p.is_online && now - p.last_seen > max_silence)
+            .cloned()
+            .collect()
+    }
+
     /// Get a specific peer by node_id.
     pub async fn get_peer(&self, node_id: &str) -> Option<PeerInfo> {
         let peers = self.peers.read().await;
@@ -121,13 +705,46 @@ impl PeerRegistry {
         peers.len()
     }
 
+    /// Set the admin's label, notes and trust tier for a known peer. Returns
+    /// the updated peer, or `None` if it is unknown.
+    pub async fn set_peer_details(
+        &self,
+        node_id: &str,
+        label: Option<String>,
+        notes: Option<String>,
+        trust: PeerTrust,
+    ) -> Option<PeerInfo> {
+        let mut peers = self.peers.write().await;
+        let info = peers.get_mut(node_id)?;
+        info.label = label;
+        info.notes = notes;
+        info.trust = trust;
+        Some(info.clone())
+    }
+
+    /// Whether `node_id` is a known, quarantined peer.
+    pub async fn is_quarantined(&self, node_id: &str) -> bool {
+        let peers = self.peers.read().await;
+        peers.get(node_id).is_some_and(PeerInfo::is_quarantined)
+    }
+
+    /// IDs of the known peers we may tell other peers about in a
+    /// `PeerExchange`: every peer except quarantined ones.
+    pub async fn pex_shareable_peers(&self) -> Vec<String> {
+        let peers = self.peers.read().await;
+        peers
+            .values()
+            .filter(|p| !p.is_quarantined())
+            .map(|p| p.node_id.clone())
+            .collect()
+    }
+
     /// Persist the entire peer registry to the database.
     ///
     /// Uses upsert (INSERT … ON CONFLICT UPDATE) so it is safe to call
     /// repeatedly.  Runs inside a single transaction for atomicity.
     pub async fn save_to_db(&self, db: &sea_orm::DatabaseConnection) -> Result<(), P2pError> {
-        use sea_orm::{EntityTrait, Set, TransactionTrait};
-        use soundtime_db::entities::p2p_peer;
+        use sea_orm::TransactionTrait;
 
         let peers = self.peers.read().await;
         let txn = db
@@ -136,31 +753,7 @@ impl PeerRegistry {
             .map_err(|e| P2pError::Connection(format!("failed to begin transaction: {e}")))?;
 
         for info in peers.values() {
-            let model = p2p_peer::ActiveModel {
-                node_id: Set(info.node_id.clone()),
-                name: Set(info.name.clone()),
-                version: Set(info.version.clone()),
-                track_count: Set(info.track_count as i64),
-                is_online: Set(info.is_online),
-                last_seen_at: Set(info.last_seen.into()),
-                created_at: Set(chrono::Utc::now().into()),
-            };
-            // Insert or update on conflict (node_id is the PK)
-            p2p_peer::Entity::insert(model)
-                .on_conflict(
-                    sea_orm::sea_query::OnConflict::column(p2p_peer::Column::NodeId)
-                        .update_columns([
-                            p2p_peer::Column::Name,
-                            p2p_peer::Column::Version,
-                            p2p_peer::Column::TrackCount,
-                            p2p_peer::Column::IsOnline,
-                            p2p_peer::Column::LastSeenAt,
-                        ])
-                        .to_owned(),
-                )
-                .exec(&txn)
-                .await
-                .map_err(|e| P2pError::Connection(format!("failed to upsert peer: {e}")))?;
+            upsert_peer_row(&txn, info).await?;
         }
 
         txn.commit()
@@ -171,6 +764,37 @@ impl PeerRegistry {
         Ok(())
     }
 
+    /// Persist a single peer, e.g. right after the admin edits it.
+    pub async fn save_peer_to_db(
+        &self,
+        db: &sea_orm::DatabaseConnection,
+        node_id: &str,
+    ) -> Result<(), P2pError> {
+        let Some(info) = self.get_peer(node_id).await else {
+            return Ok(());
+        };
+        upsert_peer_row(db, &info).await
+    }
+
+    /// Delete peers from the `p2p_peers` table, e.g. after eviction.
+    pub async fn delete_from_db(
+        db: &sea_orm::DatabaseConnection,
+        node_ids: &[String],
+    ) -> Result<u64, P2pError> {
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+        use soundtime_db::entities::p2p_peer;
+
+        if node_ids.is_empty() {
+            return Ok(0);
+        }
+        let res = p2p_peer::Entity::delete_many()
+            .filter(p2p_peer::Column::NodeId.is_in(node_ids.iter().cloned()))
+            .exec(db)
+            .await
+            .map_err(|e| P2pError::Connection(format!("failed to delete peers: {e}")))?;
+        Ok(res.rows_affected)
+    }
+
     /// Load peers from the database (used at startup to restore known peers).
     /// All loaded peers are marked offline until pinged.
     pub async fn load_from_db(&self, db: &sea_orm::DatabaseConnection) -> Result<usize, P2pError> {
@@ -185,14 +809,7 @@ impl PeerRegistry {
         let count = rows.len();
         let mut peers = self.peers.write().await;
         for row in rows {
-            let info = PeerInfo {
-                node_id: row.node_id,
-                name: row.name,
-                version: row.version,
-                track_count: row.track_count as u64,
-                last_seen: row.last_seen_at.into(),
-                is_online: false, // mark offline until we ping
-            };
+            let info = peer_from_row(row);
             peers.insert(info.node_id.clone(), info);
         }
 
@@ -207,6 +824,97 @@ impl Default for PeerRegistry {
     }
 }
 
+/// Row written to `p2p_peers` for a registry entry.
+fn peer_active_model(info: &PeerInfo) -> soundtime_db::entities::p2p_peer::ActiveModel {
+    use sea_orm::Set;
+    use soundtime_db::entities::p2p_peer;
+
+    p2p_peer::ActiveModel {
+        node_id: Set(info.node_id.clone()),
+        name: Set(info.name.clone()),
+        version: Set(info.version.clone()),
+        track_count: Set(info.track_count as i64),
+        is_online: Set(info.is_online),
+        last_seen_at: Set(info.last_seen.into()),
+        created_at: Set(chrono::Utc::now().into()),
+        last_catalog_sync_at: Set(info.last_catalog_sync_at.map(Into::into)),
+        capabilities: Set(Some(serde_json::json!(info.capabilities))),
+        p50_rtt_ms: Set(info.p50_rtt_ms.map(|v| v.min(i32::MAX as u32) as i32)),
+        avg_latency_ms: Set(info.avg_latency_ms.map(|v| v.min(i32::MAX as u32) as i32)),
+        success_rate: Set(info.success_rate),
+        // Only changed by the admin: left out of the upsert's updated
+        // columns, so this only applies to new rows
+        trusted_moderator: Set(false),
+        label: Set(info.label.clone()),
+        notes: Set(info.notes.clone()),
+        trust: Set(info.trust.as_str().to_string()),
+    }
+}
+
+/// Registry entry restored from a `p2p_peers` row. The peer is marked
+/// offline until we ping it; an unknown trust value falls back to normal.
+fn peer_from_row(row: soundtime_db::entities::p2p_peer::Model) -> PeerInfo {
+    PeerInfo {
+        node_id: row.node_id,
+        name: row.name,
+        version: row.version,
+        track_count: row.track_count as u64,
+        last_seen: row.last_seen_at.into(),
+        is_online: false,
+        protocol_version: None,
+        rtt_ms: None,
+        rtt_samples: VecDeque::new(),
+        p50_rtt_ms: row.p50_rtt_ms.and_then(|v| u32::try_from(v).ok()),
+        interactions: VecDeque::new(),
+        avg_latency_ms: row.avg_latency_ms.and_then(|v| u32::try_from(v).ok()),
+        success_rate: row.success_rate,
+        last_catalog_sync_at: row.last_catalog_sync_at.map(Into::into),
+        capabilities: row
+            .capabilities
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default(),
+        departed_at: None,
+        consecutive_failures: 0,
+        label: row.label,
+        notes: row.notes,
+        trust: PeerTrust::parse(&row.trust).unwrap_or_default(),
+    }
+}
+
+/// Insert or update a peer's row (node_id is the PK).
+async fn upsert_peer_row<C: sea_orm::ConnectionTrait>(
+    db: &C,
+    info: &PeerInfo,
+) -> Result<(), P2pError> {
+    use sea_orm::EntityTrait;
+    use soundtime_db::entities::p2p_peer;
+
+    p2p_peer::Entity::insert(peer_active_model(info))
+        .on_conflict(
+            sea_orm::sea_query::OnConflict::column(p2p_peer::Column::NodeId)
+                .update_columns([
+                    p2p_peer::Column::Name,
+                    p2p_peer::Column::Version,
+                    p2p_peer::Column::TrackCount,
+                    p2p_peer::Column::IsOnline,
+                    p2p_peer::Column::LastSeenAt,
+                    p2p_peer::Column::LastCatalogSyncAt,
+                    p2p_peer::Column::Capabilities,
+                    p2p_peer::Column::P50RttMs,
+                    p2p_peer::Column::AvgLatencyMs,
+                    p2p_peer::Column::SuccessRate,
+                    p2p_peer::Column::Label,
+                    p2p_peer::Column::Notes,
+                    p2p_peer::Column::Trust,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await
+        .map_err(|e| P2pError::Connection(format!("failed to upsert peer: {e}")))?;
+    Ok(())
+}
+
 /// Manually add a peer by its EndpointAddr and ping it.
 /// Returns the PeerInfo if the peer responds.
 pub async fn add_and_ping_peer(
@@ -222,10 +930,12 @@ pub async fn add_and_ping_peer(
             node_id,
             track_count,
             version,
+            capabilities,
         }) => {
             registry
                 .upsert_peer_versioned(&node_id, None, track_count, version)
                 .await;
+            registry.set_capabilities(&node_id, capabilities).await;
             let info = match registry.get_peer(&node_id).await {
                 Some(info) => info,
                 None => {
@@ -262,20 +972,60 @@ pub async fn refresh_all_peers(node: &Arc<P2pNode>, registry: &PeerRegistry) {
             }
         };
 
-        let peer_addr = EndpointAddr::new(node_id);
-        match node.ping_peer(peer_addr).await {
-            Ok(P2pMessage::Pong {
-                node_id: _,
-                track_count,
-                version,
-            }) => {
-                registry
-                    .upsert_peer_versioned(&peer.node_id, peer.name.clone(), track_count, version)
-                    .await;
-            }
-            _ => {
-                registry.mark_offline(&peer.node_id).await;
-            }
+        ping_and_update(node, registry, &peer, node_id).await;
+    }
+}
+
+/// Ping online peers not heard from for longer than `max_silence`, so a
+/// connection lost without notice is noticed before the next sync cycle.
+/// Returns how many peers were pinged.
+pub async fn heartbeat_quiet_peers(
+    node: &Arc<P2pNode>,
+    registry: &PeerRegistry,
+    max_silence: chrono::Duration,
+) -> usize {
+    let quiet = registry.quiet_peers(max_silence, chrono::Utc::now()).await;
+    if quiet.is_empty() {
+        return 0;
+    }
+    debug!(count = quiet.len(), "pinging quiet peers");
+    let mut pinged = 0;
+    for peer in quiet {
+        let Ok(node_id) = peer.node_id.parse::<EndpointId>() else {
+            continue;
+        };
+        if !ping_and_update(node, registry, &peer, node_id).await {
+            info!(peer_id = %peer.node_id, "quiet peer did not answer heartbeat");
+        }
+        pinged += 1;
+    }
+    pinged
+}
+
+/// Ping `peer` and record the answer in the registry, or mark it offline.
+/// Returns whether it answered.
+async fn ping_and_update(
+    node: &Arc<P2pNode>,
+    registry: &PeerRegistry,
+    peer: &PeerInfo,
+    node_id: EndpointId,
+) -> bool {
+    match node.ping_peer(EndpointAddr::new(node_id)).await {
+        Ok(P2pMessage::Pong {
+            node_id: _,
+            track_count,
+            version,
+            capabilities,
+        }) => {
+            registry
+                .upsert_peer_versioned(&peer.node_id, peer.name.clone(), track_count, version)
+                .await;
+            registry.set_capabilities(&peer.node_id, capabilities).await;
+            true
+        }
+        _ => {
+            registry.mark_offline(&peer.node_id).await;
+            false
         }
     }
 }
@@ -409,6 +1159,32 @@ mod tests {
         assert!(second_seen >= first_seen);
     }
 
+    // ── quiet_peers ──────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_quiet_peers_only_lists_silent_online_peers() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("p1", None, 0).await;
+        registry.upsert_peer("p2", None, 0).await;
+        registry.upsert_peer("gone", None, 0).await;
+        registry.mark_offline("gone").await;
+
+        let max_silence = chrono::Duration::seconds(90);
+        let now = chrono::Utc::now();
+        assert!(registry.quiet_peers(max_silence, now).await.is_empty());
+
+        // Offline peers are left to the periodic refresh
+        let later = now + chrono::Duration::seconds(120);
+        let mut quiet: Vec<String> = registry
+            .quiet_peers(max_silence, later)
+            .await
+            .into_iter()
+            .map(|p| p.node_id)
+            .collect();
+        quiet.sort();
+        assert_eq!(quiet, vec!["p1".to_string(), "p2".to_string()]);
+    }
+
     // ── mark_offline: nonexistent peer ───────────────────────────────
 
     #[tokio::test]
@@ -495,7 +1271,331 @@ mod tests {
         assert_eq!(registry.peer_count().await, 2);
     }
 
-    // ── PeerInfo serde roundtrip ─────────────────────────────────────
+    // ── capabilities ─────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_set_capabilities() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("p1", None, 0).await;
+        registry
+            .set_capabilities("p1", vec!["signed-announcements".to_string()])
+            .await;
+        // Unknown peers are ignored
+        registry
+            .set_capabilities("ghost", vec!["waveform-sync".to_string()])
+            .await;
+
+        let peer = registry.get_peer("p1").await.unwrap();
+        assert!(peer.has_capability("signed-announcements"));
+        assert!(!peer.has_capability("waveform-sync"));
+        assert!(registry.get_peer("ghost").await.is_none());
+
+        // A later upsert without a Pong keeps what the peer advertised
+        registry.upsert_peer("p1", None, 3).await;
+        assert_eq!(
+            registry.get_peer("p1").await.unwrap().capabilities,
+            vec!["signed-announcements".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_peer_info_without_capabilities_deserializes() {
+        let json = r#"{"node_id":"x","name":null,"track_count":0,"last_seen":"2024-01-01T00:00:00Z","is_online":false}"#;
+        let info: PeerInfo = serde_json::from_str(json).unwrap();
+        assert!(info.capabilities.is_empty());
+    }
+
+    // ── departed peers ───────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_mark_departed_until_heard_from_again() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("p1", None, 3).await;
+        assert!(!registry.has_departed("p1").await);
+
+        registry.mark_departed("p1").await;
+        let info = registry.get_peer("p1").await.unwrap();
+        assert!(!info.is_online);
+        assert!(info.departed_at.is_some());
+        assert!(registry.has_departed("p1").await);
+        assert!(registry.online_peers().await.is_empty());
+
+        // The peer is back once it answers a ping
+        registry.upsert_peer("p1", None, 3).await;
+        assert!(!registry.has_departed("p1").await);
+        assert!(registry.get_peer("p1").await.unwrap().is_online);
+
+        // Unknown peers are never departed
+        registry.mark_departed("ghost").await;
+        assert!(!registry.has_departed("ghost").await);
+    }
+
+    // ── dead peer eviction ───────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_failures_reset_when_heard_from() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("p1", None, 0).await;
+        registry.mark_offline("p1").await;
+        registry.mark_offline("p1").await;
+        assert_eq!(
+            registry.get_peer("p1").await.unwrap().consecutive_failures,
+            2
+        );
+
+        registry.upsert_peer("p1", None, 0).await;
+        assert_eq!(
+            registry.get_peer("p1").await.unwrap().consecutive_failures,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_evict_dead_peers() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("dead", Some("Gone".into()), 0).await;
+        registry.upsert_peer("flaky", None, 0).await;
+        registry.upsert_peer("alive", None, 0).await;
+        for _ in 0..3 {
+            registry.mark_offline("dead").await;
+        }
+        registry.mark_offline("flaky").await;
+
+        assert_eq!(registry.evict_dead_peers(3).await, vec!["dead".to_string()]);
+        assert!(registry.get_peer("dead").await.is_none());
+        assert!(registry.get_peer("flaky").await.is_some());
+        assert_eq!(registry.peer_count().await, 2);
+
+        let evictions = registry.recent_evictions();
+        assert_eq!(evictions.len(), 1);
+        assert_eq!(evictions[0].node_id, "dead");
+        assert_eq!(evictions[0].name.as_deref(), Some("Gone"));
+        assert_eq!(evictions[0].consecutive_failures, 3);
+
+        // Nothing left to evict
+        assert!(registry.evict_dead_peers(3).await.is_empty());
+        assert_eq!(registry.recent_evictions().len(), 1);
+
+        // An evicted peer that comes back starts from scratch
+        registry.upsert_peer("dead", None, 0).await;
+        assert_eq!(
+            registry
+                .get_peer("dead")
+                .await
+                .unwrap()
+                .consecutive_failures,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_eviction_threshold_zero_disables() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("p1", None, 0).await;
+        for _ in 0..50 {
+            registry.mark_offline("p1").await;
+        }
+        assert!(registry.evict_dead_peers(0).await.is_empty());
+        assert!(registry.get_peer("p1").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_eviction_log_is_capped() {
+        let registry = PeerRegistry::new();
+        for i in 0..MAX_EVICTION_LOG + 5 {
+            let id = format!("p{i}");
+            registry.upsert_peer(&id, None, 0).await;
+            registry.mark_offline(&id).await;
+            registry.evict_dead_peers(1).await;
+        }
+        let evictions = registry.recent_evictions();
+        assert_eq!(evictions.len(), MAX_EVICTION_LOG);
+        // Newest first
+        assert_eq!(evictions[0].node_id, format!("p{}", MAX_EVICTION_LOG + 4));
+    }
+
+    // ── offline retention pruning ────────────────────────────────────
+
+    async fn age_peer(registry: &PeerRegistry, node_id: &str, days: i64) {
+        registry.mark_offline(node_id).await;
+        let mut peers = registry.peers.write().await;
+        let peer = peers.get_mut(node_id).unwrap();
+        peer.last_seen = chrono::Utc::now() - chrono::Duration::days(days);
+    }
+
+    #[tokio::test]
+    async fn test_is_prunable_respects_cutoff() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("old", None, 0).await;
+        registry.upsert_peer("recent", None, 0).await;
+        age_peer(&registry, "old", 31).await;
+        age_peer(&registry, "recent", 29).await;
+
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(30);
+        let seeds = HashSet::new();
+        let old = registry.get_peer("old").await.unwrap();
+        let recent = registry.get_peer("recent").await.unwrap();
+        assert!(is_prunable(&old, cutoff, &seeds));
+        assert!(!is_prunable(&recent, cutoff, &seeds));
+    }
+
+    #[tokio::test]
+    async fn test_is_prunable_skips_online_peers() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("p", None, 0).await;
+        {
+            let mut peers = registry.peers.write().await;
+            peers.get_mut("p").unwrap().last_seen = chrono::Utc::now() - chrono::Duration::days(60);
+        }
+        let peer = registry.get_peer("p").await.unwrap();
+        assert!(peer.is_online);
+        assert!(!is_prunable(&peer, chrono::Utc::now(), &HashSet::new()));
+    }
+
+    #[tokio::test]
+    async fn test_prune_offline_peers_exempts_seeds_and_trusted() {
+        let registry = PeerRegistry::new();
+        for id in ["stale", "seed", "trusted"] {
+            registry.upsert_peer(id, None, 0).await;
+            age_peer(&registry, id, 90).await;
+        }
+        registry
+            .set_peer_details("trusted", None, None, PeerTrust::Trusted)
+            .await;
+        let seeds: HashSet<String> = ["seed".to_string()].into_iter().collect();
+
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(30);
+        let pruned = registry.prune_offline_peers(cutoff, &seeds).await;
+
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned[0].node_id, "stale");
+        assert!(registry.get_peer("stale").await.is_none());
+        assert!(registry.get_peer("seed").await.is_some());
+        assert!(registry.get_peer("trusted").await.is_some());
+    }
+
+    // ── peer limit ───────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_eviction_candidate_preference_order() {
+        let registry = PeerRegistry::new();
+        for id in [
+            "online",
+            "quarantined",
+            "measured",
+            "unmeasured-old",
+            "unmeasured-new",
+        ] {
+            registry.upsert_peer(id, None, 0).await;
+        }
+        registry
+            .set_peer_details("quarantined", None, None, PeerTrust::Quarantined)
+            .await;
+        registry.record_rtt("measured", 40).await;
+        age_peer(&registry, "measured", 30).await;
+        age_peer(&registry, "unmeasured-old", 5).await;
+        age_peer(&registry, "unmeasured-new", 1).await;
+        let seeds = HashSet::new();
+
+        // Offline peers we never heard back from go first, oldest first,
+        // then quarantined online ones
+        let mut order = Vec::new();
+        loop {
+            let peers = registry.list_peers().await;
+            let Some(victim) = eviction_candidate(&peers, &seeds) else {
+                break;
+            };
+            order.push(victim.node_id.clone());
+            registry.remove_peer(&victim.node_id).await;
+        }
+        assert_eq!(
+            order,
+            vec!["unmeasured-old", "unmeasured-new", "quarantined"]
+        );
+        // Online peers in good standing and measured peers are never
+        // displaced
+        assert!(registry.get_peer("online").await.is_some());
+        assert!(registry.get_peer("measured").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_peer_limit_never_displaces_verified_peers() {
+        let registry = PeerRegistry::new();
+        registry.set_max_peers(2);
+        for id in ["pinged", "fetched"] {
+            registry.upsert_peer(id, None, 0).await;
+        }
+        registry.record_rtt("pinged", 40).await;
+        registry.record_fetch("fetched", true).await;
+        age_peer(&registry, "pinged", 30).await;
+        age_peer(&registry, "fetched", 30).await;
+
+        // Fresh identities cannot churn out peers we know are real
+        assert_eq!(registry.admission_capacity().await, Some(0));
+        assert!(!registry.upsert_peer("newcomer", None, 0).await);
+        assert!(registry.get_peer("pinged").await.is_some());
+        assert!(registry.get_peer("fetched").await.is_some());
+        assert!(registry.take_displaced_peers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_peer_limit_displaces_stale_peer() {
+        let registry = PeerRegistry::new();
+        registry.set_max_peers(2);
+        registry.upsert_peer("a", None, 0).await;
+        registry.upsert_peer("b", None, 0).await;
+        age_peer(&registry, "a", 3).await;
+
+        assert!(registry.upsert_peer("c", None, 0).await);
+        assert_eq!(registry.peer_count().await, 2);
+        assert!(registry.get_peer("a").await.is_none());
+        assert_eq!(registry.take_displaced_peers(), vec!["a".to_string()]);
+        assert!(registry.take_displaced_peers().is_empty());
+
+        // Nothing left to displace: new peers are turned away, known ones
+        // are still updated
+        assert_eq!(registry.admission_capacity().await, Some(0));
+        assert!(!registry.upsert_peer("d", None, 0).await);
+        assert!(registry.get_peer("d").await.is_none());
+        assert!(registry.upsert_peer("b", None, 5).await);
+    }
+
+    #[tokio::test]
+    async fn test_peer_limit_never_displaces_seeds_or_trusted() {
+        let registry = PeerRegistry::new();
+        registry.set_max_peers(2);
+        registry.add_seed("seed");
+        registry.upsert_peer("seed", None, 0).await;
+        registry.upsert_peer("trusted", None, 0).await;
+        registry
+            .set_peer_details("trusted", None, None, PeerTrust::Trusted)
+            .await;
+        age_peer(&registry, "seed", 60).await;
+        age_peer(&registry, "trusted", 60).await;
+
+        assert!(!registry.upsert_peer("flood", None, 0).await);
+        assert!(registry.get_peer("seed").await.is_some());
+        assert!(registry.get_peer("trusted").await.is_some());
+
+        // Another seed is admitted over the limit
+        registry.add_seed("seed-2");
+        assert!(registry.upsert_peer("seed-2", None, 0).await);
+        assert_eq!(registry.peer_count().await, 3);
+        assert!(registry.is_seed("seed-2"));
+        assert!(registry.take_displaced_peers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_no_peer_limit_by_default() {
+        let registry = PeerRegistry::new();
+        assert_eq!(registry.admission_capacity().await, None);
+        for n in 0..600 {
+            assert!(registry.upsert_peer(&format!("peer-{n}"), None, 0).await);
+        }
+        assert_eq!(registry.peer_count().await, 600);
+    }
+
+    // ── PeerInfo serde roundtrip ─────────────────────────────────────
 
     #[test]
     fn test_peer_info_serde_roundtrip() {
@@ -506,6 +1606,20 @@ mod tests {
             track_count: 42,
             last_seen: chrono::Utc::now(),
             is_online: true,
+            protocol_version: None,
+            rtt_ms: None,
+            rtt_samples: VecDeque::new(),
+            p50_rtt_ms: None,
+            interactions: VecDeque::new(),
+            avg_latency_ms: None,
+            success_rate: None,
+            last_catalog_sync_at: None,
+            capabilities: Vec::new(),
+            departed_at: None,
+            consecutive_failures: 0,
+            label: None,
+            notes: None,
+            trust: PeerTrust::Normal,
         };
         let json = serde_json::to_string(&info).unwrap();
         let decoded: PeerInfo = serde_json::from_str(&json).unwrap();
@@ -524,6 +1638,20 @@ mod tests {
             track_count: 0,
             last_seen: chrono::Utc::now(),
             is_online: false,
+            protocol_version: None,
+            rtt_ms: None,
+            rtt_samples: VecDeque::new(),
+            p50_rtt_ms: None,
+            interactions: VecDeque::new(),
+            avg_latency_ms: None,
+            success_rate: None,
+            last_catalog_sync_at: None,
+            capabilities: Vec::new(),
+            departed_at: None,
+            consecutive_failures: 0,
+            label: None,
+            notes: None,
+            trust: PeerTrust::Normal,
         };
         let json = serde_json::to_string(&info).unwrap();
         let decoded: PeerInfo = serde_json::from_str(&json).unwrap();
@@ -540,6 +1668,20 @@ mod tests {
             track_count: 10,
             last_seen: chrono::Utc::now(),
             is_online: true,
+            protocol_version: None,
+            rtt_ms: None,
+            rtt_samples: VecDeque::new(),
+            p50_rtt_ms: None,
+            interactions: VecDeque::new(),
+            avg_latency_ms: None,
+            success_rate: None,
+            last_catalog_sync_at: None,
+            capabilities: Vec::new(),
+            departed_at: None,
+            consecutive_failures: 0,
+            label: None,
+            notes: None,
+            trust: PeerTrust::Normal,
         };
         let cloned = info.clone();
         assert_eq!(info.node_id, cloned.node_id);
@@ -556,12 +1698,486 @@ mod tests {
             track_count: 0,
             last_seen: chrono::Utc::now(),
             is_online: false,
+            protocol_version: None,
+            rtt_ms: None,
+            rtt_samples: VecDeque::new(),
+            p50_rtt_ms: None,
+            interactions: VecDeque::new(),
+            avg_latency_ms: None,
+            success_rate: None,
+            last_catalog_sync_at: None,
+            capabilities: Vec::new(),
+            departed_at: None,
+            consecutive_failures: 0,
+            label: None,
+            notes: None,
+            trust: PeerTrust::Normal,
         };
         let debug = format!("{:?}", info);
         assert!(debug.contains("PeerInfo"));
         assert!(debug.contains("dbg"));
     }
 
+    // ── Peer status updates ──────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_status_update_on_first_upsert_and_changes_only() {
+        let registry = PeerRegistry::new();
+        let mut updates = registry.subscribe_status();
+
+        registry
+            .upsert_peer_versioned("p1", None, 5, Some("0.1.0".into()))
+            .await;
+        registry.upsert_peer("p1", None, 5).await;
+        registry.upsert_peer("p1", None, 6).await;
+
+        assert_eq!(
+            updates.try_recv().unwrap(),
+            PeerStatusUpdate::PeerOnline(PeerStatus {
+                node_id: "p1".into(),
+                track_count: 5,
+                rtt_ms: None,
+                version: Some("0.1.0".into()),
+            })
+        );
+        match updates.try_recv().unwrap() {
+            PeerStatusUpdate::PeerOnline(status) => assert_eq!(status.track_count, 6),
+            other => panic!("expected peer_online, got {other:?}"),
+        }
+        assert!(updates.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_status_update_when_peer_goes_offline() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("p1", None, 3).await;
+        registry.record_rtt("p1", 42).await;
+        let mut updates = registry.subscribe_status();
+
+        registry.mark_offline("p1").await;
+        registry.mark_offline("p1").await;
+        registry.mark_departed("p1").await;
+
+        assert_eq!(
+            updates.try_recv().unwrap(),
+            PeerStatusUpdate::PeerOffline(PeerStatus {
+                node_id: "p1".into(),
+                track_count: 3,
+                rtt_ms: Some(42),
+                version: None,
+            })
+        );
+        assert!(updates.try_recv().is_err());
+
+        // Coming back is reported again
+        registry.upsert_peer("p1", None, 3).await;
+        assert!(matches!(
+            updates.try_recv().unwrap(),
+            PeerStatusUpdate::PeerOnline(_)
+        ));
+    }
+
+    #[test]
+    fn test_status_update_serialization() {
+        let update = PeerStatusUpdate::PeerOffline(PeerStatus {
+            node_id: "p1".into(),
+            track_count: 2,
+            rtt_ms: Some(10),
+            version: None,
+        });
+        assert_eq!(
+            serde_json::to_value(&update).unwrap(),
+            serde_json::json!({
+                "type": "peer_offline",
+                "node_id": "p1",
+                "track_count": 2,
+                "rtt_ms": 10,
+                "version": null,
+            })
+        );
+    }
+
+    // ── Protocol version tracking ────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_set_protocol_version_v1_peer() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("old", None, 0).await;
+        assert!(registry
+            .get_peer("old")
+            .await
+            .unwrap()
+            .protocol_version
+            .is_none());
+
+        registry
+            .set_protocol_version("old", ProtocolVersion::V1)
+            .await;
+        assert_eq!(
+            registry.get_peer("old").await.unwrap().protocol_version,
+            Some(1)
+        );
+
+        // Upserting keeps the negotiated version
+        registry.upsert_peer("old", None, 5).await;
+        assert_eq!(
+            registry.get_peer("old").await.unwrap().protocol_version,
+            Some(1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_protocol_version_unknown_peer() {
+        let registry = PeerRegistry::new();
+        registry
+            .set_protocol_version("ghost", ProtocolVersion::V2)
+            .await;
+        assert_eq!(registry.peer_count().await, 0);
+    }
+
+    #[test]
+    fn test_peer_info_deserialize_without_protocol_version() {
+        let json = r#"{"node_id":"p","name":null,"track_count":0,"last_seen":"2026-01-01T00:00:00Z","is_online":true}"#;
+        let info: PeerInfo = serde_json::from_str(json).unwrap();
+        assert!(info.protocol_version.is_none());
+        assert!(info.rtt_ms.is_none());
+        assert!(info.label.is_none());
+        assert_eq!(info.trust, PeerTrust::Normal);
+    }
+
+    // ── Admin label, notes and trust ─────────────────────────────────
+
+    #[test]
+    fn test_peer_trust_parse_roundtrip() {
+        for trust in [
+            PeerTrust::Trusted,
+            PeerTrust::Normal,
+            PeerTrust::Quarantined,
+        ] {
+            assert_eq!(PeerTrust::parse(trust.as_str()), Some(trust));
+            assert_eq!(
+                serde_json::to_value(trust).unwrap(),
+                serde_json::json!(trust.as_str())
+            );
+        }
+        assert_eq!(PeerTrust::parse("banned"), None);
+        assert_eq!(PeerTrust::default(), PeerTrust::Normal);
+    }
+
+    #[tokio::test]
+    async fn test_set_peer_details() {
+        let registry = PeerRegistry::new();
+        assert!(registry
+            .set_peer_details("p1", None, None, PeerTrust::Trusted)
+            .await
+            .is_none());
+
+        registry.upsert_peer("p1", Some("Peer".into()), 3).await;
+        let updated = registry
+            .set_peer_details(
+                "p1",
+                Some("Alice".into()),
+                Some("flaky uplink".into()),
+                PeerTrust::Quarantined,
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.label.as_deref(), Some("Alice"));
+        assert!(registry.is_quarantined("p1").await);
+        assert!(!registry.is_quarantined("unknown").await);
+
+        // Hearing from the peer again keeps the admin's settings
+        registry.upsert_peer("p1", Some("Renamed".into()), 4).await;
+        let peer = registry.get_peer("p1").await.unwrap();
+        assert_eq!(peer.label.as_deref(), Some("Alice"));
+        assert_eq!(peer.notes.as_deref(), Some("flaky uplink"));
+        assert_eq!(peer.trust, PeerTrust::Quarantined);
+    }
+
+    #[tokio::test]
+    async fn test_pex_shareable_peers_skip_quarantined() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("p1", None, 0).await;
+        registry.upsert_peer("p2", None, 0).await;
+        registry.upsert_peer("p3", None, 0).await;
+        registry
+            .set_peer_details("p2", None, None, PeerTrust::Quarantined)
+            .await;
+        registry
+            .set_peer_details("p3", None, None, PeerTrust::Trusted)
+            .await;
+
+        let mut shared = registry.pex_shareable_peers().await;
+        shared.sort();
+        assert_eq!(shared, vec!["p1".to_string(), "p3".to_string()]);
+    }
+
+    #[test]
+    fn test_peer_row_roundtrip_keeps_admin_fields() {
+        use soundtime_db::entities::p2p_peer;
+
+        let mut info = PeerInfo {
+            node_id: "p1".to_string(),
+            name: Some("Peer".to_string()),
+            version: Some("0.1.0".to_string()),
+            track_count: 9,
+            last_seen: chrono::Utc::now(),
+            is_online: true,
+            protocol_version: Some(2),
+            rtt_ms: Some(30),
+            rtt_samples: VecDeque::from([30]),
+            p50_rtt_ms: Some(30),
+            interactions: VecDeque::new(),
+            avg_latency_ms: Some(34),
+            success_rate: Some(0.75),
+            last_catalog_sync_at: None,
+            capabilities: vec!["waveform-sync".to_string()],
+            departed_at: None,
+            consecutive_failures: 0,
+            label: Some("Alice".to_string()),
+            notes: Some("flaky uplink".to_string()),
+            trust: PeerTrust::Trusted,
+        };
+        let to_row = |info: &PeerInfo| {
+            let am = peer_active_model(info);
+            assert_eq!(am.trusted_moderator, sea_orm::Set(false));
+            p2p_peer::Model {
+                node_id: am.node_id.unwrap(),
+                name: am.name.unwrap(),
+                version: am.version.unwrap(),
+                track_count: am.track_count.unwrap(),
+                is_online: am.is_online.unwrap(),
+                last_seen_at: am.last_seen_at.unwrap(),
+                created_at: am.created_at.unwrap(),
+                last_catalog_sync_at: am.last_catalog_sync_at.unwrap(),
+                capabilities: am.capabilities.unwrap(),
+                trusted_moderator: am.trusted_moderator.unwrap(),
+                p50_rtt_ms: am.p50_rtt_ms.unwrap(),
+                avg_latency_ms: am.avg_latency_ms.unwrap(),
+                success_rate: am.success_rate.unwrap(),
+                label: am.label.unwrap(),
+                notes: am.notes.unwrap(),
+                trust: am.trust.unwrap(),
+            }
+        };
+
+        let restored = peer_from_row(to_row(&info));
+        assert_eq!(restored.node_id, "p1");
+        assert_eq!(restored.label.as_deref(), Some("Alice"));
+        assert_eq!(restored.notes.as_deref(), Some("flaky uplink"));
+        assert_eq!(restored.trust, PeerTrust::Trusted);
+        assert_eq!(restored.capabilities, info.capabilities);
+        assert_eq!(restored.p50_rtt_ms, Some(30));
+        assert_eq!(restored.avg_latency_ms, Some(34));
+        assert_eq!(restored.success_rate, Some(0.75));
+        assert!(!restored.is_online);
+
+        info.label = None;
+        info.notes = None;
+        info.trust = PeerTrust::Quarantined;
+        let restored = peer_from_row(to_row(&info));
+        assert!(restored.label.is_none());
+        assert!(restored.notes.is_none());
+        assert_eq!(restored.trust, PeerTrust::Quarantined);
+    }
+
+    #[test]
+    fn test_peer_from_row_unknown_trust_is_normal() {
+        let row = soundtime_db::entities::p2p_peer::Model {
+            node_id: "p1".to_string(),
+            name: None,
+            version: None,
+            track_count: 0,
+            is_online: true,
+            last_seen_at: chrono::Utc::now().into(),
+            created_at: chrono::Utc::now().into(),
+            last_catalog_sync_at: None,
+            capabilities: None,
+            trusted_moderator: false,
+            p50_rtt_ms: None,
+            label: None,
+            notes: None,
+            trust: "bogus".to_string(),
+            avg_latency_ms: None,
+            success_rate: None,
+        };
+        assert_eq!(peer_from_row(row).trust, PeerTrust::Normal);
+    }
+
+    // ── latency and reliability history ──────────────────────────────
+
+    #[test]
+    fn test_average_latency_ignores_fetches() {
+        let interactions = VecDeque::from([
+            PeerInteraction::ping(20),
+            PeerInteraction::fetch(false),
+            PeerInteraction::ping(41),
+        ]);
+        assert_eq!(average_latency(&interactions), Some(30));
+        assert_eq!(
+            average_latency(&VecDeque::from([PeerInteraction::fetch(true)])),
+            None
+        );
+        assert_eq!(average_latency(&VecDeque::new()), None);
+    }
+
+    #[test]
+    fn test_success_rate() {
+        let interactions = VecDeque::from([
+            PeerInteraction::ping(20),
+            PeerInteraction::fetch(true),
+            PeerInteraction::fetch(false),
+            PeerInteraction::fetch(true),
+        ]);
+        assert_eq!(success_rate(&interactions), Some(0.75));
+        assert_eq!(success_rate(&VecDeque::new()), None);
+    }
+
+    #[tokio::test]
+    async fn test_interaction_window_drops_oldest() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("p", None, 0).await;
+        for _ in 0..MAX_PEER_INTERACTIONS {
+            registry.record_fetch("p", false).await;
+        }
+        assert_eq!(
+            registry.get_peer("p").await.unwrap().success_rate,
+            Some(0.0)
+        );
+
+        // Half the window replaced by successes
+        for _ in 0..MAX_PEER_INTERACTIONS / 2 {
+            registry.record_rtt("p", 10).await;
+        }
+        let peer = registry.get_peer("p").await.unwrap();
+        assert_eq!(peer.interactions.len(), MAX_PEER_INTERACTIONS);
+        assert_eq!(peer.success_rate, Some(0.5));
+        assert_eq!(peer.avg_latency_ms, Some(10));
+    }
+
+    #[tokio::test]
+    async fn test_restored_latency_kept_until_pinged() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("p", None, 0).await;
+        {
+            let mut peers = registry.peers.write().await;
+            peers.get_mut("p").unwrap().avg_latency_ms = Some(300);
+        }
+        registry.record_fetch("p", true).await;
+        let peer = registry.get_peer("p").await.unwrap();
+        assert_eq!(peer.avg_latency_ms, Some(300));
+        assert_eq!(peer.success_rate, Some(1.0));
+
+        registry.record_rtt("p", 40).await;
+        assert_eq!(
+            registry.get_peer("p").await.unwrap().avg_latency_ms,
+            Some(40)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_rtt() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("p", None, 0).await;
+        registry.record_rtt("p", 42).await;
+        assert_eq!(registry.get_peer("p").await.unwrap().rtt_ms, Some(42));
+
+        // Unknown peers are not created
+        registry.record_rtt("ghost", 10).await;
+        assert!(registry.get_peer("ghost").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_record_rtt_tracks_median() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("p", None, 0).await;
+        for rtt in [300, 20, 25, 900, 30] {
+            registry.record_rtt("p", rtt).await;
+        }
+        let info = registry.get_peer("p").await.unwrap();
+        // A few slow pings do not move the median
+        assert_eq!(info.p50_rtt_ms, Some(30));
+        assert_eq!(info.rtt_ms, Some(30));
+
+        // Only the last MAX_RTT_SAMPLES are kept
+        for _ in 0..MAX_RTT_SAMPLES {
+            registry.record_rtt("p", 500).await;
+        }
+        let info = registry.get_peer("p").await.unwrap();
+        assert_eq!(info.rtt_samples.len(), MAX_RTT_SAMPLES);
+        assert_eq!(info.p50_rtt_ms, Some(500));
+        assert_eq!(registry.p50_rtts().await.get("p"), Some(&500));
+    }
+
+    #[test]
+    fn test_median_rtt() {
+        assert_eq!(median_rtt(&VecDeque::new()), None);
+        assert_eq!(median_rtt(&VecDeque::from([7])), Some(7));
+        assert_eq!(median_rtt(&VecDeque::from([40, 10, 30, 20])), Some(20));
+    }
+
+    #[test]
+    fn test_order_by_latency() {
+        let mut peers: Vec<String> = ["slow", "unknown-a", "fast", "unknown-b", "mid"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let rtts = HashMap::from([
+            ("slow".to_string(), 400),
+            ("fast".to_string(), 15),
+            ("mid".to_string(), 90),
+        ]);
+        order_by_latency(&mut peers, &rtts);
+        assert_eq!(peers, ["fast", "mid", "slow", "unknown-a", "unknown-b"]);
+    }
+
+    // ── Catalog sync state ───────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_second_ping_triggers_delta_sync() {
+        let registry = PeerRegistry::new();
+
+        // First Ping: the connection registers the peer, nothing synced yet
+        registry.upsert_peer("peer1", None, 0).await;
+        assert_eq!(
+            registry.catalog_sync_plan("peer1").await,
+            CatalogSyncPlan::Full
+        );
+        let synced_at = chrono::Utc::now();
+        registry.mark_catalog_synced("peer1", synced_at).await;
+
+        // Second Ping from the same peer re-registers it but keeps the sync state
+        registry.upsert_peer("peer1", None, 0).await;
+        assert_eq!(
+            registry.catalog_sync_plan("peer1").await,
+            CatalogSyncPlan::Delta { since: synced_at }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_catalog_sync_state_is_per_peer() {
+        let registry = PeerRegistry::new();
+        registry.upsert_peer("peer1", None, 0).await;
+        registry.upsert_peer("peer2", None, 0).await;
+        registry
+            .mark_catalog_synced("peer1", chrono::Utc::now())
+            .await;
+
+        assert!(matches!(
+            registry.catalog_sync_plan("peer1").await,
+            CatalogSyncPlan::Delta { .. }
+        ));
+        assert_eq!(
+            registry.catalog_sync_plan("peer2").await,
+            CatalogSyncPlan::Full
+        );
+        // Unknown peers always get the full catalog
+        assert_eq!(
+            registry.catalog_sync_plan("ghost").await,
+            CatalogSyncPlan::Full
+        );
+    }
+
     // ── Concurrent access ────────────────────────────────────────────
 
     #[tokio::test]
@@ -2,6 +2,8 @@
 
 use thiserror::Error;
 
+use crate::moderation::BlockedPath;
+
 #[derive(Error, Debug)]
 pub enum P2pError {
     #[error("iroh endpoint error: {0}")]
@@ -16,6 +18,9 @@ pub enum P2pError {
     #[error("track not found: {0}")]
     TrackNotFound(String),
 
+    #[error("content hash {hash} is blocked, refusing to {path}")]
+    HashBlocked { hash: String, path: BlockedPath },
+
     #[error("database error: {0}")]
     Database(#[from] sea_orm::DbErr),
 
@@ -27,6 +32,36 @@ pub enum P2pError {
 
     #[error("serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
+
+    #[error("unsupported protocol version: {0}")]
+    UnsupportedProtocol(String),
+
+    #[error("hash mismatch: expected {expected}, got {actual}")]
+    HashMismatch { expected: String, actual: String },
+
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
+    #[error("invalid peer address: {0}")]
+    InvalidPeerAddr(String),
+
+    #[error("invalid search cursor: {0}")]
+    InvalidCursor(String),
+
+    #[error("access denied: {0}")]
+    AccessDenied(String),
+
+    #[error("musicbrainz lookup failed: {0}")]
+    MusicBrainz(String),
+
+    #[error("outbound queue full for peer {0}")]
+    QueueFull(String),
+
+    #[error("peer {0} is rate limiting our catalog pages")]
+    RateLimited(String),
 }
 
 #[cfg(test)]
@@ -60,12 +95,93 @@ mod tests {
         assert_eq!(err.to_string(), "track not found: deadbeef");
     }
 
+    #[test]
+    fn test_display_hash_blocked() {
+        let err = P2pError::HashBlocked {
+            hash: "deadbeef".into(),
+            path: BlockedPath::Fetch,
+        };
+        assert_eq!(
+            err.to_string(),
+            "content hash deadbeef is blocked, refusing to fetch"
+        );
+    }
+
     #[test]
     fn test_display_connection() {
         let err = P2pError::Connection("timeout".into());
         assert_eq!(err.to_string(), "connection error: timeout");
     }
 
+    #[test]
+    fn test_display_unsupported_protocol() {
+        let err = P2pError::UnsupportedProtocol("peer speaks v1".into());
+        assert_eq!(
+            err.to_string(),
+            "unsupported protocol version: peer speaks v1"
+        );
+    }
+
+    #[test]
+    fn test_display_hash_mismatch() {
+        let err = P2pError::HashMismatch {
+            expected: "aaa".into(),
+            actual: "bbb".into(),
+        };
+        assert_eq!(err.to_string(), "hash mismatch: expected aaa, got bbb");
+    }
+
+    #[test]
+    fn test_display_invalid_signature() {
+        let err = P2pError::InvalidSignature("malformed signature".into());
+        assert_eq!(err.to_string(), "invalid signature: malformed signature");
+    }
+
+    #[test]
+    fn test_display_config() {
+        let err = P2pError::Config("bad value".into());
+        assert_eq!(err.to_string(), "invalid configuration: bad value");
+    }
+
+    #[test]
+    fn test_display_invalid_peer_addr() {
+        let err = P2pError::InvalidPeerAddr("missing port".into());
+        assert_eq!(err.to_string(), "invalid peer address: missing port");
+    }
+
+    #[test]
+    fn test_display_invalid_cursor() {
+        let err = P2pError::InvalidCursor("expired".into());
+        assert_eq!(err.to_string(), "invalid search cursor: expired");
+    }
+
+    #[test]
+    fn test_display_access_denied() {
+        let err = P2pError::AccessDenied("deadbeef".into());
+        assert_eq!(err.to_string(), "access denied: deadbeef");
+    }
+
+    #[test]
+    fn test_display_musicbrainz() {
+        let err = P2pError::MusicBrainz("HTTP 503".into());
+        assert_eq!(err.to_string(), "musicbrainz lookup failed: HTTP 503");
+    }
+
+    #[test]
+    fn test_display_queue_full() {
+        let err = P2pError::QueueFull("abc123".into());
+        assert_eq!(err.to_string(), "outbound queue full for peer abc123");
+    }
+
+    #[test]
+    fn test_display_rate_limited() {
+        let err = P2pError::RateLimited("abc123".into());
+        assert_eq!(
+            err.to_string(),
+            "peer abc123 is rate limiting our catalog pages"
+        );
+    }
+
     // ── From conversions ──────────────────────────────────────────────
 
     #[test]
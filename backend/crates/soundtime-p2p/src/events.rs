@@ -0,0 +1,348 @@
+//! Live P2P node events for admin dashboards.
+//!
+//! [`P2pEventBus`] wraps a `tokio::sync::broadcast` channel. The node
+//! publishes an event when a peer connects, leaves, comes online or goes
+//! offline, announces a track, pushes its catalog to us, queries our library
+//! or sends a new Bloom filter, when a track is replicated and when a health
+//! sweep finishes; the server's `GET /api/admin/p2p/events` forwards them as
+//! Server-Sent Events.
+//!
+//! Each event type is limited to [`MAX_EVENTS_PER_SECOND`] so a large catalog
+//! sync cannot flood subscribers. Events over the limit are dropped and
+//! counted; nothing is buffered or published while no one is subscribed.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::discovery::PeerStatusUpdate;
+use crate::track_health::BatchCheckResult;
+
+/// Events buffered per subscriber before a slow one starts missing events.
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// Events of one type published per second; the rest of the second's events
+/// of that type are dropped.
+pub const MAX_EVENTS_PER_SECOND: u32 = 20;
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Something that happened on the P2P node. Serialized with a `type` field
+/// naming the variant (`peer_connected`, `track_announced`, ...).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum P2pEvent {
+    /// A peer opened a connection to us
+    PeerConnected {
+        peer_id: String,
+        protocol_version: u8,
+    },
+    /// A peer closed its connection or announced that it is shutting down
+    PeerDisconnected { peer_id: String, reason: String },
+    /// The registry saw a peer come online or change what it reports
+    PeerOnline { peer_id: String, track_count: u64 },
+    /// The registry marked a peer offline
+    PeerOffline { peer_id: String },
+    /// A peer announced a single new track
+    TrackAnnounced {
+        peer_id: String,
+        hash: String,
+        title: String,
+        artist_name: String,
+    },
+    /// A peer started pushing its catalog to us
+    CatalogSyncStarted {
+        peer_id: String,
+        /// `None` for v1 peers, whose catalog arrives in one message
+        sync_id: Option<Uuid>,
+        total_pages: u64,
+    },
+    /// We stored one page of a peer's catalog
+    CatalogSyncPage {
+        peer_id: String,
+        sync_id: Option<Uuid>,
+        page: u64,
+        total_pages: u64,
+        inserted: u64,
+        skipped: u64,
+        failed: u64,
+    },
+    /// A peer finished pushing its catalog to us
+    CatalogSyncFinished {
+        peer_id: String,
+        sync_id: Option<Uuid>,
+        total_pages: u64,
+    },
+    /// A peer searched our library
+    SearchQueryReceived { peer_id: String, query: String },
+    /// A peer sent us a new Bloom filter of its library
+    BloomFilterUpdated { peer_id: String, item_count: u64 },
+    /// A track announced by a peer was added to our catalog
+    TrackReplicated {
+        peer_id: String,
+        hash: String,
+        title: String,
+        artist_name: String,
+    },
+    /// A remote track health sweep finished
+    HealthSweepFinished {
+        total_checked: usize,
+        healthy: usize,
+        recovered: usize,
+        failed: usize,
+        dereferenced: usize,
+    },
+}
+
+impl P2pEvent {
+    /// Value of the `type` field, also used as the SSE event name.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            P2pEvent::PeerConnected { .. } => "peer_connected",
+            P2pEvent::PeerDisconnected { .. } => "peer_disconnected",
+            P2pEvent::PeerOnline { .. } => "peer_online",
+            P2pEvent::PeerOffline { .. } => "peer_offline",
+            P2pEvent::TrackAnnounced { .. } => "track_announced",
+            P2pEvent::CatalogSyncStarted { .. } => "catalog_sync_started",
+            P2pEvent::CatalogSyncPage { .. } => "catalog_sync_page",
+            P2pEvent::CatalogSyncFinished { .. } => "catalog_sync_finished",
+            P2pEvent::SearchQueryReceived { .. } => "search_query_received",
+            P2pEvent::BloomFilterUpdated { .. } => "bloom_filter_updated",
+            P2pEvent::TrackReplicated { .. } => "track_replicated",
+            P2pEvent::HealthSweepFinished { .. } => "health_sweep_finished",
+        }
+    }
+}
+
+impl From<&PeerStatusUpdate> for P2pEvent {
+    fn from(update: &PeerStatusUpdate) -> Self {
+        match update {
+            PeerStatusUpdate::PeerOnline(status) => P2pEvent::PeerOnline {
+                peer_id: status.node_id.clone(),
+                track_count: status.track_count,
+            },
+            PeerStatusUpdate::PeerOffline(status) => P2pEvent::PeerOffline {
+                peer_id: status.node_id.clone(),
+            },
+        }
+    }
+}
+
+impl From<&BatchCheckResult> for P2pEvent {
+    fn from(result: &BatchCheckResult) -> Self {
+        P2pEvent::HealthSweepFinished {
+            total_checked: result.total_checked,
+            healthy: result.healthy,
+            recovered: result.recovered,
+            failed: result.failed,
+            dereferenced: result.dereferenced,
+        }
+    }
+}
+
+/// A [`P2pEvent`] with the time it was published. Serialized as the event's
+/// fields plus `timestamp`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TimestampedEvent {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: P2pEvent,
+}
+
+/// Broadcast channel of [`P2pEvent`]s with a per-type rate limit.
+pub struct P2pEventBus {
+    tx: broadcast::Sender<TimestampedEvent>,
+    max_per_window: u32,
+    /// Start of the current window and events let through in it, per type
+    windows: Mutex<HashMap<&'static str, (Instant, u32)>>,
+    dropped: AtomicU64,
+}
+
+impl Default for P2pEventBus {
+    fn default() -> Self {
+        Self::new(MAX_EVENTS_PER_SECOND)
+    }
+}
+
+impl P2pEventBus {
+    pub fn new(max_per_second: u32) -> Self {
+        let (tx, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            tx,
+            max_per_window: max_per_second,
+            windows: Mutex::new(HashMap::new()),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Receive events published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<TimestampedEvent> {
+        self.tx.subscribe()
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.tx.receiver_count()
+    }
+
+    /// Events dropped by the rate limit since the node started.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Publish `event` to current subscribers, unless its type is over the
+    /// rate limit. Does nothing when there are no subscribers.
+    pub fn emit(&self, event: P2pEvent) {
+        if self.tx.receiver_count() == 0 {
+            return;
+        }
+        if !self.allow_at(event.kind(), Instant::now()) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        // Only fails if every subscriber left since the check above
+        let _ = self.tx.send(TimestampedEvent {
+            timestamp: Utc::now(),
+            event,
+        });
+    }
+
+    /// Publish a `peer_online` / `peer_offline` event for every registry
+    /// status change received on `updates`, until the registry is dropped.
+    pub async fn forward_peer_status(&self, mut updates: broadcast::Receiver<PeerStatusUpdate>) {
+        loop {
+            match updates.recv().await {
+                Ok(update) => self.emit(P2pEvent::from(&update)),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    fn allow_at(&self, kind: &'static str, now: Instant) -> bool {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        let (start, count) = windows.entry(kind).or_insert((now, 0));
+        if now.duration_since(*start) >= RATE_WINDOW {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= self.max_per_window {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn announced(n: u32) -> P2pEvent {
+        P2pEvent::TrackAnnounced {
+            peer_id: "peer-a".into(),
+            hash: format!("hash-{n}"),
+            title: "Song".into(),
+            artist_name: "Artist".into(),
+        }
+    }
+
+    #[test]
+    fn test_event_serializes_with_type() {
+        let event = P2pEvent::PeerDisconnected {
+            peer_id: "peer-a".into(),
+            reason: "shutdown".into(),
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], event.kind());
+        assert_eq!(json["peer_id"], "peer-a");
+        assert_eq!(json["reason"], "shutdown");
+
+        let timestamped = TimestampedEvent {
+            timestamp: Utc::now(),
+            event,
+        };
+        let json = serde_json::to_value(&timestamped).unwrap();
+        assert_eq!(json["type"], "peer_disconnected");
+        assert_eq!(json["peer_id"], "peer-a");
+        assert!(json["timestamp"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_events() {
+        let bus = P2pEventBus::default();
+        // Nothing is published without subscribers
+        bus.emit(announced(0));
+
+        let mut rx = bus.subscribe();
+        bus.emit(P2pEvent::SearchQueryReceived {
+            peer_id: "peer-a".into(),
+            query: "jazz".into(),
+        });
+        match rx.recv().await.unwrap().event {
+            P2pEvent::SearchQueryReceived { query, .. } => assert_eq!(query, "jazz"),
+            other => panic!("unexpected event {other:?}"),
+        }
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_registry_upsert_publishes_peer_online() {
+        let registry = crate::discovery::PeerRegistry::new();
+        let bus = std::sync::Arc::new(P2pEventBus::default());
+        let mut rx = bus.subscribe();
+        let forwarder = {
+            let bus = std::sync::Arc::clone(&bus);
+            let updates = registry.subscribe_status();
+            tokio::spawn(async move { bus.forward_peer_status(updates).await })
+        };
+
+        registry.upsert_peer("peer-a", None, 7).await;
+        let received = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .expect("no event published")
+            .unwrap();
+        assert_eq!(
+            received.event,
+            P2pEvent::PeerOnline {
+                peer_id: "peer-a".into(),
+                track_count: 7,
+            }
+        );
+        assert!(received.timestamp <= Utc::now());
+        forwarder.abort();
+    }
+
+    #[test]
+    fn test_rate_limit_per_type() {
+        let bus = P2pEventBus::new(3);
+        let _rx = bus.subscribe();
+        for n in 0..10 {
+            bus.emit(announced(n));
+        }
+        assert_eq!(bus.dropped(), 7);
+
+        // Another type has its own budget
+        bus.emit(P2pEvent::BloomFilterUpdated {
+            peer_id: "peer-a".into(),
+            item_count: 5,
+        });
+        assert_eq!(bus.dropped(), 7);
+    }
+
+    #[test]
+    fn test_rate_limit_window_resets() {
+        let bus = P2pEventBus::new(2);
+        let t0 = Instant::now();
+        assert!(bus.allow_at("track_announced", t0));
+        assert!(bus.allow_at("track_announced", t0));
+        assert!(!bus.allow_at("track_announced", t0 + Duration::from_millis(500)));
+        assert!(bus.allow_at("track_announced", t0 + RATE_WINDOW));
+    }
+}
@@ -0,0 +1,115 @@
+//! Loop protection for gossiped messages.
+//!
+//! Messages forwarded peer to peer (currently `DeleteTrack`) reach a node
+//! over several paths. [`GossipSeen`] remembers the IDs already handled so
+//! each one is processed and forwarded once. The set is bounded: past
+//! [`GOSSIP_SEEN_CAPACITY`] entries the oldest are forgotten.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use dashmap::DashSet;
+
+/// Default number of hops a gossiped message travels from its origin.
+pub const DEFAULT_GOSSIP_TTL: u8 = 3;
+
+/// Gossip message IDs remembered before the oldest are evicted.
+pub const GOSSIP_SEEN_CAPACITY: usize = 10_000;
+
+/// Bounded set of gossip message IDs already handled by this node.
+pub struct GossipSeen {
+    seen: DashSet<String>,
+    /// Insertion order, oldest first, for eviction.
+    order: Mutex<VecDeque<String>>,
+    capacity: usize,
+}
+
+impl GossipSeen {
+    /// Create a set remembering at most `capacity` IDs.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            seen: DashSet::new(),
+            order: Mutex::new(VecDeque::new()),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record `id`; `false` if it was already seen and should be dropped.
+    pub fn insert(&self, id: &str) -> bool {
+        let mut order = self.order.lock().unwrap_or_else(|e| e.into_inner());
+        if !self.seen.insert(id.to_string()) {
+            return false;
+        }
+        order.push_back(id.to_string());
+        while order.len() > self.capacity {
+            if let Some(oldest) = order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    /// Whether `id` is currently remembered.
+    pub fn contains(&self, id: &str) -> bool {
+        self.seen.contains(id)
+    }
+
+    /// Number of IDs currently remembered.
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Whether no ID is remembered.
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+}
+
+impl Default for GossipSeen {
+    fn default() -> Self {
+        Self::new(GOSSIP_SEEN_CAPACITY)
+    }
+}
+
+/// Gossip ID of a track deletion: the same deletion arriving over several
+/// paths maps to one ID. The deletion time is left out, so a relayed copy
+/// re-dated by a peer is still recognised.
+pub fn delete_track_id(hash: &str, origin_node: &str) -> String {
+    format!("{hash}-{origin_node}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_reports_duplicates() {
+        let seen = GossipSeen::new(10);
+        assert!(seen.insert("a"));
+        assert!(!seen.insert("a"));
+        assert!(seen.insert("b"));
+        assert_eq!(seen.len(), 2);
+    }
+
+    #[test]
+    fn test_oldest_evicted_past_capacity() {
+        let seen = GossipSeen::new(2);
+        seen.insert("a");
+        seen.insert("b");
+        seen.insert("c");
+        assert_eq!(seen.len(), 2);
+        assert!(!seen.contains("a"));
+        assert!(seen.contains("b"));
+        assert!(seen.contains("c"));
+        // Forgotten IDs are treated as new again
+        assert!(seen.insert("a"));
+    }
+
+    #[test]
+    fn test_delete_track_id_stable() {
+        assert_eq!(delete_track_id("h1", "n1"), delete_track_id("h1", "n1"));
+        assert_ne!(delete_track_id("h1", "n1"), delete_track_id("h2", "n1"));
+        assert_ne!(delete_track_id("h1", "n1"), delete_track_id("h1", "n2"));
+        assert_eq!(delete_track_id("h1", "n1"), "h1-n1");
+    }
+}
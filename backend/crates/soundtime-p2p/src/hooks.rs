@@ -0,0 +1,124 @@
+//! Callbacks from the node into the embedding server.
+//!
+//! The P2P crate knows nothing about plugins. The server implements
+//! [`TrackAnnouncedHook`] on top of its plugin registry and registers it with
+//! `P2pNode::set_track_announced_hook`; the node then calls it for every
+//! track announced by a peer that it stores in the local catalog, whether the
+//! announcement came alone or in a catalog sync page.
+
+use std::sync::{Arc, OnceLock};
+
+use crate::node::TrackAnnouncement;
+
+/// Told about each replicated track added to the local catalog.
+pub trait TrackAnnouncedHook: Send + Sync {
+    /// `ann`, received from `peer_id`, was stored. Called on the
+    /// announcement path, so implementations must not block; spawn anything
+    /// slow.
+    fn track_announced(&self, ann: &TrackAnnouncement, peer_id: &str);
+}
+
+/// Hooks registered by the server, each set at most once.
+#[derive(Default)]
+pub struct NodeHooks {
+    track_announced: OnceLock<Arc<dyn TrackAnnouncedHook>>,
+}
+
+impl NodeHooks {
+    /// Register the track announcement hook. Returns `false` if one was
+    /// already registered, which is kept.
+    pub fn set_track_announced(&self, hook: Arc<dyn TrackAnnouncedHook>) -> bool {
+        self.track_announced.set(hook).is_ok()
+    }
+
+    /// Call the track announcement hook, if registered.
+    pub fn track_announced(&self, ann: &TrackAnnouncement, peer_id: &str) {
+        if let Some(hook) = self.track_announced.get() {
+            hook.track_announced(ann, peer_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingHook {
+        calls: Mutex<Vec<(String, String)>>,
+    }
+
+    impl TrackAnnouncedHook for RecordingHook {
+        fn track_announced(&self, ann: &TrackAnnouncement, peer_id: &str) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((ann.hash.clone(), peer_id.to_string()));
+        }
+    }
+
+    fn announcement(hash: &str) -> TrackAnnouncement {
+        TrackAnnouncement {
+            hash: hash.into(),
+            title: "Song".into(),
+            artist_name: "Artist".into(),
+            album_artist_name: None,
+            album_title: None,
+            duration_secs: 180.0,
+            format: "flac".into(),
+            file_size: 1000,
+            genre: None,
+            year: None,
+            track_number: None,
+            disc_number: None,
+            bitrate: None,
+            sample_rate: None,
+            origin_node: "origin".into(),
+            cover_hash: None,
+            fingerprint: None,
+            waveform_data: None,
+            artist_image_hash: None,
+            artist_bio: None,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            signature: None,
+        }
+    }
+
+    #[test]
+    fn test_no_hook_is_a_no_op() {
+        NodeHooks::default().track_announced(&announcement("h1"), "peer1");
+    }
+
+    #[test]
+    fn test_registered_hook_receives_announcements() {
+        let hooks = NodeHooks::default();
+        let hook = Arc::new(RecordingHook::default());
+        assert!(hooks.set_track_announced(hook.clone()));
+
+        hooks.track_announced(&announcement("h1"), "peer1");
+        hooks.track_announced(&announcement("h2"), "peer2");
+        assert_eq!(
+            *hook.calls.lock().unwrap(),
+            [
+                ("h1".to_string(), "peer1".to_string()),
+                ("h2".to_string(), "peer2".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_first_hook_wins() {
+        let hooks = NodeHooks::default();
+        let first = Arc::new(RecordingHook::default());
+        let second = Arc::new(RecordingHook::default());
+        assert!(hooks.set_track_announced(first.clone()));
+        assert!(!hooks.set_track_announced(second.clone()));
+
+        hooks.track_announced(&announcement("h1"), "peer1");
+        assert_eq!(first.calls.lock().unwrap().len(), 1);
+        assert!(second.calls.lock().unwrap().is_empty());
+    }
+}
@@ -6,32 +6,105 @@
 //! bloom-filter search routing, and
 //! distributed search across the network.
 
+pub mod bandwidth;
 pub mod blob_cache;
+pub mod blob_gc;
 pub mod blocked;
+pub mod catalog_ack;
+pub mod catalog_checksum;
+pub mod catalog_progress;
+pub mod catalog_rate;
+pub mod conn_limit;
 pub mod connection_pool;
 pub mod discovery;
 pub mod error;
+pub mod events;
+pub mod gossip;
+pub mod hooks;
 pub mod library_sync;
+pub mod message_rate;
+pub mod metrics;
+pub mod moderation;
 pub mod musicbrainz;
 pub mod node;
+pub mod outgoing_sync;
+pub mod partial;
+pub mod peer_filter;
+pub mod popularity;
+pub mod prewarm;
+pub mod published;
+pub mod replication_policy;
+pub mod search_cache;
 pub mod search_index;
+pub mod search_session;
+pub mod seed_peers;
+pub mod stats;
+pub mod stream_range;
+pub mod swarm;
+pub mod sync_checkpoint;
+pub mod sync_schedule;
+#[cfg(test)]
+mod test_node;
+pub mod track_access;
+pub mod track_cleanup;
 pub mod track_health;
 
-pub use blob_cache::BlobCache;
-pub use connection_pool::ConnectionPool;
-pub use discovery::{PeerInfo, PeerRegistry};
+pub use bandwidth::{TokenBucket, UploadLimiter};
+pub use blob_cache::{BlobCache, BlobCacheStats, DiskSpace, FreeSpaceProvider, FsFreeSpace};
+pub use blob_gc::GcReport;
+pub use catalog_ack::{CatalogPageAck, CatalogPageHeader, CatalogSyncRecord};
+pub use catalog_checksum::CatalogChecksum;
+pub use catalog_progress::CatalogSyncProgress;
+pub use conn_limit::IpConnectionLimiter;
+pub use connection_pool::{ConnectionPool, MessagePriority};
+pub use discovery::{
+    CatalogSyncPlan, PeerEviction, PeerInfo, PeerRegistry, PeerStatus, PeerStatusUpdate, PeerTrust,
+    MAX_RTT_SAMPLES,
+};
 pub use error::P2pError;
+pub use events::{P2pEvent, P2pEventBus, TimestampedEvent};
+pub use gossip::GossipSeen;
+pub use hooks::TrackAnnouncedHook;
 pub use library_sync::{
     get_library_sync_overview, new_sync_tracker, spawn_library_resync, LibrarySyncOverview,
-    LibrarySyncTaskStatus, PeerSyncStatus, SyncProgress, SyncResult, SyncState, SyncTaskHandle,
+    LibrarySyncTaskStatus, PeerSyncStatus, ResumePoint, SyncCancelled, SyncControl,
+    SyncControlError, SyncGate, SyncProgress, SyncResult, SyncState, SyncTaskHandle,
+    SyncTaskTracker,
+};
+pub use metrics::{P2pMetrics, P2P_METRICS};
+pub use moderation::{BlockStatus, BlockedPath};
+pub use musicbrainz::{
+    LookupRequest, MusicBrainzArtist, MusicBrainzClient, MusicBrainzQueue, MusicBrainzRelease,
+    RetryQueueSummary,
+};
+pub use node::{
+    parse_peer_addr, NetworkDuplicate, P2pConfig, P2pMessage, P2pNode, ProtocolVersion, RelayPlan,
+    SearchResultItem, TrackAnnouncement, TrackMetadataUpdate, SUPPORTED_CAPABILITIES,
+};
+pub use outgoing_sync::OutgoingSync;
+pub use peer_filter::PeerFilter;
+pub use popularity::PopularityEntry;
+pub use prewarm::PrewarmScope;
+pub use published::{PublishedHashes, PublishedKind, PublishedStore};
+pub use replication_policy::{PeerRejections, RejectedAnnouncement, ReplicationPolicy};
+pub use search_index::{
+    BloomDeltaData, BloomFilterData, QueryHistoryEntry, QueryHistoryPeer, SearchIndex, TrackSource,
+};
+pub use search_session::SearchPage;
+pub use stats::{ConnectionPoolStats, MessageStats, P2pStats, SearchCacheStats};
+pub use stream_range::{TrackRange, MAX_STREAM_RANGE_BYTES};
+pub use sync_schedule::{
+    spawn_sync_scheduler, ScheduleError, ScheduledSync, SyncSchedule, SCHEDULE_SETTING_KEY,
 };
-pub use musicbrainz::MusicBrainzClient;
-pub use node::{P2pConfig, P2pMessage, P2pNode, SearchResultItem, TrackAnnouncement};
-pub use search_index::{BloomFilterData, SearchIndex};
+pub use track_access::GRANT_MAX_AGE_SECS;
+pub use track_cleanup::{CleanedTrack, CleanupMode, CleanupPolicy, CleanupReport};
 pub use track_health::{
-    auto_repair_on_failure, persist_track_status, run_health_sweep, spawn_health_monitor,
-    BatchCheckResult, HealthMonitorConfig, HealthStatus, PeerTrackInfo, RecoveryResult,
-    TrackCheckItem, TrackFetcher, TrackHealthManager,
+    auto_repair_on_failure, bulk_re_reference, find_remote_track, health_history,
+    new_health_sweep_tracker, persist_track_status, recovery_log, remote_track_titles,
+    repair_track_now, run_health_sweep, spawn_health_monitor, start_health_sweep, BatchCheckResult,
+    HealthMonitorConfig, HealthSchedule, HealthStatus, HealthSweepRun, HealthSweepTaskHandle,
+    HealthSweepTaskStatus, PeerTrackInfo, RecoveryAttempt, RecoveryResult, TrackCheckItem,
+    TrackFetcher, TrackHealthManager, HEALTH_HISTORY_RETENTION_DAYS, RECOVERY_LOG_MAX_ROWS,
 };
 
 // Re-export iroh types needed by consumers
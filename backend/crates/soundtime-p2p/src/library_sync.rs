@@ -3,17 +3,20 @@
 //! Provides per-peer library sync status comparison (local tracks vs. what
 //! they've announced), and background full-sync tasks with progress tracking.
 
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter};
 use serde::Serialize;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex, MutexGuard};
 use tracing::{info, warn};
+use uuid::Uuid;
 
 use soundtime_db::entities::{remote_track, track};
 
 use crate::discovery::PeerInfo;
 use crate::node::P2pNode;
+use crate::sync_schedule::ScheduledSync;
 
 // ─── Sync status types ─────────────────────────────────────────────
 
@@ -75,6 +78,11 @@ pub struct LibrarySyncOverview {
     pub not_synced_peers: usize,
     /// Per-peer status
     pub peers: Vec<PeerSyncStatus>,
+    /// Current or last re-sync task, including whether it is paused or
+    /// was cancelled
+    pub task: LibrarySyncTaskStatus,
+    /// Peers with a scheduled re-sync, with their last and next run
+    pub schedule: Vec<ScheduledSync>,
 }
 
 // ─── Sync task progress tracking ────────────────────────────────────
@@ -89,17 +97,59 @@ pub enum LibrarySyncTaskStatus {
     /// A sync task is currently running
     #[serde(rename = "running")]
     Running {
+        task_id: Uuid,
+        peer_id: String,
+        progress: SyncProgress,
+    },
+    /// The admin paused the task; it waits before its next page
+    #[serde(rename = "paused")]
+    Paused {
+        task_id: Uuid,
         peer_id: String,
         progress: SyncProgress,
     },
     /// Sync completed
     #[serde(rename = "completed")]
     Completed { result: SyncResult },
+    /// The admin cancelled the task; a later re-sync resumes the catalog
+    /// push from `progress.resume_point`
+    #[serde(rename = "cancelled")]
+    Cancelled {
+        task_id: Uuid,
+        peer_id: String,
+        progress: SyncProgress,
+    },
     /// Sync failed
     #[serde(rename = "error")]
     Error { message: String },
 }
 
+impl LibrarySyncTaskStatus {
+    /// ID of the task this status belongs to, if it is one that can be
+    /// controlled.
+    pub fn task_id(&self) -> Option<Uuid> {
+        match self {
+            Self::Running { task_id, .. }
+            | Self::Paused { task_id, .. }
+            | Self::Cancelled { task_id, .. } => Some(*task_id),
+            _ => None,
+        }
+    }
+
+    /// Whether a task is running or paused, so no other may start.
+    pub fn is_active(&self) -> bool {
+        matches!(self, Self::Running { .. } | Self::Paused { .. })
+    }
+
+    /// Peer of the running or paused task.
+    pub fn active_peer(&self) -> Option<&str> {
+        match self {
+            Self::Running { peer_id, .. } | Self::Paused { peer_id, .. } => Some(peer_id),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct SyncProgress {
     /// Tracks processed so far
@@ -108,6 +158,27 @@ pub struct SyncProgress {
     pub total: Option<u64>,
     /// Current phase description
     pub phase: String,
+    /// Last catalog page delivered to the peer, where the push resumes
+    pub resume_point: Option<ResumePoint>,
+}
+
+impl SyncProgress {
+    fn new(processed: u64, total: Option<u64>, phase: impl Into<String>) -> Self {
+        Self {
+            processed,
+            total,
+            phase: phase.into(),
+            resume_point: None,
+        }
+    }
+}
+
+/// Where an interrupted catalog push picks up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResumePoint {
+    pub peer_id: String,
+    /// Last page delivered; the push resumes at the next one
+    pub page: u64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -119,12 +190,270 @@ pub struct SyncResult {
     pub duration_secs: f64,
 }
 
+/// Admin command to a library sync task, checked between pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncControl {
+    Run,
+    Pause,
+    Cancel,
+}
+
+/// Why a pause, resume or cancel request was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum SyncControlError {
+    #[error("no library sync task with this ID")]
+    NotFound,
+    #[error("library sync task has been cancelled")]
+    Cancelled,
+}
+
+/// The library sync task was cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncCancelled;
+
+/// Status of the library sync task, plus the channel controlling it.
+pub struct SyncTaskTracker {
+    status: Mutex<LibrarySyncTaskStatus>,
+    /// Control channel of the current task. Each task gets its own, so a
+    /// cancelled task still winding down never sees a later task's `Run`.
+    control: std::sync::Mutex<Option<(Uuid, watch::Sender<SyncControl>)>>,
+    /// Scheduled re-syncs by peer, kept by the sync scheduler.
+    schedule: std::sync::Mutex<BTreeMap<String, ScheduledSync>>,
+}
+
 /// Shared handle for the sync task tracker.
-pub type SyncTaskHandle = Arc<Mutex<LibrarySyncTaskStatus>>;
+pub type SyncTaskHandle = Arc<SyncTaskTracker>;
 
 /// Create a new sync task tracker.
 pub fn new_sync_tracker() -> SyncTaskHandle {
-    Arc::new(Mutex::new(LibrarySyncTaskStatus::Idle))
+    Arc::new(SyncTaskTracker {
+        status: Mutex::new(LibrarySyncTaskStatus::Idle),
+        control: std::sync::Mutex::new(None),
+        schedule: std::sync::Mutex::new(BTreeMap::new()),
+    })
+}
+
+impl SyncTaskTracker {
+    /// Lock the task status.
+    pub async fn lock(&self) -> MutexGuard<'_, LibrarySyncTaskStatus> {
+        self.status.lock().await
+    }
+
+    /// Scheduled re-syncs with their last and next run, by peer.
+    pub fn scheduled_syncs(&self) -> Vec<ScheduledSync> {
+        self.scheduled_runs().values().cloned().collect()
+    }
+
+    /// Internal: the sync scheduler's table of runs.
+    pub(crate) fn scheduled_runs(
+        &self,
+    ) -> std::sync::MutexGuard<'_, BTreeMap<String, ScheduledSync>> {
+        self.schedule.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Register a new task syncing with `peer_id`, unless one is running or
+    /// paused. The returned gate is what the task checks between pages.
+    pub async fn start(self: &Arc<Self>, peer_id: &str) -> Option<SyncGate> {
+        let mut status = self.status.lock().await;
+        if status.is_active() {
+            return None;
+        }
+        let task_id = Uuid::new_v4();
+        let (tx, rx) = watch::channel(SyncControl::Run);
+        *self.control.lock().unwrap_or_else(|e| e.into_inner()) = Some((task_id, tx));
+        *status = LibrarySyncTaskStatus::Running {
+            task_id,
+            peer_id: peer_id.to_string(),
+            progress: SyncProgress::new(0, None, "Connecting to peer..."),
+        };
+        Some(SyncGate {
+            task_id,
+            tracker: Arc::clone(self),
+            control: rx,
+        })
+    }
+
+    /// Pause `task_id` before its next page. Pausing a paused task is a
+    /// no-op.
+    pub async fn pause(&self, task_id: Uuid) -> Result<(), SyncControlError> {
+        let mut status = self.status.lock().await;
+        match &*status {
+            LibrarySyncTaskStatus::Running {
+                task_id: id,
+                peer_id,
+                progress,
+            } if *id == task_id => {
+                *status = LibrarySyncTaskStatus::Paused {
+                    task_id,
+                    peer_id: peer_id.clone(),
+                    progress: progress.clone(),
+                };
+                self.send(task_id, SyncControl::Pause);
+                Ok(())
+            }
+            other => Self::unchanged(other, task_id, |s| {
+                matches!(s, LibrarySyncTaskStatus::Paused { .. })
+            }),
+        }
+    }
+
+    /// Resume a paused `task_id`. Resuming a running task is a no-op.
+    pub async fn resume(&self, task_id: Uuid) -> Result<(), SyncControlError> {
+        let mut status = self.status.lock().await;
+        match &*status {
+            LibrarySyncTaskStatus::Paused {
+                task_id: id,
+                peer_id,
+                progress,
+            } if *id == task_id => {
+                *status = LibrarySyncTaskStatus::Running {
+                    task_id,
+                    peer_id: peer_id.clone(),
+                    progress: progress.clone(),
+                };
+                self.send(task_id, SyncControl::Run);
+                Ok(())
+            }
+            other => Self::unchanged(other, task_id, |s| {
+                matches!(s, LibrarySyncTaskStatus::Running { .. })
+            }),
+        }
+    }
+
+    /// Stop `task_id` at its next check, running or paused. Cancelling a
+    /// cancelled task is a no-op.
+    pub async fn cancel(&self, task_id: Uuid) -> Result<(), SyncControlError> {
+        let mut status = self.status.lock().await;
+        match &*status {
+            LibrarySyncTaskStatus::Running {
+                task_id: id,
+                peer_id,
+                progress,
+            }
+            | LibrarySyncTaskStatus::Paused {
+                task_id: id,
+                peer_id,
+                progress,
+            } if *id == task_id => {
+                *status = LibrarySyncTaskStatus::Cancelled {
+                    task_id,
+                    peer_id: peer_id.clone(),
+                    progress: progress.clone(),
+                };
+                self.send(task_id, SyncControl::Cancel);
+                Ok(())
+            }
+            other => Self::unchanged(other, task_id, |s| {
+                matches!(s, LibrarySyncTaskStatus::Cancelled { .. })
+            }),
+        }
+    }
+
+    /// Internal: outcome of a control request that changes nothing —
+    /// fine if `task_id` is already where it was asked to go.
+    fn unchanged(
+        status: &LibrarySyncTaskStatus,
+        task_id: Uuid,
+        already_there: impl Fn(&LibrarySyncTaskStatus) -> bool,
+    ) -> Result<(), SyncControlError> {
+        if status.task_id() != Some(task_id) {
+            Err(SyncControlError::NotFound)
+        } else if already_there(status) {
+            Ok(())
+        } else {
+            Err(SyncControlError::Cancelled)
+        }
+    }
+
+    /// Internal: send `control` to `task_id` if it is the current task.
+    fn send(&self, task_id: Uuid, control: SyncControl) {
+        if let Some((id, tx)) = &*self.control.lock().unwrap_or_else(|e| e.into_inner()) {
+            if *id == task_id {
+                tx.send_replace(control);
+            }
+        }
+    }
+}
+
+/// A library sync task's side of the tracker: reports its progress and
+/// waits here between pages while paused.
+pub struct SyncGate {
+    task_id: Uuid,
+    tracker: SyncTaskHandle,
+    control: watch::Receiver<SyncControl>,
+}
+
+impl SyncGate {
+    pub fn task_id(&self) -> Uuid {
+        self.task_id
+    }
+
+    /// Return at once unless paused, then wait for resume. `Err` if the
+    /// task is cancelled, before or while waiting.
+    pub async fn wait(&mut self) -> Result<(), SyncCancelled> {
+        loop {
+            let control = *self.control.borrow_and_update();
+            match control {
+                SyncControl::Run => return Ok(()),
+                SyncControl::Cancel => return Err(SyncCancelled),
+                SyncControl::Pause => {}
+            }
+            // The tracker replaced this task's channel: treat as cancelled
+            if self.control.changed().await.is_err() {
+                return Err(SyncCancelled);
+            }
+        }
+    }
+
+    /// Record that catalog page `page` reached `peer_id`, then [`Self::wait`].
+    pub async fn page_done(&mut self, peer_id: &str, page: u64) -> Result<(), SyncCancelled> {
+        {
+            let mut status = self.tracker.status.lock().await;
+            if let Some(progress) = self.progress_mut(&mut status) {
+                progress.resume_point = Some(ResumePoint {
+                    peer_id: peer_id.to_string(),
+                    page,
+                });
+            }
+        }
+        self.wait().await
+    }
+
+    /// Update the progress shown for this task, keeping its resume point.
+    /// Ignored once the task was cancelled or dismissed.
+    pub async fn report(&self, processed: u64, total: Option<u64>, phase: impl Into<String>) {
+        let mut status = self.tracker.status.lock().await;
+        if let Some(progress) = self.progress_mut(&mut status) {
+            progress.processed = processed;
+            progress.total = total;
+            progress.phase = phase.into();
+        }
+    }
+
+    /// Set the final status of this task, unless it was cancelled or
+    /// dismissed meanwhile.
+    pub async fn finish(&self, result: LibrarySyncTaskStatus) {
+        let mut status = self.tracker.status.lock().await;
+        if status.is_active() && status.task_id() == Some(self.task_id) {
+            *status = result;
+        }
+    }
+
+    /// Internal: progress of this task if it is running or paused.
+    fn progress_mut<'a>(
+        &self,
+        status: &'a mut LibrarySyncTaskStatus,
+    ) -> Option<&'a mut SyncProgress> {
+        match status {
+            LibrarySyncTaskStatus::Running {
+                task_id, progress, ..
+            }
+            | LibrarySyncTaskStatus::Paused {
+                task_id, progress, ..
+            } if *task_id == self.task_id => Some(progress),
+            _ => None,
+        }
+    }
 }
 
 // ─── Helpers ─────────────────────────────────────────────────────────
@@ -148,6 +477,7 @@ fn normalize_instance_domain(node_id: &str) -> String {
 pub async fn get_library_sync_overview(
     node: &Arc<P2pNode>,
     db: &DatabaseConnection,
+    tracker: &SyncTaskHandle,
 ) -> LibrarySyncOverview {
     let peers = node.registry().list_peers().await;
 
@@ -182,6 +512,8 @@ pub async fn get_library_sync_overview(
         partial_peers: partial,
         not_synced_peers: not_synced,
         peers: peer_statuses,
+        task: tracker.lock().await.clone(),
+        schedule: tracker.scheduled_syncs(),
     }
 }
 
@@ -260,273 +592,242 @@ async fn get_peer_sync_status(
 /// 5. **Incremental sync** — send any remaining tracks the peer may have missed.
 ///
 /// Progress is tracked via the shared `tracker` handle so the API can report
-/// real-time status to the frontend.
-pub fn spawn_library_resync(node: Arc<P2pNode>, peer_node_id: String, tracker: SyncTaskHandle) {
+/// real-time status to the frontend. The task checks the tracker between
+/// catalog pages, polls and phases, so it can be paused, resumed and
+/// cancelled through it.
+///
+/// Returns the task ID, or `None` if a sync is already running or paused.
+pub async fn spawn_library_resync(
+    node: Arc<P2pNode>,
+    peer_node_id: String,
+    tracker: SyncTaskHandle,
+) -> Option<Uuid> {
+    let mut gate = tracker.start(&peer_node_id).await?;
+    let task_id = gate.task_id();
     tokio::spawn(async move {
-        let start = std::time::Instant::now();
-
-        // Set running status
+        if run_library_resync(&node, &peer_node_id, &mut gate)
+            .await
+            .is_err()
         {
-            let mut status = tracker.lock().await;
-            *status = LibrarySyncTaskStatus::Running {
-                peer_id: peer_node_id.clone(),
-                progress: SyncProgress {
-                    processed: 0,
-                    total: None,
-                    phase: "Connecting to peer...".to_string(),
-                },
-            };
+            info!(peer = %peer_node_id, task_id = %gate.task_id(), "library re-sync cancelled");
         }
+    });
+    Some(task_id)
+}
 
-        let nid: iroh::EndpointId = match peer_node_id.parse() {
-            Ok(id) => id,
-            Err(_) => {
-                let mut status = tracker.lock().await;
-                *status = LibrarySyncTaskStatus::Error {
-                    message: format!("Invalid node ID: {peer_node_id}"),
-                };
-                return;
-            }
-        };
-
-        // Phase 1: Ping peer to verify connectivity
-        {
-            let mut status = tracker.lock().await;
-            *status = LibrarySyncTaskStatus::Running {
-                peer_id: peer_node_id.clone(),
-                progress: SyncProgress {
-                    processed: 0,
-                    total: None,
-                    phase: "Pinging peer...".to_string(),
-                },
-            };
+/// Internal: body of [`spawn_library_resync`]. `Err` if cancelled.
+async fn run_library_resync(
+    node: &Arc<P2pNode>,
+    peer_node_id: &str,
+    gate: &mut SyncGate,
+) -> Result<(), SyncCancelled> {
+    let start = std::time::Instant::now();
+
+    let nid: iroh::EndpointId = match peer_node_id.parse() {
+        Ok(id) => id,
+        Err(_) => {
+            gate.finish(LibrarySyncTaskStatus::Error {
+                message: format!("Invalid node ID: {peer_node_id}"),
+            })
+            .await;
+            return Ok(());
         }
+    };
 
-        let peer_addr = iroh::EndpointAddr::new(nid);
-        let expected_tracks: u64;
-        match node.ping_peer(peer_addr).await {
-            Ok(crate::node::P2pMessage::Pong {
-                node_id: ref nid_str,
-                track_count,
-                version,
-            }) => {
-                node.registry()
-                    .upsert_peer_versioned(nid_str, None, track_count, version)
-                    .await;
-                info!(peer = %peer_node_id, %track_count, "peer responded to ping — starting sync");
-                expected_tracks = track_count;
-
-                // Update total
-                let mut status = tracker.lock().await;
-                *status = LibrarySyncTaskStatus::Running {
-                    peer_id: peer_node_id.clone(),
-                    progress: SyncProgress {
-                        processed: 0,
-                        total: Some(track_count),
-                        phase: "Sending our catalog...".to_string(),
-                    },
-                };
-            }
-            Ok(_) => {
-                warn!(peer = %peer_node_id, "unexpected response from peer");
-                let mut status = tracker.lock().await;
-                *status = LibrarySyncTaskStatus::Error {
-                    message: "Unexpected response from peer".to_string(),
-                };
-                return;
-            }
-            Err(e) => {
-                let mut status = tracker.lock().await;
-                *status = LibrarySyncTaskStatus::Error {
-                    message: format!("Failed to reach peer: {e}"),
-                };
-                return;
-            }
+    // Phase 1: Ping peer to verify connectivity
+    gate.report(0, None, "Pinging peer...").await;
+
+    let peer_addr = iroh::EndpointAddr::new(nid);
+    let expected_tracks: u64;
+    match node.ping_peer(peer_addr).await {
+        Ok(crate::node::P2pMessage::Pong {
+            node_id: ref nid_str,
+            track_count,
+            version,
+            capabilities,
+        }) => {
+            node.registry()
+                .upsert_peer_versioned(nid_str, None, track_count, version)
+                .await;
+            node.registry()
+                .set_capabilities(nid_str, capabilities)
+                .await;
+            info!(peer = %peer_node_id, %track_count, "peer responded to ping — starting sync");
+            expected_tracks = track_count;
+
+            // Update total
+            gate.report(0, Some(track_count), "Sending our catalog...")
+                .await;
         }
-
-        // Phase 2: Send our full catalog to the peer
-        {
-            let mut status = tracker.lock().await;
-            *status = LibrarySyncTaskStatus::Running {
-                peer_id: peer_node_id.clone(),
-                progress: SyncProgress {
-                    processed: 0,
-                    total: None,
-                    phase: "Sending our catalog to peer...".to_string(),
-                },
-            };
+        Ok(_) => {
+            warn!(peer = %peer_node_id, "unexpected response from peer");
+            gate.finish(LibrarySyncTaskStatus::Error {
+                message: "Unexpected response from peer".to_string(),
+            })
+            .await;
+            return Ok(());
         }
-        node.announce_all_tracks_to_peer(nid).await;
-
-        // Phase 3: Request peer's full catalog and wait for tracks to arrive
-        {
-            let mut status = tracker.lock().await;
-            *status = LibrarySyncTaskStatus::Running {
-                peer_id: peer_node_id.clone(),
-                progress: SyncProgress {
-                    processed: 0,
-                    total: Some(expected_tracks),
-                    phase: "Requesting peer's catalog...".to_string(),
-                },
-            };
+        Err(e) => {
+            gate.finish(LibrarySyncTaskStatus::Error {
+                message: format!("Failed to reach peer: {e}"),
+            })
+            .await;
+            return Ok(());
         }
+    }
 
-        // Also run PEX for peer discovery (non-blocking, just fire and forget)
-        node.discover_via_peer(nid).await;
+    // Phase 2: Send our full catalog to the peer, pausing between pages
+    gate.wait().await?;
+    gate.report(0, None, "Sending our catalog to peer...").await;
+    node.announce_all_tracks_to_peer_gated(nid, gate).await;
 
-        // Send explicit RequestCatalog to the peer
-        if let Err(e) = node.request_catalog_from_peer(nid).await {
-            warn!(peer = %peer_node_id, "failed to request catalog from peer: {e}");
-            // Don't abort — the Ping handler may have already triggered a catalog send
-        }
+    // Phase 3: Request peer's full catalog and wait for tracks to arrive
+    gate.wait().await?;
+    gate.report(0, Some(expected_tracks), "Requesting peer's catalog...")
+        .await;
 
-        // Wait for incoming tracks from the peer (they arrive asynchronously via
-        // the CatalogSync handler). Poll the remote_track table until we reach the
-        // expected count or timeout.
-        if expected_tracks > 0 {
-            let db = node.db();
-            let domain = normalize_instance_domain(&peer_node_id);
-            let poll_interval = std::time::Duration::from_secs(2);
-            // Scale timeout dynamically: 2s per track, min 5m, max 2h
-            let timeout_secs = (expected_tracks * 2).clamp(300, 7200);
-            let timeout = std::time::Duration::from_secs(timeout_secs);
-            let deadline = std::time::Instant::now() + timeout;
-            let mut last_count = 0u64;
-            let mut stall_count = 0u32;
-
-            // Scale stall threshold: allow more time between pages for large catalogs.
-            // Each page of 500 tracks can take 25-60s to process, so we allow
-            // (pages * 5 + 30) polls × 2s each.
-            let max_stall = ((expected_tracks / 500) * 5 + 30) as u32;
-
-            loop {
-                tokio::time::sleep(poll_interval).await;
-
-                let current_count = remote_track::Entity::find()
-                    .filter(remote_track::Column::InstanceDomain.eq(&domain))
-                    .count(db)
-                    .await
-                    .unwrap_or(0);
-
-                // Update progress
-                {
-                    let mut status = tracker.lock().await;
-                    *status = LibrarySyncTaskStatus::Running {
-                        peer_id: peer_node_id.clone(),
-                        progress: SyncProgress {
-                            processed: current_count,
-                            total: Some(expected_tracks),
-                            phase: format!(
-                                "Receiving catalog... ({}/{})",
-                                current_count, expected_tracks
-                            ),
-                        },
-                    };
-                }
+    // Also run PEX for peer discovery (non-blocking, just fire and forget)
+    node.discover_via_peer(nid).await;
 
-                if current_count >= expected_tracks {
-                    info!(
-                        peer = %peer_node_id,
-                        tracks = current_count,
-                        "all expected tracks received from peer"
-                    );
-                    break;
-                }
+    // Send explicit RequestCatalog to the peer
+    if let Err(e) = node.request_catalog_from_peer(nid).await {
+        warn!(peer = %peer_node_id, "failed to request catalog from peer: {e}");
+        // Don't abort — the Ping handler may have already triggered a catalog send
+    }
 
-                // Detect stall: if count hasn't changed for 10 consecutive polls (20s)
-                if current_count == last_count {
-                    stall_count += 1;
-                    if stall_count >= max_stall {
-                        info!(
-                            peer = %peer_node_id,
-                            received = current_count,
-                            expected = expected_tracks,
-                            "catalog reception stalled — continuing with received tracks"
-                        );
-                        break;
-                    }
-                } else {
-                    stall_count = 0;
-                }
-                last_count = current_count;
+    // Wait for incoming tracks from the peer (they arrive asynchronously via
+    // the CatalogSync handler). Poll the remote_track table until we reach the
+    // expected count or timeout.
+    if expected_tracks > 0 {
+        let db = node.db();
+        let domain = normalize_instance_domain(peer_node_id);
+        let poll_interval = std::time::Duration::from_secs(2);
+        // Scale timeout dynamically: 2s per track, min 5m, max 2h
+        let timeout_secs = (expected_tracks * 2).clamp(300, 7200);
+        let timeout = std::time::Duration::from_secs(timeout_secs);
+        let mut deadline = std::time::Instant::now() + timeout;
+        let mut last_count = 0u64;
+        let mut stall_count = 0u32;
+
+        // Scale stall threshold: allow more time between pages for large catalogs.
+        // Each page of 500 tracks can take 25-60s to process, so we allow
+        // (pages * 5 + 30) polls × 2s each.
+        let max_stall = ((expected_tracks / 500) * 5 + 30) as u32;
+
+        loop {
+            tokio::time::sleep(poll_interval).await;
+
+            // Time spent paused does not count towards the timeout
+            let paused_at = std::time::Instant::now();
+            gate.wait().await?;
+            deadline += paused_at.elapsed();
+
+            let current_count = remote_track::Entity::find()
+                .filter(remote_track::Column::InstanceDomain.eq(&domain))
+                .count(db)
+                .await
+                .unwrap_or(0);
+
+            // Update progress
+            gate.report(
+                current_count,
+                Some(expected_tracks),
+                format!(
+                    "Receiving catalog... ({}/{})",
+                    current_count, expected_tracks
+                ),
+            )
+            .await;
+
+            if current_count >= expected_tracks {
+                info!(
+                    peer = %peer_node_id,
+                    tracks = current_count,
+                    "all expected tracks received from peer"
+                );
+                break;
+            }
 
-                if std::time::Instant::now() >= deadline {
-                    warn!(
+            // Detect stall: if count hasn't changed for 10 consecutive polls (20s)
+            if current_count == last_count {
+                stall_count += 1;
+                if stall_count >= max_stall {
+                    info!(
                         peer = %peer_node_id,
                         received = current_count,
                         expected = expected_tracks,
-                        timeout_secs,
-                        "catalog reception timed out"
+                        "catalog reception stalled — continuing with received tracks"
                     );
                     break;
                 }
+            } else {
+                stall_count = 0;
+            }
+            last_count = current_count;
+
+            if std::time::Instant::now() >= deadline {
+                warn!(
+                    peer = %peer_node_id,
+                    received = current_count,
+                    expected = expected_tracks,
+                    timeout_secs,
+                    "catalog reception timed out"
+                );
+                break;
             }
         }
+    }
 
-        // Phase 4: Exchange bloom filters for search
-        {
-            let mut status = tracker.lock().await;
-            *status = LibrarySyncTaskStatus::Running {
-                peer_id: peer_node_id.clone(),
-                progress: SyncProgress {
-                    processed: 2,
-                    total: Some(3),
-                    phase: "Exchanging search indexes...".to_string(),
-                },
-            };
-        }
-        node.broadcast_bloom_filter().await;
-
-        // Phase 5: Incremental sync to send any missing data
-        {
-            let mut status = tracker.lock().await;
-            *status = LibrarySyncTaskStatus::Running {
-                peer_id: peer_node_id.clone(),
-                progress: SyncProgress {
-                    processed: 3,
-                    total: Some(3),
-                    phase: "Finalizing incremental sync...".to_string(),
-                },
-            };
-        }
-        // Pass sync start time so only tracks added DURING the sync are sent
-        // (Phase 2 already sent the full catalog — this avoids duplicating 24K announcements)
-        let sync_start_chrono =
-            chrono::Utc::now() - chrono::Duration::seconds(start.elapsed().as_secs() as i64);
-        node.incremental_sync_to_peer(nid, Some(sync_start_chrono))
-            .await;
+    // Phase 4: Exchange bloom filters for search
+    gate.wait().await?;
+    gate.report(2, Some(3), "Exchanging search indexes...")
+        .await;
+    node.broadcast_bloom_filter().await;
+
+    // Phase 5: Incremental sync to send any missing data
+    gate.wait().await?;
+    gate.report(3, Some(3), "Finalizing incremental sync...")
+        .await;
+    // Pass sync start time so only tracks added DURING the sync are sent
+    // (Phase 2 already sent the full catalog — this avoids duplicating 24K announcements)
+    let sync_start_chrono =
+        chrono::Utc::now() - chrono::Duration::seconds(start.elapsed().as_secs() as i64);
+    if let Err(e) = node
+        .incremental_sync_to_peer(nid, Some(sync_start_chrono))
+        .await
+    {
+        warn!(peer = %peer_node_id, "final incremental sync incomplete: {e}");
+    }
 
-        // Count final results
-        let db = node.db();
-        let domain = normalize_instance_domain(&peer_node_id);
-        let tracks_synced = remote_track::Entity::find()
-            .filter(remote_track::Column::InstanceDomain.eq(&domain))
-            .count(db)
-            .await
-            .unwrap_or(0);
-
-        let elapsed = start.elapsed().as_secs_f64();
-        info!(
-            peer = %peer_node_id,
-            tracks = tracks_synced,
-            duration_secs = elapsed,
-            "library re-sync completed"
-        );
+    // Count final results
+    let db = node.db();
+    let domain = normalize_instance_domain(peer_node_id);
+    let tracks_synced = remote_track::Entity::find()
+        .filter(remote_track::Column::InstanceDomain.eq(&domain))
+        .count(db)
+        .await
+        .unwrap_or(0);
 
-        // Set completed
-        {
-            let mut status = tracker.lock().await;
-            *status = LibrarySyncTaskStatus::Completed {
-                result: SyncResult {
-                    peer_id: peer_node_id,
-                    tracks_synced,
-                    tracks_already_known: 0,
-                    errors: 0,
-                    duration_secs: (elapsed * 100.0).round() / 100.0,
-                },
-            };
-        }
-    });
+    let elapsed = start.elapsed().as_secs_f64();
+    info!(
+        peer = %peer_node_id,
+        tracks = tracks_synced,
+        duration_secs = elapsed,
+        "library re-sync completed"
+    );
+
+    // Set completed
+    gate.finish(LibrarySyncTaskStatus::Completed {
+        result: SyncResult {
+            peer_id: peer_node_id.to_string(),
+            tracks_synced,
+            tracks_already_known: 0,
+            errors: 0,
+            duration_secs: (elapsed * 100.0).round() / 100.0,
+        },
+    })
+    .await;
+    Ok(())
 }
 
 #[cfg(test)]
@@ -611,12 +912,9 @@ mod tests {
     #[test]
     fn test_task_status_running_serialize() {
         let status = LibrarySyncTaskStatus::Running {
+            task_id: Uuid::nil(),
             peer_id: "abc123".to_string(),
-            progress: SyncProgress {
-                processed: 10,
-                total: Some(100),
-                phase: "Syncing...".to_string(),
-            },
+            progress: SyncProgress::new(10, Some(100), "Syncing..."),
         };
         let val = serde_json::to_value(&status).unwrap();
         assert_eq!(val["status"], "running");
@@ -624,6 +922,8 @@ mod tests {
         assert_eq!(val["progress"]["processed"], 10);
         assert_eq!(val["progress"]["total"], 100);
         assert_eq!(val["progress"]["phase"], "Syncing...");
+        assert!(val["progress"]["resume_point"].is_null());
+        assert_eq!(val["task_id"], Uuid::nil().to_string());
     }
 
     #[test]
@@ -662,20 +962,22 @@ mod tests {
             processed: 42,
             total: Some(100),
             phase: "Downloading".to_string(),
+            resume_point: Some(ResumePoint {
+                peer_id: "abc".to_string(),
+                page: 3,
+            }),
         };
         let val = serde_json::to_value(&progress).unwrap();
         assert_eq!(val["processed"], 42);
         assert_eq!(val["total"], 100);
         assert_eq!(val["phase"], "Downloading");
+        assert_eq!(val["resume_point"]["peer_id"], "abc");
+        assert_eq!(val["resume_point"]["page"], 3);
     }
 
     #[test]
     fn test_sync_progress_serialize_no_total() {
-        let progress = SyncProgress {
-            processed: 0,
-            total: None,
-            phase: "Starting".to_string(),
-        };
+        let progress = SyncProgress::new(0, None, "Starting");
         let val = serde_json::to_value(&progress).unwrap();
         assert_eq!(val["processed"], 0);
         assert!(val["total"].is_null());
@@ -753,15 +1055,37 @@ mod tests {
             partial_peers: 0,
             not_synced_peers: 0,
             peers: vec![],
+            task: LibrarySyncTaskStatus::Idle,
+            schedule: vec![],
         };
         let val = serde_json::to_value(&overview).unwrap();
         assert_eq!(val["local_track_count"], 0);
         assert_eq!(val["total_peers"], 0);
         assert!(val["peers"].as_array().unwrap().is_empty());
+        assert_eq!(val["task"]["status"], "idle");
+        assert!(val["schedule"].as_array().unwrap().is_empty());
     }
 
     // ─── new_sync_tracker ────────────────────────────────────────────
 
+    #[test]
+    fn test_active_peer_only_for_running_or_paused_task() {
+        let progress = SyncProgress::new(0, None, "Sending our catalog...");
+        let running = LibrarySyncTaskStatus::Running {
+            task_id: Uuid::new_v4(),
+            peer_id: "peer-a".into(),
+            progress: progress.clone(),
+        };
+        let cancelled = LibrarySyncTaskStatus::Cancelled {
+            task_id: Uuid::new_v4(),
+            peer_id: "peer-a".into(),
+            progress,
+        };
+        assert_eq!(running.active_peer(), Some("peer-a"));
+        assert_eq!(cancelled.active_peer(), None);
+        assert_eq!(LibrarySyncTaskStatus::Idle.active_peer(), None);
+    }
+
     #[tokio::test]
     async fn test_new_sync_tracker_starts_idle() {
         let tracker = new_sync_tracker();
@@ -779,12 +1103,9 @@ mod tests {
         {
             let mut status = tracker.lock().await;
             *status = LibrarySyncTaskStatus::Running {
+                task_id: Uuid::new_v4(),
                 peer_id: "test".to_string(),
-                progress: SyncProgress {
-                    processed: 0,
-                    total: None,
-                    phase: "Testing".to_string(),
-                },
+                progress: SyncProgress::new(0, None, "Testing"),
             };
         }
 
@@ -817,4 +1138,194 @@ mod tests {
         assert_eq!(val["status"], "completed");
         assert_eq!(val["result"]["tracks_synced"], 10);
     }
+
+    // ─── Pause / resume / cancel ─────────────────────────────────────
+
+    fn progress_of(status: &LibrarySyncTaskStatus) -> &SyncProgress {
+        match status {
+            LibrarySyncTaskStatus::Running { progress, .. }
+            | LibrarySyncTaskStatus::Paused { progress, .. }
+            | LibrarySyncTaskStatus::Cancelled { progress, .. } => progress,
+            other => panic!("expected a task with progress, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_start_rejects_second_task() {
+        let tracker = new_sync_tracker();
+        let gate = tracker.start("peer-a").await.unwrap();
+        assert!(tracker.start("peer-b").await.is_none());
+
+        // Still refused while paused, accepted once cancelled
+        tracker.pause(gate.task_id()).await.unwrap();
+        assert!(tracker.start("peer-b").await.is_none());
+        tracker.cancel(gate.task_id()).await.unwrap();
+        assert!(tracker.start("peer-b").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume_update_status() {
+        let tracker = new_sync_tracker();
+        let mut gate = tracker.start("peer-a").await.unwrap();
+        let task_id = gate.task_id();
+
+        tracker.pause(task_id).await.unwrap();
+        tracker.pause(task_id).await.unwrap();
+        assert!(matches!(
+            &*tracker.lock().await,
+            LibrarySyncTaskStatus::Paused { task_id: id, peer_id, .. }
+                if *id == task_id && peer_id == "peer-a"
+        ));
+        assert_eq!(
+            tracker.lock().await.clone().task_id(),
+            Some(task_id),
+            "paused status keeps the task ID"
+        );
+
+        // Progress reported while paused is kept, and the task stays paused
+        gate.report(5, Some(10), "Receiving catalog...").await;
+        assert!(matches!(
+            &*tracker.lock().await,
+            LibrarySyncTaskStatus::Paused { .. }
+        ));
+        assert_eq!(progress_of(&*tracker.lock().await).processed, 5);
+
+        tracker.resume(task_id).await.unwrap();
+        assert!(matches!(
+            &*tracker.lock().await,
+            LibrarySyncTaskStatus::Running { .. }
+        ));
+        assert_eq!(gate.wait().await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_control_unknown_task() {
+        let tracker = new_sync_tracker();
+        let gate = tracker.start("peer-a").await.unwrap();
+        let other = Uuid::new_v4();
+        assert_eq!(tracker.pause(other).await, Err(SyncControlError::NotFound));
+        assert_eq!(tracker.resume(other).await, Err(SyncControlError::NotFound));
+        assert_eq!(tracker.cancel(other).await, Err(SyncControlError::NotFound));
+
+        tracker.cancel(gate.task_id()).await.unwrap();
+        tracker.cancel(gate.task_id()).await.unwrap();
+        assert_eq!(
+            tracker.resume(gate.task_id()).await,
+            Err(SyncControlError::Cancelled)
+        );
+        assert_eq!(
+            tracker.pause(gate.task_id()).await,
+            Err(SyncControlError::Cancelled)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_paused_task_waits_until_resumed() {
+        let tracker = new_sync_tracker();
+        let mut gate = tracker.start("peer-a").await.unwrap();
+        let task_id = gate.task_id();
+        tracker.pause(task_id).await.unwrap();
+
+        let waiting = tokio::spawn(async move { gate.wait().await });
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+
+        tracker.resume(task_id).await.unwrap();
+        let result = tokio::time::timeout(std::time::Duration::from_secs(1), waiting)
+            .await
+            .expect("resumed task stopped waiting")
+            .unwrap();
+        assert_eq!(result, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_paused_task_promptly() {
+        let tracker = new_sync_tracker();
+        let mut gate = tracker.start("peer-a").await.unwrap();
+        let task_id = gate.task_id();
+        tracker.pause(task_id).await.unwrap();
+
+        let waiting = tokio::spawn(async move { gate.wait().await });
+        tracker.cancel(task_id).await.unwrap();
+        let result = tokio::time::timeout(std::time::Duration::from_millis(100), waiting)
+            .await
+            .expect("cancelled task stopped waiting")
+            .unwrap();
+        assert_eq!(result, Err(SyncCancelled));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_running_task_at_next_page() {
+        let tracker = new_sync_tracker();
+        let mut gate = tracker.start("peer-a").await.unwrap();
+        assert_eq!(gate.page_done("peer-a", 0).await, Ok(()));
+
+        tracker.cancel(gate.task_id()).await.unwrap();
+        assert_eq!(gate.page_done("peer-a", 1).await, Err(SyncCancelled));
+
+        // The task's last words do not overwrite the cancellation
+        gate.report(9, None, "Finalizing incremental sync...").await;
+        gate.finish(LibrarySyncTaskStatus::Error {
+            message: "late".to_string(),
+        })
+        .await;
+        let status = tracker.lock().await.clone();
+        assert!(matches!(status, LibrarySyncTaskStatus::Cancelled { .. }));
+        assert_eq!(progress_of(&status).processed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_keeps_resume_point() {
+        let tracker = new_sync_tracker();
+        let mut gate = tracker.start("peer-a").await.unwrap();
+        for page in 0..=4 {
+            gate.page_done("peer-a", page).await.unwrap();
+        }
+        tracker.cancel(gate.task_id()).await.unwrap();
+
+        let status = tracker.lock().await.clone();
+        let resume_point = progress_of(&status).resume_point.clone().unwrap();
+        assert_eq!(
+            resume_point,
+            ResumePoint {
+                peer_id: "peer-a".to_string(),
+                page: 4,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_task_ignores_next_tasks_control() {
+        let tracker = new_sync_tracker();
+        let mut old = tracker.start("peer-a").await.unwrap();
+        tracker.cancel(old.task_id()).await.unwrap();
+        let new = tracker.start("peer-b").await.unwrap();
+        assert_ne!(old.task_id(), new.task_id());
+        // The new task runs, but the old one stays cancelled
+        assert_eq!(old.wait().await, Err(SyncCancelled));
+    }
+
+    #[test]
+    fn test_task_status_paused_and_cancelled_serialize() {
+        let task_id = Uuid::new_v4();
+        let paused = LibrarySyncTaskStatus::Paused {
+            task_id,
+            peer_id: "abc".to_string(),
+            progress: SyncProgress::new(1, Some(2), "Sending our catalog to peer..."),
+        };
+        let val = serde_json::to_value(&paused).unwrap();
+        assert_eq!(val["status"], "paused");
+        assert_eq!(val["task_id"], task_id.to_string());
+
+        let cancelled = LibrarySyncTaskStatus::Cancelled {
+            task_id,
+            peer_id: "abc".to_string(),
+            progress: SyncProgress::new(1, Some(2), "Sending our catalog to peer..."),
+        };
+        let val = serde_json::to_value(&cancelled).unwrap();
+        assert_eq!(val["status"], "cancelled");
+        assert_eq!(val["peer_id"], "abc");
+        assert!(!cancelled.is_active());
+        assert!(paused.is_active());
+    }
 }
@@ -0,0 +1,93 @@
+//! Per-peer rate limits on messages peers send unprompted.
+//!
+//! `PlayCountUpdate` and `DeleteTrack` each write to the database, and a
+//! peer can send as many as it likes. [`PeerMessageRate`] counts one kind of
+//! message per peer over [`MESSAGE_RATE_WINDOW`] and turns away those over
+//! the limit.
+
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+
+/// Length of the window messages are counted over.
+pub const MESSAGE_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// `PlayCountUpdate`s accepted from one peer per window.
+pub const PLAY_COUNT_UPDATES_PER_MINUTE: u32 = 600;
+
+/// `DeleteTrack`s accepted from one peer per window.
+pub const DELETE_TRACKS_PER_MINUTE: u32 = 60;
+
+/// Messages of one kind received from each peer in its current window.
+pub struct PeerMessageRate {
+    limit: u32,
+    /// Start of the window and messages counted in it, per peer
+    windows: DashMap<String, (Instant, u32)>,
+}
+
+impl PeerMessageRate {
+    /// Accept at most `limit` messages per peer per window.
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            windows: DashMap::new(),
+        }
+    }
+
+    /// Count a message from `peer_id` received at `now`. Returns `false`,
+    /// without counting it, if the peer already sent `limit` messages in
+    /// the current window.
+    pub fn admit(&self, peer_id: &str, now: Instant) -> bool {
+        let mut window = self.windows.entry(peer_id.to_string()).or_insert((now, 0));
+        let (start, count) = &mut *window;
+        if now.saturating_duration_since(*start) >= MESSAGE_RATE_WINDOW {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= self.limit {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+
+    /// Forget the window of a peer that left.
+    pub fn remove_peer(&self, peer_id: &str) {
+        self.windows.remove(peer_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admits_up_to_limit_per_peer() {
+        let rate = PeerMessageRate::new(3);
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert!(rate.admit("a", start));
+        }
+        assert!(!rate.admit("a", start + Duration::from_secs(30)));
+        // Other peers have their own window
+        assert!(rate.admit("b", start));
+    }
+
+    #[test]
+    fn test_window_resets() {
+        let rate = PeerMessageRate::new(1);
+        let start = Instant::now();
+        assert!(rate.admit("a", start));
+        assert!(!rate.admit("a", start));
+        assert!(rate.admit("a", start + MESSAGE_RATE_WINDOW));
+    }
+
+    #[test]
+    fn test_remove_peer_forgets_window() {
+        let rate = PeerMessageRate::new(1);
+        let start = Instant::now();
+        assert!(rate.admit("a", start));
+        rate.remove_peer("a");
+        assert!(rate.admit("a", start));
+    }
+}
@@ -0,0 +1,236 @@
+//! Prometheus metrics for the P2P layer.
+//!
+//! A process-wide [`P2pMetrics`] instance holds atomic counters, gauges and
+//! histograms registered in a dedicated `prometheus::Registry`. Counters are
+//! bumped on the hot paths; gauges are point-in-time readings that
+//! [`crate::P2pNode::refresh_metrics`] sets right before each scrape. The
+//! server exposes them at `GET /metrics` in the Prometheus text exposition
+//! format.
+
+use std::sync::LazyLock;
+
+use prometheus::{Histogram, HistogramOpts, IntCounter, IntGauge, IntGaugeVec, Opts, Registry};
+
+/// Global P2P metrics — updated from the node, connection pool and health monitor.
+pub static P2P_METRICS: LazyLock<P2pMetrics> = LazyLock::new(P2pMetrics::new);
+
+/// Bucket boundaries (seconds) for the health sweep duration histogram.
+const HEALTH_SWEEP_BUCKETS: &[f64] = &[0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0];
+
+/// Counters and histograms describing P2P activity.
+pub struct P2pMetrics {
+    registry: Registry,
+    /// Messages successfully written to peers.
+    pub messages_sent_total: IntCounter,
+    /// Messages received and decoded from peers.
+    pub messages_received_total: IntCounter,
+    /// Message payload bytes written to peers.
+    pub bytes_sent_total: IntCounter,
+    /// Message payload bytes read from peers.
+    pub bytes_received_total: IntCounter,
+    /// Track announcements processed from `CatalogSync` pages.
+    pub catalog_sync_tracks_processed_total: IntCounter,
+    /// Catalog pages pushed to peers.
+    pub catalog_sync_pages_sent_total: IntCounter,
+    /// Catalog pages received from peers.
+    pub catalog_sync_pages_received_total: IntCounter,
+    /// `get_or_fetch_track` calls served from the local blob store.
+    pub blob_cache_hits_total: IntCounter,
+    /// `get_or_fetch_track` calls that had to go to a peer.
+    pub blob_cache_misses_total: IntCounter,
+    /// Blobs evicted to restore free disk space while the cache was within
+    /// its own limit.
+    pub blob_cache_watermark_evictions_total: IntCounter,
+    /// Failed attempts to reach or write to a peer.
+    pub peer_connection_errors_total: IntCounter,
+    /// Peers forgotten after staying offline past the retention period.
+    pub peers_pruned_total: IntCounter,
+    /// Wall time of each track health sweep.
+    pub health_sweep_duration_seconds: Histogram,
+    /// Peers currently marked online.
+    pub peers_online: IntGauge,
+    /// Bytes held by the replicated blob cache.
+    pub blob_cache_bytes: IntGauge,
+    /// Tracked remote tracks per health status (`status` label).
+    pub track_health: IntGaugeVec,
+}
+
+impl P2pMetrics {
+    /// Create and register all metrics in a fresh registry.
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("soundtime_p2p".to_string()), None)
+            .expect("valid registry prefix");
+
+        let counter = |name: &str, help: &str| {
+            let c = IntCounter::with_opts(Opts::new(name, help)).expect("valid counter opts");
+            registry
+                .register(Box::new(c.clone()))
+                .expect("metric registered once");
+            c
+        };
+
+        let messages_sent_total = counter("messages_sent_total", "P2P messages sent to peers");
+        let messages_received_total = counter(
+            "messages_received_total",
+            "P2P messages received from peers",
+        );
+        let bytes_sent_total = counter("bytes_sent_total", "P2P message bytes sent");
+        let bytes_received_total = counter("bytes_received_total", "P2P message bytes received");
+        let catalog_sync_tracks_processed_total = counter(
+            "catalog_sync_tracks_processed_total",
+            "Track announcements processed from catalog sync pages",
+        );
+        let catalog_sync_pages_sent_total = counter(
+            "catalog_sync_pages_sent_total",
+            "Catalog sync pages pushed to peers",
+        );
+        let catalog_sync_pages_received_total = counter(
+            "catalog_sync_pages_received_total",
+            "Catalog sync pages received from peers",
+        );
+        let blob_cache_hits_total = counter(
+            "blob_cache_hits_total",
+            "Track blobs served from the local store",
+        );
+        let blob_cache_misses_total = counter(
+            "blob_cache_misses_total",
+            "Track blobs that had to be fetched from a peer",
+        );
+        let blob_cache_watermark_evictions_total = counter(
+            "blob_cache_watermark_evictions_total",
+            "Blobs evicted to keep the minimum free disk space",
+        );
+        let peer_connection_errors_total = counter(
+            "peer_connection_errors_total",
+            "Failed connection or write attempts to peers",
+        );
+        let peers_pruned_total = counter(
+            "peers_pruned_total",
+            "Peers removed after being offline longer than the retention period",
+        );
+
+        let health_sweep_duration_seconds = Histogram::with_opts(
+            HistogramOpts::new(
+                "health_sweep_duration_seconds",
+                "Duration of track health sweeps",
+            )
+            .buckets(HEALTH_SWEEP_BUCKETS.to_vec()),
+        )
+        .expect("valid histogram opts");
+        registry
+            .register(Box::new(health_sweep_duration_seconds.clone()))
+            .expect("metric registered once");
+
+        let gauge = |name: &str, help: &str| {
+            let g = IntGauge::with_opts(Opts::new(name, help)).expect("valid gauge opts");
+            registry
+                .register(Box::new(g.clone()))
+                .expect("metric registered once");
+            g
+        };
+
+        let peers_online = gauge("peers_online", "Peers currently online");
+        let blob_cache_bytes = gauge("blob_cache_bytes", "Bytes held by the blob cache");
+
+        let track_health = IntGaugeVec::new(
+            Opts::new("track_health", "Remote tracks per health status"),
+            &["status"],
+        )
+        .expect("valid gauge vec opts");
+        registry
+            .register(Box::new(track_health.clone()))
+            .expect("metric registered once");
+
+        Self {
+            registry,
+            messages_sent_total,
+            messages_received_total,
+            bytes_sent_total,
+            bytes_received_total,
+            catalog_sync_tracks_processed_total,
+            catalog_sync_pages_sent_total,
+            catalog_sync_pages_received_total,
+            blob_cache_hits_total,
+            blob_cache_misses_total,
+            blob_cache_watermark_evictions_total,
+            peer_connection_errors_total,
+            peers_pruned_total,
+            health_sweep_duration_seconds,
+            peers_online,
+            blob_cache_bytes,
+            track_health,
+        }
+    }
+
+    /// Registry holding all P2P metrics, for encoding by the server.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+}
+
+impl Default for P2pMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::{Encoder, TextEncoder};
+
+    fn encode(metrics: &P2pMetrics) -> String {
+        let mut buf = Vec::new();
+        TextEncoder::new()
+            .encode(&metrics.registry().gather(), &mut buf)
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_all_metrics_registered() {
+        let metrics = P2pMetrics::new();
+        metrics.health_sweep_duration_seconds.observe(1.0);
+        metrics.track_health.with_label_values(&["healthy"]).set(1);
+        let families = metrics.registry().gather();
+        assert_eq!(families.len(), 16);
+    }
+
+    #[test]
+    fn test_counters_in_text_format() {
+        let metrics = P2pMetrics::new();
+        metrics.messages_sent_total.inc();
+        metrics.bytes_sent_total.inc_by(1024);
+        let text = encode(&metrics);
+        assert!(text.contains("soundtime_p2p_messages_sent_total 1"));
+        assert!(text.contains("soundtime_p2p_bytes_sent_total 1024"));
+        assert!(text.contains("# TYPE soundtime_p2p_blob_cache_hits_total counter"));
+    }
+
+    #[test]
+    fn test_health_sweep_histogram() {
+        let metrics = P2pMetrics::new();
+        metrics.health_sweep_duration_seconds.observe(2.5);
+        let text = encode(&metrics);
+        assert!(text.contains("soundtime_p2p_health_sweep_duration_seconds_count 1"));
+        assert!(text.contains("soundtime_p2p_health_sweep_duration_seconds_bucket{le=\"5\"} 1"));
+    }
+
+    #[test]
+    fn test_gauges_in_text_format() {
+        let metrics = P2pMetrics::new();
+        metrics.peers_online.set(3);
+        metrics.track_health.with_label_values(&["degraded"]).set(2);
+        let text = encode(&metrics);
+        assert!(text.contains("# TYPE soundtime_p2p_peers_online gauge"));
+        assert!(text.contains("soundtime_p2p_peers_online 3"));
+        assert!(text.contains("soundtime_p2p_track_health{status=\"degraded\"} 2"));
+    }
+
+    #[test]
+    fn test_global_metrics_accessible() {
+        let before = P2P_METRICS.peer_connection_errors_total.get();
+        P2P_METRICS.peer_connection_errors_total.inc();
+        assert!(P2P_METRICS.peer_connection_errors_total.get() > before);
+    }
+}
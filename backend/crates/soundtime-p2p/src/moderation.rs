@@ -0,0 +1,382 @@
+//! Network-wide moderation of track blobs.
+//!
+//! An admin blocks a content hash with `P2pNode::block_hash`: the hash is
+//! recorded in `blocked_hashes`, no longer served to peers, and sent to every
+//! online peer as `P2pMessage::BlockHash`. A receiver applies the block at
+//! once only if the sender is one of its trusted moderators
+//! (`p2p_peers.trusted_moderator`); blocks from any other peer are stored as
+//! [`BlockStatus::Pending`] until an admin approves them.
+//!
+//! Active blocks are kept in a [`HashBlocklist`] that the node checks on
+//! every path a blob travels: serving it to a peer, replicating it from an
+//! announcement, and fetching it from peers to stream locally.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use soundtime_db::entities::{blocked_hash, p2p_peer};
+
+use tokio::sync::RwLock;
+
+use crate::error::P2pError;
+
+/// Longest reason stored with a block; longer ones are truncated.
+pub const MAX_BLOCK_REASON_LEN: usize = 500;
+
+/// State of a row in `blocked_hashes`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockStatus {
+    /// Applied: the hash is not served and replicated copies are hidden
+    Active,
+    /// Received from a peer that is not a trusted moderator; awaits review
+    Pending,
+}
+
+impl BlockStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BlockStatus::Active => "active",
+            BlockStatus::Pending => "pending",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "active" => Some(BlockStatus::Active),
+            "pending" => Some(BlockStatus::Pending),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BlockStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Path on which a blocked hash is refused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockedPath {
+    /// Serving the blob to a peer (`FetchTrack` / `FetchTrackRange`)
+    Serve,
+    /// Replicating it from a track announcement
+    Replicate,
+    /// Fetching it from peers to stream locally
+    Fetch,
+}
+
+impl fmt::Display for BlockedPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BlockedPath::Serve => "serve",
+            BlockedPath::Replicate => "replicate",
+            BlockedPath::Fetch => "fetch",
+        })
+    }
+}
+
+/// Hashes with an active block, held in memory.
+#[derive(Debug, Default)]
+pub struct HashBlocklist {
+    hashes: RwLock<HashSet<String>>,
+}
+
+impl HashBlocklist {
+    pub fn new(hashes: HashSet<String>) -> Self {
+        Self {
+            hashes: RwLock::new(hashes),
+        }
+    }
+
+    /// Add a block. Returns `false` if `hash` was already blocked.
+    pub async fn insert(&self, hash: &str) -> bool {
+        self.hashes.write().await.insert(hash.to_string())
+    }
+
+    /// Lift a block. Returns `false` if `hash` was not blocked.
+    pub async fn remove(&self, hash: &str) -> bool {
+        self.hashes.write().await.remove(hash)
+    }
+
+    pub async fn contains(&self, hash: &str) -> bool {
+        self.hashes.read().await.contains(hash)
+    }
+
+    /// Every blocked hash.
+    pub async fn snapshot(&self) -> HashSet<String> {
+        self.hashes.read().await.clone()
+    }
+
+    /// Refuse `hash` on `path` if it is blocked.
+    pub async fn check(&self, hash: &str, path: BlockedPath) -> Result<(), P2pError> {
+        if self.contains(hash).await {
+            return Err(P2pError::HashBlocked {
+                hash: hash.to_string(),
+                path,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Status to store for a `BlockHash` received from a peer, given whether the
+/// sender is a trusted moderator and what we already have for the hash.
+/// `None` means the stored block is left as it is.
+pub fn incoming_block_status(
+    sender_trusted: bool,
+    existing: Option<BlockStatus>,
+) -> Option<BlockStatus> {
+    match (existing, sender_trusted) {
+        (None, true) => Some(BlockStatus::Active),
+        (None, false) => Some(BlockStatus::Pending),
+        // A trusted moderator confirms a block still awaiting review
+        (Some(BlockStatus::Pending), true) => Some(BlockStatus::Active),
+        (Some(_), _) => None,
+    }
+}
+
+/// Trim a block reason and cut it to [`MAX_BLOCK_REASON_LEN`] characters.
+pub fn clean_reason(reason: &str) -> String {
+    reason.trim().chars().take(MAX_BLOCK_REASON_LEN).collect()
+}
+
+/// Whether the admin marked `peer_id` as a trusted moderator.
+pub async fn is_trusted_moderator(
+    db: &DatabaseConnection,
+    peer_id: &str,
+) -> Result<bool, P2pError> {
+    Ok(p2p_peer::Entity::find_by_id(peer_id.to_string())
+        .one(db)
+        .await?
+        .is_some_and(|p| p.trusted_moderator))
+}
+
+/// Mark or unmark a known peer as a trusted moderator. Returns `false` if
+/// the peer is not in `p2p_peers`.
+pub async fn set_trusted_moderator<C: ConnectionTrait>(
+    db: &C,
+    peer_id: &str,
+    trusted: bool,
+) -> Result<bool, P2pError> {
+    let res = p2p_peer::Entity::update_many()
+        .col_expr(
+            p2p_peer::Column::TrustedModerator,
+            sea_orm::sea_query::Expr::value(trusted),
+        )
+        .filter(p2p_peer::Column::NodeId.eq(peer_id))
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected > 0)
+}
+
+/// Hashes of every active block, loaded when the node starts.
+pub async fn load_active_blocks(db: &DatabaseConnection) -> Result<HashSet<String>, P2pError> {
+    Ok(blocked_hash::Entity::find()
+        .filter(blocked_hash::Column::Status.eq(BlockStatus::Active.as_str()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|b| b.hash)
+        .collect())
+}
+
+/// All blocks, active and pending, newest first.
+pub async fn list_blocks(db: &DatabaseConnection) -> Result<Vec<blocked_hash::Model>, P2pError> {
+    Ok(blocked_hash::Entity::find()
+        .order_by_desc(blocked_hash::Column::CreatedAt)
+        .all(db)
+        .await?)
+}
+
+/// Stored status of the block on `hash`, if any.
+pub async fn block_status<C: ConnectionTrait>(
+    db: &C,
+    hash: &str,
+) -> Result<Option<BlockStatus>, P2pError> {
+    Ok(blocked_hash::Entity::find_by_id(hash.to_string())
+        .one(db)
+        .await?
+        .and_then(|b| BlockStatus::parse(&b.status)))
+}
+
+/// Insert or replace the block on `hash`.
+pub async fn save_block<C: ConnectionTrait>(
+    db: &C,
+    hash: &str,
+    reason: &str,
+    source_peer: Option<&str>,
+    status: BlockStatus,
+) -> Result<(), P2pError> {
+    let model = blocked_hash::ActiveModel {
+        hash: Set(hash.to_string()),
+        reason: Set(clean_reason(reason)),
+        source_peer: Set(source_peer.map(str::to_string)),
+        status: Set(status.as_str().to_string()),
+        created_at: Set(chrono::Utc::now().into()),
+    };
+    blocked_hash::Entity::insert(model)
+        .on_conflict(
+            sea_orm::sea_query::OnConflict::column(blocked_hash::Column::Hash)
+                .update_columns([
+                    blocked_hash::Column::Reason,
+                    blocked_hash::Column::SourcePeer,
+                    blocked_hash::Column::Status,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Change the status of the stored block on `hash`.
+pub async fn set_block_status<C: ConnectionTrait>(
+    db: &C,
+    hash: &str,
+    status: BlockStatus,
+) -> Result<(), P2pError> {
+    blocked_hash::Entity::update_many()
+        .col_expr(
+            blocked_hash::Column::Status,
+            sea_orm::sea_query::Expr::value(status.as_str()),
+        )
+        .filter(blocked_hash::Column::Hash.eq(hash))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Remove the block on `hash`. Returns `false` if there was none.
+pub async fn delete_block<C: ConnectionTrait>(db: &C, hash: &str) -> Result<bool, P2pError> {
+    let res = blocked_hash::Entity::delete_by_id(hash.to_string())
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── incoming_block_status ──
+
+    #[test]
+    fn test_trusted_moderator_block_is_applied() {
+        assert_eq!(incoming_block_status(true, None), Some(BlockStatus::Active));
+    }
+
+    #[test]
+    fn test_untrusted_block_awaits_review() {
+        assert_eq!(
+            incoming_block_status(false, None),
+            Some(BlockStatus::Pending)
+        );
+    }
+
+    #[test]
+    fn test_trusted_moderator_confirms_pending_block() {
+        assert_eq!(
+            incoming_block_status(true, Some(BlockStatus::Pending)),
+            Some(BlockStatus::Active)
+        );
+    }
+
+    #[test]
+    fn test_existing_block_is_kept() {
+        // An untrusted peer can neither re-queue nor downgrade a block
+        assert_eq!(
+            incoming_block_status(false, Some(BlockStatus::Pending)),
+            None
+        );
+        assert_eq!(
+            incoming_block_status(false, Some(BlockStatus::Active)),
+            None
+        );
+        assert_eq!(incoming_block_status(true, Some(BlockStatus::Active)), None);
+    }
+
+    // ── HashBlocklist ──
+
+    const BLOCKED: &str = "b1a3e1d9c0c7f5c2a3d6e8f0b4c1a2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9";
+    const CLEAN: &str = "0f1e2d3c4b5a69788796a5b4c3d2e1f00f1e2d3c4b5a69788796a5b4c3d2e1f0";
+
+    fn assert_refused(res: Result<(), P2pError>, path: BlockedPath) {
+        match res {
+            Err(P2pError::HashBlocked {
+                hash,
+                path: refused,
+            }) => {
+                assert_eq!(hash, BLOCKED);
+                assert_eq!(refused, path);
+            }
+            other => panic!("expected {path} of blocked hash to be refused, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_blocked_hash_is_not_served() {
+        let list = HashBlocklist::new(HashSet::from([BLOCKED.to_string()]));
+        assert_refused(
+            list.check(BLOCKED, BlockedPath::Serve).await,
+            BlockedPath::Serve,
+        );
+        assert!(list.check(CLEAN, BlockedPath::Serve).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_blocked_hash_is_not_replicated() {
+        let list = HashBlocklist::default();
+        assert!(list.check(BLOCKED, BlockedPath::Replicate).await.is_ok());
+        assert!(list.insert(BLOCKED).await);
+        assert_refused(
+            list.check(BLOCKED, BlockedPath::Replicate).await,
+            BlockedPath::Replicate,
+        );
+        assert!(list.check(CLEAN, BlockedPath::Replicate).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_blocked_hash_is_not_fetched() {
+        let list = HashBlocklist::new(HashSet::from([BLOCKED.to_string()]));
+        assert_refused(
+            list.check(BLOCKED, BlockedPath::Fetch).await,
+            BlockedPath::Fetch,
+        );
+        assert!(list.check(CLEAN, BlockedPath::Fetch).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unblocked_hash_passes_every_path() {
+        let list = HashBlocklist::new(HashSet::from([BLOCKED.to_string()]));
+        assert!(list.remove(BLOCKED).await);
+        assert!(!list.remove(BLOCKED).await);
+        for path in [
+            BlockedPath::Serve,
+            BlockedPath::Replicate,
+            BlockedPath::Fetch,
+        ] {
+            assert!(list.check(BLOCKED, path).await.is_ok());
+        }
+    }
+
+    // ── helpers ──
+
+    #[test]
+    fn test_block_status_roundtrip() {
+        for status in [BlockStatus::Active, BlockStatus::Pending] {
+            assert_eq!(BlockStatus::parse(status.as_str()), Some(status));
+        }
+        assert_eq!(BlockStatus::parse("revoked"), None);
+    }
+
+    #[test]
+    fn test_clean_reason() {
+        assert_eq!(clean_reason("  copyright  "), "copyright");
+        let long = "x".repeat(MAX_BLOCK_REASON_LEN + 50);
+        assert_eq!(clean_reason(&long).len(), MAX_BLOCK_REASON_LEN);
+    }
+}
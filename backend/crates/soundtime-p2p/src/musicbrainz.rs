@@ -3,12 +3,33 @@
 //! Uses the MusicBrainz Web Service v2 to look up recordings by title+artist
 //! and retrieve canonical metadata (MusicBrainz ID, genre, year, etc.).
 //!
-//! Rate-limited to 1 request/second per MusicBrainz API terms.
-
-use serde::Deserialize;
+//! Rate-limited to 1 request/second per MusicBrainz API terms, by a single
+//! limiter every request goes through. Lookups that fail with a network
+//! error, 429 or 5xx are retried with exponential backoff, without holding
+//! up other lookups while they wait; those that still fail can be queued in
+//! `mb_lookup_queue` with [`queue_failed_lookup`] and retried later by
+//! [`MusicBrainzClient::process_retry_queue`].
+//!
+//! Lookups for tracks received from peers go through a [`MusicBrainzQueue`],
+//! a single task that runs them one at a time however many tracks arrive at
+//! once.
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use soundtime_db::entities::{mb_lookup_queue, track};
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Semaphore;
-use tracing::{debug, warn};
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+use crate::error::P2pError;
 
 /// MusicBrainz API base URL.
 const MB_BASE_URL: &str = "https://musicbrainz.org/ws/2";
@@ -16,8 +37,41 @@ const MB_BASE_URL: &str = "https://musicbrainz.org/ws/2";
 /// User-Agent required by MusicBrainz API policy.
 const MB_USER_AGENT: &str = "SoundTime/0.1.0 (https://github.com/CICCADA-CORP/SoundTime)";
 
-/// Rate-limit: max 1 concurrent request (MusicBrainz enforces 1 req/s).
-static MB_SEMAPHORE: Semaphore = Semaphore::const_new(1);
+/// Largest artist image accepted by [`MusicBrainzClient::fetch_image`].
+const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Retries after a failed lookup, by default.
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Wait before the first retry, by default.
+pub const DEFAULT_INITIAL_DELAY_MS: u64 = 1000;
+
+/// Longest `Retry-After` honoured; longer requests are cut to this.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Failed lookups after which a queued entry is dropped.
+pub const MAX_QUEUED_LOOKUP_ATTEMPTS: i32 = 10;
+
+/// Lookups waiting for the [`MusicBrainzQueue`] before senders block.
+pub const LOOKUP_QUEUE_CAPACITY: usize = 1024;
+
+/// Shortest time between two MusicBrainz requests (MusicBrainz enforces
+/// 1 req/s).
+const MB_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// When the next MusicBrainz request may start, shared by every client.
+static MB_NEXT_REQUEST: Mutex<Option<Instant>> = Mutex::const_new(None);
+
+/// Wait for this process's turn to send a MusicBrainz request. Only the
+/// start of each request is spaced out: nothing is held while it runs or
+/// while a failed lookup waits to retry.
+async fn wait_for_rate_limit() {
+    let mut next = MB_NEXT_REQUEST.lock().await;
+    if let Some(at) = *next {
+        tokio::time::sleep_until(at).await;
+    }
+    *next = Some(Instant::now() + MB_REQUEST_INTERVAL);
+}
 
 /// A resolved recording from MusicBrainz.
 #[derive(Debug, Clone)]
@@ -30,6 +84,25 @@ pub struct MusicBrainzRecording {
     pub score: u8,
 }
 
+/// An artist found by [`MusicBrainzClient::lookup_artist`].
+#[derive(Debug, Clone)]
+pub struct MusicBrainzArtist {
+    pub id: String,
+    pub name: String,
+    pub score: u8,
+}
+
+/// A release (album) found by [`MusicBrainzClient::lookup_release`].
+#[derive(Debug, Clone)]
+pub struct MusicBrainzRelease {
+    pub id: String,
+    pub title: String,
+    /// Only set when MusicBrainz has the full date
+    pub release_date: Option<chrono::NaiveDate>,
+    pub year: Option<i16>,
+    pub score: u8,
+}
+
 // ── Internal API response types ─────────────────────────────────
 
 #[derive(Deserialize)]
@@ -63,10 +136,159 @@ struct MbRelease {
     date: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct MbArtistSearchResponse {
+    artists: Vec<MbArtistResult>,
+}
+
+#[derive(Deserialize)]
+struct MbArtistResult {
+    id: String,
+    name: String,
+    score: Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct MbReleaseSearchResponse {
+    releases: Vec<MbReleaseResult>,
+}
+
+#[derive(Deserialize)]
+struct MbReleaseResult {
+    id: String,
+    title: String,
+    score: Option<u8>,
+    date: Option<String>,
+}
+
+/// Lowest search score accepted as a match.
+const MIN_MATCH_SCORE: u8 = 80;
+
+/// The year of a MusicBrainz date (`YYYY`, `YYYY-MM` or `YYYY-MM-DD`).
+fn date_year(date: &str) -> Option<i16> {
+    date.split('-').next().and_then(|y| y.parse().ok())
+}
+
+/// Query-safe form of a search term.
+fn search_term(term: &str) -> String {
+    term.replace('"', "")
+}
+
+/// What [`MusicBrainzClient::process_retry_queue`] did.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RetryQueueSummary {
+    pub processed: usize,
+    /// Lookups that found a match
+    pub resolved: usize,
+    /// Lookups that succeeded without a match
+    pub not_found: usize,
+    /// Lookups that failed again
+    pub failed: usize,
+}
+
+/// Whether a lookup that got `status` is worth retrying.
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// The delay a `Retry-After` header asks for, in seconds (HTTP dates are
+/// not supported), capped at [`MAX_RETRY_AFTER`].
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let secs: u64 = headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(secs).min(MAX_RETRY_AFTER))
+}
+
+/// Wait before retry number `attempt` (from 0): `initial`, doubled each time.
+fn backoff_delay(initial: Duration, attempt: u32) -> Duration {
+    initial.saturating_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX))
+}
+
+/// Queue a lookup for `track_id` that failed with `error`, to be retried by
+/// [`MusicBrainzClient::process_retry_queue`]. Queuing a track again only
+/// records the new error.
+pub async fn queue_failed_lookup(
+    db: &DatabaseConnection,
+    track_id: uuid::Uuid,
+    title: &str,
+    artist: &str,
+    error: &P2pError,
+) -> Result<(), P2pError> {
+    let now = chrono::Utc::now();
+    let entry = mb_lookup_queue::ActiveModel {
+        track_id: Set(track_id),
+        title: Set(title.to_string()),
+        artist_name: Set(artist.to_string()),
+        attempts: Set(1),
+        last_error: Set(Some(error.to_string())),
+        pending_verification: Set(false),
+        announced_by: Set(None),
+        created_at: Set(now.into()),
+        updated_at: Set(now.into()),
+    };
+    mb_lookup_queue::Entity::insert(entry)
+        .on_conflict(
+            OnConflict::column(mb_lookup_queue::Column::TrackId)
+                .update_columns([
+                    mb_lookup_queue::Column::LastError,
+                    mb_lookup_queue::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Queue the lookup that decides whether the replicated track `track_id`,
+/// announced by `peer_id`, is kept. The track stays hidden until then.
+pub async fn queue_pending_verification(
+    db: &DatabaseConnection,
+    track_id: uuid::Uuid,
+    title: &str,
+    artist: &str,
+    peer_id: &str,
+) -> Result<(), P2pError> {
+    let now = chrono::Utc::now();
+    let entry = mb_lookup_queue::ActiveModel {
+        track_id: Set(track_id),
+        title: Set(title.to_string()),
+        artist_name: Set(artist.to_string()),
+        attempts: Set(0),
+        last_error: Set(None),
+        pending_verification: Set(true),
+        announced_by: Set(Some(peer_id.to_string())),
+        created_at: Set(now.into()),
+        updated_at: Set(now.into()),
+    };
+    mb_lookup_queue::Entity::insert(entry)
+        .on_conflict(
+            OnConflict::column(mb_lookup_queue::Column::TrackId)
+                .update_columns([
+                    mb_lookup_queue::Column::PendingVerification,
+                    mb_lookup_queue::Column::AnnouncedBy,
+                    mb_lookup_queue::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
 /// MusicBrainz client for metadata resolution.
 pub struct MusicBrainzClient {
     http: reqwest::Client,
     base_url: String,
+    /// Retries after a failed lookup (network error, 429 or 5xx)
+    max_retries: u32,
+    /// Wait before the first retry; doubled for each one after
+    initial_delay: Duration,
 }
 
 impl MusicBrainzClient {
@@ -78,14 +300,36 @@ impl MusicBrainzClient {
     /// and `new()` returns `Self` (not `Result`), so changing the signature
     /// would be a breaking API change.
     pub fn new() -> Self {
+        Self::with_config(
+            MB_BASE_URL,
+            MB_USER_AGENT,
+            DEFAULT_MAX_RETRIES,
+            DEFAULT_INITIAL_DELAY_MS,
+        )
+    }
+
+    /// Create a client for `base_url` that retries a failed lookup up to
+    /// `max_retries` times, waiting `initial_delay_ms` before the first retry
+    /// and twice as long before each one after (or what `Retry-After` asks).
+    ///
+    /// # Panics
+    /// Panics if the HTTP client cannot be built, like [`new`](Self::new).
+    pub fn with_config(
+        base_url: &str,
+        user_agent: &str,
+        max_retries: u32,
+        initial_delay_ms: u64,
+    ) -> Self {
         let http = reqwest::Client::builder()
-            .user_agent(MB_USER_AGENT)
+            .user_agent(user_agent)
             .timeout(Duration::from_secs(10))
             .build()
             .expect("failed to build HTTP client");
         Self {
             http,
-            base_url: MB_BASE_URL.to_string(),
+            base_url: base_url.to_string(),
+            max_retries,
+            initial_delay: Duration::from_millis(initial_delay_ms),
         }
     }
 
@@ -99,63 +343,51 @@ impl MusicBrainzClient {
         Self {
             http,
             base_url: base_url.to_string(),
+            max_retries: 0,
+            initial_delay: Duration::ZERO,
         }
     }
 
     /// Look up a recording by title and artist name.
     /// Returns the best match if found with a score >= 80.
+    ///
+    /// Network errors, 429 and 5xx responses are retried with exponential
+    /// backoff; `None` is returned once retries run out, as when there is no
+    /// match. Use [`try_lookup_recording`](Self::try_lookup_recording) to
+    /// tell the two apart.
     pub async fn lookup_recording(
         &self,
         title: &str,
         artist: &str,
     ) -> Option<MusicBrainzRecording> {
-        // Acquire rate-limit permit
-        let _permit = MB_SEMAPHORE.acquire().await.ok()?;
-
-        let query = format!(
-            "recording:\"{}\" AND artist:\"{}\"",
-            title.replace('"', ""),
-            artist.replace('"', "")
-        );
-
-        let url = format!(
-            "{}/recording?query={}&fmt=json&limit=3",
-            self.base_url,
-            urlencoding::encode(&query)
-        );
-
-        debug!(title = title, artist = artist, "querying MusicBrainz");
-
-        let resp = match self.http.get(&url).send().await {
-            Ok(r) => r,
+        match self.try_lookup_recording(title, artist).await {
+            Ok(recording) => recording,
             Err(e) => {
-                warn!("MusicBrainz request failed: {e}");
-                // Respect rate limit even on failure
-                tokio::time::sleep(Duration::from_secs(1)).await;
-                return None;
+                warn!(title = title, artist = artist, "{e}");
+                None
             }
-        };
-
-        // Respect rate limit
-        tokio::time::sleep(Duration::from_secs(1)).await;
-
-        if !resp.status().is_success() {
-            warn!(status = %resp.status(), "MusicBrainz returned error");
-            return None;
         }
+    }
 
-        let body: MbSearchResponse = match resp.json().await {
-            Ok(b) => b,
-            Err(e) => {
-                warn!("failed to parse MusicBrainz response: {e}");
-                return None;
-            }
-        };
+    /// Like [`lookup_recording`](Self::lookup_recording), but a lookup that
+    /// failed is an error rather than `None`.
+    pub async fn try_lookup_recording(
+        &self,
+        title: &str,
+        artist: &str,
+    ) -> Result<Option<MusicBrainzRecording>, P2pError> {
+        let query = format!(
+            "recording:\"{}\" AND artist:\"{}\"",
+            search_term(title),
+            search_term(artist)
+        );
+        let body: MbSearchResponse = self.search("recording", &query).await?;
 
         // Find best match with score >= 80
-        body.recordings
+        Ok(body
+            .recordings
             .into_iter()
-            .filter(|r| r.score.unwrap_or(0) >= 80)
+            .filter(|r| r.score.unwrap_or(0) >= MIN_MATCH_SCORE)
             .map(|r| {
                 let artist_name = r
                     .artist_credit
@@ -163,17 +395,17 @@ impl MusicBrainzClient {
                     .and_then(|ac| ac.first())
                     .map(|ac| ac.artist.name.clone());
 
-                let (release_title, year) =
-                    r.releases
-                        .as_ref()
-                        .and_then(|rels| rels.first())
-                        .map(|rel| {
-                            let y = rel.date.as_ref().and_then(|d| {
-                                d.split('-').next().and_then(|y| y.parse::<i16>().ok())
-                            });
-                            (Some(rel.title.clone()), y)
-                        })
-                        .unwrap_or((None, None));
+                let (release_title, year) = r
+                    .releases
+                    .as_ref()
+                    .and_then(|rels| rels.first())
+                    .map(|rel| {
+                        (
+                            Some(rel.title.clone()),
+                            rel.date.as_deref().and_then(date_year),
+                        )
+                    })
+                    .unwrap_or((None, None));
 
                 MusicBrainzRecording {
                     id: r.id,
@@ -184,7 +416,234 @@ impl MusicBrainzClient {
                     score: r.score.unwrap_or(0),
                 }
             })
-            .next()
+            .next())
+    }
+
+    /// Look up an artist by name. Returns the best match if found with a
+    /// score >= 80.
+    pub async fn lookup_artist(&self, name: &str) -> Option<MusicBrainzArtist> {
+        let query = format!("artist:\"{}\"", search_term(name));
+        match self
+            .search::<MbArtistSearchResponse>("artist", &query)
+            .await
+        {
+            Ok(body) => body
+                .artists
+                .into_iter()
+                .find(|a| a.score.unwrap_or(0) >= MIN_MATCH_SCORE)
+                .map(|a| MusicBrainzArtist {
+                    id: a.id,
+                    name: a.name,
+                    score: a.score.unwrap_or(0),
+                }),
+            Err(e) => {
+                warn!(artist = name, "{e}");
+                None
+            }
+        }
+    }
+
+    /// Look up a release by album title and artist name. Returns the best
+    /// match if found with a score >= 80.
+    pub async fn lookup_release(
+        &self,
+        album_title: &str,
+        artist_name: &str,
+    ) -> Option<MusicBrainzRelease> {
+        let query = format!(
+            "release:\"{}\" AND artist:\"{}\"",
+            search_term(album_title),
+            search_term(artist_name)
+        );
+        match self
+            .search::<MbReleaseSearchResponse>("release", &query)
+            .await
+        {
+            Ok(body) => body
+                .releases
+                .into_iter()
+                .find(|r| r.score.unwrap_or(0) >= MIN_MATCH_SCORE)
+                .map(|r| MusicBrainzRelease {
+                    release_date: r
+                        .date
+                        .as_deref()
+                        .and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()),
+                    year: r.date.as_deref().and_then(date_year),
+                    id: r.id,
+                    title: r.title,
+                    score: r.score.unwrap_or(0),
+                }),
+            Err(e) => {
+                warn!(album = album_title, artist = artist_name, "{e}");
+                None
+            }
+        }
+    }
+
+    /// Run a search of `entity` (`recording`, `artist`, ...) for `query`,
+    /// retrying network errors, 429 and 5xx responses with backoff.
+    async fn search<T: DeserializeOwned>(&self, entity: &str, query: &str) -> Result<T, P2pError> {
+        let url = format!(
+            "{}/{entity}?query={}&fmt=json&limit=3",
+            self.base_url,
+            urlencoding::encode(query)
+        );
+
+        let mut attempt = 0;
+        let resp = loop {
+            wait_for_rate_limit().await;
+            debug!(query = query, attempt, "querying MusicBrainz");
+            let result = self.http.get(&url).send().await;
+
+            let (error, retry_after) = match result {
+                Ok(r) if r.status().is_success() => break r,
+                Ok(r) if is_retryable_status(r.status()) => (
+                    format!("MusicBrainz returned {}", r.status()),
+                    retry_after(r.headers()),
+                ),
+                Ok(r) => {
+                    return Err(P2pError::MusicBrainz(format!(
+                        "MusicBrainz returned {}",
+                        r.status()
+                    )))
+                }
+                Err(e) => (format!("MusicBrainz request failed: {e}"), None),
+            };
+            if attempt >= self.max_retries {
+                return Err(P2pError::MusicBrainz(error));
+            }
+            let delay = retry_after.unwrap_or_else(|| backoff_delay(self.initial_delay, attempt));
+            warn!(
+                attempt,
+                delay_ms = delay.as_millis() as u64,
+                "{error}, retrying"
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        };
+
+        resp.json().await.map_err(|e| {
+            P2pError::MusicBrainz(format!("failed to parse MusicBrainz response: {e}"))
+        })
+    }
+
+    /// Look up a recording through `queue`, waiting for its turn. A lookup
+    /// that fails is saved to `mb_lookup_queue` for `track_id`, if given.
+    ///
+    /// `Ok(None)` means MusicBrainz has no match. A lookup that failed, or
+    /// a queue that has stopped, is an error: try again later.
+    pub async fn queue_lookup(
+        queue: &mpsc::Sender<LookupRequest>,
+        track_id: Option<uuid::Uuid>,
+        title: &str,
+        artist: &str,
+    ) -> Result<Option<MusicBrainzRecording>, P2pError> {
+        let stopped = || P2pError::MusicBrainz("lookup queue has stopped".into());
+        let (reply, response) = oneshot::channel();
+        let request = LookupRequest {
+            track_id,
+            title: title.to_string(),
+            artist: artist.to_string(),
+            reply,
+        };
+        queue.send(request).await.map_err(|_| stopped())?;
+        response.await.map_err(|_| stopped())?
+    }
+
+    /// Retry every lookup in `mb_lookup_queue`, oldest first. A match sets
+    /// the track's MusicBrainz ID; a match or a definite "no match" removes
+    /// the entry. Entries that fail [`MAX_QUEUED_LOOKUP_ATTEMPTS`] times are
+    /// dropped. Tracks pending verification are left to the node.
+    pub async fn process_retry_queue(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<RetryQueueSummary, P2pError> {
+        let queued = mb_lookup_queue::Entity::find()
+            .filter(mb_lookup_queue::Column::PendingVerification.eq(false))
+            .order_by_asc(mb_lookup_queue::Column::CreatedAt)
+            .all(db)
+            .await?;
+        let mut summary = RetryQueueSummary::default();
+        for entry in queued {
+            summary.processed += 1;
+            match self
+                .try_lookup_recording(&entry.title, &entry.artist_name)
+                .await
+            {
+                Ok(found) => {
+                    if let Some(recording) = found {
+                        let update = track::ActiveModel {
+                            id: Set(entry.track_id),
+                            musicbrainz_id: Set(Some(recording.id)),
+                            ..Default::default()
+                        };
+                        update.update(db).await?;
+                        summary.resolved += 1;
+                    } else {
+                        summary.not_found += 1;
+                    }
+                    mb_lookup_queue::Entity::delete_by_id(entry.track_id)
+                        .exec(db)
+                        .await?;
+                }
+                Err(e) => {
+                    summary.failed += 1;
+                    if entry.attempts + 1 >= MAX_QUEUED_LOOKUP_ATTEMPTS {
+                        warn!(track_id = %entry.track_id, "giving up on MusicBrainz lookup: {e}");
+                        mb_lookup_queue::Entity::delete_by_id(entry.track_id)
+                            .exec(db)
+                            .await?;
+                    } else {
+                        let update = mb_lookup_queue::ActiveModel {
+                            track_id: Set(entry.track_id),
+                            attempts: Set(entry.attempts + 1),
+                            last_error: Set(Some(e.to_string())),
+                            updated_at: Set(chrono::Utc::now().into()),
+                            ..Default::default()
+                        };
+                        update.update(db).await?;
+                    }
+                }
+            }
+        }
+        info!(
+            processed = summary.processed,
+            resolved = summary.resolved,
+            not_found = summary.not_found,
+            failed = summary.failed,
+            "processed MusicBrainz retry queue"
+        );
+        Ok(summary)
+    }
+
+    /// Download an artist image found during metadata lookup (usually a
+    /// Wikimedia URL). Returns `None` on any error or if the image is larger
+    /// than 5 MiB.
+    pub async fn fetch_image(&self, url: &str) -> Option<bytes::Bytes> {
+        let resp = match self.http.get(url).send().await {
+            Ok(r) => r,
+            Err(e) => {
+                warn!(%url, "artist image request failed: {e}");
+                return None;
+            }
+        };
+        if !resp.status().is_success() {
+            warn!(%url, status = %resp.status(), "artist image request returned error");
+            return None;
+        }
+        if resp
+            .content_length()
+            .is_some_and(|len| len > MAX_IMAGE_BYTES as u64)
+        {
+            warn!(%url, "artist image too large, skipping");
+            return None;
+        }
+        let body = resp.bytes().await.ok()?;
+        if body.len() > MAX_IMAGE_BYTES {
+            warn!(%url, "artist image too large, skipping");
+            return None;
+        }
+        Some(body)
     }
 }
 
@@ -194,6 +653,73 @@ impl Default for MusicBrainzClient {
     }
 }
 
+// ── Lookup queue ────────────────────────────────────────────────────
+
+/// A lookup waiting in a [`MusicBrainzQueue`].
+pub struct LookupRequest {
+    /// Track to queue in `mb_lookup_queue` if the lookup fails
+    pub track_id: Option<uuid::Uuid>,
+    pub title: String,
+    pub artist: String,
+    /// Receives the best match, `None` if there is none, or why the lookup
+    /// failed
+    pub reply: oneshot::Sender<Result<Option<MusicBrainzRecording>, P2pError>>,
+}
+
+/// Runs queued lookups one at a time; their requests are spaced out like
+/// every other MusicBrainz request. Stops once every sender is dropped.
+pub struct MusicBrainzQueue {
+    client: Arc<MusicBrainzClient>,
+    db: DatabaseConnection,
+    requests: mpsc::Receiver<LookupRequest>,
+}
+
+impl MusicBrainzQueue {
+    /// Create a queue for `client` and the sender to submit lookups with.
+    pub fn new(
+        client: Arc<MusicBrainzClient>,
+        db: DatabaseConnection,
+    ) -> (mpsc::Sender<LookupRequest>, Self) {
+        let (tx, requests) = mpsc::channel(LOOKUP_QUEUE_CAPACITY);
+        (
+            tx,
+            Self {
+                client,
+                db,
+                requests,
+            },
+        )
+    }
+
+    pub async fn run(mut self) {
+        while let Some(request) = self.requests.recv().await {
+            if request.reply.is_closed() {
+                continue;
+            }
+            let found = self
+                .client
+                .try_lookup_recording(&request.title, &request.artist)
+                .await;
+            if let Err(ref e) = found {
+                if let Some(track_id) = request.track_id {
+                    // Retried by the next enrich-all admin run
+                    warn!(%track_id, "{e}, queuing for retry");
+                    if let Err(e) =
+                        queue_failed_lookup(&self.db, track_id, &request.title, &request.artist, e)
+                            .await
+                    {
+                        warn!(%track_id, "failed to queue MusicBrainz lookup: {e}");
+                    }
+                } else {
+                    warn!(title = %request.title, "{e}");
+                }
+            }
+            let _ = request.reply.send(found);
+        }
+        debug!("MusicBrainz lookup queue stopped");
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -317,6 +843,38 @@ mod tests {
         assert!(rec.score >= 80);
     }
 
+    #[tokio::test]
+    async fn test_fetch_image() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path_regex(r"/img/ok\.jpg"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0xFFu8, 0xD8, 0xFF]))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex(r"/img/big\.jpg"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0u8; MAX_IMAGE_BYTES + 1]))
+            .mount(&server)
+            .await;
+
+        let client = MusicBrainzClient::with_base_url(&server.uri());
+        let img = client
+            .fetch_image(&format!("{}/img/ok.jpg", server.uri()))
+            .await
+            .expect("image should download");
+        assert_eq!(img.as_ref(), &[0xFF, 0xD8, 0xFF]);
+
+        assert!(client
+            .fetch_image(&format!("{}/img/big.jpg", server.uri()))
+            .await
+            .is_none());
+        assert!(client
+            .fetch_image(&format!("{}/img/missing.jpg", server.uri()))
+            .await
+            .is_none());
+    }
+
     #[tokio::test]
     async fn test_lookup_recording_low_score() {
         let server = MockServer::start().await;
@@ -563,6 +1121,342 @@ mod tests {
         assert!(result.is_none());
     }
 
+    // ── lookup_artist / lookup_release ───────────────────────────────
+
+    #[tokio::test]
+    async fn test_lookup_artist() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path_regex(r"/artist.*"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"artists": [
+                    {"id": "low", "name": "Queens", "score": 50},
+                    {"id": "queen-mbid", "name": "Queen", "score": 100}
+                ]}"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = MusicBrainzClient::with_base_url(&format!("{}/ws/2", server.uri()));
+        let artist = client.lookup_artist("Queen").await.unwrap();
+        assert_eq!(artist.id, "queen-mbid");
+        assert_eq!(artist.name, "Queen");
+        assert_eq!(artist.score, 100);
+    }
+
+    #[tokio::test]
+    async fn test_lookup_artist_no_match() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path_regex(r"/artist.*"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(
+                    r#"{"artists": [{"id": "low", "name": "Queens", "score": 50}]}"#,
+                ),
+            )
+            .mount(&server)
+            .await;
+
+        let client = MusicBrainzClient::with_base_url(&format!("{}/ws/2", server.uri()));
+        assert!(client.lookup_artist("Queen").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_release() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path_regex(r"/release.*"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"releases": [{
+                    "id": "release-mbid",
+                    "title": "A Night at the Opera",
+                    "score": 95,
+                    "date": "1975-11-21"
+                }]}"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = MusicBrainzClient::with_base_url(&format!("{}/ws/2", server.uri()));
+        let release = client
+            .lookup_release("A Night at the Opera", "Queen")
+            .await
+            .unwrap();
+        assert_eq!(release.id, "release-mbid");
+        assert_eq!(
+            release.release_date,
+            chrono::NaiveDate::from_ymd_opt(1975, 11, 21)
+        );
+        assert_eq!(release.year, Some(1975));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_release_partial_date() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path_regex(r"/release.*"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"releases": [{"id": "r", "title": "T", "score": 90, "date": "1975"}]}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let client = MusicBrainzClient::with_base_url(&format!("{}/ws/2", server.uri()));
+        let release = client.lookup_release("T", "A").await.unwrap();
+        assert_eq!(release.release_date, None);
+        assert_eq!(release.year, Some(1975));
+    }
+
+    // ── Retries ──────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_lookup_recording_retries_server_errors() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path_regex(r"/recording.*"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex(r"/recording.*"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(mb_response_json(95, None, true, true)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client =
+            MusicBrainzClient::with_config(&format!("{}/ws/2", server.uri()), "test", 3, 10);
+        let rec = client
+            .try_lookup_recording("Bohemian Rhapsody", "Queen")
+            .await
+            .unwrap();
+        assert_eq!(rec.unwrap().id, "test-mbid-123");
+    }
+
+    #[tokio::test]
+    async fn test_lookup_recording_retries_exhausted() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path_regex(r"/recording.*"))
+            .respond_with(ResponseTemplate::new(429))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let client =
+            MusicBrainzClient::with_config(&format!("{}/ws/2", server.uri()), "test", 2, 10);
+        let err = client.try_lookup_recording("T", "A").await.unwrap_err();
+        assert!(matches!(err, P2pError::MusicBrainz(_)));
+        assert!(err.to_string().contains("429"));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_recording_client_error_not_retried() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path_regex(r"/recording.*"))
+            .respond_with(ResponseTemplate::new(400))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client =
+            MusicBrainzClient::with_config(&format!("{}/ws/2", server.uri()), "test", 3, 10);
+        assert!(client.try_lookup_recording("T", "A").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_throttled_lookup_does_not_hold_up_others() {
+        let throttled = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path_regex(r"/recording.*"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "30"))
+            .mount(&throttled)
+            .await;
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path_regex(r"/recording.*"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(mb_response_json(95, None, true, true)),
+            )
+            .mount(&server)
+            .await;
+
+        let waiting =
+            MusicBrainzClient::with_config(&format!("{}/ws/2", throttled.uri()), "test", 1, 10);
+        let waiting = tokio::spawn(async move { waiting.try_lookup_recording("T", "A").await });
+        while throttled.received_requests().await.unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // Served while the throttled lookup sleeps out its Retry-After
+        let client =
+            MusicBrainzClient::with_config(&format!("{}/ws/2", server.uri()), "test", 0, 10);
+        let rec = tokio::time::timeout(
+            Duration::from_secs(10),
+            client.try_lookup_recording("Bohemian Rhapsody", "Queen"),
+        )
+        .await
+        .expect("lookup waited for the throttled one")
+        .unwrap();
+        assert_eq!(rec.unwrap().id, "test-mbid-123");
+        assert!(!waiting.is_finished());
+        waiting.abort();
+    }
+
+    #[tokio::test]
+    async fn test_requests_spaced_by_rate_limit() {
+        let first = {
+            wait_for_rate_limit().await;
+            Instant::now()
+        };
+        wait_for_rate_limit().await;
+        assert!(first.elapsed() >= MB_REQUEST_INTERVAL);
+    }
+
+    #[test]
+    fn test_is_retryable_status() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(!is_retryable_status(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, "5".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(5)));
+
+        headers.insert(RETRY_AFTER, "3600".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(MAX_RETRY_AFTER));
+
+        headers.insert(
+            RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_backoff_delay_doubles() {
+        let initial = Duration::from_secs(1);
+        assert_eq!(backoff_delay(initial, 0), Duration::from_secs(1));
+        assert_eq!(backoff_delay(initial, 1), Duration::from_secs(2));
+        assert_eq!(backoff_delay(initial, 3), Duration::from_secs(8));
+        // Late attempts saturate instead of overflowing
+        assert_eq!(
+            backoff_delay(initial, 64),
+            Duration::from_secs(u32::MAX as u64)
+        );
+    }
+
+    // ── Lookup queue ─────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_queue_lookup_answers_each_request() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path_regex(r"/recording.*"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(mb_response_json(95, None, true, true)),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = Arc::new(MusicBrainzClient::with_base_url(&format!(
+            "{}/ws/2",
+            server.uri()
+        )));
+        let (queue, task) = MusicBrainzQueue::new(client, DatabaseConnection::Disconnected);
+        let handle = tokio::spawn(task.run());
+
+        let (first, second) = tokio::join!(
+            MusicBrainzClient::queue_lookup(&queue, None, "Bohemian Rhapsody", "Queen"),
+            MusicBrainzClient::queue_lookup(&queue, None, "Bohemian Rhapsody", "Queen"),
+        );
+        assert_eq!(first.unwrap().unwrap().id, "test-mbid-123");
+        assert_eq!(second.unwrap().unwrap().id, "test-mbid-123");
+
+        // The queue stops once every sender is gone
+        drop(queue);
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_queue_lookup_failure_is_error() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path_regex(r"/recording.*"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let client = Arc::new(MusicBrainzClient::with_base_url(&format!(
+            "{}/ws/2",
+            server.uri()
+        )));
+        // Queuing the failed lookup fails too without a database, which is
+        // only logged
+        let (queue, task) = MusicBrainzQueue::new(client, DatabaseConnection::Disconnected);
+        tokio::spawn(task.run());
+
+        // A failed lookup is not "no match"
+        let found =
+            MusicBrainzClient::queue_lookup(&queue, Some(uuid::Uuid::new_v4()), "T", "A").await;
+        assert!(matches!(found, Err(P2pError::MusicBrainz(_))));
+    }
+
+    #[tokio::test]
+    async fn test_queue_lookup_no_match_is_none() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path_regex(r"/recording.*"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"recordings": []}"#))
+            .mount(&server)
+            .await;
+
+        let client = Arc::new(MusicBrainzClient::with_base_url(&format!(
+            "{}/ws/2",
+            server.uri()
+        )));
+        let (queue, task) = MusicBrainzQueue::new(client, DatabaseConnection::Disconnected);
+        tokio::spawn(task.run());
+
+        let found = MusicBrainzClient::queue_lookup(&queue, None, "T", "A").await;
+        assert!(found.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_queue_lookup_stopped_queue() {
+        let client = Arc::new(MusicBrainzClient::with_base_url("http://127.0.0.1:1"));
+        let (queue, task) = MusicBrainzQueue::new(client, DatabaseConnection::Disconnected);
+        drop(task);
+
+        assert!(MusicBrainzClient::queue_lookup(&queue, None, "T", "A")
+            .await
+            .is_err());
+    }
+
     // ── Internal deserialization structs ──────────────────────────────
 
     #[test]
@@ -6,7 +6,7 @@
 //! - Fetches tracks from remote peers by hash
 //! - Exposes the local EndpointId for discovery
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::net::{Ipv4Addr, SocketAddrV4};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -14,39 +14,260 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use dashmap::{DashMap, DashSet};
 use iroh::endpoint::Connection;
-use iroh::{Endpoint, EndpointAddr, EndpointId, SecretKey};
+use iroh::{Endpoint, EndpointAddr, EndpointId, RelayMap, RelayMode, RelayUrl, SecretKey};
 use iroh_blobs::store::fs::FsStore;
 use iroh_blobs::{Hash, HashAndFormat};
+use rand::seq::SliceRandom;
 use rand::SeedableRng;
+use sea_orm::sea_query::Query;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
-    Set,
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DatabaseTransaction, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set,
 };
-use soundtime_db::entities::{album, artist, remote_track, track};
-use tokio::sync::watch;
+use soundtime_db::entities::{
+    album, artist, blocked_hash, mb_lookup_queue, peer_track_grant, pinned_track, published_hash,
+    remote_track, track,
+};
+use tokio::sync::{mpsc, watch};
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use crate::blob_cache::BlobCache;
+use crate::bandwidth::{UploadLimiter, UPLOAD_CHUNK_SIZE};
+use crate::blob_cache::{self, BlobCache};
+use crate::blob_gc::{self, GcReport};
 use crate::blocked::is_peer_blocked;
-use crate::connection_pool::ConnectionPool;
-use crate::discovery::PeerRegistry;
+use crate::catalog_ack::{
+    deliver_page, CatalogPageAck, CatalogPageHeader, CatalogPageSink, CatalogSyncHistory,
+    CatalogSyncRecord,
+};
+use crate::catalog_checksum::{CatalogChecksum, ChecksumCache};
+use crate::catalog_progress::{CatalogSyncProgress, CatalogSyncTracker};
+use crate::catalog_rate::{
+    PeerCatalogSync, CATALOG_RATE_LIMIT_PAUSE, DEFAULT_CATALOG_SYNC_PAGES_PER_MINUTE,
+};
+use crate::conn_limit::{IpConnectionLimiter, DEFAULT_MAX_CONNECTIONS_PER_IP};
+use crate::connection_pool::{
+    ConnectionPool, MessagePriority, DEFAULT_MAX_QUEUE_DEPTH, MAX_IDLE_SECS,
+};
+use crate::discovery::{order_by_latency, CatalogSyncPlan, PeerInfo, PeerRegistry, PeerTrust};
 use crate::error::P2pError;
-use crate::musicbrainz::MusicBrainzClient;
-use crate::search_index::{BloomFilterData, SearchIndex};
-use crate::track_health::{spawn_health_monitor, PeerTrackInfo, TrackFetcher, TrackHealthManager};
+use crate::events::{P2pEvent, P2pEventBus};
+use crate::gossip::{self, GossipSeen, DEFAULT_GOSSIP_TTL};
+use crate::hooks::{NodeHooks, TrackAnnouncedHook};
+use crate::library_sync::SyncGate;
+use crate::message_rate::{
+    PeerMessageRate, DELETE_TRACKS_PER_MINUTE, PLAY_COUNT_UPDATES_PER_MINUTE,
+};
+use crate::metrics::P2P_METRICS;
+use crate::moderation::{self, incoming_block_status, BlockStatus, BlockedPath, HashBlocklist};
+use crate::musicbrainz::{self, LookupRequest, MusicBrainzClient, MusicBrainzQueue};
+use crate::outgoing_sync::{OutgoingSync, OutgoingSyncGuard};
+use crate::partial::PartialDownload;
+use crate::peer_filter::{self, PeerFilter};
+use crate::popularity::{self, PopularityEntry};
+use crate::prewarm::{self, PrewarmScope, MAX_PREWARM_PER_CYCLE};
+use crate::published::{PublishedHashes, PublishedKind};
+use crate::replication_policy::{
+    PeerRejections, RejectReason, RejectedAnnouncement, RejectionLog, ReplicationPolicy,
+};
+use crate::search_cache::{
+    SearchResultCache, DEFAULT_SEARCH_CACHE_MAX_ENTRIES, DEFAULT_SEARCH_CACHE_TTL_SECS,
+};
+use crate::search_index::{
+    BloomDeltaData, BloomFilterData, SearchIndex, DEFAULT_BLOOM_CAPACITY, FALSE_POSITIVE_RATE,
+};
+use crate::search_session::{
+    decode_cursor, encode_cursor, PeerSearcher, SearchPage, SearchSession, SearchSessionCache,
+};
+use crate::seed_peers;
+use crate::stats::{P2pStats, P2pStatsCollector};
+use crate::stream_range::{
+    clamp_range, export_verified_range, import_verified_range, read_blob_range, TrackRange,
+    MAX_STREAM_RANGE_BYTES,
+};
+use crate::swarm::{swarm_fetch, RangeSource, MAX_SWARM_SOURCES, MIN_SWARM_BLOB_SIZE};
+use crate::sync_checkpoint::{CheckpointFile, SyncCheckpoint};
+use crate::track_access;
+use crate::track_cleanup::CleanupReport;
+use crate::track_health::{
+    fetch_verified, quality_score, save_recovery_attempt, select_best_copy, sort_by_quality,
+    spawn_health_monitor, verify_blob, BatchCheckResult, HealthMonitorConfig, PeerTrackInfo,
+    RecoveryAttempt, TrackFetcher, TrackHealthManager,
+};
 
-/// ALPN protocol identifier for SoundTime P2P
+/// ALPN protocol identifier for SoundTime P2P (protocol v1)
 pub const SOUNDTIME_ALPN: &[u8] = b"soundtime/p2p/1";
 
-/// Maximum allowed size for a single P2P message (64 MiB).
-/// CatalogSync messages can be large for instances with many tracks.
-const MAX_P2P_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+/// ALPN protocol identifier for SoundTime P2P protocol v2
+pub const SOUNDTIME_ALPN_V2: &[u8] = b"soundtime/p2p/2";
+
+/// ALPNs advertised by this node, most preferred first.
+pub const SUPPORTED_ALPNS: &[&[u8]] = &[SOUNDTIME_ALPN_V2, SOUNDTIME_ALPN];
+
+/// Wire protocol version negotiated with a peer via ALPN.
+///
+/// v1 peers only understand the original message set; messages introduced
+/// later report a higher [`P2pMessage::min_protocol_version`] and are never
+/// sent to (or accepted from) a v1 connection.
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,
+)]
+pub enum ProtocolVersion {
+    V1 = 1,
+    V2 = 2,
+}
+
+impl ProtocolVersion {
+    /// Newest protocol version this build speaks.
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V2;
+
+    /// Map a negotiated ALPN to a protocol version.
+    pub fn from_alpn(alpn: &[u8]) -> Option<Self> {
+        match alpn {
+            a if a == SOUNDTIME_ALPN => Some(ProtocolVersion::V1),
+            a if a == SOUNDTIME_ALPN_V2 => Some(ProtocolVersion::V2),
+            _ => None,
+        }
+    }
+
+    /// ALPN identifier for this version.
+    pub fn alpn(self) -> &'static [u8] {
+        match self {
+            ProtocolVersion::V1 => SOUNDTIME_ALPN,
+            ProtocolVersion::V2 => SOUNDTIME_ALPN_V2,
+        }
+    }
+
+    /// Numeric version (1, 2, …).
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+}
+
+/// Default maximum size of a single incoming P2P message (64 MiB).
+/// CatalogSync messages can be large for instances with many tracks. Each
+/// announcement may carry up to [`MAX_WAVEFORM_SAMPLES`] waveform points
+/// (roughly 20 KiB of JSON), so a 2000-track page stays well under the limit.
+const DEFAULT_MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Smallest accepted `P2P_MAX_MESSAGE_BYTES` (1 MiB).
+const MIN_MESSAGE_BYTES_LIMIT: usize = 1024 * 1024;
+
+/// Largest accepted `P2P_MAX_MESSAGE_BYTES` (512 MiB).
+const MAX_MESSAGE_BYTES_LIMIT: usize = 512 * 1024 * 1024;
+
+/// Waveform points included in a track announcement; longer waveforms are
+/// truncated.
+pub const MAX_WAVEFORM_SAMPLES: usize = 2000;
+
+/// Capability: track announcements are signed and signatures are verified.
+pub const CAP_SIGNED_ANNOUNCEMENTS: &str = "signed-announcements";
+
+/// Capability: track announcements carry waveform data.
+pub const CAP_WAVEFORM_SYNC: &str = "waveform-sync";
+
+/// Capability: `FetchTrackRange` can be answered with a BAO slice the
+/// requester verifies against the content hash.
+pub const CAP_VERIFIED_RANGES: &str = "verified-ranges";
+
+/// Optional features advertised to peers in our `Pong`.
+pub const SUPPORTED_CAPABILITIES: &[&str] = &[
+    CAP_SIGNED_ANNOUNCEMENTS,
+    CAP_WAVEFORM_SYNC,
+    CAP_VERIFIED_RANGES,
+];
+
+/// How long to wait for a peer to acknowledge a `CatalogSyncPage`.
+const CATALOG_PAGE_ACK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Largest `CatalogSyncAck` read back, length prefix included.
+const CATALOG_PAGE_ACK_MAX_BYTES: usize = 64 * 1024;
 
-/// Maximum number of concurrent incoming P2P connections.
+/// How often replicated tracks pending MusicBrainz verification are looked
+/// up, besides right after one is stored.
+const MB_VERIFICATION_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Wait before looking up a pending track again after a failed lookup.
+const MB_VERIFICATION_RETRY_DELAY: chrono::Duration = chrono::Duration::minutes(5);
+
+/// Pending tracks looked up per verification pass.
+const MB_VERIFICATION_BATCH: u64 = 100;
+
+/// Longest `shutdown` waits for peers to receive our `Goodbye`.
+const GOODBYE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Longest `check_duplicate` waits for peers to answer.
+const DUPLICATE_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Largest reply accepted to a `CheckDuplicate`.
+const MAX_DUPLICATE_REPLY_BYTES: usize = 64 * 1024;
+
+/// Attempts made by `get_or_fetch_track` before giving up on a dropped fetch.
+const MAX_FETCH_ATTEMPTS: u32 = 3;
+
+/// Length prefix of a blob reply that carries a JSON `AccessDenied` instead
+/// of blob bytes (v2). No blob reply is this long.
+const ACCESS_DENIED_LEN: u32 = u32::MAX;
+
+/// Default maximum number of concurrent incoming P2P connections.
 const MAX_CONCURRENT_P2P_CONNECTIONS: usize = 64;
 
+/// Default number of PEX-discovered peers pinged per discovery cycle.
+const DEFAULT_PEX_BATCH_SIZE: usize = 10;
+
+/// Failed pings in a row after which a peer is evicted from the registry.
+const DEFAULT_PEER_EVICTION_THRESHOLD: u32 = 10;
+
+/// Days an offline peer is kept before it is pruned.
+const DEFAULT_PEER_OFFLINE_RETENTION_DAYS: u32 = 30;
+
+/// Default limit on the number of peers kept in the registry.
+const DEFAULT_MAX_PEERS: usize = 500;
+
+/// Default interval between keepalive passes over the connection pool.
+const DEFAULT_POOL_KEEPALIVE_SECS: u64 = 15;
+
+/// Default interval between QUIC keep-alive packets on every connection.
+const DEFAULT_KEEP_ALIVE_SECS: u64 = 30;
+
+/// Default minimum trigram similarity for fuzzy search matches (pg_trgm's
+/// own default).
+const DEFAULT_SEARCH_SIMILARITY_THRESHOLD: f32 = 0.3;
+
+/// Minimum time between blob garbage collection runs of the periodic loop.
+const BLOB_GC_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Maximum peer IDs sent in a single `PeerExchange` message.
+const MAX_PEX_PEERS: usize = 50;
+
+/// Maximum peer IDs waiting in the PEX queue; further IDs are dropped until
+/// the queue drains.
+const MAX_PEX_QUEUE_LEN: usize = 1000;
+
+/// Decode a track's stored waveform for an announcement, truncated to
+/// [`MAX_WAVEFORM_SAMPLES`] points.
+fn announced_waveform(stored: Option<&serde_json::Value>, hash: &str) -> Option<Vec<f32>> {
+    let mut waveform: Vec<f32> = match serde_json::from_value(stored?.clone()) {
+        Ok(w) => w,
+        Err(e) => {
+            debug!(%hash, "ignoring undecodable waveform data: {e}");
+            return None;
+        }
+    };
+    if waveform.len() > MAX_WAVEFORM_SAMPLES {
+        warn!(
+            %hash,
+            samples = waveform.len(),
+            max = MAX_WAVEFORM_SAMPLES,
+            "truncating waveform for track announcement"
+        );
+        waveform.truncate(MAX_WAVEFORM_SAMPLES);
+    }
+    Some(waveform)
+}
+
 /// Sanitize a string for use as a filesystem directory name.
 fn sanitize_for_path(name: &str) -> String {
     name.chars()
@@ -99,15 +320,278 @@ pub struct TrackAnnouncement {
     pub origin_node: String,
     /// BLAKE3 hash of the cover art blob (if any)
     pub cover_hash: Option<String>,
+    /// Chromaprint acoustic fingerprint (absent from older peers or when
+    /// `fpcalc` was unavailable on the origin)
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    /// Waveform peaks for the player, at most [`MAX_WAVEFORM_SAMPLES`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub waveform_data: Option<Vec<f32>>,
+    /// BLAKE3 hash of the artist image blob (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artist_image_hash: Option<String>,
+    /// Artist biography (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artist_bio: Option<String>,
+    /// EBU R128 integrated loudness in LUFS (if measured on the origin)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loudness_lufs: Option<f32>,
+    /// EBU R128 loudness range in LU (if measured on the origin)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dynamic_range: Option<f32>,
+    /// How the audio is encoded, e.g. "CBR 320kbps" (if known)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding_quality: Option<String>,
+    /// Base64 ed25519 signature by `origin_node` over the fields listed in
+    /// `signing_payload`. Absent from older peers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// Append a length-prefixed string to a signing payload.
+fn put_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u64).to_le_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+/// Append an optional value to a signing payload behind a presence byte.
+fn put_opt<T>(buf: &mut Vec<u8>, v: Option<T>, put: impl FnOnce(&mut Vec<u8>, T)) {
+    match v {
+        Some(v) => {
+            buf.push(1);
+            put(buf, v);
+        }
+        None => buf.push(0),
+    }
+}
+
+impl TrackAnnouncement {
+    /// Prefix of the signed bytes for announcements without loudness or
+    /// encoding analysis, naming the encoding below. Older peers sign and
+    /// verify only this layout. A change to the signed fields needs a new
+    /// version.
+    const SIGNING_DOMAIN_V1: &'static [u8] = b"soundtime-track-announcement-v1";
+
+    /// Prefix of the signed bytes when the announcement carries loudness or
+    /// encoding analysis, which the v1 layout does not cover.
+    const SIGNING_DOMAIN: &'static [u8] = b"soundtime-track-announcement-v2";
+
+    /// Whether any field only covered by the v2 layout is set.
+    fn has_analysis(&self) -> bool {
+        self.loudness_lufs.is_some()
+            || self.dynamic_range.is_some()
+            || self.encoding_quality.is_some()
+    }
+
+    /// Bytes covered by the signature. Announcements without analysis
+    /// fields use the v1 layout, so peers that predate those fields still
+    /// verify them; the rest use v2.
+    fn signing_payload(&self) -> Vec<u8> {
+        if self.has_analysis() {
+            self.signing_payload_v2()
+        } else {
+            self.signing_payload_v1()
+        }
+    }
+
+    /// v1 layout: [`Self::SIGNING_DOMAIN_V1`], then the catalog fields a
+    /// replica stores verbatim, in this fixed order. Strings are
+    /// length-prefixed, options carry a presence byte, numbers are
+    /// little-endian. Artwork, biography, waveform and album artist are not
+    /// covered: a reseeder republishes them from its own copy, so they need
+    /// not match what the origin sent.
+    fn signing_payload_v1(&self) -> Vec<u8> {
+        let mut buf = Self::SIGNING_DOMAIN_V1.to_vec();
+        self.put_catalog_fields(&mut buf);
+        buf
+    }
+
+    /// v2 layout: [`Self::SIGNING_DOMAIN`], the v1 fields, then loudness,
+    /// dynamic range and encoding quality.
+    fn signing_payload_v2(&self) -> Vec<u8> {
+        let mut buf = Self::SIGNING_DOMAIN.to_vec();
+        self.put_catalog_fields(&mut buf);
+        for n in [self.loudness_lufs, self.dynamic_range] {
+            put_opt(&mut buf, n, |b, n| {
+                b.extend_from_slice(&n.to_bits().to_le_bytes())
+            });
+        }
+        put_opt(&mut buf, self.encoding_quality.as_deref(), put_str);
+        buf
+    }
+
+    /// Fields shared by every signing layout.
+    fn put_catalog_fields(&self, buf: &mut Vec<u8>) {
+        put_str(buf, &self.hash);
+        put_str(buf, &self.origin_node);
+        put_str(buf, &self.title);
+        put_str(buf, &self.artist_name);
+        put_opt(buf, self.album_title.as_deref(), put_str);
+        buf.extend_from_slice(&self.duration_secs.to_bits().to_le_bytes());
+        put_str(buf, &self.format);
+        buf.extend_from_slice(&self.file_size.to_le_bytes());
+        put_opt(buf, self.genre.as_deref(), put_str);
+        for n in [self.year, self.track_number, self.disc_number] {
+            put_opt(buf, n, |b, n| b.extend_from_slice(&n.to_le_bytes()));
+        }
+        for n in [self.bitrate, self.sample_rate] {
+            put_opt(buf, n, |b, n| b.extend_from_slice(&n.to_le_bytes()));
+        }
+        put_opt(buf, self.fingerprint.as_deref(), put_str);
+    }
+
+    /// Sign the announcement with the origin node's secret key.
+    pub fn sign(&mut self, key: &SecretKey) {
+        let signature = key.sign(&self.signing_payload());
+        self.signature = Some(data_encoding::BASE64.encode(&signature.to_bytes()));
+    }
+
+    /// Check the signature against the public key in `origin_node`.
+    /// Unsigned announcements pass, for compatibility with older peers.
+    pub fn verify_signature(&self) -> Result<(), P2pError> {
+        let Some(ref encoded) = self.signature else {
+            return Ok(());
+        };
+        verify_origin_signature(&self.origin_node, &self.signing_payload(), encoded)
+    }
+}
+
+/// Check a base64 ed25519 `signature` of `payload` against the public key
+/// in `origin_node`.
+fn verify_origin_signature(
+    origin_node: &str,
+    payload: &[u8],
+    signature: &str,
+) -> Result<(), P2pError> {
+    let origin: EndpointId = origin_node
+        .parse()
+        .map_err(|_| P2pError::InvalidSignature("origin node is not a valid key".into()))?;
+    let bytes: [u8; 64] = data_encoding::BASE64
+        .decode(signature.as_bytes())
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| P2pError::InvalidSignature("malformed signature".into()))?;
+    let signature = iroh::Signature::from_bytes(&bytes);
+    origin
+        .verify(payload, &signature)
+        .map_err(|_| P2pError::InvalidSignature(format!("not signed by {origin_node}")))
+}
+
+/// Prefix of the bytes signed in a `DeleteTrack`.
+const DELETE_TRACK_SIGNING_DOMAIN: &[u8] = b"soundtime-delete-track-v1";
+
+/// How old a `DeleteTrack` may be when it arrives. Gossip crosses the
+/// network in seconds; older deletions are replays.
+pub const MAX_DELETE_TRACK_AGE: chrono::Duration = chrono::Duration::hours(1);
+
+/// Bytes covered by a `DeleteTrack` signature: [`DELETE_TRACK_SIGNING_DOMAIN`],
+/// the hash and origin as length-prefixed strings, then the deletion time
+/// in milliseconds, little-endian.
+fn delete_track_signing_payload(
+    hash: &str,
+    origin_node: &str,
+    deleted_at: &chrono::DateTime<chrono::Utc>,
+) -> Vec<u8> {
+    let mut buf = DELETE_TRACK_SIGNING_DOMAIN.to_vec();
+    put_str(&mut buf, hash);
+    put_str(&mut buf, origin_node);
+    buf.extend_from_slice(&deleted_at.timestamp_millis().to_le_bytes());
+    buf
+}
+
+/// Sign the deletion of our track `hash`.
+fn sign_delete_track(
+    key: &SecretKey,
+    hash: &str,
+    deleted_at: &chrono::DateTime<chrono::Utc>,
+) -> String {
+    let payload = delete_track_signing_payload(hash, &key.public().to_string(), deleted_at);
+    data_encoding::BASE64.encode(&key.sign(&payload).to_bytes())
+}
+
+/// Check a `DeleteTrack` received from `peer_id` at `now`. A signed
+/// deletion must verify against `origin_node`; an unsigned one is only
+/// believed straight from the origin. Deletions older than
+/// [`MAX_DELETE_TRACK_AGE`], or from the future, are refused as replays.
+fn check_delete_track(
+    hash: &str,
+    origin_node: &str,
+    deleted_at: &chrono::DateTime<chrono::Utc>,
+    signature: Option<&str>,
+    peer_id: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<(), P2pError> {
+    match signature {
+        Some(signature) => verify_origin_signature(
+            origin_node,
+            &delete_track_signing_payload(hash, origin_node, deleted_at),
+            signature,
+        )?,
+        None if peer_id == origin_node => {}
+        None => {
+            return Err(P2pError::InvalidSignature(format!(
+                "unsigned deletion relayed by {peer_id}"
+            )))
+        }
+    }
+    if *deleted_at < now - MAX_DELETE_TRACK_AGE || *deleted_at > now + MAX_DELETE_TRACK_AGE {
+        return Err(P2pError::InvalidSignature(format!(
+            "deletion dated {deleted_at} is outside the accepted window"
+        )));
+    }
+    Ok(())
+}
+
+/// Edited metadata of a local track, broadcast to peers that replicated it.
+/// Only `Some` fields changed; `None` leaves the peer's copy untouched.
+#[derive(Debug, Clone, Default)]
+pub struct TrackMetadataUpdate {
+    pub title: Option<String>,
+    pub artist_name: Option<String>,
+    pub album_title: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<i16>,
+    pub track_number: Option<i16>,
+}
+
+/// What processing one announced track did to the local catalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AnnouncementOutcome {
+    Inserted,
+    /// Already known, by content hash or acoustic fingerprint
+    Skipped,
+    /// Refused by the admin's replication policy
+    Rejected,
+    Failed,
 }
 
 /// Protocol message types exchanged between peers.
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub enum P2pMessage {
-    /// Request a track blob by its content hash
-    FetchTrack { hash: String },
+    /// Request a track blob by its content hash. `token` is a grant from
+    /// `AuthorizeTrackAccess`, needed for private tracks
+    FetchTrack {
+        hash: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token: Option<String>,
+    },
+    /// Request the bytes of a track blob starting at `offset`, to resume an
+    /// interrupted download or fetch one range of a swarm download. `length`
+    /// caps the reply; `None` means "to the end of the blob". With
+    /// `verified`, the reply is a BAO slice of the range and the blob's last
+    /// chunk instead of raw bytes (v2, [`CAP_VERIFIED_RANGES`])
+    FetchTrackRange {
+        hash: String,
+        offset: u64,
+        #[serde(default)]
+        length: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token: Option<String>,
+        #[serde(default)]
+        verified: bool,
+    },
     /// Announce a track with full metadata for catalog replication
-    AnnounceTrack(TrackAnnouncement),
+    AnnounceTrack(Box<TrackAnnouncement>),
     /// Response containing track data
     TrackData { hash: String, size: u64 },
     /// Peer discovery ping
@@ -119,11 +603,22 @@ pub enum P2pMessage {
         /// Software version of the peer (e.g. "0.1.42")
         #[serde(default)]
         version: Option<String>,
+        /// Optional features the peer supports (see [`SUPPORTED_CAPABILITIES`])
+        #[serde(default)]
+        capabilities: Vec<String>,
     },
     /// Peer exchange — share list of known peer EndpointIds for network discovery
     PeerExchange { peers: Vec<String> },
     /// Full catalog sync — send all locally-uploaded tracks to a peer at once
     CatalogSync(Vec<TrackAnnouncement>),
+    /// One page of a full catalog sync; the receiver answers on the same
+    /// stream with `CatalogSyncAck` (v2)
+    CatalogSyncPage {
+        header: CatalogPageHeader,
+        tracks: Vec<TrackAnnouncement>,
+    },
+    /// Per-track results for a `CatalogSyncPage` (v2)
+    CatalogSyncAck(CatalogPageAck),
     /// Incremental catalog delta — only tracks modified since the given timestamp
     CatalogDelta {
         since: chrono::DateTime<chrono::Utc>,
@@ -142,6 +637,9 @@ pub enum P2pMessage {
         query: String,
         /// Maximum results to return
         limit: u32,
+        /// Results to skip, for later pages (ignored by older peers)
+        #[serde(default)]
+        offset: u32,
     },
     /// Search results returned by a peer
     SearchResults {
@@ -152,6 +650,215 @@ pub enum P2pMessage {
         /// Total number of matches on this peer (may exceed returned results)
         total: u64,
     },
+    /// Metadata of an announced track was edited on its origin instance (v2)
+    UpdateTrackMetadata {
+        hash: String,
+        title: Option<String>,
+        artist_name: Option<String>,
+        album_title: Option<String>,
+        genre: Option<String>,
+        year: Option<i16>,
+        track_number: Option<i16>,
+    },
+    /// Liveness probe on a pooled connection; the receiver just finishes
+    /// the stream (v2)
+    KeepAlive,
+    /// Sent to online peers when the node shuts down, so they mark it
+    /// offline at once instead of timing out against it (v2)
+    Goodbye { node_id: String, reason: String },
+    /// An admin blocked a track blob network-wide; receivers stop serving it
+    /// (at once if the sender is one of their trusted moderators, otherwise
+    /// after review) (v2)
+    BlockHash { hash: String, reason: String },
+    /// A replicated track was played on the sender; the origin instance adds
+    /// `incremental_count` to its play count (v2)
+    PlayCountUpdate {
+        hash: String,
+        origin_node: String,
+        incremental_count: u32,
+    },
+    /// The sender's most played content hashes over a recent window,
+    /// replacing the counts it gossiped before (v2)
+    PopularityGossip { entries: Vec<PopularityEntry> },
+    /// Bits set in the sender's Bloom filter since its last full
+    /// `BloomExchange` (v2)
+    BloomDelta { delta: BloomDeltaData },
+    /// Ask a peer for its full Bloom filter, e.g. after a `BloomDelta` for a
+    /// generation we do not have (v2)
+    RequestBloom,
+    /// Ask a peer for its catalog checksum; answered on the same stream
+    /// with `CatalogChecksumResponse` (v2)
+    CatalogChecksumRequest,
+    /// BLAKE3 hash over the sender's sorted content hashes (v2)
+    CatalogChecksumResponse { checksum: String, track_count: u64 },
+    /// Grants `requester_node` access to the private track `hash`; `token`
+    /// goes into its `FetchTrack` requests to the sender (v2)
+    AuthorizeTrackAccess {
+        hash: String,
+        requester_node: String,
+        token: String,
+    },
+    /// Answer to a fetch of a private track without a valid grant (v2)
+    AccessDenied { hash: String },
+    /// Ask a peer whether it already has a track with this acoustic
+    /// fingerprint; answered on the same stream with `DuplicateFound`, or
+    /// the stream is finished without a reply if it has none. With
+    /// `duration_secs`, fingerprints are compared by similarity among the
+    /// tracks of about that duration; without it, only an identical
+    /// fingerprint matches (v2)
+    CheckDuplicate {
+        fingerprint: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        duration_secs: Option<f32>,
+    },
+    /// A public track on `source_node` matches the fingerprint of a
+    /// `CheckDuplicate` (v2)
+    DuplicateFound { hash: String, source_node: String },
+    /// `origin_node` deleted one of its tracks. Receivers mark their copy
+    /// unavailable and, while `ttl > 0`, forward the message with `ttl - 1`
+    /// so peers of peers hear about it too (v2)
+    DeleteTrack {
+        hash: String,
+        origin_node: String,
+        deleted_at: chrono::DateTime<chrono::Utc>,
+        ttl: u8,
+        /// Base64 ed25519 signature by `origin_node`, see
+        /// [`delete_track_signing_payload`]. Absent from older peers, whose
+        /// deletions are only applied when they arrive from the origin itself.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        signature: Option<String>,
+    },
+}
+
+impl P2pMessage {
+    /// Outbound queue priority for this message.
+    ///
+    /// Control and search traffic is high priority so it is never delayed by
+    /// bulk catalog replication.
+    pub fn priority(&self) -> MessagePriority {
+        match self {
+            P2pMessage::Ping
+            | P2pMessage::Pong { .. }
+            | P2pMessage::KeepAlive
+            | P2pMessage::Goodbye { .. }
+            | P2pMessage::BlockHash { .. }
+            | P2pMessage::PeerExchange { .. }
+            | P2pMessage::BloomExchange { .. }
+            | P2pMessage::BloomDelta { .. }
+            | P2pMessage::RequestBloom
+            | P2pMessage::CatalogChecksumRequest
+            | P2pMessage::CatalogChecksumResponse { .. }
+            | P2pMessage::AuthorizeTrackAccess { .. }
+            | P2pMessage::AccessDenied { .. }
+            | P2pMessage::CheckDuplicate { .. }
+            | P2pMessage::DuplicateFound { .. }
+            | P2pMessage::SearchQuery { .. }
+            | P2pMessage::SearchResults { .. }
+            | P2pMessage::FetchTrack { .. }
+            | P2pMessage::FetchTrackRange { .. } => MessagePriority::High,
+            P2pMessage::CatalogSync(_)
+            | P2pMessage::CatalogSyncPage { .. }
+            | P2pMessage::CatalogSyncAck(_)
+            | P2pMessage::CatalogDelta { .. }
+            | P2pMessage::AnnounceTrack(_)
+            | P2pMessage::RequestCatalog
+            | P2pMessage::UpdateTrackMetadata { .. }
+            | P2pMessage::PlayCountUpdate { .. }
+            | P2pMessage::PopularityGossip { .. }
+            | P2pMessage::DeleteTrack { .. }
+            | P2pMessage::TrackData { .. } => MessagePriority::Low,
+        }
+    }
+
+    /// Oldest protocol version that understands this message.
+    ///
+    /// Every variant added after v1 must return [`ProtocolVersion::V2`] (or
+    /// later) here so it is never sent to, or accepted from, older peers.
+    pub fn min_protocol_version(&self) -> ProtocolVersion {
+        match self {
+            P2pMessage::FetchTrack { .. }
+            | P2pMessage::AnnounceTrack(_)
+            | P2pMessage::TrackData { .. }
+            | P2pMessage::Ping
+            | P2pMessage::Pong { .. }
+            | P2pMessage::PeerExchange { .. }
+            | P2pMessage::CatalogSync(_)
+            | P2pMessage::CatalogDelta { .. }
+            | P2pMessage::RequestCatalog
+            | P2pMessage::BloomExchange { .. }
+            | P2pMessage::SearchQuery { .. }
+            | P2pMessage::SearchResults { .. } => ProtocolVersion::V1,
+            P2pMessage::FetchTrackRange { .. }
+            | P2pMessage::CatalogSyncPage { .. }
+            | P2pMessage::CatalogSyncAck(_)
+            | P2pMessage::UpdateTrackMetadata { .. }
+            | P2pMessage::KeepAlive
+            | P2pMessage::Goodbye { .. }
+            | P2pMessage::BlockHash { .. }
+            | P2pMessage::PlayCountUpdate { .. }
+            | P2pMessage::PopularityGossip { .. }
+            | P2pMessage::BloomDelta { .. }
+            | P2pMessage::RequestBloom
+            | P2pMessage::CatalogChecksumRequest
+            | P2pMessage::CatalogChecksumResponse { .. }
+            | P2pMessage::AuthorizeTrackAccess { .. }
+            | P2pMessage::AccessDenied { .. }
+            | P2pMessage::CheckDuplicate { .. }
+            | P2pMessage::DuplicateFound { .. }
+            | P2pMessage::DeleteTrack { .. } => ProtocolVersion::V2,
+        }
+    }
+
+    /// Variant name, used to break down traffic statistics by message type.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            P2pMessage::FetchTrack { .. } => "FetchTrack",
+            P2pMessage::FetchTrackRange { .. } => "FetchTrackRange",
+            P2pMessage::AnnounceTrack(_) => "AnnounceTrack",
+            P2pMessage::TrackData { .. } => "TrackData",
+            P2pMessage::Ping => "Ping",
+            P2pMessage::Pong { .. } => "Pong",
+            P2pMessage::PeerExchange { .. } => "PeerExchange",
+            P2pMessage::CatalogSync(_) => "CatalogSync",
+            P2pMessage::CatalogSyncPage { .. } => "CatalogSyncPage",
+            P2pMessage::CatalogSyncAck(_) => "CatalogSyncAck",
+            P2pMessage::CatalogDelta { .. } => "CatalogDelta",
+            P2pMessage::RequestCatalog => "RequestCatalog",
+            P2pMessage::BloomExchange { .. } => "BloomExchange",
+            P2pMessage::SearchQuery { .. } => "SearchQuery",
+            P2pMessage::SearchResults { .. } => "SearchResults",
+            P2pMessage::UpdateTrackMetadata { .. } => "UpdateTrackMetadata",
+            P2pMessage::KeepAlive => "KeepAlive",
+            P2pMessage::Goodbye { .. } => "Goodbye",
+            P2pMessage::BlockHash { .. } => "BlockHash",
+            P2pMessage::PlayCountUpdate { .. } => "PlayCountUpdate",
+            P2pMessage::PopularityGossip { .. } => "PopularityGossip",
+            P2pMessage::BloomDelta { .. } => "BloomDelta",
+            P2pMessage::RequestBloom => "RequestBloom",
+            P2pMessage::CatalogChecksumRequest => "CatalogChecksumRequest",
+            P2pMessage::CatalogChecksumResponse { .. } => "CatalogChecksumResponse",
+            P2pMessage::AuthorizeTrackAccess { .. } => "AuthorizeTrackAccess",
+            P2pMessage::AccessDenied { .. } => "AccessDenied",
+            P2pMessage::CheckDuplicate { .. } => "CheckDuplicate",
+            P2pMessage::DuplicateFound { .. } => "DuplicateFound",
+            P2pMessage::DeleteTrack { .. } => "DeleteTrack",
+        }
+    }
+
+    /// Whether a peer on the given protocol version understands this message.
+    pub fn supported_by(&self, version: ProtocolVersion) -> bool {
+        self.min_protocol_version() <= version
+    }
+}
+
+/// A track on another instance with the same acoustic fingerprint as a
+/// local upload, reported by [`P2pNode::check_duplicate`].
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NetworkDuplicate {
+    /// BLAKE3 content hash of the matching track
+    pub hash: String,
+    /// The peer EndpointId that has it
+    pub source_node: String,
 }
 
 /// A lightweight search result item returned by distributed search.
@@ -182,6 +889,83 @@ pub struct SearchResultItem {
     pub musicbrainz_id: Option<String>,
     /// Relevance score (ts_rank or similar)
     pub relevance: f32,
+    /// Plays on the source instance, including those reported by peers
+    /// (0 from peers that predate it)
+    #[serde(default)]
+    pub play_count: i64,
+    /// EBU R128 integrated loudness in LUFS (if measured)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loudness_lufs: Option<f32>,
+    /// EBU R128 loudness range in LU (if measured)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dynamic_range: Option<f32>,
+    /// How the audio is encoded, e.g. "CBR 320kbps" (if known)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding_quality: Option<String>,
+}
+
+/// How much play counts weigh in search ranking: the score is
+/// `relevance * (1 + POPULARITY_WEIGHT * ln(1 + play_count))`.
+const POPULARITY_WEIGHT: f32 = 0.1;
+
+/// Largest play count increment accepted in one `PlayCountUpdate`.
+const MAX_PLAY_COUNT_INCREMENT: u32 = 1000;
+
+/// Ranking score of a search result: its relevance, boosted by popularity.
+fn search_score(item: &SearchResultItem) -> f32 {
+    item.relevance * (1.0 + POPULARITY_WEIGHT * (item.play_count.max(0) as f32).ln_1p())
+}
+
+/// Sort merged search results by [`search_score`] (highest first) and keep
+/// only the best-ranked result per content hash.
+pub(crate) fn rank_search_results(results: &mut Vec<SearchResultItem>) {
+    results.sort_by(|a, b| {
+        search_score(b)
+            .partial_cmp(&search_score(a))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let mut seen_hashes = std::collections::HashSet::new();
+    results.retain(|r| seen_hashes.insert(r.hash.clone()));
+}
+
+/// Decode the reply to a `CheckDuplicate`: nothing when the peer has no
+/// match, otherwise one length-prefixed `DuplicateFound`.
+fn parse_duplicate_reply(bytes: &[u8]) -> Result<Option<NetworkDuplicate>, P2pError> {
+    if bytes.is_empty() {
+        return Ok(None);
+    }
+    let Some((len_buf, body)) = bytes.split_first_chunk::<4>() else {
+        return Err(P2pError::Connection(
+            "truncated duplicate check reply".into(),
+        ));
+    };
+    let msg_len = u32::from_be_bytes(*len_buf) as usize;
+    let Some(body) = body.get(..msg_len) else {
+        return Err(P2pError::Connection(
+            "truncated duplicate check reply".into(),
+        ));
+    };
+    match serde_json::from_slice(body)? {
+        P2pMessage::DuplicateFound { hash, source_node } => {
+            Ok(Some(NetworkDuplicate { hash, source_node }))
+        }
+        other => Err(P2pError::Connection(format!(
+            "unexpected reply to duplicate check: {}",
+            other.kind()
+        ))),
+    }
+}
+
+/// Search text for the trigram fallback: whitespace collapsed, and empty
+/// when fewer than 3 characters remain, since such short strings share
+/// trigrams with almost anything.
+pub(crate) fn fuzzy_query_text(query: &str) -> String {
+    let text = query.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() < 3 {
+        String::new()
+    } else {
+        text
+    }
 }
 
 /// Configuration for the P2P node.
@@ -203,6 +987,118 @@ pub struct P2pConfig {
     pub audio_storage_path: PathBuf,
     /// Optional separate directory for metadata files (covers, etc.)
     pub metadata_storage_path: Option<PathBuf>,
+    /// Upload cap in bytes/sec for blobs served to peers, shared by all
+    /// connections (0 = unlimited)
+    pub max_upload_bps: u64,
+    /// Upload cap in bytes/sec applied to each peer individually (0 = unlimited)
+    pub max_upload_bps_per_peer: u64,
+    /// File the local Bloom filter is persisted to between restarts
+    pub bloom_persist_path: PathBuf,
+    /// Target false positive rate of the local Bloom filter
+    pub bloom_fpr: f64,
+    /// Items the local Bloom filter is sized for at startup
+    pub bloom_expected_items: u32,
+    /// Maximum concurrent incoming connections across all peers
+    pub max_concurrent_connections: usize,
+    /// Maximum concurrent incoming connections from a single IP (0 = unlimited)
+    pub max_connections_per_ip: u32,
+    /// Peers learned through peer exchange that are pinged per cycle; the
+    /// rest wait in a queue for the next periodic tick
+    pub pex_batch_size: usize,
+    /// Hops a `DeleteTrack` travels from its origin (0 = direct peers only)
+    pub gossip_ttl: u8,
+    /// Seconds between keepalive probes of pooled outbound connections
+    /// (0 = disabled)
+    pub pool_keepalive_secs: u64,
+    /// Pooled connections unused for this many seconds are evicted
+    pub pool_idle_ttl_secs: u64,
+    /// Messages a peer may have waiting in its outbound queues before new
+    /// low-priority (catalog) messages to it are dropped
+    pub max_peer_queue_depth: usize,
+    /// Catalog sync pages accepted from a single peer per minute
+    pub catalog_sync_pages_per_minute: u32,
+    /// Seconds between QUIC keep-alive packets, so NATs and firewalls keep
+    /// idle relay and peer connections open. Online peers not heard from
+    /// for three intervals are pinged each cycle (0 = disabled)
+    pub keep_alive_interval_secs: u64,
+    /// Largest incoming P2P message accepted, in bytes
+    pub max_message_bytes: usize,
+    /// Self-hosted relay servers to use instead of n0's public relays
+    pub relay_urls: Vec<RelayUrl>,
+    /// Skip n0's relays and pkarr/DNS publishers (for air-gapped deployments)
+    pub disable_default_discovery: bool,
+    /// Self-hosted pkarr relay / DNS discovery server to publish to and
+    /// resolve peers from
+    pub dns_discovery_url: Option<reqwest::Url>,
+    /// Mark replicated copies of a blocked hash unavailable, not just stop
+    /// serving it
+    pub hide_blocked_tracks: bool,
+    /// Failed pings in a row after which a peer is forgotten (0 = never)
+    pub peer_eviction_threshold: u32,
+    /// Days since a peer was last seen after which an offline peer is
+    /// forgotten, unless it is a seed or trusted (0 = never)
+    pub peer_offline_retention_days: u32,
+    /// Most peers kept in the registry (0 = no limit). When full, a new
+    /// peer displaces an offline or quarantined one; seed and trusted peers
+    /// are never displaced.
+    pub max_peers: usize,
+    /// Minimum trigram similarity of a title or artist name to a search
+    /// query when full-text search finds nothing
+    pub search_similarity_threshold: f32,
+    /// Seconds distributed search results are cached (0 = disabled)
+    pub search_cache_ttl_secs: u64,
+    /// Most queries whose results are cached at once
+    pub search_cache_max_entries: usize,
+    /// Delete unreferenced blobs from the blob store once an hour
+    pub blob_gc_enabled: bool,
+    /// Serve replicated blobs cached here to peers and announce them with
+    /// their original origin, until the blob cache evicts them
+    pub reseed_replicated: bool,
+    /// Fetch the blobs of favorited and playlisted P2P tracks on every
+    /// periodic refresh, before anyone plays them
+    pub prewarm_enabled: bool,
+    /// Which tracks pre-warming covers
+    pub prewarm_scope: PrewarmScope,
+}
+
+/// Which relay servers the endpoint uses, derived from [`P2pConfig`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RelayPlan {
+    /// n0's public relay servers
+    Default,
+    /// Relays from `P2P_RELAY_URLS`
+    Custom(Vec<RelayUrl>),
+    /// No relay: default discovery is disabled and none is configured
+    Disabled,
+}
+
+impl RelayPlan {
+    /// Short name shown in the status API.
+    pub fn mode(&self) -> &'static str {
+        match self {
+            RelayPlan::Default => "default",
+            RelayPlan::Custom(_) => "custom",
+            RelayPlan::Disabled => "disabled",
+        }
+    }
+
+    /// The configured relay URLs (empty unless [`RelayPlan::Custom`]).
+    pub fn urls(&self) -> Vec<String> {
+        match self {
+            RelayPlan::Custom(urls) => urls.iter().map(|u| u.to_string()).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn relay_mode(&self) -> RelayMode {
+        match self {
+            RelayPlan::Default => RelayMode::Default,
+            RelayPlan::Custom(urls) => {
+                RelayMode::Custom(urls.iter().cloned().collect::<RelayMap>())
+            }
+            RelayPlan::Disabled => RelayMode::Disabled,
+        }
+    }
 }
 
 impl Default for P2pConfig {
@@ -216,6 +1112,35 @@ impl Default for P2pConfig {
             seed_peers: Vec::new(),
             audio_storage_path: PathBuf::from("data/music"),
             metadata_storage_path: None,
+            max_upload_bps: 0,
+            max_upload_bps_per_peer: 0,
+            bloom_persist_path: PathBuf::from("data/p2p/bloom.bin"),
+            bloom_fpr: FALSE_POSITIVE_RATE,
+            bloom_expected_items: DEFAULT_BLOOM_CAPACITY as u32,
+            max_concurrent_connections: MAX_CONCURRENT_P2P_CONNECTIONS,
+            max_connections_per_ip: DEFAULT_MAX_CONNECTIONS_PER_IP,
+            pex_batch_size: DEFAULT_PEX_BATCH_SIZE,
+            gossip_ttl: DEFAULT_GOSSIP_TTL,
+            pool_keepalive_secs: DEFAULT_POOL_KEEPALIVE_SECS,
+            keep_alive_interval_secs: DEFAULT_KEEP_ALIVE_SECS,
+            pool_idle_ttl_secs: MAX_IDLE_SECS,
+            max_peer_queue_depth: DEFAULT_MAX_QUEUE_DEPTH,
+            catalog_sync_pages_per_minute: DEFAULT_CATALOG_SYNC_PAGES_PER_MINUTE,
+            max_message_bytes: DEFAULT_MAX_MESSAGE_BYTES,
+            relay_urls: Vec::new(),
+            disable_default_discovery: false,
+            dns_discovery_url: None,
+            hide_blocked_tracks: true,
+            peer_eviction_threshold: DEFAULT_PEER_EVICTION_THRESHOLD,
+            peer_offline_retention_days: DEFAULT_PEER_OFFLINE_RETENTION_DAYS,
+            max_peers: DEFAULT_MAX_PEERS,
+            search_similarity_threshold: DEFAULT_SEARCH_SIMILARITY_THRESHOLD,
+            search_cache_ttl_secs: DEFAULT_SEARCH_CACHE_TTL_SECS,
+            search_cache_max_entries: DEFAULT_SEARCH_CACHE_MAX_ENTRIES,
+            blob_gc_enabled: false,
+            reseed_replicated: false,
+            prewarm_enabled: false,
+            prewarm_scope: PrewarmScope::default(),
         }
     }
 }
@@ -231,6 +1156,24 @@ impl P2pConfig {
             .map(PathBuf::from)
             .unwrap_or_else(|_| blobs_dir.parent().unwrap_or(&blobs_dir).join("secret_key"));
 
+        let bloom_persist_path = std::env::var("P2P_BLOOM_PERSIST_PATH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| blobs_dir.parent().unwrap_or(&blobs_dir).join("bloom.bin"));
+
+        // `P2P_BLOOM_FPR` is the older name of `P2P_BLOOM_TARGET_FPR`
+        let bloom_fpr = std::env::var("P2P_BLOOM_TARGET_FPR")
+            .or_else(|_| std::env::var("P2P_BLOOM_FPR"))
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&p: &f64| p > 0.0 && p < 1.0)
+            .unwrap_or(FALSE_POSITIVE_RATE);
+
+        let bloom_expected_items = std::env::var("P2P_BLOOM_EXPECTED_ITEMS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n: &u32| n > 0)
+            .unwrap_or(DEFAULT_BLOOM_CAPACITY as u32);
+
         let bind_port = std::env::var("P2P_BIND_PORT")
             .ok()
             .and_then(|v| v.parse().ok())
@@ -244,12 +1187,7 @@ impl P2pConfig {
             .unwrap_or_else(|_| "true".to_string())
             .eq_ignore_ascii_case("true");
 
-        let seed_peers = std::env::var("P2P_SEED_PEERS")
-            .unwrap_or_default()
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
+        let seed_peers = split_seed_peers(&std::env::var("P2P_SEED_PEERS").unwrap_or_default());
 
         let audio_storage_path = std::env::var("AUDIO_STORAGE_PATH")
             .map(PathBuf::from)
@@ -259,93 +1197,497 @@ impl P2pConfig {
             .ok()
             .map(PathBuf::from);
 
-        Self {
-            blobs_dir,
-            secret_key_path,
-            bind_port,
-            enable_local_discovery,
-            enable_dht_discovery,
-            seed_peers,
-            audio_storage_path,
-            metadata_storage_path,
-        }
-    }
-}
+        let max_upload_bps = std::env::var("P2P_MAX_UPLOAD_BPS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
 
-/// The main P2P node. Wraps an iroh `Endpoint` and an iroh-blobs `FsStore`.
-pub struct P2pNode {
-    /// iroh QUIC endpoint
-    endpoint: Endpoint,
-    /// Content-addressed blob store (filesystem-backed)
-    blob_store: FsStore,
-    /// Database handle for peer blocking checks
-    db: DatabaseConnection,
-    /// Registry of known peers (in-memory)
-    registry: Arc<PeerRegistry>,
-    /// Bloom-filter search index for distributed search routing
-    search_index: Arc<SearchIndex>,
-    /// MusicBrainz client for metadata enrichment
-    mb_client: Arc<MusicBrainzClient>,
-    /// Shutdown signal sender
-    shutdown_tx: watch::Sender<bool>,
-    /// Configuration used to create this node
-    _config: P2pConfig,
-    /// Path to audio file storage (for writing cover art from peers)
-    audio_storage_path: PathBuf,
-    /// Optional separate path for metadata (covers). Falls back to audio_storage_path.
-    metadata_storage_path: Option<PathBuf>,
-    /// LRU cache for P2P track blobs.
-    blob_cache: Arc<BlobCache>,
-    /// Track health manager for failure tracking and auto-repair.
-    health_manager: Arc<TrackHealthManager>,
-    /// Semaphore to limit concurrent incoming P2P connections.
-    conn_semaphore: Arc<tokio::sync::Semaphore>,
-    /// Set of blob hashes that have been explicitly published/announced.
-    /// Only these blobs can be served to peers via FetchTrack.
-    published_hashes: tokio::sync::RwLock<std::collections::HashSet<String>>,
-    /// Round-robin index for PEX peer rotation.
-    pex_index: AtomicUsize,
-    /// Per-peer mutexes that serialize concurrent `CatalogSync` page processing.
-    ///
-    /// Keyed by peer EndpointId string. Each entry holds an `Arc<Mutex<()>>` so
-    /// that multiple CatalogSync pages from the same peer are processed one at a
-    /// time (preventing duplicate track insertions), while syncs from different
-    /// peers proceed in parallel.
-    catalog_sync_in_progress:
-        tokio::sync::Mutex<std::collections::HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
-    /// Pool of reusable QUIC connections to peers (FIX-30).
-    conn_pool: Arc<ConnectionPool>,
-}
+        let max_upload_bps_per_peer = std::env::var("P2P_MAX_UPLOAD_BPS_PER_PEER")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
 
-impl P2pNode {
-    /// Start a new P2P node with the given config and database.
-    pub async fn start(config: P2pConfig, db: DatabaseConnection) -> Result<Arc<Self>, P2pError> {
-        // Ensure blobs directory exists
-        tokio::fs::create_dir_all(&config.blobs_dir)
-            .await
-            .map_err(P2pError::Io)?;
+        let max_concurrent_connections = std::env::var("P2P_MAX_CONCURRENT_CONNECTIONS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n: &usize| n > 0)
+            .unwrap_or(MAX_CONCURRENT_P2P_CONNECTIONS);
 
-        // Load or generate secret key for stable EndpointId across restarts
-        let secret_key = Self::load_or_generate_key(&config).await?;
-        let node_id = secret_key.public();
-        info!(%node_id, "starting P2P node");
+        let max_connections_per_ip = std::env::var("P2P_MAX_CONNECTIONS_PER_IP")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_CONNECTIONS_PER_IP);
 
-        // Initialize the blob store
-        let blob_store = FsStore::load(&config.blobs_dir)
-            .await
-            .map_err(|e| P2pError::BlobStore(e.to_string()))?;
+        let pex_batch_size = std::env::var("P2P_PEX_BATCH_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n: &usize| n > 0)
+            .unwrap_or(DEFAULT_PEX_BATCH_SIZE);
 
-        // Build the iroh endpoint with discovery services and relay
-        //
-        // Endpoint::builder() uses the N0 preset which registers:
-        //   - PkarrPublisher  — publishes our EndpointId + relay URL to n0's DNS server
-        //   - DnsDiscovery    — resolves other EndpointIds via DNS queries to n0's server
-        //
+        let gossip_ttl = std::env::var("P2P_GOSSIP_TTL")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_GOSSIP_TTL);
+
+        let pool_keepalive_secs = std::env::var("P2P_POOL_KEEPALIVE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_POOL_KEEPALIVE_SECS);
+
+        let keep_alive_interval_secs = std::env::var("P2P_KEEP_ALIVE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_KEEP_ALIVE_SECS);
+
+        let pool_idle_ttl_secs = std::env::var("P2P_POOL_IDLE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n: &u64| n > 0)
+            .unwrap_or(MAX_IDLE_SECS);
+
+        let max_peer_queue_depth = std::env::var("P2P_MAX_PEER_QUEUE_DEPTH")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n: &usize| n > 0)
+            .unwrap_or(DEFAULT_MAX_QUEUE_DEPTH);
+
+        let catalog_sync_pages_per_minute = std::env::var("P2P_CATALOG_SYNC_PAGES_PER_MINUTE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n: &u32| n > 0)
+            .unwrap_or(DEFAULT_CATALOG_SYNC_PAGES_PER_MINUTE);
+
+        let max_message_bytes = std::env::var("P2P_MAX_MESSAGE_BYTES")
+            .ok()
+            .and_then(|v| parse_byte_size(&v))
+            .unwrap_or(DEFAULT_MAX_MESSAGE_BYTES);
+
+        let relay_urls = std::env::var("P2P_RELAY_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| match s.parse::<RelayUrl>() {
+                Ok(url) => Some(url),
+                Err(e) => {
+                    warn!(url = %s, "ignoring invalid relay URL in P2P_RELAY_URLS: {e}");
+                    None
+                }
+            })
+            .collect();
+
+        let disable_default_discovery = std::env::var("P2P_DISABLE_DEFAULT_DISCOVERY")
+            .unwrap_or_else(|_| "false".to_string())
+            .eq_ignore_ascii_case("true");
+
+        let dns_discovery_url = std::env::var("P2P_DNS_DISCOVERY_URL")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .and_then(|s| match s.trim().parse::<reqwest::Url>() {
+                Ok(url) => Some(url),
+                Err(e) => {
+                    warn!(url = %s, "ignoring invalid P2P_DNS_DISCOVERY_URL: {e}");
+                    None
+                }
+            });
+
+        let hide_blocked_tracks = std::env::var("P2P_HIDE_BLOCKED_TRACKS")
+            .unwrap_or_else(|_| "true".to_string())
+            .eq_ignore_ascii_case("true");
+
+        let peer_eviction_threshold = std::env::var("P2P_PEER_EVICTION_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_PEER_EVICTION_THRESHOLD);
+
+        let peer_offline_retention_days = std::env::var("P2P_PEER_OFFLINE_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_PEER_OFFLINE_RETENTION_DAYS);
+
+        let max_peers = std::env::var("P2P_MAX_PEERS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_PEERS);
+
+        let search_similarity_threshold = std::env::var("P2P_SEARCH_SIMILARITY_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&t: &f32| t > 0.0 && t <= 1.0)
+            .unwrap_or(DEFAULT_SEARCH_SIMILARITY_THRESHOLD);
+
+        let search_cache_ttl_secs = std::env::var("P2P_SEARCH_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_SEARCH_CACHE_TTL_SECS);
+
+        let search_cache_max_entries = std::env::var("P2P_SEARCH_CACHE_MAX_ENTRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n: &usize| n > 0)
+            .unwrap_or(DEFAULT_SEARCH_CACHE_MAX_ENTRIES);
+
+        let blob_gc_enabled = std::env::var("P2P_BLOB_GC_ENABLED")
+            .unwrap_or_else(|_| "false".to_string())
+            .eq_ignore_ascii_case("true");
+
+        let reseed_replicated = std::env::var("P2P_RESEED_REPLICATED")
+            .unwrap_or_else(|_| "false".to_string())
+            .eq_ignore_ascii_case("true");
+
+        let prewarm_enabled = std::env::var("P2P_PREWARM_ENABLED")
+            .unwrap_or_else(|_| "false".to_string())
+            .eq_ignore_ascii_case("true");
+        let prewarm_scope = match std::env::var("P2P_PREWARM_SCOPE") {
+            Ok(v) if !v.trim().is_empty() => PrewarmScope::parse(&v).unwrap_or_else(|| {
+                warn!(value = %v, "ignoring P2P_PREWARM_SCOPE: expected favorites, playlists or all");
+                PrewarmScope::default()
+            }),
+            _ => PrewarmScope::default(),
+        };
+
+        Self {
+            blobs_dir,
+            secret_key_path,
+            bind_port,
+            enable_local_discovery,
+            enable_dht_discovery,
+            seed_peers,
+            audio_storage_path,
+            metadata_storage_path,
+            max_upload_bps,
+            max_upload_bps_per_peer,
+            bloom_persist_path,
+            bloom_fpr,
+            bloom_expected_items,
+            max_concurrent_connections,
+            max_connections_per_ip,
+            pex_batch_size,
+            gossip_ttl,
+            pool_keepalive_secs,
+            keep_alive_interval_secs,
+            pool_idle_ttl_secs,
+            max_peer_queue_depth,
+            catalog_sync_pages_per_minute,
+            max_message_bytes,
+            relay_urls,
+            disable_default_discovery,
+            dns_discovery_url,
+            hide_blocked_tracks,
+            peer_eviction_threshold,
+            peer_offline_retention_days,
+            max_peers,
+            search_similarity_threshold,
+            search_cache_ttl_secs,
+            search_cache_max_entries,
+            blob_gc_enabled,
+            reseed_replicated,
+            prewarm_enabled,
+            prewarm_scope,
+        }
+    }
+
+    /// Relay servers the endpoint should use. Configured relays always win;
+    /// otherwise n0's relays are used unless default discovery is disabled.
+    pub fn relay_plan(&self) -> RelayPlan {
+        if !self.relay_urls.is_empty() {
+            RelayPlan::Custom(self.relay_urls.clone())
+        } else if self.disable_default_discovery {
+            RelayPlan::Disabled
+        } else {
+            RelayPlan::Default
+        }
+    }
+
+    /// Reject settings the node cannot run with.
+    pub fn validate(&self) -> Result<(), P2pError> {
+        if !(MIN_MESSAGE_BYTES_LIMIT..=MAX_MESSAGE_BYTES_LIMIT).contains(&self.max_message_bytes) {
+            return Err(P2pError::Config(format!(
+                "P2P_MAX_MESSAGE_BYTES must be between 1M and 512M, got {} bytes",
+                self.max_message_bytes
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Parse a byte count such as `1048576`, `512K`, `64M` or `1G`. Suffixes are
+/// binary multiples, case-insensitive, and may end in `B` or `iB` (`64MiB`).
+pub fn parse_byte_size(s: &str) -> Option<usize> {
+    let lower = s.trim().to_ascii_lowercase();
+    let value = lower
+        .strip_suffix("ib")
+        .or_else(|| lower.strip_suffix('b'))
+        .unwrap_or(&lower);
+    let (digits, multiplier) = match value.char_indices().last()? {
+        (i, 'k') => (&value[..i], 1024),
+        (i, 'm') => (&value[..i], 1024 * 1024),
+        (i, 'g') => (&value[..i], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+    digits.trim().parse::<usize>().ok()?.checked_mul(multiplier)
+}
+
+/// Split a `P2P_SEED_PEERS` value into one entry per peer. Entries are
+/// comma-separated, so socket addresses following an `<id>@<ip:port>` entry
+/// belong to that peer: `id1@10.0.0.1:4433,10.0.0.2:4433,id2` is two peers.
+fn split_seed_peers(raw: &str) -> Vec<String> {
+    let mut entries: Vec<String> = Vec::new();
+    for token in raw.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        if token.parse::<std::net::SocketAddr>().is_ok() {
+            if let Some(last) = entries.last_mut().filter(|e| e.contains('@')) {
+                last.push(',');
+                last.push_str(token);
+                continue;
+            }
+        }
+        entries.push(token.to_string());
+    }
+    entries
+}
+
+/// Parse a peer given as `<endpoint_id>` or
+/// `<endpoint_id>@<ip:port>[,<ip:port>...]`, as accepted by
+/// `P2P_SEED_PEERS` and the admin add-peer API. The socket addresses let
+/// the peer be dialled directly, without relay or DNS discovery.
+pub fn parse_peer_addr(s: &str) -> Result<EndpointAddr, P2pError> {
+    let s = s.trim();
+    let (id, addrs) = match s.split_once('@') {
+        Some((id, addrs)) => (id.trim(), Some(addrs)),
+        None => (s, None),
+    };
+    let node_id: EndpointId = id
+        .parse()
+        .map_err(|e| P2pError::InvalidPeerAddr(format!("invalid endpoint id '{id}': {e}")))?;
+
+    let mut addr = EndpointAddr::new(node_id);
+    if let Some(addrs) = addrs {
+        for a in addrs.split(',').map(str::trim) {
+            let socket: std::net::SocketAddr = a.parse().map_err(|_| {
+                P2pError::InvalidPeerAddr(format!(
+                    "invalid socket address '{a}' for peer {id} (expected ip:port)"
+                ))
+            })?;
+            addr = addr.with_ip_addr(socket);
+        }
+    }
+    Ok(addr)
+}
+
+/// Pick the peer IDs for an outgoing `PeerExchange`: a random sample of
+/// `known` peers plus our own ID (so the remote learns about us), at most
+/// [`MAX_PEX_PEERS`] in total.
+fn sample_pex_peers(mut known: Vec<String>, our_id: String) -> Vec<String> {
+    known.retain(|p| *p != our_id);
+    known.shuffle(&mut rand::rng());
+    known.truncate(MAX_PEX_PEERS - 1);
+    known.push(our_id);
+    known
+}
+
+/// Append candidate peer IDs to the PEX queue, skipping IDs already queued
+/// and stopping at [`MAX_PEX_QUEUE_LEN`]. Returns how many were added.
+fn enqueue_pex_candidates(queue: &mut VecDeque<String>, candidates: Vec<String>) -> usize {
+    let mut added = 0;
+    for id in candidates {
+        if queue.len() >= MAX_PEX_QUEUE_LEN {
+            break;
+        }
+        if !queue.contains(&id) {
+            queue.push_back(id);
+            added += 1;
+        }
+    }
+    added
+}
+
+/// Number of tracks per `CatalogSync` page for a peer with the given
+/// round-trip time. Fast links get large pages; slow (usually relayed)
+/// links get small ones so a single page does not time out:
+///
+/// | RTT            | Page size |
+/// |----------------|-----------|
+/// | < 50 ms        | 2000      |
+/// | 50–200 ms      | 500       |
+/// | 200–1000 ms    | 100       |
+/// | > 1000 ms      | 50        |
+///
+/// Peers we have not measured yet get 500.
+pub fn compute_catalog_page_size(rtt_ms: Option<u32>) -> u64 {
+    match rtt_ms {
+        None => 500,
+        Some(rtt) if rtt < 50 => 2000,
+        Some(rtt) if rtt < 200 => 500,
+        Some(rtt) if rtt <= 1000 => 100,
+        Some(_) => 50,
+    }
+}
+
+/// The main P2P node. Wraps an iroh `Endpoint` and an iroh-blobs `FsStore`.
+pub struct P2pNode {
+    /// iroh QUIC endpoint
+    endpoint: Endpoint,
+    /// Content-addressed blob store (filesystem-backed)
+    blob_store: FsStore,
+    /// Database handle for peer blocking checks
+    db: DatabaseConnection,
+    /// Registry of known peers (in-memory)
+    registry: Arc<PeerRegistry>,
+    /// Bloom-filter search index for distributed search routing
+    search_index: Arc<SearchIndex>,
+    /// Network searches being paged through
+    search_sessions: SearchSessionCache,
+    /// Recent results of `distributed_search`, by normalized query
+    search_cache: SearchResultCache,
+    /// Checksum of the local catalog, see [`catalog_checksum`](crate::catalog_checksum)
+    catalog_checksum_cache: ChecksumCache,
+    /// MusicBrainz client for metadata enrichment
+    mb_client: Arc<MusicBrainzClient>,
+    /// Lookups for announced tracks, run by the `MusicBrainzQueue` task
+    mb_queue: mpsc::Sender<LookupRequest>,
+    /// Wakes the MusicBrainz verification task when a track starts waiting
+    mb_verification_wake: tokio::sync::Notify,
+    /// Shutdown signal sender
+    shutdown_tx: watch::Sender<bool>,
+    /// Configuration used to create this node
+    _config: P2pConfig,
+    /// Path to audio file storage (for writing cover art from peers)
+    audio_storage_path: PathBuf,
+    /// Optional separate path for metadata (covers). Falls back to audio_storage_path.
+    metadata_storage_path: Option<PathBuf>,
+    /// LRU cache for P2P track blobs.
+    blob_cache: Arc<BlobCache>,
+    /// Track health manager for failure tracking and auto-repair.
+    health_manager: Arc<TrackHealthManager>,
+    /// Semaphore to limit concurrent incoming P2P connections.
+    conn_semaphore: Arc<tokio::sync::Semaphore>,
+    /// Per-remote-IP cap on concurrent incoming connections.
+    ip_limiter: IpConnectionLimiter,
+    /// Blob hashes that have been explicitly published/announced, persisted
+    /// in `published_hashes`. Only these blobs can be served to peers via
+    /// FetchTrack.
+    published_hashes: PublishedHashes,
+    /// Set once `published-*` tags older than `published_hashes` are
+    /// recorded there; blob GC waits for it.
+    published_backfill: tokio::sync::OnceCell<()>,
+    /// Round-robin index for PEX peer rotation.
+    pex_index: AtomicUsize,
+    /// Peer IDs learned through PEX that have not been pinged yet.
+    pex_queue: Arc<tokio::sync::Mutex<VecDeque<String>>>,
+    /// Number of queued PEX peers pinged per cycle.
+    pex_batch_size: usize,
+    /// Hops our own `DeleteTrack` messages may travel.
+    gossip_ttl: u8,
+    /// Gossip messages already handled, so each is forwarded once.
+    gossip_seen: Arc<GossipSeen>,
+    /// Per-peer mutexes that serialize concurrent `CatalogSync` page processing.
+    ///
+    /// Keyed by peer EndpointId string. Each entry holds an
+    /// `Arc<Mutex<PeerCatalogSync>>` so that multiple CatalogSync pages from
+    /// the same peer are processed one at a time (preventing duplicate track
+    /// insertions), while syncs from different peers proceed in parallel. The
+    /// mutex also guards the peer's page count for the rate limit.
+    catalog_sync_in_progress: tokio::sync::Mutex<
+        std::collections::HashMap<String, Arc<tokio::sync::Mutex<PeerCatalogSync>>>,
+    >,
+    /// Pool of reusable QUIC connections to peers (FIX-30).
+    conn_pool: Arc<ConnectionPool>,
+    /// Token-bucket limiter for blob bytes served to peers.
+    upload_limiter: Arc<UploadLimiter>,
+    /// Per-message-type traffic counters exposed through the admin API.
+    stats: P2pStatsCollector,
+    /// Progress of full catalog pushes, per peer.
+    catalog_sync: CatalogSyncTracker,
+    /// Final tallies of recent catalog pushes, per peer.
+    catalog_sync_history: CatalogSyncHistory,
+    /// Coalesces overlapping outgoing catalog syncs to the same peer.
+    outgoing_syncs: OutgoingSyncGuard,
+    /// Highest `CatalogSync` page delivered per peer by an unfinished push.
+    catalog_sync_checkpoints: Arc<DashMap<String, SyncCheckpoint>>,
+    /// Where `catalog_sync_checkpoints` is persisted.
+    checkpoint_file: Arc<CheckpointFile>,
+    /// Blob hashes of downloaded external artist images, keyed by URL.
+    remote_image_hashes: DashMap<String, String>,
+    /// Directory holding partially downloaded blobs for resumable fetches.
+    partial_dir: PathBuf,
+    /// Live events streamed to admin dashboards.
+    events: P2pEventBus,
+    /// Callbacks registered by the server (plugin events).
+    hooks: NodeHooks,
+    /// Admin content policy for announced tracks, reloaded when edited.
+    replication_policy: std::sync::RwLock<ReplicationPolicy>,
+    /// Announcements refused by `replication_policy`.
+    rejections: RejectionLog,
+    /// Hashes with an active block: never served, replicated or fetched.
+    blocked_hashes: HashBlocklist,
+    /// Per-peer replication filters, keyed by peer ID.
+    peer_filters: DashMap<String, PeerFilter>,
+    /// Tracks counted against each capped peer's `max_tracks`, reloaded at
+    /// the start of each of its catalog pages and taken one at a time as
+    /// new tracks are stored, so announcements do not each run a COUNT.
+    peer_track_counts: DashMap<String, u64>,
+    /// `PlayCountUpdate`s received per peer.
+    play_count_rate: PeerMessageRate,
+    /// `DeleteTrack`s received per peer.
+    delete_track_rate: PeerMessageRate,
+    /// Blob chosen by `stream_source` for each replicated track, so every
+    /// range request of a playback reads the same encoding.
+    stream_sources: DashMap<Uuid, Hash>,
+    /// Blobs that failed an integrity check, treated as missing until the
+    /// store's garbage collector sweeps their bytes.
+    corrupted_blobs: DashSet<Hash>,
+}
+
+impl P2pNode {
+    /// Start a new P2P node with the given config and database.
+    pub async fn start(config: P2pConfig, db: DatabaseConnection) -> Result<Arc<Self>, P2pError> {
+        config.validate()?;
+
+        // Ensure blobs directory exists
+        tokio::fs::create_dir_all(&config.blobs_dir)
+            .await
+            .map_err(P2pError::Io)?;
+
+        // Load or generate secret key for stable EndpointId across restarts
+        let secret_key = Self::load_or_generate_key(&config).await?;
+        let node_id = secret_key.public();
+        info!(%node_id, "starting P2P node");
+
+        // Initialize the blob store
+        let blob_store = blob_gc::load_store(&config.blobs_dir, blob_gc::STORE_GC_INTERVAL).await?;
+
+        // Build the iroh endpoint with discovery services and relay
+        //
+        // Endpoint::builder() uses the N0 preset which registers:
+        //   - PkarrPublisher  — publishes our EndpointId + relay URL to n0's DNS server
+        //   - DnsDiscovery    — resolves other EndpointIds via DNS queries to n0's server
+        //
         // Without these, the node cannot register with relay servers and peers
         // cannot discover us — this was the root cause of "Relay Disconnected".
-        let mut builder = Endpoint::builder()
-            .secret_key(secret_key.clone())
-            .alpns(vec![SOUNDTIME_ALPN.to_vec()]);
+        //
+        // Air-gapped deployments set P2P_DISABLE_DEFAULT_DISCOVERY and start
+        // from an empty builder instead, optionally pointing at self-hosted
+        // relays (P2P_RELAY_URLS) and a pkarr/DNS server (P2P_DNS_DISCOVERY_URL).
+        let relay_plan = config.relay_plan();
+        let mut builder = if config.disable_default_discovery {
+            info!("default n0 relays and DNS discovery disabled");
+            Endpoint::empty_builder(relay_plan.relay_mode())
+        } else {
+            Endpoint::builder().relay_mode(relay_plan.relay_mode())
+        }
+        .secret_key(secret_key.clone())
+        .alpns(SUPPORTED_ALPNS.iter().map(|alpn| alpn.to_vec()).collect());
+        if let RelayPlan::Custom(ref urls) = relay_plan {
+            info!(count = urls.len(), "using custom relay servers");
+        }
+
+        // Optionally publish to and resolve from a self-hosted pkarr/DNS server
+        if let Some(ref url) = config.dns_discovery_url {
+            info!(%url, "enabling custom DNS discovery");
+            builder = builder
+                .address_lookup(iroh::address_lookup::PkarrPublisher::builder(url.clone()))
+                .address_lookup(iroh::address_lookup::PkarrResolver::builder(url.clone()));
+        }
 
         // Optionally enable local network discovery (mDNS)
         if config.enable_local_discovery {
@@ -358,12 +1700,31 @@ impl P2pNode {
         // Optionally enable DHT (BitTorrent Mainline) discovery
         if config.enable_dht_discovery {
             info!("enabling DHT (BitTorrent Mainline) discovery");
-            let dht_builder =
-                iroh::address_lookup::DhtAddressLookup::builder().n0_dns_pkarr_relay();
+            let dht_builder = iroh::address_lookup::DhtAddressLookup::builder();
+            let dht_builder = match config.dns_discovery_url {
+                Some(ref url) => dht_builder.pkarr_relay(url.clone()),
+                None if config.disable_default_discovery => dht_builder,
+                None => dht_builder.n0_dns_pkarr_relay(),
+            };
             builder = builder.address_lookup(dht_builder);
             tracing::info!("DHT address lookup configured");
         }
 
+        // Keep idle connections open through NATs and firewalls, which drop
+        // silent flows after 60–300 seconds
+        if config.keep_alive_interval_secs > 0 {
+            let transport = iroh::endpoint::QuicTransportConfig::builder()
+                .keep_alive_interval(std::time::Duration::from_secs(
+                    config.keep_alive_interval_secs,
+                ))
+                .build();
+            builder = builder.transport_config(transport);
+            info!(
+                secs = config.keep_alive_interval_secs,
+                "QUIC keep-alive enabled"
+            );
+        }
+
         // Bind to the configured port (0 = random)
         if config.bind_port > 0 {
             builder = builder
@@ -382,17 +1743,33 @@ impl P2pNode {
 
         // Wait for relay connection (up to 15 seconds)
         // With discovery services registered, the endpoint will automatically
-        // connect to one of n0's production relay servers and publish its address.
-        info!("waiting for relay connection...");
-        match tokio::time::timeout(
-            std::time::Duration::from_secs(15),
-            Self::wait_for_relay(&endpoint),
-        )
-        .await
-        {
-            Ok(Some(relay)) => info!(%relay, "connected to relay server"),
-            Ok(None) => warn!("no relay server available — P2P will use direct connections only"),
-            Err(_) => warn!("relay connection timed out after 15s — continuing without relay"),
+        // connect to one of n0's production relay servers (or the configured
+        // ones) and publish its address.
+        if relay_plan == RelayPlan::Disabled {
+            warn!(
+                "no relay configured and default discovery disabled (P2P_DISABLE_DEFAULT_DISCOVERY) \
+                 — P2P will use direct connections only; set P2P_RELAY_URLS to use a self-hosted relay"
+            );
+        } else {
+            info!("waiting for relay connection...");
+            match tokio::time::timeout(
+                std::time::Duration::from_secs(15),
+                Self::wait_for_relay(&endpoint),
+            )
+            .await
+            {
+                Ok(Some(relay)) => info!(%relay, "connected to relay server"),
+                Ok(None) => {
+                    warn!("no relay server available — P2P will use direct connections only")
+                }
+                Err(_) => warn!("relay connection timed out after 15s — continuing without relay"),
+            }
+        }
+        if config.disable_default_discovery && config.dns_discovery_url.is_none() {
+            warn!(
+                "no DNS discovery configured — peers can only be reached via mDNS, DHT \
+                 or seed peers with a known relay/address (set P2P_DNS_DISCOVERY_URL)"
+            );
         }
 
         // Log direct addresses for diagnostics
@@ -408,6 +1785,7 @@ impl P2pNode {
         let (shutdown_tx, _) = watch::channel(false);
 
         let registry = Arc::new(PeerRegistry::new());
+        registry.set_max_peers(config.max_peers);
 
         // Load persisted peers from database (previous runs)
         match registry.load_from_db(&db).await {
@@ -416,17 +1794,90 @@ impl P2pNode {
             Err(e) => warn!("failed to load peers from database: {e}"),
         }
 
-        let search_index = Arc::new(SearchIndex::new());
+        let search_index = Arc::new(SearchIndex::with_params(
+            config.bloom_expected_items as usize,
+            config.bloom_fpr,
+        ));
+        search_index.set_track_source(Arc::new(db.clone()));
         let mb_client = Arc::new(MusicBrainzClient::new());
+        let (mb_queue, mb_queue_task) = MusicBrainzQueue::new(Arc::clone(&mb_client), db.clone());
+        tokio::spawn(mb_queue_task.run());
 
         let audio_storage_path = config.audio_storage_path.clone();
         let metadata_storage_path = config.metadata_storage_path.clone();
 
-        let blob_cache = Arc::new(BlobCache::from_env());
+        let blob_cache = Arc::new(BlobCache::from_env(&config.blobs_dir));
+        // A limit set by an admin at runtime wins over P2P_CACHE_MAX_SIZE
+        match blob_cache::load_max_bytes(&db).await {
+            Ok(Some(max_bytes)) => blob_cache.set_max_bytes(max_bytes),
+            Ok(None) => {}
+            Err(e) => warn!("failed to load saved blob cache limit: {e}"),
+        }
+        match pinned_track::Entity::find().all(&db).await {
+            Ok(pins) => {
+                for pin in pins {
+                    if let Ok(hash) = pin.content_hash.parse::<Hash>() {
+                        blob_cache.pin(hash).await;
+                    }
+                }
+            }
+            Err(e) => warn!("failed to load pinned tracks: {e}"),
+        }
+
+        let health_manager = Arc::new(TrackHealthManager::with_config(
+            HealthMonitorConfig::from_env(),
+        ));
+
+        let conn_pool = Arc::new(
+            ConnectionPool::new(endpoint.clone(), SUPPORTED_ALPNS)
+                .with_idle_ttl(std::time::Duration::from_secs(config.pool_idle_ttl_secs))
+                .with_max_queue_depth(config.max_peer_queue_depth),
+        );
 
-        let health_manager = Arc::new(TrackHealthManager::new());
+        let upload_limiter = Arc::new(UploadLimiter::new(
+            config.max_upload_bps,
+            config.max_upload_bps_per_peer,
+        ));
+        if config.max_upload_bps > 0 || config.max_upload_bps_per_peer > 0 {
+            info!(
+                global_bps = config.max_upload_bps,
+                per_peer_bps = config.max_upload_bps_per_peer,
+                "P2P upload bandwidth limits enabled"
+            );
+        }
 
-        let conn_pool = Arc::new(ConnectionPool::new(endpoint.clone(), SOUNDTIME_ALPN));
+        let max_concurrent_connections = config.max_concurrent_connections;
+        let max_connections_per_ip = config.max_connections_per_ip;
+        let pex_batch_size = config.pex_batch_size;
+        let gossip_ttl = config.gossip_ttl;
+        let partial_dir = config
+            .blobs_dir
+            .parent()
+            .unwrap_or(&config.blobs_dir)
+            .join("partial");
+        let checkpoint_file = CheckpointFile::new(
+            config
+                .blobs_dir
+                .parent()
+                .unwrap_or(&config.blobs_dir)
+                .join("sync_checkpoints.json"),
+        );
+        let catalog_sync_checkpoints = Arc::new(checkpoint_file.load());
+        let replication_policy = ReplicationPolicy::load(&db).await.unwrap_or_else(|e| {
+            warn!("failed to load replication policy, accepting all tracks: {e}");
+            ReplicationPolicy::default()
+        });
+        let peer_filters = peer_filter::load_all(&db).await.unwrap_or_else(|e| {
+            warn!("failed to load peer replication filters: {e}");
+            Default::default()
+        });
+        let blocked_hashes = moderation::load_active_blocks(&db)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("failed to load blocked hashes: {e}");
+                Default::default()
+            });
+        let published_hashes = PublishedHashes::new(Arc::new(db.clone()));
 
         let node = Arc::new(Self {
             endpoint,
@@ -434,52 +1885,93 @@ impl P2pNode {
             db,
             registry,
             search_index,
+            search_sessions: SearchSessionCache::new(),
+            search_cache: SearchResultCache::new(
+                std::time::Duration::from_secs(config.search_cache_ttl_secs),
+                config.search_cache_max_entries,
+            ),
+            catalog_checksum_cache: ChecksumCache::default(),
             mb_client,
+            mb_queue,
+            mb_verification_wake: tokio::sync::Notify::new(),
             shutdown_tx,
             _config: config,
             audio_storage_path,
             metadata_storage_path,
             blob_cache,
             health_manager,
-            conn_semaphore: Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_P2P_CONNECTIONS)),
-            published_hashes: tokio::sync::RwLock::new(std::collections::HashSet::new()),
+            conn_semaphore: Arc::new(tokio::sync::Semaphore::new(max_concurrent_connections)),
+            ip_limiter: IpConnectionLimiter::new(max_connections_per_ip),
+            published_hashes,
+            published_backfill: tokio::sync::OnceCell::new(),
             pex_index: AtomicUsize::new(0),
+            pex_queue: Arc::new(tokio::sync::Mutex::new(VecDeque::new())),
+            pex_batch_size,
+            gossip_ttl,
+            gossip_seen: Arc::new(GossipSeen::default()),
             catalog_sync_in_progress: tokio::sync::Mutex::new(std::collections::HashMap::new()),
             conn_pool,
+            upload_limiter,
+            stats: P2pStatsCollector::new(),
+            catalog_sync: CatalogSyncTracker::new(),
+            catalog_sync_history: CatalogSyncHistory::new(),
+            outgoing_syncs: OutgoingSyncGuard::new(),
+            catalog_sync_checkpoints,
+            checkpoint_file: Arc::new(checkpoint_file),
+            remote_image_hashes: DashMap::new(),
+            partial_dir,
+            events: P2pEventBus::default(),
+            hooks: NodeHooks::default(),
+            replication_policy: std::sync::RwLock::new(replication_policy),
+            rejections: RejectionLog::default(),
+            blocked_hashes: HashBlocklist::new(blocked_hashes),
+            peer_filters: peer_filters.into_iter().collect(),
+            peer_track_counts: DashMap::new(),
+            play_count_rate: PeerMessageRate::new(PLAY_COUNT_UPDATES_PER_MINUTE),
+            delete_track_rate: PeerMessageRate::new(DELETE_TRACKS_PER_MINUTE),
+            stream_sources: DashMap::new(),
+            corrupted_blobs: DashSet::new(),
         });
 
-        // Build the local Bloom filter index from existing tracks in DB
+        // Restore the local Bloom filter saved by the previous run, or build
+        // it from existing tracks in DB if there is no usable file or the
+        // catalog changed since it was saved
         {
             let node_clone = Arc::clone(&node);
             tokio::spawn(async move {
-                node_clone.rebuild_search_index().await;
+                let path = &node_clone._config.bloom_persist_path;
+                match node_clone.search_index.load_from_disk(path).await {
+                    Ok(()) => match node_clone.search_index.is_current(&node_clone.db).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            info!("persisted bloom filter is stale, rebuilding");
+                            node_clone.rebuild_search_index().await;
+                        }
+                        Err(e) => {
+                            warn!("failed to check persisted bloom filter: {e}");
+                            node_clone.search_index.mark_dirty().await;
+                        }
+                    },
+                    Err(e) => {
+                        if e.kind() != std::io::ErrorKind::NotFound {
+                            warn!(path = %path.display(), "ignoring persisted bloom filter: {e}");
+                        }
+                        node_clone.rebuild_search_index().await;
+                    }
+                }
             });
         }
 
-        // Pre-populate published_hashes with all locally-uploaded tracks (FIX-19)
+        // Load the persisted published hashes (FIX-19), a page at a time,
+        // then record published tags that predate the table
         {
             let node_clone = Arc::clone(&node);
             tokio::spawn(async move {
-                match track::Entity::find()
-                    .filter(track::Column::ContentHash.is_not_null())
-                    .all(&node_clone.db)
-                    .await
-                {
-                    Ok(tracks) => {
-                        let mut hashes = node_clone.published_hashes.write().await;
-                        for t in &tracks {
-                            if let Some(ref h) = t.content_hash {
-                                hashes.insert(h.clone());
-                            }
-                        }
-                        info!(
-                            count = hashes.len(),
-                            "pre-populated published hashes from database"
-                        );
-                    }
-                    Err(e) => {
-                        warn!("failed to pre-populate published hashes: {e}");
-                    }
+                if let Err(e) = node_clone.published_hashes.load().await {
+                    warn!("failed to load published hashes: {e}");
+                }
+                if let Err(e) = node_clone.backfill_published_hashes().await {
+                    warn!("failed to record pre-existing published tags: {e}");
                 }
             });
         }
@@ -489,12 +1981,38 @@ impl P2pNode {
         {
             let node_clone = Arc::clone(&node);
             tokio::spawn(async move {
-                // Small delay to let the published_hashes pre-population finish first
+                // Small delay to let the published hashes load first
                 tokio::time::sleep(std::time::Duration::from_secs(1)).await;
                 node_clone.backfill_content_hashes().await;
             });
         }
 
+        // Publish registry status changes as live events
+        {
+            let node_clone = Arc::clone(&node);
+            let updates = node.registry.subscribe_status();
+            tokio::spawn(async move {
+                node_clone.events.forward_peer_status(updates).await;
+            });
+        }
+
+        // Look up replicated tracks held back by the MusicBrainz requirement
+        {
+            let node_clone = Arc::clone(&node);
+            let mut shutdown_rx = node.shutdown_tx.subscribe();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(MB_VERIFICATION_INTERVAL);
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {}
+                        _ = node_clone.mb_verification_wake.notified() => {}
+                        _ = shutdown_rx.changed() => break,
+                    }
+                    node_clone.verify_pending_tracks().await;
+                }
+            });
+        }
+
         // Spawn the connection accept loop
         let node_clone = Arc::clone(&node);
         tokio::spawn(async move {
@@ -502,14 +2020,20 @@ impl P2pNode {
         });
 
         // Connect to seed peers in background (after a short delay for relay setup)
-        if !node._config.seed_peers.is_empty() {
-            let seed_peers = node._config.seed_peers.clone();
-            let node_clone = Arc::clone(&node);
-            tokio::spawn(async move {
-                // Give the relay a moment to fully stabilize
-                tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-                node_clone.connect_to_seed_peers(&seed_peers).await;
+        {
+            let db_seeds = seed_peers::list(&node.db).await.unwrap_or_else(|e| {
+                warn!("failed to load seed peers from database: {e}");
+                Vec::new()
             });
+            let seed_peers = seed_peers::merge_seeds(&node._config.seed_peers, &db_seeds);
+            if !seed_peers.is_empty() {
+                let node_clone = Arc::clone(&node);
+                tokio::spawn(async move {
+                    // Give the relay a moment to fully stabilize
+                    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+                    node_clone.connect_to_seed_peers(&seed_peers).await;
+                });
+            }
         }
 
         // Re-ping persisted peers in background (after relay setup)
@@ -523,6 +2047,9 @@ impl P2pNode {
                 if let Err(e) = node_clone.registry.save_to_db(&node_clone.db).await {
                     warn!("failed to save peers after refresh: {e}");
                 }
+                // Now that peers are reachable, fetch what users played last
+                node_clone.warm_up_blob_cache().await;
+                node_clone.fetch_pinned_blobs().await;
             });
         }
 
@@ -533,9 +2060,17 @@ impl P2pNode {
             tokio::spawn(async move {
                 let mut interval = tokio::time::interval(std::time::Duration::from_secs(300));
                 interval.tick().await; // skip first immediate tick
+                let mut last_blob_gc = std::time::Instant::now();
                 loop {
                     tokio::select! {
                         _ = interval.tick() => {
+                            // Revalidate quiet peers so the cycle below only
+                            // talks to peers that still answer
+                            let keep_alive_secs = node_clone._config.keep_alive_interval_secs;
+                            if keep_alive_secs > 0 {
+                                let max_silence = chrono::Duration::seconds(keep_alive_secs.saturating_mul(3) as i64);
+                                crate::discovery::heartbeat_quiet_peers(&node_clone, &node_clone.registry, max_silence).await;
+                            }
                             let peers = node_clone.registry.online_peers().await;
                             if !peers.is_empty() {
                                 info!(online = peers.len(), "periodic peer exchange + bloom sync");
@@ -548,14 +2083,57 @@ impl P2pNode {
                                         node_clone.discover_via_peer(nid).await;
                                     }
                                 }
+                                // Work through peers deferred by earlier exchanges
+                                node_clone.process_pex_queue().await;
                                 // Rebuild Bloom filter if it was marked dirty (e.g. track deletion)
                                 if node_clone.search_index.is_dirty() {
                                     if let Err(e) = node_clone.search_index.rebuild_from_db(&node_clone.db).await {
                                         warn!("failed to rebuild dirty search index: {e}");
                                     }
+                                    node_clone.search_cache.invalidate();
                                 }
                                 // Exchange Bloom filters with all online peers
                                 node_clone.broadcast_bloom_filter().await;
+                                // Share what our users play most
+                                node_clone.broadcast_popularity().await;
+                                // Spot-check one peer's catalog against ours
+                                node_clone.check_catalog_drift().await;
+                                // Retry pinned blobs whose peers were unreachable
+                                let pin_node = Arc::clone(&node_clone);
+                                tokio::spawn(async move { pin_node.fetch_pinned_blobs().await });
+                                // Fetch favorited and playlisted tracks before they are played
+                                if node_clone._config.prewarm_enabled {
+                                    let prewarm_node = Arc::clone(&node_clone);
+                                    tokio::spawn(async move { prewarm_node.prewarm_blob_cache().await });
+                                }
+                            }
+                            if let Err(e) = popularity::prune_stale(&node_clone.db).await {
+                                warn!("failed to prune gossiped play counts: {e}");
+                            }
+                            if let Err(e) = track_access::prune_expired_grants(&node_clone.db).await {
+                                warn!("failed to prune expired track grants: {e}");
+                            }
+                            // Retry seeds added by admins that are not online
+                            let seed_node = Arc::clone(&node_clone);
+                            tokio::spawn(async move { seed_node.connect_db_seeds().await });
+                            // Forget peers that stopped answering long ago
+                            node_clone.evict_dead_peers().await;
+                            node_clone.prune_offline_peers().await;
+                            if node_clone._config.blob_gc_enabled
+                                && last_blob_gc.elapsed() >= BLOB_GC_INTERVAL
+                            {
+                                last_blob_gc = std::time::Instant::now();
+                                if let Err(e) = node_clone.collect_garbage(false).await {
+                                    warn!("blob garbage collection failed: {e}");
+                                }
+                            }
+                            // Persist the Bloom filter so the next start can skip the rebuild
+                            if let Err(e) = node_clone
+                                .search_index
+                                .save_to_disk(&node_clone._config.bloom_persist_path)
+                                .await
+                            {
+                                warn!("failed to persist bloom filter: {e}");
                             }
                             // Persist peer registry to database
                             if let Err(e) = node_clone.registry.save_to_db(&node_clone.db).await {
@@ -570,7 +2148,40 @@ impl P2pNode {
             });
         }
 
-        // Spawn periodic health monitor for remote tracks
+        // Spawn periodic keepalive over pooled outbound connections, so a
+        // connection to a restarted peer is evicted before it is reused
+        let keepalive_secs = node._config.pool_keepalive_secs;
+        if keepalive_secs > 0 {
+            let node_clone = Arc::clone(&node);
+            let mut shutdown_rx = node.shutdown_tx.subscribe();
+            tokio::spawn(async move {
+                let keepalive = match serde_json::to_vec(&P2pMessage::KeepAlive) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        warn!("failed to serialize keepalive probe: {e}");
+                        return;
+                    }
+                };
+                let mut interval =
+                    tokio::time::interval(std::time::Duration::from_secs(keepalive_secs));
+                interval.tick().await; // skip first immediate tick
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {
+                            let sent = node_clone.conn_pool.keepalive(&keepalive).await;
+                            for _ in 0..sent {
+                                node_clone.stats.record_sent(P2pMessage::KeepAlive.kind());
+                            }
+                        }
+                        _ = shutdown_rx.changed() => {
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+
+        // Spawn periodic health monitor for remote tracks
         {
             let node_clone: Arc<P2pNode> = Arc::clone(&node);
             let health_manager = Arc::clone(&node.health_manager);
@@ -660,7 +2271,9 @@ impl P2pNode {
     }
 
     /// Get the relay URL this node is connected to (if any).
-    /// By default, iroh connects to n0.computer production relay servers.
+    /// By default, iroh connects to n0.computer production relay servers;
+    /// with `P2P_RELAY_URLS` set it is one of those, and with relays
+    /// disabled ([`RelayPlan::Disabled`]) it is always `None`.
     pub fn relay_url(&self) -> Option<String> {
         self.node_addr()
             .ok()
@@ -674,6 +2287,11 @@ impl P2pNode {
             .unwrap_or(0)
     }
 
+    /// Relay servers this node was configured with.
+    pub fn relay_plan(&self) -> RelayPlan {
+        self._config.relay_plan()
+    }
+
     /// Whether DHT discovery is enabled for this node.
     pub fn dht_discovery_enabled(&self) -> bool {
         self._config.enable_dht_discovery
@@ -684,419 +2302,574 @@ impl P2pNode {
         &self.blob_cache
     }
 
-    /// Get the track health manager for failure tracking and auto-repair.
-    pub fn health_manager(&self) -> &Arc<TrackHealthManager> {
-        &self.health_manager
+    /// Change the blob cache limit, save it for future starts and evict
+    /// right away if the cache is now over it. Returns the evicted hashes.
+    pub async fn set_cache_max_bytes(&self, max_bytes: u64) -> Result<Vec<Hash>, P2pError> {
+        blob_cache::save_max_bytes(&self.db, max_bytes).await?;
+        self.blob_cache.set_max_bytes(max_bytes);
+        let evicted = self.blob_cache.evict_if_needed(&self.blob_store).await;
+        self.unpublish_reseeded(&evicted).await;
+        Ok(evicted)
     }
 
-    /// Publish a track's audio data to the local blob store.
-    /// Returns the content hash (BLAKE3) that identifies the blob.
-    pub async fn publish_track(&self, data: Bytes) -> Result<Hash, P2pError> {
-        let outcome = self
-            .blob_store
-            .blobs()
-            .add_bytes(data)
-            .temp_tag()
-            .await
-            .map_err(|e| P2pError::BlobStore(e.to_string()))?;
+    /// Get the upload bandwidth limiter.
+    pub fn upload_limiter(&self) -> &Arc<UploadLimiter> {
+        &self.upload_limiter
+    }
 
-        let hash = outcome.hash();
+    /// Snapshot of message and blob traffic counters since startup.
+    pub fn stats(&self) -> P2pStats {
+        let mut stats = self.stats.snapshot();
+        stats.pool = self.conn_pool.stats();
+        stats.search_cache = self.search_cache.stats();
+        stats
+    }
 
-        // Create a persistent tag so the blob is not garbage collected
-        let tag_name = format!("published-{}", hash);
-        self.blob_store
-            .tags()
-            .set(tag_name, HashAndFormat::raw(hash))
-            .await
-            .map_err(|e| P2pError::BlobStore(format!("failed to set persistent tag: {e}")))?;
+    /// Messages waiting in each peer's outbound queues, keyed by node ID.
+    /// Peers nothing was sent to since they were last pruned are left out.
+    pub async fn peer_queue_depths(&self) -> HashMap<String, usize> {
+        self.conn_pool.queue_depths().await
+    }
 
-        // SECURITY: Register hash so it can be served to peers (FIX-19)
-        self.published_hashes.write().await.insert(hash.to_string());
-        info!(%hash, "track published to blob store");
-        Ok(hash)
+    /// Update the point-in-time gauges in [`P2P_METRICS`] (online peers,
+    /// blob cache size, health status counts). Called before each scrape.
+    pub async fn refresh_metrics(&self) {
+        P2P_METRICS
+            .peers_online
+            .set(self.registry.online_peers().await.len() as i64);
+        P2P_METRICS
+            .blob_cache_bytes
+            .set(self.blob_cache.total_size().await as i64);
+        // Drop statuses with no tracks left instead of keeping their last value
+        P2P_METRICS.track_health.reset();
+        for (status, count) in self.health_manager.status_counts().await {
+            P2P_METRICS
+                .track_health
+                .with_label_values(&[status.as_str()])
+                .set(count as i64);
+        }
     }
 
-    /// Publish cover art data to the local blob store.
-    /// Returns the content hash (BLAKE3) that identifies the blob.
-    pub async fn publish_cover(&self, data: Bytes) -> Result<Hash, P2pError> {
-        let outcome = self
-            .blob_store
-            .blobs()
-            .add_bytes(data)
-            .temp_tag()
-            .await
-            .map_err(|e| P2pError::BlobStore(e.to_string()))?;
+    /// Live node events, streamed by `GET /api/admin/p2p/events`.
+    pub fn events(&self) -> &P2pEventBus {
+        &self.events
+    }
 
-        let hash = outcome.hash();
+    /// Call `hook` for every track announced by a peer and stored locally.
+    /// Only the first hook registered is kept; returns `false` for later ones.
+    pub fn set_track_announced_hook(&self, hook: Arc<dyn TrackAnnouncedHook>) -> bool {
+        self.hooks.set_track_announced(hook)
+    }
 
-        // Create a persistent tag so the blob is not garbage collected
-        let tag_name = format!("published-{}", hash);
-        self.blob_store
-            .tags()
-            .set(tag_name, HashAndFormat::raw(hash))
-            .await
-            .map_err(|e| P2pError::BlobStore(format!("failed to set persistent tag: {e}")))?;
+    /// Content policy currently applied to announced tracks.
+    pub fn replication_policy(&self) -> ReplicationPolicy {
+        self.replication_policy
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
 
-        // SECURITY: Register hash so it can be served to peers (FIX-19)
-        self.published_hashes.write().await.insert(hash.to_string());
-        debug!(%hash, "cover art published to blob store");
-        Ok(hash)
+    /// Re-read the `p2p_replication_*` instance settings. Called after an
+    /// admin edits one, so the change applies without a restart.
+    pub async fn reload_replication_policy(&self) -> Result<(), P2pError> {
+        let policy = ReplicationPolicy::load(&self.db).await?;
+        info!(?policy, "replication policy reloaded");
+        *self
+            .replication_policy
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = policy;
+        Ok(())
     }
 
-    /// Retrieve a track's data from the local blob store by hash.
-    pub async fn get_local_track(&self, hash: Hash) -> Result<Bytes, P2pError> {
-        let data = self
-            .blob_store
-            .blobs()
-            .get_bytes(hash)
-            .await
-            .map_err(|e| P2pError::BlobStore(e.to_string()))?;
+    /// Most recent announcements refused by the replication policy, newest first.
+    pub fn rejected_announcements(&self) -> Vec<RejectedAnnouncement> {
+        self.rejections.recent()
+    }
 
-        Ok(data)
+    /// Announcements refused by the replication policy, counted per peer.
+    pub fn rejections_per_peer(&self) -> Vec<PeerRejections> {
+        self.rejections.per_peer()
     }
 
-    /// Retrieve a P2P track by hash, fetching from the origin peer on-demand if not cached.
-    ///
-    /// 1. Try the local blob store (fast path).
-    /// 2. If missing, look up the origin peer from `remote_tracks` and fetch via P2P.
-    /// 3. Store the fetched blob locally and register it in the LRU cache.
-    /// 4. Trigger eviction if the cache exceeds the configured limit.
-    pub async fn get_or_fetch_track(&self, hash: Hash) -> Result<Bytes, P2pError> {
-        // Fast path: blob exists locally
-        if let Ok(data) = self.get_local_track(hash).await {
-            self.blob_cache
-                .record_access_with_tag(hash, data.len() as u64, &self.blob_store)
-                .await;
-            return Ok(data);
+    /// Remove peers that failed `P2P_PEER_EVICTION_THRESHOLD` pings in a
+    /// row from the registry, the database, the search index and the upload
+    /// limiter, along with peers displaced under `P2P_MAX_PEERS`.
+    async fn evict_dead_peers(&self) {
+        let mut evicted = self
+            .registry
+            .evict_dead_peers(self._config.peer_eviction_threshold)
+            .await;
+        evicted.extend(self.registry.take_displaced_peers());
+        if evicted.is_empty() {
+            return;
         }
+        for peer_id in &evicted {
+            self.search_index.remove_peer(peer_id).await;
+            self.upload_limiter.remove_peer(peer_id).await;
+        }
+        if let Err(e) = PeerRegistry::delete_from_db(&self.db, &evicted).await {
+            warn!("failed to delete evicted peers: {e}");
+        }
+    }
 
-        // Look up origin peer from remote_track table
-        let hash_str = hash.to_string();
-        let remote = remote_track::Entity::find()
-            .filter(remote_track::Column::RemoteUri.ends_with(format!("/{}", hash_str)))
-            .one(&self.db)
-            .await
-            .map_err(P2pError::Database)?
-            .ok_or_else(|| P2pError::TrackNotFound(hash_str.clone()))?;
-
-        let origin = remote
-            .instance_domain
-            .strip_prefix("p2p://")
-            .unwrap_or(&remote.instance_domain);
-
-        let nid: EndpointId = origin
-            .parse()
-            .map_err(|_| P2pError::Connection(format!("invalid peer id: {}", origin)))?;
-
-        // In-flight dedup: if another task is already fetching this blob, wait and retry
-        if !self.blob_cache.try_start_fetch(hash).await {
-            debug!(%hash, "another fetch in progress, waiting");
-            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-            // Retry local — the other fetch may have completed
-            if let Ok(data) = self.get_local_track(hash).await {
-                self.blob_cache
-                    .record_access_with_tag(hash, data.len() as u64, &self.blob_store)
-                    .await;
-                return Ok(data);
+    /// Remove peers offline for longer than `P2P_PEER_OFFLINE_RETENTION_DAYS`
+    /// from the registry, the database, the search index and the upload
+    /// limiter. Seed peers and trusted peers are kept.
+    async fn prune_offline_peers(&self) {
+        let days = self._config.peer_offline_retention_days;
+        if days == 0 {
+            return;
+        }
+        let db_seeds = match seed_peers::list(&self.db).await {
+            Ok(seeds) => seeds,
+            Err(e) => {
+                // Without the list, seed peers added by an admin would look
+                // prunable
+                warn!("failed to load seed peers, not pruning offline peers: {e}");
+                return;
             }
-            return Err(P2pError::TrackNotFound(hash_str));
+        };
+        let seeds: std::collections::HashSet<String> =
+            seed_peers::merge_seeds(&self._config.seed_peers, &db_seeds)
+                .iter()
+                .map(|entry| seed_peers::seed_node_id(entry).to_string())
+                .collect();
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(i64::from(days));
+        let pruned = self.registry.prune_offline_peers(cutoff, &seeds).await;
+        if pruned.is_empty() {
+            return;
         }
-
-        // Fetch from peer
-        let result = async {
-            info!(%hash, peer = %origin, "on-demand fetch from peer");
-            let peer_addr = EndpointAddr::new(nid);
-            let data = self.fetch_track_from_peer(peer_addr, hash).await?;
-
-            // Store in local blob store
-            let _tag = self
-                .blob_store
-                .blobs()
-                .add_bytes(data.clone())
-                .temp_tag()
-                .await
-                .map_err(|e| P2pError::BlobStore(e.to_string()))?;
-
-            // Register in LRU cache and evict if over limit
-            self.blob_cache
-                .record_access_with_tag(hash, data.len() as u64, &self.blob_store)
-                .await;
-            self.blob_cache.evict_if_needed(&self.blob_store).await;
-
-            info!(%hash, bytes = data.len(), "on-demand fetch complete, blob cached");
-            Ok(data)
+        let ids: Vec<String> = pruned.iter().map(|p| p.node_id.clone()).collect();
+        for peer in &pruned {
+            info!(
+                peer = %peer.node_id,
+                last_seen = %peer.last_seen,
+                "pruning peer offline for more than {days} days"
+            );
+            self.forget_peer(&peer.node_id).await;
         }
-        .await;
+        P2P_METRICS.peers_pruned_total.inc_by(pruned.len() as u64);
+        if let Err(e) = PeerRegistry::delete_from_db(&self.db, &ids).await {
+            warn!("failed to delete pruned peers: {e}");
+        }
+    }
 
-        // Always clear in-flight flag
-        self.blob_cache.finish_fetch(hash).await;
+    /// Remove a peer from the registry, the search index and the upload
+    /// limiter (admin action). It comes back if it connects again.
+    pub async fn remove_peer(&self, peer_id: &str) {
+        self.registry.remove_peer(peer_id).await;
+        self.forget_peer(peer_id).await;
+    }
 
-        result
+    /// Drop what the node keeps about a peer outside the registry: its
+    /// bloom filter, rate limits and pooled connection. Its latency goes
+    /// with its registry entry.
+    async fn forget_peer(&self, peer_id: &str) {
+        self.search_index.remove_peer(peer_id).await;
+        self.upload_limiter.remove_peer(peer_id).await;
+        self.play_count_rate.remove_peer(peer_id);
+        self.delete_track_rate.remove_peer(peer_id);
+        if let Ok(node_id) = peer_id.parse::<EndpointId>() {
+            self.conn_pool.invalidate(&node_id).await;
+        }
     }
 
-    /// Check if a blob exists locally.
-    pub async fn has_blob(&self, hash: Hash) -> bool {
-        self.blob_store.blobs().has(hash).await.unwrap_or(false)
+    /// Replication filter for `peer_id`, if one is set.
+    pub fn peer_filter(&self, peer_id: &str) -> Option<PeerFilter> {
+        self.peer_filters.get(peer_id).map(|f| f.clone())
     }
 
-    /// Connect to a remote peer and fetch a track by its content hash.
-    pub async fn fetch_track_from_peer(
+    /// Set the replication filter for `peer_id`. It applies to the peer's
+    /// next announcements; tracks already replicated are kept.
+    pub async fn set_peer_filter(
         &self,
-        peer_addr: EndpointAddr,
-        hash: Hash,
-    ) -> Result<Bytes, P2pError> {
-        let peer_id = peer_addr.id.to_string();
+        peer_id: &str,
+        filter: PeerFilter,
+    ) -> Result<PeerFilter, P2pError> {
+        let filter = filter.normalized();
+        peer_filter::save(&self.db, peer_id, &filter).await?;
+        info!(%peer_id, ?filter, "peer replication filter updated");
+        self.peer_filters
+            .insert(peer_id.to_string(), filter.clone());
+        self.peer_track_counts.remove(peer_id);
+        Ok(filter)
+    }
 
-        // Check if peer is blocked
-        if is_peer_blocked(&self.db, &peer_id).await {
-            return Err(P2pError::PeerBlocked(peer_id));
+    /// Remove the replication filter for `peer_id`. Returns `false` if none
+    /// was set.
+    pub async fn remove_peer_filter(&self, peer_id: &str) -> Result<bool, P2pError> {
+        let removed = peer_filter::delete(&self.db, peer_id).await?;
+        self.peer_filters.remove(peer_id);
+        self.peer_track_counts.remove(peer_id);
+        if removed {
+            info!(%peer_id, "peer replication filter removed");
         }
+        Ok(removed)
+    }
 
-        info!(peer = %peer_id, %hash, "fetching track from peer");
+    /// Internal: describe a peer's copy of a track for quality ranking.
+    async fn peer_track_info(&self, rt: &remote_track::Model, file_size: i64) -> PeerTrackInfo {
+        let origin = rt
+            .instance_domain
+            .strip_prefix("p2p://")
+            .unwrap_or(&rt.instance_domain)
+            .to_string();
+        let peer = self.registry.get_peer(&origin).await;
+        PeerTrackInfo {
+            format: rt.format.clone().unwrap_or_default(),
+            bitrate: rt.bitrate,
+            sample_rate: rt.sample_rate,
+            is_online: peer.as_ref().is_some_and(|p| p.is_online),
+            file_size,
+            trusted: peer.as_ref().is_some_and(|p| p.trust == PeerTrust::Trusted),
+            avg_latency_ms: peer.as_ref().and_then(|p| p.avg_latency_ms),
+            success_rate: peer.as_ref().and_then(|p| p.success_rate),
+            peer_id: origin,
+        }
+    }
 
-        let conn = self.conn_pool.get_connection(peer_addr.id).await?;
+    /// Whether `peer_id` is filtered to metadata-only replication.
+    fn is_metadata_only(&self, peer_id: &str) -> bool {
+        self.peer_filters
+            .get(peer_id)
+            .is_some_and(|f| f.metadata_only)
+    }
 
-        // Send fetch request
-        let (mut send, mut recv) = match conn.open_bi().await {
-            Ok(streams) => streams,
-            Err(e) => {
-                self.conn_pool.invalidate(&peer_addr.id).await;
-                return Err(P2pError::Connection(e.to_string()));
-            }
+    /// Internal: check an announcement against the sending peer's filter.
+    /// The track cap is only checked for tracks we don't have yet, and a
+    /// track passing it takes one of the peer's slots; give it back with
+    /// [`release_peer_track`](Self::release_peer_track) if the track is not
+    /// stored after all.
+    async fn check_peer_filter(
+        &self,
+        ann: &TrackAnnouncement,
+        peer_id: &str,
+        new_track: bool,
+    ) -> Result<(), RejectReason> {
+        let Some(filter) = self.peer_filter(peer_id) else {
+            return Ok(());
         };
+        filter.check(ann)?;
+        if new_track && filter.max_tracks.is_some() {
+            self.reserve_peer_track(&filter, peer_id).await?;
+        }
+        Ok(())
+    }
 
-        let request = P2pMessage::FetchTrack {
-            hash: hash.to_string(),
-        };
-        let request_bytes = serde_json::to_vec(&request)?;
-        send.write_all(&(request_bytes.len() as u32).to_be_bytes())
-            .await
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
-        send.write_all(&request_bytes)
+    /// Internal: tracks counted against `peer_id`'s cap — those it
+    /// originated or reseeds.
+    async fn count_peer_tracks(&self, peer_id: &str) -> Result<u64, sea_orm::DbErr> {
+        remote_track::Entity::find()
+            .filter(remote_track::Column::InstanceDomain.eq(format!("p2p://{peer_id}")))
+            .count(&self.db)
             .await
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
-        send.finish()
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
+    }
 
-        // Read response — first 4 bytes = length, then data
-        let mut len_buf = [0u8; 4];
-        recv.read_exact(&mut len_buf)
-            .await
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
-        let data_len = u32::from_be_bytes(len_buf) as usize;
+    /// Internal: reload the cached track count of a capped peer, so tracks
+    /// removed since are given back. Called once per catalog page.
+    async fn refresh_peer_track_count(&self, peer_id: &str) {
+        if self
+            .peer_filter(peer_id)
+            .is_none_or(|f| f.max_tracks.is_none())
+        {
+            self.peer_track_counts.remove(peer_id);
+            return;
+        }
+        match self.count_peer_tracks(peer_id).await {
+            Ok(count) => {
+                self.peer_track_counts.insert(peer_id.to_string(), count);
+            }
+            Err(e) => {
+                warn!(%peer_id, "failed to count the peer's replicated tracks: {e}");
+                self.peer_track_counts.remove(peer_id);
+            }
+        }
+    }
 
-        if data_len == 0 {
-            return Err(P2pError::TrackNotFound(hash.to_string()));
+    /// Internal: take one of the peer's `max_tracks` slots for a new track.
+    /// The check and the increment happen under the count's lock, so
+    /// concurrent announcements cannot overshoot the cap. Refused when the
+    /// count cannot be loaded.
+    async fn reserve_peer_track(
+        &self,
+        filter: &PeerFilter,
+        peer_id: &str,
+    ) -> Result<(), RejectReason> {
+        if !self.peer_track_counts.contains_key(peer_id) {
+            let count = self.count_peer_tracks(peer_id).await.map_err(|e| {
+                warn!(%peer_id, "failed to count the peer's replicated tracks: {e}");
+                RejectReason::PeerTrackLimit(filter.max_tracks.unwrap_or(0))
+            })?;
+            self.peer_track_counts
+                .entry(peer_id.to_string())
+                .or_insert(count);
         }
+        let mut count = self
+            .peer_track_counts
+            .entry(peer_id.to_string())
+            .or_insert(0);
+        filter.check_track_limit(*count)?;
+        *count += 1;
+        Ok(())
+    }
 
-        let data = recv
-            .read_to_end(data_len)
-            .await
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
+    /// Internal: give back a slot taken by
+    /// [`reserve_peer_track`](Self::reserve_peer_track) for a track that was
+    /// not stored.
+    fn release_peer_track(&self, peer_id: &str) {
+        if let Some(mut count) = self.peer_track_counts.get_mut(peer_id) {
+            *count = count.saturating_sub(1);
+        }
+    }
 
-        debug!(%hash, bytes = data.len(), "received track from peer");
-        Ok(Bytes::from(data))
+    /// Block a track blob on this instance and ask every online peer to do
+    /// the same. The block is written in `txn`, which is committed first, so
+    /// the caller can record the action in the same transaction. Returns the
+    /// number of peers the block was sent to.
+    pub async fn block_hash(
+        self: &Arc<Self>,
+        txn: DatabaseTransaction,
+        hash: Hash,
+        reason: &str,
+    ) -> Result<usize, P2pError> {
+        let hash = hash.to_string();
+        moderation::save_block(&txn, &hash, reason, None, BlockStatus::Active).await?;
+        txn.commit().await?;
+        self.apply_block(&hash).await;
+        info!(%hash, "blocked hash, propagating to peers");
+        Ok(self
+            .broadcast_block_hash(hash, moderation::clean_reason(reason))
+            .await)
     }
 
-    /// Send a ping to a peer and wait for pong.
-    pub async fn ping_peer(&self, peer_addr: EndpointAddr) -> Result<P2pMessage, P2pError> {
-        let conn = self.conn_pool.get_connection(peer_addr.id).await.map_err(|e| {
-            let err_str = format!("{e}");
-            if err_str.contains("ALPN") || err_str.contains("protocol") || err_str.contains("timed out") {
-                P2pError::Connection(format!(
-                    "{e} (possible protocol version mismatch — this node uses iroh 0.96 / ALPN 'soundtime/p2p/1')"
-                ))
-            } else {
-                e
-            }
-        })?;
+    /// Block a track blob on this instance only, without telling peers.
+    pub async fn block_hash_locally(&self, hash: Hash, reason: &str) -> Result<(), P2pError> {
+        let hash = hash.to_string();
+        moderation::save_block(&self.db, &hash, reason, None, BlockStatus::Active).await?;
+        self.apply_block(&hash).await;
+        info!(%hash, "blocked hash on this instance");
+        Ok(())
+    }
 
-        let (mut send, mut recv) = match conn.open_bi().await {
-            Ok(streams) => streams,
-            Err(e) => {
-                self.conn_pool.invalidate(&peer_addr.id).await;
-                return Err(P2pError::Connection(e.to_string()));
-            }
+    /// Apply a block received from an untrusted peer after admin review,
+    /// committing `txn` with the change. Returns `false`, and rolls `txn`
+    /// back, if no block is stored for `hash`.
+    pub async fn approve_blocked_hash(
+        &self,
+        txn: DatabaseTransaction,
+        hash: &str,
+    ) -> Result<bool, P2pError> {
+        let Some(status) = moderation::block_status(&txn, hash).await? else {
+            return Ok(false);
         };
+        if status == BlockStatus::Pending {
+            moderation::set_block_status(&txn, hash, BlockStatus::Active).await?;
+        }
+        txn.commit().await?;
+        if status == BlockStatus::Pending {
+            info!(%hash, "approved pending block");
+        }
+        self.apply_block(hash).await;
+        Ok(true)
+    }
 
-        let ping = serde_json::to_vec(&P2pMessage::Ping)?;
-        send.write_all(&(ping.len() as u32).to_be_bytes())
-            .await
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
-        send.write_all(&ping)
-            .await
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
-        send.finish()
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
-
-        let mut len_buf = [0u8; 4];
-        recv.read_exact(&mut len_buf)
-            .await
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
-        let msg_len = u32::from_be_bytes(len_buf) as usize;
-
-        let response = recv
-            .read_to_end(msg_len)
-            .await
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
-
-        let pong: P2pMessage = serde_json::from_slice(&response)?;
-        Ok(pong)
+    /// Remove the block on `hash` (active or pending), committing `txn`
+    /// with the change. A local track with that hash is served again, and
+    /// replicated tracks the block hid are shown again with their copies
+    /// marked available, for the health monitor to re-check. Tracks hidden
+    /// as dead or unverified stay hidden. Returns `false`, and rolls `txn`
+    /// back, if there was no block.
+    pub async fn unblock_hash(
+        &self,
+        txn: DatabaseTransaction,
+        hash: &str,
+    ) -> Result<bool, P2pError> {
+        if !moderation::delete_block(&txn, hash).await? {
+            return Ok(false);
+        }
+        txn.commit().await?;
+        self.blocked_hashes.remove(hash).await;
+        let local = track::Entity::find()
+            .filter(track::Column::ContentHash.eq(Some(hash.to_string())))
+            .filter(track::Column::FilePath.not_like("p2p://%"))
+            .one(&self.db)
+            .await?;
+        if local.is_some() {
+            self.published_hashes
+                .publish(hash, PublishedKind::Track)
+                .await?;
+        }
+        self.unhide_unblocked(hash).await?;
+        info!(%hash, "unblocked hash");
+        Ok(true)
     }
 
-    /// Connect to a list of seed peers by EndpointId.
-    /// Pings each one and registers it in the registry.
-    async fn connect_to_seed_peers(&self, seed_peers: &[String]) {
-        info!(count = seed_peers.len(), "connecting to seed peers");
+    /// Internal: show again the replicated tracks of `hash` its block hid.
+    async fn unhide_unblocked(&self, hash: &str) -> Result<(), P2pError> {
+        use sea_orm::QuerySelect;
 
-        for peer_id_str in seed_peers {
-            let node_id: EndpointId = match peer_id_str.parse() {
-                Ok(id) => id,
-                Err(e) => {
-                    warn!(peer = %peer_id_str, "invalid seed peer EndpointId: {e}");
-                    continue;
-                }
-            };
+        let hidden: Vec<Uuid> = track::Entity::find()
+            .select_only()
+            .column(track::Column::Id)
+            .filter(track::Column::ContentHash.eq(hash))
+            .filter(track::Column::HiddenByBlock.eq(true))
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+        if hidden.is_empty() {
+            return Ok(());
+        }
+        remote_track::Entity::update_many()
+            .col_expr(
+                remote_track::Column::IsAvailable,
+                sea_orm::sea_query::Expr::value(true),
+            )
+            .filter(remote_track::Column::LocalTrackId.is_in(hidden.clone()))
+            .exec(&self.db)
+            .await?;
+        let shown = track::Entity::update_many()
+            .col_expr(
+                track::Column::IsHidden,
+                sea_orm::sea_query::Expr::value(false),
+            )
+            .col_expr(
+                track::Column::HiddenByBlock,
+                sea_orm::sea_query::Expr::value(false),
+            )
+            .filter(track::Column::Id.is_in(hidden))
+            .exec(&self.db)
+            .await?;
+        info!(%hash, tracks = shown.rows_affected, "showed replicated tracks of unblocked hash");
+        self.rebuild_search_index().await;
+        Ok(())
+    }
 
-            // Skip ourselves
-            if node_id == self.node_id() {
-                debug!("skipping self in seed peers");
-                continue;
-            }
+    /// Whether `hash` has an active block.
+    pub async fn is_hash_blocked(&self, hash: &str) -> bool {
+        self.blocked_hashes.contains(hash).await
+    }
 
-            let peer_addr = EndpointAddr::new(node_id);
-            info!(peer = %peer_id_str, "pinging seed peer");
+    /// Every stored block, active and pending, newest first.
+    pub async fn list_blocked_hashes(&self) -> Result<Vec<blocked_hash::Model>, P2pError> {
+        moderation::list_blocks(&self.db).await
+    }
 
-            match self.ping_peer(peer_addr).await {
-                Ok(P2pMessage::Pong {
-                    node_id: nid,
-                    track_count,
-                    version,
-                }) => {
-                    self.registry
-                        .upsert_peer_versioned(&nid, None, track_count, version)
-                        .await;
-                    info!(peer = %nid, %track_count, "seed peer connected and registered");
-                    // Exchange peer lists to discover the wider network
-                    self.discover_via_peer(node_id).await;
-                    // Sync our full catalog to this peer so they get our existing tracks
-                    self.announce_all_tracks_to_peer(node_id).await;
-                }
-                Ok(_) => {
-                    self.registry.upsert_peer(peer_id_str, None, 0).await;
-                    warn!(peer = %peer_id_str, "seed peer responded with unexpected message");
-                }
-                Err(e) => {
-                    // Check if it's a protocol version mismatch
-                    let err_str = format!("{e}");
-                    if err_str.contains("ALPN")
-                        || err_str.contains("protocol")
-                        || err_str.contains("version")
-                        || err_str.contains("timed out")
-                    {
-                        warn!(
-                            peer = %peer_id_str,
-                            "failed to reach seed peer (possible protocol version mismatch — \
-                             this node uses iroh 0.96 / ALPN 'soundtime/p2p/1', the remote peer \
-                             may be running an incompatible version): {e}"
-                        );
-                    } else {
-                        warn!(peer = %peer_id_str, "failed to reach seed peer: {e}");
-                    }
-                }
-            }
+    /// Let `peer_id`'s blocks apply without review, or stop doing so,
+    /// committing `txn` with the change. Returns `false`, and rolls `txn`
+    /// back, if the peer is unknown.
+    pub async fn set_trusted_moderator(
+        &self,
+        txn: DatabaseTransaction,
+        peer_id: &str,
+        trusted: bool,
+    ) -> Result<bool, P2pError> {
+        if !moderation::set_trusted_moderator(&txn, peer_id, trusted).await? {
+            return Ok(false);
         }
-
-        let total = self.registry.peer_count().await;
-        let online = self.registry.online_peers().await.len();
-        info!(%total, %online, "seed peer discovery complete");
+        txn.commit().await?;
+        info!(%peer_id, trusted, "trusted moderator flag changed");
+        Ok(true)
     }
 
-    /// Send a message to a specific peer with retry and exponential backoff.
-    ///
-    /// Serializes the message once, then attempts to deliver it up to 3 times
-    /// with doubling delays (1s, 2s, 4s) between retries.
-    async fn send_message_to_peer(
+    /// Set the admin's label, notes and trust tier for a known peer and
+    /// save them. Returns the updated peer, or `None` if it is unknown.
+    pub async fn set_peer_details(
         &self,
-        node_id: EndpointId,
-        msg: &P2pMessage,
-    ) -> Result<(), P2pError> {
-        let msg_bytes = serde_json::to_vec(msg)?;
-        let max_retries = 3u32;
-        let mut delay = std::time::Duration::from_secs(1);
+        peer_id: &str,
+        label: Option<String>,
+        notes: Option<String>,
+        trust: PeerTrust,
+    ) -> Result<Option<PeerInfo>, P2pError> {
+        let Some(info) = self
+            .registry
+            .set_peer_details(peer_id, label, notes, trust)
+            .await
+        else {
+            return Ok(None);
+        };
+        self.registry.save_peer_to_db(&self.db, peer_id).await?;
+        info!(%peer_id, %trust, "peer details updated");
+        Ok(Some(info))
+    }
 
-        for attempt in 0..max_retries {
-            match self.try_send_bytes(node_id, &msg_bytes).await {
-                Ok(()) => return Ok(()),
-                Err(e) if attempt < max_retries - 1 => {
-                    warn!(peer = %node_id, attempt, "send failed, retrying in {:?}: {e}", delay);
-                    tokio::time::sleep(delay).await;
-                    delay *= 2;
-                }
-                Err(e) => return Err(e),
+    /// Internal: record a `BlockHash` from a peer. Applied at once if the
+    /// peer is a trusted moderator, otherwise queued for admin review.
+    async fn receive_block(&self, hash: &str, reason: &str, peer_id: &str) -> Result<(), P2pError> {
+        if hash.parse::<Hash>().is_err() {
+            warn!(%peer_id, %hash, "ignoring block of invalid hash");
+            return Ok(());
+        }
+        let trusted = moderation::is_trusted_moderator(&self.db, peer_id).await?;
+        let existing = moderation::block_status(&self.db, hash).await?;
+        let Some(status) = incoming_block_status(trusted, existing) else {
+            debug!(%peer_id, %hash, "hash already blocked");
+            return Ok(());
+        };
+        moderation::save_block(&self.db, hash, reason, Some(peer_id), status).await?;
+        match status {
+            BlockStatus::Active => {
+                info!(%peer_id, %hash, %reason, "applying block from trusted moderator");
+                self.apply_block(hash).await;
+            }
+            BlockStatus::Pending => {
+                info!(%peer_id, %hash, %reason, "queued block from untrusted peer for review");
             }
         }
-        unreachable!()
+        Ok(())
     }
 
-    /// Internal: single attempt to send pre-serialized message bytes to a peer.
-    async fn try_send_bytes(&self, node_id: EndpointId, msg_bytes: &[u8]) -> Result<(), P2pError> {
-        let conn = self.conn_pool.get_connection(node_id).await?;
-
-        let result: Result<(), P2pError> = async {
-            let (mut send, _recv) = conn
-                .open_bi()
-                .await
-                .map_err(|e| P2pError::Connection(e.to_string()))?;
-
-            send.write_all(&(msg_bytes.len() as u32).to_be_bytes())
-                .await
-                .map_err(|e| P2pError::Connection(e.to_string()))?;
-            send.write_all(msg_bytes)
-                .await
-                .map_err(|e| P2pError::Connection(e.to_string()))?;
-            send.finish()
-                .map_err(|e| P2pError::Connection(e.to_string()))?;
-
-            // Wait for the peer to acknowledge receipt (with timeout)
-            match tokio::time::timeout(std::time::Duration::from_secs(30), send.stopped()).await {
-                Ok(Ok(_)) => {}
-                Ok(Err(_)) => {}
-                Err(_) => {
-                    warn!("timed out waiting for stream ack");
-                }
-            }
-
-            Ok(())
+    /// Internal: stop serving `hash` and, if configured, hide replicated
+    /// copies of it and drop them from the search index.
+    async fn apply_block(&self, hash: &str) {
+        self.blocked_hashes.insert(hash).await;
+        self.published_hashes.suspend(hash).await;
+        if !self._config.hide_blocked_tracks {
+            return;
         }
-        .await;
-
-        if result.is_err() {
-            self.conn_pool.invalidate(&node_id).await;
+        if let Err(e) = remote_track::Entity::update_many()
+            .col_expr(
+                remote_track::Column::IsAvailable,
+                sea_orm::sea_query::Expr::value(false),
+            )
+            .filter(remote_track::Column::RemoteUri.like(format!("p2p://%/{hash}")))
+            .exec(&self.db)
+            .await
+        {
+            warn!(%hash, "failed to hide replicated copies of blocked track: {e}");
+        }
+        // Marked so unblocking shows them again, unlike tracks hidden as
+        // dead or unverified
+        let hidden = track::Entity::update_many()
+            .col_expr(
+                track::Column::IsHidden,
+                sea_orm::sea_query::Expr::value(true),
+            )
+            .col_expr(
+                track::Column::HiddenByBlock,
+                sea_orm::sea_query::Expr::value(true),
+            )
+            .filter(track::Column::ContentHash.eq(hash))
+            .filter(track::Column::FilePath.like("p2p://%"))
+            .filter(track::Column::IsHidden.eq(false))
+            .exec(&self.db)
+            .await;
+        match hidden {
+            // Hidden tracks are left out of the rebuilt index, so their
+            // Bloom tokens go and the next broadcast sends the full filter
+            Ok(res) if res.rows_affected > 0 => {
+                info!(%hash, tracks = res.rows_affected, "hid replicated tracks of blocked hash");
+                self.rebuild_search_index().await;
+            }
+            Ok(_) => {}
+            Err(e) => warn!(%hash, "failed to hide replicated tracks of blocked hash: {e}"),
         }
-
-        result
     }
 
-    /// Broadcast a track announcement to all online peers concurrently.
-    /// Called after a track is published to the local blob store.
-    /// Uses a semaphore to limit concurrency to 10 simultaneous sends.
-    pub async fn broadcast_announce_track(self: &Arc<Self>, announcement: TrackAnnouncement) {
+    /// Internal: send `BlockHash` to every online peer. Returns how many
+    /// peers it was sent to.
+    async fn broadcast_block_hash(self: &Arc<Self>, hash: String, reason: String) -> usize {
         let peers = self.registry.online_peers().await;
-        if peers.is_empty() {
-            debug!(hash = %announcement.hash, "no online peers to announce track to");
-            return;
-        }
-
-        info!(
-            hash = %announcement.hash,
-            title = %announcement.title,
-            artist = %announcement.artist_name,
-            peer_count = peers.len(),
-            "broadcasting track announcement"
-        );
-
-        let msg = P2pMessage::AnnounceTrack(announcement);
+        let msg = P2pMessage::BlockHash { hash, reason };
         let semaphore = Arc::new(tokio::sync::Semaphore::new(10));
         let mut handles = Vec::new();
 
@@ -1113,557 +2886,1384 @@ impl P2pNode {
 
             handles.push(tokio::spawn(async move {
                 let _permit = sem.acquire().await.ok();
-                if let Err(e) = node.send_message_to_peer(node_id, &msg).await {
-                    warn!(peer = %peer_id, "failed to announce track: {e}");
-                    node.registry.mark_offline(&peer_id).await;
-                } else {
-                    debug!(peer = %peer_id, "track announced");
+                match node.send_message_to_peer(node_id, &msg).await {
+                    Ok(()) => true,
+                    Err(e) => {
+                        warn!(peer = %peer_id, "failed to send blocked hash: {e}");
+                        false
+                    }
                 }
             }));
         }
 
+        let mut sent = 0;
         for h in handles {
-            let _ = h.await;
+            if let Ok(true) = h.await {
+                sent += 1;
+            }
         }
+        sent
     }
 
-    /// Backfill `content_hash` for local tracks that were imported before P2P was enabled.
-    /// Reads each file, publishes it to the blob store (BLAKE3), and updates the DB.
-    /// Runs on startup to ensure all local tracks are available for P2P catalog sync.
-    async fn backfill_content_hashes(&self) {
-        let page_size = 100u64;
-        let total = match track::Entity::find()
-            .filter(track::Column::ContentHash.is_null())
-            .filter(track::Column::FilePath.not_like("p2p://%"))
-            .count(&self.db)
+    /// Let `peer_id` fetch the private track `hash` for the next
+    /// [`GRANT_MAX_AGE_SECS`](track_access::GRANT_MAX_AGE_SECS): sign a
+    /// grant, record it and send it to the peer as `AuthorizeTrackAccess`.
+    /// Returns the token and whether the peer received it.
+    pub async fn grant_track_access(
+        &self,
+        peer_id: &str,
+        hash: Hash,
+    ) -> Result<(String, bool), P2pError> {
+        let node_id: EndpointId = peer_id
+            .parse()
+            .map_err(|_| P2pError::InvalidPeerAddr(peer_id.to_string()))?;
+        let hash = hash.to_string();
+        if !self.published_hashes.contains(&hash).await {
+            return Err(P2pError::TrackNotFound(hash));
+        }
+
+        let token = track_access::sign_grant(
+            &self.grant_key(),
+            &hash,
+            peer_id,
+            chrono::Utc::now().timestamp(),
+        );
+        track_access::save_grant(
+            &self.db,
+            &hash,
+            peer_id,
+            &self.node_id().to_string(),
+            &token,
+        )
+        .await?;
+
+        let msg = P2pMessage::AuthorizeTrackAccess {
+            hash: hash.clone(),
+            requester_node: peer_id.to_string(),
+            token: token.clone(),
+        };
+        let delivered = match self.send_message_to_peer(node_id, &msg).await {
+            Ok(()) => true,
+            Err(e) => {
+                warn!(peer = %peer_id, %hash, "failed to send track access grant: {e}");
+                false
+            }
+        };
+        info!(peer = %peer_id, %hash, delivered, "granted access to private track");
+        Ok((token, delivered))
+    }
+
+    /// Internal: key signing track access grants.
+    fn grant_key(&self) -> [u8; 32] {
+        track_access::grant_key(&self.endpoint.secret_key().to_bytes())
+    }
+
+    /// Internal: whether `peer_id` may fetch `hash`: always if the track is
+    /// not private, otherwise only with a valid, fresh grant `token`.
+    async fn may_fetch(&self, peer_id: &str, hash: &str, token: Option<&str>) -> bool {
+        match track_access::is_private_hash(&self.db, hash).await {
+            Ok(false) => true,
+            Ok(true) => token.is_some_and(|token| {
+                track_access::verify_grant(
+                    &self.grant_key(),
+                    hash,
+                    peer_id,
+                    token,
+                    chrono::Utc::now().timestamp(),
+                )
+            }),
+            Err(e) => {
+                // Fail closed: a private track must never leak
+                warn!(%hash, "failed to check whether track is private: {e}");
+                false
+            }
+        }
+    }
+
+    /// Internal: store a grant a peer issued us, presented in our fetches
+    /// of `hash` from that peer. Only the peer that announced the track can
+    /// grant access to it.
+    async fn receive_track_grant(
+        &self,
+        hash: &str,
+        requester_node: &str,
+        token: &str,
+        peer_id: &str,
+    ) -> Result<(), P2pError> {
+        if requester_node != self.node_id().to_string() {
+            warn!(%peer_id, %hash, %requester_node, "ignoring track grant for another node");
+            return Ok(());
+        }
+        if hash.parse::<Hash>().is_err() {
+            warn!(%peer_id, %hash, "ignoring track grant for invalid hash");
+            return Ok(());
+        }
+        if !track_access::is_announced_by(&self.db, peer_id, hash).await? {
+            warn!(%peer_id, %hash, "ignoring track grant from a peer that is not the origin");
+            return Ok(());
+        }
+        track_access::save_received_grant(&self.db, hash, requester_node, peer_id, token).await?;
+        info!(%peer_id, %hash, "received access grant for private track");
+        Ok(())
+    }
+
+    /// Internal: grant token `peer_id` issued us for `hash`, if any.
+    async fn fetch_token(&self, peer_id: &str, hash: &str) -> Option<String> {
+        track_access::grant_token(&self.db, peer_id, hash)
             .await
-        {
-            Ok(0) => {
-                debug!("all local tracks already have content hashes");
+            .unwrap_or_else(|e| {
+                warn!(peer = %peer_id, %hash, "failed to look up track grant: {e}");
+                None
+            })
+    }
+
+    /// Send our most played hashes to every online peer.
+    async fn broadcast_popularity(self: &Arc<Self>) {
+        let entries = match popularity::local_top_plays(&self.db).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("failed to load play counts for gossip: {e}");
                 return;
             }
-            Ok(c) => c,
+        };
+        // An empty gossip still clears what peers hold from an earlier one
+        let msg = P2pMessage::PopularityGossip { entries };
+        for peer in self.registry.online_peers().await {
+            let Ok(node_id) = peer.node_id.parse::<EndpointId>() else {
+                continue;
+            };
+            let node = Arc::clone(self);
+            let msg = msg.clone();
+            tokio::spawn(async move {
+                if let Err(e) = node.send_message_to_peer(node_id, &msg).await {
+                    debug!(peer = %node_id, "failed to send popularity gossip: {e}");
+                }
+            });
+        }
+    }
+
+    /// Checksum of the local catalog, see [`catalog_checksum`](crate::catalog_checksum). Computed
+    /// once and reused until [`invalidate_catalog_checksum`](Self::invalidate_catalog_checksum)
+    /// or [`CHECKSUM_MAX_AGE`](crate::catalog_checksum::CHECKSUM_MAX_AGE).
+    pub async fn compute_local_catalog_checksum(&self) -> Result<CatalogChecksum, P2pError> {
+        self.catalog_checksum_cache.get(&self.db).await
+    }
+
+    /// Recompute the catalog checksum on next use. Call after local tracks
+    /// were added, deleted or given a content hash.
+    pub fn invalidate_catalog_checksum(&self) {
+        self.catalog_checksum_cache.invalidate();
+    }
+
+    /// Compare catalog checksums with one random online peer on protocol v2
+    /// and request its full catalog if it has tracks we are missing.
+    async fn check_catalog_drift(&self) {
+        let peers: Vec<_> = self
+            .registry
+            .online_peers()
+            .await
+            .into_iter()
+            .filter(|p| {
+                p.protocol_version
+                    .is_some_and(|v| v >= ProtocolVersion::V2.as_u8())
+            })
+            .collect();
+        if peers.is_empty() {
+            return;
+        }
+        let peer = &peers[rand::random_range(0..peers.len())];
+        let Ok(peer_id) = peer.node_id.parse::<EndpointId>() else {
+            return;
+        };
+
+        let local = match self.compute_local_catalog_checksum().await {
+            Ok(local) => local,
             Err(e) => {
-                warn!("failed to count tracks for content hash backfill: {e}");
+                warn!("failed to compute catalog checksum: {e}");
+                return;
+            }
+        };
+        let (checksum, track_count) = match self.request_catalog_checksum(peer_id).await {
+            Ok(remote) => remote,
+            Err(e) => {
+                debug!(peer = %peer_id, "catalog checksum exchange failed: {e}");
                 return;
             }
         };
 
-        info!(total, "starting content hash backfill for local tracks");
+        if local.behind(&checksum, track_count) {
+            info!(
+                peer = %peer_id,
+                local_tracks = local.track_count,
+                peer_tracks = track_count,
+                "catalog drift detected — requesting full catalog"
+            );
+            if let Err(e) = self.request_catalog_from_peer(peer_id).await {
+                warn!(peer = %peer_id, "failed to request catalog: {e}");
+            }
+        } else if local.checksum != checksum {
+            debug!(peer = %peer_id, "catalog checksum differs, peer has no more tracks");
+        }
+    }
 
-        let num_pages = total.div_ceil(page_size);
-        let mut backfilled = 0u64;
-        let mut skipped = 0u64;
+    /// Ask a peer for its catalog checksum and track count.
+    async fn request_catalog_checksum(
+        &self,
+        peer_id: EndpointId,
+    ) -> Result<(String, u64), P2pError> {
+        let conn = self.conn_pool.get_connection(peer_id).await?;
 
-        for page_num in 0..num_pages {
-            let tracks = match track::Entity::find()
-                .filter(track::Column::ContentHash.is_null())
-                .filter(track::Column::FilePath.not_like("p2p://%"))
-                .paginate(&self.db, page_size)
-                .fetch_page(page_num)
-                .await
+        let (mut send, mut recv) = match conn.open_bi().await {
+            Ok(streams) => streams,
+            Err(e) => {
+                self.conn_pool.invalidate(&peer_id).await;
+                return Err(P2pError::Connection(e.to_string()));
+            }
+        };
+
+        let msg = P2pMessage::CatalogChecksumRequest;
+        let msg_bytes = serde_json::to_vec(&msg)?;
+        send.write_all(&(msg_bytes.len() as u32).to_be_bytes())
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.write_all(&msg_bytes)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.finish()
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        self.stats.record_sent(msg.kind());
+
+        // Read response
+        let mut len_buf = [0u8; 4];
+        recv.read_exact(&mut len_buf)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        let msg_len = u32::from_be_bytes(len_buf) as usize;
+
+        let response = recv
+            .read_to_end(msg_len)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+
+        match serde_json::from_slice(&response)? {
+            P2pMessage::CatalogChecksumResponse {
+                checksum,
+                track_count,
+            } => Ok((checksum, track_count)),
+            other => Err(P2pError::Connection(format!(
+                "unexpected reply to catalog checksum request: {}",
+                other.kind()
+            ))),
+        }
+    }
+
+    /// Ask every online peer on protocol v2 whether it already has a track
+    /// with this acoustic fingerprint, concurrently and for at most
+    /// [`DUPLICATE_CHECK_TIMEOUT`]. Peers that fail or do not answer in
+    /// time are left out.
+    pub async fn check_duplicate(
+        self: &Arc<Self>,
+        fingerprint: &str,
+        duration_secs: f32,
+    ) -> Vec<NetworkDuplicate> {
+        let mut tasks = tokio::task::JoinSet::new();
+        for peer in self.registry.online_peers().await {
+            if peer
+                .protocol_version
+                .is_none_or(|v| v < ProtocolVersion::V2.as_u8())
             {
-                Ok(t) => t,
-                Err(e) => {
-                    warn!(
-                        page = page_num,
-                        "failed to fetch tracks page for backfill: {e}"
-                    );
-                    continue;
-                }
+                continue;
+            }
+            let Ok(nid) = peer.node_id.parse::<EndpointId>() else {
+                continue;
             };
-
-            for t in &tracks {
-                let file_path = self.audio_storage_path.join(&t.file_path);
-                let data = match tokio::fs::read(&file_path).await {
-                    Ok(d) => d,
+            let node = Arc::clone(self);
+            let fingerprint = fingerprint.to_string();
+            tasks.spawn(async move {
+                match node
+                    .request_duplicate_check(nid, &fingerprint, duration_secs)
+                    .await
+                {
+                    Ok(found) => found,
                     Err(e) => {
-                        warn!(
-                            track_id = %t.id,
-                            path = %file_path.display(),
-                            "skipping backfill — file not readable: {e}"
-                        );
-                        skipped += 1;
-                        continue;
+                        debug!(peer = %nid, "duplicate check failed: {e}");
+                        None
                     }
-                };
+                }
+            });
+        }
+        if tasks.is_empty() {
+            return vec![];
+        }
 
-                match self.publish_track(Bytes::from(data)).await {
-                    Ok(hash) => {
-                        let update = track::ActiveModel {
-                            id: Set(t.id),
-                            content_hash: Set(Some(hash.to_string())),
-                            ..Default::default()
-                        };
-                        if let Err(e) = update.update(&self.db).await {
-                            warn!(track_id = %t.id, "failed to save content_hash during backfill: {e}");
-                            skipped += 1;
-                            continue;
-                        }
-                        // Register hash so it can be served to peers
-                        self.published_hashes.write().await.insert(hash.to_string());
-                        backfilled += 1;
-                    }
-                    Err(e) => {
-                        warn!(track_id = %t.id, "failed to publish track during backfill: {e}");
-                        skipped += 1;
-                    }
+        let peers = tasks.len();
+        let mut matches = Vec::new();
+        let collected = tokio::time::timeout(DUPLICATE_CHECK_TIMEOUT, async {
+            while let Some(result) = tasks.join_next().await {
+                if let Ok(Some(found)) = result {
+                    matches.push(found);
                 }
             }
-
-            info!(
-                processed = backfilled + skipped,
-                total, backfilled, skipped, "content hash backfill progress"
+        })
+        .await;
+        if collected.is_err() {
+            debug!(
+                peers,
+                answered = matches.len(),
+                "duplicate check timed out after {DUPLICATE_CHECK_TIMEOUT:?}"
             );
         }
+        matches
+    }
 
-        info!(backfilled, skipped, total, "content hash backfill complete");
+    /// Send a `CheckDuplicate` to one peer. `None` if it finished the
+    /// stream without a `DuplicateFound`.
+    async fn request_duplicate_check(
+        &self,
+        peer_id: EndpointId,
+        fingerprint: &str,
+        duration_secs: f32,
+    ) -> Result<Option<NetworkDuplicate>, P2pError> {
+        let conn = self.conn_pool.get_connection(peer_id).await?;
+
+        let (mut send, mut recv) = match conn.open_bi().await {
+            Ok(streams) => streams,
+            Err(e) => {
+                self.conn_pool.invalidate(&peer_id).await;
+                return Err(P2pError::Connection(e.to_string()));
+            }
+        };
+
+        let msg = P2pMessage::CheckDuplicate {
+            fingerprint: fingerprint.to_string(),
+            duration_secs: Some(duration_secs),
+        };
+        let msg_bytes = serde_json::to_vec(&msg)?;
+        send.write_all(&(msg_bytes.len() as u32).to_be_bytes())
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.write_all(&msg_bytes)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.finish()
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        self.stats.record_sent(msg.kind());
+
+        let response = recv
+            .read_to_end(MAX_DUPLICATE_REPLY_BYTES)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        parse_duplicate_reply(&response)
     }
 
-    /// Announce all locally-uploaded tracks to a specific peer using paginated queries.
-    /// Called when a new peer connects to sync existing catalogs.
-    /// Sends one CatalogSync message per page (500 tracks) to avoid loading all
-    /// tracks into memory at once.
-    pub async fn announce_all_tracks_to_peer(&self, peer_id: EndpointId) {
-        let page_size = 500u64;
-        let total = match track::Entity::find()
+    /// Content hash of a public track that is the same recording as
+    /// `fingerprint`, if we have one. With `duration_secs`, fingerprints are
+    /// compared by similarity among the tracks of about that duration, as in
+    /// [`Self::find_track_by_fingerprint`]; peers that do not send it only
+    /// match an identical fingerprint.
+    async fn find_public_track_by_fingerprint(
+        &self,
+        fingerprint: &str,
+        duration_secs: Option<f32>,
+    ) -> Result<Option<String>, P2pError> {
+        let query = track::Entity::find()
             .filter(track::Column::ContentHash.is_not_null())
-            .filter(track::Column::FilePath.not_like("p2p://%"))
-            .count(&self.db)
+            .filter(track::Column::IsPrivate.eq(false));
+        let Some(duration_secs) = duration_secs else {
+            let found = query
+                .filter(track::Column::Fingerprint.eq(fingerprint))
+                .one(&self.db)
+                .await?;
+            return Ok(found.and_then(|t| t.content_hash));
+        };
+
+        let tolerance = soundtime_audio::fingerprint::DURATION_TOLERANCE_SECS;
+        let candidates = query
+            .filter(track::Column::Fingerprint.is_not_null())
+            .filter(
+                track::Column::DurationSecs
+                    .between(duration_secs - tolerance, duration_secs + tolerance),
+            )
+            .all(&self.db)
+            .await?;
+        Ok(candidates
+            .into_iter()
+            .find(|t| {
+                t.fingerprint
+                    .as_deref()
+                    .is_some_and(|fp| soundtime_audio::fingerprints_match(fp, fingerprint))
+            })
+            .and_then(|t| t.content_hash))
+    }
+
+    /// A local track that is the same recording as `fingerprint`, compared
+    /// by similarity with the tracks of about the same duration, if any.
+    async fn find_track_by_fingerprint(
+        &self,
+        fingerprint: &str,
+        duration_secs: f32,
+    ) -> Result<Option<track::Model>, sea_orm::DbErr> {
+        let tolerance = soundtime_audio::fingerprint::DURATION_TOLERANCE_SECS;
+        let candidates = track::Entity::find()
+            .filter(track::Column::Fingerprint.is_not_null())
+            .filter(
+                track::Column::DurationSecs
+                    .between(duration_secs - tolerance, duration_secs + tolerance),
+            )
+            .all(&self.db)
+            .await?;
+        Ok(candidates.into_iter().find(|t| {
+            t.fingerprint
+                .as_deref()
+                .is_some_and(|fp| soundtime_audio::fingerprints_match(fp, fingerprint))
+        }))
+    }
+
+    /// Log and count an announcement refused by the replication policy.
+    fn reject_announcement(&self, ann: &TrackAnnouncement, peer_id: &str, reason: RejectReason) {
+        info!(
+            hash = %ann.hash,
+            title = %ann.title,
+            %peer_id,
+            %reason,
+            "rejected track announcement by replication policy or peer filter"
+        );
+        self.rejections.record(peer_id, ann, &reason);
+    }
+
+    /// Internal: look up the replicated tracks waiting for MusicBrainz
+    /// verification, oldest first. A match shows the track, a definite "no
+    /// match" rejects it, and a failed lookup leaves it hidden to be tried
+    /// again after [`MB_VERIFICATION_RETRY_DELAY`].
+    async fn verify_pending_tracks(&self) {
+        use sea_orm::QuerySelect;
+
+        let retry_before = chrono::Utc::now() - MB_VERIFICATION_RETRY_DELAY;
+        let pending = match mb_lookup_queue::Entity::find()
+            .filter(mb_lookup_queue::Column::PendingVerification.eq(true))
+            .filter(
+                Condition::any()
+                    .add(mb_lookup_queue::Column::Attempts.eq(0))
+                    .add(mb_lookup_queue::Column::UpdatedAt.lte(retry_before)),
+            )
+            .order_by_asc(mb_lookup_queue::Column::CreatedAt)
+            .limit(MB_VERIFICATION_BATCH)
+            .all(&self.db)
             .await
         {
-            Ok(c) => c,
+            Ok(pending) => pending,
             Err(e) => {
-                warn!("failed to count tracks for catalog sync: {e}");
+                warn!("failed to load tracks pending MusicBrainz verification: {e}");
                 return;
             }
         };
 
-        if total == 0 {
-            debug!(peer = %peer_id, "no tracks to sync");
-            return;
-        }
-
-        let num_pages = total.div_ceil(page_size);
-        info!(peer = %peer_id, total, pages = num_pages, "starting paginated catalog sync");
-
-        let our_node = self.node_id().to_string();
-
-        // Cache cover hashes by album_id to avoid re-reading + re-publishing the same cover
-        let mut cover_cache: HashMap<Uuid, Option<String>> = HashMap::new();
-
-        for page_num in 0..num_pages {
-            let tracks = match track::Entity::find()
-                .filter(track::Column::ContentHash.is_not_null())
-                .filter(track::Column::FilePath.not_like("p2p://%"))
-                .paginate(&self.db, page_size)
-                .fetch_page(page_num)
-                .await
+        for entry in pending {
+            let track_id = entry.track_id;
+            let result = match MusicBrainzClient::queue_lookup(
+                &self.mb_queue,
+                None,
+                &entry.title,
+                &entry.artist_name,
+            )
+            .await
             {
-                Ok(t) => t,
+                Ok(Some(recording)) => self.accept_verified_track(track_id, recording.id).await,
+                Ok(None) => self.reject_unverified_track(&entry).await,
                 Err(e) => {
-                    warn!(
-                        page = page_num,
-                        "failed to read tracks page for catalog sync: {e}"
-                    );
-                    continue;
+                    // MusicBrainz is unreachable: the rest would fail too
+                    warn!(%track_id, "MusicBrainz verification failed, retrying later: {e}");
+                    let update = mb_lookup_queue::ActiveModel {
+                        track_id: Set(track_id),
+                        attempts: Set(entry.attempts + 1),
+                        last_error: Set(Some(e.to_string())),
+                        updated_at: Set(chrono::Utc::now().into()),
+                        ..Default::default()
+                    };
+                    if let Err(e) = update.update(&self.db).await {
+                        warn!(%track_id, "failed to record MusicBrainz verification attempt: {e}");
+                    }
+                    return;
                 }
             };
+            if let Err(e) = result {
+                warn!(%track_id, "failed to apply MusicBrainz verification: {e}");
+            }
+        }
+    }
 
-            // ── Batch-fetch artists and albums for this page ──
-            let artist_ids: Vec<Uuid> = tracks
-                .iter()
-                .map(|t| t.artist_id)
-                .collect::<std::collections::HashSet<_>>()
-                .into_iter()
-                .collect();
-            let album_ids: Vec<Uuid> = tracks
-                .iter()
-                .filter_map(|t| t.album_id)
-                .collect::<std::collections::HashSet<_>>()
-                .into_iter()
-                .collect();
-
-            let artist_map: HashMap<Uuid, String> = if !artist_ids.is_empty() {
-                artist::Entity::find()
-                    .filter(artist::Column::Id.is_in(artist_ids))
-                    .all(&self.db)
-                    .await
-                    .unwrap_or_default()
-                    .into_iter()
-                    .map(|a| (a.id, a.name))
-                    .collect()
-            } else {
-                HashMap::new()
-            };
+    /// Internal: show a pending track MusicBrainz matched.
+    async fn accept_verified_track(&self, track_id: Uuid, mbid: String) -> Result<(), P2pError> {
+        // Still hidden if its hash was blocked meanwhile
+        track::Entity::update_many()
+            .col_expr(
+                track::Column::MusicbrainzId,
+                sea_orm::sea_query::Expr::value(Some(mbid)),
+            )
+            .col_expr(
+                track::Column::IsHidden,
+                sea_orm::sea_query::Expr::col(track::Column::HiddenByBlock).into(),
+            )
+            .filter(track::Column::Id.eq(track_id))
+            .exec(&self.db)
+            .await?;
+        mb_lookup_queue::Entity::delete_by_id(track_id)
+            .exec(&self.db)
+            .await?;
+        debug!(%track_id, "replicated track verified on MusicBrainz");
+        self.search_index.mark_dirty().await;
+        Ok(())
+    }
 
-            let album_map: HashMap<Uuid, (String, Option<String>)> = if !album_ids.is_empty() {
-                album::Entity::find()
-                    .filter(album::Column::Id.is_in(album_ids))
-                    .all(&self.db)
-                    .await
-                    .unwrap_or_default()
-                    .into_iter()
-                    .map(|a| (a.id, (a.title, a.cover_url)))
-                    .collect()
-            } else {
-                HashMap::new()
-            };
+    /// Internal: drop a pending track MusicBrainz has no match for, as if
+    /// its announcement had been rejected.
+    async fn reject_unverified_track(
+        &self,
+        entry: &mb_lookup_queue::Model,
+    ) -> Result<(), P2pError> {
+        let Some(t) = track::Entity::find_by_id(entry.track_id)
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(());
+        };
+        let peer_id = entry.announced_by.as_deref().unwrap_or_default();
+        let reason = RejectReason::NoMusicBrainzMatch;
+        info!(
+            hash = ?t.content_hash,
+            title = %t.title,
+            %peer_id,
+            %reason,
+            "rejected replicated track without a MusicBrainz match"
+        );
+        self.rejections.record_track(
+            peer_id,
+            t.content_hash.as_deref().unwrap_or_default(),
+            &t.title,
+            &entry.artist_name,
+            &reason,
+        );
+        remote_track::Entity::delete_many()
+            .filter(remote_track::Column::LocalTrackId.eq(t.id))
+            .exec(&self.db)
+            .await?;
+        mb_lookup_queue::Entity::delete_by_id(t.id)
+            .exec(&self.db)
+            .await?;
+        track::Entity::delete_by_id(t.id).exec(&self.db).await?;
+        Ok(())
+    }
 
-            let mut announcements = Vec::with_capacity(tracks.len());
+    /// Change the global upload limit (bytes/sec, 0 = unlimited) at runtime.
+    pub async fn set_max_upload_bps(&self, bps: u64) {
+        self.upload_limiter.set_global_limit(bps).await;
+        info!(bps, "P2P global upload limit changed");
+    }
 
-            for t in &tracks {
-                // Skip P2P-replicated tracks (they came from another peer, don't re-announce)
-                if t.file_path.starts_with("p2p://") {
-                    continue;
-                }
+    /// Change the per-peer upload limit (bytes/sec, 0 = unlimited) at runtime.
+    pub async fn set_max_upload_bps_per_peer(&self, bps: u64) {
+        self.upload_limiter.set_per_peer_limit(bps).await;
+        info!(bps, "P2P per-peer upload limit changed");
+    }
 
-                let hash = match &t.content_hash {
-                    Some(h) => h.clone(),
-                    None => continue,
-                };
+    /// Get the track health manager for failure tracking and auto-repair.
+    pub fn health_manager(&self) -> &Arc<TrackHealthManager> {
+        &self.health_manager
+    }
 
-                let artist_name = artist_map
-                    .get(&t.artist_id)
-                    .cloned()
-                    .unwrap_or_else(|| "Unknown".to_string());
-
-                let (album_title, cover_hash) = match t.album_id {
-                    Some(aid) => {
-                        match album_map.get(&aid) {
-                            Some((title, cover_url)) => {
-                                let title = Some(title.clone());
-                                // Use cover cache to avoid re-reading/re-publishing same cover
-                                let ch = if let Some(cached) = cover_cache.get(&aid) {
-                                    cached.clone()
-                                } else {
-                                    let result = if let Some(ref url) = cover_url {
-                                        let rel = url.strip_prefix("/api/media/").unwrap_or(url);
-                                        let full = self.audio_storage_path.join(rel);
-                                        let cover_data = match tokio::fs::read(&full).await {
-                                            Ok(data) => Some(data),
-                                            Err(_) => {
-                                                if let Some(ref meta) = self.metadata_storage_path {
-                                                    tokio::fs::read(meta.join(rel)).await.ok()
-                                                } else {
-                                                    None
-                                                }
-                                            }
-                                        };
-                                        match cover_data {
-                                            Some(data) => {
-                                                match self.publish_cover(Bytes::from(data)).await {
-                                                    Ok(h) => Some(h.to_string()),
-                                                    Err(e) => {
-                                                        warn!("failed to publish cover blob: {e}");
-                                                        None
-                                                    }
-                                                }
-                                            }
-                                            None => None,
-                                        }
-                                    } else {
-                                        None
-                                    };
-                                    cover_cache.insert(aid, result.clone());
-                                    result
-                                };
-                                (title, ch)
-                            }
-                            None => (None, None),
-                        }
-                    }
-                    None => (None, None),
-                };
+    /// Publish a track's audio data to the local blob store.
+    /// Returns the content hash (BLAKE3) that identifies the blob.
+    pub async fn publish_track(&self, data: Bytes) -> Result<Hash, P2pError> {
+        let outcome = self
+            .blob_store
+            .blobs()
+            .add_bytes(data)
+            .temp_tag()
+            .await
+            .map_err(|e| P2pError::BlobStore(e.to_string()))?;
 
-                announcements.push(TrackAnnouncement {
-                    hash,
-                    title: t.title.clone(),
-                    artist_name,
-                    album_artist_name: None,
-                    album_title,
-                    duration_secs: t.duration_secs,
-                    format: t.format.clone(),
-                    file_size: t.file_size,
-                    genre: t.genre.clone(),
-                    year: t.year,
-                    track_number: t.track_number,
-                    disc_number: t.disc_number,
-                    bitrate: t.bitrate,
-                    sample_rate: t.sample_rate,
-                    origin_node: our_node.clone(),
-                    cover_hash,
-                });
-            }
+        let hash = outcome.hash();
 
-            if !announcements.is_empty() {
-                info!(
-                    peer = %peer_id,
-                    count = announcements.len(),
-                    page = page_num,
-                    "syncing catalog page to peer"
-                );
+        // SECURITY: Register hash so it can be served to peers (FIX-19).
+        // Recorded before tagging so blob GC never sees an unrecorded tag.
+        self.published_hashes
+            .publish(&hash.to_string(), PublishedKind::Track)
+            .await?;
 
-                let msg = P2pMessage::CatalogSync(announcements);
-                if let Err(e) = self.send_message_to_peer(peer_id, &msg).await {
-                    warn!(peer = %peer_id, page = page_num, "failed to sync catalog page: {e}");
-                }
-            }
-        }
+        // Create a persistent tag so the blob is not garbage collected
+        let tag_name = format!("published-{}", hash);
+        self.blob_store
+            .tags()
+            .set(tag_name, HashAndFormat::raw(hash))
+            .await
+            .map_err(|e| P2pError::BlobStore(format!("failed to set persistent tag: {e}")))?;
+        info!(%hash, "track published to blob store");
+        Ok(hash)
     }
 
-    /// Send a `RequestCatalog` message to a peer, asking them to send us their
-    /// full catalog via paginated `CatalogSync` messages.
-    pub async fn request_catalog_from_peer(&self, peer_id: EndpointId) -> Result<(), P2pError> {
-        info!(peer = %peer_id, "requesting full catalog from peer");
-        self.send_message_to_peer(peer_id, &P2pMessage::RequestCatalog)
+    /// Publish cover art data to the local blob store.
+    /// Returns the content hash (BLAKE3) that identifies the blob.
+    pub async fn publish_cover(&self, data: Bytes) -> Result<Hash, P2pError> {
+        let outcome = self
+            .blob_store
+            .blobs()
+            .add_bytes(data)
+            .temp_tag()
             .await
-    }
+            .map_err(|e| P2pError::BlobStore(e.to_string()))?;
 
-    /// Discover peers by exchanging peer lists with a known peer (Peer Exchange / PEX).
-    /// Sends our known peers, receives theirs, and connects to any new ones.
-    pub async fn discover_via_peer(&self, peer_node_id: EndpointId) {
-        info!(peer = %peer_node_id, "initiating peer exchange");
+        let hash = outcome.hash();
 
-        let peer_addr = EndpointAddr::new(peer_node_id);
-        match self.exchange_peers(peer_addr).await {
-            Ok(remote_peers) => {
-                let our_id = self.node_id().to_string();
-                let mut new_count = 0u32;
+        // SECURITY: Register hash so it can be served to peers (FIX-19).
+        // Recorded before tagging so blob GC never sees an unrecorded tag.
+        self.published_hashes
+            .publish(&hash.to_string(), PublishedKind::Image)
+            .await?;
 
-                for peer_id_str in remote_peers {
-                    if peer_id_str == our_id {
-                        continue;
-                    }
-                    if self.registry.get_peer(&peer_id_str).await.is_some() {
-                        continue;
-                    }
+        // Create a persistent tag so the blob is not garbage collected
+        let tag_name = format!("published-{}", hash);
+        self.blob_store
+            .tags()
+            .set(tag_name, HashAndFormat::raw(hash))
+            .await
+            .map_err(|e| P2pError::BlobStore(format!("failed to set persistent tag: {e}")))?;
+        debug!(%hash, "cover art published to blob store");
+        Ok(hash)
+    }
 
-                    let nid: EndpointId = match peer_id_str.parse() {
-                        Ok(id) => id,
-                        Err(_) => continue,
-                    };
+    /// Stop serving a published blob: drop its persistent tag so it can be
+    /// garbage collected and forget the hash, in memory and in the database.
+    pub async fn unpublish(&self, hash: Hash) -> Result<(), P2pError> {
+        self.blob_store
+            .tags()
+            .delete(format!("published-{}", hash))
+            .await
+            .map_err(|e| P2pError::BlobStore(format!("failed to delete persistent tag: {e}")))?;
+        self.published_hashes.unpublish(&hash.to_string()).await?;
+        info!(%hash, "blob unpublished");
+        Ok(())
+    }
 
-                    let addr = EndpointAddr::new(nid);
-                    match self.ping_peer(addr).await {
-                        Ok(P2pMessage::Pong {
-                            node_id,
-                            track_count,
-                            version,
-                        }) => {
-                            self.registry
-                                .upsert_peer_versioned(&node_id, None, track_count, version)
-                                .await;
-                            info!(peer = %node_id, %track_count, "discovered new peer via PEX");
-                            new_count += 1;
-                        }
-                        Ok(_) => {
-                            self.registry.upsert_peer(&peer_id_str, None, 0).await;
-                            new_count += 1;
-                        }
-                        Err(e) => {
-                            debug!(peer = %peer_id_str, "PEX peer unreachable: {e}");
-                        }
-                    }
+    /// Delete blobs that no track, replicated track or published image
+    /// refers to and the blob cache does not hold. With `dry_run` nothing
+    /// is deleted; the report says what would be.
+    pub async fn collect_garbage(&self, dry_run: bool) -> Result<GcReport, P2pError> {
+        self.backfill_published_hashes().await?;
+        let tags = blob_gc::list_tags(&self.blob_store).await?;
+        let referenced = blob_gc::referenced_hashes(&self.db).await?;
+        let pinned = self.blob_cache.pinned().await;
+        blob_gc::collect_garbage(&self.blob_store, &tags, &referenced, &pinned, dry_run).await
+    }
+
+    /// Record the blobs behind `published-*` tags set before
+    /// `published_hashes` existed, once per run; until then blob GC would
+    /// take them for unreferenced.
+    async fn backfill_published_hashes(&self) -> Result<(), P2pError> {
+        self.published_backfill
+            .get_or_try_init(|| async {
+                let tags = blob_gc::list_tags(&self.blob_store).await?;
+                let blocked = self.blocked_hashes.snapshot().await;
+                blob_gc::backfill_published(&tags, &self.published_hashes, &blocked).await?;
+                Ok::<_, P2pError>(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Internal: publish the image behind a cover or artist image URL and
+    /// return its blob hash. `/api/media/...` URLs are read from local
+    /// storage; external http(s) URLs are downloaded once and remembered.
+    async fn publish_media_url(&self, url: &str) -> Option<String> {
+        let remote = url.starts_with("http://") || url.starts_with("https://");
+        let data = if remote {
+            if let Some(hash) = self.remote_image_hashes.get(url) {
+                return Some(hash.clone());
+            }
+            self.mb_client.fetch_image(url).await?
+        } else {
+            let rel = url.strip_prefix("/api/media/").unwrap_or(url);
+            match tokio::fs::read(self.audio_storage_path.join(rel)).await {
+                Ok(data) => Bytes::from(data),
+                Err(_) => {
+                    let meta = self.metadata_storage_path.as_ref()?;
+                    Bytes::from(tokio::fs::read(meta.join(rel)).await.ok()?)
                 }
+            }
+        };
 
-                info!(peer = %peer_node_id, %new_count, "peer exchange complete");
+        match self.publish_cover(data).await {
+            Ok(h) => {
+                if remote {
+                    self.remote_image_hashes
+                        .insert(url.to_string(), h.to_string());
+                }
+                Some(h.to_string())
             }
             Err(e) => {
-                warn!(peer = %peer_node_id, "peer exchange failed: {e}");
+                warn!(%url, "failed to publish image blob: {e}");
+                None
             }
         }
     }
 
-    /// Send our peer list to a remote peer and receive theirs.
-    async fn exchange_peers(&self, peer_addr: EndpointAddr) -> Result<Vec<String>, P2pError> {
-        let conn = self.conn_pool.get_connection(peer_addr.id).await?;
+    /// Retrieve a track's data from the local blob store by hash.
+    pub async fn get_local_track(&self, hash: Hash) -> Result<Bytes, P2pError> {
+        if self.is_marked_corrupted(hash).await {
+            return Err(P2pError::BlobStore(format!("blob {hash} is corrupted")));
+        }
+        let data = self
+            .blob_store
+            .blobs()
+            .get_bytes(hash)
+            .await
+            .map_err(|e| P2pError::BlobStore(e.to_string()))?;
 
-        let (mut send, mut recv) = match conn.open_bi().await {
-            Ok(streams) => streams,
-            Err(e) => {
-                self.conn_pool.invalidate(&peer_addr.id).await;
-                return Err(P2pError::Connection(e.to_string()));
-            }
-        };
+        Ok(data)
+    }
 
-        // Build our peer list (include ourselves so remote knows us)
-        let mut our_peers: Vec<String> = self
-            .registry
-            .list_peers()
-            .await
-            .into_iter()
-            .map(|p| p.node_id)
-            .collect();
-        our_peers.push(self.node_id().to_string());
+    /// Read up to `length` bytes of a local blob from `offset` without
+    /// loading the rest of it. Returns the bytes and the blob's total size,
+    /// or `None` if the blob is not stored locally.
+    pub async fn get_local_track_range(
+        &self,
+        hash: Hash,
+        offset: u64,
+        length: Option<u64>,
+    ) -> Result<Option<(Bytes, u64)>, P2pError> {
+        if self.is_marked_corrupted(hash).await {
+            return Ok(None);
+        }
+        read_blob_range(&self.blob_store, hash, offset, length).await
+    }
 
-        let msg = P2pMessage::PeerExchange { peers: our_peers };
-        let msg_bytes = serde_json::to_vec(&msg)?;
-        send.write_all(&(msg_bytes.len() as u32).to_be_bytes())
-            .await
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
-        send.write_all(&msg_bytes)
-            .await
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
-        send.finish()
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
+    /// Retrieve bytes `start..=end` of a P2P track for an HTTP range request.
+    ///
+    /// A local blob is read directly. Otherwise only the range is requested
+    /// with a verified `FetchTrackRange` from an online peer holding the
+    /// blob that supports [`CAP_VERIFIED_RANGES`], best copy first, and the
+    /// whole blob is fetched in the background so later ranges are served
+    /// locally. The range and the blob size come from the BAO proof, never
+    /// from what the peer announced. Ranges are capped at
+    /// [`MAX_STREAM_RANGE_BYTES`]; an unsatisfiable range comes back empty.
+    /// If no peer can serve the range, falls back to
+    /// [`get_or_fetch_track`](Self::get_or_fetch_track). Blocked hashes are
+    /// refused like there.
+    pub async fn get_or_fetch_track_range(
+        self: &Arc<Self>,
+        hash: Hash,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<TrackRange, P2pError> {
+        self.blocked_hashes
+            .check(&hash.to_string(), BlockedPath::Fetch)
+            .await?;
+        let wanted = end
+            .map_or(MAX_STREAM_RANGE_BYTES, |e| e.saturating_sub(start) + 1)
+            .min(MAX_STREAM_RANGE_BYTES);
+        if let Some((data, total_size)) = self
+            .get_local_track_range(hash, start, Some(wanted))
+            .await?
+        {
+            P2P_METRICS.blob_cache_hits_total.inc();
+            self.blob_cache
+                .record_access_with_tag(hash, total_size, &self.blob_store)
+                .await;
+            let data = if end.is_some_and(|e| e < start) {
+                Bytes::new()
+            } else {
+                data
+            };
+            return Ok(TrackRange {
+                data,
+                start,
+                total_size,
+            });
+        }
+        P2P_METRICS.blob_cache_misses_total.inc();
 
-        // Read response
-        let mut len_buf = [0u8; 4];
-        recv.read_exact(&mut len_buf)
+        let mut candidates: Vec<PeerTrackInfo> = self
+            .alternative_sources(&hash.to_string())
             .await
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
-        let msg_len = u32::from_be_bytes(len_buf) as usize;
+            .into_iter()
+            .filter(|c| c.is_online && !self.is_metadata_only(&c.peer_id))
+            .collect();
+        candidates.sort_by_key(|c| std::cmp::Reverse(quality_score(c)));
 
-        let response = recv
-            .read_to_end(msg_len)
-            .await
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        for candidate in candidates {
+            if !self.peer_supports_verified_ranges(&candidate.peer_id).await {
+                continue;
+            }
+            match self
+                .fetch_verified_range(&candidate.peer_id, hash, start, wanted)
+                .await
+            {
+                Ok((data, total_size)) => {
+                    debug!(%hash, peer = %candidate.peer_id, start, len = data.len(), "fetched verified stream range from peer");
+                    self.prefetch_track(hash).await;
+                    let data = if end.is_some_and(|e| e < start) {
+                        Bytes::new()
+                    } else {
+                        data
+                    };
+                    return Ok(TrackRange {
+                        data,
+                        start,
+                        total_size,
+                    });
+                }
+                Err(e) => {
+                    warn!(%hash, peer = %candidate.peer_id, "stream range fetch failed: {e}");
+                }
+            }
+        }
 
-        let response_msg: P2pMessage = serde_json::from_slice(&response)?;
+        // No peer could serve the range — fetch the whole blob instead
+        let data = self.get_or_fetch_track(hash).await?;
+        let total_size = data.len() as u64;
+        Ok(
+            match clamp_range(start, end, total_size, MAX_STREAM_RANGE_BYTES) {
+                Some((first, last)) => TrackRange {
+                    data: data.slice(first as usize..=last as usize),
+                    start: first,
+                    total_size,
+                },
+                None => TrackRange {
+                    data: Bytes::new(),
+                    start,
+                    total_size,
+                },
+            },
+        )
+    }
 
-        match response_msg {
-            P2pMessage::PeerExchange { peers } => Ok(peers),
-            _ => Ok(vec![]),
+    /// Fetch the whole blob in the background unless it is already stored
+    /// or being fetched.
+    async fn prefetch_track(self: &Arc<Self>, hash: Hash) {
+        if self.has_blob(hash).await || self.blob_cache.is_fetching(hash).await {
+            return;
         }
+        let node = Arc::clone(self);
+        tokio::spawn(async move {
+            if let Err(e) = node.get_or_fetch_track(hash).await {
+                debug!(%hash, "background blob fetch failed: {e}");
+            }
+        });
     }
 
-    /// Gracefully shutdown the P2P node.
-    pub async fn shutdown(&self) {
-        info!("shutting down P2P node");
-        let _ = self.shutdown_tx.send(true);
-        self.endpoint.close().await;
-        let _ = self.blob_store.shutdown().await;
-        info!("P2P node shutdown complete");
+    /// Whether a peer negotiated protocol v2, which range requests need.
+    async fn peer_supports_ranges(&self, peer_id: &str) -> bool {
+        self.registry
+            .get_peer(peer_id)
+            .await
+            .and_then(|p| p.protocol_version)
+            .is_some_and(|v| v >= ProtocolVersion::V2.as_u8())
     }
 
-    /// Rebuild the local Bloom filter search index from all tracks in the database.
-    async fn rebuild_search_index(&self) {
-        match self.search_index.rebuild_from_db(&self.db).await {
-            Ok(()) => info!("search index rebuilt at startup"),
-            Err(e) => warn!("failed to rebuild search index from database: {e}"),
-        }
+    /// Whether a peer can answer range requests with a BAO proof.
+    async fn peer_supports_verified_ranges(&self, peer_id: &str) -> bool {
+        self.registry.get_peer(peer_id).await.is_some_and(|p| {
+            p.protocol_version
+                .is_some_and(|v| v >= ProtocolVersion::V2.as_u8())
+                && p.has_capability(CAP_VERIFIED_RANGES)
+        })
     }
 
-    /// Broadcast our Bloom filter to all online peers for search routing.
-    pub async fn broadcast_bloom_filter(&self) {
-        let bloom_data = self.search_index.export_local_bloom().await;
-        let msg = P2pMessage::BloomExchange { bloom: bloom_data };
-        let peers = self.registry.online_peers().await;
-
-        for peer in &peers {
-            let node_id: EndpointId = match peer.node_id.parse() {
-                Ok(id) => id,
-                Err(_) => continue,
-            };
-            if let Err(e) = self.send_message_to_peer(node_id, &msg).await {
-                debug!(peer = %peer.node_id, "failed to send bloom filter: {e}");
+    /// Pick the best copy of a replicated track to stream.
+    ///
+    /// Several peers may have announced the same track (same
+    /// `local_track_id`) in different formats. Each available copy is ranked
+    /// with [`quality_score`], so an online lossless copy beats an online
+    /// lossy one, which beats any offline copy. Copies from metadata-only
+    /// peers are skipped since their blobs are never fetched. Returns the
+    /// peer and blob hash of the winner, or `None` if no copy qualifies.
+    pub async fn negotiate_best_fetch(&self, track_id: Uuid) -> Option<(String, Hash)> {
+        let remotes = match remote_track::Entity::find()
+            .filter(remote_track::Column::LocalTrackId.eq(track_id))
+            .filter(remote_track::Column::IsAvailable.eq(true))
+            .all(&self.db)
+            .await
+        {
+            Ok(remotes) => remotes,
+            Err(e) => {
+                warn!(%track_id, "failed to load remote copies: {e}");
+                return None;
             }
-        }
+        };
 
-        if !peers.is_empty() {
-            info!(peers = peers.len(), "broadcast bloom filter to peers");
+        let mut best: Option<(u64, String, Hash)> = None;
+        for rt in remotes {
+            let Some(hash) = rt
+                .remote_uri
+                .rsplit('/')
+                .next()
+                .and_then(|h| h.parse::<Hash>().ok())
+            else {
+                continue;
+            };
+            let info = self.peer_track_info(&rt, 0).await;
+            if self.is_metadata_only(&info.peer_id) {
+                continue;
+            }
+            let score = quality_score(&info);
+            if best.as_ref().is_none_or(|(top, _, _)| score > *top) {
+                best = Some((score, info.peer_id, hash));
+            }
         }
+        best.map(|(_, peer_id, hash)| (peer_id, hash))
     }
 
-    /// Perform a distributed search across the P2P network.
-    /// Uses Bloom filters to route the query only to peers likely to have results.
-    /// Queries up to 10 matching peers concurrently with a 10-second timeout per peer.
-    /// Returns search results from all matching peers, merged and sorted by relevance.
-    pub async fn distributed_search(
-        self: &Arc<Self>,
-        query: &str,
-        limit: u32,
-    ) -> Vec<SearchResultItem> {
-        let matching_peers = self.search_index.peers_matching_query(query).await;
-
-        if matching_peers.is_empty() {
-            debug!(query = query, "no peers match bloom filter for query");
-            return vec![];
+    /// Blob to stream for a replicated track whose file path names `own`.
+    ///
+    /// A cached copy of `own` is served as is. Otherwise the best copy is
+    /// picked by [`Self::negotiate_best_fetch`] on the first request and
+    /// pinned, so the range requests of a seek never mix encodings from
+    /// different peers. [`Self::unpin_stream_source`] drops the pin when
+    /// the chosen copy fails.
+    pub async fn stream_source(&self, track_id: Uuid, own: Hash) -> Hash {
+        if self.has_blob(own).await {
+            return own;
         }
-
-        let request_id = uuid::Uuid::new_v4().to_string();
-        let msg = P2pMessage::SearchQuery {
-            request_id: request_id.clone(),
-            query: query.to_string(),
-            limit,
+        if let Some(pinned) = self.stream_sources.get(&track_id) {
+            return *pinned;
+        }
+        let chosen = match self.negotiate_best_fetch(track_id).await {
+            Some((peer_id, best)) => {
+                debug!(%track_id, %peer_id, hash = %best, "negotiated P2P source");
+                best
+            }
+            None => own,
         };
+        *self.stream_sources.entry(track_id).or_insert(chosen)
+    }
 
-        let mut all_results: Vec<SearchResultItem> = Vec::new();
+    /// Forget the blob pinned by [`Self::stream_source`] for a track, so the
+    /// next request negotiates again.
+    pub fn unpin_stream_source(&self, track_id: Uuid) {
+        self.stream_sources.remove(&track_id);
+    }
 
-        // Collect peers to query (up to 10)
-        let mut peers_to_query = Vec::new();
-        for peer_id_str in matching_peers.iter().take(10) {
-            let nid: EndpointId = match peer_id_str.parse() {
-                Ok(id) => id,
-                Err(_) => continue,
-            };
-            let peer_addr = EndpointAddr::new(nid);
-            peers_to_query.push((peer_id_str.clone(), peer_addr));
+    /// Retrieve a P2P track by hash, fetching from the origin peer on-demand if not cached.
+    /// Fails with [`P2pError::HashBlocked`] if the hash is blocked.
+    ///
+    /// 1. Try the local blob store (fast path).
+    /// 2. If missing and several online peers hold a large enough copy, fetch
+    ///    ranges from up to [`MAX_SWARM_SOURCES`] of them in parallel.
+    /// 3. Otherwise look up the origin peer from `remote_tracks` and fetch via P2P.
+    /// 4. Store the fetched blob locally and register it in the LRU cache.
+    /// 5. Trigger eviction if the cache exceeds the configured limit.
+    pub async fn get_or_fetch_track(self: &Arc<Self>, hash: Hash) -> Result<Bytes, P2pError> {
+        // Blocked blobs are refused even when a copy is still cached
+        self.blocked_hashes
+            .check(&hash.to_string(), BlockedPath::Fetch)
+            .await?;
+
+        // Fast path: blob exists locally
+        if let Ok(data) = self.get_local_track(hash).await {
+            P2P_METRICS.blob_cache_hits_total.inc();
+            self.blob_cache
+                .record_access_with_tag(hash, data.len() as u64, &self.blob_store)
+                .await;
+            return Ok(data);
         }
+        P2P_METRICS.blob_cache_misses_total.inc();
 
-        // Query all matched peers concurrently using JoinSet
-        let timeout_dur = std::time::Duration::from_secs(10);
-        let mut join_set = tokio::task::JoinSet::new();
+        // Look up origin peer from remote_track table
+        let hash_str = hash.to_string();
+        let remote = remote_track::Entity::find()
+            .filter(remote_track::Column::RemoteUri.ends_with(format!("/{}", hash_str)))
+            .one(&self.db)
+            .await
+            .map_err(P2pError::Database)?
+            .ok_or_else(|| P2pError::TrackNotFound(hash_str.clone()))?;
 
-        for (peer_id_str, peer_addr) in peers_to_query {
-            let node = Arc::clone(self);
-            let msg = msg.clone();
-            join_set.spawn(async move {
-                match tokio::time::timeout(timeout_dur, node.search_peer(peer_addr, &msg)).await {
-                    Ok(Ok(results)) => {
-                        info!(peer = %peer_id_str, results = results.len(), "received search results");
-                        results
-                    }
-                    Ok(Err(e)) => {
-                        debug!(peer = %peer_id_str, "search failed: {e}");
-                        vec![]
+        let origin = remote
+            .instance_domain
+            .strip_prefix("p2p://")
+            .unwrap_or(&remote.instance_domain);
+        if self.is_metadata_only(origin) {
+            debug!(%hash, peer = %origin, "not fetching blob from metadata-only peer");
+            return Err(P2pError::TrackNotFound(format!(
+                "{hash_str} (peer {origin} is metadata-only)"
+            )));
+        }
+
+        // In-flight dedup: if another task is already fetching this blob, wait and retry
+        if !self.blob_cache.try_start_fetch(hash).await {
+            debug!(%hash, "another fetch in progress, waiting");
+            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+            // Retry local — the other fetch may have completed
+            if let Ok(data) = self.get_local_track(hash).await {
+                self.blob_cache
+                    .record_access_with_tag(hash, data.len() as u64, &self.blob_store)
+                    .await;
+                return Ok(data);
+            }
+            return Err(P2pError::TrackNotFound(hash_str));
+        }
+
+        // Fetch from peer
+        let result = async {
+            // Both paths verify the BLAKE3 hash, so only intact data reaches
+            // the blob store
+            let data = match self.swarm_fetch_track(hash).await {
+                Some(data) => data,
+                None => {
+                    info!(%hash, peer = %origin, "on-demand fetch from peer");
+                    // Origin first, then other online peers holding the blob
+                    let mut alternatives: Vec<PeerTrackInfo> = self
+                        .alternative_sources(&hash_str)
+                        .await
+                        .into_iter()
+                        .filter(|c| {
+                            c.is_online && c.peer_id != origin && !self.is_metadata_only(&c.peer_id)
+                        })
+                        .collect();
+                    alternatives.sort_by_key(|c| std::cmp::Reverse(quality_score(c)));
+                    let mut sources = vec![origin.to_string()];
+                    for alt in alternatives {
+                        if !sources.contains(&alt.peer_id) {
+                            sources.push(alt.peer_id);
+                        }
                     }
-                    Err(_) => {
-                        debug!(peer = %peer_id_str, "search timed out");
-                        vec![]
+
+                    let (data, peer) =
+                        fetch_verified(&self.health_manager, self, hash, &sources).await?;
+                    if peer != origin {
+                        info!(%hash, %peer, "fetched blob from alternative source");
                     }
+                    data
                 }
-            });
+            };
+
+            // A corrupted copy still in the store would be kept and tagged
+            // again instead of this one; cache it after the sweep
+            if self.is_marked_corrupted(hash).await {
+                debug!(%hash, "corrupted copy not swept yet, not caching fetched blob");
+                return Ok(data);
+            }
+
+            // Store in local blob store
+            let _tag = self
+                .blob_store
+                .blobs()
+                .add_bytes(data.clone())
+                .temp_tag()
+                .await
+                .map_err(|e| P2pError::BlobStore(e.to_string()))?;
+
+            // Register in LRU cache and evict if over limit
+            self.blob_cache
+                .record_access_with_tag(hash, data.len() as u64, &self.blob_store)
+                .await;
+            self.reseed(&hash_str).await;
+            let evicted = self.blob_cache.evict_if_needed(&self.blob_store).await;
+            self.unpublish_reseeded(&evicted).await;
+
+            info!(%hash, bytes = data.len(), "on-demand fetch complete, blob cached");
+            Ok(data)
+        }
+        .await;
+
+        // Always clear in-flight flag
+        self.blob_cache.finish_fetch(hash).await;
+
+        result
+    }
+
+    /// Pin a track's blob so the cache never evicts it, fetching it in the
+    /// background if it is not stored yet. The caller records the pin in
+    /// `pinned_tracks`.
+    pub async fn pin_track(self: &Arc<Self>, hash: Hash) {
+        self.blob_cache.pin(hash).await;
+        self.prefetch_track(hash).await;
+    }
+
+    /// Release a track's pin on its blob. Once no other pinned track shares
+    /// the blob the cache may evict it again, right away if the cache is
+    /// over its limit. Returns the evicted hashes.
+    pub async fn unpin_track(&self, hash: Hash) -> Vec<Hash> {
+        if !self.blob_cache.unpin(hash).await {
+            return Vec::new();
         }
+        let evicted = self.blob_cache.evict_if_needed(&self.blob_store).await;
+        self.unpublish_reseeded(&evicted).await;
+        evicted
+    }
 
-        while let Some(result) = join_set.join_next().await {
-            if let Ok(results) = result {
-                all_results.extend(results);
+    /// Fetch pinned blobs missing from the store, one at a time. Runs after
+    /// startup and on every periodic cycle, so pins whose peers were offline
+    /// are picked up once they are back.
+    pub async fn fetch_pinned_blobs(self: &Arc<Self>) {
+        let pins = match pinned_track::Entity::find().all(&self.db).await {
+            Ok(pins) => pins,
+            Err(e) => {
+                warn!("failed to list pinned tracks: {e}");
+                return;
+            }
+        };
+        let mut fetched = 0usize;
+        for pin in pins {
+            let Ok(hash) = pin.content_hash.parse::<Hash>() else {
+                continue;
+            };
+            if self.has_blob(hash).await {
+                continue;
             }
+            match self.get_or_fetch_track(hash).await {
+                Ok(_) => fetched += 1,
+                Err(e) => debug!(%hash, "failed to fetch pinned blob: {e}"),
+            }
+        }
+        if fetched > 0 {
+            info!(fetched, "fetched pinned blobs");
         }
+    }
 
-        // Sort by relevance (highest first), deduplicate by hash
-        all_results.sort_by(|a, b| {
-            b.relevance
-                .partial_cmp(&a.relevance)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
+    /// Fetch the missing blobs of the P2P tracks in users' favorites and/or
+    /// playlists (see [`prewarm`](crate::prewarm)). Returns how many were
+    /// fetched, which is also reported in the blob cache stats.
+    pub async fn prewarm_blob_cache(self: &Arc<Self>) -> usize {
+        let hashes = match prewarm::kept_hashes(&self.db, self._config.prewarm_scope).await {
+            Ok(hashes) => hashes,
+            Err(e) => {
+                warn!("failed to list tracks to pre-warm: {e}");
+                return 0;
+            }
+        };
+        let hashes: Vec<Hash> = hashes.iter().filter_map(|h| h.parse().ok()).collect();
+        let warmed = prewarm::prewarm_blobs(
+            Arc::clone(self),
+            Arc::clone(&self.health_manager),
+            hashes,
+            MAX_PREWARM_PER_CYCLE,
+        )
+        .await;
+        self.blob_cache.set_prewarmed_last_cycle(warmed);
+        if warmed > 0 {
+            info!(warmed, "pre-warmed blob cache with kept tracks");
+        }
+        warmed
+    }
 
-        // Deduplicate by content hash (keep highest relevance)
-        let mut seen_hashes = std::collections::HashSet::new();
-        all_results.retain(|r| seen_hashes.insert(r.hash.clone()));
+    /// Fetch the blobs of recently played P2P tracks into the blob cache
+    /// (see [`BlobCache::warm_up_from_history`]). Returns the hashes fetched.
+    pub async fn warm_up_blob_cache(self: &Arc<Self>) -> Vec<Hash> {
+        let limit = self.blob_cache.warm_up_limit();
+        let warmed = self
+            .blob_cache
+            .warm_up_from_history(&self.db, &self.blob_store, self, limit)
+            .await;
+        for hash in &warmed {
+            self.reseed(&hash.to_string()).await;
+        }
+        let evicted = self.blob_cache.evict_if_needed(&self.blob_store).await;
+        self.unpublish_reseeded(&evicted).await;
+        warmed
+    }
 
-        // Apply limit
-        all_results.truncate(limit as usize);
+    /// Internal: in reseed mode, start serving a replicated blob that was
+    /// just cached.
+    async fn reseed(&self, hash: &str) {
+        if !self._config.reseed_replicated {
+            return;
+        }
+        if let Err(e) = self
+            .published_hashes
+            .publish(hash, PublishedKind::Track)
+            .await
+        {
+            warn!(%hash, "failed to publish cached blob for reseeding: {e}");
+        }
+    }
 
-        info!(
-            query = query,
-            total_results = all_results.len(),
-            "distributed search complete"
-        );
+    /// Internal: stop serving replicated blobs the cache just evicted. Our
+    /// own tracks stay published.
+    async fn unpublish_reseeded(&self, evicted: &[Hash]) {
+        if evicted.is_empty() {
+            return;
+        }
+        let hashes: Vec<String> = evicted.iter().map(|h| h.to_string()).collect();
+        let replicated = match track::Entity::find()
+            .filter(track::Column::ContentHash.is_in(hashes))
+            .filter(track::Column::FilePath.like("p2p://%"))
+            .all(&self.db)
+            .await
+        {
+            Ok(tracks) => tracks,
+            Err(e) => {
+                warn!("failed to look up evicted blobs: {e}");
+                return;
+            }
+        };
+        for hash in replicated.into_iter().filter_map(|t| t.content_hash) {
+            match self.published_hashes.unpublish(&hash).await {
+                Ok(()) => debug!(%hash, "evicted blob no longer reseeded"),
+                Err(e) => warn!(%hash, "failed to unpublish evicted blob: {e}"),
+            }
+        }
+    }
+
+    /// Fetch `hash` from several peers at once when possible.
+    ///
+    /// Sources are the online v2 peers holding the blob that are not
+    /// filtered to metadata-only replication, picked best-first
+    /// with [`select_best_copy`]. Returns `None` — so the caller falls back to
+    /// a single-peer fetch — when fewer than two sources qualify, the blob is
+    /// below [`MIN_SWARM_BLOB_SIZE`], or the swarm download fails.
+    async fn swarm_fetch_track(self: &Arc<Self>, hash: Hash) -> Option<Bytes> {
+        let mut candidates: Vec<PeerTrackInfo> = self
+            .alternative_sources(&hash.to_string())
+            .await
+            .into_iter()
+            .filter(|c| c.is_online && !self.is_metadata_only(&c.peer_id))
+            .collect();
+
+        let mut peers = Vec::new();
+        let mut total_size = 0u64;
+        while peers.len() < MAX_SWARM_SOURCES {
+            let Some(best) = select_best_copy(&candidates) else {
+                break;
+            };
+            let peer_id = best.peer_id.clone();
+            let file_size = best.file_size.max(0) as u64;
+            candidates.retain(|c| c.peer_id != peer_id);
+
+            if self.peer_supports_ranges(&peer_id).await {
+                total_size = total_size.max(file_size);
+                peers.push(peer_id);
+            }
+        }
 
-        all_results
+        if peers.len() < 2 || total_size < MIN_SWARM_BLOB_SIZE {
+            return None;
+        }
+
+        info!(%hash, sources = peers.len(), bytes = total_size, "swarm fetch from peers");
+        match swarm_fetch(Arc::clone(self), hash, total_size, &peers).await {
+            Ok(result) => {
+                info!(%hash, contributions = ?result.contributions, "swarm fetch complete");
+                Some(result.data)
+            }
+            Err(e) => {
+                warn!(%hash, "swarm fetch failed, falling back to single peer: {e}");
+                None
+            }
+        }
     }
 
-    /// Send a search query to a specific peer and wait for results.
-    async fn search_peer(
+    /// Fetch a blob from one peer, retrying dropped connections. Each retry
+    /// resumes from the bytes already saved by the previous attempt.
+    async fn fetch_track_resuming(
+        &self,
+        peer_id: EndpointId,
+        hash: Hash,
+    ) -> Result<Bytes, P2pError> {
+        let mut attempt = 1;
+        loop {
+            match self
+                .fetch_track_from_peer(EndpointAddr::new(peer_id), hash)
+                .await
+            {
+                Ok(data) => return Ok(data),
+                Err(P2pError::Connection(e)) if attempt < MAX_FETCH_ATTEMPTS => {
+                    warn!(%hash, attempt, "blob fetch failed, resuming: {e}");
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Check if a blob exists locally.
+    pub async fn has_blob(&self, hash: Hash) -> bool {
+        !self.is_marked_corrupted(hash).await
+            && self.blob_store.blobs().has(hash).await.unwrap_or(false)
+    }
+
+    /// Whether `hash` failed an integrity check and its bytes are still in
+    /// the blob store. The store only deletes blobs in its garbage
+    /// collector, so until the next sweep such a blob is neither read nor
+    /// served; the mark is dropped once the store no longer has it.
+    async fn is_marked_corrupted(&self, hash: Hash) -> bool {
+        if !self.corrupted_blobs.contains(&hash) {
+            return false;
+        }
+        if self.blob_store.blobs().has(hash).await.unwrap_or(true) {
+            return true;
+        }
+        self.corrupted_blobs.remove(&hash);
+        false
+    }
+
+    /// Connect to a remote peer and fetch a track by its content hash.
+    ///
+    /// Received bytes are saved to a partial file as they arrive; if an
+    /// earlier attempt was interrupted, only the missing tail is requested
+    /// (from v2 peers). The data is returned only after its BLAKE3 hash
+    /// verifies. The outcome goes into the peer's reliability history.
+    pub async fn fetch_track_from_peer(
         &self,
         peer_addr: EndpointAddr,
-        msg: &P2pMessage,
-    ) -> Result<Vec<SearchResultItem>, P2pError> {
+        hash: Hash,
+    ) -> Result<Bytes, P2pError> {
+        let peer_id = peer_addr.id.to_string();
+        let result = self.download_track(peer_addr, hash).await;
+        // Refusals say nothing about how reliable the peer is
+        if !matches!(
+            result,
+            Err(P2pError::PeerBlocked(_) | P2pError::AccessDenied(_))
+        ) {
+            self.registry.record_fetch(&peer_id, result.is_ok()).await;
+        }
+        result
+    }
+
+    /// Internal: the transfer behind [`Self::fetch_track_from_peer`].
+    async fn download_track(&self, peer_addr: EndpointAddr, hash: Hash) -> Result<Bytes, P2pError> {
+        let peer_id = peer_addr.id.to_string();
+
+        // Check if peer is blocked
+        if is_peer_blocked(&self.db, &peer_id).await {
+            return Err(P2pError::PeerBlocked(peer_id));
+        }
+
+        info!(peer = %peer_id, %hash, "fetching track from peer");
+
         let conn = self.conn_pool.get_connection(peer_addr.id).await?;
 
+        // Pick up bytes left by an earlier, interrupted attempt
+        let mut partial = PartialDownload::open(&self.partial_dir, hash).await?;
+        let peer_version = self
+            .conn_pool
+            .negotiated_version(&peer_addr.id)
+            .await
+            .unwrap_or(ProtocolVersion::V1);
+        // Needed if the track is private on the peer
+        let token = self.fetch_token(&peer_id, &hash.to_string()).await;
+        let request = if partial.received() == 0 {
+            P2pMessage::FetchTrack {
+                hash: hash.to_string(),
+                token,
+            }
+        } else if peer_version >= ProtocolVersion::V2 {
+            info!(%hash, offset = partial.received(), "resuming partial blob fetch");
+            P2pMessage::FetchTrackRange {
+                hash: hash.to_string(),
+                offset: partial.received(),
+                length: None,
+                token,
+                verified: false,
+            }
+        } else {
+            // v1 peers can only send the whole blob
+            partial.reset().await?;
+            P2pMessage::FetchTrack {
+                hash: hash.to_string(),
+                token,
+            }
+        };
+
+        // Send fetch request
         let (mut send, mut recv) = match conn.open_bi().await {
             Ok(streams) => streams,
             Err(e) => {
@@ -1672,2100 +4272,6819 @@ impl P2pNode {
             }
         };
 
-        let msg_bytes = serde_json::to_vec(msg)?;
-        send.write_all(&(msg_bytes.len() as u32).to_be_bytes())
+        let request_bytes = serde_json::to_vec(&request)?;
+        send.write_all(&(request_bytes.len() as u32).to_be_bytes())
             .await
             .map_err(|e| P2pError::Connection(e.to_string()))?;
-        send.write_all(&msg_bytes)
+        send.write_all(&request_bytes)
             .await
             .map_err(|e| P2pError::Connection(e.to_string()))?;
         send.finish()
             .map_err(|e| P2pError::Connection(e.to_string()))?;
+        self.stats.record_sent(request.kind());
 
-        // Read response
+        // Read response — first 4 bytes = length, then data
         let mut len_buf = [0u8; 4];
         recv.read_exact(&mut len_buf)
             .await
             .map_err(|e| P2pError::Connection(e.to_string()))?;
-        let msg_len = u32::from_be_bytes(len_buf) as usize;
+        let data_len = u32::from_be_bytes(len_buf);
+        if data_len == ACCESS_DENIED_LEN {
+            return Err(self.read_access_denied(&mut recv, hash).await);
+        }
+        let data_len = data_len as u64;
 
-        let response = recv
-            .read_to_end(msg_len)
-            .await
-            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        if data_len == 0 {
+            // A resumed fetch that already holds every byte gets an empty
+            // range back — the partial file may be complete.
+            if partial.received() > 0 {
+                if let Ok(data) = partial.finish().await {
+                    return Ok(data);
+                }
+            }
+            return Err(P2pError::TrackNotFound(hash.to_string()));
+        }
 
-        let response_msg: P2pMessage = serde_json::from_slice(&response)?;
+        // Stream into the partial file so a dropped connection keeps progress
+        let expected = partial.received() + data_len;
+        let mut buf = vec![0u8; 64 * 1024];
+        while partial.received() < expected {
+            let n = match recv.read(&mut buf).await {
+                Ok(Some(n)) => n,
+                Ok(None) => break,
+                Err(e) => {
+                    warn!(%hash, received = partial.received(), "blob fetch interrupted: {e}");
+                    return Err(P2pError::Connection(e.to_string()));
+                }
+            };
+            let n = n.min((expected - partial.received()) as usize);
+            partial.append(&buf[..n]).await?;
+            self.stats.record_blob_downloaded(n as u64);
+        }
 
-        match response_msg {
-            P2pMessage::SearchResults { results, .. } => Ok(results),
-            _ => Ok(vec![]),
+        if partial.received() < expected {
+            return Err(P2pError::Connection(format!(
+                "blob stream ended early ({} of {expected} bytes)",
+                partial.received()
+            )));
         }
+
+        // Only hand the blob over once the full BLAKE3 hash verifies
+        let data = partial.finish().await?;
+        debug!(%hash, bytes = data.len(), "received track from peer");
+        Ok(data)
     }
 
-    /// Handle an incoming SearchQuery: run a local FTS query and return results.
-    async fn handle_search_query(
+    /// Internal: read the `AccessDenied` a peer sent after an
+    /// [`ACCESS_DENIED_LEN`] prefix in reply to a fetch of `hash`.
+    async fn read_access_denied(
         &self,
-        request_id: &str,
-        query: &str,
-        limit: u32,
-        mut send: iroh::endpoint::SendStream,
-    ) -> Result<(), P2pError> {
-        use sea_orm::{FromQueryResult, Statement};
-
-        // Build tsquery from the search string
-        let tsquery = query
-            .split_whitespace()
-            .filter(|w| !w.is_empty())
-            .map(|w| {
-                let clean: String = w
-                    .chars()
-                    .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
-                    .collect();
-                if clean.is_empty() {
-                    String::new()
-                } else {
-                    format!("{}:*", clean)
-                }
-            })
-            .filter(|w| !w.is_empty())
-            .collect::<Vec<_>>()
-            .join(" & ");
-
-        if tsquery.is_empty() {
-            let resp = P2pMessage::SearchResults {
-                request_id: request_id.to_string(),
-                results: vec![],
-                total: 0,
-            };
-            let resp_bytes = serde_json::to_vec(&resp)?;
-            send.write_all(&(resp_bytes.len() as u32).to_be_bytes())
-                .await
-                .map_err(|e| P2pError::Connection(e.to_string()))?;
-            send.write_all(&resp_bytes)
-                .await
-                .map_err(|e| P2pError::Connection(e.to_string()))?;
-            send.finish()
-                .map_err(|e| P2pError::Connection(e.to_string()))?;
-            return Ok(());
-        }
-
-        #[derive(Debug, FromQueryResult)]
-        struct SearchRow {
-            hash: Option<String>,
-            title: String,
-            artist_name: String,
-            album_title: Option<String>,
-            duration_secs: f32,
-            format: String,
-            genre: Option<String>,
-            year: Option<i16>,
-            bitrate: Option<i32>,
-            musicbrainz_id: Option<String>,
-            rank: f32,
+        recv: &mut iroh::endpoint::RecvStream,
+        hash: Hash,
+    ) -> P2pError {
+        if let Ok(bytes) = recv.read_to_end(64 * 1024).await {
+            if let Ok(msg) = serde_json::from_slice::<P2pMessage>(&bytes) {
+                self.stats.record_received(msg.kind());
+            }
         }
+        warn!(%hash, "peer denied access to private track");
+        P2pError::AccessDenied(hash.to_string())
+    }
 
-        let our_node = self.node_id().to_string();
-
-        let rows: Vec<SearchRow> = SearchRow::find_by_statement(Statement::from_sql_and_values(
-            sea_orm::DatabaseBackend::Postgres,
-            r#"
-            SELECT t.content_hash AS hash, t.title, a.name AS artist_name,
-                   al.title AS album_title, t.duration_secs, t.format,
-                   t.genre, t.year, t.bitrate, t.musicbrainz_id,
-                   ts_rank(
-                       setweight(to_tsvector('english', t.title), 'A') ||
-                       setweight(to_tsvector('english', a.name), 'B') ||
-                       setweight(to_tsvector('english', COALESCE(al.title, '')), 'C'),
-                       to_tsquery('english', $1)
-                   ) AS rank
-            FROM tracks t
-            JOIN artists a ON a.id = t.artist_id
-            LEFT JOIN albums al ON al.id = t.album_id
-            WHERE (
-                to_tsvector('english', t.title) ||
-                to_tsvector('english', a.name) ||
-                to_tsvector('english', COALESCE(al.title, ''))
-            ) @@ to_tsquery('english', $1)
-            ORDER BY rank DESC
-            LIMIT $2
-            "#,
-            vec![tsquery.into(), (limit as i64).into()],
-        ))
-        .all(&self.db)
-        .await
-        .unwrap_or_default();
-
-        let results: Vec<SearchResultItem> = rows
-            .into_iter()
-            .filter(|r| r.hash.is_some())
-            .map(|r| SearchResultItem {
-                hash: r.hash.unwrap_or_default(),
-                title: r.title,
-                artist_name: r.artist_name,
-                album_title: r.album_title,
-                duration_secs: r.duration_secs,
-                format: r.format,
-                genre: r.genre,
-                year: r.year,
-                bitrate: r.bitrate,
-                source_node: our_node.clone(),
-                musicbrainz_id: r.musicbrainz_id,
-                relevance: r.rank,
-            })
-            .collect();
+    /// Send a ping to a peer and wait for pong.
+    ///
+    /// Direct addresses in `peer_addr` are remembered for later connections
+    /// to the peer.
+    pub async fn ping_peer(&self, peer_addr: EndpointAddr) -> Result<P2pMessage, P2pError> {
+        self.conn_pool.add_known_addr(peer_addr.clone()).await;
+        let conn = self.conn_pool.get_connection(peer_addr.id).await.map_err(|e| {
+            let err_str = format!("{e}");
+            if err_str.contains("ALPN") || err_str.contains("protocol") || err_str.contains("timed out") {
+                P2pError::Connection(format!(
+                    "{e} (possible protocol version mismatch — this node uses iroh 0.96 / ALPN 'soundtime/p2p/2' or 'soundtime/p2p/1')"
+                ))
+            } else {
+                e
+            }
+        })?;
 
-        let total = results.len() as u64;
-        let resp = P2pMessage::SearchResults {
-            request_id: request_id.to_string(),
-            results,
-            total,
+        let (mut send, mut recv) = match conn.open_bi().await {
+            Ok(streams) => streams,
+            Err(e) => {
+                self.conn_pool.invalidate(&peer_addr.id).await;
+                return Err(P2pError::Connection(e.to_string()));
+            }
         };
 
-        let resp_bytes = serde_json::to_vec(&resp)?;
-        send.write_all(&(resp_bytes.len() as u32).to_be_bytes())
+        let ping = serde_json::to_vec(&P2pMessage::Ping)?;
+        let sent_at = std::time::Instant::now();
+        send.write_all(&(ping.len() as u32).to_be_bytes())
             .await
             .map_err(|e| P2pError::Connection(e.to_string()))?;
-        send.write_all(&resp_bytes)
+        send.write_all(&ping)
             .await
             .map_err(|e| P2pError::Connection(e.to_string()))?;
         send.finish()
             .map_err(|e| P2pError::Connection(e.to_string()))?;
+        self.stats.record_sent(P2pMessage::Ping.kind());
 
-        Ok(())
-    }
-
-    /// Announce all locally-uploaded tracks to a specific peer using incremental sync
-    /// when possible (if we have a `last_seen` timestamp for this peer), otherwise
-    /// falls back to a full CatalogSync.
-    pub async fn incremental_sync_to_peer(
-        &self,
-        peer_id: EndpointId,
-        since: Option<chrono::DateTime<chrono::Utc>>,
-    ) {
-        let since_ts = since.unwrap_or(chrono::DateTime::UNIX_EPOCH);
+        let mut len_buf = [0u8; 4];
+        recv.read_exact(&mut len_buf)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        let msg_len = u32::from_be_bytes(len_buf) as usize;
 
-        let page_size = 500u64;
-        let total = match track::Entity::find()
-            .filter(track::Column::ContentHash.is_not_null())
-            .filter(track::Column::FilePath.not_like("p2p://%"))
-            .filter(track::Column::CreatedAt.gt(since_ts))
-            .count(&self.db)
+        let response = recv
+            .read_to_end(msg_len)
             .await
-        {
-            Ok(c) => c,
-            Err(e) => {
-                warn!("failed to count tracks for incremental sync: {e}");
-                return;
-            }
-        };
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
 
-        if total == 0 {
-            debug!(peer = %peer_id, "no new tracks since last sync");
-            return;
+        let rtt_ms = u32::try_from(sent_at.elapsed().as_millis()).unwrap_or(u32::MAX);
+        let pong: P2pMessage = serde_json::from_slice(&response)?;
+        self.stats.record_received(pong.kind());
+
+        self.registry
+            .record_rtt(&peer_addr.id.to_string(), rtt_ms)
+            .await;
+        if let Some(version) = self.conn_pool.negotiated_version(&peer_addr.id).await {
+            self.registry
+                .set_protocol_version(&peer_addr.id.to_string(), version)
+                .await;
         }
 
-        let num_pages = total.div_ceil(page_size);
-        let our_node = self.node_id().to_string();
-        let mut cover_cache: HashMap<Uuid, Option<String>> = HashMap::new();
+        Ok(pong)
+    }
 
-        info!(
-            peer = %peer_id,
-            total,
-            since = %since_ts,
-            pages = num_pages,
-            "starting paginated incremental sync"
-        );
+    /// Connect to a list of seed peers, given as EndpointIds optionally
+    /// followed by direct addresses (see [`parse_peer_addr`]).
+    /// Pings each one and registers it in the registry.
+    async fn connect_to_seed_peers(&self, seed_peers: &[String]) {
+        info!(count = seed_peers.len(), "connecting to seed peers");
 
-        for page_num in 0..num_pages {
-            let tracks = match track::Entity::find()
-                .filter(track::Column::ContentHash.is_not_null())
-                .filter(track::Column::FilePath.not_like("p2p://%"))
-                .filter(track::Column::CreatedAt.gt(since_ts))
-                .paginate(&self.db, page_size)
-                .fetch_page(page_num)
-                .await
-            {
-                Ok(t) => t,
+        for entry in seed_peers {
+            let peer_addr = match parse_peer_addr(entry) {
+                Ok(addr) => addr,
                 Err(e) => {
-                    warn!(
-                        page = page_num,
-                        "failed to read tracks page for incremental sync: {e}"
-                    );
+                    warn!(peer = %entry, "skipping seed peer: {e}");
                     continue;
                 }
             };
+            let node_id = peer_addr.id;
+            let peer_id_str = node_id.to_string();
 
-            // ── Batch-fetch artists and albums for this page ──
-            let artist_ids: Vec<Uuid> = tracks
-                .iter()
-                .map(|t| t.artist_id)
-                .collect::<std::collections::HashSet<_>>()
-                .into_iter()
-                .collect();
-            let album_ids: Vec<Uuid> = tracks
-                .iter()
-                .filter_map(|t| t.album_id)
-                .collect::<std::collections::HashSet<_>>()
-                .into_iter()
-                .collect();
-
-            let artist_map: HashMap<Uuid, String> = if !artist_ids.is_empty() {
-                artist::Entity::find()
-                    .filter(artist::Column::Id.is_in(artist_ids))
-                    .all(&self.db)
-                    .await
-                    .unwrap_or_default()
-                    .into_iter()
-                    .map(|a| (a.id, a.name))
-                    .collect()
-            } else {
-                HashMap::new()
-            };
-
-            let album_map: HashMap<Uuid, (String, Option<String>)> = if !album_ids.is_empty() {
-                album::Entity::find()
-                    .filter(album::Column::Id.is_in(album_ids))
-                    .all(&self.db)
-                    .await
-                    .unwrap_or_default()
-                    .into_iter()
-                    .map(|a| (a.id, (a.title, a.cover_url)))
-                    .collect()
-            } else {
-                HashMap::new()
-            };
+            // Skip ourselves
+            if node_id == self.node_id() {
+                debug!("skipping self in seed peers");
+                continue;
+            }
+            self.registry.add_seed(&peer_id_str);
 
-            let mut announcements = Vec::with_capacity(tracks.len());
+            info!(
+                peer = %peer_id_str,
+                direct_addrs = peer_addr.ip_addrs().count(),
+                "pinging seed peer"
+            );
 
-            for t in &tracks {
-                if t.file_path.starts_with("p2p://") {
-                    continue;
+            match self.ping_peer(peer_addr).await {
+                Ok(P2pMessage::Pong {
+                    node_id: nid,
+                    track_count,
+                    version,
+                    capabilities,
+                }) => {
+                    self.registry
+                        .upsert_peer_versioned(&nid, None, track_count, version)
+                        .await;
+                    self.registry.set_capabilities(&nid, capabilities).await;
+                    info!(peer = %nid, %track_count, "seed peer connected and registered");
+                    // Exchange peer lists to discover the wider network
+                    self.discover_via_peer(node_id).await;
+                    // Sync our full catalog to this peer so they get our existing tracks
+                    if !self.registry.is_quarantined(&nid).await {
+                        self.announce_all_tracks_to_peer(node_id).await;
+                    }
                 }
-                let hash = match &t.content_hash {
-                    Some(h) => h.clone(),
-                    None => continue,
-                };
-
-                let artist_name = artist_map
-                    .get(&t.artist_id)
-                    .cloned()
-                    .unwrap_or_else(|| "Unknown".to_string());
-
-                let (album_title, cover_hash) = match t.album_id {
-                    Some(aid) => match album_map.get(&aid) {
-                        Some((title, cover_url)) => {
-                            let title = Some(title.clone());
-                            let ch = if let Some(cached) = cover_cache.get(&aid) {
-                                cached.clone()
-                            } else {
-                                let result = if let Some(ref url) = cover_url {
-                                    let rel = url.strip_prefix("/api/media/").unwrap_or(url);
-                                    let full = self.audio_storage_path.join(rel);
-                                    let cover_data = match tokio::fs::read(&full).await {
-                                        Ok(data) => Some(data),
-                                        Err(_) => {
-                                            if let Some(ref meta) = self.metadata_storage_path {
-                                                tokio::fs::read(meta.join(rel)).await.ok()
-                                            } else {
-                                                None
-                                            }
-                                        }
-                                    };
-                                    match cover_data {
-                                        Some(data) => {
-                                            match self.publish_cover(Bytes::from(data)).await {
-                                                Ok(h) => Some(h.to_string()),
-                                                Err(_) => None,
-                                            }
-                                        }
-                                        None => None,
-                                    }
-                                } else {
-                                    None
-                                };
-                                cover_cache.insert(aid, result.clone());
-                                result
-                            };
-                            (title, ch)
-                        }
-                        None => (None, None),
-                    },
-                    None => (None, None),
-                };
-
-                announcements.push(TrackAnnouncement {
-                    hash,
-                    title: t.title.clone(),
-                    artist_name,
-                    album_artist_name: None,
-                    album_title,
-                    duration_secs: t.duration_secs,
-                    format: t.format.clone(),
-                    file_size: t.file_size,
-                    genre: t.genre.clone(),
-                    year: t.year,
-                    track_number: t.track_number,
-                    disc_number: t.disc_number,
-                    bitrate: t.bitrate,
-                    sample_rate: t.sample_rate,
-                    origin_node: our_node.clone(),
-                    cover_hash,
-                });
-            }
-
-            if !announcements.is_empty() {
-                info!(
-                    peer = %peer_id,
-                    count = announcements.len(),
-                    page = page_num,
-                    since = %since_ts,
-                    "incremental catalog sync page"
-                );
-
-                let msg = P2pMessage::CatalogDelta {
-                    since: since_ts,
-                    tracks: announcements,
-                };
-                if let Err(e) = self.send_message_to_peer(peer_id, &msg).await {
-                    warn!(peer = %peer_id, page = page_num, "failed to send catalog delta page: {e}");
+                Ok(_) => {
+                    self.registry.upsert_peer(&peer_id_str, None, 0).await;
+                    warn!(peer = %peer_id_str, "seed peer responded with unexpected message");
+                }
+                Err(e) => {
+                    // Check if it's a protocol version mismatch
+                    let err_str = format!("{e}");
+                    if err_str.contains("ALPN")
+                        || err_str.contains("protocol")
+                        || err_str.contains("version")
+                        || err_str.contains("timed out")
+                    {
+                        warn!(
+                            peer = %peer_id_str,
+                            "failed to reach seed peer (possible protocol version mismatch — \
+                             this node uses iroh 0.96 / ALPN 'soundtime/p2p/2' or 'soundtime/p2p/1', the remote peer \
+                             may be running an incompatible version): {e}"
+                        );
+                    } else {
+                        warn!(peer = %peer_id_str, "failed to reach seed peer: {e}");
+                    }
                 }
             }
         }
 
-        // Also send our bloom filter so the peer can route searches to us
-        let bloom_data = self.search_index.export_local_bloom().await;
-        let bloom_msg = P2pMessage::BloomExchange { bloom: bloom_data };
-        if let Err(e) = self.send_message_to_peer(peer_id, &bloom_msg).await {
-            debug!(peer = %peer_id, "failed to send bloom filter: {e}");
-        }
+        let total = self.registry.peer_count().await;
+        let online = self.registry.online_peers().await.len();
+        info!(%total, %online, "seed peer discovery complete");
     }
 
-    /// Internal: accept incoming connections in a loop.
-    async fn accept_loop(self: &Arc<Self>) {
-        let mut shutdown_rx = self.shutdown_tx.subscribe();
-
-        loop {
-            tokio::select! {
-                incoming = self.endpoint.accept() => {
-                    match incoming {
-                        Some(incoming) => {
-                            let node = Arc::clone(self);
-                            let permit = match node.conn_semaphore.clone().try_acquire_owned() {
-                                Ok(p) => p,
-                                Err(_) => {
-                                    warn!("max concurrent P2P connections reached, dropping connection");
-                                    continue;
-                                }
-                            };
-                            tokio::spawn(async move {
-                                let _permit = permit; // held for the duration
-                                match incoming.await {
-                                    Ok(conn) => {
-                                        if let Err(e) = node.handle_connection(conn).await {
-                                            warn!("error handling connection: {e}");
-                                        }
-                                    }
-                                    Err(e) => {
-                                        warn!("error accepting connection: {e}");
-                                    }
-                                }
-                            });
-                        }
-                        None => {
-                            info!("endpoint accept stream ended");
-                            break;
-                        }
-                    }
-                }
-                _ = shutdown_rx.changed() => {
-                    info!("accept loop received shutdown signal");
-                    break;
-                }
+    /// Connect to the seeds stored in the database that are not online.
+    /// Seeds also listed in `P2P_SEED_PEERS` are left to the startup
+    /// connection and regular peer refresh.
+    pub async fn connect_db_seeds(&self) {
+        let db_seeds = match seed_peers::list(&self.db).await {
+            Ok(seeds) => seeds,
+            Err(e) => {
+                warn!("failed to load seed peers from database: {e}");
+                return;
             }
+        };
+        let online: std::collections::HashSet<String> = self
+            .registry
+            .online_peers()
+            .await
+            .into_iter()
+            .map(|p| p.node_id)
+            .collect();
+        let offline: Vec<String> = seed_peers::merge_seeds(&self._config.seed_peers, &db_seeds)
+            .split_off(self._config.seed_peers.len())
+            .into_iter()
+            .filter(|id| !online.contains(id))
+            .collect();
+        if !offline.is_empty() {
+            self.connect_to_seed_peers(&offline).await;
         }
     }
 
-    /// Internal: handle a single incoming connection.
-    async fn handle_connection(self: &Arc<Self>, conn: Connection) -> Result<(), P2pError> {
-        let peer_id = conn.remote_id().to_string();
-
-        info!(%peer_id, "incoming connection");
+    /// Connect to a single seed peer in the background, e.g. right after an
+    /// admin adds it.
+    pub fn connect_seed_in_background(self: &Arc<Self>, node_id: String) {
+        let node = Arc::clone(self);
+        tokio::spawn(async move {
+            node.connect_to_seed_peers(&[node_id]).await;
+        });
+    }
 
-        // Check if peer is blocked
-        if is_peer_blocked(&self.db, &peer_id).await {
-            warn!(%peer_id, "rejected blocked peer");
-            conn.close(1u8.into(), b"blocked");
-            return Err(P2pError::PeerBlocked(peer_id));
-        }
+    /// Labels of the database seed peers, keyed by node ID.
+    pub async fn seed_labels(&self) -> HashMap<String, String> {
+        seed_peers::labels(&self.db).await.unwrap_or_else(|e| {
+            warn!("failed to load seed peer labels: {e}");
+            HashMap::new()
+        })
+    }
 
-        // Register the peer in our registry (marks it online with last_seen = now)
-        self.registry.upsert_peer(&peer_id, None, 0).await;
+    /// Send a message to a specific peer through its outbound priority queue.
+    ///
+    /// Serializes the message once and enqueues it according to
+    /// [`P2pMessage::priority`]; the peer's send task handles delivery retries.
+    /// Fails with [`P2pError::UnsupportedProtocol`] if the negotiated protocol
+    /// version is too old for this message.
+    async fn send_message_to_peer(
+        &self,
+        node_id: EndpointId,
+        msg: &P2pMessage,
+    ) -> Result<(), P2pError> {
+        let msg_bytes = serde_json::to_vec(msg)?;
+        self.conn_pool
+            .send(
+                node_id,
+                msg.priority(),
+                msg.min_protocol_version(),
+                msg_bytes,
+            )
+            .await?;
+        self.stats.record_sent(msg.kind());
+        Ok(())
+    }
 
-        // Accept bidirectional streams from this connection
-        while let Ok((send, mut recv)) = conn.accept_bi().await {
-            let node_id = self.node_id();
+    /// Broadcast a track announcement to all online peers concurrently.
+    /// Called after a track is published to the local blob store.
+    /// Uses a semaphore to limit concurrency to 10 simultaneous sends.
+    pub async fn broadcast_announce_track(self: &Arc<Self>, mut announcement: TrackAnnouncement) {
+        let peers = self.registry.online_peers().await;
+        if peers.is_empty() {
+            debug!(hash = %announcement.hash, "no online peers to announce track to");
+            return;
+        }
 
-            // Read length-prefixed message
-            let mut len_buf = [0u8; 4];
-            if recv.read_exact(&mut len_buf).await.is_err() {
-                break;
-            }
-            let msg_len = u32::from_be_bytes(len_buf) as usize;
+        info!(
+            hash = %announcement.hash,
+            title = %announcement.title,
+            artist = %announcement.artist_name,
+            peer_count = peers.len(),
+            "broadcasting track announcement"
+        );
 
-            // SECURITY: Reject oversized messages to prevent OOM (FIX-17)
-            if msg_len > MAX_P2P_MESSAGE_SIZE {
-                warn!(%peer_id, msg_len, "rejecting oversized P2P message (max: {MAX_P2P_MESSAGE_SIZE})");
-                break;
-            }
+        // Only spend time signing if some peer will check the signature
+        if peers
+            .iter()
+            .any(|p| p.has_capability(CAP_SIGNED_ANNOUNCEMENTS))
+        {
+            self.sign_announcement(&mut announcement);
+        }
+        let msg = P2pMessage::AnnounceTrack(Box::new(announcement));
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(10));
+        let mut handles = Vec::new();
 
-            let msg_bytes = match recv.read_to_end(msg_len).await {
-                Ok(b) => b,
-                Err(_) => break,
+        for peer in &peers {
+            let node_id: EndpointId = match peer.node_id.parse() {
+                Ok(id) => id,
+                Err(_) => continue,
             };
 
-            // SECURITY: Message size is bounded by MAX_P2P_MESSAGE_SIZE (FIX-17).
-            // serde_json's default recursion limit (128) provides depth protection.
-            let msg: P2pMessage = match serde_json::from_slice(&msg_bytes) {
-                Ok(m) => m,
-                Err(e) => {
-                    warn!("invalid message from {peer_id}: {e}");
-                    break;
-                }
-            };
+            let node = Arc::clone(self);
+            let msg = msg.clone();
+            let sem = Arc::clone(&semaphore);
+            let peer_id = peer.node_id.clone();
 
-            self.handle_message(msg, send, node_id, &peer_id).await?;
+            handles.push(tokio::spawn(async move {
+                let _permit = sem.acquire().await.ok();
+                if let Err(e) = node.send_message_to_peer(node_id, &msg).await {
+                    warn!(peer = %peer_id, "failed to announce track: {e}");
+                    node.registry.mark_offline(&peer_id).await;
+                } else {
+                    debug!(peer = %peer_id, "track announced");
+                }
+            }));
         }
 
-        Ok(())
+        for h in handles {
+            let _ = h.await;
+        }
     }
 
-    /// Internal: process a single track announcement — de-duplicate, auto-fetch blob,
-    /// create artist/album/track/remote_track records in the local database.
-    /// Used by both AnnounceTrack (single) and CatalogSync (batch) handlers.
-    async fn process_track_announcement(&self, ann: TrackAnnouncement, peer_id: &str) {
-        info!(
-            hash = %ann.hash,
-            title = %ann.title,
-            artist = %ann.artist_name,
-            %peer_id,
-            "processing track announcement"
-        );
-        self.registry.upsert_peer(peer_id, None, 0).await;
-
-        // Check if we already have this track (by content_hash)
-        let already_exists = track::Entity::find()
-            .filter(track::Column::ContentHash.eq(Some(ann.hash.clone())))
-            .one(&self.db)
-            .await
-            .ok()
-            .flatten()
-            .is_some();
+    /// Internal: sign an announcement of one of our tracks with the node key.
+    fn sign_announcement(&self, ann: &mut TrackAnnouncement) {
+        ann.sign(self.endpoint.secret_key());
+    }
 
-        if already_exists {
-            debug!(hash = %ann.hash, "track already in local catalog, skipping");
+    /// Tell all online peers that the metadata of a local track was edited,
+    /// so their replicated copy stays in sync with ours.
+    pub async fn broadcast_update_track_metadata(
+        self: &Arc<Self>,
+        hash: String,
+        fields: TrackMetadataUpdate,
+    ) {
+        let peers = self.registry.online_peers().await;
+        if peers.is_empty() {
+            debug!(%hash, "no online peers to send metadata update to");
             return;
         }
 
-        // Blob is fetched lazily on first play (get_or_fetch_track) — no eager download
-        debug!(hash = %ann.hash, %peer_id, "track metadata stored, blob will be fetched on demand");
+        info!(%hash, peer_count = peers.len(), "broadcasting track metadata update");
 
-        // Create artist (find or create)
-        let artist_id = match artist::Entity::find()
-            .filter(artist::Column::Name.eq(&ann.artist_name))
-            .one(&self.db)
-            .await
-        {
-            Ok(Some(a)) => a.id,
-            _ => {
-                let new_id = Uuid::new_v4();
-                let new_artist = artist::ActiveModel {
-                    id: Set(new_id),
-                    name: Set(ann.artist_name.clone()),
-                    musicbrainz_id: Set(None),
-                    bio: Set(None),
-                    image_url: Set(None),
-                    created_at: Set(chrono::Utc::now().into()),
-                };
-                if let Err(e) = new_artist.insert(&self.db).await {
-                    warn!(artist = %ann.artist_name, "failed to create artist: {e}");
-                    match artist::Entity::find()
-                        .filter(artist::Column::Name.eq(&ann.artist_name))
-                        .one(&self.db)
-                        .await
-                    {
-                        Ok(Some(a)) => a.id,
-                        _ => return,
-                    }
-                } else {
-                    new_id
-                }
-            }
+        let msg = P2pMessage::UpdateTrackMetadata {
+            hash,
+            title: fields.title,
+            artist_name: fields.artist_name,
+            album_title: fields.album_title,
+            genre: fields.genre,
+            year: fields.year,
+            track_number: fields.track_number,
         };
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(10));
+        let mut handles = Vec::new();
 
-        // Resolve album artist for proper album grouping (compilations)
-        let album_artist_name = ann
-            .album_artist_name
-            .clone()
-            .unwrap_or_else(|| ann.artist_name.clone());
+        for peer in &peers {
+            let node_id: EndpointId = match peer.node_id.parse() {
+                Ok(id) => id,
+                Err(_) => continue,
+            };
 
-        let album_artist_id = if album_artist_name == ann.artist_name {
-            artist_id
-        } else {
-            let existing = artist::Entity::find()
-                .filter(artist::Column::Name.eq(&album_artist_name))
-                .one(&self.db)
-                .await;
-            match existing {
-                Ok(Some(a)) => a.id,
-                _ => {
-                    let new_id = Uuid::new_v4();
-                    let new_artist = artist::ActiveModel {
-                        id: Set(new_id),
-                        name: Set(album_artist_name.clone()),
-                        musicbrainz_id: Set(None),
-                        bio: Set(None),
-                        image_url: Set(None),
-                        created_at: Set(chrono::Utc::now().into()),
-                    };
-                    if let Err(e) = new_artist.insert(&self.db).await {
-                        warn!(artist = %album_artist_name, "failed to create album artist: {e}");
-                        match artist::Entity::find()
-                            .filter(artist::Column::Name.eq(&album_artist_name))
-                            .one(&self.db)
-                            .await
-                        {
-                            Ok(Some(a)) => a.id,
-                            _ => artist_id, // fallback to track artist
-                        }
-                    } else {
-                        new_id
-                    }
+            let node = Arc::clone(self);
+            let msg = msg.clone();
+            let sem = Arc::clone(&semaphore);
+            let peer_id = peer.node_id.clone();
+
+            handles.push(tokio::spawn(async move {
+                let _permit = sem.acquire().await.ok();
+                if let Err(e) = node.send_message_to_peer(node_id, &msg).await {
+                    warn!(peer = %peer_id, "failed to send track metadata update: {e}");
                 }
-            }
+            }));
+        }
+
+        for h in handles {
+            let _ = h.await;
+        }
+    }
+
+    /// Tell peers that one of our tracks was deleted. They mark their copy
+    /// unavailable and pass the news on for up to `P2P_GOSSIP_TTL` hops, so
+    /// peers we are not connected to drop it as well.
+    pub async fn broadcast_delete_track(self: &Arc<Self>, hash: String) {
+        let deleted_at = chrono::Utc::now();
+        let origin_node = self.node_id().to_string();
+        // Our own message may come back to us through other peers
+        self.gossip_seen
+            .insert(&gossip::delete_track_id(&hash, &origin_node));
+        let signature = sign_delete_track(self.endpoint.secret_key(), &hash, &deleted_at);
+        let msg = P2pMessage::DeleteTrack {
+            hash: hash.clone(),
+            origin_node,
+            deleted_at,
+            ttl: self.gossip_ttl,
+            signature: Some(signature),
         };
+        let sent = self.gossip_to_peers(&msg, &[]).await;
+        info!(%hash, peer_count = sent, "broadcast track deletion");
+    }
 
-        // Create album (find or create) if present
-        let album_id = if let Some(ref album_title) = ann.album_title {
-            match album::Entity::find()
-                .filter(album::Column::Title.eq(album_title))
-                .filter(album::Column::ArtistId.eq(album_artist_id))
-                .one(&self.db)
-                .await
-            {
-                Ok(Some(a)) => {
-                    // If album already exists but has no cover, try to sync one
-                    if a.cover_url.is_none() {
-                        self.sync_cover_for_album(a.id, &ann, peer_id).await;
-                    }
-                    Some(a.id)
-                }
-                _ => {
-                    let new_id = Uuid::new_v4();
-                    let new_album = album::ActiveModel {
-                        id: Set(new_id),
-                        title: Set(album_title.clone()),
-                        artist_id: Set(album_artist_id),
-                        release_date: Set(None),
-                        cover_url: Set(None),
-                        musicbrainz_id: Set(None),
-                        genre: Set(ann.genre.clone()),
-                        year: Set(ann.year),
-                        created_at: Set(chrono::Utc::now().into()),
-                    };
-                    match new_album.insert(&self.db).await {
-                        Ok(_) => {
-                            // Sync cover for newly created album
-                            self.sync_cover_for_album(new_id, &ann, peer_id).await;
-                            Some(new_id)
-                        }
-                        Err(e) => {
-                            warn!(album = %album_title, "failed to create album: {e}");
-                            album::Entity::find()
-                                .filter(album::Column::Title.eq(album_title))
-                                .filter(album::Column::ArtistId.eq(album_artist_id))
-                                .one(&self.db)
-                                .await
-                                .ok()
-                                .flatten()
-                                .map(|a| a.id)
-                        }
-                    }
-                }
+    /// Internal: handle a gossiped `DeleteTrack`. Each deletion is applied
+    /// and forwarded once, however many peers relay it to us.
+    ///
+    /// Only deletions signed by the origin, or unsigned ones sent by the
+    /// origin itself, are applied (see [`check_delete_track`]), and only
+    /// signed ones are forwarded. Each peer is limited to
+    /// [`DELETE_TRACKS_PER_MINUTE`] deletions, and the TTL to our own
+    /// `P2P_GOSSIP_TTL`. Applying one only marks our replicated copy
+    /// unavailable; the health monitor makes it available again if the
+    /// origin still serves the blob.
+    async fn receive_delete_track(
+        self: &Arc<Self>,
+        hash: String,
+        origin_node: String,
+        deleted_at: chrono::DateTime<chrono::Utc>,
+        ttl: u8,
+        signature: Option<String>,
+        peer_id: &str,
+    ) {
+        if origin_node == self.node_id().to_string() {
+            return;
+        }
+        if !self
+            .delete_track_rate
+            .admit(peer_id, std::time::Instant::now())
+        {
+            debug!(%peer_id, %hash, "track deletion rate limit reached, dropping deletion");
+            return;
+        }
+        if let Err(e) = check_delete_track(
+            &hash,
+            &origin_node,
+            &deleted_at,
+            signature.as_deref(),
+            peer_id,
+            chrono::Utc::now(),
+        ) {
+            warn!(%hash, origin = %origin_node, via = %peer_id, "refusing track deletion: {e}");
+            return;
+        }
+        if !self
+            .gossip_seen
+            .insert(&gossip::delete_track_id(&hash, &origin_node))
+        {
+            debug!(%hash, %peer_id, "ignoring track deletion already seen");
+            return;
+        }
+
+        match self.mark_deleted_copy(&hash, &origin_node).await {
+            Ok(true) => {
+                info!(%hash, origin = %origin_node, via = %peer_id, "origin deleted replicated track");
+                self.search_index.mark_dirty().await;
             }
-        } else {
-            None
+            Ok(false) => debug!(%hash, origin = %origin_node, "deleted track not replicated here"),
+            Err(e) => warn!(%hash, origin = %origin_node, "failed to apply track deletion: {e}"),
+        }
+
+        let ttl = ttl.min(self.gossip_ttl);
+        if ttl == 0 || signature.is_none() {
+            return;
+        }
+        let msg = P2pMessage::DeleteTrack {
+            hash,
+            origin_node: origin_node.clone(),
+            deleted_at,
+            ttl: ttl - 1,
+            signature,
         };
+        let node = Arc::clone(self);
+        let exclude = [peer_id.to_string(), origin_node];
+        tokio::spawn(async move {
+            let exclude: Vec<&str> = exclude.iter().map(String::as_str).collect();
+            node.gossip_to_peers(&msg, &exclude).await;
+        });
+    }
 
-        // Create the track record — file_path points to the P2P blob hash
-        let track_id = Uuid::new_v4();
-        let new_track = track::ActiveModel {
-            id: Set(track_id),
-            title: Set(ann.title.clone()),
-            artist_id: Set(artist_id),
-            album_id: Set(album_id),
-            track_number: Set(ann.track_number),
-            disc_number: Set(ann.disc_number),
-            duration_secs: Set(ann.duration_secs),
-            genre: Set(ann.genre.clone()),
-            year: Set(ann.year),
-            musicbrainz_id: Set(None),
-            file_path: Set(format!("p2p://{}", ann.hash)),
-            file_size: Set(ann.file_size),
-            format: Set(ann.format.clone()),
-            bitrate: Set(ann.bitrate),
-            sample_rate: Set(ann.sample_rate),
-            waveform_data: Set(None),
-            uploaded_by: Set(None),
-            content_hash: Set(Some(ann.hash.clone())),
-            play_count: Set(0),
-            created_at: Set(chrono::Utc::now().into()),
+    /// Internal: mark our copy of `origin_node`'s track `hash` unavailable,
+    /// starting its dereferenced clock for the retention cleanup. Returns
+    /// whether we had such a copy.
+    async fn mark_deleted_copy(&self, hash: &str, origin_node: &str) -> Result<bool, P2pError> {
+        let Some(rt) = remote_track::Entity::find()
+            .filter(remote_track::Column::RemoteUri.eq(format!("p2p://{origin_node}/{hash}")))
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(false);
         };
+        let dereferenced_at = rt
+            .dereferenced_at
+            .unwrap_or_else(|| chrono::Utc::now().into());
+        let mut active: remote_track::ActiveModel = rt.into();
+        active.is_available = Set(false);
+        active.dereferenced_at = Set(Some(dereferenced_at));
+        active.update(&self.db).await?;
+        Ok(true)
+    }
 
-        match new_track.insert(&self.db).await {
-            Ok(_) => {
-                info!(
-                    %track_id,
-                    title = %ann.title,
-                    artist = %ann.artist_name,
-                    hash = %ann.hash,
-                    "remote track replicated to local catalog"
-                );
+    /// Internal: send a gossip message to every online peer not in
+    /// `exclude`. Returns how many peers it went out to.
+    async fn gossip_to_peers(self: &Arc<Self>, msg: &P2pMessage, exclude: &[&str]) -> usize {
+        let peers: Vec<_> = self
+            .registry
+            .online_peers()
+            .await
+            .into_iter()
+            .filter(|p| !exclude.contains(&p.node_id.as_str()))
+            .collect();
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(10));
+        let mut handles = Vec::new();
 
-                // Index in Bloom filter for search routing
-                self.search_index
-                    .add_track_tokens(&ann.title, &ann.artist_name, ann.album_title.as_deref())
-                    .await;
+        for peer in &peers {
+            let node_id: EndpointId = match peer.node_id.parse() {
+                Ok(id) => id,
+                Err(_) => continue,
+            };
 
-                let remote_track_id = Uuid::new_v4();
-                let origin = &ann.origin_node;
-                let new_remote = remote_track::ActiveModel {
-                    id: Set(remote_track_id),
-                    local_track_id: Set(Some(track_id)),
-                    musicbrainz_id: Set(None),
-                    title: Set(ann.title.clone()),
-                    artist_name: Set(ann.artist_name.clone()),
-                    album_title: Set(ann.album_title.clone()),
-                    instance_domain: Set(format!("p2p://{}", origin)),
-                    remote_uri: Set(format!("p2p://{}/{}", origin, ann.hash)),
-                    remote_stream_url: Set(format!("/api/stream/p2p/{}", ann.hash)),
-                    bitrate: Set(ann.bitrate),
-                    sample_rate: Set(ann.sample_rate),
-                    format: Set(Some(ann.format.clone())),
-                    is_available: Set(true),
-                    last_checked_at: Set(Some(chrono::Utc::now().into())),
-                    created_at: Set(chrono::Utc::now().into()),
-                };
-                if let Err(e) = new_remote.insert(&self.db).await {
-                    warn!(hash = %ann.hash, "failed to create remote_track record: {e}");
-                }
+            let node = Arc::clone(self);
+            let msg = msg.clone();
+            let sem = Arc::clone(&semaphore);
+            let peer_id = peer.node_id.clone();
 
-                // Async MusicBrainz enrichment — spawned to avoid blocking
-                let mb = Arc::clone(&self.mb_client);
-                let db = self.db.clone();
-                let title = ann.title.clone();
-                let artist = ann.artist_name.clone();
-                tokio::spawn(async move {
-                    if let Some(recording) = mb.lookup_recording(&title, &artist).await {
-                        debug!(
-                            mb_id = %recording.id,
-                            title = %title,
-                            score = recording.score,
-                            "MusicBrainz match found"
-                        );
-                        let update = track::ActiveModel {
-                            id: Set(track_id),
-                            musicbrainz_id: Set(Some(recording.id)),
-                            ..Default::default()
-                        };
-                        if let Err(e) = update.update(&db).await {
-                            warn!(track_id = %track_id, "failed to update musicbrainz_id: {e}");
-                        }
+            handles.push(tokio::spawn(async move {
+                let _permit = sem.acquire().await.ok();
+                match node.send_message_to_peer(node_id, &msg).await {
+                    Ok(()) => true,
+                    Err(e) => {
+                        debug!(peer = %peer_id, "failed to send {}: {e}", msg.kind());
+                        false
                     }
-                });
-            }
-            Err(e) => {
-                warn!(hash = %ann.hash, "failed to create track record: {e}");
+                }
+            }));
+        }
+
+        let mut sent = 0;
+        for h in handles {
+            if h.await.unwrap_or(false) {
+                sent += 1;
             }
         }
+        sent
     }
 
-    /// Internal: handle a single protocol message.
-    async fn handle_message(
-        self: &Arc<Self>,
-        msg: P2pMessage,
-        mut send: iroh::endpoint::SendStream,
-        node_id: EndpointId,
+    /// Report a play of a replicated track to the instance it came from, so
+    /// its play count includes plays across the network. Does nothing for
+    /// local tracks.
+    pub async fn report_play(&self, track_id: Uuid) -> Result<(), P2pError> {
+        let Some(t) = track::Entity::find_by_id(track_id).one(&self.db).await? else {
+            return Ok(());
+        };
+        if !t.file_path.starts_with("p2p://") {
+            return Ok(());
+        }
+        let Some(remote) = remote_track::Entity::find()
+            .filter(remote_track::Column::LocalTrackId.eq(track_id))
+            .one(&self.db)
+            .await?
+        else {
+            debug!(%track_id, "no origin recorded for replicated track");
+            return Ok(());
+        };
+        let origin = remote
+            .instance_domain
+            .strip_prefix("p2p://")
+            .unwrap_or(&remote.instance_domain)
+            .to_string();
+        let hash = t
+            .content_hash
+            .unwrap_or_else(|| t.file_path.trim_start_matches("p2p://").to_string());
+        let node_id: EndpointId = origin
+            .parse()
+            .map_err(|_| P2pError::Connection(format!("invalid origin node id {origin}")))?;
+
+        let msg = P2pMessage::PlayCountUpdate {
+            hash,
+            origin_node: origin,
+            incremental_count: 1,
+        };
+        self.send_message_to_peer(node_id, &msg).await
+    }
+
+    /// Internal: add plays reported by a peer to one of our own tracks.
+    /// Only peers that can hold the track are heard: ones that received our
+    /// catalog, or for a private track, ones granted access to it. Each peer
+    /// is limited to [`PLAY_COUNT_UPDATES_PER_MINUTE`] updates.
+    async fn apply_play_count_update(
+        &self,
+        hash: &str,
+        incremental_count: u32,
         peer_id: &str,
     ) -> Result<(), P2pError> {
-        match msg {
-            P2pMessage::FetchTrack { hash } => {
-                // SECURITY: Only serve blobs that were explicitly published (FIX-19)
-                if !self.published_hashes.read().await.contains(&hash) {
-                    warn!(%peer_id, %hash, "rejected FetchTrack for non-published blob");
-                    send.write_all(&0u32.to_be_bytes())
-                        .await
-                        .map_err(|e| P2pError::Connection(e.to_string()))?;
-                    send.finish()
-                        .map_err(|e| P2pError::Connection(e.to_string()))?;
-                    return Ok(());
+        use sea_orm::{ConnectionTrait, Statement};
+
+        let count = incremental_count.min(MAX_PLAY_COUNT_INCREMENT);
+        if count == 0 {
+            return Ok(());
+        }
+        if !self
+            .play_count_rate
+            .admit(peer_id, std::time::Instant::now())
+        {
+            debug!(%peer_id, %hash, "play count update rate limit reached, dropping update");
+            return Ok(());
+        }
+        if !self.peer_may_hold_track(peer_id, hash).await? {
+            debug!(%peer_id, %hash, "play count update from a peer that cannot hold the track");
+            return Ok(());
+        }
+        // Atomic increment, as for local plays in POST /api/history
+        let res = self
+            .db
+            .execute(Statement::from_sql_and_values(
+                sea_orm::DatabaseBackend::Postgres,
+                "UPDATE tracks SET play_count = play_count + $1 \
+                 WHERE content_hash = $2 AND file_path NOT LIKE 'p2p://%'",
+                [(count as i64).into(), hash.into()],
+            ))
+            .await?;
+        if res.rows_affected() == 0 {
+            debug!(%peer_id, %hash, "play count update for a track we do not have");
+        } else {
+            debug!(%peer_id, %hash, count, "added remote plays");
+        }
+        Ok(())
+    }
+
+    /// Internal: whether `peer_id` can have replicated our track with this
+    /// content hash. A public track reaches every peer we sent our catalog
+    /// to; a private one only peers we granted access to it.
+    async fn peer_may_hold_track(&self, peer_id: &str, hash: &str) -> Result<bool, P2pError> {
+        let Some(t) = track::Entity::find()
+            .filter(track::Column::ContentHash.eq(hash))
+            .filter(track::Column::FilePath.not_like("p2p://%"))
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(false);
+        };
+        if t.is_private {
+            let granted = peer_track_grant::Entity::find()
+                .filter(peer_track_grant::Column::ContentHash.eq(hash))
+                .filter(peer_track_grant::Column::NodeId.eq(peer_id))
+                .filter(peer_track_grant::Column::IssuerNode.eq(self.node_id().to_string()))
+                .count(&self.db)
+                .await?;
+            return Ok(granted > 0);
+        }
+        Ok(self
+            .registry
+            .get_peer(peer_id)
+            .await
+            .is_some_and(|p| p.last_catalog_sync_at.is_some()))
+    }
+
+    /// Backfill `content_hash` for local tracks that were imported before P2P was enabled.
+    /// Reads each file, publishes it to the blob store (BLAKE3), and updates the DB.
+    /// Runs on startup to ensure all local tracks are available for P2P catalog sync.
+    async fn backfill_content_hashes(&self) {
+        let page_size = 100u64;
+        let total = match track::Entity::find()
+            .filter(track::Column::ContentHash.is_null())
+            .filter(track::Column::FilePath.not_like("p2p://%"))
+            .count(&self.db)
+            .await
+        {
+            Ok(0) => {
+                debug!("all local tracks already have content hashes");
+                return;
+            }
+            Ok(c) => c,
+            Err(e) => {
+                warn!("failed to count tracks for content hash backfill: {e}");
+                return;
+            }
+        };
+
+        info!(total, "starting content hash backfill for local tracks");
+
+        let num_pages = total.div_ceil(page_size);
+        let mut backfilled = 0u64;
+        let mut skipped = 0u64;
+
+        for page_num in 0..num_pages {
+            let tracks = match track::Entity::find()
+                .filter(track::Column::ContentHash.is_null())
+                .filter(track::Column::FilePath.not_like("p2p://%"))
+                .paginate(&self.db, page_size)
+                .fetch_page(page_num)
+                .await
+            {
+                Ok(t) => t,
+                Err(e) => {
+                    warn!(
+                        page = page_num,
+                        "failed to fetch tracks page for backfill: {e}"
+                    );
+                    continue;
                 }
+            };
 
-                let hash: Hash = hash
-                    .parse()
-                    .map_err(|_| P2pError::TrackNotFound(hash.clone()))?;
+            for t in &tracks {
+                let file_path = self.audio_storage_path.join(&t.file_path);
+                let data = match tokio::fs::read(&file_path).await {
+                    Ok(d) => d,
+                    Err(e) => {
+                        warn!(
+                            track_id = %t.id,
+                            path = %file_path.display(),
+                            "skipping backfill — file not readable: {e}"
+                        );
+                        skipped += 1;
+                        continue;
+                    }
+                };
 
-                match self.get_local_track(hash).await {
-                    Ok(data) => {
-                        let len = data.len() as u32;
-                        send.write_all(&len.to_be_bytes())
-                            .await
-                            .map_err(|e| P2pError::Connection(e.to_string()))?;
-                        send.write_all(&data)
-                            .await
-                            .map_err(|e| P2pError::Connection(e.to_string()))?;
+                match self.publish_track(Bytes::from(data)).await {
+                    Ok(hash) => {
+                        let update = track::ActiveModel {
+                            id: Set(t.id),
+                            content_hash: Set(Some(hash.to_string())),
+                            ..Default::default()
+                        };
+                        if let Err(e) = update.update(&self.db).await {
+                            warn!(track_id = %t.id, "failed to save content_hash during backfill: {e}");
+                            skipped += 1;
+                            continue;
+                        }
+                        self.invalidate_catalog_checksum();
+                        backfilled += 1;
                     }
-                    Err(_) => {
-                        // Send zero-length response to indicate not found
-                        send.write_all(&0u32.to_be_bytes())
-                            .await
-                            .map_err(|e| P2pError::Connection(e.to_string()))?;
+                    Err(e) => {
+                        warn!(track_id = %t.id, "failed to publish track during backfill: {e}");
+                        skipped += 1;
                     }
                 }
-                send.finish()
-                    .map_err(|e| P2pError::Connection(e.to_string()))?;
             }
-            P2pMessage::Ping => {
-                // Count ALL local tracks (not just those with content_hash set).
-                // During backfill, some tracks may not yet have hashes, but we still
-                // report the full catalog size so peers know to wait for announcements.
-                let track_count = match track::Entity::find()
-                    .filter(track::Column::FilePath.not_like("p2p://%"))
-                    .count(&self.db)
-                    .await
-                {
-                    Ok(c) => c,
-                    Err(e) => {
-                        tracing::error!("failed to count tracks for ping response: {e}");
-                        0
-                    }
-                };
 
-                let pong = P2pMessage::Pong {
-                    node_id: node_id.to_string(),
-                    track_count,
-                    version: Some(crate::build_version().to_string()),
-                };
-                let pong_bytes = serde_json::to_vec(&pong)?;
-                send.write_all(&(pong_bytes.len() as u32).to_be_bytes())
-                    .await
-                    .map_err(|e| P2pError::Connection(e.to_string()))?;
-                send.write_all(&pong_bytes)
-                    .await
-                    .map_err(|e| P2pError::Connection(e.to_string()))?;
-                send.finish()
-                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+            info!(
+                processed = backfilled + skipped,
+                total, backfilled, skipped, "content hash backfill progress"
+            );
+        }
 
-                // After responding to a Ping, sync our full catalog to this peer.
-                // This ensures that when a new peer connects, it receives all
-                // existing tracks — not just future uploads.
-                // Spawned asynchronously to avoid blocking the connection handler
-                // (catalog sync may query the DB for every track + fetch blobs).
-                if let Ok(remote_nid) = peer_id.parse::<EndpointId>() {
-                    let node = Arc::clone(self);
-                    tokio::spawn(async move {
-                        node.announce_all_tracks_to_peer(remote_nid).await;
-                    });
-                }
+        info!(backfilled, skipped, total, "content hash backfill complete");
+    }
+
+    /// Announce all locally-uploaded tracks to a specific peer using paginated queries.
+    /// Called when a new peer connects to sync existing catalogs.
+    /// Sends one CatalogSync message per page (500 tracks) to avoid loading all
+    /// tracks into memory at once. If a push to the same peer is already
+    /// running, it is asked to run once more when done instead of starting a
+    /// second one alongside it.
+    pub async fn announce_all_tracks_to_peer(&self, peer_id: EndpointId) {
+        let peer_key = peer_id.to_string();
+        if !self.outgoing_syncs.begin(&peer_key) {
+            debug!(peer = %peer_id, "catalog sync to peer already in progress, rerun requested");
+            return;
+        }
+        let started = self.catalog_sync.start(&peer_key).is_some();
+        self.run_catalog_pushes(peer_id, &peer_key, started, None)
+            .await;
+    }
+
+    /// Like [`Self::announce_all_tracks_to_peer`], but waits at `gate`
+    /// between pages so a library resync can pause or cancel the push.
+    /// If a push to the peer is already running, it is asked to run once
+    /// more (ungated) and this returns at once.
+    pub async fn announce_all_tracks_to_peer_gated(
+        &self,
+        peer_id: EndpointId,
+        gate: &mut SyncGate,
+    ) {
+        let peer_key = peer_id.to_string();
+        if !self.outgoing_syncs.begin(&peer_key) {
+            debug!(peer = %peer_id, "catalog sync to peer already in progress, rerun requested");
+            return;
+        }
+        let started = self.catalog_sync.start(&peer_key).is_some();
+        self.run_catalog_pushes(peer_id, &peer_key, started, Some(gate))
+            .await;
+    }
+
+    /// Start a full catalog push to `peer_id` in the background.
+    ///
+    /// Returns the task ID, or `None` if a push to that peer is already
+    /// running. Poll progress with [`Self::catalog_sync_progress`].
+    pub fn spawn_catalog_sync(self: &Arc<Self>, peer_id: EndpointId) -> Option<Uuid> {
+        let peer_key = peer_id.to_string();
+        // Checked first so a rejected request does not queue a rerun
+        if self.outgoing_syncs.is_running(&peer_key) || !self.outgoing_syncs.begin(&peer_key) {
+            return None;
+        }
+        let Some(task_id) = self.catalog_sync.start(&peer_key) else {
+            self.outgoing_syncs.finish_pass(&peer_key);
+            return None;
+        };
+        let node = Arc::clone(self);
+        tokio::spawn(async move {
+            node.run_catalog_pushes(peer_id, &peer_key, true, None)
+                .await;
+        });
+        Some(task_id)
+    }
+
+    /// Internal: run a push to a peer whose outgoing sync we hold (if
+    /// `started` registered one with the tracker), then one more for as long
+    /// as another caller asked for a rerun meanwhile.
+    async fn run_catalog_pushes(
+        &self,
+        peer_id: EndpointId,
+        peer_key: &str,
+        mut started: bool,
+        mut gate: Option<&mut SyncGate>,
+    ) {
+        loop {
+            if started {
+                self.run_catalog_push(peer_id, peer_key, gate.as_deref_mut())
+                    .await;
             }
-            P2pMessage::AnnounceTrack(ann) => {
-                self.process_track_announcement(ann, peer_id).await;
-                // Properly close our side of the stream
-                if let Err(e) = send.finish() {
-                    tracing::warn!(error = %e, "failed to finish send stream");
-                }
+            if !self.outgoing_syncs.finish_pass(peer_key) {
+                break;
             }
-            P2pMessage::CatalogSync(announcements) => {
-                // Acquire per-peer lock to serialize (not reject) concurrent CatalogSync pages
-                let peer_lock = {
-                    let mut map = self.catalog_sync_in_progress.lock().await;
-                    map.entry(peer_id.to_string())
-                        .or_insert_with(|| Arc::new(tokio::sync::Mutex::new(())))
-                        .clone()
-                };
-                let _guard = peer_lock.lock().await;
+            debug!(peer = %peer_id, "rerunning coalesced catalog sync");
+            started = self.catalog_sync.start(peer_key).is_some();
+        }
+    }
 
-                info!(count = announcements.len(), %peer_id, "received catalog sync");
+    /// Outgoing catalog syncs currently running.
+    pub fn outgoing_catalog_syncs(&self) -> Vec<OutgoingSync> {
+        self.outgoing_syncs.running()
+    }
 
-                // Process in batches of 100 with yielding to avoid blocking the runtime
-                for (i, ann) in announcements.into_iter().enumerate() {
-                    self.process_track_announcement(ann, peer_id).await;
-                    if (i + 1) % 100 == 0 {
-                        tokio::task::yield_now().await;
-                        debug!(%peer_id, processed = i + 1, "catalog sync batch progress");
-                    }
-                }
+    /// Internal: send every catalog page, then remember when the peer last
+    /// received our full catalog (only if every page went through) so later
+    /// Pings can send a delta instead.
+    async fn run_catalog_push(
+        &self,
+        peer_id: EndpointId,
+        peer_key: &str,
+        gate: Option<&mut SyncGate>,
+    ) {
+        let started = chrono::Utc::now();
+        let result = self.send_catalog_pages(peer_id, peer_key, gate).await;
+        if result.is_ok() {
+            self.registry.mark_catalog_synced(peer_key, started).await;
+        }
+        self.catalog_sync.finish(peer_key, result.err());
+    }
 
-                // Properly close our side of the stream
-                if let Err(e) = send.finish() {
-                    tracing::warn!(error = %e, "failed to finish send stream");
+    /// Internal: push our catalog to a peer that just pinged us — the full
+    /// catalog the first time, afterwards only tracks created since the last
+    /// successful sync. An interrupted full push is resumed first.
+    /// Quarantined peers get nothing.
+    async fn sync_catalog_after_ping(&self, peer_id: EndpointId) {
+        let peer_key = peer_id.to_string();
+        if self.registry.is_quarantined(&peer_key).await {
+            debug!(peer = %peer_id, "peer quarantined, skipping catalog sync");
+            return;
+        }
+        if self.catalog_sync_checkpoints.contains_key(&peer_key) {
+            self.announce_all_tracks_to_peer(peer_id).await;
+            return;
+        }
+        match self.registry.catalog_sync_plan(&peer_key).await {
+            CatalogSyncPlan::Full => self.announce_all_tracks_to_peer(peer_id).await,
+            CatalogSyncPlan::Delta { since } => {
+                let started = chrono::Utc::now();
+                match self.incremental_sync_to_peer(peer_id, Some(since)).await {
+                    Ok(()) => self.registry.mark_catalog_synced(&peer_key, started).await,
+                    Err(e) => warn!(peer = %peer_id, "catalog delta sync failed: {e}"),
                 }
             }
-            P2pMessage::PeerExchange { peers } => {
-                info!(count = peers.len(), %peer_id, "received peer exchange request");
+        }
+    }
 
-                // Verify new peers before adding them
-                let our_id = self.node_id().to_string();
-                let mut new_peers: Vec<String> = Vec::new();
-                for pid in &peers {
-                    if *pid != our_id && self.registry.get_peer(pid).await.is_none() {
-                        new_peers.push(pid.clone());
-                    }
-                }
+    /// Latest catalog push to `peer_id`, running or finished.
+    pub fn catalog_sync_progress(&self, peer_id: &str) -> Option<CatalogSyncProgress> {
+        self.catalog_sync.get(peer_id)
+    }
 
-                if !new_peers.is_empty() {
-                    let node = Arc::clone(self);
-                    tokio::spawn(async move {
-                        // Limit concurrent verification to 5
-                        let semaphore = Arc::new(tokio::sync::Semaphore::new(5));
-                        let mut handles = Vec::new();
+    /// Internal: tracks our full catalog push announces — those with a
+    /// content hash that we host, plus replicated tracks we reseed.
+    fn catalog_tracks(&self) -> sea_orm::Select<track::Entity> {
+        let served = if self._config.reseed_replicated {
+            Condition::any()
+                .add(track::Column::FilePath.not_like("p2p://%"))
+                .add(
+                    track::Column::ContentHash.in_subquery(
+                        Query::select()
+                            .column(published_hash::Column::Hash)
+                            .from(published_hash::Entity)
+                            .and_where(
+                                published_hash::Column::Kind.eq(PublishedKind::Track.as_str()),
+                            )
+                            .to_owned(),
+                    ),
+                )
+        } else {
+            Condition::all().add(track::Column::FilePath.not_like("p2p://%"))
+        };
+        track::Entity::find()
+            .filter(track::Column::ContentHash.is_not_null())
+            .filter(served)
+    }
 
-                        for pid in new_peers {
-                            let node = Arc::clone(&node);
-                            let sem = Arc::clone(&semaphore);
-                            handles.push(tokio::spawn(async move {
-                                let _permit = sem.acquire().await.ok()?;
-                                // Try to ping the peer with a 5s timeout
-                                if let Ok(nid) = pid.parse::<EndpointId>() {
-                                    let ping = P2pMessage::Ping;
-                                    match tokio::time::timeout(
-                                        std::time::Duration::from_secs(5),
-                                        node.send_message_to_peer(nid, &ping),
-                                    )
-                                    .await
-                                    {
-                                        Ok(Ok(_)) => {
-                                            node.registry.upsert_peer(&pid, None, 0).await;
-                                            info!(peer = %pid, "PEX peer verified and added");
-                                            // Sync catalogs with new peer
-                                            node.announce_all_tracks_to_peer(nid).await;
-                                            Some(pid)
-                                        }
-                                        _ => {
-                                            debug!(peer = %pid, "PEX peer unreachable, not adding");
-                                            None
-                                        }
-                                    }
-                                } else {
-                                    None
-                                }
-                            }));
-                        }
+    /// Internal: the node each of these replicated tracks was replicated
+    /// from and its signature of the track, keyed by local track ID.
+    async fn replicated_origins(
+        &self,
+        track_ids: Vec<Uuid>,
+    ) -> HashMap<Uuid, (String, Option<String>)> {
+        if track_ids.is_empty() {
+            return HashMap::new();
+        }
+        match remote_track::Entity::find()
+            .filter(remote_track::Column::LocalTrackId.is_in(track_ids))
+            .all(&self.db)
+            .await
+        {
+            Ok(rows) => rows
+                .into_iter()
+                .filter_map(|rt| {
+                    let origin = rt.instance_domain.strip_prefix("p2p://")?.to_string();
+                    Some((rt.local_track_id?, (origin, rt.signature)))
+                })
+                .collect(),
+            Err(e) => {
+                warn!("failed to look up origins of reseeded tracks: {e}");
+                HashMap::new()
+            }
+        }
+    }
 
-                        for h in handles {
-                            let _ = h.await;
-                        }
-                    });
-                }
+    /// Internal: body of `announce_all_tracks_to_peer`, reporting page
+    /// progress to the catalog sync tracker under `peer_key`.
+    /// With a `gate`, waits at it after each page and stops if the resync
+    /// holding it is cancelled; the checkpoint is kept so the next push
+    /// resumes from there.
+    async fn send_catalog_pages(
+        &self,
+        peer_id: EndpointId,
+        peer_key: &str,
+        gate: Option<&mut SyncGate>,
+    ) -> Result<(), String> {
+        self.send_catalog_pages_to(self, peer_id, peer_key, gate)
+            .await
+    }
+
+    /// Internal: `send_catalog_pages`, delivering acknowledged pages
+    /// through `sink`.
+    async fn send_catalog_pages_to<S: CatalogPageSink + ?Sized>(
+        &self,
+        sink: &S,
+        peer_id: EndpointId,
+        peer_key: &str,
+        mut gate: Option<&mut SyncGate>,
+    ) -> Result<(), String> {
+        let total = match self.catalog_tracks().count(&self.db).await {
+            Ok(c) => c,
+            Err(e) => {
+                warn!("failed to count tracks for catalog sync: {e}");
+                return Err(format!("failed to count tracks: {e}"));
+            }
+        };
+
+        if total == 0 {
+            debug!(peer = %peer_id, "no tracks to sync");
+            return Ok(());
+        }
 
-                // Reply with our peer list (including ourselves)
-                let mut our_peers: Vec<String> = self
+        // Resume after the last page an interrupted push delivered, with the
+        // page size it used. Otherwise size pages for the peer's latency.
+        let checkpoint = self.catalog_sync_checkpoints.get(peer_key).map(|c| *c);
+        let page_size = match checkpoint {
+            Some(c) if c.page_size > 0 => c.page_size,
+            _ => {
+                let rtt_ms = self
                     .registry
-                    .list_peers()
+                    .get_peer(peer_key)
                     .await
-                    .into_iter()
-                    .map(|p| p.node_id)
-                    .collect();
-                our_peers.push(our_id);
+                    .and_then(|p| p.rtt_ms);
+                compute_catalog_page_size(rtt_ms)
+            }
+        };
+        let num_pages = total.div_ceil(page_size);
+        let mut failed_pages = 0u64;
 
-                let reply = P2pMessage::PeerExchange { peers: our_peers };
-                let reply_bytes = serde_json::to_vec(&reply)?;
-                send.write_all(&(reply_bytes.len() as u32).to_be_bytes())
-                    .await
-                    .map_err(|e| P2pError::Connection(e.to_string()))?;
-                send.write_all(&reply_bytes)
-                    .await
-                    .map_err(|e| P2pError::Connection(e.to_string()))?;
-                send.finish()
-                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+        // If the catalog has shrunk past the checkpoint since, start over
+        let start_page = match checkpoint.and_then(|c| c.next_page(num_pages)) {
+            Some(next) => {
+                info!(peer = %peer_id, page = next, "resuming catalog sync from checkpoint");
+                next
             }
-            P2pMessage::CatalogDelta { since, tracks } => {
-                info!(count = tracks.len(), %since, %peer_id, "received catalog delta");
-                for ann in tracks {
-                    self.process_track_announcement(ann, peer_id).await;
+            _ => 0,
+        };
+        self.catalog_sync
+            .set_total_pages(peer_key, num_pages - start_page);
+        info!(
+            peer = %peer_id,
+            total,
+            page_size,
+            pages = num_pages,
+            start_page,
+            "starting paginated catalog sync"
+        );
+
+        let our_node = self.node_id().to_string();
+
+        // v2 peers acknowledge each page; v1 peers get fire-and-forget CatalogSync
+        let acknowledged = sink.acknowledges(peer_key).await;
+        let sync_id = self
+            .catalog_sync
+            .get(peer_key)
+            .map(|p| p.task_id)
+            .unwrap_or_else(Uuid::new_v4);
+        let mut record = CatalogSyncRecord::new(sync_id, peer_key, acknowledged);
+
+        // Cache cover hashes by album_id to avoid re-reading + re-publishing the same cover
+        let mut cover_cache: HashMap<Uuid, Option<String>> = HashMap::new();
+        let mut artist_image_cache: HashMap<Uuid, Option<String>> = HashMap::new();
+
+        for page_num in start_page..num_pages {
+            // Stable order so a resumed push sees the same pages; new tracks
+            // land on the last page
+            let tracks = match self
+                .catalog_tracks()
+                .order_by_asc(track::Column::CreatedAt)
+                .order_by_asc(track::Column::Id)
+                .paginate(&self.db, page_size)
+                .fetch_page(page_num)
+                .await
+            {
+                Ok(t) => t,
+                Err(e) => {
+                    warn!(
+                        page = page_num,
+                        "failed to read tracks page for catalog sync: {e}"
+                    );
+                    self.catalog_sync.page_failed(peer_key);
+                    failed_pages += 1;
+                    continue;
                 }
-                if let Err(e) = send.finish() {
-                    tracing::warn!(error = %e, "failed to finish send stream");
+            };
+
+            // ── Batch-fetch artists and albums for this page ──
+            let artist_ids: Vec<Uuid> = tracks
+                .iter()
+                .map(|t| t.artist_id)
+                .collect::<std::collections::HashSet<_>>()
+                .into_iter()
+                .collect();
+            let album_ids: Vec<Uuid> = tracks
+                .iter()
+                .filter_map(|t| t.album_id)
+                .collect::<std::collections::HashSet<_>>()
+                .into_iter()
+                .collect();
+
+            let artist_map: HashMap<Uuid, artist::Model> = if !artist_ids.is_empty() {
+                artist::Entity::find()
+                    .filter(artist::Column::Id.is_in(artist_ids))
+                    .all(&self.db)
+                    .await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|a| (a.id, a))
+                    .collect()
+            } else {
+                HashMap::new()
+            };
+
+            let album_map: HashMap<Uuid, (String, Option<String>)> = if !album_ids.is_empty() {
+                album::Entity::find()
+                    .filter(album::Column::Id.is_in(album_ids))
+                    .all(&self.db)
+                    .await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|a| (a.id, (a.title, a.cover_url)))
+                    .collect()
+            } else {
+                HashMap::new()
+            };
+
+            // Reseeded tracks are announced under the node they came from
+            let replicated_ids: Vec<Uuid> = tracks
+                .iter()
+                .filter(|t| t.file_path.starts_with("p2p://"))
+                .map(|t| t.id)
+                .collect();
+            let origins = self.replicated_origins(replicated_ids).await;
+
+            let mut announcements = Vec::with_capacity(tracks.len());
+
+            for t in &tracks {
+                let (origin_node, origin_signature) = if t.file_path.starts_with("p2p://") {
+                    match origins.get(&t.id) {
+                        Some(origin) => origin.clone(),
+                        None => continue,
+                    }
+                } else {
+                    (our_node.clone(), None)
+                };
+
+                let hash = match &t.content_hash {
+                    Some(h) => h.clone(),
+                    None => continue,
+                };
+
+                let track_artist = artist_map.get(&t.artist_id);
+                let artist_name = track_artist
+                    .map(|a| a.name.clone())
+                    .unwrap_or_else(|| "Unknown".to_string());
+                let artist_bio = track_artist.and_then(|a| a.bio.clone());
+                let artist_image_hash = match track_artist {
+                    Some(a) => match artist_image_cache.get(&a.id) {
+                        Some(cached) => cached.clone(),
+                        None => {
+                            let result = match a.image_url {
+                                Some(ref url) => self.publish_media_url(url).await,
+                                None => None,
+                            };
+                            artist_image_cache.insert(a.id, result.clone());
+                            result
+                        }
+                    },
+                    None => None,
+                };
+
+                let (album_title, cover_hash) = match t.album_id {
+                    Some(aid) => {
+                        match album_map.get(&aid) {
+                            Some((title, cover_url)) => {
+                                let title = Some(title.clone());
+                                // Use cover cache to avoid re-reading/re-publishing same cover
+                                let ch = if let Some(cached) = cover_cache.get(&aid) {
+                                    cached.clone()
+                                } else {
+                                    let result = match cover_url {
+                                        Some(url) => self.publish_media_url(url).await,
+                                        None => None,
+                                    };
+                                    cover_cache.insert(aid, result.clone());
+                                    result
+                                };
+                                (title, ch)
+                            }
+                            None => (None, None),
+                        }
+                    }
+                    None => (None, None),
+                };
+
+                let waveform_data = announced_waveform(t.waveform_data.as_ref(), &hash);
+                let mut ann = TrackAnnouncement {
+                    hash,
+                    title: t.title.clone(),
+                    artist_name,
+                    album_artist_name: None,
+                    album_title,
+                    duration_secs: t.duration_secs,
+                    format: t.format.clone(),
+                    file_size: t.file_size,
+                    genre: t.genre.clone(),
+                    year: t.year,
+                    track_number: t.track_number,
+                    disc_number: t.disc_number,
+                    bitrate: t.bitrate,
+                    sample_rate: t.sample_rate,
+                    origin_node,
+                    cover_hash,
+                    fingerprint: t.fingerprint.clone(),
+                    waveform_data,
+                    artist_image_hash,
+                    artist_bio,
+                    loudness_lufs: t.loudness_lufs,
+                    dynamic_range: t.dynamic_range,
+                    encoding_quality: t.encoding_quality.clone(),
+                    signature: None,
+                };
+                if ann.origin_node == our_node {
+                    self.sign_announcement(&mut ann);
+                } else {
+                    // Reseeded tracks carry the origin's signature, unless a
+                    // local edit since means it no longer matches
+                    ann.signature = origin_signature;
+                    if ann.signature.is_some() && ann.verify_signature().is_err() {
+                        debug!(hash = %ann.hash, "origin signature is stale, reseeding unsigned");
+                        ann.signature = None;
+                    }
                 }
+                announcements.push(ann);
             }
-            P2pMessage::RequestCatalog => {
-                info!(%peer_id, "received catalog request — sending full catalog");
-                if let Err(e) = send.finish() {
-                    tracing::warn!(error = %e, "failed to finish send stream");
-                }
-                // Spawn so we don't block this connection handler
-                if let Ok(remote_nid) = peer_id.parse::<EndpointId>() {
-                    let node = Arc::clone(self);
-                    tokio::spawn(async move {
-                        node.announce_all_tracks_to_peer(remote_nid).await;
-                    });
+
+            if !announcements.is_empty() {
+                info!(
+                    peer = %peer_id,
+                    count = announcements.len(),
+                    page = page_num,
+                    "syncing catalog page to peer"
+                );
+
+                let delivered = if acknowledged {
+                    let header = CatalogPageHeader {
+                        sync_id,
+                        page: page_num,
+                        total_pages: num_pages,
+                    };
+                    let delivery = deliver_page(sink, peer_key, &header, &announcements).await;
+                    record.add_delivery(&delivery);
+                    delivery.succeeded()
+                } else {
+                    let msg = P2pMessage::CatalogSync(announcements);
+                    let result = self.send_message_to_peer(peer_id, &msg).await;
+                    if let Err(ref e) = result {
+                        warn!(peer = %peer_id, page = page_num, "failed to sync catalog page: {e}");
+                    }
+                    record.add_unacknowledged(result.is_ok());
+                    result.is_ok()
+                };
+                if !delivered {
+                    self.catalog_sync.page_failed(peer_key);
+                    failed_pages += 1;
+                    continue;
                 }
             }
-            P2pMessage::BloomExchange { bloom } => {
-                info!(%peer_id, items = bloom.item_count, "received bloom filter from peer");
-                self.search_index.import_peer_bloom(peer_id, bloom).await;
-                if let Err(e) = send.finish() {
-                    tracing::warn!(error = %e, "failed to finish send stream");
-                }
+            self.catalog_sync.page_sent(peer_key);
+            P2P_METRICS.catalog_sync_pages_sent_total.inc();
+
+            // Only advance the checkpoint while every page so far went through
+            if failed_pages == 0 {
+                self.catalog_sync_checkpoints.insert(
+                    peer_key.to_string(),
+                    SyncCheckpoint {
+                        page: page_num,
+                        page_size,
+                    },
+                );
+                self.save_sync_checkpoints().await;
             }
-            P2pMessage::SearchQuery {
-                request_id,
-                query,
-                limit,
-            } => {
-                info!(%peer_id, %query, "received search query");
-                if let Err(e) = self
-                    .handle_search_query(&request_id, &query, limit, send)
-                    .await
-                {
-                    warn!(%peer_id, "failed to handle search query: {e}");
+
+            if let Some(gate) = gate.as_deref_mut() {
+                if gate.page_done(peer_key, page_num).await.is_err() {
+                    info!(peer = %peer_id, page = page_num, "catalog sync cancelled");
+                    self.catalog_sync_history.record(record);
+                    return Err("cancelled".to_string());
                 }
             }
-            P2pMessage::TrackData { .. }
-            | P2pMessage::Pong { .. }
-            | P2pMessage::SearchResults { .. } => {
-                // These are responses, not requests — ignore if received as requests
-                debug!("received unexpected response message");
+        }
+
+        info!(
+            peer = %peer_id,
+            %sync_id,
+            pages = record.pages_sent,
+            pages_failed = record.pages_failed,
+            pages_retried = record.pages_retried,
+            inserted = record.inserted,
+            skipped = record.skipped,
+            failed = record.failed,
+            "catalog sync finished"
+        );
+        self.catalog_sync_history.record(record);
+
+        if failed_pages > 0 {
+            return Err(format!(
+                "{failed_pages} of {num_pages} catalog pages failed"
+            ));
+        }
+        if self.catalog_sync_checkpoints.remove(peer_key).is_some() {
+            self.save_sync_checkpoints().await;
+        }
+        Ok(())
+    }
+
+    /// Internal: write the catalog sync checkpoints to disk.
+    async fn save_sync_checkpoints(&self) {
+        let file = Arc::clone(&self.checkpoint_file);
+        let checkpoints = Arc::clone(&self.catalog_sync_checkpoints);
+        match tokio::task::spawn_blocking(move || file.save(&checkpoints)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("failed to save catalog sync checkpoints: {e}"),
+            Err(e) => warn!("catalog sync checkpoint save task failed: {e}"),
+        }
+    }
+
+    /// Recent finished catalog pushes to `peer_id`, newest first.
+    pub fn catalog_sync_history(&self, peer_id: &str) -> Vec<CatalogSyncRecord> {
+        self.catalog_sync_history.for_peer(peer_id)
+    }
+
+    /// Highest catalog page delivered to `peer_id` by a push that has not
+    /// completed yet.
+    pub fn catalog_sync_checkpoint(&self, peer_id: &str) -> Option<u64> {
+        self.catalog_sync_checkpoints.get(peer_id).map(|c| c.page)
+    }
+
+    /// Send a `RequestCatalog` message to a peer, asking them to send us their
+    /// full catalog via paginated `CatalogSync` messages.
+    pub async fn request_catalog_from_peer(&self, peer_id: EndpointId) -> Result<(), P2pError> {
+        info!(peer = %peer_id, "requesting full catalog from peer");
+        self.send_message_to_peer(peer_id, &P2pMessage::RequestCatalog)
+            .await
+    }
+
+    /// Discover peers by exchanging peer lists with a known peer (Peer Exchange / PEX).
+    /// Sends our known peers, receives theirs, and queues any new ones. At most
+    /// `pex_batch_size` queued peers are pinged now; the rest wait for the
+    /// periodic PEX tick so a large peer list does not cause a ping storm.
+    /// Quarantined peers are skipped.
+    pub async fn discover_via_peer(&self, peer_node_id: EndpointId) {
+        if self
+            .registry
+            .is_quarantined(&peer_node_id.to_string())
+            .await
+        {
+            debug!(peer = %peer_node_id, "peer quarantined, skipping peer exchange");
+            return;
+        }
+        info!(peer = %peer_node_id, "initiating peer exchange");
+
+        let peer_addr = EndpointAddr::new(peer_node_id);
+        match self.exchange_peers(peer_addr).await {
+            Ok(remote_peers) => {
+                let our_id = self.node_id().to_string();
+                let mut candidates = Vec::new();
+                for peer_id_str in remote_peers {
+                    if peer_id_str == our_id {
+                        continue;
+                    }
+                    if self.registry.get_peer(&peer_id_str).await.is_some() {
+                        continue;
+                    }
+                    candidates.push(peer_id_str);
+                }
+
+                let queued = enqueue_pex_candidates(&mut *self.pex_queue.lock().await, candidates);
+                let new_count = self.process_pex_queue().await;
+
+                info!(peer = %peer_node_id, %queued, %new_count, "peer exchange complete");
+            }
+            Err(e) => {
+                warn!(peer = %peer_node_id, "peer exchange failed: {e}");
+            }
+        }
+    }
+
+    /// Ping up to `pex_batch_size` peers from the PEX queue and register the
+    /// ones that answer. Returns the number of peers added.
+    pub async fn process_pex_queue(&self) -> u32 {
+        let batch: Vec<String> = {
+            let mut queue = self.pex_queue.lock().await;
+            let n = self.pex_batch_size.min(queue.len());
+            queue.drain(..n).collect()
+        };
+        if batch.is_empty() {
+            return 0;
+        }
+
+        let mut new_count = 0u32;
+        for peer_id_str in batch {
+            // May have connected on its own while waiting in the queue
+            if self.registry.get_peer(&peer_id_str).await.is_some() {
+                continue;
+            }
+
+            let nid: EndpointId = match peer_id_str.parse() {
+                Ok(id) => id,
+                Err(_) => continue,
+            };
+
+            let addr = EndpointAddr::new(nid);
+            match self.ping_peer(addr).await {
+                Ok(P2pMessage::Pong {
+                    node_id,
+                    track_count,
+                    version,
+                    capabilities,
+                }) => {
+                    self.registry
+                        .upsert_peer_versioned(&node_id, None, track_count, version)
+                        .await;
+                    self.registry.set_capabilities(&node_id, capabilities).await;
+                    info!(peer = %node_id, %track_count, "discovered new peer via PEX");
+                    new_count += 1;
+                }
+                Ok(_) => {
+                    self.registry.upsert_peer(&peer_id_str, None, 0).await;
+                    new_count += 1;
+                }
+                Err(e) => {
+                    debug!(peer = %peer_id_str, "PEX peer unreachable: {e}");
+                }
+            }
+        }
+
+        let remaining = self.pex_queue.lock().await.len();
+        if remaining > 0 {
+            debug!(%remaining, "PEX peers deferred to next cycle");
+        }
+        new_count
+    }
+
+    /// Send our peer list to a remote peer and receive theirs.
+    async fn exchange_peers(&self, peer_addr: EndpointAddr) -> Result<Vec<String>, P2pError> {
+        let conn = self.conn_pool.get_connection(peer_addr.id).await?;
+
+        let (mut send, mut recv) = match conn.open_bi().await {
+            Ok(streams) => streams,
+            Err(e) => {
+                self.conn_pool.invalidate(&peer_addr.id).await;
+                return Err(P2pError::Connection(e.to_string()));
+            }
+        };
+
+        // Build our peer list (include ourselves so remote knows us)
+        let known = self.registry.pex_shareable_peers().await;
+        let our_peers = sample_pex_peers(known, self.node_id().to_string());
+
+        let msg = P2pMessage::PeerExchange { peers: our_peers };
+        let msg_bytes = serde_json::to_vec(&msg)?;
+        send.write_all(&(msg_bytes.len() as u32).to_be_bytes())
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.write_all(&msg_bytes)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.finish()
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+
+        // Read response
+        let mut len_buf = [0u8; 4];
+        recv.read_exact(&mut len_buf)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        let msg_len = u32::from_be_bytes(len_buf) as usize;
+
+        let response = recv
+            .read_to_end(msg_len)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+
+        let response_msg: P2pMessage = serde_json::from_slice(&response)?;
+
+        match response_msg {
+            P2pMessage::PeerExchange { peers } => Ok(peers),
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Gracefully shutdown the P2P node.
+    ///
+    /// Online peers are sent a `Goodbye` first (for at most
+    /// [`GOODBYE_TIMEOUT`]) so they stop routing fetches and searches to us.
+    pub async fn shutdown(&self) {
+        info!("shutting down P2P node");
+        self.say_goodbye("shutdown").await;
+        let _ = self.shutdown_tx.send(true);
+        if let Err(e) = self
+            .search_index
+            .save_to_disk(&self._config.bloom_persist_path)
+            .await
+        {
+            warn!("failed to persist bloom filter: {e}");
+        }
+        self.endpoint.close().await;
+        let _ = self.blob_store.shutdown().await;
+        info!("P2P node shutdown complete");
+    }
+
+    /// Send `Goodbye` to every online peer concurrently, giving up after
+    /// [`GOODBYE_TIMEOUT`]. Peers on protocol v1 are skipped.
+    async fn say_goodbye(&self, reason: &str) {
+        let msg = P2pMessage::Goodbye {
+            node_id: self.node_id().to_string(),
+            reason: reason.to_string(),
+        };
+        let bytes = match serde_json::to_vec(&msg) {
+            Ok(b) => b,
+            Err(e) => {
+                warn!("failed to serialize goodbye: {e}");
+                return;
+            }
+        };
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for peer in self.registry.online_peers().await {
+            let Ok(nid) = peer.node_id.parse::<EndpointId>() else {
+                continue;
+            };
+            let pool = Arc::clone(&self.conn_pool);
+            let bytes = bytes.clone();
+            let min_version = msg.min_protocol_version();
+            tasks.spawn(async move { pool.send_now(nid, min_version, &bytes).await.is_ok() });
+        }
+        if tasks.is_empty() {
+            return;
+        }
+
+        let peers = tasks.len();
+        let delivered = tokio::time::timeout(GOODBYE_TIMEOUT, async {
+            let mut sent = 0;
+            while let Some(result) = tasks.join_next().await {
+                if matches!(result, Ok(true)) {
+                    self.stats.record_sent(msg.kind());
+                    sent += 1;
+                }
+            }
+            sent
+        })
+        .await;
+        match delivered {
+            Ok(sent) => info!(sent, peers, "sent goodbye to peers"),
+            Err(_) => warn!(
+                peers,
+                "goodbye broadcast timed out after {GOODBYE_TIMEOUT:?}"
+            ),
+        }
+    }
+
+    /// Rebuild the local Bloom filter search index from all tracks in the database.
+    async fn rebuild_search_index(&self) {
+        match self.search_index.rebuild_from_db(&self.db).await {
+            Ok(()) => info!("search index rebuilt from database"),
+            Err(e) => warn!("failed to rebuild search index from database: {e}"),
+        }
+        self.search_cache.invalidate();
+    }
+
+    /// Broadcast our Bloom filter to all online peers for search routing.
+    ///
+    /// Peers that understand `BloomDelta` only get the bits set since the
+    /// last full broadcast, and nothing if there are none. The full filter
+    /// goes out (starting a new generation) on the first broadcast, after a
+    /// rebuild, or when the delta would be larger than the filter.
+    pub async fn broadcast_bloom_filter(&self) {
+        let delta = self.search_index.export_delta().await;
+        let full = match delta {
+            Some(_) => self.search_index.export_local_bloom().await,
+            None => self.search_index.begin_generation().await,
+        };
+        let full_msg = P2pMessage::BloomExchange { bloom: full };
+        let delta_msg = delta
+            .as_ref()
+            .filter(|d| !d.set_bits.is_empty())
+            .map(|d| P2pMessage::BloomDelta { delta: d.clone() });
+        let peers = self.registry.online_peers().await;
+
+        let mut sent = 0;
+        for peer in &peers {
+            let node_id: EndpointId = match peer.node_id.parse() {
+                Ok(id) => id,
+                Err(_) => continue,
+            };
+            let supports_delta = peer
+                .protocol_version
+                .is_some_and(|v| v >= ProtocolVersion::V2.as_u8());
+            let msg = if delta.is_some() && supports_delta {
+                match &delta_msg {
+                    Some(msg) => msg,
+                    // Nothing new since the last exchange
+                    None => continue,
+                }
+            } else {
+                &full_msg
+            };
+            match self.send_message_to_peer(node_id, msg).await {
+                Ok(()) => sent += 1,
+                Err(e) => debug!(peer = %peer.node_id, "failed to send bloom filter: {e}"),
+            }
+        }
+
+        if sent > 0 {
+            info!(
+                peers = sent,
+                full = delta.is_none(),
+                "broadcast bloom filter to peers"
+            );
+        }
+    }
+
+    /// Perform a distributed search across the P2P network.
+    /// Uses Bloom filters to route the query only to peers likely to have results.
+    /// Queries up to 10 matching peers, lowest median RTT first, concurrently with a 10-second timeout per peer.
+    /// Returns search results from all matching peers, merged and sorted by
+    /// relevance, with frequently played tracks ranked higher. Results are
+    /// cached for `P2P_SEARCH_CACHE_TTL_SECS`, so repeating a query does not
+    /// ask peers again.
+    pub async fn distributed_search(
+        self: &Arc<Self>,
+        query: &str,
+        limit: u32,
+    ) -> Vec<SearchResultItem> {
+        if let Some(results) = self.search_cache.get(query, limit as usize) {
+            debug!(query = query, results = results.len(), "search cache hit");
+            return results;
+        }
+
+        let mut session = SearchSession::new(query, self.search_candidates(query).await);
+        let searcher: Arc<dyn PeerSearcher> = Arc::clone(self) as Arc<dyn PeerSearcher>;
+        session.fill(&searcher, limit as usize).await;
+        let (results, _) = session.page(0, limit as usize);
+        self.search_cache
+            .insert(query, limit as usize, results.clone());
+
+        info!(
+            query = query,
+            total_results = results.len(),
+            "distributed search complete"
+        );
+
+        results
+    }
+
+    /// One page of a distributed search. Without a cursor a new search is
+    /// started; with one, the search it came from continues. `offset`
+    /// overrides the cursor's position, e.g. to jump to a page. Results are
+    /// kept for [`SEARCH_SESSION_TTL`](crate::search_session::SEARCH_SESSION_TTL),
+    /// so paging does not query every peer again.
+    pub async fn search_page(
+        self: &Arc<Self>,
+        query: &str,
+        cursor: Option<&str>,
+        offset: Option<usize>,
+        limit: u32,
+    ) -> Result<SearchPage, P2pError> {
+        let (session_id, session, cursor_offset) = match cursor {
+            Some(cursor) => {
+                let (id, cursor_offset) = decode_cursor(cursor)
+                    .ok_or_else(|| P2pError::InvalidCursor("malformed cursor".into()))?;
+                let session = self
+                    .search_sessions
+                    .get(&id)
+                    .ok_or_else(|| P2pError::InvalidCursor("search expired".into()))?;
+                if session.lock().await.query() != query {
+                    return Err(P2pError::InvalidCursor(
+                        "cursor belongs to another query".into(),
+                    ));
+                }
+                (id, session, cursor_offset)
+            }
+            None => {
+                let session = SearchSession::new(query, self.search_candidates(query).await);
+                let (id, session) = self.search_sessions.insert(session);
+                (id, session, 0)
+            }
+        };
+
+        let offset = offset.unwrap_or(cursor_offset);
+        let limit = limit as usize;
+        let searcher: Arc<dyn PeerSearcher> = Arc::clone(self) as Arc<dyn PeerSearcher>;
+        let mut session = session.lock().await;
+        session.fill(&searcher, offset.saturating_add(limit)).await;
+        let (results, has_more) = session.page(offset, limit);
+        let next_cursor = has_more.then(|| encode_cursor(&session_id, offset + results.len()));
+
+        Ok(SearchPage {
+            results,
+            has_more,
+            next_cursor,
+            peer_totals: session.peer_totals(),
+        })
+    }
+
+    /// Peers worth sending `query` to: those whose Bloom filter matches or
+    /// that returned results for the same terms before, lowest median RTT
+    /// first, at most 10, skipping departed peers.
+    async fn search_candidates(&self, query: &str) -> Vec<String> {
+        let mut matching_peers = self.search_index.peers_matching_query(query).await;
+        for peer in self.search_index.peers_from_query_history(query).await {
+            if !matching_peers.contains(&peer) {
+                matching_peers.push(peer);
+            }
+        }
+        if matching_peers.is_empty() {
+            debug!(
+                query = query,
+                "no peers match bloom filter or query history for query"
+            );
+            return vec![];
+        }
+        // Ask the lowest-latency peers first
+        order_by_latency(&mut matching_peers, &self.registry.p50_rtts().await);
+
+        let mut candidates = Vec::new();
+        for peer_id_str in matching_peers.into_iter().take(10) {
+            if self.registry.has_departed(&peer_id_str).await {
+                debug!(peer = %peer_id_str, "skipping departed peer in search");
+                continue;
+            }
+            candidates.push(peer_id_str);
+        }
+        candidates
+    }
+
+    /// Send a search query to a specific peer and wait for results.
+    /// Returns the results and the peer's total number of matches.
+    async fn search_peer(
+        &self,
+        peer_addr: EndpointAddr,
+        msg: &P2pMessage,
+    ) -> Result<(Vec<SearchResultItem>, u64), P2pError> {
+        let conn = self.conn_pool.get_connection(peer_addr.id).await?;
+
+        let (mut send, mut recv) = match conn.open_bi().await {
+            Ok(streams) => streams,
+            Err(e) => {
+                self.conn_pool.invalidate(&peer_addr.id).await;
+                return Err(P2pError::Connection(e.to_string()));
+            }
+        };
+
+        let msg_bytes = serde_json::to_vec(msg)?;
+        send.write_all(&(msg_bytes.len() as u32).to_be_bytes())
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.write_all(&msg_bytes)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.finish()
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+
+        // Read response
+        let mut len_buf = [0u8; 4];
+        recv.read_exact(&mut len_buf)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        let msg_len = u32::from_be_bytes(len_buf) as usize;
+
+        let response = recv
+            .read_to_end(msg_len)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+
+        let response_msg: P2pMessage = serde_json::from_slice(&response)?;
+
+        match response_msg {
+            P2pMessage::SearchResults { results, total, .. } => Ok((results, total)),
+            _ => Ok((vec![], 0)),
+        }
+    }
+
+    /// Handle an incoming SearchQuery: run a local FTS query and return
+    /// results `offset..offset + limit`, with the total number of matches.
+    async fn handle_search_query(
+        &self,
+        request_id: &str,
+        query: &str,
+        limit: u32,
+        offset: u32,
+        mut send: iroh::endpoint::SendStream,
+    ) -> Result<(), P2pError> {
+        use sea_orm::{FromQueryResult, Statement};
+
+        // Build tsquery from the search string
+        let tsquery = query
+            .split_whitespace()
+            .filter(|w| !w.is_empty())
+            .map(|w| {
+                let clean: String = w
+                    .chars()
+                    .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+                    .collect();
+                if clean.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}:*", clean)
+                }
+            })
+            .filter(|w| !w.is_empty())
+            .collect::<Vec<_>>()
+            .join(" & ");
+
+        if tsquery.is_empty() {
+            let resp = P2pMessage::SearchResults {
+                request_id: request_id.to_string(),
+                results: vec![],
+                total: 0,
+            };
+            let resp_bytes = serde_json::to_vec(&resp)?;
+            send.write_all(&(resp_bytes.len() as u32).to_be_bytes())
+                .await
+                .map_err(|e| P2pError::Connection(e.to_string()))?;
+            send.write_all(&resp_bytes)
+                .await
+                .map_err(|e| P2pError::Connection(e.to_string()))?;
+            send.finish()
+                .map_err(|e| P2pError::Connection(e.to_string()))?;
+            return Ok(());
+        }
+
+        #[derive(Debug, FromQueryResult)]
+        struct SearchRow {
+            hash: Option<String>,
+            title: String,
+            artist_name: String,
+            album_title: Option<String>,
+            duration_secs: f32,
+            format: String,
+            genre: Option<String>,
+            year: Option<i16>,
+            bitrate: Option<i32>,
+            musicbrainz_id: Option<String>,
+            play_count: i64,
+            loudness_lufs: Option<f32>,
+            dynamic_range: Option<f32>,
+            encoding_quality: Option<String>,
+            rank: f32,
+            total_matches: i64,
+        }
+
+        let our_node = self.node_id().to_string();
+
+        let mut rows: Vec<SearchRow> =
+            SearchRow::find_by_statement(Statement::from_sql_and_values(
+                sea_orm::DatabaseBackend::Postgres,
+                r#"
+            SELECT t.content_hash AS hash, t.title, a.name AS artist_name,
+                   al.title AS album_title, t.duration_secs, t.format,
+                   t.genre, t.year, t.bitrate, t.musicbrainz_id, t.play_count,
+                   t.loudness_lufs, t.dynamic_range, t.encoding_quality,
+                   ts_rank(
+                       setweight(to_tsvector('english', t.title), 'A') ||
+                       setweight(to_tsvector('english', a.name), 'B') ||
+                       setweight(to_tsvector('english', COALESCE(al.title, '')), 'C'),
+                       to_tsquery('english', $1)
+                   ) AS rank,
+                   COUNT(*) OVER () AS total_matches
+            FROM tracks t
+            JOIN artists a ON a.id = t.artist_id
+            LEFT JOIN albums al ON al.id = t.album_id
+            WHERE (
+                to_tsvector('english', t.title) ||
+                to_tsvector('english', a.name) ||
+                to_tsvector('english', COALESCE(al.title, ''))
+            ) @@ to_tsquery('english', $1)
+              AND t.content_hash IS NOT NULL
+            ORDER BY rank * (1 + $3::float8 * ln(1 + GREATEST(t.play_count, 0)::float8)) DESC,
+                     t.content_hash
+            LIMIT $2 OFFSET $4
+            "#,
+                vec![
+                    tsquery.clone().into(),
+                    (limit as i64).into(),
+                    (POPULARITY_WEIGHT as f64).into(),
+                    (offset as i64).into(),
+                ],
+            ))
+            .all(&self.db)
+            .await
+            .unwrap_or_default();
+
+        // Nothing matched word for word: fall back to trigram similarity so
+        // misspelled queries still find tracks. On later pages, only if the
+        // full-text query matches nothing at all, not just past the end.
+        let fuzzy_text = fuzzy_query_text(query);
+        if rows.is_empty()
+            && !fuzzy_text.is_empty()
+            && (offset == 0 || !self.fulltext_has_match(&tsquery).await)
+        {
+            use sea_orm::{ConnectionTrait, TransactionTrait};
+
+            let fuzzy = async {
+                let txn = self.db.begin().await?;
+                // `%` compares against this setting; SET LOCAL scopes it to
+                // the transaction
+                txn.execute(Statement::from_sql_and_values(
+                    sea_orm::DatabaseBackend::Postgres,
+                    "SELECT set_config('pg_trgm.similarity_threshold', $1, true)",
+                    [self._config.search_similarity_threshold.to_string().into()],
+                ))
+                .await?;
+                let rows = SearchRow::find_by_statement(Statement::from_sql_and_values(
+                    sea_orm::DatabaseBackend::Postgres,
+                    r#"
+                    SELECT t.content_hash AS hash, t.title, a.name AS artist_name,
+                           al.title AS album_title, t.duration_secs, t.format,
+                           t.genre, t.year, t.bitrate, t.musicbrainz_id, t.play_count,
+                           t.loudness_lufs, t.dynamic_range, t.encoding_quality,
+                           GREATEST(similarity(t.title, $1), similarity(a.name, $1)) AS rank,
+                           COUNT(*) OVER () AS total_matches
+                    FROM tracks t
+                    JOIN artists a ON a.id = t.artist_id
+                    LEFT JOIN albums al ON al.id = t.album_id
+                    WHERE (t.title % $1 OR a.name % $1)
+                      AND t.content_hash IS NOT NULL
+                    ORDER BY rank * (1 + $3::float8 * ln(1 + GREATEST(t.play_count, 0)::float8)) DESC,
+                             t.content_hash
+                    LIMIT $2 OFFSET $4
+                    "#,
+                    vec![
+                        fuzzy_text.clone().into(),
+                        (limit as i64).into(),
+                        (POPULARITY_WEIGHT as f64).into(),
+                        (offset as i64).into(),
+                    ],
+                ))
+                .all(&txn)
+                .await?;
+                txn.commit().await?;
+                Ok::<_, sea_orm::DbErr>(rows)
+            };
+            match fuzzy.await {
+                Ok(fuzzy_rows) => {
+                    debug!(query = %fuzzy_text, matches = fuzzy_rows.len(), "fuzzy search fallback");
+                    rows = fuzzy_rows;
+                }
+                Err(e) => warn!(error = %e, "fuzzy search fallback failed"),
+            }
+        }
+
+        let total = rows.first().map_or(0, |r| r.total_matches.max(0) as u64);
+        let results: Vec<SearchResultItem> = rows
+            .into_iter()
+            .filter(|r| r.hash.is_some())
+            .map(|r| SearchResultItem {
+                hash: r.hash.unwrap_or_default(),
+                title: r.title,
+                artist_name: r.artist_name,
+                album_title: r.album_title,
+                duration_secs: r.duration_secs,
+                format: r.format,
+                genre: r.genre,
+                year: r.year,
+                bitrate: r.bitrate,
+                source_node: our_node.clone(),
+                musicbrainz_id: r.musicbrainz_id,
+                relevance: r.rank,
+                play_count: r.play_count,
+                loudness_lufs: r.loudness_lufs,
+                dynamic_range: r.dynamic_range,
+                encoding_quality: r.encoding_quality,
+            })
+            .collect();
+
+        let resp = P2pMessage::SearchResults {
+            request_id: request_id.to_string(),
+            results,
+            total,
+        };
+
+        let resp_bytes = serde_json::to_vec(&resp)?;
+        send.write_all(&(resp_bytes.len() as u32).to_be_bytes())
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.write_all(&resp_bytes)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.finish()
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Whether any local track matches the full-text `tsquery`.
+    async fn fulltext_has_match(&self, tsquery: &str) -> bool {
+        use sea_orm::{ConnectionTrait, Statement};
+
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                sea_orm::DatabaseBackend::Postgres,
+                r#"
+                SELECT EXISTS (
+                    SELECT 1
+                    FROM tracks t
+                    JOIN artists a ON a.id = t.artist_id
+                    LEFT JOIN albums al ON al.id = t.album_id
+                    WHERE (
+                        to_tsvector('english', t.title) ||
+                        to_tsvector('english', a.name) ||
+                        to_tsvector('english', COALESCE(al.title, ''))
+                    ) @@ to_tsquery('english', $1)
+                      AND t.content_hash IS NOT NULL
+                ) AS found
+                "#,
+                [tsquery.into()],
+            ))
+            .await;
+        match row {
+            Ok(Some(row)) => row.try_get::<bool>("", "found").unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// Announce all locally-uploaded tracks to a specific peer using incremental sync
+    /// when possible (if we have a `last_seen` timestamp for this peer), otherwise
+    /// falls back to a full CatalogSync.
+    ///
+    /// Returns an error if any page could not be read or sent, so callers only
+    /// advance their sync timestamp once the peer has every page.
+    pub async fn incremental_sync_to_peer(
+        &self,
+        peer_id: EndpointId,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(), P2pError> {
+        let since_ts = since.unwrap_or(chrono::DateTime::UNIX_EPOCH);
+
+        let page_size = 500u64;
+        let total = match track::Entity::find()
+            .filter(track::Column::ContentHash.is_not_null())
+            .filter(track::Column::FilePath.not_like("p2p://%"))
+            .filter(track::Column::CreatedAt.gt(since_ts))
+            .count(&self.db)
+            .await
+        {
+            Ok(c) => c,
+            Err(e) => {
+                warn!("failed to count tracks for incremental sync: {e}");
+                return Err(P2pError::Database(e));
+            }
+        };
+
+        if total == 0 {
+            debug!(peer = %peer_id, "no new tracks since last sync");
+            return Ok(());
+        }
+
+        let num_pages = total.div_ceil(page_size);
+        let our_node = self.node_id().to_string();
+        let mut cover_cache: HashMap<Uuid, Option<String>> = HashMap::new();
+        let mut artist_image_cache: HashMap<Uuid, Option<String>> = HashMap::new();
+        let mut failed_pages = 0u64;
+
+        info!(
+            peer = %peer_id,
+            total,
+            since = %since_ts,
+            pages = num_pages,
+            "starting paginated incremental sync"
+        );
+
+        for page_num in 0..num_pages {
+            let tracks = match track::Entity::find()
+                .filter(track::Column::ContentHash.is_not_null())
+                .filter(track::Column::FilePath.not_like("p2p://%"))
+                .filter(track::Column::CreatedAt.gt(since_ts))
+                .paginate(&self.db, page_size)
+                .fetch_page(page_num)
+                .await
+            {
+                Ok(t) => t,
+                Err(e) => {
+                    warn!(
+                        page = page_num,
+                        "failed to read tracks page for incremental sync: {e}"
+                    );
+                    failed_pages += 1;
+                    continue;
+                }
+            };
+
+            // ── Batch-fetch artists and albums for this page ──
+            let artist_ids: Vec<Uuid> = tracks
+                .iter()
+                .map(|t| t.artist_id)
+                .collect::<std::collections::HashSet<_>>()
+                .into_iter()
+                .collect();
+            let album_ids: Vec<Uuid> = tracks
+                .iter()
+                .filter_map(|t| t.album_id)
+                .collect::<std::collections::HashSet<_>>()
+                .into_iter()
+                .collect();
+
+            let artist_map: HashMap<Uuid, artist::Model> = if !artist_ids.is_empty() {
+                artist::Entity::find()
+                    .filter(artist::Column::Id.is_in(artist_ids))
+                    .all(&self.db)
+                    .await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|a| (a.id, a))
+                    .collect()
+            } else {
+                HashMap::new()
+            };
+
+            let album_map: HashMap<Uuid, (String, Option<String>)> = if !album_ids.is_empty() {
+                album::Entity::find()
+                    .filter(album::Column::Id.is_in(album_ids))
+                    .all(&self.db)
+                    .await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|a| (a.id, (a.title, a.cover_url)))
+                    .collect()
+            } else {
+                HashMap::new()
+            };
+
+            let mut announcements = Vec::with_capacity(tracks.len());
+
+            for t in &tracks {
+                if t.file_path.starts_with("p2p://") {
+                    continue;
+                }
+                let hash = match &t.content_hash {
+                    Some(h) => h.clone(),
+                    None => continue,
+                };
+
+                let track_artist = artist_map.get(&t.artist_id);
+                let artist_name = track_artist
+                    .map(|a| a.name.clone())
+                    .unwrap_or_else(|| "Unknown".to_string());
+                let artist_bio = track_artist.and_then(|a| a.bio.clone());
+                let artist_image_hash = match track_artist {
+                    Some(a) => match artist_image_cache.get(&a.id) {
+                        Some(cached) => cached.clone(),
+                        None => {
+                            let result = match a.image_url {
+                                Some(ref url) => self.publish_media_url(url).await,
+                                None => None,
+                            };
+                            artist_image_cache.insert(a.id, result.clone());
+                            result
+                        }
+                    },
+                    None => None,
+                };
+
+                let (album_title, cover_hash) = match t.album_id {
+                    Some(aid) => match album_map.get(&aid) {
+                        Some((title, cover_url)) => {
+                            let title = Some(title.clone());
+                            let ch = if let Some(cached) = cover_cache.get(&aid) {
+                                cached.clone()
+                            } else {
+                                let result = match cover_url {
+                                    Some(url) => self.publish_media_url(url).await,
+                                    None => None,
+                                };
+                                cover_cache.insert(aid, result.clone());
+                                result
+                            };
+                            (title, ch)
+                        }
+                        None => (None, None),
+                    },
+                    None => (None, None),
+                };
+
+                let waveform_data = announced_waveform(t.waveform_data.as_ref(), &hash);
+                let mut ann = TrackAnnouncement {
+                    hash,
+                    title: t.title.clone(),
+                    artist_name,
+                    album_artist_name: None,
+                    album_title,
+                    duration_secs: t.duration_secs,
+                    format: t.format.clone(),
+                    file_size: t.file_size,
+                    genre: t.genre.clone(),
+                    year: t.year,
+                    track_number: t.track_number,
+                    disc_number: t.disc_number,
+                    bitrate: t.bitrate,
+                    sample_rate: t.sample_rate,
+                    origin_node: our_node.clone(),
+                    cover_hash,
+                    fingerprint: t.fingerprint.clone(),
+                    waveform_data,
+                    artist_image_hash,
+                    artist_bio,
+                    loudness_lufs: t.loudness_lufs,
+                    dynamic_range: t.dynamic_range,
+                    encoding_quality: t.encoding_quality.clone(),
+                    signature: None,
+                };
+                self.sign_announcement(&mut ann);
+                announcements.push(ann);
+            }
+
+            if !announcements.is_empty() {
+                info!(
+                    peer = %peer_id,
+                    count = announcements.len(),
+                    page = page_num,
+                    since = %since_ts,
+                    "incremental catalog sync page"
+                );
+
+                let msg = P2pMessage::CatalogDelta {
+                    since: since_ts,
+                    tracks: announcements,
+                };
+                if let Err(e) = self.send_message_to_peer(peer_id, &msg).await {
+                    warn!(peer = %peer_id, page = page_num, "failed to send catalog delta page: {e}");
+                    failed_pages += 1;
+                }
+            }
+        }
+
+        // Also send our bloom filter so the peer can route searches to us
+        let bloom_data = self.search_index.export_local_bloom().await;
+        let bloom_msg = P2pMessage::BloomExchange { bloom: bloom_data };
+        if let Err(e) = self.send_message_to_peer(peer_id, &bloom_msg).await {
+            debug!(peer = %peer_id, "failed to send bloom filter: {e}");
+        }
+
+        if failed_pages > 0 {
+            return Err(P2pError::Connection(format!(
+                "{failed_pages} of {num_pages} catalog delta pages failed"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Internal: accept incoming connections in a loop.
+    async fn accept_loop(self: &Arc<Self>) {
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        loop {
+            tokio::select! {
+                incoming = self.endpoint.accept() => {
+                    match incoming {
+                        Some(incoming) => {
+                            let node = Arc::clone(self);
+                            let remote_ip = incoming.remote_address().ip();
+                            let Some(ip_slot) = node.ip_limiter.try_acquire(remote_ip) else {
+                                warn!(
+                                    %remote_ip,
+                                    limit = node.ip_limiter.max_per_ip(),
+                                    "max concurrent P2P connections per IP reached (P2P_MAX_CONNECTIONS_PER_IP), dropping connection"
+                                );
+                                continue;
+                            };
+                            let permit = match node.conn_semaphore.clone().try_acquire_owned() {
+                                Ok(p) => p,
+                                Err(_) => {
+                                    warn!(
+                                        %remote_ip,
+                                        "max concurrent P2P connections reached (P2P_MAX_CONCURRENT_CONNECTIONS), dropping connection"
+                                    );
+                                    continue;
+                                }
+                            };
+                            tokio::spawn(async move {
+                                let _permit = permit; // held for the duration
+                                let _ip_slot = ip_slot;
+                                match incoming.await {
+                                    Ok(conn) => {
+                                        if let Err(e) = node.handle_connection(conn).await {
+                                            warn!("error handling connection: {e}");
+                                        }
+                                    }
+                                    Err(e) => {
+                                        warn!("error accepting connection: {e}");
+                                    }
+                                }
+                            });
+                        }
+                        None => {
+                            info!("endpoint accept stream ended");
+                            break;
+                        }
+                    }
+                }
+                _ = shutdown_rx.changed() => {
+                    info!("accept loop received shutdown signal");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Internal: handle a single incoming connection.
+    async fn handle_connection(self: &Arc<Self>, conn: Connection) -> Result<(), P2pError> {
+        let peer_id = conn.remote_id().to_string();
+
+        info!(%peer_id, "incoming connection");
+
+        // Check if peer is blocked
+        if is_peer_blocked(&self.db, &peer_id).await {
+            warn!(%peer_id, "rejected blocked peer");
+            conn.close(1u8.into(), b"blocked");
+            return Err(P2pError::PeerBlocked(peer_id));
+        }
+
+        // Register the peer in our registry (marks it online with last_seen = now)
+        self.registry.upsert_peer(&peer_id, None, 0).await;
+        let _active = self.stats.connection_opened();
+
+        // Record which protocol version the peer negotiated via ALPN
+        let version = ProtocolVersion::from_alpn(conn.alpn()).unwrap_or(ProtocolVersion::V1);
+        self.registry.set_protocol_version(&peer_id, version).await;
+        debug!(%peer_id, version = version.as_u8(), "negotiated P2P protocol version");
+        self.events.emit(P2pEvent::PeerConnected {
+            peer_id: peer_id.clone(),
+            protocol_version: version.as_u8(),
+        });
+
+        let result = self.serve_streams(&conn, &peer_id, version).await;
+        self.events.emit(P2pEvent::PeerDisconnected {
+            peer_id,
+            reason: "connection closed".to_string(),
+        });
+        result
+    }
+
+    /// Read and handle requests on `conn` until the peer closes it.
+    async fn serve_streams(
+        self: &Arc<Self>,
+        conn: &Connection,
+        peer_id: &str,
+        version: ProtocolVersion,
+    ) -> Result<(), P2pError> {
+        // Accept bidirectional streams from this connection
+        while let Ok((send, mut recv)) = conn.accept_bi().await {
+            let node_id = self.node_id();
+
+            // Read length-prefixed message
+            let mut len_buf = [0u8; 4];
+            if recv.read_exact(&mut len_buf).await.is_err() {
+                break;
+            }
+            let msg_len = u32::from_be_bytes(len_buf) as usize;
+
+            // SECURITY: Reject oversized messages to prevent OOM (FIX-17)
+            let max_message_bytes = self._config.max_message_bytes;
+            if msg_len > max_message_bytes {
+                warn!(%peer_id, msg_len, "rejecting oversized P2P message (max: {max_message_bytes}, P2P_MAX_MESSAGE_BYTES)");
+                break;
+            }
+
+            let msg_bytes = match recv.read_to_end(msg_len).await {
+                Ok(b) => b,
+                Err(_) => break,
+            };
+            P2P_METRICS
+                .bytes_received_total
+                .inc_by(msg_bytes.len() as u64);
+
+            // SECURITY: Message size is bounded by P2P_MAX_MESSAGE_BYTES (FIX-17).
+            // serde_json's default recursion limit (128) provides depth protection.
+            let msg: P2pMessage = match serde_json::from_slice(&msg_bytes) {
+                Ok(m) => m,
+                Err(e) => {
+                    warn!("invalid message from {peer_id}: {e}");
+                    break;
+                }
+            };
+
+            self.handle_message(msg, send, node_id, peer_id, version)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Apply a metadata edit from the instance a replicated track came from.
+    /// Updates from any other peer are ignored, as are hashes we don't have.
+    async fn apply_track_metadata_update(
+        &self,
+        hash: &str,
+        fields: TrackMetadataUpdate,
+        peer_id: &str,
+    ) -> Result<(), P2pError> {
+        let Some(remote) = remote_track::Entity::find()
+            .filter(remote_track::Column::RemoteUri.eq(format!("p2p://{peer_id}/{hash}")))
+            .one(&self.db)
+            .await?
+        else {
+            debug!(%hash, %peer_id, "metadata update for track not replicated from this peer");
+            return Ok(());
+        };
+
+        if let Some(local) = track::Entity::find()
+            .filter(track::Column::ContentHash.eq(Some(hash.to_string())))
+            .filter(track::Column::FilePath.like("p2p://%"))
+            .one(&self.db)
+            .await?
+        {
+            let mut active = track::ActiveModel {
+                id: Set(local.id),
+                ..Default::default()
+            };
+            if let Some(ref title) = fields.title {
+                active.title = Set(title.clone());
+            }
+            if let Some(genre) = fields.genre {
+                active.genre = Set(Some(genre));
+            }
+            if let Some(year) = fields.year {
+                active.year = Set(Some(year));
+            }
+            if let Some(track_number) = fields.track_number {
+                active.track_number = Set(Some(track_number));
+            }
+            if active.is_changed() {
+                active.update(&self.db).await?;
+            }
+        }
+
+        let title = fields.title.unwrap_or_else(|| remote.title.clone());
+        let artist_name = fields
+            .artist_name
+            .unwrap_or_else(|| remote.artist_name.clone());
+        let album_changed = fields.album_title.is_some();
+        let album_title = fields.album_title.or_else(|| remote.album_title.clone());
+
+        let mut active: remote_track::ActiveModel = remote.into();
+        active.title = Set(title.clone());
+        active.artist_name = Set(artist_name.clone());
+        if album_changed {
+            active.album_title = Set(album_title.clone());
+        }
+        // Signed over the old metadata; reseeded unsigned until re-announced
+        active.signature = Set(None);
+        active.update(&self.db).await?;
+
+        self.search_index
+            .add_track_tokens(&title, &artist_name, album_title.as_deref())
+            .await;
+
+        info!(%hash, %peer_id, %title, "applied track metadata update from origin");
+        Ok(())
+    }
+
+    /// Internal: remember that `peer_id` serves a track it replicated from
+    /// the announcement's origin, so `alternative_sources` offers it when
+    /// the origin is unreachable. Does nothing if the sender is the origin.
+    async fn record_reseeder(&self, ann: &TrackAnnouncement, peer_id: &str) {
+        if peer_id == ann.origin_node {
+            return;
+        }
+        let remote_uri = format!("p2p://{peer_id}/{}", ann.hash);
+        match remote_track::Entity::find()
+            .filter(remote_track::Column::RemoteUri.eq(&remote_uri))
+            .one(&self.db)
+            .await
+        {
+            Ok(None) => {}
+            Ok(Some(_)) => return,
+            Err(e) => {
+                warn!(hash = %ann.hash, %peer_id, "failed to look up reseeding peer: {e}");
+                return;
+            }
+        }
+        // Not linked to the local track: the origin's row stays the one
+        // plays are reported to
+        let source = remote_track::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            local_track_id: Set(None),
+            musicbrainz_id: Set(None),
+            title: Set(ann.title.clone()),
+            artist_name: Set(ann.artist_name.clone()),
+            album_title: Set(ann.album_title.clone()),
+            instance_domain: Set(format!("p2p://{peer_id}")),
+            remote_uri: Set(remote_uri),
+            remote_stream_url: Set(format!("/api/stream/p2p/{}", ann.hash)),
+            bitrate: Set(ann.bitrate),
+            sample_rate: Set(ann.sample_rate),
+            format: Set(Some(ann.format.clone())),
+            is_available: Set(true),
+            last_checked_at: Set(Some(chrono::Utc::now().into())),
+            dereferenced_at: Set(None),
+            signature: Set(None),
+            created_at: Set(chrono::Utc::now().into()),
+        };
+        match source.insert(&self.db).await {
+            Ok(_) => {
+                debug!(hash = %ann.hash, %peer_id, origin = %ann.origin_node, "recorded reseeding peer")
+            }
+            Err(e) => warn!(hash = %ann.hash, %peer_id, "failed to record reseeding peer: {e}"),
+        }
+    }
+
+    /// Internal: keep the signature of a re-announced track, so reseeding
+    /// it forwards the origin's latest one. Does nothing if unsigned.
+    async fn refresh_origin_signature(&self, ann: &TrackAnnouncement) {
+        let Some(ref signature) = ann.signature else {
+            return;
+        };
+        let remote_uri = format!("p2p://{}/{}", ann.origin_node, ann.hash);
+        if let Err(e) = remote_track::Entity::update_many()
+            .col_expr(
+                remote_track::Column::Signature,
+                sea_orm::sea_query::Expr::value(Some(signature.clone())),
+            )
+            .filter(remote_track::Column::RemoteUri.eq(remote_uri))
+            .exec(&self.db)
+            .await
+        {
+            warn!(hash = %ann.hash, "failed to store origin signature: {e}");
+        }
+    }
+
+    /// Internal: process a single track announcement — de-duplicate, auto-fetch blob,
+    /// create artist/album/track/remote_track records in the local database.
+    /// Used by both AnnounceTrack (single) and CatalogSync (batch) handlers.
+    async fn process_track_announcement(
+        &self,
+        ann: TrackAnnouncement,
+        peer_id: &str,
+    ) -> AnnouncementOutcome {
+        info!(
+            hash = %ann.hash,
+            title = %ann.title,
+            artist = %ann.artist_name,
+            %peer_id,
+            "processing track announcement"
+        );
+        self.registry.upsert_peer(peer_id, None, 0).await;
+
+        let policy = self.replication_policy();
+        if let Err(reason) = policy.check(&ann) {
+            self.reject_announcement(&ann, peer_id, reason);
+            return AnnouncementOutcome::Rejected;
+        }
+        if let Err(e) = self
+            .blocked_hashes
+            .check(&ann.hash, BlockedPath::Replicate)
+            .await
+        {
+            debug!(%peer_id, "ignoring announcement: {e}");
+            return AnnouncementOutcome::Rejected;
+        }
+        if let Err(reason) = self.check_peer_filter(&ann, peer_id, false).await {
+            self.reject_announcement(&ann, peer_id, reason);
+            return AnnouncementOutcome::Rejected;
+        }
+
+        if let Err(e) = ann.verify_signature() {
+            warn!(
+                hash = %ann.hash,
+                origin = %ann.origin_node,
+                %peer_id,
+                "rejecting track announcement: {e}"
+            );
+            return AnnouncementOutcome::Failed;
+        }
+
+        // Check if we already have this track (by content_hash)
+        let existing = track::Entity::find()
+            .filter(track::Column::ContentHash.eq(Some(ann.hash.clone())))
+            .one(&self.db)
+            .await
+            .ok()
+            .flatten();
+
+        if let Some(existing) = existing {
+            if existing.file_path.starts_with("p2p://") {
+                self.record_reseeder(&ann, peer_id).await;
+                self.refresh_origin_signature(&ann).await;
+                self.backfill_track(&existing, &ann).await;
+            }
+            // Still pick up an artist image or bio the first announcement lacked
+            if ann.artist_image_hash.is_some() || ann.artist_bio.is_some() {
+                if let Ok(Some(a)) = artist::Entity::find()
+                    .filter(artist::Column::Name.eq(&ann.artist_name))
+                    .one(&self.db)
+                    .await
+                {
+                    self.backfill_artist(&a, &ann, peer_id).await;
+                }
+            }
+            debug!(hash = %ann.hash, "track already in local catalog, skipping");
+            return AnnouncementOutcome::Skipped;
+        }
+
+        // A re-encode of a recording we already have has a different content
+        // hash and a similar acoustic fingerprint — don't create a second entry.
+        if let Some(ref fingerprint) = ann.fingerprint {
+            if let Ok(Some(existing)) = self
+                .find_track_by_fingerprint(fingerprint, ann.duration_secs)
+                .await
+            {
+                info!(
+                    hash = %ann.hash,
+                    existing_track = %existing.id,
+                    existing_hash = ?existing.content_hash,
+                    %peer_id,
+                    "deduplicated announcement: acoustic fingerprint matches existing track"
+                );
+                return AnnouncementOutcome::Skipped;
+            }
+        }
+
+        if let Err(reason) = self.check_peer_filter(&ann, peer_id, true).await {
+            self.reject_announcement(&ann, peer_id, reason);
+            return AnnouncementOutcome::Rejected;
+        }
+
+        // The MusicBrainz requirement is checked only for new tracks, so
+        // re-announcements of known ones do not cost a lookup each. The
+        // lookup is slow, so the track is stored hidden and checked by
+        // `verify_pending_tracks` off the announcement path.
+        let pending_verification = policy.require_musicbrainz;
+
+        // Blob is fetched lazily on first play (get_or_fetch_track) — no eager download
+        debug!(hash = %ann.hash, %peer_id, "track metadata stored, blob will be fetched on demand");
+
+        // Create artist (find or create)
+        let artist_id = match artist::Entity::find()
+            .filter(artist::Column::Name.eq(&ann.artist_name))
+            .one(&self.db)
+            .await
+        {
+            Ok(Some(a)) => {
+                self.backfill_artist(&a, &ann, peer_id).await;
+                a.id
+            }
+            _ => {
+                let new_id = Uuid::new_v4();
+                let new_artist = artist::ActiveModel {
+                    id: Set(new_id),
+                    name: Set(ann.artist_name.clone()),
+                    musicbrainz_id: Set(None),
+                    bio: Set(ann.artist_bio.clone().filter(|b| !b.is_empty())),
+                    image_url: Set(None),
+                    created_at: Set(chrono::Utc::now().into()),
+                };
+                if let Err(e) = new_artist.insert(&self.db).await {
+                    warn!(artist = %ann.artist_name, "failed to create artist: {e}");
+                    match artist::Entity::find()
+                        .filter(artist::Column::Name.eq(&ann.artist_name))
+                        .one(&self.db)
+                        .await
+                    {
+                        Ok(Some(a)) => a.id,
+                        _ => {
+                            self.release_peer_track(peer_id);
+                            return AnnouncementOutcome::Failed;
+                        }
+                    }
+                } else {
+                    // Sync image for newly created artist
+                    self.sync_artist_image(new_id, &ann, peer_id).await;
+                    new_id
+                }
+            }
+        };
+
+        // Resolve album artist for proper album grouping (compilations)
+        let album_artist_name = ann
+            .album_artist_name
+            .clone()
+            .unwrap_or_else(|| ann.artist_name.clone());
+
+        let album_artist_id = if album_artist_name == ann.artist_name {
+            artist_id
+        } else {
+            let existing = artist::Entity::find()
+                .filter(artist::Column::Name.eq(&album_artist_name))
+                .one(&self.db)
+                .await;
+            match existing {
+                Ok(Some(a)) => a.id,
+                _ => {
+                    let new_id = Uuid::new_v4();
+                    let new_artist = artist::ActiveModel {
+                        id: Set(new_id),
+                        name: Set(album_artist_name.clone()),
+                        musicbrainz_id: Set(None),
+                        bio: Set(None),
+                        image_url: Set(None),
+                        created_at: Set(chrono::Utc::now().into()),
+                    };
+                    if let Err(e) = new_artist.insert(&self.db).await {
+                        warn!(artist = %album_artist_name, "failed to create album artist: {e}");
+                        match artist::Entity::find()
+                            .filter(artist::Column::Name.eq(&album_artist_name))
+                            .one(&self.db)
+                            .await
+                        {
+                            Ok(Some(a)) => a.id,
+                            _ => artist_id, // fallback to track artist
+                        }
+                    } else {
+                        new_id
+                    }
+                }
+            }
+        };
+
+        // Create album (find or create) if present
+        let album_id = if let Some(ref album_title) = ann.album_title {
+            match album::Entity::find()
+                .filter(album::Column::Title.eq(album_title))
+                .filter(album::Column::ArtistId.eq(album_artist_id))
+                .one(&self.db)
+                .await
+            {
+                Ok(Some(a)) => {
+                    // If album already exists but has no cover, try to sync one
+                    if a.cover_url.is_none() {
+                        self.sync_cover_for_album(a.id, &ann, peer_id).await;
+                    }
+                    Some(a.id)
+                }
+                _ => {
+                    let new_id = Uuid::new_v4();
+                    let new_album = album::ActiveModel {
+                        id: Set(new_id),
+                        title: Set(album_title.clone()),
+                        artist_id: Set(album_artist_id),
+                        release_date: Set(None),
+                        cover_url: Set(None),
+                        musicbrainz_id: Set(None),
+                        genre: Set(ann.genre.clone()),
+                        year: Set(ann.year),
+                        created_at: Set(chrono::Utc::now().into()),
+                    };
+                    match new_album.insert(&self.db).await {
+                        Ok(_) => {
+                            // Sync cover for newly created album
+                            self.sync_cover_for_album(new_id, &ann, peer_id).await;
+                            Some(new_id)
+                        }
+                        Err(e) => {
+                            warn!(album = %album_title, "failed to create album: {e}");
+                            album::Entity::find()
+                                .filter(album::Column::Title.eq(album_title))
+                                .filter(album::Column::ArtistId.eq(album_artist_id))
+                                .one(&self.db)
+                                .await
+                                .ok()
+                                .flatten()
+                                .map(|a| a.id)
+                        }
+                    }
+                }
+            }
+        } else {
+            None
+        };
+
+        // Create the track record — file_path points to the P2P blob hash
+        let track_id = Uuid::new_v4();
+        let new_track = track::ActiveModel {
+            id: Set(track_id),
+            title: Set(ann.title.clone()),
+            artist_id: Set(artist_id),
+            album_id: Set(album_id),
+            track_number: Set(ann.track_number),
+            disc_number: Set(ann.disc_number),
+            duration_secs: Set(ann.duration_secs),
+            genre: Set(ann.genre.clone()),
+            year: Set(ann.year),
+            musicbrainz_id: Set(None),
+            file_path: Set(format!("p2p://{}", ann.hash)),
+            file_size: Set(ann.file_size),
+            format: Set(ann.format.clone()),
+            bitrate: Set(ann.bitrate),
+            sample_rate: Set(ann.sample_rate),
+            waveform_data: Set(ann
+                .waveform_data
+                .as_ref()
+                .map(|w| serde_json::json!(&w[..w.len().min(MAX_WAVEFORM_SAMPLES)]))),
+            uploaded_by: Set(None),
+            content_hash: Set(Some(ann.hash.clone())),
+            fingerprint: Set(ann.fingerprint.clone()),
+            play_count: Set(0),
+            is_private: Set(false),
+            is_hidden: Set(pending_verification),
+            hidden_by_block: Set(false),
+            loudness_lufs: Set(ann.loudness_lufs),
+            dynamic_range: Set(ann.dynamic_range),
+            encoding_quality: Set(ann.encoding_quality.clone()),
+            created_at: Set(chrono::Utc::now().into()),
+        };
+
+        match new_track.insert(&self.db).await {
+            Ok(_) => {
+                info!(
+                    %track_id,
+                    title = %ann.title,
+                    artist = %ann.artist_name,
+                    hash = %ann.hash,
+                    "remote track replicated to local catalog"
+                );
+
+                // Index in Bloom filter for search routing, once visible
+                if !pending_verification {
+                    self.search_index
+                        .add_track_tokens(&ann.title, &ann.artist_name, ann.album_title.as_deref())
+                        .await;
+                }
+
+                let remote_track_id = Uuid::new_v4();
+                let origin = &ann.origin_node;
+                let new_remote = remote_track::ActiveModel {
+                    id: Set(remote_track_id),
+                    local_track_id: Set(Some(track_id)),
+                    musicbrainz_id: Set(None),
+                    title: Set(ann.title.clone()),
+                    artist_name: Set(ann.artist_name.clone()),
+                    album_title: Set(ann.album_title.clone()),
+                    instance_domain: Set(format!("p2p://{}", origin)),
+                    remote_uri: Set(format!("p2p://{}/{}", origin, ann.hash)),
+                    remote_stream_url: Set(format!("/api/stream/p2p/{}", ann.hash)),
+                    bitrate: Set(ann.bitrate),
+                    sample_rate: Set(ann.sample_rate),
+                    format: Set(Some(ann.format.clone())),
+                    is_available: Set(true),
+                    last_checked_at: Set(Some(chrono::Utc::now().into())),
+                    dereferenced_at: Set(None),
+                    signature: Set(ann.signature.clone()),
+                    created_at: Set(chrono::Utc::now().into()),
+                };
+                if let Err(e) = new_remote.insert(&self.db).await {
+                    warn!(hash = %ann.hash, "failed to create remote_track record: {e}");
+                }
+                self.record_reseeder(&ann, peer_id).await;
+                self.events.emit(P2pEvent::TrackReplicated {
+                    peer_id: peer_id.to_string(),
+                    hash: ann.hash.clone(),
+                    title: ann.title.clone(),
+                    artist_name: ann.artist_name.clone(),
+                });
+
+                if pending_verification {
+                    match musicbrainz::queue_pending_verification(
+                        &self.db,
+                        track_id,
+                        &ann.title,
+                        &ann.artist_name,
+                        peer_id,
+                    )
+                    .await
+                    {
+                        Ok(()) => self.mb_verification_wake.notify_one(),
+                        Err(e) => {
+                            warn!(%track_id, "failed to queue MusicBrainz verification: {e}")
+                        }
+                    }
+                } else {
+                    // Async MusicBrainz enrichment — spawned to avoid blocking
+                    let queue = self.mb_queue.clone();
+                    let db = self.db.clone();
+                    let title = ann.title.clone();
+                    let artist = ann.artist_name.clone();
+                    tokio::spawn(async move {
+                        // Failed lookups are queued for retry by the queue
+                        if let Ok(Some(recording)) =
+                            MusicBrainzClient::queue_lookup(&queue, Some(track_id), &title, &artist)
+                                .await
+                        {
+                            debug!(
+                                mb_id = %recording.id,
+                                title = %title,
+                                score = recording.score,
+                                "MusicBrainz match found"
+                            );
+                            let update = track::ActiveModel {
+                                id: Set(track_id),
+                                musicbrainz_id: Set(Some(recording.id)),
+                                ..Default::default()
+                            };
+                            if let Err(e) = update.update(&db).await {
+                                warn!(track_id = %track_id, "failed to update musicbrainz_id: {e}");
+                            }
+                        }
+                    });
+                }
+                self.hooks.track_announced(&ann, peer_id);
+                AnnouncementOutcome::Inserted
+            }
+            Err(e) => {
+                warn!(hash = %ann.hash, "failed to create track record: {e}");
+                self.release_peer_track(peer_id);
+                AnnouncementOutcome::Failed
+            }
+        }
+    }
+
+    /// Internal: store the tracks of one `CatalogSync` / `CatalogSyncPage`
+    /// and count what happened to them. Pages from the same peer are
+    /// processed one at a time. Returns `None` for a page over the peer's
+    /// rate limit, which is dropped; the caller closes the stream unanswered.
+    async fn process_catalog_page(
+        &self,
+        announcements: Vec<TrackAnnouncement>,
+        peer_id: &str,
+    ) -> Option<CatalogPageAck> {
+        // Acquire per-peer lock to serialize (not reject) concurrent CatalogSync pages
+        let peer_lock = {
+            let mut map = self.catalog_sync_in_progress.lock().await;
+            map.entry(peer_id.to_string())
+                .or_insert_with(|| {
+                    Arc::new(tokio::sync::Mutex::new(PeerCatalogSync::new(
+                        std::time::Instant::now(),
+                    )))
+                })
+                .clone()
+        };
+        let mut sync = peer_lock.lock().await;
+
+        let limit = self._config.catalog_sync_pages_per_minute;
+        if !sync.admit(std::time::Instant::now(), limit) {
+            warn!(
+                %peer_id,
+                limit,
+                "peer exceeded its catalog sync page rate limit, dropping page"
+            );
+            // Still holding the peer's lock, so its next page waits too
+            tokio::time::sleep(CATALOG_RATE_LIMIT_PAUSE).await;
+            return None;
+        }
+
+        // One COUNT per page for a capped peer; its announcements then take
+        // slots from the cached count
+        self.refresh_peer_track_count(peer_id).await;
+
+        let mut counts = CatalogPageAck::default();
+        // Process in batches of 100 with yielding to avoid blocking the runtime
+        for (i, ann) in announcements.into_iter().enumerate() {
+            match self.process_track_announcement(ann, peer_id).await {
+                AnnouncementOutcome::Inserted => counts.inserted += 1,
+                // A refused track is final; counting it as failed would
+                // only make the sender retry the page
+                AnnouncementOutcome::Skipped | AnnouncementOutcome::Rejected => counts.skipped += 1,
+                AnnouncementOutcome::Failed => counts.failed += 1,
+            }
+            P2P_METRICS.catalog_sync_tracks_processed_total.inc();
+            if (i + 1) % 100 == 0 {
+                tokio::task::yield_now().await;
+                debug!(%peer_id, processed = i + 1, "catalog sync batch progress");
+            }
+        }
+        Some(counts)
+    }
+
+    /// Internal: answer `FetchTrack` / `FetchTrackRange` with the blob bytes
+    /// from `offset` onwards (at most `length` of them), length-prefixed. A
+    /// zero length means the blob is not available (or `offset` is past its
+    /// end). With `verified` the bytes are a BAO slice of the range instead
+    /// (see [`export_verified_range`]). A private track requested without a
+    /// valid grant `token` gets `AccessDenied` instead (a zero length on v1).
+    #[allow(clippy::too_many_arguments)]
+    async fn serve_blob(
+        &self,
+        mut send: iroh::endpoint::SendStream,
+        peer_id: &str,
+        hash: &str,
+        offset: u64,
+        length: Option<u64>,
+        verified: bool,
+        token: Option<&str>,
+        version: ProtocolVersion,
+    ) -> Result<(), P2pError> {
+        if self.published_hashes.contains(hash).await && !self.may_fetch(peer_id, hash, token).await
+        {
+            warn!(%peer_id, %hash, "rejected FetchTrack for private track without a valid grant");
+            if version >= ProtocolVersion::V2 {
+                let denied = P2pMessage::AccessDenied {
+                    hash: hash.to_string(),
+                };
+                let denied_bytes = serde_json::to_vec(&denied)?;
+                send.write_all(&ACCESS_DENIED_LEN.to_be_bytes())
+                    .await
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+                send.write_all(&denied_bytes)
+                    .await
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+                self.stats.record_sent(denied.kind());
+            } else {
+                send.write_all(&0u32.to_be_bytes())
+                    .await
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+            }
+            send.finish()
+                .map_err(|e| P2pError::Connection(e.to_string()))?;
+            return Ok(());
+        }
+
+        let remaining = self
+            .blob_reply(peer_id, hash, offset, length, verified)
+            .await;
+
+        // Send zero-length response to indicate not found
+        send.write_all(&(remaining.len() as u32).to_be_bytes())
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        if offset > 0 && !remaining.is_empty() {
+            debug!(%peer_id, %hash, offset, "resuming blob upload");
+        }
+
+        // Write in chunks, pausing whenever the upload limiter's buckets run dry.
+        for chunk in remaining.chunks(UPLOAD_CHUNK_SIZE) {
+            self.upload_limiter.acquire(peer_id, chunk.len()).await;
+            send.write_all(chunk)
+                .await
+                .map_err(|e| P2pError::Connection(e.to_string()))?;
+            self.stats.record_blob_uploaded(chunk.len() as u64);
+        }
+
+        send.finish()
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Internal: the bytes `serve_blob` answers with, empty if the blob is
+    /// not served.
+    async fn blob_reply(
+        &self,
+        peer_id: &str,
+        hash: &str,
+        offset: u64,
+        length: Option<u64>,
+        verified: bool,
+    ) -> Bytes {
+        // SECURITY: Only serve blobs that were explicitly published (FIX-19)
+        // and not blocked by a moderator.
+        // Only the requested range is read from the blob store.
+        if let Err(e) = self.blocked_hashes.check(hash, BlockedPath::Serve).await {
+            warn!(%peer_id, "rejected FetchTrack: {e}");
+            None
+        } else if self.published_hashes.contains(hash).await {
+            match hash.parse::<Hash>() {
+                Ok(h) if verified => {
+                    if self.is_marked_corrupted(h).await {
+                        None
+                    } else {
+                        export_verified_range(&self.blob_store, h, offset, length)
+                            .await
+                            .ok()
+                            .flatten()
+                    }
+                }
+                Ok(h) => self
+                    .get_local_track_range(h, offset, length)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|(data, _)| data),
+                Err(_) => None,
+            }
+        } else {
+            warn!(%peer_id, %hash, "rejected FetchTrack for non-published blob");
+            None
+        }
+        .unwrap_or_default()
+    }
+
+    /// Internal: handle a single protocol message.
+    async fn handle_message(
+        self: &Arc<Self>,
+        msg: P2pMessage,
+        mut send: iroh::endpoint::SendStream,
+        node_id: EndpointId,
+        peer_id: &str,
+        version: ProtocolVersion,
+    ) -> Result<(), P2pError> {
+        P2P_METRICS.messages_received_total.inc();
+        self.stats.record_received(msg.kind());
+
+        // Compatibility shim: a peer must not use messages newer than the
+        // protocol version it negotiated.
+        if !msg.supported_by(version) {
+            warn!(
+                %peer_id,
+                version = version.as_u8(),
+                "ignoring message not supported by negotiated protocol version"
+            );
+            if let Err(e) = send.finish() {
+                tracing::warn!(error = %e, "failed to finish send stream");
+            }
+            return Ok(());
+        }
+
+        match msg {
+            P2pMessage::FetchTrack { hash, token } => {
+                self.serve_blob(
+                    send,
+                    peer_id,
+                    &hash,
+                    0,
+                    None,
+                    false,
+                    token.as_deref(),
+                    version,
+                )
+                .await?;
+            }
+            P2pMessage::FetchTrackRange {
+                hash,
+                offset,
+                length,
+                token,
+                verified,
+            } => {
+                self.serve_blob(
+                    send,
+                    peer_id,
+                    &hash,
+                    offset,
+                    length,
+                    verified,
+                    token.as_deref(),
+                    version,
+                )
+                .await?;
+            }
+            P2pMessage::Ping => {
+                // Count ALL local tracks (not just those with content_hash set).
+                // During backfill, some tracks may not yet have hashes, but we still
+                // report the full catalog size so peers know to wait for announcements.
+                let track_count = match track::Entity::find()
+                    .filter(track::Column::FilePath.not_like("p2p://%"))
+                    .count(&self.db)
+                    .await
+                {
+                    Ok(c) => c,
+                    Err(e) => {
+                        tracing::error!("failed to count tracks for ping response: {e}");
+                        0
+                    }
+                };
+
+                let pong = P2pMessage::Pong {
+                    node_id: node_id.to_string(),
+                    track_count,
+                    version: Some(crate::build_version().to_string()),
+                    capabilities: SUPPORTED_CAPABILITIES
+                        .iter()
+                        .map(|c| c.to_string())
+                        .collect(),
+                };
+                let pong_bytes = serde_json::to_vec(&pong)?;
+                send.write_all(&(pong_bytes.len() as u32).to_be_bytes())
+                    .await
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+                send.write_all(&pong_bytes)
+                    .await
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+                send.finish()
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+                self.stats.record_sent(pong.kind());
+
+                // After responding to a Ping, sync our catalog to this peer.
+                // A peer we have never synced with gets every existing track;
+                // after that only tracks added since the last successful sync
+                // are sent, so peers that ping each other regularly do not
+                // keep exchanging full catalogs.
+                // Spawned asynchronously to avoid blocking the connection handler
+                // (catalog sync may query the DB for every track + fetch blobs).
+                if let Ok(remote_nid) = peer_id.parse::<EndpointId>() {
+                    let node = Arc::clone(self);
+                    tokio::spawn(async move {
+                        node.sync_catalog_after_ping(remote_nid).await;
+                    });
+                }
+            }
+            P2pMessage::AnnounceTrack(ann) => {
+                self.events.emit(P2pEvent::TrackAnnounced {
+                    peer_id: peer_id.to_string(),
+                    hash: ann.hash.clone(),
+                    title: ann.title.clone(),
+                    artist_name: ann.artist_name.clone(),
+                });
+                self.process_track_announcement(*ann, peer_id).await;
+                // Properly close our side of the stream
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::CatalogSync(announcements) => {
+                info!(count = announcements.len(), %peer_id, "received catalog sync");
+                P2P_METRICS.catalog_sync_pages_received_total.inc();
+                self.events.emit(P2pEvent::CatalogSyncStarted {
+                    peer_id: peer_id.to_string(),
+                    sync_id: None,
+                    total_pages: 1,
+                });
+                let Some(ack) = self.process_catalog_page(announcements, peer_id).await else {
+                    // Over the rate limit: close the stream without processing
+                    if let Err(e) = send.finish() {
+                        tracing::warn!(error = %e, "failed to finish send stream");
+                    }
+                    return Ok(());
+                };
+                self.events.emit(P2pEvent::CatalogSyncPage {
+                    peer_id: peer_id.to_string(),
+                    sync_id: None,
+                    page: 0,
+                    total_pages: 1,
+                    inserted: ack.inserted,
+                    skipped: ack.skipped,
+                    failed: ack.failed,
+                });
+                self.events.emit(P2pEvent::CatalogSyncFinished {
+                    peer_id: peer_id.to_string(),
+                    sync_id: None,
+                    total_pages: 1,
+                });
+
+                // Properly close our side of the stream
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::CatalogSyncPage { header, tracks } => {
+                info!(
+                    count = tracks.len(),
+                    page = header.page,
+                    total_pages = header.total_pages,
+                    %peer_id,
+                    "received catalog sync page"
+                );
+                P2P_METRICS.catalog_sync_pages_received_total.inc();
+                if header.page == 0 {
+                    self.events.emit(P2pEvent::CatalogSyncStarted {
+                        peer_id: peer_id.to_string(),
+                        sync_id: Some(header.sync_id),
+                        total_pages: header.total_pages,
+                    });
+                }
+                let Some(mut ack) = self.process_catalog_page(tracks, peer_id).await else {
+                    // Over the rate limit: a bare fin tells the sender to back off
+                    send.finish()
+                        .map_err(|e| P2pError::Connection(e.to_string()))?;
+                    return Ok(());
+                };
+                ack.sync_id = header.sync_id;
+                ack.page = header.page;
+                self.events.emit(P2pEvent::CatalogSyncPage {
+                    peer_id: peer_id.to_string(),
+                    sync_id: Some(header.sync_id),
+                    page: header.page,
+                    total_pages: header.total_pages,
+                    inserted: ack.inserted,
+                    skipped: ack.skipped,
+                    failed: ack.failed,
+                });
+                if header.page + 1 >= header.total_pages {
+                    self.events.emit(P2pEvent::CatalogSyncFinished {
+                        peer_id: peer_id.to_string(),
+                        sync_id: Some(header.sync_id),
+                        total_pages: header.total_pages,
+                    });
+                }
+
+                let reply = P2pMessage::CatalogSyncAck(ack);
+                let reply_bytes = serde_json::to_vec(&reply)?;
+                send.write_all(&(reply_bytes.len() as u32).to_be_bytes())
+                    .await
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+                send.write_all(&reply_bytes)
+                    .await
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+                send.finish()
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+                self.stats.record_sent(reply.kind());
+            }
+            P2pMessage::KeepAlive => {
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::Goodbye {
+                node_id: claimed,
+                reason,
+            } => {
+                // The authenticated connection says who is leaving, not the payload
+                if claimed != peer_id {
+                    warn!(%peer_id, %claimed, "goodbye names another node, ignoring its node_id");
+                }
+                info!(%peer_id, %reason, "peer is shutting down, marking offline");
+                self.registry.mark_departed(peer_id).await;
+                self.upload_limiter.remove_peer(peer_id).await;
+                if let Ok(remote_nid) = peer_id.parse::<EndpointId>() {
+                    self.conn_pool.invalidate(&remote_nid).await;
+                }
+                self.events.emit(P2pEvent::PeerDisconnected {
+                    peer_id: peer_id.to_string(),
+                    reason,
+                });
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::BlockHash { hash, reason } => {
+                if let Err(e) = self.receive_block(&hash, &reason, peer_id).await {
+                    warn!(%peer_id, %hash, "failed to record blocked hash: {e}");
+                }
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::DeleteTrack {
+                hash,
+                origin_node,
+                deleted_at,
+                ttl,
+                signature,
+            } => {
+                self.receive_delete_track(hash, origin_node, deleted_at, ttl, signature, peer_id)
+                    .await;
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::PlayCountUpdate {
+                hash,
+                origin_node,
+                incremental_count,
+            } => {
+                if origin_node != self.node_id().to_string() {
+                    debug!(%peer_id, %hash, %origin_node, "ignoring play count update for another origin");
+                } else if let Err(e) = self
+                    .apply_play_count_update(&hash, incremental_count, peer_id)
+                    .await
+                {
+                    warn!(%peer_id, %hash, "failed to apply play count update: {e}");
+                }
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::PopularityGossip { entries } => {
+                match popularity::merge_gossip(&self.db, peer_id, entries).await {
+                    Ok(stored) => debug!(%peer_id, stored, "merged popularity gossip"),
+                    Err(e) => warn!(%peer_id, "failed to merge popularity gossip: {e}"),
+                }
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::CatalogSyncAck(ack) => {
+                // Acks are read by the sender on the page's own stream
+                debug!(%peer_id, page = ack.page, "ignoring unsolicited catalog sync ack");
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::PeerExchange { peers } => {
+                info!(count = peers.len(), %peer_id, "received peer exchange request");
+
+                // A quarantined peer neither learns our peers nor adds to them
+                let quarantined = self.registry.is_quarantined(peer_id).await;
+
+                // Verify new peers before adding them
+                let our_id = self.node_id().to_string();
+                let mut new_peers: Vec<String> = Vec::new();
+                for pid in peers.iter().filter(|_| !quarantined) {
+                    if *pid != our_id && self.registry.get_peer(pid).await.is_none() {
+                        new_peers.push(pid.clone());
+                    }
+                }
+
+                // Don't ping more peers than the registry has room for
+                if let Some(capacity) = self.registry.admission_capacity().await {
+                    if new_peers.len() > capacity {
+                        warn!(
+                            %peer_id,
+                            offered = new_peers.len(),
+                            verifying = capacity,
+                            "peer limit reached, truncating peer exchange"
+                        );
+                        new_peers.truncate(capacity);
+                    }
+                }
+
+                if !new_peers.is_empty() {
+                    let node = Arc::clone(self);
+                    tokio::spawn(async move {
+                        // Limit concurrent verification to 5
+                        let semaphore = Arc::new(tokio::sync::Semaphore::new(5));
+                        let mut handles = Vec::new();
+
+                        for pid in new_peers {
+                            let node = Arc::clone(&node);
+                            let sem = Arc::clone(&semaphore);
+                            handles.push(tokio::spawn(async move {
+                                let _permit = sem.acquire().await.ok()?;
+                                // Try to ping the peer with a 5s timeout
+                                if let Ok(nid) = pid.parse::<EndpointId>() {
+                                    let ping = P2pMessage::Ping;
+                                    match tokio::time::timeout(
+                                        std::time::Duration::from_secs(5),
+                                        node.send_message_to_peer(nid, &ping),
+                                    )
+                                    .await
+                                    {
+                                        Ok(Ok(_)) => {
+                                            if !node.registry.upsert_peer(&pid, None, 0).await {
+                                                debug!(peer = %pid, "PEX peer verified, but the peer limit is reached");
+                                                return None;
+                                            }
+                                            info!(peer = %pid, "PEX peer verified and added");
+                                            // Sync catalogs with new peer
+                                            node.announce_all_tracks_to_peer(nid).await;
+                                            Some(pid)
+                                        }
+                                        _ => {
+                                            debug!(peer = %pid, "PEX peer unreachable, not adding");
+                                            None
+                                        }
+                                    }
+                                } else {
+                                    None
+                                }
+                            }));
+                        }
+
+                        for h in handles {
+                            let _ = h.await;
+                        }
+                    });
+                }
+
+                // Reply with a sample of our peer list (including ourselves)
+                let our_peers = if quarantined {
+                    Vec::new()
+                } else {
+                    let known = self.registry.pex_shareable_peers().await;
+                    sample_pex_peers(known, our_id)
+                };
+
+                let reply = P2pMessage::PeerExchange { peers: our_peers };
+                let reply_bytes = serde_json::to_vec(&reply)?;
+                send.write_all(&(reply_bytes.len() as u32).to_be_bytes())
+                    .await
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+                send.write_all(&reply_bytes)
+                    .await
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+                send.finish()
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+            }
+            P2pMessage::CatalogDelta { since, tracks } => {
+                info!(count = tracks.len(), %since, %peer_id, "received catalog delta");
+                for ann in tracks {
+                    self.process_track_announcement(ann, peer_id).await;
+                }
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::RequestCatalog => {
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+                if self.registry.is_quarantined(peer_id).await {
+                    info!(%peer_id, "ignoring catalog request from quarantined peer");
+                } else if let Ok(remote_nid) = peer_id.parse::<EndpointId>() {
+                    info!(%peer_id, "received catalog request — sending full catalog");
+                    // Spawn so we don't block this connection handler
+                    let node = Arc::clone(self);
+                    tokio::spawn(async move {
+                        node.announce_all_tracks_to_peer(remote_nid).await;
+                    });
+                }
+            }
+            P2pMessage::BloomExchange { bloom } => {
+                info!(%peer_id, items = bloom.item_count, "received bloom filter from peer");
+                self.events.emit(P2pEvent::BloomFilterUpdated {
+                    peer_id: peer_id.to_string(),
+                    item_count: bloom.item_count,
+                });
+                self.search_index.import_peer_bloom(peer_id, bloom).await;
+                // The peer may now match queries it did not before
+                self.search_cache.invalidate();
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::BloomDelta { delta } => {
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+                if self.search_index.apply_delta(peer_id, &delta).await {
+                    self.search_cache.invalidate();
+                    debug!(%peer_id, bits = delta.set_bits.len(), "applied bloom delta from peer");
+                    self.events.emit(P2pEvent::BloomFilterUpdated {
+                        peer_id: peer_id.to_string(),
+                        item_count: delta.item_count,
+                    });
+                } else if let Ok(remote_nid) = peer_id.parse::<EndpointId>() {
+                    // Our copy is missing or from another generation
+                    info!(%peer_id, generation = delta.generation, "bloom delta out of sync — requesting full filter");
+                    let node = Arc::clone(self);
+                    tokio::spawn(async move {
+                        if let Err(e) = node
+                            .send_message_to_peer(remote_nid, &P2pMessage::RequestBloom)
+                            .await
+                        {
+                            debug!(peer = %remote_nid, "failed to request bloom filter: {e}");
+                        }
+                    });
+                }
+            }
+            P2pMessage::CatalogChecksumRequest => {
+                let local = self.compute_local_catalog_checksum().await?;
+                let reply = P2pMessage::CatalogChecksumResponse {
+                    checksum: local.checksum,
+                    track_count: local.track_count,
+                };
+                let reply_bytes = serde_json::to_vec(&reply)?;
+                send.write_all(&(reply_bytes.len() as u32).to_be_bytes())
+                    .await
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+                send.write_all(&reply_bytes)
+                    .await
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+                send.finish()
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+                self.stats.record_sent(reply.kind());
+            }
+            P2pMessage::CatalogChecksumResponse { .. } => {
+                // Only expected as a reply on the requester's own stream
+                debug!(%peer_id, "ignoring unsolicited catalog checksum response");
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::AuthorizeTrackAccess {
+                hash,
+                requester_node,
+                token,
+            } => {
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+                self.receive_track_grant(&hash, &requester_node, &token, peer_id)
+                    .await?;
+            }
+            P2pMessage::AccessDenied { hash } => {
+                // Only expected as a reply to our own fetch
+                debug!(%peer_id, %hash, "ignoring unsolicited access denial");
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::CheckDuplicate {
+                fingerprint,
+                duration_secs,
+            } => {
+                if let Some(hash) = self
+                    .find_public_track_by_fingerprint(&fingerprint, duration_secs)
+                    .await?
+                {
+                    debug!(%peer_id, %hash, "duplicate check matched a local track");
+                    let reply = P2pMessage::DuplicateFound {
+                        hash,
+                        source_node: self.node_id().to_string(),
+                    };
+                    let reply_bytes = serde_json::to_vec(&reply)?;
+                    send.write_all(&(reply_bytes.len() as u32).to_be_bytes())
+                        .await
+                        .map_err(|e| P2pError::Connection(e.to_string()))?;
+                    send.write_all(&reply_bytes)
+                        .await
+                        .map_err(|e| P2pError::Connection(e.to_string()))?;
+                    self.stats.record_sent(reply.kind());
+                }
+                send.finish()
+                    .map_err(|e| P2pError::Connection(e.to_string()))?;
+            }
+            P2pMessage::DuplicateFound { .. } => {
+                // Only expected as a reply on the requester's own stream
+                debug!(%peer_id, "ignoring unsolicited duplicate match");
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::RequestBloom => {
+                debug!(%peer_id, "received bloom filter request");
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+                if let Ok(remote_nid) = peer_id.parse::<EndpointId>() {
+                    let node = Arc::clone(self);
+                    tokio::spawn(async move {
+                        let bloom = node.search_index.export_local_bloom().await;
+                        let msg = P2pMessage::BloomExchange { bloom };
+                        if let Err(e) = node.send_message_to_peer(remote_nid, &msg).await {
+                            debug!(peer = %remote_nid, "failed to send bloom filter: {e}");
+                        }
+                    });
+                }
+            }
+            P2pMessage::SearchQuery {
+                request_id,
+                query,
+                limit,
+                offset,
+            } => {
+                info!(%peer_id, %query, offset, "received search query");
+                self.events.emit(P2pEvent::SearchQueryReceived {
+                    peer_id: peer_id.to_string(),
+                    query: query.clone(),
+                });
+                if let Err(e) = self
+                    .handle_search_query(&request_id, &query, limit, offset, send)
+                    .await
+                {
+                    warn!(%peer_id, "failed to handle search query: {e}");
+                }
+            }
+            P2pMessage::UpdateTrackMetadata {
+                hash,
+                title,
+                artist_name,
+                album_title,
+                genre,
+                year,
+                track_number,
+            } => {
+                let fields = TrackMetadataUpdate {
+                    title,
+                    artist_name,
+                    album_title,
+                    genre,
+                    year,
+                    track_number,
+                };
+                if let Err(e) = self
+                    .apply_track_metadata_update(&hash, fields, peer_id)
+                    .await
+                {
+                    warn!(%hash, %peer_id, "failed to apply track metadata update: {e}");
+                }
+                if let Err(e) = send.finish() {
+                    tracing::warn!(error = %e, "failed to finish send stream");
+                }
+            }
+            P2pMessage::TrackData { .. }
+            | P2pMessage::Pong { .. }
+            | P2pMessage::SearchResults { .. } => {
+                // These are responses, not requests — ignore if received as requests
+                debug!("received unexpected response message");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Load or generate a persistent secret key.
+    /// The key is always persisted to ensure stable EndpointId across restarts.
+    async fn load_or_generate_key(config: &P2pConfig) -> Result<SecretKey, P2pError> {
+        let key_path = &config.secret_key_path;
+
+        if key_path.exists() {
+            let raw = tokio::fs::read(key_path).await.map_err(P2pError::Io)?;
+
+            // Try loading as raw 32 bytes first, then as hex string (backward compat)
+            if raw.len() == 32 {
+                let mut buf = [0u8; 32];
+                buf.copy_from_slice(&raw);
+                info!(path = %key_path.display(), "loaded existing P2P secret key (raw)");
+                return Ok(SecretKey::from_bytes(&buf));
+            }
+
+            let hex_str = String::from_utf8(raw)
+                .map_err(|e| P2pError::Endpoint(format!("invalid key file encoding: {e}")))?;
+            let decoded = data_encoding::HEXLOWER_PERMISSIVE
+                .decode(hex_str.trim().as_bytes())
+                .map_err(|e| P2pError::Endpoint(format!("invalid hex secret key: {e}")))?;
+            if decoded.len() != 32 {
+                return Err(P2pError::Endpoint(format!(
+                    "secret key has wrong length: {} (expected 32)",
+                    decoded.len()
+                )));
+            }
+            let mut buf = [0u8; 32];
+            buf.copy_from_slice(&decoded);
+            info!(path = %key_path.display(), "loaded existing P2P secret key");
+            return Ok(SecretKey::from_bytes(&buf));
+        }
+
+        // Generate and save a new key
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        if let Some(parent) = key_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(P2pError::Io)?;
+        }
+        tokio::fs::write(key_path, data_encoding::HEXLOWER.encode(&key.to_bytes()))
+            .await
+            .map_err(P2pError::Io)?;
+        info!(path = %key_path.display(), "generated and saved new P2P secret key");
+        Ok(key)
+    }
+
+    /// Wait for the relay connection to be established.
+    /// Returns the relay URL once connected, or None if unavailable.
+    async fn wait_for_relay(endpoint: &Endpoint) -> Option<String> {
+        // Poll endpoint addr until relay_url is available
+        for _ in 0..50 {
+            let addr = endpoint.addr();
+            if let Some(url) = addr.relay_urls().next() {
+                return Some(url.to_string());
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        }
+        None
+    }
+
+    /// Internal: get an image blob (cover art or artist image) announced by
+    /// `peer_id`, from the local store if we already have it, otherwise from
+    /// the peer. Fetched blobs are verified and kept so we can serve them too.
+    async fn fetch_image_blob(&self, hash_str: &str, peer_id: &str) -> Option<Bytes> {
+        let blob_hash: Hash = match hash_str.parse() {
+            Ok(h) => h,
+            Err(_) => {
+                warn!(hash = %hash_str, "invalid image hash");
+                return None;
+            }
+        };
+
+        if self.has_blob(blob_hash).await {
+            // Already have it locally
+            return self.get_local_track(blob_hash).await.ok();
+        }
+
+        let nid: EndpointId = peer_id.parse().ok()?;
+        let fetched = self
+            .fetch_track_from_peer(EndpointAddr::new(nid), blob_hash)
+            .await
+            .and_then(|data| verify_blob(&blob_hash, &data).map(|()| data));
+        let data = match fetched {
+            Ok(data) => data,
+            Err(e) => {
+                if matches!(e, P2pError::HashMismatch { .. }) {
+                    self.conn_pool.invalidate(&nid).await;
+                }
+                warn!(hash = %hash_str, %peer_id, "failed to fetch image blob: {e}");
+                return None;
+            }
+        };
+
+        // Store in blob store with a persistent tag
+        match self
+            .blob_store
+            .blobs()
+            .add_bytes(data.clone())
+            .temp_tag()
+            .await
+        {
+            Ok(outcome) => {
+                let h = outcome.hash();
+                // Register hash so it can be served to peers
+                if let Err(e) = self
+                    .published_hashes
+                    .publish(&h.to_string(), PublishedKind::Image)
+                    .await
+                {
+                    warn!(hash = %hash_str, "failed to record published image: {e}");
+                }
+                let tag_name = format!("published-{}", h);
+                if let Err(e) = self
+                    .blob_store
+                    .tags()
+                    .set(tag_name, HashAndFormat::raw(h))
+                    .await
+                {
+                    warn!(hash = %hash_str, "failed to set persistent tag for image: {e}");
+                }
+            }
+            Err(e) => warn!(hash = %hash_str, "failed to import image blob: {e}"),
+        }
+        Some(data)
+    }
+
+    /// Fetch a cover art blob from a peer, write it to the local audio storage,
+    /// and update the album's `cover_url` in the database.
+    async fn sync_cover_for_album(&self, album_id: Uuid, ann: &TrackAnnouncement, peer_id: &str) {
+        let cover_hash_str = match &ann.cover_hash {
+            Some(h) if !h.is_empty() => h.clone(),
+            _ => return,
+        };
+        let Some(cover_data) = self.fetch_image_blob(&cover_hash_str, peer_id).await else {
+            return;
+        };
+
+        // Write cover to the audio storage filesystem
+        let artist_dir = sanitize_for_path(&ann.artist_name);
+        let album_dir = ann
+            .album_title
+            .as_deref()
+            .map(sanitize_for_path)
+            .unwrap_or_else(|| "singles".to_string());
+
+        let cover_dir = self
+            .cover_base_path()
+            .join("p2p-covers")
+            .join(&artist_dir)
+            .join(&album_dir);
+
+        if let Err(e) = tokio::fs::create_dir_all(&cover_dir).await {
+            warn!("failed to create cover directory: {e}");
+            return;
+        }
+
+        let cover_file = cover_dir.join("cover.jpg");
+        if let Err(e) = tokio::fs::write(&cover_file, &cover_data).await {
+            warn!("failed to write cover file: {e}");
+            return;
+        }
+
+        // Build the relative path for the cover_url
+        let relative = cover_file
+            .strip_prefix(self.cover_base_path())
+            .unwrap_or(&cover_file)
+            .to_string_lossy()
+            .to_string();
+
+        let cover_url = format!("/api/media/{relative}");
+
+        // Update the album's cover_url in the database
+        let update = album::ActiveModel {
+            id: Set(album_id),
+            cover_url: Set(Some(cover_url.clone())),
+            ..Default::default()
+        };
+        if let Err(e) = update.update(&self.db).await {
+            warn!(%album_id, "failed to update album cover_url: {e}");
+        } else {
+            info!(%album_id, %cover_url, "album cover synced from peer");
+        }
+    }
+
+    /// Fetch an artist image blob from a peer, write it next to the artist's
+    /// synced covers, and set the artist's `image_url`.
+    async fn sync_artist_image(&self, artist_id: Uuid, ann: &TrackAnnouncement, peer_id: &str) {
+        let image_hash_str = match &ann.artist_image_hash {
+            Some(h) if !h.is_empty() => h.clone(),
+            _ => return,
+        };
+        let Some(image_data) = self.fetch_image_blob(&image_hash_str, peer_id).await else {
+            return;
+        };
+
+        let artist_dir = self
+            .cover_base_path()
+            .join("p2p-covers")
+            .join(sanitize_for_path(&ann.artist_name));
+        if let Err(e) = tokio::fs::create_dir_all(&artist_dir).await {
+            warn!("failed to create artist image directory: {e}");
+            return;
+        }
+
+        let image_file = artist_dir.join("artist.jpg");
+        if let Err(e) = tokio::fs::write(&image_file, &image_data).await {
+            warn!("failed to write artist image: {e}");
+            return;
+        }
+
+        let relative = image_file
+            .strip_prefix(self.cover_base_path())
+            .unwrap_or(&image_file)
+            .to_string_lossy()
+            .to_string();
+        let image_url = format!("/api/media/{relative}");
+
+        let update = artist::ActiveModel {
+            id: Set(artist_id),
+            image_url: Set(Some(image_url.clone())),
+            ..Default::default()
+        };
+        if let Err(e) = update.update(&self.db).await {
+            warn!(%artist_id, "failed to update artist image_url: {e}");
+        } else {
+            info!(%artist_id, %image_url, "artist image synced from peer");
+        }
+    }
+
+    /// Fill in the waveform and loudness of a replicated track from a later
+    /// announcement when they are still missing locally. The origin
+    /// announces a new upload before they are computed, then again with
+    /// them. Values already set are never replaced.
+    async fn backfill_track(&self, existing: &track::Model, ann: &TrackAnnouncement) {
+        let mut update = track::ActiveModel {
+            id: Set(existing.id),
+            ..Default::default()
+        };
+        let mut changed = false;
+        if existing.waveform_data.is_none() {
+            if let Some(waveform) = ann.waveform_data.as_ref().filter(|w| !w.is_empty()) {
+                update.waveform_data = Set(Some(serde_json::json!(
+                    &waveform[..waveform.len().min(MAX_WAVEFORM_SAMPLES)]
+                )));
+                changed = true;
+            }
+        }
+        if existing.loudness_lufs.is_none() && ann.loudness_lufs.is_some() {
+            update.loudness_lufs = Set(ann.loudness_lufs);
+            changed = true;
+        }
+        if existing.dynamic_range.is_none() && ann.dynamic_range.is_some() {
+            update.dynamic_range = Set(ann.dynamic_range);
+            changed = true;
+        }
+        if !changed {
+            return;
+        }
+        match update.update(&self.db).await {
+            Ok(_) => debug!(hash = %ann.hash, "backfilled replicated track from announcement"),
+            Err(e) => warn!(hash = %ann.hash, "failed to backfill replicated track: {e}"),
+        }
+    }
+
+    /// Fill in an existing artist's bio and image from an announcement when
+    /// they are still missing locally. Values already set are never replaced.
+    async fn backfill_artist(
+        &self,
+        artist: &artist::Model,
+        ann: &TrackAnnouncement,
+        peer_id: &str,
+    ) {
+        if artist.bio.is_none() {
+            if let Some(bio) = ann.artist_bio.as_ref().filter(|b| !b.is_empty()) {
+                let update = artist::ActiveModel {
+                    id: Set(artist.id),
+                    bio: Set(Some(bio.clone())),
+                    ..Default::default()
+                };
+                if let Err(e) = update.update(&self.db).await {
+                    warn!(artist_id = %artist.id, "failed to backfill artist bio: {e}");
+                }
+            }
+        }
+        if artist.image_url.is_none() {
+            self.sync_artist_image(artist.id, ann, peer_id).await;
+        }
+    }
+}
+
+// ── TrackFetcher implementation for P2pNode ──────────────────────────
+
+#[async_trait]
+impl RangeSource for P2pNode {
+    async fn fetch_range(
+        &self,
+        peer_id: &str,
+        hash: Hash,
+        offset: u64,
+        len: u64,
+    ) -> Result<Bytes, P2pError> {
+        self.request_range(peer_id, hash, offset, len, false).await
+    }
+}
+
+impl P2pNode {
+    /// Fetch `len` bytes of blob `hash` from `offset` with a BAO proof and
+    /// verify them against the hash. Returns the bytes (cut to the end of
+    /// the blob) and the blob's verified size. The peer must advertise
+    /// [`CAP_VERIFIED_RANGES`].
+    async fn fetch_verified_range(
+        &self,
+        peer_id: &str,
+        hash: Hash,
+        offset: u64,
+        len: u64,
+    ) -> Result<(Bytes, u64), P2pError> {
+        let encoded = self.request_range(peer_id, hash, offset, len, true).await?;
+        import_verified_range(&self.blob_store, hash, offset, len, encoded).await
+    }
+
+    /// Internal: send `FetchTrackRange` for `len` bytes from `offset` and
+    /// read the length-prefixed reply.
+    async fn request_range(
+        &self,
+        peer_id: &str,
+        hash: Hash,
+        offset: u64,
+        len: u64,
+        verified: bool,
+    ) -> Result<Bytes, P2pError> {
+        if is_peer_blocked(&self.db, peer_id).await {
+            return Err(P2pError::PeerBlocked(peer_id.to_string()));
+        }
+        let nid: EndpointId = peer_id
+            .parse()
+            .map_err(|_| P2pError::Connection(format!("invalid peer id: {peer_id}")))?;
+
+        let conn = self.conn_pool.get_connection(nid).await?;
+        let (mut send, mut recv) = match conn.open_bi().await {
+            Ok(streams) => streams,
+            Err(e) => {
+                self.conn_pool.invalidate(&nid).await;
+                return Err(P2pError::Connection(e.to_string()));
+            }
+        };
+
+        let request = P2pMessage::FetchTrackRange {
+            hash: hash.to_string(),
+            offset,
+            length: Some(len),
+            token: self.fetch_token(peer_id, &hash.to_string()).await,
+            verified,
+        };
+        let request_bytes = serde_json::to_vec(&request)?;
+        send.write_all(&(request_bytes.len() as u32).to_be_bytes())
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.write_all(&request_bytes)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.finish()
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        self.stats.record_sent(request.kind());
+
+        let mut len_buf = [0u8; 4];
+        recv.read_exact(&mut len_buf)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        let data_len = u32::from_be_bytes(len_buf);
+        if data_len == ACCESS_DENIED_LEN {
+            return Err(self.read_access_denied(&mut recv, hash).await);
+        }
+        let data_len = data_len as usize;
+        if data_len == 0 {
+            return Err(P2pError::TrackNotFound(hash.to_string()));
+        }
+
+        let data = recv
+            .read_to_end(data_len)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        self.stats.record_blob_downloaded(data.len() as u64);
+        Ok(Bytes::from(data))
+    }
+}
+
+#[async_trait]
+impl PeerSearcher for P2pNode {
+    async fn search_peer_page(
+        &self,
+        peer: &str,
+        query: &str,
+        offset: u32,
+        limit: u32,
+    ) -> Option<(Vec<SearchResultItem>, u64)> {
+        let nid: EndpointId = peer.parse().ok()?;
+        let msg = P2pMessage::SearchQuery {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            query: query.to_string(),
+            limit,
+            offset,
+        };
+        let timeout_dur = std::time::Duration::from_secs(10);
+        match tokio::time::timeout(timeout_dur, self.search_peer(EndpointAddr::new(nid), &msg))
+            .await
+        {
+            Ok(Ok((results, total))) => {
+                info!(%peer, results = results.len(), total, "received search results");
+                if offset == 0 && !results.is_empty() {
+                    self.search_index.record_query_result(query, peer).await;
+                }
+                Some((results, total))
+            }
+            Ok(Err(e)) => {
+                debug!(%peer, "search failed: {e}");
+                None
+            }
+            Err(_) => {
+                debug!(%peer, "search timed out");
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CatalogPageSink for P2pNode {
+    async fn acknowledges(&self, peer_id: &str) -> bool {
+        let Ok(nid) = peer_id.parse::<EndpointId>() else {
+            return false;
+        };
+        self.conn_pool.get_connection(nid).await.is_ok()
+            && self
+                .conn_pool
+                .negotiated_version(&nid)
+                .await
+                .is_some_and(|v| v >= ProtocolVersion::V2)
+    }
+
+    async fn send_page(
+        &self,
+        peer_id: &str,
+        header: &CatalogPageHeader,
+        tracks: &[TrackAnnouncement],
+    ) -> Result<CatalogPageAck, P2pError> {
+        let nid: EndpointId = peer_id
+            .parse()
+            .map_err(|_| P2pError::Connection(format!("invalid peer id: {peer_id}")))?;
+
+        let conn = self.conn_pool.get_connection(nid).await?;
+        let (mut send, mut recv) = match conn.open_bi().await {
+            Ok(streams) => streams,
+            Err(e) => {
+                self.conn_pool.invalidate(&nid).await;
+                return Err(P2pError::Connection(e.to_string()));
+            }
+        };
+
+        let msg = P2pMessage::CatalogSyncPage {
+            header: header.clone(),
+            tracks: tracks.to_vec(),
+        };
+        let msg_bytes = serde_json::to_vec(&msg)?;
+        send.write_all(&(msg_bytes.len() as u32).to_be_bytes())
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.write_all(&msg_bytes)
+            .await
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        send.finish()
+            .map_err(|e| P2pError::Connection(e.to_string()))?;
+        self.stats.record_sent(msg.kind());
+
+        // The peer stores the whole page (fetching covers) before it answers
+        let response = tokio::time::timeout(CATALOG_PAGE_ACK_TIMEOUT, async {
+            let framed = recv
+                .read_to_end(CATALOG_PAGE_ACK_MAX_BYTES)
+                .await
+                .map_err(|e| P2pError::Connection(e.to_string()))?;
+            // A peer over its page rate limit closes the stream unanswered
+            let Some((len_buf, body)) = framed.split_first_chunk::<4>() else {
+                return Err(if framed.is_empty() {
+                    P2pError::RateLimited(peer_id.to_string())
+                } else {
+                    P2pError::Connection("truncated catalog page ack".into())
+                });
+            };
+            if u32::from_be_bytes(*len_buf) as usize != body.len() {
+                return Err(P2pError::Connection("malformed catalog page ack".into()));
+            }
+            Ok(body.to_vec())
+        })
+        .await
+        .map_err(|_| P2pError::Connection("timed out waiting for catalog page ack".into()))??;
+
+        let reply: P2pMessage = serde_json::from_slice(&response)?;
+        self.stats.record_received(reply.kind());
+        match reply {
+            P2pMessage::CatalogSyncAck(ack) => Ok(ack),
+            other => Err(P2pError::Connection(format!(
+                "expected CatalogSyncAck, got {}",
+                other.kind()
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl prewarm::PrewarmTarget for Arc<P2pNode> {
+    async fn has_blob(&self, hash: Hash) -> bool {
+        P2pNode::has_blob(self, hash).await
+    }
+
+    async fn fetch_blob(&self, hash: Hash) -> Result<(), P2pError> {
+        self.get_or_fetch_track(hash).await.map(|_| ())
+    }
+}
+
+#[async_trait]
+impl TrackFetcher for Arc<P2pNode> {
+    async fn fetch_track(&self, peer_id: &str, hash: &str) -> Result<Bytes, P2pError> {
+        // Don't wait on connection timeouts for a peer that told us it left
+        if self.registry.has_departed(peer_id).await {
+            return Err(P2pError::Connection(format!(
+                "peer {peer_id} has shut down"
+            )));
+        }
+        let nid: EndpointId = peer_id
+            .parse()
+            .map_err(|_| P2pError::Connection(format!("invalid peer id: {}", peer_id)))?;
+        let blob_hash: Hash = hash
+            .parse()
+            .map_err(|_| P2pError::TrackNotFound(format!("invalid hash: {}", hash)))?;
+
+        self.fetch_track_resuming(nid, blob_hash).await
+    }
+
+    async fn check_blob_exists(&self, hash: &str) -> bool {
+        if let Ok(h) = hash.parse::<Hash>() {
+            self.has_blob(h).await
+        } else {
+            false
+        }
+    }
+
+    async fn peer_is_online(&self, peer_id: &str) -> bool {
+        self.registry
+            .get_peer(peer_id)
+            .await
+            .map(|p| p.is_online)
+            .unwrap_or(false)
+    }
+
+    async fn reject_source(&self, peer_id: &str) {
+        if let Ok(nid) = peer_id.parse::<EndpointId>() {
+            self.conn_pool.invalidate(&nid).await;
+        }
+    }
+
+    async fn record_recovery_attempt(&self, attempt: &RecoveryAttempt) {
+        if let Err(e) = save_recovery_attempt(&self.db, attempt).await {
+            warn!(hash = %attempt.content_hash, "failed to log recovery attempt: {e}");
+        }
+    }
+
+    async fn read_local_blob(&self, hash: &str) -> Result<Option<Bytes>, P2pError> {
+        let Ok(h) = hash.parse::<Hash>() else {
+            return Ok(None);
+        };
+        if !self.has_blob(h).await {
+            return Ok(None);
+        }
+        self.get_local_track(h).await.map(Some)
+    }
+
+    async fn delete_local_blob(&self, hash: &str) {
+        let Ok(h) = hash.parse::<Hash>() else {
+            return;
+        };
+        // Not read or served from now on, even before the sweep
+        self.corrupted_blobs.insert(h);
+        self.blob_cache.remove(&h).await;
+        // Without its cache tag the store's garbage collector reclaims the blob
+        if let Err(e) = self
+            .blob_store
+            .tags()
+            .delete(format!("p2p-cache-{h}"))
+            .await
+        {
+            warn!(%h, "failed to delete cache tag of corrupted blob: {e}");
+        }
+        // Don't offer peers bytes we no longer hold
+        self.unpublish_reseeded(&[h]).await;
+    }
+
+    async fn tracks_cleaned_up(&self, _report: &CleanupReport) {
+        // Bloom filters can't drop entries; the periodic task rebuilds it
+        self.search_index.mark_dirty().await;
+    }
+
+    async fn sweep_finished(&self, result: &BatchCheckResult) {
+        self.events.emit(P2pEvent::from(result));
+    }
+
+    async fn alternative_sources(&self, hash: &str) -> Vec<PeerTrackInfo> {
+        // Query remote_tracks that share the same content hash
+        use sea_orm::QueryFilter;
+
+        let remotes = remote_track::Entity::find()
+            .filter(remote_track::Column::RemoteUri.ends_with(format!("/{}", hash)))
+            .all(&self.db)
+            .await
+            .unwrap_or_default();
+
+        let mut sources = Vec::new();
+        for rt in remotes {
+            // Look up file_size from the linked local track record
+            let file_size = match rt.local_track_id {
+                Some(track_id) => track::Entity::find_by_id(track_id)
+                    .one(&self.db)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|t| t.file_size)
+                    .unwrap_or(0),
+                None => 0,
+            };
+            sources.push(self.peer_track_info(&rt, file_size).await);
+        }
+
+        sort_by_quality(&mut sources);
+        sources
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── sanitize_for_path ──────────────────────────────────────────────
+
+    #[test]
+    fn test_sanitize_for_path_basic() {
+        assert_eq!(sanitize_for_path("Hello World"), "Hello World");
+        assert_eq!(sanitize_for_path("my-track_01"), "my-track_01");
+    }
+
+    #[test]
+    fn test_sanitize_for_path_special_chars() {
+        assert_eq!(sanitize_for_path("AC/DC"), "AC_DC");
+        assert_eq!(sanitize_for_path("foo:bar*baz"), "foo_bar_baz");
+        assert_eq!(sanitize_for_path("a<b>c"), "a_b_c");
+    }
+
+    #[test]
+    fn test_sanitize_for_path_trim() {
+        assert_eq!(sanitize_for_path("  padded  "), "padded");
+    }
+
+    #[test]
+    fn test_sanitize_for_path_empty() {
+        assert_eq!(sanitize_for_path(""), "");
+    }
+
+    // ── P2pMessage serde roundtrip ────────────────────────────────────
+
+    #[test]
+    fn test_message_serde_ping() {
+        let msg = P2pMessage::Ping;
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        assert!(matches!(decoded, P2pMessage::Ping));
+    }
+
+    #[test]
+    fn test_message_serde_pong() {
+        let msg = P2pMessage::Pong {
+            node_id: "abc123".to_string(),
+            track_count: 42,
+            version: Some("0.1.5".to_string()),
+            capabilities: vec![CAP_SIGNED_ANNOUNCEMENTS.to_string()],
+        };
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        match decoded {
+            P2pMessage::Pong {
+                node_id,
+                track_count,
+                version,
+                capabilities,
+            } => {
+                assert_eq!(node_id, "abc123");
+                assert_eq!(track_count, 42);
+                assert_eq!(version, Some("0.1.5".to_string()));
+                assert_eq!(capabilities, vec!["signed-announcements".to_string()]);
+            }
+            _ => panic!("expected Pong"),
+        }
+    }
+
+    #[test]
+    fn test_pong_from_older_peer_has_no_capabilities() {
+        let json = r#"{"Pong":{"node_id":"abc123","track_count":3,"version":"0.1.4"}}"#;
+        match serde_json::from_str(json).unwrap() {
+            P2pMessage::Pong { capabilities, .. } => assert!(capabilities.is_empty()),
+            other => panic!("expected Pong, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_supported_capabilities() {
+        assert!(SUPPORTED_CAPABILITIES.contains(&CAP_SIGNED_ANNOUNCEMENTS));
+        assert!(SUPPORTED_CAPABILITIES.contains(&CAP_WAVEFORM_SYNC));
+        assert!(SUPPORTED_CAPABILITIES.contains(&CAP_VERIFIED_RANGES));
+    }
+
+    #[test]
+    fn test_message_serde_fetch_track() {
+        let msg = P2pMessage::FetchTrack {
+            hash: "deadbeef".to_string(),
+            token: None,
+        };
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        // No token field, so v1 peers read it as before
+        assert_eq!(
+            String::from_utf8(bytes.clone()).unwrap(),
+            r#"{"FetchTrack":{"hash":"deadbeef"}}"#
+        );
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        match decoded {
+            P2pMessage::FetchTrack { hash, token } => {
+                assert_eq!(hash, "deadbeef");
+                assert_eq!(token, None);
+            }
+            _ => panic!("expected FetchTrack"),
+        }
+    }
+
+    #[test]
+    fn test_message_serde_fetch_track_with_token() {
+        let msg = P2pMessage::FetchTrack {
+            hash: "deadbeef".to_string(),
+            token: Some("1700000000.abcd".to_string()),
+        };
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        match serde_json::from_slice(&bytes).unwrap() {
+            P2pMessage::FetchTrack { token, .. } => {
+                assert_eq!(token.as_deref(), Some("1700000000.abcd"))
+            }
+            other => panic!("expected FetchTrack, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_message_serde_peer_exchange() {
+        let msg = P2pMessage::PeerExchange {
+            peers: vec!["a".into(), "b".into(), "c".into()],
+        };
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        match decoded {
+            P2pMessage::PeerExchange { peers } => {
+                assert_eq!(peers.len(), 3);
+                assert_eq!(peers[0], "a");
+            }
+            _ => panic!("expected PeerExchange"),
+        }
+    }
+
+    #[test]
+    fn test_search_query_offset_defaults_to_zero() {
+        // Sent by peers that predate paged search
+        let json = r#"{"SearchQuery":{"request_id":"r","query":"q","limit":5}}"#;
+        match serde_json::from_str(json).unwrap() {
+            P2pMessage::SearchQuery { offset, limit, .. } => {
+                assert_eq!(offset, 0);
+                assert_eq!(limit, 5);
+            }
+            other => panic!("expected SearchQuery, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_message_serde_search_query() {
+        let msg = P2pMessage::SearchQuery {
+            request_id: "req-1".to_string(),
+            query: "bohemian rhapsody".to_string(),
+            limit: 10,
+            offset: 0,
+        };
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        match decoded {
+            P2pMessage::SearchQuery {
+                request_id,
+                query,
+                limit,
+                offset,
+            } => {
+                assert_eq!(offset, 0);
+                assert_eq!(request_id, "req-1");
+                assert_eq!(query, "bohemian rhapsody");
+                assert_eq!(limit, 10);
+            }
+            _ => panic!("expected SearchQuery"),
+        }
+    }
+
+    #[test]
+    fn test_message_serde_search_results() {
+        let msg = P2pMessage::SearchResults {
+            request_id: "req-1".to_string(),
+            results: vec![SearchResultItem {
+                hash: "h1".into(),
+                title: "Test Track".into(),
+                artist_name: "Test Artist".into(),
+                album_title: Some("Test Album".into()),
+                duration_secs: 180.0,
+                format: "FLAC".into(),
+                genre: Some("Rock".into()),
+                year: Some(2024),
+                bitrate: Some(320_000),
+                source_node: "node1".into(),
+                musicbrainz_id: None,
+                relevance: 0.95,
+                play_count: 0,
+                loudness_lufs: None,
+                dynamic_range: None,
+                encoding_quality: None,
+            }],
+            total: 1,
+        };
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        match decoded {
+            P2pMessage::SearchResults { results, total, .. } => {
+                assert_eq!(total, 1);
+                assert_eq!(results[0].title, "Test Track");
+                assert!((results[0].relevance - 0.95).abs() < f32::EPSILON);
+            }
+            _ => panic!("expected SearchResults"),
+        }
+    }
+
+    // ── TrackAnnouncement serde roundtrip ─────────────────────────────
+
+    #[test]
+    fn test_track_announcement_roundtrip() {
+        let ann = TrackAnnouncement {
+            hash: "abc".into(),
+            title: "Song".into(),
+            artist_name: "Artist".into(),
+            album_artist_name: None,
+            album_title: Some("Album".into()),
+            duration_secs: 240.5,
+            format: "MP3".into(),
+            file_size: 8_000_000,
+            genre: Some("Jazz".into()),
+            year: Some(1959),
+            track_number: Some(1),
+            disc_number: Some(1),
+            bitrate: Some(320_000),
+            sample_rate: Some(44_100),
+            origin_node: "node-xyz".into(),
+            cover_hash: Some("cover123".into()),
+            fingerprint: None,
+            waveform_data: None,
+            artist_image_hash: None,
+            artist_bio: None,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            signature: None,
+        };
+        let bytes = serde_json::to_vec(&ann).unwrap();
+        let decoded: TrackAnnouncement = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.title, "Song");
+        assert_eq!(decoded.file_size, 8_000_000);
+        assert_eq!(decoded.cover_hash.as_deref(), Some("cover123"));
+    }
+
+    #[test]
+    fn test_track_announcement_without_fingerprint_field() {
+        // Announcements from peers predating fingerprinting omit the field
+        let json = r#"{"hash":"h","title":"T","artist_name":"A","album_title":null,
+            "duration_secs":1.0,"format":"mp3","file_size":1,"genre":null,"year":null,
+            "track_number":null,"disc_number":null,"bitrate":null,"sample_rate":null,
+            "origin_node":"n","cover_hash":null}"#;
+        let ann: TrackAnnouncement = serde_json::from_str(json).unwrap();
+        assert!(ann.fingerprint.is_none());
+        assert!(ann.album_artist_name.is_none());
+    }
+
+    #[test]
+    fn test_track_announcement_artist_fields() {
+        let mut ann =
+            signed_announcement(&SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng()));
+        ann.artist_image_hash = Some("img123".into());
+        ann.artist_bio = Some("Jazz trumpeter.".into());
+        let decoded: TrackAnnouncement =
+            serde_json::from_slice(&serde_json::to_vec(&ann).unwrap()).unwrap();
+        assert_eq!(decoded.artist_image_hash.as_deref(), Some("img123"));
+        assert_eq!(decoded.artist_bio.as_deref(), Some("Jazz trumpeter."));
+
+        // Omitted entirely when unset, so older peers see the same JSON as before
+        ann.artist_image_hash = None;
+        ann.artist_bio = None;
+        let json = serde_json::to_string(&ann).unwrap();
+        assert!(!json.contains("artist_image_hash"));
+        assert!(!json.contains("artist_bio"));
+
+        let old = r#"{"hash":"h","title":"T","artist_name":"A","album_title":null,
+            "duration_secs":1.0,"format":"mp3","file_size":1,"genre":null,"year":null,
+            "track_number":null,"disc_number":null,"bitrate":null,"sample_rate":null,
+            "origin_node":"n","cover_hash":null}"#;
+        let decoded: TrackAnnouncement = serde_json::from_str(old).unwrap();
+        assert!(decoded.artist_image_hash.is_none());
+        assert!(decoded.artist_bio.is_none());
+    }
+
+    // ── TrackAnnouncement signatures ─────────────────────────────────
+
+    fn signed_announcement(key: &SecretKey) -> TrackAnnouncement {
+        let mut ann = TrackAnnouncement {
+            hash: "abc".into(),
+            title: "Song".into(),
+            artist_name: "Artist".into(),
+            album_artist_name: None,
+            album_title: None,
+            duration_secs: 180.25,
+            format: "FLAC".into(),
+            file_size: 1_000,
+            genre: None,
+            year: Some(2001),
+            track_number: None,
+            disc_number: None,
+            bitrate: None,
+            sample_rate: None,
+            origin_node: key.public().to_string(),
+            cover_hash: None,
+            fingerprint: None,
+            waveform_data: None,
+            artist_image_hash: None,
+            artist_bio: None,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            signature: None,
+        };
+        ann.sign(key);
+        ann
+    }
+
+    #[test]
+    fn test_signed_announcement_verifies_after_roundtrip() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let ann = signed_announcement(&key);
+        assert!(ann.signature.is_some());
+
+        let bytes = serde_json::to_vec(&ann).unwrap();
+        let decoded: TrackAnnouncement = serde_json::from_slice(&bytes).unwrap();
+        decoded.verify_signature().unwrap();
+    }
+
+    #[test]
+    fn test_tampered_announcement_rejected() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let mut ann = signed_announcement(&key);
+        ann.title = "Something Else".into();
+        assert!(matches!(
+            ann.verify_signature(),
+            Err(P2pError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_forged_origin_rejected() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let victim = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        // Signed with our own key but claiming to come from another node
+        let mut ann = signed_announcement(&key);
+        ann.origin_node = victim.public().to_string();
+        ann.sign(&key);
+        assert!(matches!(
+            ann.verify_signature(),
+            Err(P2pError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_signature_ignores_fields_outside_payload() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let ann = signed_announcement(&key);
+
+        // A newer peer's extra field, dropped on decode
+        let mut val = serde_json::to_value(&ann).unwrap();
+        val["added_later"] = serde_json::json!("x");
+        let decoded: TrackAnnouncement = serde_json::from_value(val).unwrap();
+        decoded.verify_signature().unwrap();
+
+        // Media a reseeder republishes from its own copy
+        let mut reseeded = ann.clone();
+        reseeded.cover_hash = Some("cover".into());
+        reseeded.waveform_data = Some(vec![0.5]);
+        reseeded.verify_signature().unwrap();
+    }
+
+    #[test]
+    fn test_signing_payload_is_versioned_and_unambiguous() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let ann = signed_announcement(&key);
+        assert!(ann
+            .signing_payload()
+            .starts_with(TrackAnnouncement::SIGNING_DOMAIN_V1));
+        let mut analysed = ann.clone();
+        analysed.loudness_lufs = Some(-14.0);
+        assert!(analysed
+            .signing_payload()
+            .starts_with(TrackAnnouncement::SIGNING_DOMAIN));
+
+        // Moving bytes between adjacent strings changes the payload
+        let mut shifted = ann.clone();
+        shifted.title = "SongA".into();
+        shifted.artist_name = "rtist".into();
+        assert_ne!(shifted.signing_payload(), ann.signing_payload());
+
+        let mut empty_genre = ann.clone();
+        empty_genre.genre = Some(String::new());
+        assert_ne!(empty_genre.signing_payload(), ann.signing_payload());
+    }
+
+    #[test]
+    fn test_v1_signed_announcement_verifies() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let mut ann = signed_announcement(&key);
+        // Signed by a peer that predates the analysis fields
+        let signature = key.sign(&ann.signing_payload_v1());
+        ann.signature = Some(data_encoding::BASE64.encode(&signature.to_bytes()));
+
+        let bytes = serde_json::to_vec(&ann).unwrap();
+        let decoded: TrackAnnouncement = serde_json::from_slice(&bytes).unwrap();
+        decoded.verify_signature().unwrap();
+
+        // The v1 layout does not cover analysis fields, so adding one
+        // invalidates the signature
+        let mut extended = decoded.clone();
+        extended.encoding_quality = Some("CBR 320kbps".into());
+        assert!(matches!(
+            extended.verify_signature(),
+            Err(P2pError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_v2_signed_announcement_covers_analysis() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let mut ann = signed_announcement(&key);
+        ann.loudness_lufs = Some(-9.5);
+        ann.dynamic_range = Some(6.0);
+        ann.encoding_quality = Some("VBR V0".into());
+        ann.sign(&key);
+        ann.verify_signature().unwrap();
+
+        let mut louder = ann.clone();
+        louder.loudness_lufs = Some(-6.0);
+        assert!(louder.verify_signature().is_err());
+
+        // Stripping the analysis does not downgrade to a valid v1 signature
+        let mut stripped = ann.clone();
+        stripped.loudness_lufs = None;
+        stripped.dynamic_range = None;
+        stripped.encoding_quality = None;
+        assert!(stripped.verify_signature().is_err());
+    }
+
+    #[test]
+    fn test_unsigned_announcement_accepted() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let mut ann = signed_announcement(&key);
+        ann.signature = None;
+        ann.verify_signature().unwrap();
+        // Unsigned announcements serialize exactly as older peers expect
+        let val = serde_json::to_value(&ann).unwrap();
+        assert!(val.get("signature").is_none());
+    }
+
+    #[test]
+    fn test_malformed_signature_rejected() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let mut ann = signed_announcement(&key);
+        ann.signature = Some("not base64!".into());
+        assert!(matches!(
+            ann.verify_signature(),
+            Err(P2pError::InvalidSignature(_))
+        ));
+    }
+
+    // ── P2pConfig defaults ───────────────────────────────────────────
+
+    #[test]
+    fn test_p2p_config_defaults() {
+        let cfg = P2pConfig::default();
+        assert_eq!(cfg.bind_port, 0);
+        assert!(cfg.enable_local_discovery);
+        assert!(cfg.enable_dht_discovery);
+        assert!(cfg.seed_peers.is_empty());
+        assert_eq!(cfg.blobs_dir, PathBuf::from("data/p2p/blobs"));
+    }
+
+    // ── SecretKey load/generate ──────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_load_or_generate_key_creates_new() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("secret_key");
+        let config = P2pConfig {
+            secret_key_path: key_path.clone(),
+            ..Default::default()
+        };
+
+        let key1 = P2pNode::load_or_generate_key(&config).await.unwrap();
+        assert!(key_path.exists());
+
+        // Loading again should return the same key
+        let key2 = P2pNode::load_or_generate_key(&config).await.unwrap();
+        assert_eq!(key1.public(), key2.public());
+    }
+
+    #[tokio::test]
+    async fn test_load_or_generate_key_hex_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("secret_key");
+        let config = P2pConfig {
+            secret_key_path: key_path.clone(),
+            ..Default::default()
+        };
+
+        let _key = P2pNode::load_or_generate_key(&config).await.unwrap();
+        let contents = tokio::fs::read_to_string(&key_path).await.unwrap();
+        // Should be 64 hex chars (32 bytes)
+        assert_eq!(contents.len(), 64);
+        assert!(contents.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[tokio::test]
+    async fn test_load_key_raw_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("secret_key");
+
+        // Write raw 32 bytes
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        tokio::fs::write(&key_path, key.to_bytes()).await.unwrap();
+
+        let config = P2pConfig {
+            secret_key_path: key_path,
+            ..Default::default()
+        };
+
+        let loaded = P2pNode::load_or_generate_key(&config).await.unwrap();
+        assert_eq!(loaded.public(), key.public());
+    }
+
+    // ── EndpointAddr / EndpointId integration ────────────────────────
+
+    #[test]
+    fn test_endpoint_addr_construction() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let id = key.public();
+        let addr = EndpointAddr::new(id);
+        assert_eq!(addr.id, id);
+        assert!(addr.ip_addrs().next().is_none());
+        assert!(addr.relay_urls().next().is_none());
+    }
+
+    #[test]
+    fn test_endpoint_id_display_roundtrip() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let id = key.public();
+        let id_str = id.to_string();
+        assert!(!id_str.is_empty());
+        let parsed: EndpointId = id_str.parse().unwrap();
+        assert_eq!(parsed, id);
+    }
+
+    // ── CatalogSync / CatalogDelta serde ─────────────────────────────
+
+    #[test]
+    fn test_message_serde_catalog_sync() {
+        let ann = TrackAnnouncement {
+            hash: "h1".into(),
+            title: "T".into(),
+            artist_name: "A".into(),
+            album_artist_name: None,
+            album_title: None,
+            duration_secs: 60.0,
+            format: "MP3".into(),
+            file_size: 1000,
+            genre: None,
+            year: None,
+            track_number: None,
+            disc_number: None,
+            bitrate: None,
+            sample_rate: None,
+            origin_node: "n".into(),
+            cover_hash: None,
+            fingerprint: None,
+            waveform_data: None,
+            artist_image_hash: None,
+            artist_bio: None,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            signature: None,
+        };
+        let msg = P2pMessage::CatalogSync(vec![ann.clone()]);
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        match decoded {
+            P2pMessage::CatalogSync(tracks) => {
+                assert_eq!(tracks.len(), 1);
+                assert_eq!(tracks[0].hash, "h1");
+            }
+            _ => panic!("expected CatalogSync"),
+        }
+    }
+
+    #[test]
+    fn test_message_serde_bloom_exchange() {
+        let bloom = BloomFilterData {
+            bitmap: vec![0u8; 128],
+            num_hashes: 7,
+            bitmap_bits: 1024,
+            sip_keys: [(1, 2), (3, 4)],
+            item_count: 42,
+            generation: 0,
+        };
+        let msg = P2pMessage::BloomExchange { bloom };
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        match decoded {
+            P2pMessage::BloomExchange { bloom } => {
+                assert_eq!(bloom.num_hashes, 7);
+                assert_eq!(bloom.bitmap_bits, 1024);
+                assert_eq!(bloom.bitmap.len(), 128);
+                assert_eq!(bloom.item_count, 42);
+                assert_eq!(bloom.sip_keys, [(1, 2), (3, 4)]);
+            }
+            _ => panic!("expected BloomExchange"),
+        }
+    }
+
+    // ── P2pMessage serde: TrackData ──────────────────────────────────
+
+    #[test]
+    fn test_message_serde_track_data() {
+        let msg = P2pMessage::TrackData {
+            hash: "abc123".to_string(),
+            size: 5_000_000,
+        };
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        match decoded {
+            P2pMessage::TrackData { hash, size } => {
+                assert_eq!(hash, "abc123");
+                assert_eq!(size, 5_000_000);
+            }
+            _ => panic!("expected TrackData"),
+        }
+    }
+
+    // ── P2pMessage serde: CatalogDelta ──────────────────────────────
+
+    #[test]
+    fn test_message_serde_catalog_delta() {
+        let since = chrono::Utc::now();
+        let ann = TrackAnnouncement {
+            hash: "delta1".into(),
+            title: "Delta Track".into(),
+            artist_name: "Delta Artist".into(),
+            album_artist_name: None,
+            album_title: None,
+            duration_secs: 120.0,
+            format: "OPUS".into(),
+            file_size: 2000,
+            genre: None,
+            year: None,
+            track_number: None,
+            disc_number: None,
+            bitrate: None,
+            sample_rate: None,
+            origin_node: "n".into(),
+            cover_hash: None,
+            fingerprint: None,
+            waveform_data: None,
+            artist_image_hash: None,
+            artist_bio: None,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            signature: None,
+        };
+        let msg = P2pMessage::CatalogDelta {
+            since,
+            tracks: vec![ann],
+        };
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        match decoded {
+            P2pMessage::CatalogDelta { since: s, tracks } => {
+                assert_eq!(tracks.len(), 1);
+                assert_eq!(tracks[0].hash, "delta1");
+                assert_eq!(tracks[0].format, "OPUS");
+                // chrono roundtrip may lose sub-nanosecond precision
+                assert!((s - since).num_seconds().abs() < 1);
+            }
+            _ => panic!("expected CatalogDelta"),
+        }
+    }
+
+    // ── P2pMessage serde: RequestCatalog ─────────────────────────────
+
+    #[test]
+    fn test_message_serde_request_catalog() {
+        let msg = P2pMessage::RequestCatalog;
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        match decoded {
+            P2pMessage::RequestCatalog => {} // expected
+            _ => panic!("expected RequestCatalog"),
+        }
+    }
+
+    #[test]
+    fn test_message_serde_request_catalog_roundtrip_json() {
+        let msg = P2pMessage::RequestCatalog;
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains("RequestCatalog"));
+        let decoded: P2pMessage = serde_json::from_str(&json).unwrap();
+        match decoded {
+            P2pMessage::RequestCatalog => {}
+            _ => panic!("expected RequestCatalog"),
+        }
+    }
+
+    // ── P2pMessage serde: AnnounceTrack ─────────────────────────────
+
+    #[test]
+    fn test_message_serde_announce_track() {
+        let ann = TrackAnnouncement {
+            hash: "ann1".into(),
+            title: "Announced".into(),
+            artist_name: "Announcer".into(),
+            album_artist_name: None,
+            album_title: Some("Album Ann".into()),
+            duration_secs: 200.0,
+            format: "FLAC".into(),
+            file_size: 50_000_000,
+            genre: Some("Electronic".into()),
+            year: Some(2024),
+            track_number: Some(3),
+            disc_number: Some(1),
+            bitrate: Some(1_411_000),
+            sample_rate: Some(48_000),
+            origin_node: "origin1".into(),
+            cover_hash: Some("cover_abc".into()),
+            fingerprint: None,
+            waveform_data: None,
+            artist_image_hash: None,
+            artist_bio: None,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            signature: None,
+        };
+        let msg = P2pMessage::AnnounceTrack(Box::new(ann));
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        match decoded {
+            P2pMessage::AnnounceTrack(a) => {
+                assert_eq!(a.hash, "ann1");
+                assert_eq!(a.title, "Announced");
+                assert_eq!(a.format, "FLAC");
+                assert_eq!(a.file_size, 50_000_000);
+                assert_eq!(a.year, Some(2024));
+                assert_eq!(a.bitrate, Some(1_411_000));
+                assert_eq!(a.sample_rate, Some(48_000));
+                assert_eq!(a.cover_hash.as_deref(), Some("cover_abc"));
+            }
+            _ => panic!("expected AnnounceTrack"),
+        }
+    }
+
+    // ── P2pMessage: empty collections ────────────────────────────────
+
+    #[test]
+    fn test_message_serde_peer_exchange_empty() {
+        let msg = P2pMessage::PeerExchange { peers: vec![] };
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        match decoded {
+            P2pMessage::PeerExchange { peers } => assert!(peers.is_empty()),
+            _ => panic!("expected PeerExchange"),
+        }
+    }
+
+    #[test]
+    fn test_message_serde_catalog_sync_empty() {
+        let msg = P2pMessage::CatalogSync(vec![]);
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        match decoded {
+            P2pMessage::CatalogSync(tracks) => assert!(tracks.is_empty()),
+            _ => panic!("expected CatalogSync"),
+        }
+    }
+
+    #[test]
+    fn test_message_serde_search_results_empty() {
+        let msg = P2pMessage::SearchResults {
+            request_id: "r".into(),
+            results: vec![],
+            total: 0,
+        };
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
+        match decoded {
+            P2pMessage::SearchResults { results, total, .. } => {
+                assert!(results.is_empty());
+                assert_eq!(total, 0);
             }
+            _ => panic!("expected SearchResults"),
         }
-
-        Ok(())
     }
 
-    /// Load or generate a persistent secret key.
-    /// The key is always persisted to ensure stable EndpointId across restarts.
-    async fn load_or_generate_key(config: &P2pConfig) -> Result<SecretKey, P2pError> {
-        let key_path = &config.secret_key_path;
-
-        if key_path.exists() {
-            let raw = tokio::fs::read(key_path).await.map_err(P2pError::Io)?;
-
-            // Try loading as raw 32 bytes first, then as hex string (backward compat)
-            if raw.len() == 32 {
-                let mut buf = [0u8; 32];
-                buf.copy_from_slice(&raw);
-                info!(path = %key_path.display(), "loaded existing P2P secret key (raw)");
-                return Ok(SecretKey::from_bytes(&buf));
-            }
+    // ── P2pMessage: invalid JSON deserialization ─────────────────────
 
-            let hex_str = String::from_utf8(raw)
-                .map_err(|e| P2pError::Endpoint(format!("invalid key file encoding: {e}")))?;
-            let decoded = data_encoding::HEXLOWER_PERMISSIVE
-                .decode(hex_str.trim().as_bytes())
-                .map_err(|e| P2pError::Endpoint(format!("invalid hex secret key: {e}")))?;
-            if decoded.len() != 32 {
-                return Err(P2pError::Endpoint(format!(
-                    "secret key has wrong length: {} (expected 32)",
-                    decoded.len()
-                )));
-            }
-            let mut buf = [0u8; 32];
-            buf.copy_from_slice(&decoded);
-            info!(path = %key_path.display(), "loaded existing P2P secret key");
-            return Ok(SecretKey::from_bytes(&buf));
-        }
+    #[test]
+    fn test_message_deserialize_invalid_json() {
+        let result = serde_json::from_str::<P2pMessage>("not valid json {{{");
+        assert!(result.is_err());
+    }
 
-        // Generate and save a new key
-        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
-        if let Some(parent) = key_path.parent() {
-            tokio::fs::create_dir_all(parent)
-                .await
-                .map_err(P2pError::Io)?;
-        }
-        tokio::fs::write(key_path, data_encoding::HEXLOWER.encode(&key.to_bytes()))
-            .await
-            .map_err(P2pError::Io)?;
-        info!(path = %key_path.display(), "generated and saved new P2P secret key");
-        Ok(key)
+    #[test]
+    fn test_message_deserialize_unknown_variant() {
+        let result = serde_json::from_str::<P2pMessage>(r#"{"UnknownVariant":{}}"#);
+        assert!(result.is_err());
     }
 
-    /// Wait for the relay connection to be established.
-    /// Returns the relay URL once connected, or None if unavailable.
-    async fn wait_for_relay(endpoint: &Endpoint) -> Option<String> {
-        // Poll endpoint addr until relay_url is available
-        for _ in 0..50 {
-            let addr = endpoint.addr();
-            if let Some(url) = addr.relay_urls().next() {
-                return Some(url.to_string());
-            }
-            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
-        }
-        None
+    #[test]
+    fn test_message_deserialize_missing_fields() {
+        // Pong without required fields
+        let result = serde_json::from_str::<P2pMessage>(r#"{"Pong":{}}"#);
+        assert!(result.is_err());
     }
 
-    /// Fetch a cover art blob from a peer, write it to the local audio storage,
-    /// and update the album's `cover_url` in the database.
-    async fn sync_cover_for_album(&self, album_id: Uuid, ann: &TrackAnnouncement, peer_id: &str) {
-        let cover_hash_str = match &ann.cover_hash {
-            Some(h) if !h.is_empty() => h.clone(),
-            _ => return,
-        };
+    // ── TrackAnnouncement edge cases ─────────────────────────────────
 
-        let blob_hash: Hash = match cover_hash_str.parse() {
-            Ok(h) => h,
-            Err(_) => {
-                warn!(hash = %cover_hash_str, "invalid cover hash");
-                return;
-            }
+    #[test]
+    fn test_track_announcement_all_optional_none() {
+        let ann = TrackAnnouncement {
+            hash: "h".into(),
+            title: "T".into(),
+            artist_name: "A".into(),
+            album_artist_name: None,
+            album_title: None,
+            duration_secs: 0.0,
+            format: "WAV".into(),
+            file_size: 0,
+            genre: None,
+            year: None,
+            track_number: None,
+            disc_number: None,
+            bitrate: None,
+            sample_rate: None,
+            origin_node: "n".into(),
+            cover_hash: None,
+            fingerprint: None,
+            waveform_data: None,
+            artist_image_hash: None,
+            artist_bio: None,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            signature: None,
         };
+        let bytes = serde_json::to_vec(&ann).unwrap();
+        let decoded: TrackAnnouncement = serde_json::from_slice(&bytes).unwrap();
+        assert!(decoded.album_title.is_none());
+        assert!(decoded.genre.is_none());
+        assert!(decoded.year.is_none());
+        assert!(decoded.track_number.is_none());
+        assert!(decoded.disc_number.is_none());
+        assert!(decoded.bitrate.is_none());
+        assert!(decoded.sample_rate.is_none());
+        assert!(decoded.cover_hash.is_none());
+    }
 
-        // Fetch the cover blob from the peer
-        let nid: EndpointId = match peer_id.parse() {
-            Ok(id) => id,
-            Err(_) => return,
+    #[test]
+    fn test_track_announcement_clone() {
+        let ann = TrackAnnouncement {
+            hash: "h".into(),
+            title: "T".into(),
+            artist_name: "A".into(),
+            album_artist_name: None,
+            album_title: None,
+            duration_secs: 1.0,
+            format: "MP3".into(),
+            file_size: 100,
+            genre: None,
+            year: None,
+            track_number: None,
+            disc_number: None,
+            bitrate: None,
+            sample_rate: None,
+            origin_node: "n".into(),
+            cover_hash: None,
+            fingerprint: None,
+            waveform_data: None,
+            artist_image_hash: None,
+            artist_bio: None,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            signature: None,
         };
+        let cloned = ann.clone();
+        assert_eq!(ann.hash, cloned.hash);
+        assert_eq!(ann.title, cloned.title);
+    }
 
-        let cover_data = if self.has_blob(blob_hash).await {
-            // Already have it locally
-            match self.get_local_track(blob_hash).await {
-                Ok(d) => d,
-                Err(_) => return,
-            }
-        } else {
-            let peer_addr = EndpointAddr::new(nid);
-            match self.fetch_track_from_peer(peer_addr, blob_hash).await {
-                Ok(data) => {
-                    // Store in blob store with a persistent tag
-                    match self
-                        .blob_store
-                        .blobs()
-                        .add_bytes(data.clone())
-                        .temp_tag()
-                        .await
-                    {
-                        Ok(outcome) => {
-                            let h = outcome.hash();
-                            let tag_name = format!("published-{}", h);
-                            if let Err(e) = self
-                                .blob_store
-                                .tags()
-                                .set(tag_name, HashAndFormat::raw(h))
-                                .await
-                            {
-                                warn!(hash = %cover_hash_str, "failed to set persistent tag for cover: {e}");
-                            }
-                            // Register hash so it can be served to peers
-                            self.published_hashes.write().await.insert(h.to_string());
-                        }
-                        Err(e) => warn!(hash = %cover_hash_str, "failed to import cover blob: {e}"),
-                    }
-                    data
-                }
-                Err(e) => {
-                    warn!(hash = %cover_hash_str, %peer_id, "failed to fetch cover: {e}");
-                    return;
-                }
-            }
+    #[test]
+    fn test_track_announcement_debug() {
+        let ann = TrackAnnouncement {
+            hash: "h".into(),
+            title: "T".into(),
+            artist_name: "A".into(),
+            album_artist_name: None,
+            album_title: None,
+            duration_secs: 1.0,
+            format: "MP3".into(),
+            file_size: 100,
+            genre: None,
+            year: None,
+            track_number: None,
+            disc_number: None,
+            bitrate: None,
+            sample_rate: None,
+            origin_node: "n".into(),
+            cover_hash: None,
+            fingerprint: None,
+            waveform_data: None,
+            artist_image_hash: None,
+            artist_bio: None,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            signature: None,
         };
+        let debug = format!("{:?}", ann);
+        assert!(debug.contains("TrackAnnouncement"));
+        assert!(debug.contains("hash"));
+    }
 
-        // Write cover to the audio storage filesystem
-        let artist_dir = sanitize_for_path(&ann.artist_name);
-        let album_dir = ann
-            .album_title
-            .as_deref()
-            .map(sanitize_for_path)
-            .unwrap_or_else(|| "singles".to_string());
-
-        let cover_dir = self
-            .cover_base_path()
-            .join("p2p-covers")
-            .join(&artist_dir)
-            .join(&album_dir);
-
-        if let Err(e) = tokio::fs::create_dir_all(&cover_dir).await {
-            warn!("failed to create cover directory: {e}");
-            return;
-        }
-
-        let cover_file = cover_dir.join("cover.jpg");
-        if let Err(e) = tokio::fs::write(&cover_file, &cover_data).await {
-            warn!("failed to write cover file: {e}");
-            return;
-        }
+    // ── SearchResultItem edge cases ──────────────────────────────────
 
-        // Build the relative path for the cover_url
-        let relative = cover_file
-            .strip_prefix(self.cover_base_path())
-            .unwrap_or(&cover_file)
-            .to_string_lossy()
-            .to_string();
+    #[test]
+    fn test_search_result_item_roundtrip() {
+        let item = SearchResultItem {
+            hash: "sr1".into(),
+            title: "Search Result".into(),
+            artist_name: "SR Artist".into(),
+            album_title: None,
+            duration_secs: 0.5,
+            format: "AAC".into(),
+            genre: None,
+            year: None,
+            bitrate: None,
+            source_node: "node-x".into(),
+            musicbrainz_id: Some("mb-123".into()),
+            relevance: 1.0,
+            play_count: 42,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+        };
+        let bytes = serde_json::to_vec(&item).unwrap();
+        let decoded: SearchResultItem = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.hash, "sr1");
+        assert_eq!(decoded.musicbrainz_id.as_deref(), Some("mb-123"));
+        assert!((decoded.relevance - 1.0).abs() < f32::EPSILON);
+        assert_eq!(decoded.play_count, 42);
+    }
 
-        let cover_url = format!("/api/media/{relative}");
+    #[test]
+    fn test_search_result_item_without_play_count() {
+        // Results from peers that predate play counts still decode
+        let json = r#"{"hash":"h","title":"T","artist_name":"A","album_title":null,
+            "duration_secs":1.0,"format":"MP3","genre":null,"year":null,"bitrate":null,
+            "source_node":"n","musicbrainz_id":null,"relevance":0.5}"#;
+        let item: SearchResultItem = serde_json::from_str(json).unwrap();
+        assert_eq!(item.play_count, 0);
+    }
 
-        // Update the album's cover_url in the database
-        let update = album::ActiveModel {
-            id: Set(album_id),
-            cover_url: Set(Some(cover_url.clone())),
-            ..Default::default()
+    #[test]
+    fn test_rank_search_results_prefers_popular_tracks() {
+        let item = |hash: &str, relevance: f32, play_count: i64| SearchResultItem {
+            hash: hash.into(),
+            title: "T".into(),
+            artist_name: "A".into(),
+            album_title: None,
+            duration_secs: 1.0,
+            format: "MP3".into(),
+            genre: None,
+            year: None,
+            bitrate: None,
+            source_node: "n".into(),
+            musicbrainz_id: None,
+            relevance,
+            play_count,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
         };
-        if let Err(e) = update.update(&self.db).await {
-            warn!(%album_id, "failed to update album cover_url: {e}");
-        } else {
-            info!(%album_id, %cover_url, "album cover synced from peer");
-        }
+        let mut results = vec![
+            item("quiet", 0.5, 0),
+            item("popular", 0.5, 1000),
+            item("relevant", 0.9, 0),
+            item("popular", 0.4, 1000),
+        ];
+        rank_search_results(&mut results);
+        let order: Vec<&str> = results.iter().map(|r| r.hash.as_str()).collect();
+        // Popularity breaks ties but does not bury a much better match
+        assert_eq!(order, ["relevant", "popular", "quiet"]);
+        assert!((results[1].relevance - 0.5).abs() < f32::EPSILON);
     }
-}
 
-// ── TrackFetcher implementation for P2pNode ──────────────────────────
+    #[test]
+    fn test_fuzzy_query_text() {
+        assert_eq!(
+            fuzzy_query_text("  bohemien   rapsody "),
+            "bohemien rapsody"
+        );
+        // Too short to compare by trigrams
+        assert_eq!(fuzzy_query_text("ab"), "");
+        assert_eq!(fuzzy_query_text("   "), "");
+        assert_eq!(fuzzy_query_text("abc"), "abc");
+    }
 
-#[async_trait]
-impl TrackFetcher for Arc<P2pNode> {
-    async fn fetch_track(&self, peer_id: &str, hash: &str) -> Result<Bytes, P2pError> {
-        let nid: EndpointId = peer_id
-            .parse()
-            .map_err(|_| P2pError::Connection(format!("invalid peer id: {}", peer_id)))?;
-        let peer_addr = EndpointAddr::new(nid);
+    #[test]
+    fn test_search_result_item_clone() {
+        let item = SearchResultItem {
+            hash: "h".into(),
+            title: "T".into(),
+            artist_name: "A".into(),
+            album_title: None,
+            duration_secs: 1.0,
+            format: "MP3".into(),
+            genre: None,
+            year: None,
+            bitrate: None,
+            source_node: "n".into(),
+            musicbrainz_id: None,
+            relevance: 0.0,
+            play_count: 0,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+        };
+        let cloned = item.clone();
+        assert_eq!(item.hash, cloned.hash);
+    }
 
-        let blob_hash: Hash = hash
-            .parse()
-            .map_err(|_| P2pError::TrackNotFound(format!("invalid hash: {}", hash)))?;
+    // ── sanitize_for_path additional edge cases ──────────────────────
 
-        self.fetch_track_from_peer(peer_addr, blob_hash).await
+    #[test]
+    fn test_sanitize_for_path_unicode() {
+        // Unicode letters (accented) should be kept as they're alphanumeric
+        assert_eq!(sanitize_for_path("Étoile"), "Étoile");
+        assert_eq!(sanitize_for_path("日本語"), "日本語");
+        assert_eq!(sanitize_for_path("Ñoño"), "Ñoño");
     }
 
-    async fn check_blob_exists(&self, hash: &str) -> bool {
-        if let Ok(h) = hash.parse::<Hash>() {
-            self.has_blob(h).await
-        } else {
-            false
-        }
+    #[test]
+    fn test_sanitize_for_path_mixed_special() {
+        assert_eq!(sanitize_for_path("a/b\\c:d*e?f"), "a_b_c_d_e_f");
     }
 
-    async fn peer_is_online(&self, peer_id: &str) -> bool {
-        self.registry
-            .get_peer(peer_id)
-            .await
-            .map(|p| p.is_online)
-            .unwrap_or(false)
+    #[test]
+    fn test_sanitize_for_path_only_special() {
+        // All special chars — trimmed result is empty
+        assert_eq!(sanitize_for_path("///"), "___");
     }
 
-    async fn alternative_sources(&self, hash: &str) -> Vec<PeerTrackInfo> {
-        // Query remote_tracks that share the same content hash
-        use sea_orm::QueryFilter;
-
-        let remotes = remote_track::Entity::find()
-            .filter(remote_track::Column::RemoteUri.ends_with(format!("/{}", hash)))
-            .all(&self.db)
-            .await
-            .unwrap_or_default();
+    #[test]
+    fn test_sanitize_for_path_preserves_hyphens_underscores_spaces() {
+        assert_eq!(sanitize_for_path("my track-name_01"), "my track-name_01");
+    }
 
-        let mut sources = Vec::new();
-        for rt in remotes {
-            let origin = rt
-                .instance_domain
-                .strip_prefix("p2p://")
-                .unwrap_or(&rt.instance_domain)
-                .to_string();
-
-            let is_online = self
-                .registry
-                .get_peer(&origin)
-                .await
-                .map(|p| p.is_online)
-                .unwrap_or(false);
+    #[test]
+    fn test_sanitize_for_path_emoji() {
+        // Emojis are not alphanumeric, should be replaced
+        assert_eq!(sanitize_for_path("🎵music🎵"), "_music_");
+    }
 
-            // Look up file_size from the linked local track record
-            let file_size = match rt.local_track_id {
-                Some(track_id) => track::Entity::find_by_id(track_id)
-                    .one(&self.db)
-                    .await
-                    .ok()
-                    .flatten()
-                    .map(|t| t.file_size)
-                    .unwrap_or(0),
-                None => 0,
-            };
+    // ── P2pConfig::from_env ──────────────────────────────────────────
 
-            sources.push(PeerTrackInfo {
-                peer_id: origin,
-                format: rt.format.unwrap_or_default(),
-                bitrate: rt.bitrate,
-                sample_rate: rt.sample_rate,
-                is_online,
-                file_size,
-            });
-        }
+    #[test]
+    fn test_config_from_env_defaults() {
+        // Clear all P2P env vars to test defaults
+        std::env::remove_var("P2P_BLOBS_DIR");
+        std::env::remove_var("P2P_SECRET_KEY_PATH");
+        std::env::remove_var("P2P_BIND_PORT");
+        std::env::remove_var("P2P_LOCAL_DISCOVERY");
+        std::env::remove_var("P2P_DHT_DISCOVERY");
+        std::env::remove_var("P2P_SEED_PEERS");
+        std::env::remove_var("AUDIO_STORAGE_PATH");
+        std::env::remove_var("METADATA_STORAGE_PATH");
+        std::env::remove_var("P2P_MAX_UPLOAD_BPS");
+        std::env::remove_var("P2P_MAX_UPLOAD_BPS_PER_PEER");
+        std::env::remove_var("P2P_BLOOM_PERSIST_PATH");
+        std::env::remove_var("P2P_MAX_CONCURRENT_CONNECTIONS");
+        std::env::remove_var("P2P_MAX_CONNECTIONS_PER_IP");
+        std::env::remove_var("P2P_PEX_BATCH_SIZE");
+        std::env::remove_var("P2P_GOSSIP_TTL");
+        std::env::remove_var("P2P_POOL_KEEPALIVE_SECS");
+        std::env::remove_var("P2P_POOL_IDLE_TTL_SECS");
+        std::env::remove_var("P2P_MAX_PEER_QUEUE_DEPTH");
+        std::env::remove_var("P2P_KEEP_ALIVE_SECS");
+        std::env::remove_var("P2P_MAX_MESSAGE_BYTES");
+        std::env::remove_var("P2P_RELAY_URLS");
+        std::env::remove_var("P2P_DISABLE_DEFAULT_DISCOVERY");
+        std::env::remove_var("P2P_DNS_DISCOVERY_URL");
+        std::env::remove_var("P2P_HIDE_BLOCKED_TRACKS");
+        std::env::remove_var("P2P_BLOOM_TARGET_FPR");
+        std::env::remove_var("P2P_BLOOM_FPR");
+        std::env::remove_var("P2P_BLOOM_EXPECTED_ITEMS");
+        std::env::remove_var("P2P_PEER_EVICTION_THRESHOLD");
+        std::env::remove_var("P2P_PEER_OFFLINE_RETENTION_DAYS");
+        std::env::remove_var("P2P_SEARCH_SIMILARITY_THRESHOLD");
+        std::env::remove_var("P2P_SEARCH_CACHE_TTL_SECS");
+        std::env::remove_var("P2P_SEARCH_CACHE_MAX_ENTRIES");
+        std::env::remove_var("P2P_BLOB_GC_ENABLED");
 
-        sources
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.blobs_dir, PathBuf::from("data/p2p/blobs"));
+        assert_eq!(cfg.secret_key_path, PathBuf::from("data/p2p/secret_key"));
+        assert_eq!(cfg.bind_port, 0);
+        assert!(cfg.enable_local_discovery);
+        assert!(cfg.enable_dht_discovery);
+        assert!(cfg.seed_peers.is_empty());
+        assert_eq!(cfg.audio_storage_path, PathBuf::from("data/music"));
+        assert!(cfg.metadata_storage_path.is_none());
+        assert_eq!(cfg.max_upload_bps, 0);
+        assert_eq!(cfg.max_upload_bps_per_peer, 0);
+        assert_eq!(cfg.bloom_persist_path, PathBuf::from("data/p2p/bloom.bin"));
+        assert_eq!(cfg.max_concurrent_connections, 64);
+        assert_eq!(cfg.max_connections_per_ip, 4);
+        assert_eq!(cfg.pex_batch_size, 10);
+        assert_eq!(cfg.gossip_ttl, 3);
+        assert_eq!(cfg.pool_keepalive_secs, 15);
+        assert_eq!(cfg.pool_idle_ttl_secs, 60);
+        assert_eq!(cfg.max_peer_queue_depth, 500);
+        assert_eq!(cfg.catalog_sync_pages_per_minute, 60);
+        assert_eq!(cfg.keep_alive_interval_secs, 30);
+        assert_eq!(cfg.max_message_bytes, 64 * 1024 * 1024);
+        assert!(cfg.relay_urls.is_empty());
+        assert!(!cfg.disable_default_discovery);
+        assert!(cfg.dns_discovery_url.is_none());
+        assert!(cfg.hide_blocked_tracks);
+        assert_eq!(cfg.bloom_fpr, 0.01);
+        assert_eq!(cfg.bloom_expected_items, 100_000);
+        assert_eq!(cfg.peer_eviction_threshold, 10);
+        assert_eq!(cfg.peer_offline_retention_days, 30);
+        assert_eq!(cfg.max_peers, 500);
+        assert_eq!(cfg.search_similarity_threshold, 0.3);
+        assert_eq!(cfg.search_cache_ttl_secs, 60);
+        assert_eq!(cfg.search_cache_max_entries, 256);
+        assert!(!cfg.blob_gc_enabled);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    // ── sanitize_for_path ──────────────────────────────────────────────
 
     #[test]
-    fn test_sanitize_for_path_basic() {
-        assert_eq!(sanitize_for_path("Hello World"), "Hello World");
-        assert_eq!(sanitize_for_path("my-track_01"), "my-track_01");
+    fn test_config_from_env_custom_blobs_dir() {
+        std::env::set_var("P2P_BLOBS_DIR", "/tmp/custom/blobs");
+        std::env::remove_var("P2P_SECRET_KEY_PATH");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.blobs_dir, PathBuf::from("/tmp/custom/blobs"));
+        // secret_key_path should derive from blobs_dir parent
+        assert_eq!(cfg.secret_key_path, PathBuf::from("/tmp/custom/secret_key"));
+        std::env::remove_var("P2P_BLOBS_DIR");
     }
 
     #[test]
-    fn test_sanitize_for_path_special_chars() {
-        assert_eq!(sanitize_for_path("AC/DC"), "AC_DC");
-        assert_eq!(sanitize_for_path("foo:bar*baz"), "foo_bar_baz");
-        assert_eq!(sanitize_for_path("a<b>c"), "a_b_c");
+    fn test_config_from_env_custom_secret_key() {
+        std::env::set_var("P2P_SECRET_KEY_PATH", "/my/key");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.secret_key_path, PathBuf::from("/my/key"));
+        std::env::remove_var("P2P_SECRET_KEY_PATH");
     }
 
     #[test]
-    fn test_sanitize_for_path_trim() {
-        assert_eq!(sanitize_for_path("  padded  "), "padded");
+    fn test_config_from_env_bind_port() {
+        std::env::set_var("P2P_BIND_PORT", "4433");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.bind_port, 4433);
+        std::env::remove_var("P2P_BIND_PORT");
     }
 
     #[test]
-    fn test_sanitize_for_path_empty() {
-        assert_eq!(sanitize_for_path(""), "");
+    fn test_config_from_env_bind_port_invalid() {
+        std::env::set_var("P2P_BIND_PORT", "not_a_number");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.bind_port, 0); // fallback
+        std::env::remove_var("P2P_BIND_PORT");
     }
 
-    // ── P2pMessage serde roundtrip ────────────────────────────────────
+    #[test]
+    fn test_config_from_env_local_discovery_false() {
+        std::env::set_var("P2P_LOCAL_DISCOVERY", "false");
+        let cfg = P2pConfig::from_env();
+        assert!(!cfg.enable_local_discovery);
+        std::env::remove_var("P2P_LOCAL_DISCOVERY");
+    }
 
     #[test]
-    fn test_message_serde_ping() {
-        let msg = P2pMessage::Ping;
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        assert!(matches!(decoded, P2pMessage::Ping));
+    fn test_config_from_env_local_discovery_case_insensitive() {
+        std::env::set_var("P2P_LOCAL_DISCOVERY", "TRUE");
+        let cfg = P2pConfig::from_env();
+        assert!(cfg.enable_local_discovery);
+        std::env::remove_var("P2P_LOCAL_DISCOVERY");
     }
 
     #[test]
-    fn test_message_serde_pong() {
-        let msg = P2pMessage::Pong {
-            node_id: "abc123".to_string(),
-            track_count: 42,
-            version: Some("0.1.5".to_string()),
-        };
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        match decoded {
-            P2pMessage::Pong {
-                node_id,
-                track_count,
-                version,
-            } => {
-                assert_eq!(node_id, "abc123");
-                assert_eq!(track_count, 42);
-                assert_eq!(version, Some("0.1.5".to_string()));
-            }
-            _ => panic!("expected Pong"),
-        }
+    fn test_config_from_env_dht_discovery_true() {
+        std::env::set_var("P2P_DHT_DISCOVERY", "true");
+        let cfg = P2pConfig::from_env();
+        assert!(cfg.enable_dht_discovery);
+        std::env::remove_var("P2P_DHT_DISCOVERY");
     }
 
     #[test]
-    fn test_message_serde_fetch_track() {
-        let msg = P2pMessage::FetchTrack {
-            hash: "deadbeef".to_string(),
-        };
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        match decoded {
-            P2pMessage::FetchTrack { hash } => assert_eq!(hash, "deadbeef"),
-            _ => panic!("expected FetchTrack"),
-        }
+    fn test_config_from_env_dht_discovery_case_insensitive() {
+        std::env::set_var("P2P_DHT_DISCOVERY", "TRUE");
+        let cfg = P2pConfig::from_env();
+        assert!(cfg.enable_dht_discovery);
+        std::env::remove_var("P2P_DHT_DISCOVERY");
     }
 
     #[test]
-    fn test_message_serde_peer_exchange() {
-        let msg = P2pMessage::PeerExchange {
-            peers: vec!["a".into(), "b".into(), "c".into()],
-        };
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        match decoded {
-            P2pMessage::PeerExchange { peers } => {
-                assert_eq!(peers.len(), 3);
-                assert_eq!(peers[0], "a");
-            }
-            _ => panic!("expected PeerExchange"),
-        }
+    fn test_config_from_env_dht_discovery_false() {
+        std::env::set_var("P2P_DHT_DISCOVERY", "false");
+        let cfg = P2pConfig::from_env();
+        assert!(!cfg.enable_dht_discovery);
+        std::env::remove_var("P2P_DHT_DISCOVERY");
     }
 
     #[test]
-    fn test_message_serde_search_query() {
-        let msg = P2pMessage::SearchQuery {
-            request_id: "req-1".to_string(),
-            query: "bohemian rhapsody".to_string(),
-            limit: 10,
-        };
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        match decoded {
-            P2pMessage::SearchQuery {
-                request_id,
-                query,
-                limit,
-            } => {
-                assert_eq!(request_id, "req-1");
-                assert_eq!(query, "bohemian rhapsody");
-                assert_eq!(limit, 10);
-            }
-            _ => panic!("expected SearchQuery"),
-        }
+    fn test_config_from_env_seed_peers() {
+        std::env::set_var("P2P_SEED_PEERS", "peer1,peer2, peer3 ");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.seed_peers, vec!["peer1", "peer2", "peer3"]);
+        std::env::remove_var("P2P_SEED_PEERS");
     }
 
     #[test]
-    fn test_message_serde_search_results() {
-        let msg = P2pMessage::SearchResults {
-            request_id: "req-1".to_string(),
-            results: vec![SearchResultItem {
-                hash: "h1".into(),
-                title: "Test Track".into(),
-                artist_name: "Test Artist".into(),
-                album_title: Some("Test Album".into()),
-                duration_secs: 180.0,
-                format: "FLAC".into(),
-                genre: Some("Rock".into()),
-                year: Some(2024),
-                bitrate: Some(320_000),
-                source_node: "node1".into(),
-                musicbrainz_id: None,
-                relevance: 0.95,
-            }],
-            total: 1,
-        };
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        match decoded {
-            P2pMessage::SearchResults { results, total, .. } => {
-                assert_eq!(total, 1);
-                assert_eq!(results[0].title, "Test Track");
-                assert!((results[0].relevance - 0.95).abs() < f32::EPSILON);
-            }
-            _ => panic!("expected SearchResults"),
-        }
+    fn test_config_from_env_seed_peers_empty_entries() {
+        std::env::set_var("P2P_SEED_PEERS", "peer1,,, ,peer2");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.seed_peers, vec!["peer1", "peer2"]);
+        std::env::remove_var("P2P_SEED_PEERS");
     }
 
-    // ── TrackAnnouncement serde roundtrip ─────────────────────────────
-
     #[test]
-    fn test_track_announcement_roundtrip() {
-        let ann = TrackAnnouncement {
-            hash: "abc".into(),
-            title: "Song".into(),
-            artist_name: "Artist".into(),
-            album_artist_name: None,
-            album_title: Some("Album".into()),
-            duration_secs: 240.5,
-            format: "MP3".into(),
-            file_size: 8_000_000,
-            genre: Some("Jazz".into()),
-            year: Some(1959),
-            track_number: Some(1),
-            disc_number: Some(1),
-            bitrate: Some(320_000),
-            sample_rate: Some(44_100),
-            origin_node: "node-xyz".into(),
-            cover_hash: Some("cover123".into()),
-        };
-        let bytes = serde_json::to_vec(&ann).unwrap();
-        let decoded: TrackAnnouncement = serde_json::from_slice(&bytes).unwrap();
-        assert_eq!(decoded.title, "Song");
-        assert_eq!(decoded.file_size, 8_000_000);
-        assert_eq!(decoded.cover_hash.as_deref(), Some("cover123"));
+    fn test_config_from_env_seed_peers_with_addrs() {
+        std::env::set_var(
+            "P2P_SEED_PEERS",
+            "peer1@192.168.1.10:4433, 10.0.0.2:4433,peer2,peer3@[::1]:4433",
+        );
+        let cfg = P2pConfig::from_env();
+        assert_eq!(
+            cfg.seed_peers,
+            vec![
+                "peer1@192.168.1.10:4433,10.0.0.2:4433",
+                "peer2",
+                "peer3@[::1]:4433"
+            ]
+        );
+        std::env::remove_var("P2P_SEED_PEERS");
     }
 
-    // ── P2pConfig defaults ───────────────────────────────────────────
-
     #[test]
-    fn test_p2p_config_defaults() {
-        let cfg = P2pConfig::default();
-        assert_eq!(cfg.bind_port, 0);
-        assert!(cfg.enable_local_discovery);
-        assert!(cfg.enable_dht_discovery);
-        assert!(cfg.seed_peers.is_empty());
-        assert_eq!(cfg.blobs_dir, PathBuf::from("data/p2p/blobs"));
+    fn test_split_seed_peers_stray_addr() {
+        // An address not following an `id@` entry stays a separate (invalid) entry
+        assert_eq!(
+            split_seed_peers("peer1,10.0.0.2:4433"),
+            vec!["peer1", "10.0.0.2:4433"]
+        );
     }
 
-    // ── SecretKey load/generate ──────────────────────────────────────
+    #[test]
+    fn test_parse_peer_addr_formats() {
+        let id = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng()).public();
 
-    #[tokio::test]
-    async fn test_load_or_generate_key_creates_new() {
-        let dir = tempfile::tempdir().unwrap();
-        let key_path = dir.path().join("secret_key");
-        let config = P2pConfig {
-            secret_key_path: key_path.clone(),
-            ..Default::default()
-        };
+        let bare = parse_peer_addr(&format!(" {id} ")).unwrap();
+        assert_eq!(bare.id, id);
+        assert_eq!(bare.ip_addrs().count(), 0);
 
-        let key1 = P2pNode::load_or_generate_key(&config).await.unwrap();
-        assert!(key_path.exists());
+        let one = parse_peer_addr(&format!("{id}@192.168.1.10:4433")).unwrap();
+        assert_eq!(one.id, id);
+        assert_eq!(
+            one.ip_addrs().copied().collect::<Vec<_>>(),
+            vec!["192.168.1.10:4433".parse().unwrap()]
+        );
 
-        // Loading again should return the same key
-        let key2 = P2pNode::load_or_generate_key(&config).await.unwrap();
-        assert_eq!(key1.public(), key2.public());
+        let many = parse_peer_addr(&format!("{id}@192.168.1.10:4433, [::1]:4433")).unwrap();
+        assert_eq!(many.ip_addrs().count(), 2);
     }
 
-    #[tokio::test]
-    async fn test_load_or_generate_key_hex_format() {
-        let dir = tempfile::tempdir().unwrap();
-        let key_path = dir.path().join("secret_key");
-        let config = P2pConfig {
-            secret_key_path: key_path.clone(),
-            ..Default::default()
-        };
+    #[test]
+    fn test_parse_peer_addr_rejects_malformed() {
+        let id = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng()).public();
+        for bad in [
+            "not-an-id".to_string(),
+            "not-an-id@10.0.0.1:4433".to_string(),
+            format!("{id}@"),
+            format!("{id}@10.0.0.1"),
+            format!("{id}@host.lan:4433"),
+            "10.0.0.2:4433".to_string(),
+        ] {
+            let err = parse_peer_addr(&bad).unwrap_err();
+            assert!(
+                matches!(err, P2pError::InvalidPeerAddr(_)),
+                "{bad}: unexpected error {err}"
+            );
+        }
+        let err = parse_peer_addr(&format!("{id}@10.0.0.1")).unwrap_err();
+        assert!(err.to_string().contains("expected ip:port"), "{err}");
+    }
 
-        let _key = P2pNode::load_or_generate_key(&config).await.unwrap();
-        let contents = tokio::fs::read_to_string(&key_path).await.unwrap();
-        // Should be 64 hex chars (32 bytes)
-        assert_eq!(contents.len(), 64);
-        assert!(contents.chars().all(|c| c.is_ascii_hexdigit()));
+    #[test]
+    fn test_config_from_env_audio_storage() {
+        std::env::set_var("AUDIO_STORAGE_PATH", "/music/storage");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.audio_storage_path, PathBuf::from("/music/storage"));
+        std::env::remove_var("AUDIO_STORAGE_PATH");
     }
 
-    #[tokio::test]
-    async fn test_load_key_raw_bytes() {
-        let dir = tempfile::tempdir().unwrap();
-        let key_path = dir.path().join("secret_key");
+    #[test]
+    fn test_config_from_env_metadata_storage() {
+        std::env::set_var("METADATA_STORAGE_PATH", "/metadata/covers");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(
+            cfg.metadata_storage_path,
+            Some(PathBuf::from("/metadata/covers"))
+        );
+        std::env::remove_var("METADATA_STORAGE_PATH");
+    }
 
-        // Write raw 32 bytes
-        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
-        tokio::fs::write(&key_path, key.to_bytes()).await.unwrap();
+    #[test]
+    fn test_config_from_env_upload_limits() {
+        std::env::set_var("P2P_MAX_UPLOAD_BPS", "1048576");
+        std::env::set_var("P2P_MAX_UPLOAD_BPS_PER_PEER", "262144");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.max_upload_bps, 1_048_576);
+        assert_eq!(cfg.max_upload_bps_per_peer, 262_144);
+        std::env::remove_var("P2P_MAX_UPLOAD_BPS");
+        std::env::remove_var("P2P_MAX_UPLOAD_BPS_PER_PEER");
+    }
 
-        let config = P2pConfig {
-            secret_key_path: key_path,
-            ..Default::default()
-        };
+    #[test]
+    fn test_config_from_env_bloom_persist_path() {
+        std::env::set_var("P2P_BLOOM_PERSIST_PATH", "/var/lib/soundtime/bloom.bin");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(
+            cfg.bloom_persist_path,
+            PathBuf::from("/var/lib/soundtime/bloom.bin")
+        );
+        std::env::remove_var("P2P_BLOOM_PERSIST_PATH");
+    }
 
-        let loaded = P2pNode::load_or_generate_key(&config).await.unwrap();
-        assert_eq!(loaded.public(), key.public());
+    #[test]
+    fn test_config_from_env_connection_limits() {
+        std::env::set_var("P2P_MAX_CONCURRENT_CONNECTIONS", "128");
+        std::env::set_var("P2P_MAX_CONNECTIONS_PER_IP", "8");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.max_concurrent_connections, 128);
+        assert_eq!(cfg.max_connections_per_ip, 8);
+        std::env::remove_var("P2P_MAX_CONCURRENT_CONNECTIONS");
+        std::env::remove_var("P2P_MAX_CONNECTIONS_PER_IP");
     }
 
-    // ── EndpointAddr / EndpointId integration ────────────────────────
+    #[test]
+    fn test_config_from_env_zero_concurrent_connections_uses_default() {
+        // A zero-permit semaphore would refuse every connection
+        std::env::set_var("P2P_MAX_CONCURRENT_CONNECTIONS", "0");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(
+            cfg.max_concurrent_connections,
+            MAX_CONCURRENT_P2P_CONNECTIONS
+        );
+        std::env::remove_var("P2P_MAX_CONCURRENT_CONNECTIONS");
+    }
 
     #[test]
-    fn test_endpoint_addr_construction() {
-        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
-        let id = key.public();
-        let addr = EndpointAddr::new(id);
-        assert_eq!(addr.id, id);
-        assert!(addr.ip_addrs().next().is_none());
-        assert!(addr.relay_urls().next().is_none());
+    fn test_config_from_env_pex_batch_size() {
+        std::env::set_var("P2P_PEX_BATCH_SIZE", "25");
+        assert_eq!(P2pConfig::from_env().pex_batch_size, 25);
+        std::env::set_var("P2P_PEX_BATCH_SIZE", "0");
+        assert_eq!(P2pConfig::from_env().pex_batch_size, DEFAULT_PEX_BATCH_SIZE);
+        std::env::remove_var("P2P_PEX_BATCH_SIZE");
     }
 
     #[test]
-    fn test_endpoint_id_display_roundtrip() {
-        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
-        let id = key.public();
-        let id_str = id.to_string();
-        assert!(!id_str.is_empty());
-        let parsed: EndpointId = id_str.parse().unwrap();
-        assert_eq!(parsed, id);
+    fn test_config_from_env_gossip_ttl() {
+        std::env::set_var("P2P_GOSSIP_TTL", "0");
+        assert_eq!(P2pConfig::from_env().gossip_ttl, 0);
+        std::env::set_var("P2P_GOSSIP_TTL", "300");
+        assert_eq!(P2pConfig::from_env().gossip_ttl, DEFAULT_GOSSIP_TTL);
+        std::env::remove_var("P2P_GOSSIP_TTL");
     }
 
-    // ── CatalogSync / CatalogDelta serde ─────────────────────────────
+    #[test]
+    fn test_config_from_env_pool_keepalive() {
+        std::env::set_var("P2P_POOL_KEEPALIVE_SECS", "0");
+        std::env::set_var("P2P_POOL_IDLE_TTL_SECS", "120");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.pool_keepalive_secs, 0);
+        assert_eq!(cfg.pool_idle_ttl_secs, 120);
+        // A zero TTL would evict every connection right after use
+        std::env::set_var("P2P_POOL_IDLE_TTL_SECS", "0");
+        assert_eq!(P2pConfig::from_env().pool_idle_ttl_secs, MAX_IDLE_SECS);
+        std::env::remove_var("P2P_POOL_KEEPALIVE_SECS");
+        std::env::remove_var("P2P_POOL_IDLE_TTL_SECS");
+    }
 
     #[test]
-    fn test_message_serde_catalog_sync() {
-        let ann = TrackAnnouncement {
-            hash: "h1".into(),
-            title: "T".into(),
-            artist_name: "A".into(),
-            album_artist_name: None,
-            album_title: None,
-            duration_secs: 60.0,
-            format: "MP3".into(),
-            file_size: 1000,
-            genre: None,
-            year: None,
-            track_number: None,
-            disc_number: None,
-            bitrate: None,
-            sample_rate: None,
-            origin_node: "n".into(),
-            cover_hash: None,
-        };
-        let msg = P2pMessage::CatalogSync(vec![ann.clone()]);
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        match decoded {
-            P2pMessage::CatalogSync(tracks) => {
-                assert_eq!(tracks.len(), 1);
-                assert_eq!(tracks[0].hash, "h1");
-            }
-            _ => panic!("expected CatalogSync"),
-        }
+    fn test_config_from_env_max_peer_queue_depth() {
+        std::env::set_var("P2P_MAX_PEER_QUEUE_DEPTH", "50");
+        assert_eq!(P2pConfig::from_env().max_peer_queue_depth, 50);
+        std::env::set_var("P2P_MAX_PEER_QUEUE_DEPTH", "0");
+        assert_eq!(
+            P2pConfig::from_env().max_peer_queue_depth,
+            DEFAULT_MAX_QUEUE_DEPTH
+        );
+        std::env::remove_var("P2P_MAX_PEER_QUEUE_DEPTH");
     }
 
     #[test]
-    fn test_message_serde_bloom_exchange() {
-        let bloom = BloomFilterData {
-            bitmap: vec![0u8; 128],
-            num_hashes: 7,
-            bitmap_bits: 1024,
-            sip_keys: [(1, 2), (3, 4)],
-            item_count: 42,
-        };
-        let msg = P2pMessage::BloomExchange { bloom };
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        match decoded {
-            P2pMessage::BloomExchange { bloom } => {
-                assert_eq!(bloom.num_hashes, 7);
-                assert_eq!(bloom.bitmap_bits, 1024);
-                assert_eq!(bloom.bitmap.len(), 128);
-                assert_eq!(bloom.item_count, 42);
-                assert_eq!(bloom.sip_keys, [(1, 2), (3, 4)]);
-            }
-            _ => panic!("expected BloomExchange"),
-        }
+    fn test_config_from_env_catalog_sync_pages_per_minute() {
+        std::env::set_var("P2P_CATALOG_SYNC_PAGES_PER_MINUTE", "10");
+        assert_eq!(P2pConfig::from_env().catalog_sync_pages_per_minute, 10);
+        std::env::set_var("P2P_CATALOG_SYNC_PAGES_PER_MINUTE", "0");
+        assert_eq!(
+            P2pConfig::from_env().catalog_sync_pages_per_minute,
+            DEFAULT_CATALOG_SYNC_PAGES_PER_MINUTE
+        );
+        std::env::remove_var("P2P_CATALOG_SYNC_PAGES_PER_MINUTE");
     }
 
-    // ── P2pMessage serde: TrackData ──────────────────────────────────
+    #[test]
+    fn test_config_from_env_keep_alive() {
+        std::env::set_var("P2P_KEEP_ALIVE_SECS", "10");
+        assert_eq!(P2pConfig::from_env().keep_alive_interval_secs, 10);
+        std::env::set_var("P2P_KEEP_ALIVE_SECS", "0");
+        assert_eq!(P2pConfig::from_env().keep_alive_interval_secs, 0);
+        std::env::set_var("P2P_KEEP_ALIVE_SECS", "soon");
+        assert_eq!(
+            P2pConfig::from_env().keep_alive_interval_secs,
+            DEFAULT_KEEP_ALIVE_SECS
+        );
+        std::env::remove_var("P2P_KEEP_ALIVE_SECS");
+    }
 
     #[test]
-    fn test_message_serde_track_data() {
-        let msg = P2pMessage::TrackData {
-            hash: "abc123".to_string(),
-            size: 5_000_000,
-        };
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        match decoded {
-            P2pMessage::TrackData { hash, size } => {
-                assert_eq!(hash, "abc123");
-                assert_eq!(size, 5_000_000);
-            }
-            _ => panic!("expected TrackData"),
+    fn test_config_from_env_search_similarity_threshold() {
+        std::env::set_var("P2P_SEARCH_SIMILARITY_THRESHOLD", "0.45");
+        assert_eq!(P2pConfig::from_env().search_similarity_threshold, 0.45);
+        for invalid in ["0", "1.5", "-0.2", "close"] {
+            std::env::set_var("P2P_SEARCH_SIMILARITY_THRESHOLD", invalid);
+            assert_eq!(
+                P2pConfig::from_env().search_similarity_threshold,
+                DEFAULT_SEARCH_SIMILARITY_THRESHOLD
+            );
         }
+        std::env::remove_var("P2P_SEARCH_SIMILARITY_THRESHOLD");
+    }
+
+    #[test]
+    fn test_config_from_env_search_cache() {
+        std::env::set_var("P2P_SEARCH_CACHE_TTL_SECS", "0");
+        std::env::set_var("P2P_SEARCH_CACHE_MAX_ENTRIES", "32");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.search_cache_ttl_secs, 0);
+        assert_eq!(cfg.search_cache_max_entries, 32);
+        // A zero-sized cache is spelled with a zero TTL
+        std::env::set_var("P2P_SEARCH_CACHE_MAX_ENTRIES", "0");
+        assert_eq!(
+            P2pConfig::from_env().search_cache_max_entries,
+            DEFAULT_SEARCH_CACHE_MAX_ENTRIES
+        );
+        std::env::remove_var("P2P_SEARCH_CACHE_TTL_SECS");
+        std::env::remove_var("P2P_SEARCH_CACHE_MAX_ENTRIES");
+    }
+
+    #[test]
+    fn test_config_from_env_blob_gc_enabled() {
+        std::env::set_var("P2P_BLOB_GC_ENABLED", "TRUE");
+        assert!(P2pConfig::from_env().blob_gc_enabled);
+        std::env::set_var("P2P_BLOB_GC_ENABLED", "no");
+        assert!(!P2pConfig::from_env().blob_gc_enabled);
+        std::env::remove_var("P2P_BLOB_GC_ENABLED");
+    }
+
+    #[test]
+    fn test_config_from_env_reseed_replicated() {
+        assert!(!P2pConfig::default().reseed_replicated);
+        std::env::set_var("P2P_RESEED_REPLICATED", "true");
+        assert!(P2pConfig::from_env().reseed_replicated);
+        std::env::set_var("P2P_RESEED_REPLICATED", "1");
+        assert!(!P2pConfig::from_env().reseed_replicated);
+        std::env::remove_var("P2P_RESEED_REPLICATED");
     }
 
-    // ── P2pMessage serde: CatalogDelta ──────────────────────────────
+    #[test]
+    fn test_config_from_env_prewarm() {
+        let cfg = P2pConfig::default();
+        assert!(!cfg.prewarm_enabled);
+        assert_eq!(cfg.prewarm_scope, PrewarmScope::All);
+        std::env::set_var("P2P_PREWARM_ENABLED", "true");
+        std::env::set_var("P2P_PREWARM_SCOPE", "favorites");
+        let cfg = P2pConfig::from_env();
+        assert!(cfg.prewarm_enabled);
+        assert_eq!(cfg.prewarm_scope, PrewarmScope::Favorites);
+        std::env::set_var("P2P_PREWARM_SCOPE", "starred");
+        assert_eq!(P2pConfig::from_env().prewarm_scope, PrewarmScope::All);
+        std::env::remove_var("P2P_PREWARM_ENABLED");
+        std::env::remove_var("P2P_PREWARM_SCOPE");
+    }
 
     #[test]
-    fn test_message_serde_catalog_delta() {
-        let since = chrono::Utc::now();
-        let ann = TrackAnnouncement {
-            hash: "delta1".into(),
-            title: "Delta Track".into(),
-            artist_name: "Delta Artist".into(),
-            album_artist_name: None,
-            album_title: None,
-            duration_secs: 120.0,
-            format: "OPUS".into(),
-            file_size: 2000,
-            genre: None,
-            year: None,
-            track_number: None,
-            disc_number: None,
-            bitrate: None,
-            sample_rate: None,
-            origin_node: "n".into(),
-            cover_hash: None,
-        };
-        let msg = P2pMessage::CatalogDelta {
-            since,
-            tracks: vec![ann],
-        };
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        match decoded {
-            P2pMessage::CatalogDelta { since: s, tracks } => {
-                assert_eq!(tracks.len(), 1);
-                assert_eq!(tracks[0].hash, "delta1");
-                assert_eq!(tracks[0].format, "OPUS");
-                // chrono roundtrip may lose sub-nanosecond precision
-                assert!((s - since).num_seconds().abs() < 1);
-            }
-            _ => panic!("expected CatalogDelta"),
-        }
+    fn test_config_from_env_max_message_bytes() {
+        std::env::set_var("P2P_MAX_MESSAGE_BYTES", "128M");
+        assert_eq!(P2pConfig::from_env().max_message_bytes, 128 * 1024 * 1024);
+        std::env::set_var("P2P_MAX_MESSAGE_BYTES", "not a size");
+        assert_eq!(
+            P2pConfig::from_env().max_message_bytes,
+            DEFAULT_MAX_MESSAGE_BYTES
+        );
+        std::env::remove_var("P2P_MAX_MESSAGE_BYTES");
     }
 
-    // ── P2pMessage serde: RequestCatalog ─────────────────────────────
+    #[test]
+    fn test_config_from_env_self_hosted_discovery() {
+        std::env::set_var(
+            "P2P_RELAY_URLS",
+            "https://relay1.example.org, not a url ,https://relay2.example.org",
+        );
+        std::env::set_var("P2P_DISABLE_DEFAULT_DISCOVERY", "TRUE");
+        std::env::set_var("P2P_DNS_DISCOVERY_URL", "https://dns.example.org/pkarr");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.relay_urls.len(), 2);
+        assert_eq!(
+            cfg.relay_urls[0],
+            "https://relay1.example.org".parse::<RelayUrl>().unwrap()
+        );
+        assert!(cfg.disable_default_discovery);
+        assert_eq!(
+            cfg.dns_discovery_url.as_ref().map(|u| u.as_str()),
+            Some("https://dns.example.org/pkarr")
+        );
+
+        std::env::set_var("P2P_DNS_DISCOVERY_URL", "::not a url");
+        assert!(P2pConfig::from_env().dns_discovery_url.is_none());
+        std::env::remove_var("P2P_RELAY_URLS");
+        std::env::remove_var("P2P_DISABLE_DEFAULT_DISCOVERY");
+        std::env::remove_var("P2P_DNS_DISCOVERY_URL");
+    }
 
     #[test]
-    fn test_message_serde_request_catalog() {
-        let msg = P2pMessage::RequestCatalog;
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        match decoded {
-            P2pMessage::RequestCatalog => {} // expected
-            _ => panic!("expected RequestCatalog"),
-        }
+    fn test_config_from_env_hide_blocked_tracks() {
+        std::env::set_var("P2P_HIDE_BLOCKED_TRACKS", "false");
+        assert!(!P2pConfig::from_env().hide_blocked_tracks);
+        std::env::set_var("P2P_HIDE_BLOCKED_TRACKS", "TRUE");
+        assert!(P2pConfig::from_env().hide_blocked_tracks);
+        std::env::remove_var("P2P_HIDE_BLOCKED_TRACKS");
     }
 
     #[test]
-    fn test_message_serde_request_catalog_roundtrip_json() {
-        let msg = P2pMessage::RequestCatalog;
-        let json = serde_json::to_string(&msg).unwrap();
-        assert!(json.contains("RequestCatalog"));
-        let decoded: P2pMessage = serde_json::from_str(&json).unwrap();
-        match decoded {
-            P2pMessage::RequestCatalog => {}
-            _ => panic!("expected RequestCatalog"),
-        }
+    fn test_config_from_env_peer_eviction_threshold() {
+        std::env::set_var("P2P_PEER_EVICTION_THRESHOLD", "25");
+        assert_eq!(P2pConfig::from_env().peer_eviction_threshold, 25);
+        // 0 disables eviction
+        std::env::set_var("P2P_PEER_EVICTION_THRESHOLD", "0");
+        assert_eq!(P2pConfig::from_env().peer_eviction_threshold, 0);
+        std::env::set_var("P2P_PEER_EVICTION_THRESHOLD", "lots");
+        assert_eq!(P2pConfig::from_env().peer_eviction_threshold, 10);
+        std::env::remove_var("P2P_PEER_EVICTION_THRESHOLD");
     }
 
-    // ── P2pMessage serde: AnnounceTrack ─────────────────────────────
+    #[test]
+    fn test_config_from_env_peer_offline_retention_days() {
+        std::env::set_var("P2P_PEER_OFFLINE_RETENTION_DAYS", "7");
+        assert_eq!(P2pConfig::from_env().peer_offline_retention_days, 7);
+        // 0 disables pruning
+        std::env::set_var("P2P_PEER_OFFLINE_RETENTION_DAYS", "0");
+        assert_eq!(P2pConfig::from_env().peer_offline_retention_days, 0);
+        std::env::set_var("P2P_PEER_OFFLINE_RETENTION_DAYS", "-3");
+        assert_eq!(P2pConfig::from_env().peer_offline_retention_days, 30);
+        std::env::remove_var("P2P_PEER_OFFLINE_RETENTION_DAYS");
+    }
 
     #[test]
-    fn test_message_serde_announce_track() {
-        let ann = TrackAnnouncement {
-            hash: "ann1".into(),
-            title: "Announced".into(),
-            artist_name: "Announcer".into(),
-            album_artist_name: None,
-            album_title: Some("Album Ann".into()),
-            duration_secs: 200.0,
-            format: "FLAC".into(),
-            file_size: 50_000_000,
-            genre: Some("Electronic".into()),
-            year: Some(2024),
-            track_number: Some(3),
-            disc_number: Some(1),
-            bitrate: Some(1_411_000),
-            sample_rate: Some(48_000),
-            origin_node: "origin1".into(),
-            cover_hash: Some("cover_abc".into()),
-        };
-        let msg = P2pMessage::AnnounceTrack(ann);
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        match decoded {
-            P2pMessage::AnnounceTrack(a) => {
-                assert_eq!(a.hash, "ann1");
-                assert_eq!(a.title, "Announced");
-                assert_eq!(a.format, "FLAC");
-                assert_eq!(a.file_size, 50_000_000);
-                assert_eq!(a.year, Some(2024));
-                assert_eq!(a.bitrate, Some(1_411_000));
-                assert_eq!(a.sample_rate, Some(48_000));
-                assert_eq!(a.cover_hash.as_deref(), Some("cover_abc"));
-            }
-            _ => panic!("expected AnnounceTrack"),
-        }
+    fn test_config_from_env_max_peers() {
+        std::env::set_var("P2P_MAX_PEERS", "50");
+        assert_eq!(P2pConfig::from_env().max_peers, 50);
+        // 0 removes the limit
+        std::env::set_var("P2P_MAX_PEERS", "0");
+        assert_eq!(P2pConfig::from_env().max_peers, 0);
+        std::env::set_var("P2P_MAX_PEERS", "lots");
+        assert_eq!(P2pConfig::from_env().max_peers, 500);
+        std::env::remove_var("P2P_MAX_PEERS");
     }
 
-    // ── P2pMessage: empty collections ────────────────────────────────
+    #[test]
+    fn test_config_from_env_bloom_params() {
+        std::env::set_var("P2P_BLOOM_FPR", "0.001");
+        std::env::set_var("P2P_BLOOM_EXPECTED_ITEMS", "2000000");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.bloom_fpr, 0.001);
+        assert_eq!(cfg.bloom_expected_items, 2_000_000);
+
+        // Rates outside (0, 1) and a zero item count fall back to defaults
+        std::env::set_var("P2P_BLOOM_FPR", "1.5");
+        std::env::set_var("P2P_BLOOM_EXPECTED_ITEMS", "0");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.bloom_fpr, 0.01);
+        assert_eq!(cfg.bloom_expected_items, 100_000);
+        std::env::set_var("P2P_BLOOM_FPR", "0");
+        assert_eq!(P2pConfig::from_env().bloom_fpr, 0.01);
+        std::env::remove_var("P2P_BLOOM_TARGET_FPR");
+        std::env::remove_var("P2P_BLOOM_FPR");
+        std::env::remove_var("P2P_BLOOM_EXPECTED_ITEMS");
+    }
 
     #[test]
-    fn test_message_serde_peer_exchange_empty() {
-        let msg = P2pMessage::PeerExchange { peers: vec![] };
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        match decoded {
-            P2pMessage::PeerExchange { peers } => assert!(peers.is_empty()),
-            _ => panic!("expected PeerExchange"),
-        }
+    fn test_config_from_env_bloom_target_fpr() {
+        std::env::set_var("P2P_BLOOM_TARGET_FPR", "0.005");
+        std::env::set_var("P2P_BLOOM_FPR", "0.05");
+        // The new name wins over the old one
+        assert_eq!(P2pConfig::from_env().bloom_fpr, 0.005);
+        std::env::remove_var("P2P_BLOOM_TARGET_FPR");
+        assert_eq!(P2pConfig::from_env().bloom_fpr, 0.05);
+        std::env::remove_var("P2P_BLOOM_FPR");
     }
 
     #[test]
-    fn test_message_serde_catalog_sync_empty() {
-        let msg = P2pMessage::CatalogSync(vec![]);
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        match decoded {
-            P2pMessage::CatalogSync(tracks) => assert!(tracks.is_empty()),
-            _ => panic!("expected CatalogSync"),
-        }
+    fn test_relay_plan_selection() {
+        let relay: RelayUrl = "https://relay.example.org".parse().unwrap();
+        let mut cfg = P2pConfig::default();
+        assert_eq!(cfg.relay_plan(), RelayPlan::Default);
+        assert_eq!(cfg.relay_plan().mode(), "default");
+
+        cfg.disable_default_discovery = true;
+        assert_eq!(cfg.relay_plan(), RelayPlan::Disabled);
+        assert_eq!(cfg.relay_plan().mode(), "disabled");
+
+        // Configured relays are used with or without default discovery
+        cfg.relay_urls = vec![relay.clone()];
+        assert_eq!(cfg.relay_plan(), RelayPlan::Custom(vec![relay.clone()]));
+        cfg.disable_default_discovery = false;
+        assert_eq!(cfg.relay_plan(), RelayPlan::Custom(vec![relay]));
+        assert_eq!(cfg.relay_plan().mode(), "custom");
+        assert_eq!(cfg.relay_plan().urls().len(), 1);
+        assert!(RelayPlan::Default.urls().is_empty());
+        assert!(matches!(
+            cfg.relay_plan().relay_mode(),
+            RelayMode::Custom(_)
+        ));
     }
 
     #[test]
-    fn test_message_serde_search_results_empty() {
-        let msg = P2pMessage::SearchResults {
-            request_id: "r".into(),
-            results: vec![],
-            total: 0,
-        };
-        let bytes = serde_json::to_vec(&msg).unwrap();
-        let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
-        match decoded {
-            P2pMessage::SearchResults { results, total, .. } => {
-                assert!(results.is_empty());
-                assert_eq!(total, 0);
-            }
-            _ => panic!("expected SearchResults"),
-        }
+    fn test_parse_byte_size() {
+        assert_eq!(parse_byte_size("1048576"), Some(1024 * 1024));
+        assert_eq!(parse_byte_size("512K"), Some(512 * 1024));
+        assert_eq!(parse_byte_size("64M"), Some(64 * 1024 * 1024));
+        assert_eq!(parse_byte_size(" 128mb "), Some(128 * 1024 * 1024));
+        assert_eq!(parse_byte_size("64MiB"), Some(64 * 1024 * 1024));
+        assert_eq!(parse_byte_size("1G"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse_byte_size(""), None);
+        assert_eq!(parse_byte_size("M"), None);
+        assert_eq!(parse_byte_size("-1M"), None);
+        assert_eq!(parse_byte_size("12X"), None);
+        assert_eq!(parse_byte_size(&format!("{}G", usize::MAX)), None);
     }
 
-    // ── P2pMessage: invalid JSON deserialization ─────────────────────
+    #[test]
+    fn test_config_validate_message_size_bounds() {
+        let mut cfg = P2pConfig::default();
+        assert!(cfg.validate().is_ok());
+        cfg.max_message_bytes = MIN_MESSAGE_BYTES_LIMIT;
+        assert!(cfg.validate().is_ok());
+        cfg.max_message_bytes = MAX_MESSAGE_BYTES_LIMIT;
+        assert!(cfg.validate().is_ok());
+
+        for bad in [MIN_MESSAGE_BYTES_LIMIT - 1, MAX_MESSAGE_BYTES_LIMIT + 1] {
+            cfg.max_message_bytes = bad;
+            let err = cfg.validate().unwrap_err();
+            assert!(matches!(err, P2pError::Config(_)));
+            assert!(err.to_string().contains("P2P_MAX_MESSAGE_BYTES"), "{err}");
+        }
+    }
 
     #[test]
-    fn test_message_deserialize_invalid_json() {
-        let result = serde_json::from_str::<P2pMessage>("not valid json {{{");
-        assert!(result.is_err());
+    fn test_config_from_env_upload_limit_invalid() {
+        std::env::set_var("P2P_MAX_UPLOAD_BPS", "fast");
+        let cfg = P2pConfig::from_env();
+        assert_eq!(cfg.max_upload_bps, 0);
+        std::env::remove_var("P2P_MAX_UPLOAD_BPS");
     }
 
+    // ── P2pConfig Clone + Debug ──────────────────────────────────────
+
     #[test]
-    fn test_message_deserialize_unknown_variant() {
-        let result = serde_json::from_str::<P2pMessage>(r#"{"UnknownVariant":{}}"#);
-        assert!(result.is_err());
+    fn test_config_clone() {
+        let cfg = P2pConfig::default();
+        let cloned = cfg.clone();
+        assert_eq!(cfg.blobs_dir, cloned.blobs_dir);
+        assert_eq!(cfg.bind_port, cloned.bind_port);
     }
 
     #[test]
-    fn test_message_deserialize_missing_fields() {
-        // Pong without required fields
-        let result = serde_json::from_str::<P2pMessage>(r#"{"Pong":{}}"#);
-        assert!(result.is_err());
+    fn test_config_debug() {
+        let cfg = P2pConfig::default();
+        let debug = format!("{:?}", cfg);
+        assert!(debug.contains("P2pConfig"));
+        assert!(debug.contains("blobs_dir"));
     }
 
-    // ── TrackAnnouncement edge cases ─────────────────────────────────
+    // ── SecretKey: edge cases ────────────────────────────────────────
 
-    #[test]
-    fn test_track_announcement_all_optional_none() {
-        let ann = TrackAnnouncement {
-            hash: "h".into(),
-            title: "T".into(),
-            artist_name: "A".into(),
-            album_artist_name: None,
-            album_title: None,
-            duration_secs: 0.0,
-            format: "WAV".into(),
-            file_size: 0,
-            genre: None,
-            year: None,
-            track_number: None,
-            disc_number: None,
-            bitrate: None,
-            sample_rate: None,
-            origin_node: "n".into(),
-            cover_hash: None,
+    #[tokio::test]
+    async fn test_load_key_invalid_hex() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("secret_key");
+        // Write invalid hex (odd length, not 64 chars)
+        tokio::fs::write(&key_path, "not_valid_hex_data")
+            .await
+            .unwrap();
+
+        let config = P2pConfig {
+            secret_key_path: key_path,
+            ..Default::default()
         };
-        let bytes = serde_json::to_vec(&ann).unwrap();
-        let decoded: TrackAnnouncement = serde_json::from_slice(&bytes).unwrap();
-        assert!(decoded.album_title.is_none());
-        assert!(decoded.genre.is_none());
-        assert!(decoded.year.is_none());
-        assert!(decoded.track_number.is_none());
-        assert!(decoded.disc_number.is_none());
-        assert!(decoded.bitrate.is_none());
-        assert!(decoded.sample_rate.is_none());
-        assert!(decoded.cover_hash.is_none());
+
+        // Should treat it as raw bytes and handle gracefully (fallback or error)
+        // The current impl tries hex first, then raw bytes — with 18 bytes it'll
+        // fail both and generate a new key
+        let result = P2pNode::load_or_generate_key(&config).await;
+        // Implementation may handle this differently — just ensure no panic
+        assert!(result.is_ok() || result.is_err());
     }
 
-    #[test]
-    fn test_track_announcement_clone() {
-        let ann = TrackAnnouncement {
-            hash: "h".into(),
-            title: "T".into(),
-            artist_name: "A".into(),
-            album_artist_name: None,
-            album_title: None,
-            duration_secs: 1.0,
-            format: "MP3".into(),
-            file_size: 100,
-            genre: None,
-            year: None,
-            track_number: None,
-            disc_number: None,
-            bitrate: None,
-            sample_rate: None,
-            origin_node: "n".into(),
-            cover_hash: None,
+    #[tokio::test]
+    async fn test_load_key_creates_parent_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("subdir").join("deep").join("secret_key");
+        let config = P2pConfig {
+            secret_key_path: key_path.clone(),
+            ..Default::default()
         };
-        let cloned = ann.clone();
-        assert_eq!(ann.hash, cloned.hash);
-        assert_eq!(ann.title, cloned.title);
+
+        let result = P2pNode::load_or_generate_key(&config).await;
+        // Parent dirs should be created automatically
+        assert!(result.is_ok());
+        assert!(key_path.exists());
     }
 
+    // ── ALPN constant ────────────────────────────────────────────────
+
     #[test]
-    fn test_track_announcement_debug() {
-        let ann = TrackAnnouncement {
-            hash: "h".into(),
-            title: "T".into(),
-            artist_name: "A".into(),
-            album_artist_name: None,
-            album_title: None,
-            duration_secs: 1.0,
-            format: "MP3".into(),
-            file_size: 100,
-            genre: None,
-            year: None,
-            track_number: None,
-            disc_number: None,
-            bitrate: None,
-            sample_rate: None,
-            origin_node: "n".into(),
-            cover_hash: None,
-        };
-        let debug = format!("{:?}", ann);
-        assert!(debug.contains("TrackAnnouncement"));
-        assert!(debug.contains("hash"));
+    fn test_alpn_constant() {
+        assert_eq!(SOUNDTIME_ALPN, b"soundtime/p2p/1");
+        assert_eq!(SOUNDTIME_ALPN.len(), 15);
+        assert_eq!(SOUNDTIME_ALPN_V2, b"soundtime/p2p/2");
+        assert_eq!(SUPPORTED_ALPNS, &[SOUNDTIME_ALPN_V2, SOUNDTIME_ALPN]);
     }
 
-    // ── SearchResultItem edge cases ──────────────────────────────────
+    // ── Protocol version negotiation ─────────────────────────────────
 
     #[test]
-    fn test_search_result_item_roundtrip() {
-        let item = SearchResultItem {
-            hash: "sr1".into(),
-            title: "Search Result".into(),
-            artist_name: "SR Artist".into(),
-            album_title: None,
-            duration_secs: 0.5,
-            format: "AAC".into(),
-            genre: None,
-            year: None,
-            bitrate: None,
-            source_node: "node-x".into(),
-            musicbrainz_id: Some("mb-123".into()),
-            relevance: 1.0,
+    fn test_protocol_version_from_alpn() {
+        assert_eq!(
+            ProtocolVersion::from_alpn(b"soundtime/p2p/1"),
+            Some(ProtocolVersion::V1)
+        );
+        assert_eq!(
+            ProtocolVersion::from_alpn(b"soundtime/p2p/2"),
+            Some(ProtocolVersion::V2)
+        );
+        assert_eq!(ProtocolVersion::from_alpn(b"other/1"), None);
+        assert_eq!(ProtocolVersion::V2.alpn(), SOUNDTIME_ALPN_V2);
+        assert_eq!(ProtocolVersion::V1.as_u8(), 1);
+        assert!(ProtocolVersion::V1 < ProtocolVersion::V2);
+    }
+
+    #[test]
+    fn test_v1_messages_supported_by_v1_peer() {
+        let msgs = [
+            P2pMessage::Ping,
+            P2pMessage::RequestCatalog,
+            P2pMessage::CatalogSync(vec![]),
+            P2pMessage::PeerExchange { peers: vec![] },
+        ];
+        for msg in &msgs {
+            assert!(msg.supported_by(ProtocolVersion::V1), "{msg:?}");
+            assert!(msg.supported_by(ProtocolVersion::V2), "{msg:?}");
+        }
+    }
+
+    #[test]
+    fn test_fetch_track_range_requires_v2() {
+        let msg = P2pMessage::FetchTrackRange {
+            hash: "h".into(),
+            offset: 1024,
+            length: Some(4096),
+            token: None,
+            verified: false,
         };
-        let bytes = serde_json::to_vec(&item).unwrap();
-        let decoded: SearchResultItem = serde_json::from_slice(&bytes).unwrap();
-        assert_eq!(decoded.hash, "sr1");
-        assert_eq!(decoded.musicbrainz_id.as_deref(), Some("mb-123"));
-        assert!((decoded.relevance - 1.0).abs() < f32::EPSILON);
+        assert!(!msg.supported_by(ProtocolVersion::V1));
+        assert!(msg.supported_by(ProtocolVersion::V2));
+        assert_eq!(msg.priority(), MessagePriority::High);
+
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        match serde_json::from_slice(&bytes).unwrap() {
+            P2pMessage::FetchTrackRange {
+                hash,
+                offset,
+                length,
+                token,
+                verified,
+            } => {
+                assert!(!verified);
+                assert_eq!(token, None);
+                assert_eq!(hash, "h");
+                assert_eq!(offset, 1024);
+                assert_eq!(length, Some(4096));
+            }
+            other => panic!("expected FetchTrackRange, got {other:?}"),
+        }
     }
 
     #[test]
-    fn test_search_result_item_clone() {
-        let item = SearchResultItem {
+    fn test_update_track_metadata_requires_v2() {
+        let msg = P2pMessage::UpdateTrackMetadata {
             hash: "h".into(),
-            title: "T".into(),
-            artist_name: "A".into(),
+            title: Some("New Title".into()),
+            artist_name: None,
             album_title: None,
-            duration_secs: 1.0,
-            format: "MP3".into(),
-            genre: None,
-            year: None,
-            bitrate: None,
-            source_node: "n".into(),
-            musicbrainz_id: None,
-            relevance: 0.0,
+            genre: Some("Jazz".into()),
+            year: Some(1999),
+            track_number: None,
         };
-        let cloned = item.clone();
-        assert_eq!(item.hash, cloned.hash);
-    }
+        assert!(!msg.supported_by(ProtocolVersion::V1));
+        assert!(msg.supported_by(ProtocolVersion::V2));
+        assert_eq!(msg.priority(), MessagePriority::Low);
 
-    // ── sanitize_for_path additional edge cases ──────────────────────
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        match serde_json::from_slice(&bytes).unwrap() {
+            P2pMessage::UpdateTrackMetadata {
+                hash,
+                title,
+                genre,
+                year,
+                track_number,
+                ..
+            } => {
+                assert_eq!(hash, "h");
+                assert_eq!(title.as_deref(), Some("New Title"));
+                assert_eq!(genre.as_deref(), Some("Jazz"));
+                assert_eq!(year, Some(1999));
+                assert_eq!(track_number, None);
+            }
+            other => panic!("expected UpdateTrackMetadata, got {other:?}"),
+        }
+    }
 
     #[test]
-    fn test_sanitize_for_path_unicode() {
-        // Unicode letters (accented) should be kept as they're alphanumeric
-        assert_eq!(sanitize_for_path("Étoile"), "Étoile");
-        assert_eq!(sanitize_for_path("日本語"), "日本語");
-        assert_eq!(sanitize_for_path("Ñoño"), "Ñoño");
+    fn test_catalog_sync_page_requires_v2() {
+        let header = CatalogPageHeader {
+            sync_id: Uuid::new_v4(),
+            page: 2,
+            total_pages: 5,
+        };
+        let msg = P2pMessage::CatalogSyncPage {
+            header: header.clone(),
+            tracks: vec![],
+        };
+        let ack = P2pMessage::CatalogSyncAck(CatalogPageAck {
+            sync_id: header.sync_id,
+            page: 2,
+            inserted: 3,
+            skipped: 1,
+            failed: 0,
+        });
+        for m in [&msg, &ack] {
+            assert!(!m.supported_by(ProtocolVersion::V1));
+            assert!(m.supported_by(ProtocolVersion::V2));
+        }
+
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        match serde_json::from_slice(&bytes).unwrap() {
+            P2pMessage::CatalogSyncPage { header: h, tracks } => {
+                assert_eq!(h, header);
+                assert!(tracks.is_empty());
+            }
+            other => panic!("expected CatalogSyncPage, got {other:?}"),
+        }
+        let bytes = serde_json::to_vec(&ack).unwrap();
+        match serde_json::from_slice(&bytes).unwrap() {
+            P2pMessage::CatalogSyncAck(a) => {
+                assert!(a.matches(&header));
+                assert_eq!((a.inserted, a.skipped, a.failed), (3, 1, 0));
+            }
+            other => panic!("expected CatalogSyncAck, got {other:?}"),
+        }
     }
 
     #[test]
-    fn test_sanitize_for_path_mixed_special() {
-        assert_eq!(sanitize_for_path("a/b\\c:d*e?f"), "a_b_c_d_e_f");
+    fn test_keepalive_requires_v2() {
+        let msg = P2pMessage::KeepAlive;
+        assert!(!msg.supported_by(ProtocolVersion::V1));
+        assert!(msg.supported_by(ProtocolVersion::V2));
+        assert_eq!(msg.priority(), MessagePriority::High);
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        assert!(matches!(
+            serde_json::from_slice(&bytes).unwrap(),
+            P2pMessage::KeepAlive
+        ));
     }
 
     #[test]
-    fn test_sanitize_for_path_only_special() {
-        // All special chars — trimmed result is empty
-        assert_eq!(sanitize_for_path("///"), "___");
+    fn test_goodbye_requires_v2() {
+        let msg = P2pMessage::Goodbye {
+            node_id: "abc".into(),
+            reason: "shutdown".into(),
+        };
+        assert!(!msg.supported_by(ProtocolVersion::V1));
+        assert!(msg.supported_by(ProtocolVersion::V2));
+        assert_eq!(msg.priority(), MessagePriority::High);
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        match serde_json::from_slice(&bytes).unwrap() {
+            P2pMessage::Goodbye { node_id, reason } => {
+                assert_eq!(node_id, "abc");
+                assert_eq!(reason, "shutdown");
+            }
+            other => panic!("expected Goodbye, got {other:?}"),
+        }
     }
 
     #[test]
-    fn test_sanitize_for_path_preserves_hyphens_underscores_spaces() {
-        assert_eq!(sanitize_for_path("my track-name_01"), "my track-name_01");
+    fn test_block_hash_requires_v2() {
+        let msg = P2pMessage::BlockHash {
+            hash: "abc".into(),
+            reason: "copyright".into(),
+        };
+        assert!(!msg.supported_by(ProtocolVersion::V1));
+        assert!(msg.supported_by(ProtocolVersion::V2));
+        assert_eq!(msg.priority(), MessagePriority::High);
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        match serde_json::from_slice(&bytes).unwrap() {
+            P2pMessage::BlockHash { hash, reason } => {
+                assert_eq!(hash, "abc");
+                assert_eq!(reason, "copyright");
+            }
+            other => panic!("expected BlockHash, got {other:?}"),
+        }
     }
 
     #[test]
-    fn test_sanitize_for_path_emoji() {
-        // Emojis are not alphanumeric, should be replaced
-        assert_eq!(sanitize_for_path("🎵music🎵"), "_music_");
+    fn test_delete_track_requires_v2() {
+        let deleted_at = chrono::Utc::now();
+        let msg = P2pMessage::DeleteTrack {
+            hash: "abc".into(),
+            origin_node: "origin".into(),
+            deleted_at,
+            ttl: 2,
+            signature: Some("sig".into()),
+        };
+        assert!(!msg.supported_by(ProtocolVersion::V1));
+        assert!(msg.supported_by(ProtocolVersion::V2));
+        assert_eq!(msg.priority(), MessagePriority::Low);
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        match serde_json::from_slice(&bytes).unwrap() {
+            P2pMessage::DeleteTrack {
+                hash,
+                origin_node,
+                deleted_at: at,
+                ttl,
+                signature,
+            } => {
+                assert_eq!(hash, "abc");
+                assert_eq!(origin_node, "origin");
+                assert_eq!(at, deleted_at);
+                assert_eq!(ttl, 2);
+                assert_eq!(signature.as_deref(), Some("sig"));
+            }
+            other => panic!("expected DeleteTrack, got {other:?}"),
+        }
+        // Older peers send no signature
+        let json = format!(
+            r#"{{"DeleteTrack":{{"hash":"abc","origin_node":"origin","deleted_at":"{}","ttl":1}}}}"#,
+            deleted_at.to_rfc3339()
+        );
+        match serde_json::from_str(&json).unwrap() {
+            P2pMessage::DeleteTrack { signature, .. } => assert!(signature.is_none()),
+            other => panic!("expected DeleteTrack, got {other:?}"),
+        }
     }
 
-    // ── P2pConfig::from_env ──────────────────────────────────────────
+    // ── DeleteTrack authentication ───────────────────────────────────
 
     #[test]
-    fn test_config_from_env_defaults() {
-        // Clear all P2P env vars to test defaults
-        std::env::remove_var("P2P_BLOBS_DIR");
-        std::env::remove_var("P2P_SECRET_KEY_PATH");
-        std::env::remove_var("P2P_BIND_PORT");
-        std::env::remove_var("P2P_LOCAL_DISCOVERY");
-        std::env::remove_var("P2P_DHT_DISCOVERY");
-        std::env::remove_var("P2P_SEED_PEERS");
-        std::env::remove_var("AUDIO_STORAGE_PATH");
-        std::env::remove_var("METADATA_STORAGE_PATH");
+    fn test_signed_delete_track_accepted_from_any_peer() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let origin = key.public().to_string();
+        let now = chrono::Utc::now();
+        let sig = sign_delete_track(&key, "h1", &now);
+        assert!(check_delete_track("h1", &origin, &now, Some(&sig), &origin, now).is_ok());
+        assert!(check_delete_track("h1", &origin, &now, Some(&sig), "relay", now).is_ok());
+        // Unsigned deletions are only believed from the origin itself
+        assert!(check_delete_track("h1", &origin, &now, None, &origin, now).is_ok());
+    }
 
-        let cfg = P2pConfig::from_env();
-        assert_eq!(cfg.blobs_dir, PathBuf::from("data/p2p/blobs"));
-        assert_eq!(cfg.secret_key_path, PathBuf::from("data/p2p/secret_key"));
-        assert_eq!(cfg.bind_port, 0);
-        assert!(cfg.enable_local_discovery);
-        assert!(cfg.enable_dht_discovery);
-        assert!(cfg.seed_peers.is_empty());
-        assert_eq!(cfg.audio_storage_path, PathBuf::from("data/music"));
-        assert!(cfg.metadata_storage_path.is_none());
+    #[test]
+    fn test_forged_delete_track_refused() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let origin = key.public().to_string();
+        let forger = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let now = chrono::Utc::now();
+
+        // Unsigned, relayed by someone other than the origin
+        assert!(matches!(
+            check_delete_track("h1", &origin, &now, None, "relay", now),
+            Err(P2pError::InvalidSignature(_))
+        ));
+        // Signed by another key
+        let sig = sign_delete_track(&forger, "h1", &now);
+        assert!(check_delete_track("h1", &origin, &now, Some(&sig), "relay", now).is_err());
+        // Origin's signature of another hash
+        let sig = sign_delete_track(&key, "h2", &now);
+        assert!(check_delete_track("h1", &origin, &now, Some(&sig), "relay", now).is_err());
+        // Garbage
+        assert!(check_delete_track("h1", &origin, &now, Some("xx"), &origin, now).is_err());
     }
 
     #[test]
-    fn test_config_from_env_custom_blobs_dir() {
-        std::env::set_var("P2P_BLOBS_DIR", "/tmp/custom/blobs");
-        std::env::remove_var("P2P_SECRET_KEY_PATH");
-        let cfg = P2pConfig::from_env();
-        assert_eq!(cfg.blobs_dir, PathBuf::from("/tmp/custom/blobs"));
-        // secret_key_path should derive from blobs_dir parent
-        assert_eq!(cfg.secret_key_path, PathBuf::from("/tmp/custom/secret_key"));
-        std::env::remove_var("P2P_BLOBS_DIR");
+    fn test_replayed_delete_track_refused() {
+        let key = SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng());
+        let origin = key.public().to_string();
+        let deleted_at = chrono::Utc::now();
+        let sig = sign_delete_track(&key, "h1", &deleted_at);
+
+        // A genuine deletion replayed long after it was made
+        let later = deleted_at + MAX_DELETE_TRACK_AGE + chrono::Duration::seconds(1);
+        assert!(
+            check_delete_track("h1", &origin, &deleted_at, Some(&sig), "relay", later).is_err()
+        );
+        // Its signature does not cover a fresher date
+        assert!(check_delete_track("h1", &origin, &later, Some(&sig), "relay", later).is_err());
+        // Dated in the future
+        let early = deleted_at - MAX_DELETE_TRACK_AGE - chrono::Duration::seconds(1);
+        assert!(
+            check_delete_track("h1", &origin, &deleted_at, Some(&sig), "relay", early).is_err()
+        );
+        // Replays within the window are dropped by the gossip ID, which
+        // ignores the date
+        let seen = GossipSeen::default();
+        assert!(seen.insert(&gossip::delete_track_id("h1", &origin)));
+        assert!(!seen.insert(&gossip::delete_track_id("h1", &origin)));
     }
 
     #[test]
-    fn test_config_from_env_custom_secret_key() {
-        std::env::set_var("P2P_SECRET_KEY_PATH", "/my/key");
-        let cfg = P2pConfig::from_env();
-        assert_eq!(cfg.secret_key_path, PathBuf::from("/my/key"));
-        std::env::remove_var("P2P_SECRET_KEY_PATH");
+    fn test_play_count_update_requires_v2() {
+        let msg = P2pMessage::PlayCountUpdate {
+            hash: "abc".into(),
+            origin_node: "origin".into(),
+            incremental_count: 3,
+        };
+        assert!(!msg.supported_by(ProtocolVersion::V1));
+        assert!(msg.supported_by(ProtocolVersion::V2));
+        assert_eq!(msg.priority(), MessagePriority::Low);
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        match serde_json::from_slice(&bytes).unwrap() {
+            P2pMessage::PlayCountUpdate {
+                hash,
+                origin_node,
+                incremental_count,
+            } => {
+                assert_eq!(hash, "abc");
+                assert_eq!(origin_node, "origin");
+                assert_eq!(incremental_count, 3);
+            }
+            other => panic!("expected PlayCountUpdate, got {other:?}"),
+        }
     }
 
     #[test]
-    fn test_config_from_env_bind_port() {
-        std::env::set_var("P2P_BIND_PORT", "4433");
-        let cfg = P2pConfig::from_env();
-        assert_eq!(cfg.bind_port, 4433);
-        std::env::remove_var("P2P_BIND_PORT");
+    fn test_popularity_gossip_requires_v2() {
+        let msg = P2pMessage::PopularityGossip {
+            entries: vec![PopularityEntry {
+                hash: "abc".into(),
+                play_count: 12,
+                window_secs: popularity::POPULARITY_WINDOW_SECS,
+            }],
+        };
+        assert!(!msg.supported_by(ProtocolVersion::V1));
+        assert!(msg.supported_by(ProtocolVersion::V2));
+        assert_eq!(msg.priority(), MessagePriority::Low);
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        match serde_json::from_slice(&bytes).unwrap() {
+            P2pMessage::PopularityGossip { entries } => {
+                assert_eq!(entries.len(), 1);
+                assert_eq!(entries[0].hash, "abc");
+                assert_eq!(entries[0].play_count, 12);
+            }
+            other => panic!("expected PopularityGossip, got {other:?}"),
+        }
     }
 
     #[test]
-    fn test_config_from_env_bind_port_invalid() {
-        std::env::set_var("P2P_BIND_PORT", "not_a_number");
-        let cfg = P2pConfig::from_env();
-        assert_eq!(cfg.bind_port, 0); // fallback
-        std::env::remove_var("P2P_BIND_PORT");
+    fn test_bloom_delta_requires_v2() {
+        let msg = P2pMessage::BloomDelta {
+            delta: BloomDeltaData {
+                generation: 3,
+                bitmap_bits: 64,
+                set_bits: vec![1, 42],
+                item_count: 7,
+            },
+        };
+        assert!(!msg.supported_by(ProtocolVersion::V1));
+        assert!(msg.supported_by(ProtocolVersion::V2));
+        assert_eq!(msg.priority(), MessagePriority::High);
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        match serde_json::from_slice(&bytes).unwrap() {
+            P2pMessage::BloomDelta { delta } => {
+                assert_eq!(delta.generation, 3);
+                assert_eq!(delta.set_bits, [1, 42]);
+            }
+            other => panic!("expected BloomDelta, got {other:?}"),
+        }
+        assert!(!P2pMessage::RequestBloom.supported_by(ProtocolVersion::V1));
     }
 
     #[test]
-    fn test_config_from_env_local_discovery_false() {
-        std::env::set_var("P2P_LOCAL_DISCOVERY", "false");
-        let cfg = P2pConfig::from_env();
-        assert!(!cfg.enable_local_discovery);
-        std::env::remove_var("P2P_LOCAL_DISCOVERY");
+    fn test_catalog_checksum_requires_v2() {
+        let msg = P2pMessage::CatalogChecksumResponse {
+            checksum: "abc".into(),
+            track_count: 42,
+        };
+        assert!(!msg.supported_by(ProtocolVersion::V1));
+        assert!(msg.supported_by(ProtocolVersion::V2));
+        assert!(!P2pMessage::CatalogChecksumRequest.supported_by(ProtocolVersion::V1));
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        match serde_json::from_slice(&bytes).unwrap() {
+            P2pMessage::CatalogChecksumResponse {
+                checksum,
+                track_count,
+            } => {
+                assert_eq!(checksum, "abc");
+                assert_eq!(track_count, 42);
+            }
+            other => panic!("expected CatalogChecksumResponse, got {other:?}"),
+        }
     }
 
     #[test]
-    fn test_config_from_env_local_discovery_case_insensitive() {
-        std::env::set_var("P2P_LOCAL_DISCOVERY", "TRUE");
-        let cfg = P2pConfig::from_env();
-        assert!(cfg.enable_local_discovery);
-        std::env::remove_var("P2P_LOCAL_DISCOVERY");
+    fn test_duplicate_check_messages_require_v2() {
+        let msg = P2pMessage::CheckDuplicate {
+            fingerprint: "AQAAE0mUaEkSRZEGAA".into(),
+            duration_secs: Some(180.0),
+        };
+        assert!(!msg.supported_by(ProtocolVersion::V1));
+        assert!(msg.supported_by(ProtocolVersion::V2));
+        assert_eq!(msg.priority(), MessagePriority::High);
+        let found = P2pMessage::DuplicateFound {
+            hash: "h".into(),
+            source_node: "n".into(),
+        };
+        assert!(!found.supported_by(ProtocolVersion::V1));
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        match serde_json::from_slice(&bytes).unwrap() {
+            P2pMessage::CheckDuplicate {
+                fingerprint,
+                duration_secs,
+            } => {
+                assert_eq!(fingerprint, "AQAAE0mUaEkSRZEGAA");
+                assert_eq!(duration_secs, Some(180.0));
+            }
+            other => panic!("expected CheckDuplicate, got {other:?}"),
+        }
+
+        // Older peers send no duration
+        let old: P2pMessage =
+            serde_json::from_str(r#"{"CheckDuplicate":{"fingerprint":"f"}}"#).unwrap();
+        assert!(matches!(
+            old,
+            P2pMessage::CheckDuplicate {
+                duration_secs: None,
+                ..
+            }
+        ));
     }
 
     #[test]
-    fn test_config_from_env_dht_discovery_true() {
-        std::env::set_var("P2P_DHT_DISCOVERY", "true");
-        let cfg = P2pConfig::from_env();
-        assert!(cfg.enable_dht_discovery);
-        std::env::remove_var("P2P_DHT_DISCOVERY");
+    fn test_parse_duplicate_reply() {
+        assert_eq!(parse_duplicate_reply(&[]).unwrap(), None);
+
+        let reply = serde_json::to_vec(&P2pMessage::DuplicateFound {
+            hash: "h".into(),
+            source_node: "n".into(),
+        })
+        .unwrap();
+        let mut bytes = (reply.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(&reply);
+        assert_eq!(
+            parse_duplicate_reply(&bytes).unwrap(),
+            Some(NetworkDuplicate {
+                hash: "h".into(),
+                source_node: "n".into(),
+            })
+        );
+
+        // Truncated body or length prefix
+        assert!(parse_duplicate_reply(&bytes[..bytes.len() - 1]).is_err());
+        assert!(parse_duplicate_reply(&bytes[..2]).is_err());
+
+        // Any other message is rejected
+        let other = serde_json::to_vec(&P2pMessage::Ping).unwrap();
+        let mut bytes = (other.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(&other);
+        assert!(parse_duplicate_reply(&bytes).is_err());
     }
 
     #[test]
-    fn test_config_from_env_dht_discovery_case_insensitive() {
-        std::env::set_var("P2P_DHT_DISCOVERY", "TRUE");
-        let cfg = P2pConfig::from_env();
-        assert!(cfg.enable_dht_discovery);
-        std::env::remove_var("P2P_DHT_DISCOVERY");
+    fn test_track_access_messages_require_v2() {
+        let msg = P2pMessage::AuthorizeTrackAccess {
+            hash: "h".into(),
+            requester_node: "n".into(),
+            token: "t".into(),
+        };
+        assert!(!msg.supported_by(ProtocolVersion::V1));
+        assert!(msg.supported_by(ProtocolVersion::V2));
+        let denied = P2pMessage::AccessDenied { hash: "h".into() };
+        assert!(!denied.supported_by(ProtocolVersion::V1));
+        assert_eq!(denied.priority(), MessagePriority::High);
+        let bytes = serde_json::to_vec(&msg).unwrap();
+        match serde_json::from_slice(&bytes).unwrap() {
+            P2pMessage::AuthorizeTrackAccess {
+                hash,
+                requester_node,
+                token,
+            } => {
+                assert_eq!(hash, "h");
+                assert_eq!(requester_node, "n");
+                assert_eq!(token, "t");
+            }
+            other => panic!("expected AuthorizeTrackAccess, got {other:?}"),
+        }
     }
 
     #[test]
-    fn test_config_from_env_dht_discovery_false() {
-        std::env::set_var("P2P_DHT_DISCOVERY", "false");
-        let cfg = P2pConfig::from_env();
-        assert!(!cfg.enable_dht_discovery);
-        std::env::remove_var("P2P_DHT_DISCOVERY");
+    fn test_fetch_track_range_length_defaults_to_rest_of_blob() {
+        let json = r#"{"FetchTrackRange":{"hash":"h","offset":10}}"#;
+        match serde_json::from_str(json).unwrap() {
+            P2pMessage::FetchTrackRange {
+                length, verified, ..
+            } => {
+                assert_eq!(length, None);
+                assert!(!verified);
+            }
+            other => panic!("expected FetchTrackRange, got {other:?}"),
+        }
     }
 
+    // ── Message priority ─────────────────────────────────────────────
+
     #[test]
-    fn test_config_from_env_seed_peers() {
-        std::env::set_var("P2P_SEED_PEERS", "peer1,peer2, peer3 ");
-        let cfg = P2pConfig::from_env();
-        assert_eq!(cfg.seed_peers, vec!["peer1", "peer2", "peer3"]);
-        std::env::remove_var("P2P_SEED_PEERS");
+    fn test_message_priority_high() {
+        let high = [
+            P2pMessage::Ping,
+            P2pMessage::Pong {
+                node_id: "n".into(),
+                track_count: 0,
+                version: None,
+                capabilities: vec![],
+            },
+            P2pMessage::PeerExchange { peers: vec![] },
+            P2pMessage::SearchQuery {
+                request_id: "r".into(),
+                query: "q".into(),
+                limit: 1,
+                offset: 0,
+            },
+            P2pMessage::SearchResults {
+                request_id: "r".into(),
+                results: vec![],
+                total: 0,
+            },
+        ];
+        for msg in &high {
+            assert_eq!(msg.priority(), MessagePriority::High, "{msg:?}");
+        }
     }
 
     #[test]
-    fn test_config_from_env_seed_peers_empty_entries() {
-        std::env::set_var("P2P_SEED_PEERS", "peer1,,, ,peer2");
-        let cfg = P2pConfig::from_env();
-        assert_eq!(cfg.seed_peers, vec!["peer1", "peer2"]);
-        std::env::remove_var("P2P_SEED_PEERS");
+    fn test_message_priority_low() {
+        let low = [
+            P2pMessage::CatalogSync(vec![]),
+            P2pMessage::CatalogDelta {
+                since: chrono::Utc::now(),
+                tracks: vec![],
+            },
+            P2pMessage::RequestCatalog,
+        ];
+        for msg in &low {
+            assert_eq!(msg.priority(), MessagePriority::Low, "{msg:?}");
+        }
     }
 
+    // ── Message kind ─────────────────────────────────────────────────
+
     #[test]
-    fn test_config_from_env_audio_storage() {
-        std::env::set_var("AUDIO_STORAGE_PATH", "/music/storage");
-        let cfg = P2pConfig::from_env();
-        assert_eq!(cfg.audio_storage_path, PathBuf::from("/music/storage"));
-        std::env::remove_var("AUDIO_STORAGE_PATH");
+    fn test_message_kind_matches_stats_kinds() {
+        let msgs = [
+            P2pMessage::FetchTrack {
+                hash: "h".into(),
+                token: None,
+            },
+            P2pMessage::FetchTrackRange {
+                hash: "h".into(),
+                offset: 1,
+                length: None,
+                token: None,
+                verified: false,
+            },
+            P2pMessage::TrackData {
+                hash: "h".into(),
+                size: 1,
+            },
+            P2pMessage::Ping,
+            P2pMessage::Pong {
+                node_id: "n".into(),
+                track_count: 0,
+                version: None,
+                capabilities: vec![],
+            },
+            P2pMessage::PeerExchange { peers: vec![] },
+            P2pMessage::CatalogSync(vec![]),
+            P2pMessage::CatalogSyncPage {
+                header: CatalogPageHeader {
+                    sync_id: Uuid::new_v4(),
+                    page: 0,
+                    total_pages: 1,
+                },
+                tracks: vec![],
+            },
+            P2pMessage::CatalogSyncAck(CatalogPageAck::default()),
+            P2pMessage::CatalogDelta {
+                since: chrono::Utc::now(),
+                tracks: vec![],
+            },
+            P2pMessage::RequestCatalog,
+            P2pMessage::BloomExchange {
+                bloom: BloomFilterData {
+                    bitmap: vec![],
+                    num_hashes: 1,
+                    bitmap_bits: 8,
+                    sip_keys: [(0, 0), (0, 0)],
+                    item_count: 0,
+                    generation: 0,
+                },
+            },
+            P2pMessage::SearchQuery {
+                request_id: "r".into(),
+                query: "q".into(),
+                limit: 1,
+                offset: 0,
+            },
+            P2pMessage::SearchResults {
+                request_id: "r".into(),
+                results: vec![],
+                total: 0,
+            },
+            P2pMessage::UpdateTrackMetadata {
+                hash: "h".into(),
+                title: None,
+                artist_name: None,
+                album_title: None,
+                genre: None,
+                year: None,
+                track_number: None,
+            },
+            P2pMessage::KeepAlive,
+            P2pMessage::Goodbye {
+                node_id: "n".into(),
+                reason: "shutdown".into(),
+            },
+            P2pMessage::BlockHash {
+                hash: "h".into(),
+                reason: "r".into(),
+            },
+            P2pMessage::PlayCountUpdate {
+                hash: "h".into(),
+                origin_node: "n".into(),
+                incremental_count: 1,
+            },
+            P2pMessage::PopularityGossip { entries: vec![] },
+            P2pMessage::BloomDelta {
+                delta: BloomDeltaData {
+                    generation: 1,
+                    bitmap_bits: 8,
+                    set_bits: vec![],
+                    item_count: 0,
+                },
+            },
+            P2pMessage::RequestBloom,
+            P2pMessage::CatalogChecksumRequest,
+            P2pMessage::CatalogChecksumResponse {
+                checksum: "c".into(),
+                track_count: 0,
+            },
+            P2pMessage::AuthorizeTrackAccess {
+                hash: "h".into(),
+                requester_node: "n".into(),
+                token: "t".into(),
+            },
+            P2pMessage::AccessDenied { hash: "h".into() },
+            P2pMessage::CheckDuplicate {
+                fingerprint: "f".into(),
+                duration_secs: None,
+            },
+            P2pMessage::DuplicateFound {
+                hash: "h".into(),
+                source_node: "n".into(),
+            },
+            P2pMessage::DeleteTrack {
+                hash: "h".into(),
+                origin_node: "n".into(),
+                deleted_at: chrono::Utc::now(),
+                ttl: 3,
+                signature: None,
+            },
+        ];
+        for msg in &msgs {
+            assert!(crate::stats::MESSAGE_KINDS.contains(&msg.kind()), "{msg:?}");
+        }
+        assert_eq!(P2pMessage::Ping.kind(), "Ping");
     }
 
+    // ── Waveform in announcements ────────────────────────────────────
+
     #[test]
-    fn test_config_from_env_metadata_storage() {
-        std::env::set_var("METADATA_STORAGE_PATH", "/metadata/covers");
-        let cfg = P2pConfig::from_env();
+    fn test_announced_waveform_decodes_and_truncates() {
+        let stored = serde_json::json!([0.0, 0.5, 1.0]);
         assert_eq!(
-            cfg.metadata_storage_path,
-            Some(PathBuf::from("/metadata/covers"))
+            announced_waveform(Some(&stored), "h"),
+            Some(vec![0.0, 0.5, 1.0])
         );
-        std::env::remove_var("METADATA_STORAGE_PATH");
-    }
 
-    // ── P2pConfig Clone + Debug ──────────────────────────────────────
+        let long = serde_json::json!(vec![0.25f32; MAX_WAVEFORM_SAMPLES + 500]);
+        let waveform = announced_waveform(Some(&long), "h").unwrap();
+        assert_eq!(waveform.len(), MAX_WAVEFORM_SAMPLES);
 
-    #[test]
-    fn test_config_clone() {
-        let cfg = P2pConfig::default();
-        let cloned = cfg.clone();
-        assert_eq!(cfg.blobs_dir, cloned.blobs_dir);
-        assert_eq!(cfg.bind_port, cloned.bind_port);
+        assert_eq!(announced_waveform(None, "h"), None);
+        let garbage = serde_json::json!({"peaks": "nope"});
+        assert_eq!(announced_waveform(Some(&garbage), "h"), None);
     }
 
     #[test]
-    fn test_config_debug() {
-        let cfg = P2pConfig::default();
-        let debug = format!("{:?}", cfg);
-        assert!(debug.contains("P2pConfig"));
-        assert!(debug.contains("blobs_dir"));
+    fn test_track_announcement_waveform_roundtrip() {
+        let mut ann =
+            signed_announcement(&SecretKey::generate(&mut rand::rngs::StdRng::from_os_rng()));
+        ann.waveform_data = Some(vec![0.1, 0.73, 1.0]);
+        let decoded: TrackAnnouncement =
+            serde_json::from_slice(&serde_json::to_vec(&ann).unwrap()).unwrap();
+        assert_eq!(decoded.waveform_data, Some(vec![0.1, 0.73, 1.0]));
+
+        ann.waveform_data = None;
+        assert!(!serde_json::to_string(&ann)
+            .unwrap()
+            .contains("waveform_data"));
     }
 
-    // ── SecretKey: edge cases ────────────────────────────────────────
-
-    #[tokio::test]
-    async fn test_load_key_invalid_hex() {
-        let dir = tempfile::tempdir().unwrap();
-        let key_path = dir.path().join("secret_key");
-        // Write invalid hex (odd length, not 64 chars)
-        tokio::fs::write(&key_path, "not_valid_hex_data")
-            .await
-            .unwrap();
+    // ── Catalog page size ────────────────────────────────────────────
 
-        let config = P2pConfig {
-            secret_key_path: key_path,
-            ..Default::default()
-        };
+    #[test]
+    fn test_catalog_page_size_by_rtt() {
+        assert_eq!(compute_catalog_page_size(Some(0)), 2000);
+        assert_eq!(compute_catalog_page_size(Some(49)), 2000);
+        assert_eq!(compute_catalog_page_size(Some(50)), 500);
+        assert_eq!(compute_catalog_page_size(Some(199)), 500);
+        assert_eq!(compute_catalog_page_size(Some(200)), 100);
+        assert_eq!(compute_catalog_page_size(Some(1000)), 100);
+        assert_eq!(compute_catalog_page_size(Some(1001)), 50);
+        assert_eq!(compute_catalog_page_size(Some(u32::MAX)), 50);
+    }
 
-        // Should treat it as raw bytes and handle gracefully (fallback or error)
-        // The current impl tries hex first, then raw bytes — with 18 bytes it'll
-        // fail both and generate a new key
-        let result = P2pNode::load_or_generate_key(&config).await;
-        // Implementation may handle this differently — just ensure no panic
-        assert!(result.is_ok() || result.is_err());
+    #[test]
+    fn test_catalog_page_size_unmeasured_peer() {
+        assert_eq!(compute_catalog_page_size(None), 500);
     }
 
-    #[tokio::test]
-    async fn test_load_key_creates_parent_dirs() {
-        let dir = tempfile::tempdir().unwrap();
-        let key_path = dir.path().join("subdir").join("deep").join("secret_key");
-        let config = P2pConfig {
-            secret_key_path: key_path.clone(),
-            ..Default::default()
-        };
+    // ── Peer exchange limits ─────────────────────────────────────────
 
-        let result = P2pNode::load_or_generate_key(&config).await;
-        // Parent dirs should be created automatically
-        assert!(result.is_ok());
-        assert!(key_path.exists());
+    #[test]
+    fn test_sample_pex_peers_caps_list() {
+        let known: Vec<String> = (0..200).map(|i| format!("peer-{i}")).collect();
+        let sample = sample_pex_peers(known, "me".into());
+        assert_eq!(sample.len(), MAX_PEX_PEERS);
+        assert_eq!(sample.last().map(String::as_str), Some("me"));
+        let unique: std::collections::HashSet<_> = sample.iter().collect();
+        assert_eq!(unique.len(), MAX_PEX_PEERS);
     }
 
-    // ── ALPN constant ────────────────────────────────────────────────
+    #[test]
+    fn test_sample_pex_peers_small_list_kept() {
+        let known = vec!["a".to_string(), "me".to_string(), "b".to_string()];
+        let mut sample = sample_pex_peers(known, "me".into());
+        sample.sort();
+        assert_eq!(sample, vec!["a", "b", "me"]);
+    }
 
     #[test]
-    fn test_alpn_constant() {
-        assert_eq!(SOUNDTIME_ALPN, b"soundtime/p2p/1");
-        assert_eq!(SOUNDTIME_ALPN.len(), 15);
+    fn test_pex_queue_defers_beyond_batch() {
+        let mut queue = VecDeque::new();
+        let candidates: Vec<String> = (0..200).map(|i| format!("peer-{i}")).collect();
+        assert_eq!(enqueue_pex_candidates(&mut queue, candidates.clone()), 200);
+        // Re-announced IDs are not queued twice
+        assert_eq!(enqueue_pex_candidates(&mut queue, candidates), 0);
+
+        let batch: Vec<String> = queue.drain(..DEFAULT_PEX_BATCH_SIZE).collect();
+        assert_eq!(batch.len(), 10);
+        assert_eq!(batch[0], "peer-0");
+        assert_eq!(queue.len(), 190);
+        assert_eq!(queue.front().map(String::as_str), Some("peer-10"));
+    }
+
+    #[test]
+    fn test_pex_queue_is_bounded() {
+        let mut queue = VecDeque::new();
+        let candidates: Vec<String> = (0..MAX_PEX_QUEUE_LEN + 50)
+            .map(|i| format!("peer-{i}"))
+            .collect();
+        assert_eq!(
+            enqueue_pex_candidates(&mut queue, candidates),
+            MAX_PEX_QUEUE_LEN
+        );
+        assert_eq!(queue.len(), MAX_PEX_QUEUE_LEN);
     }
 
     // ── Multiple TrackAnnouncements in CatalogSync ───────────────────
@@ -3789,6 +11108,14 @@ mod tests {
             sample_rate: None,
             origin_node: "n".into(),
             cover_hash: None,
+            fingerprint: None,
+            waveform_data: None,
+            artist_image_hash: None,
+            artist_bio: None,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            signature: None,
         };
         let msg = P2pMessage::CatalogSync(vec![
             make_ann("h1", "Track 1"),
@@ -3816,6 +11143,7 @@ mod tests {
             request_id: "r1".into(),
             query: "日本語の曲 Ñoño café".into(),
             limit: 5,
+            offset: 0,
         };
         let bytes = serde_json::to_vec(&msg).unwrap();
         let decoded: P2pMessage = serde_json::from_slice(&bytes).unwrap();
@@ -3845,6 +11173,10 @@ mod tests {
                 source_node: "n".into(),
                 musicbrainz_id: None,
                 relevance: i as f32 / 100.0,
+                play_count: 0,
+                loudness_lufs: None,
+                dynamic_range: None,
+                encoding_quality: None,
             })
             .collect();
         let msg = P2pMessage::SearchResults {
@@ -3863,4 +11195,564 @@ mod tests {
             _ => panic!("expected SearchResults"),
         }
     }
+
+    // ── Node integration ─────────────────────────────────────────────
+
+    /// Unsigned announcement of a new track `hash` from `origin`.
+    fn test_announcement(hash: &str, origin: &str) -> TrackAnnouncement {
+        TrackAnnouncement {
+            hash: hash.into(),
+            title: format!("Song {hash}"),
+            artist_name: "Artist".into(),
+            album_artist_name: None,
+            album_title: None,
+            duration_secs: 180.0,
+            format: "flac".into(),
+            file_size: 1_000,
+            genre: None,
+            year: None,
+            track_number: None,
+            disc_number: None,
+            bitrate: None,
+            sample_rate: None,
+            origin_node: origin.into(),
+            cover_hash: None,
+            fingerprint: None,
+            waveform_data: None,
+            artist_image_hash: None,
+            artist_bio: None,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            signature: None,
+        }
+    }
+
+    /// Require a MusicBrainz match for announced tracks on `node`.
+    fn require_musicbrainz(node: &P2pNode) {
+        node.replication_policy.write().unwrap().require_musicbrainz = true;
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_check_matches_within_duration_window() {
+        let t = crate::test_node::start_node().await;
+        let mut ann = test_announcement("h1", "origin");
+        ann.fingerprint = Some("AQAAAQE".into());
+        assert_eq!(
+            t.node.process_track_announcement(ann, "peer-a").await,
+            AnnouncementOutcome::Inserted
+        );
+
+        let find = |fp: &'static str, duration: Option<f32>| {
+            let node = Arc::clone(&t.node);
+            async move {
+                node.find_public_track_by_fingerprint(fp, duration)
+                    .await
+                    .unwrap()
+            }
+        };
+        assert_eq!(find("AQAAAQE", Some(181.5)).await.as_deref(), Some("h1"));
+        assert_eq!(find("AQAAAQE", Some(240.0)).await, None);
+        assert_eq!(find("not-a-fingerprint", Some(180.0)).await, None);
+        // Without a duration only an identical fingerprint matches
+        assert_eq!(find("AQAAAQE", None).await.as_deref(), Some("h1"));
+        assert_eq!(find("AQAAAQF", None).await, None);
+    }
+
+    /// Store `count` local tracks by one artist, oldest first.
+    async fn insert_local_tracks(db: &DatabaseConnection, count: usize) {
+        let now = chrono::Utc::now();
+        let artist_id = Uuid::new_v4();
+        artist::ActiveModel {
+            id: Set(artist_id),
+            name: Set("Artist".into()),
+            musicbrainz_id: Set(None),
+            bio: Set(None),
+            image_url: Set(None),
+            created_at: Set(now.fixed_offset()),
+        }
+        .insert(db)
+        .await
+        .unwrap();
+        let tracks = (0..count).map(|i| track::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            title: Set(format!("Track {i}")),
+            artist_id: Set(artist_id),
+            album_id: Set(None),
+            track_number: Set(None),
+            disc_number: Set(None),
+            duration_secs: Set(180.0),
+            genre: Set(None),
+            year: Set(None),
+            musicbrainz_id: Set(None),
+            file_path: Set(format!("music/track{i}.flac")),
+            file_size: Set(1000),
+            format: Set("flac".into()),
+            bitrate: Set(None),
+            sample_rate: Set(None),
+            waveform_data: Set(None),
+            uploaded_by: Set(None),
+            content_hash: Set(Some(format!("hash{i}"))),
+            fingerprint: Set(None),
+            play_count: Set(0),
+            is_private: Set(false),
+            is_hidden: Set(false),
+            hidden_by_block: Set(false),
+            loudness_lufs: Set(None),
+            dynamic_range: Set(None),
+            encoding_quality: Set(None),
+            created_at: Set((now + chrono::Duration::seconds(i as i64)).fixed_offset()),
+        });
+        track::Entity::insert_many(tracks).exec(db).await.unwrap();
+    }
+
+    /// Acknowledges every catalog page it is sent, and cancels a library
+    /// sync task once a given page went through.
+    #[derive(Default)]
+    struct RecordingSink {
+        pages: std::sync::Mutex<Vec<u64>>,
+        cancel_after: Option<(crate::library_sync::SyncTaskHandle, Uuid, u64)>,
+    }
+
+    #[async_trait]
+    impl CatalogPageSink for RecordingSink {
+        async fn send_page(
+            &self,
+            _peer_id: &str,
+            header: &CatalogPageHeader,
+            tracks: &[TrackAnnouncement],
+        ) -> Result<CatalogPageAck, P2pError> {
+            self.pages.lock().unwrap().push(header.page);
+            if let Some((tracker, task_id, page)) = &self.cancel_after {
+                if header.page == *page {
+                    tracker.cancel(*task_id).await.unwrap();
+                }
+            }
+            Ok(CatalogPageAck {
+                sync_id: header.sync_id,
+                page: header.page,
+                inserted: tracks.len() as u64,
+                ..Default::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_catalog_push_resumes_from_stored_page() {
+        let t = crate::test_node::start_node().await;
+        insert_local_tracks(&t.db, 160).await;
+        // Any valid node ID will do; a slow peer gets pages of 50 tracks
+        let peer_id = t.node.node_id();
+        let peer_key = peer_id.to_string();
+        t.node.registry().upsert_peer(&peer_key, None, 0).await;
+        t.node.registry().record_rtt(&peer_key, 2000).await;
+
+        let tracker = crate::library_sync::new_sync_tracker();
+        let mut gate = tracker.start(&peer_key).await.unwrap();
+        let sink = RecordingSink {
+            pages: Default::default(),
+            cancel_after: Some((tracker.clone(), gate.task_id(), 1)),
+        };
+        let result = t
+            .node
+            .send_catalog_pages_to(&sink, peer_id, &peer_key, Some(&mut gate))
+            .await;
+        assert_eq!(result, Err("cancelled".to_string()));
+        assert_eq!(*sink.pages.lock().unwrap(), [0, 1]);
+        let checkpoint = *t.node.catalog_sync_checkpoints.get(&peer_key).unwrap();
+        assert_eq!(checkpoint.page, 1);
+        assert_eq!(checkpoint.page_size, 50);
+
+        // The next push sends only the pages after the stored one, with the
+        // same page size, and then forgets the checkpoint
+        t.node.registry().record_rtt(&peer_key, 10).await;
+        let sink = RecordingSink::default();
+        t.node
+            .send_catalog_pages_to(&sink, peer_id, &peer_key, None)
+            .await
+            .unwrap();
+        assert_eq!(*sink.pages.lock().unwrap(), [2, 3]);
+        assert!(t.node.catalog_sync_checkpoints.get(&peer_key).is_none());
+    }
+
+    /// Records the tracks it is told about.
+    #[derive(Default)]
+    struct RecordingHook {
+        calls: std::sync::Mutex<Vec<(String, String)>>,
+    }
+
+    impl TrackAnnouncedHook for RecordingHook {
+        fn track_announced(&self, ann: &TrackAnnouncement, peer_id: &str) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((ann.hash.clone(), peer_id.to_string()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_track_announced_hook_fires_for_stored_tracks() {
+        let t = crate::test_node::start_node().await;
+        let hook = Arc::new(RecordingHook::default());
+        assert!(t.node.set_track_announced_hook(hook.clone()));
+
+        t.node
+            .process_track_announcement(test_announcement("h1", "origin"), "peer-a")
+            .await;
+        // In a catalog page, a track already stored is not announced again
+        let ack = t
+            .node
+            .process_catalog_page(
+                vec![
+                    test_announcement("h1", "origin"),
+                    test_announcement("h2", "origin"),
+                ],
+                "peer-b",
+            )
+            .await
+            .unwrap();
+        assert_eq!(ack.inserted, 1);
+
+        assert_eq!(
+            *hook.calls.lock().unwrap(),
+            [
+                ("h1".to_string(), "peer-a".to_string()),
+                ("h2".to_string(), "peer-b".to_string())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_peer_track_cap_holds_under_concurrent_announcements() {
+        let t = crate::test_node::start_node().await;
+        t.node
+            .set_peer_filter(
+                "peer-a",
+                PeerFilter {
+                    max_tracks: Some(2),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let announce = |hash: &'static str| {
+            t.node
+                .process_track_announcement(test_announcement(hash, "origin"), "peer-a")
+        };
+        let outcomes = tokio::join!(announce("h1"), announce("h2"), announce("h3"));
+        let outcomes = [outcomes.0, outcomes.1, outcomes.2];
+        let inserted = outcomes
+            .iter()
+            .filter(|o| **o == AnnouncementOutcome::Inserted)
+            .count();
+        assert_eq!(inserted, 2);
+        assert_eq!(*t.node.peer_track_counts.get("peer-a").unwrap(), 2);
+
+        let ack = t
+            .node
+            .process_catalog_page(vec![test_announcement("h4", "origin")], "peer-a")
+            .await
+            .unwrap();
+        assert_eq!((ack.inserted, ack.skipped), (0, 1));
+
+        // A track removed since frees its slot at the peer's next page
+        let row = remote_track::Entity::find()
+            .filter(remote_track::Column::InstanceDomain.eq("p2p://peer-a"))
+            .one(&t.db)
+            .await
+            .unwrap()
+            .unwrap();
+        remote_track::Entity::delete_by_id(row.id)
+            .exec(&t.db)
+            .await
+            .unwrap();
+        let ack = t
+            .node
+            .process_catalog_page(vec![test_announcement("h5", "origin")], "peer-a")
+            .await
+            .unwrap();
+        assert_eq!(ack.inserted, 1);
+    }
+
+    #[tokio::test]
+    async fn test_musicbrainz_requirement_stores_track_pending() {
+        let t = crate::test_node::start_node().await;
+        require_musicbrainz(&t.node);
+
+        // Stored right away, without waiting for a lookup
+        let outcome = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            t.node
+                .process_track_announcement(test_announcement("h1", "origin"), "peer-a"),
+        )
+        .await
+        .expect("announcement waited for a lookup");
+        assert_eq!(outcome, AnnouncementOutcome::Inserted);
+
+        let stored = track::Entity::find().one(&t.db).await.unwrap().unwrap();
+        assert!(stored.is_hidden);
+        assert!(stored.musicbrainz_id.is_none());
+        let pending = mb_lookup_queue::Entity::find_by_id(stored.id)
+            .one(&t.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(pending.pending_verification);
+        assert_eq!(pending.announced_by.as_deref(), Some("peer-a"));
+
+        // A re-reference does not show it before it is verified
+        let remote = remote_track::Entity::find()
+            .one(&t.db)
+            .await
+            .unwrap()
+            .unwrap();
+        crate::track_cleanup::unhide_tracks(&t.db, &[remote.id])
+            .await
+            .unwrap();
+        let stored = track::Entity::find_by_id(stored.id)
+            .one(&t.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(stored.is_hidden);
+    }
+
+    #[tokio::test]
+    async fn test_musicbrainz_verification_outcomes() {
+        let t = crate::test_node::start_node().await;
+        require_musicbrainz(&t.node);
+        for hash in ["matched", "unmatched"] {
+            t.node
+                .process_track_announcement(test_announcement(hash, "origin"), "peer-a")
+                .await;
+        }
+        let track_id = |hash: &'static str| {
+            let db = t.db.clone();
+            async move {
+                track::Entity::find()
+                    .filter(track::Column::ContentHash.eq(hash))
+                    .one(&db)
+                    .await
+                    .unwrap()
+                    .map(|t| t.id)
+            }
+        };
+
+        // A match shows the track
+        let matched = track_id("matched").await.unwrap();
+        t.node
+            .accept_verified_track(matched, "mbid-1".into())
+            .await
+            .unwrap();
+        let stored = track::Entity::find_by_id(matched)
+            .one(&t.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!stored.is_hidden);
+        assert_eq!(stored.musicbrainz_id.as_deref(), Some("mbid-1"));
+        assert!(mb_lookup_queue::Entity::find_by_id(matched)
+            .one(&t.db)
+            .await
+            .unwrap()
+            .is_none());
+
+        // No match rejects it
+        let unmatched = track_id("unmatched").await.unwrap();
+        let entry = mb_lookup_queue::Entity::find_by_id(unmatched)
+            .one(&t.db)
+            .await
+            .unwrap()
+            .unwrap();
+        t.node.reject_unverified_track(&entry).await.unwrap();
+        assert!(track_id("unmatched").await.is_none());
+        assert!(remote_track::Entity::find()
+            .filter(remote_track::Column::LocalTrackId.eq(unmatched))
+            .one(&t.db)
+            .await
+            .unwrap()
+            .is_none());
+        let rejected = t.node.rejected_announcements();
+        assert_eq!(rejected[0].hash, "unmatched");
+        assert_eq!(rejected[0].peer_id, "peer-a");
+        assert_eq!(
+            rejected[0].reason,
+            RejectReason::NoMusicBrainzMatch.to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reannouncement_backfills_waveform_and_loudness() {
+        let t = crate::test_node::start_node().await;
+        t.node
+            .process_track_announcement(test_announcement("h1", "origin"), "origin")
+            .await;
+
+        let mut later = test_announcement("h1", "origin");
+        later.waveform_data = Some(vec![0.25, 0.5]);
+        later.loudness_lufs = Some(-14.0);
+        later.dynamic_range = Some(6.5);
+        let outcome = t.node.process_track_announcement(later, "origin").await;
+        assert_eq!(outcome, AnnouncementOutcome::Skipped);
+
+        let stored = track::Entity::find().one(&t.db).await.unwrap().unwrap();
+        assert_eq!(stored.waveform_data, Some(serde_json::json!([0.25, 0.5])));
+        assert_eq!(stored.loudness_lufs, Some(-14.0));
+        assert_eq!(stored.dynamic_range, Some(6.5));
+
+        // Values already set are kept
+        let mut other = test_announcement("h1", "origin");
+        other.waveform_data = Some(vec![1.0]);
+        other.loudness_lufs = Some(-5.0);
+        t.node.process_track_announcement(other, "origin").await;
+        let stored = track::Entity::find().one(&t.db).await.unwrap().unwrap();
+        assert_eq!(stored.waveform_data, Some(serde_json::json!([0.25, 0.5])));
+        assert_eq!(stored.loudness_lufs, Some(-14.0));
+    }
+
+    // ── Blocked hashes ──
+
+    #[tokio::test]
+    async fn test_blocked_hash_refused_on_every_path() {
+        let t = crate::test_node::start_node().await;
+        let hash = t
+            .node
+            .publish_track(Bytes::from_static(b"blocked audio"))
+            .await
+            .unwrap();
+        let h = hash.to_string();
+        assert!(!t
+            .node
+            .blob_reply("peer-a", &h, 0, None, false)
+            .await
+            .is_empty());
+
+        t.node.block_hash_locally(hash, "test").await.unwrap();
+
+        // FetchTrack / FetchTrackRange get an empty reply
+        assert!(t
+            .node
+            .blob_reply("peer-a", &h, 0, None, false)
+            .await
+            .is_empty());
+        assert!(t
+            .node
+            .blob_reply("peer-a", &h, 0, Some(4), true)
+            .await
+            .is_empty());
+        // Announcements of it are dropped
+        let outcome = t
+            .node
+            .process_track_announcement(test_announcement(&h, "origin"), "peer-a")
+            .await;
+        assert_eq!(outcome, AnnouncementOutcome::Rejected);
+        assert!(track::Entity::find().one(&t.db).await.unwrap().is_none());
+        // Playing it is refused, though the blob is stored
+        assert!(matches!(
+            t.node.get_or_fetch_track(hash).await,
+            Err(P2pError::HashBlocked { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unblock_shows_tracks_the_block_hid() {
+        use sea_orm::TransactionTrait;
+
+        let t = crate::test_node::start_node().await;
+        let shown = Hash::new(b"shown").to_string();
+        let dead = Hash::new(b"dead").to_string();
+        for hash in [&shown, &dead] {
+            t.node
+                .process_track_announcement(test_announcement(hash, "origin"), "peer-a")
+                .await;
+        }
+        let find = |hash: String| {
+            let db = t.db.clone();
+            async move {
+                track::Entity::find()
+                    .filter(track::Column::ContentHash.eq(hash))
+                    .one(&db)
+                    .await
+                    .unwrap()
+                    .unwrap()
+            }
+        };
+        // Hidden as dead before the block
+        let mut update: track::ActiveModel = find(dead.clone()).await.into();
+        update.is_hidden = Set(true);
+        update.update(&t.db).await.unwrap();
+
+        for hash in [&shown, &dead] {
+            t.node
+                .block_hash_locally(hash.parse().unwrap(), "test")
+                .await
+                .unwrap();
+        }
+        let blocked = find(shown.clone()).await;
+        assert!(blocked.is_hidden && blocked.hidden_by_block);
+        assert!(!find(dead.clone()).await.hidden_by_block);
+
+        for hash in [&shown, &dead] {
+            let txn = t.db.begin().await.unwrap();
+            assert!(t.node.unblock_hash(txn, hash).await.unwrap());
+        }
+        let unblocked = find(shown.clone()).await;
+        assert!(!unblocked.is_hidden && !unblocked.hidden_by_block);
+        let remote = remote_track::Entity::find()
+            .filter(remote_track::Column::LocalTrackId.eq(unblocked.id))
+            .one(&t.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(remote.is_available);
+        // Still dead
+        assert!(find(dead).await.is_hidden);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_corrupt_blob_is_neither_read_nor_served() {
+        let t = crate::test_node::start_node().await;
+        let hash = t
+            .node
+            .publish_track(Bytes::from_static(b"corrupt audio"))
+            .await
+            .unwrap();
+        let h = hash.to_string();
+        assert!(t.node.has_blob(hash).await);
+
+        TrackFetcher::delete_local_blob(&t.node, &h).await;
+
+        // The bytes wait in the store for its garbage collector...
+        assert!(t.node.blob_store.blobs().has(hash).await.unwrap());
+        // ...but are treated as missing meanwhile
+        assert!(!t.node.has_blob(hash).await);
+        assert!(t.node.get_local_track(hash).await.is_err());
+        assert!(t
+            .node
+            .get_local_track_range(hash, 0, None)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(TrackFetcher::read_local_blob(&t.node, &h)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(t
+            .node
+            .blob_reply("peer-a", &h, 0, None, false)
+            .await
+            .is_empty());
+        assert!(t
+            .node
+            .blob_reply("peer-a", &h, 0, Some(4), true)
+            .await
+            .is_empty());
+
+        // The mark goes once the store no longer holds the blob
+        let swept = Hash::new(b"swept");
+        t.node.corrupted_blobs.insert(swept);
+        assert!(!t.node.is_marked_corrupted(swept).await);
+        assert!(!t.node.corrupted_blobs.contains(&swept));
+    }
 }
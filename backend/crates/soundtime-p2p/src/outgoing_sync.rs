@@ -0,0 +1,162 @@
+//! Coalescing of outgoing catalog syncs to the same peer.
+//!
+//! A newly discovered peer can trigger `announce_all_tracks_to_peer` from the
+//! seed connection, PEX verification, its first Ping and a `RequestCatalog`,
+//! all within a few seconds. [`OutgoingSyncGuard`] lets only one of those
+//! callers run a sync pass per peer; the others just flag that another pass
+//! is wanted, and the running caller does exactly one more pass once it is
+//! done. However many requests arrive during a pass, they cost at most one
+//! extra pass.
+
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use serde::Serialize;
+
+/// An outgoing catalog sync currently running for one peer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct OutgoingSync {
+    pub peer_id: String,
+    /// Another pass was requested while this one was running
+    pub rerun_requested: bool,
+}
+
+/// Per-peer guard for outgoing catalog syncs, keyed by peer EndpointId.
+#[derive(Default)]
+pub struct OutgoingSyncGuard {
+    running: DashMap<String, bool>,
+}
+
+impl OutgoingSyncGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Claim the sync for `peer_id`. Returns `true` if the caller should run
+    /// a pass now; `false` if one is already running, in which case a rerun
+    /// is requested from it instead.
+    pub fn begin(&self, peer_id: &str) -> bool {
+        match self.running.entry(peer_id.to_string()) {
+            Entry::Occupied(mut e) => {
+                *e.get_mut() = true;
+                false
+            }
+            Entry::Vacant(e) => {
+                e.insert(false);
+                true
+            }
+        }
+    }
+
+    /// Called by the claiming caller after each pass. Returns `true` if a
+    /// rerun was requested meanwhile (the caller keeps the claim and should
+    /// run another pass), otherwise releases the claim and returns `false`.
+    pub fn finish_pass(&self, peer_id: &str) -> bool {
+        match self.running.entry(peer_id.to_string()) {
+            Entry::Occupied(mut e) => {
+                if *e.get() {
+                    *e.get_mut() = false;
+                    true
+                } else {
+                    e.remove();
+                    false
+                }
+            }
+            Entry::Vacant(_) => false,
+        }
+    }
+
+    /// Whether a sync to `peer_id` is currently running.
+    pub fn is_running(&self, peer_id: &str) -> bool {
+        self.running.contains_key(peer_id)
+    }
+
+    /// All syncs currently running, sorted by peer.
+    pub fn running(&self) -> Vec<OutgoingSync> {
+        let mut syncs: Vec<OutgoingSync> = self
+            .running
+            .iter()
+            .map(|e| OutgoingSync {
+                peer_id: e.key().clone(),
+                rerun_requested: *e.value(),
+            })
+            .collect();
+        syncs.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+        syncs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Same loop `P2pNode::announce_all_tracks_to_peer` runs, with a pass
+    /// that just counts and sleeps.
+    async fn sync(guard: &OutgoingSyncGuard, peer: &str, passes: &AtomicUsize) {
+        if !guard.begin(peer) {
+            return;
+        }
+        loop {
+            passes.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            if !guard.finish_pass(peer) {
+                break;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_coalesce() {
+        let guard = Arc::new(OutgoingSyncGuard::new());
+        let passes = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..10)
+            .map(|_| {
+                let guard = Arc::clone(&guard);
+                let passes = Arc::clone(&passes);
+                tokio::spawn(async move { sync(&guard, "peer-a", &passes).await })
+            })
+            .collect();
+        for h in handles {
+            h.await.unwrap();
+        }
+
+        let passes = passes.load(Ordering::SeqCst);
+        assert!((1..=2).contains(&passes), "ran {passes} passes");
+        assert!(!guard.is_running("peer-a"));
+    }
+
+    #[test]
+    fn test_rerun_requested_once() {
+        let guard = OutgoingSyncGuard::new();
+        assert!(guard.begin("peer-a"));
+        assert!(!guard.begin("peer-a"));
+        assert!(!guard.begin("peer-a"));
+        assert_eq!(
+            guard.running(),
+            vec![OutgoingSync {
+                peer_id: "peer-a".into(),
+                rerun_requested: true,
+            }]
+        );
+
+        // One extra pass, then the claim is released
+        assert!(guard.finish_pass("peer-a"));
+        assert!(!guard.finish_pass("peer-a"));
+        assert!(guard.running().is_empty());
+        assert!(guard.begin("peer-a"));
+    }
+
+    #[test]
+    fn test_peers_are_independent() {
+        let guard = OutgoingSyncGuard::new();
+        assert!(guard.begin("peer-a"));
+        assert!(guard.begin("peer-b"));
+        assert_eq!(guard.running().len(), 2);
+        assert!(!guard.finish_pass("peer-b"));
+        assert!(guard.is_running("peer-a"));
+        assert!(!guard.is_running("peer-b"));
+    }
+}
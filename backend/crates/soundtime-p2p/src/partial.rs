@@ -0,0 +1,239 @@
+//! Partial downloads of track blobs, so interrupted fetches can resume.
+//!
+//! Bytes received from a peer are appended to `<dir>/<hash>.partial` as they
+//! arrive. If the connection drops, the file stays on disk and the next fetch
+//! asks the peer only for the bytes after what is already present (see
+//! `P2pMessage::FetchTrackRange`). Once the peer has sent everything, the whole
+//! file is checked against the expected BLAKE3 hash before it is handed to the
+//! blob store; a mismatch discards the partial data.
+//!
+//! Only one [`PartialDownload`] per file exists at a time: fetch paths that
+//! bypass the in-flight dedup (health recovery, swarm and range fetches) wait
+//! in [`PartialDownload::open`] until the attempt holding the file is done,
+//! instead of interleaving their bytes into it.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use bytes::Bytes;
+use iroh_blobs::Hash;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::OwnedMutexGuard;
+
+use crate::error::P2pError;
+use crate::track_health::verify_blob;
+
+/// Per-file locks of the partial downloads currently open, keyed by path.
+static OPEN_PARTIALS: LazyLock<Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>> =
+    LazyLock::new(Default::default);
+
+/// Exclusive use of one partial file, released on drop.
+struct PartialLock {
+    path: PathBuf,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl PartialLock {
+    /// Wait until no other download holds `path`.
+    async fn acquire(path: PathBuf) -> Self {
+        let lock = {
+            let mut open = OPEN_PARTIALS.lock().unwrap_or_else(|e| e.into_inner());
+            Arc::clone(open.entry(path.clone()).or_default())
+        };
+        let guard = lock.lock_owned().await;
+        Self {
+            path,
+            guard: Some(guard),
+        }
+    }
+}
+
+impl Drop for PartialLock {
+    fn drop(&mut self) {
+        drop(self.guard.take());
+        // Forget the lock once nobody holds or waits for it
+        let mut open = OPEN_PARTIALS.lock().unwrap_or_else(|e| e.into_inner());
+        if open
+            .get(&self.path)
+            .is_some_and(|lock| Arc::strong_count(lock) == 1)
+        {
+            open.remove(&self.path);
+        }
+    }
+}
+
+/// An in-progress blob download backed by a file on disk.
+pub struct PartialDownload {
+    hash: Hash,
+    path: PathBuf,
+    file: tokio::fs::File,
+    received: u64,
+    // Declared last so the file is closed before the next attempt opens it
+    _lock: PartialLock,
+}
+
+impl PartialDownload {
+    /// Open (or create) the partial file for `hash` in `dir`, keeping any
+    /// bytes left by an earlier attempt. Waits while another download of the
+    /// same hash holds the file.
+    pub async fn open(dir: &Path, hash: Hash) -> Result<Self, P2pError> {
+        tokio::fs::create_dir_all(dir).await?;
+        let path = dir.join(format!("{hash}.partial"));
+        let lock = PartialLock::acquire(path.clone()).await;
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        let received = file.metadata().await?.len();
+        Ok(Self {
+            hash,
+            path,
+            file,
+            received,
+            _lock: lock,
+        })
+    }
+
+    /// Number of bytes already on disk — the offset to resume from.
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// Append a chunk received from the peer.
+    pub async fn append(&mut self, chunk: &[u8]) -> Result<(), P2pError> {
+        self.file.write_all(chunk).await?;
+        self.received += chunk.len() as u64;
+        Ok(())
+    }
+
+    /// Drop any bytes received so far and start over from offset 0.
+    pub async fn reset(&mut self) -> Result<(), P2pError> {
+        self.file.set_len(0).await?;
+        self.received = 0;
+        Ok(())
+    }
+
+    /// Verify the complete download against its BLAKE3 hash and return the
+    /// data, removing the partial file. On a mismatch the file is deleted too,
+    /// so the next attempt starts from scratch.
+    pub async fn finish(mut self) -> Result<Bytes, P2pError> {
+        self.file.flush().await?;
+        let data = tokio::fs::read(&self.path).await?;
+        let _ = tokio::fs::remove_file(&self.path).await;
+
+        verify_blob(&self.hash, &data)?;
+        Ok(Bytes::from(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stream `blob[received..]` into `partial` in 1 KiB chunks, yielding
+    /// between chunks like a network read would. Fails after `fail_after`
+    /// bytes to mimic a dropped connection. Returns the number of bytes that
+    /// went over the "wire".
+    async fn transfer(
+        partial: &mut PartialDownload,
+        blob: &[u8],
+        fail_after: Option<usize>,
+    ) -> (usize, Result<(), P2pError>) {
+        let remaining = &blob[partial.received() as usize..];
+        let mut sent = 0;
+        for chunk in remaining.chunks(1024) {
+            if fail_after.is_some_and(|limit| sent >= limit) {
+                return (sent, Err(P2pError::Connection("connection lost".into())));
+            }
+            partial.append(chunk).await.unwrap();
+            sent += chunk.len();
+            tokio::task::yield_now().await;
+        }
+        (sent, Ok(()))
+    }
+
+    fn blob() -> Vec<u8> {
+        (0..100 * 1024).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_resumes_do_not_interleave() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = Arc::new(blob());
+        let hash = Hash::new(data.as_slice());
+
+        // First attempt dies 40 KiB in
+        let mut partial = PartialDownload::open(dir.path(), hash).await.unwrap();
+        let (sent, result) = transfer(&mut partial, &data, Some(40 * 1024)).await;
+        assert!(result.is_err());
+        assert_eq!(sent, 40 * 1024);
+        drop(partial);
+
+        // Two fetch paths resume the same hash at once
+        let resumes: Vec<_> = (0..2)
+            .map(|_| {
+                let dir = dir.path().to_path_buf();
+                let data = Arc::clone(&data);
+                tokio::spawn(async move {
+                    let mut partial = PartialDownload::open(&dir, hash).await.unwrap();
+                    let offset = partial.received();
+                    let (sent, result) = transfer(&mut partial, &data, None).await;
+                    result.unwrap();
+                    (offset, sent, partial.finish().await)
+                })
+            })
+            .collect();
+
+        let mut offsets = Vec::new();
+        for resume in resumes {
+            let (offset, sent, fetched) = resume.await.unwrap();
+            assert_eq!(fetched.unwrap().as_ref(), data.as_slice());
+            assert_eq!(offset as usize + sent, data.len());
+            offsets.push(offset);
+        }
+        // One picked up the saved bytes; the other waited for it to finish
+        // and, finding no partial file left, started over
+        offsets.sort_unstable();
+        assert_eq!(offsets, vec![0, 40 * 1024]);
+        assert!(!dir.path().join(format!("{hash}.partial")).exists());
+        assert!(!OPEN_PARTIALS
+            .lock()
+            .unwrap()
+            .contains_key(&dir.path().join(format!("{hash}.partial"))));
+    }
+
+    #[tokio::test]
+    async fn test_finish_rejects_hash_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = blob();
+        let hash = Hash::new(b"something else");
+
+        let mut partial = PartialDownload::open(dir.path(), hash).await.unwrap();
+        partial.append(&data).await.unwrap();
+        assert!(matches!(
+            partial.finish().await,
+            Err(P2pError::HashMismatch { .. })
+        ));
+
+        // Corrupt data is not kept around for a later resume
+        let partial = PartialDownload::open(dir.path(), hash).await.unwrap();
+        assert_eq!(partial.received(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_reset_discards_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = blob();
+        let hash = Hash::new(&data);
+
+        let mut partial = PartialDownload::open(dir.path(), hash).await.unwrap();
+        partial.append(&data[..1000]).await.unwrap();
+        partial.reset().await.unwrap();
+        assert_eq!(partial.received(), 0);
+
+        partial.append(&data).await.unwrap();
+        assert_eq!(partial.finish().await.unwrap().len(), data.len());
+    }
+}
@@ -0,0 +1,315 @@
+//! Per-peer replication filters ("pull filters").
+//!
+//! The instance-wide [`ReplicationPolicy`](crate::ReplicationPolicy) applies
+//! to every peer; a [`PeerFilter`] narrows what is replicated from one peer,
+//! e.g. only the jazz and classical uploads of a large catalog. Filters are
+//! stored in `p2p_peer_filters`, cached by the node and checked by
+//! `P2pNode::process_track_announcement` against the peer that sent the
+//! announcement. Refused announcements land in the same rejection log as
+//! policy rejections.
+//!
+//! A metadata-only peer's tracks are listed in the local catalog, but their
+//! blobs are never downloaded (`P2pNode::get_or_fetch_track` refuses them).
+
+use std::collections::HashMap;
+
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use serde::{Deserialize, Serialize};
+use soundtime_db::entities::p2p_peer_filter;
+
+use crate::error::P2pError;
+use crate::node::TrackAnnouncement;
+use crate::replication_policy::RejectReason;
+
+/// What is replicated from one peer. The default replicates everything.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PeerFilter {
+    /// Accepted genres, lowercase (empty = any)
+    pub allowed_genres: Vec<String>,
+    /// Accepted artists, lowercase (empty = any)
+    pub allowed_artists: Vec<String>,
+    /// Most tracks replicated from the peer
+    pub max_tracks: Option<u64>,
+    /// List the peer's tracks but never download their blobs
+    pub metadata_only: bool,
+}
+
+/// Trim and lowercase list items, dropping blanks and duplicates.
+fn normalize_list(items: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for item in items {
+        let item = item.trim().to_lowercase();
+        if !item.is_empty() && !out.contains(&item) {
+            out.push(item);
+        }
+    }
+    out
+}
+
+fn matches_any(allowed: &[String], value: &str) -> bool {
+    allowed.iter().any(|a| a.eq_ignore_ascii_case(value.trim()))
+}
+
+impl PeerFilter {
+    /// Lowercase the allowlists, as they are stored and compared.
+    pub fn normalized(self) -> Self {
+        Self {
+            allowed_genres: normalize_list(self.allowed_genres),
+            allowed_artists: normalize_list(self.allowed_artists),
+            ..self
+        }
+    }
+
+    /// Check the announcement's genre and artist against the allowlists.
+    /// With a genre allowlist, tracks without a genre are refused.
+    pub fn check(&self, ann: &TrackAnnouncement) -> Result<(), RejectReason> {
+        if !self.allowed_genres.is_empty() {
+            match ann.genre.as_deref() {
+                Some(genre) if matches_any(&self.allowed_genres, genre) => {}
+                genre => {
+                    return Err(RejectReason::GenreNotAllowedFromPeer(
+                        genre.map(str::to_string),
+                    ))
+                }
+            }
+        }
+        if !self.allowed_artists.is_empty()
+            && !matches_any(&self.allowed_artists, &ann.artist_name)
+            && !ann
+                .album_artist_name
+                .as_deref()
+                .is_some_and(|a| matches_any(&self.allowed_artists, a))
+        {
+            return Err(RejectReason::ArtistNotAllowedFromPeer(
+                ann.artist_name.clone(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check the track cap, given how many tracks were already replicated
+    /// from the peer.
+    pub fn check_track_limit(&self, replicated: u64) -> Result<(), RejectReason> {
+        match self.max_tracks {
+            Some(max) if replicated >= max => Err(RejectReason::PeerTrackLimit(max)),
+            _ => Ok(()),
+        }
+    }
+
+    fn from_model(model: &p2p_peer_filter::Model) -> Self {
+        let list = |v: &serde_json::Value| -> Vec<String> {
+            serde_json::from_value(v.clone()).unwrap_or_default()
+        };
+        Self {
+            allowed_genres: list(&model.allowed_genres),
+            allowed_artists: list(&model.allowed_artists),
+            max_tracks: model.max_tracks.map(|m| m.max(0) as u64),
+            metadata_only: model.metadata_only,
+        }
+    }
+}
+
+/// Every stored filter, keyed by peer ID.
+pub async fn load_all(db: &DatabaseConnection) -> Result<HashMap<String, PeerFilter>, P2pError> {
+    Ok(p2p_peer_filter::Entity::find()
+        .all(db)
+        .await?
+        .iter()
+        .map(|m| (m.peer_id.clone(), PeerFilter::from_model(m)))
+        .collect())
+}
+
+/// Insert or replace the filter for `peer_id`.
+pub async fn save(
+    db: &DatabaseConnection,
+    peer_id: &str,
+    filter: &PeerFilter,
+) -> Result<(), P2pError> {
+    let model = p2p_peer_filter::ActiveModel {
+        peer_id: Set(peer_id.to_string()),
+        allowed_genres: Set(serde_json::json!(filter.allowed_genres)),
+        allowed_artists: Set(serde_json::json!(filter.allowed_artists)),
+        max_tracks: Set(filter.max_tracks.map(|m| m.min(i64::MAX as u64) as i64)),
+        metadata_only: Set(filter.metadata_only),
+        updated_at: Set(chrono::Utc::now().into()),
+    };
+    p2p_peer_filter::Entity::insert(model)
+        .on_conflict(
+            sea_orm::sea_query::OnConflict::column(p2p_peer_filter::Column::PeerId)
+                .update_columns([
+                    p2p_peer_filter::Column::AllowedGenres,
+                    p2p_peer_filter::Column::AllowedArtists,
+                    p2p_peer_filter::Column::MaxTracks,
+                    p2p_peer_filter::Column::MetadataOnly,
+                    p2p_peer_filter::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Remove the filter for `peer_id`. Returns `false` if there was none.
+pub async fn delete(db: &DatabaseConnection, peer_id: &str) -> Result<bool, P2pError> {
+    let res = p2p_peer_filter::Entity::delete_by_id(peer_id.to_string())
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn announcement(
+        artist: &str,
+        album_artist: Option<&str>,
+        genre: Option<&str>,
+    ) -> TrackAnnouncement {
+        TrackAnnouncement {
+            hash: "hash-1".into(),
+            title: "Song".into(),
+            artist_name: artist.into(),
+            album_artist_name: album_artist.map(String::from),
+            album_title: None,
+            duration_secs: 180.0,
+            format: "flac".into(),
+            file_size: 1000,
+            genre: genre.map(String::from),
+            year: None,
+            track_number: None,
+            disc_number: None,
+            bitrate: None,
+            sample_rate: None,
+            origin_node: "origin".into(),
+            cover_hash: None,
+            fingerprint: None,
+            waveform_data: None,
+            artist_image_hash: None,
+            artist_bio: None,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            signature: None,
+        }
+    }
+
+    // ── check ──
+
+    #[test]
+    fn test_default_filter_accepts_everything() {
+        let filter = PeerFilter::default();
+        assert_eq!(filter.check(&announcement("Anyone", None, None)), Ok(()));
+        assert_eq!(filter.check_track_limit(1_000_000), Ok(()));
+    }
+
+    #[test]
+    fn test_genre_allowlist() {
+        let filter = PeerFilter {
+            allowed_genres: vec!["Jazz ".into(), "classical".into()],
+            ..Default::default()
+        }
+        .normalized();
+        assert_eq!(filter.allowed_genres, ["jazz", "classical"]);
+
+        assert_eq!(filter.check(&announcement("A", None, Some("JAZZ"))), Ok(()));
+        assert_eq!(
+            filter.check(&announcement("A", None, Some("Classical"))),
+            Ok(())
+        );
+        assert_eq!(
+            filter.check(&announcement("A", None, Some("Techno"))),
+            Err(RejectReason::GenreNotAllowedFromPeer(Some("Techno".into())))
+        );
+        // No genre cannot match an allowlist
+        assert_eq!(
+            filter.check(&announcement("A", None, None)),
+            Err(RejectReason::GenreNotAllowedFromPeer(None))
+        );
+    }
+
+    #[test]
+    fn test_artist_allowlist_matches_album_artist() {
+        let filter = PeerFilter {
+            allowed_artists: vec!["Miles Davis".into()],
+            ..Default::default()
+        }
+        .normalized();
+        assert_eq!(
+            filter.check(&announcement("miles davis", None, None)),
+            Ok(())
+        );
+        // A guest track on an allowed artist's album is accepted
+        assert_eq!(
+            filter.check(&announcement("Guest", Some("Miles Davis"), None)),
+            Ok(())
+        );
+        assert_eq!(
+            filter.check(&announcement("Someone Else", None, None)),
+            Err(RejectReason::ArtistNotAllowedFromPeer(
+                "Someone Else".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_genre_and_artist_both_apply() {
+        let filter = PeerFilter {
+            allowed_genres: vec!["jazz".into()],
+            allowed_artists: vec!["miles davis".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            filter.check(&announcement("Miles Davis", None, Some("Jazz"))),
+            Ok(())
+        );
+        assert!(filter
+            .check(&announcement("Miles Davis", None, Some("Rock")))
+            .is_err());
+        assert!(filter
+            .check(&announcement("Other", None, Some("Jazz")))
+            .is_err());
+    }
+
+    #[test]
+    fn test_track_limit() {
+        let filter = PeerFilter {
+            max_tracks: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(filter.check_track_limit(99), Ok(()));
+        assert_eq!(
+            filter.check_track_limit(100),
+            Err(RejectReason::PeerTrackLimit(100))
+        );
+    }
+
+    // ── storage ──
+
+    #[test]
+    fn test_from_model() {
+        let model = p2p_peer_filter::Model {
+            peer_id: "peer-a".into(),
+            allowed_genres: serde_json::json!(["jazz"]),
+            allowed_artists: serde_json::json!("not a list"),
+            max_tracks: Some(-5),
+            metadata_only: true,
+            updated_at: chrono::Utc::now().into(),
+        };
+        let filter = PeerFilter::from_model(&model);
+        assert_eq!(filter.allowed_genres, ["jazz"]);
+        assert!(filter.allowed_artists.is_empty());
+        assert_eq!(filter.max_tracks, Some(0));
+        assert!(filter.metadata_only);
+    }
+
+    #[test]
+    fn test_deserialize_partial_filter() {
+        let filter: PeerFilter = serde_json::from_str(r#"{"metadata_only":true}"#).unwrap();
+        assert!(filter.metadata_only);
+        assert!(filter.allowed_genres.is_empty());
+        assert!(filter.max_tracks.is_none());
+    }
+}
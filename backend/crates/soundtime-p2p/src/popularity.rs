@@ -0,0 +1,344 @@
+//! Network-wide popularity through play count gossip.
+//!
+//! Every periodic cycle the node sends `P2pMessage::PopularityGossip` to its
+//! online peers: the content hashes its own users played most over the last
+//! [`POPULARITY_WINDOW_SECS`], at most [`MAX_POPULARITY_ENTRIES`] of them.
+//! A receiver keeps the latest count per (hash, peer) in
+//! `remote_play_counts`; a new gossip from the same peer replaces its
+//! previous counts (see [`plan_merge`]).
+//!
+//! `GET /api/tracks/popular` adds these counts to the local `play_count` of
+//! replicated tracks, halved for every [`POPULARITY_HALF_LIFE_SECS`] since
+//! they were reported, so counts from peers that stop gossiping fade out.
+//! Tracks this instance is the origin of are left alone: their plays on
+//! other instances already arrive through `PlayCountUpdate`.
+
+use std::collections::{HashMap, HashSet};
+
+use iroh_blobs::Hash;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, FromQueryResult, QueryFilter,
+    Set, Statement,
+};
+use serde::{Deserialize, Serialize};
+use soundtime_db::entities::remote_play_count;
+
+use crate::error::P2pError;
+
+/// Period the gossiped play counts cover (7 days).
+pub const POPULARITY_WINDOW_SECS: u64 = 7 * 24 * 3600;
+/// Most entries sent in, or accepted from, one `PopularityGossip`.
+pub const MAX_POPULARITY_ENTRIES: usize = 100;
+/// Age after which a gossiped count weighs half as much in rankings.
+pub const POPULARITY_HALF_LIFE_SECS: u64 = 24 * 3600;
+/// Most plays one peer's gossip can add to a hash, so a single peer cannot
+/// push a track to the top of the charts.
+pub const MAX_GOSSIP_PLAY_COUNT: u64 = 1_000;
+/// Counts above this are not plausible for one instance over
+/// [`POPULARITY_WINDOW_SECS`] and are ignored rather than clamped.
+pub const MAX_PLAUSIBLE_PLAY_COUNT: u64 = 1_000_000;
+
+/// Plays of one content hash on the sender.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PopularityEntry {
+    pub hash: String,
+    pub play_count: u64,
+    /// Period the count covers, in seconds
+    pub window_secs: u64,
+}
+
+/// What to write for one received gossip.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct GossipMerge {
+    /// Counts to insert or replace, highest first
+    pub upserts: Vec<PopularityEntry>,
+    /// Hashes the peer reported before but no longer does
+    pub stale: Vec<String>,
+}
+
+/// Plan how a gossip from a peer updates its stored counts, given the hashes
+/// stored for it so far. The gossip is the peer's current top list: counts
+/// replace the stored ones (they cover a sliding window, so they may go down)
+/// and hashes missing from it are dropped. Invalid hashes, zero counts and
+/// counts above [`MAX_PLAUSIBLE_PLAY_COUNT`] are ignored, the rest are
+/// clamped to [`MAX_GOSSIP_PLAY_COUNT`], a hash listed twice keeps its
+/// highest count, and at most [`MAX_POPULARITY_ENTRIES`] entries are kept.
+pub fn plan_merge(stored: &HashSet<String>, entries: Vec<PopularityEntry>) -> GossipMerge {
+    let mut best: HashMap<String, PopularityEntry> = HashMap::new();
+    for mut entry in entries {
+        // `Hash::from_str` panics on base32 input of the wrong length, so
+        // only hex hashes (the form peers send) are parsed
+        if entry.play_count == 0
+            || entry.play_count > MAX_PLAUSIBLE_PLAY_COUNT
+            || entry.hash.len() != 64
+            || entry.hash.parse::<Hash>().is_err()
+        {
+            continue;
+        }
+        entry.play_count = entry.play_count.min(MAX_GOSSIP_PLAY_COUNT);
+        entry.window_secs = entry.window_secs.min(POPULARITY_WINDOW_SECS);
+        match best.get(&entry.hash) {
+            Some(existing) if existing.play_count >= entry.play_count => {}
+            _ => {
+                best.insert(entry.hash.clone(), entry);
+            }
+        }
+    }
+
+    let mut upserts: Vec<PopularityEntry> = best.into_values().collect();
+    upserts.sort_by(|a, b| b.play_count.cmp(&a.play_count).then(a.hash.cmp(&b.hash)));
+    upserts.truncate(MAX_POPULARITY_ENTRIES);
+
+    let kept: HashSet<&str> = upserts.iter().map(|e| e.hash.as_str()).collect();
+    let mut stale: Vec<String> = stored
+        .iter()
+        .filter(|h| !kept.contains(h.as_str()))
+        .cloned()
+        .collect();
+    stale.sort();
+
+    GossipMerge { upserts, stale }
+}
+
+/// Weight of a gossiped count reported `age_secs` ago.
+pub fn decay_weight(age_secs: f64) -> f64 {
+    0.5f64.powf(age_secs.max(0.0) / POPULARITY_HALF_LIFE_SECS as f64)
+}
+
+/// SQL expression ranking `tracks` rows by popularity: the local play count
+/// plus the decayed counts gossiped for replicated tracks.
+pub fn popularity_order_sql() -> String {
+    format!(
+        "tracks.play_count + CASE WHEN tracks.file_path LIKE 'p2p://%' THEN COALESCE((\
+         SELECT SUM(r.play_count * power(0.5, \
+         EXTRACT(EPOCH FROM (NOW() - r.updated_at))::float8 / {POPULARITY_HALF_LIFE_SECS}.0)) \
+         FROM remote_play_counts r WHERE r.content_hash = tracks.content_hash), 0) ELSE 0 END"
+    )
+}
+
+#[derive(FromQueryResult)]
+struct HashPlays {
+    hash: String,
+    plays: i64,
+}
+
+/// The hashes local users played most over the last
+/// [`POPULARITY_WINDOW_SECS`], for gossip.
+pub async fn local_top_plays(db: &DatabaseConnection) -> Result<Vec<PopularityEntry>, P2pError> {
+    let rows = HashPlays::find_by_statement(Statement::from_sql_and_values(
+        sea_orm::DatabaseBackend::Postgres,
+        "SELECT t.content_hash AS hash, COUNT(*)::bigint AS plays \
+         FROM listen_history l JOIN tracks t ON t.id = l.track_id \
+         WHERE t.content_hash IS NOT NULL \
+           AND l.listened_at > NOW() - make_interval(secs => $1) \
+         GROUP BY t.content_hash \
+         ORDER BY plays DESC \
+         LIMIT $2",
+        [
+            (POPULARITY_WINDOW_SECS as f64).into(),
+            (MAX_POPULARITY_ENTRIES as i64).into(),
+        ],
+    ))
+    .all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| PopularityEntry {
+            hash: r.hash,
+            play_count: r.plays.max(0) as u64,
+            window_secs: POPULARITY_WINDOW_SECS,
+        })
+        .collect())
+}
+
+/// Replace the counts stored for `peer_id` with those of a new gossip.
+/// Returns how many counts were stored.
+pub async fn merge_gossip(
+    db: &DatabaseConnection,
+    peer_id: &str,
+    entries: Vec<PopularityEntry>,
+) -> Result<usize, P2pError> {
+    let stored: HashSet<String> = remote_play_count::Entity::find()
+        .filter(remote_play_count::Column::PeerId.eq(peer_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|r| r.content_hash)
+        .collect();
+    let merge = plan_merge(&stored, entries);
+
+    if !merge.stale.is_empty() {
+        remote_play_count::Entity::delete_many()
+            .filter(remote_play_count::Column::PeerId.eq(peer_id))
+            .filter(remote_play_count::Column::ContentHash.is_in(merge.stale.clone()))
+            .exec(db)
+            .await?;
+    }
+    if merge.upserts.is_empty() {
+        return Ok(0);
+    }
+
+    let now = chrono::Utc::now();
+    let models = merge
+        .upserts
+        .iter()
+        .map(|e| remote_play_count::ActiveModel {
+            content_hash: Set(e.hash.clone()),
+            peer_id: Set(peer_id.to_string()),
+            play_count: Set(e.play_count.min(i64::MAX as u64) as i64),
+            window_secs: Set(e.window_secs.min(i64::MAX as u64) as i64),
+            updated_at: Set(now.into()),
+        });
+    remote_play_count::Entity::insert_many(models)
+        .on_conflict(
+            sea_orm::sea_query::OnConflict::columns([
+                remote_play_count::Column::ContentHash,
+                remote_play_count::Column::PeerId,
+            ])
+            .update_columns([
+                remote_play_count::Column::PlayCount,
+                remote_play_count::Column::WindowSecs,
+                remote_play_count::Column::UpdatedAt,
+            ])
+            .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    Ok(merge.upserts.len())
+}
+
+/// Delete counts not refreshed for [`POPULARITY_WINDOW_SECS`]; by then
+/// their weight is below 1%.
+pub async fn prune_stale(db: &DatabaseConnection) -> Result<u64, P2pError> {
+    let res = db
+        .execute(Statement::from_sql_and_values(
+            sea_orm::DatabaseBackend::Postgres,
+            "DELETE FROM remote_play_counts \
+             WHERE updated_at < NOW() - make_interval(secs => $1)",
+            [(POPULARITY_WINDOW_SECS as f64).into()],
+        ))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(n: u8) -> String {
+        Hash::new([n]).to_string()
+    }
+
+    fn entry(n: u8, play_count: u64) -> PopularityEntry {
+        PopularityEntry {
+            hash: hash(n),
+            play_count,
+            window_secs: POPULARITY_WINDOW_SECS,
+        }
+    }
+
+    // ── plan_merge ──
+
+    #[test]
+    fn test_first_gossip_inserts_everything() {
+        let merge = plan_merge(&HashSet::new(), vec![entry(1, 5), entry(2, 9)]);
+        assert_eq!(merge.upserts, vec![entry(2, 9), entry(1, 5)]);
+        assert!(merge.stale.is_empty());
+    }
+
+    #[test]
+    fn test_resend_replaces_counts() {
+        // The peer gossiped hashes 1 and 2 before; counts cover a sliding
+        // window, so an updated count replaces the old one even if lower
+        let stored: HashSet<String> = [hash(1), hash(2)].into();
+        let merge = plan_merge(&stored, vec![entry(1, 3), entry(2, 40)]);
+        assert_eq!(merge.upserts, vec![entry(2, 40), entry(1, 3)]);
+        assert!(merge.stale.is_empty());
+    }
+
+    #[test]
+    fn test_resend_drops_hashes_no_longer_reported() {
+        let stored: HashSet<String> = [hash(1), hash(2)].into();
+        let merge = plan_merge(&stored, vec![entry(2, 7), entry(3, 4)]);
+        assert_eq!(merge.upserts, vec![entry(2, 7), entry(3, 4)]);
+        assert_eq!(merge.stale, vec![hash(1)]);
+
+        // An empty gossip clears the peer's counts
+        let merge = plan_merge(&stored, vec![]);
+        assert!(merge.upserts.is_empty());
+        assert_eq!(merge.stale.len(), 2);
+    }
+
+    #[test]
+    fn test_duplicate_hash_keeps_highest_count() {
+        let merge = plan_merge(&HashSet::new(), vec![entry(1, 2), entry(1, 8), entry(1, 5)]);
+        assert_eq!(merge.upserts, vec![entry(1, 8)]);
+    }
+
+    #[test]
+    fn test_invalid_entries_are_ignored() {
+        let stored: HashSet<String> = [hash(1)].into();
+        let bad_hash = PopularityEntry {
+            hash: "not-a-hash".into(),
+            play_count: 10,
+            window_secs: POPULARITY_WINDOW_SECS,
+        };
+        let merge = plan_merge(&stored, vec![bad_hash, entry(1, 0)]);
+        assert!(merge.upserts.is_empty());
+        // A zero count is as good as not reporting the hash
+        assert_eq!(merge.stale, vec![hash(1)]);
+    }
+
+    #[test]
+    fn test_gossip_is_capped() {
+        let entries: Vec<PopularityEntry> = (0..=200u8).map(|n| entry(n, n as u64 + 1)).collect();
+        let merge = plan_merge(&HashSet::new(), entries);
+        assert_eq!(merge.upserts.len(), MAX_POPULARITY_ENTRIES);
+        // The highest counts are kept
+        assert_eq!(merge.upserts[0].play_count, 201);
+        assert_eq!(
+            merge.upserts.last().unwrap().play_count,
+            201 - MAX_POPULARITY_ENTRIES as u64 + 1
+        );
+    }
+
+    #[test]
+    fn test_counts_are_bounded() {
+        let merge = plan_merge(
+            &HashSet::new(),
+            vec![
+                entry(1, MAX_GOSSIP_PLAY_COUNT + 50),
+                entry(2, MAX_PLAUSIBLE_PLAY_COUNT + 1),
+                entry(3, u64::MAX),
+                entry(4, 7),
+            ],
+        );
+        // Large counts are clamped, implausible ones dropped
+        assert_eq!(
+            merge.upserts,
+            vec![entry(1, MAX_GOSSIP_PLAY_COUNT), entry(4, 7)]
+        );
+
+        // Neither can a window longer than ours inflate the count's weight
+        let long_window = PopularityEntry {
+            window_secs: u64::MAX,
+            ..entry(5, 3)
+        };
+        let merge = plan_merge(&HashSet::new(), vec![long_window]);
+        assert_eq!(merge.upserts, vec![entry(5, 3)]);
+    }
+
+    // ── decay ──
+
+    #[test]
+    fn test_decay_weight() {
+        assert_eq!(decay_weight(0.0), 1.0);
+        assert!((decay_weight(POPULARITY_HALF_LIFE_SECS as f64) - 0.5).abs() < 1e-9);
+        assert!((decay_weight(2.0 * POPULARITY_HALF_LIFE_SECS as f64) - 0.25).abs() < 1e-9);
+        // Clock skew cannot push the weight above 1
+        assert_eq!(decay_weight(-60.0), 1.0);
+        // Pruned counts weigh less than 1%
+        assert!(decay_weight(POPULARITY_WINDOW_SECS as f64) < 0.01);
+    }
+}
@@ -0,0 +1,271 @@
+//! Pre-warming the blob cache for tracks users kept.
+//!
+//! Replicated tracks are fetched lazily, so the first play of a favorited
+//! remote track waits for its blob. With `P2P_PREWARM_ENABLED=true`, every
+//! periodic refresh looks up the P2P tracks in users' favorites and/or
+//! playlists (`P2P_PREWARM_SCOPE`) and fetches the blobs that are not stored
+//! yet. Fetches run [`PREWARM_CONCURRENCY`] at a time, each holding a
+//! recovery permit from the [`TrackHealthManager`], so pre-warming shares the
+//! recovery budget instead of adding to it. Playback fetches take no permit
+//! and are never held up.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use iroh_blobs::Hash;
+use sea_orm::{DatabaseConnection, FromQueryResult, Statement};
+use serde::Serialize;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::debug;
+
+use crate::error::P2pError;
+use crate::track_health::TrackHealthManager;
+
+/// Blob fetches a pre-warm runs at once.
+pub const PREWARM_CONCURRENCY: usize = 2;
+
+/// Most blobs fetched per cycle, so a small cache is not churned through in
+/// one go. Later cycles pick up the rest.
+pub const MAX_PREWARM_PER_CYCLE: usize = 50;
+
+/// Which tracks are pre-warmed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrewarmScope {
+    Favorites,
+    Playlists,
+    /// Favorites and playlists
+    #[default]
+    All,
+}
+
+impl PrewarmScope {
+    /// Parse `P2P_PREWARM_SCOPE`: `favorites`, `playlists` or `all`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "favorites" => Some(Self::Favorites),
+            "playlists" => Some(Self::Playlists),
+            "all" => Some(Self::All),
+            _ => None,
+        }
+    }
+
+    fn sql(self) -> String {
+        let favorites = "EXISTS (SELECT 1 FROM favorites f WHERE f.track_id = t.id)";
+        let playlists = "EXISTS (SELECT 1 FROM playlist_tracks p WHERE p.track_id = t.id)";
+        let kept = match self {
+            Self::Favorites => favorites.to_string(),
+            Self::Playlists => playlists.to_string(),
+            Self::All => format!("({favorites} OR {playlists})"),
+        };
+        format!(
+            "SELECT DISTINCT t.content_hash AS hash FROM tracks t \
+             WHERE t.content_hash IS NOT NULL AND t.file_path LIKE 'p2p://%' AND {kept}"
+        )
+    }
+}
+
+/// What pre-warming needs from the node.
+#[async_trait]
+pub trait PrewarmTarget: Clone + Send + Sync + 'static {
+    /// Whether the blob is already stored locally.
+    async fn has_blob(&self, hash: Hash) -> bool;
+    /// Fetch the blob from a peer into the cache.
+    async fn fetch_blob(&self, hash: Hash) -> Result<(), P2pError>;
+}
+
+#[derive(FromQueryResult)]
+struct KeptHash {
+    hash: String,
+}
+
+/// Content hashes of the replicated tracks in `scope`.
+pub async fn kept_hashes(
+    db: &DatabaseConnection,
+    scope: PrewarmScope,
+) -> Result<Vec<String>, P2pError> {
+    let rows = KeptHash::find_by_statement(Statement::from_string(
+        sea_orm::DatabaseBackend::Postgres,
+        scope.sql(),
+    ))
+    .all(db)
+    .await?;
+    Ok(rows.into_iter().map(|r| r.hash).collect())
+}
+
+/// Fetch the blobs among `hashes` that `target` does not have, at most
+/// `max_fetches` of them and [`PREWARM_CONCURRENCY`] at a time, each while
+/// holding a recovery permit from `manager`. Returns how many were fetched.
+pub async fn prewarm_blobs<T: PrewarmTarget>(
+    target: T,
+    manager: Arc<TrackHealthManager>,
+    hashes: Vec<Hash>,
+    max_fetches: usize,
+) -> usize {
+    let slots = Arc::new(Semaphore::new(PREWARM_CONCURRENCY));
+    let mut tasks = JoinSet::new();
+    let mut started = 0usize;
+    for hash in hashes {
+        if started >= max_fetches {
+            break;
+        }
+        if target.has_blob(hash).await {
+            continue;
+        }
+        started += 1;
+        let slot = Arc::clone(&slots)
+            .acquire_owned()
+            .await
+            .expect("prewarm semaphore closed unexpectedly");
+        let permit = manager.acquire_recovery_permit().await;
+        let target = target.clone();
+        tasks.spawn(async move {
+            let _held = (slot, permit);
+            match target.fetch_blob(hash).await {
+                Ok(()) => true,
+                Err(e) => {
+                    debug!(%hash, "pre-warm fetch failed: {e}");
+                    false
+                }
+            }
+        });
+    }
+
+    let mut warmed = 0usize;
+    while let Some(result) = tasks.join_next().await {
+        if matches!(result, Ok(true)) {
+            warmed += 1;
+        }
+    }
+    warmed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    use crate::track_health::HealthMonitorConfig;
+
+    #[derive(Clone, Default)]
+    struct MockTarget {
+        stored: Arc<Mutex<HashSet<Hash>>>,
+        failing: Arc<HashSet<Hash>>,
+        fetched: Arc<Mutex<Vec<Hash>>>,
+        running: Arc<AtomicUsize>,
+        max_running: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl PrewarmTarget for MockTarget {
+        async fn has_blob(&self, hash: Hash) -> bool {
+            self.stored.lock().unwrap().contains(&hash)
+        }
+
+        async fn fetch_blob(&self, hash: Hash) -> Result<(), P2pError> {
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            self.fetched.lock().unwrap().push(hash);
+            if self.failing.contains(&hash) {
+                return Err(P2pError::TrackNotFound(hash.to_string()));
+            }
+            self.stored.lock().unwrap().insert(hash);
+            Ok(())
+        }
+    }
+
+    fn hashes(n: u8) -> Vec<Hash> {
+        (1..=n).map(|i| Hash::from_bytes([i; 32])).collect()
+    }
+
+    fn manager(max_concurrent_recoveries: usize) -> Arc<TrackHealthManager> {
+        Arc::new(TrackHealthManager::with_config(HealthMonitorConfig {
+            max_concurrent_recoveries,
+            ..Default::default()
+        }))
+    }
+
+    // ── scope ──
+
+    #[test]
+    fn test_scope_parse() {
+        assert_eq!(
+            PrewarmScope::parse("favorites"),
+            Some(PrewarmScope::Favorites)
+        );
+        assert_eq!(
+            PrewarmScope::parse(" Playlists "),
+            Some(PrewarmScope::Playlists)
+        );
+        assert_eq!(PrewarmScope::parse("ALL"), Some(PrewarmScope::All));
+        assert_eq!(PrewarmScope::parse("everything"), None);
+        assert_eq!(PrewarmScope::default(), PrewarmScope::All);
+    }
+
+    #[test]
+    fn test_scope_sql() {
+        let favorites = PrewarmScope::Favorites.sql();
+        assert!(favorites.contains("FROM favorites"));
+        assert!(!favorites.contains("playlist_tracks"));
+        let playlists = PrewarmScope::Playlists.sql();
+        assert!(playlists.contains("FROM playlist_tracks"));
+        assert!(!playlists.contains("favorites"));
+        let all = PrewarmScope::All.sql();
+        assert!(all.contains("favorites") && all.contains("playlist_tracks"));
+        assert!(all.contains("LIKE 'p2p://%'"));
+    }
+
+    // ── prewarm_blobs ──
+
+    #[tokio::test]
+    async fn test_prewarm_skips_stored_blobs() {
+        let all = hashes(4);
+        let target = MockTarget::default();
+        target.stored.lock().unwrap().extend([all[0], all[2]]);
+
+        let warmed = prewarm_blobs(target.clone(), manager(8), all.clone(), 10).await;
+        assert_eq!(warmed, 2);
+        let fetched: HashSet<Hash> = target.fetched.lock().unwrap().iter().copied().collect();
+        assert_eq!(fetched, HashSet::from([all[1], all[3]]));
+    }
+
+    #[tokio::test]
+    async fn test_prewarm_respects_concurrency_limit() {
+        let target = MockTarget::default();
+        let warmed = prewarm_blobs(target.clone(), manager(8), hashes(8), 10).await;
+        assert_eq!(warmed, 8);
+        assert_eq!(
+            target.max_running.load(Ordering::SeqCst),
+            PREWARM_CONCURRENCY
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prewarm_bounded_by_recovery_permits() {
+        let target = MockTarget::default();
+        let manager = manager(1);
+        let warmed = prewarm_blobs(target.clone(), Arc::clone(&manager), hashes(4), 10).await;
+        assert_eq!(warmed, 4);
+        assert_eq!(target.max_running.load(Ordering::SeqCst), 1);
+        // Permits are all returned afterwards
+        assert_eq!(manager.available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_prewarm_caps_fetches_and_counts_failures() {
+        let all = hashes(5);
+        let target = MockTarget {
+            failing: Arc::new(HashSet::from([all[0]])),
+            ..Default::default()
+        };
+        let warmed = prewarm_blobs(target.clone(), manager(8), all, 3).await;
+        // Three fetches started, the first failed
+        assert_eq!(target.fetched.lock().unwrap().len(), 3);
+        assert_eq!(warmed, 2);
+    }
+}
@@ -0,0 +1,308 @@
+//! Blob hashes this node serves to peers.
+//!
+//! Only blobs that were explicitly published can be fetched by peers
+//! (FIX-19). The set is kept in memory for the serve path and mirrored in the
+//! `published_hashes` table, written whenever a blob is published or
+//! unpublished. At startup [`PublishedHashes::load`] reads the table in
+//! pages of [`LOAD_PAGE_SIZE`], taking the write lock once per page, so
+//! fetches are served while a large catalog is still loading.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use soundtime_db::entities::published_hash;
+use tokio::sync::RwLock;
+use tracing::info;
+
+use crate::error::P2pError;
+
+/// Hashes read per query when loading the set at startup.
+pub const LOAD_PAGE_SIZE: u64 = 5000;
+
+/// What a published blob holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PublishedKind {
+    /// Track audio
+    Track,
+    /// Cover art or an artist image
+    Image,
+}
+
+impl PublishedKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PublishedKind::Track => "track",
+            PublishedKind::Image => "image",
+        }
+    }
+}
+
+/// Where published hashes are persisted.
+#[async_trait]
+pub trait PublishedStore: Send + Sync {
+    /// Up to `limit` hashes sorting after `after`, in order.
+    async fn page_after(&self, after: &str, limit: u64) -> Result<Vec<String>, DbErr>;
+
+    /// Record `hash`; recording it again is not an error.
+    async fn save(&self, hash: &str, kind: PublishedKind) -> Result<(), DbErr>;
+
+    async fn delete(&self, hash: &str) -> Result<(), DbErr>;
+}
+
+#[async_trait]
+impl PublishedStore for DatabaseConnection {
+    async fn page_after(&self, after: &str, limit: u64) -> Result<Vec<String>, DbErr> {
+        Ok(published_hash::Entity::find()
+            .filter(published_hash::Column::Hash.gt(after))
+            .order_by_asc(published_hash::Column::Hash)
+            .limit(limit)
+            .all(self)
+            .await?
+            .into_iter()
+            .map(|row| row.hash)
+            .collect())
+    }
+
+    async fn save(&self, hash: &str, kind: PublishedKind) -> Result<(), DbErr> {
+        published_hash::Entity::insert(published_hash::ActiveModel {
+            hash: Set(hash.to_string()),
+            kind: Set(kind.as_str().to_string()),
+            published_at: Set(chrono::Utc::now().into()),
+        })
+        .on_conflict(
+            sea_orm::sea_query::OnConflict::column(published_hash::Column::Hash)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, hash: &str) -> Result<(), DbErr> {
+        published_hash::Entity::delete_by_id(hash.to_string())
+            .exec(self)
+            .await?;
+        Ok(())
+    }
+}
+
+/// The published hashes, in memory and in a [`PublishedStore`].
+pub struct PublishedHashes {
+    hashes: RwLock<HashSet<String>>,
+    store: Arc<dyn PublishedStore>,
+}
+
+impl PublishedHashes {
+    pub fn new(store: Arc<dyn PublishedStore>) -> Self {
+        Self {
+            hashes: RwLock::new(HashSet::new()),
+            store,
+        }
+    }
+
+    /// Whether `hash` may be served to peers.
+    pub async fn contains(&self, hash: &str) -> bool {
+        self.hashes.read().await.contains(hash)
+    }
+
+    pub async fn len(&self) -> usize {
+        self.hashes.read().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.hashes.read().await.is_empty()
+    }
+
+    /// Persist `hash` and start serving it.
+    pub async fn publish(&self, hash: &str, kind: PublishedKind) -> Result<(), P2pError> {
+        self.store.save(hash, kind).await?;
+        self.hashes.write().await.insert(hash.to_string());
+        Ok(())
+    }
+
+    /// Stop serving `hash` and forget it for good.
+    pub async fn unpublish(&self, hash: &str) -> Result<(), P2pError> {
+        self.hashes.write().await.remove(hash);
+        self.store.delete(hash).await?;
+        Ok(())
+    }
+
+    /// Stop serving `hash` until it is published again, keeping its row
+    /// (e.g. while a block applies).
+    pub async fn suspend(&self, hash: &str) {
+        self.hashes.write().await.remove(hash);
+    }
+
+    /// Add every persisted hash to the in-memory set. Returns how many
+    /// were read.
+    pub async fn load(&self) -> Result<usize, P2pError> {
+        self.load_in_pages(LOAD_PAGE_SIZE).await
+    }
+
+    async fn load_in_pages(&self, page_size: u64) -> Result<usize, P2pError> {
+        let mut after = String::new();
+        let mut loaded = 0;
+        loop {
+            let page = self.store.page_after(&after, page_size).await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            after = last.clone();
+            let full_page = page.len() as u64 == page_size;
+            loaded += page.len();
+            self.hashes.write().await.extend(page);
+            if !full_page {
+                break;
+            }
+        }
+        info!(count = loaded, "loaded published hashes");
+        Ok(loaded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// In-memory stand-in for the `published_hashes` table.
+    #[derive(Default)]
+    struct MemoryStore {
+        rows: Mutex<BTreeMap<String, PublishedKind>>,
+        pages_read: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl PublishedStore for MemoryStore {
+        async fn page_after(&self, after: &str, limit: u64) -> Result<Vec<String>, DbErr> {
+            self.pages_read.fetch_add(1, Ordering::Relaxed);
+            let rows = self.rows.lock().unwrap();
+            Ok(rows
+                .keys()
+                .filter(|h| h.as_str() > after)
+                .take(limit as usize)
+                .cloned()
+                .collect())
+        }
+
+        async fn save(&self, hash: &str, kind: PublishedKind) -> Result<(), DbErr> {
+            self.rows
+                .lock()
+                .unwrap()
+                .entry(hash.to_string())
+                .or_insert(kind);
+            Ok(())
+        }
+
+        async fn delete(&self, hash: &str) -> Result<(), DbErr> {
+            self.rows.lock().unwrap().remove(hash);
+            Ok(())
+        }
+    }
+
+    // ── publish / unpublish ──
+
+    #[tokio::test]
+    async fn test_publish_unpublish_lifecycle() {
+        let store = Arc::new(MemoryStore::default());
+        let published = PublishedHashes::new(store.clone());
+        assert!(!published.contains("track1").await);
+
+        published
+            .publish("track1", PublishedKind::Track)
+            .await
+            .unwrap();
+        published
+            .publish("cover1", PublishedKind::Image)
+            .await
+            .unwrap();
+        assert!(published.contains("track1").await);
+        assert_eq!(
+            store.rows.lock().unwrap().get("cover1"),
+            Some(&PublishedKind::Image)
+        );
+
+        // Publishing again is harmless
+        published
+            .publish("track1", PublishedKind::Track)
+            .await
+            .unwrap();
+        assert_eq!(published.len().await, 2);
+
+        published.unpublish("track1").await.unwrap();
+        assert!(!published.contains("track1").await);
+        assert!(!store.rows.lock().unwrap().contains_key("track1"));
+        assert!(published.contains("cover1").await);
+    }
+
+    #[tokio::test]
+    async fn test_suspend_keeps_row() {
+        let store = Arc::new(MemoryStore::default());
+        let published = PublishedHashes::new(store.clone());
+        published
+            .publish("track1", PublishedKind::Track)
+            .await
+            .unwrap();
+
+        published.suspend("track1").await;
+        assert!(!published.contains("track1").await);
+        assert!(store.rows.lock().unwrap().contains_key("track1"));
+    }
+
+    // ── load ──
+
+    #[tokio::test]
+    async fn test_load_reads_every_page() {
+        let store = Arc::new(MemoryStore::default());
+        for i in 0..25 {
+            store
+                .save(&format!("hash{i:03}"), PublishedKind::Track)
+                .await
+                .unwrap();
+        }
+        let published = PublishedHashes::new(store.clone());
+
+        assert_eq!(published.load_in_pages(10).await.unwrap(), 25);
+        assert_eq!(published.len().await, 25);
+        assert!(published.contains("hash000").await);
+        assert!(published.contains("hash024").await);
+        // Two full pages and a short one
+        assert_eq!(store.pages_read.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn test_load_exact_multiple_of_page_size() {
+        let store = Arc::new(MemoryStore::default());
+        for i in 0..20 {
+            store
+                .save(&format!("hash{i:03}"), PublishedKind::Track)
+                .await
+                .unwrap();
+        }
+        let published = PublishedHashes::new(store.clone());
+
+        assert_eq!(published.load_in_pages(10).await.unwrap(), 20);
+        // The empty third page ends the scan
+        assert_eq!(store.pages_read.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn test_load_empty_store() {
+        let published = PublishedHashes::new(Arc::new(MemoryStore::default()));
+        assert_eq!(published.load().await.unwrap(), 0);
+        assert!(published.is_empty().await);
+    }
+
+    #[test]
+    fn test_kind_as_str() {
+        assert_eq!(PublishedKind::Track.as_str(), "track");
+        assert_eq!(PublishedKind::Image.as_str(), "image");
+    }
+}
@@ -0,0 +1,436 @@
+//! Admin content policy for tracks replicated from peers.
+//!
+//! A [`ReplicationPolicy`] is built from the `p2p_replication_*` instance
+//! settings and checked by `P2pNode::process_track_announcement` before an
+//! announced track is stored. Announcements it refuses are counted per peer
+//! and the most recent ones are kept in a [`RejectionLog`] for
+//! `GET /api/admin/p2p/rejected`. `P2pNode::reload_replication_policy` picks
+//! up edited settings without restarting the node.
+//!
+//! | Setting | Example | Meaning |
+//! |---------|---------|---------|
+//! | `p2p_replication_max_file_size` | `1G` | Largest file accepted (empty = no limit) |
+//! | `p2p_replication_allowed_formats` | `flac,mp3` | Accepted formats (empty = any) |
+//! | `p2p_replication_blocked_genres` | `podcast` | Genres refused, case-insensitive |
+//! | `p2p_replication_require_album` | `true` | Refuse tracks without an album |
+//! | `p2p_replication_require_genre` | `true` | Refuse tracks without a genre |
+//! | `p2p_replication_require_musicbrainz` | `true` | Refuse tracks with no MusicBrainz match |
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde::Serialize;
+use soundtime_db::entities::instance_setting;
+use tracing::warn;
+
+use crate::error::P2pError;
+use crate::node::{parse_byte_size, TrackAnnouncement};
+
+/// Prefix shared by every replication policy setting.
+pub const SETTING_PREFIX: &str = "p2p_replication_";
+
+/// Rejected announcements kept for the admin listing.
+pub const MAX_REJECTION_LOG: usize = 500;
+
+/// Which announced tracks this instance accepts. The default accepts all.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ReplicationPolicy {
+    /// Largest `file_size` accepted, in bytes
+    pub max_file_size: Option<u64>,
+    /// Accepted formats, lowercase (empty = any)
+    pub allowed_formats: Vec<String>,
+    /// Refused genres, lowercase
+    pub blocked_genres: Vec<String>,
+    pub require_album: bool,
+    pub require_genre: bool,
+    /// Only accept tracks MusicBrainz can match by title and artist
+    pub require_musicbrainz: bool,
+}
+
+/// Why an announcement was refused.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RejectReason {
+    TooLarge {
+        size: u64,
+        max: u64,
+    },
+    FormatNotAllowed(String),
+    BlockedGenre(String),
+    MissingAlbum,
+    MissingGenre,
+    NoMusicBrainzMatch,
+    /// Genre not in the sending peer's filter (`None` = no genre)
+    GenreNotAllowedFromPeer(Option<String>),
+    /// Artist not in the sending peer's filter
+    ArtistNotAllowedFromPeer(String),
+    /// The sending peer's track cap is reached
+    PeerTrackLimit(u64),
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RejectReason::TooLarge { size, max } => {
+                write!(f, "file size {size} bytes exceeds limit of {max} bytes")
+            }
+            RejectReason::FormatNotAllowed(format) => write!(f, "format {format} not allowed"),
+            RejectReason::BlockedGenre(genre) => write!(f, "genre {genre} is blocked"),
+            RejectReason::MissingAlbum => write!(f, "no album"),
+            RejectReason::MissingGenre => write!(f, "no genre"),
+            RejectReason::NoMusicBrainzMatch => write!(f, "no MusicBrainz match"),
+            RejectReason::GenreNotAllowedFromPeer(Some(genre)) => {
+                write!(f, "genre {genre} not allowed from this peer")
+            }
+            RejectReason::GenreNotAllowedFromPeer(None) => {
+                write!(f, "no genre, peer filter requires one")
+            }
+            RejectReason::ArtistNotAllowedFromPeer(artist) => {
+                write!(f, "artist {artist} not allowed from this peer")
+            }
+            RejectReason::PeerTrackLimit(max) => {
+                write!(f, "peer track limit of {max} reached")
+            }
+        }
+    }
+}
+
+/// Split a comma-separated setting into trimmed, lowercase, non-empty items.
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn parse_flag(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "true" | "1" | "yes" | "on"
+    )
+}
+
+fn is_blank(value: &Option<String>) -> bool {
+    value.as_deref().is_none_or(|v| v.trim().is_empty())
+}
+
+impl ReplicationPolicy {
+    /// Build the policy from `(key, value)` instance settings. Unrelated keys
+    /// are ignored; an unparsable size is logged and treated as no limit.
+    pub fn from_settings<'a>(settings: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut policy = Self::default();
+        for (key, value) in settings {
+            let Some(name) = key.strip_prefix(SETTING_PREFIX) else {
+                continue;
+            };
+            match name {
+                "max_file_size" if !value.trim().is_empty() => match parse_byte_size(value) {
+                    Some(0) => policy.max_file_size = None,
+                    Some(size) => policy.max_file_size = Some(size as u64),
+                    None => warn!(%key, %value, "ignoring invalid replication size limit"),
+                },
+                "allowed_formats" => policy.allowed_formats = parse_list(value),
+                "blocked_genres" => policy.blocked_genres = parse_list(value),
+                "require_album" => policy.require_album = parse_flag(value),
+                "require_genre" => policy.require_genre = parse_flag(value),
+                "require_musicbrainz" => policy.require_musicbrainz = parse_flag(value),
+                _ => {}
+            }
+        }
+        policy
+    }
+
+    /// Read the policy from the `instance_settings` table.
+    pub async fn load(db: &DatabaseConnection) -> Result<Self, P2pError> {
+        let rows = instance_setting::Entity::find()
+            .filter(instance_setting::Column::Key.starts_with(SETTING_PREFIX))
+            .all(db)
+            .await?;
+        Ok(Self::from_settings(
+            rows.iter().map(|r| (r.key.as_str(), r.value.as_str())),
+        ))
+    }
+
+    /// Check everything but the MusicBrainz match, which needs a lookup and
+    /// is left to the caller (see `require_musicbrainz`).
+    pub fn check(&self, ann: &TrackAnnouncement) -> Result<(), RejectReason> {
+        if let Some(max) = self.max_file_size {
+            let size = ann.file_size.max(0) as u64;
+            if size > max {
+                return Err(RejectReason::TooLarge { size, max });
+            }
+        }
+        if !self.allowed_formats.is_empty()
+            && !self
+                .allowed_formats
+                .iter()
+                .any(|f| f.eq_ignore_ascii_case(ann.format.trim()))
+        {
+            return Err(RejectReason::FormatNotAllowed(ann.format.clone()));
+        }
+        if let Some(genre) = ann.genre.as_deref() {
+            if self
+                .blocked_genres
+                .iter()
+                .any(|g| g.eq_ignore_ascii_case(genre.trim()))
+            {
+                return Err(RejectReason::BlockedGenre(genre.to_string()));
+            }
+        }
+        if self.require_album && is_blank(&ann.album_title) {
+            return Err(RejectReason::MissingAlbum);
+        }
+        if self.require_genre && is_blank(&ann.genre) {
+            return Err(RejectReason::MissingGenre);
+        }
+        Ok(())
+    }
+}
+
+/// One refused announcement, as listed to admins.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RejectedAnnouncement {
+    pub peer_id: String,
+    pub hash: String,
+    pub title: String,
+    pub artist_name: String,
+    pub reason: String,
+    pub rejected_at: DateTime<Utc>,
+}
+
+/// Rejections counted for one peer since the node started.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PeerRejections {
+    pub peer_id: String,
+    pub rejected: u64,
+}
+
+#[derive(Default)]
+struct RejectionLogInner {
+    recent: VecDeque<RejectedAnnouncement>,
+    per_peer: HashMap<String, u64>,
+}
+
+/// Per-peer rejection counts and the most recent rejected announcements.
+pub struct RejectionLog {
+    inner: Mutex<RejectionLogInner>,
+    capacity: usize,
+}
+
+impl Default for RejectionLog {
+    fn default() -> Self {
+        Self::new(MAX_REJECTION_LOG)
+    }
+}
+
+impl RejectionLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(RejectionLogInner::default()),
+            capacity,
+        }
+    }
+
+    pub fn record(&self, peer_id: &str, ann: &TrackAnnouncement, reason: &RejectReason) {
+        self.record_track(peer_id, &ann.hash, &ann.title, &ann.artist_name, reason);
+    }
+
+    /// Record a rejected track by its fields, for tracks rejected after
+    /// their announcement was processed.
+    pub fn record_track(
+        &self,
+        peer_id: &str,
+        hash: &str,
+        title: &str,
+        artist_name: &str,
+        reason: &RejectReason,
+    ) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        *inner.per_peer.entry(peer_id.to_string()).or_default() += 1;
+        if inner.recent.len() >= self.capacity {
+            inner.recent.pop_back();
+        }
+        inner.recent.push_front(RejectedAnnouncement {
+            peer_id: peer_id.to_string(),
+            hash: hash.to_string(),
+            title: title.to_string(),
+            artist_name: artist_name.to_string(),
+            reason: reason.to_string(),
+            rejected_at: Utc::now(),
+        });
+    }
+
+    /// Most recent rejections, newest first.
+    pub fn recent(&self) -> Vec<RejectedAnnouncement> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.recent.iter().cloned().collect()
+    }
+
+    /// Rejection counts per peer, highest first.
+    pub fn per_peer(&self) -> Vec<PeerRejections> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut counts: Vec<PeerRejections> = inner
+            .per_peer
+            .iter()
+            .map(|(peer_id, &rejected)| PeerRejections {
+                peer_id: peer_id.clone(),
+                rejected,
+            })
+            .collect();
+        counts.sort_by(|a, b| {
+            b.rejected
+                .cmp(&a.rejected)
+                .then_with(|| a.peer_id.cmp(&b.peer_id))
+        });
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn announcement(format: &str, file_size: i64, genre: Option<&str>) -> TrackAnnouncement {
+        TrackAnnouncement {
+            hash: "hash-1".into(),
+            title: "Song".into(),
+            artist_name: "Artist".into(),
+            album_artist_name: None,
+            album_title: None,
+            duration_secs: 180.0,
+            format: format.into(),
+            file_size,
+            genre: genre.map(String::from),
+            year: None,
+            track_number: None,
+            disc_number: None,
+            bitrate: None,
+            sample_rate: None,
+            origin_node: "origin".into(),
+            cover_hash: None,
+            fingerprint: None,
+            waveform_data: None,
+            artist_image_hash: None,
+            artist_bio: None,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            signature: None,
+        }
+    }
+
+    // ── from_settings ──
+
+    #[test]
+    fn test_from_settings() {
+        let policy = ReplicationPolicy::from_settings([
+            ("p2p_replication_max_file_size", "1G"),
+            ("p2p_replication_allowed_formats", " FLAC, mp3 ,"),
+            ("p2p_replication_blocked_genres", "Podcast"),
+            ("p2p_replication_require_album", "true"),
+            ("p2p_replication_require_musicbrainz", "1"),
+            ("instance_private", "true"),
+        ]);
+        assert_eq!(policy.max_file_size, Some(1024 * 1024 * 1024));
+        assert_eq!(policy.allowed_formats, vec!["flac", "mp3"]);
+        assert_eq!(policy.blocked_genres, vec!["podcast"]);
+        assert!(policy.require_album);
+        assert!(!policy.require_genre);
+        assert!(policy.require_musicbrainz);
+    }
+
+    #[test]
+    fn test_from_settings_invalid_or_empty_size_is_unlimited() {
+        for value in ["", "lots", "0"] {
+            let policy =
+                ReplicationPolicy::from_settings([("p2p_replication_max_file_size", value)]);
+            assert_eq!(policy.max_file_size, None, "value {value:?}");
+        }
+    }
+
+    // ── check ──
+
+    #[test]
+    fn test_default_policy_accepts_everything() {
+        let policy = ReplicationPolicy::default();
+        assert!(policy.check(&announcement("wav", i64::MAX, None)).is_ok());
+    }
+
+    #[test]
+    fn test_check_rejections() {
+        let policy = ReplicationPolicy::from_settings([
+            ("p2p_replication_max_file_size", "100M"),
+            ("p2p_replication_allowed_formats", "flac,mp3"),
+            ("p2p_replication_blocked_genres", "podcast"),
+            ("p2p_replication_require_genre", "true"),
+        ]);
+
+        assert!(policy
+            .check(&announcement("FLAC", 50_000_000, Some("Jazz")))
+            .is_ok());
+        assert_eq!(
+            policy.check(&announcement("flac", 200 * 1024 * 1024, Some("Jazz"))),
+            Err(RejectReason::TooLarge {
+                size: 200 * 1024 * 1024,
+                max: 100 * 1024 * 1024
+            })
+        );
+        assert_eq!(
+            policy.check(&announcement("ogg", 1000, Some("Jazz"))),
+            Err(RejectReason::FormatNotAllowed("ogg".into()))
+        );
+        assert_eq!(
+            policy.check(&announcement("mp3", 1000, Some("Podcast"))),
+            Err(RejectReason::BlockedGenre("Podcast".into()))
+        );
+        assert_eq!(
+            policy.check(&announcement("mp3", 1000, Some("  "))),
+            Err(RejectReason::MissingGenre)
+        );
+    }
+
+    #[test]
+    fn test_check_require_album() {
+        let policy = ReplicationPolicy::from_settings([("p2p_replication_require_album", "yes")]);
+        let mut ann = announcement("flac", 1000, None);
+        assert_eq!(policy.check(&ann), Err(RejectReason::MissingAlbum));
+        ann.album_title = Some("Album".into());
+        assert!(policy.check(&ann).is_ok());
+    }
+
+    // ── RejectionLog ──
+
+    #[test]
+    fn test_rejection_log_counts_and_caps() {
+        let log = RejectionLog::new(2);
+        let ann = announcement("ogg", 1000, None);
+        let reason = RejectReason::FormatNotAllowed("ogg".into());
+        log.record("peer-a", &ann, &reason);
+        log.record("peer-b", &ann, &reason);
+        log.record("peer-a", &ann, &RejectReason::MissingAlbum);
+
+        let recent = log.recent();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].peer_id, "peer-a");
+        assert_eq!(recent[0].reason, "no album");
+        assert_eq!(recent[1].peer_id, "peer-b");
+
+        // Counts are kept even for entries that fell out of the log
+        assert_eq!(
+            log.per_peer(),
+            vec![
+                PeerRejections {
+                    peer_id: "peer-a".into(),
+                    rejected: 2
+                },
+                PeerRejections {
+                    peer_id: "peer-b".into(),
+                    rejected: 1
+                },
+            ]
+        );
+    }
+}
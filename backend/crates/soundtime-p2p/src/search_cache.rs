@@ -0,0 +1,261 @@
+//! Short-lived cache of distributed search results.
+//!
+//! Typing in the search box sends a network search per keystroke, each
+//! fanning out to up to 10 peers. [`SearchResultCache`] keeps the merged
+//! results of recent queries, keyed by the normalized query, for a
+//! configurable TTL so a repeated query is answered without asking peers
+//! again. Every entry is dropped when the local Bloom filter is rebuilt or a
+//! peer's filter is imported, since either can change which peers match.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::node::SearchResultItem;
+use crate::stats::SearchCacheStats;
+
+/// Default time a cached search stays valid.
+pub const DEFAULT_SEARCH_CACHE_TTL_SECS: u64 = 60;
+/// Default number of queries cached at once.
+pub const DEFAULT_SEARCH_CACHE_MAX_ENTRIES: usize = 256;
+
+struct CachedSearch {
+    results: Vec<SearchResultItem>,
+    /// Result limit the search ran with; larger requests miss
+    limit: usize,
+    inserted: Instant,
+}
+
+/// Cache key for a query: lowercased, with whitespace collapsed.
+pub fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub struct SearchResultCache {
+    entries: Mutex<HashMap<String, CachedSearch>>,
+    ttl: Duration,
+    max_entries: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl SearchResultCache {
+    /// A cache holding up to `max_entries` queries for `ttl` each. A zero
+    /// TTL or size disables it.
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            ttl,
+            max_entries,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        !self.ttl.is_zero() && self.max_entries > 0
+    }
+
+    /// Cached results of `query`, at most `limit` of them, if a search for
+    /// at least that many ran within the TTL.
+    pub fn get(&self, query: &str, limit: usize) -> Option<Vec<SearchResultItem>> {
+        self.get_at(query, limit, Instant::now())
+    }
+
+    fn get_at(&self, query: &str, limit: usize, now: Instant) -> Option<Vec<SearchResultItem>> {
+        if !self.enabled() {
+            return None;
+        }
+        let key = normalize_query(query);
+        let mut entries = self.entries.lock().expect("search cache lock poisoned");
+        let hit = match entries.get(&key) {
+            Some(e) if now.duration_since(e.inserted) >= self.ttl => {
+                entries.remove(&key);
+                None
+            }
+            // A search with a smaller limit may have cut off results
+            Some(e) if e.limit >= limit || e.results.len() < e.limit => {
+                Some(e.results.iter().take(limit).cloned().collect())
+            }
+            _ => None,
+        };
+        let counter = if hit.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        hit
+    }
+
+    /// Cache the merged results of a search for `query` run with `limit`.
+    pub fn insert(&self, query: &str, limit: usize, results: Vec<SearchResultItem>) {
+        self.insert_at(query, limit, results, Instant::now());
+    }
+
+    fn insert_at(&self, query: &str, limit: usize, results: Vec<SearchResultItem>, now: Instant) {
+        if !self.enabled() {
+            return;
+        }
+        let mut entries = self.entries.lock().expect("search cache lock poisoned");
+        entries.retain(|_, e| now.duration_since(e.inserted) < self.ttl);
+        let key = normalize_query(query);
+        while !entries.contains_key(&key) && entries.len() >= self.max_entries {
+            let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, e)| e.inserted)
+                .map(|(k, _)| k.clone())
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+        entries.insert(
+            key,
+            CachedSearch {
+                results,
+                limit,
+                inserted: now,
+            },
+        );
+    }
+
+    /// Drop every cached search.
+    pub fn invalidate(&self) {
+        self.entries
+            .lock()
+            .expect("search cache lock poisoned")
+            .clear();
+    }
+
+    pub fn stats(&self) -> SearchCacheStats {
+        SearchCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self
+                .entries
+                .lock()
+                .expect("search cache lock poisoned")
+                .len() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(hash: &str) -> SearchResultItem {
+        SearchResultItem {
+            hash: hash.into(),
+            title: format!("Track {hash}"),
+            artist_name: "Artist".into(),
+            album_title: None,
+            duration_secs: 180.0,
+            format: "flac".into(),
+            genre: None,
+            year: None,
+            bitrate: None,
+            source_node: "peer".into(),
+            musicbrainz_id: None,
+            relevance: 1.0,
+            play_count: 0,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+        }
+    }
+
+    fn hashes(results: &[SearchResultItem]) -> Vec<&str> {
+        results.iter().map(|r| r.hash.as_str()).collect()
+    }
+
+    // ── normalize_query ──
+
+    #[test]
+    fn test_normalize_query() {
+        assert_eq!(normalize_query("  Daft   PUNK "), "daft punk");
+        assert_eq!(normalize_query(""), "");
+    }
+
+    // ── get / insert ──
+
+    #[test]
+    fn test_hit_for_normalized_query() {
+        let cache = SearchResultCache::new(Duration::from_secs(60), 10);
+        assert!(cache.get("daft punk", 20).is_none());
+        cache.insert("daft punk", 20, vec![item("a"), item("b")]);
+
+        let hit = cache.get("Daft  Punk", 20).unwrap();
+        assert_eq!(hashes(&hit), ["a", "b"]);
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+    }
+
+    #[test]
+    fn test_entry_expires_after_ttl() {
+        let cache = SearchResultCache::new(Duration::from_secs(60), 10);
+        let start = Instant::now();
+        cache.insert_at("jazz", 20, vec![item("a")], start);
+
+        assert!(cache
+            .get_at("jazz", 20, start + Duration::from_secs(59))
+            .is_some());
+        assert!(cache
+            .get_at("jazz", 20, start + Duration::from_secs(60))
+            .is_none());
+        // The expired entry is gone, not just skipped
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_larger_limit_misses_unless_results_were_exhausted() {
+        let cache = SearchResultCache::new(Duration::from_secs(60), 10);
+        cache.insert("full", 2, vec![item("a"), item("b")]);
+        cache.insert("short", 5, vec![item("a")]);
+
+        assert_eq!(hashes(&cache.get("full", 1).unwrap()), ["a"]);
+        assert!(cache.get("full", 5).is_none());
+        // Fewer results than asked for: there are no more to find
+        assert_eq!(hashes(&cache.get("short", 50).unwrap()), ["a"]);
+    }
+
+    #[test]
+    fn test_oldest_entry_evicted_at_capacity() {
+        let cache = SearchResultCache::new(Duration::from_secs(60), 2);
+        let start = Instant::now();
+        cache.insert_at("one", 10, vec![item("1")], start);
+        cache.insert_at("two", 10, vec![item("2")], start + Duration::from_secs(1));
+        cache.insert_at("three", 10, vec![item("3")], start + Duration::from_secs(2));
+
+        let now = start + Duration::from_secs(3);
+        assert!(cache.get_at("one", 10, now).is_none());
+        assert!(cache.get_at("two", 10, now).is_some());
+        assert!(cache.get_at("three", 10, now).is_some());
+    }
+
+    #[test]
+    fn test_invalidate_drops_all_entries() {
+        let cache = SearchResultCache::new(Duration::from_secs(60), 10);
+        cache.insert("one", 10, vec![item("1")]);
+        cache.insert("two", 10, vec![item("2")]);
+        cache.invalidate();
+
+        assert_eq!(cache.stats().entries, 0);
+        assert!(cache.get("one", 10).is_none());
+        assert!(cache.get("two", 10).is_none());
+    }
+
+    #[test]
+    fn test_zero_ttl_disables_cache() {
+        let cache = SearchResultCache::new(Duration::ZERO, 10);
+        cache.insert("jazz", 10, vec![item("a")]);
+        assert!(cache.get("jazz", 10).is_none());
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
@@ -8,22 +8,78 @@
 //! A Bloom filter of 1M entries takes ~1.2 MB with 1% false positive rate.
 //! This allows efficient search routing: instead of broadcasting a search
 //! query to every peer, we only query peers whose Bloom filter matches.
+//!
+//! The local filter is sized for a number of items at a
+//! `P2P_BLOOM_TARGET_FPR` false positive rate, using the standard formulas
+//! (see [`optimal_bloom_params`]): `P2P_BLOOM_EXPECTED_ITEMS` at startup, then
+//! whatever the local catalog needs each time it is rebuilt, so small
+//! instances keep a small filter. Once more than `RESIZE_LOAD_FACTOR` of the
+//! capacity is used, [`SearchIndex::maybe_resize`] rebuilds it at twice the
+//! size from the local catalog (see [`SearchIndex::set_track_source`]), so
+//! the false positive rate stays near its target as the library grows.
+//!
+//! Peers get the full filter on first contact and whenever it was rebuilt.
+//! In between, the periodic exchange only sends the bits set since the last
+//! full broadcast ([`SearchIndex::export_delta`]), tagged with that
+//! broadcast's generation. A receiver ORs them into its copy
+//! ([`SearchIndex::apply_delta`]); if its copy is from another generation it
+//! asks for the full filter again.
+//!
+//! Next to the filters, a query history remembers which peers returned
+//! results for the terms local users searched for
+//! ([`SearchIndex::record_query_result`]), so later searches for the same
+//! terms also go to those peers when their filter does not match.
 
+use async_trait::async_trait;
 use bloomfilter::Bloom;
-use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
 use soundtime_db::entities::{album, artist, track};
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
 use tokio::sync::RwLock;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 /// Default Bloom filter capacity — number of expected items.
-const DEFAULT_BLOOM_CAPACITY: usize = 100_000;
-/// Target false positive rate (1%).
-const FALSE_POSITIVE_RATE: f64 = 0.01;
+pub const DEFAULT_BLOOM_CAPACITY: usize = 100_000;
+/// Default target false positive rate (1%).
+pub const FALSE_POSITIVE_RATE: f64 = 0.01;
+/// Share of the capacity in use above which the local filter is resized.
+const RESIZE_LOAD_FACTOR: f64 = 0.7;
+/// Smallest capacity a rebuild sizes the local filter for.
+const MIN_BLOOM_CAPACITY: usize = 1_000;
+/// Terms a track is assumed to add before a rebuild has counted them.
+const ESTIMATED_TERMS_PER_TRACK: u64 = 6;
 /// Page size for paginated database queries during rebuild.
 const REBUILD_PAGE_SIZE: u64 = 1000;
+/// Magic bytes (and format version) at the start of a persisted Bloom filter.
+const PERSIST_MAGIC: &[u8; 8] = b"STBLOOM2";
+/// Header length: magic + num_hashes (u32) + bitmap_bits, 4 SIP keys, item
+/// count and track count (u64) + dirty flag (u8).
+const PERSIST_HEADER_LEN: usize = 8 + 4 + 8 * 7 + 1;
+/// Terms kept in the query history; the least hit one is dropped first.
+const MAX_QUERY_HISTORY_TERMS: usize = 10_000;
+/// Peers kept per term in the query history.
+const MAX_QUERY_HISTORY_PEERS: usize = 32;
+
+/// One term of the query history with the peers that returned results for it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct QueryHistoryEntry {
+    pub term: String,
+    /// Results received for queries with this term, over all peers
+    pub hits: u64,
+    /// Most hits first
+    pub peers: Vec<QueryHistoryPeer>,
+}
+
+/// A peer that returned results for a query history term.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct QueryHistoryPeer {
+    pub peer_id: String,
+    pub hits: u64,
+}
 
 /// Compact serializable representation of a Bloom filter for network exchange.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -38,6 +94,140 @@ pub struct BloomFilterData {
     pub sip_keys: [(u64, u64); 2],
     /// Number of items inserted
     pub item_count: u64,
+    /// Generation of the sender's last full broadcast, which deltas build on
+    #[serde(default)]
+    pub generation: u64,
+}
+
+/// Bits set in a Bloom filter since its last full broadcast.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BloomDeltaData {
+    /// Generation of the full filter the bits apply to
+    pub generation: u64,
+    /// Bitmap size in bits, to catch a filter that was resized meanwhile
+    pub bitmap_bits: u64,
+    /// Newly set bits, as `byte * 8 + bit` offsets into the bitmap
+    pub set_bits: Vec<u64>,
+    /// Number of items inserted
+    pub item_count: u64,
+}
+
+/// Searchable fields of one local track: (title, artist name, album title).
+pub type TrackTerms = (String, String, Option<String>);
+
+/// The local catalog, read page by page to rebuild the local filter.
+#[async_trait]
+pub trait TrackSource: Send + Sync {
+    async fn track_count(&self) -> Result<u64, DbErr>;
+
+    /// Page `page` (zero-based) of `page_size` tracks.
+    async fn track_page(&self, page: u64, page_size: u64) -> Result<Vec<TrackTerms>, DbErr>;
+}
+
+#[async_trait]
+impl TrackSource for DatabaseConnection {
+    async fn track_count(&self) -> Result<u64, DbErr> {
+        track::Entity::find()
+            .filter(track::Column::IsHidden.eq(false))
+            .count(self)
+            .await
+    }
+
+    async fn track_page(&self, page: u64, page_size: u64) -> Result<Vec<TrackTerms>, DbErr> {
+        let tracks_with_artists: Vec<(track::Model, Option<artist::Model>)> = track::Entity::find()
+            .filter(track::Column::IsHidden.eq(false))
+            .find_also_related(artist::Entity)
+            .paginate(self, page_size)
+            .fetch_page(page)
+            .await?;
+
+        let mut terms = Vec::with_capacity(tracks_with_artists.len());
+        for (t, artist_opt) in tracks_with_artists {
+            let artist_name = artist_opt.map(|a| a.name).unwrap_or_default();
+
+            // Fetch album title if available (individual query per track with album).
+            let album_title = if let Some(album_id) = t.album_id {
+                album::Entity::find_by_id(album_id)
+                    .one(self)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|a| a.title)
+            } else {
+                None
+            };
+            terms.push((t.title, artist_name, album_title));
+        }
+        Ok(terms)
+    }
+}
+
+/// Bitmap size in bits and number of hash functions for `items` items at a
+/// false positive rate of `fpr`: `m = -n·ln(p) / ln(2)²` and `k = m/n · ln(2)`.
+pub fn optimal_bloom_params(items: usize, fpr: f64) -> (u64, u32) {
+    let n = items.max(1) as f64;
+    let ln2 = std::f64::consts::LN_2;
+    let bits = (-n * fpr.ln() / (ln2 * ln2)).ceil().max(8.0);
+    let hashes = (bits / n * ln2).round().max(1.0);
+    (bits as u64, hashes as u32)
+}
+
+/// Empty filter sized by [`optimal_bloom_params`], with random SIP keys.
+fn new_bloom(items: usize, fpr: f64) -> Bloom<String> {
+    let (bits, hashes) = optimal_bloom_params(items, fpr);
+    let sip_keys = [
+        (rand::random(), rand::random()),
+        (rand::random(), rand::random()),
+    ];
+    Bloom::from_existing(&vec![0; bits.div_ceil(8) as usize], bits, hashes, sip_keys)
+}
+
+/// Items a filter of `bits` bits holds at a false positive rate of `fpr`.
+fn capacity_for_bits(bits: u64, fpr: f64) -> usize {
+    let ln2_sq = std::f64::consts::LN_2 * std::f64::consts::LN_2;
+    ((bits as f64 * ln2_sq / -fpr.ln()) as usize).max(1)
+}
+
+/// Expected false positive rate of a filter of `bits` bits and `hashes`
+/// hash functions holding `items` items: `(1 - e^(-k·n/m))^k`.
+fn estimated_fpr(bits: u64, hashes: u32, items: u64) -> f64 {
+    if bits == 0 {
+        return 1.0;
+    }
+    let k = hashes as f64;
+    (1.0 - (-k * items as f64 / bits as f64).exp()).powf(k)
+}
+
+/// Offsets (`byte * 8 + bit`) of the bits set in `current` but not in `baseline`.
+fn new_bits(baseline: &[u8], current: &[u8]) -> Vec<u64> {
+    let mut bits = Vec::new();
+    for (i, (old, new)) in baseline.iter().zip(current).enumerate() {
+        let added = new & !old;
+        for bit in 0..8 {
+            if added & (1 << bit) != 0 {
+                bits.push(i as u64 * 8 + bit);
+            }
+        }
+    }
+    bits
+}
+
+fn exceeds_load_factor(item_count: u64, capacity: usize) -> bool {
+    item_count as f64 > capacity as f64 * RESIZE_LOAD_FACTOR
+}
+
+/// Capacity for `items` items that leaves them below the resize threshold.
+fn capacity_for_items(items: u64) -> usize {
+    ((items as f64 / RESIZE_LOAD_FACTOR).ceil() as usize).max(MIN_BLOOM_CAPACITY)
+}
+
+/// `capacity` doubled until `item_count` fits below the resize threshold.
+fn grown_capacity(item_count: u64, capacity: usize) -> usize {
+    let mut capacity = capacity.max(1) * 2;
+    while exceeds_load_factor(item_count, capacity) {
+        capacity *= 2;
+    }
+    capacity
 }
 
 /// Per-peer search index — stores the peer's Bloom filter for query routing.
@@ -54,27 +244,69 @@ pub struct SearchIndex {
     local_bloom: RwLock<Bloom<String>>,
     /// Number of items in local bloom
     local_item_count: RwLock<u64>,
+    /// Number of tracks whose terms are in the local bloom
+    local_track_count: AtomicU64,
     /// Bloom filters received from peers, keyed by NodeId
     peer_indexes: RwLock<HashMap<String, PeerSearchIndex>>,
     /// Flag indicating the Bloom filter needs a full rebuild (e.g. after a track deletion).
     /// Bloom filters don't support removal, so deletions require a complete rebuild.
     dirty: AtomicBool,
+    /// Items the local Bloom filter was sized for
+    capacity: AtomicUsize,
+    /// Catalog the local filter is rebuilt from when it is resized
+    track_source: OnceLock<Arc<dyn TrackSource>>,
+    /// Set while a resize is running, so only one runs at a time
+    resizing: AtomicBool,
+    /// Target false positive rate of the local filter
+    fpr: f64,
+    /// Generation of the last full broadcast of the local filter
+    generation: AtomicU64,
+    /// Local bitmap at the last full broadcast; `None` until the first one
+    /// and after a rebuild, when peers need the full filter again
+    baseline: RwLock<Option<Vec<u8>>>,
+    /// Query term → peers that returned results for it → hits
+    query_history: RwLock<HashMap<String, HashMap<String, u64>>>,
 }
 
 impl SearchIndex {
     /// Create a new, empty search index.
     pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_BLOOM_CAPACITY)
+    }
+
+    /// Create an empty search index whose local filter is sized for
+    /// `capacity` items.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_params(capacity, FALSE_POSITIVE_RATE)
+    }
+
+    /// Create an empty search index whose local filter is sized for
+    /// `expected_items` items at a false positive rate of `fpr`.
+    pub fn with_params(expected_items: usize, fpr: f64) -> Self {
+        let capacity = expected_items.max(1);
         Self {
-            local_bloom: RwLock::new(Bloom::new_for_fp_rate(
-                DEFAULT_BLOOM_CAPACITY,
-                FALSE_POSITIVE_RATE,
-            )),
+            local_bloom: RwLock::new(new_bloom(capacity, fpr)),
             local_item_count: RwLock::new(0),
+            local_track_count: AtomicU64::new(0),
             peer_indexes: RwLock::new(HashMap::new()),
             dirty: AtomicBool::new(false),
+            capacity: AtomicUsize::new(capacity),
+            track_source: OnceLock::new(),
+            resizing: AtomicBool::new(false),
+            fpr,
+            generation: AtomicU64::new(0),
+            baseline: RwLock::new(None),
+            query_history: RwLock::new(HashMap::new()),
         }
     }
 
+    /// Set the catalog the local filter is rebuilt from when it outgrows its
+    /// capacity. Without one the filter is never resized. Only the first
+    /// call has an effect.
+    pub fn set_track_source(&self, source: Arc<dyn TrackSource>) {
+        let _ = self.track_source.set(source);
+    }
+
     /// Normalize a term for insertion / lookup: lowercase + split words.
     fn normalize_terms(text: &str) -> Vec<String> {
         text.to_lowercase()
@@ -104,6 +336,7 @@ impl SearchIndex {
                 *count += 1;
             }
         }
+        self.local_track_count.fetch_add(1, Ordering::AcqRel);
     }
 
     /// Check if a query term *might* match our local index.
@@ -119,19 +352,85 @@ impl SearchIndex {
     pub async fn export_local_bloom(&self) -> BloomFilterData {
         let bloom = self.local_bloom.read().await;
         let count = self.local_item_count.read().await;
-        let bitmap = bloom.bitmap();
-        let num_hashes = bloom.number_of_hash_functions();
-        let bitmap_bits = bloom.number_of_bits();
-
-        let sip_keys = bloom.sip_keys();
+        self.export_bloom(&bloom, *count)
+    }
 
+    fn export_bloom(&self, bloom: &Bloom<String>, item_count: u64) -> BloomFilterData {
         BloomFilterData {
-            bitmap,
-            num_hashes,
-            bitmap_bits,
-            sip_keys,
+            bitmap: bloom.bitmap(),
+            num_hashes: bloom.number_of_hash_functions(),
+            bitmap_bits: bloom.number_of_bits(),
+            sip_keys: bloom.sip_keys(),
+            item_count,
+            generation: self.generation.load(Ordering::Acquire),
+        }
+    }
+
+    /// Start a new generation: export the full local filter and remember its
+    /// bitmap as the baseline later deltas are computed against. Call this
+    /// when broadcasting the full filter to every peer.
+    pub async fn begin_generation(&self) -> BloomFilterData {
+        let bloom = self.local_bloom.read().await;
+        let count = self.local_item_count.read().await;
+        let mut baseline = self.baseline.write().await;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        let data = self.export_bloom(&bloom, *count);
+        *baseline = Some(data.bitmap.clone());
+        data
+    }
+
+    /// Bits set in the local filter since the current generation began.
+    ///
+    /// Returns `None` when peers need the full filter instead: before the
+    /// first [`begin_generation`](Self::begin_generation), after a rebuild,
+    /// or when the delta would be larger than the filter itself.
+    pub async fn export_delta(&self) -> Option<BloomDeltaData> {
+        let bloom = self.local_bloom.read().await;
+        let count = self.local_item_count.read().await;
+        let baseline = self.baseline.read().await;
+        let baseline = baseline.as_ref()?;
+
+        let bitmap = bloom.bitmap();
+        if bitmap.len() != baseline.len() {
+            return None;
+        }
+        let set_bits = new_bits(baseline, &bitmap);
+        // Each offset takes 8 bytes on the wire
+        if set_bits.len() * 8 >= bitmap.len() {
+            return None;
+        }
+        Some(BloomDeltaData {
+            generation: self.generation.load(Ordering::Acquire),
+            bitmap_bits: bloom.number_of_bits(),
+            set_bits,
             item_count: *count,
+        })
+    }
+
+    /// Set a delta's bits in the filter stored for a peer. Returns `false`,
+    /// leaving the filter untouched, if there is none or it is not the
+    /// generation the delta builds on; the peer's full filter is needed then.
+    pub async fn apply_delta(&self, node_id: &str, delta: &BloomDeltaData) -> bool {
+        let mut indexes = self.peer_indexes.write().await;
+        let Some(peer_index) = indexes.get_mut(node_id) else {
+            return false;
+        };
+        let bloom = &mut peer_index.bloom;
+        if bloom.generation != delta.generation || bloom.bitmap_bits != delta.bitmap_bits {
+            return false;
+        }
+        let len = bloom.bitmap.len() as u64 * 8;
+        if delta.set_bits.iter().any(|&bit| bit >= len) {
+            return false;
+        }
+
+        for &bit in &delta.set_bits {
+            bloom.bitmap[(bit / 8) as usize] |= 1 << (bit % 8);
         }
+        bloom.item_count = delta.item_count;
+        peer_index.last_updated = chrono::Utc::now();
+        debug!(%node_id, bits = delta.set_bits.len(), "applied peer bloom delta");
+        true
     }
 
     /// Import a peer's Bloom filter for search routing.
@@ -204,6 +503,101 @@ impl SearchIndex {
     pub async fn remove_peer(&self, node_id: &str) {
         let mut indexes = self.peer_indexes.write().await;
         indexes.remove(node_id);
+        drop(indexes);
+
+        let mut history = self.query_history.write().await;
+        history.retain(|_, peers| {
+            peers.remove(node_id);
+            !peers.is_empty()
+        });
+    }
+
+    /// Remember that `peer_id` returned results for `query`, so later
+    /// queries with the same terms are sent to it even if its Bloom filter
+    /// does not match.
+    pub async fn record_query_result(&self, query: &str, peer_id: &str) {
+        let mut terms = Self::normalize_terms(query);
+        terms.sort();
+        terms.dedup();
+        if terms.is_empty() {
+            return;
+        }
+
+        let mut history = self.query_history.write().await;
+        for term in terms {
+            if !history.contains_key(&term) && history.len() >= MAX_QUERY_HISTORY_TERMS {
+                let least_hit = history
+                    .iter()
+                    .min_by_key(|(_, peers)| peers.values().sum::<u64>())
+                    .map(|(term, _)| term.clone());
+                if let Some(least_hit) = least_hit {
+                    history.remove(&least_hit);
+                }
+            }
+            let peers = history.entry(term).or_default();
+            if !peers.contains_key(peer_id) && peers.len() >= MAX_QUERY_HISTORY_PEERS {
+                let least_hit = peers
+                    .iter()
+                    .min_by_key(|(_, hits)| **hits)
+                    .map(|(peer, _)| peer.clone());
+                if let Some(least_hit) = least_hit {
+                    peers.remove(&least_hit);
+                }
+            }
+            *peers.entry(peer_id.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    /// Peers that returned results for every term of `query` before, most
+    /// hits first.
+    pub async fn peers_from_query_history(&self, query: &str) -> Vec<String> {
+        let terms = Self::normalize_terms(query);
+        let Some((first, rest)) = terms.split_first() else {
+            return vec![];
+        };
+
+        let history = self.query_history.read().await;
+        let Some(candidates) = history.get(first) else {
+            return vec![];
+        };
+        let mut matching: Vec<(&String, u64)> = candidates
+            .iter()
+            .filter_map(|(peer, hits)| {
+                rest.iter()
+                    .try_fold(*hits, |total, term| {
+                        Some(total + history.get(term)?.get(peer)?)
+                    })
+                    .map(|total| (peer, total))
+            })
+            .collect();
+        matching.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        matching.into_iter().map(|(peer, _)| peer.clone()).collect()
+    }
+
+    /// The `limit` most hit terms of the query history, most hits first.
+    pub async fn query_history(&self, limit: usize) -> Vec<QueryHistoryEntry> {
+        let history = self.query_history.read().await;
+        let mut entries: Vec<QueryHistoryEntry> = history
+            .iter()
+            .map(|(term, peers)| {
+                let mut peers: Vec<QueryHistoryPeer> = peers
+                    .iter()
+                    .map(|(peer_id, hits)| QueryHistoryPeer {
+                        peer_id: peer_id.clone(),
+                        hits: *hits,
+                    })
+                    .collect();
+                peers.sort_by(|a, b| b.hits.cmp(&a.hits).then_with(|| a.peer_id.cmp(&b.peer_id)));
+                QueryHistoryEntry {
+                    term: term.clone(),
+                    hits: peers.iter().map(|p| p.hits).sum(),
+                    peers,
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| b.hits.cmp(&a.hits).then_with(|| a.term.cmp(&b.term)));
+        entries.truncate(limit);
+        entries
     }
 
     /// Get count of indexed peers.
@@ -212,7 +606,8 @@ impl SearchIndex {
         indexes.len()
     }
 
-    /// Rebuild the local Bloom filter from a full list of tracks.
+    /// Rebuild the local Bloom filter from a full list of tracks, sized for
+    /// the terms they contain.
     /// Called at startup to populate the index from the database.
     pub async fn rebuild_from_tracks(
         &self,
@@ -222,10 +617,20 @@ impl SearchIndex {
         let mut count = self.local_item_count.write().await;
 
         // Reset
-        *bloom = Bloom::new_for_fp_rate(
-            tracks.len().max(DEFAULT_BLOOM_CAPACITY),
-            FALSE_POSITIVE_RATE,
-        );
+        *self.baseline.write().await = None;
+        let terms: usize = tracks
+            .iter()
+            .map(|(title, artist, album)| {
+                Self::normalize_terms(title).len()
+                    + Self::normalize_terms(artist).len()
+                    + album
+                        .as_deref()
+                        .map_or(0, |a| Self::normalize_terms(a).len())
+            })
+            .sum();
+        let capacity = capacity_for_items(terms as u64);
+        *bloom = new_bloom(capacity, self.fpr);
+        self.capacity.store(capacity, Ordering::Release);
         *count = 0;
 
         for (title, artist, album) in tracks {
@@ -245,6 +650,9 @@ impl SearchIndex {
             }
         }
 
+        self.local_track_count
+            .store(tracks.len() as u64, Ordering::Release);
+
         info!(
             tracks = tracks.len(),
             terms = *count,
@@ -258,18 +666,86 @@ impl SearchIndex {
     /// `REBUILD_PAGE_SIZE` records at a time to avoid OOM on large instances.
     /// Resets the dirty flag on success.
     pub async fn rebuild_from_db(&self, db: &DatabaseConnection) -> Result<(), sea_orm::DbErr> {
+        self.rebuild_from_source(db, None).await
+    }
+
+    /// Like [`rebuild_from_db`](Self::rebuild_from_db), but size the new
+    /// filter to about `new_bits` bits instead of the track count.
+    pub async fn rebuild_from_db_with_capacity(
+        &self,
+        db: &DatabaseConnection,
+        new_bits: u64,
+    ) -> Result<(), sea_orm::DbErr> {
+        self.rebuild_from_source(db, Some(capacity_for_bits(new_bits, self.fpr)))
+            .await
+    }
+
+    /// Rebuild the local filter from `source`, sized for `capacity` items
+    /// (by default an estimate from the track count). If the catalog turns
+    /// out to hold more terms than the filter comfortably fits, it is read
+    /// again into a larger one, so a rebuild never ends above the resize
+    /// threshold.
+    async fn rebuild_from_source(
+        &self,
+        source: &dyn TrackSource,
+        capacity: Option<usize>,
+    ) -> Result<(), sea_orm::DbErr> {
         // First, count total tracks to size the Bloom filter appropriately.
-        let total_tracks = track::Entity::find().count(db).await?;
+        let total_tracks = source.track_count().await?;
 
         let mut bloom = self.local_bloom.write().await;
         let mut count = self.local_item_count.write().await;
 
-        // Reset the Bloom filter with capacity based on actual track count.
-        *bloom = Bloom::new_for_fp_rate(
-            (total_tracks as usize).max(DEFAULT_BLOOM_CAPACITY),
-            FALSE_POSITIVE_RATE,
+        let mut capacity = capacity.unwrap_or_else(|| {
+            capacity_for_items(total_tracks.saturating_mul(ESTIMATED_TERMS_PER_TRACK))
+        });
+        let (new_bloom, new_count, tracks_read) = loop {
+            let (new_bloom, new_count, tracks_read) = self
+                .build_from_source(source, total_tracks, capacity)
+                .await?;
+            if !exceeds_load_factor(new_count, capacity) {
+                break (new_bloom, new_count, tracks_read);
+            }
+            let grown = grown_capacity(new_count, capacity);
+            debug!(
+                terms = new_count,
+                capacity, grown, "catalog outgrew the rebuilt bloom filter, rebuilding larger"
+            );
+            capacity = grown;
+        };
+
+        // Only replace the filter once every page was read
+        *bloom = new_bloom;
+        *count = new_count;
+        *self.baseline.write().await = None;
+        self.capacity.store(capacity, Ordering::Release);
+        self.local_track_count.store(tracks_read, Ordering::Release);
+
+        // Clear the dirty flag after a successful full rebuild.
+        self.dirty.store(false, Ordering::Release);
+
+        info!(
+            tracks = total_tracks,
+            terms = *count,
+            capacity,
+            "rebuilt local bloom filter from database (paginated)"
         );
-        *count = 0;
+
+        Ok(())
+    }
+
+    /// Read every page of `source` into a new filter sized for `capacity`
+    /// items. Returns the filter, the number of terms inserted and the
+    /// number of tracks read.
+    async fn build_from_source(
+        &self,
+        source: &dyn TrackSource,
+        total_tracks: u64,
+        capacity: usize,
+    ) -> Result<(Bloom<String>, u64, u64), sea_orm::DbErr> {
+        let mut new_bloom = new_bloom(capacity, self.fpr);
+        let mut new_count = 0;
+        let mut tracks_read = 0;
 
         let num_pages = if total_tracks == 0 {
             0
@@ -278,64 +754,76 @@ impl SearchIndex {
         };
 
         for page_num in 0..num_pages {
-            let tracks_with_artists: Vec<(track::Model, Option<artist::Model>)> =
-                track::Entity::find()
-                    .find_also_related(artist::Entity)
-                    .paginate(db, REBUILD_PAGE_SIZE)
-                    .fetch_page(page_num)
-                    .await?;
-
-            for (t, artist_opt) in &tracks_with_artists {
-                let artist_name = artist_opt
-                    .as_ref()
-                    .map(|a| a.name.clone())
-                    .unwrap_or_default();
-
-                // Fetch album title if available (individual query per track with album).
-                let album_title = if let Some(album_id) = t.album_id {
-                    album::Entity::find_by_id(album_id)
-                        .one(db)
-                        .await
-                        .ok()
-                        .flatten()
-                        .map(|a| a.title)
-                } else {
-                    None
-                };
-
-                for term in Self::normalize_terms(&t.title) {
-                    bloom.set(&term);
-                    *count += 1;
+            let tracks = source.track_page(page_num, REBUILD_PAGE_SIZE).await?;
+            tracks_read += tracks.len() as u64;
+
+            for (title, artist_name, album_title) in &tracks {
+                for term in Self::normalize_terms(title) {
+                    new_bloom.set(&term);
+                    new_count += 1;
                 }
-                for term in Self::normalize_terms(&artist_name) {
-                    bloom.set(&term);
-                    *count += 1;
+                for term in Self::normalize_terms(artist_name) {
+                    new_bloom.set(&term);
+                    new_count += 1;
                 }
-                if let Some(ref alb) = album_title {
+                if let Some(alb) = album_title {
                     for term in Self::normalize_terms(alb) {
-                        bloom.set(&term);
-                        *count += 1;
+                        new_bloom.set(&term);
+                        new_count += 1;
                     }
                 }
             }
 
             debug!(
                 page = page_num,
-                tracks_in_page = tracks_with_artists.len(),
+                tracks_in_page = tracks.len(),
                 "processed search index page"
             );
         }
 
-        // Clear the dirty flag after a successful full rebuild.
-        self.dirty.store(false, Ordering::Release);
+        Ok((new_bloom, new_count, tracks_read))
+    }
 
-        info!(
-            tracks = total_tracks,
-            terms = *count,
-            "rebuilt local bloom filter from database (paginated)"
-        );
+    /// Rebuild the local filter at twice its size if more than
+    /// `RESIZE_LOAD_FACTOR` of its capacity is used. Needs a track source
+    /// (see [`set_track_source`](Self::set_track_source)). Returns whether
+    /// the filter was resized.
+    pub async fn maybe_resize(&self) -> bool {
+        let item_count = *self.local_item_count.read().await;
+        let capacity = self.capacity.load(Ordering::Acquire);
+        if !exceeds_load_factor(item_count, capacity) {
+            return false;
+        }
+        let Some(source) = self.track_source.get() else {
+            debug!(
+                item_count,
+                capacity, "bloom filter over capacity, no track source to resize from"
+            );
+            return false;
+        };
+        if self.resizing.swap(true, Ordering::AcqRel) {
+            return false;
+        }
 
-        Ok(())
+        // Usually one doubling; more if the count already outgrew that
+        let new_capacity = grown_capacity(item_count, capacity);
+        let old = self.local_bloom.read().await.number_of_bits();
+        let result = self
+            .rebuild_from_source(source.as_ref(), Some(new_capacity))
+            .await;
+        self.resizing.store(false, Ordering::Release);
+
+        match result {
+            Ok(()) => {
+                let new = self.local_bloom.read().await.number_of_bits();
+                info!("bloom filter resized from {old} to {new} bits");
+                true
+            }
+            Err(e) => {
+                warn!("failed to resize bloom filter: {e}");
+                false
+            }
+        }
     }
 
     /// Add a single track's tokens to the search index without a full rebuild.
@@ -360,12 +848,30 @@ impl SearchIndex {
                 *count += 1;
             }
         }
+        self.local_track_count.fetch_add(1, Ordering::AcqRel);
+
+        drop(count);
+        drop(bloom);
 
         debug!(
             title = title,
             artist = artist,
             "added track tokens to search index"
         );
+        self.maybe_resize().await;
+    }
+
+    /// Expected false positive rate of the local filter at its current fill,
+    /// from its size, hash count and the number of items inserted. It rises
+    /// above the target as the filter nears capacity, until it is resized.
+    pub async fn fpr_estimate(&self) -> f64 {
+        let bloom = self.local_bloom.read().await;
+        let items = *self.local_item_count.read().await;
+        estimated_fpr(
+            bloom.number_of_bits(),
+            bloom.number_of_hash_functions(),
+            items,
+        )
     }
 
     /// Mark the search index as dirty, requiring a full rebuild.
@@ -382,6 +888,127 @@ impl SearchIndex {
     pub fn is_dirty(&self) -> bool {
         self.dirty.load(Ordering::Acquire)
     }
+
+    /// Whether the local filter covers `source` as it is now: it is not
+    /// dirty and holds the terms of as many tracks as `source` has. A filter
+    /// loaded from disk that fails this missed changes made while the node
+    /// was down and needs a rebuild.
+    pub async fn is_current(&self, source: &dyn TrackSource) -> Result<bool, DbErr> {
+        if self.is_dirty() {
+            return Ok(false);
+        }
+        let tracks = source.track_count().await?;
+        Ok(tracks == self.local_track_count.load(Ordering::Acquire))
+    }
+
+    /// Persist the local Bloom filter to `path` so it can be reloaded on restart.
+    ///
+    /// The file is a fixed little-endian header (magic, `num_hashes`,
+    /// `bitmap_bits`, SIP keys, item count, track count, dirty flag) followed
+    /// by the raw bitmap. It is written to a temporary file first and renamed
+    /// into place.
+    pub async fn save_to_disk(&self, path: &Path) -> std::io::Result<()> {
+        let data = self.export_local_bloom().await;
+
+        let mut buf = Vec::with_capacity(PERSIST_HEADER_LEN + data.bitmap.len());
+        buf.extend_from_slice(PERSIST_MAGIC);
+        buf.extend_from_slice(&data.num_hashes.to_le_bytes());
+        buf.extend_from_slice(&data.bitmap_bits.to_le_bytes());
+        for (k0, k1) in data.sip_keys {
+            buf.extend_from_slice(&k0.to_le_bytes());
+            buf.extend_from_slice(&k1.to_le_bytes());
+        }
+        buf.extend_from_slice(&data.item_count.to_le_bytes());
+        buf.extend_from_slice(&self.local_track_count.load(Ordering::Acquire).to_le_bytes());
+        buf.push(self.is_dirty() as u8);
+        buf.extend_from_slice(&data.bitmap);
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp_path = path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, &buf).await?;
+        tokio::fs::rename(&tmp_path, path).await?;
+
+        debug!(path = %path.display(), bytes = buf.len(), "saved local bloom filter");
+        Ok(())
+    }
+
+    /// Replace the local Bloom filter with one previously written by
+    /// [`save_to_disk`](Self::save_to_disk).
+    ///
+    /// Returns an `InvalidData` error if the file is truncated or does not
+    /// look like a persisted filter; the current index is left untouched.
+    /// The track count and dirty flag are restored too, so
+    /// [`is_current`](Self::is_current) tells whether the filter is stale.
+    pub async fn load_from_disk(&self, path: &Path) -> std::io::Result<()> {
+        let buf = tokio::fs::read(path).await?;
+        let (data, tracks, dirty) = Self::decode_persisted(&buf).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, "corrupt bloom filter file")
+        })?;
+
+        let mut bloom = self.local_bloom.write().await;
+        let mut count = self.local_item_count.write().await;
+        *bloom = Bloom::from_existing(
+            &data.bitmap,
+            data.bitmap_bits,
+            data.num_hashes,
+            data.sip_keys,
+        );
+        *count = data.item_count;
+        *self.baseline.write().await = None;
+        self.capacity.store(
+            capacity_for_bits(data.bitmap_bits, self.fpr),
+            Ordering::Release,
+        );
+        self.local_track_count.store(tracks, Ordering::Release);
+        self.dirty.store(dirty, Ordering::Release);
+
+        info!(
+            path = %path.display(),
+            terms = data.item_count,
+            tracks,
+            dirty,
+            "loaded local bloom filter from disk"
+        );
+        Ok(())
+    }
+
+    /// Decode the on-disk format, validating that the bitmap matches the
+    /// header. Returns the filter, its track count and its dirty flag.
+    fn decode_persisted(buf: &[u8]) -> Option<(BloomFilterData, u64, bool)> {
+        if buf.len() < PERSIST_HEADER_LEN || &buf[..8] != PERSIST_MAGIC {
+            return None;
+        }
+        let u64_at = |offset: usize| -> u64 {
+            u64::from_le_bytes(buf[offset..offset + 8].try_into().expect("8-byte slice"))
+        };
+
+        let num_hashes = u32::from_le_bytes(buf[8..12].try_into().expect("4-byte slice"));
+        let bitmap_bits = u64_at(12);
+        let sip_keys = [(u64_at(20), u64_at(28)), (u64_at(36), u64_at(44))];
+        let item_count = u64_at(52);
+        let tracks = u64_at(60);
+        let dirty = buf[68] != 0;
+        let bitmap = buf[PERSIST_HEADER_LEN..].to_vec();
+
+        if num_hashes == 0 || bitmap_bits == 0 || bitmap.len() as u64 != bitmap_bits.div_ceil(8) {
+            return None;
+        }
+
+        Some((
+            BloomFilterData {
+                bitmap,
+                num_hashes,
+                bitmap_bits,
+                sip_keys,
+                item_count,
+                generation: 0,
+            },
+            tracks,
+            dirty,
+        ))
+    }
 }
 
 impl Default for SearchIndex {
@@ -661,6 +1288,103 @@ mod tests {
         assert_eq!(idx.indexed_peer_count().await, 0);
     }
 
+    // ── query history ────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_query_history_routes_repeated_queries() {
+        let idx = SearchIndex::new();
+        assert!(idx.peers_from_query_history("beethoven").await.is_empty());
+
+        idx.record_query_result("Beethoven Symphony", "peer1").await;
+        idx.record_query_result("beethoven", "peer2").await;
+        idx.record_query_result("beethoven", "peer2").await;
+
+        // Most hits first
+        assert_eq!(
+            idx.peers_from_query_history("BEETHOVEN").await,
+            vec!["peer2".to_string(), "peer1".to_string()]
+        );
+        // Every term must have been answered by the peer
+        assert_eq!(
+            idx.peers_from_query_history("beethoven symphony").await,
+            vec!["peer1".to_string()]
+        );
+        assert!(idx.peers_from_query_history("mozart").await.is_empty());
+        // Short or empty queries match nothing
+        assert!(idx.peers_from_query_history("").await.is_empty());
+        idx.record_query_result("a", "peer3").await;
+        assert!(idx.query_history(10).await.iter().all(|e| e.term != "a"));
+    }
+
+    #[tokio::test]
+    async fn test_query_history_listing() {
+        let idx = SearchIndex::new();
+        idx.record_query_result("Take Five", "peer1").await;
+        idx.record_query_result("five five", "peer2").await;
+        idx.record_query_result("five", "peer2").await;
+
+        let history = idx.query_history(10).await;
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].term, "five");
+        assert_eq!(history[0].hits, 3);
+        assert_eq!(
+            history[0].peers,
+            vec![
+                QueryHistoryPeer {
+                    peer_id: "peer2".into(),
+                    hits: 2,
+                },
+                QueryHistoryPeer {
+                    peer_id: "peer1".into(),
+                    hits: 1,
+                },
+            ]
+        );
+        assert_eq!(history[1].term, "take");
+        assert_eq!(idx.query_history(1).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_query_history_is_bounded() {
+        let idx = SearchIndex::new();
+        idx.record_query_result("popular", "peer0").await;
+        idx.record_query_result("popular", "peer0").await;
+        for i in 0..MAX_QUERY_HISTORY_TERMS {
+            idx.record_query_result(&format!("term{i}"), "peer0").await;
+        }
+        let history = idx.query_history(usize::MAX).await;
+        assert_eq!(history.len(), MAX_QUERY_HISTORY_TERMS);
+        assert_eq!(history[0].term, "popular");
+
+        for i in 0..=MAX_QUERY_HISTORY_PEERS {
+            idx.record_query_result("popular", &format!("peer{}", i + 1))
+                .await;
+        }
+        let popular = &idx.query_history(1).await[0];
+        assert_eq!(popular.peers.len(), MAX_QUERY_HISTORY_PEERS);
+        assert_eq!(popular.peers[0].peer_id, "peer0");
+    }
+
+    #[tokio::test]
+    async fn test_remove_peer_clears_query_history() {
+        let idx = SearchIndex::new();
+        idx.record_query_result("beethoven", "peer1").await;
+        idx.record_query_result("beethoven mozart", "peer2").await;
+        idx.remove_peer("peer2").await;
+
+        assert_eq!(
+            idx.peers_from_query_history("beethoven").await,
+            vec!["peer1".to_string()]
+        );
+        let terms: Vec<String> = idx
+            .query_history(10)
+            .await
+            .into_iter()
+            .map(|e| e.term)
+            .collect();
+        assert_eq!(terms, vec!["beethoven".to_string()]);
+    }
+
     // ── indexed_peer_count ───────────────────────────────────────────
 
     #[tokio::test]
@@ -717,6 +1441,7 @@ mod tests {
             bitmap_bits: 8192,
             sip_keys: [(111, 222), (333, 444)],
             item_count: 99,
+            generation: 0,
         };
         let json = serde_json::to_string(&data).unwrap();
         let decoded: BloomFilterData = serde_json::from_str(&json).unwrap();
@@ -735,6 +1460,7 @@ mod tests {
             bitmap_bits: 512,
             sip_keys: [(1, 2), (3, 4)],
             item_count: 50,
+            generation: 0,
         };
         let cloned = data.clone();
         assert_eq!(data.bitmap, cloned.bitmap);
@@ -811,6 +1537,7 @@ mod tests {
                 bitmap_bits: 0,
                 sip_keys: [(0, 0), (0, 0)],
                 item_count: 0,
+                generation: 0,
             },
             last_updated: chrono::Utc::now(),
         };
@@ -893,4 +1620,466 @@ mod tests {
         let idx = SearchIndex::new();
         assert!(!idx.is_dirty());
     }
+
+    // ── save_to_disk / load_from_disk ─────────────────────────────────
+
+    #[tokio::test]
+    async fn test_save_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("p2p").join("bloom.bin");
+
+        let idx = SearchIndex::new();
+        idx.insert_track("Blue in Green", "Miles Davis", Some("Kind of Blue"))
+            .await;
+        idx.save_to_disk(&path).await.unwrap();
+        assert!(path.exists());
+
+        let restored = SearchIndex::new();
+        restored.load_from_disk(&path).await.unwrap();
+        assert!(restored.local_might_match("miles davis").await);
+        assert!(restored.local_might_match("kind blue").await);
+        assert!(!restored.local_might_match("coltrane").await);
+
+        let a = idx.export_local_bloom().await;
+        let b = restored.export_local_bloom().await;
+        assert_eq!(a.bitmap, b.bitmap);
+        assert_eq!(a.sip_keys, b.sip_keys);
+        assert_eq!(a.item_count, b.item_count);
+    }
+
+    #[tokio::test]
+    async fn test_load_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let idx = SearchIndex::new();
+        let err = idx
+            .load_from_disk(&dir.path().join("absent.bin"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_load_corrupt_file_keeps_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bloom.bin");
+
+        let idx = SearchIndex::new();
+        idx.insert_track("Track", "Artist", None).await;
+        idx.save_to_disk(&path).await.unwrap();
+
+        // Truncate the bitmap so it no longer matches the header
+        let mut bytes = tokio::fs::read(&path).await.unwrap();
+        bytes.truncate(bytes.len() - 1);
+        tokio::fs::write(&path, &bytes).await.unwrap();
+
+        let err = idx.load_from_disk(&path).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(idx.local_might_match("artist").await);
+
+        tokio::fs::write(&path, b"garbage").await.unwrap();
+        assert!(idx.load_from_disk(&path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_loaded_filter_is_stale_when_catalog_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bloom.bin");
+        let tracks = MemoryTracks::default();
+        tracks
+            .0
+            .lock()
+            .unwrap()
+            .push(("Track".to_string(), "Artist".to_string(), None));
+
+        let idx = SearchIndex::new();
+        idx.rebuild_from_source(&tracks, None).await.unwrap();
+        idx.save_to_disk(&path).await.unwrap();
+
+        let restored = SearchIndex::new();
+        restored.load_from_disk(&path).await.unwrap();
+        assert!(restored.is_current(&tracks).await.unwrap());
+
+        // A track added while the node was down
+        tracks
+            .0
+            .lock()
+            .unwrap()
+            .push(("Other".to_string(), "Band".to_string(), None));
+        assert!(!restored.is_current(&tracks).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_dirty_flag_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bloom.bin");
+        let tracks = MemoryTracks::default();
+
+        let idx = SearchIndex::new();
+        idx.mark_dirty().await;
+        idx.save_to_disk(&path).await.unwrap();
+
+        let restored = SearchIndex::new();
+        restored.load_from_disk(&path).await.unwrap();
+        assert!(restored.is_dirty());
+        assert!(!restored.is_current(&tracks).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_load_rejects_previous_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bloom.bin");
+
+        let idx = SearchIndex::new();
+        idx.save_to_disk(&path).await.unwrap();
+        let mut bytes = tokio::fs::read(&path).await.unwrap();
+        bytes[..8].copy_from_slice(b"STBLOOM1");
+        tokio::fs::write(&path, &bytes).await.unwrap();
+
+        let err = idx.load_from_disk(&path).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    // ── maybe_resize ──────────────────────────────────────────────────
+
+    /// In-memory catalog standing in for the database.
+    #[derive(Default)]
+    struct MemoryTracks(std::sync::Mutex<Vec<TrackTerms>>);
+
+    #[async_trait]
+    impl TrackSource for MemoryTracks {
+        async fn track_count(&self) -> Result<u64, DbErr> {
+            Ok(self.0.lock().unwrap().len() as u64)
+        }
+
+        async fn track_page(&self, page: u64, page_size: u64) -> Result<Vec<TrackTerms>, DbErr> {
+            let tracks = self.0.lock().unwrap();
+            Ok(tracks
+                .iter()
+                .skip((page * page_size) as usize)
+                .take(page_size as usize)
+                .cloned()
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_capacity_for_bits() {
+        // ~9.6 bits per item at a 1% false positive rate
+        let bloom = Bloom::<String>::new_for_fp_rate(10_000, FALSE_POSITIVE_RATE);
+        let capacity = capacity_for_bits(bloom.number_of_bits(), FALSE_POSITIVE_RATE);
+        assert!((9_900..=10_100).contains(&capacity), "capacity {capacity}");
+    }
+
+    #[tokio::test]
+    async fn test_no_resize_without_track_source() {
+        let idx = SearchIndex::with_capacity(10);
+        for i in 0..20 {
+            idx.add_track_tokens(&format!("title{i}"), "artist", None)
+                .await;
+        }
+        assert!(!idx.maybe_resize().await);
+        assert_eq!(idx.capacity.load(Ordering::Acquire), 10);
+    }
+
+    #[tokio::test]
+    async fn test_resize_keeps_false_positive_rate() {
+        let idx = SearchIndex::with_capacity(1_000);
+        let tracks = Arc::new(MemoryTracks::default());
+        idx.set_track_source(tracks.clone());
+        let initial_bits = idx.export_local_bloom().await.bitmap_bits;
+
+        // Two distinct terms per track: 1,300 tracks outgrow 1,000 and then
+        // 2,000 items at the 0.7 load factor, but not 4,000
+        let mut resizes = 0;
+        let mut bits = initial_bits;
+        for i in 0..1_300 {
+            let (title, artist) = (format!("title{i}"), format!("artist{i}"));
+            tracks
+                .0
+                .lock()
+                .unwrap()
+                .push((title.clone(), artist.clone(), None));
+            idx.add_track_tokens(&title, &artist, None).await;
+
+            let now = idx.export_local_bloom().await.bitmap_bits;
+            if now != bits {
+                assert!(now > bits * 3 / 2, "resized from {bits} to {now} bits");
+                resizes += 1;
+                bits = now;
+            }
+        }
+        assert_eq!(resizes, 2);
+        assert_eq!(idx.capacity.load(Ordering::Acquire), 4_000);
+
+        // Every inserted term is still found
+        for i in 0..1_300 {
+            assert!(idx.local_might_match(&format!("title{i}")).await);
+            assert!(idx.local_might_match(&format!("artist{i}")).await);
+        }
+
+        let false_positives = {
+            let mut n = 0;
+            for i in 0..10_000 {
+                if idx.local_might_match(&format!("absent{i}")).await {
+                    n += 1;
+                }
+            }
+            n
+        };
+        assert!(
+            false_positives < 100,
+            "{false_positives} false positives in 10,000 lookups"
+        );
+    }
+
+    // ── bloom parameters ──────────────────────────────────────────────
+
+    #[test]
+    fn test_optimal_bloom_params() {
+        // 100k items at 1%: ~958k bits (~117 KiB) and 7 hash functions
+        let (bits, hashes) = optimal_bloom_params(100_000, 0.01);
+        assert!((958_000..=959_000).contains(&bits), "{bits} bits");
+        assert_eq!(hashes, 7);
+
+        // A lower target costs more bits and more hashes
+        let (strict_bits, strict_hashes) = optimal_bloom_params(100_000, 0.001);
+        assert!(strict_bits > bits);
+        assert_eq!(strict_hashes, 10);
+
+        assert_eq!(capacity_for_bits(bits, 0.01), 100_000);
+    }
+
+    #[tokio::test]
+    async fn test_fpr_estimate_tracks_fill() {
+        let idx = SearchIndex::with_params(1_000, 0.02);
+        assert_eq!(idx.fpr_estimate().await, 0.0);
+
+        for i in 0..500 {
+            idx.add_track_tokens(&format!("title{i}"), &format!("artist{i}"), None)
+                .await;
+        }
+        // Filled to its expected item count, the estimate is the target
+        let estimate = idx.fpr_estimate().await;
+        assert!((0.015..=0.025).contains(&estimate), "estimate {estimate}");
+    }
+
+    /// Insert `items` random terms into a filter sized for them, then count
+    /// hits among random terms that were never inserted.
+    fn measured_fpr(items: usize, fpr: f64, lookups: usize) -> f64 {
+        let mut bloom = new_bloom(items, fpr);
+        for _ in 0..items {
+            bloom.set(&format!("in-{:016x}", rand::random::<u64>()));
+        }
+        let hits = (0..lookups)
+            .filter(|_| bloom.check(&format!("out-{:016x}", rand::random::<u64>())))
+            .count();
+        hits as f64 / lookups as f64
+    }
+
+    #[test]
+    fn test_false_positive_rate_within_twice_target() {
+        // Random sizes and targets; a filter filled to its expected item
+        // count must stay within 2x of the target rate
+        for _ in 0..8 {
+            let items = 500 + (rand::random::<u64>() % 5_000) as usize;
+            let fpr = 0.005 + rand::random::<f64>() * 0.095;
+            // Enough lookups to expect ~200 false positives
+            let lookups = (200.0 / fpr) as usize;
+            let measured = measured_fpr(items, fpr, lookups);
+            assert!(
+                measured <= 2.0 * fpr,
+                "{items} items at target {fpr}: measured {measured}"
+            );
+        }
+    }
+
+    // ── automatic sizing ──────────────────────────────────────────────
+
+    /// Grow an index from the smallest size to `items` terms the way a
+    /// library grows, resizing from its catalog, and measure its false
+    /// positive rate on terms never inserted.
+    async fn measured_index_fpr(items: usize) -> f64 {
+        let idx = SearchIndex::with_capacity(MIN_BLOOM_CAPACITY);
+        let tracks = Arc::new(MemoryTracks::default());
+        idx.set_track_source(tracks.clone());
+        for i in 0..items / 2 {
+            let (title, artist) = (format!("title{i}"), format!("artist{i}"));
+            tracks
+                .0
+                .lock()
+                .unwrap()
+                .push((title.clone(), artist.clone(), None));
+            idx.add_track_tokens(&title, &artist, None).await;
+        }
+        assert_eq!(*idx.local_item_count.read().await, items as u64);
+
+        let lookups = 20_000;
+        let mut hits = 0;
+        for i in 0..lookups {
+            if idx.local_might_match(&format!("absent{i}")).await {
+                hits += 1;
+            }
+        }
+        hits as f64 / lookups as f64
+    }
+
+    #[tokio::test]
+    async fn test_grown_filter_fpr_under_target_10k() {
+        let measured = measured_index_fpr(10_000).await;
+        assert!(measured < FALSE_POSITIVE_RATE, "measured {measured}");
+    }
+
+    #[tokio::test]
+    async fn test_grown_filter_fpr_under_target_100k() {
+        let measured = measured_index_fpr(100_000).await;
+        assert!(measured < FALSE_POSITIVE_RATE, "measured {measured}");
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_sizes_small_catalog_down() {
+        let idx = SearchIndex::new();
+        let startup_bits = idx.export_local_bloom().await.bitmap_bits;
+
+        let tracks: Vec<TrackTerms> = (0..10)
+            .map(|i| (format!("title{i}"), "artist".to_string(), None))
+            .collect();
+        idx.rebuild_from_tracks(&tracks).await;
+
+        let bits = idx.export_local_bloom().await.bitmap_bits;
+        assert!(bits < startup_bits / 10, "{bits} bits");
+        assert_eq!(
+            bits,
+            optimal_bloom_params(MIN_BLOOM_CAPACITY, FALSE_POSITIVE_RATE).0
+        );
+        assert!(idx.local_might_match("title7").await);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_grows_past_estimate() {
+        // Far more terms per track than estimated
+        let tracks = MemoryTracks::default();
+        for i in 0..500 {
+            let title: Vec<String> = (0..20).map(|w| format!("word{i}x{w}")).collect();
+            tracks
+                .0
+                .lock()
+                .unwrap()
+                .push((title.join(" "), "artist".into(), None));
+        }
+
+        let idx = SearchIndex::new();
+        idx.rebuild_from_source(&tracks, None).await.unwrap();
+
+        let count = *idx.local_item_count.read().await;
+        let capacity = idx.capacity.load(Ordering::Acquire);
+        assert_eq!(count, 500 * 21);
+        assert!(capacity > capacity_for_items(500 * ESTIMATED_TERMS_PER_TRACK));
+        assert!(!exceeds_load_factor(count, capacity));
+        assert!(idx.local_might_match("word499x19").await);
+    }
+
+    // ── export_delta / apply_delta ────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_applied_deltas_match_full_filter() {
+        let sender = SearchIndex::new();
+        sender
+            .insert_track("Blue in Green", "Miles Davis", None)
+            .await;
+        assert!(sender.export_delta().await.is_none());
+
+        // First contact: one peer gets the full filter, then only deltas
+        let by_delta = SearchIndex::new();
+        by_delta
+            .import_peer_bloom("sender", sender.begin_generation().await)
+            .await;
+
+        for (title, artist) in [
+            ("So What", "Miles Davis"),
+            ("Naima", "John Coltrane"),
+            ("Take Five", "Dave Brubeck"),
+        ] {
+            sender.insert_track(title, artist, None).await;
+            let delta = sender.export_delta().await.unwrap();
+            assert!(!delta.set_bits.is_empty());
+            assert!(by_delta.apply_delta("sender", &delta).await);
+        }
+
+        // Another peer gets the full filter as it is now
+        let by_full = SearchIndex::new();
+        by_full
+            .import_peer_bloom("sender", sender.export_local_bloom().await)
+            .await;
+
+        let indexes_a = by_delta.peer_indexes.read().await;
+        let indexes_b = by_full.peer_indexes.read().await;
+        let (a, b) = (&indexes_a["sender"].bloom, &indexes_b["sender"].bloom);
+        assert_eq!(a.bitmap, b.bitmap);
+        assert_eq!(a.item_count, b.item_count);
+        drop((indexes_a, indexes_b));
+        assert!(by_delta.peer_might_match("sender", "coltrane").await);
+    }
+
+    #[tokio::test]
+    async fn test_delta_is_empty_without_new_terms() {
+        let idx = SearchIndex::new();
+        idx.insert_track("Track", "Artist", None).await;
+        idx.begin_generation().await;
+        assert!(idx.export_delta().await.unwrap().set_bits.is_empty());
+
+        // Terms already in the filter set no new bits
+        idx.insert_track("Track", "Artist", None).await;
+        assert!(idx.export_delta().await.unwrap().set_bits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_requires_full_filter() {
+        let idx = SearchIndex::new();
+        let first = idx.begin_generation().await;
+        idx.insert_track("Track", "Artist", None).await;
+        assert!(idx.export_delta().await.is_some());
+
+        idx.rebuild_from_tracks(&[("Track".into(), "Artist".into(), None)])
+            .await;
+        assert!(idx.export_delta().await.is_none());
+
+        let second = idx.begin_generation().await;
+        assert_eq!(second.generation, first.generation + 1);
+        assert!(idx.export_delta().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_apply_delta_from_other_generation_is_refused() {
+        let sender = SearchIndex::new();
+        let receiver = SearchIndex::new();
+        let old = sender.begin_generation().await;
+        receiver.import_peer_bloom("sender", old.clone()).await;
+
+        // The sender started a new generation the receiver never saw
+        sender.begin_generation().await;
+        sender.insert_track("Track", "Artist", None).await;
+        let delta = sender.export_delta().await.unwrap();
+        assert!(!receiver.apply_delta("sender", &delta).await);
+        assert!(!receiver.apply_delta("unknown", &delta).await);
+
+        // Out-of-range offsets are refused too
+        let bad = BloomDeltaData {
+            generation: old.generation,
+            bitmap_bits: old.bitmap_bits,
+            set_bits: vec![old.bitmap.len() as u64 * 8],
+            item_count: 1,
+        };
+        assert!(!receiver.apply_delta("sender", &bad).await);
+        let indexes = receiver.peer_indexes.read().await;
+        assert_eq!(indexes["sender"].bloom.bitmap, old.bitmap);
+    }
+
+    #[test]
+    fn test_new_bits() {
+        assert_eq!(
+            new_bits(&[0b0000_0001, 0], &[0b1000_0011, 0b0000_0100]),
+            [1, 7, 10]
+        );
+        assert!(new_bits(&[0xFF], &[0xFF]).is_empty());
+    }
 }
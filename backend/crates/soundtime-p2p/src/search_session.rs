@@ -0,0 +1,508 @@
+//! Paged distributed search.
+//!
+//! A network search asks every matching peer for a batch of
+//! [`PEER_BATCH_SIZE`] results and keeps the merged, ranked and deduplicated
+//! list in a [`SearchSession`]. Later pages are served from the session; once
+//! they run past it, peers that reported more matches than they sent are
+//! asked for their next batch (`SearchQuery::offset`). Results of a later
+//! batch are appended after the earlier ones and skip hashes already listed,
+//! so a track never shows up on two pages.
+//!
+//! Sessions live in a [`SearchSessionCache`] for [`SEARCH_SESSION_TTL`] and
+//! are addressed by an opaque cursor that also carries the page offset.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use data_encoding::BASE64URL_NOPAD;
+
+use crate::node::{rank_search_results, SearchResultItem};
+
+/// How long a search session can be paged through after its last use.
+pub const SEARCH_SESSION_TTL: Duration = Duration::from_secs(5 * 60);
+/// Results asked from each peer per round.
+pub const PEER_BATCH_SIZE: u32 = 50;
+/// Most sessions cached at once; the least recently used go first.
+const MAX_SEARCH_SESSIONS: usize = 256;
+/// Most rounds of peer queries one page request may trigger.
+const MAX_FETCH_ROUNDS: usize = 4;
+
+/// Runs a search query on one peer.
+#[async_trait]
+pub trait PeerSearcher: Send + Sync {
+    /// Results `offset..offset + limit` of `query` on `peer`, with the
+    /// peer's total number of matches. `None` if the peer did not answer.
+    async fn search_peer_page(
+        &self,
+        peer: &str,
+        query: &str,
+        offset: u32,
+        limit: u32,
+    ) -> Option<(Vec<SearchResultItem>, u64)>;
+}
+
+/// How far a session got through one peer's matches.
+#[derive(Clone, Debug)]
+struct PeerCursor {
+    peer: String,
+    /// Results received so far, i.e. the offset of the next batch
+    offset: u32,
+    /// Matches the peer reported; `None` until it answered once
+    total: Option<u64>,
+    /// The peer failed to answer; it is not asked again
+    failed: bool,
+}
+
+impl PeerCursor {
+    fn has_more(&self) -> bool {
+        !self.failed && self.total.is_none_or(|t| (self.offset as u64) < t)
+    }
+}
+
+/// One page of a paged search.
+#[derive(Clone, Debug)]
+pub struct SearchPage {
+    pub results: Vec<SearchResultItem>,
+    /// Whether a later page may hold more results
+    pub has_more: bool,
+    /// Cursor for the next page, if there is one
+    pub next_cursor: Option<String>,
+    /// Matches each answering peer reported, keyed by node ID
+    pub peer_totals: HashMap<String, u64>,
+}
+
+/// The merged results of one network search, fetched from peers as pages
+/// are requested.
+pub struct SearchSession {
+    query: String,
+    results: Vec<SearchResultItem>,
+    seen: HashSet<String>,
+    peers: Vec<PeerCursor>,
+}
+
+impl SearchSession {
+    /// A session for `query` on `peers`, asked in this order.
+    pub fn new(query: &str, peers: Vec<String>) -> Self {
+        Self {
+            query: query.to_string(),
+            results: Vec::new(),
+            seen: HashSet::new(),
+            peers: peers
+                .into_iter()
+                .map(|peer| PeerCursor {
+                    peer,
+                    offset: 0,
+                    total: None,
+                    failed: false,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    fn peers_have_more(&self) -> bool {
+        self.peers.iter().any(PeerCursor::has_more)
+    }
+
+    /// Ask every peer with more matches for its next batch and append the
+    /// new results, ranked among themselves.
+    async fn fetch_round(&mut self, searcher: &Arc<dyn PeerSearcher>) {
+        let mut join_set = tokio::task::JoinSet::new();
+        for (i, cursor) in self.peers.iter().enumerate() {
+            if !cursor.has_more() {
+                continue;
+            }
+            let searcher = Arc::clone(searcher);
+            let (peer, query, offset) = (cursor.peer.clone(), self.query.clone(), cursor.offset);
+            join_set.spawn(async move {
+                let page = searcher
+                    .search_peer_page(&peer, &query, offset, PEER_BATCH_SIZE)
+                    .await;
+                (i, page)
+            });
+        }
+
+        let mut pages = Vec::new();
+        while let Some(joined) = join_set.join_next().await {
+            if let Ok(page) = joined {
+                pages.push(page);
+            }
+        }
+        // Merge in peer order, so ties rank the same way every time
+        pages.sort_by_key(|(i, _)| *i);
+
+        let mut batch = Vec::new();
+        for (i, page) in pages {
+            let cursor = &mut self.peers[i];
+            match page {
+                Some((results, total)) => {
+                    cursor.offset += results.len() as u32;
+                    // A peer that sent nothing has nothing more to send
+                    cursor.total = Some(if results.is_empty() {
+                        cursor.offset as u64
+                    } else {
+                        total
+                    });
+                    batch.extend(results);
+                }
+                None => cursor.failed = true,
+            }
+        }
+        self.merge(batch);
+    }
+
+    /// Append a batch of results, best first, leaving out hashes already
+    /// listed.
+    fn merge(&mut self, mut batch: Vec<SearchResultItem>) {
+        rank_search_results(&mut batch);
+        batch.retain(|r| self.seen.insert(r.hash.clone()));
+        self.results.extend(batch);
+    }
+
+    /// Fetch from peers until the session holds `want` results or no peer
+    /// has more.
+    pub async fn fill(&mut self, searcher: &Arc<dyn PeerSearcher>, want: usize) {
+        for _ in 0..MAX_FETCH_ROUNDS {
+            if self.results.len() >= want || !self.peers_have_more() {
+                break;
+            }
+            self.fetch_round(searcher).await;
+        }
+    }
+
+    /// Results `offset..offset + limit` of what was fetched so far, and
+    /// whether more may follow.
+    pub fn page(&self, offset: usize, limit: usize) -> (Vec<SearchResultItem>, bool) {
+        let start = offset.min(self.results.len());
+        let end = offset.saturating_add(limit).min(self.results.len());
+        let has_more = end < self.results.len() || self.peers_have_more();
+        (self.results[start..end].to_vec(), has_more)
+    }
+
+    /// Matches each peer reported so far.
+    pub fn peer_totals(&self) -> HashMap<String, u64> {
+        self.peers
+            .iter()
+            .filter_map(|c| c.total.map(|t| (c.peer.clone(), t)))
+            .collect()
+    }
+}
+
+/// Encode a session ID and page offset as an opaque cursor.
+pub fn encode_cursor(session_id: &str, offset: usize) -> String {
+    BASE64URL_NOPAD.encode(format!("{session_id}:{offset}").as_bytes())
+}
+
+/// Decode a cursor made by [`encode_cursor`].
+pub fn decode_cursor(cursor: &str) -> Option<(String, usize)> {
+    let bytes = BASE64URL_NOPAD.decode(cursor.as_bytes()).ok()?;
+    let text = String::from_utf8(bytes).ok()?;
+    let (session_id, offset) = text.rsplit_once(':')?;
+    Some((session_id.to_string(), offset.parse().ok()?))
+}
+
+struct CachedSession {
+    session: Arc<tokio::sync::Mutex<SearchSession>>,
+    last_used: Instant,
+}
+
+/// Search sessions by ID, dropped [`SEARCH_SESSION_TTL`] after their last use.
+#[derive(Default)]
+pub struct SearchSessionCache {
+    sessions: Mutex<HashMap<String, CachedSession>>,
+}
+
+impl SearchSessionCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cache a new session. Returns its ID and a handle to it.
+    pub fn insert(
+        &self,
+        session: SearchSession,
+    ) -> (String, Arc<tokio::sync::Mutex<SearchSession>>) {
+        self.insert_at(session, Instant::now())
+    }
+
+    fn insert_at(
+        &self,
+        session: SearchSession,
+        now: Instant,
+    ) -> (String, Arc<tokio::sync::Mutex<SearchSession>>) {
+        let id = uuid::Uuid::new_v4().to_string();
+        let session = Arc::new(tokio::sync::Mutex::new(session));
+        let mut sessions = self.sessions.lock().expect("search session lock poisoned");
+        sessions.retain(|_, s| now.duration_since(s.last_used) < SEARCH_SESSION_TTL);
+        while sessions.len() >= MAX_SEARCH_SESSIONS {
+            let Some(oldest) = sessions
+                .iter()
+                .min_by_key(|(_, s)| s.last_used)
+                .map(|(id, _)| id.clone())
+            else {
+                break;
+            };
+            sessions.remove(&oldest);
+        }
+        sessions.insert(
+            id.clone(),
+            CachedSession {
+                session: Arc::clone(&session),
+                last_used: now,
+            },
+        );
+        (id, session)
+    }
+
+    /// The session with this ID, unless it expired.
+    pub fn get(&self, id: &str) -> Option<Arc<tokio::sync::Mutex<SearchSession>>> {
+        self.get_at(id, Instant::now())
+    }
+
+    fn get_at(&self, id: &str, now: Instant) -> Option<Arc<tokio::sync::Mutex<SearchSession>>> {
+        let mut sessions = self.sessions.lock().expect("search session lock poisoned");
+        let cached = sessions.get_mut(id)?;
+        if now.duration_since(cached.last_used) >= SEARCH_SESSION_TTL {
+            sessions.remove(id);
+            return None;
+        }
+        cached.last_used = now;
+        Some(Arc::clone(&cached.session))
+    }
+
+    /// Number of cached sessions, expired ones included.
+    pub fn len(&self) -> usize {
+        self.sessions
+            .lock()
+            .expect("search session lock poisoned")
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn item(hash: &str, peer: &str, relevance: f32) -> SearchResultItem {
+        SearchResultItem {
+            hash: hash.into(),
+            title: format!("Track {hash}"),
+            artist_name: "Artist".into(),
+            album_title: None,
+            duration_secs: 180.0,
+            format: "flac".into(),
+            genre: None,
+            year: None,
+            bitrate: None,
+            source_node: peer.into(),
+            musicbrainz_id: None,
+            relevance,
+            play_count: 0,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+        }
+    }
+
+    /// Peers answering from fixed result lists, ordered best first.
+    struct MockPeers {
+        results: HashMap<String, Vec<SearchResultItem>>,
+        calls: AtomicUsize,
+    }
+
+    impl MockPeers {
+        fn new(peers: &[(&str, Vec<SearchResultItem>)]) -> Arc<Self> {
+            Arc::new(Self {
+                results: peers
+                    .iter()
+                    .map(|(p, r)| (p.to_string(), r.clone()))
+                    .collect(),
+                calls: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl PeerSearcher for MockPeers {
+        async fn search_peer_page(
+            &self,
+            peer: &str,
+            _query: &str,
+            offset: u32,
+            limit: u32,
+        ) -> Option<(Vec<SearchResultItem>, u64)> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            let all = self.results.get(peer)?;
+            let page = all
+                .iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .cloned()
+                .collect();
+            Some((page, all.len() as u64))
+        }
+    }
+
+    /// Two peers with 80 matches each, of which hashes 40..80 are on both.
+    fn overlapping_peers() -> Arc<MockPeers> {
+        let a = (0..80)
+            .map(|i| item(&format!("h{i:03}"), "peer-a", 1.0 - i as f32 / 200.0))
+            .collect();
+        let b = (40..120)
+            .map(|i| item(&format!("h{i:03}"), "peer-b", 0.9 - i as f32 / 200.0))
+            .collect();
+        MockPeers::new(&[("peer-a", a), ("peer-b", b)])
+    }
+
+    async fn page_through(
+        searcher: &Arc<dyn PeerSearcher>,
+        session: &mut SearchSession,
+        limit: usize,
+    ) -> Vec<Vec<String>> {
+        let mut pages = Vec::new();
+        let mut offset = 0;
+        loop {
+            session.fill(searcher, offset + limit).await;
+            let (results, has_more) = session.page(offset, limit);
+            offset += results.len();
+            pages.push(results.into_iter().map(|r| r.hash).collect());
+            if !has_more {
+                return pages;
+            }
+        }
+    }
+
+    // ── paging ──
+
+    #[tokio::test]
+    async fn test_paging_deduplicates_across_pages() {
+        let mock = overlapping_peers();
+        let searcher: Arc<dyn PeerSearcher> = mock.clone();
+        let mut session = SearchSession::new("q", vec!["peer-a".into(), "peer-b".into()]);
+
+        let pages = page_through(&searcher, &mut session, 30).await;
+        let all: Vec<String> = pages.concat();
+        let unique: HashSet<&String> = all.iter().collect();
+        // 120 distinct hashes, each listed exactly once
+        assert_eq!(all.len(), 120);
+        assert_eq!(unique.len(), 120);
+        assert_eq!(pages.len(), 4);
+        // Two rounds of two peers: 50 + 30 results each
+        assert_eq!(mock.calls.load(Ordering::Relaxed), 4);
+        assert_eq!(session.peer_totals()["peer-a"], 80);
+        assert_eq!(session.peer_totals()["peer-b"], 80);
+    }
+
+    #[tokio::test]
+    async fn test_paging_is_stable() {
+        let searcher: Arc<dyn PeerSearcher> = overlapping_peers();
+        let mut first = SearchSession::new("q", vec!["peer-a".into(), "peer-b".into()]);
+        let mut second = SearchSession::new("q", vec!["peer-a".into(), "peer-b".into()]);
+        assert_eq!(
+            page_through(&searcher, &mut first, 25).await,
+            page_through(&searcher, &mut second, 25).await
+        );
+
+        // Asking for an earlier page again returns the same results
+        let (again, _) = first.page(25, 25);
+        let (once_more, _) = first.page(25, 25);
+        let hashes = |r: Vec<SearchResultItem>| r.into_iter().map(|r| r.hash).collect::<Vec<_>>();
+        assert_eq!(hashes(again), hashes(once_more));
+    }
+
+    #[tokio::test]
+    async fn test_first_page_keeps_best_result_per_hash() {
+        let searcher: Arc<dyn PeerSearcher> = overlapping_peers();
+        let mut session = SearchSession::new("q", vec!["peer-a".into(), "peer-b".into()]);
+        session.fill(&searcher, 10).await;
+        let (results, has_more) = session.page(0, 10);
+        assert!(has_more);
+        assert_eq!(results[0].hash, "h000");
+        // Shared hashes come from the peer ranking them higher
+        let shared = session.results.iter().find(|r| r.hash == "h045").unwrap();
+        assert_eq!(shared.source_node, "peer-a");
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_peer_is_not_asked_again() {
+        let mock = MockPeers::new(&[("peer-a", vec![item("h1", "peer-a", 1.0)])]);
+        let searcher: Arc<dyn PeerSearcher> = mock.clone();
+        let mut session = SearchSession::new("q", vec!["peer-a".into(), "gone".into()]);
+        session.fill(&searcher, 10).await;
+        let (results, has_more) = session.page(0, 10);
+        assert_eq!(results.len(), 1);
+        assert!(!has_more);
+        assert_eq!(mock.calls.load(Ordering::Relaxed), 2);
+        assert!(!session.peer_totals().contains_key("gone"));
+    }
+
+    #[tokio::test]
+    async fn test_page_past_the_end_is_empty() {
+        let searcher: Arc<dyn PeerSearcher> =
+            MockPeers::new(&[("peer-a", vec![item("h1", "peer-a", 1.0)])]);
+        let mut session = SearchSession::new("q", vec!["peer-a".into()]);
+        session.fill(&searcher, 100).await;
+        let (results, has_more) = session.page(50, 10);
+        assert!(results.is_empty());
+        assert!(!has_more);
+    }
+
+    // ── cursors ──
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = encode_cursor("3f2a-session", 40);
+        assert_eq!(decode_cursor(&cursor), Some(("3f2a-session".into(), 40)));
+        assert_eq!(decode_cursor("not a cursor!"), None);
+        assert_eq!(decode_cursor(&BASE64URL_NOPAD.encode(b"no-offset")), None);
+    }
+
+    // ── cache ──
+
+    #[test]
+    fn test_cache_expires_sessions() {
+        let cache = SearchSessionCache::new();
+        let start = Instant::now();
+        let (id, _) = cache.insert_at(SearchSession::new("q", vec![]), start);
+        assert!(cache.get_at(&id, start + Duration::from_secs(60)).is_some());
+        // Each use extends the session's life
+        assert!(cache
+            .get_at(
+                &id,
+                start + Duration::from_secs(60) + SEARCH_SESSION_TTL / 2
+            )
+            .is_some());
+        assert!(cache
+            .get_at(
+                &id,
+                start + Duration::from_secs(61) + SEARCH_SESSION_TTL * 2
+            )
+            .is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_cache_is_bounded() {
+        let cache = SearchSessionCache::new();
+        let start = Instant::now();
+        let (first, _) = cache.insert_at(SearchSession::new("q", vec![]), start);
+        for i in 1..=MAX_SEARCH_SESSIONS {
+            cache.insert_at(
+                SearchSession::new("q", vec![]),
+                start + Duration::from_millis(i as u64),
+            );
+        }
+        assert_eq!(cache.len(), MAX_SEARCH_SESSIONS);
+        assert!(cache.get_at(&first, start).is_none());
+    }
+}
@@ -0,0 +1,139 @@
+//! Seed peers stored in the database.
+//!
+//! `P2P_SEED_PEERS` fixes the seed list at deploy time; admins can add more
+//! at runtime through the API, stored in `p2p_seed_peers`. Both lists are
+//! used: env seeds come first, and a database seed naming the same node as
+//! an env seed is ignored so the env entry (with its direct addresses)
+//! wins. The node connects to every seed at startup and retries offline
+//! database seeds on each 5-minute refresh cycle.
+
+use std::collections::HashMap;
+
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set};
+use soundtime_db::entities::p2p_seed_peer;
+use uuid::Uuid;
+
+use crate::error::P2pError;
+
+/// The endpoint ID of a seed entry (`<endpoint_id>[@<ip:port>,...]`).
+pub fn seed_node_id(entry: &str) -> &str {
+    entry.split('@').next().unwrap_or(entry).trim()
+}
+
+/// Combine env and database seeds, env first. Database seeds whose node is
+/// already listed in the env are dropped, as are duplicates.
+pub fn merge_seeds(env: &[String], db: &[p2p_seed_peer::Model]) -> Vec<String> {
+    let mut merged: Vec<String> = env.to_vec();
+    for seed in db {
+        let id = seed.node_id.trim();
+        if !merged.iter().any(|e| seed_node_id(e) == id) {
+            merged.push(id.to_string());
+        }
+    }
+    merged
+}
+
+/// Every stored seed, oldest first.
+pub async fn list(db: &DatabaseConnection) -> Result<Vec<p2p_seed_peer::Model>, P2pError> {
+    Ok(p2p_seed_peer::Entity::find()
+        .order_by_asc(p2p_seed_peer::Column::AddedAt)
+        .all(db)
+        .await?)
+}
+
+/// Labels of the stored seeds that have one, keyed by node ID.
+pub async fn labels(db: &DatabaseConnection) -> Result<HashMap<String, String>, P2pError> {
+    Ok(list(db)
+        .await?
+        .into_iter()
+        .filter_map(|s| s.label.map(|label| (s.node_id, label)))
+        .collect())
+}
+
+/// Store a seed. Returns `None` if `node_id` is already a stored seed.
+pub async fn add(
+    db: &DatabaseConnection,
+    node_id: &str,
+    label: Option<String>,
+    added_by: Option<Uuid>,
+) -> Result<Option<p2p_seed_peer::Model>, P2pError> {
+    let existing = p2p_seed_peer::Entity::find()
+        .filter(p2p_seed_peer::Column::NodeId.eq(node_id))
+        .one(db)
+        .await?;
+    if existing.is_some() {
+        return Ok(None);
+    }
+    let model = p2p_seed_peer::Model {
+        id: Uuid::new_v4(),
+        node_id: node_id.to_string(),
+        label,
+        added_at: chrono::Utc::now().into(),
+        added_by,
+    };
+    p2p_seed_peer::Entity::insert(p2p_seed_peer::ActiveModel {
+        id: Set(model.id),
+        node_id: Set(model.node_id.clone()),
+        label: Set(model.label.clone()),
+        added_at: Set(model.added_at),
+        added_by: Set(model.added_by),
+    })
+    .exec(db)
+    .await?;
+    Ok(Some(model))
+}
+
+/// Remove the seed for `node_id`. Returns `false` if there was none.
+pub async fn remove(db: &DatabaseConnection, node_id: &str) -> Result<bool, P2pError> {
+    let res = p2p_seed_peer::Entity::delete_many()
+        .filter(p2p_seed_peer::Column::NodeId.eq(node_id))
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed(node_id: &str) -> p2p_seed_peer::Model {
+        p2p_seed_peer::Model {
+            id: Uuid::new_v4(),
+            node_id: node_id.into(),
+            label: None,
+            added_at: chrono::Utc::now().into(),
+            added_by: None,
+        }
+    }
+
+    // ── seed_node_id ──
+
+    #[test]
+    fn test_seed_node_id_strips_addrs() {
+        assert_eq!(seed_node_id("abc"), "abc");
+        assert_eq!(seed_node_id(" abc @10.0.0.1:4433"), "abc");
+        assert_eq!(seed_node_id("abc@10.0.0.1:4433,10.0.0.2:4433"), "abc");
+    }
+
+    // ── merge_seeds ──
+
+    #[test]
+    fn test_merge_env_first() {
+        let env = vec!["env1".to_string()];
+        let merged = merge_seeds(&env, &[seed("db1"), seed("db2")]);
+        assert_eq!(merged, ["env1", "db1", "db2"]);
+    }
+
+    #[test]
+    fn test_merge_env_wins_over_db() {
+        let env = vec!["same@10.0.0.1:4433".to_string()];
+        let merged = merge_seeds(&env, &[seed("same"), seed("other")]);
+        assert_eq!(merged, ["same@10.0.0.1:4433", "other"]);
+    }
+
+    #[test]
+    fn test_merge_empty() {
+        assert!(merge_seeds(&[], &[]).is_empty());
+        assert_eq!(merge_seeds(&[], &[seed("db1"), seed("db1")]), ["db1"]);
+    }
+}
@@ -0,0 +1,253 @@
+//! Per-node P2P traffic statistics.
+//!
+//! Unlike the process-wide Prometheus metrics in [`crate::metrics`], these
+//! counters belong to a single [`P2pNode`](crate::P2pNode) and break traffic
+//! down by message type. [`P2pStatsCollector::snapshot`] produces a
+//! serializable [`P2pStats`] for the admin API; `P2pNode::stats` adds the
+//! outbound connection pool counters.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+/// Every `P2pMessage` variant name, in declaration order.
+///
+/// New variants must be added here, otherwise their traffic is not counted.
+pub const MESSAGE_KINDS: [&str; 30] = [
+    "FetchTrack",
+    "FetchTrackRange",
+    "AnnounceTrack",
+    "TrackData",
+    "Ping",
+    "Pong",
+    "PeerExchange",
+    "CatalogSync",
+    "CatalogDelta",
+    "RequestCatalog",
+    "BloomExchange",
+    "SearchQuery",
+    "SearchResults",
+    "UpdateTrackMetadata",
+    "CatalogSyncPage",
+    "CatalogSyncAck",
+    "KeepAlive",
+    "Goodbye",
+    "BlockHash",
+    "PlayCountUpdate",
+    "PopularityGossip",
+    "BloomDelta",
+    "RequestBloom",
+    "CatalogChecksumRequest",
+    "CatalogChecksumResponse",
+    "AuthorizeTrackAccess",
+    "AccessDenied",
+    "CheckDuplicate",
+    "DuplicateFound",
+    "DeleteTrack",
+];
+
+/// Sent/received counts for one message type.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageStats {
+    /// `P2pMessage` variant name
+    pub kind: String,
+    pub sent: u64,
+    pub received: u64,
+}
+
+/// Point-in-time snapshot of a node's P2P traffic.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct P2pStats {
+    /// Per-message-type counts, in `P2pMessage` declaration order
+    pub messages: Vec<MessageStats>,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    /// Track blob bytes served to peers
+    pub blob_bytes_uploaded: u64,
+    /// Track blob bytes fetched from peers
+    pub blob_bytes_downloaded: u64,
+    /// Incoming connections currently being served
+    pub active_connections: u64,
+    /// Outbound connection pool
+    #[serde(default)]
+    pub pool: ConnectionPoolStats,
+    /// Cache of distributed search results
+    #[serde(default)]
+    pub search_cache: SearchCacheStats,
+}
+
+/// Counters of the outbound QUIC connection pool.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionPoolStats {
+    /// Cached connections to peers
+    pub open_connections: u64,
+    /// Connections dropped as dead, idle past the TTL or to make room
+    pub evictions: u64,
+    /// Keepalive probes that found a connection dead
+    pub failed_probes: u64,
+}
+
+/// Counters of the distributed search result cache.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchCacheStats {
+    /// Searches answered from the cache
+    pub hits: u64,
+    /// Searches that had to ask peers
+    pub misses: u64,
+    /// Queries currently cached
+    pub entries: u64,
+}
+
+/// Atomic counters updated from the node's send and receive paths.
+#[derive(Default)]
+pub struct P2pStatsCollector {
+    sent: [AtomicU64; MESSAGE_KINDS.len()],
+    received: [AtomicU64; MESSAGE_KINDS.len()],
+    blob_bytes_uploaded: AtomicU64,
+    blob_bytes_downloaded: AtomicU64,
+    active_connections: Arc<AtomicUsize>,
+}
+
+impl P2pStatsCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn index(kind: &str) -> Option<usize> {
+        let idx = MESSAGE_KINDS.iter().position(|k| *k == kind);
+        debug_assert!(idx.is_some(), "unknown P2P message kind: {kind}");
+        idx
+    }
+
+    /// Count a message of the given kind written to a peer.
+    pub fn record_sent(&self, kind: &str) {
+        if let Some(i) = Self::index(kind) {
+            self.sent[i].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Count a message of the given kind read from a peer.
+    pub fn record_received(&self, kind: &str) {
+        if let Some(i) = Self::index(kind) {
+            self.received[i].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_blob_uploaded(&self, bytes: u64) {
+        self.blob_bytes_uploaded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_blob_downloaded(&self, bytes: u64) {
+        self.blob_bytes_downloaded
+            .fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Mark an incoming connection as active until the returned guard is dropped.
+    pub fn connection_opened(&self) -> ConnectionGuard {
+        self.active_connections.fetch_add(1, Ordering::Relaxed);
+        ConnectionGuard {
+            active: Arc::clone(&self.active_connections),
+        }
+    }
+
+    /// Read all counters into a serializable snapshot.
+    pub fn snapshot(&self) -> P2pStats {
+        let messages: Vec<MessageStats> = MESSAGE_KINDS
+            .iter()
+            .enumerate()
+            .map(|(i, kind)| MessageStats {
+                kind: (*kind).to_string(),
+                sent: self.sent[i].load(Ordering::Relaxed),
+                received: self.received[i].load(Ordering::Relaxed),
+            })
+            .collect();
+
+        P2pStats {
+            messages_sent: messages.iter().map(|m| m.sent).sum(),
+            messages_received: messages.iter().map(|m| m.received).sum(),
+            messages,
+            blob_bytes_uploaded: self.blob_bytes_uploaded.load(Ordering::Relaxed),
+            blob_bytes_downloaded: self.blob_bytes_downloaded.load(Ordering::Relaxed),
+            active_connections: self.active_connections.load(Ordering::Relaxed) as u64,
+            pool: ConnectionPoolStats::default(),
+            search_cache: SearchCacheStats::default(),
+        }
+    }
+}
+
+/// Decrements the active connection count when dropped.
+pub struct ConnectionGuard {
+    active: Arc<AtomicUsize>,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind_stats<'a>(stats: &'a P2pStats, kind: &str) -> &'a MessageStats {
+        stats.messages.iter().find(|m| m.kind == kind).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_starts_empty() {
+        let stats = P2pStatsCollector::new().snapshot();
+        assert_eq!(stats.messages.len(), MESSAGE_KINDS.len());
+        assert_eq!(stats.messages_sent, 0);
+        assert_eq!(stats.messages_received, 0);
+        assert_eq!(stats.active_connections, 0);
+    }
+
+    #[test]
+    fn test_simulated_exchange_moves_counters() {
+        let collector = P2pStatsCollector::new();
+
+        // Ping/Pong round trip, then a track fetch served to the peer
+        collector.record_sent("Ping");
+        collector.record_received("Pong");
+        collector.record_received("FetchTrack");
+        collector.record_blob_uploaded(4096);
+        collector.record_sent("FetchTrack");
+        collector.record_blob_downloaded(1024);
+
+        let stats = collector.snapshot();
+        assert_eq!(kind_stats(&stats, "Ping").sent, 1);
+        assert_eq!(kind_stats(&stats, "Pong").received, 1);
+        assert_eq!(kind_stats(&stats, "FetchTrack").sent, 1);
+        assert_eq!(kind_stats(&stats, "FetchTrack").received, 1);
+        assert_eq!(kind_stats(&stats, "CatalogSync").sent, 0);
+        assert_eq!(stats.messages_sent, 2);
+        assert_eq!(stats.messages_received, 2);
+        assert_eq!(stats.blob_bytes_uploaded, 4096);
+        assert_eq!(stats.blob_bytes_downloaded, 1024);
+    }
+
+    #[test]
+    fn test_connection_guard() {
+        let collector = P2pStatsCollector::new();
+        let a = collector.connection_opened();
+        let b = collector.connection_opened();
+        assert_eq!(collector.snapshot().active_connections, 2);
+        drop(a);
+        assert_eq!(collector.snapshot().active_connections, 1);
+        drop(b);
+        assert_eq!(collector.snapshot().active_connections, 0);
+    }
+
+    #[test]
+    fn test_stats_serialization() {
+        let collector = P2pStatsCollector::new();
+        collector.record_sent("BloomExchange");
+        let val = serde_json::to_value(collector.snapshot()).unwrap();
+        assert_eq!(val["messages_sent"], 1);
+        assert_eq!(val["messages"][10]["kind"], "BloomExchange");
+        assert_eq!(val["messages"][10]["sent"], 1);
+        assert_eq!(val["pool"]["open_connections"], 0);
+    }
+}
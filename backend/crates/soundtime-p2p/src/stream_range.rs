@@ -0,0 +1,339 @@
+//! Byte ranges of track blobs for HTTP range requests.
+//!
+//! `/api/tracks/{id}/stream` answers `Range: bytes=start-end` for P2P tracks
+//! with `206 Partial Content`. A blob already in the local store is read
+//! from `start` without loading the rest of the file; a remote one is asked
+//! for with `P2pMessage::FetchTrackRange` so playback can begin before the
+//! whole blob has arrived (see `P2pNode::get_or_fetch_track_range`).
+//!
+//! Ranges for players are fetched verified: the peer answers with a BAO
+//! slice (BLAKE3 verified streaming, see [`export_verified_range`]) of the
+//! range and the blob's last chunk, which [`import_verified_range`] checks
+//! against the content hash before any byte is served. The last chunk's
+//! proof also verifies the blob size used in `Content-Range`.
+
+use bytes::Bytes;
+use iroh_blobs::api::blobs::BlobStatus;
+use iroh_blobs::protocol::{ChunkRanges, ChunkRangesExt};
+use iroh_blobs::store::fs::FsStore;
+use iroh_blobs::Hash;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::error::P2pError;
+
+/// Largest range returned for a single request. Open-ended ranges
+/// (`bytes=0-`) are cut to this length; players request the rest as they go.
+pub const MAX_STREAM_RANGE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Part of a track blob served to a player.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrackRange {
+    pub data: Bytes,
+    /// Offset of `data` within the blob
+    pub start: u64,
+    /// Size of the whole blob
+    pub total_size: u64,
+}
+
+impl TrackRange {
+    /// Offset of the last byte of `data`, as used in `Content-Range`.
+    /// `None` when the range is empty (not satisfiable).
+    pub fn end(&self) -> Option<u64> {
+        (!self.data.is_empty()).then(|| self.start + self.data.len() as u64 - 1)
+    }
+}
+
+/// Clamp the requested range `start..=end` to a blob of `total` bytes and at
+/// most `max_len` bytes. Returns the inclusive `(start, end)` to serve, or
+/// `None` if the range is not satisfiable.
+pub fn clamp_range(start: u64, end: Option<u64>, total: u64, max_len: u64) -> Option<(u64, u64)> {
+    if start >= total || end.is_some_and(|e| e < start) || max_len == 0 {
+        return None;
+    }
+    let last = end.unwrap_or(total - 1).min(total - 1);
+    Some((start, last.min(start + max_len - 1)))
+}
+
+/// Read up to `length` bytes of blob `hash` from `offset` (to the end if
+/// `length` is `None`). Returns the bytes and the blob's total size, or
+/// `None` if the store does not hold the complete blob.
+pub async fn read_blob_range(
+    store: &FsStore,
+    hash: Hash,
+    offset: u64,
+    length: Option<u64>,
+) -> Result<Option<(Bytes, u64)>, P2pError> {
+    let size = match store
+        .blobs()
+        .status(hash)
+        .await
+        .map_err(|e| P2pError::BlobStore(e.to_string()))?
+    {
+        BlobStatus::Complete { size } => size,
+        _ => return Ok(None),
+    };
+
+    let offset = offset.min(size);
+    let len = length.unwrap_or(u64::MAX).min(size - offset);
+    let mut reader = store.blobs().reader(hash);
+    reader.seek(std::io::SeekFrom::Start(offset)).await?;
+    let mut buf = Vec::with_capacity(len as usize);
+    reader.take(len).read_to_end(&mut buf).await?;
+    Ok(Some((Bytes::from(buf), size)))
+}
+
+/// Chunks of a verified range: those holding `length` bytes from `offset`
+/// (to the end if `None`), and the last chunk, whose proof verifies the
+/// blob size.
+fn verified_range_chunks(offset: u64, length: Option<u64>) -> ChunkRanges {
+    let range = match length {
+        Some(len) => ChunkRanges::bytes(offset..offset.saturating_add(len)),
+        None => ChunkRanges::bytes(offset..),
+    };
+    range | ChunkRanges::last_chunk()
+}
+
+/// Encode up to `length` bytes of blob `hash` from `offset` as a BAO slice
+/// for [`import_verified_range`]. Returns `None` if the store does not hold
+/// the complete blob.
+pub async fn export_verified_range(
+    store: &FsStore,
+    hash: Hash,
+    offset: u64,
+    length: Option<u64>,
+) -> Result<Option<Bytes>, P2pError> {
+    let status = store
+        .blobs()
+        .status(hash)
+        .await
+        .map_err(|e| P2pError::BlobStore(e.to_string()))?;
+    if !matches!(status, BlobStatus::Complete { .. }) {
+        return Ok(None);
+    }
+    let encoded = store
+        .blobs()
+        .export_bao(hash, verified_range_chunks(offset, length))
+        .bao_to_vec()
+        .await
+        .map_err(|e| P2pError::BlobStore(e.to_string()))?;
+    Ok(Some(Bytes::from(encoded)))
+}
+
+/// Verify a BAO slice of blob `hash` received for `len` bytes from
+/// `offset`, keep its chunks in `store`, and return those bytes (cut to the
+/// end of the blob) with the blob's size. Fails without returning anything
+/// if a single chunk or the size does not match `hash`.
+pub async fn import_verified_range(
+    store: &FsStore,
+    hash: Hash,
+    offset: u64,
+    len: u64,
+    encoded: Bytes,
+) -> Result<(Bytes, u64), P2pError> {
+    let size = encoded
+        .get(..8)
+        .and_then(|b| b.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or_else(|| P2pError::BlobStore("verified range too short".into()))?;
+    store
+        .blobs()
+        .import_bao_bytes(hash, verified_range_chunks(offset, Some(len)), encoded)
+        .await
+        .map_err(|e| P2pError::BlobStore(format!("range of {hash} failed verification: {e}")))?;
+    let Some((first, last)) = clamp_range(offset, None, size, len) else {
+        return Ok((Bytes::new(), size));
+    };
+    let data = store
+        .blobs()
+        .export_ranges(hash, first..last + 1)
+        .concatenate()
+        .await
+        .map_err(|e| P2pError::BlobStore(e.to_string()))?;
+    Ok((Bytes::from(data), size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iroh_blobs::api::TempTag;
+
+    // ── clamp_range ──
+
+    #[test]
+    fn test_clamp_range() {
+        assert_eq!(clamp_range(0, Some(99), 1000, 4096), Some((0, 99)));
+        // Open-ended ranges run to the end of the blob...
+        assert_eq!(clamp_range(900, None, 1000, 4096), Some((900, 999)));
+        // ...or stop at the length cap
+        assert_eq!(clamp_range(0, None, 10_000, 4096), Some((0, 4095)));
+        // An end past the blob is cut to its last byte
+        assert_eq!(clamp_range(10, Some(5000), 1000, 4096), Some((10, 999)));
+    }
+
+    #[test]
+    fn test_clamp_range_unsatisfiable() {
+        assert_eq!(clamp_range(1000, None, 1000, 4096), None);
+        assert_eq!(clamp_range(50, Some(10), 1000, 4096), None);
+        assert_eq!(clamp_range(0, None, 0, 4096), None);
+    }
+
+    #[test]
+    fn test_track_range_end() {
+        let range = TrackRange {
+            data: Bytes::from_static(b"abcd"),
+            start: 10,
+            total_size: 100,
+        };
+        assert_eq!(range.end(), Some(13));
+        let empty = TrackRange {
+            data: Bytes::new(),
+            ..range
+        };
+        assert_eq!(empty.end(), None);
+    }
+
+    // ── read_blob_range ──
+
+    #[tokio::test]
+    async fn test_read_blob_range() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let tag = store
+            .blobs()
+            .add_bytes(data.clone())
+            .temp_tag()
+            .await
+            .unwrap();
+        let hash = tag.hash();
+
+        let (bytes, size) = read_blob_range(&store, hash, 70_000, Some(1000))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(size, 100_000);
+        assert_eq!(bytes.as_ref(), &data[70_000..71_000]);
+
+        // No length reads to the end; an offset past the end reads nothing
+        let (tail, _) = read_blob_range(&store, hash, 99_990, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tail.as_ref(), &data[99_990..]);
+        let (past, _) = read_blob_range(&store, hash, 200_000, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(past.is_empty());
+
+        store.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_blob_range_missing_blob() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+        let missing = read_blob_range(&store, Hash::new(b"not stored"), 0, None)
+            .await
+            .unwrap();
+        assert!(missing.is_none());
+        store.shutdown().await.unwrap();
+    }
+
+    // ── Verified ranges ──
+
+    /// A store holding a 100 kB blob, the tag keeping it, and the blob.
+    async fn store_with_blob(dir: &std::path::Path) -> (FsStore, TempTag, Vec<u8>) {
+        let store = FsStore::load(dir.join("blobs")).await.unwrap();
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let tag = store
+            .blobs()
+            .add_bytes(data.clone())
+            .temp_tag()
+            .await
+            .unwrap();
+        (store, tag, data)
+    }
+
+    #[tokio::test]
+    async fn test_verified_range_roundtrip() {
+        let td = tempfile::tempdir().unwrap();
+        let (source, tag, data) = store_with_blob(td.path()).await;
+        let hash = tag.hash();
+        let target = FsStore::load(td.path().join("target")).await.unwrap();
+
+        let encoded = export_verified_range(&source, hash, 70_000, Some(1000))
+            .await
+            .unwrap()
+            .unwrap();
+        let (bytes, size) = import_verified_range(&target, hash, 70_000, 1000, encoded)
+            .await
+            .unwrap();
+        assert_eq!(size, 100_000);
+        assert_eq!(bytes.as_ref(), &data[70_000..71_000]);
+
+        // A range running past the end is cut to the blob
+        let encoded = export_verified_range(&source, hash, 99_000, Some(4096))
+            .await
+            .unwrap()
+            .unwrap();
+        let (bytes, _) = import_verified_range(&target, hash, 99_000, 4096, encoded)
+            .await
+            .unwrap();
+        assert_eq!(bytes.as_ref(), &data[99_000..]);
+
+        source.shutdown().await.unwrap();
+        target.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_verified_range_rejects_tampering() {
+        let td = tempfile::tempdir().unwrap();
+        let (source, tag, _) = store_with_blob(td.path()).await;
+        let hash = tag.hash();
+        let target = FsStore::load(td.path().join("target")).await.unwrap();
+        let encoded = export_verified_range(&source, hash, 0, Some(1000))
+            .await
+            .unwrap()
+            .unwrap();
+
+        // A flipped byte in the data
+        let mut tampered = encoded.to_vec();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0xff;
+        assert!(
+            import_verified_range(&target, hash, 0, 1000, Bytes::from(tampered))
+                .await
+                .is_err()
+        );
+
+        // A forged blob size
+        let mut forged = encoded.to_vec();
+        forged[..8].copy_from_slice(&10_000_000u64.to_le_bytes());
+        assert!(
+            import_verified_range(&target, hash, 0, 1000, Bytes::from(forged))
+                .await
+                .is_err()
+        );
+
+        // Another blob's slice
+        let other = Hash::new(b"another blob");
+        assert!(import_verified_range(&target, other, 0, 1000, encoded)
+            .await
+            .is_err());
+
+        source.shutdown().await.unwrap();
+        target.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_export_verified_range_missing_blob() {
+        let td = tempfile::tempdir().unwrap();
+        let store = FsStore::load(td.path().join("blobs")).await.unwrap();
+        let missing = export_verified_range(&store, Hash::new(b"not stored"), 0, None)
+            .await
+            .unwrap();
+        assert!(missing.is_none());
+        store.shutdown().await.unwrap();
+    }
+}
@@ -0,0 +1,293 @@
+//! Swarm-style download of a single blob from several peers at once.
+//!
+//! When more than one online peer holds the same content hash, the blob is
+//! split into byte ranges that are requested concurrently (one
+//! `FetchTrackRange` per range). A range whose peer fails is reassigned to
+//! the peers that are still healthy. The reassembled blob is only returned
+//! after its BLAKE3 hash verifies.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use iroh_blobs::Hash;
+use tracing::{info, warn};
+
+use crate::error::P2pError;
+use crate::track_health::verify_blob;
+
+/// Maximum number of peers a single blob is fetched from in parallel.
+pub const MAX_SWARM_SOURCES: usize = 3;
+
+/// Blobs smaller than this are fetched from a single peer.
+pub const MIN_SWARM_BLOB_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Something that can serve a byte range of a blob held by a peer.
+#[async_trait]
+pub trait RangeSource: Send + Sync + 'static {
+    /// Fetch `len` bytes of blob `hash` starting at `offset` from `peer_id`.
+    async fn fetch_range(
+        &self,
+        peer_id: &str,
+        hash: Hash,
+        offset: u64,
+        len: u64,
+    ) -> Result<Bytes, P2pError>;
+}
+
+/// A fully downloaded and verified blob.
+#[derive(Debug)]
+pub struct SwarmResult {
+    pub data: Bytes,
+    /// Bytes contributed by each peer, in the order peers were given.
+    pub contributions: Vec<(String, u64)>,
+}
+
+/// Split `total` bytes into at most `n` contiguous `(offset, len)` ranges.
+pub fn split_ranges(total: u64, n: usize) -> Vec<(u64, u64)> {
+    let n = (n.max(1) as u64).min(total.max(1));
+    let base = total / n;
+    let extra = total % n;
+    let mut offset = 0;
+    (0..n)
+        .map(|i| {
+            let len = base + u64::from(i < extra);
+            let range = (offset, len);
+            offset += len;
+            range
+        })
+        .collect()
+}
+
+/// Download blob `hash` of `total_size` bytes from `peers` in parallel.
+///
+/// The blob is split into one range per peer. Failed ranges are retried on
+/// the remaining peers until every range succeeds or no peer is left.
+pub async fn swarm_fetch<S: RangeSource>(
+    source: Arc<S>,
+    hash: Hash,
+    total_size: u64,
+    peers: &[String],
+) -> Result<SwarmResult, P2pError> {
+    if peers.is_empty() {
+        return Err(P2pError::TrackNotFound(hash.to_string()));
+    }
+
+    let ranges = split_ranges(total_size, peers.len());
+    let mut parts: Vec<Option<Bytes>> = vec![None; ranges.len()];
+    let mut contributions: HashMap<String, u64> = HashMap::new();
+    let mut healthy: Vec<String> = peers.to_vec();
+    let mut pending: Vec<usize> = (0..ranges.len()).collect();
+
+    while !pending.is_empty() {
+        if healthy.is_empty() {
+            return Err(P2pError::Connection(format!(
+                "all {} sources failed for blob {hash}",
+                peers.len()
+            )));
+        }
+
+        // Spread the outstanding ranges over the peers that are still healthy
+        let mut handles = Vec::with_capacity(pending.len());
+        for (i, &range_idx) in pending.iter().enumerate() {
+            let peer = healthy[i % healthy.len()].clone();
+            let (offset, len) = ranges[range_idx];
+            let source = Arc::clone(&source);
+            handles.push(tokio::spawn(async move {
+                let result = source.fetch_range(&peer, hash, offset, len).await;
+                (range_idx, peer, result)
+            }));
+        }
+
+        let mut retry = Vec::new();
+        for handle in handles {
+            let (range_idx, peer, result) = handle
+                .await
+                .map_err(|e| P2pError::Connection(format!("range task failed: {e}")))?;
+            let (offset, len) = ranges[range_idx];
+            match result {
+                Ok(bytes) if bytes.len() as u64 == len => {
+                    *contributions.entry(peer).or_default() += len;
+                    parts[range_idx] = Some(bytes);
+                }
+                Ok(bytes) => {
+                    warn!(%peer, %hash, offset, expected = len, got = bytes.len(), "short range from peer");
+                    healthy.retain(|p| p != &peer);
+                    retry.push(range_idx);
+                }
+                Err(e) => {
+                    warn!(%peer, %hash, offset, len, "range fetch failed, failing over: {e}");
+                    healthy.retain(|p| p != &peer);
+                    retry.push(range_idx);
+                }
+            }
+        }
+        pending = retry;
+    }
+
+    let mut data = Vec::with_capacity(total_size as usize);
+    for part in parts.into_iter().flatten() {
+        data.extend_from_slice(&part);
+    }
+
+    verify_blob(&hash, &data)?;
+
+    let contributions: Vec<(String, u64)> = peers
+        .iter()
+        .map(|p| (p.clone(), contributions.get(p).copied().unwrap_or(0)))
+        .collect();
+    for (peer, bytes) in &contributions {
+        info!(%hash, %peer, bytes, "swarm fetch source contribution");
+    }
+
+    Ok(SwarmResult {
+        data: Bytes::from(data),
+        contributions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    /// Peers that all hold the same blob; some can be made to fail.
+    struct MockPeers {
+        blob: Vec<u8>,
+        failing: HashSet<String>,
+        requests: Mutex<Vec<(String, u64, u64)>>,
+    }
+
+    impl MockPeers {
+        fn new(blob: Vec<u8>, failing: &[&str]) -> Arc<Self> {
+            Arc::new(Self {
+                blob,
+                failing: failing.iter().map(|p| p.to_string()).collect(),
+                requests: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl RangeSource for MockPeers {
+        async fn fetch_range(
+            &self,
+            peer_id: &str,
+            _hash: Hash,
+            offset: u64,
+            len: u64,
+        ) -> Result<Bytes, P2pError> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push((peer_id.to_string(), offset, len));
+            if self.failing.contains(peer_id) {
+                return Err(P2pError::Connection("peer went away".into()));
+            }
+            let start = offset as usize;
+            Ok(Bytes::copy_from_slice(
+                &self.blob[start..start + len as usize],
+            ))
+        }
+    }
+
+    fn blob(size: usize) -> Vec<u8> {
+        (0..size).map(|i| (i % 253) as u8).collect()
+    }
+
+    fn peers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|p| p.to_string()).collect()
+    }
+
+    // ── split_ranges ──
+
+    #[test]
+    fn test_split_ranges_covers_blob() {
+        let ranges = split_ranges(10, 3);
+        assert_eq!(ranges, vec![(0, 4), (4, 3), (7, 3)]);
+        assert_eq!(split_ranges(1, 3), vec![(0, 1)]);
+        assert_eq!(split_ranges(8, 0), vec![(0, 8)]);
+    }
+
+    // ── swarm_fetch ──
+
+    #[tokio::test]
+    async fn test_two_peers_each_serve_half() {
+        let data = blob(64 * 1024);
+        let hash = Hash::new(&data);
+        let source = MockPeers::new(data.clone(), &[]);
+
+        let result = swarm_fetch(
+            Arc::clone(&source),
+            hash,
+            data.len() as u64,
+            &peers(&["a", "b"]),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.data.as_ref(), data.as_slice());
+        assert_eq!(
+            result.contributions,
+            vec![("a".to_string(), 32 * 1024), ("b".to_string(), 32 * 1024)]
+        );
+        assert_eq!(source.requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_range_fails_over() {
+        let data = blob(30_000);
+        let hash = Hash::new(&data);
+        let source = MockPeers::new(data.clone(), &["b"]);
+
+        let result = swarm_fetch(
+            Arc::clone(&source),
+            hash,
+            data.len() as u64,
+            &peers(&["a", "b", "c"]),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.data.as_ref(), data.as_slice());
+        let from_b = result
+            .contributions
+            .iter()
+            .find(|(p, _)| p == "b")
+            .unwrap()
+            .1;
+        assert_eq!(from_b, 0);
+        let total: u64 = result.contributions.iter().map(|(_, b)| b).sum();
+        assert_eq!(total, 30_000);
+    }
+
+    #[tokio::test]
+    async fn test_all_peers_fail() {
+        let data = blob(1000);
+        let hash = Hash::new(&data);
+        let source = MockPeers::new(data.clone(), &["a", "b"]);
+
+        let err = swarm_fetch(source, hash, data.len() as u64, &peers(&["a", "b"]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, P2pError::Connection(_)));
+    }
+
+    #[tokio::test]
+    async fn test_hash_mismatch_rejected() {
+        let data = blob(1000);
+        let source = MockPeers::new(data.clone(), &[]);
+
+        let err = swarm_fetch(
+            source,
+            Hash::new(b"other"),
+            data.len() as u64,
+            &peers(&["a", "b"]),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, P2pError::HashMismatch { .. }));
+    }
+}
@@ -0,0 +1,155 @@
+//! Resumable catalog pushes.
+//!
+//! `P2pNode::announce_all_tracks_to_peer` records, per peer, the highest
+//! `CatalogSync` page delivered so far and the page size in use. If the push
+//! is interrupted, the next one starts after that page (keeping the same page
+//! size, even if the peer's RTT has changed) instead of re-sending the whole
+//! catalog. The
+//! map is persisted as JSON (`sync_checkpoints.json` next to the blob
+//! store) so a restart does not lose it; each write goes to a temporary file
+//! that is renamed over the old one.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::error::P2pError;
+
+/// Progress of an unfinished catalog push to one peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncCheckpoint {
+    /// Highest page delivered
+    pub page: u64,
+    /// Tracks per page in that push
+    pub page_size: u64,
+}
+
+impl SyncCheckpoint {
+    /// Page a push over `num_pages` pages resumes from, or `None` if the
+    /// catalog has shrunk past the checkpoint and it must start over.
+    pub fn next_page(&self, num_pages: u64) -> Option<u64> {
+        Some(self.page + 1).filter(|&next| next < num_pages)
+    }
+}
+
+/// On-disk store for the per-peer catalog sync checkpoints.
+pub struct CheckpointFile {
+    path: PathBuf,
+    /// Serializes writers so an older snapshot never replaces a newer one.
+    write_lock: Mutex<()>,
+}
+
+impl CheckpointFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// Read the saved checkpoints. A missing file yields an empty map; an
+    /// unreadable one is logged and ignored.
+    pub fn load(&self) -> DashMap<String, SyncCheckpoint> {
+        let data = match std::fs::read(&self.path) {
+            Ok(d) => d,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return DashMap::new(),
+            Err(e) => {
+                warn!(path = %self.path.display(), "failed to read sync checkpoints: {e}");
+                return DashMap::new();
+            }
+        };
+        match serde_json::from_slice::<std::collections::HashMap<String, SyncCheckpoint>>(&data) {
+            Ok(map) => map.into_iter().collect(),
+            Err(e) => {
+                warn!(path = %self.path.display(), "ignoring corrupt sync checkpoints: {e}");
+                DashMap::new()
+            }
+        }
+    }
+
+    /// Atomically replace the file with the current contents of `checkpoints`.
+    pub fn save(&self, checkpoints: &DashMap<String, SyncCheckpoint>) -> Result<(), P2pError> {
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        let snapshot: std::collections::BTreeMap<String, SyncCheckpoint> = checkpoints
+            .iter()
+            .map(|e| (e.key().clone(), *e.value()))
+            .collect();
+        let json = serde_json::to_vec_pretty(&snapshot)?;
+
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        std::fs::create_dir_all(dir)?;
+        let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+        tmp.write_all(&json)?;
+        tmp.as_file().sync_all()?;
+        tmp.persist(&self.path).map_err(|e| P2pError::Io(e.error))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_page_resumes_after_checkpoint() {
+        let checkpoint = SyncCheckpoint {
+            page: 4,
+            page_size: 500,
+        };
+        assert_eq!(checkpoint.next_page(10), Some(5));
+        // Last page already delivered, or the catalog shrank: start over
+        assert_eq!(checkpoint.next_page(5), None);
+        assert_eq!(checkpoint.next_page(3), None);
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = CheckpointFile::new(dir.path().join("p2p").join("sync_checkpoints.json"));
+
+        let a = SyncCheckpoint {
+            page: 2,
+            page_size: 500,
+        };
+        let b = SyncCheckpoint {
+            page: 7,
+            page_size: 50,
+        };
+        let checkpoints = DashMap::new();
+        checkpoints.insert("peer-a".to_string(), a);
+        checkpoints.insert("peer-b".to_string(), b);
+        file.save(&checkpoints).unwrap();
+
+        let loaded = file.load();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(*loaded.get("peer-a").unwrap(), a);
+        assert_eq!(*loaded.get("peer-b").unwrap(), b);
+
+        // Clearing a peer and saving again drops it from the file
+        checkpoints.remove("peer-a");
+        file.save(&checkpoints).unwrap();
+        let loaded = file.load();
+        assert!(loaded.get("peer-a").is_none());
+        assert_eq!(*loaded.get("peer-b").unwrap(), b);
+    }
+
+    #[test]
+    fn test_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = CheckpointFile::new(dir.path().join("sync_checkpoints.json"));
+        assert!(file.load().is_empty());
+    }
+
+    #[test]
+    fn test_corrupt_file_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sync_checkpoints.json");
+        std::fs::write(&path, b"{not json").unwrap();
+        assert!(CheckpointFile::new(path).load().is_empty());
+    }
+}
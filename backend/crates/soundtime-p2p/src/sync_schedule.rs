@@ -0,0 +1,636 @@
+//! Scheduled library re-syncs.
+//!
+//! The schedule is a JSON [`SyncSchedule`] kept in the
+//! `library_sync_schedule` instance setting: an optional global cron
+//! expression applied to every online peer, and per-peer expressions that
+//! take precedence over it. Expressions have a seconds field and are
+//! evaluated in UTC, as for `P2P_HEALTH_SCHEDULE`.
+//!
+//! [`spawn_sync_scheduler`] reads the schedule every [`SCHEDULER_TICK`] and
+//! starts [`spawn_library_resync`] for the peer whose run is most overdue.
+//! Only one library sync runs at a time, so:
+//!
+//! - a peer that comes due while its own sync is running or paused skips
+//!   that run;
+//! - a peer that comes due while another peer syncs, or while it is
+//!   offline, waits until the tracker is free (or it is back online).
+//!
+//! Last and next run times are kept on the [`SyncTaskTracker`] and reported
+//! in `LibrarySyncOverview::schedule`.
+//!
+//! [`SyncTaskTracker`]: crate::library_sync::SyncTaskTracker
+
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set};
+use serde::{Deserialize, Serialize};
+use soundtime_db::entities::instance_setting;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::error::P2pError;
+use crate::library_sync::{spawn_library_resync, SyncTaskHandle};
+use crate::node::P2pNode;
+
+/// Instance setting holding the schedule as JSON.
+pub const SCHEDULE_SETTING_KEY: &str = "library_sync_schedule";
+
+/// How often the scheduler checks for due re-syncs.
+pub const SCHEDULER_TICK: Duration = Duration::from_secs(30);
+
+/// Cron expressions for scheduled library re-syncs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncSchedule {
+    /// Expression for every online peer without one of its own
+    #[serde(default)]
+    pub global: Option<String>,
+    /// Expression by peer node ID
+    #[serde(default)]
+    pub peers: BTreeMap<String, String>,
+}
+
+/// Why a schedule was refused.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ScheduleError {
+    #[error("invalid cron expression '{expr}': {reason}")]
+    InvalidCron { expr: String, reason: String },
+    #[error("cron expression '{0}' has no upcoming times")]
+    NeverFires(String),
+}
+
+/// Parse a cron expression, rejecting ones the `cron` crate cannot read or
+/// that never fire.
+pub fn parse_cron(expr: &str) -> Result<cron::Schedule, ScheduleError> {
+    let expr = expr.trim();
+    let schedule = cron::Schedule::from_str(expr).map_err(|e| ScheduleError::InvalidCron {
+        expr: expr.to_string(),
+        reason: e.to_string(),
+    })?;
+    if schedule.upcoming(Utc).next().is_none() {
+        return Err(ScheduleError::NeverFires(expr.to_string()));
+    }
+    Ok(schedule)
+}
+
+/// First time `schedule` fires strictly after `after`.
+pub fn next_run(schedule: &cron::Schedule, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    schedule.after(&after).next()
+}
+
+impl SyncSchedule {
+    /// Trim every expression, drop empty ones and check the rest parse.
+    pub fn normalized(self) -> Result<Self, ScheduleError> {
+        let global = match self.global.as_deref().map(str::trim) {
+            Some(expr) if !expr.is_empty() => {
+                parse_cron(expr)?;
+                Some(expr.to_string())
+            }
+            _ => None,
+        };
+        let mut peers = BTreeMap::new();
+        for (peer_id, expr) in self.peers {
+            let (peer_id, expr) = (peer_id.trim(), expr.trim());
+            if peer_id.is_empty() || expr.is_empty() {
+                continue;
+            }
+            parse_cron(expr)?;
+            peers.insert(peer_id.to_string(), expr.to_string());
+        }
+        Ok(Self { global, peers })
+    }
+
+    /// Expression for `peer_id`, and whether it is the global one.
+    pub fn expr_for(&self, peer_id: &str) -> Option<(&str, bool)> {
+        match self.peers.get(peer_id) {
+            Some(expr) => Some((expr, false)),
+            None => self.global.as_deref().map(|expr| (expr, true)),
+        }
+    }
+
+    /// Read the schedule from the `instance_settings` table. No setting
+    /// means no schedule.
+    pub async fn load<C: ConnectionTrait>(db: &C) -> Result<Self, P2pError> {
+        let row = instance_setting::Entity::find()
+            .filter(instance_setting::Column::Key.eq(SCHEDULE_SETTING_KEY))
+            .one(db)
+            .await?;
+        match row {
+            Some(row) if !row.value.trim().is_empty() => Ok(serde_json::from_str(&row.value)?),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Write the schedule to the `instance_settings` table.
+    pub async fn save<C: ConnectionTrait>(&self, db: &C) -> Result<(), P2pError> {
+        instance_setting::Entity::insert(instance_setting::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            key: Set(SCHEDULE_SETTING_KEY.to_string()),
+            value: Set(serde_json::to_string(self)?),
+            updated_at: Set(Utc::now().into()),
+        })
+        .on_conflict(
+            OnConflict::column(instance_setting::Column::Key)
+                .update_columns([
+                    instance_setting::Column::Value,
+                    instance_setting::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+        Ok(())
+    }
+}
+
+/// A peer's scheduled re-sync.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScheduledSync {
+    pub peer_id: String,
+    pub cron: String,
+    /// Whether `cron` comes from the global schedule
+    pub global: bool,
+    /// When the scheduler last started a re-sync with the peer
+    pub last_run: Option<DateTime<Utc>>,
+    /// When the next re-sync is due; in the past while it waits for the
+    /// tracker or the peer
+    pub next_run: Option<DateTime<Utc>>,
+    /// When `cron` was first seen, or the last skipped run. Runs are
+    /// counted from the later of this and `last_run`.
+    #[serde(skip)]
+    since: DateTime<Utc>,
+}
+
+impl ScheduledSync {
+    fn new(peer_id: &str, cron: &str, global: bool, now: DateTime<Utc>) -> Self {
+        Self {
+            peer_id: peer_id.to_string(),
+            cron: cron.to_string(),
+            global,
+            last_run: None,
+            next_run: None,
+            since: now,
+        }
+    }
+
+    /// Internal: recompute `next_run` from the expression.
+    fn refresh(&mut self) {
+        let from = self
+            .last_run
+            .map_or(self.since, |last| last.max(self.since));
+        self.next_run = parse_cron(&self.cron)
+            .ok()
+            .and_then(|schedule| next_run(&schedule, from));
+    }
+
+    fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.next_run.is_some_and(|next| next <= now)
+    }
+}
+
+/// Bring `runs` in line with `schedule` and pick the peer to re-sync now.
+///
+/// Global runs cover the `online` peers; per-peer ones are listed whether
+/// the peer is online or not. `active_peer` is the peer of the running or
+/// paused sync task, if any. Returns the online peer whose run is most
+/// overdue, if nothing is syncing; the caller records it with
+/// [`record_run`] once the sync has started.
+pub(crate) fn plan_runs(
+    runs: &mut BTreeMap<String, ScheduledSync>,
+    schedule: &SyncSchedule,
+    online: &HashSet<String>,
+    active_peer: Option<&str>,
+    now: DateTime<Utc>,
+) -> Option<String> {
+    let mut wanted: BTreeMap<&str, (&str, bool)> = BTreeMap::new();
+    if let Some(global) = schedule.global.as_deref() {
+        for peer_id in online {
+            wanted.insert(peer_id.as_str(), (global, true));
+        }
+    }
+    for (peer_id, expr) in &schedule.peers {
+        wanted.insert(peer_id.as_str(), (expr.as_str(), false));
+    }
+
+    runs.retain(|peer_id, _| wanted.contains_key(peer_id.as_str()));
+    for (peer_id, (expr, global)) in wanted {
+        if parse_cron(expr).is_err() {
+            runs.remove(peer_id);
+            continue;
+        }
+        let run = runs
+            .entry(peer_id.to_string())
+            .or_insert_with(|| ScheduledSync::new(peer_id, expr, global, now));
+        if run.cron != expr {
+            // A new expression counts from now, not from the last run
+            *run = ScheduledSync {
+                last_run: run.last_run,
+                ..ScheduledSync::new(peer_id, expr, global, now)
+            };
+        }
+        run.global = global;
+        run.refresh();
+
+        // Its own sync is still going: this run is skipped, not queued
+        if run.is_due(now) && active_peer == Some(peer_id) {
+            run.since = now;
+            run.refresh();
+        }
+    }
+
+    if active_peer.is_some() {
+        return None;
+    }
+    runs.values()
+        .filter(|run| run.is_due(now) && online.contains(&run.peer_id))
+        .min_by_key(|run| run.next_run)
+        .map(|run| run.peer_id.clone())
+}
+
+/// Record that a scheduled re-sync with `peer_id` started at `now`.
+pub(crate) fn record_run(
+    runs: &mut BTreeMap<String, ScheduledSync>,
+    peer_id: &str,
+    now: DateTime<Utc>,
+) {
+    if let Some(run) = runs.get_mut(peer_id) {
+        run.last_run = Some(now);
+        run.refresh();
+    }
+}
+
+/// Spawn the background task starting scheduled library re-syncs.
+pub fn spawn_sync_scheduler(
+    node: Arc<P2pNode>,
+    db: sea_orm::DatabaseConnection,
+    tracker: SyncTaskHandle,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        info!(
+            tick_secs = SCHEDULER_TICK.as_secs(),
+            "library sync scheduler started"
+        );
+        let mut interval = tokio::time::interval(SCHEDULER_TICK);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            run_scheduler_tick(&node, &db, &tracker).await;
+        }
+    })
+}
+
+/// Internal: one pass of the scheduler.
+async fn run_scheduler_tick(
+    node: &Arc<P2pNode>,
+    db: &sea_orm::DatabaseConnection,
+    tracker: &SyncTaskHandle,
+) {
+    let schedule = match SyncSchedule::load(db).await {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            warn!("failed to read the library sync schedule: {e}");
+            return;
+        }
+    };
+    let online: HashSet<String> = node
+        .registry()
+        .online_peers()
+        .await
+        .into_iter()
+        .map(|peer| peer.node_id)
+        .collect();
+    let active_peer = tracker.lock().await.active_peer().map(str::to_string);
+
+    let now = Utc::now();
+    let due = plan_runs(
+        &mut tracker.scheduled_runs(),
+        &schedule,
+        &online,
+        active_peer.as_deref(),
+        now,
+    );
+    let Some(peer_id) = due else {
+        return;
+    };
+
+    // Refused if an admin started a sync since the tracker was read; the
+    // run stays due for the next tick
+    if let Some(task_id) =
+        spawn_library_resync(Arc::clone(node), peer_id.clone(), Arc::clone(tracker)).await
+    {
+        info!(peer = %peer_id, %task_id, "scheduled library re-sync started");
+        record_run(&mut tracker.scheduled_runs(), &peer_id, now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const HOURLY: &str = "0 0 * * * *";
+
+    fn at(h: u32, m: u32, s: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 14, h, m, s).unwrap()
+    }
+
+    fn online(peers: &[&str]) -> HashSet<String> {
+        peers.iter().map(|p| p.to_string()).collect()
+    }
+
+    fn per_peer(peers: &[(&str, &str)]) -> SyncSchedule {
+        SyncSchedule {
+            global: None,
+            peers: peers
+                .iter()
+                .map(|(p, e)| (p.to_string(), e.to_string()))
+                .collect(),
+        }
+    }
+
+    // ── parse_cron ──
+
+    #[test]
+    fn test_parse_cron_accepts_seconds_field() {
+        let schedule = parse_cron(" 0 */30 * * * * ").unwrap();
+        assert_eq!(next_run(&schedule, at(10, 5, 0)), Some(at(10, 30, 0)));
+    }
+
+    #[test]
+    fn test_parse_cron_rejects_garbage() {
+        let err = parse_cron("every hour").unwrap_err();
+        assert!(matches!(err, ScheduleError::InvalidCron { ref expr, .. } if expr == "every hour"));
+    }
+
+    #[test]
+    fn test_parse_cron_rejects_schedule_in_the_past() {
+        // Seconds, minutes, hours, day, month, weekday, year
+        let err = parse_cron("0 0 0 1 1 * 2000").unwrap_err();
+        assert_eq!(err, ScheduleError::NeverFires("0 0 0 1 1 * 2000".into()));
+    }
+
+    // ── SyncSchedule ──
+
+    #[test]
+    fn test_normalized_trims_and_drops_empty_entries() {
+        let schedule = SyncSchedule {
+            global: Some("  ".into()),
+            peers: [
+                ("peer-a".to_string(), format!(" {HOURLY} ")),
+                ("peer-b".to_string(), String::new()),
+            ]
+            .into_iter()
+            .collect(),
+        }
+        .normalized()
+        .unwrap();
+        assert_eq!(schedule, per_peer(&[("peer-a", HOURLY)]));
+    }
+
+    #[test]
+    fn test_normalized_rejects_invalid_peer_expression() {
+        let schedule = per_peer(&[("peer-a", HOURLY), ("peer-b", "* *")]);
+        assert!(matches!(
+            schedule.normalized(),
+            Err(ScheduleError::InvalidCron { .. })
+        ));
+    }
+
+    #[test]
+    fn test_expr_for_prefers_peer_expression() {
+        let mut schedule = per_peer(&[("peer-a", "0 */5 * * * *")]);
+        schedule.global = Some(HOURLY.into());
+        assert_eq!(schedule.expr_for("peer-a"), Some(("0 */5 * * * *", false)));
+        assert_eq!(schedule.expr_for("peer-b"), Some((HOURLY, true)));
+        assert_eq!(SyncSchedule::default().expr_for("peer-b"), None);
+    }
+
+    #[test]
+    fn test_schedule_json_defaults_missing_fields() {
+        let schedule: SyncSchedule = serde_json::from_str(r#"{"global":"0 0 3 * * *"}"#).unwrap();
+        assert_eq!(schedule.global.as_deref(), Some("0 0 3 * * *"));
+        assert!(schedule.peers.is_empty());
+    }
+
+    // ── plan_runs: due times ──
+
+    #[test]
+    fn test_first_run_counts_from_when_schedule_is_seen() {
+        let mut runs = BTreeMap::new();
+        let schedule = per_peer(&[("peer-a", HOURLY)]);
+        let peers = online(&["peer-a"]);
+
+        assert_eq!(
+            plan_runs(&mut runs, &schedule, &peers, None, at(10, 15, 0)),
+            None
+        );
+        assert_eq!(runs["peer-a"].next_run, Some(at(11, 0, 0)));
+        assert_eq!(
+            plan_runs(&mut runs, &schedule, &peers, None, at(10, 59, 59)),
+            None
+        );
+        assert_eq!(
+            plan_runs(&mut runs, &schedule, &peers, None, at(11, 0, 0)),
+            Some("peer-a".to_string())
+        );
+    }
+
+    #[test]
+    fn test_record_run_moves_next_run_on() {
+        let mut runs = BTreeMap::new();
+        let schedule = per_peer(&[("peer-a", HOURLY)]);
+        let peers = online(&["peer-a"]);
+        plan_runs(&mut runs, &schedule, &peers, None, at(10, 15, 0));
+
+        record_run(&mut runs, "peer-a", at(11, 0, 20));
+        assert_eq!(runs["peer-a"].last_run, Some(at(11, 0, 20)));
+        assert_eq!(runs["peer-a"].next_run, Some(at(12, 0, 0)));
+        assert_eq!(
+            plan_runs(&mut runs, &schedule, &peers, None, at(11, 30, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_changed_expression_counts_from_now() {
+        let mut runs = BTreeMap::new();
+        let peers = online(&["peer-a"]);
+        plan_runs(
+            &mut runs,
+            &per_peer(&[("peer-a", HOURLY)]),
+            &peers,
+            None,
+            at(9, 0, 0),
+        );
+        record_run(&mut runs, "peer-a", at(10, 0, 0));
+
+        let daily = per_peer(&[("peer-a", "0 0 3 * * *")]);
+        assert_eq!(
+            plan_runs(&mut runs, &daily, &peers, None, at(10, 30, 0)),
+            None
+        );
+        assert_eq!(runs["peer-a"].last_run, Some(at(10, 0, 0)));
+        assert_eq!(
+            runs["peer-a"].next_run,
+            Some(Utc.with_ymd_and_hms(2026, 3, 15, 3, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_global_schedule_covers_online_peers_only() {
+        let mut runs = BTreeMap::new();
+        let schedule = SyncSchedule {
+            global: Some(HOURLY.into()),
+            peers: [("peer-c".to_string(), "0 */5 * * * *".to_string())]
+                .into_iter()
+                .collect(),
+        };
+        plan_runs(
+            &mut runs,
+            &schedule,
+            &online(&["peer-a", "peer-b"]),
+            None,
+            at(10, 0, 0),
+        );
+        assert_eq!(
+            runs.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["peer-a", "peer-b", "peer-c"]
+        );
+        assert!(runs["peer-a"].global);
+        assert!(!runs["peer-c"].global);
+
+        // peer-b went offline and the global schedule no longer covers it
+        plan_runs(
+            &mut runs,
+            &schedule,
+            &online(&["peer-a"]),
+            None,
+            at(10, 1, 0),
+        );
+        assert!(!runs.contains_key("peer-b"));
+    }
+
+    #[test]
+    fn test_most_overdue_online_peer_runs_first() {
+        let mut runs = BTreeMap::new();
+        let schedule = per_peer(&[("peer-a", HOURLY), ("peer-b", "0 */10 * * * *")]);
+        let peers = online(&["peer-a", "peer-b"]);
+        plan_runs(&mut runs, &schedule, &peers, None, at(10, 5, 0));
+
+        // peer-b was due at 10:10, peer-a at 11:00
+        assert_eq!(
+            plan_runs(&mut runs, &schedule, &peers, None, at(11, 0, 0)),
+            Some("peer-b".to_string())
+        );
+    }
+
+    #[test]
+    fn test_offline_peer_waits_until_back_online() {
+        let mut runs = BTreeMap::new();
+        let schedule = per_peer(&[("peer-a", HOURLY)]);
+        plan_runs(&mut runs, &schedule, &online(&[]), None, at(10, 15, 0));
+
+        assert_eq!(
+            plan_runs(&mut runs, &schedule, &online(&[]), None, at(12, 0, 0)),
+            None
+        );
+        assert_eq!(runs["peer-a"].next_run, Some(at(11, 0, 0)));
+        assert_eq!(
+            plan_runs(
+                &mut runs,
+                &schedule,
+                &online(&["peer-a"]),
+                None,
+                at(12, 30, 0)
+            ),
+            Some("peer-a".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_stored_expression_is_ignored() {
+        let mut runs = BTreeMap::new();
+        let schedule = per_peer(&[("peer-a", "not cron")]);
+        assert_eq!(
+            plan_runs(
+                &mut runs,
+                &schedule,
+                &online(&["peer-a"]),
+                None,
+                at(10, 0, 0)
+            ),
+            None
+        );
+        assert!(runs.is_empty());
+    }
+
+    // ── plan_runs: overlap guard ──
+
+    #[test]
+    fn test_peer_already_syncing_skips_its_run() {
+        let mut runs = BTreeMap::new();
+        let schedule = per_peer(&[("peer-a", HOURLY)]);
+        let peers = online(&["peer-a"]);
+        plan_runs(&mut runs, &schedule, &peers, None, at(10, 15, 0));
+
+        assert_eq!(
+            plan_runs(&mut runs, &schedule, &peers, Some("peer-a"), at(11, 0, 10)),
+            None
+        );
+        assert_eq!(runs["peer-a"].next_run, Some(at(12, 0, 0)));
+        // The skipped run is not picked up once the sync finishes
+        assert_eq!(
+            plan_runs(&mut runs, &schedule, &peers, None, at(11, 5, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_peer_due_during_another_sync_waits() {
+        let mut runs = BTreeMap::new();
+        let schedule = per_peer(&[("peer-a", HOURLY)]);
+        let peers = online(&["peer-a", "peer-b"]);
+        plan_runs(&mut runs, &schedule, &peers, None, at(10, 15, 0));
+
+        assert_eq!(
+            plan_runs(&mut runs, &schedule, &peers, Some("peer-b"), at(11, 0, 10)),
+            None
+        );
+        assert_eq!(runs["peer-a"].next_run, Some(at(11, 0, 0)));
+        assert_eq!(
+            plan_runs(&mut runs, &schedule, &peers, None, at(11, 2, 0)),
+            Some("peer-a".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tracker_refuses_second_sync_and_reports_schedule() {
+        let tracker = crate::library_sync::new_sync_tracker();
+        let _gate = tracker.start("peer-a").await.unwrap();
+        assert!(tracker.start("peer-b").await.is_none());
+        assert_eq!(tracker.lock().await.active_peer(), Some("peer-a"));
+
+        let schedule = per_peer(&[("peer-a", HOURLY)]);
+        let active = tracker.lock().await.active_peer().map(str::to_string);
+        let due = plan_runs(
+            &mut tracker.scheduled_runs(),
+            &schedule,
+            &online(&["peer-a"]),
+            active.as_deref(),
+            at(10, 15, 0),
+        );
+        assert_eq!(due, None);
+
+        let listed = tracker.scheduled_syncs();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].peer_id, "peer-a");
+        assert_eq!(listed[0].next_run, Some(at(11, 0, 0)));
+        let json = serde_json::to_value(&listed[0]).unwrap();
+        assert!(json.get("since").is_none());
+        assert_eq!(json["cron"], HOURLY);
+    }
+}
@@ -0,0 +1,98 @@
+//! Nodes for integration tests.
+//!
+//! Each node gets an in-memory SQLite database with the tables it reads,
+//! created straight from the SeaORM entities, and its blobs, key and Bloom
+//! filter in a temporary directory. Relays and discovery are off, so nodes
+//! only reach each other by direct address.
+
+use std::sync::Arc;
+
+use sea_orm::{ConnectOptions, ConnectionTrait, Database, DatabaseConnection, EntityTrait, Schema};
+use soundtime_db::entities::{
+    album, artist, blocked_hash, instance_setting, mb_lookup_queue, p2p_peer, p2p_peer_filter,
+    p2p_seed_peer, peer_track_grant, pinned_track, published_hash, remote_play_count, remote_track,
+    track, track_recovery_attempt,
+};
+use tempfile::TempDir;
+
+use crate::node::{P2pConfig, P2pNode};
+
+/// A running node and what it is backed by.
+pub(crate) struct TestNode {
+    pub node: Arc<P2pNode>,
+    pub db: DatabaseConnection,
+    /// Blobs, key and Bloom filter; removed on drop
+    _dir: TempDir,
+}
+
+/// Open an empty database with the node's tables.
+pub(crate) async fn connect() -> DatabaseConnection {
+    // A single connection: each connection to `:memory:` is its own database
+    let mut options = ConnectOptions::new("sqlite::memory:");
+    options
+        .max_connections(1)
+        .min_connections(1)
+        .sqlx_logging(false);
+    let db = Database::connect(options)
+        .await
+        .expect("failed to open SQLite database");
+    db.execute_unprepared("PRAGMA foreign_keys = OFF")
+        .await
+        .expect("failed to disable foreign keys");
+
+    create_table(&db, artist::Entity).await;
+    create_table(&db, album::Entity).await;
+    create_table(&db, track::Entity).await;
+    create_table(&db, remote_track::Entity).await;
+    create_table(&db, remote_play_count::Entity).await;
+    create_table(&db, mb_lookup_queue::Entity).await;
+    create_table(&db, blocked_hash::Entity).await;
+    create_table(&db, instance_setting::Entity).await;
+    create_table(&db, p2p_peer::Entity).await;
+    create_table(&db, p2p_peer_filter::Entity).await;
+    create_table(&db, p2p_seed_peer::Entity).await;
+    create_table(&db, peer_track_grant::Entity).await;
+    create_table(&db, pinned_track::Entity).await;
+    create_table(&db, published_hash::Entity).await;
+    create_table(&db, track_recovery_attempt::Entity).await;
+    db
+}
+
+/// Create the table of `entity`.
+async fn create_table<E: EntityTrait>(db: &DatabaseConnection, entity: E) {
+    let backend = db.get_database_backend();
+    let stmt = Schema::new(backend).create_table_from_entity(entity);
+    db.execute(backend.build(&stmt))
+        .await
+        .expect("failed to create table");
+}
+
+/// Start a node on a fresh database.
+pub(crate) async fn start_node() -> TestNode {
+    start_node_with(|_| {}).await
+}
+
+/// Start a node on a fresh database, after `configure` adjusts its config.
+pub(crate) async fn start_node_with(configure: impl FnOnce(&mut P2pConfig)) -> TestNode {
+    let dir = TempDir::new().expect("failed to create temp dir");
+    let mut config = P2pConfig {
+        blobs_dir: dir.path().join("blobs"),
+        secret_key_path: dir.path().join("secret_key"),
+        bloom_persist_path: dir.path().join("bloom.bin"),
+        audio_storage_path: dir.path().join("music"),
+        enable_local_discovery: false,
+        enable_dht_discovery: false,
+        disable_default_discovery: true,
+        ..Default::default()
+    };
+    configure(&mut config);
+    let db = connect().await;
+    let node = P2pNode::start(config, db.clone())
+        .await
+        .expect("failed to start node");
+    TestNode {
+        node,
+        db,
+        _dir: dir,
+    }
+}
@@ -0,0 +1,269 @@
+//! Per-peer access to private tracks.
+//!
+//! A track marked `tracks.is_private` is not served to every peer that asks.
+//! An admin grants one peer access with `P2pNode::grant_track_access`: the
+//! origin signs a token (HMAC-SHA256 over the content hash, the peer's node
+//! ID and the issue time, keyed by [`grant_key`]), records it in
+//! `peer_track_grants` and sends it to the peer as
+//! `P2pMessage::AuthorizeTrackAccess`. The peer stores it, if the sender
+//! announced the track, and presents it in `FetchTrack` / `FetchTrackRange`;
+//! the origin serves the blob only if the token verifies and is at most
+//! [`GRANT_MAX_AGE_SECS`] old, and answers `AccessDenied` otherwise.
+
+use hmac::{Hmac, Mac};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use sha2::Sha256;
+use soundtime_db::entities::{peer_track_grant, remote_track, track};
+
+use crate::error::P2pError;
+
+/// How long a grant token stays valid after it was issued.
+pub const GRANT_MAX_AGE_SECS: i64 = 24 * 60 * 60;
+
+/// Most grants kept from one issuing peer; the oldest go first.
+pub const MAX_GRANTS_PER_PEER: u64 = 1_000;
+
+/// Context of the [`grant_key`] derivation, so the key is never reused for
+/// anything else.
+const GRANT_KEY_CONTEXT: &str = "soundtime p2p 2026-10 track access grant HMAC key";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Key signing grants, derived from the node's secret key rather than the
+/// secret key itself.
+pub fn grant_key(secret_key: &[u8; 32]) -> [u8; 32] {
+    blake3::derive_key(GRANT_KEY_CONTEXT, secret_key)
+}
+
+fn grant_mac(key: &[u8], hash: &str, node_id: &str, issued_at: i64) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    // Newline-separated so fields cannot run together
+    mac.update(format!("{hash}\n{node_id}\n{issued_at}").as_bytes());
+    mac
+}
+
+/// Sign a grant letting `node_id` fetch `hash`, issued at `issued_at` (Unix
+/// seconds). The token is `<issued_at>.<hex MAC>`.
+pub fn sign_grant(key: &[u8], hash: &str, node_id: &str, issued_at: i64) -> String {
+    let tag = grant_mac(key, hash, node_id, issued_at)
+        .finalize()
+        .into_bytes();
+    format!("{issued_at}.{}", data_encoding::HEXLOWER.encode(&tag))
+}
+
+/// Issue time of a token made by [`sign_grant`], without verifying it.
+pub fn token_issued_at(token: &str) -> Option<i64> {
+    token.split_once('.')?.0.parse().ok()
+}
+
+/// Whether `token` was signed with `key` for `node_id` to fetch `hash`, and
+/// was issued no more than [`GRANT_MAX_AGE_SECS`] before `now`.
+pub fn verify_grant(key: &[u8], hash: &str, node_id: &str, token: &str, now: i64) -> bool {
+    let Some((issued_at, tag)) = token.split_once('.') else {
+        return false;
+    };
+    let Ok(issued_at) = issued_at.parse::<i64>() else {
+        return false;
+    };
+    if issued_at > now || now - issued_at > GRANT_MAX_AGE_SECS {
+        return false;
+    }
+    let Ok(tag) = data_encoding::HEXLOWER.decode(tag.as_bytes()) else {
+        return false;
+    };
+    // Constant-time comparison
+    grant_mac(key, hash, node_id, issued_at)
+        .verify_slice(&tag)
+        .is_ok()
+}
+
+/// Whether a local track with this content hash is marked private.
+pub async fn is_private_hash(db: &DatabaseConnection, hash: &str) -> Result<bool, P2pError> {
+    Ok(track::Entity::find()
+        .filter(track::Column::ContentHash.eq(hash))
+        .filter(track::Column::IsPrivate.eq(true))
+        .count(db)
+        .await?
+        > 0)
+}
+
+/// Record a grant token, issued by us or received from `issuer_node`.
+pub async fn save_grant(
+    db: &DatabaseConnection,
+    hash: &str,
+    node_id: &str,
+    issuer_node: &str,
+    token: &str,
+) -> Result<(), P2pError> {
+    let issued_at = token_issued_at(token)
+        .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+        .ok_or_else(|| P2pError::InvalidSignature("malformed grant token".into()))?;
+    let expires_at = issued_at + chrono::Duration::seconds(GRANT_MAX_AGE_SECS);
+    let model = peer_track_grant::ActiveModel {
+        id: Set(uuid::Uuid::new_v4()),
+        content_hash: Set(hash.to_string()),
+        node_id: Set(node_id.to_string()),
+        issuer_node: Set(issuer_node.to_string()),
+        token: Set(token.to_string()),
+        issued_at: Set(issued_at.into()),
+        expires_at: Set(expires_at.into()),
+    };
+    peer_track_grant::Entity::insert(model).exec(db).await?;
+    Ok(())
+}
+
+/// Whether `peer_id` announced a track with this content hash to us, i.e.
+/// is its origin.
+pub async fn is_announced_by(
+    db: &DatabaseConnection,
+    peer_id: &str,
+    hash: &str,
+) -> Result<bool, P2pError> {
+    Ok(remote_track::Entity::find()
+        .filter(remote_track::Column::RemoteUri.eq(format!("p2p://{peer_id}/{hash}")))
+        .count(db)
+        .await?
+        > 0)
+}
+
+/// Record a grant `issuer_node` sent us, replacing an earlier one for the
+/// same track and dropping the oldest once the peer holds
+/// [`MAX_GRANTS_PER_PEER`].
+pub async fn save_received_grant(
+    db: &DatabaseConnection,
+    hash: &str,
+    node_id: &str,
+    issuer_node: &str,
+    token: &str,
+) -> Result<(), P2pError> {
+    peer_track_grant::Entity::delete_many()
+        .filter(peer_track_grant::Column::IssuerNode.eq(issuer_node))
+        .filter(peer_track_grant::Column::ContentHash.eq(hash))
+        .exec(db)
+        .await?;
+
+    let held = peer_track_grant::Entity::find()
+        .filter(peer_track_grant::Column::IssuerNode.eq(issuer_node))
+        .count(db)
+        .await?;
+    if held >= MAX_GRANTS_PER_PEER {
+        let oldest: Vec<uuid::Uuid> = peer_track_grant::Entity::find()
+            .filter(peer_track_grant::Column::IssuerNode.eq(issuer_node))
+            .order_by_asc(peer_track_grant::Column::IssuedAt)
+            .limit(held + 1 - MAX_GRANTS_PER_PEER)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|g| g.id)
+            .collect();
+        peer_track_grant::Entity::delete_many()
+            .filter(peer_track_grant::Column::Id.is_in(oldest))
+            .exec(db)
+            .await?;
+    }
+
+    save_grant(db, hash, node_id, issuer_node, token).await
+}
+
+/// The newest unexpired token `issuer_node` granted us for `hash`, if any.
+pub async fn grant_token(
+    db: &DatabaseConnection,
+    issuer_node: &str,
+    hash: &str,
+) -> Result<Option<String>, P2pError> {
+    Ok(peer_track_grant::Entity::find()
+        .filter(peer_track_grant::Column::IssuerNode.eq(issuer_node))
+        .filter(peer_track_grant::Column::ContentHash.eq(hash))
+        .filter(peer_track_grant::Column::ExpiresAt.gt(chrono::Utc::now()))
+        .order_by_desc(peer_track_grant::Column::IssuedAt)
+        .one(db)
+        .await?
+        .map(|g| g.token))
+}
+
+/// Delete grants that expired. Returns how many were removed.
+pub async fn prune_expired_grants(db: &DatabaseConnection) -> Result<u64, P2pError> {
+    let res = peer_track_grant::Entity::delete_many()
+        .filter(peer_track_grant::Column::ExpiresAt.lte(chrono::Utc::now()))
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"0123456789abcdef0123456789abcdef";
+    const NOW: i64 = 1_700_000_000;
+
+    // ── sign_grant / verify_grant ──
+
+    #[test]
+    fn test_grant_roundtrip() {
+        let token = sign_grant(KEY, "hash", "peer", NOW);
+        assert!(verify_grant(KEY, "hash", "peer", &token, NOW));
+        assert!(verify_grant(KEY, "hash", "peer", &token, NOW + 3600));
+        assert_eq!(token_issued_at(&token), Some(NOW));
+    }
+
+    #[test]
+    fn test_grant_is_bound_to_hash_peer_and_key() {
+        let token = sign_grant(KEY, "hash", "peer", NOW);
+        assert!(!verify_grant(KEY, "other", "peer", &token, NOW));
+        assert!(!verify_grant(KEY, "hash", "other", &token, NOW));
+        assert!(!verify_grant(
+            b"another key entirely",
+            "hash",
+            "peer",
+            &token,
+            NOW
+        ));
+    }
+
+    #[test]
+    fn test_grant_expires_after_max_age() {
+        let token = sign_grant(KEY, "hash", "peer", NOW);
+        assert!(verify_grant(
+            KEY,
+            "hash",
+            "peer",
+            &token,
+            NOW + GRANT_MAX_AGE_SECS
+        ));
+        assert!(!verify_grant(
+            KEY,
+            "hash",
+            "peer",
+            &token,
+            NOW + GRANT_MAX_AGE_SECS + 1
+        ));
+        // Not valid before it was issued either
+        assert!(!verify_grant(KEY, "hash", "peer", &token, NOW - 1));
+    }
+
+    #[test]
+    fn test_grant_key_is_derived_from_secret_key() {
+        let secret = [7u8; 32];
+        let key = grant_key(&secret);
+        assert_eq!(key, grant_key(&secret));
+        assert_ne!(key, secret);
+        assert_ne!(key, grant_key(&[8u8; 32]));
+    }
+
+    #[test]
+    fn test_tampered_grant_is_rejected() {
+        let token = sign_grant(KEY, "hash", "peer", NOW);
+        // Moving the issue time forward invalidates the MAC
+        let (_, tag) = token.split_once('.').unwrap();
+        let forged = format!("{}.{tag}", NOW + 1000);
+        assert!(!verify_grant(KEY, "hash", "peer", &forged, NOW + 1000));
+
+        assert!(!verify_grant(KEY, "hash", "peer", "garbage", NOW));
+        assert!(!verify_grant(KEY, "hash", "peer", "123.nothex", NOW));
+        assert!(!verify_grant(KEY, "hash", "peer", "", NOW));
+    }
+}
@@ -0,0 +1,447 @@
+//! Retention cleanup of dereferenced remote tracks.
+//!
+//! A health sweep stamps `remote_tracks.dereferenced_at` when it first finds
+//! a track dereferenced and clears it once the track is available again.
+//! At the end of each sweep, [`cleanup_dereferenced_tracks`] takes the
+//! P2P-replicated tracks whose every source has stayed dereferenced longer
+//! than the retention window and either hides them from browsing or deletes
+//! them, following the `p2p_cleanup_*` instance settings:
+//!
+//! | Setting | Example | Meaning |
+//! |---------|---------|---------|
+//! | `p2p_cleanup_retention_days` | `30` | Days a track stays dereferenced before cleanup (0 = never) |
+//! | `p2p_cleanup_mode` | `delete` | `hide` (default) keeps the rows but hides the track; `delete` removes them |
+//!
+//! Hidden tracks come back on their own once a sweep or re-reference finds
+//! a source available again. Local uploads are never touched.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    sea_query::{Expr, Query},
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect,
+};
+use serde::Serialize;
+use soundtime_db::entities::{instance_setting, mb_lookup_queue, remote_track, track};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::error::P2pError;
+use crate::track_health::{TrackCheckItem, TrackHealthManager};
+
+/// Prefix shared by every cleanup setting.
+pub const SETTING_PREFIX: &str = "p2p_cleanup_";
+
+/// Days a track may stay dereferenced before it is cleaned up by default.
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// What happens to a replicated track once its retention has passed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupMode {
+    /// Set `tracks.is_hidden`, keeping the rows so the track can come back
+    #[default]
+    Hide,
+    /// Delete the `remote_tracks` rows and the replicated `tracks` row
+    Delete,
+}
+
+impl fmt::Display for CleanupMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CleanupMode::Hide => f.write_str("hide"),
+            CleanupMode::Delete => f.write_str("delete"),
+        }
+    }
+}
+
+/// Retention policy for dereferenced tracks, from the instance settings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct CleanupPolicy {
+    /// 0 disables the cleanup
+    pub retention_days: u32,
+    pub mode: CleanupMode,
+}
+
+impl Default for CleanupPolicy {
+    fn default() -> Self {
+        Self {
+            retention_days: DEFAULT_RETENTION_DAYS,
+            mode: CleanupMode::default(),
+        }
+    }
+}
+
+impl CleanupPolicy {
+    /// Build the policy from `(key, value)` instance settings. Unrelated keys
+    /// are ignored; invalid values are logged and leave the default.
+    pub fn from_settings<'a>(settings: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut policy = Self::default();
+        for (key, value) in settings {
+            let Some(name) = key.strip_prefix(SETTING_PREFIX) else {
+                continue;
+            };
+            match name {
+                "retention_days" => match value.trim().parse() {
+                    Ok(days) => policy.retention_days = days,
+                    Err(_) => warn!(%key, %value, "ignoring invalid cleanup retention"),
+                },
+                "mode" => match value.trim().to_ascii_lowercase().as_str() {
+                    "hide" => policy.mode = CleanupMode::Hide,
+                    "delete" => policy.mode = CleanupMode::Delete,
+                    _ => warn!(%key, %value, "ignoring invalid cleanup mode"),
+                },
+                _ => {}
+            }
+        }
+        policy
+    }
+
+    /// Read the policy from the `instance_settings` table.
+    pub async fn load(db: &DatabaseConnection) -> Result<Self, P2pError> {
+        let rows = instance_setting::Entity::find()
+            .filter(instance_setting::Column::Key.starts_with(SETTING_PREFIX))
+            .all(db)
+            .await?;
+        Ok(Self::from_settings(
+            rows.iter().map(|r| (r.key.as_str(), r.value.as_str())),
+        ))
+    }
+
+    /// Tracks dereferenced before this instant are due for cleanup; `None`
+    /// when the cleanup is disabled.
+    pub fn cutoff(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (self.retention_days > 0).then(|| now - Duration::days(i64::from(self.retention_days)))
+    }
+}
+
+/// One track hidden or deleted by a cleanup pass.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CleanedTrack {
+    pub track_id: Uuid,
+    pub title: String,
+    pub content_hash: Option<String>,
+}
+
+/// What a cleanup pass did.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct CleanupReport {
+    pub ran_at: DateTime<Utc>,
+    pub mode: CleanupMode,
+    pub tracks: Vec<CleanedTrack>,
+    /// `remote_tracks` rows deleted (always 0 when hiding)
+    pub remote_tracks_deleted: usize,
+}
+
+/// What to clean up, worked out from the expired `remote_tracks` rows.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct CleanupPlan {
+    /// Replicated tracks none of whose sources is still live
+    pub tracks: Vec<Uuid>,
+    /// Expired rows to delete in [`CleanupMode::Delete`]
+    pub remote_tracks: Vec<Uuid>,
+}
+
+/// Tracks in `expired` that are in `replicated` and not in `live` (those
+/// with a source that is available or not yet past the retention), in
+/// first-seen order.
+pub(crate) fn plan_cleanup(
+    expired: &[remote_track::Model],
+    replicated: &HashSet<Uuid>,
+    live: &HashSet<Uuid>,
+) -> CleanupPlan {
+    let mut seen = HashSet::new();
+    let tracks = expired
+        .iter()
+        .filter_map(|rt| rt.local_track_id)
+        .filter(|id| replicated.contains(id) && !live.contains(id) && seen.insert(*id))
+        .collect();
+    CleanupPlan {
+        tracks,
+        remote_tracks: expired.iter().map(|rt| rt.id).collect(),
+    }
+}
+
+/// Hide or delete the P2P-replicated tracks whose sources have all been
+/// dereferenced since before the policy's cutoff. In delete mode the
+/// expired `remote_tracks` rows go too, and their health records are
+/// forgotten. The search index is left to the caller.
+pub async fn cleanup_dereferenced_tracks(
+    manager: &TrackHealthManager,
+    db: &DatabaseConnection,
+    policy: &CleanupPolicy,
+) -> Result<CleanupReport, P2pError> {
+    let now = Utc::now();
+    let mut report = CleanupReport {
+        ran_at: now,
+        mode: policy.mode,
+        ..Default::default()
+    };
+    let Some(cutoff) = policy.cutoff(now) else {
+        return Ok(report);
+    };
+    let cutoff = cutoff.fixed_offset();
+
+    let expired = remote_track::Entity::find()
+        .filter(remote_track::Column::RemoteUri.starts_with("p2p://"))
+        .filter(remote_track::Column::IsAvailable.eq(false))
+        .filter(remote_track::Column::DereferencedAt.lt(cutoff))
+        .all(db)
+        .await?;
+    if expired.is_empty() {
+        return Ok(report);
+    }
+
+    let local_ids: Vec<Uuid> = expired.iter().filter_map(|rt| rt.local_track_id).collect();
+    let mut replicated = track::Entity::find()
+        .select_only()
+        .column(track::Column::Id)
+        .filter(track::Column::Id.is_in(local_ids.clone()))
+        .filter(track::Column::FilePath.starts_with("p2p://"));
+    if policy.mode == CleanupMode::Hide {
+        replicated = replicated.filter(track::Column::IsHidden.eq(false));
+    }
+    let replicated: HashSet<Uuid> = replicated
+        .into_tuple::<Uuid>()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+    let live: HashSet<Uuid> = remote_track::Entity::find()
+        .select_only()
+        .column(remote_track::Column::LocalTrackId)
+        .filter(remote_track::Column::LocalTrackId.is_in(local_ids))
+        .filter(
+            Condition::any()
+                .add(remote_track::Column::IsAvailable.eq(true))
+                .add(remote_track::Column::DereferencedAt.is_null())
+                .add(remote_track::Column::DereferencedAt.gte(cutoff)),
+        )
+        .into_tuple::<Option<Uuid>>()
+        .all(db)
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
+    let plan = plan_cleanup(&expired, &replicated, &live);
+
+    if !plan.tracks.is_empty() {
+        report.tracks = track::Entity::find()
+            .filter(track::Column::Id.is_in(plan.tracks.clone()))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|t| CleanedTrack {
+                track_id: t.id,
+                title: t.title,
+                content_hash: t.content_hash,
+            })
+            .collect();
+    }
+
+    match policy.mode {
+        CleanupMode::Hide => {
+            if !plan.tracks.is_empty() {
+                track::Entity::update_many()
+                    .col_expr(track::Column::IsHidden, Expr::value(true))
+                    .filter(track::Column::Id.is_in(plan.tracks.clone()))
+                    .exec(db)
+                    .await?;
+            }
+        }
+        CleanupMode::Delete => {
+            report.remote_tracks_deleted = remote_track::Entity::delete_many()
+                .filter(remote_track::Column::Id.is_in(plan.remote_tracks.clone()))
+                .exec(db)
+                .await?
+                .rows_affected as usize;
+            if !plan.tracks.is_empty() {
+                // Playlist entries, favorites and history cascade
+                track::Entity::delete_many()
+                    .filter(track::Column::Id.is_in(plan.tracks.clone()))
+                    .exec(db)
+                    .await?;
+            }
+            for item in expired.iter().filter_map(TrackCheckItem::from_remote) {
+                manager.remove_record(&item.content_hash).await;
+            }
+        }
+    }
+
+    for cleaned in &report.tracks {
+        debug!(
+            track_id = %cleaned.track_id,
+            title = %cleaned.title,
+            mode = %policy.mode,
+            "cleaned up dereferenced track"
+        );
+    }
+    if !report.tracks.is_empty() || report.remote_tracks_deleted > 0 {
+        info!(
+            mode = %policy.mode,
+            retention_days = policy.retention_days,
+            tracks = report.tracks.len(),
+            remote_tracks_deleted = report.remote_tracks_deleted,
+            "cleaned up dereferenced tracks"
+        );
+    }
+    Ok(report)
+}
+
+/// Show the hidden tracks behind `remote_track_ids` again, e.g. after they
+/// were re-referenced. Tracks still pending MusicBrainz verification stay
+/// hidden. Returns how many were unhidden.
+pub async fn unhide_tracks(
+    db: &DatabaseConnection,
+    remote_track_ids: &[Uuid],
+) -> Result<u64, P2pError> {
+    if remote_track_ids.is_empty() {
+        return Ok(0);
+    }
+    let local_ids: Vec<Uuid> = remote_track::Entity::find()
+        .select_only()
+        .column(remote_track::Column::LocalTrackId)
+        .filter(remote_track::Column::Id.is_in(remote_track_ids.iter().copied()))
+        .into_tuple::<Option<Uuid>>()
+        .all(db)
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
+    if local_ids.is_empty() {
+        return Ok(0);
+    }
+    let res = track::Entity::update_many()
+        .col_expr(track::Column::IsHidden, Expr::value(false))
+        .filter(track::Column::Id.is_in(local_ids))
+        .filter(track::Column::IsHidden.eq(true))
+        // Blocked hashes stay hidden until unblocked
+        .filter(track::Column::HiddenByBlock.eq(false))
+        .filter(
+            track::Column::Id.not_in_subquery(
+                Query::select()
+                    .column(mb_lookup_queue::Column::TrackId)
+                    .from(mb_lookup_queue::Entity)
+                    .and_where(mb_lookup_queue::Column::PendingVerification.eq(true))
+                    .to_owned(),
+            ),
+        )
+        .exec(db)
+        .await?;
+    if res.rows_affected > 0 {
+        info!(count = res.rows_affected, "unhid re-referenced tracks");
+    }
+    Ok(res.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expired_row(local_track_id: Option<Uuid>, hash: &str) -> remote_track::Model {
+        remote_track::Model {
+            id: Uuid::new_v4(),
+            local_track_id,
+            musicbrainz_id: None,
+            title: "Gone".into(),
+            artist_name: "Artist".into(),
+            album_title: None,
+            instance_domain: "p2p://origin".into(),
+            remote_uri: format!("p2p://origin/{hash}"),
+            remote_stream_url: String::new(),
+            bitrate: None,
+            sample_rate: None,
+            format: None,
+            is_available: false,
+            last_checked_at: None,
+            dereferenced_at: Some((Utc::now() - Duration::days(60)).into()),
+            signature: None,
+            created_at: Utc::now().into(),
+        }
+    }
+
+    // ── CleanupPolicy ────────────────────────────────────────────────
+
+    #[test]
+    fn test_policy_defaults() {
+        let policy = CleanupPolicy::from_settings(std::iter::empty());
+        assert_eq!(policy.retention_days, DEFAULT_RETENTION_DAYS);
+        assert_eq!(policy.mode, CleanupMode::Hide);
+    }
+
+    #[test]
+    fn test_policy_from_settings() {
+        let policy = CleanupPolicy::from_settings([
+            ("p2p_cleanup_retention_days", " 7 "),
+            ("p2p_cleanup_mode", "Delete"),
+            ("p2p_replication_require_album", "true"),
+        ]);
+        assert_eq!(policy.retention_days, 7);
+        assert_eq!(policy.mode, CleanupMode::Delete);
+    }
+
+    #[test]
+    fn test_policy_invalid_values_keep_defaults() {
+        let policy = CleanupPolicy::from_settings([
+            ("p2p_cleanup_retention_days", "-3"),
+            ("p2p_cleanup_mode", "purge"),
+        ]);
+        assert_eq!(policy, CleanupPolicy::default());
+    }
+
+    #[test]
+    fn test_policy_cutoff() {
+        let now = Utc::now();
+        let policy = CleanupPolicy {
+            retention_days: 30,
+            mode: CleanupMode::Hide,
+        };
+        assert_eq!(policy.cutoff(now), Some(now - Duration::days(30)));
+        let disabled = CleanupPolicy {
+            retention_days: 0,
+            ..policy
+        };
+        assert_eq!(disabled.cutoff(now), None);
+    }
+
+    #[test]
+    fn test_mode_serializes_snake_case() {
+        assert_eq!(serde_json::to_value(CleanupMode::Delete).unwrap(), "delete");
+        assert_eq!(CleanupMode::Hide.to_string(), "hide");
+    }
+
+    // ── plan_cleanup ─────────────────────────────────────────────────
+
+    #[test]
+    fn test_plan_cleans_replicated_tracks_without_live_sources() {
+        let gone = Uuid::new_v4();
+        let rows = vec![expired_row(Some(gone), "h1"), expired_row(Some(gone), "h1")];
+        let plan = plan_cleanup(&rows, &HashSet::from([gone]), &HashSet::new());
+        assert_eq!(plan.tracks, vec![gone]);
+        assert_eq!(plan.remote_tracks.len(), 2);
+    }
+
+    #[test]
+    fn test_plan_keeps_tracks_with_a_live_source() {
+        let still_served = Uuid::new_v4();
+        let rows = vec![expired_row(Some(still_served), "h1")];
+        let plan = plan_cleanup(
+            &rows,
+            &HashSet::from([still_served]),
+            &HashSet::from([still_served]),
+        );
+        assert!(plan.tracks.is_empty());
+        assert_eq!(plan.remote_tracks, vec![rows[0].id]);
+    }
+
+    #[test]
+    fn test_plan_never_touches_local_uploads() {
+        let local = Uuid::new_v4();
+        let rows = vec![expired_row(Some(local), "h1"), expired_row(None, "h2")];
+        let plan = plan_cleanup(&rows, &HashSet::new(), &HashSet::new());
+        assert!(plan.tracks.is_empty());
+        assert_eq!(plan.remote_tracks.len(), 2);
+    }
+}
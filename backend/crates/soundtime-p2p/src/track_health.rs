@@ -17,20 +17,27 @@
 //! - Chunked iteration to bound memory usage
 
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use chrono::Utc;
+use iroh_blobs::Hash;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
-    Set,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set, Statement,
 };
-use soundtime_db::entities::remote_track;
+use serde::Serialize;
+use soundtime_db::entities::{health_sweep_run, remote_track, track_recovery_attempt};
 use tokio::sync::{watch, RwLock, Semaphore};
-use tracing::{debug, info, warn};
+use tracing::{debug, error, info, warn};
 
 use crate::error::P2pError;
+use crate::metrics::P2P_METRICS;
+use crate::track_cleanup::{
+    cleanup_dereferenced_tracks, unhide_tracks, CleanupPolicy, CleanupReport,
+};
 
 // ── Configuration ────────────────────────────────────────────────────
 
@@ -40,12 +47,31 @@ const DEFAULT_MAX_CONCURRENT_RECOVERIES: usize = 32;
 /// Maximum retries before dereferencing a track.
 const MAX_RETRY_ATTEMPTS: u32 = 3;
 
+/// Cooldown before `auto_repair_on_failure` retries a degraded track, by
+/// number of failed attempts so far (the last entry repeats).
+const REPAIR_BACKOFF_SECS: [i64; 3] = [30, 5 * 60, 30 * 60];
+
 /// Default monitoring interval (10 minutes).
 const DEFAULT_MONITOR_INTERVAL_SECS: u64 = 600;
 
 /// Batch size for processing tracks during monitoring scans.
 const MONITOR_BATCH_SIZE: usize = 500;
 
+/// Default fraction of locally cached remote tracks re-hashed per sweep.
+const DEFAULT_INTEGRITY_SAMPLE_RATE: f64 = 0.05;
+
+/// Largest `P2P_HEALTH_BATCH_SIZE` accepted; larger values are capped.
+pub const MAX_MONITOR_BATCH_SIZE: usize = 10_000;
+
+/// Largest `P2P_HEALTH_MAX_CONCURRENT` accepted; larger values are capped.
+pub const MAX_CONCURRENT_RECOVERIES_LIMIT: usize = 1024;
+
+/// Days of sweep history kept in `health_sweep_runs`.
+pub const HEALTH_HISTORY_RETENTION_DAYS: i64 = 90;
+
+/// Newest rows kept in `track_recovery_attempts`.
+pub const RECOVERY_LOG_MAX_ROWS: u64 = 10_000;
+
 // ── Types ────────────────────────────────────────────────────────────
 
 /// Track health status after a recovery or check attempt.
@@ -61,6 +87,17 @@ pub enum HealthStatus {
     Dereferenced,
 }
 
+impl HealthStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HealthStatus::Healthy => "healthy",
+            HealthStatus::Recovered => "recovered",
+            HealthStatus::Degraded { .. } => "degraded",
+            HealthStatus::Dereferenced => "dereferenced",
+        }
+    }
+}
+
 /// Record of a track's health state, including retry history.
 #[derive(Debug, Clone)]
 pub struct TrackHealthRecord {
@@ -72,10 +109,30 @@ pub struct TrackHealthRecord {
     pub failed_attempts: u32,
     /// Last attempt timestamp.
     pub last_attempt: Option<chrono::DateTime<chrono::Utc>>,
+    /// `auto_repair_on_failure` leaves the track alone until then; set
+    /// from `failed_attempts` on every failure, see [`repair_backoff`].
+    pub next_attempt_after: Option<chrono::DateTime<chrono::Utc>>,
     /// Current health status.
     pub status: HealthStatus,
 }
 
+impl TrackHealthRecord {
+    /// Whether a repair of this track is still cooling down at `now`.
+    /// Only degraded tracks cool down; dereferenced ones are only probed
+    /// without adding failures.
+    pub fn in_cooldown(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        matches!(self.status, HealthStatus::Degraded { .. })
+            && self.next_attempt_after.is_some_and(|after| now < after)
+    }
+}
+
+/// Wait before the next repair of a track after `failed_attempts`
+/// consecutive failures: 30 s, 5 min, then 30 min.
+pub fn repair_backoff(failed_attempts: u32) -> chrono::Duration {
+    let step = (failed_attempts.max(1) as usize - 1).min(REPAIR_BACKOFF_SECS.len() - 1);
+    chrono::Duration::seconds(REPAIR_BACKOFF_SECS[step])
+}
+
 /// Information about a peer's copy of a track for duplicate resolution.
 #[derive(Debug, Clone)]
 pub struct PeerTrackInfo {
@@ -91,6 +148,12 @@ pub struct PeerTrackInfo {
     pub is_online: bool,
     /// File size in bytes.
     pub file_size: i64,
+    /// Whether the admin marked the peer as trusted.
+    pub trusted: bool,
+    /// Peer's mean ping RTT over its recent interactions.
+    pub avg_latency_ms: Option<u32>,
+    /// Share of recent pings and fetches the peer answered, from 0 to 1.
+    pub success_rate: Option<f32>,
 }
 
 /// Result of a recovery attempt.
@@ -108,26 +171,189 @@ pub struct RecoveryResult {
     pub error: Option<String>,
 }
 
+/// One run of [`auto_repair_on_failure`], as kept in the recovery log.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecoveryAttempt {
+    pub content_hash: String,
+    pub origin_node: String,
+    /// Peer that served the track, or the last one tried
+    pub peer_tried: Option<String>,
+    pub success: bool,
+    pub error_message: Option<String>,
+    pub attempted_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<track_recovery_attempt::Model> for RecoveryAttempt {
+    fn from(row: track_recovery_attempt::Model) -> Self {
+        Self {
+            content_hash: row.content_hash,
+            origin_node: row.origin_node,
+            peer_tried: row.peer_tried,
+            success: row.success,
+            error_message: row.error_message,
+            attempted_at: row.attempted_at.with_timezone(&Utc),
+        }
+    }
+}
+
+/// When the health monitor runs its sweeps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthSchedule {
+    /// A fixed delay between the end of one sweep and the start of the next.
+    Interval(std::time::Duration),
+    /// A cron expression with a seconds field, evaluated in UTC
+    /// (e.g. `"0 */30 * * * *"` for every 30 minutes).
+    Cron(String),
+}
+
+impl HealthSchedule {
+    /// Parse a cron expression, rejecting ones the `cron` crate cannot read
+    /// or that never fire.
+    pub fn cron(expr: &str) -> Result<Self, String> {
+        let expr = expr.trim();
+        let schedule = cron::Schedule::from_str(expr).map_err(|e| e.to_string())?;
+        if schedule.upcoming(Utc).next().is_none() {
+            return Err("schedule has no upcoming times".to_string());
+        }
+        Ok(HealthSchedule::Cron(expr.to_string()))
+    }
+
+    /// How long to wait before the next sweep. `None` if a cron schedule
+    /// has no upcoming time.
+    pub fn next_delay(&self) -> Option<std::time::Duration> {
+        match self {
+            HealthSchedule::Interval(interval) => Some(*interval),
+            HealthSchedule::Cron(expr) => {
+                let next = cron::Schedule::from_str(expr).ok()?.upcoming(Utc).next()?;
+                // A time in the past (clock moved) means "run now"
+                Some((next - Utc::now()).to_std().unwrap_or_default())
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for HealthSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HealthSchedule::Interval(interval) => write!(f, "every {}s", interval.as_secs()),
+            HealthSchedule::Cron(expr) => write!(f, "cron '{expr}'"),
+        }
+    }
+}
+
 /// Configuration for the health monitor.
 #[derive(Debug, Clone)]
 pub struct HealthMonitorConfig {
     /// Maximum concurrent recovery requests.
     pub max_concurrent_recoveries: usize,
-    /// Interval between monitoring scans (seconds).
-    pub monitor_interval_secs: u64,
+    /// When monitoring scans run.
+    pub schedule: HealthSchedule,
     /// Maximum retries before dereferencing.
     pub max_retry_attempts: u32,
     /// Batch size for processing during scans.
     pub batch_size: usize,
+    /// Fraction (0 to 1) of locally cached remote tracks whose bytes each
+    /// sweep re-hashes; 0 only checks that blobs exist.
+    pub integrity_sample_rate: f64,
 }
 
 impl Default for HealthMonitorConfig {
     fn default() -> Self {
         Self {
             max_concurrent_recoveries: DEFAULT_MAX_CONCURRENT_RECOVERIES,
-            monitor_interval_secs: DEFAULT_MONITOR_INTERVAL_SECS,
+            schedule: HealthSchedule::Interval(std::time::Duration::from_secs(
+                DEFAULT_MONITOR_INTERVAL_SECS,
+            )),
             max_retry_attempts: MAX_RETRY_ATTEMPTS,
             batch_size: MONITOR_BATCH_SIZE,
+            integrity_sample_rate: DEFAULT_INTEGRITY_SAMPLE_RATE,
+        }
+    }
+}
+
+impl HealthMonitorConfig {
+    /// Default configuration, overridden by the environment:
+    ///
+    /// - `P2P_HEALTH_INTERVAL_SECS`: seconds between sweeps
+    /// - `P2P_HEALTH_SCHEDULE`: a cron expression, used instead of the
+    ///   interval when both are set
+    /// - `P2P_HEALTH_MAX_RETRIES`: failed fetches before a track is
+    ///   dereferenced
+    /// - `P2P_HEALTH_BATCH_SIZE`: remote tracks read per page, at most
+    ///   [`MAX_MONITOR_BATCH_SIZE`]
+    /// - `P2P_HEALTH_MAX_CONCURRENT`: concurrent recoveries, at most
+    ///   [`MAX_CONCURRENT_RECOVERIES_LIMIT`]
+    /// - `P2P_HEALTH_INTEGRITY_SAMPLE`: fraction of cached blobs re-hashed
+    ///   per sweep, from 0 to 1
+    ///
+    /// Values that do not parse, or are zero (except the sample fraction),
+    /// are logged and ignored.
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let mut config = Self::default();
+        if let Some(secs) = positive_var(&var, "P2P_HEALTH_INTERVAL_SECS") {
+            config.schedule = HealthSchedule::Interval(std::time::Duration::from_secs(secs));
+        }
+        if let Some(expr) = var("P2P_HEALTH_SCHEDULE") {
+            if !expr.trim().is_empty() {
+                match HealthSchedule::cron(&expr) {
+                    Ok(schedule) => config.schedule = schedule,
+                    Err(e) => warn!(
+                        schedule = %expr,
+                        "ignoring invalid P2P_HEALTH_SCHEDULE: {e}"
+                    ),
+                }
+            }
+        }
+        if let Some(retries) = positive_var(&var, "P2P_HEALTH_MAX_RETRIES") {
+            config.max_retry_attempts = u32::try_from(retries).unwrap_or(u32::MAX);
+        }
+        if let Some(size) = positive_var(&var, "P2P_HEALTH_BATCH_SIZE") {
+            config.batch_size = capped("P2P_HEALTH_BATCH_SIZE", size, MAX_MONITOR_BATCH_SIZE);
+        }
+        if let Some(max) = positive_var(&var, "P2P_HEALTH_MAX_CONCURRENT") {
+            config.max_concurrent_recoveries = capped(
+                "P2P_HEALTH_MAX_CONCURRENT",
+                max,
+                MAX_CONCURRENT_RECOVERIES_LIMIT,
+            );
+        }
+        if let Some(value) = var("P2P_HEALTH_INTEGRITY_SAMPLE") {
+            match value.trim().parse::<f64>() {
+                Ok(rate) if (0.0..=1.0).contains(&rate) => config.integrity_sample_rate = rate,
+                _ => warn!(
+                    %value,
+                    "ignoring P2P_HEALTH_INTEGRITY_SAMPLE: expected a fraction from 0 to 1"
+                ),
+            }
+        }
+        config
+    }
+}
+
+/// The value of `name` if it is a positive integer. Other values are logged
+/// and ignored.
+fn positive_var(var: &impl Fn(&str) -> Option<String>, name: &str) -> Option<u64> {
+    let value = var(name)?;
+    match value.trim().parse::<u64>() {
+        Ok(n) if n > 0 => Some(n),
+        _ => {
+            warn!(%value, "ignoring {name}: expected a positive integer");
+            None
+        }
+    }
+}
+
+/// `value`, or `max` if it is larger.
+fn capped(name: &str, value: u64, max: usize) -> usize {
+    match usize::try_from(value) {
+        Ok(v) if v <= max => v,
+        _ => {
+            warn!(value, max, "{name} is too large, using {max}");
+            max
         }
     }
 }
@@ -144,6 +370,8 @@ pub struct TrackHealthManager {
     recovery_semaphore: Arc<Semaphore>,
     /// Configuration.
     config: HealthMonitorConfig,
+    /// The last sweep cleanup that hid or deleted tracks.
+    last_cleanup: RwLock<Option<CleanupReport>>,
 }
 
 impl Default for TrackHealthManager {
@@ -165,6 +393,7 @@ impl TrackHealthManager {
             records: RwLock::new(HashMap::new()),
             recovery_semaphore: semaphore,
             config,
+            last_cleanup: RwLock::new(None),
         }
     }
 
@@ -173,6 +402,12 @@ impl TrackHealthManager {
         &self.config
     }
 
+    /// The last sweep cleanup that hid or deleted tracks, if any since
+    /// startup.
+    pub async fn last_cleanup(&self) -> Option<CleanupReport> {
+        self.last_cleanup.read().await.clone()
+    }
+
     /// Record a failed access attempt for a track.
     /// Returns the updated health status.
     pub async fn record_failure(&self, content_hash: &str, origin_node: &str) -> HealthStatus {
@@ -184,11 +419,14 @@ impl TrackHealthManager {
                 origin_node: origin_node.to_string(),
                 failed_attempts: 0,
                 last_attempt: None,
+                next_attempt_after: None,
                 status: HealthStatus::Healthy,
             });
 
+        let now = chrono::Utc::now();
         record.failed_attempts += 1;
-        record.last_attempt = Some(chrono::Utc::now());
+        record.last_attempt = Some(now);
+        record.next_attempt_after = Some(now + repair_backoff(record.failed_attempts));
 
         if record.failed_attempts >= self.config.max_retry_attempts {
             record.status = HealthStatus::Dereferenced;
@@ -201,12 +439,14 @@ impl TrackHealthManager {
         record.status.clone()
     }
 
-    /// Record a successful recovery/access for a track, resetting its failure count.
+    /// Record a successful recovery/access for a track, resetting its
+    /// failure count and repair cooldown.
     pub async fn record_success(&self, content_hash: &str) {
         let mut records = self.records.write().await;
         if let Some(record) = records.get_mut(content_hash) {
             record.failed_attempts = 0;
             record.last_attempt = Some(chrono::Utc::now());
+            record.next_attempt_after = None;
             record.status = HealthStatus::Recovered;
         }
     }
@@ -221,9 +461,11 @@ impl TrackHealthManager {
                 origin_node: origin_node.to_string(),
                 failed_attempts: 0,
                 last_attempt: None,
+                next_attempt_after: None,
                 status: HealthStatus::Healthy,
             });
         record.failed_attempts = 0;
+        record.next_attempt_after = None;
         record.status = HealthStatus::Healthy;
     }
 
@@ -272,13 +514,15 @@ impl TrackHealthManager {
     ///
     /// Called when a dereferenced track becomes available again (the peer
     /// came back online, the blob was re-announced, etc.).  Resets
-    /// `failed_attempts` and sets the status back to `Healthy`.
+    /// `failed_attempts` and the repair cooldown and sets the status back to
+    /// `Healthy`.
     pub async fn re_reference(&self, content_hash: &str, origin_node: &str) {
         let mut records = self.records.write().await;
         if let Some(record) = records.get_mut(content_hash) {
             if record.status == HealthStatus::Dereferenced {
                 record.failed_attempts = 0;
                 record.last_attempt = Some(chrono::Utc::now());
+                record.next_attempt_after = None;
                 record.status = HealthStatus::Healthy;
                 info!(
                     hash = %content_hash,
@@ -334,18 +578,47 @@ impl TrackHealthManager {
         records.values().cloned().collect()
     }
 
+    /// Degraded and dereferenced tracks, most recently attempted first,
+    /// skipping `offset` and returning at most `limit`, with their total.
+    pub async fn unhealthy_page(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> (usize, Vec<TrackHealthRecord>) {
+        let records = self.records.read().await;
+        let mut unhealthy: Vec<&TrackHealthRecord> = records
+            .values()
+            .filter(|r| {
+                matches!(
+                    r.status,
+                    HealthStatus::Degraded { .. } | HealthStatus::Dereferenced
+                )
+            })
+            .collect();
+        // `None` sorts first ascending, so last when reversed
+        unhealthy.sort_by(|a, b| {
+            b.last_attempt
+                .cmp(&a.last_attempt)
+                .then_with(|| a.content_hash.cmp(&b.content_hash))
+        });
+        let total = unhealthy.len();
+        let page = unhealthy
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .cloned()
+            .collect();
+        (total, page)
+    }
+
     /// Count tracks by health status.
     pub async fn status_counts(&self) -> HashMap<String, usize> {
         let records = self.records.read().await;
         let mut counts = HashMap::new();
         for record in records.values() {
-            let key = match &record.status {
-                HealthStatus::Healthy => "healthy",
-                HealthStatus::Recovered => "recovered",
-                HealthStatus::Degraded { .. } => "degraded",
-                HealthStatus::Dereferenced => "dereferenced",
-            };
-            *counts.entry(key.to_string()).or_insert(0) += 1;
+            *counts
+                .entry(record.status.as_str().to_string())
+                .or_insert(0) += 1;
         }
         counts
     }
@@ -384,26 +657,46 @@ pub fn quality_score(info: &PeerTrackInfo) -> u64 {
         score += 2000;
     }
 
-    score
+    score + responsiveness_bonus(info)
+}
+
+/// Bonus (0-300) for a peer that answers reliably (up to 200) and fast (up
+/// to 100, full marks at 100 ms or less). Peers without history get none.
+fn responsiveness_bonus(info: &PeerTrackInfo) -> u64 {
+    let reliability = info
+        .success_rate
+        .map_or(0, |rate| (rate.clamp(0.0, 1.0) * 200.0) as u64);
+    let latency = info
+        .avg_latency_ms
+        .map_or(0, |ms| 100 * 100 / u64::from(ms.max(100)));
+    reliability + latency
 }
 
 /// Select the best peer copy from a list of duplicate track sources.
+/// Online copies from trusted peers win over better quality elsewhere.
 /// Returns `None` if no copies are available.
 pub fn select_best_copy(copies: &[PeerTrackInfo]) -> Option<&PeerTrackInfo> {
     if copies.is_empty() {
         return None;
     }
 
-    // First try to find the best from online peers
+    // First try to find the best from online peers, trusted ones first
     let online_copies: Vec<&PeerTrackInfo> = copies.iter().filter(|c| c.is_online).collect();
     if !online_copies.is_empty() {
-        return online_copies.into_iter().max_by_key(|c| quality_score(c));
+        return online_copies
+            .into_iter()
+            .max_by_key(|c| (c.trusted, quality_score(c)));
     }
 
     // Fall back to best overall (even offline — might come online later)
     copies.iter().max_by_key(|c| quality_score(c))
 }
 
+/// Sort copies best first by [`quality_score`].
+pub fn sort_by_quality(copies: &mut [PeerTrackInfo]) {
+    copies.sort_by_key(|c| std::cmp::Reverse(quality_score(c)));
+}
+
 /// Group track copies by content hash and select the best source for each.
 pub fn resolve_duplicates(all_copies: &[PeerTrackInfo], track_hash: &str) -> Option<PeerTrackInfo> {
     // Filter to copies that belong to this track hash
@@ -425,8 +718,28 @@ pub struct TrackCheckItem {
     pub title: String,
 }
 
+impl TrackCheckItem {
+    /// The item for a P2P remote track; `None` if its `remote_uri` is not
+    /// of the form `p2p://node_id/hash`.
+    pub fn from_remote(rt: &remote_track::Model) -> Option<Self> {
+        let hash = rt
+            .remote_uri
+            .strip_prefix("p2p://")
+            .and_then(|rest| rest.split('/').nth(1))?;
+        let origin = rt
+            .instance_domain
+            .strip_prefix("p2p://")
+            .unwrap_or(&rt.instance_domain);
+        Some(Self {
+            content_hash: hash.to_string(),
+            origin_node: origin.to_string(),
+            title: rt.title.clone(),
+        })
+    }
+}
+
 /// Result of a batch health check.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BatchCheckResult {
     /// Total tracks checked.
     pub total_checked: usize,
@@ -442,6 +755,11 @@ pub struct BatchCheckResult {
     pub re_referenced: usize,
     /// Tracks whose origin peer is currently offline (data not cached locally).
     pub unavailable_source: usize,
+    /// Cached blobs whose bytes no longer matched their content hash and
+    /// were deleted.
+    pub corrupted: usize,
+    /// Long-dereferenced tracks hidden or deleted after the sweep.
+    pub cleaned_up: usize,
 }
 
 impl BatchCheckResult {
@@ -454,6 +772,8 @@ impl BatchCheckResult {
             dereferenced: 0,
             re_referenced: 0,
             unavailable_source: 0,
+            corrupted: 0,
+            cleaned_up: 0,
         }
     }
 
@@ -465,6 +785,8 @@ impl BatchCheckResult {
         self.dereferenced += other.dereferenced;
         self.re_referenced += other.re_referenced;
         self.unavailable_source += other.unavailable_source;
+        self.corrupted += other.corrupted;
+        self.cleaned_up += other.cleaned_up;
     }
 }
 
@@ -600,8 +922,141 @@ pub trait TrackFetcher: Send + Sync + 'static {
     async fn peer_is_online(&self, peer_id: &str) -> bool;
 
     /// List known alternative sources for a given content hash.
-    /// Returns `PeerTrackInfo` entries from other peers that announced this track.
+    /// Returns `PeerTrackInfo` entries from other peers that announced this
+    /// track, sorted best first by [`quality_score`].
     async fn alternative_sources(&self, hash: &str) -> Vec<PeerTrackInfo>;
+
+    /// Called when `peer_id` served data that failed hash verification, so
+    /// implementations can drop state tied to that peer (e.g. a pooled
+    /// connection). The default does nothing.
+    async fn reject_source(&self, _peer_id: &str) {}
+
+    /// Called once at the end of every [`auto_repair_on_failure`], whatever
+    /// the outcome, so implementations can keep an audit trail. The default
+    /// does nothing.
+    async fn record_recovery_attempt(&self, _attempt: &RecoveryAttempt) {}
+
+    /// Read the locally stored bytes of `hash`: `Ok(None)` if they are not
+    /// stored, an error if they are but cannot be read. The default never
+    /// finds any, which skips integrity checks.
+    async fn read_local_blob(&self, _hash: &str) -> Result<Option<Bytes>, P2pError> {
+        Ok(None)
+    }
+
+    /// Delete the locally stored copy of `hash`, or stop using it until it
+    /// is deleted, so the next play fetches it again. The default does
+    /// nothing.
+    async fn delete_local_blob(&self, _hash: &str) {}
+
+    /// Called after a sweep's cleanup hid or deleted tracks, e.g. to drop
+    /// them from the search index. The default does nothing.
+    async fn tracks_cleaned_up(&self, _report: &CleanupReport) {}
+
+    /// Called with the result of every [`run_health_sweep`]. The default
+    /// does nothing.
+    async fn sweep_finished(&self, _result: &BatchCheckResult) {}
+}
+
+// ── Verified fetch ───────────────────────────────────────────────────
+
+/// Check that `data` has the BLAKE3 hash `expected`.
+pub fn verify_blob(expected: &Hash, data: &[u8]) -> Result<(), P2pError> {
+    let actual = Hash::new(data);
+    if actual == *expected {
+        Ok(())
+    } else {
+        Err(P2pError::HashMismatch {
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        })
+    }
+}
+
+/// Fetch `hash` from each of `sources` in turn and return the first copy
+/// whose BLAKE3 hash verifies, together with the peer that served it.
+///
+/// A peer that sends mismatching bytes gets a failure recorded against the
+/// track in `manager` and is handed to [`TrackFetcher::reject_source`]
+/// before the next source is tried. Unverified bytes are never returned, so
+/// they cannot reach the blob store.
+pub async fn fetch_verified<F: TrackFetcher>(
+    manager: &TrackHealthManager,
+    fetcher: &F,
+    hash: Hash,
+    sources: &[String],
+) -> Result<(Bytes, String), P2pError> {
+    let hash_str = hash.to_string();
+    let mut last_err = P2pError::TrackNotFound(hash_str.clone());
+
+    for peer_id in sources {
+        let result = fetcher
+            .fetch_track(peer_id, &hash_str)
+            .await
+            .and_then(|data| verify_blob(&hash, &data).map(|()| data));
+        match result {
+            Ok(data) => return Ok((data, peer_id.clone())),
+            Err(e @ P2pError::HashMismatch { .. }) => {
+                warn!(hash = %hash_str, peer = %peer_id, "peer served corrupt blob: {e}");
+                manager.record_failure(&hash_str, peer_id).await;
+                fetcher.reject_source(peer_id).await;
+                last_err = e;
+            }
+            Err(e) => {
+                debug!(hash = %hash_str, peer = %peer_id, "fetch from source failed: {e}");
+                last_err = e;
+            }
+        }
+    }
+
+    Err(last_err)
+}
+
+// ── Integrity sampling ───────────────────────────────────────────────
+
+/// Re-hash the locally stored bytes of a random `sample_rate` fraction of
+/// `items` and return how many no longer match their content hash.
+///
+/// A blob that fails the check, or cannot be read at all, is deleted through
+/// [`TrackFetcher::delete_local_blob`] and a failure is recorded against the
+/// track, so its next play fetches a fresh copy. Tracks not cached locally
+/// are skipped.
+pub async fn verify_cached_blobs<F: TrackFetcher + ?Sized>(
+    manager: &TrackHealthManager,
+    fetcher: &F,
+    items: &[TrackCheckItem],
+    sample_rate: f64,
+) -> usize {
+    if sample_rate <= 0.0 {
+        return 0;
+    }
+    let mut corrupted = 0;
+    for item in items {
+        if rand::random::<f64>() >= sample_rate {
+            continue;
+        }
+        let Ok(hash) = Hash::from_str(&item.content_hash) else {
+            continue;
+        };
+        let error = match fetcher.read_local_blob(&item.content_hash).await {
+            Ok(None) => continue,
+            Ok(Some(data)) => match verify_blob(&hash, &data) {
+                Ok(()) => continue,
+                Err(e) => e,
+            },
+            Err(e) => e,
+        };
+        warn!(
+            hash = %item.content_hash,
+            title = %item.title,
+            "cached blob failed integrity check, deleting it: {error}"
+        );
+        fetcher.delete_local_blob(&item.content_hash).await;
+        manager
+            .record_failure(&item.content_hash, &item.origin_node)
+            .await;
+        corrupted += 1;
+    }
+    corrupted
 }
 
 // ── Auto-repair on failure ───────────────────────────────────────────
@@ -614,19 +1069,141 @@ pub trait TrackFetcher: Send + Sync + 'static {
 /// 3. Record success/failure in the health manager.
 /// 4. After `max_retry_attempts` consecutive failures the track is dereferenced.
 ///
+/// A degraded track is not retried until its cooldown (see
+/// [`repair_backoff`]) has passed, so repeated playback attempts against a
+/// briefly offline peer do not use up its retries. Calls inside the window
+/// return `Degraded` at once without contacting any peer or counting a
+/// failure.
+///
 /// Returns the `RecoveryResult` describing what happened.
 pub async fn auto_repair_on_failure<F: TrackFetcher>(
     manager: &TrackHealthManager,
     fetcher: &F,
     content_hash: &str,
     origin_node: &str,
+) -> RecoveryResult {
+    if let Some(record) = manager.get_record(content_hash).await {
+        if record.in_cooldown(Utc::now()) {
+            debug!(hash = %content_hash, next_attempt_after = ?record.next_attempt_after, "auto-repair: cooling down, skipping");
+            return RecoveryResult {
+                content_hash: content_hash.to_string(),
+                success: false,
+                status: record.status,
+                peer_used: None,
+                error: record
+                    .next_attempt_after
+                    .map(|after| format!("repair cooling down until {}", after.to_rfc3339())),
+            };
+        }
+    }
+
+    run_repair(manager, fetcher, content_hash, origin_node, None).await
+}
+
+/// Repair a track right away on an admin's request: like
+/// [`auto_repair_on_failure`], but without waiting for its cooldown. With
+/// `peer_id`, only that peer is asked instead of the origin and the best
+/// alternative.
+pub async fn repair_track_now<F: TrackFetcher>(
+    manager: &TrackHealthManager,
+    fetcher: &F,
+    content_hash: &str,
+    origin_node: &str,
+    peer_id: Option<&str>,
+) -> RecoveryResult {
+    run_repair(manager, fetcher, content_hash, origin_node, peer_id).await
+}
+
+/// Run [`repair_from_sources`] and add the attempt to the recovery log.
+async fn run_repair<F: TrackFetcher>(
+    manager: &TrackHealthManager,
+    fetcher: &F,
+    content_hash: &str,
+    origin_node: &str,
+    requested_peer: Option<&str>,
+) -> RecoveryResult {
+    let mut peer_tried = None;
+    let result = repair_from_sources(
+        manager,
+        fetcher,
+        content_hash,
+        origin_node,
+        requested_peer,
+        &mut peer_tried,
+    )
+    .await;
+    fetcher
+        .record_recovery_attempt(&RecoveryAttempt {
+            content_hash: content_hash.to_string(),
+            origin_node: origin_node.to_string(),
+            peer_tried: result.peer_used.clone().or(peer_tried),
+            success: result.success,
+            error_message: result.error.clone(),
+            attempted_at: Utc::now(),
+        })
+        .await;
+    result
+}
+
+/// The body of [`auto_repair_on_failure`]. `peer_tried` is set to each peer
+/// before it is asked. With `requested_peer`, only that peer is asked.
+async fn repair_from_sources<F: TrackFetcher>(
+    manager: &TrackHealthManager,
+    fetcher: &F,
+    content_hash: &str,
+    origin_node: &str,
+    requested_peer: Option<&str>,
+    peer_tried: &mut Option<String>,
 ) -> RecoveryResult {
     let was_dereferenced = manager.is_dereferenced(content_hash).await;
 
     // Acquire a recovery permit for backpressure
     let _permit = manager.acquire_recovery_permit().await;
 
+    // 0. A peer chosen by an admin replaces the usual sources
+    if let Some(peer_id) = requested_peer {
+        *peer_tried = Some(peer_id.to_string());
+        info!(hash = %content_hash, peer = %peer_id, "repair: trying requested peer");
+        let err = match fetcher.fetch_track(peer_id, content_hash).await {
+            Ok(_data) => {
+                if was_dereferenced {
+                    manager.re_reference(content_hash, origin_node).await;
+                }
+                manager.record_success(content_hash).await;
+                info!(hash = %content_hash, peer = %peer_id, "repair: recovered from requested peer");
+                return RecoveryResult {
+                    content_hash: content_hash.to_string(),
+                    success: true,
+                    status: HealthStatus::Recovered,
+                    peer_used: Some(peer_id.to_string()),
+                    error: None,
+                };
+            }
+            Err(e) => e,
+        };
+        warn!(hash = %content_hash, peer = %peer_id, error = %err, "repair: requested peer failed");
+        if was_dereferenced {
+            return RecoveryResult {
+                content_hash: content_hash.to_string(),
+                success: false,
+                status: HealthStatus::Dereferenced,
+                peer_used: None,
+                error: Some(format!(
+                    "requested peer failed: {err}, track remains dereferenced"
+                )),
+            };
+        }
+        return RecoveryResult {
+            content_hash: content_hash.to_string(),
+            success: false,
+            status: manager.record_failure(content_hash, origin_node).await,
+            peer_used: None,
+            error: Some(format!("requested peer failed: {err}")),
+        };
+    }
+
     // 1. Try origin peer
+    *peer_tried = Some(origin_node.to_string());
     info!(hash = %content_hash, peer = %origin_node, dereferenced = was_dereferenced, "auto-repair: trying origin peer");
     match fetcher.fetch_track(origin_node, content_hash).await {
         Ok(_data) => {
@@ -653,6 +1230,7 @@ pub async fn auto_repair_on_failure<F: TrackFetcher>(
     let alternatives = fetcher.alternative_sources(content_hash).await;
     if let Some(best) = select_best_copy(&alternatives) {
         let peer_id = best.peer_id.clone();
+        *peer_tried = Some(peer_id.clone());
         info!(hash = %content_hash, peer = %peer_id, "auto-repair: trying alternative peer");
         match fetcher.fetch_track(&peer_id, content_hash).await {
             Ok(_data) => {
@@ -715,15 +1293,19 @@ pub fn spawn_health_monitor<F: TrackFetcher>(
     db: DatabaseConnection,
     mut shutdown_rx: watch::Receiver<bool>,
 ) -> tokio::task::JoinHandle<()> {
-    let interval_secs = manager.config().monitor_interval_secs;
+    let schedule = manager.config().schedule.clone();
     let batch_size = manager.config().batch_size;
 
     tokio::spawn(async move {
-        info!(interval_secs, batch_size, "health monitor started");
+        info!(%schedule, batch_size, "health monitor started");
 
         loop {
+            let Some(delay) = schedule.next_delay() else {
+                warn!(%schedule, "health monitor: schedule has no upcoming run, stopping");
+                break;
+            };
             tokio::select! {
-                _ = tokio::time::sleep(std::time::Duration::from_secs(interval_secs)) => {
+                _ = tokio::time::sleep(delay) => {
                     info!("health monitor: starting sweep");
                     let result = run_health_sweep(&manager, &*fetcher, &db, batch_size).await;
                     info!(
@@ -733,6 +1315,7 @@ pub fn spawn_health_monitor<F: TrackFetcher>(
                         failed = result.failed,
                         dereferenced = result.dereferenced,
                         unavailable_source = result.unavailable_source,
+                        corrupted = result.corrupted,
                         "health monitor: sweep complete"
                     );
                 }
@@ -747,17 +1330,153 @@ pub fn spawn_health_monitor<F: TrackFetcher>(
     })
 }
 
+// ── On-demand sweeps ─────────────────────────────────────────────────
+
+/// State of the last sweep started with [`start_health_sweep`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status")]
+pub enum HealthSweepTaskStatus {
+    /// No sweep started yet
+    #[serde(rename = "idle")]
+    Idle,
+    #[serde(rename = "running")]
+    Running {
+        started_at: chrono::DateTime<chrono::Utc>,
+    },
+    #[serde(rename = "completed")]
+    Completed {
+        started_at: chrono::DateTime<chrono::Utc>,
+        finished_at: chrono::DateTime<chrono::Utc>,
+        result: BatchCheckResult,
+    },
+    /// The sweep panicked before finishing
+    #[serde(rename = "error")]
+    Error {
+        started_at: chrono::DateTime<chrono::Utc>,
+        finished_at: chrono::DateTime<chrono::Utc>,
+        error: String,
+    },
+}
+
+/// Shared handle to the on-demand sweep state.
+pub type HealthSweepTaskHandle = Arc<tokio::sync::Mutex<HealthSweepTaskStatus>>;
+
+/// Create a new on-demand sweep tracker.
+pub fn new_health_sweep_tracker() -> HealthSweepTaskHandle {
+    Arc::new(tokio::sync::Mutex::new(HealthSweepTaskStatus::Idle))
+}
+
+/// Run [`run_health_sweep`] in the background, reporting through `tracker`.
+/// Returns `None`, starting nothing, if a sweep started this way is still
+/// running. A sweep that panics is reported as
+/// [`Error`](HealthSweepTaskStatus::Error), so another one can be started.
+/// Scheduled sweeps are not tracked and may overlap.
+pub async fn start_health_sweep<F: TrackFetcher>(
+    manager: Arc<TrackHealthManager>,
+    fetcher: Arc<F>,
+    db: DatabaseConnection,
+    tracker: HealthSweepTaskHandle,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let started_at = Utc::now();
+    {
+        let mut status = tracker.lock().await;
+        if matches!(*status, HealthSweepTaskStatus::Running { .. }) {
+            return None;
+        }
+        *status = HealthSweepTaskStatus::Running { started_at };
+    }
+
+    let sweep = tokio::spawn(async move {
+        info!("health sweep requested by an admin");
+        let batch_size = manager.config().batch_size;
+        run_health_sweep(&manager, &*fetcher, &db, batch_size).await
+    });
+    Some(tokio::spawn(async move {
+        let status = match sweep.await {
+            Ok(result) => HealthSweepTaskStatus::Completed {
+                started_at,
+                finished_at: Utc::now(),
+                result,
+            },
+            Err(e) => {
+                let error = if e.is_panic() {
+                    let panic = e.into_panic();
+                    panic
+                        .downcast_ref::<&str>()
+                        .map(|m| m.to_string())
+                        .or_else(|| panic.downcast_ref::<String>().cloned())
+                        .unwrap_or_else(|| "health sweep panicked".to_string())
+                } else {
+                    e.to_string()
+                };
+                error!(%error, "health sweep failed");
+                HealthSweepTaskStatus::Error {
+                    started_at,
+                    finished_at: Utc::now(),
+                    error,
+                }
+            }
+        };
+        *tracker.lock().await = status;
+    }))
+}
+
 /// Perform one full sweep of all remote tracks.
 ///
 /// Queries `remote_tracks` from the database in pages, checks blob availability
 /// via the fetcher, and triggers auto-repair for missing blobs.
-/// Results are persisted back to the `remote_tracks` table.
+/// Results are persisted back to the `remote_tracks` table, tracks
+/// dereferenced past the retention are cleaned up (see
+/// [`crate::track_cleanup`]), and the counts are recorded in
+/// `health_sweep_runs`.
 pub async fn run_health_sweep<F: TrackFetcher>(
     manager: &TrackHealthManager,
     fetcher: &F,
     db: &DatabaseConnection,
     batch_size: usize,
 ) -> BatchCheckResult {
+    if let Err(e) = prune_health_history(db).await {
+        warn!(error = %e, "health sweep: failed to prune history");
+    }
+    if let Err(e) = prune_recovery_log(db).await {
+        warn!(error = %e, "health sweep: failed to prune recovery log");
+    }
+
+    let run_at = Utc::now();
+    let started = std::time::Instant::now();
+    let mut result = sweep_remote_tracks(manager, fetcher, db, batch_size).await;
+    match cleanup_after_sweep(manager, db).await {
+        Ok(report) if !report.tracks.is_empty() => {
+            result.cleaned_up = report.tracks.len();
+            fetcher.tracks_cleaned_up(&report).await;
+            *manager.last_cleanup.write().await = Some(report);
+        }
+        Ok(_) => {}
+        Err(e) => warn!(error = %e, "health sweep: dereferenced track cleanup failed"),
+    }
+    if let Err(e) = record_sweep_run(db, &result, run_at, started.elapsed()).await {
+        warn!(error = %e, "health sweep: failed to record run");
+    }
+    fetcher.sweep_finished(&result).await;
+    result
+}
+
+async fn cleanup_after_sweep(
+    manager: &TrackHealthManager,
+    db: &DatabaseConnection,
+) -> Result<CleanupReport, P2pError> {
+    let policy = CleanupPolicy::load(db).await?;
+    cleanup_dereferenced_tracks(manager, db, &policy).await
+}
+
+async fn sweep_remote_tracks<F: TrackFetcher>(
+    manager: &TrackHealthManager,
+    fetcher: &F,
+    db: &DatabaseConnection,
+    batch_size: usize,
+) -> BatchCheckResult {
+    // Observed into the histogram when dropped, including early returns
+    let _timer = P2P_METRICS.health_sweep_duration_seconds.start_timer();
     let mut overall = BatchCheckResult::new();
 
     // Count total remote tracks for logging
@@ -788,23 +1507,7 @@ pub async fn run_health_sweep<F: TrackFetcher>(
 
         let items: Vec<TrackCheckItem> = page
             .iter()
-            .filter_map(|rt| {
-                // Extract content hash from remote_uri format "p2p://node_id/hash"
-                let hash = rt
-                    .remote_uri
-                    .strip_prefix("p2p://")
-                    .and_then(|rest| rest.split('/').nth(1));
-                let origin = rt
-                    .instance_domain
-                    .strip_prefix("p2p://")
-                    .unwrap_or(&rt.instance_domain);
-
-                hash.map(|h| TrackCheckItem {
-                    content_hash: h.to_string(),
-                    origin_node: origin.to_string(),
-                    title: rt.title.clone(),
-                })
-            })
+            .filter_map(TrackCheckItem::from_remote)
             .collect();
 
         if items.is_empty() {
@@ -812,7 +1515,7 @@ pub async fn run_health_sweep<F: TrackFetcher>(
         }
 
         // Use process_health_batch with the fetcher
-        let batch_result = process_health_batch(
+        let mut batch_result = process_health_batch(
             manager,
             &items,
             |h| {
@@ -834,16 +1537,35 @@ pub async fn run_health_sweep<F: TrackFetcher>(
         )
         .await;
 
+        // Corrupted blobs were counted healthy above because they exist
+        let corrupted = verify_cached_blobs(
+            manager,
+            fetcher,
+            &items,
+            manager.config().integrity_sample_rate,
+        )
+        .await;
+        batch_result.corrupted = corrupted;
+        batch_result.healthy = batch_result.healthy.saturating_sub(corrupted);
+
         // Persist updated statuses back to DB
         for item in &items {
-            let is_healthy = !manager.is_dereferenced(&item.content_hash).await
+            let is_dereferenced = manager.is_dereferenced(&item.content_hash).await;
+            let is_healthy = !is_dereferenced
                 && manager
                     .get_record(&item.content_hash)
                     .await
                     .map(|r| matches!(r.status, HealthStatus::Healthy | HealthStatus::Recovered))
                     .unwrap_or(true);
 
-            persist_track_status(db, &item.content_hash, &item.origin_node, is_healthy).await;
+            persist_track_status(
+                db,
+                &item.content_hash,
+                &item.origin_node,
+                is_healthy,
+                is_dereferenced,
+            )
+            .await;
         }
 
         overall.merge(&batch_result);
@@ -856,6 +1578,7 @@ pub async fn run_health_sweep<F: TrackFetcher>(
         failed = overall.failed,
         dereferenced = overall.dereferenced,
         unavailable_source = overall.unavailable_source,
+        corrupted = overall.corrupted,
         "health sweep: finished"
     );
 
@@ -867,11 +1590,15 @@ pub async fn run_health_sweep<F: TrackFetcher>(
 /// Update a remote track's `is_available` and `last_checked_at` in the database.
 ///
 /// Matches by reconstructing the `remote_uri` pattern `p2p://origin_node/content_hash`.
+/// `dereferenced_at` keeps the time the track was first found `dereferenced`
+/// and is cleared otherwise; an available track hidden by the cleanup is
+/// shown again.
 pub async fn persist_track_status(
     db: &DatabaseConnection,
     content_hash: &str,
     origin_node: &str,
     is_available: bool,
+    dereferenced: bool,
 ) {
     let remote_uri = format!("p2p://{}/{}", origin_node, content_hash);
     let now = Utc::now().fixed_offset();
@@ -883,9 +1610,20 @@ pub async fn persist_track_status(
 
     match result {
         Ok(Some(model)) => {
+            let id = model.id;
+            let dereferenced_at = match model.dereferenced_at {
+                Some(since) if dereferenced => Some(since),
+                _ => dereferenced.then_some(now),
+            };
             let mut active: remote_track::ActiveModel = model.into();
             active.is_available = Set(is_available);
             active.last_checked_at = Set(Some(now));
+            active.dereferenced_at = Set(dereferenced_at);
+            if is_available {
+                if let Err(e) = unhide_tracks(db, &[id]).await {
+                    warn!(remote_uri = %remote_uri, error = %e, "failed to unhide track");
+                }
+            }
             if let Err(e) = active.update(db).await {
                 warn!(
                     remote_uri = %remote_uri,
@@ -905,36 +1643,433 @@ pub async fn persist_track_status(
     }
 }
 
-// ── Tests ────────────────────────────────────────────────────────────
-
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Rows per `UPDATE` when bulk re-referencing.
+const RE_REFERENCE_CHUNK: usize = 1000;
 
-    // ── HealthMonitorConfig ──────────────────────────────────────────
+/// Re-reference the given remote tracks in the health manager. Returns the
+/// IDs of those that are P2P tracks.
+pub async fn re_reference_remote_tracks(
+    manager: &TrackHealthManager,
+    tracks: &[remote_track::Model],
+) -> Vec<uuid::Uuid> {
+    let mut ids = Vec::with_capacity(tracks.len());
+    for rt in tracks {
+        if let Some(item) = TrackCheckItem::from_remote(rt) {
+            manager
+                .re_reference(&item.content_hash, &item.origin_node)
+                .await;
+            ids.push(rt.id);
+        }
+    }
+    ids
+}
 
-    #[test]
-    fn test_default_config() {
-        let config = HealthMonitorConfig::default();
-        assert_eq!(
-            config.max_concurrent_recoveries,
-            DEFAULT_MAX_CONCURRENT_RECOVERIES
+/// Mark every unavailable P2P remote track available again, or only those
+/// from `peer_id`, and reset their health records. For admins to recover
+/// after an outage without waiting for each track to be played. Returns how
+/// many tracks were re-referenced.
+pub async fn bulk_re_reference(
+    manager: &TrackHealthManager,
+    db: &DatabaseConnection,
+    peer_id: Option<&str>,
+) -> Result<usize, P2pError> {
+    let mut query = remote_track::Entity::find()
+        .filter(remote_track::Column::IsAvailable.eq(false))
+        .filter(remote_track::Column::RemoteUri.starts_with("p2p://"));
+    if let Some(peer_id) = peer_id {
+        query = query.filter(
+            remote_track::Column::InstanceDomain
+                .is_in([peer_id.to_string(), format!("p2p://{peer_id}")]),
         );
-        assert_eq!(config.monitor_interval_secs, DEFAULT_MONITOR_INTERVAL_SECS);
-        assert_eq!(config.max_retry_attempts, MAX_RETRY_ATTEMPTS);
-        assert_eq!(config.batch_size, MONITOR_BATCH_SIZE);
     }
+    let tracks = query.all(db).await?;
+    let ids = re_reference_remote_tracks(manager, &tracks).await;
 
-    #[test]
-    fn test_custom_config() {
-        let config = HealthMonitorConfig {
-            max_concurrent_recoveries: 8,
-            monitor_interval_secs: 60,
-            max_retry_attempts: 5,
-            batch_size: 100,
-        };
+    let now = Utc::now().fixed_offset();
+    for chunk in ids.chunks(RE_REFERENCE_CHUNK) {
+        remote_track::Entity::update_many()
+            .col_expr(
+                remote_track::Column::IsAvailable,
+                sea_orm::sea_query::Expr::value(true),
+            )
+            .col_expr(
+                remote_track::Column::LastCheckedAt,
+                sea_orm::sea_query::Expr::value(now),
+            )
+            .col_expr(
+                remote_track::Column::DereferencedAt,
+                sea_orm::sea_query::Expr::value(
+                    Option::<sea_orm::prelude::DateTimeWithTimeZone>::None,
+                ),
+            )
+            .filter(remote_track::Column::Id.is_in(chunk.iter().copied()))
+            .exec(db)
+            .await?;
+        unhide_tracks(db, chunk).await?;
+    }
+    info!(count = ids.len(), peer = ?peer_id, "bulk re-referenced remote tracks");
+    Ok(ids.len())
+}
+
+/// Titles of the remote tracks behind `records`, keyed by content hash.
+/// Records without a `remote_tracks` row are left out.
+pub async fn remote_track_titles(
+    db: &DatabaseConnection,
+    records: &[TrackHealthRecord],
+) -> Result<HashMap<String, String>, P2pError> {
+    if records.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let uris: Vec<String> = records
+        .iter()
+        .map(|r| format!("p2p://{}/{}", r.origin_node, r.content_hash))
+        .collect();
+    let rows = remote_track::Entity::find()
+        .filter(remote_track::Column::RemoteUri.is_in(uris))
+        .all(db)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let hash = row.remote_uri.rsplit('/').next()?.to_string();
+            Some((hash, row.title))
+        })
+        .collect())
+}
+
+/// The remote P2P track with this content hash, for repairing it.
+pub async fn find_remote_track(
+    db: &DatabaseConnection,
+    content_hash: &str,
+) -> Result<Option<TrackCheckItem>, P2pError> {
+    let row = remote_track::Entity::find()
+        .filter(remote_track::Column::RemoteUri.starts_with("p2p://"))
+        .filter(remote_track::Column::RemoteUri.ends_with(format!("/{content_hash}")))
+        .one(db)
+        .await?;
+    Ok(row.as_ref().and_then(TrackCheckItem::from_remote))
+}
+
+// ── Sweep history ────────────────────────────────────────────────────
+
+/// One recorded health sweep, as listed to admins.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthSweepRun {
+    pub run_at: chrono::DateTime<chrono::Utc>,
+    pub total_checked: i64,
+    pub healthy: i64,
+    pub recovered: i64,
+    pub failed: i64,
+    pub dereferenced: i64,
+    pub unavailable_source: i64,
+    pub corrupted: i64,
+    pub cleaned_up: i64,
+    pub duration_ms: i64,
+    /// `healthy / total_checked * 100`
+    pub health_pct: f64,
+}
+
+impl From<health_sweep_run::Model> for HealthSweepRun {
+    fn from(run: health_sweep_run::Model) -> Self {
+        Self {
+            run_at: run.run_at.with_timezone(&Utc),
+            total_checked: run.total_checked,
+            healthy: run.healthy,
+            recovered: run.recovered,
+            failed: run.failed,
+            dereferenced: run.dereferenced,
+            unavailable_source: run.unavailable_source,
+            corrupted: run.corrupted,
+            cleaned_up: run.cleaned_up,
+            duration_ms: run.duration_ms,
+            health_pct: health_pct(run.healthy, run.total_checked),
+        }
+    }
+}
+
+/// Percentage of checked tracks that were healthy; 100 when nothing was
+/// checked.
+pub fn health_pct(healthy: i64, total_checked: i64) -> f64 {
+    if total_checked <= 0 {
+        return 100.0;
+    }
+    healthy as f64 / total_checked as f64 * 100.0
+}
+
+/// Record the counts of a sweep that started at `run_at`.
+pub async fn record_sweep_run(
+    db: &DatabaseConnection,
+    result: &BatchCheckResult,
+    run_at: chrono::DateTime<chrono::Utc>,
+    duration: std::time::Duration,
+) -> Result<(), P2pError> {
+    let run = health_sweep_run::ActiveModel {
+        id: Set(uuid::Uuid::new_v4()),
+        run_at: Set(run_at.into()),
+        total_checked: Set(result.total_checked as i64),
+        healthy: Set(result.healthy as i64),
+        recovered: Set(result.recovered as i64),
+        failed: Set(result.failed as i64),
+        dereferenced: Set(result.dereferenced as i64),
+        unavailable_source: Set(result.unavailable_source as i64),
+        corrupted: Set(result.corrupted as i64),
+        cleaned_up: Set(result.cleaned_up as i64),
+        duration_ms: Set(duration.as_millis() as i64),
+    };
+    health_sweep_run::Entity::insert(run).exec(db).await?;
+    Ok(())
+}
+
+/// Delete sweep runs older than [`HEALTH_HISTORY_RETENTION_DAYS`]. Returns
+/// how many were removed.
+pub async fn prune_health_history(db: &DatabaseConnection) -> Result<u64, P2pError> {
+    let cutoff = Utc::now() - chrono::Duration::days(HEALTH_HISTORY_RETENTION_DAYS);
+    let res = health_sweep_run::Entity::delete_many()
+        .filter(health_sweep_run::Column::RunAt.lt(cutoff))
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected)
+}
+
+/// The last `limit` sweep runs, newest first.
+pub async fn health_history(
+    db: &DatabaseConnection,
+    limit: u64,
+) -> Result<Vec<HealthSweepRun>, P2pError> {
+    Ok(health_sweep_run::Entity::find()
+        .order_by_desc(health_sweep_run::Column::RunAt)
+        .limit(limit)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(HealthSweepRun::from)
+        .collect())
+}
+
+/// Add `attempt` to the recovery log.
+pub async fn save_recovery_attempt(
+    db: &DatabaseConnection,
+    attempt: &RecoveryAttempt,
+) -> Result<(), P2pError> {
+    let row = track_recovery_attempt::ActiveModel {
+        id: Set(uuid::Uuid::new_v4()),
+        content_hash: Set(attempt.content_hash.clone()),
+        origin_node: Set(attempt.origin_node.clone()),
+        peer_tried: Set(attempt.peer_tried.clone()),
+        success: Set(attempt.success),
+        error_message: Set(attempt.error_message.clone()),
+        attempted_at: Set(attempt.attempted_at.into()),
+    };
+    track_recovery_attempt::Entity::insert(row).exec(db).await?;
+    Ok(())
+}
+
+/// Delete all but the newest [`RECOVERY_LOG_MAX_ROWS`] recovery attempts.
+/// Returns how many were removed.
+pub async fn prune_recovery_log(db: &DatabaseConnection) -> Result<u64, P2pError> {
+    let res = db
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "DELETE FROM track_recovery_attempts WHERE id IN (
+                SELECT id FROM track_recovery_attempts
+                ORDER BY attempted_at DESC
+                OFFSET $1
+            )",
+            [(RECOVERY_LOG_MAX_ROWS as i64).into()],
+        ))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Up to `limit` recovery attempts made before `before` (all if `None`),
+/// newest first, optionally only for `content_hash`.
+pub async fn recovery_log(
+    db: &DatabaseConnection,
+    content_hash: Option<&str>,
+    before: Option<chrono::DateTime<chrono::Utc>>,
+    limit: u64,
+) -> Result<Vec<RecoveryAttempt>, P2pError> {
+    let mut query = track_recovery_attempt::Entity::find();
+    if let Some(hash) = content_hash {
+        query = query.filter(track_recovery_attempt::Column::ContentHash.eq(hash));
+    }
+    if let Some(before) = before {
+        query = query.filter(track_recovery_attempt::Column::AttemptedAt.lt(before));
+    }
+    Ok(query
+        .order_by_desc(track_recovery_attempt::Column::AttemptedAt)
+        .limit(limit)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(RecoveryAttempt::from)
+        .collect())
+}
+
+// ── Tests ────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── HealthMonitorConfig ──────────────────────────────────────────
+
+    #[test]
+    fn test_default_config() {
+        let config = HealthMonitorConfig::default();
+        assert_eq!(
+            config.max_concurrent_recoveries,
+            DEFAULT_MAX_CONCURRENT_RECOVERIES
+        );
+        assert_eq!(
+            config.schedule,
+            HealthSchedule::Interval(std::time::Duration::from_secs(
+                DEFAULT_MONITOR_INTERVAL_SECS
+            ))
+        );
+        assert_eq!(config.max_retry_attempts, MAX_RETRY_ATTEMPTS);
+        assert_eq!(config.batch_size, MONITOR_BATCH_SIZE);
+    }
+
+    #[test]
+    fn test_health_schedule_cron() {
+        let schedule = HealthSchedule::cron(" 0 */30 * * * * ").unwrap();
+        assert_eq!(schedule, HealthSchedule::Cron("0 */30 * * * *".to_string()));
+        let delay = schedule.next_delay().unwrap();
+        assert!(delay <= std::time::Duration::from_secs(30 * 60));
+
+        assert!(HealthSchedule::cron("every half hour").is_err());
+        assert!(HealthSchedule::cron("").is_err());
+        // Valid syntax, but the year is over
+        assert!(HealthSchedule::cron("0 0 0 1 1 * 2001").is_err());
+    }
+
+    #[test]
+    fn test_health_config_from_env() {
+        std::env::remove_var("P2P_HEALTH_SCHEDULE");
+        assert_eq!(
+            HealthMonitorConfig::from_env().schedule,
+            HealthMonitorConfig::default().schedule
+        );
+        std::env::set_var("P2P_HEALTH_SCHEDULE", "0 */30 * * * *");
+        assert_eq!(
+            HealthMonitorConfig::from_env().schedule,
+            HealthSchedule::Cron("0 */30 * * * *".to_string())
+        );
+        std::env::set_var("P2P_HEALTH_SCHEDULE", "not cron");
+        assert_eq!(
+            HealthMonitorConfig::from_env().schedule,
+            HealthMonitorConfig::default().schedule
+        );
+        std::env::remove_var("P2P_HEALTH_SCHEDULE");
+    }
+
+    fn vars<'a>(pairs: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            pairs
+                .iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn test_health_config_from_vars() {
+        let config = HealthMonitorConfig::from_vars(vars(&[
+            ("P2P_HEALTH_INTERVAL_SECS", "3600"),
+            ("P2P_HEALTH_MAX_RETRIES", " 5 "),
+            ("P2P_HEALTH_BATCH_SIZE", "200"),
+            ("P2P_HEALTH_MAX_CONCURRENT", "8"),
+        ]));
+        assert_eq!(
+            config.schedule,
+            HealthSchedule::Interval(std::time::Duration::from_secs(3600))
+        );
+        assert_eq!(config.max_retry_attempts, 5);
+        assert_eq!(config.batch_size, 200);
+        assert_eq!(config.max_concurrent_recoveries, 8);
+    }
+
+    #[test]
+    fn test_health_config_from_vars_integrity_sample() {
+        let config =
+            HealthMonitorConfig::from_vars(vars(&[("P2P_HEALTH_INTEGRITY_SAMPLE", "0.25")]));
+        assert_eq!(config.integrity_sample_rate, 0.25);
+
+        let config = HealthMonitorConfig::from_vars(vars(&[("P2P_HEALTH_INTEGRITY_SAMPLE", "0")]));
+        assert_eq!(config.integrity_sample_rate, 0.0);
+
+        for invalid in ["1.5", "-0.1", "some"] {
+            let config =
+                HealthMonitorConfig::from_vars(vars(&[("P2P_HEALTH_INTEGRITY_SAMPLE", invalid)]));
+            assert_eq!(config.integrity_sample_rate, DEFAULT_INTEGRITY_SAMPLE_RATE);
+        }
+    }
+
+    #[test]
+    fn test_health_config_from_vars_defaults() {
+        let config = HealthMonitorConfig::from_vars(vars(&[]));
+        let default = HealthMonitorConfig::default();
+        assert_eq!(config.schedule, default.schedule);
+        assert_eq!(config.max_retry_attempts, MAX_RETRY_ATTEMPTS);
+        assert_eq!(config.batch_size, MONITOR_BATCH_SIZE);
+        assert_eq!(
+            config.max_concurrent_recoveries,
+            DEFAULT_MAX_CONCURRENT_RECOVERIES
+        );
+    }
+
+    #[test]
+    fn test_health_config_from_vars_invalid_values_fall_back() {
+        let config = HealthMonitorConfig::from_vars(vars(&[
+            ("P2P_HEALTH_INTERVAL_SECS", "0"),
+            ("P2P_HEALTH_MAX_RETRIES", "-1"),
+            ("P2P_HEALTH_BATCH_SIZE", "lots"),
+            ("P2P_HEALTH_MAX_CONCURRENT", ""),
+        ]));
+        let default = HealthMonitorConfig::default();
+        assert_eq!(config.schedule, default.schedule);
+        assert_eq!(config.max_retry_attempts, MAX_RETRY_ATTEMPTS);
+        assert_eq!(config.batch_size, MONITOR_BATCH_SIZE);
+        assert_eq!(
+            config.max_concurrent_recoveries,
+            DEFAULT_MAX_CONCURRENT_RECOVERIES
+        );
+    }
+
+    #[test]
+    fn test_health_config_from_vars_caps_and_cron_precedence() {
+        let config = HealthMonitorConfig::from_vars(vars(&[
+            ("P2P_HEALTH_INTERVAL_SECS", "60"),
+            ("P2P_HEALTH_SCHEDULE", "0 */30 * * * *"),
+            ("P2P_HEALTH_BATCH_SIZE", "1000000"),
+            ("P2P_HEALTH_MAX_CONCURRENT", "99999"),
+        ]));
+        assert_eq!(
+            config.schedule,
+            HealthSchedule::Cron("0 */30 * * * *".to_string())
+        );
+        assert_eq!(config.batch_size, MAX_MONITOR_BATCH_SIZE);
+        assert_eq!(
+            config.max_concurrent_recoveries,
+            MAX_CONCURRENT_RECOVERIES_LIMIT
+        );
+    }
+
+    #[test]
+    fn test_custom_config() {
+        let config = HealthMonitorConfig {
+            max_concurrent_recoveries: 8,
+            schedule: HealthSchedule::Interval(std::time::Duration::from_secs(60)),
+            max_retry_attempts: 5,
+            batch_size: 100,
+            integrity_sample_rate: 0.5,
+        };
         assert_eq!(config.max_concurrent_recoveries, 8);
-        assert_eq!(config.monitor_interval_secs, 60);
+        assert_eq!(
+            config.schedule.next_delay(),
+            Some(std::time::Duration::from_secs(60))
+        );
         assert_eq!(config.max_retry_attempts, 5);
         assert_eq!(config.batch_size, 100);
     }
@@ -1075,6 +2210,63 @@ mod tests {
         assert_eq!(record.failed_attempts, 0);
     }
 
+    fn remote_track_model(i: usize, origin: &str) -> remote_track::Model {
+        remote_track::Model {
+            id: uuid::Uuid::new_v4(),
+            local_track_id: None,
+            musicbrainz_id: None,
+            title: format!("Track {i}"),
+            artist_name: "Artist".into(),
+            album_title: None,
+            instance_domain: format!("p2p://{origin}"),
+            remote_uri: format!("p2p://{origin}/hash{i}"),
+            remote_stream_url: String::new(),
+            bitrate: None,
+            sample_rate: None,
+            format: None,
+            is_available: false,
+            last_checked_at: None,
+            dereferenced_at: None,
+            signature: None,
+            created_at: chrono::Utc::now().into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_re_reference_remote_tracks_resets_all() {
+        let mgr = TrackHealthManager::new();
+        let tracks: Vec<_> = (0..100).map(|i| remote_track_model(i, "node1")).collect();
+        for i in 0..100 {
+            for _ in 0..MAX_RETRY_ATTEMPTS {
+                mgr.record_failure(&format!("hash{i}"), "node1").await;
+            }
+        }
+        assert_eq!(mgr.dereferenced_tracks().await.len(), 100);
+
+        let ids = re_reference_remote_tracks(&mgr, &tracks).await;
+
+        assert_eq!(ids.len(), 100);
+        assert!(mgr.dereferenced_tracks().await.is_empty());
+        assert_eq!(mgr.status_counts().await.get("healthy"), Some(&100));
+    }
+
+    #[tokio::test]
+    async fn test_re_reference_remote_tracks_skips_non_p2p() {
+        let mgr = TrackHealthManager::new();
+        let mut federated = remote_track_model(0, "node1");
+        federated.remote_uri = "https://music.example.org/tracks/1".into();
+        let ids = re_reference_remote_tracks(&mgr, &[federated]).await;
+        assert!(ids.is_empty());
+    }
+
+    #[test]
+    fn test_track_check_item_from_remote() {
+        let item = TrackCheckItem::from_remote(&remote_track_model(7, "node1")).unwrap();
+        assert_eq!(item.content_hash, "hash7");
+        assert_eq!(item.origin_node, "node1");
+        assert_eq!(item.title, "Track 7");
+    }
+
     #[tokio::test]
     async fn test_re_reference_noop_on_healthy_track() {
         let mgr = TrackHealthManager::new();
@@ -1151,6 +2343,34 @@ mod tests {
         assert_eq!(*counts.get("dereferenced").unwrap_or(&0), 1);
     }
 
+    #[tokio::test]
+    async fn test_unhealthy_page() {
+        let mgr = TrackHealthManager::new();
+        mgr.record_failure("hash_a", "peer1").await;
+        for _ in 0..MAX_RETRY_ATTEMPTS {
+            mgr.record_failure("hash_b", "peer2").await;
+        }
+        mgr.mark_healthy("hash_c", "peer1").await;
+
+        let (total, first) = mgr.unhealthy_page(0, 1).await;
+        assert_eq!(total, 2);
+        assert_eq!(first.len(), 1);
+        let (_, rest) = mgr.unhealthy_page(1, 10).await;
+        assert_eq!(rest.len(), 1);
+
+        let mut hashes: Vec<_> = first
+            .iter()
+            .chain(&rest)
+            .map(|r| r.content_hash.as_str())
+            .collect();
+        hashes.sort();
+        assert_eq!(hashes, ["hash_a", "hash_b"]);
+
+        let (total, past_end) = mgr.unhealthy_page(5, 10).await;
+        assert_eq!(total, 2);
+        assert!(past_end.is_empty());
+    }
+
     // ── Remove & clear ───────────────────────────────────────────────
 
     #[tokio::test]
@@ -1251,6 +2471,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         let mp3 = PeerTrackInfo {
             peer_id: "p2".into(),
@@ -1259,6 +2482,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         assert!(quality_score(&flac) > quality_score(&mp3));
     }
@@ -1272,6 +2498,9 @@ mod tests {
             sample_rate: Some(44100),
             is_online: true,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         let offline = PeerTrackInfo {
             peer_id: "p2".into(),
@@ -1280,6 +2509,9 @@ mod tests {
             sample_rate: Some(44100),
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         assert!(quality_score(&online) > quality_score(&offline));
     }
@@ -1293,6 +2525,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         let low_br = PeerTrackInfo {
             peer_id: "p2".into(),
@@ -1301,6 +2536,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         assert!(quality_score(&high_br) > quality_score(&low_br));
     }
@@ -1314,6 +2552,9 @@ mod tests {
             sample_rate: Some(96_000),
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         let low_sr = PeerTrackInfo {
             peer_id: "p2".into(),
@@ -1322,6 +2563,9 @@ mod tests {
             sample_rate: Some(44_100),
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         assert!(quality_score(&high_sr) > quality_score(&low_sr));
     }
@@ -1336,6 +2580,9 @@ mod tests {
             sample_rate: Some(44100),
             is_online: true,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         let offline_flac = PeerTrackInfo {
             peer_id: "p2".into(),
@@ -1344,6 +2591,9 @@ mod tests {
             sample_rate: Some(96_000),
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         assert!(quality_score(&online_mp3) > quality_score(&offline_flac));
     }
@@ -1357,6 +2607,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         assert_eq!(quality_score(&unknown), 300); // base format score only
     }
@@ -1370,6 +2623,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         let aac = PeerTrackInfo {
             peer_id: "p2".into(),
@@ -1378,6 +2634,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         assert!(quality_score(&opus) > quality_score(&aac));
     }
@@ -1391,6 +2650,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         let flac = PeerTrackInfo {
             peer_id: "p2".into(),
@@ -1399,6 +2661,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         assert!(quality_score(&flac) > quality_score(&wav));
     }
@@ -1413,6 +2678,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         let capped = PeerTrackInfo {
             peer_id: "p2".into(),
@@ -1421,6 +2689,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         assert_eq!(quality_score(&huge_br), quality_score(&capped));
     }
@@ -1442,6 +2713,9 @@ mod tests {
             sample_rate: None,
             is_online: true,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         }];
         let best = select_best_copy(&copies).unwrap();
         assert_eq!(best.peer_id, "p1");
@@ -1457,6 +2731,9 @@ mod tests {
                 sample_rate: Some(44100),
                 is_online: true,
                 file_size: 1_000_000,
+                trusted: false,
+                avg_latency_ms: None,
+                success_rate: None,
             },
             PeerTrackInfo {
                 peer_id: "p2".into(),
@@ -1465,6 +2742,9 @@ mod tests {
                 sample_rate: Some(96_000),
                 is_online: true,
                 file_size: 50_000_000,
+                trusted: false,
+                avg_latency_ms: None,
+                success_rate: None,
             },
         ];
         let best = select_best_copy(&copies).unwrap();
@@ -1481,6 +2761,9 @@ mod tests {
                 sample_rate: None,
                 is_online: true,
                 file_size: 0,
+                trusted: false,
+                avg_latency_ms: None,
+                success_rate: None,
             },
             PeerTrackInfo {
                 peer_id: "p2".into(),
@@ -1489,6 +2772,9 @@ mod tests {
                 sample_rate: Some(96_000),
                 is_online: false,
                 file_size: 0,
+                trusted: false,
+                avg_latency_ms: None,
+                success_rate: None,
             },
         ];
         let best = select_best_copy(&copies).unwrap();
@@ -1505,6 +2791,9 @@ mod tests {
                 sample_rate: None,
                 is_online: false,
                 file_size: 0,
+                trusted: false,
+                avg_latency_ms: None,
+                success_rate: None,
             },
             PeerTrackInfo {
                 peer_id: "p2".into(),
@@ -1513,12 +2802,101 @@ mod tests {
                 sample_rate: Some(96_000),
                 is_online: false,
                 file_size: 0,
+                trusted: false,
+                avg_latency_ms: None,
+                success_rate: None,
             },
         ];
         let best = select_best_copy(&copies).unwrap();
         assert_eq!(best.peer_id, "p2"); // Best quality among offline peers
     }
 
+    #[test]
+    fn test_select_best_copy_prefers_trusted_online_peer() {
+        let copy = |peer_id: &str, format: &str, is_online: bool, trusted: bool| PeerTrackInfo {
+            peer_id: peer_id.into(),
+            format: format.into(),
+            bitrate: None,
+            sample_rate: None,
+            is_online,
+            file_size: 0,
+            trusted,
+            avg_latency_ms: None,
+            success_rate: None,
+        };
+        let copies = vec![
+            copy("flac-normal", "FLAC", true, false),
+            copy("mp3-trusted", "MP3", true, true),
+            copy("flac-trusted-offline", "FLAC", false, true),
+        ];
+        let best = select_best_copy(&copies).unwrap();
+        assert_eq!(best.peer_id, "mp3-trusted");
+    }
+
+    #[test]
+    fn test_quality_score_responsiveness_bonus() {
+        let copy = |avg_latency_ms: Option<u32>, success_rate: Option<f32>| PeerTrackInfo {
+            peer_id: "p".into(),
+            format: "FLAC".into(),
+            bitrate: None,
+            sample_rate: None,
+            is_online: true,
+            file_size: 0,
+            trusted: false,
+            avg_latency_ms,
+            success_rate,
+        };
+        let base = quality_score(&copy(None, None));
+        assert_eq!(quality_score(&copy(Some(40), Some(1.0))), base + 300);
+        assert_eq!(quality_score(&copy(Some(100), Some(0.5))), base + 200);
+        assert_eq!(quality_score(&copy(Some(400), None)), base + 25);
+        assert_eq!(quality_score(&copy(None, Some(0.0))), base);
+    }
+
+    #[test]
+    fn test_select_best_copy_prefers_responsive_peer() {
+        let copy = |peer_id: &str, avg_latency_ms: u32, success_rate: f32| PeerTrackInfo {
+            peer_id: peer_id.into(),
+            format: "FLAC".into(),
+            bitrate: Some(900_000),
+            sample_rate: Some(44_100),
+            is_online: true,
+            file_size: 0,
+            trusted: false,
+            avg_latency_ms: Some(avg_latency_ms),
+            success_rate: Some(success_rate),
+        };
+        let copies = vec![
+            copy("flaky", 30, 0.4),
+            copy("steady", 120, 0.98),
+            copy("slow", 900, 1.0),
+        ];
+        assert_eq!(select_best_copy(&copies).unwrap().peer_id, "steady");
+    }
+
+    #[test]
+    fn test_sort_by_quality_best_first() {
+        let copy = |peer_id: &str, format: &str, is_online: bool| PeerTrackInfo {
+            peer_id: peer_id.into(),
+            format: format.into(),
+            bitrate: None,
+            sample_rate: None,
+            is_online,
+            file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
+        };
+        let mut copies = vec![
+            copy("mp3-online", "MP3", true),
+            copy("flac-offline", "FLAC", false),
+            copy("flac-online", "FLAC", true),
+        ];
+        sort_by_quality(&mut copies);
+        let order: Vec<&str> = copies.iter().map(|c| c.peer_id.as_str()).collect();
+        assert_eq!(order, vec!["flac-online", "mp3-online", "flac-offline"]);
+    }
+
     #[test]
     fn test_resolve_duplicates() {
         let copies = vec![
@@ -1529,6 +2907,9 @@ mod tests {
                 sample_rate: None,
                 is_online: true,
                 file_size: 0,
+                trusted: false,
+                avg_latency_ms: None,
+                success_rate: None,
             },
             PeerTrackInfo {
                 peer_id: "p2".into(),
@@ -1537,6 +2918,9 @@ mod tests {
                 sample_rate: Some(44100),
                 is_online: true,
                 file_size: 0,
+                trusted: false,
+                avg_latency_ms: None,
+                success_rate: None,
             },
         ];
         let best = resolve_duplicates(&copies, "hash1").unwrap();
@@ -1555,6 +2939,8 @@ mod tests {
         assert_eq!(r.dereferenced, 0);
         assert_eq!(r.re_referenced, 0);
         assert_eq!(r.unavailable_source, 0);
+        assert_eq!(r.corrupted, 0);
+        assert_eq!(r.cleaned_up, 0);
     }
 
     #[test]
@@ -1567,6 +2953,8 @@ mod tests {
             dereferenced: 1,
             re_referenced: 1,
             unavailable_source: 3,
+            corrupted: 1,
+            cleaned_up: 0,
         };
         let r2 = BatchCheckResult {
             total_checked: 5,
@@ -1576,6 +2964,8 @@ mod tests {
             dereferenced: 0,
             re_referenced: 2,
             unavailable_source: 1,
+            corrupted: 2,
+            cleaned_up: 4,
         };
         r1.merge(&r2);
         assert_eq!(r1.total_checked, 15);
@@ -1585,6 +2975,8 @@ mod tests {
         assert_eq!(r1.dereferenced, 1);
         assert_eq!(r1.re_referenced, 3);
         assert_eq!(r1.unavailable_source, 4);
+        assert_eq!(r1.corrupted, 3);
+        assert_eq!(r1.cleaned_up, 4);
     }
 
     // ── check_blob_exists ────────────────────────────────────────────
@@ -1984,6 +3376,49 @@ mod tests {
         assert_ne!(HealthStatus::Healthy, HealthStatus::Recovered);
     }
 
+    #[test]
+    fn test_health_status_as_str() {
+        assert_eq!(HealthStatus::Healthy.as_str(), "healthy");
+        assert_eq!(HealthStatus::Recovered.as_str(), "recovered");
+        assert_eq!(HealthStatus::Degraded { attempts: 2 }.as_str(), "degraded");
+        assert_eq!(HealthStatus::Dereferenced.as_str(), "dereferenced");
+    }
+
+    // ── Sweep history ──
+
+    #[test]
+    fn test_health_pct() {
+        assert_eq!(health_pct(50, 100), 50.0);
+        assert_eq!(health_pct(3, 3), 100.0);
+        assert_eq!(health_pct(0, 4), 0.0);
+        // An empty sweep found nothing wrong
+        assert_eq!(health_pct(0, 0), 100.0);
+    }
+
+    #[test]
+    fn test_health_sweep_run_from_model() {
+        let run_at = chrono::Utc::now();
+        let run = HealthSweepRun::from(health_sweep_run::Model {
+            id: uuid::Uuid::new_v4(),
+            run_at: run_at.into(),
+            total_checked: 8,
+            healthy: 6,
+            recovered: 0,
+            failed: 2,
+            dereferenced: 1,
+            unavailable_source: 0,
+            corrupted: 0,
+            cleaned_up: 0,
+            duration_ms: 1500,
+        });
+        assert_eq!(run.run_at, run_at);
+        assert_eq!(run.total_checked, 8);
+        assert_eq!(run.health_pct, 75.0);
+        let json = serde_json::to_value(&run).unwrap();
+        assert_eq!(json["health_pct"], 75.0);
+        assert_eq!(json["duration_ms"], 1500);
+    }
+
     #[test]
     fn test_health_status_clone() {
         let s = HealthStatus::Degraded { attempts: 2 };
@@ -2008,6 +3443,7 @@ mod tests {
             origin_node: "node1".into(),
             failed_attempts: 2,
             last_attempt: Some(chrono::Utc::now()),
+            next_attempt_after: None,
             status: HealthStatus::Degraded { attempts: 2 },
         };
         let r2 = r.clone();
@@ -2022,6 +3458,7 @@ mod tests {
             origin_node: "node1".into(),
             failed_attempts: 0,
             last_attempt: None,
+            next_attempt_after: None,
             status: HealthStatus::Healthy,
         };
         let dbg = format!("{:?}", r);
@@ -2040,6 +3477,9 @@ mod tests {
             sample_rate: Some(44100),
             is_online: true,
             file_size: 50_000_000,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         let info2 = info.clone();
         assert_eq!(info2.peer_id, "p1");
@@ -2055,6 +3495,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         let dbg = format!("{:?}", info);
         assert!(dbg.contains("MP3"));
@@ -2201,6 +3644,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         // Format score (400) only
         assert_eq!(quality_score(&info), 400);
@@ -2215,6 +3661,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         let upper = PeerTrackInfo {
             peer_id: "p2".into(),
@@ -2223,6 +3672,9 @@ mod tests {
             sample_rate: None,
             is_online: false,
             file_size: 0,
+            trusted: false,
+            avg_latency_ms: None,
+            success_rate: None,
         };
         assert_eq!(quality_score(&lower), quality_score(&upper));
     }
@@ -2237,6 +3689,9 @@ mod tests {
                 sample_rate: Some(44100),
                 is_online: true,
                 file_size: 0,
+                trusted: false,
+                avg_latency_ms: None,
+                success_rate: None,
             },
             PeerTrackInfo {
                 peer_id: "p2".into(),
@@ -2245,6 +3700,9 @@ mod tests {
                 sample_rate: Some(96_000),
                 is_online: true,
                 file_size: 0,
+                trusted: false,
+                avg_latency_ms: None,
+                success_rate: None,
             },
         ];
         let best = select_best_copy(&copies).unwrap();
@@ -2270,6 +3728,8 @@ mod tests {
         alternatives: RwLock<Vec<PeerTrackInfo>>,
         /// Counter: how many fetch_track calls were made (for assertion).
         fetch_call_count: AtomicUsize,
+        /// Recovery attempts reported by `auto_repair_on_failure`.
+        recovery_attempts: std::sync::Mutex<Vec<RecoveryAttempt>>,
     }
 
     impl MockFetcher {
@@ -2280,6 +3740,7 @@ mod tests {
                 fetchable_hashes: RwLock::new(std::collections::HashSet::new()),
                 alternatives: RwLock::new(Vec::new()),
                 fetch_call_count: AtomicUsize::new(0),
+                recovery_attempts: std::sync::Mutex::new(Vec::new()),
             }
         }
 
@@ -2302,6 +3763,10 @@ mod tests {
         fn fetch_count(&self) -> usize {
             self.fetch_call_count.load(Ordering::SeqCst)
         }
+
+        fn recovery_attempts(&self) -> Vec<RecoveryAttempt> {
+            self.recovery_attempts.lock().unwrap().clone()
+        }
     }
 
     #[async_trait]
@@ -2326,10 +3791,88 @@ mod tests {
         async fn alternative_sources(&self, _hash: &str) -> Vec<PeerTrackInfo> {
             self.alternatives.read().await.clone()
         }
+
+        async fn record_recovery_attempt(&self, attempt: &RecoveryAttempt) {
+            self.recovery_attempts.lock().unwrap().push(attempt.clone());
+        }
     }
 
     // ── auto_repair_on_failure ───────────────────────────────────────
 
+    /// Let the repair cooldown of `hash` pass.
+    async fn expire_cooldown(mgr: &TrackHealthManager, hash: &str) {
+        if let Some(record) = mgr.records.write().await.get_mut(hash) {
+            record.next_attempt_after = Some(Utc::now() - chrono::Duration::seconds(1));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auto_repair_records_successful_attempt() {
+        let mgr = TrackHealthManager::new();
+        let fetcher = MockFetcher::new();
+        fetcher.set_fetchable("hash1").await;
+
+        auto_repair_on_failure(&mgr, &fetcher, "hash1", "origin1").await;
+
+        let attempts = fetcher.recovery_attempts();
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].content_hash, "hash1");
+        assert_eq!(attempts[0].origin_node, "origin1");
+        assert_eq!(attempts[0].peer_tried.as_deref(), Some("origin1"));
+        assert!(attempts[0].success);
+        assert!(attempts[0].error_message.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_auto_repair_records_failed_attempt_with_last_peer() {
+        let mgr = TrackHealthManager::new();
+        let fetcher = MockFetcher::new();
+        fetcher
+            .set_alternatives(vec![PeerTrackInfo {
+                peer_id: "alt1".into(),
+                format: "FLAC".into(),
+                bitrate: None,
+                sample_rate: None,
+                is_online: true,
+                file_size: 0,
+                trusted: false,
+                avg_latency_ms: None,
+                success_rate: None,
+            }])
+            .await;
+
+        let result = auto_repair_on_failure(&mgr, &fetcher, "hash1", "origin1").await;
+        assert!(!result.success);
+
+        let attempts = fetcher.recovery_attempts();
+        assert_eq!(attempts.len(), 1);
+        assert!(!attempts[0].success);
+        assert_eq!(attempts[0].peer_tried.as_deref(), Some("alt1"));
+        assert_eq!(attempts[0].error_message, result.error);
+
+        // Already dereferenced tracks are logged too
+        mgr.record_failure("hash1", "origin1").await;
+        mgr.record_failure("hash1", "origin1").await;
+        auto_repair_on_failure(&mgr, &fetcher, "hash1", "origin1").await;
+        assert_eq!(fetcher.recovery_attempts().len(), 2);
+    }
+
+    #[test]
+    fn test_recovery_attempt_from_model() {
+        let at = Utc::now();
+        let attempt = RecoveryAttempt::from(track_recovery_attempt::Model {
+            id: uuid::Uuid::new_v4(),
+            content_hash: "hash1".into(),
+            origin_node: "origin1".into(),
+            peer_tried: Some("alt1".into()),
+            success: false,
+            error_message: Some("all sources exhausted".into()),
+            attempted_at: at.into(),
+        });
+        assert_eq!(attempt.peer_tried.as_deref(), Some("alt1"));
+        assert_eq!(attempt.attempted_at, at);
+    }
+
     #[tokio::test]
     async fn test_auto_repair_origin_success() {
         let mgr = TrackHealthManager::new();
@@ -2365,6 +3908,9 @@ mod tests {
                 sample_rate: Some(44100),
                 is_online: true,
                 file_size: 50_000_000,
+                trusted: false,
+                avg_latency_ms: None,
+                success_rate: None,
             }])
             .await;
 
@@ -2406,6 +3952,7 @@ mod tests {
         // First failure → degraded
         let r1 = auto_repair_on_failure(&mgr, &fetcher, "hash1", "origin1").await;
         assert_eq!(r1.status, HealthStatus::Degraded { attempts: 1 });
+        expire_cooldown(&mgr, "hash1").await;
 
         // Second failure → dereferenced (max_retry_attempts = 2)
         let r2 = auto_repair_on_failure(&mgr, &fetcher, "hash1", "origin1").await;
@@ -2473,8 +4020,9 @@ mod tests {
         mgr.record_failure("hash1", "origin1").await;
         mgr.record_failure("hash1", "origin1").await;
 
-        // Now make it fetchable and repair
+        // Now make it fetchable and repair once the cooldown has passed
         fetcher.set_fetchable("hash1").await;
+        expire_cooldown(&mgr, "hash1").await;
         let result = auto_repair_on_failure(&mgr, &fetcher, "hash1", "origin1").await;
 
         assert!(result.success);
@@ -2482,6 +4030,160 @@ mod tests {
         let record = mgr.get_record("hash1").await.unwrap();
         assert_eq!(record.failed_attempts, 0);
         assert_eq!(record.status, HealthStatus::Recovered);
+        assert!(record.next_attempt_after.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_auto_repair_inside_cooldown_skips_attempt() {
+        let mgr = TrackHealthManager::new();
+        let fetcher = MockFetcher::new();
+
+        let r1 = auto_repair_on_failure(&mgr, &fetcher, "hash1", "origin1").await;
+        assert_eq!(r1.status, HealthStatus::Degraded { attempts: 1 });
+        assert_eq!(fetcher.fetch_count(), 1);
+
+        // Pressing play again right away neither contacts a peer nor
+        // counts another failure, even when the peer is back
+        fetcher.set_fetchable("hash1").await;
+        for _ in 0..5 {
+            let r = auto_repair_on_failure(&mgr, &fetcher, "hash1", "origin1").await;
+            assert!(!r.success);
+            assert_eq!(r.status, HealthStatus::Degraded { attempts: 1 });
+            assert!(r.error.unwrap().contains("cooling down"));
+        }
+        assert_eq!(fetcher.fetch_count(), 1);
+        assert_eq!(fetcher.recovery_attempts().len(), 1);
+        let record = mgr.get_record("hash1").await.unwrap();
+        assert_eq!(record.failed_attempts, 1);
+        assert!(!mgr.is_dereferenced("hash1").await);
+
+        // After the cooldown the track is repaired
+        expire_cooldown(&mgr, "hash1").await;
+        let r = auto_repair_on_failure(&mgr, &fetcher, "hash1", "origin1").await;
+        assert!(r.success);
+        assert_eq!(fetcher.fetch_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_record_failure_sets_growing_cooldown() {
+        let mgr = TrackHealthManager::new();
+        let mut previous = None;
+        for attempts in 1..=2 {
+            let before = Utc::now();
+            mgr.record_failure("hash1", "origin1").await;
+            let record = mgr.get_record("hash1").await.unwrap();
+            let after = record.next_attempt_after.unwrap();
+            assert!(after >= before + repair_backoff(attempts));
+            assert!(record.in_cooldown(before));
+            assert!(!record.in_cooldown(after));
+            if let Some(previous) = previous {
+                assert!(after > previous);
+            }
+            previous = Some(after);
+        }
+    }
+
+    #[test]
+    fn test_repair_backoff_schedule() {
+        assert_eq!(repair_backoff(0), chrono::Duration::seconds(30));
+        assert_eq!(repair_backoff(1), chrono::Duration::seconds(30));
+        assert_eq!(repair_backoff(2), chrono::Duration::minutes(5));
+        assert_eq!(repair_backoff(3), chrono::Duration::minutes(30));
+        assert_eq!(repair_backoff(50), chrono::Duration::minutes(30));
+    }
+
+    #[tokio::test]
+    async fn test_re_reference_and_mark_healthy_clear_cooldown() {
+        let mgr = TrackHealthManager::new();
+        for _ in 0..MAX_RETRY_ATTEMPTS {
+            mgr.record_failure("hash1", "origin1").await;
+        }
+        assert!(mgr
+            .get_record("hash1")
+            .await
+            .unwrap()
+            .next_attempt_after
+            .is_some());
+        mgr.re_reference("hash1", "origin1").await;
+        assert!(mgr
+            .get_record("hash1")
+            .await
+            .unwrap()
+            .next_attempt_after
+            .is_none());
+
+        mgr.record_failure("hash2", "origin1").await;
+        mgr.mark_healthy("hash2", "origin1").await;
+        let record = mgr.get_record("hash2").await.unwrap();
+        assert!(record.next_attempt_after.is_none());
+        assert!(!record.in_cooldown(Utc::now()));
+    }
+
+    // ── repair_track_now ─────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_repair_now_ignores_cooldown() {
+        let mgr = TrackHealthManager::new();
+        let fetcher = MockFetcher::new();
+        auto_repair_on_failure(&mgr, &fetcher, "hash1", "origin1").await;
+        assert!(mgr
+            .get_record("hash1")
+            .await
+            .unwrap()
+            .in_cooldown(Utc::now()));
+
+        fetcher.set_fetchable("hash1").await;
+        let result = repair_track_now(&mgr, &fetcher, "hash1", "origin1", None).await;
+        assert!(result.success);
+        assert_eq!(result.peer_used.as_deref(), Some("origin1"));
+        assert_eq!(fetcher.recovery_attempts().len(), 2);
+        assert!(mgr
+            .get_record("hash1")
+            .await
+            .unwrap()
+            .next_attempt_after
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_repair_now_asks_only_requested_peer() {
+        let mgr = TrackHealthManager::new();
+        let fetcher = MockFetcher::new();
+        fetcher
+            .set_alternatives(vec![PeerTrackInfo {
+                peer_id: "alt1".into(),
+                format: "FLAC".into(),
+                bitrate: None,
+                sample_rate: None,
+                is_online: true,
+                file_size: 0,
+                trusted: false,
+                avg_latency_ms: None,
+                success_rate: None,
+            }])
+            .await;
+
+        let failed = repair_track_now(&mgr, &fetcher, "hash1", "origin1", Some("alt2")).await;
+        assert!(!failed.success);
+        assert_eq!(failed.status, HealthStatus::Degraded { attempts: 1 });
+        assert!(failed.error.unwrap().contains("requested peer failed"));
+        assert_eq!(fetcher.fetch_count(), 1);
+        assert_eq!(
+            fetcher.recovery_attempts()[0].peer_tried.as_deref(),
+            Some("alt2")
+        );
+
+        // A dereferenced track is re-referenced once the peer serves it
+        for _ in 1..MAX_RETRY_ATTEMPTS {
+            mgr.record_failure("hash1", "origin1").await;
+        }
+        assert!(mgr.is_dereferenced("hash1").await);
+        fetcher.set_fetchable("hash1").await;
+        let result = repair_track_now(&mgr, &fetcher, "hash1", "origin1", Some("alt2")).await;
+        assert!(result.success);
+        assert_eq!(result.peer_used.as_deref(), Some("alt2"));
+        assert_eq!(fetcher.fetch_count(), 2);
+        assert!(!mgr.is_dereferenced("hash1").await);
     }
 
     #[tokio::test]
@@ -2499,6 +4201,9 @@ mod tests {
                     sample_rate: Some(44100),
                     is_online: true,
                     file_size: 5_000_000,
+                    trusted: false,
+                    avg_latency_ms: None,
+                    success_rate: None,
                 },
                 PeerTrackInfo {
                     peer_id: "alt2".into(),
@@ -2507,6 +4212,9 @@ mod tests {
                     sample_rate: Some(96_000),
                     is_online: true,
                     file_size: 50_000_000,
+                    trusted: false,
+                    avg_latency_ms: None,
+                    success_rate: None,
                 },
             ])
             .await;
@@ -2583,6 +4291,9 @@ mod tests {
                 sample_rate: None,
                 is_online: true,
                 file_size: 0,
+                trusted: false,
+                avg_latency_ms: None,
+                success_rate: None,
             }])
             .await;
 
@@ -2599,7 +4310,7 @@ mod tests {
         // We use a very long interval so the sweep never triggers,
         // then immediately signal shutdown and expect clean exit.
         let config = HealthMonitorConfig {
-            monitor_interval_secs: 3600, // Won't trigger
+            schedule: HealthSchedule::Interval(std::time::Duration::from_secs(3600)), // Won't trigger
             ..Default::default()
         };
         let _mgr = Arc::new(TrackHealthManager::with_config(config));
@@ -2632,6 +4343,124 @@ mod tests {
         assert!(result.is_ok(), "monitor should shut down promptly");
     }
 
+    // ── On-demand sweeps ──
+
+    #[tokio::test]
+    async fn test_start_health_sweep_completes() {
+        let mgr = Arc::new(TrackHealthManager::new());
+        let tracker = new_health_sweep_tracker();
+        // No tables: nothing to check, and every query fails
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        let handle = start_health_sweep(mgr, Arc::new(MockFetcher::new()), db, tracker.clone())
+            .await
+            .expect("no sweep was running");
+        handle.await.unwrap();
+
+        let status = tracker.lock().await.clone();
+        match &status {
+            HealthSweepTaskStatus::Completed {
+                started_at,
+                finished_at,
+                result,
+            } => {
+                assert!(finished_at >= started_at);
+                assert_eq!(result.total_checked, 0);
+            }
+            other => panic!("expected a completed sweep, got {other:?}"),
+        }
+    }
+
+    /// Panics once the sweep is over, like a bug in the sweep would.
+    struct PanickingFetcher;
+
+    #[async_trait]
+    impl TrackFetcher for PanickingFetcher {
+        async fn fetch_track(&self, _peer_id: &str, hash: &str) -> Result<Bytes, P2pError> {
+            Err(P2pError::TrackNotFound(hash.to_string()))
+        }
+
+        async fn check_blob_exists(&self, _hash: &str) -> bool {
+            false
+        }
+
+        async fn peer_is_online(&self, _peer_id: &str) -> bool {
+            false
+        }
+
+        async fn alternative_sources(&self, _hash: &str) -> Vec<PeerTrackInfo> {
+            Vec::new()
+        }
+
+        async fn sweep_finished(&self, _result: &BatchCheckResult) {
+            panic!("sweep bug");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_panicking_health_sweep_reports_error() {
+        let mgr = Arc::new(TrackHealthManager::new());
+        let tracker = new_health_sweep_tracker();
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        let handle = start_health_sweep(
+            mgr.clone(),
+            Arc::new(PanickingFetcher),
+            db.clone(),
+            tracker.clone(),
+        )
+        .await
+        .expect("no sweep was running");
+        handle.await.unwrap();
+
+        match &*tracker.lock().await {
+            HealthSweepTaskStatus::Error { error, .. } => assert_eq!(error, "sweep bug"),
+            other => panic!("expected a failed sweep, got {other:?}"),
+        }
+        // No longer stuck running: the next sweep starts
+        let handle = start_health_sweep(mgr, Arc::new(MockFetcher::new()), db, tracker.clone())
+            .await
+            .expect("the failed sweep still counts as running");
+        handle.await.unwrap();
+        assert!(matches!(
+            *tracker.lock().await,
+            HealthSweepTaskStatus::Completed { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_start_health_sweep_refuses_while_running() {
+        let tracker = new_health_sweep_tracker();
+        *tracker.lock().await = HealthSweepTaskStatus::Running {
+            started_at: Utc::now(),
+        };
+        let started = start_health_sweep(
+            Arc::new(TrackHealthManager::new()),
+            Arc::new(MockFetcher::new()),
+            DatabaseConnection::Disconnected,
+            tracker.clone(),
+        )
+        .await;
+        assert!(started.is_none());
+        assert!(matches!(
+            *tracker.lock().await,
+            HealthSweepTaskStatus::Running { .. }
+        ));
+    }
+
+    #[test]
+    fn test_health_sweep_task_status_serialization() {
+        let json = serde_json::to_value(HealthSweepTaskStatus::Idle).unwrap();
+        assert_eq!(json["status"], "idle");
+
+        let json = serde_json::to_value(HealthSweepTaskStatus::Completed {
+            started_at: Utc::now(),
+            finished_at: Utc::now(),
+            result: BatchCheckResult::new(),
+        })
+        .unwrap();
+        assert_eq!(json["status"], "completed");
+        assert_eq!(json["result"]["total_checked"], 0);
+    }
+
     // ── persist_track_status ─────────────────────────────────────────
     // persist_track_status requires a real DatabaseConnection to run.
     // It is covered via integration tests with a live PostgreSQL database.
@@ -2791,8 +4620,283 @@ mod tests {
                 + r.failed
                 + r.dereferenced
                 + r.re_referenced
-                + r.unavailable_source,
+                + r.unavailable_source
+                + r.corrupted
+                + r.cleaned_up,
             0
         );
     }
+
+    // ── fetch_verified ───────────────────────────────────────────────
+
+    /// Serves `good` from `good_peer` and corrupted bytes from every other peer.
+    struct CorruptingFetcher {
+        good_peer: String,
+        good: Bytes,
+        rejected: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl CorruptingFetcher {
+        fn new(good_peer: &str, good: &'static [u8]) -> Self {
+            Self {
+                good_peer: good_peer.to_string(),
+                good: Bytes::from_static(good),
+                rejected: std::sync::Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl TrackFetcher for CorruptingFetcher {
+        async fn fetch_track(&self, peer_id: &str, _hash: &str) -> Result<Bytes, P2pError> {
+            if peer_id == self.good_peer {
+                Ok(self.good.clone())
+            } else {
+                Ok(Bytes::from_static(b"poisoned bytes"))
+            }
+        }
+
+        async fn check_blob_exists(&self, _hash: &str) -> bool {
+            false
+        }
+
+        async fn peer_is_online(&self, _peer_id: &str) -> bool {
+            true
+        }
+
+        async fn alternative_sources(&self, _hash: &str) -> Vec<PeerTrackInfo> {
+            Vec::new()
+        }
+
+        async fn reject_source(&self, peer_id: &str) {
+            self.rejected.lock().unwrap().push(peer_id.to_string());
+        }
+    }
+
+    #[test]
+    fn test_verify_blob() {
+        let hash = Hash::new(b"track data");
+        assert!(verify_blob(&hash, b"track data").is_ok());
+        assert!(matches!(
+            verify_blob(&hash, b"other data"),
+            Err(P2pError::HashMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_verified_rejects_wrong_bytes() {
+        let mgr = TrackHealthManager::new();
+        let fetcher = CorruptingFetcher::new("honest", b"real audio");
+        let hash = Hash::new(b"real audio");
+        let mut store: Vec<Bytes> = Vec::new();
+
+        let result = fetch_verified(&mgr, &fetcher, hash, &["evil".to_string()]).await;
+        if let Ok((data, _)) = &result {
+            store.push(data.clone());
+        }
+
+        assert!(matches!(result, Err(P2pError::HashMismatch { .. })));
+        assert!(store.is_empty());
+        let record = mgr.get_record(&hash.to_string()).await.unwrap();
+        assert_eq!(record.failed_attempts, 1);
+        assert_eq!(record.origin_node, "evil");
+        assert_eq!(*fetcher.rejected.lock().unwrap(), vec!["evil".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_verified_falls_through_to_next_source() {
+        let mgr = TrackHealthManager::new();
+        let fetcher = CorruptingFetcher::new("honest", b"real audio");
+        let hash = Hash::new(b"real audio");
+
+        let (data, peer) = fetch_verified(
+            &mgr,
+            &fetcher,
+            hash,
+            &["evil".to_string(), "honest".to_string()],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(data.as_ref(), b"real audio");
+        assert_eq!(peer, "honest");
+        assert_eq!(*fetcher.rejected.lock().unwrap(), vec!["evil".to_string()]);
+    }
+
+    // ── reseeding ────────────────────────────────────────────────────
+
+    /// Peers and the blobs each of them serves.
+    #[derive(Default)]
+    struct SwarmFetcher {
+        holdings: std::sync::Mutex<HashMap<String, HashMap<String, Bytes>>>,
+    }
+
+    impl SwarmFetcher {
+        fn serve(&self, peer_id: &str, hash: &str, data: Bytes) {
+            self.holdings
+                .lock()
+                .unwrap()
+                .entry(peer_id.to_string())
+                .or_default()
+                .insert(hash.to_string(), data);
+        }
+
+        fn go_offline(&self, peer_id: &str) {
+            self.holdings.lock().unwrap().remove(peer_id);
+        }
+    }
+
+    #[async_trait]
+    impl TrackFetcher for SwarmFetcher {
+        async fn fetch_track(&self, peer_id: &str, hash: &str) -> Result<Bytes, P2pError> {
+            self.holdings
+                .lock()
+                .unwrap()
+                .get(peer_id)
+                .and_then(|blobs| blobs.get(hash).cloned())
+                .ok_or_else(|| P2pError::TrackNotFound(hash.to_string()))
+        }
+
+        async fn check_blob_exists(&self, _hash: &str) -> bool {
+            false
+        }
+
+        async fn peer_is_online(&self, peer_id: &str) -> bool {
+            self.holdings.lock().unwrap().contains_key(peer_id)
+        }
+
+        async fn alternative_sources(&self, _hash: &str) -> Vec<PeerTrackInfo> {
+            Vec::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_two_hop_fetch_through_reseeding_peer() {
+        let mgr = TrackHealthManager::new();
+        let swarm = SwarmFetcher::default();
+        let hash = Hash::new(b"replicated audio");
+        let hash_str = hash.to_string();
+        swarm.serve("a", &hash_str, Bytes::from_static(b"replicated audio"));
+
+        // B fetches from the origin A, caches the blob and reseeds it
+        let (data, peer) = fetch_verified(&mgr, &swarm, hash, &["a".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(peer, "a");
+        swarm.serve("b", &hash_str, data);
+
+        // With A gone, C still gets the blob from B
+        swarm.go_offline("a");
+        let (data, peer) = fetch_verified(&mgr, &swarm, hash, &["a".to_string(), "b".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(data.as_ref(), b"replicated audio");
+        assert_eq!(peer, "b");
+
+        // Once B evicts it, nobody serves it
+        swarm.go_offline("b");
+        let result = fetch_verified(&mgr, &swarm, hash, &["a".to_string(), "b".to_string()]).await;
+        assert!(matches!(result, Err(P2pError::TrackNotFound(_))));
+    }
+
+    // ── verify_cached_blobs ──────────────────────────────────────────
+
+    /// Local blob store whose contents tests can tamper with.
+    #[derive(Default)]
+    struct StoredBlobFetcher {
+        stored: std::sync::Mutex<HashMap<String, Bytes>>,
+        deleted: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl StoredBlobFetcher {
+        fn store(&self, hash: &str, data: &'static [u8]) {
+            self.stored
+                .lock()
+                .unwrap()
+                .insert(hash.to_string(), Bytes::from_static(data));
+        }
+    }
+
+    #[async_trait]
+    impl TrackFetcher for StoredBlobFetcher {
+        async fn fetch_track(&self, _peer_id: &str, hash: &str) -> Result<Bytes, P2pError> {
+            Err(P2pError::TrackNotFound(hash.to_string()))
+        }
+
+        async fn check_blob_exists(&self, hash: &str) -> bool {
+            self.stored.lock().unwrap().contains_key(hash)
+        }
+
+        async fn peer_is_online(&self, _peer_id: &str) -> bool {
+            true
+        }
+
+        async fn alternative_sources(&self, _hash: &str) -> Vec<PeerTrackInfo> {
+            Vec::new()
+        }
+
+        async fn read_local_blob(&self, hash: &str) -> Result<Option<Bytes>, P2pError> {
+            Ok(self.stored.lock().unwrap().get(hash).cloned())
+        }
+
+        async fn delete_local_blob(&self, hash: &str) {
+            self.stored.lock().unwrap().remove(hash);
+            self.deleted.lock().unwrap().push(hash.to_string());
+        }
+    }
+
+    fn check_item(hash: &Hash) -> TrackCheckItem {
+        TrackCheckItem {
+            content_hash: hash.to_string(),
+            origin_node: "origin".into(),
+            title: "Cached Track".into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_cached_blobs_detects_and_deletes_corruption() {
+        let mgr = TrackHealthManager::new();
+        let fetcher = StoredBlobFetcher::default();
+        let intact = Hash::new(b"intact audio");
+        let rotten = Hash::new(b"original audio");
+        fetcher.store(&intact.to_string(), b"intact audio");
+        fetcher.store(&rotten.to_string(), b"bit-rotted audio");
+        let items = vec![check_item(&intact), check_item(&rotten)];
+
+        let corrupted = verify_cached_blobs(&mgr, &fetcher, &items, 1.0).await;
+
+        assert_eq!(corrupted, 1);
+        assert_eq!(*fetcher.deleted.lock().unwrap(), vec![rotten.to_string()]);
+        assert!(!fetcher.check_blob_exists(&rotten.to_string()).await);
+        assert!(fetcher.check_blob_exists(&intact.to_string()).await);
+        let record = mgr.get_record(&rotten.to_string()).await.unwrap();
+        assert_eq!(record.status, HealthStatus::Degraded { attempts: 1 });
+        assert_eq!(record.failed_attempts, 1);
+        assert!(mgr.get_record(&intact.to_string()).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_verify_cached_blobs_zero_rate_skips() {
+        let mgr = TrackHealthManager::new();
+        let fetcher = StoredBlobFetcher::default();
+        let rotten = Hash::new(b"original audio");
+        fetcher.store(&rotten.to_string(), b"bit-rotted audio");
+
+        let corrupted = verify_cached_blobs(&mgr, &fetcher, &[check_item(&rotten)], 0.0).await;
+
+        assert_eq!(corrupted, 0);
+        assert!(fetcher.deleted.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_verify_cached_blobs_skips_uncached_tracks() {
+        let mgr = TrackHealthManager::new();
+        let fetcher = StoredBlobFetcher::default();
+        let missing = Hash::new(b"never cached");
+
+        let corrupted = verify_cached_blobs(&mgr, &fetcher, &[check_item(&missing)], 1.0).await;
+
+        assert_eq!(corrupted, 0);
+        assert!(mgr.get_record(&missing.to_string()).await.is_none());
+    }
 }
@@ -43,6 +43,9 @@ pub enum PluginError {
     #[error("HTTP error: {0}")]
     Http(String),
 
+    #[error("rate limited: {0}")]
+    RateLimited(String),
+
     #[error("database error: {0}")]
     Database(#[from] sea_orm::DbErr),
 
@@ -147,6 +150,12 @@ mod tests {
         assert_eq!(err.to_string(), "HTTP error: timeout");
     }
 
+    #[test]
+    fn test_display_rate_limited() {
+        let err = PluginError::RateLimited("p2p_search".into());
+        assert_eq!(err.to_string(), "rate limited: p2p_search");
+    }
+
     // ── From conversions ──────────────────────────────────────────────
 
     #[test]
@@ -13,6 +13,7 @@ pub const KNOWN_EVENTS: &[&str] = &[
     "on_playlist_created",
     "on_peer_connected",
     "on_peer_disconnected",
+    "on_track_announced",
     "on_plugin_event",
 ];
 
@@ -99,6 +100,8 @@ pub struct PeerConnectedPayload {
     pub peer_id: String,
     pub domain: Option<String>,
     pub track_count: Option<u64>,
+    /// SoundTime version the peer reports.
+    pub version: Option<String>,
 }
 
 /// Payload for `on_peer_disconnected` events.
@@ -107,6 +110,17 @@ pub struct PeerDisconnectedPayload {
     pub peer_id: String,
 }
 
+/// Payload for `on_track_announced` events, fired when a track announced by
+/// a P2P peer is added to the local catalog.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackAnnouncedPayload {
+    pub hash: String,
+    pub title: String,
+    pub artist_name: String,
+    pub origin_node: String,
+    pub format: String,
+}
+
 /// Payload for `on_plugin_event` (inter-plugin communication).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginEventPayload {
@@ -124,6 +138,7 @@ mod tests {
         assert!(PluginEvent::is_known_event("on_track_added"));
         assert!(PluginEvent::is_known_event("on_track_played"));
         assert!(PluginEvent::is_known_event("on_peer_connected"));
+        assert!(PluginEvent::is_known_event("on_track_announced"));
         assert!(!PluginEvent::is_known_event("on_unknown_event"));
     }
 
@@ -142,7 +157,7 @@ mod tests {
 
     #[test]
     fn test_known_events_count() {
-        assert_eq!(KNOWN_EVENTS.len(), 10);
+        assert_eq!(KNOWN_EVENTS.len(), 11);
     }
 
     #[test]
@@ -202,12 +217,21 @@ mod tests {
             peer_id: "p".into(),
             domain: Some("example.com".into()),
             track_count: Some(100),
+            version: Some("0.1.0".into()),
         })
         .unwrap();
         let _ = serde_json::to_value(PeerDisconnectedPayload {
             peer_id: "p".into(),
         })
         .unwrap();
+        let _ = serde_json::to_value(TrackAnnouncedPayload {
+            hash: "h".into(),
+            title: "Song".into(),
+            artist_name: "Artist".into(),
+            origin_node: "node".into(),
+            format: "flac".into(),
+        })
+        .unwrap();
         let _ = serde_json::to_value(PluginEventPayload {
             source_plugin: "src".into(),
             event_name: "custom".into(),
@@ -4,6 +4,8 @@
 //! plugins. Each function checks permissions before execution.
 
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
 use chrono::Utc;
 use sea_orm::{
@@ -23,6 +25,15 @@ const MAX_HTTP_RESPONSE_BYTES: usize = 10 * 1024 * 1024;
 /// Maximum log message length from plugins.
 const MAX_LOG_MESSAGE_LEN: usize = 2048;
 
+/// P2P searches a plugin may run per [`P2P_SEARCH_WINDOW`].
+pub const P2P_SEARCHES_PER_WINDOW: u32 = 5;
+
+/// Window over which [`P2P_SEARCHES_PER_WINDOW`] applies.
+pub const P2P_SEARCH_WINDOW: Duration = Duration::from_secs(60);
+
+/// Maximum results a plugin may ask for in one P2P search.
+const MAX_P2P_SEARCH_LIMIT: u32 = 50;
+
 /// Sanitize a log message from a plugin.
 ///
 /// Strips control characters (except newline/tab), truncates to max length.
@@ -95,6 +106,74 @@ pub struct UserInfo {
     pub created_at: String,
 }
 
+/// A track found on the P2P network, as returned by `p2p_search`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct P2pSearchResult {
+    pub hash: String,
+    pub title: String,
+    pub artist_name: String,
+    pub album_title: Option<String>,
+    pub duration_secs: f64,
+    pub format: String,
+    pub genre: Option<String>,
+    pub year: Option<i32>,
+    pub bitrate: Option<i32>,
+    /// Node ID of the peer holding the track.
+    pub source_node: String,
+}
+
+// ─── P2P search ───────────────────────────────────────────────────────
+
+/// Runs distributed searches for plugins.
+///
+/// The plugin crate does not depend on the P2P node; the server registers
+/// an implementation with [`PluginRegistry::set_p2p_search`](crate::PluginRegistry::set_p2p_search)
+/// once the node is running.
+#[async_trait::async_trait]
+pub trait P2pSearchProvider: Send + Sync {
+    /// Search the network for `query`, returning at most `limit` results.
+    async fn search(&self, query: &str, limit: u32) -> Vec<P2pSearchResult>;
+}
+
+/// Slot for the P2P search provider, shared by the registry and every
+/// plugin's host context so one registered late reaches loaded plugins.
+pub type P2pSearchSlot = Arc<OnceLock<Arc<dyn P2pSearchProvider>>>;
+
+/// Token bucket limiting how often a plugin may call a host function.
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    /// Tokens added per second.
+    refill_rate: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket allowing `capacity` calls per `window`.
+    fn new(capacity: u32, window: Duration, now: Instant) -> Self {
+        Self {
+            capacity: capacity as f64,
+            tokens: capacity as f64,
+            refill_rate: capacity as f64 / window.as_secs_f64(),
+            last_refill: now,
+        }
+    }
+
+    /// Take a token if one is available at `now`.
+    fn try_take(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.refill_rate).min(self.capacity);
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
 // ─── Host context ─────────────────────────────────────────────────────
 
 /// Context for host function execution.
@@ -113,6 +192,10 @@ pub struct HostContext {
     http_client: reqwest::Client,
     /// Server version string (passed from main.rs).
     server_version: String,
+    /// Distributed search, once the P2P node is running.
+    p2p_search: P2pSearchSlot,
+    /// Rate limit on `p2p_search`, shared by clones of this context.
+    p2p_search_bucket: Arc<Mutex<TokenBucket>>,
 }
 
 impl HostContext {
@@ -141,9 +224,21 @@ impl HostContext {
             domain,
             http_client,
             server_version,
+            p2p_search: P2pSearchSlot::default(),
+            p2p_search_bucket: Arc::new(Mutex::new(TokenBucket::new(
+                P2P_SEARCHES_PER_WINDOW,
+                P2P_SEARCH_WINDOW,
+                Instant::now(),
+            ))),
         }
     }
 
+    /// Use `slot` for P2P searches instead of the context's own, empty one.
+    pub fn with_p2p_search(mut self, slot: P2pSearchSlot) -> Self {
+        self.p2p_search = slot;
+        self
+    }
+
     // ── Track read functions (no special permission) ─────────────────
 
     /// Get track metadata by ID.
@@ -661,6 +756,50 @@ impl HostContext {
         })
     }
 
+    // ── P2P search (no permission, rate limited) ─────────────────────
+
+    /// Search the P2P network. Limited to [`P2P_SEARCHES_PER_WINDOW`]
+    /// calls per minute per plugin; `limit` is capped at 50.
+    pub async fn p2p_search(
+        &self,
+        query: &str,
+        limit: u32,
+    ) -> Result<Vec<P2pSearchResult>, PluginError> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Err(PluginError::HostFunction(
+                "search query cannot be empty".into(),
+            ));
+        }
+        let provider = self
+            .p2p_search
+            .get()
+            .ok_or_else(|| PluginError::HostFunction("P2P network is not enabled".into()))?;
+
+        let allowed = self
+            .p2p_search_bucket
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .try_take(Instant::now());
+        if !allowed {
+            return Err(PluginError::RateLimited(format!(
+                "plugin '{}' exceeded {P2P_SEARCHES_PER_WINDOW} p2p_search calls per minute",
+                self.plugin_name
+            )));
+        }
+
+        let results = provider
+            .search(query, limit.clamp(1, MAX_P2P_SEARCH_LIMIT))
+            .await;
+        tracing::debug!(
+            plugin = %self.plugin_name,
+            query = %query,
+            results = results.len(),
+            "plugin ran p2p search"
+        );
+        Ok(results)
+    }
+
     // ── Event emission (no special permission) ───────────────────────
 
     /// Emit a custom event. Returns true if the event name is valid.
@@ -1248,4 +1387,113 @@ mod tests {
         assert!(perms.read_users);
         assert_eq!(perms.http_hosts, vec!["example.com"]);
     }
+
+    // ─── P2P search tests ───────────────────────────────────────────
+
+    struct MockSearch;
+
+    #[async_trait::async_trait]
+    impl P2pSearchProvider for MockSearch {
+        async fn search(&self, query: &str, limit: u32) -> Vec<P2pSearchResult> {
+            (0..limit.min(3))
+                .map(|i| P2pSearchResult {
+                    hash: format!("hash{i}"),
+                    title: format!("{query} {i}"),
+                    artist_name: "Artist".into(),
+                    album_title: None,
+                    duration_secs: 200.0,
+                    format: "flac".into(),
+                    genre: Some("Jazz".into()),
+                    year: None,
+                    bitrate: None,
+                    source_node: "peer1".into(),
+                })
+                .collect()
+        }
+    }
+
+    fn search_context() -> HostContext {
+        let slot = P2pSearchSlot::default();
+        let provider: Arc<dyn P2pSearchProvider> = Arc::new(MockSearch);
+        assert!(slot.set(provider).is_ok());
+        test_context(vec![]).with_p2p_search(slot)
+    }
+
+    #[test]
+    fn test_token_bucket_refills_over_window() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(5, Duration::from_secs(60), start);
+        for _ in 0..5 {
+            assert!(bucket.try_take(start));
+        }
+        assert!(!bucket.try_take(start));
+        // One token comes back every 12 seconds
+        assert!(!bucket.try_take(start + Duration::from_secs(11)));
+        assert!(bucket.try_take(start + Duration::from_secs(12)));
+        // Never more than the capacity, however long it waited
+        let later = start + Duration::from_secs(3600);
+        for _ in 0..5 {
+            assert!(bucket.try_take(later));
+        }
+        assert!(!bucket.try_take(later));
+    }
+
+    #[tokio::test]
+    async fn test_p2p_search_returns_results() {
+        let ctx = search_context();
+        let results = ctx.p2p_search("jazz", 5).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].title, "jazz 0");
+        assert_eq!(results[0].source_node, "peer1");
+    }
+
+    #[tokio::test]
+    async fn test_p2p_search_without_node() {
+        let ctx = test_context(vec![]);
+        let err = ctx.p2p_search("jazz", 5).await.unwrap_err();
+        assert!(matches!(err, PluginError::HostFunction(_)));
+    }
+
+    #[tokio::test]
+    async fn test_p2p_search_empty_query() {
+        let ctx = search_context();
+        let err = ctx.p2p_search("  ", 5).await.unwrap_err();
+        assert!(matches!(err, PluginError::HostFunction(_)));
+    }
+
+    #[tokio::test]
+    async fn test_p2p_search_rate_limited_per_plugin() {
+        let ctx = search_context();
+        // Clones share the bucket, as dispatch clones the context per event
+        let clone = ctx.clone();
+        for _ in 0..P2P_SEARCHES_PER_WINDOW {
+            assert!(clone.p2p_search("jazz", 5).await.is_ok());
+        }
+        let err = ctx.p2p_search("jazz", 5).await.unwrap_err();
+        assert!(matches!(err, PluginError::RateLimited(_)));
+
+        // Another plugin has its own bucket
+        assert!(search_context().p2p_search("jazz", 5).await.is_ok());
+    }
+
+    #[test]
+    fn test_p2p_search_result_serialization() {
+        let result = P2pSearchResult {
+            hash: "h".into(),
+            title: "So What".into(),
+            artist_name: "Miles Davis".into(),
+            album_title: Some("Kind of Blue".into()),
+            duration_secs: 562.0,
+            format: "flac".into(),
+            genre: None,
+            year: Some(1959),
+            bitrate: None,
+            source_node: "peer1".into(),
+        };
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["title"], "So What");
+        assert_eq!(json["year"], 1959);
+        let back: P2pSearchResult = serde_json::from_value(json).unwrap();
+        assert_eq!(back, result);
+    }
 }
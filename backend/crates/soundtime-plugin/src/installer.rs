@@ -117,6 +117,34 @@ async fn copy_dir_recursive(src: &Path, dest: &Path) -> Result<(), PluginError>
 
 // ─── Installer ──────────────────────────────────────────────────────────
 
+/// A new version of an installed plugin, cloned and validated but not
+/// installed yet. See [`PluginInstaller::prepare_update`].
+pub struct PreparedUpdate {
+    existing: plugin::Model,
+    manifest: PluginManifest,
+    manifest_path: PathBuf,
+    wasm_path: PathBuf,
+    /// Checkout of the new version, removed when the update is dropped
+    _checkout: tempfile::TempDir,
+}
+
+impl PreparedUpdate {
+    /// The plugin's record as it will be once the update is committed,
+    /// except that `wasm_path` still points into the checkout.
+    pub fn candidate(&self) -> Result<plugin::Model, PluginError> {
+        let mut model = self.existing.clone();
+        model.version = self.manifest.plugin.version.clone();
+        model.description = Some(self.manifest.plugin.description.clone());
+        model.author = self.manifest.plugin.author.clone();
+        model.license = self.manifest.plugin.license.clone();
+        model.homepage = self.manifest.plugin.homepage.clone();
+        model.wasm_path = self.wasm_path.to_string_lossy().to_string();
+        model.permissions = serde_json::to_value(&self.manifest.permissions)?;
+        model.error_message = None;
+        Ok(model)
+    }
+}
+
 /// Plugin installer handles the complete installation flow.
 pub struct PluginInstaller {
     /// Base directory for installed plugins.
@@ -398,21 +426,27 @@ impl PluginInstaller {
 
     /// Update an installed plugin by re-cloning and re-validating.
     ///
-    /// The old version is preserved for rollback. If loading the new
-    /// version fails, the old version is restored.
+    /// Same as [`prepare_update`](Self::prepare_update) followed by
+    /// [`commit_update`](Self::commit_update).
     pub async fn update_plugin(&self, plugin_id: Uuid) -> Result<plugin::Model, PluginError> {
+        let update = self.prepare_update(plugin_id).await?;
+        self.commit_update(update).await
+    }
+
+    /// Clone the latest version of an installed plugin and validate its
+    /// manifest and WASM binary, without installing it. Nothing is written
+    /// to the plugin directory or the database until
+    /// [`commit_update`](Self::commit_update).
+    pub async fn prepare_update(&self, plugin_id: Uuid) -> Result<PreparedUpdate, PluginError> {
         // Get existing plugin from DB
         let existing = plugin::Entity::find_by_id(plugin_id)
             .one(&self.db)
             .await?
             .ok_or_else(|| PluginError::NotFound(plugin_id.to_string()))?;
 
-        let old_version = existing.version.clone();
-        let git_url = existing.git_url.clone();
-
         tracing::info!(
             plugin_name = %existing.name,
-            old_version = %old_version,
+            old_version = %existing.version,
             "starting plugin update"
         );
 
@@ -421,12 +455,22 @@ impl PluginInstaller {
             .map_err(|e| PluginError::Installation(format!("failed to create temp dir: {e}")))?;
 
         // SECURITY: Validate git URL before cloning
-        validate_git_url(&git_url)?;
+        validate_git_url(&existing.git_url)?;
 
-        self.clone_repo(&git_url, temp_dir.path())?;
+        self.clone_repo(&existing.git_url, temp_dir.path())?;
 
+        self.read_checkout(existing, temp_dir).await
+    }
+
+    /// Validate the manifest and WASM binary of a new version of `existing`
+    /// checked out in `checkout`.
+    async fn read_checkout(
+        &self,
+        existing: plugin::Model,
+        checkout: tempfile::TempDir,
+    ) -> Result<PreparedUpdate, PluginError> {
         // Parse and validate new manifest
-        let manifest_path = temp_dir.path().join("plugin.toml");
+        let manifest_path = checkout.path().join("plugin.toml");
         if !manifest_path.exists() {
             return Err(PluginError::Installation(
                 "plugin.toml not found in updated repository".into(),
@@ -458,7 +502,7 @@ impl PluginInstaller {
         }
 
         // Validate WASM
-        let wasm_path = temp_dir.path().join(&manifest.build.wasm);
+        let wasm_path = checkout.path().join(&manifest.build.wasm);
         if !wasm_path.exists() {
             return Err(PluginError::Installation(format!(
                 "WASM binary not found at: {}",
@@ -470,7 +514,11 @@ impl PluginInstaller {
         let wasm_path = wasm_path
             .canonicalize()
             .map_err(|e| PluginError::Installation(format!("invalid WASM path: {e}")))?;
-        if !wasm_path.starts_with(temp_dir.path()) {
+        let checkout_root = checkout
+            .path()
+            .canonicalize()
+            .map_err(|e| PluginError::Installation(format!("invalid checkout path: {e}")))?;
+        if !wasm_path.starts_with(&checkout_root) {
             return Err(PluginError::Installation(
                 "WASM path escapes repository directory (path traversal)".into(),
             ));
@@ -478,6 +526,31 @@ impl PluginInstaller {
 
         self.validate_wasm(&wasm_path).await?;
 
+        Ok(PreparedUpdate {
+            existing,
+            manifest,
+            manifest_path,
+            wasm_path,
+            _checkout: checkout,
+        })
+    }
+
+    /// Install a prepared update: copy it to the plugin directory and update
+    /// the plugin's record. The old installation is kept as a backup until
+    /// both succeed, and restored if either fails.
+    pub async fn commit_update(
+        &self,
+        update: PreparedUpdate,
+    ) -> Result<plugin::Model, PluginError> {
+        let PreparedUpdate {
+            existing,
+            manifest,
+            manifest_path,
+            wasm_path,
+            _checkout,
+        } = update;
+        let old_version = existing.version.clone();
+
         // Copy new version to install dir
         let install_dir = self.plugin_dir.join(format!(
             "{}-{}",
@@ -489,6 +562,7 @@ impl PluginInstaller {
         let old_install_dir = old_wasm_path.parent().map(|p| p.to_path_buf());
         let backup_dir = old_install_dir.as_ref().map(|d| d.with_extension("bak"));
 
+        let mut backed_up = None;
         if let Some(ref old_dir) = old_install_dir {
             if old_dir.exists() {
                 if let Some(ref bak) = backup_dir {
@@ -498,52 +572,71 @@ impl PluginInstaller {
                     tokio::fs::rename(old_dir, bak).await.map_err(|e| {
                         PluginError::Installation(format!("failed to backup old version: {e}"))
                     })?;
+                    backed_up = Some((old_dir.clone(), bak.clone()));
                 }
             }
         }
 
-        if install_dir.exists() {
-            tokio::fs::remove_dir_all(&install_dir).await.map_err(|e| {
-                PluginError::Installation(format!("failed to clean install dir: {e}"))
-            })?;
-        }
-
-        tokio::fs::create_dir_all(&install_dir)
-            .await
-            .map_err(|e| PluginError::Installation(format!("failed to create install dir: {e}")))?;
-
-        let dest_wasm = install_dir.join("plugin.wasm");
-        tokio::fs::copy(&wasm_path, &dest_wasm)
-            .await
-            .map_err(|e| PluginError::Installation(format!("failed to copy WASM: {e}")))?;
-
-        let dest_manifest = install_dir.join("plugin.toml");
-        tokio::fs::copy(&manifest_path, &dest_manifest)
-            .await
-            .map_err(|e| PluginError::Installation(format!("failed to copy manifest: {e}")))?;
+        let installed = async {
+            if install_dir.exists() {
+                tokio::fs::remove_dir_all(&install_dir).await.map_err(|e| {
+                    PluginError::Installation(format!("failed to clean install dir: {e}"))
+                })?;
+            }
 
-        // Update DB record
-        let permissions_json = serde_json::to_value(&manifest.permissions)?;
-        let now = chrono::Utc::now().fixed_offset();
+            tokio::fs::create_dir_all(&install_dir).await.map_err(|e| {
+                PluginError::Installation(format!("failed to create install dir: {e}"))
+            })?;
 
-        let mut active: plugin::ActiveModel = existing.into();
-        active.version = Set(manifest.plugin.version.clone());
-        active.description = Set(Some(manifest.plugin.description.clone()));
-        active.author = Set(manifest.plugin.author.clone());
-        active.license = Set(manifest.plugin.license.clone());
-        active.homepage = Set(manifest.plugin.homepage.clone());
-        active.wasm_path = Set(dest_wasm.to_string_lossy().to_string());
-        active.permissions = Set(permissions_json);
-        active.error_message = Set(None);
-        active.updated_at = Set(now);
+            let dest_wasm = install_dir.join("plugin.wasm");
+            tokio::fs::copy(&wasm_path, &dest_wasm)
+                .await
+                .map_err(|e| PluginError::Installation(format!("failed to copy WASM: {e}")))?;
 
-        let model = active.update(&self.db).await?;
+            let dest_manifest = install_dir.join("plugin.toml");
+            tokio::fs::copy(&manifest_path, &dest_manifest)
+                .await
+                .map_err(|e| PluginError::Installation(format!("failed to copy manifest: {e}")))?;
+
+            // Update DB record
+            let permissions_json = serde_json::to_value(&manifest.permissions)?;
+            let now = chrono::Utc::now().fixed_offset();
+
+            let mut active: plugin::ActiveModel = existing.into();
+            active.version = Set(manifest.plugin.version.clone());
+            active.description = Set(Some(manifest.plugin.description.clone()));
+            active.author = Set(manifest.plugin.author.clone());
+            active.license = Set(manifest.plugin.license.clone());
+            active.homepage = Set(manifest.plugin.homepage.clone());
+            active.wasm_path = Set(dest_wasm.to_string_lossy().to_string());
+            active.permissions = Set(permissions_json);
+            active.error_message = Set(None);
+            active.updated_at = Set(now);
+
+            Ok::<_, PluginError>(active.update(&self.db).await?)
+        }
+        .await;
+
+        let model = match installed {
+            Ok(model) => model,
+            Err(e) => {
+                // Put the old installation back
+                let _ = tokio::fs::remove_dir_all(&install_dir).await;
+                if let Some((old_dir, bak)) = backed_up {
+                    if let Err(restore) = tokio::fs::rename(&bak, &old_dir).await {
+                        tracing::error!(
+                            plugin_name = %manifest.plugin.name,
+                            "failed to restore previous plugin version: {restore}"
+                        );
+                    }
+                }
+                return Err(e);
+            }
+        };
 
         // Clean up backup on success
-        if let Some(ref bak) = backup_dir {
-            if bak.exists() {
-                let _ = tokio::fs::remove_dir_all(bak).await;
-            }
+        if let Some((_, bak)) = backed_up {
+            let _ = tokio::fs::remove_dir_all(bak).await;
         }
 
         tracing::info!(
@@ -885,4 +978,101 @@ mod tests {
 
         assert!(installer.validate_wasm_imports(&wasm).is_ok());
     }
+
+    // ── Update preparation ──────────────────────────────────────────────
+
+    const UPDATED_MANIFEST: &str = r#"
+[plugin]
+name = "ab"
+version = "0.2.0"
+description = "Minimal plugin"
+
+[build]
+wasm = "plugin.wasm"
+"#;
+
+    const EMPTY_MODULE: [u8; 8] = [0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00];
+
+    /// An installed plugin `name` at version 0.1.0 under `plugin_dir`.
+    async fn installed_plugin(plugin_dir: &Path, name: &str) -> plugin::Model {
+        let install_dir = plugin_dir.join(format!("{name}-0.1.0"));
+        tokio::fs::create_dir_all(&install_dir).await.unwrap();
+        let wasm_path = install_dir.join("plugin.wasm");
+        tokio::fs::write(&wasm_path, EMPTY_MODULE).await.unwrap();
+
+        let now = chrono::Utc::now().fixed_offset();
+        plugin::Model {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            version: "0.1.0".to_string(),
+            description: None,
+            author: None,
+            license: None,
+            homepage: None,
+            git_url: "https://github.com/example/ab.git".to_string(),
+            wasm_path: wasm_path.to_string_lossy().to_string(),
+            permissions: serde_json::json!({}),
+            status: "enabled".to_string(),
+            error_message: None,
+            installed_at: now,
+            updated_at: now,
+            installed_by: None,
+        }
+    }
+
+    /// A checkout of version 0.2.0 of plugin "ab".
+    async fn updated_checkout() -> tempfile::TempDir {
+        let checkout = tempfile::tempdir().unwrap();
+        tokio::fs::write(checkout.path().join("plugin.toml"), UPDATED_MANIFEST)
+            .await
+            .unwrap();
+        tokio::fs::write(checkout.path().join("plugin.wasm"), EMPTY_MODULE)
+            .await
+            .unwrap();
+        checkout
+    }
+
+    #[tokio::test]
+    async fn test_read_checkout_leaves_installation_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let installer = PluginInstaller {
+            plugin_dir: dir.path().to_path_buf(),
+            max_wasm_size: 50 * 1024 * 1024,
+            db: sea_orm::DatabaseConnection::Disconnected,
+        };
+        let existing = installed_plugin(dir.path(), "ab").await;
+        let old_wasm = PathBuf::from(&existing.wasm_path);
+
+        let checkout = updated_checkout().await;
+        let checkout_root = checkout.path().canonicalize().unwrap();
+        let update = installer.read_checkout(existing, checkout).await.unwrap();
+
+        // The candidate loads from the checkout...
+        let candidate = update.candidate().unwrap();
+        assert_eq!(candidate.version, "0.2.0");
+        assert!(PathBuf::from(&candidate.wasm_path).starts_with(&checkout_root));
+
+        // ...and nothing is installed until the update is committed
+        assert!(old_wasm.exists());
+        assert!(!dir.path().join("ab-0.2.0").exists());
+    }
+
+    #[tokio::test]
+    async fn test_read_checkout_rejects_name_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let installer = PluginInstaller {
+            plugin_dir: dir.path().to_path_buf(),
+            max_wasm_size: 50 * 1024 * 1024,
+            db: sea_orm::DatabaseConnection::Disconnected,
+        };
+        let existing = installed_plugin(dir.path(), "other").await;
+
+        let err = installer
+            .read_checkout(existing, updated_checkout().await)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, PluginError::Installation(_)));
+        assert!(err.to_string().contains("name mismatch"));
+    }
 }
@@ -17,10 +17,11 @@ pub use error::PluginError;
 pub use events::{
     LibraryScanCompletePayload, PeerConnectedPayload, PeerDisconnectedPayload,
     PlaylistCreatedPayload, PluginEvent, PluginEventPayload, TrackAddedPayload,
-    TrackDeletedPayload, TrackPlayedPayload, UserLoginPayload, UserRegisteredPayload, KNOWN_EVENTS,
+    TrackAnnouncedPayload, TrackDeletedPayload, TrackPlayedPayload, UserLoginPayload,
+    UserRegisteredPayload, KNOWN_EVENTS,
 };
-pub use host_functions::HostContext;
+pub use host_functions::{HostContext, P2pSearchProvider, P2pSearchResult};
 pub use installer::PluginInstaller;
 pub use manifest::PluginManifest;
-pub use registry::PluginRegistry;
+pub use registry::{PluginRegistry, PluginReloadProgress, ReloadStage};
 pub use sandbox::{PluginSandbox, SandboxConfig};
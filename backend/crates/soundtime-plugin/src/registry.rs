@@ -4,15 +4,17 @@
 //! It loads plugins from the database, maintains their WASM sandboxes,
 //! and routes events to subscribed plugins.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
 use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use uuid::Uuid;
 
 use crate::error::PluginError;
-use crate::host_functions::HostContext;
+use crate::host_functions::{HostContext, P2pSearchProvider, P2pSearchSlot};
+use crate::installer::PluginInstaller;
 use crate::manifest::Permissions;
 use crate::sandbox::{PluginSandbox, SandboxConfig};
 use soundtime_db::entities::{plugin, plugin_events_log};
@@ -32,6 +34,17 @@ struct HostRequest {
     args: serde_json::Value,
 }
 
+/// Export called with the results of a `p2p_search` host request.
+const P2P_SEARCH_RESULTS_HANDLER: &str = "handle_p2p_search_results";
+
+/// Data a host request produced, delivered back to the plugin by calling
+/// its `handler` export with `payload`.
+#[derive(Debug)]
+struct HostReply {
+    handler: &'static str,
+    payload: serde_json::Value,
+}
+
 /// Response from a plugin event handler, optionally containing host requests.
 #[derive(Debug, serde::Deserialize)]
 struct PluginResponse {
@@ -50,6 +63,41 @@ struct LoadedPlugin {
     /// Events this plugin is subscribed to (kept for reload/introspection).
     #[allow(dead_code)]
     subscribed_events: Vec<String>,
+    /// Read-locked while an event is handled, including its host requests
+    /// and replies; a reload write-locks it to wait for them to finish.
+    in_flight: Arc<RwLock<()>>,
+}
+
+// ─── Hot reload ─────────────────────────────────────────────────────────
+
+/// Reload progress events kept for slow subscribers.
+const RELOAD_CHANNEL_CAPACITY: usize = 64;
+
+/// Stage of a plugin hot reload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReloadStage {
+    /// Pulling the latest version from git and validating it.
+    Fetching,
+    /// Loading the new WASM binary into a sandbox.
+    Loading,
+    /// Waiting for event handlers running on the old sandbox to finish.
+    Draining,
+    /// The new version is handling events.
+    Reloaded,
+    /// The reload stopped; the old sandbox keeps handling events.
+    Failed,
+}
+
+/// Progress of a plugin hot reload, streamed by
+/// `GET /api/admin/plugins/events`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PluginReloadProgress {
+    pub plugin_id: Uuid,
+    pub stage: ReloadStage,
+    /// The new version once reloaded, or the error if the reload failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
 }
 
 // ─── Registry ───────────────────────────────────────────────────────────
@@ -76,13 +124,21 @@ pub struct PluginRegistry {
     log_events: bool,
     /// Server version string, passed to plugins via host context.
     server_version: String,
+    /// Distributed search shared with every plugin's host context.
+    p2p_search: P2pSearchSlot,
+    /// Plugins with a reload in progress.
+    reloading: Mutex<HashSet<Uuid>>,
+    /// Reload progress for `GET /api/admin/plugins/events`.
+    reload_events: broadcast::Sender<PluginReloadProgress>,
 }
 
 /// Process host function requests from a plugin's event handler response.
+/// Returns the replies to hand back to the plugin.
 async fn process_host_requests(
     host_ctx: &crate::host_functions::HostContext,
     requests: Vec<HostRequest>,
-) {
+) -> Vec<HostReply> {
+    let mut replies = Vec::new();
     for req in requests {
         let result = match req.function.as_str() {
             "set_track_metadata" => {
@@ -127,6 +183,23 @@ async fn process_host_requests(
                 let payload = req.args["payload"].as_str().unwrap_or_default();
                 host_ctx.emit_event(event, payload).map(|_| ())
             }
+            "p2p_search" => {
+                let query = req.args["query"].as_str().unwrap_or_default();
+                let limit = req.args["limit"]
+                    .as_u64()
+                    .unwrap_or(10)
+                    .min(u32::MAX as u64) as u32;
+                host_ctx.p2p_search(query, limit).await.map(|results| {
+                    replies.push(HostReply {
+                        handler: P2P_SEARCH_RESULTS_HANDLER,
+                        payload: serde_json::json!({
+                            "request_id": req.args["request_id"],
+                            "query": query,
+                            "results": results,
+                        }),
+                    });
+                })
+            }
             other => {
                 tracing::warn!(
                     plugin = %host_ctx.plugin_name(),
@@ -145,6 +218,7 @@ async fn process_host_requests(
             );
         }
     }
+    replies
 }
 
 impl PluginRegistry {
@@ -163,6 +237,8 @@ impl PluginRegistry {
         let log_events =
             std::env::var("PLUGIN_LOG_EVENTS").unwrap_or_else(|_| "true".to_string()) == "true";
 
+        let (reload_events, _) = broadcast::channel(RELOAD_CHANNEL_CAPACITY);
+
         Ok(Self {
             plugins: RwLock::new(HashMap::new()),
             subscriptions: RwLock::new(HashMap::new()),
@@ -172,9 +248,18 @@ impl PluginRegistry {
             domain: domain.to_string(),
             log_events,
             server_version: server_version.to_string(),
+            p2p_search: P2pSearchSlot::default(),
+            reloading: Mutex::new(HashSet::new()),
+            reload_events,
         })
     }
 
+    /// Let plugins search the P2P network through `provider`. Can only be
+    /// set once; returns `false` if a provider was already set.
+    pub fn set_p2p_search(&self, provider: Arc<dyn P2pSearchProvider>) -> bool {
+        self.p2p_search.set(provider).is_ok()
+    }
+
     /// Load all enabled plugins from the database.
     ///
     /// Queries plugins with status "enabled", loads their WASM sandboxes,
@@ -209,6 +294,13 @@ impl PluginRegistry {
 
     /// Load a single plugin from its DB model.
     async fn load_plugin_from_model(&self, model: &plugin::Model) -> Result<(), PluginError> {
+        let loaded = self.build_loaded_plugin(model)?;
+        self.insert_loaded_plugin(loaded).await;
+        Ok(())
+    }
+
+    /// Load a plugin's WASM sandbox and host context without registering it.
+    fn build_loaded_plugin(&self, model: &plugin::Model) -> Result<LoadedPlugin, PluginError> {
         let wasm_path = PathBuf::from(&model.wasm_path);
 
         // Parse permissions from JSONB
@@ -229,38 +321,57 @@ impl PluginRegistry {
             self.sandbox_config.http_timeout_secs,
             self.domain.clone(),
             self.server_version.clone(),
-        );
+        )
+        .with_p2p_search(self.p2p_search.clone());
 
-        let loaded = LoadedPlugin {
+        Ok(LoadedPlugin {
             id: model.id,
             name: model.name.clone(),
             sandbox,
             host_ctx,
-            subscribed_events: subscribed_events.clone(),
-        };
+            subscribed_events,
+            in_flight: Arc::new(RwLock::new(())),
+        })
+    }
+
+    /// Register a loaded plugin, replacing any previous sandbox for it, and
+    /// return the sandbox it replaced. A plugin keeps its place in the
+    /// dispatch order of events it was already subscribed to.
+    async fn insert_loaded_plugin(&self, loaded: LoadedPlugin) -> Option<LoadedPlugin> {
+        let plugin_id = loaded.id;
+        let plugin_name = loaded.name.clone();
+        let subscribed_events = loaded.subscribed_events.clone();
 
         // Register in plugins map
-        {
+        let previous = {
             let mut plugins = self.plugins.write().await;
-            plugins.insert(model.id, loaded);
-        }
+            plugins.insert(plugin_id, loaded)
+        };
 
         // Register event subscriptions
         {
             let mut subs = self.subscriptions.write().await;
+            for (event, subscribers) in subs.iter_mut() {
+                if !subscribed_events.contains(event) {
+                    subscribers.retain(|id| *id != plugin_id);
+                }
+            }
             for event in &subscribed_events {
-                subs.entry(event.clone()).or_default().push(model.id);
+                let subscribers = subs.entry(event.clone()).or_default();
+                if !subscribers.contains(&plugin_id) {
+                    subscribers.push(plugin_id);
+                }
             }
         }
 
         tracing::info!(
-            plugin_name = %model.name,
-            plugin_id = %model.id,
+            plugin_name = %plugin_name,
+            plugin_id = %plugin_id,
             events = ?subscribed_events,
             "plugin loaded"
         );
 
-        Ok(())
+        previous
     }
 
     /// Load a plugin by its database ID.
@@ -303,6 +414,110 @@ impl PluginRegistry {
         Ok(())
     }
 
+    /// Pull the latest version of a plugin from its git repository and swap
+    /// it in without a restart. The old sandbox keeps handling events until
+    /// the new one is loaded; the swap then waits for its in-flight event
+    /// handlers to finish. The new version is only installed once it has
+    /// been swapped in, so one that fails to load leaves the old version
+    /// running and on disk. Disabled plugins are only updated on disk.
+    /// Progress is published to [`Self::subscribe_reloads`].
+    pub async fn reload_plugin(&self, plugin_id: Uuid) -> Result<plugin::Model, PluginError> {
+        if !self.lock_reloading().insert(plugin_id) {
+            let e = PluginError::Installation("a reload is already in progress".into());
+            self.report_reload(plugin_id, ReloadStage::Failed, Some(e.to_string()));
+            return Err(e);
+        }
+
+        let result = self.swap_in_latest(plugin_id).await;
+        self.lock_reloading().remove(&plugin_id);
+
+        match &result {
+            Ok(model) => {
+                tracing::info!(
+                    plugin_id = %plugin_id,
+                    version = %model.version,
+                    "plugin reloaded"
+                );
+                self.report_reload(
+                    plugin_id,
+                    ReloadStage::Reloaded,
+                    Some(model.version.clone()),
+                );
+            }
+            Err(e) => {
+                tracing::error!(plugin_id = %plugin_id, "plugin reload failed: {e}");
+                self.report_reload(plugin_id, ReloadStage::Failed, Some(e.to_string()));
+            }
+        }
+        result
+    }
+
+    async fn swap_in_latest(&self, plugin_id: Uuid) -> Result<plugin::Model, PluginError> {
+        self.report_reload(plugin_id, ReloadStage::Fetching, None);
+        let installer = PluginInstaller::new(self.db.clone());
+        let update = installer.prepare_update(plugin_id).await?;
+        let candidate = update.candidate()?;
+        if candidate.status != "enabled" {
+            return installer.commit_update(update).await;
+        }
+
+        // Build from the checkout, before anything is installed
+        self.report_reload(plugin_id, ReloadStage::Loading, None);
+        let loaded = self.build_loaded_plugin(&candidate)?;
+
+        self.report_reload(plugin_id, ReloadStage::Draining, None);
+        let old_in_flight = self
+            .plugins
+            .read()
+            .await
+            .get(&plugin_id)
+            .map(|p| Arc::clone(&p.in_flight));
+        let _drained = match &old_in_flight {
+            Some(in_flight) => Some(in_flight.write().await),
+            None => None,
+        };
+        let previous = self.insert_loaded_plugin(loaded).await;
+
+        match installer.commit_update(update).await {
+            Ok(model) => Ok(model),
+            Err(e) => {
+                // Put the old sandbox back so memory matches what is installed
+                match previous {
+                    Some(old) => {
+                        self.insert_loaded_plugin(old).await;
+                    }
+                    None => {
+                        let _ = self.unload_plugin(plugin_id).await;
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Whether a reload of `plugin_id` is in progress.
+    pub fn is_reloading(&self, plugin_id: Uuid) -> bool {
+        self.lock_reloading().contains(&plugin_id)
+    }
+
+    /// Receive reload progress published from now on.
+    pub fn subscribe_reloads(&self) -> broadcast::Receiver<PluginReloadProgress> {
+        self.reload_events.subscribe()
+    }
+
+    fn lock_reloading(&self) -> std::sync::MutexGuard<'_, HashSet<Uuid>> {
+        self.reloading.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn report_reload(&self, plugin_id: Uuid, stage: ReloadStage, message: Option<String>) {
+        // No subscribers is fine
+        let _ = self.reload_events.send(PluginReloadProgress {
+            plugin_id,
+            stage,
+            message,
+        });
+    }
+
     /// Dispatch an event to all subscribed plugins.
     ///
     /// Iterates over subscribed plugins in load order. Each plugin's WASM
@@ -329,6 +544,14 @@ impl PluginRegistry {
         };
 
         for plugin_id in subscriber_ids {
+            let in_flight = match self.plugins.read().await.get(&plugin_id) {
+                Some(loaded) => Arc::clone(&loaded.in_flight),
+                None => continue,
+            };
+            // Held until the handler's host requests and replies are done,
+            // so a reload cannot swap the sandbox in between
+            let _in_flight = in_flight.read_owned().await;
+
             let start = std::time::Instant::now();
             let mut result_str = "success";
             let mut error_msg: Option<String> = None;
@@ -402,7 +625,8 @@ impl PluginRegistry {
                     count = requests.len(),
                     "processing host function requests"
                 );
-                process_host_requests(&host_ctx, requests).await;
+                let replies = process_host_requests(&host_ctx, requests).await;
+                self.deliver_host_replies(plugin_id, replies).await;
             }
 
             // Log event execution to DB if enabled
@@ -422,6 +646,63 @@ impl PluginRegistry {
         }
     }
 
+    /// Hand host request results back to a plugin by calling the export
+    /// each reply names. Plugins that do not export it are skipped. Host
+    /// requests in the plugin's answer are processed, but their own replies
+    /// are dropped so a plugin cannot loop on itself.
+    async fn deliver_host_replies(&self, plugin_id: Uuid, replies: Vec<HostReply>) {
+        for reply in replies {
+            let payload_bytes = match serde_json::to_vec(&reply.payload) {
+                Ok(b) => b,
+                Err(e) => {
+                    tracing::error!(handler = %reply.handler, "failed to serialize host reply: {e}");
+                    continue;
+                }
+            };
+
+            let follow_up = {
+                let mut plugins = self.plugins.write().await;
+                let Some(loaded) = plugins.get_mut(&plugin_id) else {
+                    return;
+                };
+                if !loaded.sandbox.has_function(reply.handler) {
+                    tracing::debug!(
+                        plugin = %loaded.name,
+                        handler = %reply.handler,
+                        "plugin does not export reply handler, dropping reply"
+                    );
+                    continue;
+                }
+                match loaded.sandbox.call(reply.handler, &payload_bytes) {
+                    Ok(output) => serde_json::from_slice::<PluginResponse>(&output)
+                        .ok()
+                        .filter(|r| !r.host_requests.is_empty())
+                        .map(|r| (r.host_requests, loaded.host_ctx.clone())),
+                    Err(e) => {
+                        tracing::error!(
+                            plugin = %loaded.name,
+                            handler = %reply.handler,
+                            "reply handler failed: {e}"
+                        );
+                        None
+                    }
+                }
+            };
+            // Write lock dropped here
+
+            if let Some((requests, host_ctx)) = follow_up {
+                let dropped = process_host_requests(&host_ctx, requests).await;
+                if !dropped.is_empty() {
+                    tracing::warn!(
+                        plugin = %host_ctx.plugin_name(),
+                        count = dropped.len(),
+                        "host requests made from a reply handler get no reply"
+                    );
+                }
+            }
+        }
+    }
+
     /// Log an event execution to the `plugin_events_log` table.
     async fn log_event_execution(
         &self,
@@ -852,6 +1133,156 @@ mod tests {
         process_host_requests(&host_ctx, requests).await;
     }
 
+    struct JazzSearch;
+
+    #[async_trait::async_trait]
+    impl P2pSearchProvider for JazzSearch {
+        async fn search(
+            &self,
+            query: &str,
+            limit: u32,
+        ) -> Vec<crate::host_functions::P2pSearchResult> {
+            (0..limit)
+                .map(|i| crate::host_functions::P2pSearchResult {
+                    hash: format!("hash{i}"),
+                    title: format!("{query} standard {i}"),
+                    artist_name: "Quartet".into(),
+                    album_title: None,
+                    duration_secs: 300.0,
+                    format: "flac".into(),
+                    genre: Some("Jazz".into()),
+                    year: Some(1959),
+                    bitrate: None,
+                    source_node: "peer1".into(),
+                })
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_host_requests_p2p_search_replies_with_results() {
+        use crate::manifest::Permissions;
+
+        let slot = P2pSearchSlot::default();
+        let provider: Arc<dyn P2pSearchProvider> = Arc::new(JazzSearch);
+        assert!(slot.set(provider).is_ok());
+        let host_ctx = HostContext::new(
+            sea_orm::DatabaseConnection::Disconnected,
+            Uuid::new_v4(),
+            "test-plugin".to_string(),
+            Permissions::default(),
+            10,
+            "localhost".to_string(),
+            "0.1.0".to_string(),
+        )
+        .with_p2p_search(slot);
+
+        // What a plugin returns to run p2p_search("jazz", 5)
+        let response: PluginResponse = serde_json::from_str(
+            r#"{"host_requests":[{"function":"p2p_search","args":{"query":"jazz","limit":5,"request_id":"r1"}}]}"#,
+        )
+        .unwrap();
+        let replies = process_host_requests(&host_ctx, response.host_requests).await;
+
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].handler, "handle_p2p_search_results");
+        let payload = &replies[0].payload;
+        assert_eq!(payload["request_id"], "r1");
+        assert_eq!(payload["query"], "jazz");
+        let results: Vec<crate::host_functions::P2pSearchResult> =
+            serde_json::from_value(payload["results"].clone()).unwrap();
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].title, "jazz standard 0");
+        assert_eq!(results[0].source_node, "peer1");
+    }
+
+    #[tokio::test]
+    async fn test_process_host_requests_p2p_search_without_node_has_no_reply() {
+        use crate::manifest::Permissions;
+
+        let host_ctx = HostContext::new(
+            sea_orm::DatabaseConnection::Disconnected,
+            Uuid::new_v4(),
+            "test-plugin".to_string(),
+            Permissions::default(),
+            10,
+            "localhost".to_string(),
+            "0.1.0".to_string(),
+        );
+        let requests = vec![HostRequest {
+            function: "p2p_search".to_string(),
+            args: serde_json::json!({"query": "jazz", "limit": 5}),
+        }];
+
+        assert!(process_host_requests(&host_ctx, requests).await.is_empty());
+    }
+
+    // ── Hot reload ──────────────────────────────────────────────────
+
+    #[test]
+    fn test_reload_progress_serialization() {
+        let id = Uuid::new_v4();
+        let progress = PluginReloadProgress {
+            plugin_id: id,
+            stage: ReloadStage::Reloaded,
+            message: Some("1.2.0".into()),
+        };
+        let val = serde_json::to_value(&progress).unwrap();
+        assert_eq!(val["plugin_id"], id.to_string());
+        assert_eq!(val["stage"], "reloaded");
+        assert_eq!(val["message"], "1.2.0");
+
+        let progress = PluginReloadProgress {
+            plugin_id: id,
+            stage: ReloadStage::Draining,
+            message: None,
+        };
+        let val = serde_json::to_value(&progress).unwrap();
+        assert_eq!(val["stage"], "draining");
+        assert!(val.get("message").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reload_failure_is_reported_and_clears_in_progress() {
+        // No plugins table, so looking the plugin up fails
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        let registry = PluginRegistry::new(&db, "localhost", "0.1.0")
+            .await
+            .unwrap();
+        let mut progress = registry.subscribe_reloads();
+        let id = Uuid::new_v4();
+
+        assert!(registry.reload_plugin(id).await.is_err());
+        assert!(!registry.is_reloading(id));
+
+        let first = progress.recv().await.unwrap();
+        assert_eq!(first.stage, ReloadStage::Fetching);
+        let last = progress.recv().await.unwrap();
+        assert_eq!(last.plugin_id, id);
+        assert_eq!(last.stage, ReloadStage::Failed);
+        assert!(last.message.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_reload_rejected_while_in_progress() {
+        let registry = PluginRegistry::new(
+            &sea_orm::DatabaseConnection::Disconnected,
+            "localhost",
+            "0.1.0",
+        )
+        .await
+        .unwrap();
+        let id = Uuid::new_v4();
+        registry.lock_reloading().insert(id);
+        let mut progress = registry.subscribe_reloads();
+
+        let err = registry.reload_plugin(id).await.unwrap_err();
+        assert!(err.to_string().contains("already in progress"));
+        // The running reload still owns the plugin
+        assert!(registry.is_reloading(id));
+        assert_eq!(progress.recv().await.unwrap().stage, ReloadStage::Failed);
+    }
+
     #[test]
     fn test_subscription_duplicate_event() {
         let mut subscriptions: HashMap<String, Vec<Uuid>> = HashMap::new();
@@ -1,22 +1,30 @@
 //! Admin API — P2P settings, instance management, blocked domains, monitoring, metadata
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
     Extension, Json,
 };
 use sea_orm::sea_query::Expr;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, EntityTrait, FromQueryResult, PaginatorTrait, QueryFilter,
-    QueryOrder, QuerySelect, Set,
+    QueryOrder, QuerySelect, Set, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
 use std::sync::Arc;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tokio_stream::{Stream, StreamExt};
 use uuid::Uuid;
 
+use super::audit::{AuditAction, AuditEntry, ClientIp};
 use crate::auth::middleware::AuthUser;
 use crate::metadata_lookup;
-use soundtime_db::entities::{blocked_domain, instance_setting, remote_track, track, user};
+use soundtime_db::entities::{
+    blocked_domain, blocklist_override, blocklist_subscription, instance_setting, remote_track,
+    track, user,
+};
 
 /// Extract p2p node from type-erased state
 fn get_p2p_node(state: &soundtime_db::AppState) -> Option<Arc<soundtime_p2p::P2pNode>> {
@@ -62,14 +70,26 @@ pub struct UpdateSettingRequest {
 }
 
 /// PUT /api/admin/settings/:key — update a single setting
+///
+/// The audit log records the key but not the value, which may be a secret.
 pub async fn update_setting(
     State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    ip: ClientIp,
     Path(key): Path<String>,
     Json(body): Json<UpdateSettingRequest>,
 ) -> Result<Json<SettingResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let db_error = |_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "DB error" })),
+        )
+    };
+    let txn = state.db.begin().await.map_err(db_error)?;
+
     let existing = instance_setting::Entity::find()
         .filter(instance_setting::Column::Key.eq(&key))
-        .one(&state.db)
+        .one(&txn)
         .await
         .map_err(|_| {
             (
@@ -83,7 +103,7 @@ pub async fn update_setting(
             let mut update: instance_setting::ActiveModel = s.into();
             update.value = Set(body.value.clone());
             update.updated_at = Set(chrono::Utc::now().into());
-            update.update(&state.db).await.map_err(|_| {
+            update.update(&txn).await.map_err(|_| {
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(serde_json::json!({ "error": "Update failed" })),
@@ -98,7 +118,7 @@ pub async fn update_setting(
                 value: Set(body.value.clone()),
                 updated_at: Set(chrono::Utc::now().into()),
             }
-            .insert(&state.db)
+            .insert(&txn)
             .await
             .map_err(|_| {
                 (
@@ -109,6 +129,21 @@ pub async fn update_setting(
         }
     }
 
+    AuditEntry::new(user.0.sub, AuditAction::UpdateSetting, key.clone())
+        .record(&txn, &ip)
+        .await
+        .map_err(db_error)?;
+    txn.commit().await.map_err(db_error)?;
+
+    // Replication policy settings apply to the running P2P node right away
+    if key.starts_with(soundtime_p2p::replication_policy::SETTING_PREFIX) {
+        if let Some(node) = get_p2p_node(&state) {
+            if let Err(e) = node.reload_replication_policy().await {
+                tracing::warn!("failed to reload replication policy: {e}");
+            }
+        }
+    }
+
     Ok(Json(SettingResponse {
         key,
         value: body.value,
@@ -123,26 +158,63 @@ pub struct BlockedDomainResponse {
     pub domain: String,
     pub reason: Option<String>,
     pub created_at: String,
+    /// When the block lapses, `None` for a permanent block.
+    pub expires_at: Option<String>,
+    /// Seconds left before the block lapses, `None` for a permanent block.
+    pub remaining_secs: Option<i64>,
+    /// URL of the subscription that added the block, `None` for a manual block.
+    pub source: Option<String>,
+}
+
+impl BlockedDomainResponse {
+    fn from_model(
+        d: blocked_domain::Model,
+        source: Option<String>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        Self {
+            id: d.id,
+            domain: d.domain,
+            reason: d.reason,
+            created_at: d.created_at.to_rfc3339(),
+            expires_at: d.expires_at.map(|e| e.to_rfc3339()),
+            remaining_secs: soundtime_p2p::blocked::remaining_secs(d.expires_at, now),
+            source,
+        }
+    }
 }
 
 /// GET /api/admin/blocked-domains
 pub async fn list_blocked_domains(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<BlockedDomainResponse>>, StatusCode> {
+    // Lapsed blocks no longer apply, so drop them rather than list them.
+    if let Err(e) = soundtime_p2p::blocked::purge_expired_blocks(&state.db).await {
+        tracing::warn!("failed to purge expired blocks: {e}");
+    }
+
+    let now = chrono::Utc::now();
     let domains = blocked_domain::Entity::find()
         .order_by_desc(blocked_domain::Column::CreatedAt)
         .all(&state.db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let sources: std::collections::HashMap<Uuid, String> = blocklist_subscription::Entity::find()
+        .all(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|s| (s.id, s.url))
+        .collect();
 
     Ok(Json(
         domains
             .into_iter()
-            .map(|d| BlockedDomainResponse {
-                id: d.id,
-                domain: d.domain,
-                reason: d.reason,
-                created_at: d.created_at.to_rfc3339(),
+            .map(|d| {
+                let source = d
+                    .source_subscription_id
+                    .and_then(|id| sources.get(&id).cloned());
+                BlockedDomainResponse::from_model(d, source, now)
             })
             .collect(),
     ))
@@ -152,44 +224,89 @@ pub async fn list_blocked_domains(
 pub struct BlockDomainRequest {
     pub domain: String,
     pub reason: Option<String>,
+    /// Lift the block automatically after this many hours. Omit for a
+    /// permanent block.
+    pub duration_hours: Option<u32>,
 }
 
 /// POST /api/admin/blocked-domains
 pub async fn block_domain(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthUser>,
+    ip: ClientIp,
     Json(body): Json<BlockDomainRequest>,
 ) -> Result<(StatusCode, Json<BlockedDomainResponse>), (StatusCode, Json<serde_json::Value>)> {
-    // Check if already blocked
-    let existing = blocked_domain::Entity::find()
-        .filter(blocked_domain::Column::Domain.eq(&body.domain))
-        .one(&state.db)
-        .await
-        .map_err(|_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "DB error" })),
-            )
-        })?;
+    let db_error = |_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "DB error" })),
+        )
+    };
 
-    if existing.is_some() {
+    if body.duration_hours == Some(0) {
         return Err((
-            StatusCode::CONFLICT,
-            Json(serde_json::json!({ "error": "Domain already blocked" })),
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "duration_hours must be at least 1" })),
+        ));
+    }
+
+    // Stored the way imports and blocklist subscriptions store domains
+    let domain = body.domain.trim().to_lowercase();
+    if domain.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "domain must not be empty" })),
         ));
     }
 
+    let txn = state.db.begin().await.map_err(db_error)?;
+
+    // Check if already blocked
+    let existing = blocked_domain::Entity::find()
+        .filter(blocked_domain::Column::Domain.eq(&domain))
+        .one(&txn)
+        .await
+        .map_err(db_error)?;
+
     let now = chrono::Utc::now();
-    let id = Uuid::new_v4();
 
-    blocked_domain::ActiveModel {
-        id: Set(id),
-        domain: Set(body.domain.clone()),
-        reason: Set(body.reason.clone()),
+    if let Some(existing) = existing {
+        if existing.source_subscription_id.is_none()
+            && soundtime_p2p::blocked::is_block_active(existing.expires_at, now)
+        {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(serde_json::json!({ "error": "Domain already blocked" })),
+            ));
+        }
+        // The old block has lapsed or came from a subscription — replace it
+        // with a manual one that no subscription can lift.
+        blocked_domain::Entity::delete_by_id(existing.id)
+            .exec(&txn)
+            .await
+            .map_err(db_error)?;
+    }
+
+    // Blocking by hand lifts an earlier manual unblock
+    blocklist_override::Entity::delete_by_id(domain.clone())
+        .exec(&txn)
+        .await
+        .map_err(db_error)?;
+
+    let expires_at = body
+        .duration_hours
+        .map(|hours| now + chrono::Duration::hours(i64::from(hours)));
+
+    let model = blocked_domain::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        domain: Set(domain),
+        reason: Set(body.reason),
         blocked_by: Set(Some(user.0.sub)),
         created_at: Set(now.into()),
+        expires_at: Set(expires_at.map(Into::into)),
+        source_subscription_id: Set(None),
     }
-    .insert(&state.db)
+    .insert(&txn)
     .await
     .map_err(|_| {
         (
@@ -198,38 +315,76 @@ pub async fn block_domain(
         )
     })?;
 
+    AuditEntry::new(user.0.sub, AuditAction::BlockPeer, model.domain.clone())
+        .with_reason(model.reason.clone())
+        .record(&txn, &ip)
+        .await
+        .map_err(db_error)?;
+    txn.commit().await.map_err(db_error)?;
+
     Ok((
         StatusCode::CREATED,
-        Json(BlockedDomainResponse {
-            id,
-            domain: body.domain,
-            reason: body.reason,
-            created_at: now.to_rfc3339(),
-        }),
+        Json(BlockedDomainResponse::from_model(model, None, now)),
     ))
 }
 
 /// DELETE /api/admin/blocked-domains/:id
+///
+/// The domain is also recorded as a manual unblock, so blocklist
+/// subscriptions never block it again until an admin blocks it by hand.
 pub async fn unblock_domain(
     State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    ip: ClientIp,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
-    let result = blocked_domain::Entity::delete_by_id(id)
-        .exec(&state.db)
-        .await
-        .map_err(|_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "Delete failed" })),
-            )
-        })?;
+    let db_error = |_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Delete failed" })),
+        )
+    };
+    let txn = state.db.begin().await.map_err(db_error)?;
 
-    if result.rows_affected == 0 {
+    let Some(existing) = blocked_domain::Entity::find_by_id(id)
+        .one(&txn)
+        .await
+        .map_err(db_error)?
+    else {
         return Err((
             StatusCode::NOT_FOUND,
             Json(serde_json::json!({ "error": "Domain not found" })),
         ));
-    }
+    };
+
+    blocked_domain::Entity::delete_by_id(id)
+        .exec(&txn)
+        .await
+        .map_err(db_error)?;
+
+    AuditEntry::new(
+        user.0.sub,
+        AuditAction::UnblockPeer,
+        existing.domain.clone(),
+    )
+    .record(&txn, &ip)
+    .await
+    .map_err(db_error)?;
+
+    blocklist_override::Entity::insert(blocklist_override::ActiveModel {
+        domain: Set(existing.domain),
+        created_by: Set(Some(user.0.sub)),
+        created_at: Set(chrono::Utc::now().into()),
+    })
+    .on_conflict(
+        sea_orm::sea_query::OnConflict::column(blocklist_override::Column::Domain)
+            .do_nothing()
+            .to_owned(),
+    )
+    .exec_without_returning(&txn)
+    .await
+    .map_err(db_error)?;
+    txn.commit().await.map_err(db_error)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -240,6 +395,9 @@ pub async fn unblock_domain(
 pub struct ImportDomainEntry {
     pub domain: String,
     pub reason: Option<String>,
+    /// RFC 3339 expiry as produced by the export. Entries that have already
+    /// lapsed are skipped.
+    pub expires_at: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -261,6 +419,11 @@ pub async fn import_blocked_domains(
     Extension(user): Extension<AuthUser>,
     Json(body): Json<Vec<ImportDomainEntry>>,
 ) -> Result<Json<ImportResult>, (StatusCode, Json<serde_json::Value>)> {
+    // Purge lapsed blocks first so their domains can be re-imported.
+    if let Err(e) = soundtime_p2p::blocked::purge_expired_blocks(&state.db).await {
+        tracing::warn!("failed to purge expired blocks: {e}");
+    }
+
     let existing: std::collections::HashSet<String> = blocked_domain::Entity::find()
         .all(&state.db)
         .await
@@ -285,12 +448,40 @@ pub async fn import_blocked_domains(
         }
 
         let now = chrono::Utc::now();
+        let expires_at = match entry.expires_at.as_deref() {
+            Some(raw) => match chrono::DateTime::parse_from_rfc3339(raw) {
+                Ok(parsed) => Some(parsed),
+                Err(_) => {
+                    skipped += 1;
+                    continue;
+                }
+            },
+            None => None,
+        };
+        if !soundtime_p2p::blocked::is_block_active(expires_at, now) {
+            skipped += 1;
+            continue;
+        }
+
+        // An imported block is a manual one and lifts an earlier manual unblock
+        blocklist_override::Entity::delete_by_id(domain.clone())
+            .exec(&state.db)
+            .await
+            .map_err(|_| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": "DB error"})),
+                )
+            })?;
+
         blocked_domain::ActiveModel {
             id: Set(Uuid::new_v4()),
             domain: Set(domain),
             reason: Set(entry.reason),
             blocked_by: Set(Some(user.0.sub)),
             created_at: Set(now.into()),
+            expires_at: Set(expires_at),
+            source_subscription_id: Set(None),
         }
         .insert(&state.db)
         .await
@@ -307,6 +498,250 @@ pub async fn import_blocked_domains(
     Ok(Json(ImportResult { imported, skipped }))
 }
 
+// ─── Blocklist Subscriptions ────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct BlocklistSubscriptionResponse {
+    pub id: Uuid,
+    pub url: String,
+    pub enabled: bool,
+    pub last_fetched: Option<String>,
+    /// `ok`, `not_modified` or `error`, `None` until the first fetch
+    pub last_status: Option<String>,
+    pub last_error: Option<String>,
+    /// Entries in the upstream list at the last successful fetch
+    pub entry_count: i32,
+    /// Blocks currently attributed to the subscription
+    pub blocked_count: u64,
+    pub created_at: String,
+}
+
+impl BlocklistSubscriptionResponse {
+    fn from_model(s: blocklist_subscription::Model, blocked_count: u64) -> Self {
+        Self {
+            id: s.id,
+            url: s.url,
+            enabled: s.enabled,
+            last_fetched: s.last_fetched.map(|t| t.to_rfc3339()),
+            last_status: s.last_status,
+            last_error: s.last_error,
+            entry_count: s.entry_count,
+            blocked_count,
+            created_at: s.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Whether `url` is an absolute http(s) URL a blocklist can be fetched from.
+fn is_valid_blocklist_url(url: &str) -> bool {
+    reqwest::Url::parse(url)
+        .map(|u| matches!(u.scheme(), "http" | "https") && u.host_str().is_some())
+        .unwrap_or(false)
+}
+
+/// GET /api/admin/blocklist-subscriptions — subscriptions and their last fetch results
+pub async fn list_blocklist_subscriptions(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<BlocklistSubscriptionResponse>>, StatusCode> {
+    let subscriptions = blocklist_subscription::Entity::find()
+        .order_by_asc(blocklist_subscription::Column::CreatedAt)
+        .all(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut result = Vec::with_capacity(subscriptions.len());
+    for s in subscriptions {
+        let blocked_count = blocked_domain::Entity::find()
+            .filter(blocked_domain::Column::SourceSubscriptionId.eq(s.id))
+            .count(&state.db)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        result.push(BlocklistSubscriptionResponse::from_model(s, blocked_count));
+    }
+
+    Ok(Json(result))
+}
+
+#[derive(Deserialize)]
+pub struct AddBlocklistSubscriptionRequest {
+    pub url: String,
+}
+
+/// POST /api/admin/blocklist-subscriptions — subscribe to a remote blocklist
+/// and fetch it in the background.
+pub async fn add_blocklist_subscription(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Json(body): Json<AddBlocklistSubscriptionRequest>,
+) -> Result<(StatusCode, Json<BlocklistSubscriptionResponse>), (StatusCode, Json<serde_json::Value>)>
+{
+    let url = body.url.trim().to_string();
+    if !is_valid_blocklist_url(&url) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "url must be an http(s) URL" })),
+        ));
+    }
+
+    let existing = blocklist_subscription::Entity::find()
+        .filter(blocklist_subscription::Column::Url.eq(&url))
+        .one(&state.db)
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "DB error" })),
+            )
+        })?;
+    if existing.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({ "error": "Already subscribed to this blocklist" })),
+        ));
+    }
+
+    let model = blocklist_subscription::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        url: Set(url),
+        enabled: Set(true),
+        etag: Set(None),
+        last_fetched: Set(None),
+        last_status: Set(None),
+        last_error: Set(None),
+        entry_count: Set(0),
+        added_by: Set(Some(user.0.sub)),
+        created_at: Set(chrono::Utc::now().into()),
+    }
+    .insert(&state.db)
+    .await
+    .map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Insert failed" })),
+        )
+    })?;
+
+    crate::blocklist_worker::trigger();
+
+    Ok((
+        StatusCode::CREATED,
+        Json(BlocklistSubscriptionResponse::from_model(model, 0)),
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateBlocklistSubscriptionRequest {
+    pub enabled: bool,
+}
+
+/// PUT /api/admin/blocklist-subscriptions/:id — enable or pause a subscription.
+/// A paused subscription keeps its blocks but is no longer fetched.
+pub async fn update_blocklist_subscription(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateBlocklistSubscriptionRequest>,
+) -> Result<Json<BlocklistSubscriptionResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let db_error = |_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "DB error" })),
+        )
+    };
+
+    let Some(existing) = blocklist_subscription::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(db_error)?
+    else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Subscription not found" })),
+        ));
+    };
+
+    let mut update: blocklist_subscription::ActiveModel = existing.into();
+    update.enabled = Set(body.enabled);
+    let model = update.update(&state.db).await.map_err(db_error)?;
+
+    let blocked_count = blocked_domain::Entity::find()
+        .filter(blocked_domain::Column::SourceSubscriptionId.eq(id))
+        .count(&state.db)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(BlocklistSubscriptionResponse::from_model(
+        model,
+        blocked_count,
+    )))
+}
+
+/// DELETE /api/admin/blocklist-subscriptions/:id — unsubscribe and lift
+/// every block the subscription added.
+pub async fn remove_blocklist_subscription(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    // Its blocks go with it (ON DELETE CASCADE)
+    let result = blocklist_subscription::Entity::delete_by_id(id)
+        .exec(&state.db)
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Delete failed" })),
+            )
+        })?;
+
+    if result.rows_affected == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Subscription not found" })),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/admin/blocklist-subscriptions/:id/refresh — fetch and merge the
+/// blocklist now and return the result.
+pub async fn refresh_blocklist_subscription(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<crate::blocklist_worker::RefreshOutcome>, (StatusCode, Json<serde_json::Value>)> {
+    let subscription = blocklist_subscription::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "DB error" })),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "Subscription not found" })),
+            )
+        })?;
+
+    let client = crate::blocklist_worker::http_client().map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )
+    })?;
+
+    crate::blocklist_worker::refresh_subscription(&state, &client, &subscription)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({ "error": e })),
+            )
+        })
+}
+
 // ─── Statistics ─────────────────────────────────────────────────────
 
 #[derive(Serialize)]
@@ -561,11 +996,21 @@ pub struct BanUserRequest {
 /// PUT /api/admin/users/:id/ban
 pub async fn ban_user(
     State(state): State<Arc<AppState>>,
+    Extension(admin): Extension<AuthUser>,
+    ip: ClientIp,
     Path(id): Path<Uuid>,
     Json(body): Json<BanUserRequest>,
 ) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    let db_error = |_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "DB error" })),
+        )
+    };
+    let txn = state.db.begin().await.map_err(db_error)?;
+
     let existing = user::Entity::find_by_id(id)
-        .one(&state.db)
+        .one(&txn)
         .await
         .map_err(|_| {
             (
@@ -590,15 +1035,22 @@ pub async fn ban_user(
 
     let mut update: user::ActiveModel = existing.into();
     update.is_banned = Set(true);
-    update.ban_reason = Set(body.reason);
+    update.ban_reason = Set(body.reason.clone());
     update.banned_at = Set(Some(chrono::Utc::now().into()));
-    update.update(&state.db).await.map_err(|_| {
+    update.update(&txn).await.map_err(|_| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(serde_json::json!({ "error": "Update failed" })),
         )
     })?;
 
+    AuditEntry::new(admin.0.sub, AuditAction::BanUser, id.to_string())
+        .with_reason(body.reason)
+        .record(&txn, &ip)
+        .await
+        .map_err(db_error)?;
+    txn.commit().await.map_err(db_error)?;
+
     tracing::info!(%id, "User banned");
     Ok(StatusCode::NO_CONTENT)
 }
@@ -606,10 +1058,20 @@ pub async fn ban_user(
 /// DELETE /api/admin/users/:id/ban
 pub async fn unban_user(
     State(state): State<Arc<AppState>>,
+    Extension(admin): Extension<AuthUser>,
+    ip: ClientIp,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    let db_error = |_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "DB error" })),
+        )
+    };
+    let txn = state.db.begin().await.map_err(db_error)?;
+
     let existing = user::Entity::find_by_id(id)
-        .one(&state.db)
+        .one(&txn)
         .await
         .map_err(|_| {
             (
@@ -628,31 +1090,40 @@ pub async fn unban_user(
     update.is_banned = Set(false);
     update.ban_reason = Set(None);
     update.banned_at = Set(None);
-    update.update(&state.db).await.map_err(|_| {
+    update.update(&txn).await.map_err(|_| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(serde_json::json!({ "error": "Update failed" })),
         )
     })?;
 
+    AuditEntry::new(admin.0.sub, AuditAction::UnbanUser, id.to_string())
+        .record(&txn, &ip)
+        .await
+        .map_err(db_error)?;
+    txn.commit().await.map_err(db_error)?;
+
     tracing::info!(%id, "User unbanned");
     Ok(StatusCode::NO_CONTENT)
 }
 
 // ─── Metadata Enrichment ────────────────────────────────────────────
 
-/// POST /api/admin/metadata/enrich/:track_id — enrich a single track via MusicBrainz
+/// POST /api/admin/metadata/enrich/:track_id — enrich a single track via
+/// MusicBrainz, then its artist and album
 pub async fn enrich_track_metadata(
     State(state): State<Arc<AppState>>,
     Path(track_id): Path<Uuid>,
-) -> Result<Json<metadata_lookup::MetadataResult>, (StatusCode, Json<serde_json::Value>)> {
-    let result = metadata_lookup::enrich_track(&state.db, &*state.storage, track_id).await;
+) -> Result<Json<metadata_lookup::TrackEnrichment>, (StatusCode, Json<serde_json::Value>)> {
+    let result =
+        metadata_lookup::enrich_track_and_entities(&state.db, &*state.storage, track_id).await;
     Ok(Json(result))
 }
 
 /// POST /api/admin/metadata/enrich-all — start background batch enrichment.
 ///
-/// Spawns a tokio task that enriches all tracks lacking a MusicBrainz ID.
+/// Spawns a tokio task that first retries the MusicBrainz lookups queued in
+/// `mb_lookup_queue`, then enriches all tracks lacking a MusicBrainz ID.
 /// The task runs asynchronously and reports progress through the shared
 /// [`MetadataTaskTrackerHandle`] (injected as an `Extension`).
 ///
@@ -695,7 +1166,15 @@ pub async fn enrich_all_metadata(
 
     let tracker_clone = tracker.clone();
     tokio::spawn(async move {
-        let result = metadata_lookup::enrich_all_tracks_background(
+        // Lookups that failed during P2P ingestion first
+        if let Err(e) = soundtime_p2p::MusicBrainzClient::new()
+            .process_retry_queue(&state.db)
+            .await
+        {
+            tracing::warn!("MusicBrainz retry queue failed: {e}");
+        }
+
+        let result = metadata_lookup::enrich_all_tracks_background(
             &state.db,
             &*state.storage,
             &tracker_clone,
@@ -777,6 +1256,21 @@ pub async fn metadata_status(
 
     let total_albums = album::Entity::find().count(&state.db).await.unwrap_or(0);
 
+    let enriched_albums = album::Entity::find()
+        .filter(album::Column::MusicbrainzId.is_not_null())
+        .count(&state.db)
+        .await
+        .unwrap_or(0);
+
+    use soundtime_db::entities::artist;
+    let total_artists = artist::Entity::find().count(&state.db).await.unwrap_or(0);
+
+    let enriched_artists = artist::Entity::find()
+        .filter(artist::Column::MusicbrainzId.is_not_null())
+        .count(&state.db)
+        .await
+        .unwrap_or(0);
+
     let total_remote_tracks = remote_track::Entity::find()
         .count(&state.db)
         .await
@@ -794,7 +1288,10 @@ pub async fn metadata_status(
         pending_tracks: total_tracks - enriched_tracks,
         tracks_with_bitrate,
         total_albums,
+        enriched_albums,
         albums_with_cover,
+        total_artists,
+        enriched_artists,
         total_remote_tracks,
         available_remote_tracks,
     }))
@@ -807,7 +1304,12 @@ pub struct MetadataStatusResponse {
     pub pending_tracks: u64,
     pub tracks_with_bitrate: u64,
     pub total_albums: u64,
+    /// Albums with a MusicBrainz ID
+    pub enriched_albums: u64,
     pub albums_with_cover: u64,
+    pub total_artists: u64,
+    /// Artists with a MusicBrainz ID
+    pub enriched_artists: u64,
     pub total_remote_tracks: u64,
     pub available_remote_tracks: u64,
 }
@@ -1027,6 +1529,24 @@ pub async fn run_integrity_check(
     ))
 }
 
+/// GET /api/admin/storage/integrity-check/stream
+///
+/// Live progress of integrity checks as Server-Sent Events: one
+/// JSON-encoded `IntegrityEvent` per checked track. A subscriber too slow to
+/// keep up gets a `lagged` event with the number of events it missed.
+pub async fn integrity_check_stream(
+    Extension(tracker): Extension<crate::storage_worker::TaskTrackerHandle>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream =
+        BroadcastStream::new(tracker.subscribe_integrity()).filter_map(|item| match item {
+            Ok(event) => Event::default().json_data(&event).ok().map(Ok),
+            Err(BroadcastStreamRecvError::Lagged(missed)) => Some(Ok(Event::default()
+                .event("lagged")
+                .data(missed.to_string()))),
+        });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
 /// POST /api/admin/storage/sync
 ///
 /// Launches the storage sync as a background task and returns immediately.
@@ -1078,6 +1598,140 @@ pub async fn run_storage_sync(
     ))
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct MigrateShardingRequest {
+    /// Layout to move files to; defaults to `AUDIO_STORAGE_SHARDING`
+    pub strategy: Option<soundtime_audio::ShardingStrategy>,
+}
+
+/// POST /api/admin/storage/migrate-sharding
+///
+/// Moves local audio files into a sharded directory layout as a background
+/// task and returns immediately.
+pub async fn migrate_storage_sharding(
+    State(state): State<Arc<AppState>>,
+    Extension(tracker): Extension<crate::storage_worker::TaskTrackerHandle>,
+    payload: Option<Json<MigrateShardingRequest>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let Some(local) = state.storage.as_local() else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(
+                serde_json::json!({ "error": "Sharding only applies to local filesystem storage" }),
+            ),
+        ));
+    };
+    let strategy = payload
+        .and_then(|Json(req)| req.strategy)
+        .unwrap_or_else(|| local.sharding());
+    if strategy == soundtime_audio::ShardingStrategy::None {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "Choose a sharding strategy: by-first-char or by-hash-prefix-2"
+            })),
+        ));
+    }
+
+    // Reject if a task is already running
+    {
+        let mut lock = tracker.lock().await;
+        if let Some(crate::storage_worker::TaskStatus::Running { .. }) = &*lock {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(serde_json::json!({ "error": "A storage task is already running" })),
+            ));
+        }
+        *lock = Some(crate::storage_worker::TaskStatus::Running {
+            progress: crate::storage_worker::TaskProgress {
+                processed: 0,
+                total: None,
+            },
+        });
+    }
+
+    let tracker_clone = tracker.clone();
+    tokio::spawn(async move {
+        match crate::storage_worker::run_sharding_migration(&state, strategy, Some(&tracker_clone))
+            .await
+        {
+            Ok(report) => {
+                let mut lock = tracker_clone.lock().await;
+                *lock = Some(crate::storage_worker::TaskStatus::Completed {
+                    result: crate::storage_worker::TaskResult::Sharding(report),
+                });
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "sharding migration background task failed");
+                let mut lock = tracker_clone.lock().await;
+                *lock = Some(crate::storage_worker::TaskStatus::Error { message: e });
+            }
+        }
+    });
+
+    Ok(Json(serde_json::json!({
+        "status": "started",
+        "task": "migrate-sharding",
+        "strategy": strategy,
+    })))
+}
+
+/// POST /api/admin/storage/extract-embedded-covers
+///
+/// Sets the cover of every album that has none from the art embedded in its
+/// tracks, as a background task, and returns immediately.
+pub async fn extract_embedded_covers(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Extension(tracker): Extension<crate::storage_worker::TaskTrackerHandle>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    // Reject if a task is already running
+    {
+        let mut lock = tracker.lock().await;
+        if let Some(crate::storage_worker::TaskStatus::Running { .. }) = &*lock {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(serde_json::json!({ "error": "A storage task is already running" })),
+            ));
+        }
+        *lock = Some(crate::storage_worker::TaskStatus::Running {
+            progress: crate::storage_worker::TaskProgress {
+                processed: 0,
+                total: None,
+            },
+        });
+    }
+
+    let tracker_clone = tracker.clone();
+    let admin_id = user.0.sub;
+    tokio::spawn(async move {
+        match crate::storage_worker::run_embedded_cover_extraction(
+            &state,
+            admin_id,
+            Some(&tracker_clone),
+        )
+        .await
+        {
+            Ok(report) => {
+                let mut lock = tracker_clone.lock().await;
+                *lock = Some(crate::storage_worker::TaskStatus::Completed {
+                    result: crate::storage_worker::TaskResult::Covers(report),
+                });
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "embedded cover extraction background task failed");
+                let mut lock = tracker_clone.lock().await;
+                *lock = Some(crate::storage_worker::TaskStatus::Error { message: e });
+            }
+        }
+    });
+
+    Ok(Json(serde_json::json!({
+        "status": "started",
+        "task": "extract-embedded-covers",
+    })))
+}
+
 /// GET /api/admin/storage/task-status
 ///
 /// Returns the current status of a running or completed storage task.
@@ -1093,9 +1747,259 @@ pub async fn storage_task_status(
     result
 }
 
+// ─── Duplicate Tracks (Chromaprint) ─────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct DuplicatesQuery {
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+/// Two tracks whose acoustic fingerprints are the same recording.
+#[derive(Debug, Serialize)]
+pub struct DuplicateTrackPair {
+    /// Share of matching fingerprint bits, from 0.85 to 1.0
+    pub similarity: f64,
+    pub track_a_id: Uuid,
+    pub track_a_title: String,
+    pub track_a_content_hash: Option<String>,
+    pub track_b_id: Uuid,
+    pub track_b_title: String,
+    pub track_b_content_hash: Option<String>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct FingerprintedTrack {
+    id: Uuid,
+    fingerprint: String,
+    duration_secs: f32,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+/// A matching pair, without the track details that change independently of
+/// the fingerprints.
+#[derive(Debug, Clone, Copy)]
+struct DuplicateCandidate {
+    similarity: f64,
+    track_a_id: Uuid,
+    track_b_id: Uuid,
+}
+
+/// How long computed candidates are reused when the fingerprinted tracks
+/// look unchanged, catching edits the cache key cannot see.
+const DUPLICATE_CANDIDATES_TTL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+/// Fingerprinted track count and newest fingerprinted track, which change
+/// whenever one is added or deleted.
+type DuplicateCandidatesKey = (u64, Option<Uuid>);
+
+struct CachedDuplicateCandidates {
+    key: DuplicateCandidatesKey,
+    computed_at: std::time::Instant,
+    pairs: Arc<Vec<DuplicateCandidate>>,
+}
+
+/// Candidates from the last comparison. Held while recomputing so
+/// concurrent requests wait for one comparison instead of each running it.
+static DUPLICATE_CANDIDATES: std::sync::LazyLock<
+    tokio::sync::Mutex<Option<CachedDuplicateCandidates>>,
+> = std::sync::LazyLock::new(Default::default);
+
+/// Pairs of `tracks` (sorted by duration) that are the same recording,
+/// newest first. Only tracks within
+/// [`DURATION_TOLERANCE_SECS`](soundtime_audio::fingerprint::DURATION_TOLERANCE_SECS)
+/// of each other are compared.
+fn similar_track_pairs(tracks: &[FingerprintedTrack]) -> Vec<DuplicateCandidate> {
+    use soundtime_audio::fingerprint::{
+        decode_fingerprint, similarity, DURATION_TOLERANCE_SECS, MATCH_THRESHOLD,
+    };
+
+    let decoded: Vec<_> = tracks
+        .iter()
+        .map(|t| decode_fingerprint(&t.fingerprint))
+        .collect();
+    let mut pairs = Vec::new();
+    for (i, a) in tracks.iter().enumerate() {
+        for (j, b) in tracks.iter().enumerate().skip(i + 1) {
+            if b.duration_secs - a.duration_secs > DURATION_TOLERANCE_SECS {
+                break;
+            }
+            let score = if a.fingerprint == b.fingerprint {
+                1.0
+            } else {
+                match (&decoded[i], &decoded[j]) {
+                    (Some(x), Some(y)) => similarity(x, y),
+                    _ => continue,
+                }
+            };
+            if score < MATCH_THRESHOLD {
+                continue;
+            }
+            let (a, b) = if a.id < b.id { (a, b) } else { (b, a) };
+            pairs.push((
+                a.created_at,
+                DuplicateCandidate {
+                    similarity: score,
+                    track_a_id: a.id,
+                    track_b_id: b.id,
+                },
+            ));
+        }
+    }
+    pairs.sort_by(|(a_at, a), (b_at, b)| {
+        b_at.cmp(a_at)
+            .then(a.track_a_id.cmp(&b.track_a_id))
+            .then(a.track_b_id.cmp(&b.track_b_id))
+    });
+    pairs.into_iter().map(|(_, pair)| pair).collect()
+}
+
+/// Matching pairs among all fingerprinted tracks, compared again only when
+/// a fingerprinted track was added or deleted, or the last comparison is
+/// older than [`DUPLICATE_CANDIDATES_TTL`].
+async fn duplicate_candidates(
+    db: &sea_orm::DatabaseConnection,
+) -> Result<Arc<Vec<DuplicateCandidate>>, (StatusCode, Json<serde_json::Value>)> {
+    let query_failed = |e: sea_orm::DbErr| {
+        tracing::error!(error = %e, "list_duplicate_tracks: query failed");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Failed to query duplicate tracks" })),
+        )
+    };
+
+    let fingerprinted = track::Entity::find()
+        .filter(track::Column::Fingerprint.is_not_null())
+        .count(db)
+        .await
+        .map_err(query_failed)?;
+    let newest = track::Entity::find()
+        .select_only()
+        .column(track::Column::Id)
+        .filter(track::Column::Fingerprint.is_not_null())
+        .order_by_desc(track::Column::CreatedAt)
+        .into_tuple::<Uuid>()
+        .one(db)
+        .await
+        .map_err(query_failed)?;
+    let key = (fingerprinted, newest);
+
+    let mut cached = DUPLICATE_CANDIDATES.lock().await;
+    if let Some(c) = cached.as_ref() {
+        if c.key == key && c.computed_at.elapsed() < DUPLICATE_CANDIDATES_TTL {
+            return Ok(c.pairs.clone());
+        }
+    }
+
+    let tracks = track::Entity::find()
+        .select_only()
+        .columns([
+            track::Column::Id,
+            track::Column::Fingerprint,
+            track::Column::DurationSecs,
+            track::Column::CreatedAt,
+        ])
+        .filter(track::Column::Fingerprint.is_not_null())
+        .order_by_asc(track::Column::DurationSecs)
+        .into_model::<FingerprintedTrack>()
+        .all(db)
+        .await
+        .map_err(query_failed)?;
+
+    let pairs = tokio::task::spawn_blocking(move || similar_track_pairs(&tracks))
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "list_duplicate_tracks: comparison failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to compare fingerprints" })),
+            )
+        })?;
+    let pairs = Arc::new(pairs);
+    *cached = Some(CachedDuplicateCandidates {
+        key,
+        computed_at: std::time::Instant::now(),
+        pairs: pairs.clone(),
+    });
+    Ok(pairs)
+}
+
+/// GET /api/admin/tracks/duplicates
+///
+/// Lists pairs of tracks whose Chromaprint fingerprints are similar enough
+/// to be the same recording — imported in different formats or bitrates.
+/// Fingerprints are compared in memory, among tracks of about the same
+/// duration, and the matches are reused across pages until a fingerprinted
+/// track is added or deleted. Titles and hashes are loaded for the
+/// requested page only.
+pub async fn list_duplicate_tracks(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DuplicatesQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(50).clamp(1, 200);
+    let offset = (page - 1) * per_page;
+
+    let pairs = duplicate_candidates(&state.db).await?;
+    let total = pairs.len() as u64;
+    let total_pages = total.div_ceil(per_page);
+    let page_pairs: Vec<DuplicateCandidate> = pairs
+        .iter()
+        .skip(offset as usize)
+        .take(per_page as usize)
+        .copied()
+        .collect();
+
+    let ids: Vec<Uuid> = page_pairs
+        .iter()
+        .flat_map(|p| [p.track_a_id, p.track_b_id])
+        .collect();
+    let tracks: std::collections::HashMap<Uuid, track::Model> = track::Entity::find()
+        .filter(track::Column::Id.is_in(ids))
+        .all(&state.db)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "list_duplicate_tracks: query failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to query duplicate tracks" })),
+            )
+        })?
+        .into_iter()
+        .map(|t| (t.id, t))
+        .collect();
+
+    // A track deleted since the comparison drops out of its pairs
+    let data: Vec<DuplicateTrackPair> = page_pairs
+        .iter()
+        .filter_map(|p| {
+            let a = tracks.get(&p.track_a_id)?;
+            let b = tracks.get(&p.track_b_id)?;
+            Some(DuplicateTrackPair {
+                similarity: p.similarity,
+                track_a_id: a.id,
+                track_a_title: a.title.clone(),
+                track_a_content_hash: a.content_hash.clone(),
+                track_b_id: b.id,
+                track_b_title: b.title.clone(),
+                track_b_content_hash: b.content_hash.clone(),
+            })
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "data": data,
+        "total": total,
+        "page": page,
+        "per_page": per_page,
+        "total_pages": total_pages,
+    })))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use soundtime_db::entities::admin_audit_log;
 
     // 1. SettingResponse serialization
     #[test]
@@ -1117,10 +2021,15 @@ mod tests {
             domain: "evil.com".to_string(),
             reason: Some("spam".to_string()),
             created_at: "2024-01-01T00:00:00Z".to_string(),
+            expires_at: None,
+            remaining_secs: None,
+            source: None,
         };
         let val = serde_json::to_value(&resp).unwrap();
         assert_eq!(val["domain"], "evil.com");
         assert_eq!(val["reason"], "spam");
+        assert!(val["expires_at"].is_null());
+        assert!(val["remaining_secs"].is_null());
     }
 
     // 3. AdminStats serialization
@@ -1254,13 +2163,18 @@ mod tests {
             pending_tracks: 20,
             tracks_with_bitrate: 95,
             total_albums: 10,
+            enriched_albums: 7,
             albums_with_cover: 8,
+            total_artists: 4,
+            enriched_artists: 3,
             total_remote_tracks: 50,
             available_remote_tracks: 45,
         };
         let val = serde_json::to_value(&resp).unwrap();
         assert_eq!(val["total_tracks"], 100);
         assert_eq!(val["pending_tracks"], 20);
+        assert_eq!(val["enriched_albums"], 7);
+        assert_eq!(val["enriched_artists"], 3);
     }
 
     // 13. StorageStatusResponse serialization
@@ -1354,6 +2268,11 @@ mod tests {
         let req: BlockDomainRequest = serde_json::from_str(json).unwrap();
         assert_eq!(req.domain, "bad.com");
         assert_eq!(req.reason, Some("malware".to_string()));
+        assert_eq!(req.duration_hours, None);
+
+        let json = r#"{"domain":"bad.com","reason":null,"duration_hours":168}"#;
+        let req: BlockDomainRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(req.duration_hours, Some(168));
     }
 
     // 18. ImportDomainEntry deserialization
@@ -1363,6 +2282,7 @@ mod tests {
         let entry: ImportDomainEntry = serde_json::from_str(json).unwrap();
         assert_eq!(entry.domain, "spam.org");
         assert_eq!(entry.reason, None);
+        assert_eq!(entry.expires_at, None);
     }
 
     // 19. get_p2p_node returns None when state.p2p is None
@@ -1380,4 +2300,402 @@ mod tests {
         };
         assert!(get_p2p_node(&state).is_none());
     }
+
+    // 20. DuplicateTrackPair serialization
+    #[test]
+    fn test_serialize_duplicate_track_pair() {
+        let pair = DuplicateTrackPair {
+            similarity: 0.97,
+            track_a_id: Uuid::new_v4(),
+            track_a_title: "Song (FLAC)".to_string(),
+            track_a_content_hash: Some("abc".to_string()),
+            track_b_id: Uuid::new_v4(),
+            track_b_title: "Song (MP3)".to_string(),
+            track_b_content_hash: None,
+        };
+        let val = serde_json::to_value(&pair).unwrap();
+        assert_eq!(val["track_a_title"], "Song (FLAC)");
+        assert_eq!(val["track_b_title"], "Song (MP3)");
+        assert!(val["track_b_content_hash"].is_null());
+        assert_eq!(val["similarity"], 0.97);
+    }
+
+    // 21. DuplicatesQuery deserialization
+    #[test]
+    fn test_deserialize_duplicates_query() {
+        let q: DuplicatesQuery = serde_json::from_str(r#"{"page":2}"#).unwrap();
+        assert_eq!(q.page, Some(2));
+        assert_eq!(q.per_page, None);
+    }
+
+    // 22. TrackEnrichment keeps the track fields at the top level
+    #[test]
+    fn test_serialize_track_enrichment() {
+        use metadata_lookup::{EntityEnrichmentStatus, MetadataResult, MetadataStatus};
+
+        let track_id = Uuid::new_v4();
+        let enrichment = metadata_lookup::TrackEnrichment {
+            track: MetadataResult {
+                track_id,
+                status: MetadataStatus::Enriched,
+                recording_mbid: Some("rec-mbid".to_string()),
+                corrected_title: None,
+                artist_mbid: None,
+                artist_name: None,
+                album_mbid: None,
+                album_title: None,
+                genre: None,
+                year: None,
+                cover_url: None,
+            },
+            artist_status: EntityEnrichmentStatus::AlreadyEnriched,
+            album_status: EntityEnrichmentStatus::NoEntity,
+        };
+        let val = serde_json::to_value(&enrichment).unwrap();
+        assert_eq!(val["track_id"], track_id.to_string());
+        assert_eq!(val["status"], "enriched");
+        assert_eq!(val["recording_mbid"], "rec-mbid");
+        assert_eq!(val["artist_status"], "already_enriched");
+        assert_eq!(val["album_status"], "no_entity");
+    }
+
+    // 23. BlockedDomainResponse reports remaining time for timed blocks
+    #[test]
+    fn test_blocked_domain_response_remaining_secs() {
+        let now = chrono::Utc::now();
+        let model = blocked_domain::Model {
+            id: Uuid::new_v4(),
+            domain: "flaky-peer".to_string(),
+            reason: None,
+            blocked_by: None,
+            created_at: now.into(),
+            expires_at: Some((now + chrono::Duration::hours(2)).into()),
+            source_subscription_id: None,
+        };
+        let resp = BlockedDomainResponse::from_model(model, None, now);
+        assert_eq!(resp.remaining_secs, Some(7200));
+        assert!(resp.expires_at.is_some());
+    }
+
+    // 24. Blocklist subscription URLs must be absolute http(s) URLs
+    #[test]
+    fn test_is_valid_blocklist_url() {
+        assert!(is_valid_blocklist_url("https://example.org/blocklist.json"));
+        assert!(is_valid_blocklist_url(
+            "http://10.0.0.2:8080/api/admin/blocked-domains/export"
+        ));
+        assert!(!is_valid_blocklist_url("ftp://example.org/blocklist.json"));
+        assert!(!is_valid_blocklist_url("file:///etc/passwd"));
+        assert!(!is_valid_blocklist_url("example.org/blocklist.json"));
+        assert!(!is_valid_blocklist_url(""));
+    }
+
+    // 25. BlockedDomainResponse attributes subscription blocks
+    #[test]
+    fn test_blocked_domain_response_source() {
+        let now = chrono::Utc::now();
+        let model = blocked_domain::Model {
+            id: Uuid::new_v4(),
+            domain: "listed-peer".to_string(),
+            reason: None,
+            blocked_by: None,
+            created_at: now.into(),
+            expires_at: None,
+            source_subscription_id: Some(Uuid::new_v4()),
+        };
+        let source = Some("https://example.org/blocklist.json".to_string());
+        let val =
+            serde_json::to_value(BlockedDomainResponse::from_model(model, source, now)).unwrap();
+        assert_eq!(val["source"], "https://example.org/blocklist.json");
+    }
+
+    // ── Audit log entries, written in the action's transaction ──
+
+    /// Send `method uri` with `body` to `route` as `admin`, from 10.0.0.2.
+    async fn admin_request(
+        db: &sea_orm::DatabaseConnection,
+        admin: &user::Model,
+        route: axum::routing::MethodRouter<Arc<AppState>>,
+        path: &str,
+        method: &str,
+        uri: &str,
+        body: serde_json::Value,
+    ) -> StatusCode {
+        use axum::{body::Body, extract::ConnectInfo, http::Request, Router};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route(path, route)
+            .layer(Extension(crate::test_db::auth_user(admin)))
+            .with_state(crate::test_db::state(db.clone()));
+        let mut req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo(std::net::SocketAddr::from((
+                [10, 0, 0, 2],
+                40000,
+            ))));
+        app.oneshot(req).await.unwrap().status()
+    }
+
+    async fn block_db(with_audit_log: bool) -> (sea_orm::DatabaseConnection, user::Model) {
+        use crate::test_db::{connect, create_table, insert_user};
+        let db = connect().await;
+        create_table(&db, user::Entity).await;
+        create_table(&db, blocked_domain::Entity).await;
+        create_table(&db, blocklist_override::Entity).await;
+        if with_audit_log {
+            create_table(&db, admin_audit_log::Entity).await;
+        }
+        let admin = insert_user(&db, "admin", user::UserRole::Admin).await;
+        (db, admin)
+    }
+
+    async fn block(db: &sea_orm::DatabaseConnection, admin: &user::Model) -> StatusCode {
+        admin_request(
+            db,
+            admin,
+            axum::routing::post(block_domain),
+            "/blocked-domains",
+            "POST",
+            "/blocked-domains",
+            serde_json::json!({ "domain": "evil.example", "reason": "spam" }),
+        )
+        .await
+    }
+
+    // 26. Blocking a peer writes an audit entry
+    #[tokio::test]
+    async fn test_block_domain_records_audit_entry() {
+        let (db, admin) = block_db(true).await;
+        assert_eq!(block(&db, &admin).await, StatusCode::CREATED);
+
+        let entries = admin_audit_log::Entity::find().all(&db).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.actor_id, Some(admin.id));
+        assert_eq!(entry.action, "block_peer");
+        assert_eq!(entry.target, "evil.example");
+        assert_eq!(entry.reason.as_deref(), Some("spam"));
+        assert_eq!(entry.ip_address.as_deref(), Some("10.0.0.2"));
+    }
+
+    // 27. A block whose audit entry cannot be written is rolled back
+    #[tokio::test]
+    async fn test_block_domain_rolls_back_without_audit_entry() {
+        let (db, admin) = block_db(false).await;
+        assert_eq!(block(&db, &admin).await, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(blocked_domain::Entity::find().count(&db).await.unwrap(), 0);
+    }
+
+    async fn ban_db(
+        with_audit_log: bool,
+    ) -> (sea_orm::DatabaseConnection, user::Model, user::Model) {
+        use crate::test_db::{connect, create_table, insert_user};
+        let db = connect().await;
+        create_table(&db, user::Entity).await;
+        if with_audit_log {
+            create_table(&db, admin_audit_log::Entity).await;
+        }
+        let admin = insert_user(&db, "admin", user::UserRole::Admin).await;
+        let target = insert_user(&db, "troll", user::UserRole::User).await;
+        (db, admin, target)
+    }
+
+    async fn ban(
+        db: &sea_orm::DatabaseConnection,
+        admin: &user::Model,
+        target: &user::Model,
+    ) -> StatusCode {
+        admin_request(
+            db,
+            admin,
+            axum::routing::put(ban_user),
+            "/users/{id}/ban",
+            "PUT",
+            &format!("/users/{}/ban", target.id),
+            serde_json::json!({ "reason": "harassment" }),
+        )
+        .await
+    }
+
+    // 28. Banning a user writes an audit entry
+    #[tokio::test]
+    async fn test_ban_user_records_audit_entry() {
+        let (db, admin, target) = ban_db(true).await;
+        assert_eq!(ban(&db, &admin, &target).await, StatusCode::NO_CONTENT);
+
+        let banned = user::Entity::find_by_id(target.id).one(&db).await.unwrap();
+        assert!(banned.unwrap().is_banned);
+        let entries = admin_audit_log::Entity::find().all(&db).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].actor_id, Some(admin.id));
+        assert_eq!(entries[0].action, "ban_user");
+        assert_eq!(entries[0].target, target.id.to_string());
+        assert_eq!(entries[0].reason.as_deref(), Some("harassment"));
+    }
+
+    // 29. A ban whose audit entry cannot be written is rolled back
+    #[tokio::test]
+    async fn test_ban_user_rolls_back_without_audit_entry() {
+        let (db, admin, target) = ban_db(false).await;
+        assert_eq!(
+            ban(&db, &admin, &target).await,
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        let unchanged = user::Entity::find_by_id(target.id).one(&db).await.unwrap();
+        assert!(!unchanged.unwrap().is_banned);
+    }
+
+    fn fingerprinted(fingerprint: &str, duration_secs: f32) -> FingerprintedTrack {
+        FingerprintedTrack {
+            id: Uuid::new_v4(),
+            fingerprint: fingerprint.to_string(),
+            duration_secs,
+            created_at: chrono::Utc::now().into(),
+        }
+    }
+
+    // 30. Only tracks of about the same duration are paired
+    #[test]
+    fn test_similar_track_pairs_by_duration() {
+        let tracks = vec![
+            fingerprinted("AQAAAQE", 180.0),
+            fingerprinted("AQAAAQE", 181.5),
+            fingerprinted("AQAAAQE", 240.0),
+            fingerprinted("not-a-fingerprint", 240.5),
+        ];
+        let pairs = similar_track_pairs(&tracks);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].similarity, 1.0);
+        let ids = [pairs[0].track_a_id, pairs[0].track_b_id];
+        assert!(ids.contains(&tracks[0].id) && ids.contains(&tracks[1].id));
+        assert!(pairs[0].track_a_id < pairs[0].track_b_id);
+    }
+
+    // 31. Domains are blocked trimmed and lowercased, lifting any manual
+    // unblock of the same domain
+    #[tokio::test]
+    async fn test_block_domain_normalizes_domain() {
+        let (db, admin) = block_db(true).await;
+        blocklist_override::ActiveModel {
+            domain: Set("evil.example".to_string()),
+            created_by: Set(None),
+            created_at: Set(chrono::Utc::now().into()),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+
+        let status = admin_request(
+            &db,
+            &admin,
+            axum::routing::post(block_domain),
+            "/blocked-domains",
+            "POST",
+            "/blocked-domains",
+            serde_json::json!({ "domain": "  Evil.Example ", "reason": "spam" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let blocked = blocked_domain::Entity::find().all(&db).await.unwrap();
+        assert_eq!(blocked.len(), 1);
+        assert_eq!(blocked[0].domain, "evil.example");
+        assert_eq!(
+            blocklist_override::Entity::find().count(&db).await.unwrap(),
+            0
+        );
+
+        // The same domain typed differently is already blocked
+        assert_eq!(block(&db, &admin).await, StatusCode::CONFLICT);
+    }
+
+    async fn insert_fingerprinted_track(
+        db: &sea_orm::DatabaseConnection,
+        title: &str,
+        duration_secs: f32,
+    ) -> track::Model {
+        track::ActiveModel::from(track::Model {
+            id: Uuid::new_v4(),
+            title: title.to_string(),
+            artist_id: Uuid::new_v4(),
+            album_id: None,
+            track_number: None,
+            disc_number: None,
+            duration_secs,
+            genre: None,
+            year: None,
+            musicbrainz_id: None,
+            file_path: format!("/data/music/{title}.mp3"),
+            file_size: 1_000,
+            format: "mp3".into(),
+            bitrate: None,
+            sample_rate: None,
+            waveform_data: None,
+            uploaded_by: None,
+            play_count: 0,
+            is_private: false,
+            is_hidden: false,
+            hidden_by_block: false,
+            content_hash: None,
+            fingerprint: Some("AQAAAQE".into()),
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            created_at: chrono::Utc::now().fixed_offset(),
+        })
+        .insert(db)
+        .await
+        .unwrap()
+    }
+
+    async fn duplicates_page(state: &Arc<AppState>, page: u64) -> serde_json::Value {
+        let Json(body) = list_duplicate_tracks(
+            State(state.clone()),
+            Query(DuplicatesQuery {
+                page: Some(page),
+                per_page: Some(1),
+            }),
+        )
+        .await
+        .unwrap();
+        body
+    }
+
+    // 32. Matches are paged from one comparison, with current track details,
+    // and compared again once a fingerprinted track is added
+    #[tokio::test]
+    async fn test_list_duplicate_tracks_pages_cached_matches() {
+        let db = crate::test_db::connect().await;
+        crate::test_db::create_table(&db, track::Entity).await;
+        let state = crate::test_db::state(db.clone());
+        let a = insert_fingerprinted_track(&db, "a", 180.0).await;
+        insert_fingerprinted_track(&db, "b", 181.0).await;
+        insert_fingerprinted_track(&db, "c", 300.0).await;
+
+        let body = duplicates_page(&state, 1).await;
+        assert_eq!(body["total"], 1);
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+
+        // Renaming a track keeps the cached match but shows the new title
+        let mut renamed: track::ActiveModel = a.into();
+        renamed.title = Set("a (remastered)".into());
+        renamed.update(&db).await.unwrap();
+        let body = duplicates_page(&state, 1).await;
+        let pair = &body["data"][0];
+        assert!(
+            pair["track_a_title"] == "a (remastered)" || pair["track_b_title"] == "a (remastered)"
+        );
+
+        insert_fingerprinted_track(&db, "d", 180.5).await;
+        let body = duplicates_page(&state, 2).await;
+        assert_eq!(body["total"], 3);
+        assert_eq!(body["total_pages"], 3);
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    }
 }
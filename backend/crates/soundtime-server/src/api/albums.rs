@@ -135,6 +135,7 @@ pub async fn get_album(
 
     let tracks = track::Entity::find()
         .filter(track::Column::AlbumId.eq(id))
+        .filter(track::Column::IsHidden.eq(false))
         .order_by_asc(track::Column::DiscNumber)
         .order_by_asc(track::Column::TrackNumber)
         .all(&state.db)
@@ -96,6 +96,7 @@ pub async fn get_artist(
 
     let tracks = track::Entity::find()
         .filter(track::Column::ArtistId.eq(id))
+        .filter(track::Column::IsHidden.eq(false))
         .order_by_desc(track::Column::CreatedAt)
         .all(&state.db)
         .await
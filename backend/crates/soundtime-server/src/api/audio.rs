@@ -5,13 +5,14 @@ use axum::{
     response::IntoResponse,
     Extension, Json,
 };
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, Set};
 use serde::Serialize;
-use soundtime_audio::extract_metadata_from_file;
 use soundtime_audio::metadata::normalize_genre;
+use soundtime_audio::{extract_embedded_cover, extract_metadata_from_file};
 use soundtime_db::entities::{album, artist, remote_track, track};
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock};
 use tokio::io::AsyncReadExt;
+use tokio::sync::Semaphore;
 use tokio_util::io::ReaderStream;
 use uuid::Uuid;
 
@@ -69,9 +70,100 @@ pub struct UploadResponse {
     pub duration: f64,
     pub format: String,
     pub message: String,
+    /// Tracks on peers with the same acoustic fingerprint
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub network_duplicates: Vec<soundtime_p2p::NetworkDuplicate>,
 }
 
+/// Fill the fields missing from the tags of an upload from the name the
+/// client gave the file (which may include `Artist/Album/` directories).
+fn fill_metadata_from_filename(meta: &mut soundtime_audio::AudioMetadata, filename: &str) {
+    let guess = soundtime_audio::parse_filename_metadata(std::path::Path::new(filename));
+    match meta.fill_missing(guess) {
+        soundtime_audio::MetadataTier::Tags => {
+            tracing::debug!(%filename, "metadata read from tags");
+        }
+        tier => tracing::info!(
+            %filename,
+            tier = tier.as_str(),
+            title = ?meta.title,
+            artist = ?meta.artist,
+            album = ?meta.album,
+            "tags incomplete, filled metadata from fallback"
+        ),
+    }
+}
+
+/// Outcome of [`check_duplicate_upload`] that refuses the upload.
+#[derive(Debug)]
+enum DuplicateCheckError {
+    /// The library already has this track
+    Duplicate(Uuid),
+    Db(sea_orm::DbErr),
+}
+
+/// Acoustic fingerprint duplicate check shared by single and batch uploads.
+///
+/// A track that the uploader can see (their own, or a public one) and that
+/// is the same recording — a similar fingerprint and about the same
+/// duration, as in the admin duplicates view — refuses the upload, unless
+/// `allow_duplicate` is set, and the stored file at `relative_path` is
+/// removed. Other users' private tracks never match, so their ids are not
+/// disclosed. Matching tracks on peers never refuse it; they are returned
+/// so the response can list them.
+async fn check_duplicate_upload(
+    state: &AppState,
+    user_id: Uuid,
+    fingerprint: Option<&str>,
+    duration_secs: f32,
+    allow_duplicate: bool,
+    relative_path: &str,
+) -> Result<Vec<soundtime_p2p::NetworkDuplicate>, DuplicateCheckError> {
+    let Some(fp) = fingerprint else {
+        return Ok(Vec::new());
+    };
+    if !allow_duplicate {
+        let tolerance = soundtime_audio::fingerprint::DURATION_TOLERANCE_SECS;
+        let candidates = track::Entity::find()
+            .filter(track::Column::Fingerprint.is_not_null())
+            .filter(
+                track::Column::DurationSecs
+                    .between(duration_secs - tolerance, duration_secs + tolerance),
+            )
+            .filter(
+                Condition::any()
+                    .add(track::Column::UploadedBy.eq(user_id))
+                    .add(track::Column::IsPrivate.eq(false)),
+            )
+            .all(&state.db)
+            .await
+            .map_err(DuplicateCheckError::Db)?;
+        let existing = candidates.into_iter().find(|t| {
+            t.fingerprint
+                .as_deref()
+                .is_some_and(|existing| soundtime_audio::fingerprints_match(existing, fp))
+        });
+        if let Some(existing) = existing {
+            if let Err(e) = state.storage.delete_file(relative_path).await {
+                tracing::warn!(error = %e, "failed to remove duplicate upload");
+            }
+            return Err(DuplicateCheckError::Duplicate(existing.id));
+        }
+    }
+
+    Ok(match get_p2p_node(state) {
+        Some(node) => node.check_duplicate(fp, duration_secs).await,
+        None => Vec::new(),
+    })
+}
+
+const DUPLICATE_UPLOAD_ERROR: &str = "A track with the same audio fingerprint already exists";
+
 /// POST /api/upload  — Multipart audio file upload
+///
+/// Returns `409 Conflict` with `"duplicate": true` and the existing
+/// `track_id` when a track with the same acoustic fingerprint is already in
+/// the library; resend with `allow_duplicate=true` to upload it anyway.
 pub async fn upload_track(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthUser>,
@@ -83,6 +175,7 @@ pub async fn upload_track(
     let mut meta_title: Option<String> = None;
     let mut meta_album: Option<String> = None;
     let mut meta_artist: Option<String> = None;
+    let mut allow_duplicate = false;
 
     while let Ok(Some(field)) = multipart.next_field().await {
         let name = field.name().unwrap_or("").to_string();
@@ -106,6 +199,9 @@ pub async fn upload_track(
             "artist" => {
                 meta_artist = field.text().await.ok();
             }
+            "allow_duplicate" => {
+                allow_duplicate = field.text().await.is_ok_and(|v| v.trim() == "true");
+            }
             _ => {}
         }
     }
@@ -187,16 +283,42 @@ pub async fn upload_track(
         (full_path, relative_path)
     };
 
-    let audio_meta = extract_metadata_from_file(&full_path).map_err(|e| {
+    let mut audio_meta = extract_metadata_from_file(&full_path).map_err(|e| {
         tracing::error!("Metadata extraction error: {e}");
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(serde_json::json!({ "error": "Failed to extract metadata" })),
         )
     })?;
+    fill_metadata_from_filename(&mut audio_meta, &filename);
+    let embedded_cover = extract_embedded_cover(&full_path);
+
+    // Acoustic fingerprint for duplicate detection (best-effort, needs fpcalc)
+    let fingerprint = soundtime_audio::compute_fingerprint(&full_path).await;
 
-    // Generate waveform
-    let waveform = soundtime_audio::generate_waveform(&full_path, 200).ok();
+    let network_duplicates = check_duplicate_upload(
+        &state,
+        user_id,
+        fingerprint.as_deref(),
+        audio_meta.duration_secs as f32,
+        allow_duplicate,
+        &relative_path,
+    )
+    .await
+    .map_err(|e| match e {
+        DuplicateCheckError::Duplicate(existing) => (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": DUPLICATE_UPLOAD_ERROR,
+                "duplicate": true,
+                "track_id": existing,
+            })),
+        ),
+        DuplicateCheckError::Db(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": format!("DB error: {e}") })),
+        ),
+    })?;
 
     // Resolve or create artist
     let artist_name = meta_artist
@@ -298,8 +420,8 @@ pub async fn upload_track(
             )
         })?;
 
-    let album_id = if let Some(a) = existing_album {
-        a.id
+    let album_record = if let Some(a) = existing_album {
+        a
     } else {
         let new_album = album::ActiveModel {
             id: Set(Uuid::new_v4()),
@@ -316,30 +438,20 @@ pub async fn upload_track(
             year: Set(audio_meta.year.map(|y| y as i16)),
             created_at: Set(chrono::Utc::now().into()),
         };
-        let result = new_album.insert(&state.db).await.map_err(|e| {
+        new_album.insert(&state.db).await.map_err(|e| {
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(serde_json::json!({ "error": format!("DB error: {e}") })),
             )
-        })?;
+        })?
+    };
+    let album_id = album_record.id;
 
-        // Store cover art if present
-        if let Some(cover_data) = &audio_meta.cover_art {
-            if let Ok(cover_path) = state
-                .storage
-                .store_cover(user_id, Some(&album_title), cover_data)
-                .await
-            {
-                let mut update: album::ActiveModel = result.clone().into();
-                update.cover_url = Set(Some(format!("/api/media/{cover_path}")));
-                if let Err(e) = update.update(&state.db).await {
-                    tracing::warn!(error = %e, "failed to update album cover URL");
-                }
-            }
+    if let Some(cover) = embedded_cover {
+        if let Err(e) = apply_embedded_cover(&state, user_id, &album_record, cover).await {
+            tracing::warn!(%album_id, "failed to apply embedded cover: {e}");
         }
-
-        result.id
-    };
+    }
 
     // Create track
     let track_title = meta_title.or(audio_meta.title.clone()).unwrap_or_else(|| {
@@ -371,10 +483,19 @@ pub async fn upload_track(
         file_size: Set(audio_meta.file_size as i64),
         bitrate: Set(audio_meta.bitrate.map(|b| b as i32)),
         sample_rate: Set(audio_meta.sample_rate.map(|s| s as i32)),
-        waveform_data: Set(waveform.map(|w| serde_json::json!(w))),
+        // Computed in the background, see `finish_upload_in_background`
+        waveform_data: Set(None),
         uploaded_by: Set(Some(user_id)),
         content_hash: Set(None),
+        fingerprint: Set(fingerprint.clone()),
         play_count: Set(0),
+        is_private: Set(false),
+        is_hidden: Set(false),
+        hidden_by_block: Set(false),
+        // Measured in the background, see `finish_upload_in_background`
+        loudness_lufs: Set(None),
+        dynamic_range: Set(None),
+        encoding_quality: Set(audio_meta.encoding_quality.clone()),
         created_at: Set(chrono::Utc::now().into()),
     };
 
@@ -385,17 +506,18 @@ pub async fn upload_track(
         )
     })?;
 
-    // Publish track to P2P blob store (best-effort)
-    publish_track_to_p2p(
-        &state,
+    finish_upload_in_background(
+        Arc::clone(&state),
         track_id,
-        &data,
-        &track_title,
-        &artist_name,
-        &album_title,
-        &audio_meta,
-    )
-    .await;
+        full_path,
+        PublishedTrack {
+            title: track_title.clone(),
+            artist_name: artist_name.clone(),
+            album_title: album_title.clone(),
+            audio_meta: audio_meta.clone(),
+            fingerprint,
+        },
+    );
 
     // Dispatch plugin event (best-effort)
     if let Some(registry) = super::get_plugin_registry(&state) {
@@ -418,6 +540,7 @@ pub async fn upload_track(
         duration: audio_meta.duration_secs,
         format: audio_meta.format,
         message: "Track uploaded successfully".into(),
+        network_duplicates,
     }))
 }
 
@@ -445,17 +568,7 @@ pub async fn stream_track(
 
     let file_path_str = &track_record.file_path;
 
-    // Determine content type from format
-    let content_type = match track_record.format.as_str() {
-        "mp3" => "audio/mpeg",
-        "flac" => "audio/flac",
-        "ogg" => "audio/ogg",
-        "wav" => "audio/wav",
-        "aac" => "audio/aac",
-        "opus" => "audio/opus",
-        "aiff" => "audio/aiff",
-        _ => "application/octet-stream",
-    };
+    let content_type = audio_content_type(&track_record.format);
 
     // Parse Range header
     let range = headers
@@ -472,6 +585,15 @@ pub async fn stream_track(
             )
         })?;
 
+        // Peers may hold this track in different formats; unless our own
+        // copy is cached, stream the best-rated one, chosen once per track
+        // so every range of a playback comes from the same encoding
+        let negotiated = match hash_str.parse::<soundtime_p2p::BlobHash>() {
+            Ok(own) => p2p_node.stream_source(id, own).await.to_string(),
+            Err(_) => hash_str.to_string(),
+        };
+        let hash_str = negotiated.as_str();
+
         // FIX-16: Check if the remote track is marked unavailable before attempting fetch
         let remote_record = remote_track::Entity::find()
             .filter(remote_track::Column::RemoteUri.ends_with(format!("/{hash_str}")))
@@ -487,6 +609,7 @@ pub async fn stream_track(
 
         if let Some(ref rt) = remote_record {
             if !rt.is_available {
+                p2p_node.unpin_stream_source(id);
                 tracing::warn!(%hash_str, "P2P track marked unavailable, returning 410 Gone");
                 return Err((
                     StatusCode::GONE,
@@ -494,6 +617,11 @@ pub async fn stream_track(
                 ));
             }
         }
+        let content_type = remote_record
+            .as_ref()
+            .and_then(|rt| rt.format.as_deref())
+            .map(audio_content_type)
+            .unwrap_or(content_type);
 
         let hash: soundtime_p2p::BlobHash = hash_str.parse().map_err(|_| {
             (
@@ -502,11 +630,34 @@ pub async fn stream_track(
             )
         })?;
 
+        // Range requests only need the requested bytes, so playback can start
+        // before the whole blob has been fetched from a peer.
+        let fetched = match range {
+            Some((start, end)) => p2p_node.get_or_fetch_track_range(hash, start, end).await,
+            None => p2p_node
+                .get_or_fetch_track(hash)
+                .await
+                .map(|data| soundtime_p2p::TrackRange {
+                    start: 0,
+                    total_size: data.len() as u64,
+                    data,
+                }),
+        };
+
         // FIX-15: When fetch fails, trigger auto_repair_on_failure for health tracking
-        let data = match p2p_node.get_or_fetch_track(hash).await {
-            Ok(data) => data,
+        let track_range = match fetched {
+            Ok(track_range) => track_range,
+            // A moderation block is not a source failure: nothing to repair
+            Err(e @ soundtime_p2p::P2pError::HashBlocked { .. }) => {
+                tracing::info!(%hash, "refused to stream P2P track: {e}");
+                return Err((
+                    StatusCode::FORBIDDEN,
+                    Json(serde_json::json!({ "error": "This track has been blocked" })),
+                ));
+            }
             Err(e) => {
                 tracing::warn!(%hash, error = %e, "failed to fetch P2P track");
+                p2p_node.unpin_stream_source(id);
 
                 // Determine origin node from the remote_track record
                 let origin_node = remote_record
@@ -541,44 +692,59 @@ pub async fn stream_track(
             }
         };
 
-        let file_size = data.len() as u64;
-
-        let (start, end) = match range {
-            Some((s, e)) => {
-                let end = e.unwrap_or(file_size - 1).min(file_size - 1);
-                (s, end)
-            }
-            None => (0, file_size - 1),
-        };
+        let file_size = track_range.total_size;
+        let content_length = track_range.data.len() as u64;
 
-        let content_length = end - start + 1;
-        let slice = data.slice(start as usize..(start + content_length) as usize);
-        let body = Body::from(slice);
+        let mut response_headers = HeaderMap::new();
+        response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
 
-        let status = if range.is_some() {
-            StatusCode::PARTIAL_CONTENT
-        } else {
-            StatusCode::OK
+        let status = match (range, track_range.end()) {
+            (None, _) => StatusCode::OK,
+            (Some(_), Some(end)) => {
+                let start = track_range.start;
+                response_headers.insert(
+                    header::CONTENT_RANGE,
+                    HeaderValue::from_str(&format!("bytes {start}-{end}/{file_size}"))
+                        .unwrap_or_else(|_| HeaderValue::from_static("bytes */*")),
+                );
+                StatusCode::PARTIAL_CONTENT
+            }
+            (Some(_), None) => {
+                response_headers.insert(
+                    header::CONTENT_RANGE,
+                    HeaderValue::from_str(&format!("bytes */{file_size}"))
+                        .unwrap_or_else(|_| HeaderValue::from_static("bytes */*")),
+                );
+                return Ok((
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    response_headers,
+                    Body::empty(),
+                ));
+            }
         };
 
-        let mut response_headers = HeaderMap::new();
         response_headers.insert(
             header::CONTENT_TYPE,
             HeaderValue::from_str(content_type)
                 .unwrap_or_else(|_| HeaderValue::from_static("application/octet-stream")),
         );
         response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(content_length));
-        response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
 
-        if range.is_some() {
-            response_headers.insert(
-                header::CONTENT_RANGE,
-                HeaderValue::from_str(&format!("bytes {start}-{end}/{file_size}"))
-                    .unwrap_or_else(|_| HeaderValue::from_static("bytes */*")),
-            );
-        }
+        return Ok((status, response_headers, Body::from(track_range.data)));
+    }
 
-        return Ok((status, response_headers, body));
+    // Object storage can serve the file directly; send the client there
+    // instead of proxying it through the cache
+    if let Some(url) = state.storage.presigned_url(file_path_str).await {
+        if let Ok(location) = HeaderValue::from_str(&url) {
+            let mut response_headers = HeaderMap::new();
+            response_headers.insert(header::LOCATION, location);
+            return Ok((
+                StatusCode::TEMPORARY_REDIRECT,
+                response_headers,
+                Body::empty(),
+            ));
+        }
     }
 
     // Regular filesystem track
@@ -660,6 +826,20 @@ pub async fn stream_track(
     Ok((status, response_headers, body))
 }
 
+/// MIME type for an audio format name (case-insensitive)
+fn audio_content_type(format: &str) -> &'static str {
+    match format.to_ascii_lowercase().as_str() {
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "aac" => "audio/aac",
+        "opus" => "audio/opus",
+        "aiff" => "audio/aiff",
+        _ => "application/octet-stream",
+    }
+}
+
 /// Parse "bytes=start-end" range header
 fn parse_range_header(header: &str) -> Option<(u64, Option<u64>)> {
     let range = header.strip_prefix("bytes=")?;
@@ -757,10 +937,33 @@ pub struct BatchUploadItem {
     pub track: Option<UploadResponse>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Library track with the same acoustic fingerprint, when the file was
+    /// refused as a duplicate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<Uuid>,
+}
+
+/// Why one file of a batch upload failed.
+#[derive(Debug)]
+struct BatchItemError {
+    message: String,
+    duplicate_of: Option<Uuid>,
+}
+
+impl From<String> for BatchItemError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            duplicate_of: None,
+        }
+    }
 }
 
 /// POST /api/upload/batch — Upload multiple audio files at once.
 /// Each file is sent as a multipart field named "files".
+///
+/// Files whose acoustic fingerprint matches a library track fail with
+/// `duplicate_of` set, unless `allow_duplicate=true` is sent.
 pub async fn upload_tracks_batch(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthUser>,
@@ -768,11 +971,14 @@ pub async fn upload_tracks_batch(
 ) -> Result<Json<BatchUploadResponse>, (StatusCode, Json<serde_json::Value>)> {
     let user_id = user.0.sub;
     let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut allow_duplicate = false;
     const MAX_BATCH_FILES: usize = 50;
 
     while let Ok(Some(field)) = multipart.next_field().await {
         let name = field.name().unwrap_or("").to_string();
-        if name == "files" || name == "file" {
+        if name == "allow_duplicate" {
+            allow_duplicate = field.text().await.is_ok_and(|v| v.trim() == "true");
+        } else if name == "files" || name == "file" {
             let filename = field.file_name().unwrap_or("upload.mp3").to_string();
             let data = field.bytes().await.map_err(|e| {
                 (
@@ -804,7 +1010,7 @@ pub async fn upload_tracks_batch(
     let mut success_count = 0usize;
 
     for (filename, data) in files {
-        match process_single_upload(&state, user_id, &filename, &data).await {
+        match process_single_upload(&state, user_id, &filename, data, allow_duplicate).await {
             Ok(resp) => {
                 success_count += 1;
                 results.push(BatchUploadItem {
@@ -812,6 +1018,7 @@ pub async fn upload_tracks_batch(
                     success: true,
                     track: Some(resp),
                     error: None,
+                    duplicate_of: None,
                 });
             }
             Err(e) => {
@@ -819,7 +1026,8 @@ pub async fn upload_tracks_batch(
                     filename,
                     success: false,
                     track: None,
-                    error: Some(e),
+                    error: Some(e.message),
+                    duplicate_of: e.duplicate_of,
                 });
             }
         }
@@ -835,11 +1043,12 @@ pub async fn upload_tracks_batch(
 
 /// Shared logic for processing a single file upload (used by both single and batch).
 async fn process_single_upload(
-    state: &AppState,
+    state: &Arc<AppState>,
     user_id: Uuid,
     filename: &str,
-    data: &[u8],
-) -> Result<UploadResponse, String> {
+    data: Vec<u8>,
+    allow_duplicate: bool,
+) -> Result<UploadResponse, BatchItemError> {
     let ext = std::path::Path::new(filename)
         .extension()
         .and_then(|e| e.to_str())
@@ -847,17 +1056,19 @@ async fn process_single_upload(
         .to_lowercase();
 
     if !soundtime_audio::metadata::is_supported_format(&ext) {
-        return Err(format!("Unsupported format: {ext}"));
+        return Err(format!("Unsupported format: {ext}").into());
     }
 
     // SECURITY: validate audio magic bytes
-    if !validate_audio_magic_bytes(data) {
-        return Err("File content does not match a recognized audio format".to_string());
+    if !validate_audio_magic_bytes(&data) {
+        return Err("File content does not match a recognized audio format"
+            .to_string()
+            .into());
     }
 
     let relative_path = state
         .storage
-        .store_file(user_id, None, filename, data)
+        .store_file(user_id, None, filename, &data)
         .await
         .map_err(|e| format!("Storage error: {e}"))?;
 
@@ -879,10 +1090,28 @@ async fn process_single_upload(
         (full_path, relative_path)
     };
 
-    let audio_meta =
+    let mut audio_meta =
         extract_metadata_from_file(&full_path).map_err(|e| format!("Metadata: {e}"))?;
+    fill_metadata_from_filename(&mut audio_meta, filename);
+    let embedded_cover = extract_embedded_cover(&full_path);
 
-    let waveform = soundtime_audio::generate_waveform(&full_path, 200).ok();
+    let fingerprint = soundtime_audio::compute_fingerprint(&full_path).await;
+    let network_duplicates = check_duplicate_upload(
+        state,
+        user_id,
+        fingerprint.as_deref(),
+        audio_meta.duration_secs as f32,
+        allow_duplicate,
+        &relative_path,
+    )
+    .await
+    .map_err(|e| match e {
+        DuplicateCheckError::Duplicate(existing) => BatchItemError {
+            message: DUPLICATE_UPLOAD_ERROR.to_string(),
+            duplicate_of: Some(existing),
+        },
+        DuplicateCheckError::Db(e) => format!("DB: {e}").into(),
+    })?;
 
     let artist_name = audio_meta
         .artist
@@ -958,8 +1187,8 @@ async fn process_single_upload(
         .await
         .map_err(|e| format!("DB: {e}"))?;
 
-    let album_id = if let Some(a) = existing_album {
-        a.id
+    let album_record = if let Some(a) = existing_album {
+        a
     } else {
         let new_album = album::ActiveModel {
             id: Set(Uuid::new_v4()),
@@ -976,28 +1205,18 @@ async fn process_single_upload(
             year: Set(audio_meta.year.map(|y| y as i16)),
             created_at: Set(chrono::Utc::now().into()),
         };
-        let result = new_album
+        new_album
             .insert(&state.db)
             .await
-            .map_err(|e| format!("DB: {e}"))?;
+            .map_err(|e| format!("DB: {e}"))?
+    };
+    let album_id = album_record.id;
 
-        // Store cover art if present in metadata
-        if let Some(cover_data) = &audio_meta.cover_art {
-            if let Ok(cover_path) = state
-                .storage
-                .store_cover(user_id, Some(&album_title), cover_data)
-                .await
-            {
-                let mut update: album::ActiveModel = result.clone().into();
-                update.cover_url = Set(Some(format!("/api/media/{cover_path}")));
-                if let Err(e) = update.update(&state.db).await {
-                    tracing::warn!(error = %e, "failed to update album cover URL");
-                }
-            }
+    if let Some(cover) = embedded_cover {
+        if let Err(e) = apply_embedded_cover(state, user_id, &album_record, cover).await {
+            tracing::warn!(%album_id, "failed to apply embedded cover: {e}");
         }
-
-        result.id
-    };
+    }
 
     let track_title = audio_meta.title.clone().unwrap_or_else(|| {
         std::path::Path::new(filename)
@@ -1028,10 +1247,19 @@ async fn process_single_upload(
         file_size: Set(audio_meta.file_size as i64),
         bitrate: Set(audio_meta.bitrate.map(|b| b as i32)),
         sample_rate: Set(audio_meta.sample_rate.map(|s| s as i32)),
-        waveform_data: Set(waveform.map(|w| serde_json::json!(w))),
+        // Computed in the background, see `finish_upload_in_background`
+        waveform_data: Set(None),
         uploaded_by: Set(Some(user_id)),
         content_hash: Set(None),
+        fingerprint: Set(fingerprint.clone()),
         play_count: Set(0),
+        is_private: Set(false),
+        is_hidden: Set(false),
+        hidden_by_block: Set(false),
+        // Measured in the background, see `finish_upload_in_background`
+        loudness_lufs: Set(None),
+        dynamic_range: Set(None),
+        encoding_quality: Set(audio_meta.encoding_quality.clone()),
         created_at: Set(chrono::Utc::now().into()),
     };
 
@@ -1040,17 +1268,18 @@ async fn process_single_upload(
         .await
         .map_err(|e| format!("DB: {e}"))?;
 
-    // Publish track to P2P blob store (best-effort)
-    publish_track_to_p2p(
-        state,
+    finish_upload_in_background(
+        Arc::clone(state),
         track_id,
-        data,
-        &track_title,
-        &artist_name,
-        &album_title,
-        &audio_meta,
-    )
-    .await;
+        full_path,
+        PublishedTrack {
+            title: track_title.clone(),
+            artist_name: artist_name.clone(),
+            album_title: album_title.clone(),
+            audio_meta: audio_meta.clone(),
+            fingerprint,
+        },
+    );
 
     // Dispatch plugin event (best-effort)
     if let Some(registry) = super::get_plugin_registry(state) {
@@ -1073,32 +1302,117 @@ async fn process_single_upload(
         duration: audio_meta.duration_secs,
         format: audio_meta.format,
         message: "Track uploaded successfully".into(),
+        network_duplicates,
     })
 }
 
 // ─── P2P publication helper ─────────────────────────────────────────
 
-/// Publish a newly uploaded track to the P2P blob store and broadcast
-/// an announcement to all connected peers.
+/// What a new upload's P2P announcement is built from.
+struct PublishedTrack {
+    title: String,
+    artist_name: String,
+    album_title: String,
+    audio_meta: soundtime_audio::AudioMetadata,
+    fingerprint: Option<String>,
+}
+
+/// Uploads finished in the background at once; later ones wait their turn.
+const MAX_CONCURRENT_UPLOAD_FINISHES: usize = 2;
+
+/// Permits for [`finish_upload_in_background`].
+static UPLOAD_FINISHES: LazyLock<Semaphore> =
+    LazyLock::new(|| Semaphore::new(MAX_CONCURRENT_UPLOAD_FINISHES));
+
+/// Publish a new upload to P2P, then measure its loudness and compute its
+/// waveform, without holding up the response. At most
+/// [`MAX_CONCURRENT_UPLOAD_FINISHES`] uploads are worked on at once; the
+/// audio is read back from `full_path` when its turn comes. Once both are
+/// known the track is announced again so peers fill them in.
+fn finish_upload_in_background(
+    state: Arc<AppState>,
+    track_id: Uuid,
+    full_path: std::path::PathBuf,
+    mut published: PublishedTrack,
+) {
+    // Read back with the audio rather than held while waiting
+    published.audio_meta.cover_art = None;
+    tokio::spawn(async move {
+        let Ok(_permit) = UPLOAD_FINISHES.acquire().await else {
+            return;
+        };
+        let announcement = publish_track_to_p2p(&state, track_id, &full_path, published).await;
+        let (loudness_lufs, dynamic_range) = save_loudness(&state, track_id, &full_path).await;
+        let waveform = crate::waveform_worker::generate(&state, track_id, full_path).await;
+
+        let Some(mut announcement) = announcement else {
+            return;
+        };
+        if waveform.is_none() && loudness_lufs.is_none() && dynamic_range.is_none() {
+            return;
+        }
+        announcement.waveform_data = waveform;
+        announcement.loudness_lufs = loudness_lufs;
+        announcement.dynamic_range = dynamic_range;
+        if let Some(p2p) = get_p2p_node(&state) {
+            p2p.broadcast_announce_track(announcement).await;
+        }
+    });
+}
+
+/// Measure the loudness of an uploaded track on the blocking thread pool
+/// and save it to the track.
+async fn save_loudness(
+    state: &AppState,
+    track_id: Uuid,
+    path: &std::path::Path,
+) -> (Option<f32>, Option<f32>) {
+    let (loudness_lufs, dynamic_range) =
+        soundtime_audio::measure_loudness_async(path.to_path_buf()).await;
+    if loudness_lufs.is_some() || dynamic_range.is_some() {
+        let update = track::ActiveModel {
+            id: Set(track_id),
+            loudness_lufs: Set(loudness_lufs),
+            dynamic_range: Set(dynamic_range),
+            ..Default::default()
+        };
+        if let Err(e) = update.update(&state.db).await {
+            tracing::warn!(%track_id, "failed to save loudness: {e}");
+        }
+    }
+    (loudness_lufs, dynamic_range)
+}
+
+/// Publish a newly uploaded track, read from `full_path`, to the P2P blob
+/// store and broadcast an announcement to all connected peers. Returns the
+/// announcement, without waveform or loudness, for announcing them later.
 ///
 /// This is best-effort: failures are logged but do not prevent the upload
 /// from succeeding. Called from both single and batch upload paths.
 async fn publish_track_to_p2p(
     state: &AppState,
     track_id: Uuid,
-    data: &[u8],
-    track_title: &str,
-    artist_name: &str,
-    album_title: &str,
-    audio_meta: &soundtime_audio::AudioMetadata,
-) {
-    let p2p = match get_p2p_node(state) {
-        Some(p2p) => p2p,
-        None => return,
+    full_path: &std::path::Path,
+    published: PublishedTrack,
+) -> Option<soundtime_p2p::TrackAnnouncement> {
+    let p2p = get_p2p_node(state)?;
+    let PublishedTrack {
+        title: track_title,
+        artist_name,
+        album_title,
+        audio_meta,
+        fingerprint,
+    } = published;
+
+    let data = match tokio::fs::read(full_path).await {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::warn!(%track_id, path = %full_path.display(), "failed to read upload for P2P: {e}");
+            return None;
+        }
     };
 
-    let data_bytes = bytes::Bytes::from(data.to_vec());
-    match p2p.publish_track(data_bytes).await {
+    match p2p.publish_track(data.into()).await {
         Ok(hash) => {
             tracing::info!(%track_id, %hash, "track published to P2P blob store");
             // Update the content_hash in the DB
@@ -1110,13 +1424,11 @@ async fn publish_track_to_p2p(
             if let Err(e) = update.update(&state.db).await {
                 tracing::warn!(%track_id, "failed to save content_hash: {e}");
             }
+            p2p.invalidate_catalog_checksum();
 
             // Publish cover art to P2P blob store if available
-            let cover_hash = if let Some(cover_data) = &audio_meta.cover_art {
-                match p2p
-                    .publish_cover(bytes::Bytes::from(cover_data.clone()))
-                    .await
-                {
+            let cover_hash = if let Some(cover_data) = extract_embedded_cover(full_path) {
+                match p2p.publish_cover(cover_data).await {
                     Ok(h) => Some(h.to_string()),
                     Err(e) => {
                         tracing::warn!(%track_id, "failed to publish cover to P2P: {e}");
@@ -1130,10 +1442,10 @@ async fn publish_track_to_p2p(
             // Broadcast full track metadata to all connected peers
             let announcement = soundtime_p2p::TrackAnnouncement {
                 hash: hash.to_string(),
-                title: track_title.to_string(),
-                artist_name: artist_name.to_string(),
+                title: track_title,
+                artist_name,
                 album_artist_name: audio_meta.album_artist.clone(),
-                album_title: Some(album_title.to_string()),
+                album_title: Some(album_title),
                 duration_secs: audio_meta.duration_secs as f32,
                 format: audio_meta.format.clone(),
                 file_size: audio_meta.file_size as i64,
@@ -1145,20 +1457,79 @@ async fn publish_track_to_p2p(
                 sample_rate: audio_meta.sample_rate.map(|s| s as i32),
                 origin_node: p2p.node_id().to_string(),
                 cover_hash,
+                fingerprint,
+                waveform_data: None,
+                artist_image_hash: None,
+                artist_bio: None,
+                loudness_lufs: None,
+                dynamic_range: None,
+                encoding_quality: audio_meta.encoding_quality.clone(),
+                signature: None,
             };
             let p2p_clone = Arc::clone(&p2p);
+            let first = announcement.clone();
             tokio::spawn(async move {
-                p2p_clone.broadcast_announce_track(announcement).await;
+                p2p_clone.broadcast_announce_track(first).await;
             });
+            Some(announcement)
         }
         Err(e) => {
             tracing::warn!(%track_id, "failed to publish track to P2P: {e}");
+            None
         }
     }
 }
 
 // ─── Album Cover Upload ────────────────────────────────────────────
 
+/// Give `album` the art embedded in one of its tracks if it has no cover
+/// yet: store the image, point `albums.cover_url` at it and publish it to
+/// the P2P blob store. Returns whether the album got a cover.
+pub(crate) async fn apply_embedded_cover(
+    state: &AppState,
+    uploader: Uuid,
+    album: &album::Model,
+    cover: bytes::Bytes,
+) -> Result<bool, String> {
+    if album.cover_url.is_some() {
+        return Ok(false);
+    }
+
+    let cover_path = state
+        .storage
+        .store_cover(uploader, Some(&album.title), &cover)
+        .await
+        .map_err(|e| format!("Store cover: {e}"))?;
+    let cover_url = format!("/api/media/{cover_path}");
+
+    let mut update: album::ActiveModel = album.clone().into();
+    update.cover_url = Set(Some(cover_url.clone()));
+    update
+        .update(&state.db)
+        .await
+        .map_err(|e| format!("Update album: {e}"))?;
+
+    let cover_hash = match get_p2p_node(state) {
+        Some(p2p) => match p2p.publish_cover(cover.clone()).await {
+            Ok(hash) => Some(hash.to_string()),
+            Err(e) => {
+                tracing::warn!(album_id = %album.id, "failed to publish cover to P2P: {e}");
+                None
+            }
+        },
+        None => None,
+    };
+
+    tracing::info!(
+        album_id = %album.id,
+        %cover_url,
+        size = cover.len(),
+        ?cover_hash,
+        "extracted embedded album cover"
+    );
+    Ok(true)
+}
+
 /// POST /api/albums/:id/cover — Upload a custom cover image for an album.
 /// Only the user who uploaded tracks to this album can update the cover.
 pub async fn upload_album_cover(
@@ -1412,6 +1783,14 @@ mod tests {
 
     // ─── parse_range_header tests ──────────────────────────────────
 
+    #[test]
+    fn test_audio_content_type() {
+        assert_eq!(audio_content_type("flac"), "audio/flac");
+        assert_eq!(audio_content_type("FLAC"), "audio/flac");
+        assert_eq!(audio_content_type("mp3"), "audio/mpeg");
+        assert_eq!(audio_content_type("xyz"), "application/octet-stream");
+    }
+
     #[test]
     fn test_parse_range_open_end() {
         let result = parse_range_header("bytes=0-");
@@ -1458,6 +1837,7 @@ mod tests {
             duration: 180.5,
             format: "mp3".to_string(),
             message: "Track uploaded successfully".to_string(),
+            network_duplicates: vec![],
         };
         let json = serde_json::to_value(&resp).unwrap();
         assert_eq!(json["title"], "My Song");
@@ -1465,6 +1845,25 @@ mod tests {
         assert_eq!(json["format"], "mp3");
         assert_eq!(json["message"], "Track uploaded successfully");
         assert!(json["id"].is_string());
+        assert!(json.get("network_duplicates").is_none());
+    }
+
+    #[test]
+    fn test_upload_response_lists_network_duplicates() {
+        let resp = UploadResponse {
+            id: Uuid::nil(),
+            title: "My Song".to_string(),
+            duration: 180.5,
+            format: "mp3".to_string(),
+            message: "Track uploaded successfully".to_string(),
+            network_duplicates: vec![soundtime_p2p::NetworkDuplicate {
+                hash: "abc".to_string(),
+                source_node: "node".to_string(),
+            }],
+        };
+        let json = serde_json::to_value(&resp).unwrap();
+        assert_eq!(json["network_duplicates"][0]["hash"], "abc");
+        assert_eq!(json["network_duplicates"][0]["source_node"], "node");
     }
 
     #[test]
@@ -1480,14 +1879,17 @@ mod tests {
                         duration: 120.0,
                         format: "mp3".to_string(),
                         message: "ok".to_string(),
+                        network_duplicates: vec![],
                     }),
                     error: None,
+                    duplicate_of: None,
                 },
                 BatchUploadItem {
                     filename: "bad.exe".to_string(),
                     success: false,
                     track: None,
                     error: Some("Unsupported format".to_string()),
+                    duplicate_of: None,
                 },
             ],
             total: 2,
@@ -1512,6 +1914,7 @@ mod tests {
             success: true,
             track: None,
             error: None,
+            duplicate_of: None,
         };
         let json = serde_json::to_value(&item).unwrap();
         // Fields with skip_serializing_if = "Option::is_none" should be absent
@@ -1520,4 +1923,254 @@ mod tests {
         assert_eq!(json["filename"], "test.flac");
         assert!(json["success"].as_bool().unwrap());
     }
+
+    // ─── fingerprint duplicates in batch uploads ───────────────────
+
+    const DUPLICATE_FINGERPRINT: &str = "AQADtEmUaFmSRUmS";
+
+    /// Point `FPCALC_PATH` at a script printing [`DUPLICATE_FINGERPRINT`],
+    /// shared by every test so none removes it under another.
+    fn fake_fpcalc() {
+        static FPCALC: std::sync::OnceLock<std::path::PathBuf> = std::sync::OnceLock::new();
+        FPCALC.get_or_init(|| {
+            use std::os::unix::fs::PermissionsExt;
+            let dir = tempfile::tempdir().unwrap().keep();
+            let path = dir.join("fpcalc");
+            std::fs::write(
+                &path,
+                format!(
+                    "#!/bin/sh\necho '{{\"duration\": 0.1, \"fingerprint\": \"{DUPLICATE_FINGERPRINT}\"}}'\n"
+                ),
+            )
+            .unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            std::env::set_var("FPCALC_PATH", &path);
+            path
+        });
+    }
+
+    /// A tenth of a second of silence as a mono 16-bit PCM WAV.
+    fn silent_wav() -> Vec<u8> {
+        let sample_rate = 8000u32;
+        let data_size = sample_rate / 10 * 2;
+        let mut bytes = Vec::with_capacity(44 + data_size as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_size.to_le_bytes());
+        bytes.resize(44 + data_size as usize, 0);
+        bytes
+    }
+
+    /// Database holding one library track with [`DUPLICATE_FINGERPRINT`].
+    async fn db_with_fingerprinted_track() -> (sea_orm::DatabaseConnection, Uuid) {
+        let db = crate::test_db::connect().await;
+        crate::test_db::create_table(&db, artist::Entity).await;
+        crate::test_db::create_table(&db, album::Entity).await;
+        crate::test_db::create_table(&db, track::Entity).await;
+        let id = Uuid::new_v4();
+        track::ActiveModel {
+            id: Set(id),
+            title: Set("Original".to_string()),
+            artist_id: Set(Uuid::new_v4()),
+            album_id: Set(None),
+            track_number: Set(None),
+            disc_number: Set(None),
+            duration_secs: Set(0.1),
+            genre: Set(None),
+            year: Set(None),
+            musicbrainz_id: Set(None),
+            file_path: Set("original.wav".to_string()),
+            file_size: Set(1644),
+            format: Set("wav".to_string()),
+            bitrate: Set(None),
+            sample_rate: Set(None),
+            waveform_data: Set(None),
+            uploaded_by: Set(None),
+            content_hash: Set(None),
+            fingerprint: Set(Some(DUPLICATE_FINGERPRINT.to_string())),
+            play_count: Set(0),
+            is_private: Set(false),
+            is_hidden: Set(false),
+            hidden_by_block: Set(false),
+            loudness_lufs: Set(None),
+            dynamic_range: Set(None),
+            encoding_quality: Set(None),
+            created_at: Set(chrono::Utc::now().into()),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        (db, id)
+    }
+
+    /// POST one WAV to the batch upload handler and return the JSON body.
+    async fn batch_upload(state: Arc<AppState>, allow_duplicate: bool) -> serde_json::Value {
+        use axum::{http::Request, routing::post, Router};
+        use tower::ServiceExt;
+
+        let boundary = "soundtime-test-boundary";
+        let mut body = Vec::new();
+        if allow_duplicate {
+            body.extend_from_slice(
+                format!(
+                    "--{boundary}\r\nContent-Disposition: form-data; name=\"allow_duplicate\"\r\n\r\ntrue\r\n"
+                )
+                .as_bytes(),
+            );
+        }
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"files\"; filename=\"Artist - Copy.wav\"\r\nContent-Type: audio/wav\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(&silent_wav());
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+        let user = crate::auth::middleware::AuthUser(crate::auth::jwt::Claims {
+            sub: Uuid::new_v4(),
+            username: "uploader".to_string(),
+            role: "user".to_string(),
+            token_type: crate::auth::jwt::TokenType::Access,
+            iat: 0,
+            exp: 9999999999,
+        });
+        let app = Router::new()
+            .route("/upload/batch", post(upload_tracks_batch))
+            .layer(Extension(user))
+            .with_state(state);
+        let req = Request::builder()
+            .method("POST")
+            .uri("/upload/batch")
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    fn stored_files(root: &std::path::Path) -> usize {
+        let mut count = 0;
+        let mut dirs = vec![root.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(dir).unwrap().flatten() {
+                if entry.file_type().unwrap().is_dir() {
+                    dirs.push(entry.path());
+                } else {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    #[tokio::test]
+    async fn test_batch_upload_refuses_fingerprint_duplicate() {
+        fake_fpcalc();
+        let storage = tempfile::tempdir().unwrap();
+        let (db, existing) = db_with_fingerprinted_track().await;
+
+        let json = batch_upload(
+            crate::test_db::state_with_storage(db.clone(), storage.path()),
+            false,
+        )
+        .await;
+        assert_eq!(json["success"], 0);
+        assert_eq!(json["failed"], 1);
+        let item = &json["results"][0];
+        assert_eq!(item["success"], false);
+        assert_eq!(item["error"], DUPLICATE_UPLOAD_ERROR);
+        assert_eq!(item["duplicate_of"], existing.to_string());
+
+        assert_eq!(stored_files(storage.path()), 0, "duplicate file is removed");
+        assert_eq!(track::Entity::find().all(&db).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_upload_ignores_other_users_private_track() {
+        fake_fpcalc();
+        let storage = tempfile::tempdir().unwrap();
+        let (db, existing) = db_with_fingerprinted_track().await;
+        track::Entity::update_many()
+            .col_expr(
+                track::Column::IsPrivate,
+                sea_orm::sea_query::Expr::value(true),
+            )
+            .filter(track::Column::Id.eq(existing))
+            .exec(&db)
+            .await
+            .unwrap();
+
+        let json = batch_upload(
+            crate::test_db::state_with_storage(db.clone(), storage.path()),
+            false,
+        )
+        .await;
+        assert_eq!(json["success"], 1);
+        let item = &json["results"][0];
+        assert!(item.get("duplicate_of").is_none());
+        assert!(!item.to_string().contains(&existing.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_batch_upload_allow_duplicate_uploads_anyway() {
+        fake_fpcalc();
+        let storage = tempfile::tempdir().unwrap();
+        let (db, _) = db_with_fingerprinted_track().await;
+
+        let json = batch_upload(
+            crate::test_db::state_with_storage(db.clone(), storage.path()),
+            true,
+        )
+        .await;
+        assert_eq!(json["success"], 1);
+        assert!(json["results"][0].get("duplicate_of").is_none());
+
+        let copies = track::Entity::find()
+            .filter(track::Column::Fingerprint.eq(DUPLICATE_FINGERPRINT))
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(copies.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_batch_upload_ignores_same_fingerprint_of_other_duration() {
+        fake_fpcalc();
+        let storage = tempfile::tempdir().unwrap();
+        let (db, existing) = db_with_fingerprinted_track().await;
+        track::Entity::update_many()
+            .col_expr(
+                track::Column::DurationSecs,
+                sea_orm::sea_query::Expr::value(200.0f32),
+            )
+            .filter(track::Column::Id.eq(existing))
+            .exec(&db)
+            .await
+            .unwrap();
+
+        let json = batch_upload(
+            crate::test_db::state_with_storage(db.clone(), storage.path()),
+            false,
+        )
+        .await;
+        assert_eq!(json["success"], 1);
+        assert!(json["results"][0].get("duplicate_of").is_none());
+    }
 }
@@ -0,0 +1,495 @@
+//! Admin audit log — who blocked, banned, moderated or reconfigured what.
+//!
+//! Handlers for peer block/unblock, user ban/unban, track moderation, hash
+//! blocks, trusted moderators and setting changes (the Terms of Service
+//! included) write an `admin_audit_log` row with [`AuditEntry::record`]
+//! inside the transaction that applies the action, so an entry exists if and
+//! only if the action was committed.
+//!
+//! - Admins can list the log (GET /api/admin/audit-log), filtered by actor,
+//!   action and date range
+
+use axum::{
+    extract::{FromRequestParts, Query, State},
+    http::{request::Parts, StatusCode},
+    Json,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, OnceLock};
+use uuid::Uuid;
+
+use soundtime_db::entities::{admin_audit_log, user};
+use soundtime_db::AppState;
+
+/// Kind of admin action recorded in the audit log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    BlockPeer,
+    UnblockPeer,
+    BanUser,
+    UnbanUser,
+    ModerateTrack,
+    UpdateSetting,
+    BlockHash,
+    ApproveBlockedHash,
+    UnblockHash,
+    SetTrustedModerator,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::BlockPeer => "block_peer",
+            AuditAction::UnblockPeer => "unblock_peer",
+            AuditAction::BanUser => "ban_user",
+            AuditAction::UnbanUser => "unban_user",
+            AuditAction::ModerateTrack => "moderate_track",
+            AuditAction::UpdateSetting => "update_setting",
+            AuditAction::BlockHash => "block_hash",
+            AuditAction::ApproveBlockedHash => "approve_blocked_hash",
+            AuditAction::UnblockHash => "unblock_hash",
+            AuditAction::SetTrustedModerator => "set_trusted_moderator",
+        }
+    }
+}
+
+/// An address or CIDR range of reverse proxies, e.g. `10.0.0.2` or
+/// `172.16.0.0/12`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ProxyRange {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl ProxyRange {
+    fn parse(s: &str) -> Option<Self> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr.trim().parse().ok()?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p.trim().parse().ok().filter(|p| *p <= max)?,
+            None => max,
+        };
+        Some(Self { addr, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Reverse proxies whose `X-Real-IP` is believed (`TRUSTED_PROXIES`,
+/// comma-separated addresses or CIDR ranges). Empty when unset, so the
+/// header is ignored.
+fn trusted_proxies() -> &'static [ProxyRange] {
+    static TRUSTED: OnceLock<Vec<ProxyRange>> = OnceLock::new();
+    TRUSTED.get_or_init(|| {
+        let raw = std::env::var("TRUSTED_PROXIES").unwrap_or_default();
+        raw.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| {
+                let range = ProxyRange::parse(s);
+                if range.is_none() {
+                    tracing::warn!(entry = %s, "ignoring invalid TRUSTED_PROXIES entry");
+                }
+                range
+            })
+            .collect()
+    })
+}
+
+/// IP address the request came from: the address of the TCP peer, or the
+/// `X-Real-IP` it sets when that peer is a trusted reverse proxy. Anyone
+/// else could put any address in the header.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClientIp(pub Option<String>);
+
+impl ClientIp {
+    fn resolve(parts: &Parts, trusted: &[ProxyRange]) -> Self {
+        let peer = parts
+            .extensions
+            .get::<axum::extract::ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip());
+        let forwarded = || {
+            parts
+                .headers
+                .get("X-Real-IP")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|ip| ip.trim().parse::<IpAddr>().ok())
+        };
+        let ip = match peer {
+            Some(peer) if trusted.iter().any(|r| r.contains(peer)) => forwarded().or(Some(peer)),
+            other => other,
+        };
+        ClientIp(ip.map(|ip| ip.to_string()))
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::resolve(parts, trusted_proxies()))
+    }
+}
+
+/// An admin action about to be written to the audit log.
+#[derive(Clone, Debug)]
+pub struct AuditEntry {
+    pub actor: Uuid,
+    pub action: AuditAction,
+    pub target: String,
+    pub reason: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(actor: Uuid, action: AuditAction, target: impl Into<String>) -> Self {
+        Self {
+            actor,
+            action,
+            target: target.into(),
+            reason: None,
+        }
+    }
+
+    pub fn with_reason(mut self, reason: Option<String>) -> Self {
+        self.reason = reason;
+        self
+    }
+
+    fn into_active_model(
+        self,
+        ip: &ClientIp,
+        now: chrono::DateTime<chrono::FixedOffset>,
+    ) -> admin_audit_log::ActiveModel {
+        admin_audit_log::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            actor_id: Set(Some(self.actor)),
+            action: Set(self.action.as_str().to_string()),
+            target: Set(self.target),
+            reason: Set(self.reason),
+            ip_address: Set(ip.0.clone()),
+            created_at: Set(now),
+        }
+    }
+
+    /// Write the entry. Pass the transaction that applies the action.
+    pub async fn record<C: ConnectionTrait>(self, db: &C, ip: &ClientIp) -> Result<(), DbErr> {
+        self.into_active_model(ip, chrono::Utc::now().fixed_offset())
+            .insert(db)
+            .await?;
+        Ok(())
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════
+// ADMIN: List the audit log
+// ═══════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct AuditLogParams {
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+    /// Only entries by this admin
+    pub actor: Option<Uuid>,
+    pub action: Option<AuditAction>,
+    /// Only entries at or after this time (RFC 3339)
+    pub from: Option<chrono::DateTime<chrono::FixedOffset>>,
+    /// Only entries before this time (RFC 3339)
+    pub to: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogEntryResponse {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>,
+    /// `None` once the admin's account is deleted
+    pub actor_username: Option<String>,
+    pub action: String,
+    pub target: String,
+    pub reason: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+/// GET /api/admin/audit-log — paginated audit log, newest first
+pub async fn list_audit_log(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AuditLogParams>,
+) -> Result<Json<super::tracks::PaginatedResponse<AuditLogEntryResponse>>, (StatusCode, String)> {
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(50).clamp(1, 100);
+
+    let mut query =
+        admin_audit_log::Entity::find().order_by_desc(admin_audit_log::Column::CreatedAt);
+    if let Some(actor) = params.actor {
+        query = query.filter(admin_audit_log::Column::ActorId.eq(actor));
+    }
+    if let Some(action) = params.action {
+        query = query.filter(admin_audit_log::Column::Action.eq(action.as_str()));
+    }
+    if let Some(from) = params.from {
+        query = query.filter(admin_audit_log::Column::CreatedAt.gte(from));
+    }
+    if let Some(to) = params.to {
+        query = query.filter(admin_audit_log::Column::CreatedAt.lt(to));
+    }
+
+    let paginator = query.paginate(&state.db, per_page);
+    let total = paginator
+        .num_items()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+    let entries = paginator
+        .fetch_page(page - 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+
+    let actor_ids: Vec<Uuid> = entries.iter().filter_map(|e| e.actor_id).collect();
+    let usernames: HashMap<Uuid, String> = if actor_ids.is_empty() {
+        HashMap::new()
+    } else {
+        user::Entity::find()
+            .filter(user::Column::Id.is_in(actor_ids))
+            .all(&state.db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?
+            .into_iter()
+            .map(|u| (u.id, u.username))
+            .collect()
+    };
+
+    let data = entries
+        .into_iter()
+        .map(|e| AuditLogEntryResponse {
+            id: e.id,
+            actor_username: e.actor_id.and_then(|id| usernames.get(&id).cloned()),
+            actor_id: e.actor_id,
+            action: e.action,
+            target: e.target,
+            reason: e.reason,
+            ip_address: e.ip_address,
+            created_at: e.created_at,
+        })
+        .collect();
+
+    Ok(Json(super::tracks::PaginatedResponse {
+        data,
+        total,
+        page,
+        per_page,
+        total_pages: total.div_ceil(per_page),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn now() -> chrono::DateTime<chrono::FixedOffset> {
+        chrono::Utc::now().fixed_offset()
+    }
+
+    async fn test_state() -> Arc<AppState> {
+        crate::test_db::state(crate::test_db::connect().await)
+    }
+
+    // ── entries ──
+
+    #[test]
+    fn test_block_entry_records_actor_target_reason_and_ip() {
+        let admin = Uuid::new_v4();
+        let ip = ClientIp(Some("203.0.113.7".to_string()));
+        let model = AuditEntry::new(admin, AuditAction::BlockPeer, "evil.example")
+            .with_reason(Some("spam".to_string()))
+            .into_active_model(&ip, now());
+
+        assert_eq!(model.actor_id, Set(Some(admin)));
+        assert_eq!(model.action, Set("block_peer".to_string()));
+        assert_eq!(model.target, Set("evil.example".to_string()));
+        assert_eq!(model.reason, Set(Some("spam".to_string())));
+        assert_eq!(model.ip_address, Set(Some("203.0.113.7".to_string())));
+    }
+
+    #[test]
+    fn test_ban_entry_targets_user_id() {
+        let admin = Uuid::new_v4();
+        let banned = Uuid::new_v4();
+        let model = AuditEntry::new(admin, AuditAction::BanUser, banned.to_string())
+            .with_reason(Some("harassment".to_string()))
+            .into_active_model(&ClientIp(None), now());
+
+        assert_eq!(model.action, Set("ban_user".to_string()));
+        assert_eq!(model.target, Set(banned.to_string()));
+        assert_eq!(model.reason, Set(Some("harassment".to_string())));
+        assert_eq!(model.ip_address, Set(None));
+    }
+
+    #[test]
+    fn test_action_names_match_query_values() {
+        for action in [
+            AuditAction::BlockPeer,
+            AuditAction::UnblockPeer,
+            AuditAction::BanUser,
+            AuditAction::UnbanUser,
+            AuditAction::ModerateTrack,
+            AuditAction::UpdateSetting,
+            AuditAction::BlockHash,
+            AuditAction::ApproveBlockedHash,
+            AuditAction::UnblockHash,
+            AuditAction::SetTrustedModerator,
+        ] {
+            let parsed: AuditAction =
+                serde_json::from_value(serde_json::json!(action.as_str())).unwrap();
+            assert_eq!(parsed, action);
+        }
+    }
+
+    // ── ClientIp ──
+
+    fn client_ip(real_ip: Option<&str>, peer: Option<[u8; 4]>, trusted: &[&str]) -> ClientIp {
+        let mut req = Request::builder();
+        if let Some(ip) = real_ip {
+            req = req.header("X-Real-IP", ip);
+        }
+        let mut req = req.body(Body::empty()).unwrap();
+        if let Some(peer) = peer {
+            req.extensions_mut()
+                .insert(axum::extract::ConnectInfo(SocketAddr::from((peer, 40000))));
+        }
+        let trusted: Vec<ProxyRange> = trusted
+            .iter()
+            .map(|r| ProxyRange::parse(r).unwrap())
+            .collect();
+        let (parts, _) = req.into_parts();
+        ClientIp::resolve(&parts, &trusted)
+    }
+
+    fn ip(s: &str) -> ClientIp {
+        ClientIp(Some(s.to_string()))
+    }
+
+    #[test]
+    fn test_client_ip_uses_header_from_trusted_proxy() {
+        let real_ip = Some(" 198.51.100.4 ");
+        let proxy = Some([10, 0, 0, 2]);
+        assert_eq!(client_ip(real_ip, proxy, &["10.0.0.2"]), ip("198.51.100.4"));
+        assert_eq!(
+            client_ip(real_ip, proxy, &["10.0.0.0/8"]),
+            ip("198.51.100.4")
+        );
+        // A trusted proxy that did not set it
+        assert_eq!(client_ip(None, proxy, &["10.0.0.2"]), ip("10.0.0.2"));
+    }
+
+    #[test]
+    fn test_client_ip_ignores_header_from_untrusted_peer() {
+        let real_ip = Some("198.51.100.4");
+        let peer = Some([10, 0, 0, 2]);
+        assert_eq!(client_ip(real_ip, peer, &[]), ip("10.0.0.2"));
+        assert_eq!(client_ip(real_ip, peer, &["10.0.0.3"]), ip("10.0.0.2"));
+        assert_eq!(client_ip(real_ip, peer, &["10.0.1.0/24"]), ip("10.0.0.2"));
+        // Without a connection address nothing can be trusted
+        assert_eq!(client_ip(real_ip, None, &["0.0.0.0/0"]), ClientIp(None));
+        assert_eq!(client_ip(None, None, &[]), ClientIp(None));
+    }
+
+    #[test]
+    fn test_client_ip_rejects_malformed_header() {
+        let peer = Some([10, 0, 0, 2]);
+        assert_eq!(
+            client_ip(Some("not-an-ip"), peer, &["10.0.0.2"]),
+            ip("10.0.0.2")
+        );
+    }
+
+    #[test]
+    fn test_proxy_range_parse_and_contains() {
+        let range = ProxyRange::parse("172.16.0.0/12").unwrap();
+        assert!(range.contains("172.31.255.1".parse().unwrap()));
+        assert!(!range.contains("172.32.0.1".parse().unwrap()));
+        // IPv4-mapped IPv6 peers match IPv4 ranges
+        assert!(range.contains("::ffff:172.17.0.1".parse().unwrap()));
+
+        let v6 = ProxyRange::parse("fd00::/8").unwrap();
+        assert!(v6.contains("fd12::1".parse().unwrap()));
+        assert!(!v6.contains("10.0.0.1".parse().unwrap()));
+
+        assert!(ProxyRange::parse("0.0.0.0/0")
+            .unwrap()
+            .contains("203.0.113.9".parse().unwrap()));
+        assert!(ProxyRange::parse("10.0.0.0/33").is_none());
+        assert!(ProxyRange::parse("proxy.local").is_none());
+    }
+
+    // ── list_audit_log ──
+
+    #[tokio::test]
+    async fn test_list_rejects_unknown_action_filter() {
+        let app = Router::new()
+            .route("/audit-log", get(list_audit_log))
+            .with_state(test_state().await);
+        let req = Request::builder()
+            .uri("/audit-log?action=delete_everything")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_list_rejects_malformed_date_filter() {
+        let app = Router::new()
+            .route("/audit-log", get(list_audit_log))
+            .with_state(test_state().await);
+        let req = Request::builder()
+            .uri("/audit-log?from=yesterday")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_params_parse_filters() {
+        let actor = Uuid::new_v4();
+        let params: AuditLogParams = serde_json::from_value(serde_json::json!({
+            "actor": actor,
+            "action": "unban_user",
+            "from": "2026-10-01T00:00:00Z",
+            "to": "2026-10-16T00:00:00+02:00",
+        }))
+        .unwrap();
+        assert_eq!(params.actor, Some(actor));
+        assert_eq!(params.action, Some(AuditAction::UnbanUser));
+        assert!(params.from.unwrap() < params.to.unwrap());
+    }
+}
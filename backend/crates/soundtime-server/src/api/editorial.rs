@@ -65,6 +65,7 @@ pub async fn list_editorial_playlists(
             vec![]
         } else {
             track::Entity::find()
+                .filter(track::Column::IsHidden.eq(false))
                 .filter(track::Column::Id.is_in(track_ids.clone()))
                 .all(&state.db)
                 .await
@@ -306,6 +307,7 @@ async fn generate_editorial_inner(state: &AppState) -> Result<usize, String> {
         .unwrap_or(500);
 
     let all_tracks = track::Entity::find()
+        .filter(track::Column::IsHidden.eq(false))
         .order_by(Expr::cust("RANDOM()"), Order::Asc)
         .limit(max_tracks)
         .all(&state.db)
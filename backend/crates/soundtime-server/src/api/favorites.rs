@@ -47,6 +47,7 @@ pub async fn list_favorites(
     let track_ids: Vec<Uuid> = favs.iter().map(|f| f.track_id).collect();
     let tracks_map: std::collections::HashMap<Uuid, track::Model> = if !track_ids.is_empty() {
         track::Entity::find()
+            .filter(track::Column::IsHidden.eq(false))
             .filter(track::Column::Id.is_in(track_ids.clone()))
             .all(&state.db)
             .await
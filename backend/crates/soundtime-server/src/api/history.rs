@@ -100,6 +100,7 @@ pub async fn list_history(
     let track_ids: Vec<Uuid> = entries.iter().map(|e| e.track_id).collect();
     let tracks_map: HashMap<Uuid, track::Model> = if !track_ids.is_empty() {
         track::Entity::find()
+            .filter(track::Column::IsHidden.eq(false))
             .filter(track::Column::Id.is_in(track_ids))
             .all(&state.db)
             .await
@@ -149,6 +150,8 @@ pub async fn list_history(
 ///
 /// Also dispatches plugin events and Last.fm scrobbles on a best-effort,
 /// fire-and-forget basis (failures are logged but do not fail the request).
+/// A play of a P2P track is likewise reported to the instance it was
+/// replicated from, which adds it to its own play count.
 ///
 /// On every 10th listen, recomputes the user's taste vector (a weighted
 /// average of track embeddings) used by the similarity radio seed.
@@ -192,6 +195,21 @@ pub async fn log_listen(
         }
     }
 
+    // Report plays of replicated tracks to their origin instance
+    // (best-effort, fire-and-forget)
+    if let Some(node) = state
+        .p2p
+        .as_ref()
+        .and_then(|any| any.clone().downcast::<soundtime_p2p::P2pNode>().ok())
+    {
+        let track_id = body.track_id;
+        tokio::spawn(async move {
+            if let Err(e) = node.report_play(track_id).await {
+                tracing::debug!(error = %e, %track_id, "failed to report play to origin peer");
+            }
+        });
+    }
+
     // Update Redis trending scores (best-effort, fire-and-forget)
     #[cfg(feature = "redis")]
     {
@@ -312,6 +330,7 @@ pub async fn list_recent_history(
     // PERF: Batch-fetch all tracks in a single query using `IS IN`.
     let track_ids: Vec<Uuid> = entries.iter().map(|e| e.track_id).collect();
     let tracks_map: HashMap<Uuid, track::Model> = track::Entity::find()
+        .filter(track::Column::IsHidden.eq(false))
         .filter(track::Column::Id.is_in(track_ids))
         .all(&state.db)
         .await
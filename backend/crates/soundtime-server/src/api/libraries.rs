@@ -101,6 +101,7 @@ pub async fn get_library(
         vec![]
     } else {
         track::Entity::find()
+            .filter(track::Column::IsHidden.eq(false))
             .filter(track::Column::Id.is_in(track_ids))
             .all(&state.db)
             .await
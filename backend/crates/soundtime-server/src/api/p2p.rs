@@ -1,22 +1,40 @@
 //! P2P API routes — status, peer management, track sharing, distributed search, library sync.
 
 use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
     extract::{Path, Query, State},
     http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    response::Response,
     Extension, Json,
 };
-use sea_orm::{ActiveModelTrait, EntityTrait, PaginatorTrait, Set};
+use sea_orm::{ActiveModelTrait, EntityTrait, PaginatorTrait, Set, TransactionTrait};
 use serde::{Deserialize, Serialize};
-use soundtime_db::entities::remote_track;
+use soundtime_db::entities::{blocked_hash, p2p_seed_peer, remote_track};
 use soundtime_db::AppState;
 use soundtime_p2p::{
     get_library_sync_overview, spawn_library_resync, LibrarySyncOverview, LibrarySyncTaskStatus,
-    SyncTaskHandle,
+    SyncControlError, SyncSchedule, SyncTaskHandle, SCHEDULE_SETTING_KEY,
 };
-use soundtime_p2p::{P2pMessage, P2pNode, PeerInfo};
-use std::sync::Arc;
+use soundtime_p2p::{
+    CatalogSyncProgress, CatalogSyncRecord, HealthSweepRun, HealthSweepTaskHandle,
+    HealthSweepTaskStatus, OutgoingSync, P2pMessage, P2pNode, P2pStats, PeerEviction, PeerFilter,
+    PeerInfo, PeerRejections, PeerStatus, PeerTrust, QueryHistoryEntry, RecoveryAttempt,
+    RejectedAnnouncement, ReplicationPolicy, TrackFetcher, TrackHealthManager,
+    SUPPORTED_CAPABILITIES,
+};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, LazyLock};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Semaphore;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tokio_stream::{Stream, StreamExt};
 use uuid::Uuid;
 
+use super::audit::{AuditAction, AuditEntry, ClientIp};
+use crate::auth::middleware::AuthUser;
+
 /// Helper: extract `Arc<P2pNode>` from type-erased AppState field.
 fn get_p2p_node(state: &AppState) -> Option<Arc<P2pNode>> {
     state
@@ -33,23 +51,69 @@ pub struct P2pStatus {
     pub node_id: Option<String>,
     pub relay_url: Option<String>,
     pub relay_connected: bool,
+    /// Relay configuration: `default` (n0), `custom` or `disabled`
+    pub relay_mode: String,
+    /// Self-hosted relays from `P2P_RELAY_URLS`
+    pub relay_urls: Vec<String>,
     pub direct_addresses: usize,
     pub peer_count: usize,
     pub online_peer_count: usize,
     pub dht_discovery_enabled: bool,
+    /// Message and blob traffic counters since the node started
+    pub stats: Option<P2pStats>,
+    /// Catalog syncs to peers currently in progress
+    pub outgoing_syncs: Vec<OutgoingSync>,
+    /// Optional features this node advertises to peers
+    pub capabilities: Vec<String>,
+    /// Expected false positive rate of the local search Bloom filter
+    pub bloom_fpr_estimate: Option<f64>,
+    /// BLAKE3 checksum of the local catalog's content hashes, compared with
+    /// peers to detect sync drift
+    pub catalog_checksum: Option<String>,
+    /// Blob cache usage, hit counts and evictions
+    pub blob_cache: Option<soundtime_p2p::BlobCacheStats>,
+}
+
+/// A known peer, as listed to admins.
+#[derive(Serialize)]
+pub struct AdminPeer {
+    #[serde(flatten)]
+    pub peer: PeerInfo,
+    /// Tallies of our recent catalog pushes to this peer, newest first
+    pub catalog_sync_history: Vec<CatalogSyncRecord>,
+    /// Messages waiting in our outbound queues to this peer
+    pub queue_depth: usize,
+    /// Label of the matching seed peer added by an admin, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed_label: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct AddPeerRequest {
-    /// iroh NodeId (public key) of the peer to add
+    /// iroh NodeId (public key) of the peer to add, optionally followed by
+    /// direct addresses: `<node_id>@<ip:port>[,<ip:port>...]`
     pub node_id: String,
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Serialize)]
 pub struct MessageResponse {
     pub message: String,
 }
 
+#[derive(Serialize)]
+pub struct CatalogSyncStarted {
+    pub task_id: Uuid,
+    pub peer_id: String,
+}
+
+#[derive(Serialize)]
+pub struct CatalogSyncCheckpoint {
+    pub peer_id: String,
+    /// Highest catalog page delivered by an unfinished push; `null` when
+    /// the next push starts from the first page
+    pub last_synced_page: Option<u64>,
+}
+
 // ── Handlers ────────────────────────────────────────────────────
 
 /// GET /api/p2p/status — P2P node status (public, gated by instance privacy)
@@ -60,36 +124,109 @@ pub async fn p2p_status(State(state): State<Arc<AppState>>) -> Json<P2pStatus> {
             node_id: None,
             relay_url: None,
             relay_connected: false,
+            relay_mode: "disabled".to_string(),
+            relay_urls: vec![],
             direct_addresses: 0,
             peer_count: 0,
             online_peer_count: 0,
             dht_discovery_enabled: false,
+            stats: None,
+            outgoing_syncs: vec![],
+            capabilities: vec![],
+            bloom_fpr_estimate: None,
+            catalog_checksum: None,
+            blob_cache: None,
         });
     };
 
     let relay_url = node.relay_url();
     let relay_connected = relay_url.is_some();
     let direct_addresses = node.direct_addresses_count();
+    let relay_plan = node.relay_plan();
+    let catalog_checksum = match node.compute_local_catalog_checksum().await {
+        Ok(c) => Some(c.checksum),
+        Err(e) => {
+            tracing::warn!("failed to compute catalog checksum: {e}");
+            None
+        }
+    };
 
     Json(P2pStatus {
         enabled: true,
         node_id: Some(node.node_id().to_string()),
         relay_url,
         relay_connected,
+        relay_mode: relay_plan.mode().to_string(),
+        relay_urls: relay_plan.urls(),
         direct_addresses,
         peer_count: node.registry().peer_count().await,
         online_peer_count: node.registry().online_peers().await.len(),
         dht_discovery_enabled: node.dht_discovery_enabled(),
+        stats: Some(node.stats()),
+        outgoing_syncs: node.outgoing_catalog_syncs(),
+        capabilities: SUPPORTED_CAPABILITIES
+            .iter()
+            .map(|c| c.to_string())
+            .collect(),
+        bloom_fpr_estimate: Some(node.search_index().fpr_estimate().await),
+        catalog_checksum,
+        blob_cache: Some(node.blob_cache().stats().await),
     })
 }
 
-/// GET /api/admin/p2p/peers — list known peers (admin only)
-pub async fn list_peers(State(state): State<Arc<AppState>>) -> Json<Vec<PeerInfo>> {
+/// GET /api/admin/p2p/events — live P2P node events as Server-Sent Events
+/// (admin only, also served at `/api/p2p/events`)
+///
+/// Each event is named after its `type` and carries the JSON-encoded
+/// `TimestampedEvent`. A subscriber too slow to keep up gets a `lagged` event with
+/// the number of events it missed.
+pub async fn p2p_events(
+    State(state): State<Arc<AppState>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, Json<MessageResponse>)>
+{
+    let Some(node) = get_p2p_node(&state) else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(MessageResponse {
+                message: "P2P node is not enabled".to_string(),
+            }),
+        ));
+    };
+
+    let stream = BroadcastStream::new(node.events().subscribe()).filter_map(|item| match item {
+        Ok(event) => Event::default()
+            .event(event.event.kind())
+            .json_data(&event)
+            .ok()
+            .map(Ok),
+        Err(BroadcastStreamRecvError::Lagged(missed)) => Some(Ok(Event::default()
+            .event("lagged")
+            .data(missed.to_string()))),
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// GET /api/admin/p2p/peers — list known peers with their recent catalog
+/// sync results (admin only)
+pub async fn list_peers(State(state): State<Arc<AppState>>) -> Json<Vec<AdminPeer>> {
     let Some(node) = get_p2p_node(&state) else {
         return Json(vec![]);
     };
 
-    let peers = node.registry().list_peers().await;
+    let queue_depths = node.peer_queue_depths().await;
+    let seed_labels = node.seed_labels().await;
+    let peers = node
+        .registry()
+        .list_peers()
+        .await
+        .into_iter()
+        .map(|peer| AdminPeer {
+            catalog_sync_history: node.catalog_sync_history(&peer.node_id),
+            queue_depth: queue_depths.get(&peer.node_id).copied().unwrap_or(0),
+            seed_label: seed_labels.get(&peer.node_id).cloned(),
+            peer,
+        })
+        .collect();
     Json(peers)
 }
 
@@ -107,25 +244,30 @@ pub async fn add_peer(
         ));
     };
 
-    let node_id: soundtime_p2p::NodeId = payload.node_id.parse().map_err(|_| {
+    let peer_addr = soundtime_p2p::parse_peer_addr(&payload.node_id).map_err(|e| {
         (
             StatusCode::BAD_REQUEST,
             Json(MessageResponse {
-                message: "Invalid node ID format".to_string(),
+                message: e.to_string(),
             }),
         )
     })?;
+    let node_id = peer_addr.id;
+    let peer_id = node_id.to_string();
 
-    let peer_addr = soundtime_p2p::NodeAddr::new(node_id);
     match node.ping_peer(peer_addr).await {
         Ok(P2pMessage::Pong {
             node_id: peer_nid,
             track_count,
             version,
+            capabilities,
         }) => {
             node.registry()
                 .upsert_peer_versioned(&peer_nid, None, track_count, version)
                 .await;
+            node.registry()
+                .set_capabilities(&peer_nid, capabilities)
+                .await;
             // Trigger peer exchange in background to discover wider network
             let p2p_clone = Arc::clone(&node);
             tokio::spawn(async move {
@@ -133,25 +275,24 @@ pub async fn add_peer(
             });
             Ok(Json(MessageResponse {
                 message: format!(
-                    "peer {} added and responded to ping ({} tracks)",
-                    payload.node_id, track_count
+                    "peer {peer_id} added and responded to ping ({track_count} tracks)"
                 ),
             }))
         }
         Ok(_) => {
             // Got a response but not a Pong — register anyway
-            node.registry().upsert_peer(&payload.node_id, None, 0).await;
+            node.registry().upsert_peer(&peer_id, None, 0).await;
             Ok(Json(MessageResponse {
-                message: format!("peer {} added (unexpected response type)", payload.node_id),
+                message: format!("peer {peer_id} added (unexpected response type)"),
             }))
         }
         Err(e) => {
             // Register as offline peer
-            node.registry().upsert_peer(&payload.node_id, None, 0).await;
-            node.registry().mark_offline(&payload.node_id).await;
-            tracing::warn!(peer = %payload.node_id, "ping failed: {e}");
+            node.registry().upsert_peer(&peer_id, None, 0).await;
+            node.registry().mark_offline(&peer_id).await;
+            tracing::warn!(peer = %peer_id, "ping failed: {e}");
             Ok(Json(MessageResponse {
-                message: format!("peer {} added but ping failed: {e}", payload.node_id),
+                message: format!("peer {peer_id} added but ping failed: {e}"),
             }))
         }
     }
@@ -197,226 +338,1063 @@ pub async fn remove_peer(
     Path(peer_node_id): Path<String>,
 ) -> Json<MessageResponse> {
     if let Some(node) = get_p2p_node(&state) {
-        node.registry().remove_peer(&peer_node_id).await;
+        node.remove_peer(&peer_node_id).await;
     }
     Json(MessageResponse {
         message: format!("peer {peer_node_id} removed"),
     })
 }
 
-// ── Network graph types ─────────────────────────────────────────
+/// POST /api/admin/p2p/peers/{node_id}/sync — push our full catalog to a peer
+/// in the background (admin only)
+pub async fn sync_catalog_to_peer(
+    State(state): State<Arc<AppState>>,
+    Path(peer_node_id): Path<String>,
+) -> Result<(StatusCode, Json<CatalogSyncStarted>), (StatusCode, Json<MessageResponse>)> {
+    let Some(node) = get_p2p_node(&state) else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(MessageResponse {
+                message: "P2P node is not enabled".to_string(),
+            }),
+        ));
+    };
 
-#[derive(Serialize)]
-pub struct NetworkGraphNode {
-    pub id: String,
-    /// "self", "peer", "relay"
-    pub node_type: String,
-    pub label: String,
-    pub online: bool,
-    /// Number of tracks on this node (for peers: from Pong data; for self: from DB)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub track_count: Option<u64>,
-    /// Software version (for peers: from Pong data)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub version: Option<String>,
-}
+    let node_id: soundtime_p2p::NodeId = peer_node_id.parse().map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(MessageResponse {
+                message: "Invalid node ID format".to_string(),
+            }),
+        )
+    })?;
 
-#[derive(Serialize)]
-pub struct NetworkGraphLink {
-    pub source: String,
-    pub target: String,
-    /// "relay", "direct", "peer"
-    pub link_type: String,
+    let Some(task_id) = node.spawn_catalog_sync(node_id) else {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(MessageResponse {
+                message: format!("A catalog sync to peer {peer_node_id} is already in progress"),
+            }),
+        ));
+    };
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(CatalogSyncStarted {
+            task_id,
+            peer_id: peer_node_id,
+        }),
+    ))
 }
 
+/// Announcements refused by the replication policy.
 #[derive(Serialize)]
-pub struct NetworkGraph {
-    pub nodes: Vec<NetworkGraphNode>,
-    pub links: Vec<NetworkGraphLink>,
+pub struct RejectedAnnouncements {
+    /// Policy currently in force
+    pub policy: ReplicationPolicy,
+    /// Rejections since the node started, per peer, highest first
+    pub per_peer: Vec<PeerRejections>,
+    /// Most recent rejections, newest first
+    pub recent: Vec<RejectedAnnouncement>,
 }
 
-/// GET /api/p2p/network-graph — P2P network topology for D3 visualization
-pub async fn network_graph(State(state): State<Arc<AppState>>) -> Json<NetworkGraph> {
+/// GET /api/admin/p2p/rejected — track announcements refused by the
+/// replication policy (admin only)
+pub async fn rejected_announcements(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<RejectedAnnouncements>, (StatusCode, Json<MessageResponse>)> {
     let Some(node) = get_p2p_node(&state) else {
-        return Json(NetworkGraph {
-            nodes: vec![],
-            links: vec![],
-        });
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(MessageResponse {
+                message: "P2P node is not enabled".to_string(),
+            }),
+        ));
     };
 
-    let mut nodes = Vec::new();
-    let mut links = Vec::new();
+    Ok(Json(RejectedAnnouncements {
+        policy: node.replication_policy(),
+        per_peer: node.rejections_per_peer(),
+        recent: node.rejected_announcements(),
+    }))
+}
 
-    let node_id = node.node_id().to_string();
-    let short_id = if node_id.len() > 8 {
-        &node_id[..8]
-    } else {
-        &node_id
-    };
+/// GET /api/admin/p2p/evicted-peers — the last 100 peers removed after too
+/// many failed pings in a row, newest first (admin only)
+pub async fn evicted_peers(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<PeerEviction>>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    Ok(Json(node.registry().recent_evictions()))
+}
 
-    // Self node — get local track count
-    let self_track_count = {
-        use soundtime_db::entities::track;
-        track::Entity::find().count(&state.db).await.unwrap_or(0)
-    };
+const QUERY_HISTORY_DEFAULT_LIMIT: usize = 50;
+const QUERY_HISTORY_MAX_LIMIT: usize = 500;
 
-    // Self node (always present)
-    nodes.push(NetworkGraphNode {
-        id: node_id.clone(),
-        node_type: "self".to_string(),
-        label: format!("Me ({short_id}…)"),
-        online: true,
-        track_count: Some(self_track_count),
-        version: Some(soundtime_p2p::build_version().to_string()),
-    });
+#[derive(Deserialize)]
+pub struct QueryHistoryQuery {
+    pub limit: Option<usize>,
+}
 
-    // Relay node
-    if let Some(relay_url) = node.relay_url() {
-        let relay_id = format!("relay:{relay_url}");
-        let relay_host = relay_url
-            .trim_start_matches("https://")
-            .trim_start_matches("http://")
-            .trim_end_matches('/')
-            .to_string();
-        nodes.push(NetworkGraphNode {
-            id: relay_id.clone(),
-            node_type: "relay".to_string(),
-            label: relay_host,
-            online: true,
-            track_count: None,
-            version: None,
-        });
-        links.push(NetworkGraphLink {
-            source: node_id.clone(),
-            target: relay_id,
-            link_type: "relay".to_string(),
-        });
-    }
+/// GET /api/admin/p2p/search/query-history — the most queried search terms
+/// and the peers that returned results for them (admin only)
+pub async fn search_query_history(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<QueryHistoryQuery>,
+) -> Result<Json<Vec<QueryHistoryEntry>>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let limit = params
+        .limit
+        .unwrap_or(QUERY_HISTORY_DEFAULT_LIMIT)
+        .clamp(1, QUERY_HISTORY_MAX_LIMIT);
+    Ok(Json(node.search_index().query_history(limit).await))
+}
 
-    // Peers from registry
-    let peers = node.registry().list_peers().await;
-    for peer in &peers {
-        let short_peer_id = if peer.node_id.len() > 8 {
-            &peer.node_id[..8]
-        } else {
-            &peer.node_id
-        };
-        let label = peer
-            .name
-            .as_deref()
-            .map(|n| format!("{n} ({short_peer_id}…)"))
-            .unwrap_or_else(|| format!("Peer ({short_peer_id}…)"));
+#[derive(Deserialize)]
+pub struct BlockHashRequest {
+    /// BLAKE3 content hash of the track blob
+    pub hash: String,
+    #[serde(default)]
+    pub reason: String,
+}
 
-        nodes.push(NetworkGraphNode {
-            id: peer.node_id.clone(),
-            node_type: "peer".to_string(),
-            label,
-            online: peer.is_online,
-            track_count: Some(peer.track_count),
-            version: peer.version.clone(),
-        });
-        links.push(NetworkGraphLink {
-            source: node_id.clone(),
-            target: peer.node_id.clone(),
-            link_type: "peer".to_string(),
-        });
-    }
+#[derive(Serialize)]
+pub struct BlockHashResponse {
+    pub hash: String,
+    /// Online peers the block was sent to
+    pub peers_notified: usize,
+}
 
-    Json(NetworkGraph { nodes, links })
+#[derive(Deserialize)]
+pub struct TrustedModeratorRequest {
+    pub trusted: bool,
 }
 
-// ── Distributed search ──────────────────────────────────────────
+/// Longest peer label accepted, matching `p2p_peers.label`.
+const MAX_PEER_LABEL_LEN: usize = 255;
 
 #[derive(Deserialize)]
-pub struct NetworkSearchQuery {
-    pub q: String,
-    pub limit: Option<u32>,
+pub struct UpdatePeerRequest {
+    /// Nickname shown in the admin peer list; empty clears it
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Free-text notes; empty clears them
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// `trusted`, `normal` (default) or `quarantined`
+    #[serde(default)]
+    pub trust: PeerTrust,
+}
+
+#[derive(Deserialize)]
+pub struct GrantAccessRequest {
+    /// BLAKE3 content hash of the private track
+    pub hash: String,
 }
 
 #[derive(Serialize)]
-pub struct NetworkSearchResponse {
-    pub results: Vec<soundtime_p2p::SearchResultItem>,
-    pub total: usize,
+pub struct GrantAccessResponse {
+    pub hash: String,
+    pub node_id: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    /// Whether the grant reached the peer; if not, it can be granted again
+    /// once the peer is online
+    pub delivered: bool,
 }
 
-/// GET /api/p2p/search?q=...&limit=... — distributed search across the P2P network.
-/// Queries peers whose Bloom filter indicates they might have matching content.
-pub async fn network_search(
+fn p2p_disabled() -> (StatusCode, Json<MessageResponse>) {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(MessageResponse {
+            message: "P2P node is not enabled".to_string(),
+        }),
+    )
+}
+
+fn moderation_error(e: soundtime_p2p::P2pError) -> (StatusCode, Json<MessageResponse>) {
+    tracing::error!("moderation update failed: {e}");
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(MessageResponse {
+            message: "Database error".to_string(),
+        }),
+    )
+}
+
+/// POST /api/admin/p2p/block-hash — stop serving a track blob and ask every
+/// online peer to do the same (admin only)
+pub async fn block_hash(
     State(state): State<Arc<AppState>>,
-    Query(params): Query<NetworkSearchQuery>,
-) -> Result<Json<NetworkSearchResponse>, (StatusCode, Json<MessageResponse>)> {
-    let Some(node) = get_p2p_node(&state) else {
-        return Err((
-            StatusCode::SERVICE_UNAVAILABLE,
+    Extension(user): Extension<AuthUser>,
+    ip: ClientIp,
+    Json(payload): Json<BlockHashRequest>,
+) -> Result<Json<BlockHashResponse>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let hash: soundtime_p2p::BlobHash = payload.hash.trim().parse().map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
             Json(MessageResponse {
-                message: "P2P node is not enabled".to_string(),
+                message: "Invalid content hash".to_string(),
             }),
-        ));
-    };
-
-    let query = params.q.trim();
-    if query.is_empty() {
-        return Ok(Json(NetworkSearchResponse {
-            results: vec![],
-            total: 0,
-        }));
-    }
+        )
+    })?;
 
-    let limit = params.limit.unwrap_or(20).min(100);
-    let results = node.distributed_search(query, limit).await;
-    let total = results.len();
+    let reason = soundtime_p2p::moderation::clean_reason(&payload.reason);
+    let entry = AuditEntry::new(user.0.sub, AuditAction::BlockHash, hash.to_string())
+        .with_reason(Some(reason));
+    let txn = begin_audited(&state, entry, &ip).await?;
+    let peers_notified = node
+        .block_hash(txn, hash, &payload.reason)
+        .await
+        .map_err(moderation_error)?;
 
-    Ok(Json(NetworkSearchResponse { results, total }))
+    Ok(Json(BlockHashResponse {
+        hash: hash.to_string(),
+        peers_notified,
+    }))
 }
 
-// ── Library Sync ────────────────────────────────────────────────
+/// Open a transaction holding `entry`, for a node method to apply its change
+/// in and commit.
+async fn begin_audited(
+    state: &AppState,
+    entry: AuditEntry,
+    ip: &ClientIp,
+) -> Result<sea_orm::DatabaseTransaction, (StatusCode, Json<MessageResponse>)> {
+    let db_error = |e: sea_orm::DbErr| moderation_error(e.into());
+    let txn = state.db.begin().await.map_err(db_error)?;
+    entry.record(&txn, ip).await.map_err(db_error)?;
+    Ok(txn)
+}
 
-/// GET /api/admin/p2p/library-sync — library sync overview for all peers (admin only)
-pub async fn library_sync_overview(
+/// GET /api/admin/p2p/blocked-hashes — active blocks and blocks from
+/// untrusted peers awaiting review (admin only)
+pub async fn list_blocked_hashes(
     State(state): State<Arc<AppState>>,
-) -> Json<LibrarySyncOverview> {
-    let Some(node) = get_p2p_node(&state) else {
-        return Json(LibrarySyncOverview {
-            local_track_count: 0,
-            total_peers: 0,
-            synced_peers: 0,
-            partial_peers: 0,
-            not_synced_peers: 0,
-            peers: vec![],
-        });
-    };
-
-    let overview = get_library_sync_overview(&node, &state.db).await;
-    Json(overview)
+) -> Result<Json<Vec<blocked_hash::Model>>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let blocks = node.list_blocked_hashes().await.map_err(moderation_error)?;
+    Ok(Json(blocks))
 }
 
-/// POST /api/admin/p2p/library-sync/:node_id — force a full re-sync with a peer (admin only)
-pub async fn trigger_library_resync(
+/// POST /api/admin/p2p/blocked-hashes/{hash}/approve — apply a block
+/// received from a peer that is not a trusted moderator (admin only)
+pub async fn approve_blocked_hash(
     State(state): State<Arc<AppState>>,
-    Extension(tracker): Extension<SyncTaskHandle>,
-    Path(peer_node_id): Path<String>,
+    Extension(user): Extension<AuthUser>,
+    ip: ClientIp,
+    Path(hash): Path<String>,
 ) -> Result<Json<MessageResponse>, (StatusCode, Json<MessageResponse>)> {
-    let Some(node) = get_p2p_node(&state) else {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let txn = begin_audited(
+        &state,
+        AuditEntry::new(user.0.sub, AuditAction::ApproveBlockedHash, hash.clone()),
+        &ip,
+    )
+    .await?;
+    if !node
+        .approve_blocked_hash(txn, &hash)
+        .await
+        .map_err(moderation_error)?
+    {
         return Err((
-            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::NOT_FOUND,
             Json(MessageResponse {
-                message: "P2P node is not enabled".to_string(),
+                message: format!("No block for hash {hash}"),
             }),
         ));
-    };
+    }
+    Ok(Json(MessageResponse {
+        message: format!("Block on {hash} applied"),
+    }))
+}
 
-    // Check if a sync is already running
+/// DELETE /api/admin/p2p/blocked-hashes/{hash} — lift a block or dismiss a
+/// pending one (admin only)
+pub async fn unblock_hash(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    ip: ClientIp,
+    Path(hash): Path<String>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let txn = begin_audited(
+        &state,
+        AuditEntry::new(user.0.sub, AuditAction::UnblockHash, hash.clone()),
+        &ip,
+    )
+    .await?;
+    if !node
+        .unblock_hash(txn, &hash)
+        .await
+        .map_err(moderation_error)?
     {
-        let status = tracker.lock().await;
-        if let LibrarySyncTaskStatus::Running { .. } = &*status {
-            return Err((
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(MessageResponse {
+                message: format!("No block for hash {hash}"),
+            }),
+        ));
+    }
+    Ok(Json(MessageResponse {
+        message: format!("Block on {hash} removed"),
+    }))
+}
+
+/// PUT /api/admin/p2p/peers/{node_id}/trusted-moderator — apply this peer's
+/// blocks without review, or stop doing so (admin only)
+pub async fn set_trusted_moderator(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    ip: ClientIp,
+    Path(peer_node_id): Path<String>,
+    Json(payload): Json<TrustedModeratorRequest>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let change = if payload.trusted {
+        "trusted"
+    } else {
+        "untrusted"
+    };
+    let txn = begin_audited(
+        &state,
+        AuditEntry::new(
+            user.0.sub,
+            AuditAction::SetTrustedModerator,
+            peer_node_id.clone(),
+        )
+        .with_reason(Some(change.to_string())),
+        &ip,
+    )
+    .await?;
+    if !node
+        .set_trusted_moderator(txn, &peer_node_id, payload.trusted)
+        .await
+        .map_err(moderation_error)?
+    {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(MessageResponse {
+                message: format!("Peer {peer_node_id} not found"),
+            }),
+        ));
+    }
+    let message = if payload.trusted {
+        format!("Peer {peer_node_id} is now a trusted moderator")
+    } else {
+        format!("Peer {peer_node_id} is no longer a trusted moderator")
+    };
+    Ok(Json(MessageResponse { message }))
+}
+
+/// PUT /api/admin/p2p/peers/{node_id} — set a known peer's label, notes and
+/// trust tier (admin only)
+pub async fn update_peer(
+    State(state): State<Arc<AppState>>,
+    Path(peer_node_id): Path<String>,
+    Json(payload): Json<UpdatePeerRequest>,
+) -> Result<Json<PeerInfo>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let trimmed = |v: Option<String>| v.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let label = trimmed(payload.label);
+    if label
+        .as_ref()
+        .is_some_and(|l| l.chars().count() > MAX_PEER_LABEL_LEN)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(MessageResponse {
+                message: format!("Label must be at most {MAX_PEER_LABEL_LEN} characters"),
+            }),
+        ));
+    }
+    let peer = node
+        .set_peer_details(&peer_node_id, label, trimmed(payload.notes), payload.trust)
+        .await
+        .map_err(|e| {
+            tracing::error!("peer details update failed: {e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(MessageResponse {
+                    message: "Database error".to_string(),
+                }),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(MessageResponse {
+                    message: format!("Peer {peer_node_id} not found"),
+                }),
+            )
+        })?;
+    Ok(Json(peer))
+}
+
+/// POST /api/admin/p2p/peers/{node_id}/grant-access — let this peer fetch
+/// a private track for the next 24 hours (admin only)
+pub async fn grant_track_access(
+    State(state): State<Arc<AppState>>,
+    Path(peer_node_id): Path<String>,
+    Json(payload): Json<GrantAccessRequest>,
+) -> Result<Json<GrantAccessResponse>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let hash: soundtime_p2p::BlobHash = payload.hash.trim().parse().map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(MessageResponse {
+                message: "Invalid content hash".to_string(),
+            }),
+        )
+    })?;
+    let (_, delivered) =
+        node.grant_track_access(&peer_node_id, hash)
+            .await
+            .map_err(|e| match e {
+                soundtime_p2p::P2pError::InvalidPeerAddr(_) => (
+                    StatusCode::BAD_REQUEST,
+                    Json(MessageResponse {
+                        message: format!("Invalid node ID: {peer_node_id}"),
+                    }),
+                ),
+                soundtime_p2p::P2pError::TrackNotFound(_) => (
+                    StatusCode::NOT_FOUND,
+                    Json(MessageResponse {
+                        message: "No local track with this hash".to_string(),
+                    }),
+                ),
+                other => {
+                    tracing::error!("track access grant failed: {other}");
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(MessageResponse {
+                            message: "Database error".to_string(),
+                        }),
+                    )
+                }
+            })?;
+
+    Ok(Json(GrantAccessResponse {
+        hash: hash.to_string(),
+        node_id: peer_node_id,
+        expires_at: chrono::Utc::now()
+            + chrono::Duration::seconds(soundtime_p2p::GRANT_MAX_AGE_SECS),
+        delivered,
+    }))
+}
+
+fn no_peer_filter(peer_node_id: &str) -> (StatusCode, Json<MessageResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(MessageResponse {
+            message: format!("No replication filter for peer {peer_node_id}"),
+        }),
+    )
+}
+
+/// GET /api/admin/p2p/peers/{node_id}/filters — what is replicated from
+/// this peer (admin only)
+pub async fn get_peer_filter(
+    State(state): State<Arc<AppState>>,
+    Path(peer_node_id): Path<String>,
+) -> Result<Json<PeerFilter>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    node.peer_filter(&peer_node_id)
+        .map(Json)
+        .ok_or_else(|| no_peer_filter(&peer_node_id))
+}
+
+/// PUT /api/admin/p2p/peers/{node_id}/filters — restrict replication from
+/// this peer by genre, artist and track count, or to metadata only (admin only)
+pub async fn put_peer_filter(
+    State(state): State<Arc<AppState>>,
+    Path(peer_node_id): Path<String>,
+    Json(filter): Json<PeerFilter>,
+) -> Result<Json<PeerFilter>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let filter = node
+        .set_peer_filter(&peer_node_id, filter)
+        .await
+        .map_err(|e| {
+            tracing::error!("failed to save peer filter: {e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(MessageResponse {
+                    message: "Database error".to_string(),
+                }),
+            )
+        })?;
+    Ok(Json(filter))
+}
+
+/// DELETE /api/admin/p2p/peers/{node_id}/filters — replicate everything
+/// from this peer again (admin only)
+pub async fn delete_peer_filter(
+    State(state): State<Arc<AppState>>,
+    Path(peer_node_id): Path<String>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let removed = node.remove_peer_filter(&peer_node_id).await.map_err(|e| {
+        tracing::error!("failed to delete peer filter: {e}");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(MessageResponse {
+                message: "Database error".to_string(),
+            }),
+        )
+    })?;
+    if !removed {
+        return Err(no_peer_filter(&peer_node_id));
+    }
+    Ok(Json(MessageResponse {
+        message: format!("Replication filter for peer {peer_node_id} removed"),
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct AddSeedRequest {
+    /// iroh NodeId (public key) of the seed peer
+    pub node_id: String,
+    /// Free-form name shown in the admin peer list
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+fn seed_db_error(e: soundtime_p2p::P2pError) -> (StatusCode, Json<MessageResponse>) {
+    tracing::error!("seed peer update failed: {e}");
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(MessageResponse {
+            message: "Database error".to_string(),
+        }),
+    )
+}
+
+/// GET /api/admin/p2p/seeds — seed peers added by admins (admin only).
+/// Seeds from `P2P_SEED_PEERS` are not listed.
+pub async fn list_seeds(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<p2p_seed_peer::Model>>, (StatusCode, Json<MessageResponse>)> {
+    let seeds = soundtime_p2p::seed_peers::list(&state.db)
+        .await
+        .map_err(seed_db_error)?;
+    Ok(Json(seeds))
+}
+
+/// POST /api/admin/p2p/seeds — add a seed peer, connected to right away
+/// and on every refresh cycle while offline (admin only)
+pub async fn add_seed(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Json(payload): Json<AddSeedRequest>,
+) -> Result<(StatusCode, Json<p2p_seed_peer::Model>), (StatusCode, Json<MessageResponse>)> {
+    let node_id = payload
+        .node_id
+        .trim()
+        .parse::<soundtime_p2p::EndpointId>()
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(MessageResponse {
+                    message: format!("invalid endpoint id '{}': {e}", payload.node_id.trim()),
+                }),
+            )
+        })?
+        .to_string();
+    let label = payload
+        .label
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty());
+
+    let seed = soundtime_p2p::seed_peers::add(&state.db, &node_id, label, Some(user.0.sub))
+        .await
+        .map_err(seed_db_error)?
+        .ok_or_else(|| {
+            (
                 StatusCode::CONFLICT,
                 Json(MessageResponse {
-                    message: "A library sync is already in progress".to_string(),
+                    message: format!("Seed peer {node_id} already exists"),
+                }),
+            )
+        })?;
+
+    if let Some(node) = get_p2p_node(&state) {
+        node.connect_seed_in_background(node_id);
+    }
+    Ok((StatusCode::CREATED, Json(seed)))
+}
+
+/// DELETE /api/admin/p2p/seeds/{node_id} — stop using a peer as a seed.
+/// The peer stays known until it goes away on its own (admin only).
+pub async fn remove_seed(
+    State(state): State<Arc<AppState>>,
+    Path(node_id): Path<String>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<MessageResponse>)> {
+    let removed = soundtime_p2p::seed_peers::remove(&state.db, &node_id)
+        .await
+        .map_err(seed_db_error)?;
+    if !removed {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(MessageResponse {
+                message: format!("No seed peer {node_id}"),
+            }),
+        ));
+    }
+    Ok(Json(MessageResponse {
+        message: format!("Seed peer {node_id} removed"),
+    }))
+}
+
+/// GET /api/admin/p2p/peers/{node_id}/sync-status — progress of the latest
+/// catalog push to a peer (admin only)
+pub async fn peer_sync_status(
+    State(state): State<Arc<AppState>>,
+    Path(peer_node_id): Path<String>,
+) -> Result<Json<CatalogSyncProgress>, (StatusCode, Json<MessageResponse>)> {
+    let Some(node) = get_p2p_node(&state) else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(MessageResponse {
+                message: "P2P node is not enabled".to_string(),
+            }),
+        ));
+    };
+
+    node.catalog_sync_progress(&peer_node_id)
+        .map(Json)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(MessageResponse {
+                    message: format!("No catalog sync recorded for peer {peer_node_id}"),
                 }),
-            ));
+            )
+        })
+}
+
+/// GET /api/admin/p2p/peers/{node_id}/sync-checkpoint — page an interrupted
+/// catalog push to a peer will resume after (admin only)
+pub async fn peer_sync_checkpoint(
+    State(state): State<Arc<AppState>>,
+    Path(peer_node_id): Path<String>,
+) -> Result<Json<CatalogSyncCheckpoint>, (StatusCode, Json<MessageResponse>)> {
+    let Some(node) = get_p2p_node(&state) else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(MessageResponse {
+                message: "P2P node is not enabled".to_string(),
+            }),
+        ));
+    };
+
+    Ok(Json(CatalogSyncCheckpoint {
+        last_synced_page: node.catalog_sync_checkpoint(&peer_node_id),
+        peer_id: peer_node_id,
+    }))
+}
+
+// ── Network graph types ─────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct NetworkGraphNode {
+    pub id: String,
+    /// "self", "peer", "relay"
+    pub node_type: String,
+    pub label: String,
+    pub online: bool,
+    /// Number of tracks on this node (for peers: from Pong data; for self: from DB)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track_count: Option<u64>,
+    /// Software version (for peers: from Pong data)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Median ping round-trip time (peers only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p50_rtt_ms: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct NetworkGraphLink {
+    pub source: String,
+    pub target: String,
+    /// "relay", "direct", "peer"
+    pub link_type: String,
+}
+
+#[derive(Serialize)]
+pub struct NetworkGraph {
+    pub nodes: Vec<NetworkGraphNode>,
+    pub links: Vec<NetworkGraphLink>,
+}
+
+/// GET /api/p2p/network-graph — P2P network topology for D3 visualization
+pub async fn network_graph(State(state): State<Arc<AppState>>) -> Json<NetworkGraph> {
+    let Some(node) = get_p2p_node(&state) else {
+        return Json(NetworkGraph {
+            nodes: vec![],
+            links: vec![],
+        });
+    };
+
+    let mut nodes = Vec::new();
+    let mut links = Vec::new();
+
+    let node_id = node.node_id().to_string();
+    let short_id = if node_id.len() > 8 {
+        &node_id[..8]
+    } else {
+        &node_id
+    };
+
+    // Self node — get local track count
+    let self_track_count = {
+        use soundtime_db::entities::track;
+        track::Entity::find().count(&state.db).await.unwrap_or(0)
+    };
+
+    // Self node (always present)
+    nodes.push(NetworkGraphNode {
+        id: node_id.clone(),
+        node_type: "self".to_string(),
+        label: format!("Me ({short_id}…)"),
+        online: true,
+        track_count: Some(self_track_count),
+        version: Some(soundtime_p2p::build_version().to_string()),
+        p50_rtt_ms: None,
+    });
+
+    // Relay node
+    if let Some(relay_url) = node.relay_url() {
+        let relay_id = format!("relay:{relay_url}");
+        let relay_host = relay_url
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_end_matches('/')
+            .to_string();
+        nodes.push(NetworkGraphNode {
+            id: relay_id.clone(),
+            node_type: "relay".to_string(),
+            label: relay_host,
+            online: true,
+            track_count: None,
+            version: None,
+            p50_rtt_ms: None,
+        });
+        links.push(NetworkGraphLink {
+            source: node_id.clone(),
+            target: relay_id,
+            link_type: "relay".to_string(),
+        });
+    }
+
+    // Peers from registry
+    let peers = node.registry().list_peers().await;
+    for peer in &peers {
+        let short_peer_id = if peer.node_id.len() > 8 {
+            &peer.node_id[..8]
+        } else {
+            &peer.node_id
+        };
+        let label = peer
+            .name
+            .as_deref()
+            .map(|n| format!("{n} ({short_peer_id}…)"))
+            .unwrap_or_else(|| format!("Peer ({short_peer_id}…)"));
+
+        nodes.push(NetworkGraphNode {
+            id: peer.node_id.clone(),
+            node_type: "peer".to_string(),
+            label,
+            online: peer.is_online,
+            track_count: Some(peer.track_count),
+            version: peer.version.clone(),
+            p50_rtt_ms: peer.p50_rtt_ms,
+        });
+        links.push(NetworkGraphLink {
+            source: node_id.clone(),
+            target: peer.node_id.clone(),
+            link_type: "peer".to_string(),
+        });
+    }
+
+    Json(NetworkGraph { nodes, links })
+}
+
+/// Most `GET /api/p2p/ws` connections open at once.
+const MAX_PEER_STATUS_SOCKETS: usize = 10;
+
+static PEER_STATUS_SOCKETS: LazyLock<Arc<Semaphore>> =
+    LazyLock::new(|| Arc::new(Semaphore::new(MAX_PEER_STATUS_SOCKETS)));
+
+/// Most `GET /api/p2p/ws` connections one user may hold open at once, so a
+/// single account cannot take every slot.
+const MAX_PEER_STATUS_SOCKETS_PER_USER: usize = 2;
+
+static PEER_STATUS_SOCKETS_BY_USER: LazyLock<std::sync::Mutex<HashMap<Uuid, usize>>> =
+    LazyLock::new(Default::default);
+
+/// One of a user's [`MAX_PEER_STATUS_SOCKETS_PER_USER`] peer status
+/// sockets, given back on drop.
+struct UserSocketSlot(Uuid);
+
+impl UserSocketSlot {
+    fn acquire(user_id: Uuid) -> Option<Self> {
+        let mut open = PEER_STATUS_SOCKETS_BY_USER
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let count = open.entry(user_id).or_insert(0);
+        if *count >= MAX_PEER_STATUS_SOCKETS_PER_USER {
+            return None;
+        }
+        *count += 1;
+        Some(Self(user_id))
+    }
+}
+
+impl Drop for UserSocketSlot {
+    fn drop(&mut self) {
+        let mut open = PEER_STATUS_SOCKETS_BY_USER
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(count) = open.get_mut(&self.0) {
+            *count -= 1;
+            if *count == 0 {
+                open.remove(&self.0);
+            }
         }
     }
+}
+
+/// A known peer in the `snapshot` message of `GET /api/p2p/ws`.
+#[derive(Debug, Serialize)]
+pub struct PeerStatusSnapshot {
+    #[serde(flatten)]
+    pub status: PeerStatus,
+    pub online: bool,
+}
+
+impl From<&PeerInfo> for PeerStatusSnapshot {
+    fn from(info: &PeerInfo) -> Self {
+        Self {
+            status: PeerStatus::from(info),
+            online: info.is_online,
+        }
+    }
+}
+
+/// `{"type":"snapshot","peers":[...]}`, sent when a socket opens.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum PeerStatusMessage {
+    Snapshot { peers: Vec<PeerStatusSnapshot> },
+}
+
+/// GET /api/p2p/ws — live peer status over a WebSocket (admin only)
+///
+/// Sends every known peer in a `snapshot` message, then a `peer_online` or
+/// `peer_offline` message each time a peer's status changes. A client too
+/// slow to keep up gets a fresh snapshot instead of the updates it missed.
+/// At most [`MAX_PEER_STATUS_SOCKETS`] clients are served at once, and at
+/// most [`MAX_PEER_STATUS_SOCKETS_PER_USER`] per user.
+pub async fn peer_status_ws(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let slot = UserSocketSlot::acquire(user.0.sub).ok_or_else(|| {
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(MessageResponse {
+                message: "Too many peer status connections for this user".to_string(),
+            }),
+        )
+    })?;
+    let permit = PEER_STATUS_SOCKETS
+        .clone()
+        .try_acquire_owned()
+        .map_err(|_| {
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(MessageResponse {
+                    message: "Too many peer status connections".to_string(),
+                }),
+            )
+        })?;
+    Ok(ws.on_upgrade(move |socket| async move {
+        stream_peer_status(socket, node).await;
+        drop(permit);
+        drop(slot);
+    }))
+}
+
+/// Internal: serve one `GET /api/p2p/ws` client until it disconnects.
+async fn stream_peer_status(mut socket: WebSocket, node: Arc<P2pNode>) {
+    // Subscribe first so no update falls between the snapshot and the stream
+    let mut updates = node.registry().subscribe_status();
+    if send_peer_snapshot(&mut socket, &node).await.is_err() {
+        return;
+    }
+    loop {
+        tokio::select! {
+            update = updates.recv() => {
+                let sent = match update {
+                    Ok(update) => send_json(&mut socket, &update).await,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::debug!(missed, "peer status socket lagged, resending snapshot");
+                        send_peer_snapshot(&mut socket, &node).await
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if sent.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+async fn send_peer_snapshot(socket: &mut WebSocket, node: &P2pNode) -> Result<(), axum::Error> {
+    let peers = node.registry().list_peers().await;
+    let snapshot = PeerStatusMessage::Snapshot {
+        peers: peers.iter().map(PeerStatusSnapshot::from).collect(),
+    };
+    send_json(socket, &snapshot).await
+}
+
+async fn send_json<T: Serialize>(socket: &mut WebSocket, value: &T) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(value).map_err(axum::Error::new)?;
+    socket.send(Message::Text(text.into())).await
+}
+
+// ── Distributed search ──────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct NetworkSearchQuery {
+    pub q: String,
+    pub limit: Option<u32>,
+    /// Continuation cursor from a previous response
+    pub cursor: Option<String>,
+    /// 1-based page number, counted in pages of `limit` results
+    pub page: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct NetworkSearchResponse {
+    pub results: Vec<soundtime_p2p::SearchResultItem>,
+    pub total: usize,
+    /// Whether a later page may hold more results
+    pub has_more: bool,
+    /// Pass as `cursor` to fetch the next page
+    pub cursor: Option<String>,
+    /// Matches each answering peer reported, keyed by node ID
+    pub peer_totals: HashMap<String, u64>,
+}
+
+/// GET /api/p2p/search?q=...&limit=...&cursor=...&page=... — distributed search across the P2P network.
+/// Queries peers whose Bloom filter indicates they might have matching content.
+/// Follow `cursor` to page through the results of the same search.
+pub async fn network_search(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<NetworkSearchQuery>,
+) -> Result<Json<NetworkSearchResponse>, (StatusCode, Json<MessageResponse>)> {
+    let Some(node) = get_p2p_node(&state) else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(MessageResponse {
+                message: "P2P node is not enabled".to_string(),
+            }),
+        ));
+    };
+
+    let query = params.q.trim();
+    if query.is_empty() {
+        return Ok(Json(NetworkSearchResponse {
+            results: vec![],
+            total: 0,
+            has_more: false,
+            cursor: None,
+            peer_totals: HashMap::new(),
+        }));
+    }
+
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
+    let offset = params
+        .page
+        .map(|page| (page.max(1) as usize - 1) * limit as usize);
+    let page = node
+        .search_page(query, params.cursor.as_deref(), offset, limit)
+        .await
+        .map_err(|e| match e {
+            soundtime_p2p::P2pError::InvalidCursor(reason) => (
+                StatusCode::BAD_REQUEST,
+                Json(MessageResponse {
+                    message: format!("invalid cursor: {reason}"),
+                }),
+            ),
+            other => {
+                tracing::error!("network search failed: {other}");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(MessageResponse {
+                        message: "Search failed".to_string(),
+                    }),
+                )
+            }
+        })?;
+
+    Ok(Json(NetworkSearchResponse {
+        total: page.results.len(),
+        results: page.results,
+        has_more: page.has_more,
+        cursor: page.next_cursor,
+        peer_totals: page.peer_totals,
+    }))
+}
+
+// ── Library Sync ────────────────────────────────────────────────
+
+/// GET /api/admin/p2p/library-sync — library sync overview for all peers (admin only)
+pub async fn library_sync_overview(
+    State(state): State<Arc<AppState>>,
+    Extension(tracker): Extension<SyncTaskHandle>,
+) -> Json<LibrarySyncOverview> {
+    let Some(node) = get_p2p_node(&state) else {
+        return Json(LibrarySyncOverview {
+            local_track_count: 0,
+            total_peers: 0,
+            synced_peers: 0,
+            partial_peers: 0,
+            not_synced_peers: 0,
+            peers: vec![],
+            task: tracker.lock().await.clone(),
+            schedule: tracker.scheduled_syncs(),
+        });
+    };
+
+    let overview = get_library_sync_overview(&node, &state.db, &tracker).await;
+    Json(overview)
+}
+
+/// POST /api/admin/p2p/library-sync/:node_id — force a full re-sync with a peer (admin only)
+pub async fn trigger_library_resync(
+    State(state): State<Arc<AppState>>,
+    Extension(tracker): Extension<SyncTaskHandle>,
+    Path(peer_node_id): Path<String>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<MessageResponse>)> {
+    let Some(node) = get_p2p_node(&state) else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(MessageResponse {
+                message: "P2P node is not enabled".to_string(),
+            }),
+        ));
+    };
 
     // Validate peer exists
     if node.registry().get_peer(&peer_node_id).await.is_none() {
@@ -428,13 +1406,65 @@ pub async fn trigger_library_resync(
         ));
     }
 
-    spawn_library_resync(node, peer_node_id.clone(), tracker);
+    // Refused if a sync is already running or paused
+    let Some(task_id) = spawn_library_resync(node, peer_node_id.clone(), tracker).await else {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(MessageResponse {
+                message: "A library sync is already in progress".to_string(),
+            }),
+        ));
+    };
 
     Ok(Json(MessageResponse {
-        message: format!("Library re-sync started with peer {peer_node_id}"),
+        message: format!("Library re-sync {task_id} started with peer {peer_node_id}"),
     }))
 }
 
+/// Map a refused library sync control request to its HTTP error.
+fn sync_control_error(e: SyncControlError) -> (StatusCode, Json<MessageResponse>) {
+    let status = match e {
+        SyncControlError::NotFound => StatusCode::NOT_FOUND,
+        SyncControlError::Cancelled => StatusCode::CONFLICT,
+    };
+    (
+        status,
+        Json(MessageResponse {
+            message: e.to_string(),
+        }),
+    )
+}
+
+/// POST /api/admin/p2p/sync/{task_id}/pause — pause a library re-sync
+/// before its next catalog page (admin only)
+pub async fn pause_library_sync(
+    Extension(tracker): Extension<SyncTaskHandle>,
+    Path(task_id): Path<Uuid>,
+) -> Result<Json<LibrarySyncTaskStatus>, (StatusCode, Json<MessageResponse>)> {
+    tracker.pause(task_id).await.map_err(sync_control_error)?;
+    Ok(Json(tracker.lock().await.clone()))
+}
+
+/// POST /api/admin/p2p/sync/{task_id}/resume — resume a paused library
+/// re-sync (admin only)
+pub async fn resume_library_sync(
+    Extension(tracker): Extension<SyncTaskHandle>,
+    Path(task_id): Path<Uuid>,
+) -> Result<Json<LibrarySyncTaskStatus>, (StatusCode, Json<MessageResponse>)> {
+    tracker.resume(task_id).await.map_err(sync_control_error)?;
+    Ok(Json(tracker.lock().await.clone()))
+}
+
+/// POST /api/admin/p2p/sync/{task_id}/cancel — stop a running or paused
+/// library re-sync (admin only)
+pub async fn cancel_library_sync(
+    Extension(tracker): Extension<SyncTaskHandle>,
+    Path(task_id): Path<Uuid>,
+) -> Result<Json<LibrarySyncTaskStatus>, (StatusCode, Json<MessageResponse>)> {
+    tracker.cancel(task_id).await.map_err(sync_control_error)?;
+    Ok(Json(tracker.lock().await.clone()))
+}
+
 /// GET /api/admin/p2p/library-sync/task-status — poll the background sync task status
 pub async fn library_sync_task_status(
     Extension(tracker): Extension<SyncTaskHandle>,
@@ -443,15 +1473,79 @@ pub async fn library_sync_task_status(
     Json(status.clone())
 }
 
-/// POST /api/admin/p2p/library-sync/task-dismiss — reset task status to idle
+/// POST /api/admin/p2p/library-sync/task-dismiss — reset task status to
+/// idle. A running or paused task is left alone: cancel it first.
 pub async fn library_sync_task_dismiss(
     Extension(tracker): Extension<SyncTaskHandle>,
-) -> Json<MessageResponse> {
+) -> Result<Json<MessageResponse>, (StatusCode, Json<MessageResponse>)> {
     let mut status = tracker.lock().await;
+    if status.is_active() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(MessageResponse {
+                message: "Task is still running; cancel it first".to_string(),
+            }),
+        ));
+    }
     *status = LibrarySyncTaskStatus::Idle;
-    Json(MessageResponse {
+    Ok(Json(MessageResponse {
         message: "Task status reset".to_string(),
-    })
+    }))
+}
+
+/// GET /api/admin/p2p/sync/schedule — cron expressions of scheduled
+/// library re-syncs, global and per peer (admin only)
+pub async fn get_sync_schedule(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<SyncSchedule>, (StatusCode, Json<MessageResponse>)> {
+    let schedule = SyncSchedule::load(&state.db).await.map_err(|e| {
+        tracing::error!("Failed to read library sync schedule: {e}");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(MessageResponse {
+                message: "Failed to read library sync schedule".to_string(),
+            }),
+        )
+    })?;
+    Ok(Json(schedule))
+}
+
+/// PUT /api/admin/p2p/sync/schedule — replace the library re-sync schedule.
+/// Empty expressions are dropped; ones that do not parse are refused with
+/// 400. The scheduler picks the change up on its next tick (admin only)
+pub async fn update_sync_schedule(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    ip: ClientIp,
+    Json(body): Json<SyncSchedule>,
+) -> Result<Json<SyncSchedule>, (StatusCode, Json<MessageResponse>)> {
+    let schedule = body.normalized().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(MessageResponse {
+                message: e.to_string(),
+            }),
+        )
+    })?;
+
+    let db_error = |e: &dyn std::fmt::Display| {
+        tracing::error!("Failed to save library sync schedule: {e}");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(MessageResponse {
+                message: "Failed to save library sync schedule".to_string(),
+            }),
+        )
+    };
+    let txn = state.db.begin().await.map_err(|e| db_error(&e))?;
+    schedule.save(&txn).await.map_err(|e| db_error(&e))?;
+    AuditEntry::new(user.0.sub, AuditAction::UpdateSetting, SCHEDULE_SETTING_KEY)
+        .record(&txn, &ip)
+        .await
+        .map_err(|e| db_error(&e))?;
+    txn.commit().await.map_err(|e| db_error(&e))?;
+
+    Ok(Json(schedule))
 }
 
 // ── Remote track dereference / rereference ───────────────────────
@@ -527,6 +1621,7 @@ pub async fn rereference_remote_track(
 
     let mut update: remote_track::ActiveModel = track.into();
     update.is_available = Set(true);
+    update.dereferenced_at = Set(None);
     update.update(&state.db).await.map_err(|e| {
         tracing::error!("Failed to rereference remote track {id}: {e}");
         (
@@ -536,12 +1631,561 @@ pub async fn rereference_remote_track(
             }),
         )
     })?;
+    if let Err(e) = soundtime_p2p::track_cleanup::unhide_tracks(&state.db, &[id]).await {
+        tracing::warn!("Failed to unhide rereferenced track {id}: {e}");
+    }
 
     Ok(Json(MessageResponse {
         message: format!("Remote track {id} rereferenced (marked available)"),
     }))
 }
 
+#[derive(Deserialize, Default)]
+pub struct RepairTrackRequest {
+    /// Ask only this peer instead of the origin and the best alternative
+    pub peer_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TrackRepairResult {
+    pub content_hash: String,
+    pub origin_node: String,
+    pub success: bool,
+    pub status: &'static str,
+    pub failed_attempts: u32,
+    pub peer_used: Option<String>,
+    pub error: Option<String>,
+}
+
+/// POST /api/admin/p2p/tracks/{hash}/repair — fetch a remote track again
+/// right now instead of waiting for the next sweep, optionally from a given
+/// peer (admin only)
+pub async fn repair_remote_track(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    payload: Option<Json<RepairTrackRequest>>,
+) -> Result<Json<TrackRepairResult>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let hash: soundtime_p2p::BlobHash = hash.trim().parse().map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(MessageResponse {
+                message: "Invalid content hash".to_string(),
+            }),
+        )
+    })?;
+    let hash = hash.to_string();
+
+    let item = soundtime_p2p::find_remote_track(&state.db, &hash)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to query remote track: {e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(MessageResponse {
+                    message: "Database error".to_string(),
+                }),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(MessageResponse {
+                    message: format!("Remote track {hash} not found"),
+                }),
+            )
+        })?;
+
+    let Json(payload) = payload.unwrap_or_default();
+    let manager = Arc::clone(node.health_manager());
+    // TrackFetcher is implemented for Arc<P2pNode>
+    Ok(Json(
+        repair_track(
+            &state.db,
+            &manager,
+            &node,
+            &item,
+            payload.peer_id.as_deref(),
+        )
+        .await,
+    ))
+}
+
+/// Repair `item` with `fetcher` and save whether it is available.
+async fn repair_track<F: TrackFetcher>(
+    db: &sea_orm::DatabaseConnection,
+    manager: &TrackHealthManager,
+    fetcher: &F,
+    item: &soundtime_p2p::TrackCheckItem,
+    peer_id: Option<&str>,
+) -> TrackRepairResult {
+    let result = soundtime_p2p::repair_track_now(
+        manager,
+        fetcher,
+        &item.content_hash,
+        &item.origin_node,
+        peer_id,
+    )
+    .await;
+    if result.success || result.status == soundtime_p2p::HealthStatus::Dereferenced {
+        soundtime_p2p::persist_track_status(
+            db,
+            &item.content_hash,
+            &item.origin_node,
+            result.success,
+            !result.success,
+        )
+        .await;
+    }
+
+    let failed_attempts = manager
+        .get_record(&item.content_hash)
+        .await
+        .map_or(0, |r| r.failed_attempts);
+    TrackRepairResult {
+        status: result.status.as_str(),
+        content_hash: result.content_hash,
+        origin_node: item.origin_node.clone(),
+        success: result.success,
+        failed_attempts,
+        peer_used: result.peer_used,
+        error: result.error,
+    }
+}
+
+// ── Track health ─────────────────────────────────────────────────
+
+/// Default and maximum number of runs returned by the health history.
+const HEALTH_HISTORY_DEFAULT_LIMIT: u64 = 30;
+const HEALTH_HISTORY_MAX_LIMIT: u64 = 500;
+
+#[derive(Deserialize)]
+pub struct HealthHistoryQuery {
+    pub limit: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct HealthTrackEntry {
+    pub content_hash: String,
+    pub origin_node: String,
+    pub failed_attempts: u32,
+    pub last_attempt: Option<chrono::DateTime<chrono::Utc>>,
+    pub status: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct CurrentHealth {
+    pub counts: HashMap<String, usize>,
+    pub tracks: Vec<HealthTrackEntry>,
+}
+
+/// GET /api/admin/p2p/health/history — the last N health sweeps, newest
+/// first (admin only)
+pub async fn health_history(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HealthHistoryQuery>,
+) -> Result<Json<Vec<HealthSweepRun>>, (StatusCode, Json<MessageResponse>)> {
+    let limit = params
+        .limit
+        .unwrap_or(HEALTH_HISTORY_DEFAULT_LIMIT)
+        .clamp(1, HEALTH_HISTORY_MAX_LIMIT);
+    let runs = soundtime_p2p::health_history(&state.db, limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load health history: {e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(MessageResponse {
+                    message: "Database error".to_string(),
+                }),
+            )
+        })?;
+    Ok(Json(runs))
+}
+
+/// GET /api/admin/p2p/health/current — in-memory health state of the
+/// tracks checked so far (admin only)
+pub async fn current_health(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<CurrentHealth>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let manager = node.health_manager();
+    let tracks = manager
+        .snapshot()
+        .await
+        .into_iter()
+        .map(|record| HealthTrackEntry {
+            status: record.status.as_str(),
+            content_hash: record.content_hash,
+            origin_node: record.origin_node,
+            failed_attempts: record.failed_attempts,
+            last_attempt: record.last_attempt,
+        })
+        .collect();
+    Ok(Json(CurrentHealth {
+        counts: manager.status_counts().await,
+        tracks,
+    }))
+}
+
+/// Default and maximum number of tracks per health overview page.
+const HEALTH_TRACKS_DEFAULT_PER_PAGE: usize = 50;
+const HEALTH_TRACKS_MAX_PER_PAGE: usize = 200;
+
+#[derive(Deserialize)]
+pub struct HealthOverviewQuery {
+    /// 1-based
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct UnhealthyTrackEntry {
+    pub content_hash: String,
+    pub origin_node: String,
+    /// Absent if the remote track row is gone
+    pub title: Option<String>,
+    pub status: &'static str,
+    pub failed_attempts: u32,
+    pub last_attempt: Option<chrono::DateTime<chrono::Utc>>,
+    /// Playback will not retry the track before then
+    pub next_attempt_after: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// The health monitor settings in force.
+#[derive(Serialize)]
+pub struct HealthMonitorSettings {
+    /// e.g. `every 600s` or `cron '0 */30 * * * *'`
+    pub schedule: String,
+    pub max_retry_attempts: u32,
+    pub batch_size: usize,
+    pub max_concurrent_recoveries: usize,
+    /// Fraction of cached blobs re-hashed each sweep
+    pub integrity_sample_rate: f64,
+}
+
+impl From<&soundtime_p2p::HealthMonitorConfig> for HealthMonitorSettings {
+    fn from(config: &soundtime_p2p::HealthMonitorConfig) -> Self {
+        Self {
+            schedule: config.schedule.to_string(),
+            max_retry_attempts: config.max_retry_attempts,
+            batch_size: config.batch_size,
+            max_concurrent_recoveries: config.max_concurrent_recoveries,
+            integrity_sample_rate: config.integrity_sample_rate,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct HealthOverview {
+    pub config: HealthMonitorSettings,
+    pub counts: HashMap<String, usize>,
+    pub last_sweep: Option<HealthSweepRun>,
+    /// Retention cleanup of dereferenced tracks, from the instance settings
+    pub cleanup: soundtime_p2p::CleanupPolicy,
+    /// The last cleanup that hid or deleted tracks since startup
+    pub last_cleanup: Option<soundtime_p2p::CleanupReport>,
+    /// Degraded and dereferenced tracks, most recently attempted first
+    pub tracks: Vec<UnhealthyTrackEntry>,
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+}
+
+/// GET /api/admin/p2p/health — status counts, the last sweep and a page of
+/// degraded or dereferenced tracks (admin only)
+pub async fn health_overview(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HealthOverviewQuery>,
+) -> Result<Json<HealthOverview>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params
+        .per_page
+        .unwrap_or(HEALTH_TRACKS_DEFAULT_PER_PAGE)
+        .clamp(1, HEALTH_TRACKS_MAX_PER_PAGE);
+    let manager = node.health_manager();
+    let (total, records) = manager
+        .unhealthy_page((page - 1).saturating_mul(per_page), per_page)
+        .await;
+
+    let db_error = |e: soundtime_p2p::P2pError| {
+        tracing::error!("Failed to load health overview: {e}");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(MessageResponse {
+                message: "Database error".to_string(),
+            }),
+        )
+    };
+    let last_sweep = soundtime_p2p::health_history(&state.db, 1)
+        .await
+        .map_err(db_error)?
+        .pop();
+    let cleanup = soundtime_p2p::CleanupPolicy::load(&state.db)
+        .await
+        .map_err(db_error)?;
+    let mut titles = soundtime_p2p::remote_track_titles(&state.db, &records)
+        .await
+        .map_err(db_error)?;
+
+    let tracks = records
+        .into_iter()
+        .map(|record| UnhealthyTrackEntry {
+            title: titles.remove(&record.content_hash),
+            status: record.status.as_str(),
+            content_hash: record.content_hash,
+            origin_node: record.origin_node,
+            failed_attempts: record.failed_attempts,
+            last_attempt: record.last_attempt,
+            next_attempt_after: record.next_attempt_after,
+        })
+        .collect();
+    Ok(Json(HealthOverview {
+        config: manager.config().into(),
+        counts: manager.status_counts().await,
+        last_sweep,
+        cleanup,
+        last_cleanup: manager.last_cleanup().await,
+        tracks,
+        total,
+        page,
+        per_page,
+    }))
+}
+
+/// Start a sweep with `fetcher` unless one is already running.
+async fn start_sweep<F: TrackFetcher>(
+    manager: Arc<TrackHealthManager>,
+    fetcher: Arc<F>,
+    db: sea_orm::DatabaseConnection,
+    tracker: HealthSweepTaskHandle,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<MessageResponse>)> {
+    if soundtime_p2p::start_health_sweep(manager, fetcher, db, tracker)
+        .await
+        .is_none()
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(MessageResponse {
+                message: "A health sweep is already running".to_string(),
+            }),
+        ));
+    }
+    Ok(Json(MessageResponse {
+        message: "Health sweep started".to_string(),
+    }))
+}
+
+/// POST /api/admin/p2p/health/sweep — check every remote track now, in the
+/// background; poll `/health/sweep/status` for the result (admin only)
+pub async fn trigger_health_sweep(
+    State(state): State<Arc<AppState>>,
+    Extension(tracker): Extension<HealthSweepTaskHandle>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let manager = Arc::clone(node.health_manager());
+    // TrackFetcher is implemented for Arc<P2pNode>
+    start_sweep(manager, Arc::new(node), state.db.clone(), tracker).await
+}
+
+/// GET /api/admin/p2p/health/sweep/status — state of the last sweep started
+/// from the API (admin only)
+pub async fn health_sweep_status(
+    Extension(tracker): Extension<HealthSweepTaskHandle>,
+) -> Json<HealthSweepTaskStatus> {
+    let status = tracker.lock().await;
+    Json(status.clone())
+}
+
+/// Default and maximum number of attempts returned per recovery log page.
+const RECOVERY_LOG_DEFAULT_LIMIT: u64 = 100;
+const RECOVERY_LOG_MAX_LIMIT: u64 = 500;
+
+#[derive(Deserialize)]
+pub struct RecoveryLogQuery {
+    /// Only attempts for this content hash
+    pub hash: Option<String>,
+    pub limit: Option<u64>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct RecoveryLogPage {
+    pub attempts: Vec<RecoveryAttempt>,
+    /// Pass as `cursor` for the next page; absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Cursor for the page after `attempts`, if the page was full.
+fn recovery_log_cursor(attempts: &[RecoveryAttempt], limit: u64) -> Option<String> {
+    if (attempts.len() as u64) < limit {
+        return None;
+    }
+    attempts.last().map(|a| {
+        a.attempted_at
+            .to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+    })
+}
+
+/// GET /api/admin/p2p/health/recovery-log — recent auto-repair attempts,
+/// newest first (admin only)
+pub async fn recovery_log(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<RecoveryLogQuery>,
+) -> Result<Json<RecoveryLogPage>, (StatusCode, Json<MessageResponse>)> {
+    let limit = params
+        .limit
+        .unwrap_or(RECOVERY_LOG_DEFAULT_LIMIT)
+        .clamp(1, RECOVERY_LOG_MAX_LIMIT);
+    let hash = params
+        .hash
+        .as_deref()
+        .map(str::trim)
+        .filter(|h| !h.is_empty());
+    let attempts = soundtime_p2p::recovery_log(&state.db, hash, params.cursor, limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load recovery log: {e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(MessageResponse {
+                    message: "Database error".to_string(),
+                }),
+            )
+        })?;
+    Ok(Json(RecoveryLogPage {
+        next_cursor: recovery_log_cursor(&attempts, limit),
+        attempts,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct ReReferenceRequest {
+    /// Only re-reference tracks from this peer
+    pub peer_id: Option<String>,
+    /// Re-reference tracks from every peer; required without `peer_id`
+    #[serde(default)]
+    pub all: bool,
+}
+
+#[derive(Serialize)]
+pub struct ReReferenceResponse {
+    pub re_referenced: usize,
+}
+
+/// POST /api/admin/p2p/health/re-reference — mark unavailable P2P tracks
+/// available again, from one peer or all of them (admin only)
+pub async fn bulk_re_reference(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ReReferenceRequest>,
+) -> Result<Json<ReReferenceResponse>, (StatusCode, Json<MessageResponse>)> {
+    let peer_id = payload
+        .peer_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty());
+    if peer_id.is_none() && !payload.all {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(MessageResponse {
+                message: "Set peer_id, or all to re-reference tracks from every peer".to_string(),
+            }),
+        ));
+    }
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let re_referenced = soundtime_p2p::bulk_re_reference(node.health_manager(), &state.db, peer_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to re-reference remote tracks: {e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(MessageResponse {
+                    message: "Database error".to_string(),
+                }),
+            )
+        })?;
+    Ok(Json(ReReferenceResponse { re_referenced }))
+}
+
+#[derive(Deserialize)]
+pub struct BlobGcQuery {
+    /// Report what would be deleted without deleting it
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// POST /api/admin/p2p/gc — delete blobs nothing refers to any more and
+/// report the bytes reclaimed (admin only)
+pub async fn collect_blob_garbage(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<BlobGcQuery>,
+) -> Result<Json<soundtime_p2p::GcReport>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let report = node.collect_garbage(params.dry_run).await.map_err(|e| {
+        tracing::error!("Blob garbage collection failed: {e}");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(MessageResponse {
+                message: "Blob garbage collection failed".to_string(),
+            }),
+        )
+    })?;
+    Ok(Json(report))
+}
+
+/// GET /api/admin/p2p/cache/stats — blob cache size, hit rate and last
+/// warm-up (admin only)
+pub async fn blob_cache_stats(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<soundtime_p2p::BlobCacheStats>, (StatusCode, Json<MessageResponse>)> {
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    Ok(Json(node.blob_cache().stats().await))
+}
+
+#[derive(Deserialize)]
+pub struct SetBlobCacheLimitRequest {
+    /// New cache limit in bytes; must be greater than zero
+    pub max_bytes: u64,
+}
+
+/// PUT /api/admin/p2p/cache — change the blob cache limit (admin only).
+/// Saved to instance settings, so it also applies after a restart. A lower
+/// limit evicts least-recently-used blobs right away.
+pub async fn set_blob_cache_limit(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<SetBlobCacheLimitRequest>,
+) -> Result<Json<soundtime_p2p::BlobCacheStats>, (StatusCode, Json<MessageResponse>)> {
+    if body.max_bytes == 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(MessageResponse {
+                message: "max_bytes must be greater than zero".to_string(),
+            }),
+        ));
+    }
+    let node = get_p2p_node(&state).ok_or_else(p2p_disabled)?;
+    let evicted = node
+        .set_cache_max_bytes(body.max_bytes)
+        .await
+        .map_err(|e| {
+            tracing::error!("failed to save blob cache limit: {e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(MessageResponse {
+                    message: "Failed to save blob cache limit".to_string(),
+                }),
+            )
+        })?;
+    tracing::info!(
+        max_bytes = body.max_bytes,
+        evicted = evicted.len(),
+        "blob cache limit updated"
+    );
+    Ok(Json(node.blob_cache().stats().await))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -554,15 +2198,26 @@ mod tests {
             node_id: None,
             relay_url: None,
             relay_connected: false,
+            relay_mode: "disabled".to_string(),
+            relay_urls: vec![],
             direct_addresses: 0,
             peer_count: 0,
             online_peer_count: 0,
             dht_discovery_enabled: false,
+            stats: None,
+            outgoing_syncs: vec![],
+            capabilities: vec![],
+            bloom_fpr_estimate: None,
+            catalog_checksum: None,
+            blob_cache: None,
         };
         let val = serde_json::to_value(&status).unwrap();
         assert_eq!(val["enabled"], false);
         assert!(val["node_id"].is_null());
         assert_eq!(val["dht_discovery_enabled"], false);
+        assert_eq!(val["relay_mode"], "disabled");
+        assert!(val["stats"].is_null());
+        assert!(val["blob_cache"].is_null());
     }
 
     // 2. P2pStatus serialization (enabled)
@@ -573,16 +2228,56 @@ mod tests {
             node_id: Some("abc123".to_string()),
             relay_url: Some("https://relay.example.com".to_string()),
             relay_connected: true,
+            relay_mode: "custom".to_string(),
+            relay_urls: vec!["https://relay.example.com/".to_string()],
             direct_addresses: 2,
             peer_count: 5,
             online_peer_count: 3,
             dht_discovery_enabled: true,
+            stats: Some(P2pStats {
+                messages_sent: 4,
+                blob_bytes_uploaded: 1024,
+                ..Default::default()
+            }),
+            outgoing_syncs: vec![OutgoingSync {
+                peer_id: "peer1".to_string(),
+                rerun_requested: true,
+            }],
+            capabilities: vec!["signed-announcements".to_string()],
+            bloom_fpr_estimate: Some(0.004),
+            catalog_checksum: Some("af13".to_string()),
+            blob_cache: Some(soundtime_p2p::BlobCacheStats {
+                total_bytes: 512,
+                max_bytes: 1024,
+                entries: 2,
+                evictions: 1,
+                pinned: 1,
+                pinned_bytes: 256,
+                hits: 3,
+                misses: 1,
+                hit_rate: Some(0.75),
+                last_warm_up_at: None,
+                prewarmed_last_cycle: Some(4),
+            }),
         };
         let val = serde_json::to_value(&status).unwrap();
         assert_eq!(val["enabled"], true);
         assert_eq!(val["node_id"], "abc123");
         assert_eq!(val["peer_count"], 5);
         assert_eq!(val["dht_discovery_enabled"], true);
+        assert_eq!(val["relay_mode"], "custom");
+        assert_eq!(val["relay_urls"][0], "https://relay.example.com/");
+        assert_eq!(val["blob_cache"]["max_bytes"], 1024);
+        assert_eq!(val["blob_cache"]["evictions"], 1);
+        assert_eq!(val["blob_cache"]["pinned_bytes"], 256);
+        assert_eq!(val["blob_cache"]["prewarmed_last_cycle"], 4);
+        assert_eq!(val["stats"]["messages_sent"], 4);
+        assert_eq!(val["stats"]["blob_bytes_uploaded"], 1024);
+        assert_eq!(val["outgoing_syncs"][0]["peer_id"], "peer1");
+        assert_eq!(val["outgoing_syncs"][0]["rerun_requested"], true);
+        assert_eq!(val["capabilities"][0], "signed-announcements");
+        assert_eq!(val["bloom_fpr_estimate"], 0.004);
+        assert_eq!(val["catalog_checksum"], "af13");
     }
 
     // 3. AddPeerRequest deserialization
@@ -613,10 +2308,12 @@ mod tests {
             online: true,
             track_count: Some(42),
             version: Some("0.1.0".to_string()),
+            p50_rtt_ms: Some(80),
         };
         let val = serde_json::to_value(&node).unwrap();
         assert_eq!(val["node_type"], "peer");
         assert_eq!(val["track_count"], 42);
+        assert_eq!(val["p50_rtt_ms"], 80);
     }
 
     // 6. NetworkGraphNode with skip_serializing_if None
@@ -629,11 +2326,13 @@ mod tests {
             online: true,
             track_count: None,
             version: None,
+            p50_rtt_ms: None,
         };
         let val = serde_json::to_value(&node).unwrap();
         // track_count and version have skip_serializing_if = "Option::is_none"
         assert!(val.get("track_count").is_none());
         assert!(val.get("version").is_none());
+        assert!(val.get("p50_rtt_ms").is_none());
     }
 
     // 7. NetworkGraphLink serialization
@@ -679,42 +2378,1050 @@ mod tests {
         assert_eq!(query.limit, None);
     }
 
-    // 11. p2p_status returns disabled when no P2P node
+    // 11. p2p_status reports whether a node is running, and which
+    #[tokio::test]
+    async fn test_p2p_status() {
+        let disabled = crate::test_db::state(crate::test_db::connect().await);
+        let Json(status) = p2p_status(State(disabled)).await;
+        let val = serde_json::to_value(&status).unwrap();
+        assert_eq!(val["enabled"], false);
+        assert!(val["node_id"].is_null());
+        assert_eq!(val["dht_discovery_enabled"], false);
+        assert!(val["bloom_fpr_estimate"].is_null());
+        assert!(val["catalog_checksum"].is_null());
+
+        let (state, node, _dir) = crate::test_db::state_with_node().await;
+        let Json(status) = p2p_status(State(state)).await;
+        assert!(status.enabled);
+        assert_eq!(status.node_id, Some(node.node_id().to_string()));
+        assert_eq!(status.peer_count, 0);
+        assert!(status.bloom_fpr_estimate.is_some());
+        // The catalog checksum query is Postgres-only, so it stays unset here
+        assert!(status.blob_cache.is_some());
+    }
+
+    // 12. A catalog push needs a valid peer ID and runs in the background
+    #[tokio::test]
+    async fn test_sync_catalog_to_peer() {
+        let (state, node, _dir) = crate::test_db::state_with_node().await;
+
+        assert_eq!(
+            error_status(sync_catalog_to_peer(State(state.clone()), Path("abc".to_string())).await),
+            StatusCode::BAD_REQUEST
+        );
+
+        // Any valid node ID will do: the push itself fails in the background
+        let peer = node.node_id().to_string();
+        let (status, Json(started)) = sync_catalog_to_peer(State(state), Path(peer.clone()))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(started.peer_id, peer);
+    }
+
+    // 13. CatalogSyncStarted serialization
+    #[test]
+    fn test_serialize_catalog_sync_started() {
+        let task_id = Uuid::new_v4();
+        let started = CatalogSyncStarted {
+            task_id,
+            peer_id: "peer1".to_string(),
+        };
+        let val = serde_json::to_value(&started).unwrap();
+        assert_eq!(val["task_id"], task_id.to_string());
+        assert_eq!(val["peer_id"], "peer1");
+    }
+
+    // 14. CatalogSyncCheckpoint serialization
+    #[test]
+    fn test_serialize_catalog_sync_checkpoint() {
+        let checkpoint = CatalogSyncCheckpoint {
+            peer_id: "peer1".to_string(),
+            last_synced_page: Some(3),
+        };
+        let val = serde_json::to_value(&checkpoint).unwrap();
+        assert_eq!(val["peer_id"], "peer1");
+        assert_eq!(val["last_synced_page"], 3);
+
+        let none = CatalogSyncCheckpoint {
+            peer_id: "peer2".to_string(),
+            last_synced_page: None,
+        };
+        let val = serde_json::to_value(&none).unwrap();
+        assert!(val["last_synced_page"].is_null());
+    }
+
+    // 15. AdminPeer flattens PeerInfo next to its sync history
+    #[test]
+    fn test_serialize_admin_peer() {
+        let peer = AdminPeer {
+            peer: PeerInfo {
+                node_id: "peer1".to_string(),
+                name: None,
+                version: Some("0.1.42".to_string()),
+                track_count: 12,
+                last_seen: chrono::Utc::now(),
+                is_online: true,
+                protocol_version: Some(2),
+                rtt_ms: Some(40),
+                rtt_samples: Default::default(),
+                p50_rtt_ms: Some(35),
+                interactions: Default::default(),
+                avg_latency_ms: Some(41),
+                success_rate: Some(0.9),
+                last_catalog_sync_at: None,
+                capabilities: vec!["waveform-sync".to_string()],
+                departed_at: None,
+                consecutive_failures: 0,
+                label: None,
+                notes: None,
+                trust: PeerTrust::Normal,
+            },
+            catalog_sync_history: vec![CatalogSyncRecord::new(Uuid::new_v4(), "peer1", true)],
+            queue_depth: 3,
+            seed_label: Some("Main seed".to_string()),
+        };
+        let val = serde_json::to_value(&peer).unwrap();
+        assert_eq!(val["node_id"], "peer1");
+        assert_eq!(val["track_count"], 12);
+        assert_eq!(val["capabilities"][0], "waveform-sync");
+        assert_eq!(val["p50_rtt_ms"], 35);
+        assert_eq!(val["avg_latency_ms"], 41);
+        assert!((val["success_rate"].as_f64().unwrap() - 0.9).abs() < 1e-6);
+        assert!(val.get("interactions").is_none());
+        assert_eq!(val["queue_depth"], 3);
+        assert_eq!(val["seed_label"], "Main seed");
+        assert!(val.get("rtt_samples").is_none());
+        assert!(val.get("peer").is_none());
+        let history = val["catalog_sync_history"].as_array().unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0]["acknowledged"], true);
+        assert_eq!(history[0]["pages_sent"], 0);
+    }
+
+    // 16. p2p_events streams node events as Server-Sent Events
     #[tokio::test]
-    async fn test_p2p_status_disabled() {
+    async fn test_p2p_events_stream() {
         use axum::{body::Body, http::Request, routing::get, Router};
         use tower::ServiceExt;
 
-        let state = Arc::new(AppState {
-            db: sea_orm::DatabaseConnection::Disconnected,
-            jwt_secret: "test".to_string(),
-            domain: "localhost".to_string(),
-            storage: Arc::new(soundtime_audio::AudioStorage::new("/tmp/test")),
-            p2p: None,
-            plugins: None,
-            #[cfg(feature = "redis")]
-            redis: None,
-        });
-
+        let (state, _node, _dir) = crate::test_db::state_with_node().await;
         let app = Router::new()
-            .route("/p2p/status", get(p2p_status))
+            .route("/p2p/events", get(p2p_events))
             .with_state(state);
 
         let req = Request::builder()
             .method("GET")
-            .uri("/p2p/status")
+            .uri("/p2p/events")
             .body(Body::empty())
             .unwrap();
 
         let resp = app.oneshot(req).await.unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "text/event-stream");
+    }
+
+    // 17. rejected_announcements reports the policy in force and no rejections yet
+    #[tokio::test]
+    async fn test_rejected_announcements() {
+        let (state, node, _dir) = crate::test_db::state_with_node().await;
+
+        let Json(rejected) = rejected_announcements(State(state)).await.unwrap();
+        assert_eq!(rejected.policy, node.replication_policy());
+        assert!(rejected.per_peer.is_empty());
+        assert!(rejected.recent.is_empty());
+    }
+
+    // 18. block_hash stores the block and writes an audit entry
+    #[tokio::test]
+    async fn test_block_hash() {
+        use soundtime_db::entities::{admin_audit_log, user::UserRole};
+
+        let (state, node, _dir) = crate::test_db::state_with_node().await;
+        let admin = crate::test_db::insert_user(&state.db, "admin", UserRole::Admin).await;
+        let block = |hash: &str| {
+            block_hash(
+                State(state.clone()),
+                Extension(crate::test_db::auth_user(&admin)),
+                ClientIp(None),
+                Json(BlockHashRequest {
+                    hash: hash.to_string(),
+                    reason: "copyright".to_string(),
+                }),
+            )
+        };
+
+        assert_eq!(
+            error_status(block(&malformed_hash()).await),
+            StatusCode::BAD_REQUEST
+        );
+
+        let hash = soundtime_p2p::BlobHash::new(b"blocked track").to_string();
+        let Json(resp) = block(&hash).await.unwrap();
+        assert_eq!(resp.hash, hash);
+        assert_eq!(resp.peers_notified, 0);
 
-        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        let row = blocked_hash::Entity::find_by_id(hash.clone())
+            .one(&state.db)
+            .await
+            .unwrap()
+            .expect("block is stored");
+        assert_eq!(row.reason, "copyright");
+        assert!(node.is_hash_blocked(&hash).await);
+
+        let entries = admin_audit_log::Entity::find()
+            .all(&state.db)
             .await
             .unwrap();
-        let val: serde_json::Value = serde_json::from_slice(&body).unwrap();
-        assert_eq!(val["enabled"], false);
-        assert!(val["node_id"].is_null());
-        assert_eq!(val["dht_discovery_enabled"], false);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].actor_id, Some(admin.id));
+        assert_eq!(entries[0].action, "block_hash");
+        assert_eq!(entries[0].target, hash);
+    }
+
+    // 19. A peer filter is saved normalized and read back
+    #[tokio::test]
+    async fn test_put_peer_filter() {
+        let (state, _node, _dir) = crate::test_db::state_with_node().await;
+
+        assert_eq!(
+            error_status(get_peer_filter(State(state.clone()), Path("peer1".to_string())).await),
+            StatusCode::NOT_FOUND
+        );
+
+        let Json(saved) = put_peer_filter(
+            State(state.clone()),
+            Path("peer1".to_string()),
+            Json(PeerFilter {
+                allowed_genres: vec![" Jazz ".to_string()],
+                max_tracks: Some(100),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(saved.allowed_genres, vec!["jazz".to_string()]);
+        assert_eq!(saved.max_tracks, Some(100));
+
+        let Json(read) = get_peer_filter(State(state.clone()), Path("peer1".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(read, saved);
+        let stored = soundtime_db::entities::p2p_peer_filter::Entity::find()
+            .count(&state.db)
+            .await
+            .unwrap();
+        assert_eq!(stored, 1);
+    }
+
+    // 20. evicted_peers lists no peers on a fresh node
+    #[tokio::test]
+    async fn test_evicted_peers() {
+        let (state, _node, _dir) = crate::test_db::state_with_node().await;
+
+        let Json(evicted) = evicted_peers(State(state)).await.unwrap();
+        assert!(evicted.is_empty());
+    }
+
+    // 21. network search query accepts a cursor and a page number
+    #[test]
+    fn test_network_search_query_paging_params() {
+        let uri: axum::http::Uri = "/p2p/search?q=jazz&limit=10&cursor=abc&page=3"
+            .parse()
+            .unwrap();
+        let Query(params) = Query::<NetworkSearchQuery>::try_from_uri(&uri).unwrap();
+        assert_eq!(params.q, "jazz");
+        assert_eq!(params.limit, Some(10));
+        assert_eq!(params.cursor.as_deref(), Some("abc"));
+        assert_eq!(params.page, Some(3));
+
+        let uri: axum::http::Uri = "/p2p/search?q=jazz".parse().unwrap();
+        let Query(params) = Query::<NetworkSearchQuery>::try_from_uri(&uri).unwrap();
+        assert!(params.cursor.is_none());
+        assert!(params.page.is_none());
+    }
+
+    // 22. grant-access checks the peer ID and that the track is ours
+    #[tokio::test]
+    async fn test_grant_track_access() {
+        let (state, node, _dir) = crate::test_db::state_with_node().await;
+        let hash = soundtime_p2p::BlobHash::new(b"private track").to_string();
+        let grant = |peer: String, hash: &str| {
+            grant_track_access(
+                State(state.clone()),
+                Path(peer),
+                Json(GrantAccessRequest {
+                    hash: hash.to_string(),
+                }),
+            )
+        };
+
+        assert_eq!(
+            error_status(grant(node.node_id().to_string(), &malformed_hash()).await),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            error_status(grant("abc".to_string(), &hash).await),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            error_status(grant(node.node_id().to_string(), &hash).await),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    // 23. health/current lists the tracks the health monitor has seen
+    #[tokio::test]
+    async fn test_current_health() {
+        let (state, node, _dir) = crate::test_db::state_with_node().await;
+
+        let Json(health) = current_health(State(state.clone())).await.unwrap();
+        assert!(health.tracks.is_empty());
+
+        node.health_manager().record_failure("hash1", "peer1").await;
+        let Json(health) = current_health(State(state)).await.unwrap();
+        assert_eq!(health.counts.get("degraded"), Some(&1));
+        assert_eq!(health.tracks.len(), 1);
+        assert_eq!(health.tracks[0].content_hash, "hash1");
+        assert_eq!(health.tracks[0].origin_node, "peer1");
+        assert_eq!(health.tracks[0].status, "degraded");
+        assert_eq!(health.tracks[0].failed_attempts, 1);
+    }
+
+    /// Insert an unavailable P2P copy of `hash` from `origin`.
+    async fn insert_unavailable_remote_track(
+        db: &sea_orm::DatabaseConnection,
+        origin: &str,
+        hash: &str,
+    ) -> remote_track::Model {
+        remote_track::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            local_track_id: Set(None),
+            musicbrainz_id: Set(None),
+            title: Set(format!("Track {hash}")),
+            artist_name: Set("Artist".to_string()),
+            album_title: Set(None),
+            instance_domain: Set(format!("p2p://{origin}")),
+            remote_uri: Set(format!("p2p://{origin}/{hash}")),
+            remote_stream_url: Set(format!("/api/stream/p2p/{hash}")),
+            bitrate: Set(None),
+            sample_rate: Set(None),
+            format: Set(None),
+            is_available: Set(false),
+            last_checked_at: Set(None),
+            dereferenced_at: Set(None),
+            signature: Set(None),
+            created_at: Set(chrono::Utc::now().into()),
+        }
+        .insert(db)
+        .await
+        .unwrap()
+    }
+
+    // 24. re-reference needs a peer or an explicit "all", and marks the
+    // matching tracks available again
+    #[tokio::test]
+    async fn test_bulk_re_reference() {
+        use axum::{body::Body, http::Request, routing::post, Router};
+        use tower::ServiceExt;
+
+        let (state, _node, _dir) = crate::test_db::state_with_node().await;
+        let from_peer1 = insert_unavailable_remote_track(&state.db, "peer1", "hash1").await;
+        let from_peer2 = insert_unavailable_remote_track(&state.db, "peer2", "hash2").await;
+
+        let app = Router::new()
+            .route("/p2p/health/re-reference", post(bulk_re_reference))
+            .with_state(state.clone());
+
+        let request = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri("/p2p/health/re-reference")
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let re_referenced = |resp: Response| async move {
+            assert_eq!(resp.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let val: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            val["re_referenced"].as_u64().unwrap()
+        };
+        let is_available = |id: Uuid| {
+            let db = state.db.clone();
+            async move {
+                remote_track::Entity::find_by_id(id)
+                    .one(&db)
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .is_available
+            }
+        };
+
+        let resp = app.clone().oneshot(request("{}")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = app
+            .clone()
+            .oneshot(request(r#"{"peer_id":"  ","all":false}"#))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = app
+            .clone()
+            .oneshot(request(r#"{"peer_id":"peer1"}"#))
+            .await
+            .unwrap();
+        assert_eq!(re_referenced(resp).await, 1);
+        assert!(is_available(from_peer1.id).await);
+        assert!(!is_available(from_peer2.id).await);
+
+        let resp = app.oneshot(request(r#"{"all":true}"#)).await.unwrap();
+        assert_eq!(re_referenced(resp).await, 1);
+        assert!(is_available(from_peer2.id).await);
+    }
+
+    // 25. GcReport serialization, dry run
+    #[test]
+    fn test_serialize_gc_report() {
+        let report = soundtime_p2p::GcReport {
+            dry_run: true,
+            tags_scanned: 10,
+            tags_deleted: 3,
+            blobs_deleted: 2,
+            bytes_reclaimed: 4096,
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["dry_run"], true);
+        assert_eq!(json["blobs_deleted"], 2);
+        assert_eq!(json["bytes_reclaimed"], 4096);
+
+        let uri: axum::http::Uri = "/p2p/gc?dry_run=true".parse().unwrap();
+        let Query(params) = Query::<BlobGcQuery>::try_from_uri(&uri).unwrap();
+        assert!(params.dry_run);
+        let uri: axum::http::Uri = "/p2p/gc".parse().unwrap();
+        let Query(params) = Query::<BlobGcQuery>::try_from_uri(&uri).unwrap();
+        assert!(!params.dry_run);
+    }
+
+    // 26. Recovery log cursor: set only on full pages, round-trips as a query
+    #[test]
+    fn test_recovery_log_cursor() {
+        let at = chrono::DateTime::parse_from_rfc3339("2026-01-02T12:00:00.123456Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let attempt = RecoveryAttempt {
+            content_hash: "hash1".into(),
+            origin_node: "origin1".into(),
+            peer_tried: Some("origin1".into()),
+            success: false,
+            error_message: Some("all sources exhausted".into()),
+            attempted_at: at,
+        };
+        let page = vec![attempt.clone(), attempt];
+
+        assert_eq!(recovery_log_cursor(&page, 3), None);
+        let cursor = recovery_log_cursor(&page, 2).unwrap();
+        assert_eq!(cursor, "2026-01-02T12:00:00.123456Z");
+
+        let uri: axum::http::Uri = format!("/recovery-log?hash=hash1&limit=2&cursor={cursor}")
+            .parse()
+            .unwrap();
+        let Query(params) = Query::<RecoveryLogQuery>::try_from_uri(&uri).unwrap();
+        assert_eq!(params.cursor, Some(at));
+        assert_eq!(params.hash.as_deref(), Some("hash1"));
+
+        let uri: axum::http::Uri = "/recovery-log?cursor=yesterday".parse().unwrap();
+        assert!(Query::<RecoveryLogQuery>::try_from_uri(&uri).is_err());
+    }
+
+    /// Fetcher with no local blobs and no peers online.
+    struct MockFetcher;
+
+    #[async_trait::async_trait]
+    impl TrackFetcher for MockFetcher {
+        async fn fetch_track(
+            &self,
+            _peer_id: &str,
+            hash: &str,
+        ) -> Result<bytes::Bytes, soundtime_p2p::P2pError> {
+            Err(soundtime_p2p::P2pError::TrackNotFound(hash.to_string()))
+        }
+
+        async fn check_blob_exists(&self, _hash: &str) -> bool {
+            false
+        }
+
+        async fn peer_is_online(&self, _peer_id: &str) -> bool {
+            false
+        }
+
+        async fn alternative_sources(&self, _hash: &str) -> Vec<soundtime_p2p::PeerTrackInfo> {
+            vec![]
+        }
+    }
+
+    // 27. health overview pages through degraded and dereferenced tracks
+    #[tokio::test]
+    async fn test_health_overview() {
+        let (state, node, _dir) = crate::test_db::state_with_node().await;
+        insert_unavailable_remote_track(&state.db, "peer1", "hash1").await;
+        let manager = node.health_manager();
+        manager.record_failure("hash1", "peer1").await;
+        manager.record_failure("hash2", "peer1").await;
+        manager.record_success("hash2").await;
+
+        let query = |page: usize| {
+            Query(HealthOverviewQuery {
+                page: Some(page),
+                per_page: Some(10),
+            })
+        };
+        let Json(overview) = health_overview(State(state.clone()), query(1))
+            .await
+            .unwrap();
+        assert_eq!(overview.total, 1, "recovered tracks are not listed");
+        assert_eq!(overview.tracks.len(), 1);
+        assert_eq!(overview.tracks[0].content_hash, "hash1");
+        assert_eq!(overview.tracks[0].title.as_deref(), Some("Track hash1"));
+        assert_eq!(overview.tracks[0].status, "degraded");
+        assert!(overview.last_sweep.is_none());
+        assert_eq!(overview.counts.get("recovered"), Some(&1));
+
+        let Json(overview) = health_overview(State(state), query(2)).await.unwrap();
+        assert_eq!(overview.total, 1);
+        assert!(overview.tracks.is_empty());
+        assert_eq!(overview.page, 2);
+    }
+
+    // 28. On-demand sweep runs with the given fetcher and reports completion
+    #[tokio::test]
+    async fn test_start_sweep_with_mock_fetcher() {
+        let tracker = soundtime_p2p::new_health_sweep_tracker();
+        let manager = Arc::new(TrackHealthManager::new());
+
+        // No tables: nothing to check, and every query fails
+        let Json(resp) = start_sweep(
+            Arc::clone(&manager),
+            Arc::new(MockFetcher),
+            crate::test_db::connect().await,
+            tracker.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.message, "Health sweep started");
+
+        let status = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                let Json(status) = health_sweep_status(Extension(tracker.clone())).await;
+                if !matches!(status, HealthSweepTaskStatus::Running { .. }) {
+                    break status;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("sweep should finish");
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["status"], "completed");
+        assert_eq!(json["result"]["total_checked"], 0);
+    }
+
+    // 29. A second sweep is refused while one is running
+    #[tokio::test]
+    async fn test_start_sweep_conflict() {
+        let tracker = soundtime_p2p::new_health_sweep_tracker();
+        *tracker.lock().await = HealthSweepTaskStatus::Running {
+            started_at: chrono::Utc::now(),
+        };
+
+        let err = start_sweep(
+            Arc::new(TrackHealthManager::new()),
+            Arc::new(MockFetcher),
+            crate::test_db::connect().await,
+            tracker,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+    }
+
+    // 30. Sweep status is idle before any sweep
+    #[tokio::test]
+    async fn test_health_sweep_status_idle() {
+        let Json(status) =
+            health_sweep_status(Extension(soundtime_p2p::new_health_sweep_tracker())).await;
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["status"], "idle");
+    }
+
+    // 31. Health monitor settings as shown in the overview
+    #[test]
+    fn test_health_monitor_settings_from_config() {
+        let config = soundtime_p2p::HealthMonitorConfig {
+            max_concurrent_recoveries: 8,
+            schedule: soundtime_p2p::HealthSchedule::Interval(std::time::Duration::from_secs(3600)),
+            max_retry_attempts: 5,
+            batch_size: 200,
+            integrity_sample_rate: 0.1,
+        };
+        let json = serde_json::to_value(HealthMonitorSettings::from(&config)).unwrap();
+        assert_eq!(json["schedule"], "every 3600s");
+        assert_eq!(json["max_retry_attempts"], 5);
+        assert_eq!(json["batch_size"], 200);
+        assert_eq!(json["max_concurrent_recoveries"], 8);
+        assert_eq!(json["integrity_sample_rate"], 0.1);
+    }
+
+    // 32. Query history lists the terms peers returned results for
+    #[tokio::test]
+    async fn test_search_query_history() {
+        let (state, node, _dir) = crate::test_db::state_with_node().await;
+        let history = |limit: usize| {
+            search_query_history(
+                State(state.clone()),
+                Query(QueryHistoryQuery { limit: Some(limit) }),
+            )
+        };
+
+        let Json(entries) = history(10).await.unwrap();
+        assert!(entries.is_empty());
+
+        let index = node.search_index();
+        index.record_query_result("miles davis", "peer1").await;
+        index.record_query_result("davis", "peer2").await;
+        let Json(entries) = history(10).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].term, "davis");
+        assert_eq!(entries[0].hits, 2);
+        assert_eq!(entries[0].peers.len(), 2);
+
+        let Json(entries) = history(1).await.unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+
+    /// Fetcher that only `alt_peer` can serve.
+    struct ServingFetcher;
+
+    #[async_trait::async_trait]
+    impl TrackFetcher for ServingFetcher {
+        async fn fetch_track(
+            &self,
+            peer_id: &str,
+            hash: &str,
+        ) -> Result<bytes::Bytes, soundtime_p2p::P2pError> {
+            if peer_id == "alt_peer" {
+                Ok(bytes::Bytes::from_static(b"audio"))
+            } else {
+                Err(soundtime_p2p::P2pError::TrackNotFound(hash.to_string()))
+            }
+        }
+
+        async fn check_blob_exists(&self, _hash: &str) -> bool {
+            false
+        }
+
+        async fn peer_is_online(&self, peer_id: &str) -> bool {
+            peer_id == "alt_peer"
+        }
+
+        async fn alternative_sources(&self, _hash: &str) -> Vec<soundtime_p2p::PeerTrackInfo> {
+            vec![]
+        }
+    }
+
+    fn repair_item() -> soundtime_p2p::TrackCheckItem {
+        soundtime_p2p::TrackCheckItem {
+            content_hash: "hash1".into(),
+            origin_node: "origin1".into(),
+            title: "Track".into(),
+        }
+    }
+
+    // 33. Manual repair from a requested peer re-references the track
+    #[tokio::test]
+    async fn test_repair_track_from_requested_peer() {
+        let manager = TrackHealthManager::new();
+        for _ in 0..manager.config().max_retry_attempts {
+            manager.record_failure("hash1", "origin1").await;
+        }
+        assert!(manager.is_dereferenced("hash1").await);
+
+        // No tables: recording the recovery fails and is only logged
+        let db = crate::test_db::connect().await;
+        let result = repair_track(
+            &db,
+            &manager,
+            &ServingFetcher,
+            &repair_item(),
+            Some("alt_peer"),
+        )
+        .await;
+        assert!(result.success);
+        assert_eq!(result.status, "recovered");
+        assert_eq!(result.peer_used.as_deref(), Some("alt_peer"));
+        assert_eq!(result.failed_attempts, 0);
+        assert!(!manager.is_dereferenced("hash1").await);
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["origin_node"], "origin1");
+        assert!(json["error"].is_null());
+    }
+
+    // 34. A failed manual repair counts a failure, even during the cooldown
+    #[tokio::test]
+    async fn test_repair_track_failure() {
+        let manager = TrackHealthManager::new();
+        let db = crate::test_db::connect().await;
+
+        let first = repair_track(&db, &manager, &MockFetcher, &repair_item(), None).await;
+        assert!(!first.success);
+        assert_eq!(first.status, "degraded");
+        assert_eq!(first.failed_attempts, 1);
+        assert!(first.error.is_some());
+
+        // The origin alone cannot serve it; the manual repair does not wait
+        let second = repair_track(&db, &manager, &ServingFetcher, &repair_item(), None).await;
+        assert!(!second.success);
+        assert_eq!(second.failed_attempts, 2);
+    }
+
+    // 35. Manual repair checks the hash and that the remote track exists
+    #[tokio::test]
+    async fn test_repair_remote_track_unknown() {
+        let (state, _node, _dir) = crate::test_db::state_with_node().await;
+        let repair = |hash: String| {
+            repair_remote_track(
+                State(state.clone()),
+                Path(hash),
+                Some(Json(RepairTrackRequest {
+                    peer_id: Some("alt_peer".to_string()),
+                })),
+            )
+        };
+
+        assert_eq!(
+            error_status(repair(malformed_hash()).await),
+            StatusCode::BAD_REQUEST
+        );
+        let hash = soundtime_p2p::BlobHash::new(b"unknown track").to_string();
+        assert_eq!(error_status(repair(hash).await), StatusCode::NOT_FOUND);
+    }
+
+    // 36. The peer status snapshot lists each peer with its online state
+    #[test]
+    fn test_peer_status_snapshot_serialization() {
+        let peer = PeerInfo {
+            node_id: "p1".to_string(),
+            name: None,
+            version: Some("0.1.0".to_string()),
+            track_count: 7,
+            last_seen: chrono::Utc::now(),
+            is_online: false,
+            protocol_version: None,
+            rtt_ms: Some(25),
+            rtt_samples: Default::default(),
+            p50_rtt_ms: None,
+            interactions: Default::default(),
+            avg_latency_ms: None,
+            success_rate: None,
+            last_catalog_sync_at: None,
+            capabilities: Vec::new(),
+            departed_at: None,
+            consecutive_failures: 0,
+            label: None,
+            notes: None,
+            trust: PeerTrust::Normal,
+        };
+
+        let msg = PeerStatusMessage::Snapshot {
+            peers: vec![PeerStatusSnapshot::from(&peer)],
+        };
+        assert_eq!(
+            serde_json::to_value(&msg).unwrap(),
+            serde_json::json!({
+                "type": "snapshot",
+                "peers": [{
+                    "node_id": "p1",
+                    "track_count": 7,
+                    "rtt_ms": 25,
+                    "version": "0.1.0",
+                    "online": false,
+                }],
+            })
+        );
+    }
+
+    // 37. Blob cache stats describe an empty cache on a fresh node
+    #[tokio::test]
+    async fn test_blob_cache_stats() {
+        let (state, _node, _dir) = crate::test_db::state_with_node().await;
+
+        let Json(stats) = blob_cache_stats(State(state)).await.unwrap();
+        assert_eq!(stats.entries, 0);
+        assert_eq!(stats.total_bytes, 0);
+        assert!(stats.max_bytes > 0);
+    }
+
+    // 38. Setting the blob cache limit rejects zero and saves the new limit
+    #[tokio::test]
+    async fn test_set_blob_cache_limit() {
+        let (state, _node, _dir) = crate::test_db::state_with_node().await;
+
+        assert_eq!(
+            error_status(
+                set_blob_cache_limit(
+                    State(state.clone()),
+                    Json(SetBlobCacheLimitRequest { max_bytes: 0 }),
+                )
+                .await
+            ),
+            StatusCode::BAD_REQUEST
+        );
+
+        let Json(stats) = set_blob_cache_limit(
+            State(state.clone()),
+            Json(SetBlobCacheLimitRequest { max_bytes: 1024 }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(stats.max_bytes, 1024);
+        let saved = soundtime_p2p::blob_cache::load_max_bytes(&state.db)
+            .await
+            .unwrap();
+        assert_eq!(saved, Some(1024));
+    }
+
+    // 39. Adding a seed rejects a malformed node ID, then stores it once
+    #[tokio::test]
+    async fn test_add_seed() {
+        use soundtime_db::entities::user::UserRole;
+
+        let (state, node, _dir) = crate::test_db::state_with_node().await;
+        let admin = crate::test_db::insert_user(&state.db, "admin", UserRole::Admin).await;
+        let add = |node_id: String| {
+            add_seed(
+                State(state.clone()),
+                Extension(crate::test_db::auth_user(&admin)),
+                Json(AddSeedRequest {
+                    node_id,
+                    label: Some(" Main seed ".to_string()),
+                }),
+            )
+        };
+
+        assert_eq!(
+            error_status(add("not-a-node-id".to_string()).await),
+            StatusCode::BAD_REQUEST
+        );
+
+        // Any valid node ID will do: connecting to it fails in the background
+        let seed_id = node.node_id().to_string();
+        let (status, Json(seed)) = add(seed_id.clone()).await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(seed.node_id, seed_id);
+        assert_eq!(seed.label.as_deref(), Some("Main seed"));
+        assert_eq!(seed.added_by, Some(admin.id));
+
+        assert_eq!(error_status(add(seed_id).await), StatusCode::CONFLICT);
+        let Json(seeds) = list_seeds(State(state)).await.unwrap();
+        assert_eq!(seeds, vec![seed]);
+    }
+
+    // 40. The seed label is optional in the add-seed body
+    #[test]
+    fn test_deserialize_add_seed_request() {
+        let req: AddSeedRequest = serde_json::from_str(r#"{"node_id":"abc"}"#).unwrap();
+        assert_eq!(req.node_id, "abc");
+        assert!(req.label.is_none());
+
+        let req: AddSeedRequest =
+            serde_json::from_str(r#"{"node_id":"abc","label":"Main seed"}"#).unwrap();
+        assert_eq!(req.label.as_deref(), Some("Main seed"));
+    }
+
+    // 41. Peer updates default to the normal trust tier and reject unknown ones
+    #[test]
+    fn test_deserialize_update_peer_request() {
+        let req: UpdatePeerRequest = serde_json::from_str(r#"{"label":"Alice"}"#).unwrap();
+        assert_eq!(req.label.as_deref(), Some("Alice"));
+        assert!(req.notes.is_none());
+        assert_eq!(req.trust, PeerTrust::Normal);
+
+        let req: UpdatePeerRequest =
+            serde_json::from_str(r#"{"notes":"flaky uplink","trust":"quarantined"}"#).unwrap();
+        assert_eq!(req.trust, PeerTrust::Quarantined);
+
+        assert!(serde_json::from_str::<UpdatePeerRequest>(r#"{"trust":"banned"}"#).is_err());
+    }
+
+    // 42. Updating a peer sets its label and trust tier
+    #[tokio::test]
+    async fn test_update_peer() {
+        let (state, node, _dir) = crate::test_db::state_with_node().await;
+        let update = |label: &str| {
+            update_peer(
+                State(state.clone()),
+                Path("peer1".to_string()),
+                Json(UpdatePeerRequest {
+                    label: Some(label.to_string()),
+                    notes: None,
+                    trust: PeerTrust::Trusted,
+                }),
+            )
+        };
+
+        assert_eq!(error_status(update("Alice").await), StatusCode::NOT_FOUND);
+
+        node.registry().upsert_peer("peer1", None, 0).await;
+        let Json(peer) = update(" Alice ").await.unwrap();
+        assert_eq!(peer.label.as_deref(), Some("Alice"));
+        assert_eq!(peer.trust, PeerTrust::Trusted);
+        let saved = soundtime_db::entities::p2p_peer::Entity::find()
+            .one(&state.db)
+            .await
+            .unwrap()
+            .expect("peer is saved");
+        assert_eq!(saved.label.as_deref(), Some("Alice"));
+
+        assert_eq!(
+            error_status(update(&"x".repeat(MAX_PEER_LABEL_LEN + 1)).await),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    // 43. Library sync control endpoints
+    #[tokio::test]
+    async fn test_library_sync_control_endpoints() {
+        let tracker = soundtime_p2p::new_sync_tracker();
+        let err = pause_library_sync(Extension(tracker.clone()), Path(Uuid::new_v4()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+
+        let task_id = tracker.start("peer1").await.unwrap().task_id();
+        let Json(status) = pause_library_sync(Extension(tracker.clone()), Path(task_id))
+            .await
+            .unwrap();
+        assert!(matches!(status, LibrarySyncTaskStatus::Paused { .. }));
+
+        // A paused task cannot be dismissed
+        let err = library_sync_task_dismiss(Extension(tracker.clone()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+        assert!(tracker.lock().await.is_active());
+
+        let Json(status) = resume_library_sync(Extension(tracker.clone()), Path(task_id))
+            .await
+            .unwrap();
+        assert!(matches!(status, LibrarySyncTaskStatus::Running { .. }));
+
+        let Json(status) = cancel_library_sync(Extension(tracker.clone()), Path(task_id))
+            .await
+            .unwrap();
+        assert!(matches!(status, LibrarySyncTaskStatus::Cancelled { .. }));
+        let err = resume_library_sync(Extension(tracker.clone()), Path(task_id))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+
+        // A finished task can be dismissed
+        let Json(resp) = library_sync_task_dismiss(Extension(tracker.clone()))
+            .await
+            .unwrap();
+        assert_eq!(resp.message, "Task status reset");
+        assert!(matches!(*tracker.lock().await, LibrarySyncTaskStatus::Idle));
+    }
+
+    // 44. Each user holds at most a few peer status sockets at once
+    #[test]
+    fn test_peer_status_sockets_limited_per_user() {
+        let user = Uuid::new_v4();
+        let slots: Vec<_> = (0..MAX_PEER_STATUS_SOCKETS_PER_USER)
+            .map(|_| UserSocketSlot::acquire(user).unwrap())
+            .collect();
+        assert!(UserSocketSlot::acquire(user).is_none());
+        // Other users are not affected
+        let other = UserSocketSlot::acquire(Uuid::new_v4());
+        assert!(other.is_some());
+
+        drop(slots);
+        assert!(UserSocketSlot::acquire(user).is_some());
+        let open = PEER_STATUS_SOCKETS_BY_USER.lock().unwrap();
+        assert!(!open.contains_key(&user), "released slots are forgotten");
+    }
+
+    /// Status of a handler error; panics if the handler succeeded.
+    fn error_status<T>(result: Result<T, (StatusCode, Json<MessageResponse>)>) -> StatusCode {
+        match result {
+            Ok(_) => panic!("expected an error response"),
+            Err((status, _)) => status,
+        }
+    }
+
+    /// A blob hash of the right length that does not parse. Parsing one of
+    /// the wrong length trips an assertion in debug builds.
+    fn malformed_hash() -> String {
+        "z".repeat(64)
+    }
+
+    // 45. Node endpoints answer 503 without a running P2P node
+    #[tokio::test]
+    async fn test_p2p_endpoints_need_node() {
+        let state = crate::test_db::state(crate::test_db::connect().await);
+        let unavailable = StatusCode::SERVICE_UNAVAILABLE;
+        let peer = || Path("peer1".to_string());
+
+        let status = error_status(sync_catalog_to_peer(State(state.clone()), peer()).await);
+        assert_eq!(status, unavailable);
+        assert!(p2p_events(State(state.clone())).await.is_err());
+        let status = error_status(rejected_announcements(State(state.clone())).await);
+        assert_eq!(status, unavailable);
+        let status = error_status(evicted_peers(State(state.clone())).await);
+        assert_eq!(status, unavailable);
+        let filter = Json(PeerFilter::default());
+        let status = error_status(put_peer_filter(State(state.clone()), peer(), filter).await);
+        assert_eq!(status, unavailable);
+        let grant = Json(GrantAccessRequest {
+            hash: "deadbeef".to_string(),
+        });
+        let status = error_status(grant_track_access(State(state.clone()), peer(), grant).await);
+        assert_eq!(status, unavailable);
+        let status = error_status(current_health(State(state.clone())).await);
+        assert_eq!(status, unavailable);
+        let query = Query(HealthOverviewQuery {
+            page: None,
+            per_page: None,
+        });
+        let status = error_status(health_overview(State(state.clone()), query).await);
+        assert_eq!(status, unavailable);
+        let query = Query(QueryHistoryQuery { limit: None });
+        let status = error_status(search_query_history(State(state.clone()), query).await);
+        assert_eq!(status, unavailable);
+        let path = Path("abc".to_string());
+        let status = error_status(repair_remote_track(State(state.clone()), path, None).await);
+        assert_eq!(status, unavailable);
+        let scope = Json(ReReferenceRequest {
+            peer_id: None,
+            all: true,
+        });
+        let status = error_status(bulk_re_reference(State(state.clone()), scope).await);
+        assert_eq!(status, unavailable);
+        let status = error_status(blob_cache_stats(State(state.clone())).await);
+        assert_eq!(status, unavailable);
+        let limit = Json(SetBlobCacheLimitRequest { max_bytes: 1024 });
+        let status = error_status(set_blob_cache_limit(State(state.clone()), limit).await);
+        assert_eq!(status, unavailable);
+        let details = Json(UpdatePeerRequest {
+            label: None,
+            notes: None,
+            trust: PeerTrust::Normal,
+        });
+        let status = error_status(update_peer(State(state), peer(), details).await);
+        assert_eq!(status, unavailable);
     }
 }
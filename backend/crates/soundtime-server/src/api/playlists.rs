@@ -158,6 +158,7 @@ pub async fn get_playlist(
         vec![]
     } else {
         track::Entity::find()
+            .filter(track::Column::IsHidden.eq(false))
             .filter(track::Column::Id.is_in(track_ids.clone()))
             .all(&state.db)
             .await
@@ -6,6 +6,7 @@
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
     Json,
 };
 use sea_orm::{
@@ -15,7 +16,10 @@ use sea_orm::{
 use serde::{Deserialize, Serialize};
 use soundtime_db::entities::{plugin, plugin_config, plugin_events_log};
 use soundtime_db::AppState;
+use std::convert::Infallible;
 use std::sync::Arc;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tokio_stream::{Stream, StreamExt};
 use uuid::Uuid;
 
 use crate::auth::middleware::AuthUser;
@@ -228,6 +232,77 @@ pub async fn update_plugin(
     Ok(Json(model))
 }
 
+/// POST /api/admin/plugins/:id/reload — Pull the latest version of a plugin
+/// and swap it in without restarting the server.
+///
+/// Returns 202 right away; progress is streamed by
+/// `GET /api/admin/plugins/events`.
+pub async fn reload_plugin(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<serde_json::Value>), (StatusCode, Json<serde_json::Value>)> {
+    let registry = super::get_plugin_registry(&state).ok_or_else(plugin_system_disabled)?;
+
+    plugin::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|e| {
+            tracing::error!(plugin_id = %id, "failed to query plugin: {e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": format!("DB error: {e}") })),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "Plugin not found" })),
+            )
+        })?;
+
+    if registry.is_reloading(id) {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({ "error": "Plugin is already reloading" })),
+        ));
+    }
+
+    tokio::spawn(async move {
+        // Failures are logged and streamed by the registry
+        let _ = registry.reload_plugin(id).await;
+    });
+
+    tracing::info!(plugin_id = %id, "plugin reload requested via API");
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({ "status": "reloading" })),
+    ))
+}
+
+/// GET /api/admin/plugins/events — Plugin reload progress as Server-Sent Events.
+///
+/// Each `reload` event carries a JSON-encoded `PluginReloadProgress`. A
+/// subscriber too slow to keep up gets a `lagged` event with the number of
+/// events it missed.
+pub async fn plugin_events(
+    State(state): State<Arc<AppState>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, Json<serde_json::Value>)>
+{
+    let registry = super::get_plugin_registry(&state).ok_or_else(plugin_system_disabled)?;
+
+    let stream = BroadcastStream::new(registry.subscribe_reloads()).filter_map(|item| match item {
+        Ok(progress) => Event::default()
+            .event("reload")
+            .json_data(&progress)
+            .ok()
+            .map(Ok),
+        Err(BroadcastStreamRecvError::Lagged(missed)) => Some(Ok(Event::default()
+            .event("lagged")
+            .data(missed.to_string()))),
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
 /// GET /api/admin/plugins/:id/config — Get plugin configuration.
 pub async fn get_config(
     State(state): State<Arc<AppState>>,
@@ -190,7 +190,9 @@ async fn seed_track(
     let phase3_quota = count as usize; // remainder, trimmed by final take
 
     // Phase 1 — Same artist (50%)
-    let mut phase1_query = track::Entity::find().filter(track::Column::ArtistId.eq(seed.artist_id));
+    let mut phase1_query = track::Entity::find()
+        .filter(track::Column::IsHidden.eq(false))
+        .filter(track::Column::ArtistId.eq(seed.artist_id));
     if !exclude_vec.is_empty() {
         phase1_query = phase1_query.filter(track::Column::Id.is_not_in(exclude_vec.clone()));
     }
@@ -206,6 +208,7 @@ async fn seed_track(
     let mut phase2 = Vec::new();
     if let Some(ref genre) = seed.genre {
         let mut q = track::Entity::find()
+            .filter(track::Column::IsHidden.eq(false))
             .filter(track::Column::Genre.eq(genre.clone()))
             .filter(track::Column::ArtistId.ne(seed.artist_id));
         if !exclude_vec.is_empty() {
@@ -224,6 +227,7 @@ async fn seed_track(
     let mut phase3 = Vec::new();
     if let Some(year) = seed.year {
         let mut q = track::Entity::find()
+            .filter(track::Column::IsHidden.eq(false))
             .filter(track::Column::Year.gte(year - 5))
             .filter(track::Column::Year.lte(year + 5));
         if !exclude_vec.is_empty() {
@@ -303,7 +307,9 @@ async fn seed_artist(
         .collect();
 
     // Phase 1 — Artist's own tracks (60%)
-    let mut phase1_query = track::Entity::find().filter(track::Column::ArtistId.eq(seed_id));
+    let mut phase1_query = track::Entity::find()
+        .filter(track::Column::IsHidden.eq(false))
+        .filter(track::Column::ArtistId.eq(seed_id));
     if !exclude_vec.is_empty() {
         phase1_query = phase1_query.filter(track::Column::Id.is_not_in(exclude_vec.clone()));
     }
@@ -319,6 +325,7 @@ async fn seed_artist(
     let mut phase2 = Vec::new();
     if !genres.is_empty() {
         let mut q = track::Entity::find()
+            .filter(track::Column::IsHidden.eq(false))
             .filter(track::Column::Genre.is_in(genres))
             .filter(track::Column::ArtistId.ne(seed_id));
         if !exclude_vec.is_empty() {
@@ -363,7 +370,9 @@ async fn seed_genre(
     let pool_size = (count * 3).min(200);
     let exclude_vec: Vec<Uuid> = exclude.iter().copied().collect();
 
-    let mut query = track::Entity::find().filter(track::Column::Genre.eq(genre));
+    let mut query = track::Entity::find()
+        .filter(track::Column::IsHidden.eq(false))
+        .filter(track::Column::Genre.eq(genre));
     if !exclude_vec.is_empty() {
         query = query.filter(track::Column::Id.is_not_in(exclude_vec));
     }
@@ -452,6 +461,7 @@ async fn seed_personal_mix(
     if all_track_ids.is_empty() {
         // Fallback: random tracks
         let mut pool = track::Entity::find()
+            .filter(track::Column::IsHidden.eq(false))
             .order_by(Expr::cust("RANDOM()"), Order::Asc)
             .limit((count * 3).min(200))
             .all(db)
@@ -535,8 +545,9 @@ async fn seed_personal_mix(
     // Phase 1 — Tracks from favorite artists (40%)
     let mut phase1 = Vec::new();
     if !top_artists.is_empty() {
-        let mut q =
-            track::Entity::find().filter(track::Column::ArtistId.is_in(top_artists.clone()));
+        let mut q = track::Entity::find()
+            .filter(track::Column::IsHidden.eq(false))
+            .filter(track::Column::ArtistId.is_in(top_artists.clone()));
         if !exclude_vec.is_empty() {
             q = q.filter(track::Column::Id.is_not_in(exclude_vec.clone()));
         }
@@ -552,7 +563,9 @@ async fn seed_personal_mix(
     // Phase 2 — Tracks from favorite genres, different artists (40%)
     let mut phase2 = Vec::new();
     if !top_genres.is_empty() {
-        let mut q = track::Entity::find().filter(track::Column::Genre.is_in(top_genres));
+        let mut q = track::Entity::find()
+            .filter(track::Column::IsHidden.eq(false))
+            .filter(track::Column::Genre.is_in(top_genres));
         if !top_artists.is_empty() {
             q = q.filter(track::Column::ArtistId.is_not_in(top_artists));
         }
@@ -569,7 +582,7 @@ async fn seed_personal_mix(
     let phase2: Vec<track::Model> = phase2.into_iter().take(phase2_quota).collect();
 
     // Phase 3 — Random tracks for discovery (20%)
-    let mut phase3_query = track::Entity::find();
+    let mut phase3_query = track::Entity::find().filter(track::Column::IsHidden.eq(false));
     if !exclude_vec.is_empty() {
         phase3_query = phase3_query.filter(track::Column::Id.is_not_in(exclude_vec));
     }
@@ -664,6 +677,7 @@ async fn seed_similar(
     // Fetch full track models for the returned IDs, preserving similarity order
     let ids: Vec<Uuid> = similar_ids.iter().map(|(id, _)| *id).collect();
     let tracks = track::Entity::find()
+        .filter(track::Column::IsHidden.eq(false))
         .filter(track::Column::Id.is_in(ids.clone()))
         .all(db)
         .await
@@ -907,4 +921,73 @@ mod tests {
         assert_eq!(json["exhausted"], true);
         assert!(json["tracks"].as_array().unwrap().is_empty());
     }
+
+    // ── Hidden tracks ───────────────────────────────────────────────────
+
+    /// Insert a rock track by `artist_id` from 2020.
+    async fn insert_track(
+        db: &sea_orm::DatabaseConnection,
+        artist_id: Uuid,
+        is_hidden: bool,
+    ) -> track::Model {
+        use sea_orm::{ActiveModelTrait, Set};
+        track::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            title: Set("Track".into()),
+            artist_id: Set(artist_id),
+            album_id: Set(None),
+            track_number: Set(None),
+            disc_number: Set(None),
+            duration_secs: Set(200.0),
+            genre: Set(Some("Rock".into())),
+            year: Set(Some(2020)),
+            musicbrainz_id: Set(None),
+            file_path: Set("/data/music/track.mp3".into()),
+            file_size: Set(1_000_000),
+            format: Set("mp3".into()),
+            bitrate: Set(None),
+            sample_rate: Set(None),
+            waveform_data: Set(None),
+            uploaded_by: Set(None),
+            play_count: Set(0),
+            is_private: Set(false),
+            is_hidden: Set(is_hidden),
+            hidden_by_block: Set(false),
+            content_hash: Set(None),
+            fingerprint: Set(None),
+            loudness_lufs: Set(None),
+            dynamic_range: Set(None),
+            encoding_quality: Set(None),
+            created_at: Set(chrono::Utc::now().fixed_offset()),
+        }
+        .insert(db)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_radio_skips_hidden_tracks() {
+        let db = crate::test_db::connect().await;
+        crate::test_db::create_table(&db, track::Entity).await;
+        let artist_id = Uuid::new_v4();
+        let seed = insert_track(&db, artist_id, false).await;
+        let visible = insert_track(&db, Uuid::new_v4(), false).await;
+        let hidden = insert_track(&db, artist_id, true).await;
+
+        let exclude = HashSet::new();
+        let ids = |tracks: Vec<track::Model>| -> HashSet<Uuid> {
+            tracks.into_iter().map(|t| t.id).collect()
+        };
+
+        let genre = ids(seed_genre(&db, "Rock", 10, &exclude).await.unwrap());
+        assert_eq!(genre, HashSet::from([seed.id, visible.id]));
+
+        let by_track = ids(seed_track(&db, seed.id, 10, &exclude).await.unwrap());
+        assert!(by_track.contains(&visible.id));
+        assert!(!by_track.contains(&hidden.id));
+
+        let by_artist = ids(seed_artist(&db, artist_id, 10, &exclude).await.unwrap());
+        assert!(by_artist.contains(&seed.id));
+        assert!(!by_artist.contains(&hidden.id));
+    }
 }
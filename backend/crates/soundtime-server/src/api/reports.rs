@@ -12,17 +12,27 @@ use axum::{
 };
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+    TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
 
+use super::audit::{AuditAction, AuditEntry, ClientIp};
 use crate::auth::middleware::AuthUser;
 use soundtime_db::entities::{
     artist, instance_setting, playlist_track, remote_track, track, track_report, user,
 };
 use soundtime_db::AppState;
 
+/// Extract p2p node from type-erased state
+fn get_p2p_node(state: &AppState) -> Option<Arc<soundtime_p2p::P2pNode>> {
+    state
+        .p2p
+        .as_ref()
+        .and_then(|any| any.clone().downcast::<soundtime_p2p::P2pNode>().ok())
+}
+
 // ═══════════════════════════════════════════════════════════════════
 // USER: Report a track
 // ═══════════════════════════════════════════════════════════════════
@@ -127,6 +137,8 @@ pub struct AdminReportResponse {
     pub track_title: String,
     pub track_artist: String,
     pub is_local: bool,
+    /// Content hash of the reported track, if it has one (blockable)
+    pub content_hash: Option<String>,
     pub reporter_username: String,
     pub reason: String,
     pub status: String,
@@ -154,6 +166,7 @@ pub async fn list_reports(
             .await
             .unwrap_or(None);
 
+        let content_hash = t.as_ref().and_then(|trk| trk.content_hash.clone());
         let (track_title, track_artist, is_local) = match t {
             Some(ref trk) => {
                 let artist_name = artist::Entity::find_by_id(trk.artist_id)
@@ -183,6 +196,7 @@ pub async fn list_reports(
             track_title,
             track_artist,
             is_local,
+            content_hash,
             reporter_username: reporter,
             reason: r.reason,
             status: r.status,
@@ -199,7 +213,7 @@ pub async fn list_reports(
 pub struct ResolveReportRequest {
     /// "resolved" | "dismissed"
     pub action: String,
-    /// "delete" | "unlist" | "none"
+    /// "delete" | "unlist" | "block_hash" | "none"
     pub track_action: Option<String>,
     pub admin_note: Option<String>,
 }
@@ -269,6 +283,9 @@ pub async fn resolve_report(
                     if let Err(e) = tokio::fs::remove_file(&trk.file_path).await {
                         tracing::warn!(error = %e, track_id = %trk.id, "failed to delete track file");
                     }
+                    if let Some(node) = get_p2p_node(&state) {
+                        node.invalidate_catalog_checksum();
+                    }
                     track_action_msg = "Local track deleted.".to_string();
                     tracing::info!(track_id = %trk.id, "Local track deleted via report");
                 }
@@ -317,6 +334,34 @@ pub async fn resolve_report(
                     track_action_msg =
                         "Remote track dereferenced (deletion not possible).".to_string();
                 }
+                "block_hash" => {
+                    // Block the blob on this instance only; peers are not told
+                    let hash = trk
+                        .content_hash
+                        .as_deref()
+                        .and_then(|h| h.parse::<soundtime_p2p::BlobHash>().ok())
+                        .ok_or_else(|| {
+                            (
+                                StatusCode::BAD_REQUEST,
+                                Json(serde_json::json!({ "error": "Track has no content hash to block." })),
+                            )
+                        })?;
+                    let node = get_p2p_node(&state).ok_or_else(|| {
+                        (
+                            StatusCode::SERVICE_UNAVAILABLE,
+                            Json(serde_json::json!({ "error": "P2P node is not enabled." })),
+                        )
+                    })?;
+                    let reason = body.admin_note.as_deref().unwrap_or(&report.reason);
+                    node.block_hash_locally(hash, reason).await.map_err(|e| {
+                        (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            Json(serde_json::json!({ "error": format!("Failed to block hash: {e}") })),
+                        )
+                    })?;
+                    track_action_msg = "Content hash blocked on this instance.".to_string();
+                    tracing::info!(track_id = %trk.id, %hash, "content hash blocked via report");
+                }
                 _ => {}
             }
         }
@@ -461,8 +506,17 @@ pub async fn browse_tracks(
 /// DELETE /api/admin/tracks/:id/moderate — delete or unlist a track (admin)
 pub async fn moderate_track(
     State(state): State<Arc<AppState>>,
+    Extension(admin): Extension<AuthUser>,
+    ip: ClientIp,
     Path(track_id): Path<Uuid>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let db_error = |e: sea_orm::DbErr| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": format!("DB error: {e}") })),
+        )
+    };
+
     let trk = track::Entity::find_by_id(track_id)
         .one(&state.db)
         .await
@@ -481,36 +535,51 @@ pub async fn moderate_track(
 
     let is_local = true;
 
+    // The track rows and the audit entry go together; the file is only
+    // removed once they are committed
+    let txn = state.db.begin().await.map_err(db_error)?;
+
     // Remove from playlists
-    if let Err(e) = playlist_track::Entity::delete_many()
+    playlist_track::Entity::delete_many()
         .filter(playlist_track::Column::TrackId.eq(trk.id))
-        .exec(&state.db)
+        .exec(&txn)
         .await
-    {
-        tracing::warn!(error = %e, track_id = %trk.id, "failed to delete playlist tracks during moderation");
-    }
+        .map_err(db_error)?;
 
     if !is_local {
         // Unlist remote
-        if let Err(e) = remote_track::Entity::delete_many()
+        remote_track::Entity::delete_many()
             .filter(remote_track::Column::LocalTrackId.eq(Some(trk.id)))
-            .exec(&state.db)
+            .exec(&txn)
             .await
-        {
-            tracing::warn!(error = %e, track_id = %trk.id, "failed to delete remote track reference during moderation");
-        }
+            .map_err(db_error)?;
     }
 
     // Delete track entry
-    if let Err(e) = track::Entity::delete_by_id(trk.id).exec(&state.db).await {
-        tracing::warn!(error = %e, track_id = %trk.id, "failed to delete track during moderation");
-    }
+    track::Entity::delete_by_id(trk.id)
+        .exec(&txn)
+        .await
+        .map_err(db_error)?;
+
+    // The row is gone after this, so the title is kept with the ID
+    AuditEntry::new(
+        admin.0.sub,
+        AuditAction::ModerateTrack,
+        format!("{} ({})", trk.id, trk.title),
+    )
+    .record(&txn, &ip)
+    .await
+    .map_err(db_error)?;
+    txn.commit().await.map_err(db_error)?;
 
     // Delete file if local
     if is_local {
         if let Err(e) = tokio::fs::remove_file(&trk.file_path).await {
             tracing::warn!(error = %e, track_id = %trk.id, "failed to delete track file during moderation");
         }
+        if let Some(node) = get_p2p_node(&state) {
+            node.invalidate_catalog_checksum();
+        }
     }
 
     // Auto-resolve any pending reports for this track
@@ -591,7 +660,7 @@ pub struct TosResponse {
 /// GET /api/tos — public, get Terms of Service
 pub async fn get_tos(State(state): State<Arc<AppState>>) -> Result<Json<TosResponse>, StatusCode> {
     let setting = instance_setting::Entity::find()
-        .filter(instance_setting::Column::Key.eq("tos_content"))
+        .filter(instance_setting::Column::Key.eq(TOS_SETTING_KEY))
         .one(&state.db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -613,40 +682,48 @@ pub struct UpdateTosRequest {
     pub content: String,
 }
 
+/// Setting key the Terms of Service are stored under.
+const TOS_SETTING_KEY: &str = "tos_content";
+
 /// PUT /api/admin/tos — update Terms of Service
 pub async fn update_tos(
     State(state): State<Arc<AppState>>,
+    Extension(admin): Extension<AuthUser>,
+    ip: ClientIp,
     Json(body): Json<UpdateTosRequest>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let db_error =
+        |e: sea_orm::DbErr| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}"));
     let now = chrono::Utc::now().fixed_offset();
+    let txn = state.db.begin().await.map_err(db_error)?;
 
     let existing = instance_setting::Entity::find()
-        .filter(instance_setting::Column::Key.eq("tos_content"))
-        .one(&state.db)
+        .filter(instance_setting::Column::Key.eq(TOS_SETTING_KEY))
+        .one(&txn)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+        .map_err(db_error)?;
 
     if let Some(s) = existing {
         let mut active: instance_setting::ActiveModel = s.into();
         active.value = Set(body.content);
         active.updated_at = Set(now);
-        active
-            .update(&state.db)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+        active.update(&txn).await.map_err(db_error)?;
     } else {
         let new_setting = instance_setting::ActiveModel {
             id: Set(Uuid::new_v4()),
-            key: Set("tos_content".to_string()),
+            key: Set(TOS_SETTING_KEY.to_string()),
             value: Set(body.content),
             updated_at: Set(now),
         };
-        new_setting
-            .insert(&state.db)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+        new_setting.insert(&txn).await.map_err(db_error)?;
     }
 
+    AuditEntry::new(admin.0.sub, AuditAction::UpdateSetting, TOS_SETTING_KEY)
+        .record(&txn, &ip)
+        .await
+        .map_err(db_error)?;
+    txn.commit().await.map_err(db_error)?;
+
     Ok(Json(
         serde_json::json!({ "message": "Terms of Service updated." }),
     ))
@@ -655,12 +732,25 @@ pub async fn update_tos(
 /// DELETE /api/admin/tos — reset ToS to default
 pub async fn reset_tos(
     State(state): State<Arc<AppState>>,
+    Extension(admin): Extension<AuthUser>,
+    ip: ClientIp,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let db_error =
+        |e: sea_orm::DbErr| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}"));
+    let txn = state.db.begin().await.map_err(db_error)?;
+
     instance_setting::Entity::delete_many()
-        .filter(instance_setting::Column::Key.eq("tos_content"))
-        .exec(&state.db)
+        .filter(instance_setting::Column::Key.eq(TOS_SETTING_KEY))
+        .exec(&txn)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+        .map_err(db_error)?;
+
+    AuditEntry::new(admin.0.sub, AuditAction::UpdateSetting, TOS_SETTING_KEY)
+        .with_reason(Some("reset to default".to_string()))
+        .record(&txn, &ip)
+        .await
+        .map_err(db_error)?;
+    txn.commit().await.map_err(db_error)?;
 
     Ok(Json(
         serde_json::json!({ "message": "Terms of Service reset to default." }),
@@ -742,6 +832,7 @@ mod tests {
             track_title: "Song".to_string(),
             track_artist: "Artist".to_string(),
             is_local: true,
+            content_hash: Some("abc123".to_string()),
             reporter_username: "user1".to_string(),
             reason: "spam".to_string(),
             status: "pending".to_string(),
@@ -752,6 +843,7 @@ mod tests {
         let val = serde_json::to_value(&resp).unwrap();
         assert_eq!(val["track_title"], "Song");
         assert_eq!(val["status"], "pending");
+        assert_eq!(val["content_hash"], "abc123");
         assert!(val["admin_note"].is_null());
         assert!(val["resolved_at"].is_null());
     }
@@ -918,4 +1010,13 @@ mod tests {
         let resp = app.oneshot(req).await.unwrap();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
+
+    // 13. ResolveReportRequest with the network-local hash block action
+    #[test]
+    fn test_deserialize_resolve_report_block_hash() {
+        let json = r#"{"action":"resolved","track_action":"block_hash"}"#;
+        let req: ResolveReportRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(req.action, "resolved");
+        assert_eq!(req.track_action.as_deref(), Some("block_hash"));
+    }
 }
@@ -111,6 +111,7 @@ pub async fn search(
                 to_tsvector('english', a.name) ||
                 to_tsvector('english', COALESCE(al.title, ''))
             ) @@ to_tsquery('english', $1)
+            AND NOT t.is_hidden
             ORDER BY rank DESC
             LIMIT $2 OFFSET $3
             "#,
@@ -4,7 +4,8 @@ use axum::{
     Extension, Json,
 };
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -29,6 +30,59 @@ pub struct PaginationParams {
     pub per_page: Option<u64>,
 }
 
+/// Query parameters of `GET /api/tracks`.
+#[derive(Debug, Deserialize)]
+pub struct ListTracksParams {
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+    /// Cursor from a previous response's `next_cursor`; takes precedence
+    /// over `page`
+    pub after: Option<String>,
+    /// Only list tracks of at least this quality
+    pub min_quality: Option<MinQuality>,
+}
+
+/// Position in the track listing, which is ordered by `created_at` then
+/// `id`, newest first. Travels as base64-encoded JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackCursor {
+    pub created_at: chrono::DateTime<chrono::FixedOffset>,
+    pub id: Uuid,
+}
+
+impl TrackCursor {
+    /// Cursor pointing just after `track`.
+    pub fn of(track: &track::Model) -> Self {
+        Self {
+            created_at: track.created_at,
+            id: track.id,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        use base64::Engine;
+        let json = serde_json::to_vec(self).expect("cursor serializes");
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json)
+    }
+
+    /// Parse a cursor, `None` if it is not one we issued.
+    pub fn decode(cursor: &str) -> Option<Self> {
+        use base64::Engine;
+        let json = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(cursor.trim_end_matches('='))
+            .ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+/// Quality floor for `GET /api/tracks?min_quality=`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MinQuality {
+    /// FLAC, WAV or AIFF
+    Lossless,
+}
+
 #[derive(Debug, Serialize)]
 pub struct PaginatedResponse<T: Serialize> {
     pub data: Vec<T>,
@@ -38,6 +92,16 @@ pub struct PaginatedResponse<T: Serialize> {
     pub total_pages: u64,
 }
 
+/// Response of `GET /api/tracks`: a page plus the cursor of the next one.
+#[derive(Debug, Serialize)]
+pub struct TrackListResponse {
+    #[serde(flatten)]
+    pub page: PaginatedResponse<TrackResponse>,
+    /// Pass as `after` to get the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct TrackResponse {
     pub id: Uuid,
@@ -55,6 +119,8 @@ pub struct TrackResponse {
     pub musicbrainz_id: Option<String>,
     pub uploaded_by: Option<Uuid>,
     pub play_count: i64,
+    /// Only served to peers holding a grant
+    pub is_private: bool,
     pub created_at: chrono::DateTime<chrono::FixedOffset>,
     /// Joined artist name
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -71,6 +137,15 @@ pub struct TrackResponse {
     /// Source providing the best bitrate ("local" or instance domain)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub best_source: Option<String>,
+    /// EBU R128 integrated loudness in LUFS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loudness_lufs: Option<f32>,
+    /// EBU R128 loudness range in LU
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dynamic_range: Option<f32>,
+    /// How the audio is encoded, e.g. "CBR 320kbps"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding_quality: Option<String>,
 }
 
 impl From<track::Model> for TrackResponse {
@@ -91,40 +166,115 @@ impl From<track::Model> for TrackResponse {
             musicbrainz_id: t.musicbrainz_id,
             uploaded_by: t.uploaded_by,
             play_count: t.play_count,
+            is_private: t.is_private,
             created_at: t.created_at,
             artist_name: None,
             album_title: None,
             cover_url: None,
             best_bitrate: None,
             best_source: None,
+            loudness_lufs: t.loudness_lufs,
+            dynamic_range: t.dynamic_range,
+            encoding_quality: t.encoding_quality,
         }
     }
 }
 
-/// GET /api/tracks
+/// Keep the tracks listed after `cursor`: those with a smaller
+/// `(created_at, id)`.
+fn seek_after(
+    query: sea_orm::Select<track::Entity>,
+    cursor: &TrackCursor,
+) -> sea_orm::Select<track::Entity> {
+    use sea_orm::sea_query::Expr;
+
+    query.filter(
+        Expr::tuple([
+            Expr::col((track::Entity, track::Column::CreatedAt)).into(),
+            Expr::col((track::Entity, track::Column::Id)).into(),
+        ])
+        .lt(Expr::tuple([
+            Expr::val(cursor.created_at).into(),
+            Expr::val(cursor.id).into(),
+        ])),
+    )
+}
+
+/// Largest `per_page` of the track listing
+const MAX_TRACKS_PER_PAGE: u64 = 500;
+
+/// GET /api/tracks — `?min_quality=lossless` keeps only FLAC, WAV and AIFF
 pub async fn list_tracks(
     State(state): State<Arc<AppState>>,
-    Query(params): Query<PaginationParams>,
-) -> Result<Json<PaginatedResponse<TrackResponse>>, (StatusCode, String)> {
-    let page = params.page.unwrap_or(1).max(1);
-    let per_page = params.per_page.unwrap_or(20).min(100);
-
-    let paginator = track::Entity::find()
-        .order_by_desc(track::Column::CreatedAt)
-        .paginate(&state.db, per_page);
+    Query(params): Query<ListTracksParams>,
+) -> Result<Json<TrackListResponse>, (StatusCode, String)> {
+    use sea_orm::sea_query::{Expr, Func};
 
-    let total = paginator
-        .num_items()
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(20).clamp(1, MAX_TRACKS_PER_PAGE);
+    let after = match params.after.as_deref() {
+        Some(cursor) => Some(
+            TrackCursor::decode(cursor)
+                .ok_or((StatusCode::BAD_REQUEST, "Invalid cursor".to_string()))?,
+        ),
+        None => None,
+    };
 
-    let tracks = paginator
-        .fetch_page(page - 1)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+    let mut query = track::Entity::find().filter(track::Column::IsHidden.eq(false));
+    if params.min_quality == Some(MinQuality::Lossless) {
+        // Replicated tracks carry the format as announced by their origin,
+        // which may be upper case
+        query = query.filter(
+            Expr::expr(Func::lower(Expr::col(track::Column::Format)))
+                .is_in(soundtime_audio::metadata::LOSSLESS_FORMATS.iter().copied()),
+        );
+    }
 
+    // Only numbered pages are counted: counting the whole catalog on every
+    // cursor page would cost what seeking saves
+    let total = match after {
+        Some(_) => 0,
+        None => query
+            .clone()
+            .count(&state.db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?,
+    };
     let total_pages = total.div_ceil(per_page);
 
+    // `id` breaks ties between tracks created in the same instant, so that
+    // the order is total and a cursor never skips or repeats a track
+    let query = query
+        .order_by_desc(track::Column::CreatedAt)
+        .order_by_desc(track::Column::Id);
+
+    let (tracks, page, has_more) = match after {
+        Some(cursor) => {
+            // Seek past the cursor on the (created_at, id) index instead of
+            // scanning and discarding an OFFSET's worth of rows
+            let mut tracks = seek_after(query, &cursor)
+                .limit(per_page + 1)
+                .all(&state.db)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+            let has_more = tracks.len() as u64 > per_page;
+            tracks.truncate(per_page as usize);
+            // Cursor pages have no page number
+            (tracks, 0, has_more)
+        }
+        None => {
+            let tracks = query
+                .paginate(&state.db, per_page)
+                .fetch_page(page - 1)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+            (tracks, page, page < total_pages)
+        }
+    };
+    let next_cursor = has_more
+        .then(|| tracks.last().map(|t| TrackCursor::of(t).encode()))
+        .flatten();
+
     // Batch-fetch artist and album data
     let artist_ids: Vec<Uuid> = tracks
         .iter()
@@ -194,12 +344,15 @@ pub async fn list_tracks(
         })
         .collect();
 
-    Ok(Json(PaginatedResponse {
-        data,
-        total,
-        page,
-        per_page,
-        total_pages,
+    Ok(Json(TrackListResponse {
+        page: PaginatedResponse {
+            data,
+            total,
+            page,
+            per_page,
+            total_pages,
+        },
+        next_cursor,
     }))
 }
 
@@ -483,6 +636,48 @@ pub async fn get_track_credits(
     }))
 }
 
+// ─── Waveform Status (public) ───────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct WaveformStatusResponse {
+    pub ready: bool,
+    /// Fraction of the audio decoded so far (1.0 once ready)
+    pub progress: f32,
+}
+
+/// Waveform status of a track: ready once `waveform_data` is stored,
+/// otherwise the progress of a running generation (0.0 if none is running).
+fn waveform_status(has_waveform: bool, running: Option<f32>) -> WaveformStatusResponse {
+    if has_waveform {
+        WaveformStatusResponse {
+            ready: true,
+            progress: 1.0,
+        }
+    } else {
+        WaveformStatusResponse {
+            ready: false,
+            progress: running.unwrap_or(0.0),
+        }
+    }
+}
+
+/// GET /api/tracks/:id/waveform-status — poll while a new upload's waveform computes
+pub async fn get_waveform_status(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<WaveformStatusResponse>, (StatusCode, String)> {
+    let track_model = track::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?
+        .ok_or((StatusCode::NOT_FOUND, "Track not found".to_string()))?;
+
+    Ok(Json(waveform_status(
+        track_model.waveform_data.is_some(),
+        crate::waveform_worker::progress(id),
+    )))
+}
+
 // ─── Track Update (owner only) ──────────────────────────────────────
 
 #[derive(Debug, Deserialize)]
@@ -519,11 +714,11 @@ pub async fn update_track(
     }
 
     let mut active: track::ActiveModel = existing.into();
-    if let Some(title) = body.title {
-        active.title = Set(title);
+    if let Some(ref title) = body.title {
+        active.title = Set(title.clone());
     }
-    if let Some(genre) = body.genre {
-        active.genre = Set(Some(genre));
+    if let Some(ref genre) = body.genre {
+        active.genre = Set(Some(genre.clone()));
     }
     if let Some(year) = body.year {
         active.year = Set(Some(year));
@@ -540,6 +735,75 @@ pub async fn update_track(
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
 
+    // Propagate the edit to peers that replicated this track (best-effort)
+    if let (Some(p2p_node), Some(hash)) = (get_p2p_node(&state), updated.content_hash.clone()) {
+        if !updated.file_path.starts_with("p2p://") {
+            let fields = soundtime_p2p::TrackMetadataUpdate {
+                title: body.title,
+                artist_name: None,
+                album_title: None,
+                genre: body.genre,
+                year: body.year,
+                track_number: body.track_number,
+            };
+            tokio::spawn(async move {
+                p2p_node.broadcast_update_track_metadata(hash, fields).await;
+            });
+        }
+    }
+
+    Ok(Json(TrackResponse::from(updated)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrackPrivacyRequest {
+    pub is_private: bool,
+}
+
+/// PUT /api/tracks/:id/privacy — make a track private, so peers need a
+/// grant to fetch it over P2P, or public again (uploader or admin)
+pub async fn set_track_privacy(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<TrackPrivacyRequest>,
+) -> Result<Json<TrackResponse>, (StatusCode, String)> {
+    let existing = track::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?
+        .ok_or((StatusCode::NOT_FOUND, "Track not found".to_string()))?;
+
+    if existing.uploaded_by != Some(user.0.sub) {
+        use soundtime_db::entities::user::{self, UserRole};
+        let is_admin = user::Entity::find_by_id(user.0.sub)
+            .one(&state.db)
+            .await
+            .ok()
+            .flatten()
+            .is_some_and(|u| u.role == UserRole::Admin);
+        if !is_admin {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Only the uploader or an admin can change a track's privacy".to_string(),
+            ));
+        }
+    }
+    if existing.file_path.starts_with("p2p://") {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Replicated tracks are served by their origin".to_string(),
+        ));
+    }
+
+    let mut active: track::ActiveModel = existing.into();
+    active.is_private = Set(body.is_private);
+    let updated = active
+        .update(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+
+    tracing::info!(%id, is_private = updated.is_private, "track privacy changed");
     Ok(Json(TrackResponse::from(updated)))
 }
 
@@ -631,6 +895,32 @@ pub async fn delete_track(
     // Bloom filters don't support individual removal, so we mark the index
     // dirty and trigger an async rebuild from the database.
     if let Some(p2p_node) = get_p2p_node(&state) {
+        // Stop serving the blob unless another track shares its content
+        if let Some(ref content_hash) = existing.content_hash {
+            let still_used = track::Entity::find()
+                .filter(track::Column::ContentHash.eq(content_hash.as_str()))
+                .count(&state.db)
+                .await
+                .map(|n| n > 0)
+                .unwrap_or(true);
+            if !still_used {
+                if let Ok(hash) = content_hash.parse::<soundtime_p2p::BlobHash>() {
+                    if let Err(e) = p2p_node.unpublish(hash).await {
+                        tracing::warn!(track_id = %id, "failed to unpublish deleted track: {e}");
+                    }
+                }
+                // Tell peers holding a copy of our track that it is gone
+                if !existing.file_path.starts_with("p2p://") {
+                    let node = p2p_node.clone();
+                    let hash = content_hash.clone();
+                    tokio::spawn(async move {
+                        node.broadcast_delete_track(hash).await;
+                    });
+                }
+            }
+        }
+
+        p2p_node.invalidate_catalog_checksum();
         p2p_node.search_index().mark_dirty().await;
         let db = state.db.clone();
         let search_index = p2p_node.search_index().clone();
@@ -656,6 +946,140 @@ pub async fn delete_track(
     Ok(StatusCode::NO_CONTENT)
 }
 
+// ─── Pinning ────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct PinResponse {
+    pub track_id: Uuid,
+    pub content_hash: String,
+    pub pinned: bool,
+}
+
+/// Internal: load a track the user may pin (its uploader or an admin) and
+/// parse its content hash.
+async fn pinnable_track(
+    state: &AppState,
+    user: &AuthUser,
+    id: Uuid,
+) -> Result<(track::Model, soundtime_p2p::BlobHash), (StatusCode, String)> {
+    let existing = track::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?
+        .ok_or((StatusCode::NOT_FOUND, "Track not found".to_string()))?;
+
+    if existing.uploaded_by != Some(user.0.sub) {
+        use soundtime_db::entities::user::{self, UserRole};
+        let is_admin = user::Entity::find_by_id(user.0.sub)
+            .one(&state.db)
+            .await
+            .ok()
+            .flatten()
+            .is_some_and(|u| u.role == UserRole::Admin);
+        if !is_admin {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Only the uploader or an admin can pin a track".to_string(),
+            ));
+        }
+    }
+
+    let hash = existing
+        .content_hash
+        .as_deref()
+        .and_then(|h| h.parse::<soundtime_p2p::BlobHash>().ok())
+        .ok_or((
+            StatusCode::BAD_REQUEST,
+            "Track has no P2P content hash".to_string(),
+        ))?;
+    Ok((existing, hash))
+}
+
+/// POST /api/tracks/:id/pin — keep the track's blob in the P2P cache,
+/// fetching it now if needed (uploader or admin)
+pub async fn pin_track(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PinResponse>, (StatusCode, String)> {
+    use soundtime_db::entities::pinned_track;
+
+    let (existing, hash) = pinnable_track(&state, &user, id).await?;
+    let content_hash = hash.to_string();
+
+    let already = pinned_track::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+    // Pins are counted per blob, so a track only takes one
+    if already.is_none() {
+        pinned_track::ActiveModel {
+            track_id: Set(existing.id),
+            content_hash: Set(content_hash.clone()),
+            pinned_by: Set(Some(user.0.sub)),
+            pinned_at: Set(chrono::Utc::now().into()),
+        }
+        .insert(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+
+        if let Some(p2p_node) = get_p2p_node(&state) {
+            p2p_node.pin_track(hash).await;
+        }
+    }
+
+    Ok(Json(PinResponse {
+        track_id: id,
+        content_hash,
+        pinned: true,
+    }))
+}
+
+/// DELETE /api/tracks/:id/pin — let the P2P cache evict the track's blob
+/// again (uploader or admin)
+pub async fn unpin_track(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PinResponse>, (StatusCode, String)> {
+    use soundtime_db::entities::pinned_track;
+
+    let (_, hash) = pinnable_track(&state, &user, id).await?;
+
+    let pin = pinned_track::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+    let Some(pin) = pin else {
+        return Ok(Json(PinResponse {
+            track_id: id,
+            content_hash: hash.to_string(),
+            pinned: false,
+        }));
+    };
+    let removed = pinned_track::Entity::delete_by_id(id)
+        .exec(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {e}")))?;
+
+    // Release the hash the pin was taken on, which a re-reference may have
+    // since changed on the track
+    if removed.rows_affected > 0 {
+        if let (Some(p2p_node), Ok(pinned_hash)) = (
+            get_p2p_node(&state),
+            pin.content_hash.parse::<soundtime_p2p::BlobHash>(),
+        ) {
+            p2p_node.unpin_track(pinned_hash).await;
+        }
+    }
+
+    Ok(Json(PinResponse {
+        track_id: id,
+        content_hash: hash.to_string(),
+        pinned: false,
+    }))
+}
+
 // ─── Explore: Popular Tracks ────────────────────────────────────────
 
 #[derive(Debug, Deserialize)]
@@ -664,15 +1088,26 @@ pub struct ExploreParams {
     pub per_page: Option<u64>,
 }
 
-/// GET /api/tracks/popular — tracks sorted by play_count DESC
+/// GET /api/tracks/popular — tracks sorted by play count DESC.
+///
+/// Replicated tracks also count the plays peers gossiped for them, decayed
+/// by age (see `soundtime_p2p::popularity`), so the ranking reflects the
+/// whole network rather than this instance alone.
 pub async fn list_popular_tracks(
     State(state): State<Arc<AppState>>,
     Query(params): Query<ExploreParams>,
 ) -> Result<Json<PaginatedResponse<TrackResponse>>, (StatusCode, String)> {
+    use sea_orm::{sea_query::Expr, Order};
+
     let page = params.page.unwrap_or(1).max(1);
     let per_page = params.per_page.unwrap_or(20).min(100);
 
     let paginator = track::Entity::find()
+        .filter(track::Column::IsHidden.eq(false))
+        .order_by(
+            Expr::cust(soundtime_p2p::popularity::popularity_order_sql()),
+            Order::Desc,
+        )
         .order_by_desc(track::Column::PlayCount)
         .paginate(&state.db, per_page);
 
@@ -828,6 +1263,7 @@ pub async fn list_trending_tracks(
     // Fetch full track data for the trending IDs
     let track_ids: Vec<Uuid> = entries.iter().map(|e| e.track_id).collect();
     let tracks_map: HashMap<Uuid, track::Model> = track::Entity::find()
+        .filter(track::Column::IsHidden.eq(false))
         .filter(track::Column::Id.is_in(track_ids.clone()))
         .all(&state.db)
         .await
@@ -928,7 +1364,7 @@ pub async fn list_random_tracks(
 
     let count = params.count.unwrap_or(10).min(50);
 
-    let mut query = track::Entity::find();
+    let mut query = track::Entity::find().filter(track::Column::IsHidden.eq(false));
     if let Some(ref genre) = params.genre {
         query = query.filter(track::Column::Genre.eq(genre.clone()));
     }
@@ -1023,6 +1459,7 @@ pub async fn list_recently_added_tracks(
     let per_page = params.per_page.unwrap_or(20).min(100);
 
     let paginator = track::Entity::find()
+        .filter(track::Column::IsHidden.eq(false))
         .order_by_desc(track::Column::CreatedAt)
         .paginate(&state.db, per_page);
 
@@ -1132,6 +1569,7 @@ pub async fn list_genres(
     let rows = track::Entity::find()
         .select_only()
         .column(track::Column::Genre)
+        .filter(track::Column::IsHidden.eq(false))
         .distinct()
         .into_model::<GenreRow>()
         .all(&state.db)
@@ -1160,6 +1598,7 @@ pub async fn list_genre_tracks(
 
     let paginator = track::Entity::find()
         .filter(track::Column::Genre.eq(genre))
+        .filter(track::Column::IsHidden.eq(false))
         .order_by(Expr::cust("RANDOM()"), Order::Asc)
         .paginate(&state.db, per_page);
 
@@ -1278,7 +1717,14 @@ mod tests {
             waveform_data: None,
             uploaded_by: Some(Uuid::new_v4()),
             play_count: 42,
+            is_private: false,
+            is_hidden: false,
+            hidden_by_block: false,
             content_hash: None,
+            fingerprint: None,
+            loudness_lufs: Some(-14.2),
+            dynamic_range: None,
+            encoding_quality: Some("CBR 320kbps".into()),
             created_at: Utc::now().fixed_offset(),
         }
     }
@@ -1307,6 +1753,9 @@ mod tests {
         // Optional None fields with skip_serializing_if should be absent
         assert!(json.get("artist_name").is_none());
         assert!(json.get("album_title").is_none());
+        assert!(json.get("dynamic_range").is_none());
+        assert_eq!(json["encoding_quality"], "CBR 320kbps");
+        assert!((json["loudness_lufs"].as_f64().unwrap() + 14.2).abs() < 1e-4);
     }
 
     #[test]
@@ -1326,6 +1775,179 @@ mod tests {
         assert_eq!(json["data"].as_array().unwrap().len(), 2);
     }
 
+    // ── TrackCursor ──
+
+    #[test]
+    fn test_track_cursor_round_trip() {
+        let model = make_track_model();
+        let cursor = TrackCursor::of(&model);
+        let encoded = cursor.encode();
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(TrackCursor::decode(&encoded), Some(cursor));
+    }
+
+    #[test]
+    fn test_track_cursor_is_base64_json() {
+        use base64::Engine;
+        let json = r#"{"created_at":"2026-01-01T12:00:00+00:00","id":"7f1c9e2a-0000-4000-8000-000000000001"}"#;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(json);
+        let cursor = TrackCursor::decode(&encoded.replace('+', "-").replace('/', "_")).unwrap();
+        assert_eq!(
+            cursor.id,
+            "7f1c9e2a-0000-4000-8000-000000000001"
+                .parse::<Uuid>()
+                .unwrap()
+        );
+        assert_eq!(cursor.created_at.to_rfc3339(), "2026-01-01T12:00:00+00:00");
+    }
+
+    #[test]
+    fn test_track_cursor_rejects_garbage() {
+        use base64::Engine;
+        assert_eq!(TrackCursor::decode("not a cursor!"), None);
+        assert_eq!(TrackCursor::decode(""), None);
+        let wrong = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(r#"{"id":1}"#);
+        assert_eq!(TrackCursor::decode(&wrong), None);
+    }
+
+    #[test]
+    fn test_seek_after_compares_created_at_then_id() {
+        use sea_orm::{DbBackend, QueryTrait};
+        let cursor = TrackCursor::of(&make_track_model());
+        let sql = seek_after(
+            track::Entity::find()
+                .order_by_desc(track::Column::CreatedAt)
+                .order_by_desc(track::Column::Id),
+            &cursor,
+        )
+        .build(DbBackend::Postgres)
+        .to_string();
+
+        let (_, seek) = sql.split_once("WHERE ").expect("query has a WHERE clause");
+        assert!(
+            seek.starts_with(r#"("tracks"."created_at", "tracks"."id") < ('"#),
+            "{sql}"
+        );
+        assert!(seek.contains(&cursor.id.to_string()), "{sql}");
+        assert!(
+            sql.ends_with(r#"ORDER BY "tracks"."created_at" DESC, "tracks"."id" DESC"#),
+            "{sql}"
+        );
+    }
+
+    /// GET `uri` from `list_tracks` backed by `db`.
+    async fn get_listing(db: &sea_orm::DatabaseConnection, uri: &str) -> serde_json::Value {
+        use axum::{body::Body, http::Request, routing::get, Router};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route("/tracks", get(list_tracks))
+            .with_state(crate::test_db::state(db.clone()));
+        let resp = app
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "{uri}");
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    fn page_ids(json: &serde_json::Value) -> Vec<Uuid> {
+        json["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["id"].as_str().unwrap().parse().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_cursor_pages_match_offset_pages() {
+        const TRACKS: usize = 1203;
+        let db = crate::test_db::connect().await;
+        crate::test_db::create_table(&db, track::Entity).await;
+        let base = Utc::now().fixed_offset();
+        // Groups of 4 tracks share a timestamp, so ordering relies on the id
+        let tracks: Vec<track::Model> = (0..TRACKS)
+            .map(|i| {
+                let mut t = make_track_model();
+                t.created_at = base - chrono::Duration::seconds((i / 4) as i64);
+                t
+            })
+            .collect();
+        for chunk in tracks.chunks(100) {
+            track::Entity::insert_many(chunk.iter().cloned().map(track::ActiveModel::from))
+                .exec(&db)
+                .await
+                .unwrap();
+        }
+        let mut expected: Vec<_> = tracks.iter().map(|t| (t.created_at, t.id)).collect();
+        expected.sort_by_key(|key| std::cmp::Reverse(*key));
+        let expected: Vec<Uuid> = expected.into_iter().map(|(_, id)| id).collect();
+
+        for per_page in [10, 100, 500] {
+            let mut offset = Vec::new();
+            for page in 1..=TRACKS.div_ceil(per_page) {
+                let json =
+                    get_listing(&db, &format!("/tracks?per_page={per_page}&page={page}")).await;
+                assert_eq!(json["total"], TRACKS);
+                offset.extend(page_ids(&json));
+            }
+            assert_eq!(offset, expected, "offset pages, per_page {per_page}");
+
+            let mut cursor = Vec::new();
+            let mut json = get_listing(&db, &format!("/tracks?per_page={per_page}")).await;
+            loop {
+                let ids = page_ids(&json);
+                assert!(ids.len() <= per_page);
+                cursor.extend(ids);
+                let Some(next) = json["next_cursor"].as_str() else {
+                    break;
+                };
+                json = get_listing(&db, &format!("/tracks?per_page={per_page}&after={next}")).await;
+                // Cursor pages are not counted
+                assert_eq!(json["total"], 0);
+            }
+            assert_eq!(cursor, expected, "cursor pages, per_page {per_page}");
+        }
+    }
+
+    #[test]
+    fn test_track_list_response_serialization() {
+        let page = PaginatedResponse {
+            data: vec![TrackResponse::from(make_track_model())],
+            total: 30,
+            page: 1,
+            per_page: 1,
+            total_pages: 30,
+        };
+        let json = serde_json::to_value(TrackListResponse {
+            page,
+            next_cursor: Some("abc".to_string()),
+        })
+        .unwrap();
+        assert_eq!(json["total"], 30);
+        assert_eq!(json["data"].as_array().unwrap().len(), 1);
+        assert_eq!(json["next_cursor"], "abc");
+
+        let json = serde_json::to_value(TrackListResponse {
+            page: PaginatedResponse {
+                data: Vec::new(),
+                total: 0,
+                page: 1,
+                per_page: 20,
+                total_pages: 0,
+            },
+            next_cursor: None,
+        })
+        .unwrap();
+        assert!(json.get("next_cursor").is_none());
+    }
+
     #[test]
     fn test_pagination_params_defaults() {
         let params: PaginationParams = serde_json::from_str("{}").unwrap();
@@ -1340,4 +1962,126 @@ mod tests {
         assert_eq!(params.page, Some(3));
         assert_eq!(params.per_page, Some(50));
     }
+
+    #[test]
+    fn test_list_tracks_params_min_quality() {
+        let params: ListTracksParams = serde_json::from_str("{}").unwrap();
+        assert!(params.min_quality.is_none());
+        assert!(params.after.is_none());
+
+        let params: ListTracksParams =
+            serde_json::from_str(r#"{"page": 2, "min_quality": "lossless"}"#).unwrap();
+        assert_eq!(params.page, Some(2));
+        assert_eq!(params.min_quality, Some(MinQuality::Lossless));
+
+        assert!(serde_json::from_str::<ListTracksParams>(r#"{"min_quality": "hifi"}"#).is_err());
+    }
+
+    #[test]
+    fn test_waveform_status() {
+        let ready = waveform_status(true, None);
+        assert!(ready.ready);
+        assert_eq!(ready.progress, 1.0);
+
+        let running = waveform_status(false, Some(0.4));
+        assert!(!running.ready);
+        assert_eq!(running.progress, 0.4);
+
+        let val = serde_json::to_value(waveform_status(false, None)).unwrap();
+        assert_eq!(val, serde_json::json!({ "ready": false, "progress": 0.0 }));
+    }
+
+    // ── set_track_privacy ──
+
+    async fn privacy_db() -> sea_orm::DatabaseConnection {
+        use soundtime_db::entities::user;
+        let db = crate::test_db::connect().await;
+        crate::test_db::create_table(&db, user::Entity).await;
+        crate::test_db::create_table(&db, track::Entity).await;
+        db
+    }
+
+    async fn put_privacy(
+        db: &sea_orm::DatabaseConnection,
+        as_user: &soundtime_db::entities::user::Model,
+        track_id: Uuid,
+        is_private: bool,
+    ) -> StatusCode {
+        use axum::{body::Body, http::Request, routing::put, Router};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route("/tracks/{id}/privacy", put(set_track_privacy))
+            .layer(Extension(crate::test_db::auth_user(as_user)))
+            .with_state(crate::test_db::state(db.clone()));
+        let req = Request::builder()
+            .method("PUT")
+            .uri(format!("/tracks/{track_id}/privacy"))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({ "is_private": is_private }).to_string(),
+            ))
+            .unwrap();
+        app.oneshot(req).await.unwrap().status()
+    }
+
+    async fn insert_track(db: &sea_orm::DatabaseConnection, uploader: Uuid) -> track::Model {
+        let mut model = make_track_model();
+        model.uploaded_by = Some(uploader);
+        track::ActiveModel::from(model).insert(db).await.unwrap()
+    }
+
+    async fn is_private(db: &sea_orm::DatabaseConnection, id: Uuid) -> bool {
+        track::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap()
+            .is_private
+    }
+
+    #[tokio::test]
+    async fn test_uploader_can_make_track_private_and_public() {
+        use soundtime_db::entities::user::UserRole;
+        let db = privacy_db().await;
+        let owner = crate::test_db::insert_user(&db, "owner", UserRole::User).await;
+        let t = insert_track(&db, owner.id).await;
+
+        assert_eq!(put_privacy(&db, &owner, t.id, true).await, StatusCode::OK);
+        assert!(is_private(&db, t.id).await);
+
+        assert_eq!(put_privacy(&db, &owner, t.id, false).await, StatusCode::OK);
+        assert!(!is_private(&db, t.id).await);
+    }
+
+    #[tokio::test]
+    async fn test_admin_can_make_any_track_private() {
+        use soundtime_db::entities::user::UserRole;
+        let db = privacy_db().await;
+        let owner = crate::test_db::insert_user(&db, "owner", UserRole::User).await;
+        let admin = crate::test_db::insert_user(&db, "admin", UserRole::Admin).await;
+        let t = insert_track(&db, owner.id).await;
+
+        assert_eq!(put_privacy(&db, &admin, t.id, true).await, StatusCode::OK);
+        assert!(is_private(&db, t.id).await);
+    }
+
+    #[tokio::test]
+    async fn test_other_user_cannot_change_privacy() {
+        use soundtime_db::entities::user::UserRole;
+        let db = privacy_db().await;
+        let owner = crate::test_db::insert_user(&db, "owner", UserRole::User).await;
+        let other = crate::test_db::insert_user(&db, "other", UserRole::User).await;
+        let t = insert_track(&db, owner.id).await;
+
+        assert_eq!(
+            put_privacy(&db, &other, t.id, true).await,
+            StatusCode::FORBIDDEN
+        );
+        assert!(!is_private(&db, t.id).await);
+        assert_eq!(
+            put_privacy(&db, &owner, Uuid::new_v4(), true).await,
+            StatusCode::NOT_FOUND
+        );
+    }
 }
@@ -0,0 +1,618 @@
+//! Blocklist subscription worker — keeps `blocked_domains` in step with
+//! remote blocklists.
+//!
+//! Every `BLOCKLIST_REFRESH_INTERVAL_SECS` (6 hours by default), or when an
+//! admin adds a subscription, each enabled subscription is fetched with
+//! `If-None-Match` and parsed as the JSON array written by
+//! `GET /api/admin/blocked-domains/export`. The list is then merged:
+//!
+//! - domains that are not blocked yet are blocked, attributed to the
+//!   subscription through `source_subscription_id`;
+//! - domains the subscription blocked but no longer lists are unblocked;
+//! - manual blocks are never touched, and domains an admin unblocked by hand
+//!   (`blocklist_overrides`) are never blocked again — the local admin wins.
+//!
+//! A failed fetch leaves the subscription's blocks as they are.
+
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use soundtime_db::entities::{blocked_domain, blocklist_override, blocklist_subscription};
+use soundtime_db::AppState;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// Default interval between refreshes of every subscription (6 hours).
+const DEFAULT_REFRESH_INTERVAL_SECS: u64 = 21_600;
+
+/// Largest blocklist body we accept (5 MiB).
+const MAX_BLOCKLIST_BYTES: usize = 5 * 1024 * 1024;
+
+/// Shared notifier so the admin API can wake up the worker immediately.
+static REFRESH_NOTIFY: std::sync::LazyLock<Notify> = std::sync::LazyLock::new(Notify::new);
+
+/// One entry of a remote blocklist. Extra fields of the export format
+/// (`id`, `created_at`, ...) are ignored.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemoteBlockEntry {
+    pub domain: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// RFC 3339 expiry of a time-limited block
+    #[serde(default)]
+    pub expires_at: Option<String>,
+}
+
+/// A block a subscription adds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewBlock {
+    pub domain: String,
+    pub reason: Option<String>,
+    pub expires_at: Option<DateTime<FixedOffset>>,
+}
+
+/// Changes needed to bring a subscription's blocks in line with its list.
+#[derive(Debug, Default, PartialEq)]
+pub struct MergePlan {
+    pub add: Vec<NewBlock>,
+    /// `blocked_domains` rows to delete
+    pub remove: Vec<Uuid>,
+}
+
+/// Result of refreshing one subscription.
+#[derive(Debug, Clone, Serialize)]
+pub struct RefreshOutcome {
+    /// `ok`, or `not_modified` when the list is unchanged since the last fetch
+    pub status: &'static str,
+    /// Entries in the upstream list, `None` when it was not modified
+    pub entries: Option<usize>,
+    pub added: usize,
+    pub removed: usize,
+}
+
+enum FetchResult {
+    NotModified,
+    Fetched {
+        entries: Vec<RemoteBlockEntry>,
+        etag: Option<String>,
+    },
+}
+
+/// Work out which blocks `subscription_id` must add and remove so that it
+/// blocks exactly the live entries of `upstream`. Blocks from elsewhere —
+/// manual or another subscription — are left alone, and `overrides` are
+/// never blocked. `existing` must not hold lapsed blocks.
+pub fn plan_merge(
+    subscription_id: Uuid,
+    upstream: &[RemoteBlockEntry],
+    existing: &[blocked_domain::Model],
+    overrides: &HashSet<String>,
+    now: DateTime<Utc>,
+) -> MergePlan {
+    let mut listed = HashSet::new();
+    let mut wanted = Vec::new();
+    for entry in upstream {
+        let domain = entry.domain.trim().to_lowercase();
+        if domain.is_empty() || overrides.contains(&domain) || listed.contains(&domain) {
+            continue;
+        }
+        let expires_at = match entry.expires_at.as_deref() {
+            Some(raw) => match DateTime::parse_from_rfc3339(raw) {
+                Ok(parsed) => Some(parsed),
+                Err(_) => continue,
+            },
+            None => None,
+        };
+        if !soundtime_p2p::blocked::is_block_active(expires_at, now) {
+            continue;
+        }
+        listed.insert(domain.clone());
+        wanted.push(NewBlock {
+            domain,
+            reason: entry.reason.clone(),
+            expires_at,
+        });
+    }
+
+    let blocked: HashMap<&str, &blocked_domain::Model> =
+        existing.iter().map(|b| (b.domain.as_str(), b)).collect();
+
+    let add = wanted
+        .into_iter()
+        .filter(|b| !blocked.contains_key(b.domain.as_str()))
+        .collect();
+    let remove = existing
+        .iter()
+        .filter(|b| b.source_subscription_id == Some(subscription_id))
+        .filter(|b| !listed.contains(&b.domain))
+        .map(|b| b.id)
+        .collect();
+
+    MergePlan { add, remove }
+}
+
+/// HTTP client used to fetch blocklists.
+pub fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .user_agent(format!("SoundTime/{}", soundtime_p2p::build_version()))
+        .build()
+        .map_err(|e| format!("failed to build HTTP client: {e}"))
+}
+
+async fn fetch_blocklist(
+    client: &reqwest::Client,
+    url: &str,
+    etag: Option<&str>,
+) -> Result<FetchResult, String> {
+    let mut request = client.get(url);
+    if let Some(etag) = etag {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    let mut resp = request
+        .send()
+        .await
+        .map_err(|e| format!("HTTP request failed: {e}"))?;
+
+    let status = resp.status();
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(FetchResult::NotModified);
+    }
+    if !status.is_success() {
+        return Err(format!("HTTP {status}"));
+    }
+    if resp
+        .content_length()
+        .is_some_and(|len| len > MAX_BLOCKLIST_BYTES as u64)
+    {
+        return Err(format!("blocklist larger than {MAX_BLOCKLIST_BYTES} bytes"));
+    }
+
+    let etag = resp
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    // The length header is optional (chunked responses) and may lie, so
+    // stop reading as soon as the body outgrows the limit
+    let mut body = Vec::new();
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| format!("failed to read body: {e}"))?
+    {
+        if body.len() + chunk.len() > MAX_BLOCKLIST_BYTES {
+            return Err(format!("blocklist larger than {MAX_BLOCKLIST_BYTES} bytes"));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let entries = serde_json::from_slice(&body).map_err(|e| format!("invalid blocklist: {e}"))?;
+
+    Ok(FetchResult::Fetched { entries, etag })
+}
+
+/// Fetch one subscription, merge its list and record the result on it.
+pub async fn refresh_subscription(
+    state: &AppState,
+    client: &reqwest::Client,
+    subscription: &blocklist_subscription::Model,
+) -> Result<RefreshOutcome, String> {
+    let result = fetch_and_merge(state, client, subscription).await;
+
+    let mut update: blocklist_subscription::ActiveModel = subscription.clone().into();
+    update.last_fetched = Set(Some(Utc::now().into()));
+    match &result {
+        Ok((outcome, etag)) => {
+            update.last_status = Set(Some(outcome.status.to_string()));
+            update.last_error = Set(None);
+            if let Some(entries) = outcome.entries {
+                update.etag = Set(etag.clone());
+                update.entry_count = Set(entries as i32);
+            }
+        }
+        Err(e) => {
+            update.last_status = Set(Some("error".to_string()));
+            update.last_error = Set(Some(e.clone()));
+        }
+    }
+    if let Err(e) = update.update(&state.db).await {
+        tracing::warn!(url = %subscription.url, "failed to record blocklist fetch result: {e}");
+    }
+
+    result.map(|(outcome, _)| outcome)
+}
+
+async fn fetch_and_merge(
+    state: &AppState,
+    client: &reqwest::Client,
+    subscription: &blocklist_subscription::Model,
+) -> Result<(RefreshOutcome, Option<String>), String> {
+    let (entries, etag) =
+        match fetch_blocklist(client, &subscription.url, subscription.etag.as_deref()).await? {
+            FetchResult::NotModified => {
+                return Ok((
+                    RefreshOutcome {
+                        status: "not_modified",
+                        entries: None,
+                        added: 0,
+                        removed: 0,
+                    },
+                    None,
+                ))
+            }
+            FetchResult::Fetched { entries, etag } => (entries, etag),
+        };
+
+    // Lapsed blocks would otherwise hold their domain against the merge
+    soundtime_p2p::blocked::purge_expired_blocks(&state.db)
+        .await
+        .map_err(|e| format!("DB: {e}"))?;
+
+    let existing = blocked_domain::Entity::find()
+        .all(&state.db)
+        .await
+        .map_err(|e| format!("DB: {e}"))?;
+    let overrides: HashSet<String> = blocklist_override::Entity::find()
+        .all(&state.db)
+        .await
+        .map_err(|e| format!("DB: {e}"))?
+        .into_iter()
+        .map(|o| o.domain)
+        .collect();
+
+    let plan = plan_merge(subscription.id, &entries, &existing, &overrides, Utc::now());
+    let (added, removed) = apply_plan(state, subscription.id, plan)
+        .await
+        .map_err(|e| format!("DB: {e}"))?;
+
+    tracing::info!(
+        url = %subscription.url,
+        entries = entries.len(),
+        added,
+        removed,
+        "blocklist subscription refreshed"
+    );
+    Ok((
+        RefreshOutcome {
+            status: "ok",
+            entries: Some(entries.len()),
+            added,
+            removed,
+        },
+        etag,
+    ))
+}
+
+async fn apply_plan(
+    state: &AppState,
+    subscription_id: Uuid,
+    plan: MergePlan,
+) -> Result<(usize, usize), sea_orm::DbErr> {
+    let txn = state.db.begin().await?;
+    let now = Utc::now();
+
+    let mut added = 0;
+    for block in plan.add {
+        let row = blocked_domain::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            domain: Set(block.domain),
+            reason: Set(block.reason),
+            blocked_by: Set(None),
+            created_at: Set(now.into()),
+            expires_at: Set(block.expires_at),
+            source_subscription_id: Set(Some(subscription_id)),
+        };
+        // A manual block may have landed since the plan was made
+        added += blocked_domain::Entity::insert(row)
+            .on_conflict(
+                OnConflict::column(blocked_domain::Column::Domain)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(&txn)
+            .await? as usize;
+    }
+
+    let mut removed = 0;
+    if !plan.remove.is_empty() {
+        removed = blocked_domain::Entity::delete_many()
+            .filter(blocked_domain::Column::Id.is_in(plan.remove))
+            .filter(blocked_domain::Column::SourceSubscriptionId.eq(subscription_id))
+            .exec(&txn)
+            .await?
+            .rows_affected as usize;
+    }
+
+    txn.commit().await?;
+    Ok((added, removed))
+}
+
+/// Refresh every enabled subscription.
+async fn refresh_all(state: &AppState, client: &reqwest::Client) {
+    let subscriptions = match blocklist_subscription::Entity::find()
+        .filter(blocklist_subscription::Column::Enabled.eq(true))
+        .all(&state.db)
+        .await
+    {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("failed to load blocklist subscriptions: {e}");
+            return;
+        }
+    };
+
+    for subscription in &subscriptions {
+        if let Err(e) = refresh_subscription(state, client, subscription).await {
+            tracing::warn!(url = %subscription.url, "blocklist subscription refresh failed: {e}");
+        }
+    }
+}
+
+/// Wake the worker so it refreshes every subscription now.
+pub fn trigger() {
+    REFRESH_NOTIFY.notify_one();
+}
+
+fn refresh_interval_secs() -> u64 {
+    std::env::var("BLOCKLIST_REFRESH_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(DEFAULT_REFRESH_INTERVAL_SECS)
+}
+
+pub fn spawn(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let interval = refresh_interval_secs();
+        tracing::info!(
+            interval_secs = interval,
+            "blocklist subscription worker started"
+        );
+
+        // Let the server finish starting before the first fetch
+        tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+
+        let client = match http_client() {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::error!("blocklist subscription worker: {e}");
+                return;
+            }
+        };
+
+        loop {
+            refresh_all(&state, &client).await;
+
+            tokio::select! {
+                _ = tokio::time::sleep(std::time::Duration::from_secs(interval)) => {},
+                _ = REFRESH_NOTIFY.notified() => {
+                    tracing::info!("blocklist subscription refresh triggered");
+                },
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    // ─── Helper ─────────────────────────────────────────────────────
+
+    fn entry(domain: &str) -> RemoteBlockEntry {
+        RemoteBlockEntry {
+            domain: domain.to_string(),
+            reason: None,
+            expires_at: None,
+        }
+    }
+
+    fn block(domain: &str, source: Option<Uuid>) -> blocked_domain::Model {
+        blocked_domain::Model {
+            id: Uuid::new_v4(),
+            domain: domain.to_string(),
+            reason: None,
+            blocked_by: None,
+            created_at: Utc::now().into(),
+            expires_at: None,
+            source_subscription_id: source,
+        }
+    }
+
+    fn domains(plan: &MergePlan) -> Vec<&str> {
+        plan.add.iter().map(|b| b.domain.as_str()).collect()
+    }
+
+    // ─── plan_merge ─────────────────────────────────────────────────
+
+    #[test]
+    fn merge_adds_new_domains_normalised_and_deduplicated() {
+        let sub = Uuid::new_v4();
+        let upstream = [entry(" Bad.Example "), entry("bad.example"), entry("")];
+        let plan = plan_merge(sub, &upstream, &[], &HashSet::new(), Utc::now());
+        assert_eq!(domains(&plan), vec!["bad.example"]);
+        assert!(plan.remove.is_empty());
+    }
+
+    #[test]
+    fn merge_leaves_existing_blocks_alone() {
+        let sub = Uuid::new_v4();
+        let manual = block("manual.example", None);
+        let other = block("other.example", Some(Uuid::new_v4()));
+        let upstream = [entry("manual.example"), entry("other.example")];
+        let plan = plan_merge(
+            sub,
+            &upstream,
+            &[manual, other],
+            &HashSet::new(),
+            Utc::now(),
+        );
+        assert_eq!(plan, MergePlan::default());
+    }
+
+    #[test]
+    fn merge_removes_only_own_blocks_missing_upstream() {
+        let sub = Uuid::new_v4();
+        let own_listed = block("still.example", Some(sub));
+        let own_dropped = block("gone.example", Some(sub));
+        let manual = block("manual.example", None);
+        let other = block("other.example", Some(Uuid::new_v4()));
+        let dropped_id = own_dropped.id;
+
+        let plan = plan_merge(
+            sub,
+            &[entry("still.example")],
+            &[own_listed, own_dropped, manual, other],
+            &HashSet::new(),
+            Utc::now(),
+        );
+        assert!(plan.add.is_empty());
+        assert_eq!(plan.remove, vec![dropped_id]);
+    }
+
+    #[test]
+    fn merge_never_blocks_overridden_domains() {
+        let sub = Uuid::new_v4();
+        let own = block("unblocked.example", Some(sub));
+        let own_id = own.id;
+        let overrides = HashSet::from(["unblocked.example".to_string()]);
+
+        let plan = plan_merge(
+            sub,
+            &[entry("unblocked.example"), entry("Unblocked.Example")],
+            &[own],
+            &overrides,
+            Utc::now(),
+        );
+        assert!(plan.add.is_empty());
+        // A leftover block of ours for an overridden domain goes too
+        assert_eq!(plan.remove, vec![own_id]);
+    }
+
+    #[test]
+    fn merge_keeps_expiry_and_skips_lapsed_or_invalid_entries() {
+        let sub = Uuid::new_v4();
+        let now = Utc::now();
+        let mut timed = entry("timed.example");
+        timed.expires_at = Some((now + chrono::Duration::hours(1)).to_rfc3339());
+        let mut lapsed = entry("lapsed.example");
+        lapsed.expires_at = Some((now - chrono::Duration::hours(1)).to_rfc3339());
+        let mut invalid = entry("invalid.example");
+        invalid.expires_at = Some("next tuesday".to_string());
+
+        let plan = plan_merge(sub, &[timed, lapsed, invalid], &[], &HashSet::new(), now);
+        assert_eq!(domains(&plan), vec!["timed.example"]);
+        assert!(plan.add[0].expires_at.is_some());
+    }
+
+    #[test]
+    fn remote_entry_accepts_export_format() {
+        let json = r#"[{"id":"6f1c2c1e-8d1e-4d7b-9d55-1f0c8a3b2e11","domain":"evil.example",
+            "reason":"spam","created_at":"2024-01-01T00:00:00+00:00",
+            "expires_at":null,"remaining_secs":null,"source":null}]"#;
+        let entries: Vec<RemoteBlockEntry> = serde_json::from_str(json).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].domain, "evil.example");
+        assert_eq!(entries[0].reason.as_deref(), Some("spam"));
+    }
+
+    // ─── fetch_blocklist ────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn fetch_returns_entries_and_etag() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/blocklist.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_json(serde_json::json!([{ "domain": "evil.example" }])),
+            )
+            .mount(&server)
+            .await;
+
+        let client = http_client().unwrap();
+        let url = format!("{}/blocklist.json", server.uri());
+        match fetch_blocklist(&client, &url, None).await.unwrap() {
+            FetchResult::Fetched { entries, etag } => {
+                assert_eq!(entries.len(), 1);
+                assert_eq!(etag.as_deref(), Some("\"v1\""));
+            }
+            FetchResult::NotModified => panic!("expected a fetched list"),
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_sends_etag_and_handles_not_modified() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .mount(&server)
+            .await;
+
+        let client = http_client().unwrap();
+        let result = fetch_blocklist(&client, &server.uri(), Some("\"v1\""))
+            .await
+            .unwrap();
+        assert!(matches!(result, FetchResult::NotModified));
+    }
+
+    #[tokio::test]
+    async fn fetch_rejects_errors_and_invalid_bodies() {
+        let server = MockServer::start().await;
+        Mock::given(path("/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(path("/garbage"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html>"))
+            .mount(&server)
+            .await;
+
+        let client = http_client().unwrap();
+        let err = fetch_blocklist(&client, &format!("{}/missing", server.uri()), None)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.contains("404"));
+        let err = fetch_blocklist(&client, &format!("{}/garbage", server.uri()), None)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.contains("invalid blocklist"));
+    }
+
+    #[tokio::test]
+    async fn fetch_stops_reading_oversized_chunked_body() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // No Content-Length, and a body that never ends
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+            let head = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                        Transfer-Encoding: chunked\r\n\r\n";
+            if socket.write_all(head.as_bytes()).await.is_err() {
+                return;
+            }
+            let chunk = format!("10000\r\n{}\r\n", " ".repeat(0x10000));
+            while socket.write_all(chunk.as_bytes()).await.is_ok() {}
+        });
+
+        let client = http_client().unwrap();
+        let err = fetch_blocklist(&client, &format!("http://{addr}/blocklist.json"), None)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.contains("larger than"), "{err}");
+    }
+}
@@ -24,13 +24,19 @@ use tracing_subscriber::util::SubscriberInitExt;
 
 mod api;
 mod auth;
+mod blocklist_worker;
 #[allow(dead_code)] // Public API for future recommendation endpoints (Phase 4.5+)
 mod embeddings;
 mod listing_worker;
 pub mod metadata_lookup;
+mod metrics;
 mod p2p_logs;
+mod plugin_hooks;
 mod storage_worker;
+#[cfg(test)]
+mod test_db;
 mod trending;
+mod waveform_worker;
 
 #[derive(Serialize)]
 struct ApiStatus {
@@ -116,13 +122,14 @@ async fn main() {
             let prefix = std::env::var("S3_PREFIX").unwrap_or_default();
 
             Arc::new(
-                soundtime_audio::S3Storage::from_config(
+                soundtime_audio::S3Storage::from_config_with_options(
                     endpoint.as_deref(),
                     &region,
                     &access_key,
                     &secret_key,
                     &bucket,
                     &prefix,
+                    soundtime_audio::S3Options::from_env(),
                 )
                 .await
                 .expect("failed to initialize S3 storage"),
@@ -224,6 +231,26 @@ async fn main() {
         }
     };
 
+    // Kept to say goodbye to peers on shutdown
+    let p2p_node = p2p
+        .as_ref()
+        .and_then(|any| any.clone().downcast::<soundtime_p2p::P2pNode>().ok());
+
+    // Let plugins hear about tracks replicated from peers and peers coming
+    // and going, and search the network
+    let plugin_registry = plugins.as_ref().and_then(|any| {
+        any.clone()
+            .downcast::<soundtime_plugin::PluginRegistry>()
+            .ok()
+    });
+    if let (Some(node), Some(registry)) = (&p2p_node, plugin_registry) {
+        registry.set_p2p_search(Arc::new(plugin_hooks::PluginP2pSearch::new(node.clone())));
+        plugin_hooks::spawn_peer_presence_events(node, registry.clone());
+        node.set_track_announced_hook(Arc::new(plugin_hooks::PluginTrackAnnouncedHook::new(
+            registry,
+        )));
+    }
+
     let state = Arc::new(AppState {
         db,
         jwt_secret,
@@ -244,6 +271,9 @@ async fn main() {
     // Spawn the listing heartbeat worker (announces to public directory)
     listing_worker::spawn(state.clone());
 
+    // Spawn the blocklist subscription worker (merges remote blocklists)
+    blocklist_worker::spawn(state.clone());
+
     // Backfill track embeddings (best-effort background task)
     {
         let db = state.db.clone();
@@ -266,6 +296,15 @@ async fn main() {
     // Task tracker for async storage operations (sync, integrity check)
     let storage_task_tracker = storage_worker::new_tracker();
     let sync_task_tracker = soundtime_p2p::new_sync_tracker();
+    // Start library re-syncs on the admin's cron schedule
+    if let Some(node) = &p2p_node {
+        soundtime_p2p::spawn_sync_scheduler(
+            node.clone(),
+            state.db.clone(),
+            sync_task_tracker.clone(),
+        );
+    }
+    let health_sweep_tracker = soundtime_p2p::new_health_sweep_tracker();
     // Shared tracker for the background metadata enrichment task.
     // Installed as an Axum Extension on the /admin/metadata routes so
     // the spawned tokio task and HTTP handlers share the same state.
@@ -335,6 +374,10 @@ async fn main() {
         )
         .route("/tracks/{id}", get(api::tracks::get_track))
         .route("/tracks/{id}/credits", get(api::tracks::get_track_credits))
+        .route(
+            "/tracks/{id}/waveform-status",
+            get(api::tracks::get_waveform_status),
+        )
         .route("/tracks/{id}/stream", get(api::audio::stream_track))
         .route("/tracks/{id}/lyrics", get(api::lyrics::get_track_lyrics))
         .route("/media/{*path}", get(api::audio::serve_media))
@@ -413,6 +456,14 @@ async fn main() {
         )
         .route("/history/recent", get(api::history::list_recent_history))
         .route("/tracks/{id}/report", post(api::reports::report_track))
+        .route(
+            "/tracks/{id}/pin",
+            post(api::tracks::pin_track).delete(api::tracks::unpin_track),
+        )
+        .route(
+            "/tracks/{id}/privacy",
+            axum::routing::put(api::tracks::set_track_privacy),
+        )
         .route("/lastfm/status", get(api::lastfm::lastfm_status))
         .route("/lastfm/connect", get(api::lastfm::lastfm_connect))
         .route("/lastfm/callback", post(api::lastfm::lastfm_callback))
@@ -439,6 +490,18 @@ async fn main() {
         .route("/p2p/status", get(api::p2p::p2p_status))
         .route("/p2p/network-graph", get(api::p2p::network_graph))
         .route("/p2p/search", get(api::p2p::network_search))
+        // Live P2P events (admin only: they include peers' search queries).
+        // Kept for existing clients; also served at /api/admin/p2p/events.
+        // Live peer status lists every known peer, so it is admin only too
+        .merge(
+            Router::new()
+                .route("/p2p/events", get(api::p2p::p2p_events))
+                .route("/p2p/ws", get(api::p2p::peer_status_ws))
+                .layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    auth::middleware::require_admin,
+                )),
+        )
         // Public theme routes
         .route("/themes/active", get(api::themes::get_active_theme))
         .route("/themes/active.css", get(api::themes::serve_active_css))
@@ -471,6 +534,20 @@ async fn main() {
                     "/blocked-domains/{id}",
                     axum::routing::delete(api::admin::unblock_domain),
                 )
+                .route(
+                    "/blocklist-subscriptions",
+                    get(api::admin::list_blocklist_subscriptions)
+                        .post(api::admin::add_blocklist_subscription),
+                )
+                .route(
+                    "/blocklist-subscriptions/{id}",
+                    axum::routing::put(api::admin::update_blocklist_subscription)
+                        .delete(api::admin::remove_blocklist_subscription),
+                )
+                .route(
+                    "/blocklist-subscriptions/{id}/refresh",
+                    post(api::admin::refresh_blocklist_subscription),
+                )
                 .route("/instances", get(api::admin::list_instances))
                 .route(
                     "/instances/health-check",
@@ -508,6 +585,7 @@ async fn main() {
                     "/users/{id}/ban",
                     axum::routing::put(api::admin::ban_user).delete(api::admin::unban_user),
                 )
+                .route("/audit-log", get(api::audit::list_audit_log))
                 .route("/editorial/status", get(api::editorial::editorial_status))
                 .route(
                     "/editorial/generate",
@@ -520,6 +598,7 @@ async fn main() {
                     axum::routing::put(api::reports::resolve_report),
                 )
                 .route("/tracks/browse", get(api::reports::browse_tracks))
+                .route("/tracks/duplicates", get(api::admin::list_duplicate_tracks))
                 .route(
                     "/tracks/{id}/moderate",
                     axum::routing::delete(api::reports::moderate_track),
@@ -533,7 +612,19 @@ async fn main() {
                     "/storage/integrity-check",
                     post(api::admin::run_integrity_check),
                 )
+                .route(
+                    "/storage/integrity-check/stream",
+                    get(api::admin::integrity_check_stream),
+                )
                 .route("/storage/sync", post(api::admin::run_storage_sync))
+                .route(
+                    "/storage/migrate-sharding",
+                    post(api::admin::migrate_storage_sharding),
+                )
+                .route(
+                    "/storage/extract-embedded-covers",
+                    post(api::admin::extract_embedded_covers),
+                )
                 .route("/storage/task-status", get(api::admin::storage_task_status))
                 .layer(Extension(storage_task_tracker))
                 // P2P admin routes
@@ -543,10 +634,62 @@ async fn main() {
                 )
                 .route(
                     "/p2p/peers/{node_id}",
-                    axum::routing::delete(api::p2p::remove_peer),
+                    axum::routing::delete(api::p2p::remove_peer).put(api::p2p::update_peer),
+                )
+                .route(
+                    "/p2p/seeds",
+                    get(api::p2p::list_seeds).post(api::p2p::add_seed),
+                )
+                .route(
+                    "/p2p/seeds/{node_id}",
+                    axum::routing::delete(api::p2p::remove_seed),
                 )
+                .route("/p2p/events", get(api::p2p::p2p_events))
                 .route("/p2p/peers/{node_id}/ping", post(api::p2p::ping_peer))
+                .route(
+                    "/p2p/peers/{node_id}/sync",
+                    post(api::p2p::sync_catalog_to_peer),
+                )
+                .route(
+                    "/p2p/peers/{node_id}/sync-status",
+                    get(api::p2p::peer_sync_status),
+                )
+                .route(
+                    "/p2p/peers/{node_id}/sync-checkpoint",
+                    get(api::p2p::peer_sync_checkpoint),
+                )
                 // P2P library sync routes
+                .route("/p2p/rejected", get(api::p2p::rejected_announcements))
+                .route("/p2p/evicted-peers", get(api::p2p::evicted_peers))
+                .route(
+                    "/p2p/search/query-history",
+                    get(api::p2p::search_query_history),
+                )
+                .route(
+                    "/p2p/peers/{node_id}/trusted-moderator",
+                    axum::routing::put(api::p2p::set_trusted_moderator),
+                )
+                .route(
+                    "/p2p/peers/{node_id}/grant-access",
+                    post(api::p2p::grant_track_access),
+                )
+                .route(
+                    "/p2p/peers/{node_id}/filters",
+                    get(api::p2p::get_peer_filter)
+                        .put(api::p2p::put_peer_filter)
+                        .delete(api::p2p::delete_peer_filter),
+                )
+                // P2P moderation routes
+                .route("/p2p/block-hash", post(api::p2p::block_hash))
+                .route("/p2p/blocked-hashes", get(api::p2p::list_blocked_hashes))
+                .route(
+                    "/p2p/blocked-hashes/{hash}/approve",
+                    post(api::p2p::approve_blocked_hash),
+                )
+                .route(
+                    "/p2p/blocked-hashes/{hash}",
+                    axum::routing::delete(api::p2p::unblock_hash),
+                )
                 .route("/p2p/library-sync", get(api::p2p::library_sync_overview))
                 .route(
                     "/p2p/library-sync/task-status",
@@ -560,6 +703,22 @@ async fn main() {
                     "/p2p/library-sync/{node_id}",
                     post(api::p2p::trigger_library_resync),
                 )
+                .route(
+                    "/p2p/sync/schedule",
+                    get(api::p2p::get_sync_schedule).put(api::p2p::update_sync_schedule),
+                )
+                .route(
+                    "/p2p/sync/{task_id}/pause",
+                    post(api::p2p::pause_library_sync),
+                )
+                .route(
+                    "/p2p/sync/{task_id}/resume",
+                    post(api::p2p::resume_library_sync),
+                )
+                .route(
+                    "/p2p/sync/{task_id}/cancel",
+                    post(api::p2p::cancel_library_sync),
+                )
                 .layer(Extension(sync_task_tracker))
                 // P2P remote track admin routes
                 .route(
@@ -570,6 +729,31 @@ async fn main() {
                     "/p2p/tracks/{id}/rereference",
                     axum::routing::patch(api::p2p::rereference_remote_track),
                 )
+                // `{id}` is the content hash here; axum needs one name per segment
+                .route(
+                    "/p2p/tracks/{id}/repair",
+                    post(api::p2p::repair_remote_track),
+                )
+                .route("/p2p/health", get(api::p2p::health_overview))
+                .route("/p2p/health/sweep", post(api::p2p::trigger_health_sweep))
+                .route(
+                    "/p2p/health/sweep/status",
+                    get(api::p2p::health_sweep_status),
+                )
+                .route("/p2p/health/history", get(api::p2p::health_history))
+                .route("/p2p/health/current", get(api::p2p::current_health))
+                .route("/p2p/health/recovery-log", get(api::p2p::recovery_log))
+                .route(
+                    "/p2p/health/re-reference",
+                    post(api::p2p::bulk_re_reference),
+                )
+                .route("/p2p/gc", post(api::p2p::collect_blob_garbage))
+                .route(
+                    "/p2p/cache",
+                    axum::routing::put(api::p2p::set_blob_cache_limit),
+                )
+                .route("/p2p/cache/stats", get(api::p2p::blob_cache_stats))
+                .layer(Extension(health_sweep_tracker))
                 // Plugin admin routes
                 .route("/plugins", get(api::plugins::list_plugins))
                 .merge(
@@ -584,6 +768,8 @@ async fn main() {
                     axum::routing::delete(api::plugins::uninstall_plugin),
                 )
                 .route("/plugins/{id}/update", post(api::plugins::update_plugin))
+                .route("/plugins/{id}/reload", post(api::plugins::reload_plugin))
+                .route("/plugins/events", get(api::plugins::plugin_events))
                 .route(
                     "/plugins/{id}/config",
                     get(api::plugins::get_config).put(api::plugins::update_config),
@@ -660,14 +846,31 @@ async fn main() {
         }
     };
 
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/healthz", get(healthz))
         // Well-known nodeinfo alias — used by other instances for health checks
-        .route(
-            "/.well-known/nodeinfo",
-            get(api::admin::nodeinfo),
-        )
-        .nest("/api", api_routes)
+        .route("/.well-known/nodeinfo", get(api::admin::nodeinfo));
+
+    // Prometheus scrape endpoint — guarded by METRICS_TOKEN when set
+    let metrics_enabled = metrics::metrics_enabled();
+    if metrics_enabled {
+        let token = metrics::metrics_token();
+        if token.is_some() {
+            tracing::info!("Prometheus metrics enabled at /metrics (bearer token required)");
+        } else {
+            tracing::warn!(
+                "Prometheus metrics enabled at /metrics without METRICS_TOKEN — restrict access at the proxy"
+            );
+        }
+        app = app.merge(metrics::routes(token));
+    }
+
+    let mut app = app.nest("/api", api_routes);
+    if metrics_enabled {
+        app = app.layer(axum_middleware::from_fn(metrics::track_http_metrics));
+    }
+
+    let app = app
         .layer(TraceLayer::new_for_http())
         .layer(cors)
         // Security headers
@@ -720,10 +923,48 @@ async fn main() {
         tokio::net::TcpListener::bind(addr).await.unwrap(),
         app.into_make_service_with_connect_info::<SocketAddr>(),
     )
+    .with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        // Tell peers we are going away before HTTP connections drain
+        if let Some(node) = p2p_node {
+            node.shutdown().await;
+        }
+    })
     .await
     .unwrap();
 }
 
+/// Resolve on Ctrl+C or SIGTERM (what `docker stop` sends).
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("failed to listen for Ctrl+C: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("failed to listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("shutdown signal received");
+}
+
 async fn healthz() -> Json<ApiStatus> {
     Json(ApiStatus {
         status: "ok",
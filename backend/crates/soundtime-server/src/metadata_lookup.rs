@@ -760,6 +760,264 @@ pub async fn enrich_track(
     }
 }
 
+// ─── Artist & album enrichment ──────────────────────────────────────
+
+/// What enrichment did to the artist or album of a track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityEnrichmentStatus {
+    /// Missing metadata was found and saved
+    Enriched,
+    /// Nothing was missing
+    AlreadyEnriched,
+    /// MusicBrainz had nothing to add
+    NotFound,
+    /// The track has no artist or album to enrich
+    NoEntity,
+    Error,
+}
+
+/// Result of enriching a track and its artist and album.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackEnrichment {
+    #[serde(flatten)]
+    pub track: MetadataResult,
+    pub artist_status: EntityEnrichmentStatus,
+    pub album_status: EntityEnrichmentStatus,
+}
+
+/// Whether `name` is a placeholder given to tracks without tags, which
+/// should not be searched for.
+fn is_placeholder_name(name: &str) -> bool {
+    matches!(name, "Unknown Artist" | "Unknown Album" | "Inconnu")
+}
+
+/// Enrich a track with [`enrich_track`], then look up whatever its artist
+/// and album still lack: the artist by name, the album as a release by
+/// title and artist.
+pub async fn enrich_track_and_entities(
+    db: &DatabaseConnection,
+    storage: &dyn StorageBackend,
+    track_id: Uuid,
+) -> TrackEnrichment {
+    let track = enrich_track(db, storage, track_id).await;
+    let (artist_status, album_status) = enrich_track_entities(db, storage, track_id).await;
+    TrackEnrichment {
+        track,
+        artist_status,
+        album_status,
+    }
+}
+
+/// Fill in the MusicBrainz ID, bio and image of the artist of `track_id`,
+/// and the MusicBrainz ID, release date and cover of its album.
+async fn enrich_track_entities(
+    db: &DatabaseConnection,
+    storage: &dyn StorageBackend,
+    track_id: Uuid,
+) -> (EntityEnrichmentStatus, EntityEnrichmentStatus) {
+    use EntityEnrichmentStatus::{Error, NoEntity};
+
+    let track_model = match track::Entity::find_by_id(track_id).one(db).await {
+        Ok(Some(t)) => t,
+        Ok(None) => return (NoEntity, NoEntity),
+        Err(e) => {
+            tracing::warn!(error = %e, %track_id, "failed to load track for enrichment");
+            return (Error, Error);
+        }
+    };
+    let artist_model = match artist::Entity::find_by_id(track_model.artist_id)
+        .one(db)
+        .await
+    {
+        Ok(Some(a)) => a,
+        Ok(None) => return (NoEntity, NoEntity),
+        Err(e) => {
+            tracing::warn!(error = %e, %track_id, "failed to load artist for enrichment");
+            return (Error, Error);
+        }
+    };
+
+    let (mb_base_url, mb_user_agent, caa_base_url) = load_mb_config(db).await;
+    let client = match build_client_with_ua(&mb_user_agent) {
+        Ok(c) => c,
+        Err(_) => return (Error, Error),
+    };
+    let mb = soundtime_p2p::MusicBrainzClient::with_config(
+        &mb_base_url,
+        &mb_user_agent,
+        soundtime_p2p::musicbrainz::DEFAULT_MAX_RETRIES,
+        soundtime_p2p::musicbrainz::DEFAULT_INITIAL_DELAY_MS,
+    );
+
+    let artist_status = enrich_artist(db, &mb, &client, &mb_base_url, &artist_model).await;
+    let album_status = match track_model.album_id {
+        None => NoEntity,
+        Some(album_id) => match album::Entity::find_by_id(album_id).one(db).await {
+            Ok(Some(album_model)) => {
+                let owner_id = track_model.uploaded_by.unwrap_or_else(Uuid::nil);
+                enrich_album(
+                    db,
+                    storage,
+                    &mb,
+                    &client,
+                    &caa_base_url,
+                    &album_model,
+                    &artist_model.name,
+                    owner_id,
+                )
+                .await
+            }
+            Ok(None) => NoEntity,
+            Err(e) => {
+                tracing::warn!(error = %e, %album_id, "failed to load album for enrichment");
+                Error
+            }
+        },
+    };
+    (artist_status, album_status)
+}
+
+async fn enrich_artist(
+    db: &DatabaseConnection,
+    mb: &soundtime_p2p::MusicBrainzClient,
+    client: &Client,
+    mb_base_url: &str,
+    artist_model: &artist::Model,
+) -> EntityEnrichmentStatus {
+    if artist_model.musicbrainz_id.is_some()
+        && artist_model.bio.is_some()
+        && artist_model.image_url.is_some()
+    {
+        return EntityEnrichmentStatus::AlreadyEnriched;
+    }
+    if is_placeholder_name(&artist_model.name) {
+        return EntityEnrichmentStatus::NotFound;
+    }
+
+    let mut artist_update: artist::ActiveModel = artist_model.clone().into();
+    let mut changed = false;
+    let mbid = match &artist_model.musicbrainz_id {
+        Some(mbid) => mbid.clone(),
+        None => match mb.lookup_artist(&artist_model.name).await {
+            Some(found) => {
+                artist_update.musicbrainz_id = Set(Some(found.id.clone()));
+                changed = true;
+                found.id
+            }
+            None => return EntityEnrichmentStatus::NotFound,
+        },
+    };
+
+    // Bio and image from Wikipedia via the artist's MusicBrainz relations
+    if artist_model.bio.is_none() || artist_model.image_url.is_none() {
+        let (bio, image) =
+            fetch_artist_bio_image(client, &mbid, &artist_model.name, mb_base_url).await;
+        if artist_model.bio.is_none() && bio.is_some() {
+            artist_update.bio = Set(bio);
+            changed = true;
+        }
+        if artist_model.image_url.is_none() && image.is_some() {
+            artist_update.image_url = Set(image);
+            changed = true;
+        }
+    }
+
+    if !changed {
+        return EntityEnrichmentStatus::NotFound;
+    }
+    match artist_update.update(db).await {
+        Ok(_) => EntityEnrichmentStatus::Enriched,
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to update artist metadata");
+            EntityEnrichmentStatus::Error
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn enrich_album(
+    db: &DatabaseConnection,
+    storage: &dyn StorageBackend,
+    mb: &soundtime_p2p::MusicBrainzClient,
+    client: &Client,
+    caa_base_url: &str,
+    album_model: &album::Model,
+    artist_name: &str,
+    owner_id: Uuid,
+) -> EntityEnrichmentStatus {
+    if album_model.musicbrainz_id.is_some()
+        && album_model.release_date.is_some()
+        && album_model.cover_url.is_some()
+    {
+        return EntityEnrichmentStatus::AlreadyEnriched;
+    }
+    if is_placeholder_name(&album_model.title) {
+        return EntityEnrichmentStatus::NotFound;
+    }
+
+    // A release with another ID than the one already stored is a different
+    // edition, whose date would not apply
+    let release = if album_model.musicbrainz_id.is_none() || album_model.release_date.is_none() {
+        mb.lookup_release(&album_model.title, artist_name)
+            .await
+            .filter(|r| match &album_model.musicbrainz_id {
+                Some(mbid) => *mbid == r.id,
+                None => true,
+            })
+    } else {
+        None
+    };
+    let Some(mbid) = album_model
+        .musicbrainz_id
+        .clone()
+        .or_else(|| release.as_ref().map(|r| r.id.clone()))
+    else {
+        return EntityEnrichmentStatus::NotFound;
+    };
+
+    let mut album_update: album::ActiveModel = album_model.clone().into();
+    let mut changed = false;
+    if album_model.musicbrainz_id.is_none() {
+        album_update.musicbrainz_id = Set(Some(mbid.clone()));
+        changed = true;
+    }
+    if let Some(release) = &release {
+        if album_model.release_date.is_none() && release.release_date.is_some() {
+            album_update.release_date = Set(release.release_date);
+            changed = true;
+        }
+        if album_model.year.is_none() && release.year.is_some() {
+            album_update.year = Set(release.year);
+            changed = true;
+        }
+    }
+    if album_model.cover_url.is_none() {
+        if let Some(cover_url) = fetch_cover_art_url(client, &mbid, caa_base_url).await {
+            if let Some(cover_bytes) = download_cover(client, &cover_url).await {
+                if let Ok(relative) = storage
+                    .store_cover(owner_id, Some(album_model.title.as_str()), &cover_bytes)
+                    .await
+                {
+                    album_update.cover_url = Set(Some(format!("/api/media/{relative}")));
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    if !changed {
+        return EntityEnrichmentStatus::NotFound;
+    }
+    match album_update.update(db).await {
+        Ok(_) => EntityEnrichmentStatus::Enriched,
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to update album metadata");
+            EntityEnrichmentStatus::Error
+        }
+    }
+}
+
 // ─── AI Fallback enrichment ─────────────────────────────────────────
 
 /// Response structure expected from the AI for metadata resolution.
@@ -1484,6 +1742,8 @@ pub async fn register_remote_track(
         format: Set(format.map(|f| f.to_string())),
         is_available: Set(true),
         last_checked_at: Set(Some(chrono::Utc::now().into())),
+        dereferenced_at: Set(None),
+        signature: Set(None),
         created_at: Set(chrono::Utc::now().into()),
     }
     .insert(db)
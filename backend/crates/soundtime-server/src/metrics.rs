@@ -0,0 +1,348 @@
+//! Prometheus scrape endpoint — `GET /metrics`.
+//!
+//! Only mounted when `METRICS_ENABLED=true`. Besides the P2P metrics from
+//! [`soundtime_p2p::P2P_METRICS`], the server keeps its own registry for HTTP
+//! request latencies, database pool usage and storage size. Gauges are read
+//! fresh on every scrape. When `METRICS_TOKEN` is set, scrapers must send it
+//! as `Authorization: Bearer <token>`; without it the endpoint is open and
+//! should be restricted at the reverse proxy or firewall.
+
+use std::sync::{Arc, LazyLock};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        StatusCode,
+    },
+    middleware::{self as axum_middleware, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntGauge, Opts, Registry, TextEncoder};
+use sea_orm::sea_query::Expr;
+use sea_orm::{DatabaseConnection, EntityTrait, FromQueryResult, QuerySelect};
+use soundtime_db::entities::track;
+use soundtime_db::AppState;
+use soundtime_p2p::{P2pNode, P2P_METRICS};
+
+/// Server-side metrics — updated by the HTTP middleware and at scrape time.
+pub static SERVER_METRICS: LazyLock<ServerMetrics> = LazyLock::new(ServerMetrics::new);
+
+/// Bucket boundaries (seconds) for the HTTP request latency histogram.
+const HTTP_LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// HTTP, database and storage metrics of this server process.
+pub struct ServerMetrics {
+    registry: Registry,
+    /// Request latency by method, matched route and status code.
+    pub http_request_duration_seconds: HistogramVec,
+    /// Open connections in the database pool.
+    pub db_pool_connections: IntGauge,
+    /// Idle connections in the database pool.
+    pub db_pool_idle_connections: IntGauge,
+    /// Total size of all track files.
+    pub storage_bytes: IntGauge,
+}
+
+impl ServerMetrics {
+    /// Create and register all metrics in a fresh registry.
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("soundtime".to_string()), None)
+            .expect("valid registry prefix");
+
+        let http_request_duration_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency by method, route and status",
+            )
+            .buckets(HTTP_LATENCY_BUCKETS.to_vec()),
+            &["method", "route", "status"],
+        )
+        .expect("valid histogram opts");
+        registry
+            .register(Box::new(http_request_duration_seconds.clone()))
+            .expect("metric registered once");
+
+        let gauge = |name: &str, help: &str| {
+            let g = IntGauge::with_opts(Opts::new(name, help)).expect("valid gauge opts");
+            registry
+                .register(Box::new(g.clone()))
+                .expect("metric registered once");
+            g
+        };
+
+        let db_pool_connections = gauge("db_pool_connections", "Open database pool connections");
+        let db_pool_idle_connections =
+            gauge("db_pool_idle_connections", "Idle database pool connections");
+        let storage_bytes = gauge("storage_bytes", "Total size of all track files");
+
+        Self {
+            registry,
+            http_request_duration_seconds,
+            db_pool_connections,
+            db_pool_idle_connections,
+            storage_bytes,
+        }
+    }
+
+    /// Registry holding all server metrics.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+}
+
+impl Default for ServerMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether the `/metrics` route should be mounted (`METRICS_ENABLED`, default false).
+pub fn metrics_enabled() -> bool {
+    std::env::var("METRICS_ENABLED")
+        .unwrap_or_else(|_| "false".to_string())
+        .eq_ignore_ascii_case("true")
+}
+
+/// Bearer token scrapers must present (`METRICS_TOKEN`), if one is set.
+pub fn metrics_token() -> Option<Arc<str>> {
+    std::env::var("METRICS_TOKEN")
+        .ok()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .map(Arc::from)
+}
+
+/// The `/metrics` route, guarded by `token` when one is given.
+pub fn routes(token: Option<Arc<str>>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/metrics", get(metrics_handler))
+        .layer(axum_middleware::from_fn_with_state(token, require_token))
+}
+
+/// Middleware: reject scrapes without the configured bearer token.
+async fn require_token(
+    State(token): State<Option<Arc<str>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(expected) = token else {
+        return next.run(request).await;
+    };
+    let presented = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match presented {
+        Some(t) if constant_time_eq(t.as_bytes(), expected.as_bytes()) => next.run(request).await,
+        _ => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
+
+/// Compare two byte strings without short-circuiting on the first mismatch.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Middleware: record the latency of every HTTP request.
+///
+/// Requests are labelled with their matched route template rather than the
+/// raw path, so IDs in the URL don't create a series each.
+pub async fn track_http_metrics(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let started = std::time::Instant::now();
+
+    let response = next.run(request).await;
+
+    SERVER_METRICS
+        .http_request_duration_seconds
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .observe(started.elapsed().as_secs_f64());
+    response
+}
+
+/// Read the database pool and storage gauges. Skipped when the server has no
+/// live database connection (as in tests).
+async fn refresh_db_metrics(db: &DatabaseConnection) {
+    if !matches!(db, DatabaseConnection::SqlxPostgresPoolConnection(_)) {
+        return;
+    }
+
+    let pool = db.get_postgres_connection_pool();
+    SERVER_METRICS.db_pool_connections.set(pool.size() as i64);
+    SERVER_METRICS
+        .db_pool_idle_connections
+        .set(pool.num_idle() as i64);
+
+    #[derive(Debug, FromQueryResult)]
+    struct SizeSum {
+        total_size: Option<i64>,
+    }
+
+    match track::Entity::find()
+        .select_only()
+        .column_as(
+            Expr::cust("COALESCE(SUM(file_size)::bigint, 0)"),
+            "total_size",
+        )
+        .into_model::<SizeSum>()
+        .one(db)
+        .await
+    {
+        Ok(row) => SERVER_METRICS
+            .storage_bytes
+            .set(row.and_then(|r| r.total_size).unwrap_or(0)),
+        Err(e) => tracing::warn!(error = %e, "metrics: failed to sum track file sizes"),
+    }
+}
+
+/// GET /metrics — all metrics in the Prometheus text exposition format.
+pub async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    if let Some(node) = state
+        .p2p
+        .as_ref()
+        .and_then(|any| any.clone().downcast::<P2pNode>().ok())
+    {
+        node.refresh_metrics().await;
+    }
+    refresh_db_metrics(&state.db).await;
+
+    let mut families = P2P_METRICS.registry().gather();
+    families.extend(SERVER_METRICS.registry().gather());
+
+    let encoder = TextEncoder::new();
+    let mut buf = Vec::new();
+    if let Err(e) = encoder.encode(&families, &mut buf) {
+        tracing::error!("failed to encode metrics: {e}");
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [(CONTENT_TYPE, "text/plain; charset=utf-8".to_string())],
+            String::new(),
+        );
+    }
+
+    (
+        StatusCode::OK,
+        [(CONTENT_TYPE, encoder.format_type().to_string())],
+        String::from_utf8(buf).unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    fn test_state() -> Arc<AppState> {
+        Arc::new(AppState {
+            db: DatabaseConnection::Disconnected,
+            jwt_secret: "test".to_string(),
+            domain: "localhost".to_string(),
+            storage: Arc::new(soundtime_audio::AudioStorage::new("/tmp/test")),
+            p2p: None,
+            plugins: None,
+            #[cfg(feature = "redis")]
+            redis: None,
+        })
+    }
+
+    async fn scrape(token: Option<&str>, auth: Option<&str>) -> Response {
+        let app = routes(token.map(Arc::from)).with_state(test_state());
+        let mut req = axum::http::Request::builder().uri("/metrics");
+        if let Some(auth) = auth {
+            req = req.header(AUTHORIZATION, auth);
+        }
+        app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    #[test]
+    fn test_metrics_enabled_env() {
+        std::env::remove_var("METRICS_ENABLED");
+        assert!(!metrics_enabled());
+        std::env::set_var("METRICS_ENABLED", "TRUE");
+        assert!(metrics_enabled());
+        std::env::set_var("METRICS_ENABLED", "no");
+        assert!(!metrics_enabled());
+        std::env::remove_var("METRICS_ENABLED");
+    }
+
+    #[test]
+    fn test_metrics_token_env() {
+        std::env::remove_var("METRICS_TOKEN");
+        assert!(metrics_token().is_none());
+        std::env::set_var("METRICS_TOKEN", "  ");
+        assert!(metrics_token().is_none());
+        std::env::set_var("METRICS_TOKEN", "s3cret");
+        assert_eq!(metrics_token().as_deref(), Some("s3cret"));
+        std::env::remove_var("METRICS_TOKEN");
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_handler_text_format() {
+        P2P_METRICS.messages_received_total.inc();
+
+        let resp = scrape(None, None).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers()[CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain"));
+
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains("# TYPE soundtime_p2p_messages_received_total counter"));
+        assert!(text.contains("soundtime_p2p_health_sweep_duration_seconds"));
+        assert!(text.contains("# TYPE soundtime_storage_bytes gauge"));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_token_required() {
+        let resp = scrape(Some("s3cret"), None).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = scrape(Some("s3cret"), Some("Bearer wrong")).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = scrape(Some("s3cret"), Some("Bearer s3cret")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_http_latency_recorded_by_route() {
+        let app = Router::new()
+            .route("/api/tracks/{id}", get(|| async { "ok" }))
+            .layer(axum_middleware::from_fn(track_http_metrics));
+        let req = axum::http::Request::builder()
+            .uri("/api/tracks/42")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let count = SERVER_METRICS
+            .http_request_duration_seconds
+            .with_label_values(&["GET", "/api/tracks/{id}", "200"])
+            .get_sample_count();
+        assert!(count >= 1);
+    }
+}
@@ -0,0 +1,316 @@
+//! Bridges between the P2P node and the plugin system.
+//!
+//! Neither crate can see the other, so once both are started the server
+//! registers [`PluginTrackAnnouncedHook`] with the node,
+//! [`PluginP2pSearch`] with the plugin registry, and forwards peer status
+//! changes with [`spawn_peer_presence_events`].
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use soundtime_p2p::{
+    P2pNode, PeerStatusUpdate, SearchResultItem, TrackAnnouncedHook, TrackAnnouncement,
+};
+use soundtime_plugin::{
+    P2pSearchProvider, P2pSearchResult, PeerConnectedPayload, PeerDisconnectedPayload,
+    PluginRegistry, TrackAnnouncedPayload,
+};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+/// Stored announcements waiting for `on_track_announced`; while full, new
+/// ones are dropped.
+const TRACK_ANNOUNCED_QUEUE_CAPACITY: usize = 1024;
+
+/// Fires `on_track_announced` for every track a peer announced that the
+/// node stored.
+///
+/// A catalog page stores thousands of tracks at once, so events go through
+/// a bounded queue to a single task that dispatches them in order, instead
+/// of a task per track.
+pub struct PluginTrackAnnouncedHook {
+    events: mpsc::Sender<serde_json::Value>,
+}
+
+impl PluginTrackAnnouncedHook {
+    /// Create the hook and spawn the task dispatching its events, which
+    /// stops once the hook is dropped.
+    pub fn new(registry: Arc<PluginRegistry>) -> Self {
+        let (events, mut queue) = mpsc::channel(TRACK_ANNOUNCED_QUEUE_CAPACITY);
+        tokio::spawn(async move {
+            while let Some(payload_val) = queue.recv().await {
+                registry.dispatch("on_track_announced", &payload_val).await;
+            }
+        });
+        Self { events }
+    }
+}
+
+/// Plugin payload for a stored announcement.
+fn track_announced_payload(ann: &TrackAnnouncement) -> TrackAnnouncedPayload {
+    TrackAnnouncedPayload {
+        hash: ann.hash.clone(),
+        title: ann.title.clone(),
+        artist_name: ann.artist_name.clone(),
+        origin_node: ann.origin_node.clone(),
+        format: ann.format.clone(),
+    }
+}
+
+impl TrackAnnouncedHook for PluginTrackAnnouncedHook {
+    fn track_announced(&self, ann: &TrackAnnouncement, _peer_id: &str) {
+        let payload_val = serde_json::to_value(track_announced_payload(ann)).unwrap_or_default();
+        if let Err(TrySendError::Full(_)) = self.events.try_send(payload_val) {
+            tracing::warn!(hash = %ann.hash, "plugin event queue full, dropping on_track_announced");
+        }
+    }
+}
+
+/// Fire `on_peer_connected` when a peer comes online and
+/// `on_peer_disconnected` when it goes offline, for as long as the node runs.
+pub fn spawn_peer_presence_events(node: &P2pNode, registry: Arc<PluginRegistry>) {
+    let mut updates = node.registry().subscribe_status();
+    tokio::spawn(async move {
+        let mut online = HashSet::new();
+        loop {
+            let update = match updates.recv().await {
+                Ok(update) => update,
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!(missed, "plugin peer events lagged behind peer status");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            if let Some((event, payload_val)) = peer_presence_event(&mut online, &update) {
+                // Slow plugins must not hold up the status stream
+                let registry = registry.clone();
+                tokio::spawn(async move {
+                    registry.dispatch(event, &payload_val).await;
+                });
+            }
+        }
+    });
+}
+
+/// Plugin event for a status update: `on_peer_connected` when a peer not
+/// in `online` comes online, `on_peer_disconnected` when one in `online`
+/// goes offline. Updates from a peer already online fire nothing.
+fn peer_presence_event(
+    online: &mut HashSet<String>,
+    update: &PeerStatusUpdate,
+) -> Option<(&'static str, serde_json::Value)> {
+    match update {
+        PeerStatusUpdate::PeerOnline(status) => {
+            if !online.insert(status.node_id.clone()) {
+                return None;
+            }
+            let payload = PeerConnectedPayload {
+                peer_id: status.node_id.clone(),
+                domain: None,
+                track_count: Some(status.track_count),
+                version: status.version.clone(),
+            };
+            Some(("on_peer_connected", serde_json::to_value(payload).ok()?))
+        }
+        PeerStatusUpdate::PeerOffline(status) => {
+            if !online.remove(&status.node_id) {
+                return None;
+            }
+            let payload = PeerDisconnectedPayload {
+                peer_id: status.node_id.clone(),
+            };
+            Some(("on_peer_disconnected", serde_json::to_value(payload).ok()?))
+        }
+    }
+}
+
+/// Runs plugins' `p2p_search` host requests on the node.
+pub struct PluginP2pSearch {
+    node: Arc<P2pNode>,
+}
+
+impl PluginP2pSearch {
+    pub fn new(node: Arc<P2pNode>) -> Self {
+        Self { node }
+    }
+}
+
+/// Flat result handed to plugins for a network search hit.
+fn plugin_search_result(item: SearchResultItem) -> P2pSearchResult {
+    P2pSearchResult {
+        hash: item.hash,
+        title: item.title,
+        artist_name: item.artist_name,
+        album_title: item.album_title,
+        duration_secs: item.duration_secs as f64,
+        format: item.format,
+        genre: item.genre,
+        year: item.year.map(i32::from),
+        bitrate: item.bitrate,
+        source_node: item.source_node,
+    }
+}
+
+#[async_trait::async_trait]
+impl P2pSearchProvider for PluginP2pSearch {
+    async fn search(&self, query: &str, limit: u32) -> Vec<P2pSearchResult> {
+        self.node
+            .distributed_search(query, limit)
+            .await
+            .into_iter()
+            .map(plugin_search_result)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn announcement(hash: &str) -> TrackAnnouncement {
+        TrackAnnouncement {
+            hash: hash.into(),
+            title: "Song".into(),
+            artist_name: "Artist".into(),
+            album_artist_name: None,
+            album_title: Some("Album".into()),
+            duration_secs: 180.0,
+            format: "flac".into(),
+            file_size: 1000,
+            genre: None,
+            year: None,
+            track_number: None,
+            disc_number: None,
+            bitrate: None,
+            sample_rate: None,
+            origin_node: "origin-node".into(),
+            cover_hash: None,
+            fingerprint: None,
+            waveform_data: None,
+            artist_image_hash: None,
+            artist_bio: None,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+            signature: None,
+        }
+    }
+
+    #[test]
+    fn test_track_announced_payload() {
+        let ann = announcement("3f9a");
+        let json = serde_json::to_value(track_announced_payload(&ann)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "hash": "3f9a",
+                "title": "Song",
+                "artist_name": "Artist",
+                "origin_node": "origin-node",
+                "format": "flac",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_track_announced_events_are_queued_without_blocking() {
+        let (events, mut queue) = mpsc::channel(2);
+        let hook = PluginTrackAnnouncedHook { events };
+
+        // Nothing is dispatching: the third event is dropped, not waited on
+        for hash in ["h1", "h2", "h3"] {
+            hook.track_announced(&announcement(hash), "peer1");
+        }
+        assert_eq!(queue.recv().await.unwrap()["hash"], "h1");
+        assert_eq!(queue.recv().await.unwrap()["hash"], "h2");
+        assert!(queue.try_recv().is_err());
+
+        // Room again once the dispatcher caught up
+        hook.track_announced(&announcement("h4"), "peer1");
+        assert_eq!(queue.recv().await.unwrap()["hash"], "h4");
+    }
+
+    fn status(node_id: &str, track_count: u64) -> soundtime_p2p::PeerStatus {
+        soundtime_p2p::PeerStatus {
+            node_id: node_id.into(),
+            track_count,
+            rtt_ms: None,
+            version: Some("0.4.0".into()),
+        }
+    }
+
+    #[test]
+    fn test_peer_presence_event_connected_once_per_online_spell() {
+        let mut online = HashSet::new();
+
+        let (event, payload) =
+            peer_presence_event(&mut online, &PeerStatusUpdate::PeerOnline(status("p1", 12)))
+                .unwrap();
+        assert_eq!(event, "on_peer_connected");
+        assert_eq!(
+            payload,
+            serde_json::json!({
+                "peer_id": "p1",
+                "domain": null,
+                "track_count": 12,
+                "version": "0.4.0",
+            })
+        );
+
+        // A new track count while online is not a new connection
+        assert!(
+            peer_presence_event(&mut online, &PeerStatusUpdate::PeerOnline(status("p1", 13)))
+                .is_none()
+        );
+
+        let (event, payload) = peer_presence_event(
+            &mut online,
+            &PeerStatusUpdate::PeerOffline(status("p1", 13)),
+        )
+        .unwrap();
+        assert_eq!(event, "on_peer_disconnected");
+        assert_eq!(payload, serde_json::json!({ "peer_id": "p1" }));
+
+        // Back online fires again
+        assert!(
+            peer_presence_event(&mut online, &PeerStatusUpdate::PeerOnline(status("p1", 13)))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_peer_presence_event_ignores_offline_for_unknown_peer() {
+        let mut online = HashSet::new();
+        assert!(
+            peer_presence_event(&mut online, &PeerStatusUpdate::PeerOffline(status("p2", 0)))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_plugin_search_result() {
+        let item = SearchResultItem {
+            hash: "3f9a".into(),
+            title: "So What".into(),
+            artist_name: "Miles Davis".into(),
+            album_title: Some("Kind of Blue".into()),
+            duration_secs: 562.0,
+            format: "flac".into(),
+            genre: Some("Jazz".into()),
+            year: Some(1959),
+            bitrate: Some(900_000),
+            source_node: "peer1".into(),
+            musicbrainz_id: None,
+            relevance: 0.8,
+            play_count: 12,
+            loudness_lufs: None,
+            dynamic_range: None,
+            encoding_quality: None,
+        };
+        let result = plugin_search_result(item);
+        assert_eq!(result.year, Some(1959));
+        assert_eq!(result.duration_secs, 562.0);
+        assert_eq!(result.source_node, "peer1");
+        assert_eq!(result.album_title.as_deref(), Some("Kind of Blue"));
+    }
+}
@@ -4,10 +4,15 @@
 //!   computes a SHA-256 hash to detect corruption.
 //! - **Sync / import**: scans the storage backend for audio files that
 //!   are not yet referenced in the database and imports them.
+//! - **Sharding migration**: moves local audio files into the directory
+//!   layout of a [`ShardingStrategy`] and updates their tracks.
+//! - **Embedded cover extraction**: gives albums without a cover the art
+//!   embedded in one of their tracks.
 //!
 //! Both operations run as background tasks to avoid HTTP timeouts.
 //! The admin API triggers them asynchronously and polls for results
-//! via `TaskTracker`.
+//! via `TaskTracker`. The integrity check also publishes an
+//! [`IntegrityEvent`] per track, streamed to the admin UI over SSE.
 
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, EntityTrait, FromQueryResult, PaginatorTrait, QueryFilter,
@@ -15,15 +20,19 @@ use sea_orm::{
 };
 use serde::Serialize;
 use soundtime_audio::metadata::normalize_genre;
-use soundtime_db::entities::{track, user};
+use soundtime_audio::ShardingStrategy;
+use soundtime_db::entities::{album, track, user};
 use soundtime_db::AppState;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex, MutexGuard};
 use uuid::Uuid;
 
 /// Interval between automatic runs (24 hours).
 const DAILY_INTERVAL_SECS: u64 = 86_400;
 
+/// Integrity events buffered per subscriber before it starts lagging.
+const INTEGRITY_EVENT_CAPACITY: usize = 256;
+
 /// Audio extensions we consider importable.
 const AUDIO_EXTENSIONS: &[&str] = &["mp3", "flac", "ogg", "wav", "aac", "opus", "aiff", "aif"];
 
@@ -44,6 +53,17 @@ pub struct MissingTrack {
     pub file_path: String,
 }
 
+/// Progress of a running integrity check, published after each track.
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityEvent {
+    pub checked: u64,
+    pub total: u64,
+    /// Title of the track just checked
+    pub current_track: String,
+    /// Problems found with that track (empty if it is healthy)
+    pub issues: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct SyncReport {
     pub scanned: u64,
@@ -52,6 +72,29 @@ pub struct SyncReport {
     pub errors: Vec<String>,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct ShardingReport {
+    pub strategy: ShardingStrategy,
+    /// Distinct local files referenced by tracks
+    pub files: u64,
+    /// Files moved to their shard
+    pub moved: u64,
+    /// Files referenced by tracks but absent from storage, left alone
+    pub missing: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoverReport {
+    /// Albums without a cover when the task started
+    pub albums_checked: u64,
+    /// Albums that got a cover from embedded art
+    pub covers_set: u64,
+    /// Albums none of whose local tracks carry embedded art
+    pub no_embedded_cover: u64,
+    pub errors: Vec<String>,
+}
+
 // ─── Async task tracker ────────────────────────────────────────────
 
 /// Status of a background storage task (sync or integrity check).
@@ -82,14 +125,45 @@ pub enum TaskResult {
     Sync(SyncReport),
     #[serde(rename = "integrity")]
     Integrity(IntegrityReport),
+    #[serde(rename = "sharding")]
+    Sharding(ShardingReport),
+    #[serde(rename = "covers")]
+    Covers(CoverReport),
+}
+
+/// State of the current background task, plus the live feed of the
+/// integrity check.
+pub struct TaskTracker {
+    status: Mutex<Option<TaskStatus>>,
+    integrity_events: broadcast::Sender<IntegrityEvent>,
+}
+
+impl TaskTracker {
+    /// Lock the status of the current task.
+    pub async fn lock(&self) -> MutexGuard<'_, Option<TaskStatus>> {
+        self.status.lock().await
+    }
+
+    /// Receive the events of integrity checks run from now on.
+    pub fn subscribe_integrity(&self) -> broadcast::Receiver<IntegrityEvent> {
+        self.integrity_events.subscribe()
+    }
+
+    fn publish_integrity(&self, event: IntegrityEvent) {
+        // No subscriber is not an error: nobody is watching
+        let _ = self.integrity_events.send(event);
+    }
 }
 
 /// Shared handle to track the current background task.
-pub type TaskTrackerHandle = Arc<Mutex<Option<TaskStatus>>>;
+pub type TaskTrackerHandle = Arc<TaskTracker>;
 
 /// Create a new task tracker.
 pub fn new_tracker() -> TaskTrackerHandle {
-    Arc::new(Mutex::new(None))
+    Arc::new(TaskTracker {
+        status: Mutex::new(None),
+        integrity_events: broadcast::channel(INTEGRITY_EVENT_CAPACITY).0,
+    })
 }
 
 // ─── Background spawner ────────────────────────────────────────────
@@ -177,28 +251,237 @@ pub async fn run_integrity_check(
                 }
             }
 
+            let mut issues = Vec::new();
             if !state.storage.file_exists(&t.file_path).await {
                 report.missing.push(MissingTrack {
                     track_id: t.id.to_string(),
                     title: t.title.clone(),
                     file_path: t.file_path.clone(),
                 });
+                issues.push(format!("File missing: {}", t.file_path));
+            } else {
+                // Verify file is readable (hash check)
+                match state.storage.hash_file(&t.file_path).await {
+                    Ok(_) => report.healthy += 1,
+                    Err(e) => {
+                        let issue = format!("Track {} ({}): hash error — {}", t.id, t.title, e);
+                        report.errors.push(issue.clone());
+                        issues.push(issue);
+                    }
+                }
+            }
+
+            if let Some(tr) = tracker {
+                tr.publish_integrity(IntegrityEvent {
+                    checked: report.total_checked,
+                    total,
+                    current_track: t.title.clone(),
+                    issues,
+                });
+            }
+        }
+        page += 1;
+    }
+
+    Ok(report)
+}
+
+// ─── Sharding migration ────────────────────────────────────────────
+
+/// Move every local track file to its directory under `strategy` and point
+/// its tracks at the new path, one file at a time: the file is linked to its
+/// new path, the tracks are updated, then the old path is removed. A crash
+/// leaves every track pointing at a file that exists. Missing files are
+/// skipped and reported.
+pub async fn run_sharding_migration(
+    state: &AppState,
+    strategy: ShardingStrategy,
+    tracker: Option<&TaskTrackerHandle>,
+) -> Result<ShardingReport, String> {
+    use sea_orm::sea_query::Expr;
+    use soundtime_audio::StorageError;
+
+    let Some(local) = state.storage.as_local() else {
+        return Err("Sharding only applies to local filesystem storage".to_string());
+    };
+
+    let file_paths: Vec<String> = track::Entity::find()
+        .select_only()
+        .column(track::Column::FilePath)
+        .filter(track::Column::FilePath.not_like("p2p://%"))
+        .into_tuple()
+        .all(&state.db)
+        .await
+        .map_err(|e| format!("DB query: {e}"))?;
+    // Tracks may share a file; move each one once
+    let mut seen = std::collections::HashSet::new();
+    let file_paths: Vec<String> = file_paths
+        .into_iter()
+        .filter(|p| seen.insert(p.clone()))
+        .collect();
+    let files = file_paths.len() as u64;
+
+    let mut report = ShardingReport {
+        strategy,
+        files,
+        moved: 0,
+        missing: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    for (i, from) in file_paths.iter().enumerate() {
+        let processed = i as u64;
+        if let Some(tr) = tracker {
+            if processed.is_multiple_of(10) {
+                let mut lock = tr.lock().await;
+                *lock = Some(TaskStatus::Running {
+                    progress: TaskProgress {
+                        processed,
+                        total: Some(files),
+                    },
+                });
+            }
+        }
+
+        let m = match local.link_to_shard(strategy, from).await {
+            Ok(Some(m)) => m,
+            Ok(None) => continue,
+            Err(StorageError::NotFound(_)) => {
+                tracing::warn!(path = %from, "sharding migration: file missing, skipping");
+                report.missing.push(from.clone());
                 continue;
             }
+            Err(e) => {
+                tracing::warn!(path = %from, error = %e, "sharding migration: cannot move file");
+                report.errors.push(format!("{from}: {e}"));
+                continue;
+            }
+        };
+
+        let updated = track::Entity::update_many()
+            .col_expr(track::Column::FilePath, Expr::value(m.to.clone()))
+            .filter(track::Column::FilePath.eq(m.from.as_str()))
+            .exec(&state.db)
+            .await;
+        if let Err(e) = updated {
+            tracing::error!(path = %from, error = %e, "sharding migration: updating tracks failed");
+            local.undo_shard_move(&m).await;
+            report.errors.push(format!("{from}: updating tracks: {e}"));
+            continue;
+        }
+        // The tracks point at the new path; the old one can go
+        if let Err(e) = local.finish_shard_move(&m).await {
+            tracing::warn!(path = %from, error = %e, "sharding migration: cannot remove old file");
+            report
+                .errors
+                .push(format!("{from}: removing old file: {e}"));
+        }
+        report.moved += 1;
+    }
+
+    if let Some(tr) = tracker {
+        let mut lock = tr.lock().await;
+        *lock = Some(TaskStatus::Running {
+            progress: TaskProgress {
+                processed: files,
+                total: Some(files),
+            },
+        });
+    }
+
+    tracing::info!(
+        %strategy,
+        files,
+        moved = report.moved,
+        missing = report.missing.len(),
+        errors = report.errors.len(),
+        "storage sharding migration done"
+    );
+    Ok(report)
+}
+
+// ─── Embedded cover extraction ─────────────────────────────────────
+
+/// Look for embedded art in the local tracks of every album without a
+/// cover, and set the first one found as the album cover. Covers are stored
+/// under the track's uploader, or `fallback_owner` if it has none.
+pub async fn run_embedded_cover_extraction(
+    state: &AppState,
+    fallback_owner: Uuid,
+    tracker: Option<&TaskTrackerHandle>,
+) -> Result<CoverReport, String> {
+    let albums = album::Entity::find()
+        .filter(album::Column::CoverUrl.is_null())
+        .all(&state.db)
+        .await
+        .map_err(|e| format!("DB query: {e}"))?;
+    let total = albums.len() as u64;
+
+    let mut report = CoverReport {
+        albums_checked: total,
+        covers_set: 0,
+        no_embedded_cover: 0,
+        errors: Vec::new(),
+    };
+
+    for (i, album) in albums.iter().enumerate() {
+        let processed = i as u64 + 1;
+        if let Some(tr) = tracker {
+            if processed.is_multiple_of(5) || processed == total {
+                let mut lock = tr.lock().await;
+                *lock = Some(TaskStatus::Running {
+                    progress: TaskProgress {
+                        processed,
+                        total: Some(total),
+                    },
+                });
+            }
+        }
 
-            // Verify file is readable (hash check)
-            match state.storage.hash_file(&t.file_path).await {
-                Ok(_) => report.healthy += 1,
+        let tracks = track::Entity::find()
+            .filter(track::Column::AlbumId.eq(album.id))
+            .filter(track::Column::FilePath.not_like("p2p://%"))
+            .all(&state.db)
+            .await
+            .map_err(|e| format!("DB query: {e}"))?;
+
+        let mut found = false;
+        for t in tracks {
+            let local_path = match soundtime_audio::ensure_local_file(
+                state.storage.as_ref(),
+                &t.file_path,
+            )
+            .await
+            {
+                Ok(p) => p,
                 Err(e) => {
-                    report
-                        .errors
-                        .push(format!("Track {} ({}): hash error — {}", t.id, t.title, e));
+                    report.errors.push(format!("{}: {e}", t.file_path));
+                    continue;
                 }
+            };
+            let Some(cover) = soundtime_audio::extract_embedded_cover(&local_path) else {
+                continue;
+            };
+
+            found = true;
+            let owner = t.uploaded_by.unwrap_or(fallback_owner);
+            match crate::api::audio::apply_embedded_cover(state, owner, album, cover).await {
+                Ok(true) => report.covers_set += 1,
+                Ok(false) => {}
+                Err(e) => report.errors.push(format!("album {}: {e}", album.id)),
             }
+            break;
+        }
+        if !found {
+            report.no_embedded_cover += 1;
         }
-        page += 1;
     }
 
+    tracing::info!(
+        albums = total,
+        covers_set = report.covers_set,
+        "embedded cover extraction done"
+    );
     Ok(report)
 }
 
@@ -465,7 +748,16 @@ async fn import_file(state: &AppState, relative_path: &str) -> Result<Uuid, Stri
             .to_string()
     });
 
-    let waveform = soundtime_audio::generate_waveform(&local_path, 200).ok();
+    let waveform = soundtime_audio::generate_waveform_async(
+        local_path.clone(),
+        crate::waveform_worker::WAVEFORM_POINTS,
+        Default::default(),
+    )
+    .await
+    .ok();
+    let (loudness_lufs, dynamic_range) =
+        soundtime_audio::measure_loudness_async(local_path.clone()).await;
+    let fingerprint = soundtime_audio::compute_fingerprint(&local_path).await;
 
     let track_id = Uuid::new_v4();
     let new_track = track::ActiveModel {
@@ -491,7 +783,14 @@ async fn import_file(state: &AppState, relative_path: &str) -> Result<Uuid, Stri
         waveform_data: Set(waveform.map(|w| serde_json::json!(w))),
         uploaded_by: Set(uploaded_by),
         content_hash: Set(None),
+        fingerprint: Set(fingerprint),
         play_count: Set(0),
+        is_private: Set(false),
+        is_hidden: Set(false),
+        hidden_by_block: Set(false),
+        loudness_lufs: Set(loudness_lufs),
+        dynamic_range: Set(dynamic_range),
+        encoding_quality: Set(meta.encoding_quality.clone()),
         created_at: Set(chrono::Utc::now().into()),
     };
 
@@ -502,3 +801,65 @@ async fn import_file(state: &AppState, relative_path: &str) -> Result<Uuid, Stri
 
     Ok(track_id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── TaskTracker ──
+
+    fn event(checked: u64) -> IntegrityEvent {
+        IntegrityEvent {
+            checked,
+            total: 3,
+            current_track: format!("Track {checked}"),
+            issues: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_integrity_events_reach_subscribers() {
+        let tracker = new_tracker();
+        // Published before anyone subscribed: dropped, not an error
+        tracker.publish_integrity(event(1));
+
+        let mut rx = tracker.subscribe_integrity();
+        tracker.publish_integrity(event(2));
+        tracker.publish_integrity(event(3));
+        assert_eq!(rx.recv().await.unwrap().checked, 2);
+        assert_eq!(rx.recv().await.unwrap().checked, 3);
+    }
+
+    #[tokio::test]
+    async fn test_tracker_status_lock() {
+        let tracker = new_tracker();
+        assert!(tracker.lock().await.is_none());
+        *tracker.lock().await = Some(TaskStatus::Error {
+            message: "boom".to_string(),
+        });
+        assert!(matches!(
+            &*tracker.lock().await,
+            Some(TaskStatus::Error { message }) if message == "boom"
+        ));
+    }
+
+    #[test]
+    fn test_integrity_event_serialization() {
+        let val = serde_json::to_value(IntegrityEvent {
+            checked: 12,
+            total: 50_000,
+            current_track: "Song".to_string(),
+            issues: vec!["File missing: a/b.flac".to_string()],
+        })
+        .unwrap();
+        assert_eq!(
+            val,
+            serde_json::json!({
+                "checked": 12,
+                "total": 50_000,
+                "current_track": "Song",
+                "issues": ["File missing: a/b.flac"],
+            })
+        );
+    }
+}
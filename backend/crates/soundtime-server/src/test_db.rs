@@ -0,0 +1,163 @@
+//! In-memory SQLite databases for handler tests.
+//!
+//! Tables are created straight from the SeaORM entities, and only the ones
+//! a test asks for exist: leaving one out makes every write to it fail,
+//! which is how tests force a transaction to roll back. Foreign keys are not
+//! enforced, so a test only inserts the rows it looks at.
+
+use std::sync::Arc;
+
+use sea_orm::{
+    ActiveModelTrait, ConnectOptions, ConnectionTrait, Database, DatabaseConnection, EntityTrait,
+    Schema, Set,
+};
+use soundtime_db::entities::user::{self, UserRole};
+use soundtime_db::entities::{
+    admin_audit_log, album, artist, blocked_hash, health_sweep_run, instance_setting,
+    mb_lookup_queue, p2p_peer, p2p_peer_filter, p2p_seed_peer, peer_track_grant, pinned_track,
+    published_hash, remote_play_count, remote_track, track, track_recovery_attempt,
+};
+use soundtime_db::AppState;
+use soundtime_p2p::{P2pConfig, P2pNode};
+use tempfile::TempDir;
+use uuid::Uuid;
+
+use crate::auth::jwt::{Claims, TokenType};
+use crate::auth::middleware::AuthUser;
+
+/// Open an empty database.
+pub async fn connect() -> DatabaseConnection {
+    // A single connection: each connection to `:memory:` is its own database
+    let mut options = ConnectOptions::new("sqlite::memory:");
+    options
+        .max_connections(1)
+        .min_connections(1)
+        .sqlx_logging(false);
+    let db = Database::connect(options)
+        .await
+        .expect("failed to open SQLite database");
+    db.execute_unprepared("PRAGMA foreign_keys = OFF")
+        .await
+        .expect("failed to disable foreign keys");
+    db
+}
+
+/// Create the table of `entity`.
+pub async fn create_table<E: EntityTrait>(db: &DatabaseConnection, entity: E) {
+    let backend = db.get_database_backend();
+    let stmt = Schema::new(backend).create_table_from_entity(entity);
+    db.execute(backend.build(&stmt))
+        .await
+        .expect("failed to create table");
+}
+
+/// Create the tables a running P2P node and its admin endpoints use.
+pub async fn create_p2p_tables(db: &DatabaseConnection) {
+    create_table(db, user::Entity).await;
+    create_table(db, admin_audit_log::Entity).await;
+    create_table(db, artist::Entity).await;
+    create_table(db, album::Entity).await;
+    create_table(db, track::Entity).await;
+    create_table(db, remote_track::Entity).await;
+    create_table(db, remote_play_count::Entity).await;
+    create_table(db, mb_lookup_queue::Entity).await;
+    create_table(db, blocked_hash::Entity).await;
+    create_table(db, instance_setting::Entity).await;
+    create_table(db, p2p_peer::Entity).await;
+    create_table(db, p2p_peer_filter::Entity).await;
+    create_table(db, p2p_seed_peer::Entity).await;
+    create_table(db, peer_track_grant::Entity).await;
+    create_table(db, pinned_track::Entity).await;
+    create_table(db, published_hash::Entity).await;
+    create_table(db, track_recovery_attempt::Entity).await;
+    create_table(db, health_sweep_run::Entity).await;
+}
+
+/// App state with a running P2P node on a fresh database with the P2P
+/// tables. Relays and discovery are off; the node's blobs and key live in
+/// the returned directory, removed on drop.
+pub async fn state_with_node() -> (Arc<AppState>, Arc<P2pNode>, TempDir) {
+    let db = connect().await;
+    create_p2p_tables(&db).await;
+    let dir = TempDir::new().expect("failed to create temp dir");
+    let config = P2pConfig {
+        blobs_dir: dir.path().join("blobs"),
+        secret_key_path: dir.path().join("secret_key"),
+        bloom_persist_path: dir.path().join("bloom.bin"),
+        audio_storage_path: dir.path().join("music"),
+        enable_local_discovery: false,
+        enable_dht_discovery: false,
+        disable_default_discovery: true,
+        ..Default::default()
+    };
+    let node = P2pNode::start(config, db.clone())
+        .await
+        .expect("failed to start P2P node");
+    let state = Arc::new(AppState {
+        db,
+        jwt_secret: "test-secret".to_string(),
+        domain: "localhost".to_string(),
+        storage: Arc::new(soundtime_audio::AudioStorage::new(dir.path().join("music"))),
+        p2p: Some(node.clone() as Arc<dyn std::any::Any + Send + Sync>),
+        plugins: None,
+        #[cfg(feature = "redis")]
+        redis: None,
+    });
+    (state, node, dir)
+}
+
+/// App state backed by `db`, without P2P or plugins.
+pub fn state(db: DatabaseConnection) -> Arc<AppState> {
+    state_with_storage(db, "/tmp/test")
+}
+
+/// App state backed by `db` storing files under `storage_root`.
+pub fn state_with_storage(
+    db: DatabaseConnection,
+    storage_root: impl Into<std::path::PathBuf>,
+) -> Arc<AppState> {
+    Arc::new(AppState {
+        db,
+        jwt_secret: "test-secret".to_string(),
+        domain: "localhost".to_string(),
+        storage: Arc::new(soundtime_audio::AudioStorage::new(storage_root)),
+        p2p: None,
+        plugins: None,
+        #[cfg(feature = "redis")]
+        redis: None,
+    })
+}
+
+/// Insert a user with `role` and return it.
+pub async fn insert_user(db: &DatabaseConnection, username: &str, role: UserRole) -> user::Model {
+    let now = chrono::Utc::now().fixed_offset();
+    user::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        username: Set(username.to_string()),
+        email: Set(format!("{username}@example.com")),
+        password_hash: Set(String::new()),
+        display_name: Set(None),
+        avatar_url: Set(None),
+        role: Set(role),
+        is_banned: Set(false),
+        ban_reason: Set(None),
+        banned_at: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(db)
+    .await
+    .expect("failed to insert user")
+}
+
+/// The authenticated identity of `user`.
+pub fn auth_user(user: &user::Model) -> AuthUser {
+    AuthUser(Claims {
+        sub: user.id,
+        username: user.username.clone(),
+        role: user.role.as_str().to_string(),
+        token_type: TokenType::Access,
+        iat: 0,
+        exp: 9999999999,
+    })
+}
@@ -0,0 +1,112 @@
+//! Waveform worker — computes the waveform of an uploaded track after the
+//! upload has been answered.
+//!
+//! Decoding a long lossless file takes minutes, so uploads store the track
+//! with `waveform_data = NULL` and hand the file to [`generate`], which
+//! decodes it on the blocking thread pool and saves the waveform when done.
+//! Progress of running generations is kept in memory so the UI can poll
+//! `GET /api/tracks/{id}/waveform-status`.
+
+use sea_orm::{ActiveModelTrait, Set};
+use soundtime_audio::WaveformProgress;
+use soundtime_db::entities::track;
+use soundtime_db::AppState;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, Mutex};
+use uuid::Uuid;
+
+/// Number of peak values stored per track.
+pub const WAVEFORM_POINTS: usize = 200;
+
+/// Generations in progress, keyed by track ID.
+static IN_PROGRESS: LazyLock<Mutex<HashMap<Uuid, Arc<WaveformProgress>>>> =
+    LazyLock::new(Default::default);
+
+/// Fraction decoded so far of the waveform being computed for `track_id`,
+/// or `None` if none is running.
+pub fn progress(track_id: Uuid) -> Option<f32> {
+    IN_PROGRESS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&track_id)
+        .map(|p| p.get())
+}
+
+/// Removes a track's progress entry when its generation ends, however it ends.
+struct ProgressGuard(Uuid);
+
+impl ProgressGuard {
+    fn register(track_id: Uuid) -> (Self, Arc<WaveformProgress>) {
+        let progress = Arc::new(WaveformProgress::default());
+        IN_PROGRESS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(track_id, Arc::clone(&progress));
+        (Self(track_id), progress)
+    }
+}
+
+impl Drop for ProgressGuard {
+    fn drop(&mut self) {
+        IN_PROGRESS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.0);
+    }
+}
+
+/// Compute the waveform of `path` for `track_id` and save it to the track.
+/// Returns the waveform, or `None` if the file could not be decoded.
+pub async fn generate(state: &AppState, track_id: Uuid, path: PathBuf) -> Option<Vec<f32>> {
+    let (_guard, progress) = ProgressGuard::register(track_id);
+    let waveform =
+        match soundtime_audio::generate_waveform_async(path, WAVEFORM_POINTS, progress).await {
+            Ok(waveform) => waveform,
+            Err(e) => {
+                tracing::warn!(%track_id, "waveform generation failed: {e}");
+                return None;
+            }
+        };
+
+    let update = track::ActiveModel {
+        id: Set(track_id),
+        waveform_data: Set(Some(serde_json::json!(waveform))),
+        ..Default::default()
+    };
+    if let Err(e) = update.update(&state.db).await {
+        tracing::warn!(%track_id, "failed to save waveform: {e}");
+    } else {
+        tracing::debug!(%track_id, "waveform saved");
+    }
+    Some(waveform)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── progress ──
+
+    #[test]
+    fn test_progress_tracked_while_registered() {
+        let track_id = Uuid::new_v4();
+        assert_eq!(progress(track_id), None);
+        let (guard, _) = ProgressGuard::register(track_id);
+        assert_eq!(progress(track_id), Some(0.0));
+        drop(guard);
+        assert_eq!(progress(track_id), None);
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_file_clears_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notaudio.wav");
+        std::fs::write(&path, "this is not audio data").unwrap();
+        let state = crate::test_db::state(crate::test_db::connect().await);
+
+        let track_id = Uuid::new_v4();
+        assert_eq!(generate(&state, track_id, path).await, None);
+        assert_eq!(progress(track_id), None);
+    }
+}
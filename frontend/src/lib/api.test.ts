@@ -1,5 +1,5 @@
 import { describe, it, expect, vi, beforeEach } from 'vitest';
-import { clearTokens, streamUrl, apiFetch, api, setTokens, API_BASE, pluginApi, themeApi, homeApi, radioApi, lastfmApi } from '$lib/api';
+import { clearTokens, streamUrl, apiFetch, api, setTokens, API_BASE, pluginApi, themeApi, homeApi, radioApi, lastfmApi, UploadError } from '$lib/api';
 
 // Mock localStorage
 const localStorageMock = (() => {
@@ -389,6 +389,22 @@ describe('API module', () => {
 
 			await expect(promise).rejects.toThrow('File too large');
 		});
+
+		it('keeps status and body of a duplicate conflict', async () => {
+			const xhr = createMockXHR({
+				status: 409,
+				responseText: '{"error":"Duplicate","duplicate":true,"track_id":"abc"}'
+			});
+			const { promise } = api.uploadWithProgress('/upload', new FormData());
+
+			const loadCall = xhr.addEventListener.mock.calls.find((c: any[]) => c[0] === 'load');
+			loadCall![1]();
+
+			const err = await promise.catch((e) => e);
+			expect(err).toBeInstanceOf(UploadError);
+			expect(err.status).toBe(409);
+			expect(err.body).toEqual({ error: 'Duplicate', duplicate: true, track_id: 'abc' });
+		});
 	});
 
 	describe('api.uploadWithProgress edge cases', () => {
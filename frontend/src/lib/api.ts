@@ -53,6 +53,18 @@ async function refreshAccessToken(): Promise<boolean> {
   }
 }
 
+/** Rejection of `api.uploadWithProgress` for a non-2xx response. */
+export class UploadError extends Error {
+  constructor(
+    message: string,
+    readonly status: number,
+    readonly body?: ApiError,
+  ) {
+    super(message);
+    this.name = "UploadError";
+  }
+}
+
 export async function apiFetch<T = unknown>(
   path: string,
   options: RequestInit = {}
@@ -159,12 +171,13 @@ export const api = {
             resolve(undefined as T);
           }
         } else {
+          let body: ApiError | undefined;
           try {
-            const err = JSON.parse(xhr.responseText);
-            reject(new Error(err.error || `HTTP ${xhr.status}`));
+            body = JSON.parse(xhr.responseText);
           } catch {
-            reject(new Error(`HTTP ${xhr.status}`));
+            body = undefined;
           }
+          reject(new UploadError(body?.error || `HTTP ${xhr.status}`, xhr.status, body));
         }
       });
 
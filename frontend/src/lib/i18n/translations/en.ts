@@ -209,7 +209,11 @@ export default {
   "upload.queuePending": "{count} pending",
   "upload.clearAll": "Clear all",
   "upload.pending": "Pending…",
+  "upload.waveformProgress": "Waveform {percent}%",
   "upload.remove": "Remove",
+  "upload.duplicate": "This track is already in the library",
+  "upload.uploadAnyway": "Upload anyway",
+  "upload.viewExisting": "View existing",
   "upload.sizeB": "{n} B",
   "upload.sizeKB": "{n} KB",
   "upload.sizeMB": "{n} MB",
@@ -461,6 +465,19 @@ export default {
   "admin.blocked.importJson": "Import (JSON)",
   "admin.blocked.unblock": "Unblock",
   "admin.blocked.noDomains": "No blocked peers.",
+  "admin.blocked.permanent": "Permanent",
+  "admin.blocked.duration1d": "1 day",
+  "admin.blocked.duration7d": "7 days",
+  "admin.blocked.duration30d": "30 days",
+  "admin.blocked.expiresIn": "Expires in {time}",
+  "admin.blocked.subscriptions": "Blocklist subscriptions",
+  "admin.blocked.subscriptionUrlPlaceholder": "https://example.org/blocklist.json",
+  "admin.blocked.subscribe": "Subscribe",
+  "admin.blocked.refresh": "Refresh",
+  "admin.blocked.unsubscribe": "Unsubscribe",
+  "admin.blocked.neverFetched": "Not fetched yet",
+  "admin.blocked.subscriptionCounts": "{entries} listed, {blocked} blocked",
+  "admin.blocked.fromSubscription": "From {url}",
   "admin.blocked.importDone": "Import complete: {imported} added, {skipped} skipped.",
 
   // Admin — instances
@@ -482,6 +499,13 @@ export default {
   "admin.p2p.directAddresses": "Direct addresses",
   "admin.p2p.peers": "Connected peers",
   "admin.p2p.onlinePeers": "Online peers",
+  "admin.p2p.traffic": "Traffic",
+  "admin.p2p.blobUploaded": "Blobs uploaded",
+  "admin.p2p.blobDownloaded": "Blobs downloaded",
+  "admin.p2p.activeConnections": "Active connections",
+  "admin.p2p.messageType": "Message type",
+  "admin.p2p.sent": "Sent",
+  "admin.p2p.received": "Received",
   "admin.p2p.addPeer": "Add peer",
   "admin.p2p.addPeerPlaceholder": "Enter peer Node ID",
   "admin.p2p.ping": "Ping",
@@ -517,6 +541,13 @@ export default {
   "admin.libSync.noPeers": "No peers to synchronize.",
   "admin.libSync.noPeersHint": "Add peers in the P2P Status tab first.",
   "admin.libSync.taskRunning": "Sync in progress",
+  "admin.libSync.taskPaused": "Sync paused",
+  "admin.libSync.taskCancelled": "Sync cancelled",
+  "admin.libSync.pause": "Pause",
+  "admin.libSync.resume": "Resume",
+  "admin.libSync.cancel": "Cancel",
+  "admin.libSync.resumePoint": "Catalog sent up to page {page}; the next sync resumes from there",
+  "admin.libSync.nextScheduledRun": "Next scheduled sync: {time}",
   "admin.libSync.taskCompleted": "Sync completed",
   "admin.libSync.taskError": "Sync failed",
   "admin.libSync.taskDismiss": "Dismiss",
@@ -627,6 +658,8 @@ export default {
   "admin.reports.statusDismissed": "Dismissed",
   "admin.reports.delete": "Delete",
   "admin.reports.unlist": "Unlist",
+  "admin.reports.blockHash": "Block hash",
+  "admin.reports.blockHashHint": "Block this file on this instance only (not sent to peers)",
   "admin.reports.resolve": "Resolve",
   "admin.reports.dismiss": "Dismiss",
   "admin.reports.noReports": "No reports.",
@@ -209,7 +209,11 @@ export default {
   "upload.queuePending": "{count} pendientes",
   "upload.clearAll": "Borrar todo",
   "upload.pending": "Pendiente…",
+  "upload.waveformProgress": "Forma de onda {percent} %",
   "upload.remove": "Quitar",
+  "upload.duplicate": "Esta pista ya está en la biblioteca",
+  "upload.uploadAnyway": "Subir de todos modos",
+  "upload.viewExisting": "Ver la existente",
   "upload.sizeB": "{n} B",
   "upload.sizeKB": "{n} KB",
   "upload.sizeMB": "{n} MB",
@@ -449,6 +453,19 @@ export default {
   "admin.blocked.importJson": "Importar (JSON)",
   "admin.blocked.unblock": "Desbloquear",
   "admin.blocked.noDomains": "Ningún par bloqueado.",
+  "admin.blocked.permanent": "Permanente",
+  "admin.blocked.duration1d": "1 día",
+  "admin.blocked.duration7d": "7 días",
+  "admin.blocked.duration30d": "30 días",
+  "admin.blocked.expiresIn": "Expira en {time}",
+  "admin.blocked.subscriptions": "Suscripciones a listas de bloqueo",
+  "admin.blocked.subscriptionUrlPlaceholder": "https://example.org/blocklist.json",
+  "admin.blocked.subscribe": "Suscribirse",
+  "admin.blocked.refresh": "Actualizar",
+  "admin.blocked.unsubscribe": "Cancelar suscripción",
+  "admin.blocked.neverFetched": "Aún no descargada",
+  "admin.blocked.subscriptionCounts": "{entries} listados, {blocked} bloqueados",
+  "admin.blocked.fromSubscription": "Desde {url}",
   "admin.blocked.importDone": "Importación completa: {imported} añadido(s), {skipped} omitido(s).",
   "admin.instances.domain": "Dominio",
   "admin.instances.tracks": "Pistas P2P",
@@ -467,6 +484,13 @@ export default {
   "admin.p2p.directAddresses": "Direcciones directas",
   "admin.p2p.peers": "Pares conectados",
   "admin.p2p.onlinePeers": "Pares en línea",
+  "admin.p2p.traffic": "Tráfico",
+  "admin.p2p.blobUploaded": "Blobs subidos",
+  "admin.p2p.blobDownloaded": "Blobs descargados",
+  "admin.p2p.activeConnections": "Conexiones activas",
+  "admin.p2p.messageType": "Tipo de mensaje",
+  "admin.p2p.sent": "Enviados",
+  "admin.p2p.received": "Recibidos",
   "admin.p2p.addPeer": "Agregar par",
   "admin.p2p.addPeerPlaceholder": "Ingresar ID del par",
   "admin.p2p.ping": "Ping",
@@ -502,6 +526,13 @@ export default {
   "admin.libSync.noPeers": "No hay pares para sincronizar.",
   "admin.libSync.noPeersHint": "Añade pares en la pestaña Estado P2P primero.",
   "admin.libSync.taskRunning": "Sincronización en curso",
+  "admin.libSync.taskPaused": "Sincronización en pausa",
+  "admin.libSync.taskCancelled": "Sincronización cancelada",
+  "admin.libSync.pause": "Pausar",
+  "admin.libSync.resume": "Reanudar",
+  "admin.libSync.cancel": "Cancelar",
+  "admin.libSync.resumePoint": "Catálogo enviado hasta la página {page}; la próxima sincronización continuará desde ahí",
+  "admin.libSync.nextScheduledRun": "Próxima sincronización programada: {time}",
   "admin.libSync.taskCompleted": "Sincronización completada",
   "admin.libSync.taskError": "Sincronización fallida",
   "admin.libSync.taskDismiss": "Cerrar",
@@ -605,6 +636,8 @@ export default {
   "admin.reports.statusDismissed": "Desestimado",
   "admin.reports.delete": "Eliminar",
   "admin.reports.unlist": "Desreferenciar",
+  "admin.reports.blockHash": "Bloquear hash",
+  "admin.reports.blockHashHint": "Bloquear este archivo solo en esta instancia (no se envía a los pares)",
   "admin.reports.resolve": "Resolver",
   "admin.reports.dismiss": "Desestimar",
   "admin.reports.noReports": "Sin reportes.",
@@ -209,7 +209,11 @@ export default {
   "upload.queuePending": "{count} en attente",
   "upload.clearAll": "Tout effacer",
   "upload.pending": "En attente…",
+  "upload.waveformProgress": "Forme d'onde {percent} %",
   "upload.remove": "Retirer",
+  "upload.duplicate": "Ce titre est déjà dans la bibliothèque",
+  "upload.uploadAnyway": "Envoyer quand même",
+  "upload.viewExisting": "Voir l'existant",
   "upload.sizeB": "{n} o",
   "upload.sizeKB": "{n} Ko",
   "upload.sizeMB": "{n} Mo",
@@ -461,6 +465,19 @@ export default {
   "admin.blocked.importJson": "Importer (JSON)",
   "admin.blocked.unblock": "Débloquer",
   "admin.blocked.noDomains": "Aucun pair bloqué.",
+  "admin.blocked.permanent": "Permanent",
+  "admin.blocked.duration1d": "1 jour",
+  "admin.blocked.duration7d": "7 jours",
+  "admin.blocked.duration30d": "30 jours",
+  "admin.blocked.expiresIn": "Expire dans {time}",
+  "admin.blocked.subscriptions": "Abonnements aux listes de blocage",
+  "admin.blocked.subscriptionUrlPlaceholder": "https://example.org/blocklist.json",
+  "admin.blocked.subscribe": "S'abonner",
+  "admin.blocked.refresh": "Actualiser",
+  "admin.blocked.unsubscribe": "Se désabonner",
+  "admin.blocked.neverFetched": "Pas encore récupérée",
+  "admin.blocked.subscriptionCounts": "{entries} listés, {blocked} bloqués",
+  "admin.blocked.fromSubscription": "Depuis {url}",
   "admin.blocked.importDone": "Import terminé : {imported} ajouté(s), {skipped} ignoré(s).",
 
   // Admin — instances
@@ -482,6 +499,13 @@ export default {
   "admin.p2p.directAddresses": "Adresses directes",
   "admin.p2p.peers": "Pairs connectés",
   "admin.p2p.onlinePeers": "Pairs en ligne",
+  "admin.p2p.traffic": "Trafic",
+  "admin.p2p.blobUploaded": "Blobs envoyés",
+  "admin.p2p.blobDownloaded": "Blobs reçus",
+  "admin.p2p.activeConnections": "Connexions actives",
+  "admin.p2p.messageType": "Type de message",
+  "admin.p2p.sent": "Envoyés",
+  "admin.p2p.received": "Reçus",
   "admin.p2p.addPeer": "Ajouter un pair",
   "admin.p2p.addPeerPlaceholder": "Entrer l'ID du pair",
   "admin.p2p.ping": "Ping",
@@ -517,6 +541,13 @@ export default {
   "admin.libSync.noPeers": "Aucun pair à synchroniser.",
   "admin.libSync.noPeersHint": "Ajoutez des pairs dans l'onglet Statut P2P d'abord.",
   "admin.libSync.taskRunning": "Synchronisation en cours",
+  "admin.libSync.taskPaused": "Synchronisation en pause",
+  "admin.libSync.taskCancelled": "Synchronisation annulée",
+  "admin.libSync.pause": "Pause",
+  "admin.libSync.resume": "Reprendre",
+  "admin.libSync.cancel": "Annuler",
+  "admin.libSync.resumePoint": "Catalogue envoyé jusqu'à la page {page} ; la prochaine synchronisation reprendra à partir de là",
+  "admin.libSync.nextScheduledRun": "Prochaine synchronisation planifiée : {time}",
   "admin.libSync.taskCompleted": "Synchronisation terminée",
   "admin.libSync.taskError": "Synchronisation échouée",
   "admin.libSync.taskDismiss": "Fermer",
@@ -627,6 +658,8 @@ export default {
   "admin.reports.statusDismissed": "Classé",
   "admin.reports.delete": "Supprimer",
   "admin.reports.unlist": "Déréférencer",
+  "admin.reports.blockHash": "Bloquer le hash",
+  "admin.reports.blockHashHint": "Bloquer ce fichier sur cette instance uniquement (non transmis aux pairs)",
   "admin.reports.resolve": "Résoudre",
   "admin.reports.dismiss": "Classer",
   "admin.reports.noReports": "Aucun signalement.",
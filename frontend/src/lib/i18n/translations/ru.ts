@@ -209,7 +209,11 @@ export default {
   "upload.queuePending": "{count} в ожидании",
   "upload.clearAll": "Очистить всё",
   "upload.pending": "Ожидание…",
+  "upload.waveformProgress": "Волна {percent}%",
   "upload.remove": "Убрать",
+  "upload.duplicate": "Этот трек уже есть в библиотеке",
+  "upload.uploadAnyway": "Всё равно загрузить",
+  "upload.viewExisting": "Открыть существующий",
   "upload.sizeB": "{n} Б",
   "upload.sizeKB": "{n} КБ",
   "upload.sizeMB": "{n} МБ",
@@ -449,6 +453,19 @@ export default {
   "admin.blocked.importJson": "Импорт (JSON)",
   "admin.blocked.unblock": "Разблокировать",
   "admin.blocked.noDomains": "Нет заблокированных пиров.",
+  "admin.blocked.permanent": "Навсегда",
+  "admin.blocked.duration1d": "1 день",
+  "admin.blocked.duration7d": "7 дней",
+  "admin.blocked.duration30d": "30 дней",
+  "admin.blocked.expiresIn": "Истекает через {time}",
+  "admin.blocked.subscriptions": "Подписки на списки блокировки",
+  "admin.blocked.subscriptionUrlPlaceholder": "https://example.org/blocklist.json",
+  "admin.blocked.subscribe": "Подписаться",
+  "admin.blocked.refresh": "Обновить",
+  "admin.blocked.unsubscribe": "Отписаться",
+  "admin.blocked.neverFetched": "Ещё не загружен",
+  "admin.blocked.subscriptionCounts": "{entries} в списке, {blocked} заблокировано",
+  "admin.blocked.fromSubscription": "Из {url}",
   "admin.blocked.importDone": "Импорт завершён: добавлено {imported}, пропущено {skipped}.",
   "admin.instances.domain": "Домен",
   "admin.instances.tracks": "P2P-треки",
@@ -467,6 +484,13 @@ export default {
   "admin.p2p.directAddresses": "Прямые адреса",
   "admin.p2p.peers": "Подключённые пиры",
   "admin.p2p.onlinePeers": "Пиры онлайн",
+  "admin.p2p.traffic": "Трафик",
+  "admin.p2p.blobUploaded": "Отдано блобов",
+  "admin.p2p.blobDownloaded": "Загружено блобов",
+  "admin.p2p.activeConnections": "Активные соединения",
+  "admin.p2p.messageType": "Тип сообщения",
+  "admin.p2p.sent": "Отправлено",
+  "admin.p2p.received": "Получено",
   "admin.p2p.addPeer": "Добавить пир",
   "admin.p2p.addPeerPlaceholder": "Введите ID пира",
   "admin.p2p.ping": "Ping",
@@ -502,6 +526,13 @@ export default {
   "admin.libSync.noPeers": "Нет пиров для синхронизации.",
   "admin.libSync.noPeersHint": "Сначала добавьте пиров на вкладке Статус P2P.",
   "admin.libSync.taskRunning": "Синхронизация выполняется",
+  "admin.libSync.taskPaused": "Синхронизация приостановлена",
+  "admin.libSync.taskCancelled": "Синхронизация отменена",
+  "admin.libSync.pause": "Пауза",
+  "admin.libSync.resume": "Продолжить",
+  "admin.libSync.cancel": "Отменить",
+  "admin.libSync.resumePoint": "Каталог отправлен до страницы {page}; следующая синхронизация продолжится с этого места",
+  "admin.libSync.nextScheduledRun": "Следующая плановая синхронизация: {time}",
   "admin.libSync.taskCompleted": "Синхронизация завершена",
   "admin.libSync.taskError": "Ошибка синхронизации",
   "admin.libSync.taskDismiss": "Закрыть",
@@ -605,6 +636,8 @@ export default {
   "admin.reports.statusDismissed": "Отклонена",
   "admin.reports.delete": "Удалить",
   "admin.reports.unlist": "Исключить",
+  "admin.reports.blockHash": "Заблокировать хеш",
+  "admin.reports.blockHashHint": "Заблокировать этот файл только на этом узле (пиры не уведомляются)",
   "admin.reports.resolve": "Решить",
   "admin.reports.dismiss": "Отклонить",
   "admin.reports.noReports": "Нет жалоб.",
@@ -209,7 +209,11 @@ export default {
   "upload.queuePending": "{count} 待处理",
   "upload.clearAll": "全部清除",
   "upload.pending": "等待中…",
+  "upload.waveformProgress": "波形 {percent}%",
   "upload.remove": "移除",
+  "upload.duplicate": "该曲目已在库中",
+  "upload.uploadAnyway": "仍然上传",
+  "upload.viewExisting": "查看已有曲目",
   "upload.sizeB": "{n} B",
   "upload.sizeKB": "{n} KB",
   "upload.sizeMB": "{n} MB",
@@ -449,6 +453,19 @@ export default {
   "admin.blocked.importJson": "导入 (JSON)",
   "admin.blocked.unblock": "取消屏蔽",
   "admin.blocked.noDomains": "没有已屏蔽的节点。",
+  "admin.blocked.permanent": "永久",
+  "admin.blocked.duration1d": "1 天",
+  "admin.blocked.duration7d": "7 天",
+  "admin.blocked.duration30d": "30 天",
+  "admin.blocked.expiresIn": "{time} 后到期",
+  "admin.blocked.subscriptions": "屏蔽列表订阅",
+  "admin.blocked.subscriptionUrlPlaceholder": "https://example.org/blocklist.json",
+  "admin.blocked.subscribe": "订阅",
+  "admin.blocked.refresh": "刷新",
+  "admin.blocked.unsubscribe": "取消订阅",
+  "admin.blocked.neverFetched": "尚未获取",
+  "admin.blocked.subscriptionCounts": "列出 {entries} 个，已屏蔽 {blocked} 个",
+  "admin.blocked.fromSubscription": "来自 {url}",
   "admin.blocked.importDone": "导入完成：已添加 {imported} 个，已跳过 {skipped} 个。",
   "admin.instances.domain": "域名",
   "admin.instances.tracks": "P2P 曲目",
@@ -467,6 +484,13 @@ export default {
   "admin.p2p.directAddresses": "直连地址",
   "admin.p2p.peers": "已连接节点",
   "admin.p2p.onlinePeers": "在线节点",
+  "admin.p2p.traffic": "流量",
+  "admin.p2p.blobUploaded": "Blob 上传",
+  "admin.p2p.blobDownloaded": "Blob 下载",
+  "admin.p2p.activeConnections": "活动连接",
+  "admin.p2p.messageType": "消息类型",
+  "admin.p2p.sent": "已发送",
+  "admin.p2p.received": "已接收",
   "admin.p2p.addPeer": "添加节点",
   "admin.p2p.addPeerPlaceholder": "输入节点 ID",
   "admin.p2p.ping": "Ping",
@@ -502,6 +526,13 @@ export default {
   "admin.libSync.noPeers": "没有可同步的节点。",
   "admin.libSync.noPeersHint": "请先在 P2P 状态标签页中添加节点。",
   "admin.libSync.taskRunning": "正在同步",
+  "admin.libSync.taskPaused": "同步已暂停",
+  "admin.libSync.taskCancelled": "同步已取消",
+  "admin.libSync.pause": "暂停",
+  "admin.libSync.resume": "继续",
+  "admin.libSync.cancel": "取消",
+  "admin.libSync.resumePoint": "目录已发送至第 {page} 页；下次同步将从此处继续",
+  "admin.libSync.nextScheduledRun": "下次计划同步：{time}",
   "admin.libSync.taskCompleted": "同步完成",
   "admin.libSync.taskError": "同步失败",
   "admin.libSync.taskDismiss": "关闭",
@@ -605,6 +636,8 @@ export default {
   "admin.reports.statusDismissed": "已驳回",
   "admin.reports.delete": "删除",
   "admin.reports.unlist": "取消引用",
+  "admin.reports.blockHash": "屏蔽哈希",
+  "admin.reports.blockHashHint": "仅在本实例屏蔽此文件（不通知对等节点）",
   "admin.reports.resolve": "解决",
   "admin.reports.dismiss": "驳回",
   "admin.reports.noReports": "暂无举报。",
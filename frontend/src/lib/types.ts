@@ -165,11 +165,19 @@ export interface NetworkSearchResult {
   source_node: string;
   musicbrainz_id?: string;
   relevance: number;
+  /** Plays on the source instance, including plays reported by peers */
+  play_count: number;
 }
 
 export interface NetworkSearchResponse {
   results: NetworkSearchResult[];
   total: number;
+  /** Whether a later page may hold more results */
+  has_more: boolean;
+  /** Pass back as `cursor` to fetch the next page */
+  cursor: string | null;
+  /** Matches each answering peer reported, keyed by node ID */
+  peer_totals: Record<string, number>;
 }
 
 export interface TokenPair {
@@ -196,8 +204,16 @@ export interface UploadResponse {
   message: string;
 }
 
+export interface WaveformStatus {
+  ready: boolean;
+  progress: number;
+}
+
 export interface ApiError {
   error: string;
+  /** Set on `409 Conflict` from `POST /upload` for a fingerprint duplicate */
+  duplicate?: boolean;
+  track_id?: string;
 }
 
 // ─── Admin / P2P Types ──────────────────────────────────────────────
@@ -221,6 +237,21 @@ export interface BlockedDomain {
   domain: string;
   reason: string | null;
   created_at: string;
+  expires_at: string | null;
+  remaining_secs: number | null;
+  source: string | null;
+}
+
+export interface BlocklistSubscription {
+  id: string;
+  url: string;
+  enabled: boolean;
+  last_fetched: string | null;
+  last_status: "ok" | "not_modified" | "error" | null;
+  last_error: string | null;
+  entry_count: number;
+  blocked_count: number;
+  created_at: string;
 }
 
 export interface KnownInstance {
@@ -234,10 +265,116 @@ export interface P2pStatus {
   node_id: string | null;
   relay_url: string | null;
   relay_connected: boolean;
+  /** Relay configuration: n0's relays, self-hosted ones, or none */
+  relay_mode?: 'default' | 'custom' | 'disabled';
+  /** Self-hosted relay URLs, when `relay_mode` is `custom` */
+  relay_urls?: string[];
   direct_addresses: number;
   peer_count: number;
   online_peer_count: number;
   dht_discovery_enabled: boolean;
+  stats: P2pStats | null;
+  outgoing_syncs: P2pOutgoingSync[];
+  /** Optional features this node advertises to peers */
+  capabilities?: string[];
+  /** Expected false positive rate of the local search Bloom filter */
+  bloom_fpr_estimate?: number | null;
+  /** BLAKE3 checksum of the local catalog's content hashes */
+  catalog_checksum?: string | null;
+}
+
+export interface P2pOutgoingSync {
+  peer_id: string;
+  rerun_requested: boolean;
+}
+
+export interface P2pMessageStats {
+  kind: string;
+  sent: number;
+  received: number;
+}
+
+export interface P2pStats {
+  messages: P2pMessageStats[];
+  messages_sent: number;
+  messages_received: number;
+  blob_bytes_uploaded: number;
+  blob_bytes_downloaded: number;
+  active_connections: number;
+  /** Outgoing connection pool */
+  pool?: P2pConnectionPoolStats;
+  /** Distributed search result cache */
+  search_cache?: P2pSearchCacheStats;
+}
+
+export interface P2pConnectionPoolStats {
+  open_connections: number;
+  /** Connections dropped as dead, idle past the TTL or to make room */
+  evictions: number;
+  /** Keepalive probes that found a connection dead */
+  failed_probes: number;
+}
+
+export interface P2pSearchCacheStats {
+  /** Searches answered from the cache */
+  hits: number;
+  /** Searches that had to ask peers */
+  misses: number;
+  /** Queries currently cached */
+  entries: number;
+}
+
+/** Event from `GET /api/admin/p2p/events` (Server-Sent Events) */
+export type P2pEvent = { timestamp: string } & (
+  | { type: "peer_connected"; peer_id: string; protocol_version: number }
+  | { type: "peer_disconnected"; peer_id: string; reason: string }
+  | { type: "peer_online"; peer_id: string; track_count: number }
+  | { type: "peer_offline"; peer_id: string }
+  | { type: "track_announced"; peer_id: string; hash: string; title: string; artist_name: string }
+  | { type: "catalog_sync_started"; peer_id: string; sync_id: string | null; total_pages: number }
+  | {
+      type: "catalog_sync_page";
+      peer_id: string;
+      sync_id: string | null;
+      page: number;
+      total_pages: number;
+      inserted: number;
+      skipped: number;
+      failed: number;
+    }
+  | { type: "catalog_sync_finished"; peer_id: string; sync_id: string | null; total_pages: number }
+  | { type: "search_query_received"; peer_id: string; query: string }
+  | { type: "bloom_filter_updated"; peer_id: string; item_count: number }
+  | { type: "track_replicated"; peer_id: string; hash: string; title: string; artist_name: string }
+  | {
+      type: "health_sweep_finished";
+      total_checked: number;
+      healthy: number;
+      recovered: number;
+      failed: number;
+      dereferenced: number;
+    }
+);
+
+/** `GET /api/admin/p2p/rejected` — announcements refused by the replication policy */
+export interface P2pRejectedAnnouncements {
+  policy: {
+    max_file_size: number | null;
+    allowed_formats: string[];
+    blocked_genres: string[];
+    require_album: boolean;
+    require_genre: boolean;
+    require_musicbrainz: boolean;
+  };
+  per_peer: { peer_id: string; rejected: number }[];
+  recent: {
+    peer_id: string;
+    hash: string;
+    title: string;
+    artist_name: string;
+    reason: string;
+    rejected_at: string;
+  }[];
 }
 
 export interface P2pPeer {
@@ -247,6 +384,68 @@ export interface P2pPeer {
   track_count: number;
   last_seen: string;
   is_online: boolean;
+  /** P2P protocol version negotiated via ALPN (null until connected) */
+  protocol_version?: number | null;
+  /** Round-trip time of our last ping to this peer (null = not measured) */
+  rtt_ms?: number | null;
+  /** Median RTT over the last 20 pings (null = not measured) */
+  p50_rtt_ms?: number | null;
+  /** When this peer last received our full catalog (null = never) */
+  last_catalog_sync_at?: string | null;
+  /** Optional features the peer advertised in its last Pong */
+  capabilities?: string[];
+  /** When the peer announced a graceful shutdown (null = not departed) */
+  departed_at?: string | null;
+  /** Failed pings in a row; the peer is evicted at the configured threshold */
+  consecutive_failures?: number;
+  /** Tallies of our recent catalog pushes to this peer, newest first */
+  catalog_sync_history?: P2pCatalogSyncRecord[];
+}
+
+export interface P2pPeerEviction {
+  node_id: string;
+  name: string | null;
+  consecutive_failures: number;
+  last_seen: string;
+  evicted_at: string;
+}
+
+export interface P2pBlockedHash {
+  hash: string;
+  reason: string;
+  /** Peer the block came from (null = blocked on this instance) */
+  source_peer: string | null;
+  /** `pending` blocks came from an untrusted peer and await review */
+  status: "active" | "pending";
+  created_at: string;
+}
+
+/** Per-peer replication filter */
+export interface P2pPeerFilter {
+  /** Lowercase genres accepted from the peer (empty = any) */
+  allowed_genres: string[];
+  /** Lowercase artists accepted from the peer (empty = any) */
+  allowed_artists: string[];
+  max_tracks: number | null;
+  /** List the peer's tracks without downloading their audio */
+  metadata_only: boolean;
+}
+
+export interface P2pCatalogSyncRecord {
+  sync_id: string;
+  peer_id: string;
+  started_at: string;
+  finished_at: string | null;
+  /** False for peers on protocol v1, which report no track counts */
+  acknowledged: boolean;
+  pages_sent: number;
+  /** Pages still failing after the retry */
+  pages_failed: number;
+  /** Pages that needed a second attempt */
+  pages_retried: number;
+  inserted: number;
+  skipped: number;
+  failed: number;
 }
 
 export interface NetworkGraphNode {
@@ -256,6 +455,8 @@ export interface NetworkGraphNode {
   online: boolean;
   track_count?: number;
   version?: string;
+  /** Median ping round-trip time, peers only (absent until measured) */
+  p50_rtt_ms?: number;
 }
 
 export interface NetworkGraphLink {
@@ -423,6 +624,7 @@ export interface TrackReport {
   track_title: string;
   track_artist: string;
   is_local: boolean;
+  content_hash: string | null;
   reporter_username: string;
   reason: string;
   status: string;
@@ -531,6 +733,7 @@ export interface BatchUploadItem {
   success: boolean;
   track?: UploadResponse;
   error?: string;
+  duplicate_of?: string;
 }
 
 export interface BatchUploadResponse {
@@ -576,12 +779,23 @@ export interface LibrarySyncOverview {
   partial_peers: number;
   not_synced_peers: number;
   peers: PeerSyncStatus[];
+  task: LibrarySyncTaskStatus;
+  schedule: ScheduledSync[];
+}
+
+export interface ScheduledSync {
+  peer_id: string;
+  cron: string;
+  global: boolean;
+  last_run: string | null;
+  next_run: string | null;
 }
 
 export interface SyncProgress {
   processed: number;
   total: number | null;
   phase: string;
+  resume_point: { peer_id: string; page: number } | null;
 }
 
 export interface SyncResult {
@@ -594,8 +808,10 @@ export interface SyncResult {
 
 export type LibrarySyncTaskStatus =
   | { status: "idle" }
-  | { status: "running"; peer_id: string; progress: SyncProgress }
+  | { status: "running"; task_id: string; peer_id: string; progress: SyncProgress }
+  | { status: "paused"; task_id: string; peer_id: string; progress: SyncProgress }
   | { status: "completed"; result: SyncResult }
+  | { status: "cancelled"; task_id: string; peer_id: string; progress: SyncProgress }
   | { status: "error"; message: string };
 
 // ─── Plugins ────────────────────────────────────────────────────────